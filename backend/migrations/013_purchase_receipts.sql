@@ -0,0 +1,20 @@
+-- Persistent receipts for box purchases
+CREATE TABLE purchase_receipts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    receipt_number VARCHAR(32) UNIQUE NOT NULL,
+    verification_code VARCHAR(64) UNIQUE NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE NOT NULL,
+    raffle_id UUID REFERENCES raffles(id) ON DELETE CASCADE NOT NULL,
+    purchase_id UUID REFERENCES box_purchases(id) ON DELETE CASCADE NOT NULL UNIQUE,
+    box_numbers INTEGER[] NOT NULL,
+    subtotal DECIMAL(10,2) NOT NULL,
+    credits_used DECIMAL(10,2) NOT NULL,
+    tax_lines JSONB NOT NULL DEFAULT '[]',
+    total_amount DECIMAL(10,2) NOT NULL,
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    emailed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_purchase_receipts_user_id ON purchase_receipts(user_id);
+CREATE INDEX idx_purchase_receipts_raffle_id ON purchase_receipts(raffle_id);
@@ -0,0 +1,29 @@
+-- Chart of accounts mapping used by accounting exports (QuickBooks/Xero)
+CREATE TABLE chart_of_accounts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    transaction_type transaction_type UNIQUE NOT NULL,
+    debit_account_code VARCHAR(20) NOT NULL,
+    debit_account_name VARCHAR(100) NOT NULL,
+    credit_account_code VARCHAR(20) NOT NULL,
+    credit_account_name VARCHAR(100) NOT NULL,
+    tax_rate_name VARCHAR(50) NOT NULL DEFAULT 'Tax Exempt',
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE TRIGGER update_chart_of_accounts_updated_at
+    BEFORE UPDATE ON chart_of_accounts
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Default mapping; finance can adjust codes to match their ledger
+INSERT INTO chart_of_accounts (transaction_type, debit_account_code, debit_account_name, credit_account_code, credit_account_name) VALUES
+    ('credit_deposit', '1010', 'Stripe Clearing', '2100', 'Customer Credit Liability'),
+    ('credit_withdrawal', '2100', 'Customer Credit Liability', '1010', 'Stripe Clearing'),
+    ('box_purchase_credit_deduction', '2100', 'Customer Credit Liability', '4000', 'Raffle Box Revenue'),
+    ('item_purchase_credit_deduction', '2100', 'Customer Credit Liability', '4010', 'Item Sales Revenue'),
+    ('raffle_win_credit_addition', '5000', 'Raffle Credit Rebates', '2100', 'Customer Credit Liability'),
+    ('payout', '2200', 'Seller Payables', '1000', 'Operating Bank'),
+    ('free_item_redemption', '2100', 'Customer Credit Liability', '5010', 'Free Item Cost'),
+    ('seller_subscription_fee', '1010', 'Stripe Clearing', '4100', 'Seller Subscription Revenue'),
+    ('seller_listing_fee', '2200', 'Seller Payables', '4110', 'Seller Listing Fee Revenue'),
+    ('seller_transaction_fee', '2200', 'Seller Payables', '4120', 'Seller Transaction Fee Revenue');
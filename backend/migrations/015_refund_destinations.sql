@@ -0,0 +1,19 @@
+-- Extend refunds to support card refunds with credit clawback
+
+ALTER TYPE payment_status ADD VALUE IF NOT EXISTS 'partially_refunded';
+
+-- Credit refunds have no Stripe refund object
+ALTER TABLE refunds ALTER COLUMN stripe_refund_id DROP NOT NULL;
+
+ALTER TABLE refunds
+    ADD COLUMN destination VARCHAR(30) NOT NULL DEFAULT 'credits',
+    ADD COLUMN credits_clawed_back DECIMAL(10,2) NOT NULL DEFAULT 0,
+    ADD COLUMN clawback_shortfall DECIMAL(10,2) NOT NULL DEFAULT 0,
+    ADD COLUMN failure_reason TEXT,
+    ADD COLUMN requested_by UUID REFERENCES users(id),
+    ADD COLUMN completed_at TIMESTAMP WITH TIME ZONE,
+    ADD CONSTRAINT valid_refund_destination CHECK (destination IN ('credits', 'original_payment_method')),
+    ADD CONSTRAINT valid_refund_status CHECK (status IN ('pending', 'succeeded', 'failed', 'canceled'));
+
+CREATE INDEX IF NOT EXISTS idx_refunds_payment_id ON refunds(payment_id);
+CREATE INDEX IF NOT EXISTS idx_refunds_status ON refunds(status);
@@ -0,0 +1,29 @@
+-- Versioned fee schedules; rows are never updated in place so history is retained for audits
+CREATE TABLE fee_schedules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    fee_type VARCHAR(20) NOT NULL,
+    scope VARCHAR(20) NOT NULL DEFAULT 'default',
+    scope_value VARCHAR(100),
+    percentage DECIMAL(5,2) NOT NULL,
+    fixed_amount DECIMAL(10,2) NOT NULL DEFAULT 0,
+    effective_from TIMESTAMP WITH TIME ZONE NOT NULL,
+    effective_to TIMESTAMP WITH TIME ZONE,
+    notes TEXT,
+    created_by UUID REFERENCES users(id),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    CONSTRAINT valid_fee_type CHECK (fee_type IN ('platform', 'creator')),
+    CONSTRAINT valid_fee_scope CHECK (scope IN ('default', 'category', 'tier')),
+    CONSTRAINT scope_value_required CHECK ((scope = 'default') = (scope_value IS NULL)),
+    CONSTRAINT valid_fee_percentage CHECK (percentage >= 0 AND percentage <= 100),
+    CONSTRAINT valid_fee_window CHECK (effective_to IS NULL OR effective_to > effective_from)
+);
+
+CREATE INDEX idx_fee_schedules_lookup ON fee_schedules(fee_type, scope, scope_value, effective_from);
+
+-- Record which schedule priced each raffle
+ALTER TABLE raffles ADD COLUMN fee_schedule_id UUID REFERENCES fee_schedules(id);
+
+-- Seed the platform default with the previously hard-coded 5% fee
+INSERT INTO fee_schedules (fee_type, scope, percentage, effective_from, notes)
+VALUES ('platform', 'default', 5.00, '2024-01-01T00:00:00Z', 'Initial platform fee');
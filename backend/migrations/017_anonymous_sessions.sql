@@ -0,0 +1,22 @@
+-- Anonymous analytics sessions for guest (pre-signup) activity
+
+CREATE TABLE anonymous_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Set when the guest signs up or logs in; all of the session's events are attributed to this user
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    ip_address INET,
+    user_agent TEXT,
+    landing_page TEXT,
+    referrer TEXT,
+    first_seen_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    last_seen_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    stitched_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX idx_anonymous_sessions_user_id ON anonymous_sessions(user_id);
+CREATE INDEX idx_anonymous_sessions_last_seen_at ON anonymous_sessions(last_seen_at);
+
+ALTER TABLE user_activity_logs
+    ADD COLUMN anonymous_id UUID REFERENCES anonymous_sessions(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_user_activity_logs_anonymous_id ON user_activity_logs(anonymous_id);
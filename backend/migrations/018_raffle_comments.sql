@@ -0,0 +1,51 @@
+-- Per-raffle comments / Q&A with moderation
+
+CREATE TABLE raffle_comments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    raffle_id UUID REFERENCES raffles(id) ON DELETE CASCADE NOT NULL,
+    -- Replies point at a top-level comment; threads are one level deep
+    parent_id UUID REFERENCES raffle_comments(id) ON DELETE CASCADE,
+    author_id UUID REFERENCES users(id) ON DELETE CASCADE NOT NULL,
+    body TEXT NOT NULL,
+    -- Reply written by the raffle's seller
+    is_seller_answer BOOLEAN DEFAULT FALSE,
+    -- Seller-chosen answer shown first in the thread
+    is_highlighted BOOLEAN DEFAULT FALSE,
+    status VARCHAR(20) NOT NULL DEFAULT 'visible',
+    flag_count INTEGER DEFAULT 0,
+    moderated_by UUID REFERENCES users(id),
+    moderated_at TIMESTAMP WITH TIME ZONE,
+    moderation_reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT raffle_comments_status_check CHECK (status IN ('visible', 'hidden', 'removed')),
+    CONSTRAINT raffle_comments_body_check CHECK (char_length(body) BETWEEN 1 AND 2000)
+);
+
+CREATE INDEX idx_raffle_comments_raffle_id ON raffle_comments(raffle_id, created_at);
+CREATE INDEX idx_raffle_comments_parent_id ON raffle_comments(parent_id);
+CREATE INDEX idx_raffle_comments_author_created ON raffle_comments(author_id, created_at);
+
+CREATE TRIGGER update_raffle_comments_updated_at
+    BEFORE UPDATE ON raffle_comments
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE raffle_comment_flags (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    comment_id UUID REFERENCES raffle_comments(id) ON DELETE CASCADE NOT NULL,
+    reporter_id UUID REFERENCES users(id) ON DELETE CASCADE NOT NULL,
+    reason VARCHAR(30) NOT NULL,
+    details TEXT,
+    resolved_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE(comment_id, reporter_id),
+    CONSTRAINT raffle_comment_flags_reason_check CHECK (reason IN ('spam', 'abuse', 'off_topic', 'scam', 'other'))
+);
+
+CREATE INDEX idx_raffle_comment_flags_unresolved ON raffle_comment_flags(comment_id) WHERE resolved_at IS NULL;
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('comment_rate_limit_per_hour', '20', 'Maximum comments a user may post per hour'),
+    ('comment_min_interval_seconds', '10', 'Minimum seconds between two comments by the same user'),
+    ('comment_auto_hide_flag_threshold', '3', 'Open flags that hide a comment until a moderator reviews it')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,75 @@
+-- Tax reporting for raffle winners: jurisdiction thresholds, per-prize assessments and collected tax forms
+
+CREATE TABLE tax_jurisdictions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    country_code VARCHAR(2) UNIQUE NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    -- Form collected from the winner (e.g. W-9)
+    form_type VARCHAR(20) NOT NULL,
+    -- Information return filed by the platform (e.g. 1099-MISC)
+    filing_form_type VARCHAR(20) NOT NULL,
+    reporting_threshold DECIMAL(12,2) NOT NULL,
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    -- 'annual' sums all prizes won in a tax year, 'per_prize' compares each prize on its own
+    threshold_period VARCHAR(10) NOT NULL DEFAULT 'annual',
+    is_active BOOLEAN DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT tax_jurisdictions_threshold_check CHECK (reporting_threshold >= 0),
+    CONSTRAINT tax_jurisdictions_period_check CHECK (threshold_period IN ('annual', 'per_prize'))
+);
+
+CREATE TRIGGER update_tax_jurisdictions_updated_at
+    BEFORE UPDATE ON tax_jurisdictions
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- One form per winner per tax year; the payload holds legal name, TIN and address, encrypted at rest
+CREATE TABLE winner_tax_forms (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE NOT NULL,
+    tax_year INTEGER NOT NULL,
+    country_code VARCHAR(2) NOT NULL,
+    form_type VARCHAR(20) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'required',
+    encrypted_tax_info TEXT,
+    tin_last4 VARCHAR(4),
+    submitted_at TIMESTAMP WITH TIME ZONE,
+    reviewed_by UUID REFERENCES users(id),
+    reviewed_at TIMESTAMP WITH TIME ZONE,
+    rejection_reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE(user_id, tax_year),
+    CONSTRAINT winner_tax_forms_status_check CHECK (status IN ('required', 'submitted', 'verified', 'rejected'))
+);
+
+CREATE INDEX idx_winner_tax_forms_year_status ON winner_tax_forms(tax_year, status);
+
+CREATE TRIGGER update_winner_tax_forms_updated_at
+    BEFORE UPDATE ON winner_tax_forms
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Every prize won is assessed once; the form link is set when the prize (alone or with the
+-- year's other prizes) crosses the jurisdiction threshold
+CREATE TABLE prize_tax_assessments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    raffle_id UUID REFERENCES raffles(id) ON DELETE CASCADE NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE NOT NULL,
+    prize_value DECIMAL(12,2) NOT NULL,
+    tax_year INTEGER NOT NULL,
+    country_code VARCHAR(2) NOT NULL,
+    form_required BOOLEAN NOT NULL DEFAULT FALSE,
+    tax_form_id UUID REFERENCES winner_tax_forms(id) ON DELETE SET NULL,
+    assessed_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE(raffle_id, user_id)
+);
+
+CREATE INDEX idx_prize_tax_assessments_user_year ON prize_tax_assessments(user_id, tax_year);
+
+INSERT INTO tax_jurisdictions (country_code, name, form_type, filing_form_type, reporting_threshold, currency, threshold_period) VALUES
+    ('US', 'United States', 'W-9', '1099-MISC', 600.00, 'USD', 'annual')
+ON CONFLICT (country_code) DO NOTHING;
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('tax_default_country_code', '"US"', 'Jurisdiction assumed for winners who have not submitted a tax form')
+ON CONFLICT (key) DO NOTHING;
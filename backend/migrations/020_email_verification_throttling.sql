@@ -0,0 +1,34 @@
+-- Resend throttling and attempt ledger for email verification
+
+-- Set when a newer token is issued for the same user
+ALTER TABLE email_verification_tokens ADD COLUMN invalidated_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX idx_email_verification_tokens_user_active
+    ON email_verification_tokens(user_id) WHERE is_used = FALSE AND invalidated_at IS NULL;
+
+-- Every resend request and verification attempt, including ones that were refused
+CREATE TABLE email_verification_attempts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Normalized (lowercased) address; NULL when a token could not be matched to a user
+    email VARCHAR(255),
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    action VARCHAR(10) NOT NULL,
+    outcome VARCHAR(20) NOT NULL,
+    ip_address INET,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT email_verification_attempts_action_check CHECK (action IN ('resend', 'verify')),
+    CONSTRAINT email_verification_attempts_outcome_check CHECK (outcome IN (
+        'sent', 'throttled', 'unknown_email', 'already_verified',
+        'verified', 'invalid_token', 'expired_token', 'superseded_token'
+    ))
+);
+
+CREATE INDEX idx_email_verification_attempts_email_created ON email_verification_attempts(email, created_at);
+CREATE INDEX idx_email_verification_attempts_user_id ON email_verification_attempts(user_id);
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('email_verification_resend_base_seconds', '60', 'Wait after the first verification email; doubles with each further resend'),
+    ('email_verification_resend_max_seconds', '3600', 'Longest wait between verification emails'),
+    ('email_verification_resend_window_hours', '24', 'Hours of resend history that count towards the backoff'),
+    ('email_verification_token_ttl_hours', '24', 'Hours a verification link stays valid')
+ON CONFLICT (key) DO NOTHING;
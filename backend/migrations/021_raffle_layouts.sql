@@ -0,0 +1,20 @@
+-- Non-rectangular raffle layouts and admin-configurable grid limits
+
+-- Layouts can disable cells, so the grid only has to be large enough for the boxes
+ALTER TABLE raffles DROP CONSTRAINT IF EXISTS check_grid_dimensions;
+ALTER TABLE raffles ADD CONSTRAINT check_grid_dimensions
+    CHECK (grid_rows > 0 AND grid_cols > 0 AND grid_rows * grid_cols >= total_boxes);
+
+-- Boxes are numbered 1..total_boxes across the enabled cells in row-major order
+CREATE TABLE raffle_layouts (
+    raffle_id UUID PRIMARY KEY REFERENCES raffles(id) ON DELETE CASCADE,
+    name VARCHAR(50),
+    -- [{"row": 0, "col": 0}, ...]
+    disabled_cells JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('raffle_max_grid_rows', '100', 'Maximum number of rows in a raffle grid'),
+    ('raffle_max_grid_cols', '100', 'Maximum number of columns in a raffle grid')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,38 @@
+-- Durable storage of incoming webhooks with internal retries and manual redelivery
+
+CREATE TABLE webhook_deliveries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    source VARCHAR(20) NOT NULL,
+    -- Sender's event id; redeliveries from the sender reuse the stored row
+    external_event_id VARCHAR(255),
+    event_type VARCHAR(100) NOT NULL,
+    payload JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP WITH TIME ZONE,
+    last_attempt_at TIMESTAMP WITH TIME ZONE,
+    processed_at TIMESTAMP WITH TIME ZONE,
+    redelivered_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    redelivered_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT webhook_deliveries_source_check CHECK (source IN ('stripe', 'blockchain')),
+    CONSTRAINT webhook_deliveries_status_check CHECK (status IN ('pending', 'processing', 'processed', 'failed', 'dead')),
+    CONSTRAINT webhook_deliveries_attempts_check CHECK (attempts >= 0)
+);
+
+CREATE UNIQUE INDEX idx_webhook_deliveries_source_event
+    ON webhook_deliveries(source, external_event_id) WHERE external_event_id IS NOT NULL;
+CREATE INDEX idx_webhook_deliveries_retry ON webhook_deliveries(next_attempt_at) WHERE status = 'failed';
+CREATE INDEX idx_webhook_deliveries_status_created ON webhook_deliveries(status, created_at);
+
+CREATE TRIGGER update_webhook_deliveries_updated_at
+    BEFORE UPDATE ON webhook_deliveries
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('webhook_retry_max_attempts', '8', 'Processing attempts before a stored webhook is marked dead'),
+    ('webhook_retry_base_seconds', '30', 'Wait before the first webhook retry; doubles with each further attempt'),
+    ('webhook_retry_max_seconds', '3600', 'Longest wait between webhook retries')
+ON CONFLICT (key) DO NOTHING;
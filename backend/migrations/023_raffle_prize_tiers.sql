@@ -0,0 +1,27 @@
+-- Tiered prizes: each winning place gets its own item or credit amount
+
+CREATE TYPE prize_type AS ENUM ('item', 'credits');
+
+ALTER TYPE credit_source ADD VALUE IF NOT EXISTS 'raffle_prize';
+
+CREATE TABLE raffle_prize_tiers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    raffle_id UUID NOT NULL REFERENCES raffles(id) ON DELETE CASCADE,
+    -- 1 for first place; the nth winner drawn takes place n
+    place INTEGER NOT NULL,
+    prize_type prize_type NOT NULL,
+    item_id UUID REFERENCES items(id),
+    credit_amount DECIMAL(10,2),
+    winner_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    awarded_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT raffle_prize_tiers_place_unique UNIQUE (raffle_id, place),
+    CONSTRAINT raffle_prize_tiers_place_check CHECK (place > 0),
+    CONSTRAINT raffle_prize_tiers_prize_check CHECK (
+        (prize_type = 'item' AND item_id IS NOT NULL AND credit_amount IS NULL)
+        OR (prize_type = 'credits' AND item_id IS NULL AND credit_amount > 0)
+    )
+);
+
+CREATE INDEX idx_raffle_prize_tiers_winner ON raffle_prize_tiers(winner_user_id) WHERE winner_user_id IS NOT NULL;
+CREATE INDEX idx_raffle_prize_tiers_item ON raffle_prize_tiers(item_id) WHERE item_id IS NOT NULL;
@@ -0,0 +1,50 @@
+-- Partner API keys and temporary holds on user credits
+
+CREATE TABLE partner_api_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    -- First characters of the key, shown to admins to tell keys apart
+    key_prefix VARCHAR(12) NOT NULL,
+    key_hash VARCHAR(64) NOT NULL UNIQUE,
+    scopes TEXT[] NOT NULL DEFAULT '{}',
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- A hold reserves general credits until it is captured, released or expires
+CREATE TABLE credit_holds (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    api_key_id UUID NOT NULL REFERENCES partner_api_keys(id),
+    -- Partner's own id for the hold; repeating it returns the existing hold
+    reference VARCHAR(255) NOT NULL,
+    amount DECIMAL(10,2) NOT NULL,
+    captured_amount DECIMAL(10,2),
+    status VARCHAR(20) NOT NULL DEFAULT 'held',
+    description TEXT,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    captured_at TIMESTAMP WITH TIME ZONE,
+    released_at TIMESTAMP WITH TIME ZONE,
+    voided_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT credit_holds_reference_unique UNIQUE (api_key_id, reference),
+    CONSTRAINT credit_holds_amount_check CHECK (amount > 0),
+    CONSTRAINT credit_holds_captured_amount_check CHECK (captured_amount IS NULL OR (captured_amount > 0 AND captured_amount <= amount)),
+    CONSTRAINT credit_holds_status_check CHECK (status IN ('held', 'captured', 'released', 'voided', 'expired'))
+);
+
+CREATE INDEX idx_credit_holds_user_held ON credit_holds(user_id) WHERE status = 'held';
+CREATE INDEX idx_credit_holds_expires_at ON credit_holds(expires_at) WHERE status = 'held';
+
+CREATE TRIGGER update_credit_holds_updated_at
+    BEFORE UPDATE ON credit_holds
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('credit_hold_default_minutes', '60', 'Minutes a credit hold lasts when the partner does not ask for a duration'),
+    ('credit_hold_max_minutes', '10080', 'Longest a partner may hold credits'),
+    ('credit_hold_void_window_hours', '24', 'Hours after capture during which a partner may void it and refund the credits')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,15 @@
+-- Scheduled raffle start and end times
+
+ALTER TYPE raffle_status ADD VALUE IF NOT EXISTS 'scheduled' BEFORE 'open';
+
+CREATE TYPE raffle_deadline_action AS ENUM ('cancel', 'complete');
+
+ALTER TABLE raffles
+    ADD COLUMN starts_at TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN ends_at TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN deadline_action raffle_deadline_action NOT NULL DEFAULT 'cancel',
+    ADD CONSTRAINT raffles_schedule_check CHECK (starts_at IS NULL OR ends_at IS NULL OR ends_at > starts_at);
+
+-- The scheduler scans these every minute
+CREATE INDEX idx_raffles_starts_at ON raffles(starts_at) WHERE starts_at IS NOT NULL;
+CREATE INDEX idx_raffles_ends_at ON raffles(ends_at) WHERE ends_at IS NOT NULL;
@@ -0,0 +1,25 @@
+-- Stored responses for requests sent with an Idempotency-Key header
+
+CREATE TABLE idempotency_keys (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    idempotency_key VARCHAR(255) NOT NULL,
+    method VARCHAR(10) NOT NULL,
+    path VARCHAR(500) NOT NULL,
+    -- SHA-256 of the request body; a retry must send the same body
+    request_hash VARCHAR(64) NOT NULL,
+    -- NULL until the first request finishes
+    response_status INTEGER,
+    response_content_type VARCHAR(255),
+    response_body BYTEA,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    CONSTRAINT idempotency_keys_user_key_unique UNIQUE (user_id, idempotency_key)
+);
+
+CREATE INDEX idx_idempotency_keys_expires_at ON idempotency_keys(expires_at);
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('idempotency_key_ttl_hours', '24', 'Hours a stored response is replayed for retries with the same Idempotency-Key')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,29 @@
+-- Exchange rates for localized price display and per-payment rate snapshots
+
+CREATE TABLE fx_rates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    base_currency CHAR(3) NOT NULL,
+    quote_currency CHAR(3) NOT NULL,
+    -- Units of quote_currency per one unit of base_currency
+    rate DECIMAL(18,8) NOT NULL,
+    provider VARCHAR(50) NOT NULL,
+    fetched_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CONSTRAINT fx_rates_rate_check CHECK (rate > 0)
+);
+
+CREATE INDEX idx_fx_rates_pair_fetched ON fx_rates(base_currency, quote_currency, fetched_at DESC);
+
+-- NULL shows prices in the settlement currency
+ALTER TABLE users ADD COLUMN display_currency CHAR(3);
+
+-- Rate in effect when the payment was made, so history shows what the user saw
+ALTER TABLE payments
+    ADD COLUMN display_currency CHAR(3),
+    ADD COLUMN display_amount DECIMAL(12,2),
+    ADD COLUMN fx_rate DECIMAL(18,8),
+    ADD COLUMN fx_rate_id UUID REFERENCES fx_rates(id) ON DELETE SET NULL;
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('fx_refresh_interval_minutes', '60', 'Minutes between exchange rate refreshes from the rate feed'),
+    ('fx_max_rate_age_hours', '24', 'Rates older than this are not used; prices are then shown in the settlement currency only')
+ON CONFLICT (key) DO NOTHING;
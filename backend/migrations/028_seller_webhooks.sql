@@ -0,0 +1,60 @@
+-- Outbound webhooks: sellers register callback URLs and receive signed events
+
+CREATE TABLE webhook_endpoints (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    seller_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    -- Signing secret; kept in plaintext because every delivery is signed with it
+    secret VARCHAR(100) NOT NULL,
+    event_types TEXT[] NOT NULL,
+    description TEXT,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT webhook_endpoints_event_types_check CHECK (
+        cardinality(event_types) > 0
+        AND event_types <@ ARRAY['raffle.completed', 'box.purchased', 'item.sold_out']::TEXT[]
+    )
+);
+
+CREATE INDEX idx_webhook_endpoints_seller ON webhook_endpoints(seller_id) WHERE is_active;
+
+-- One row per event sent to an endpoint; retried with exponential backoff until delivered or dead
+CREATE TABLE webhook_endpoint_deliveries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    endpoint_id UUID NOT NULL REFERENCES webhook_endpoints(id) ON DELETE CASCADE,
+    -- Same for every endpoint receiving the event, so receivers can deduplicate
+    event_id UUID NOT NULL,
+    event_type VARCHAR(50) NOT NULL,
+    payload JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_response_status INTEGER,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP WITH TIME ZONE,
+    last_attempt_at TIMESTAMP WITH TIME ZONE,
+    delivered_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT webhook_endpoint_deliveries_status_check CHECK (status IN ('pending', 'sending', 'delivered', 'failed', 'dead')),
+    CONSTRAINT webhook_endpoint_deliveries_attempts_check CHECK (attempts >= 0)
+);
+
+CREATE INDEX idx_webhook_endpoint_deliveries_endpoint ON webhook_endpoint_deliveries(endpoint_id, created_at DESC);
+CREATE INDEX idx_webhook_endpoint_deliveries_retry ON webhook_endpoint_deliveries(next_attempt_at) WHERE status IN ('pending', 'failed');
+CREATE INDEX idx_webhook_endpoint_deliveries_status_created ON webhook_endpoint_deliveries(status, created_at);
+
+CREATE TRIGGER update_webhook_endpoints_updated_at
+    BEFORE UPDATE ON webhook_endpoints
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_webhook_endpoint_deliveries_updated_at
+    BEFORE UPDATE ON webhook_endpoint_deliveries
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('seller_webhook_max_attempts', '10', 'Delivery attempts before a seller webhook is marked dead'),
+    ('seller_webhook_retry_base_seconds', '60', 'Wait before the first seller webhook retry; doubles with each further attempt'),
+    ('seller_webhook_retry_max_seconds', '21600', 'Longest wait between seller webhook retries'),
+    ('seller_webhook_max_endpoints', '10', 'Most webhook endpoints a seller may register')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,66 @@
+-- Seller sandbox: test-mode API keys and sandboxed items, raffles and fake purchases
+--
+-- Sandbox data lives in its own tables, so production queries, analytics and payouts
+-- never see it and a purge cannot touch real data.
+
+ALTER TABLE partner_api_keys
+    ADD COLUMN seller_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    ADD COLUMN is_test BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD CONSTRAINT partner_api_keys_test_seller_check CHECK (NOT is_test OR seller_id IS NOT NULL);
+
+CREATE INDEX idx_partner_api_keys_seller ON partner_api_keys(seller_id) WHERE seller_id IS NOT NULL;
+
+CREATE TABLE sandbox_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    seller_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    images TEXT[] NOT NULL DEFAULT '{}',
+    retail_price DECIMAL(10,2) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT sandbox_items_retail_price_check CHECK (retail_price > 0)
+);
+
+CREATE INDEX idx_sandbox_items_seller ON sandbox_items(seller_id, created_at DESC);
+
+CREATE TABLE sandbox_raffles (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    seller_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    item_id UUID NOT NULL REFERENCES sandbox_items(id) ON DELETE CASCADE,
+    total_boxes INTEGER NOT NULL,
+    box_price DECIMAL(10,2) NOT NULL,
+    boxes_sold INTEGER NOT NULL DEFAULT 0,
+    total_winners INTEGER NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'open',
+    -- Drawn at random from the sold boxes once the raffle sells out
+    winning_box_numbers INTEGER[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT sandbox_raffles_boxes_check CHECK (total_boxes > 0 AND boxes_sold >= 0 AND boxes_sold <= total_boxes),
+    CONSTRAINT sandbox_raffles_winners_check CHECK (total_winners > 0 AND total_winners <= total_boxes),
+    CONSTRAINT sandbox_raffles_box_price_check CHECK (box_price > 0),
+    CONSTRAINT sandbox_raffles_status_check CHECK (status IN ('open', 'completed'))
+);
+
+CREATE INDEX idx_sandbox_raffles_seller ON sandbox_raffles(seller_id, created_at DESC);
+
+CREATE TABLE sandbox_purchases (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    raffle_id UUID NOT NULL REFERENCES sandbox_raffles(id) ON DELETE CASCADE,
+    box_number INTEGER NOT NULL,
+    -- Made-up buyer name supplied by the seller's test, never a real user
+    buyer_reference VARCHAR(100),
+    amount DECIMAL(10,2) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT sandbox_purchases_box_unique UNIQUE (raffle_id, box_number)
+);
+
+CREATE TRIGGER update_sandbox_raffles_updated_at
+    BEFORE UPDATE ON sandbox_raffles
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('sandbox_max_raffles', '100', 'Most sandbox raffles a seller may have before purging'),
+    ('sandbox_max_test_keys', '5', 'Most active test-mode API keys a seller may hold')
+ON CONFLICT (key) DO NOTHING;
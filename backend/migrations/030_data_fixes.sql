@@ -0,0 +1,25 @@
+-- Admin data-fix toolkit: archive for box purchases detached from their raffle
+--
+-- Detaching deletes the purchase (and, by cascade, its receipt) so the box count can be
+-- repaired; the archived row keeps both so nothing is lost.
+
+CREATE TABLE detached_box_purchases (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    purchase_id UUID NOT NULL UNIQUE,
+    raffle_id UUID NOT NULL REFERENCES raffles(id),
+    user_id UUID NOT NULL REFERENCES users(id),
+    box_number INTEGER NOT NULL,
+    purchase_price_in_credits DECIMAL(10,2) NOT NULL,
+    transaction_id UUID,
+    blockchain_tx_hash VARCHAR(66),
+    purchased_at TIMESTAMP WITH TIME ZONE,
+    receipt JSONB,
+    orphan_reason VARCHAR(20) NOT NULL,
+    detached_by UUID NOT NULL REFERENCES users(id),
+    detach_note TEXT NOT NULL,
+    detached_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT detached_box_purchases_reason_check CHECK (orphan_reason IN ('outside_grid', 'unpaid'))
+);
+
+CREATE INDEX idx_detached_box_purchases_raffle ON detached_box_purchases(raffle_id);
+CREATE INDEX idx_detached_box_purchases_user ON detached_box_purchases(user_id);
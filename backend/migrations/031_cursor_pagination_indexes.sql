@@ -0,0 +1,11 @@
+-- Keyset indexes for cursor pagination on (created_at, id), newest first
+
+CREATE INDEX idx_items_available_created_id ON items(created_at DESC, id DESC)
+    WHERE status = 'available' AND stock_quantity > 0;
+
+CREATE INDEX idx_raffles_active_created_id ON raffles(created_at DESC, id DESC)
+    WHERE status IN ('open', 'full', 'drawing');
+
+CREATE INDEX idx_user_credits_user_created_id ON user_credits(user_id, created_at DESC, id DESC);
+
+CREATE INDEX idx_users_active_created_id ON users(created_at DESC, id DESC) WHERE is_active = true;
@@ -0,0 +1,31 @@
+-- Admin payment and transaction search
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- Card last4 is copied from the saved payment method when the intent is confirmed
+ALTER TABLE payments
+    ADD COLUMN card_last4 VARCHAR(4),
+    ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+        to_tsvector('simple', coalesce(description, '') || ' ' || coalesce(failure_reason, ''))
+    ) STORED;
+
+ALTER TABLE payments
+    ADD CONSTRAINT valid_payment_card_last4 CHECK (card_last4 IS NULL OR card_last4 ~ '^[0-9]{4}$');
+
+ALTER TABLE transactions
+    ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+        to_tsvector('simple', coalesce(payment_gateway_ref, '') || ' ' || coalesce(metadata::text, ''))
+    ) STORED;
+
+CREATE INDEX idx_payments_search_vector ON payments USING gin(search_vector);
+CREATE INDEX idx_payments_card_last4 ON payments(card_last4) WHERE card_last4 IS NOT NULL;
+CREATE INDEX idx_payments_amount ON payments(amount);
+CREATE INDEX idx_payments_created_id ON payments(created_at DESC, id DESC);
+CREATE INDEX idx_payments_intent_trgm ON payments USING gin(stripe_payment_intent_id gin_trgm_ops);
+
+CREATE INDEX idx_transactions_search_vector ON transactions USING gin(search_vector);
+CREATE INDEX idx_transactions_gateway_ref ON transactions(payment_gateway_ref) WHERE payment_gateway_ref IS NOT NULL;
+CREATE INDEX idx_transactions_amount ON transactions(amount);
+CREATE INDEX idx_transactions_created_id ON transactions(created_at DESC, id DESC);
+
+CREATE INDEX idx_users_email_trgm ON users USING gin(email gin_trgm_ops);
@@ -0,0 +1,37 @@
+-- Account deletion: soft delete on request, anonymize and purge after a grace period
+
+ALTER TABLE users
+    ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN anonymized_at TIMESTAMP WITH TIME ZONE;
+
+CREATE TABLE account_deletion_requests (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- Kept after anonymization as the record that the erasure was carried out
+    user_id UUID NOT NULL REFERENCES users(id),
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'cancelled', 'completed')),
+    reason TEXT,
+    requested_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    purge_after TIMESTAMP WITH TIME ZONE NOT NULL,
+    cancelled_at TIMESTAMP WITH TIME ZONE,
+    cancelled_by UUID REFERENCES users(id),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    -- Rows touched per table by the purge
+    purge_summary JSONB,
+    last_error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- At most one open request per user
+CREATE UNIQUE INDEX idx_account_deletion_requests_pending_user
+    ON account_deletion_requests(user_id) WHERE status = 'pending';
+CREATE INDEX idx_account_deletion_requests_due
+    ON account_deletion_requests(purge_after) WHERE status = 'pending';
+
+CREATE TRIGGER update_account_deletion_requests_updated_at BEFORE UPDATE ON account_deletion_requests
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('account_deletion_grace_days', '30', 'Days a deleted account can still be restored before its personal data is erased')
+ON CONFLICT (key) DO NOTHING;
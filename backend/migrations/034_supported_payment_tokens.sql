@@ -0,0 +1,7 @@
+-- ERC-20 tokens raffles may be priced in. Each entry:
+-- {"symbol": "USDC", "address": "0x...", "decimals": 6, "enabled": true}
+-- Decimals are checked against the token contract before every purchase.
+
+INSERT INTO system_settings (key, value, description, is_public) VALUES
+    ('supported_payment_tokens', '[]', 'ERC-20 tokens accepted for box purchases', true)
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,11 @@
+-- Early-bird and dynamic box pricing
+
+-- Raffles without a row sell every box at raffles.box_price
+CREATE TABLE raffle_pricing_curves (
+    raffle_id UUID PRIMARY KEY REFERENCES raffles(id) ON DELETE CASCADE,
+    basis VARCHAR(20) NOT NULL CHECK (basis IN ('fill_percentage', 'time')),
+    -- [{"from": 20, "box_price": "5.00"}, ...], ascending by "from"
+    tiers JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
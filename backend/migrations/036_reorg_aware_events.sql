@@ -0,0 +1,34 @@
+-- Reorg-aware blockchain event processing
+
+-- Events are pending until they are confirmation_blocks deep; rows already stored are final
+ALTER TABLE blockchain_events
+    ADD COLUMN IF NOT EXISTS status VARCHAR(20) NOT NULL DEFAULT 'confirmed'
+        CHECK (status IN ('pending', 'confirmed')),
+    ADD COLUMN IF NOT EXISTS confirmed_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE blockchain_events ALTER COLUMN status SET DEFAULT 'pending';
+
+CREATE INDEX IF NOT EXISTS idx_blockchain_events_pending
+    ON blockchain_events(block_number) WHERE status = 'pending';
+
+-- Hashes of recently processed blocks, compared against the chain to detect reorgs.
+-- Holds every block with an event plus each catch-up checkpoint, pruned past the reorg window.
+CREATE TABLE IF NOT EXISTS processed_blocks (
+    block_number BIGINT PRIMARY KEY,
+    block_hash VARCHAR(66) NOT NULL,
+    parent_hash VARCHAR(66) NOT NULL,
+    processed_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- One row per detected reorg, for operators
+CREATE TABLE IF NOT EXISTS blockchain_reorgs (
+    id BIGSERIAL PRIMARY KEY,
+    -- First block that was replaced; events from here on were rolled back and replayed
+    fork_block BIGINT NOT NULL,
+    old_block_hash VARCHAR(66) NOT NULL,
+    new_block_hash VARCHAR(66) NOT NULL,
+    rolled_back_events INTEGER NOT NULL,
+    affected_raffle_ids BIGINT[] NOT NULL DEFAULT '{}',
+    detected_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_blockchain_reorgs_detected_at ON blockchain_reorgs(detected_at);
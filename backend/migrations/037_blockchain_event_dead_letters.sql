@@ -0,0 +1,25 @@
+-- Blockchain events whose persistence failed, kept for inspection and retry
+
+CREATE TABLE IF NOT EXISTS blockchain_event_dead_letters (
+    id BIGSERIAL PRIMARY KEY,
+    event_type VARCHAR(50) NOT NULL,
+    blockchain_raffle_id BIGINT NOT NULL,
+    block_number BIGINT NOT NULL,
+    transaction_hash VARCHAR(66) NOT NULL,
+    -- The processed event as the handler built it; replayed as-is
+    payload JSONB NOT NULL,
+    last_error TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'resolved', 'abandoned')),
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_attempted_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    -- The same event failing again updates its existing row
+    UNIQUE (transaction_hash, event_type, blockchain_raffle_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_blockchain_event_dead_letters_due
+    ON blockchain_event_dead_letters(next_attempt_at) WHERE status = 'pending';
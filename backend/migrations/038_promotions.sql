@@ -0,0 +1,44 @@
+-- Timed promotions: credit multipliers on credit purchases and platform fee discounts
+
+CREATE TABLE IF NOT EXISTS promotions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('credit_multiplier', 'fee_discount')),
+    -- Credits issued per purchased credit for a multiplier (2.00 = double), percent off for a discount
+    value DECIMAL(6,2) NOT NULL CHECK (value > 0),
+    starts_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    ends_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    -- Pricing goes by the window; the status only tracks what has been announced
+    status VARCHAR(20) NOT NULL DEFAULT 'scheduled'
+        CHECK (status IN ('scheduled', 'active', 'ended', 'cancelled')),
+    activated_at TIMESTAMP WITH TIME ZONE,
+    ended_at TIMESTAMP WITH TIME ZONE,
+    created_by UUID REFERENCES users(id),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    CONSTRAINT promotion_window CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_promotions_window
+    ON promotions(kind, starts_at, ends_at) WHERE status <> 'cancelled';
+
+-- Every purchase bonus or fee discount a promotion granted, for uplift reports
+CREATE TABLE IF NOT EXISTS promotion_redemptions (
+    id BIGSERIAL PRIMARY KEY,
+    promotion_id UUID NOT NULL REFERENCES promotions(id),
+    user_id UUID REFERENCES users(id),
+    payment_id UUID REFERENCES payments(id),
+    raffle_id UUID REFERENCES raffles(id),
+    -- Credits purchased, or the platform fee before the discount
+    base_amount DECIMAL(12,2) NOT NULL,
+    -- Bonus credits issued, or the fee waived
+    benefit_amount DECIMAL(12,2) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_promotion_redemptions_promotion ON promotion_redemptions(promotion_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_promotion_redemptions_payment
+    ON promotion_redemptions(promotion_id, payment_id) WHERE payment_id IS NOT NULL;
+
+-- Raffles keep the discount they were created under, so statements price them the same way
+ALTER TABLE raffles ADD COLUMN IF NOT EXISTS fee_promotion_id UUID REFERENCES promotions(id);
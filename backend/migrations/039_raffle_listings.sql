@@ -0,0 +1,179 @@
+-- Denormalized read model behind raffle search, active and featured listings
+
+-- One row per raffle with the item and seller fields the listing endpoints return,
+-- so a page is a single index range scan instead of a raffle scan plus an item lookup per row.
+-- Kept current by the triggers below; nothing writes to it directly.
+CREATE TABLE IF NOT EXISTS raffle_listings (
+    raffle_id UUID PRIMARY KEY REFERENCES raffles(id) ON DELETE CASCADE,
+    item_id UUID NOT NULL,
+    total_boxes INTEGER NOT NULL,
+    box_price DECIMAL(10,2) NOT NULL,
+    boxes_sold INTEGER NOT NULL,
+    total_winners INTEGER NOT NULL,
+    status raffle_status NOT NULL,
+    winner_user_ids UUID[] NOT NULL DEFAULT '{}',
+    grid_rows INTEGER NOT NULL,
+    grid_cols INTEGER NOT NULL,
+    starts_at TIMESTAMP WITH TIME ZONE,
+    ends_at TIMESTAMP WITH TIME ZONE,
+    deadline_action raffle_deadline_action NOT NULL,
+    started_at TIMESTAMP WITH TIME ZONE,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    completion_percentage DECIMAL(5,2) NOT NULL,
+    -- Verified sellers first, then the fullest raffles
+    featured_score DECIMAL(6,2) NOT NULL,
+    item_seller_id UUID,
+    item_name VARCHAR(255) NOT NULL,
+    item_description TEXT,
+    item_images TEXT[] NOT NULL,
+    item_retail_price DECIMAL(10,2) NOT NULL,
+    item_cost_of_goods DECIMAL(10,2) NOT NULL,
+    item_status item_status NOT NULL,
+    item_stock_quantity INTEGER NOT NULL,
+    item_listing_fee_applied DECIMAL(10,2),
+    item_category VARCHAR(100),
+    item_created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    item_updated_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    seller_company_name VARCHAR(255),
+    seller_is_verified BOOLEAN NOT NULL DEFAULT FALSE,
+    refreshed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE OR REPLACE FUNCTION refresh_raffle_listing(p_raffle_id UUID)
+RETURNS VOID AS $$
+BEGIN
+    INSERT INTO raffle_listings (
+        raffle_id, item_id, total_boxes, box_price, boxes_sold, total_winners, status,
+        winner_user_ids, grid_rows, grid_cols, starts_at, ends_at, deadline_action,
+        started_at, completed_at, created_at, completion_percentage, featured_score,
+        item_seller_id, item_name, item_description, item_images, item_retail_price,
+        item_cost_of_goods, item_status, item_stock_quantity, item_listing_fee_applied,
+        item_category, item_created_at, item_updated_at, seller_company_name,
+        seller_is_verified, refreshed_at
+    )
+    SELECT
+        r.id, r.item_id, r.total_boxes, r.box_price, COALESCE(r.boxes_sold, 0), r.total_winners,
+        COALESCE(r.status, 'open'), COALESCE(r.winner_user_ids, '{}'), r.grid_rows, r.grid_cols,
+        r.starts_at, r.ends_at, r.deadline_action, r.started_at, r.completed_at,
+        COALESCE(r.created_at, NOW()), c.completion,
+        c.completion + CASE WHEN COALESCE(s.is_verified, FALSE) THEN 100 ELSE 0 END,
+        i.seller_id, i.name, i.description, i.images, i.retail_price, i.cost_of_goods,
+        COALESCE(i.status, 'available'), COALESCE(i.stock_quantity, 1), i.listing_fee_applied,
+        i.category, COALESCE(i.created_at, NOW()), COALESCE(i.updated_at, NOW()),
+        s.company_name, COALESCE(s.is_verified, FALSE), NOW()
+    FROM raffles r
+    JOIN items i ON i.id = r.item_id
+    LEFT JOIN sellers s ON s.id = i.seller_id
+    CROSS JOIN LATERAL (
+        SELECT CASE WHEN r.total_boxes > 0
+            THEN ROUND(LEAST(COALESCE(r.boxes_sold, 0), r.total_boxes) * 100.0 / r.total_boxes, 2)
+            ELSE 0 END AS completion
+    ) c
+    WHERE r.id = p_raffle_id
+    ON CONFLICT (raffle_id) DO UPDATE SET
+        item_id = EXCLUDED.item_id,
+        total_boxes = EXCLUDED.total_boxes,
+        box_price = EXCLUDED.box_price,
+        boxes_sold = EXCLUDED.boxes_sold,
+        total_winners = EXCLUDED.total_winners,
+        status = EXCLUDED.status,
+        winner_user_ids = EXCLUDED.winner_user_ids,
+        grid_rows = EXCLUDED.grid_rows,
+        grid_cols = EXCLUDED.grid_cols,
+        starts_at = EXCLUDED.starts_at,
+        ends_at = EXCLUDED.ends_at,
+        deadline_action = EXCLUDED.deadline_action,
+        started_at = EXCLUDED.started_at,
+        completed_at = EXCLUDED.completed_at,
+        created_at = EXCLUDED.created_at,
+        completion_percentage = EXCLUDED.completion_percentage,
+        featured_score = EXCLUDED.featured_score,
+        item_seller_id = EXCLUDED.item_seller_id,
+        item_name = EXCLUDED.item_name,
+        item_description = EXCLUDED.item_description,
+        item_images = EXCLUDED.item_images,
+        item_retail_price = EXCLUDED.item_retail_price,
+        item_cost_of_goods = EXCLUDED.item_cost_of_goods,
+        item_status = EXCLUDED.item_status,
+        item_stock_quantity = EXCLUDED.item_stock_quantity,
+        item_listing_fee_applied = EXCLUDED.item_listing_fee_applied,
+        item_category = EXCLUDED.item_category,
+        item_created_at = EXCLUDED.item_created_at,
+        item_updated_at = EXCLUDED.item_updated_at,
+        seller_company_name = EXCLUDED.seller_company_name,
+        seller_is_verified = EXCLUDED.seller_is_verified,
+        refreshed_at = EXCLUDED.refreshed_at;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Triggers cover every writer, including the blockchain event processor's raw SQL
+CREATE OR REPLACE FUNCTION raffle_listings_sync_raffle()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM refresh_raffle_listing(NEW.id);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION raffle_listings_sync_item()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM refresh_raffle_listing(r.id) FROM raffles r WHERE r.item_id = NEW.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION raffle_listings_sync_seller()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM refresh_raffle_listing(r.id)
+    FROM raffles r
+    JOIN items i ON i.id = r.item_id
+    WHERE i.seller_id = NEW.id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS raffle_listings_raffle_changed ON raffles;
+CREATE TRIGGER raffle_listings_raffle_changed
+    AFTER INSERT OR UPDATE ON raffles
+    FOR EACH ROW EXECUTE FUNCTION raffle_listings_sync_raffle();
+
+DROP TRIGGER IF EXISTS raffle_listings_item_changed ON items;
+CREATE TRIGGER raffle_listings_item_changed
+    AFTER UPDATE ON items
+    FOR EACH ROW EXECUTE FUNCTION raffle_listings_sync_item();
+
+DROP TRIGGER IF EXISTS raffle_listings_seller_changed ON sellers;
+CREATE TRIGGER raffle_listings_seller_changed
+    AFTER UPDATE OF company_name, is_verified ON sellers
+    FOR EACH ROW EXECUTE FUNCTION raffle_listings_sync_seller();
+
+-- Backfill existing raffles
+SELECT refresh_raffle_listing(id) FROM raffles;
+
+-- Keyset indexes for each listing sort, limited to the raffles public listings show.
+-- The default sort carries the filter columns so filtered pages are decided from the index.
+CREATE INDEX IF NOT EXISTS idx_raffle_listings_active_created
+    ON raffle_listings(created_at DESC, raffle_id DESC)
+    INCLUDE (status, item_id, box_price, completion_percentage)
+    WHERE status IN ('open', 'full', 'drawing');
+CREATE INDEX IF NOT EXISTS idx_raffle_listings_active_price
+    ON raffle_listings(box_price, raffle_id)
+    WHERE status IN ('open', 'full', 'drawing');
+CREATE INDEX IF NOT EXISTS idx_raffle_listings_active_completion
+    ON raffle_listings(completion_percentage DESC, raffle_id DESC)
+    WHERE status IN ('open', 'full', 'drawing');
+CREATE INDEX IF NOT EXISTS idx_raffle_listings_active_ending
+    ON raffle_listings(ends_at, raffle_id)
+    WHERE status IN ('open', 'full', 'drawing') AND ends_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_raffle_listings_open_featured
+    ON raffle_listings(featured_score DESC, raffle_id DESC)
+    WHERE status = 'open';
+-- Listings filtered to one status outside the active set
+CREATE INDEX IF NOT EXISTS idx_raffle_listings_status_created
+    ON raffle_listings(status, created_at DESC, raffle_id DESC);
+CREATE INDEX IF NOT EXISTS idx_raffle_listings_item ON raffle_listings(item_id);
+
+COMMENT ON TABLE raffle_listings IS 'Trigger-maintained read model for public raffle listings';
@@ -0,0 +1,3 @@
+-- Per-user token version; every JWT carries the version it was issued at.
+-- Bumping it (password change, role change, ban) rejects all earlier tokens at once.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS token_version INTEGER NOT NULL DEFAULT 0;
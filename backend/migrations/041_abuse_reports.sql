@@ -0,0 +1,46 @@
+-- User reports of abusive listings, comments and users, triaged by admins
+
+CREATE TABLE IF NOT EXISTS abuse_reports (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    reporter_id UUID REFERENCES users(id) ON DELETE CASCADE NOT NULL,
+    target_type VARCHAR(20) NOT NULL,
+    target_id UUID NOT NULL,
+    -- Account behind the reported content; the target itself for user reports
+    reported_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    category VARCHAR(30) NOT NULL,
+    description TEXT,
+    evidence_urls TEXT[] NOT NULL DEFAULT '{}',
+    status VARCHAR(20) NOT NULL DEFAULT 'open',
+    resolution_action VARCHAR(20),
+    resolution_note TEXT,
+    reviewed_by UUID REFERENCES users(id),
+    reviewed_at TIMESTAMP WITH TIME ZONE,
+    resolved_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT abuse_reports_target_type_check CHECK (target_type IN ('raffle', 'item', 'comment', 'user')),
+    CONSTRAINT abuse_reports_category_check CHECK (category IN (
+        'spam', 'scam', 'counterfeit', 'prohibited_item', 'harassment', 'impersonation', 'offensive', 'other'
+    )),
+    CONSTRAINT abuse_reports_status_check CHECK (status IN ('open', 'reviewing', 'resolved', 'dismissed')),
+    CONSTRAINT abuse_reports_resolution_action_check CHECK (
+        resolution_action IS NULL OR resolution_action IN ('remove_listing', 'remove_comment', 'warn', 'ban')
+    ),
+    CONSTRAINT abuse_reports_description_check CHECK (description IS NULL OR char_length(description) <= 2000),
+    CONSTRAINT abuse_reports_evidence_check CHECK (cardinality(evidence_urls) <= 5)
+);
+
+-- A reporter has at most one open report per target
+CREATE UNIQUE INDEX IF NOT EXISTS idx_abuse_reports_open_per_reporter
+    ON abuse_reports(reporter_id, target_type, target_id) WHERE status IN ('open', 'reviewing');
+CREATE INDEX IF NOT EXISTS idx_abuse_reports_triage ON abuse_reports(status, created_at);
+CREATE INDEX IF NOT EXISTS idx_abuse_reports_target ON abuse_reports(target_type, target_id);
+CREATE INDEX IF NOT EXISTS idx_abuse_reports_reporter_created ON abuse_reports(reporter_id, created_at DESC);
+
+CREATE TRIGGER update_abuse_reports_updated_at
+    BEFORE UPDATE ON abuse_reports
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('abuse_report_limit_per_day', '20', 'Maximum abuse reports a user may file per day')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,48 @@
+-- Email domain blocking for registration and email changes
+
+-- Disposable address providers; replaced wholesale by the refresh job
+CREATE TABLE IF NOT EXISTS disposable_email_domains (
+    domain VARCHAR(253) PRIMARY KEY,
+    source VARCHAR(20) NOT NULL DEFAULT 'seed',
+    refreshed_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT disposable_email_domains_source_check CHECK (source IN ('seed', 'feed'))
+);
+
+-- Admin exceptions: 'allow' lets a listed disposable domain through, 'block' stops any domain.
+-- A rule covers the domain's subdomains; the most specific rule wins.
+CREATE TABLE IF NOT EXISTS email_domain_rules (
+    domain VARCHAR(253) PRIMARY KEY,
+    rule VARCHAR(10) NOT NULL,
+    reason TEXT,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT email_domain_rules_rule_check CHECK (rule IN ('block', 'allow')),
+    CONSTRAINT email_domain_rules_domain_check CHECK (domain = LOWER(domain))
+);
+
+CREATE TRIGGER update_email_domain_rules_updated_at
+    BEFORE UPDATE ON email_domain_rules
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('disposable_email_blocking_enabled', 'true', 'Reject registrations and email changes using disposable email domains'),
+    ('disposable_domains_refresh_interval_hours', '24', 'How often the disposable email domain list is refreshed from its feed')
+ON CONFLICT (key) DO NOTHING;
+
+-- Well-known providers so blocking works before the first feed refresh
+INSERT INTO disposable_email_domains (domain) VALUES
+    ('10minutemail.com'), ('20minutemail.com'), ('33mail.com'), ('anonbox.net'),
+    ('burnermail.io'), ('discard.email'), ('dispostable.com'), ('emailondeck.com'),
+    ('fakeinbox.com'), ('getairmail.com'), ('getnada.com'), ('guerrillamail.biz'),
+    ('guerrillamail.com'), ('guerrillamail.de'), ('guerrillamail.info'), ('guerrillamail.net'),
+    ('guerrillamail.org'), ('guerrillamailblock.com'), ('harakirimail.com'), ('inboxbear.com'),
+    ('incognitomail.org'), ('jetable.org'), ('mailcatch.com'), ('maildrop.cc'),
+    ('mailinator.com'), ('mailinator.net'), ('mailnesia.com'), ('mailpoof.com'),
+    ('mintemail.com'), ('mohmal.com'), ('moakt.com'), ('mytemp.email'),
+    ('sharklasers.com'), ('spam4.me'), ('spambox.us'), ('spamgourmet.com'),
+    ('temp-mail.io'), ('temp-mail.org'), ('tempail.com'), ('tempinbox.com'),
+    ('tempmail.dev'), ('tempmail.net'), ('tempmailo.com'), ('tempr.email'),
+    ('throwawaymail.com'), ('trashmail.com'), ('trashmail.de'), ('trashmail.net'),
+    ('yopmail.com'), ('yopmail.fr'), ('yopmail.net')
+ON CONFLICT (domain) DO NOTHING;
@@ -0,0 +1,5 @@
+-- Per-connection cap on WebSocket rooms (raffle:{id}, item:{id}, user:{id})
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('websocket_max_rooms_per_connection', '50', 'Maximum WebSocket rooms a single connection may join')
+ON CONFLICT (key) DO NOTHING;
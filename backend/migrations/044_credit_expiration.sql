@@ -0,0 +1,21 @@
+-- Credits past their expiry are marked expired by a worker instead of being deleted
+
+ALTER TABLE user_credits ADD COLUMN IF NOT EXISTS expired_at TIMESTAMP WITH TIME ZONE;
+
+-- Worker scan: unused credits not yet marked expired
+CREATE INDEX IF NOT EXISTS idx_user_credits_pending_expiry
+    ON user_credits(expires_at)
+    WHERE is_used = false AND expired_at IS NULL AND expires_at IS NOT NULL;
+
+-- One warning per credit per threshold, so restarts don't resend them
+CREATE TABLE IF NOT EXISTS credit_expiry_warnings (
+    credit_id UUID REFERENCES user_credits(id) ON DELETE CASCADE NOT NULL,
+    days_before INTEGER NOT NULL,
+    sent_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    PRIMARY KEY (credit_id, days_before)
+);
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('credit_expiry_warning_days', '[7, 3, 1]', 'Days before expiry at which users are warned about expiring credits'),
+    ('credit_expiration_interval_minutes', '60', 'How often credits past their expiry are marked expired')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,60 @@
+-- Seller shipping policies, item-level overrides and winner shipping addresses
+
+-- zones is an array of {country, region, rate, transit_days_min, transit_days_max};
+-- country '*' covers every destination without a more specific zone
+CREATE TABLE IF NOT EXISTS seller_shipping_policies (
+    seller_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    handling_days_min INTEGER NOT NULL DEFAULT 1,
+    handling_days_max INTEGER NOT NULL DEFAULT 3,
+    zones JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT seller_shipping_policies_handling_check
+        CHECK (handling_days_min >= 0 AND handling_days_max >= handling_days_min)
+);
+
+-- Per-item shipping data; NULL columns fall back to the seller policy and
+-- zones, when set, replace the seller's zones entirely
+CREATE TABLE IF NOT EXISTS item_shipping_overrides (
+    item_id UUID PRIMARY KEY REFERENCES items(id) ON DELETE CASCADE,
+    weight_grams INTEGER,
+    handling_days_min INTEGER,
+    handling_days_max INTEGER,
+    zones JSONB,
+    free_shipping BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT item_shipping_overrides_weight_check CHECK (weight_grams IS NULL OR weight_grams > 0),
+    CONSTRAINT item_shipping_overrides_handling_check
+        CHECK (handling_days_min IS NULL OR handling_days_min >= 0),
+    CONSTRAINT item_shipping_overrides_handling_range_check
+        CHECK (handling_days_max IS NULL OR handling_days_max >= COALESCE(handling_days_min, 0))
+);
+
+-- Where a user's prizes are shipped
+CREATE TABLE IF NOT EXISTS user_shipping_addresses (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    recipient_name VARCHAR(200) NOT NULL,
+    line1 VARCHAR(255) NOT NULL,
+    line2 VARCHAR(255),
+    city VARCHAR(100) NOT NULL,
+    region_code VARCHAR(3),
+    postal_code VARCHAR(20),
+    country_code CHAR(2) NOT NULL,
+    phone VARCHAR(30),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT user_shipping_addresses_country_check CHECK (country_code = UPPER(country_code))
+);
+
+CREATE TRIGGER update_seller_shipping_policies_updated_at
+    BEFORE UPDATE ON seller_shipping_policies
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_item_shipping_overrides_updated_at
+    BEFORE UPDATE ON item_shipping_overrides
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TRIGGER update_user_shipping_addresses_updated_at
+    BEFORE UPDATE ON user_shipping_addresses
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,5 @@
+-- Threshold for reporting WebSocket connections that are not keeping up with events
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('websocket_slow_consumer_drop_threshold', '20', 'Messages a WebSocket connection may miss in a row for a full mailbox before it is reported as a slow consumer')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,47 @@
+-- Fine-grained admin permissions granted per role
+
+-- Staff role that only holds the permissions granted to it below
+ALTER TYPE user_role ADD VALUE IF NOT EXISTS 'support';
+
+CREATE TABLE IF NOT EXISTS permissions (
+    key VARCHAR(100) PRIMARY KEY,
+    description TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- role is text rather than user_role: a value added by ALTER TYPE can't be used
+-- in the transaction that adds it
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role VARCHAR(20) NOT NULL,
+    permission VARCHAR(100) NOT NULL REFERENCES permissions(key) ON DELETE CASCADE,
+    granted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    PRIMARY KEY (role, permission),
+    CONSTRAINT role_permissions_role_check
+        CHECK (role IN ('user', 'seller', 'support', 'admin', 'operator'))
+);
+
+INSERT INTO permissions (key, description) VALUES
+    ('credits.issue', 'Issue credits and bonus credits to users'),
+    ('raffles.force_complete', 'Force a raffle to draw winners'),
+    ('payments.view', 'Search payments and view payment and refund details'),
+    ('payments.refund', 'Refund payments'),
+    ('users.ban', 'Ban and unban users'),
+    ('users.change_role', 'Change user roles'),
+    ('permissions.manage', 'Change the permissions granted to each role')
+ON CONFLICT (key) DO NOTHING;
+
+-- Operators hold every permission regardless of these rows; they are listed for visibility
+INSERT INTO role_permissions (role, permission)
+SELECT 'operator', key FROM permissions
+ON CONFLICT DO NOTHING;
+
+-- Admins keep everything they could do before, apart from managing permissions
+INSERT INTO role_permissions (role, permission)
+SELECT 'admin', key FROM permissions WHERE key <> 'permissions.manage'
+ON CONFLICT DO NOTHING;
+
+INSERT INTO role_permissions (role, permission) VALUES
+    ('support', 'payments.view'),
+    ('support', 'payments.refund')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,7 @@
+-- Per-user box limits for raffles
+
+-- NULL leaves the raffle uncapped
+ALTER TABLE raffles ADD COLUMN IF NOT EXISTS max_boxes_per_user INTEGER;
+
+ALTER TABLE raffles ADD CONSTRAINT raffles_max_boxes_per_user_check
+    CHECK (max_boxes_per_user IS NULL OR (max_boxes_per_user > 0 AND max_boxes_per_user <= total_boxes));
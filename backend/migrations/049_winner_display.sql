@@ -0,0 +1,15 @@
+-- How a user's name is shown when they win
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS first_name VARCHAR(50),
+    ADD COLUMN IF NOT EXISTS last_name VARCHAR(50),
+    -- Masked username unless the user picks otherwise
+    ADD COLUMN IF NOT EXISTS winner_display VARCHAR(20) NOT NULL DEFAULT 'masked_username';
+
+ALTER TABLE users ADD CONSTRAINT users_winner_display_check
+    CHECK (winner_display IN ('first_name_initial', 'masked_username', 'anonymous'));
+
+-- Winner gallery reads the most recent awards first
+CREATE INDEX IF NOT EXISTS idx_raffle_prize_tiers_awarded_at
+    ON raffle_prize_tiers(awarded_at DESC)
+    WHERE winner_user_id IS NOT NULL;
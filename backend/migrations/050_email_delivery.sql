@@ -0,0 +1,51 @@
+-- Outbound email: a send queue retried with exponential backoff, and addresses we no longer send to
+
+CREATE TABLE email_messages (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    to_address VARCHAR(255) NOT NULL,
+    template VARCHAR(50) NOT NULL,
+    -- Template data; cleared once the message is sent because it can carry one-time tokens
+    payload JSONB NOT NULL DEFAULT '{}',
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- Provider that accepted the message and the id it assigned, for matching bounce reports
+    provider VARCHAR(20),
+    provider_message_id VARCHAR(255),
+    last_error TEXT,
+    next_attempt_at TIMESTAMP WITH TIME ZONE,
+    last_attempt_at TIMESTAMP WITH TIME ZONE,
+    sent_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT email_messages_status_check CHECK (status IN ('pending', 'sending', 'sent', 'failed', 'dead', 'suppressed')),
+    CONSTRAINT email_messages_template_check CHECK (template IN ('verification', 'password_reset', 'raffle_won', 'receipt', 'notification')),
+    CONSTRAINT email_messages_attempts_check CHECK (attempts >= 0)
+);
+
+CREATE INDEX idx_email_messages_retry ON email_messages(next_attempt_at) WHERE status IN ('pending', 'failed');
+CREATE INDEX idx_email_messages_status_created ON email_messages(status, created_at);
+CREATE INDEX idx_email_messages_user ON email_messages(user_id, created_at DESC);
+CREATE INDEX idx_email_messages_provider_message ON email_messages(provider_message_id) WHERE provider_message_id IS NOT NULL;
+
+-- Hard bounces and complaints stop all further mail to an address; admins can add or lift entries
+CREATE TABLE email_suppressions (
+    -- Lowercased
+    email VARCHAR(255) PRIMARY KEY,
+    reason VARCHAR(20) NOT NULL,
+    details TEXT,
+    -- Admin who added the entry; NULL when it came from a bounce or complaint report
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT email_suppressions_reason_check CHECK (reason IN ('hard_bounce', 'complaint', 'manual'))
+);
+
+CREATE TRIGGER update_email_messages_updated_at
+    BEFORE UPDATE ON email_messages
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('email_max_attempts', '8', 'Send attempts before an email is marked dead'),
+    ('email_retry_base_seconds', '60', 'Wait before the first email retry; doubles with each further attempt'),
+    ('email_retry_max_seconds', '3600', 'Longest wait between email retries')
+ON CONFLICT (key) DO NOTHING;
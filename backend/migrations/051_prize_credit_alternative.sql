@@ -0,0 +1,22 @@
+-- Winners of an item prize may take a seller-set credit value instead
+
+ALTER TABLE raffle_prize_tiers
+    -- Offered in place of the item; only item prizes can have one
+    ADD COLUMN IF NOT EXISTS credit_alternative DECIMAL(10,2),
+    -- NULL until the winner chooses; item prizes without an alternative are always fulfilled as the item
+    ADD COLUMN IF NOT EXISTS winner_choice VARCHAR(20),
+    ADD COLUMN IF NOT EXISTS choice_made_at TIMESTAMP WITH TIME ZONE;
+
+ALTER TABLE raffle_prize_tiers ADD CONSTRAINT raffle_prize_tiers_credit_alternative_check
+    CHECK (credit_alternative IS NULL OR (prize_type = 'item' AND credit_alternative > 0));
+
+ALTER TABLE raffle_prize_tiers ADD CONSTRAINT raffle_prize_tiers_winner_choice_check
+    CHECK (
+        winner_choice IS NULL
+        OR (winner_choice IN ('item', 'credits') AND credit_alternative IS NOT NULL AND winner_user_id IS NOT NULL)
+    );
+
+-- Seller statements deduct credit values taken in place of items
+CREATE INDEX IF NOT EXISTS idx_raffle_prize_tiers_credits_chosen
+    ON raffle_prize_tiers(raffle_id)
+    WHERE winner_choice = 'credits';
@@ -0,0 +1,30 @@
+-- Restore drills: each new logical backup is restored into a scratch database and checked
+
+CREATE TABLE backup_verification_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- File name within the backup directory
+    backup_file VARCHAR(255) NOT NULL,
+    backup_size_bytes BIGINT NOT NULL,
+    -- File modification time, taken as when the backup was made
+    backup_taken_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'running',
+    -- One entry per integrity check: name, passed, expected/actual and details
+    checks JSONB NOT NULL DEFAULT '[]',
+    error TEXT,
+    -- Admin who started the drill; NULL for scheduled drills
+    triggered_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT backup_verification_runs_status_check CHECK (status IN ('running', 'passed', 'failed'))
+);
+
+CREATE INDEX idx_backup_verification_runs_started ON backup_verification_runs(started_at DESC);
+CREATE INDEX idx_backup_verification_runs_file ON backup_verification_runs(backup_file);
+-- Only one drill may use the scratch database at a time
+CREATE UNIQUE INDEX idx_backup_verification_runs_one_running ON backup_verification_runs((status)) WHERE status = 'running';
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('backup_max_age_hours', '26', 'A drill fails when the newest backup is older than this'),
+    ('backup_restore_timeout_minutes', '60', 'Longest a restore may run before the drill is failed'),
+    ('backup_row_count_tolerance_percent', '1', 'Allowed difference between restored and source row counts, for rows deleted since the backup')
+ON CONFLICT (key) DO NOTHING;
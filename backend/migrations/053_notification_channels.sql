@@ -0,0 +1,53 @@
+-- Per-user delivery channels for notifications, and the devices push notifications go to
+
+CREATE TABLE notification_preferences (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    -- Channels
+    in_app_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    email_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    push_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Off until the user opts in; needs a phone number on the account
+    sms_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Kinds of notification; receipts and system alerts are always sent
+    credit_expiry_notifications BOOLEAN NOT NULL DEFAULT TRUE,
+    raffle_notifications BOOLEAN NOT NULL DEFAULT TRUE,
+    payment_notifications BOOLEAN NOT NULL DEFAULT TRUE,
+    marketing_notifications BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER update_notification_preferences_updated_at
+    BEFORE UPDATE ON notification_preferences
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Carry over choices saved in the old, never-read preferences table
+INSERT INTO notification_preferences (
+    user_id, email_enabled, push_enabled, sms_enabled,
+    credit_expiry_notifications, raffle_notifications, marketing_notifications
+)
+SELECT
+    user_id,
+    COALESCE(email_notifications, TRUE),
+    COALESCE(push_notifications, TRUE),
+    COALESCE(sms_notifications, FALSE),
+    COALESCE(credit_expiry_alerts, TRUE),
+    COALESCE(raffle_updates, TRUE) OR COALESCE(winner_announcements, TRUE),
+    COALESCE(marketing_emails, FALSE)
+FROM user_notification_preferences
+ON CONFLICT (user_id) DO NOTHING;
+
+CREATE TABLE push_devices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- fcm for Android, apns for iOS
+    platform VARCHAR(10) NOT NULL,
+    -- Registration token from the device; a token belongs to whoever registered it last
+    token VARCHAR(512) NOT NULL UNIQUE,
+    device_name VARCHAR(100),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CONSTRAINT push_devices_platform_check CHECK (platform IN ('fcm', 'apns'))
+);
+
+CREATE INDEX idx_push_devices_user ON push_devices(user_id);
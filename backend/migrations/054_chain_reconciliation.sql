@@ -0,0 +1,45 @@
+-- Periodic comparison of on-chain raffle state with the raffles table
+
+CREATE TABLE chain_reconciliation_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    status VARCHAR(20) NOT NULL DEFAULT 'running',
+    -- Whether repairable divergences were written back
+    repair BOOLEAN NOT NULL DEFAULT FALSE,
+    raffles_checked INTEGER NOT NULL DEFAULT 0,
+    divergences INTEGER NOT NULL DEFAULT 0,
+    repaired INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    -- Admin who started the run; NULL for scheduled runs
+    triggered_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT chain_reconciliation_runs_status_check CHECK (status IN ('running', 'completed', 'failed'))
+);
+
+CREATE INDEX idx_chain_reconciliation_runs_started ON chain_reconciliation_runs(started_at DESC);
+-- Runs would race each other's repairs
+CREATE UNIQUE INDEX idx_chain_reconciliation_runs_one_running ON chain_reconciliation_runs((status)) WHERE status = 'running';
+
+CREATE TABLE chain_reconciliation_findings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    run_id UUID NOT NULL REFERENCES chain_reconciliation_runs(id) ON DELETE CASCADE,
+    raffle_id UUID NOT NULL REFERENCES raffles(id) ON DELETE CASCADE,
+    blockchain_raffle_id BIGINT NOT NULL,
+    kind VARCHAR(30) NOT NULL,
+    db_value TEXT,
+    chain_value TEXT,
+    repaired BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CONSTRAINT chain_reconciliation_findings_kind_check CHECK (
+        kind IN ('boxes_sold_mismatch', 'status_mismatch', 'missing_winner', 'winner_mismatch', 'chain_read_failed')
+    )
+);
+
+CREATE INDEX idx_chain_reconciliation_findings_run ON chain_reconciliation_findings(run_id);
+CREATE INDEX idx_chain_reconciliation_findings_raffle ON chain_reconciliation_findings(raffle_id, created_at DESC);
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('chain_reconciliation_auto_repair', 'false', 'Scheduled reconciliation writes chain state back once a divergence is seen on two runs in a row'),
+    ('chain_reconciliation_lookback_days', '7', 'Completed and cancelled raffles are rechecked for this many days after they settle'),
+    ('chain_reconciliation_max_raffles', '500', 'Most raffles read from the chain per run, most recently updated first')
+ON CONFLICT (key) DO NOTHING;
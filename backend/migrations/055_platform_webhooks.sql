@@ -0,0 +1,40 @@
+-- Platform webhooks: admin-managed endpoints for internal systems such as the marketing CMS,
+-- filtered by raffle tags and delivered in order per raffle
+
+-- Labels admins put on raffles, e.g. 'featured'
+ALTER TABLE raffles ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX idx_raffles_tags ON raffles USING GIN (tags) WHERE cardinality(tags) > 0;
+
+-- Endpoints without a seller belong to the platform
+ALTER TABLE webhook_endpoints ALTER COLUMN seller_id DROP NOT NULL;
+
+-- Only raffles carrying one of these tags are sent; empty means every raffle
+ALTER TABLE webhook_endpoints ADD COLUMN raffle_tags TEXT[] NOT NULL DEFAULT '{}';
+-- Admin who registered a platform endpoint
+ALTER TABLE webhook_endpoints ADD COLUMN created_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE webhook_endpoints DROP CONSTRAINT webhook_endpoints_event_types_check;
+ALTER TABLE webhook_endpoints ADD CONSTRAINT webhook_endpoints_event_types_check CHECK (
+    cardinality(event_types) > 0
+    AND event_types <@ ARRAY[
+        'raffle.opened', 'raffle.completed', 'raffle.cancelled', 'box.purchased', 'item.sold_out'
+    ]::TEXT[]
+);
+ALTER TABLE webhook_endpoints ADD CONSTRAINT webhook_endpoints_raffle_tags_check CHECK (
+    seller_id IS NULL OR cardinality(raffle_tags) = 0
+);
+
+CREATE INDEX idx_webhook_endpoints_platform ON webhook_endpoints(created_at) WHERE seller_id IS NULL AND is_active;
+
+-- Raffle the event is about; deliveries for the same endpoint and raffle go out in sequence order
+ALTER TABLE webhook_endpoint_deliveries ADD COLUMN raffle_id UUID;
+ALTER TABLE webhook_endpoint_deliveries ADD COLUMN sequence BIGSERIAL;
+
+CREATE INDEX idx_webhook_endpoint_deliveries_ordering
+    ON webhook_endpoint_deliveries(endpoint_id, raffle_id, sequence)
+    WHERE raffle_id IS NOT NULL AND status IN ('pending', 'sending', 'failed');
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('platform_webhook_max_endpoints', '20', 'Most platform webhook endpoints admins may register')
+ON CONFLICT (key) DO NOTHING;
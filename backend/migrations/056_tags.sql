@@ -0,0 +1,135 @@
+-- Tags on items and raffles: a registry with curated tags and synonyms, tag filters on
+-- raffle search, and admin-curated collections behind themed discovery pages
+
+-- Every tag in use; sellers create free-form tags by using them, admins curate
+CREATE TABLE tags (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    slug VARCHAR(40) NOT NULL UNIQUE,
+    display_name VARCHAR(60) NOT NULL,
+    description TEXT,
+    -- Curated tags are only applied by admins
+    is_curated BOOLEAN NOT NULL DEFAULT FALSE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_tags_curated ON tags(slug) WHERE is_curated;
+
+-- Spellings rewritten to their tag whenever tags are saved or searched
+CREATE TABLE tag_synonyms (
+    synonym VARCHAR(40) PRIMARY KEY,
+    tag_slug VARCHAR(40) NOT NULL REFERENCES tags(slug) ON UPDATE CASCADE ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_tag_synonyms_tag ON tag_synonyms(tag_slug);
+
+-- Free-form tags sellers put on their items
+ALTER TABLE items ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX idx_items_tags ON items USING GIN (tags) WHERE cardinality(tags) > 0;
+
+-- Raffle tags were free text until now; register them
+INSERT INTO tags (slug, display_name)
+SELECT DISTINCT t, t FROM raffles, unnest(raffles.tags) AS t
+ON CONFLICT (slug) DO NOTHING;
+
+-- Themed discovery pages: the raffles carrying any of the collection's tags
+CREATE TABLE tag_collections (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    slug VARCHAR(60) NOT NULL UNIQUE,
+    title VARCHAR(120) NOT NULL,
+    description TEXT,
+    image_url TEXT,
+    tags TEXT[] NOT NULL,
+    is_published BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Lower comes first on the discovery index
+    position INTEGER NOT NULL DEFAULT 0,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CONSTRAINT tag_collections_tags_check CHECK (cardinality(tags) > 0)
+);
+
+CREATE INDEX idx_tag_collections_published ON tag_collections(position, created_at) WHERE is_published;
+
+-- Listings carry the raffle's tags together with its item's
+ALTER TABLE raffle_listings ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE OR REPLACE FUNCTION refresh_raffle_listing(p_raffle_id UUID)
+RETURNS VOID AS $$
+BEGIN
+    INSERT INTO raffle_listings (
+        raffle_id, item_id, total_boxes, box_price, boxes_sold, total_winners, status,
+        winner_user_ids, grid_rows, grid_cols, starts_at, ends_at, deadline_action,
+        started_at, completed_at, created_at, completion_percentage, featured_score,
+        item_seller_id, item_name, item_description, item_images, item_retail_price,
+        item_cost_of_goods, item_status, item_stock_quantity, item_listing_fee_applied,
+        item_category, item_created_at, item_updated_at, seller_company_name,
+        seller_is_verified, tags, refreshed_at
+    )
+    SELECT
+        r.id, r.item_id, r.total_boxes, r.box_price, COALESCE(r.boxes_sold, 0), r.total_winners,
+        COALESCE(r.status, 'open'), COALESCE(r.winner_user_ids, '{}'), r.grid_rows, r.grid_cols,
+        r.starts_at, r.ends_at, r.deadline_action, r.started_at, r.completed_at,
+        COALESCE(r.created_at, NOW()), c.completion,
+        c.completion + CASE WHEN COALESCE(s.is_verified, FALSE) THEN 100 ELSE 0 END,
+        i.seller_id, i.name, i.description, i.images, i.retail_price, i.cost_of_goods,
+        COALESCE(i.status, 'available'), COALESCE(i.stock_quantity, 1), i.listing_fee_applied,
+        i.category, COALESCE(i.created_at, NOW()), COALESCE(i.updated_at, NOW()),
+        s.company_name, COALESCE(s.is_verified, FALSE),
+        ARRAY(SELECT DISTINCT t FROM unnest(r.tags || i.tags) AS t ORDER BY t), NOW()
+    FROM raffles r
+    JOIN items i ON i.id = r.item_id
+    LEFT JOIN sellers s ON s.id = i.seller_id
+    CROSS JOIN LATERAL (
+        SELECT CASE WHEN r.total_boxes > 0
+            THEN ROUND(LEAST(COALESCE(r.boxes_sold, 0), r.total_boxes) * 100.0 / r.total_boxes, 2)
+            ELSE 0 END AS completion
+    ) c
+    WHERE r.id = p_raffle_id
+    ON CONFLICT (raffle_id) DO UPDATE SET
+        item_id = EXCLUDED.item_id,
+        total_boxes = EXCLUDED.total_boxes,
+        box_price = EXCLUDED.box_price,
+        boxes_sold = EXCLUDED.boxes_sold,
+        total_winners = EXCLUDED.total_winners,
+        status = EXCLUDED.status,
+        winner_user_ids = EXCLUDED.winner_user_ids,
+        grid_rows = EXCLUDED.grid_rows,
+        grid_cols = EXCLUDED.grid_cols,
+        starts_at = EXCLUDED.starts_at,
+        ends_at = EXCLUDED.ends_at,
+        deadline_action = EXCLUDED.deadline_action,
+        started_at = EXCLUDED.started_at,
+        completed_at = EXCLUDED.completed_at,
+        created_at = EXCLUDED.created_at,
+        completion_percentage = EXCLUDED.completion_percentage,
+        featured_score = EXCLUDED.featured_score,
+        item_seller_id = EXCLUDED.item_seller_id,
+        item_name = EXCLUDED.item_name,
+        item_description = EXCLUDED.item_description,
+        item_images = EXCLUDED.item_images,
+        item_retail_price = EXCLUDED.item_retail_price,
+        item_cost_of_goods = EXCLUDED.item_cost_of_goods,
+        item_status = EXCLUDED.item_status,
+        item_stock_quantity = EXCLUDED.item_stock_quantity,
+        item_listing_fee_applied = EXCLUDED.item_listing_fee_applied,
+        item_category = EXCLUDED.item_category,
+        item_created_at = EXCLUDED.item_created_at,
+        item_updated_at = EXCLUDED.item_updated_at,
+        seller_company_name = EXCLUDED.seller_company_name,
+        seller_is_verified = EXCLUDED.seller_is_verified,
+        tags = EXCLUDED.tags,
+        refreshed_at = EXCLUDED.refreshed_at;
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT refresh_raffle_listing(id) FROM raffles;
+
+CREATE INDEX idx_raffle_listings_tags ON raffle_listings USING GIN (tags) WHERE cardinality(tags) > 0;
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('trending_tags_window_days', '7', 'Box purchases in this many days rank trending tags, against the window before')
+ON CONFLICT (key) DO NOTHING;
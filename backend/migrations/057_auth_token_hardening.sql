@@ -0,0 +1,37 @@
+-- Password reset and email verification tokens: hashed storage, configurable lifetimes,
+-- one live token per user and kind
+
+ALTER TABLE password_reset_tokens ADD COLUMN IF NOT EXISTS invalidated_at TIMESTAMP WITH TIME ZONE;
+
+-- Tokens issued by older builds may have been stored as sent rather than hashed, and the
+-- two kinds cannot be told apart; retire every outstanding token so users request a new link
+UPDATE password_reset_tokens SET invalidated_at = NOW() WHERE COALESCE(is_used, FALSE) = FALSE AND invalidated_at IS NULL;
+UPDATE email_verification_tokens SET invalidated_at = NOW() WHERE COALESCE(is_used, FALSE) = FALSE AND invalidated_at IS NULL;
+
+UPDATE password_reset_tokens SET is_used = FALSE WHERE is_used IS NULL;
+UPDATE email_verification_tokens SET is_used = FALSE WHERE is_used IS NULL;
+ALTER TABLE password_reset_tokens ALTER COLUMN is_used SET NOT NULL;
+ALTER TABLE email_verification_tokens ALTER COLUMN is_used SET NOT NULL;
+
+-- `token` holds the SHA-256 hex digest of the token that was sent
+ALTER TABLE password_reset_tokens
+    ADD CONSTRAINT password_reset_tokens_token_hash_check CHECK (token ~ '^[0-9a-f]{64}$') NOT VALID;
+ALTER TABLE email_verification_tokens
+    ADD CONSTRAINT email_verification_tokens_token_hash_check CHECK (token ~ '^[0-9a-f]{64}$') NOT VALID;
+
+COMMENT ON COLUMN password_reset_tokens.token IS 'SHA-256 hex digest of the reset token; the token itself is never stored';
+COMMENT ON COLUMN email_verification_tokens.token IS 'SHA-256 hex digest of the verification token; the token itself is never stored';
+
+-- Issuing a token retires the user's earlier ones; the index makes that the only possible state
+DROP INDEX IF EXISTS idx_email_verification_tokens_user_active;
+CREATE UNIQUE INDEX idx_email_verification_tokens_user_live
+    ON email_verification_tokens(user_id) WHERE is_used = FALSE AND invalidated_at IS NULL;
+CREATE UNIQUE INDEX idx_password_reset_tokens_user_live
+    ON password_reset_tokens(user_id) WHERE is_used = FALSE AND invalidated_at IS NULL;
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('password_reset_token_ttl_minutes', '60', 'Minutes a password reset link stays valid, at most 1440')
+ON CONFLICT (key) DO NOTHING;
+
+UPDATE system_settings SET description = 'Hours a verification link stays valid, at most 168'
+WHERE key = 'email_verification_token_ttl_hours';
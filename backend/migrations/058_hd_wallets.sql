@@ -0,0 +1,69 @@
+-- HD user wallets: derivation metadata, per-chain addresses and retired keys
+
+-- Where a user's wallet key comes from:
+--   platform - derived from the platform master seed at wallet_derivation_path
+--   mnemonic - derived from the user's own mnemonic (internal_wallet_mnemonic_encrypted)
+--   imported - a bare private key the user imported
+--   legacy   - created before derivation metadata was recorded
+ALTER TABLE users
+    ADD COLUMN wallet_key_source VARCHAR(20) NOT NULL DEFAULT 'legacy'
+        CHECK (wallet_key_source IN ('platform', 'mnemonic', 'imported', 'legacy')),
+    ADD COLUMN wallet_derivation_path VARCHAR(64),
+    ADD COLUMN wallet_account_index INTEGER CHECK (wallet_account_index >= 0),
+    ADD COLUMN wallet_seed_id VARCHAR(8);
+
+ALTER TABLE users ADD CONSTRAINT users_platform_wallet_derivation_check CHECK (
+    wallet_key_source <> 'platform'
+    OR (wallet_derivation_path IS NOT NULL AND wallet_account_index IS NOT NULL AND wallet_seed_id IS NOT NULL)
+);
+
+-- Wallets generated with their own mnemonic all used the first Ethereum address
+UPDATE users
+SET wallet_key_source = 'mnemonic', wallet_derivation_path = 'm/44''/60''/0''/0/0'
+WHERE internal_wallet_mnemonic_encrypted IS NOT NULL;
+
+COMMENT ON COLUMN users.wallet_key_source IS 'platform, mnemonic, imported or legacy';
+COMMENT ON COLUMN users.wallet_derivation_path IS 'BIP-44 path of the wallet key, for platform and mnemonic wallets';
+COMMENT ON COLUMN users.wallet_account_index IS 'BIP-44 account of a platform wallet under the master seed';
+COMMENT ON COLUMN users.wallet_seed_id IS 'Identifier of the master seed a platform wallet was derived from';
+
+-- Each platform wallet owns one account under the master seed
+CREATE SEQUENCE wallet_account_index_seq AS INTEGER MINVALUE 0 START WITH 0;
+CREATE UNIQUE INDEX idx_users_wallet_account
+    ON users(wallet_seed_id, wallet_account_index) WHERE wallet_key_source = 'platform';
+
+-- A user's address on each supported EVM chain
+CREATE TABLE wallet_chain_addresses (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    chain_id BIGINT NOT NULL,
+    address VARCHAR(42) NOT NULL,
+    -- NULL for imported and legacy wallets, whose one key is used on every chain
+    derivation_path VARCHAR(64),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, chain_id)
+);
+
+CREATE INDEX idx_wallet_chain_addresses_address ON wallet_chain_addresses(LOWER(address));
+
+-- Existing wallets use their one key on every chain; the backfill adds chains registered later
+INSERT INTO wallet_chain_addresses (user_id, chain_id, address, derivation_path)
+SELECT u.id, c.chain_id, u.internal_wallet_address, u.wallet_derivation_path
+FROM users u
+CROSS JOIN (VALUES (1), (137), (42161), (10), (8453), (11155111), (80002)) AS c(chain_id)
+ON CONFLICT DO NOTHING;
+
+-- Keys replaced by an import or an upgrade to a platform wallet, kept encrypted with the
+-- user's wallet password so funds left on the old address can still be moved
+CREATE TABLE retired_wallets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    address VARCHAR(42) NOT NULL,
+    private_key_encrypted TEXT NOT NULL,
+    mnemonic_encrypted TEXT,
+    key_source VARCHAR(20) NOT NULL,
+    derivation_path VARCHAR(64),
+    reason VARCHAR(20) NOT NULL CHECK (reason IN ('imported', 'upgraded')),
+    retired_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_retired_wallets_user ON retired_wallets(user_id, retired_at DESC);
@@ -0,0 +1,17 @@
+-- Per-route rate limit budgets, read by the rate limiter and re-read every 30 seconds.
+-- A budget allows `requests` per `window_seconds`; missing or invalid values fall back
+-- to the defaults built into the server.
+INSERT INTO system_settings (key, value, description) VALUES
+    ('rate_limit_login_requests', '5', 'Requests allowed per window on the login rate limit budget'),
+    ('rate_limit_login_window_seconds', '60', 'Window in seconds of the login rate limit budget'),
+    ('rate_limit_register_requests', '5', 'Requests allowed per window on the register rate limit budget'),
+    ('rate_limit_register_window_seconds', '3600', 'Window in seconds of the register rate limit budget'),
+    ('rate_limit_password_reset_requests', '5', 'Requests allowed per window on the password_reset rate limit budget'),
+    ('rate_limit_password_reset_window_seconds', '3600', 'Window in seconds of the password_reset rate limit budget'),
+    ('rate_limit_buy_boxes_requests', '10', 'Requests allowed per window on the buy_boxes rate limit budget'),
+    ('rate_limit_buy_boxes_window_seconds', '60', 'Window in seconds of the buy_boxes rate limit budget'),
+    ('rate_limit_verify_requests', '30', 'Requests allowed per window on the verify rate limit budget'),
+    ('rate_limit_verify_window_seconds', '60', 'Window in seconds of the verify rate limit budget'),
+    ('rate_limit_api_requests', '300', 'Requests allowed per window on the api rate limit budget'),
+    ('rate_limit_api_window_seconds', '60', 'Window in seconds of the api rate limit budget')
+ON CONFLICT (key) DO NOTHING;
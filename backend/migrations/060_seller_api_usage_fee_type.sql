@@ -0,0 +1,3 @@
+-- Transaction type for partner API overage charged to sellers. Added on its own because
+-- a new enum value cannot be used in the transaction that adds it.
+ALTER TYPE transaction_type ADD VALUE IF NOT EXISTS 'seller_api_usage_fee';
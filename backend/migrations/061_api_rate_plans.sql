@@ -0,0 +1,85 @@
+-- Partner API rate plans: monthly quotas per seller, usage metering and overage charges
+
+CREATE TABLE api_rate_plans (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(100) NOT NULL UNIQUE,
+    description TEXT,
+    -- Monthly quotas shared by all of a seller's production API keys
+    included_requests BIGINT NOT NULL CHECK (included_requests >= 0),
+    included_webhooks BIGINT NOT NULL CHECK (included_webhooks >= 0),
+    request_overage_per_1000 DECIMAL(10,4) NOT NULL DEFAULT 0 CHECK (request_overage_per_1000 >= 0),
+    webhook_overage_per_1000 DECIMAL(10,4) NOT NULL DEFAULT 0 CHECK (webhook_overage_per_1000 >= 0),
+    -- Refuse requests past the quota instead of billing them as overage
+    hard_limit BOOLEAN NOT NULL DEFAULT FALSE,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CONSTRAINT api_rate_plans_default_active_check CHECK (NOT is_default OR is_active)
+);
+
+-- Sellers without an assigned plan are on the default one
+CREATE UNIQUE INDEX idx_api_rate_plans_default ON api_rate_plans(is_default) WHERE is_default;
+
+CREATE TRIGGER update_api_rate_plans_updated_at
+    BEFORE UPDATE ON api_rate_plans
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO api_rate_plans
+    (name, description, included_requests, included_webhooks, request_overage_per_1000, webhook_overage_per_1000, hard_limit, is_default)
+VALUES
+    ('Starter', 'Free tier; requests past the quota are refused until the next month', 10000, 1000, 0, 0, TRUE, TRUE),
+    ('Growth', 'For integrations with steady traffic', 250000, 25000, 0.50, 1.00, FALSE, FALSE),
+    ('Scale', 'For high-volume integrations', 2000000, 200000, 0.30, 0.60, FALSE, FALSE);
+
+CREATE TABLE seller_api_plans (
+    seller_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    rate_plan_id UUID NOT NULL REFERENCES api_rate_plans(id),
+    assigned_by UUID REFERENCES users(id),
+    assigned_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Requests per API key and calendar month (UTC); `period_start` is the first of the month
+CREATE TABLE api_key_usage (
+    api_key_id UUID NOT NULL REFERENCES partner_api_keys(id) ON DELETE CASCADE,
+    period_start DATE NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (api_key_id, period_start)
+);
+
+-- Billable usage per seller and month, across all of the seller's production keys
+CREATE TABLE seller_api_usage (
+    seller_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    period_start DATE NOT NULL,
+    requests BIGINT NOT NULL DEFAULT 0,
+    webhooks_delivered BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (seller_id, period_start)
+);
+
+-- One charge per seller and closed month, priced on the plan the seller was on at close
+CREATE TABLE seller_api_usage_charges (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    seller_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    period_start DATE NOT NULL,
+    rate_plan_id UUID NOT NULL REFERENCES api_rate_plans(id),
+    plan_name VARCHAR(100) NOT NULL,
+    requests BIGINT NOT NULL,
+    webhooks_delivered BIGINT NOT NULL,
+    included_requests BIGINT NOT NULL,
+    included_webhooks BIGINT NOT NULL,
+    overage_requests BIGINT NOT NULL,
+    overage_webhooks BIGINT NOT NULL,
+    amount DECIMAL(12,2) NOT NULL CHECK (amount >= 0),
+    -- The seller fee transaction; NULL until posted, and for charges of zero
+    transaction_id UUID REFERENCES transactions(id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (seller_id, period_start)
+);
+
+CREATE INDEX idx_seller_api_usage_charges_period ON seller_api_usage_charges(period_start DESC);
+CREATE INDEX idx_seller_api_usage_charges_unposted
+    ON seller_api_usage_charges(created_at) WHERE transaction_id IS NULL AND amount > 0;
+
+INSERT INTO chart_of_accounts (transaction_type, debit_account_code, debit_account_name, credit_account_code, credit_account_name) VALUES
+    ('seller_api_usage_fee', '2200', 'Seller Payables', '4130', 'Seller API Usage Revenue')
+ON CONFLICT (transaction_type) DO NOTHING;
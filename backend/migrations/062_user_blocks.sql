@@ -0,0 +1,18 @@
+-- Users a user has blocked or muted. A mute hides the other user's comments from the
+-- muter; a block also stops the other user from replying to the blocker or commenting on
+-- the blocker's raffles.
+
+CREATE TABLE user_blocks (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    blocker_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    blocked_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- block or mute
+    kind VARCHAR(10) NOT NULL DEFAULT 'block',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CONSTRAINT user_blocks_pair_unique UNIQUE (blocker_id, blocked_id),
+    CONSTRAINT user_blocks_not_self CHECK (blocker_id <> blocked_id),
+    CONSTRAINT user_blocks_kind_check CHECK (kind IN ('block', 'mute'))
+);
+
+-- Enforcement looks up whether the recipient blocked the sender
+CREATE INDEX idx_user_blocks_blocked ON user_blocks(blocked_id, blocker_id);
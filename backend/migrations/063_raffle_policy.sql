@@ -0,0 +1,24 @@
+-- Platform raffle policy: bounds on boxes, box prices, winners and grid size, checked when a
+-- raffle is created or its fees are previewed. Subscription tiers can override any bound
+-- with a `raffle_policy_tier:<subscription id>` setting.
+--
+-- Replaces the grid limit settings, and the box count and box price settings that were
+-- never enforced; their current values seed the policy.
+INSERT INTO system_settings (key, value, description, is_public)
+SELECT
+    'raffle_policy',
+    jsonb_build_object(
+        'min_boxes', 2,
+        'max_boxes', COALESCE((SELECT (value #>> '{}')::INT FROM system_settings WHERE key = 'max_boxes_per_raffle'), 10000),
+        'min_box_price', COALESCE((SELECT (value #>> '{}')::NUMERIC FROM system_settings WHERE key = 'min_box_price'), 1.00),
+        'max_box_price', 10000,
+        'max_winners_ratio', 0.5,
+        'max_grid_rows', COALESCE((SELECT (value #>> '{}')::INT FROM system_settings WHERE key = 'raffle_max_grid_rows'), 100),
+        'max_grid_cols', COALESCE((SELECT (value #>> '{}')::INT FROM system_settings WHERE key = 'raffle_max_grid_cols'), 100)
+    ),
+    'Bounds on raffle size, price, winners and grid for every seller',
+    false
+ON CONFLICT (key) DO NOTHING;
+
+DELETE FROM system_settings
+WHERE key IN ('max_boxes_per_raffle', 'min_box_price', 'raffle_max_grid_rows', 'raffle_max_grid_cols');
@@ -0,0 +1,25 @@
+-- Version of each user's credit balance, bumped by every change to their credits or credit
+-- holds. Realtime balance pushes and the reconcile endpoint carry it so clients can tell
+-- whether their optimistic balance is behind the server's.
+ALTER TABLE users ADD COLUMN credit_balance_version BIGINT NOT NULL DEFAULT 0;
+
+CREATE OR REPLACE FUNCTION bump_credit_balance_version()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        UPDATE users SET credit_balance_version = credit_balance_version + 1 WHERE id = OLD.user_id;
+    ELSE
+        UPDATE users SET credit_balance_version = credit_balance_version + 1 WHERE id = NEW.user_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trigger_bump_credit_balance_version
+    AFTER INSERT OR UPDATE OR DELETE ON user_credits
+    FOR EACH ROW EXECUTE FUNCTION bump_credit_balance_version();
+
+-- Holds change what is spendable
+CREATE TRIGGER trigger_bump_credit_hold_balance_version
+    AFTER INSERT OR UPDATE ON credit_holds
+    FOR EACH ROW EXECUTE FUNCTION bump_credit_balance_version();
@@ -0,0 +1,76 @@
+-- Shipping of won prizes: the winner confirms where to ship, the seller ships with tracking,
+-- and the winner confirms receipt or opens a dispute for an admin to resolve
+
+CREATE TABLE fulfillments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    raffle_id UUID NOT NULL REFERENCES raffles(id) ON DELETE CASCADE,
+    winner_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- User account of the seller who ships
+    seller_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'awaiting_shipment',
+    -- Retail value of the item prizes being shipped; the default dispute refund
+    prize_value DECIMAL(10,2) NOT NULL,
+    -- Address snapshot taken when the winner confirmed it
+    recipient_name VARCHAR(255) NOT NULL,
+    line1 VARCHAR(255) NOT NULL,
+    line2 VARCHAR(255),
+    city VARCHAR(255) NOT NULL,
+    region_code VARCHAR(10),
+    postal_code VARCHAR(20),
+    country_code CHAR(2) NOT NULL,
+    phone VARCHAR(50),
+    carrier VARCHAR(100),
+    tracking_number VARCHAR(100),
+    details_confirmed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    shipped_at TIMESTAMP WITH TIME ZONE,
+    received_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT fulfillments_raffle_winner_unique UNIQUE (raffle_id, winner_id),
+    CONSTRAINT fulfillments_status_check CHECK (
+        status IN ('awaiting_shipment', 'shipped', 'received', 'disputed', 'refunded')
+    ),
+    CONSTRAINT fulfillments_tracking_check CHECK (shipped_at IS NULL OR tracking_number IS NOT NULL)
+);
+
+CREATE INDEX idx_fulfillments_winner ON fulfillments(winner_id, created_at DESC);
+CREATE INDEX idx_fulfillments_seller_status ON fulfillments(seller_id, status, created_at);
+
+CREATE TRIGGER update_fulfillments_updated_at
+    BEFORE UPDATE ON fulfillments
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE disputes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    fulfillment_id UUID NOT NULL REFERENCES fulfillments(id) ON DELETE CASCADE,
+    opened_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason VARCHAR(30) NOT NULL,
+    description TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'open',
+    -- Fulfillment status when the dispute was opened; restored if it is rejected
+    fulfillment_status VARCHAR(20) NOT NULL,
+    refund_amount DECIMAL(10,2),
+    resolution_note TEXT,
+    resolved_by UUID REFERENCES users(id),
+    resolved_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT disputes_reason_check CHECK (reason IN ('not_received', 'damaged', 'not_as_described', 'other')),
+    CONSTRAINT disputes_status_check CHECK (status IN ('open', 'refunded', 'rejected')),
+    CONSTRAINT disputes_refund_check CHECK (
+        (status = 'refunded' AND refund_amount > 0) OR (status <> 'refunded' AND refund_amount IS NULL)
+    ),
+    CONSTRAINT disputes_description_check CHECK (description IS NULL OR char_length(description) <= 2000)
+);
+
+-- A fulfillment has at most one open dispute
+CREATE UNIQUE INDEX idx_disputes_open_per_fulfillment ON disputes(fulfillment_id) WHERE status = 'open';
+CREATE INDEX idx_disputes_status_created ON disputes(status, created_at);
+
+CREATE TRIGGER update_disputes_updated_at
+    BEFORE UPDATE ON disputes
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('fulfillment_dispute_window_days', '14', 'Days after confirming receipt that a winner may still open a dispute')
+ON CONFLICT (key) DO NOTHING;
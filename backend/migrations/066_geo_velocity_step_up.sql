@@ -0,0 +1,45 @@
+-- Geo-velocity checks: where each user was last seen, and the re-authentication challenges
+-- issued when their requests jump between distant countries faster than anyone could travel
+
+CREATE TABLE user_geo_sightings (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    ip_address INET NOT NULL,
+    country_code CHAR(2) NOT NULL,
+    latitude DOUBLE PRECISION NOT NULL,
+    longitude DOUBLE PRECISION NOT NULL,
+    seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE step_up_challenges (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason VARCHAR(30) NOT NULL DEFAULT 'geo_velocity',
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    from_country_code CHAR(2) NOT NULL,
+    -- Location of the request that raised the challenge; the new baseline once it is completed
+    ip_address INET NOT NULL,
+    country_code CHAR(2) NOT NULL,
+    latitude DOUBLE PRECISION NOT NULL,
+    longitude DOUBLE PRECISION NOT NULL,
+    distance_km DOUBLE PRECISION NOT NULL,
+    speed_kmh DOUBLE PRECISION NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT step_up_challenges_reason_check CHECK (reason IN ('geo_velocity')),
+    CONSTRAINT step_up_challenges_status_check CHECK (status IN ('pending', 'completed', 'expired')),
+    CONSTRAINT step_up_challenges_completed_check CHECK ((status = 'completed') = (completed_at IS NOT NULL))
+);
+
+-- A user has at most one challenge waiting to be answered
+CREATE UNIQUE INDEX idx_step_up_challenges_pending_per_user ON step_up_challenges(user_id) WHERE status = 'pending';
+CREATE INDEX idx_step_up_challenges_user_created ON step_up_challenges(user_id, created_at DESC);
+
+INSERT INTO system_settings (key, value, description, is_public) VALUES
+    (
+        'geo_velocity_policy',
+        '{"enabled": true, "max_speed_kmh": 900, "min_distance_km": 500, "challenge_ttl_minutes": 15}',
+        'Re-authentication when a user''s requests move between countries faster than max_speed_kmh',
+        false
+    )
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,5 @@
+-- Seller onboarding saves the profile with an upsert keyed by the user, so each user has
+-- at most one seller profile
+
+DROP INDEX IF EXISTS idx_sellers_user_id;
+CREATE UNIQUE INDEX idx_sellers_user_id ON sellers(user_id);
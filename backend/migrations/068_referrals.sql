@@ -0,0 +1,46 @@
+-- Referral program: every user has a code, a new signup can attach one, and both sides
+-- earn credits once the referred user makes their first purchase
+
+ALTER TYPE credit_source ADD VALUE IF NOT EXISTS 'referral';
+
+CREATE TABLE referral_codes (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    code VARCHAR(16) NOT NULL UNIQUE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE TABLE referrals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    referrer_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- A user is referred at most once
+    referee_id UUID NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    code VARCHAR(16) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    -- Why the anti-abuse checks refused the referral; rejected referrals never pay out
+    rejection_reason VARCHAR(30),
+    ip_address INET,
+    -- SHA-256 of the X-Device-Id the referee's client sent
+    device_hash VARCHAR(64),
+    referrer_reward DECIMAL(10,2),
+    referee_reward DECIMAL(10,2),
+    rewarded_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT referrals_not_self CHECK (referrer_id <> referee_id),
+    CONSTRAINT referrals_status_check CHECK (status IN ('pending', 'rewarded', 'rejected')),
+    CONSTRAINT referrals_rejection_check CHECK ((status = 'rejected') = (rejection_reason IS NOT NULL)),
+    CONSTRAINT referrals_rewarded_check CHECK ((status = 'rewarded') = (rewarded_at IS NOT NULL))
+);
+
+CREATE INDEX idx_referrals_referrer_created ON referrals(referrer_id, created_at DESC);
+CREATE INDEX idx_referrals_status_created ON referrals(status, created_at DESC);
+CREATE INDEX idx_referrals_ip ON referrals(ip_address) WHERE ip_address IS NOT NULL;
+CREATE INDEX idx_referrals_device ON referrals(device_hash) WHERE device_hash IS NOT NULL;
+
+INSERT INTO system_settings (key, value, description, is_public) VALUES
+    (
+        'referral_policy',
+        '{"enabled": true, "referrer_reward": "5.00", "referee_reward": "5.00", "credit_expiry_days": 90, "attach_window_days": 7}',
+        'Credits paid to both sides of a referral when the referred user makes their first purchase',
+        false
+    )
+ON CONFLICT (key) DO NOTHING;
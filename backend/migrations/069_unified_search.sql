@@ -0,0 +1,28 @@
+-- Full-text search over items, raffles and sellers for the unified search endpoint
+
+-- Names weigh most, then categories and company names, then descriptions
+ALTER TABLE items
+    ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', coalesce(name, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(category, '')), 'B') ||
+        setweight(to_tsvector('english', coalesce(description, '')), 'C')
+    ) STORED;
+
+-- Raffles are searched through their listing, which already carries the item and seller
+ALTER TABLE raffle_listings
+    ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', coalesce(item_name, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(item_category, '')), 'B') ||
+        setweight(to_tsvector('english', coalesce(seller_company_name, '')), 'B') ||
+        setweight(to_tsvector('english', coalesce(item_description, '')), 'C')
+    ) STORED;
+
+ALTER TABLE sellers
+    ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', coalesce(company_name, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(description, '')), 'C')
+    ) STORED;
+
+CREATE INDEX idx_items_search_vector ON items USING gin(search_vector);
+CREATE INDEX idx_raffle_listings_search_vector ON raffle_listings USING gin(search_vector);
+CREATE INDEX idx_sellers_search_vector ON sellers USING gin(search_vector);
@@ -0,0 +1,31 @@
+-- Transactional outbox: events are written in the same transaction as the change they describe
+-- and published to websocket, notification and webhook consumers by a relay after commit
+
+CREATE TABLE event_outbox (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind VARCHAR(50) NOT NULL,
+    message JSONB NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    published_at TIMESTAMPTZ,
+    -- Set when the relay gives up; the row is kept for inspection
+    failed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_event_outbox_pending ON event_outbox (next_attempt_at, created_at)
+    WHERE published_at IS NULL AND failed_at IS NULL;
+CREATE INDEX idx_event_outbox_published_at ON event_outbox (published_at) WHERE published_at IS NOT NULL;
+
+-- Wake the relay when the inserting transaction commits instead of waiting for its next poll
+CREATE OR REPLACE FUNCTION notify_event_outbox() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('event_outbox', '');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER event_outbox_notify
+    AFTER INSERT ON event_outbox
+    FOR EACH STATEMENT EXECUTE FUNCTION notify_event_outbox();
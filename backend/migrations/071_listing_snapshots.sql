@@ -0,0 +1,125 @@
+-- Versioned snapshots of the public fields of items and raffles, so a dispute can be settled
+-- against what the listing said when the boxes were bought
+
+CREATE TABLE listing_snapshots (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    entity_type VARCHAR(20) NOT NULL CHECK (entity_type IN ('item', 'raffle')),
+    entity_id UUID NOT NULL,
+    version INTEGER NOT NULL,
+    data JSONB NOT NULL,
+    captured_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (entity_type, entity_id, version)
+);
+
+CREATE INDEX idx_listing_snapshots_effective ON listing_snapshots (entity_type, entity_id, captured_at DESC);
+
+-- Fields a buyer sees; counters such as boxes_sold and stock_quantity are left out so routine
+-- purchases don't mint a new version
+CREATE OR REPLACE FUNCTION item_snapshot_data(i items) RETURNS JSONB AS $$
+    SELECT jsonb_build_object(
+        'seller_id', i.seller_id,
+        'name', i.name,
+        'description', i.description,
+        'images', i.images,
+        'retail_price', i.retail_price,
+        'category', i.category,
+        'status', i.status,
+        'tags', i.tags
+    );
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION raffle_snapshot_data(r raffles) RETURNS JSONB AS $$
+    SELECT jsonb_build_object(
+        'item_id', r.item_id,
+        'total_boxes', r.total_boxes,
+        'box_price', r.box_price,
+        'total_winners', r.total_winners,
+        'status', r.status,
+        'grid_rows', r.grid_rows,
+        'grid_cols', r.grid_cols,
+        'starts_at', r.starts_at,
+        'ends_at', r.ends_at,
+        'deadline_action', r.deadline_action,
+        'max_boxes_per_user', r.max_boxes_per_user,
+        'tags', r.tags
+    );
+$$ LANGUAGE sql STABLE;
+
+-- Append a version when the public fields differ from the latest one
+CREATE OR REPLACE FUNCTION record_listing_snapshot(p_entity_type VARCHAR, p_entity_id UUID, p_data JSONB)
+RETURNS VOID AS $$
+DECLARE
+    latest listing_snapshots%ROWTYPE;
+BEGIN
+    SELECT * INTO latest
+    FROM listing_snapshots
+    WHERE entity_type = p_entity_type AND entity_id = p_entity_id
+    ORDER BY version DESC
+    LIMIT 1
+    FOR UPDATE;
+
+    IF FOUND AND latest.data = p_data THEN
+        RETURN;
+    END IF;
+
+    INSERT INTO listing_snapshots (entity_type, entity_id, version, data)
+    VALUES (p_entity_type, p_entity_id, COALESCE(latest.version, 0) + 1, p_data);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION snapshot_item() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM record_listing_snapshot('item', NEW.id, item_snapshot_data(NEW));
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION snapshot_raffle() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM record_listing_snapshot('raffle', NEW.id, raffle_snapshot_data(NEW));
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER items_snapshot
+    AFTER INSERT OR UPDATE ON items
+    FOR EACH ROW EXECUTE FUNCTION snapshot_item();
+
+CREATE TRIGGER raffles_snapshot
+    AFTER INSERT OR UPDATE ON raffles
+    FOR EACH ROW EXECUTE FUNCTION snapshot_raffle();
+
+-- Existing listings start at version 1
+INSERT INTO listing_snapshots (entity_type, entity_id, version, data, captured_at)
+SELECT 'item', i.id, 1, item_snapshot_data(i), COALESCE(i.updated_at, i.created_at, NOW()) FROM items i;
+
+INSERT INTO listing_snapshots (entity_type, entity_id, version, data, captured_at)
+SELECT 'raffle', r.id, 1, raffle_snapshot_data(r), COALESCE(r.updated_at, r.created_at, NOW()) FROM raffles r;
+
+-- Every purchase records the versions in effect when it was made
+ALTER TABLE box_purchases
+    ADD COLUMN item_snapshot_id UUID REFERENCES listing_snapshots(id),
+    ADD COLUMN raffle_snapshot_id UUID REFERENCES listing_snapshots(id);
+
+CREATE OR REPLACE FUNCTION attach_purchase_snapshots() RETURNS TRIGGER AS $$
+BEGIN
+    SELECT s.id INTO NEW.raffle_snapshot_id
+    FROM listing_snapshots s
+    WHERE s.entity_type = 'raffle' AND s.entity_id = NEW.raffle_id
+    ORDER BY s.version DESC
+    LIMIT 1;
+
+    SELECT s.id INTO NEW.item_snapshot_id
+    FROM listing_snapshots s
+    JOIN raffles r ON r.item_id = s.entity_id
+    WHERE s.entity_type = 'item' AND r.id = NEW.raffle_id
+    ORDER BY s.version DESC
+    LIMIT 1;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER box_purchases_attach_snapshots
+    BEFORE INSERT ON box_purchases
+    FOR EACH ROW EXECUTE FUNCTION attach_purchase_snapshots();
@@ -0,0 +1,20 @@
+-- Remediations applied to stuck raffles through the operator runbook endpoints
+
+CREATE TABLE raffle_remediations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    raffle_id UUID NOT NULL REFERENCES raffles(id) ON DELETE CASCADE,
+    action VARCHAR(40) NOT NULL CHECK (action IN ('rerequest_randomness', 'resync', 'force_complete')),
+    justification TEXT NOT NULL,
+    plan_hash VARCHAR(64) NOT NULL,
+    changes JSONB NOT NULL,
+    -- Transaction manager id of the contract call, for actions that send one
+    blockchain_tx_id UUID,
+    performed_by UUID REFERENCES users(id),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_raffle_remediations_raffle ON raffle_remediations (raffle_id, created_at DESC);
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('raffle_randomness_timeout_minutes', '30', 'Minutes a randomness request may go unfulfilled before the raffle is diagnosed as stuck')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,127 @@
+-- Explicit currencies: listings are priced in their own currency, credits and charges settle in the base one
+
+ALTER TABLE items ADD COLUMN currency CHAR(3) NOT NULL DEFAULT 'USD';
+
+-- A raffle is always priced in its item's currency
+ALTER TABLE raffles ADD COLUMN currency CHAR(3) NOT NULL DEFAULT 'USD';
+
+UPDATE raffles r SET currency = i.currency FROM items i WHERE i.id = r.item_id;
+
+-- Credits are a single ledger in the settlement currency; recorded so amounts are never ambiguous
+ALTER TABLE user_credits ADD COLUMN currency CHAR(3) NOT NULL DEFAULT 'USD';
+
+-- What a payment in another currency was worth in credits, and the rate that decided it
+ALTER TABLE payments
+    ADD COLUMN settlement_amount DECIMAL(12,2),
+    ADD COLUMN settlement_rate DECIMAL(18,8);
+
+ALTER TABLE raffle_listings ADD COLUMN currency CHAR(3) NOT NULL DEFAULT 'USD';
+
+CREATE OR REPLACE FUNCTION refresh_raffle_listing(p_raffle_id UUID)
+RETURNS VOID AS $$
+BEGIN
+    INSERT INTO raffle_listings (
+        raffle_id, item_id, total_boxes, box_price, currency, boxes_sold, total_winners, status,
+        winner_user_ids, grid_rows, grid_cols, starts_at, ends_at, deadline_action,
+        started_at, completed_at, created_at, completion_percentage, featured_score,
+        item_seller_id, item_name, item_description, item_images, item_retail_price,
+        item_cost_of_goods, item_status, item_stock_quantity, item_listing_fee_applied,
+        item_category, item_created_at, item_updated_at, seller_company_name,
+        seller_is_verified, tags, refreshed_at
+    )
+    SELECT
+        r.id, r.item_id, r.total_boxes, r.box_price, r.currency, COALESCE(r.boxes_sold, 0), r.total_winners,
+        COALESCE(r.status, 'open'), COALESCE(r.winner_user_ids, '{}'), r.grid_rows, r.grid_cols,
+        r.starts_at, r.ends_at, r.deadline_action, r.started_at, r.completed_at,
+        COALESCE(r.created_at, NOW()), c.completion,
+        c.completion + CASE WHEN COALESCE(s.is_verified, FALSE) THEN 100 ELSE 0 END,
+        i.seller_id, i.name, i.description, i.images, i.retail_price, i.cost_of_goods,
+        COALESCE(i.status, 'available'), COALESCE(i.stock_quantity, 1), i.listing_fee_applied,
+        i.category, COALESCE(i.created_at, NOW()), COALESCE(i.updated_at, NOW()),
+        s.company_name, COALESCE(s.is_verified, FALSE),
+        ARRAY(SELECT DISTINCT t FROM unnest(r.tags || i.tags) AS t ORDER BY t), NOW()
+    FROM raffles r
+    JOIN items i ON i.id = r.item_id
+    LEFT JOIN sellers s ON s.id = i.seller_id
+    CROSS JOIN LATERAL (
+        SELECT CASE WHEN r.total_boxes > 0
+            THEN ROUND(LEAST(COALESCE(r.boxes_sold, 0), r.total_boxes) * 100.0 / r.total_boxes, 2)
+            ELSE 0 END AS completion
+    ) c
+    WHERE r.id = p_raffle_id
+    ON CONFLICT (raffle_id) DO UPDATE SET
+        item_id = EXCLUDED.item_id,
+        total_boxes = EXCLUDED.total_boxes,
+        box_price = EXCLUDED.box_price,
+        currency = EXCLUDED.currency,
+        boxes_sold = EXCLUDED.boxes_sold,
+        total_winners = EXCLUDED.total_winners,
+        status = EXCLUDED.status,
+        winner_user_ids = EXCLUDED.winner_user_ids,
+        grid_rows = EXCLUDED.grid_rows,
+        grid_cols = EXCLUDED.grid_cols,
+        starts_at = EXCLUDED.starts_at,
+        ends_at = EXCLUDED.ends_at,
+        deadline_action = EXCLUDED.deadline_action,
+        started_at = EXCLUDED.started_at,
+        completed_at = EXCLUDED.completed_at,
+        created_at = EXCLUDED.created_at,
+        completion_percentage = EXCLUDED.completion_percentage,
+        featured_score = EXCLUDED.featured_score,
+        item_seller_id = EXCLUDED.item_seller_id,
+        item_name = EXCLUDED.item_name,
+        item_description = EXCLUDED.item_description,
+        item_images = EXCLUDED.item_images,
+        item_retail_price = EXCLUDED.item_retail_price,
+        item_cost_of_goods = EXCLUDED.item_cost_of_goods,
+        item_status = EXCLUDED.item_status,
+        item_stock_quantity = EXCLUDED.item_stock_quantity,
+        item_listing_fee_applied = EXCLUDED.item_listing_fee_applied,
+        item_category = EXCLUDED.item_category,
+        item_created_at = EXCLUDED.item_created_at,
+        item_updated_at = EXCLUDED.item_updated_at,
+        seller_company_name = EXCLUDED.seller_company_name,
+        seller_is_verified = EXCLUDED.seller_is_verified,
+        tags = EXCLUDED.tags,
+        refreshed_at = EXCLUDED.refreshed_at;
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT refresh_raffle_listing(id) FROM raffles;
+
+-- Snapshots record the currency a listing was priced in
+CREATE OR REPLACE FUNCTION item_snapshot_data(i items) RETURNS JSONB AS $$
+    SELECT jsonb_build_object(
+        'seller_id', i.seller_id,
+        'name', i.name,
+        'description', i.description,
+        'images', i.images,
+        'retail_price', i.retail_price,
+        'currency', i.currency,
+        'category', i.category,
+        'status', i.status,
+        'tags', i.tags
+    );
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION raffle_snapshot_data(r raffles) RETURNS JSONB AS $$
+    SELECT jsonb_build_object(
+        'item_id', r.item_id,
+        'total_boxes', r.total_boxes,
+        'box_price', r.box_price,
+        'currency', r.currency,
+        'total_winners', r.total_winners,
+        'status', r.status,
+        'grid_rows', r.grid_rows,
+        'grid_cols', r.grid_cols,
+        'starts_at', r.starts_at,
+        'ends_at', r.ends_at,
+        'deadline_action', r.deadline_action,
+        'max_boxes_per_user', r.max_boxes_per_user,
+        'tags', r.tags
+    );
+$$ LANGUAGE sql STABLE;
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('accepted_currencies', '["USD", "EUR"]', 'Currencies items may be priced and credits bought in; the base currency is always accepted')
+ON CONFLICT (key) DO NOTHING;
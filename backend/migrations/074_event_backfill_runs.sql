@@ -0,0 +1,18 @@
+-- Checkpoints of the backfill-events command, so an interrupted backfill resumes where it stopped
+
+CREATE TABLE event_backfill_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    from_block BIGINT NOT NULL,
+    to_block BIGINT NOT NULL CHECK (to_block >= from_block),
+    -- First block not yet processed; to_block + 1 once the run is done
+    next_block BIGINT NOT NULL,
+    logs_processed BIGINT NOT NULL DEFAULT 0,
+    status VARCHAR(20) NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'completed', 'abandoned')),
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+-- One resumable run per range
+CREATE UNIQUE INDEX idx_event_backfill_runs_running ON event_backfill_runs (from_block, to_block)
+    WHERE status = 'running';
@@ -0,0 +1,27 @@
+-- Sequence numbers for live grid diffs: each change to a raffle's boxes advances its sequence,
+-- so clients applying diffs can tell when they missed one
+
+ALTER TABLE raffles
+    ADD COLUMN grid_sequence BIGINT NOT NULL DEFAULT 0,
+    -- Last sequence that removed boxes; diffs only replay sales, so clients behind it refetch the grid
+    ADD COLUMN grid_reset_sequence BIGINT NOT NULL DEFAULT 0;
+
+-- Sequence of the purchase that sold the box; earlier purchases have none
+ALTER TABLE box_purchases ADD COLUMN grid_sequence BIGINT;
+
+CREATE INDEX idx_box_purchases_grid_sequence ON box_purchases (raffle_id, grid_sequence)
+    WHERE grid_sequence IS NOT NULL;
+
+-- Boxes are removed by chain reorgs and data fixes, whichever path deletes them
+CREATE OR REPLACE FUNCTION reset_raffle_grid_sequence() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE raffles
+    SET grid_sequence = grid_sequence + 1, grid_reset_sequence = grid_sequence + 1
+    WHERE id = OLD.raffle_id;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER box_purchases_grid_reset
+    AFTER DELETE ON box_purchases
+    FOR EACH ROW EXECUTE FUNCTION reset_raffle_grid_sequence();
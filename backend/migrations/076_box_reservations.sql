@@ -0,0 +1,19 @@
+-- Short holds on boxes while a purchase is paid for, so concurrent buyers can't take the same box
+
+CREATE TABLE box_reservations (
+    raffle_id UUID NOT NULL REFERENCES raffles(id) ON DELETE CASCADE,
+    box_number INTEGER NOT NULL,
+    -- Shared by the boxes of one purchase attempt
+    reservation_id UUID NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (raffle_id, box_number)
+);
+
+CREATE INDEX idx_box_reservations_reservation ON box_reservations (reservation_id);
+CREATE INDEX idx_box_reservations_expires ON box_reservations (expires_at);
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('box_reservation_ttl_seconds', '120', 'Seconds boxes stay held for a buyer while their purchase is processed; an abandoned hold frees the boxes after this')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,28 @@
+-- Wallet a payment was made with (Stripe's card wallet type, e.g. apple_pay, google_pay); NULL for a plain card
+
+ALTER TABLE payments ADD COLUMN wallet_type VARCHAR(30);
+
+CREATE INDEX idx_payments_wallet_type ON payments (wallet_type, created_at) WHERE wallet_type IS NOT NULL;
+
+-- Daily analytics gain a per-wallet breakdown
+DROP MATERIALIZED VIEW IF EXISTS payment_analytics;
+
+CREATE MATERIALIZED VIEW payment_analytics AS
+SELECT
+    DATE_TRUNC('day', created_at) as payment_date,
+    COUNT(*) as total_payments,
+    COUNT(CASE WHEN status = 'succeeded' THEN 1 END) as successful_payments,
+    COUNT(CASE WHEN status = 'failed' THEN 1 END) as failed_payments,
+    COALESCE(SUM(CASE WHEN status = 'succeeded' THEN amount ELSE 0 END), 0) as total_revenue,
+    COALESCE(AVG(CASE WHEN status = 'succeeded' THEN amount END), 0) as avg_payment_amount,
+    COUNT(DISTINCT user_id) as unique_users,
+    COUNT(CASE WHEN status = 'succeeded' AND wallet_type = 'apple_pay' THEN 1 END) as apple_pay_payments,
+    COUNT(CASE WHEN status = 'succeeded' AND wallet_type = 'google_pay' THEN 1 END) as google_pay_payments
+FROM payments
+WHERE created_at >= CURRENT_DATE - INTERVAL '90 days'
+GROUP BY DATE_TRUNC('day', created_at)
+ORDER BY payment_date DESC;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_payment_analytics_date ON payment_analytics(payment_date);
+
+COMMENT ON MATERIALIZED VIEW payment_analytics IS 'Daily payment analytics for the last 90 days';
@@ -0,0 +1,21 @@
+-- Tell every instance which setting changed so it applies without a restart
+
+CREATE OR REPLACE FUNCTION notify_system_settings_changed() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('system_settings_changed', COALESCE(NEW.key, OLD.key));
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER system_settings_notify
+    AFTER INSERT OR UPDATE OR DELETE ON system_settings
+    FOR EACH ROW EXECUTE FUNCTION notify_system_settings_changed();
+
+INSERT INTO permissions (key, description) VALUES
+    ('settings.manage', 'View and change system settings and feature flags')
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO role_permissions (role, permission) VALUES
+    ('operator', 'settings.manage'),
+    ('admin', 'settings.manage')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,21 @@
+-- Feature flags with per-user and per-role targeting and percentage rollouts
+
+CREATE TABLE feature_flags (
+    key VARCHAR(100) PRIMARY KEY,
+    description TEXT,
+    -- Off for everyone when false, whatever the targeting
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    rollout_percentage SMALLINT NOT NULL DEFAULT 0
+        CHECK (rollout_percentage BETWEEN 0 AND 100),
+    target_roles TEXT[] NOT NULL DEFAULT '{}',
+    target_user_ids UUID[] NOT NULL DEFAULT '{}',
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Both start with 5% of signed-in users; staff always see them
+INSERT INTO feature_flags (key, description, enabled, rollout_percentage, target_roles) VALUES
+    ('new_grid_ui', 'The new raffle grid UI', TRUE, 5, '{admin,operator}'),
+    ('erc20_payments', 'Paying for boxes with ERC-20 tokens', TRUE, 5, '{admin,operator}')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,5 @@
+-- Item statuses for moderation. Kept apart from 081: a value added by ALTER TYPE can't be
+-- used in the transaction that adds it
+
+ALTER TYPE item_status ADD VALUE IF NOT EXISTS 'pending_review';
+ALTER TYPE item_status ADD VALUE IF NOT EXISTS 'rejected';
@@ -0,0 +1,36 @@
+-- New items wait for a moderator before they can be raffled
+
+ALTER TABLE items ALTER COLUMN status SET DEFAULT 'pending_review';
+
+CREATE TABLE item_moderation (
+    item_id UUID PRIMARY KEY REFERENCES items(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'approved', 'rejected')),
+    -- What the automated checks found, for the reviewer
+    findings JSONB NOT NULL DEFAULT '[]',
+    -- NULL until the automated checks have run
+    checked_at TIMESTAMPTZ,
+    -- Reviewer's reason, sent to the seller
+    reason TEXT,
+    reviewed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMPTZ,
+    submitted_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_item_moderation_queue ON item_moderation (status, submitted_at);
+
+-- Difference hashes of item images, to spot the same picture on another seller's item
+CREATE TABLE item_image_hashes (
+    item_id UUID NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+    image TEXT NOT NULL,
+    hash BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (item_id, image)
+);
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('moderation_banned_keywords', '["counterfeit", "replica", "knockoff", "stolen", "fake id"]', 'Words and phrases that flag an item for a moderator''s attention'),
+    ('moderation_max_retail_price', '"100000"', 'Retail prices above this are flagged for a moderator'),
+    ('moderation_max_price_to_cost_ratio', '"20"', 'Retail prices more than this many times the cost of goods are flagged for a moderator'),
+    ('moderation_duplicate_image_distance', '6', 'Images whose 64-bit hashes differ in this many bits or fewer count as the same picture')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,41 @@
+-- Identity verification (KYC) through an external provider, for sellers and for winners of valuable prizes
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS kyc_status VARCHAR(20) NOT NULL DEFAULT 'unverified'
+    CHECK (kyc_status IN ('unverified', 'pending', 'verified', 'rejected'));
+ALTER TABLE users ADD COLUMN IF NOT EXISTS kyc_verified_at TIMESTAMPTZ;
+
+-- Sellers an admin already verified keep their verification
+UPDATE users u
+SET kyc_status = 'verified', kyc_verified_at = COALESCE(s.updated_at, NOW())
+FROM sellers s
+WHERE s.user_id = u.id AND s.is_verified = TRUE;
+
+CREATE TABLE identity_verifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Why the user was asked to verify
+    purpose VARCHAR(20) NOT NULL CHECK (purpose IN ('seller', 'winner')),
+    -- The provider's id for the verification session
+    provider_reference VARCHAR(255) NOT NULL UNIQUE,
+    -- Where the user completes the provider's hosted flow
+    session_url TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'approved', 'declined', 'expired')),
+    decline_reason TEXT,
+    completed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_identity_verifications_user ON identity_verifications (user_id, created_at DESC);
+-- One open session per user; starting again returns it
+CREATE UNIQUE INDEX idx_identity_verifications_pending ON identity_verifications (user_id) WHERE status = 'pending';
+
+-- Provider results arrive as stored webhooks
+ALTER TABLE webhook_deliveries DROP CONSTRAINT webhook_deliveries_source_check;
+ALTER TABLE webhook_deliveries ADD CONSTRAINT webhook_deliveries_source_check
+    CHECK (source IN ('stripe', 'blockchain', 'identity'));
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('kyc_winner_prize_threshold', '"600"', 'Winners of prizes worth more than this must verify their identity before the prize ships')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,143 @@
+-- Double-entry ledger for money and credit movements. Every journal entry's postings sum to zero:
+-- debits are positive, credits negative.
+
+CREATE TABLE ledger_accounts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code VARCHAR(20) NOT NULL UNIQUE,
+    name VARCHAR(100) NOT NULL,
+    account_type VARCHAR(20) NOT NULL
+        CHECK (account_type IN ('asset', 'liability', 'equity', 'revenue', 'expense')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Every account finance has mapped for exports, typed by the leading digit of its code
+INSERT INTO ledger_accounts (code, name, account_type)
+SELECT DISTINCT ON (code) code, name,
+       CASE LEFT(code, 1)
+           WHEN '1' THEN 'asset'
+           WHEN '2' THEN 'liability'
+           WHEN '3' THEN 'equity'
+           WHEN '4' THEN 'revenue'
+           ELSE 'expense'
+       END
+FROM (
+    SELECT debit_account_code AS code, debit_account_name AS name FROM chart_of_accounts
+    UNION ALL
+    SELECT credit_account_code, credit_account_name FROM chart_of_accounts
+) mapped
+ORDER BY code
+ON CONFLICT (code) DO NOTHING;
+
+-- Accounts the platform posts to that have no transaction type of their own
+INSERT INTO ledger_accounts (code, name, account_type) VALUES
+    ('1000', 'Operating Bank', 'asset'),
+    ('1010', 'Stripe Clearing', 'asset'),
+    ('2060', 'Card Refunds Clearing', 'liability'),
+    ('2100', 'Customer Credit Liability', 'liability'),
+    ('2200', 'Seller Payables', 'liability'),
+    ('3000', 'Opening Balance Equity', 'equity'),
+    ('4000', 'Raffle Box Revenue', 'revenue'),
+    ('4200', 'Expired Credit Revenue', 'revenue'),
+    ('5000', 'Raffle Credit Rebates', 'expense'),
+    ('5010', 'Free Item Cost', 'expense'),
+    ('5020', 'Promotional Credits', 'expense'),
+    ('5030', 'Refund Credits', 'expense')
+ON CONFLICT (code) DO NOTHING;
+
+CREATE TABLE journal_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    -- What caused the entry: user_credit, credit_redemption, credit_expiry, refund, transaction, opening_balance
+    source_type VARCHAR(30) NOT NULL,
+    -- The row behind the entry when there is exactly one; an entry is posted once per source row
+    source_id UUID,
+    description TEXT NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_journal_entries_source ON journal_entries (source_type, source_id)
+    WHERE source_id IS NOT NULL;
+CREATE INDEX idx_journal_entries_occurred ON journal_entries (occurred_at DESC);
+
+-- user_id and seller_id are not foreign keys so postings outlive deleted accounts
+CREATE TABLE ledger_postings (
+    id BIGSERIAL PRIMARY KEY,
+    journal_entry_id UUID NOT NULL REFERENCES journal_entries(id),
+    account_id UUID NOT NULL REFERENCES ledger_accounts(id),
+    amount DECIMAL(14,2) NOT NULL CHECK (amount <> 0),
+    user_id UUID,
+    seller_id UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_ledger_postings_entry ON ledger_postings (journal_entry_id);
+CREATE INDEX idx_ledger_postings_account ON ledger_postings (account_id, id DESC);
+CREATE INDEX idx_ledger_postings_user ON ledger_postings (user_id, account_id) WHERE user_id IS NOT NULL;
+
+-- An entry must balance by the time its transaction commits
+CREATE OR REPLACE FUNCTION check_journal_entry_balanced() RETURNS TRIGGER AS $$
+DECLARE
+    imbalance DECIMAL;
+BEGIN
+    SELECT COALESCE(SUM(amount), 0) INTO imbalance
+    FROM ledger_postings WHERE journal_entry_id = NEW.journal_entry_id;
+
+    IF imbalance <> 0 THEN
+        RAISE EXCEPTION 'Journal entry % is unbalanced by %', NEW.journal_entry_id, imbalance;
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE CONSTRAINT TRIGGER ledger_postings_balanced
+    AFTER INSERT ON ledger_postings
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION check_journal_entry_balanced();
+
+-- Corrections are new entries; nothing posted is ever changed
+CREATE OR REPLACE FUNCTION reject_ledger_change() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'The ledger is append-only; post a correcting entry instead';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER journal_entries_append_only
+    BEFORE UPDATE OR DELETE ON journal_entries
+    FOR EACH ROW EXECUTE FUNCTION reject_ledger_change();
+
+CREATE TRIGGER ledger_postings_append_only
+    BEFORE UPDATE OR DELETE ON ledger_postings
+    FOR EACH ROW EXECUTE FUNCTION reject_ledger_change();
+
+-- Outcome of each invariant check
+CREATE TABLE ledger_invariant_runs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    passed BOOLEAN NOT NULL,
+    report JSONB NOT NULL,
+    -- Admin who asked for the check; NULL for scheduled runs
+    triggered_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_ledger_invariant_runs_created ON ledger_invariant_runs (created_at DESC);
+
+-- Open the customer credit liability with what users hold today
+WITH outstanding AS (
+    SELECT user_id, SUM(amount) AS amount
+    FROM user_credits
+    WHERE is_used = FALSE AND expired_at IS NULL
+    GROUP BY user_id
+    HAVING SUM(amount) > 0
+), entry AS (
+    INSERT INTO journal_entries (source_type, description)
+    SELECT 'opening_balance', 'Customer credits outstanding when the ledger started'
+    WHERE EXISTS (SELECT 1 FROM outstanding)
+    RETURNING id
+)
+INSERT INTO ledger_postings (journal_entry_id, account_id, amount, user_id)
+SELECT entry.id, (SELECT id FROM ledger_accounts WHERE code = '2100'), -o.amount, o.user_id
+FROM entry, outstanding o
+UNION ALL
+SELECT entry.id, (SELECT id FROM ledger_accounts WHERE code = '3000'), (SELECT SUM(amount) FROM outstanding), NULL
+FROM entry;
@@ -0,0 +1,27 @@
+-- Notifications inbox: when each notification was read, and a nudge to every instance so open
+-- WebSocket connections get their unread badge updated
+
+ALTER TABLE notifications ADD COLUMN read_at TIMESTAMPTZ;
+
+UPDATE notifications SET read_at = created_at WHERE is_read = TRUE AND read_at IS NULL;
+
+CREATE INDEX idx_notifications_user_created ON notifications (user_id, created_at DESC, id DESC);
+
+-- Marking many notifications read in one statement sends a single notification, since Postgres
+-- folds identical payloads within a transaction
+CREATE OR REPLACE FUNCTION notify_notifications_changed() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify(
+        'notifications_changed',
+        json_build_object(
+            'user_id', NEW.user_id,
+            'notification_id', CASE WHEN TG_OP = 'INSERT' THEN NEW.id END
+        )::text
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER notifications_notify
+    AFTER INSERT OR UPDATE OF is_read ON notifications
+    FOR EACH ROW EXECUTE FUNCTION notify_notifications_changed();
@@ -0,0 +1,27 @@
+-- Draws the scheduler requests randomness for: one row per full raffle on chain until it is drawn
+
+CREATE TABLE raffle_draw_schedule (
+    raffle_id UUID PRIMARY KEY REFERENCES raffles(id) ON DELETE CASCADE,
+    blockchain_raffle_id BIGINT NOT NULL,
+    -- Randomness requests sent, successful or not
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- Failed requests since the last one that was accepted; drives the backoff
+    consecutive_failures INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL,
+    last_attempt_at TIMESTAMPTZ,
+    last_error TEXT,
+    -- When the scheduler's latest request was accepted, and its transaction manager id
+    requested_at TIMESTAMPTZ,
+    blockchain_tx_id UUID,
+    -- Set once the draw has been pending past raffle_draw_alert_minutes and was reported
+    alerted_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_raffle_draw_schedule_due ON raffle_draw_schedule (next_attempt_at);
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('raffle_draw_auto_request', 'true', 'Request randomness automatically for full raffles whose draw is missing or unanswered'),
+    ('raffle_draw_alert_minutes', '60', 'Minutes a full raffle may wait for its randomness before the draw is reported overdue')
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,14 @@
+-- Per-user data keys for envelope-encrypted wallet secrets. Each key is wrapped by a master
+-- key from WALLET_KEY_ENCRYPTION_KEYS; users without a row still have legacy secrets
+-- encrypted with their password key alone and are moved over when they next unlock.
+
+CREATE TABLE wallet_data_keys (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    wrapped_key TEXT NOT NULL,
+    -- Id of the master key that wrapped it; rotation rewraps rows whose id is not current
+    master_key_id VARCHAR(64) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    rotated_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_wallet_data_keys_master_key ON wallet_data_keys (master_key_id);
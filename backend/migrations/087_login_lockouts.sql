@@ -0,0 +1,47 @@
+-- Brute-force protection: failed login counters per account and per IP address with their
+-- lockouts, and the devices each user has signed in from
+
+CREATE TABLE login_lockouts (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    scope VARCHAR(10) NOT NULL,
+    -- The user id for account counters, the address for IP counters
+    subject TEXT NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    -- Failures since the last lockout, within the policy's failure window
+    failures INTEGER NOT NULL DEFAULT 0,
+    -- Lockouts in a row; each lasts twice as long as the one before
+    lockouts INTEGER NOT NULL DEFAULT 0,
+    last_failure_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_until TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT login_lockouts_scope_check CHECK (scope IN ('account', 'ip')),
+    CONSTRAINT login_lockouts_subject_unique UNIQUE (scope, subject)
+);
+
+CREATE INDEX idx_login_lockouts_locked_until ON login_lockouts (locked_until) WHERE locked_until IS NOT NULL;
+CREATE INDEX idx_login_lockouts_last_failure ON login_lockouts (last_failure_at);
+
+CREATE TABLE login_devices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Hash of the user agent without version numbers
+    fingerprint VARCHAR(32) NOT NULL,
+    label VARCHAR(100) NOT NULL,
+    last_ip INET,
+    first_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT login_devices_fingerprint_unique UNIQUE (user_id, fingerprint)
+);
+
+ALTER TABLE email_messages DROP CONSTRAINT email_messages_template_check;
+ALTER TABLE email_messages ADD CONSTRAINT email_messages_template_check
+    CHECK (template IN ('verification', 'password_reset', 'new_device_login', 'raffle_won', 'receipt', 'notification'));
+
+INSERT INTO system_settings (key, value, description, is_public) VALUES
+    (
+        'login_lockout_policy',
+        '{"enabled": true, "account_max_failures": 5, "ip_max_failures": 20, "failure_window_minutes": 15, "base_lockout_seconds": 60, "max_lockout_minutes": 1440, "reset_after_hours": 24, "new_device_alerts": true}',
+        'Lockout of accounts and IP addresses after repeated failed logins, and new device alerts',
+        false
+    )
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,54 @@
+-- Wallets users hold themselves: proof of ownership, and box purchases recorded on chain from them
+-- with a voucher the platform signed
+
+CREATE TABLE external_wallets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Checksummed; one user per address, including after it is unlinked
+    address VARCHAR(42) NOT NULL,
+    label VARCHAR(100),
+    verified_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Unlinked wallets can't be used for new purchases, but boxes they hold still count for their user
+    unlinked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_external_wallets_address ON external_wallets (lower(address));
+CREATE INDEX idx_external_wallets_user ON external_wallets (user_id);
+
+CREATE TABLE external_wallet_challenges (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    address VARCHAR(42) NOT NULL,
+    -- The exact text the wallet signs
+    message TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_external_wallet_challenges_expires ON external_wallet_challenges (expires_at);
+
+CREATE TABLE external_purchases (
+    -- Also the voucher's purchase id, right-aligned in the bytes32
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    raffle_id UUID NOT NULL REFERENCES raffles(id),
+    wallet_id UUID NOT NULL REFERENCES external_wallets(id),
+    address VARCHAR(42) NOT NULL,
+    blockchain_raffle_id BIGINT NOT NULL,
+    box_purchase_ids UUID[] NOT NULL,
+    boxes INTEGER NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    -- Of the latest voucher; a new one can be issued while the purchase is pending
+    deadline TIMESTAMPTZ NOT NULL,
+    transaction_hash VARCHAR(66),
+    confirmed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT external_purchases_status_check CHECK (status IN ('pending', 'confirmed')),
+    CONSTRAINT external_purchases_boxes_check CHECK (boxes > 0)
+);
+
+CREATE INDEX idx_external_purchases_user ON external_purchases (user_id, created_at DESC);
+CREATE INDEX idx_external_purchases_tx ON external_purchases (transaction_hash) WHERE transaction_hash IS NOT NULL;
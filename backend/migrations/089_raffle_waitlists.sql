@@ -0,0 +1,32 @@
+-- Users waiting for a sold-out raffle or out-of-stock item, notified in join order when boxes free up
+-- or the item is listed again
+
+CREATE TABLE raffle_waitlist_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    raffle_id UUID NOT NULL REFERENCES raffles(id) ON DELETE CASCADE,
+    -- A relisted item gets a new raffle; entries follow the item to it
+    item_id UUID NOT NULL REFERENCES items(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason VARCHAR(20) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'waiting',
+    -- The raffle the user was sent to: this one when boxes freed up, a new one when relisted
+    notified_raffle_id UUID REFERENCES raffles(id) ON DELETE SET NULL,
+    notified_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT raffle_waitlist_entries_reason_check CHECK (reason IN ('sold_out', 'out_of_stock')),
+    CONSTRAINT raffle_waitlist_entries_status_check CHECK (status IN ('waiting', 'notified'))
+);
+
+-- One place in line per user; a notified user can join again
+CREATE UNIQUE INDEX idx_raffle_waitlist_waiting_user ON raffle_waitlist_entries (raffle_id, user_id)
+    WHERE status = 'waiting';
+CREATE INDEX idx_raffle_waitlist_queue ON raffle_waitlist_entries (raffle_id, created_at, id)
+    WHERE status = 'waiting';
+CREATE INDEX idx_raffle_waitlist_item ON raffle_waitlist_entries (item_id) WHERE status = 'waiting';
+CREATE INDEX idx_raffle_waitlist_notified ON raffle_waitlist_entries (notified_raffle_id, notified_at)
+    WHERE status = 'notified';
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('waitlist_claim_window_minutes', '30',
+     'Minutes freed boxes are kept for notified waitlist users before the next users in line are told')
+ON CONFLICT (key) DO NOTHING;
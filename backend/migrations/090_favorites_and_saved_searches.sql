@@ -0,0 +1,45 @@
+-- Favorite raffles and saved searches, with the alerts sent for them. Item favorites already live
+-- in item_favorites.
+
+CREATE TABLE raffle_favorites (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    raffle_id UUID NOT NULL REFERENCES raffles(id) ON DELETE CASCADE,
+    -- Set once the user has been told the raffle is nearly done; each favorite alerts once
+    near_completion_notified_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT raffle_favorites_user_raffle_unique UNIQUE (user_id, raffle_id)
+);
+
+CREATE INDEX idx_raffle_favorites_pending_alert ON raffle_favorites (raffle_id)
+    WHERE near_completion_notified_at IS NULL;
+
+CREATE TABLE saved_searches (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    -- Query text, price range and tags, as the search endpoints take them
+    filter JSONB NOT NULL,
+    alerts_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_checked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_saved_searches_user ON saved_searches (user_id, created_at);
+CREATE INDEX idx_saved_searches_alerts ON saved_searches (last_checked_at NULLS FIRST) WHERE alerts_enabled;
+
+-- Raffles a saved search has already alerted on, so each is announced once
+CREATE TABLE saved_search_matches (
+    saved_search_id UUID NOT NULL REFERENCES saved_searches(id) ON DELETE CASCADE,
+    raffle_id UUID NOT NULL REFERENCES raffles(id) ON DELETE CASCADE,
+    notified_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (saved_search_id, raffle_id)
+);
+
+INSERT INTO system_settings (key, value, description) VALUES
+    ('favorite_near_completion_percent', '80',
+     'Users are alerted when a raffle they favorited has sold this percentage of its boxes'),
+    ('favorite_ending_soon_minutes', '60',
+     'Users are alerted when a raffle they favorited ends within this many minutes')
+ON CONFLICT (key) DO NOTHING;
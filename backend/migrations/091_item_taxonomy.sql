@@ -0,0 +1,167 @@
+-- Category taxonomy: hierarchical categories with slugs, typed attributes such as brand, size and
+-- condition, category and attribute values on items, and both on listings for filtered search
+
+-- Names only need to be unique among siblings now ("Shoes" under Men and under Women); slugs are global
+ALTER TABLE item_categories DROP CONSTRAINT IF EXISTS item_categories_name_key;
+ALTER TABLE item_categories ADD COLUMN slug VARCHAR(60);
+
+UPDATE item_categories
+SET slug = trim(BOTH '-' FROM regexp_replace(lower(name), '[^a-z0-9]+', '-', 'g'));
+
+UPDATE item_categories
+SET display_order = COALESCE(display_order, 0), is_active = COALESCE(is_active, TRUE),
+    created_at = COALESCE(created_at, NOW()), updated_at = COALESCE(updated_at, NOW());
+
+ALTER TABLE item_categories
+    ALTER COLUMN slug SET NOT NULL,
+    ALTER COLUMN display_order SET NOT NULL,
+    ALTER COLUMN is_active SET NOT NULL,
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET NOT NULL;
+
+ALTER TABLE item_categories ADD CONSTRAINT item_categories_slug_key UNIQUE (slug);
+CREATE UNIQUE INDEX idx_item_categories_sibling_name
+    ON item_categories (COALESCE(parent_category_id, 0), lower(name));
+
+-- Attributes an item in the category can describe itself with. Subcategories inherit their
+-- ancestors' attributes; those without a category apply to every item. Keys are unique across
+-- the taxonomy so a search filter names one attribute.
+CREATE TABLE category_attributes (
+    id BIGSERIAL PRIMARY KEY,
+    category_id BIGINT REFERENCES item_categories(id) ON DELETE CASCADE,
+    key VARCHAR(40) NOT NULL UNIQUE,
+    label VARCHAR(60) NOT NULL,
+    attribute_type VARCHAR(20) NOT NULL,
+    -- The values an enum attribute may take
+    allowed_values TEXT[] NOT NULL DEFAULT '{}',
+    is_required BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Whether raffle search can filter on it
+    is_filterable BOOLEAN NOT NULL DEFAULT FALSE,
+    display_order INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CONSTRAINT category_attributes_type_check
+        CHECK (attribute_type IN ('text', 'number', 'boolean', 'enum')),
+    CONSTRAINT category_attributes_enum_check
+        CHECK (attribute_type <> 'enum' OR cardinality(allowed_values) > 0)
+);
+
+CREATE INDEX idx_category_attributes_category ON category_attributes (category_id);
+
+-- The free-text category column stays, kept to the category's name, for fee schedules and search
+ALTER TABLE items
+    ADD COLUMN category_id BIGINT REFERENCES item_categories(id) ON DELETE SET NULL,
+    ADD COLUMN attributes JSONB NOT NULL DEFAULT '{}';
+
+UPDATE items i
+SET category_id = c.id, category = c.name
+FROM item_categories c
+WHERE i.category IS NOT NULL AND c.parent_category_id IS NULL AND lower(c.name) = lower(trim(i.category));
+
+CREATE INDEX idx_items_category_id ON items (category_id) WHERE category_id IS NOT NULL;
+
+INSERT INTO category_attributes
+    (category_id, key, label, attribute_type, allowed_values, is_required, is_filterable, display_order)
+VALUES
+    (NULL, 'condition', 'Condition', 'enum', ARRAY['new', 'like_new', 'good', 'fair', 'for_parts'], FALSE, TRUE, 1),
+    (NULL, 'brand', 'Brand', 'text', '{}', FALSE, TRUE, 2),
+    ((SELECT id FROM item_categories WHERE slug = 'fashion'), 'size', 'Size', 'text', '{}', FALSE, TRUE, 3)
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE raffle_listings
+    ADD COLUMN item_category_id BIGINT,
+    ADD COLUMN item_attributes JSONB NOT NULL DEFAULT '{}';
+
+CREATE INDEX idx_raffle_listings_category ON raffle_listings (item_category_id) WHERE item_category_id IS NOT NULL;
+CREATE INDEX idx_raffle_listings_attributes ON raffle_listings USING GIN (item_attributes jsonb_path_ops);
+
+CREATE OR REPLACE FUNCTION refresh_raffle_listing(p_raffle_id UUID)
+RETURNS VOID AS $$
+BEGIN
+    INSERT INTO raffle_listings (
+        raffle_id, item_id, total_boxes, box_price, currency, boxes_sold, total_winners, status,
+        winner_user_ids, grid_rows, grid_cols, starts_at, ends_at, deadline_action,
+        started_at, completed_at, created_at, completion_percentage, featured_score,
+        item_seller_id, item_name, item_description, item_images, item_retail_price,
+        item_cost_of_goods, item_status, item_stock_quantity, item_listing_fee_applied,
+        item_category, item_category_id, item_attributes, item_created_at, item_updated_at,
+        seller_company_name, seller_is_verified, tags, refreshed_at
+    )
+    SELECT
+        r.id, r.item_id, r.total_boxes, r.box_price, r.currency, COALESCE(r.boxes_sold, 0), r.total_winners,
+        COALESCE(r.status, 'open'), COALESCE(r.winner_user_ids, '{}'), r.grid_rows, r.grid_cols,
+        r.starts_at, r.ends_at, r.deadline_action, r.started_at, r.completed_at,
+        COALESCE(r.created_at, NOW()), c.completion,
+        c.completion + CASE WHEN COALESCE(s.is_verified, FALSE) THEN 100 ELSE 0 END,
+        i.seller_id, i.name, i.description, i.images, i.retail_price, i.cost_of_goods,
+        COALESCE(i.status, 'available'), COALESCE(i.stock_quantity, 1), i.listing_fee_applied,
+        i.category, i.category_id, i.attributes, COALESCE(i.created_at, NOW()), COALESCE(i.updated_at, NOW()),
+        s.company_name, COALESCE(s.is_verified, FALSE),
+        ARRAY(SELECT DISTINCT t FROM unnest(r.tags || i.tags) AS t ORDER BY t), NOW()
+    FROM raffles r
+    JOIN items i ON i.id = r.item_id
+    LEFT JOIN sellers s ON s.id = i.seller_id
+    CROSS JOIN LATERAL (
+        SELECT CASE WHEN r.total_boxes > 0
+            THEN ROUND(LEAST(COALESCE(r.boxes_sold, 0), r.total_boxes) * 100.0 / r.total_boxes, 2)
+            ELSE 0 END AS completion
+    ) c
+    WHERE r.id = p_raffle_id
+    ON CONFLICT (raffle_id) DO UPDATE SET
+        item_id = EXCLUDED.item_id,
+        total_boxes = EXCLUDED.total_boxes,
+        box_price = EXCLUDED.box_price,
+        currency = EXCLUDED.currency,
+        boxes_sold = EXCLUDED.boxes_sold,
+        total_winners = EXCLUDED.total_winners,
+        status = EXCLUDED.status,
+        winner_user_ids = EXCLUDED.winner_user_ids,
+        grid_rows = EXCLUDED.grid_rows,
+        grid_cols = EXCLUDED.grid_cols,
+        starts_at = EXCLUDED.starts_at,
+        ends_at = EXCLUDED.ends_at,
+        deadline_action = EXCLUDED.deadline_action,
+        started_at = EXCLUDED.started_at,
+        completed_at = EXCLUDED.completed_at,
+        created_at = EXCLUDED.created_at,
+        completion_percentage = EXCLUDED.completion_percentage,
+        featured_score = EXCLUDED.featured_score,
+        item_seller_id = EXCLUDED.item_seller_id,
+        item_name = EXCLUDED.item_name,
+        item_description = EXCLUDED.item_description,
+        item_images = EXCLUDED.item_images,
+        item_retail_price = EXCLUDED.item_retail_price,
+        item_cost_of_goods = EXCLUDED.item_cost_of_goods,
+        item_status = EXCLUDED.item_status,
+        item_stock_quantity = EXCLUDED.item_stock_quantity,
+        item_listing_fee_applied = EXCLUDED.item_listing_fee_applied,
+        item_category = EXCLUDED.item_category,
+        item_category_id = EXCLUDED.item_category_id,
+        item_attributes = EXCLUDED.item_attributes,
+        item_created_at = EXCLUDED.item_created_at,
+        item_updated_at = EXCLUDED.item_updated_at,
+        seller_company_name = EXCLUDED.seller_company_name,
+        seller_is_verified = EXCLUDED.seller_is_verified,
+        tags = EXCLUDED.tags,
+        refreshed_at = EXCLUDED.refreshed_at;
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT refresh_raffle_listing(id) FROM raffles;
+
+-- Snapshots record the item's category and attributes, so a dispute over condition can be settled
+CREATE OR REPLACE FUNCTION item_snapshot_data(i items) RETURNS JSONB AS $$
+    SELECT jsonb_build_object(
+        'seller_id', i.seller_id,
+        'name', i.name,
+        'description', i.description,
+        'images', i.images,
+        'retail_price', i.retail_price,
+        'currency', i.currency,
+        'category', i.category,
+        'category_id', i.category_id,
+        'attributes', i.attributes,
+        'status', i.status,
+        'tags', i.tags
+    );
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,27 @@
+-- A voucher purchase emits one BoxPurchased log per box in a single transaction; keyed on the
+-- transaction alone, only the first box was stored. The log's index in its block tells them apart.
+-- Rows already stored keep index 0: each was the only one kept for its transaction.
+
+ALTER TABLE blockchain_events ADD COLUMN IF NOT EXISTS log_index BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE blockchain_event_dead_letters ADD COLUMN IF NOT EXISTS log_index BIGINT NOT NULL DEFAULT 0;
+
+-- The old keys have generated names, which Postgres truncates for the dead letter table
+DO $$
+DECLARE
+    old_key RECORD;
+BEGIN
+    FOR old_key IN
+        SELECT conrelid::regclass AS table_name, conname
+        FROM pg_constraint
+        WHERE contype = 'u'
+          AND conrelid IN ('blockchain_events'::regclass, 'blockchain_event_dead_letters'::regclass)
+    LOOP
+        EXECUTE format('ALTER TABLE %s DROP CONSTRAINT %I', old_key.table_name, old_key.conname);
+    END LOOP;
+END $$;
+
+ALTER TABLE blockchain_events
+    ADD CONSTRAINT blockchain_events_log_key UNIQUE (transaction_hash, event_type, raffle_id, log_index);
+ALTER TABLE blockchain_event_dead_letters
+    ADD CONSTRAINT blockchain_event_dead_letters_log_key
+        UNIQUE (transaction_hash, event_type, blockchain_raffle_id, log_index);
@@ -0,0 +1,19 @@
+-- One receipt per checkout rather than per box. Receipts were keyed by the box a user happened to
+-- open, so a multi-box checkout could collect one full-priced receipt per box.
+
+ALTER TABLE purchase_receipts ADD COLUMN IF NOT EXISTS transaction_id UUID;
+
+UPDATE purchase_receipts pr
+SET transaction_id = COALESCE(bp.transaction_id, bp.id)
+FROM box_purchases bp
+WHERE bp.id = pr.purchase_id AND pr.transaction_id IS NULL;
+
+-- Keep the first receipt issued for each checkout
+DELETE FROM purchase_receipts pr
+USING purchase_receipts earlier
+WHERE pr.transaction_id = earlier.transaction_id
+  AND (COALESCE(earlier.created_at, '-infinity'), earlier.id) < (COALESCE(pr.created_at, '-infinity'), pr.id);
+
+ALTER TABLE purchase_receipts ALTER COLUMN transaction_id SET NOT NULL;
+ALTER TABLE purchase_receipts
+    ADD CONSTRAINT purchase_receipts_transaction_id_key UNIQUE (transaction_id);
@@ -0,0 +1,10 @@
+-- Data fixes, chain reconciliation and stuck-raffle runbooks repair raffle state outside the normal flow
+
+INSERT INTO permissions (key, description) VALUES
+    ('raffles.repair', 'Run raffle data fixes, chain reconciliation and stuck-raffle runbooks')
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO role_permissions (role, permission) VALUES
+    ('operator', 'raffles.repair'),
+    ('admin', 'raffles.repair')
+ON CONFLICT DO NOTHING;
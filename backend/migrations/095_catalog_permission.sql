@@ -0,0 +1,10 @@
+-- Curating the shared tag vocabulary, collections and category tree is its own duty, separate from moderating listings
+
+INSERT INTO permissions (key, description) VALUES
+    ('catalog.manage', 'Curate tags, tag synonyms, tag collections, categories and category attributes')
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO role_permissions (role, permission) VALUES
+    ('operator', 'catalog.manage'),
+    ('admin', 'catalog.manage')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,10 @@
+-- API rate plans set what sellers are billed, so managing them is granted like other money-moving duties
+
+INSERT INTO permissions (key, description) VALUES
+    ('api_plans.manage', 'Manage API rate plans, seller plan assignments and overage billing')
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO role_permissions (role, permission) VALUES
+    ('operator', 'api_plans.manage'),
+    ('admin', 'api_plans.manage')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,354 @@
+use crate::blockchain::client::BlockchainClient;
+use crate::blockchain::events::EventProcessor;
+use crate::blockchain::types::*;
+use sqlx::{FromRow, PgPool};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Subcommand of the backend binary that runs a backfill instead of the server
+pub const COMMAND: &str = "backfill-events";
+
+pub const USAGE: &str = "Usage: raffle-platform-backend backfill-events --from-block N [--to-block M] \
+[--blocks-per-query 1000] [--max-queries-per-second 5] [--restart]";
+
+const DEFAULT_BLOCKS_PER_QUERY: u64 = 1000;
+const DEFAULT_MAX_QUERIES_PER_SECOND: u32 = 5;
+/// Attempts at one block range before the run stops; rerunning resumes from that range
+const RANGE_ATTEMPTS: u32 = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackfillOptions {
+    pub from_block: u64,
+    /// Defaults to the current head
+    pub to_block: Option<u64>,
+    pub blocks_per_query: u64,
+    pub max_queries_per_second: u32,
+    /// Start over instead of resuming an unfinished run of the same range
+    pub restart: bool,
+}
+
+impl BackfillOptions {
+    /// Parse the arguments after the subcommand
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut from_block = None;
+        let mut to_block = None;
+        let mut blocks_per_query = DEFAULT_BLOCKS_PER_QUERY;
+        let mut max_queries_per_second = DEFAULT_MAX_QUERIES_PER_SECOND;
+        let mut restart = false;
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .and_then(|v| v.parse::<u64>().ok())
+                    .ok_or_else(|| format!("{} needs a non-negative number", name))
+            };
+
+            match arg.as_str() {
+                "--from-block" => from_block = Some(value("--from-block")?),
+                "--to-block" => to_block = Some(value("--to-block")?),
+                "--blocks-per-query" => blocks_per_query = value("--blocks-per-query")?,
+                "--max-queries-per-second" => {
+                    max_queries_per_second = u32::try_from(value("--max-queries-per-second")?)
+                        .map_err(|_| "--max-queries-per-second is too large".to_string())?
+                }
+                "--restart" => restart = true,
+                other => return Err(format!("Unknown argument: {}", other)),
+            }
+        }
+
+        let from_block = from_block.ok_or_else(|| "--from-block is required".to_string())?;
+
+        if to_block.is_some_and(|to_block| to_block < from_block) {
+            return Err("--to-block must not be before --from-block".to_string());
+        }
+        if blocks_per_query == 0 {
+            return Err("--blocks-per-query must be at least 1".to_string());
+        }
+        if max_queries_per_second == 0 {
+            return Err("--max-queries-per-second must be at least 1".to_string());
+        }
+
+        Ok(Self {
+            from_block,
+            to_block,
+            blocks_per_query,
+            max_queries_per_second,
+            restart,
+        })
+    }
+}
+
+/// A backfill run and how far it has got
+#[derive(Debug, Clone, FromRow)]
+pub struct BackfillRun {
+    pub id: Uuid,
+    pub from_block: i64,
+    pub to_block: i64,
+    pub next_block: i64,
+    pub logs_processed: i64,
+    pub status: String,
+}
+
+/// Re-reads contract logs over a block range through the event processor's historical path.
+///
+/// The processor should be configured with `events_only`, so only `blockchain_events` is
+/// rebuilt; the live processor's checkpoint is left alone. Each finished range is checkpointed
+/// in `event_backfill_runs`, and rerunning the same range resumes after the last one.
+pub struct EventBackfill {
+    client: Arc<BlockchainClient>,
+    processor: EventProcessor,
+    db_pool: PgPool,
+}
+
+impl EventBackfill {
+    pub fn new(client: Arc<BlockchainClient>, processor: EventProcessor, db_pool: PgPool) -> Self {
+        Self {
+            client,
+            processor,
+            db_pool,
+        }
+    }
+
+    /// Backfill the range, resuming an unfinished run of it unless told to restart
+    pub async fn run(&self, options: &BackfillOptions) -> BlockchainResult<BackfillRun> {
+        let head = self.client.get_block_number().await?;
+        let to_block = options.to_block.unwrap_or(head);
+
+        if options.from_block > to_block {
+            return Err(BlockchainError::Configuration(format!(
+                "Start block {} is past the head block {}",
+                options.from_block, head
+            )));
+        }
+        if to_block > head {
+            return Err(BlockchainError::Configuration(format!(
+                "End block {} is past the head block {}",
+                to_block, head
+            )));
+        }
+
+        let mut run = self
+            .checkpoint(options.from_block, to_block, options.restart)
+            .await
+            .map_err(database_error)?;
+
+        if run.next_block > run.from_block {
+            info!(
+                "Resuming backfill {} of blocks {}-{} at block {}",
+                run.id, run.from_block, run.to_block, run.next_block
+            );
+        } else {
+            info!("Starting backfill {} of blocks {}-{}", run.id, run.from_block, run.to_block);
+        }
+
+        // Nobody listens to the broadcasts; holding a receiver keeps them from failing
+        let _events = self.processor.subscribe_to_events();
+
+        let pace = Duration::from_secs_f64(1.0 / options.max_queries_per_second as f64);
+        let started = Instant::now();
+        let resumed_at = run.next_block as u64;
+
+        while run.next_block as u64 <= to_block {
+            let range_started = Instant::now();
+            let from = run.next_block as u64;
+            let to = range_end(from, to_block, options.blocks_per_query);
+
+            let logs = self.process_range(from, to).await?;
+            run = self.advance(run.id, to + 1, logs).await.map_err(database_error)?;
+
+            let remaining = to_block - to;
+            let eta = eta_seconds(to + 1 - resumed_at, started.elapsed(), remaining)
+                .map(|secs| format!("~{}s left", secs))
+                .unwrap_or_else(|| "done".to_string());
+            info!(
+                "Backfilled blocks {}-{}: {} logs, {} total, {:.1}% of the range, {}",
+                from,
+                to,
+                logs,
+                run.logs_processed,
+                percent_done(options.from_block, to_block, to),
+                eta
+            );
+
+            if let Some(wait) = pace.checked_sub(range_started.elapsed()) {
+                tokio::time::sleep(wait).await;
+            }
+        }
+
+        let run = self.complete(run.id).await.map_err(database_error)?;
+        info!(
+            "Backfill {} of blocks {}-{} completed with {} logs in {}s",
+            run.id,
+            run.from_block,
+            run.to_block,
+            run.logs_processed,
+            started.elapsed().as_secs()
+        );
+
+        Ok(run)
+    }
+
+    /// Process one range, retrying with backoff; inserts are idempotent so a retry is safe
+    async fn process_range(&self, from: u64, to: u64) -> BlockchainResult<usize> {
+        let mut delay = Duration::from_secs(1);
+        let mut attempt = 1;
+
+        loop {
+            match self.processor.process_block_range(from, to).await {
+                Ok(logs) => return Ok(logs),
+                Err(e) if attempt < RANGE_ATTEMPTS => {
+                    warn!(
+                        "Backfill of blocks {}-{} failed (attempt {}/{}): {}",
+                        from, to, attempt, RANGE_ATTEMPTS, e
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// The unfinished run of this range, or a new one
+    async fn checkpoint(&self, from_block: u64, to_block: u64, restart: bool) -> Result<BackfillRun, sqlx::Error> {
+        if restart {
+            sqlx::query(
+                "UPDATE event_backfill_runs SET status = 'abandoned', updated_at = NOW()
+                 WHERE from_block = $1 AND to_block = $2 AND status = 'running'",
+            )
+            .bind(from_block as i64)
+            .bind(to_block as i64)
+            .execute(&self.db_pool)
+            .await?;
+        }
+
+        let existing = sqlx::query_as::<_, BackfillRun>(
+            "SELECT id, from_block, to_block, next_block, logs_processed, status
+             FROM event_backfill_runs
+             WHERE from_block = $1 AND to_block = $2 AND status = 'running'",
+        )
+        .bind(from_block as i64)
+        .bind(to_block as i64)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        if let Some(run) = existing {
+            return Ok(run);
+        }
+
+        sqlx::query_as::<_, BackfillRun>(
+            "INSERT INTO event_backfill_runs (from_block, to_block, next_block)
+             VALUES ($1, $2, $1)
+             RETURNING id, from_block, to_block, next_block, logs_processed, status",
+        )
+        .bind(from_block as i64)
+        .bind(to_block as i64)
+        .fetch_one(&self.db_pool)
+        .await
+    }
+
+    async fn advance(&self, id: Uuid, next_block: u64, logs: usize) -> Result<BackfillRun, sqlx::Error> {
+        sqlx::query_as::<_, BackfillRun>(
+            "UPDATE event_backfill_runs
+             SET next_block = $2, logs_processed = logs_processed + $3, updated_at = NOW()
+             WHERE id = $1
+             RETURNING id, from_block, to_block, next_block, logs_processed, status",
+        )
+        .bind(id)
+        .bind(next_block as i64)
+        .bind(logs as i64)
+        .fetch_one(&self.db_pool)
+        .await
+    }
+
+    async fn complete(&self, id: Uuid) -> Result<BackfillRun, sqlx::Error> {
+        sqlx::query_as::<_, BackfillRun>(
+            "UPDATE event_backfill_runs
+             SET status = 'completed', completed_at = NOW(), updated_at = NOW()
+             WHERE id = $1
+             RETURNING id, from_block, to_block, next_block, logs_processed, status",
+        )
+        .bind(id)
+        .fetch_one(&self.db_pool)
+        .await
+    }
+}
+
+fn database_error(e: sqlx::Error) -> BlockchainError {
+    BlockchainError::EventProcessing(format!("Failed to checkpoint backfill: {}", e))
+}
+
+/// Last block of the query starting at `from`
+fn range_end(from: u64, to_block: u64, blocks_per_query: u64) -> u64 {
+    from.saturating_add(blocks_per_query - 1).min(to_block)
+}
+
+/// Share of the whole range processed once `through` is done
+fn percent_done(from_block: u64, to_block: u64, through: u64) -> f64 {
+    let total = (to_block - from_block + 1) as f64;
+    let done = (through.min(to_block) + 1).saturating_sub(from_block) as f64;
+    done * 100.0 / total
+}
+
+/// Seconds left at the pace so far; None when nothing is left
+fn eta_seconds(blocks_done: u64, elapsed: Duration, blocks_left: u64) -> Option<u64> {
+    if blocks_left == 0 {
+        return None;
+    }
+    if blocks_done == 0 {
+        return Some(0);
+    }
+
+    Some((elapsed.as_secs_f64() * blocks_left as f64 / blocks_done as f64).ceil() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = BackfillOptions::parse(&args(&["--from-block", "100", "--to-block", "5000"])).unwrap();
+        assert_eq!(options.from_block, 100);
+        assert_eq!(options.to_block, Some(5000));
+        assert_eq!(options.blocks_per_query, DEFAULT_BLOCKS_PER_QUERY);
+        assert_eq!(options.max_queries_per_second, DEFAULT_MAX_QUERIES_PER_SECOND);
+        assert!(!options.restart);
+
+        let options = BackfillOptions::parse(&args(&[
+            "--from-block", "1", "--blocks-per-query", "50", "--max-queries-per-second", "2", "--restart",
+        ]))
+        .unwrap();
+        assert_eq!(options.to_block, None);
+        assert_eq!(options.blocks_per_query, 50);
+        assert_eq!(options.max_queries_per_second, 2);
+        assert!(options.restart);
+
+        assert!(BackfillOptions::parse(&args(&["--to-block", "10"])).is_err());
+        assert!(BackfillOptions::parse(&args(&["--from-block", "10", "--to-block", "5"])).is_err());
+        assert!(BackfillOptions::parse(&args(&["--from-block", "x"])).is_err());
+        assert!(BackfillOptions::parse(&args(&["--from-block", "1", "--blocks-per-query", "0"])).is_err());
+        assert!(BackfillOptions::parse(&args(&["--from-block", "1", "--verbose"])).is_err());
+    }
+
+    #[test]
+    fn test_ranges_and_progress() {
+        assert_eq!(range_end(100, 5000, 1000), 1099);
+        assert_eq!(range_end(4500, 5000, 1000), 5000);
+        assert_eq!(range_end(7, 7, 1), 7);
+
+        assert_eq!(percent_done(100, 199, 149), 50.0);
+        assert_eq!(percent_done(100, 199, 199), 100.0);
+
+        assert_eq!(eta_seconds(100, Duration::from_secs(10), 300), Some(30));
+        assert_eq!(eta_seconds(100, Duration::from_secs(10), 0), None);
+    }
+}
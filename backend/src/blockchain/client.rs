@@ -1,4 +1,5 @@
 use crate::blockchain::types::*;
+use crate::blockchain::wallet::{PlatformSigner, SignerConfig};
 use ethers::prelude::*;
 use std::sync::Arc;
 use std::time::Duration;
@@ -10,7 +11,7 @@ use tracing::{debug, error, info, warn};
 pub struct BlockchainClient {
     provider: Arc<Provider<Http>>,
     ws_provider: Option<Arc<Provider<Ws>>>,
-    signer: Arc<LocalWallet>,
+    signer: Arc<PlatformSigner>,
     network_config: NetworkConfig,
     retry_config: RetryConfig,
 }
@@ -19,7 +20,7 @@ impl BlockchainClient {
     /// Create a new blockchain client
     pub async fn new(
         network_config: NetworkConfig,
-        private_key: &str,
+        signer: &SignerConfig,
         retry_config: Option<RetryConfig>,
     ) -> BlockchainResult<Self> {
         // Create HTTP provider
@@ -39,13 +40,8 @@ impl BlockchainClient {
             None
         };
 
-        // Create wallet from private key
-        let wallet: LocalWallet = private_key
-            .parse()
-            .map_err(|e| BlockchainError::Wallet(format!("Invalid private key: {}", e)))?;
-
-        // Set chain ID
-        let wallet = wallet.with_chain_id(network_config.chain_id);
+        // Connect the platform wallet's signer, local or remote
+        let wallet = PlatformSigner::connect(signer, network_config.chain_id).await?;
 
         let client = Self {
             provider: Arc::new(provider),
@@ -115,7 +111,7 @@ impl BlockchainClient {
     }
 
     /// Get the signer
-    pub fn signer(&self) -> Arc<LocalWallet> {
+    pub fn signer(&self) -> Arc<PlatformSigner> {
         self.signer.clone()
     }
 
@@ -354,7 +350,7 @@ impl BlockchainClient {
     }
 
     /// Create a signed middleware for contract interactions
-    pub fn create_signed_middleware(&self) -> Arc<SignerMiddleware<Provider<Http>, LocalWallet>> {
+    pub fn create_signed_middleware(&self) -> Arc<SignedClient> {
         Arc::new(SignerMiddleware::new(
             self.provider.clone(),
             self.signer.clone(),
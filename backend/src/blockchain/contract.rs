@@ -2,6 +2,8 @@ use crate::blockchain::types::*;
 use crate::blockchain::client::BlockchainClient;
 use crate::blockchain::transaction::TransactionManager;
 use crate::blockchain::gas::GasManager;
+use crate::blockchain::token::{AllowanceUpdate, TokenClient};
+use ethers::abi::Token;
 use ethers::prelude::*;
 use std::sync::Arc;
 use tracing::{debug, error, info, warn};
@@ -14,6 +16,7 @@ abigen!(
     r#"[
         function createRaffle(string title, string description, uint256 totalBoxes, uint256 pricePerBox, uint256 startTime, uint256 endTime, string itemImageUrl, string itemDescription, uint256 maxParticipantsPerUser, uint256 minimumParticipants, bool requiresWhitelist, bytes32 whitelistMerkleRoot, uint256 creatorFeePercentage, address paymentToken) external returns (uint256)
         function purchaseParticipation(uint256 raffleId, uint256 boxesToPurchase, bytes32[] merkleProof) external payable
+        function buyBoxesWithVoucher(uint256 raffleId, uint256 boxes, bytes32 purchaseId, uint256 deadline, bytes signature) external
         function requestRandomWinner(uint256 raffleId) external
         function raffles(uint256 raffleId) external view returns (tuple(uint256 id, address creator, string title, string description, uint256 totalBoxes, uint256 pricePerBox, uint256 startTime, uint256 endTime, uint8 status, address winner, uint256 totalParticipants, uint256 totalRevenue, string itemImageUrl, string itemDescription, uint256 createdAt, uint256 maxParticipantsPerUser, uint256 minimumParticipants, bool requiresWhitelist, bytes32 whitelistMerkleRoot, uint256 creatorFeePercentage, uint256 platformFeePercentage, address paymentToken, uint256 randomSeed, bool isVerified))
         function getRaffleParticipants(uint256 raffleId) external view returns (address[])
@@ -39,6 +42,7 @@ abigen!(
         event ParticipationPurchased(uint256 indexed raffleId, address indexed participant, uint256 indexed participationId, uint256 boxesPurchased, uint256 totalCost)
         event RaffleCompleted(uint256 indexed raffleId, address indexed winner, uint256 totalRevenue, uint256 totalParticipants, uint256 randomSeed)
         event RaffleCancelled(uint256 indexed raffleId, string reason)
+        event VoucherRedeemed(bytes32 indexed purchaseId, uint256 indexed raffleId, address indexed buyer, uint256 boxes)
         event RefundIssued(uint256 indexed raffleId, address indexed participant, uint256 amount)
         event RandomnessRequested(uint256 indexed raffleId, uint256 indexed requestId)
         event RandomnessFulfilled(uint256 indexed raffleId, uint256 randomSeed)
@@ -49,13 +53,30 @@ abigen!(
     ]"#
 );
 
+/// Multicall3 is deployed at the same address on every major chain
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+/// Raffle reads per multicall; keeps each eth_call well under node gas and response size caps
+pub const MULTICALL_CHUNK_SIZE: usize = 200;
+/// How long a token purchase waits for its approval to be mined before giving up
+const APPROVAL_WAIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(180);
+const APPROVAL_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
 /// High-level client for interacting with the RaffleContract
 #[derive(Clone)]
 pub struct RaffleContractClient {
-    contract: RaffleContract<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    contract: RaffleContract<SignedClient>,
     client: Arc<BlockchainClient>,
     transaction_manager: Arc<TransactionManager>,
     gas_manager: Arc<GasManager>,
+    tokens: TokenClient,
+}
+
+/// Transactions submitted for an ERC-20 box purchase
+#[derive(Debug, Clone)]
+pub struct TokenPurchase {
+    pub allowance: AllowanceUpdate,
+    pub purchase_tx_id: Uuid,
+    pub amount: U256,
 }
 
 impl RaffleContractClient {
@@ -68,11 +89,14 @@ impl RaffleContractClient {
         let middleware = client.create_signed_middleware();
         let contract = RaffleContract::new(client.network_config().contract_address, middleware);
 
+        let tokens = TokenClient::new(client.clone(), transaction_manager.clone(), gas_manager.clone());
+
         Ok(Self {
             contract,
             client,
             transaction_manager,
             gas_manager,
+            tokens,
         })
     }
 
@@ -126,7 +150,7 @@ impl RaffleContractClient {
         Ok(tx_id)
     }
 
-    /// Purchase participation in a raffle
+    /// Purchase participation in a raffle paid in the native coin
     pub async fn purchase_participation(
         &self,
         raffle_id: u64,
@@ -140,34 +164,85 @@ impl RaffleContractClient {
             boxes_to_purchase, raffle_id, buyer_address, payment_amount
         );
 
-        // Prepare transaction
-        let call = self.contract.purchase_participation(
-            U256::from(raffle_id),
-            U256::from(boxes_to_purchase),
-            merkle_proof,
+        let tx_id = self
+            .submit_purchase(raffle_id, boxes_to_purchase, merkle_proof, buyer_address, Some(payment_amount))
+            .await?;
+
+        debug!("Box purchase transaction submitted with ID: {}", tx_id);
+        Ok(tx_id)
+    }
+
+    /// Purchase participation in a raffle priced in an ERC-20 token.
+    ///
+    /// The contract pulls the cost from the platform wallet with `transferFrom`, so the
+    /// wallet's balance is checked and the contract's allowance raised first when needed;
+    /// the purchase is only submitted once the approval is mined.
+    /// `expected_decimals` comes from the supported-tokens registry and is checked on chain.
+    pub async fn purchase_participation_with_token(
+        &self,
+        raffle_id: u64,
+        boxes_to_purchase: u64,
+        merkle_proof: Vec<[u8; 32]>,
+        buyer_address: Address,
+        token: Address,
+        expected_decimals: u8,
+    ) -> BlockchainResult<TokenPurchase> {
+        let raffle = self.get_raffle(raffle_id).await?;
+        if raffle.payment_token.is_zero() {
+            return Err(BlockchainError::Transaction(format!(
+                "Raffle {} is paid in the native coin, not a token",
+                raffle_id
+            )));
+        }
+        if raffle.payment_token != token {
+            return Err(BlockchainError::Transaction(format!(
+                "Raffle {} is paid in token {:?}, not {:?}",
+                raffle_id, raffle.payment_token, token
+            )));
+        }
+
+        let token_info = self.tokens.verify_token(token, expected_decimals).await?;
+        let amount = raffle
+            .price_per_box
+            .checked_mul(U256::from(boxes_to_purchase))
+            .ok_or_else(|| BlockchainError::Transaction("Purchase amount overflows".to_string()))?;
+
+        let payer = self.client.signer().address();
+        let balance = self.tokens.balance_of(token, payer).await?;
+        if balance < amount {
+            return Err(BlockchainError::Transaction(format!(
+                "Platform wallet holds {} {} units but the purchase needs {}",
+                balance, token_info.symbol, amount
+            )));
+        }
+
+        info!(
+            "Purchasing {} boxes in raffle {} for address {:?} with {} {} units",
+            boxes_to_purchase, raffle_id, buyer_address, amount, token_info.symbol
         );
-        let mut tx_request = call.tx;
-        tx_request.value = Some(payment_amount);
-        tx_request.from = Some(buyer_address);
 
-        // Get gas estimate
-        let gas_estimate = self.gas_manager.estimate_gas_with_buffer(&tx_request, Some(15)).await?;
-        
-        // Configure transaction
-        let tx_config = TransactionConfig {
-            gas_limit: gas_estimate.gas_limit,
-            gas_price: Some(gas_estimate.gas_price),
-            max_fee_per_gas: gas_estimate.max_fee_per_gas,
-            max_priority_fee_per_gas: gas_estimate.max_priority_fee_per_gas,
-            confirmations: self.client.network_config().confirmations,
-            ..Default::default()
-        };
+        let allowance = self.tokens.ensure_allowance(token, self.contract_address(), amount).await?;
+        // The purchase is gas-estimated against the latest block, where `transferFrom`
+        // reverts until the approval is in it
+        for approval_tx_id in [allowance.reset_tx_id, allowance.approve_tx_id].into_iter().flatten() {
+            self.wait_until_mined(approval_tx_id).await?;
+        }
 
-        // Submit transaction
-        let tx_id = self.transaction_manager.submit_transaction(tx_request, Some(tx_config)).await?;
+        let purchase_tx_id = self
+            .submit_purchase(raffle_id, boxes_to_purchase, merkle_proof, buyer_address, None)
+            .await?;
 
-        debug!("Box purchase transaction submitted with ID: {}", tx_id);
-        Ok(tx_id)
+        debug!("Token box purchase transaction submitted with ID: {}", purchase_tx_id);
+        Ok(TokenPurchase {
+            allowance,
+            purchase_tx_id,
+            amount,
+        })
+    }
+
+    /// ERC-20 helpers sharing this client's signer and transaction queue
+    pub fn tokens(&self) -> &TokenClient {
+        &self.tokens
     }
 
     /// Get raffle details
@@ -491,8 +566,75 @@ impl RaffleContractClient {
         self.contract.address()
     }
 
+    async fn wait_until_mined(&self, tx_id: Uuid) -> BlockchainResult<()> {
+        let deadline = tokio::time::Instant::now() + APPROVAL_WAIT_TIMEOUT;
+
+        loop {
+            let status = self
+                .transaction_manager
+                .get_transaction_status(tx_id)
+                .await
+                .ok_or_else(|| BlockchainError::Transaction(format!("Unknown transaction {}", tx_id)))?;
+
+            match status.status {
+                TransactionState::Confirmed => return Ok(()),
+                TransactionState::Pending if status.block_number.is_some() => return Ok(()),
+                TransactionState::Failed | TransactionState::Dropped => {
+                    return Err(BlockchainError::Transaction(format!(
+                        "Approval transaction {} did not go through ({:?})",
+                        tx_id, status.status
+                    )));
+                }
+                TransactionState::Pending => {}
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                return Err(BlockchainError::Transaction(format!(
+                    "Approval transaction {} was not mined within {}s",
+                    tx_id,
+                    APPROVAL_WAIT_TIMEOUT.as_secs()
+                )));
+            }
+            tokio::time::sleep(APPROVAL_POLL_INTERVAL).await;
+        }
+    }
+
+    async fn submit_purchase(
+        &self,
+        raffle_id: u64,
+        boxes_to_purchase: u64,
+        merkle_proof: Vec<[u8; 32]>,
+        buyer_address: Address,
+        value: Option<U256>,
+    ) -> BlockchainResult<Uuid> {
+        let call = self.contract.purchase_participation(
+            U256::from(raffle_id),
+            U256::from(boxes_to_purchase),
+            merkle_proof,
+        );
+        let mut tx_request = call.tx;
+        // Token purchases send no value; the contract pulls the token instead
+        tx_request.value = value;
+        tx_request.from = Some(buyer_address);
+
+        // Get gas estimate
+        let gas_estimate = self.gas_manager.estimate_gas_with_buffer(&tx_request, Some(15)).await?;
+
+        // Configure transaction
+        let tx_config = TransactionConfig {
+            gas_limit: gas_estimate.gas_limit,
+            gas_price: Some(gas_estimate.gas_price),
+            max_fee_per_gas: gas_estimate.max_fee_per_gas,
+            max_priority_fee_per_gas: gas_estimate.max_priority_fee_per_gas,
+            confirmations: self.client.network_config().confirmations,
+            ..Default::default()
+        };
+
+        self.transaction_manager.submit_transaction(tx_request, Some(tx_config)).await
+    }
+
     /// Get contract instance for direct access
-    pub fn contract(&self) -> &RaffleContract<SignerMiddleware<Provider<Http>, LocalWallet>> {
+    pub fn contract(&self) -> &RaffleContract<SignedClient> {
         &self.contract
     }
 
@@ -525,17 +667,19 @@ impl RaffleContractClient {
     }
 
     /// Get multiple raffles in batch
+    ///
+    /// Raffles are read through Multicall3, one eth_call per `MULTICALL_CHUNK_SIZE` ids.
+    /// Ids that fail to load are skipped, and a chunk falls back to one call per raffle
+    /// when Multicall3 isn't available, e.g. on a local dev chain.
     pub async fn get_raffles_batch(&self, raffle_ids: Vec<u64>) -> BlockchainResult<Vec<RaffleData>> {
-        let mut raffles = Vec::new();
-        
-        // For now, we'll fetch them sequentially
-        // In production, you might want to use multicall for better efficiency
-        for raffle_id in raffle_ids {
-            match self.get_raffle(raffle_id).await {
-                Ok(raffle) => raffles.push(raffle),
+        let mut raffles = Vec::with_capacity(raffle_ids.len());
+
+        for chunk in raffle_ids.chunks(MULTICALL_CHUNK_SIZE) {
+            match self.multicall_raffles(chunk).await {
+                Ok(loaded) => raffles.extend(loaded),
                 Err(e) => {
-                    warn!("Failed to fetch raffle {}: {}", raffle_id, e);
-                    // Continue with other raffles
+                    warn!("Multicall of {} raffles failed, fetching them one by one: {}", chunk.len(), e);
+                    raffles.extend(self.get_raffles_sequential(chunk).await);
                 }
             }
         }
@@ -543,7 +687,7 @@ impl RaffleContractClient {
         Ok(raffles)
     }
 
-    /// Get raffle summary (lightweight version)
+    /// Get raffle summary (lightweight version)    /// Get raffle summary (lightweight version)
     pub async fn get_raffle_summary(&self, raffle_id: u64) -> BlockchainResult<RaffleSummary> {
         let raffle = self.get_raffle(raffle_id).await?;
         
@@ -582,6 +726,105 @@ impl RaffleContractClient {
 
         Ok(active_raffles)
     }
+
+    // Private helper methods
+
+    async fn multicall_raffles(&self, raffle_ids: &[u64]) -> BlockchainResult<Vec<RaffleData>> {
+        let address: Address = MULTICALL3_ADDRESS
+            .parse()
+            .map_err(|e| BlockchainError::Configuration(format!("Invalid Multicall3 address: {}", e)))?;
+
+        let mut multicall = Multicall::new(self.contract.client(), Some(address))
+            .await
+            .map_err(|e| BlockchainError::Multicall(e.to_string()))?
+            .version(MulticallVersion::Multicall3);
+
+        for &raffle_id in raffle_ids {
+            // Allow failure so one missing raffle doesn't fail the whole batch
+            multicall.add_call(self.contract.raffles(U256::from(raffle_id)), true);
+        }
+
+        let results = multicall
+            .call_raw()
+            .await
+            .map_err(|e| BlockchainError::Multicall(e.to_string()))?;
+
+        let mut raffles = Vec::with_capacity(results.len());
+        for (raffle_id, result) in raffle_ids.iter().zip(results) {
+            let decoded = match result {
+                Ok(token) => raffle_data_from_token(token),
+                Err(revert) => Err(BlockchainError::Multicall(format!("Call reverted: {}", revert))),
+            };
+
+            match decoded {
+                Ok(raffle) => raffles.push(raffle),
+                Err(e) => warn!("Failed to fetch raffle {}: {}", raffle_id, e),
+            }
+        }
+
+        Ok(raffles)
+    }
+
+    async fn get_raffles_sequential(&self, raffle_ids: &[u64]) -> Vec<RaffleData> {
+        let mut raffles = Vec::with_capacity(raffle_ids.len());
+
+        for &raffle_id in raffle_ids {
+            match self.get_raffle(raffle_id).await {
+                Ok(raffle) => raffles.push(raffle),
+                Err(e) => warn!("Failed to fetch raffle {}: {}", raffle_id, e),
+            }
+        }
+
+        raffles
+    }
+}
+
+/// Decode the `raffles(uint256)` return tuple as returned by a multicall
+pub fn raffle_data_from_token(token: Token) -> BlockchainResult<RaffleData> {
+    let fields = match token {
+        Token::Tuple(fields) if fields.len() == 24 => fields,
+        other => {
+            return Err(BlockchainError::Multicall(format!("Unexpected raffle data: {:?}", other)));
+        }
+    };
+    let mut fields = fields.into_iter();
+    let mut next = || fields.next().expect("length checked above");
+
+    let invalid = |name: &str| BlockchainError::Multicall(format!("Invalid raffle field {}", name));
+    let uint = |token: Token, name: &str| token.into_uint().ok_or_else(|| invalid(name));
+    let address = |token: Token, name: &str| token.into_address().ok_or_else(|| invalid(name));
+    let string = |token: Token, name: &str| token.into_string().ok_or_else(|| invalid(name));
+    let boolean = |token: Token, name: &str| token.into_bool().ok_or_else(|| invalid(name));
+
+    Ok(RaffleData {
+        id: uint(next(), "id")?,
+        creator: address(next(), "creator")?,
+        title: string(next(), "title")?,
+        description: string(next(), "description")?,
+        total_boxes: uint(next(), "totalBoxes")?,
+        price_per_box: uint(next(), "pricePerBox")?,
+        start_time: uint(next(), "startTime")?,
+        end_time: uint(next(), "endTime")?,
+        status: RaffleStatus::from(uint(next(), "status")?.low_u32() as u8),
+        winner: address(next(), "winner")?,
+        total_participants: uint(next(), "totalParticipants")?,
+        total_revenue: uint(next(), "totalRevenue")?,
+        item_image_url: string(next(), "itemImageUrl")?,
+        item_description: string(next(), "itemDescription")?,
+        created_at: uint(next(), "createdAt")?,
+        max_participants_per_user: uint(next(), "maxParticipantsPerUser")?,
+        minimum_participants: uint(next(), "minimumParticipants")?,
+        requires_whitelist: boolean(next(), "requiresWhitelist")?,
+        whitelist_merkle_root: next()
+            .into_fixed_bytes()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .ok_or_else(|| invalid("whitelistMerkleRoot"))?,
+        creator_fee_percentage: uint(next(), "creatorFeePercentage")?,
+        platform_fee_percentage: uint(next(), "platformFeePercentage")?,
+        payment_token: address(next(), "paymentToken")?,
+        random_seed: uint(next(), "randomSeed")?,
+        is_verified: boolean(next(), "isVerified")?,
+    })
 }
 
 #[derive(Debug, Clone)]
@@ -1,11 +1,14 @@
 use crate::blockchain::types::*;
 use crate::blockchain::client::BlockchainClient;
 use crate::blockchain::contract::RaffleContract;
+use crate::models::blockchain_event_dead_letter::BlockchainEventDeadLetter;
+use crate::services::external_wallet_service::purchase_uuid;
 use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::{broadcast, RwLock};
+use tokio::sync::{broadcast, Notify, RwLock};
 use tokio_stream::StreamExt;
 use tracing::{debug, error, info, warn};
 
@@ -17,6 +20,8 @@ pub struct EventProcessor {
     contract: RaffleContract<Provider<Ws>>,
     event_sender: broadcast::Sender<ProcessedEvent>,
     last_processed_block: Arc<RwLock<u64>>,
+    /// Woken when a new block doesn't build on the stored one before it
+    reorg_check: Arc<Notify>,
     config: EventProcessorConfig,
 }
 
@@ -28,6 +33,15 @@ pub struct EventProcessorConfig {
     pub retry_delay_ms: u64,
     pub max_blocks_per_query: u64,
     pub health_check_interval: u64,
+    /// Stored block hashes are kept, and reorgs detected, this many blocks back from the head
+    pub reorg_window_blocks: u64,
+    /// Seconds between retry runs over dead-lettered events
+    pub dead_letter_retry_interval: u64,
+    /// Failed attempts before a dead letter waits for an admin retry
+    pub dead_letter_max_attempts: i32,
+    /// Store events without applying them to raffles; replaying old events out of order
+    /// would move raffles back to earlier states
+    pub events_only: bool,
 }
 
 impl Default for EventProcessorConfig {
@@ -39,6 +53,10 @@ impl Default for EventProcessorConfig {
             retry_delay_ms: 1000,
             max_blocks_per_query: 1000,
             health_check_interval: 30,
+            reorg_window_blocks: 128,
+            dead_letter_retry_interval: 60,
+            dead_letter_max_attempts: 10,
+            events_only: false,
         }
     }
 }
@@ -51,9 +69,10 @@ pub enum ProcessedEvent {
     RaffleCancelled(RaffleCancelledEvent),
     RaffleFull(RaffleFullEvent),
     RandomnessRequested(RandomnessRequestedEvent),
+    VoucherRedeemed(VoucherRedeemedEvent),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RaffleCancelledEvent {
     pub raffle_id: U256,
     pub reason: String,
@@ -62,7 +81,7 @@ pub struct RaffleCancelledEvent {
     pub timestamp: u64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RaffleFullEvent {
     pub raffle_id: U256,
     pub total_boxes: U256,
@@ -71,7 +90,7 @@ pub struct RaffleFullEvent {
     pub timestamp: u64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RandomnessRequestedEvent {
     pub raffle_id: U256,
     pub request_id: U256,
@@ -80,6 +99,18 @@ pub struct RandomnessRequestedEvent {
     pub timestamp: u64,
 }
 
+/// Boxes recorded on chain by a user's own wallet with a voucher the platform signed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoucherRedeemedEvent {
+    pub purchase_id: H256,
+    pub raffle_id: U256,
+    pub buyer: Address,
+    pub boxes: U256,
+    pub block_number: u64,
+    pub transaction_hash: H256,
+    pub timestamp: u64,
+}
+
 impl EventProcessor {
     /// Create a new event processor
     pub async fn new(
@@ -111,6 +142,7 @@ impl EventProcessor {
             contract,
             event_sender,
             last_processed_block,
+            reorg_check: Arc::new(Notify::new()),
             config,
         })
     }
@@ -141,6 +173,18 @@ impl EventProcessor {
             processor.health_check_loop().await;
         });
 
+        // Confirm events once they are deep enough and roll back reorged ones
+        let processor = self.clone();
+        tokio::spawn(async move {
+            processor.chain_watch_loop().await;
+        });
+
+        // Replay events whose persistence failed
+        let processor = self.clone();
+        tokio::spawn(async move {
+            processor.dead_letter_retry_loop().await;
+        });
+
         info!("Event monitoring started successfully");
         Ok(())
     }
@@ -196,22 +240,8 @@ impl EventProcessor {
                 current_block
             );
 
-            debug!("Processing blocks {} to {}", from_block, to_block);
-
-            // Get events for this block range
-            let filter = Filter::new()
-                .address(self.client.network_config().contract_address)
-                .from_block(from_block)
-                .to_block(to_block);
-
-            match self.client.provider().get_logs(&filter).await {
-                Ok(logs) => {
-                    for log in logs {
-                        if let Err(e) = self.process_raw_log(log).await {
-                            error!("Failed to process historical log: {}", e);
-                        }
-                    }
-                    
+            match self.process_block_range(from_block, to_block).await {
+                Ok(_) => {
                     // Update last processed block
                     {
                         let mut last_block = self.last_processed_block.write().await;
@@ -241,6 +271,33 @@ impl EventProcessor {
         Ok(())
     }
 
+    /// Process the contract's logs in a block range; returns how many logs there were
+    pub async fn process_block_range(&self, from_block: u64, to_block: u64) -> BlockchainResult<usize> {
+        debug!("Processing blocks {} to {}", from_block, to_block);
+
+        // Get events for this block range
+        let filter = Filter::new()
+            .address(self.client.network_config().contract_address)
+            .from_block(from_block)
+            .to_block(to_block);
+
+        let logs = self.client.provider().get_logs(&filter).await?;
+        let count = logs.len();
+
+        for log in logs {
+            if let Err(e) = self.process_raw_log(log).await {
+                error!("Failed to process historical log: {}", e);
+            }
+        }
+
+        // Checkpoint the batch's last block so a reorg of blocks without our events is still seen
+        if let Err(e) = self.get_block_info(Some(U64::from(to_block))).await {
+            warn!("Failed to record checkpoint block {}: {}", to_block, e);
+        }
+
+        Ok(count)
+    }
+
     /// Process a contract event log
     async fn process_event_log(&self, log: RaffleContractEvents) -> BlockchainResult<()> {
         let processed_event = match log {
@@ -268,6 +325,10 @@ impl EventProcessor {
                 let processed = self.handle_randomness_requested(event).await?;
                 ProcessedEvent::RandomnessRequested(processed)
             }
+            RaffleContractEvents::VoucherRedeemedFilter(event) => {
+                let processed = self.handle_voucher_redeemed(event).await?;
+                ProcessedEvent::VoucherRedeemed(processed)
+            }
         };
 
         // Broadcast the processed event
@@ -293,6 +354,8 @@ impl EventProcessor {
             self.process_event_log(parsed).await?;
         } else if let Ok(parsed) = self.contract.decode_event::<RaffleContractEvents>("RandomnessRequested", log.topics.clone(), log.data.clone()) {
             self.process_event_log(parsed).await?;
+        } else if let Ok(parsed) = self.contract.decode_event::<RaffleContractEvents>("VoucherRedeemed", log.topics.clone(), log.data.clone()) {
+            self.process_event_log(parsed).await?;
         } else {
             debug!("Unknown event log: {:?}", log);
         }
@@ -319,6 +382,16 @@ impl EventProcessor {
         // Update database
         if let Err(e) = self.save_raffle_created_event(&processed_event).await {
             error!("Failed to save RaffleCreated event: {}", e);
+            self.dead_letter(
+                "raffle_created",
+                processed_event.raffle_id,
+                processed_event.block_number,
+                processed_event.transaction_hash,
+                0,
+                &processed_event,
+                &e,
+            )
+            .await;
         }
 
         info!(
@@ -332,6 +405,7 @@ impl EventProcessor {
     /// Handle BoxPurchased event
     async fn handle_box_purchased(&self, event: BoxPurchasedFilter) -> BlockchainResult<BoxPurchaseEvent> {
         let block_info = self.get_block_info(event.meta.block_number).await?;
+        let log_index = event.meta.log_index.as_u64();
         
         let processed_event = BoxPurchaseEvent {
             raffle_id: event.raffle_id,
@@ -344,8 +418,18 @@ impl EventProcessor {
         };
 
         // Update database
-        if let Err(e) = self.save_box_purchased_event(&processed_event).await {
+        if let Err(e) = self.save_box_purchased_event(&processed_event, log_index).await {
             error!("Failed to save BoxPurchased event: {}", e);
+            self.dead_letter(
+                "box_purchased",
+                processed_event.raffle_id,
+                processed_event.block_number,
+                processed_event.transaction_hash,
+                log_index,
+                &processed_event,
+                &e,
+            )
+            .await;
         }
 
         info!(
@@ -372,6 +456,16 @@ impl EventProcessor {
         // Update database
         if let Err(e) = self.save_winner_selected_event(&processed_event).await {
             error!("Failed to save WinnerSelected event: {}", e);
+            self.dead_letter(
+                "winner_selected",
+                processed_event.raffle_id,
+                processed_event.block_number,
+                processed_event.transaction_hash,
+                0,
+                &processed_event,
+                &e,
+            )
+            .await;
         }
 
         info!(
@@ -397,6 +491,16 @@ impl EventProcessor {
         // Update database
         if let Err(e) = self.save_raffle_cancelled_event(&processed_event).await {
             error!("Failed to save RaffleCancelled event: {}", e);
+            self.dead_letter(
+                "raffle_cancelled",
+                processed_event.raffle_id,
+                processed_event.block_number,
+                processed_event.transaction_hash,
+                0,
+                &processed_event,
+                &e,
+            )
+            .await;
         }
 
         info!(
@@ -422,6 +526,16 @@ impl EventProcessor {
         // Update database
         if let Err(e) = self.save_raffle_full_event(&processed_event).await {
             error!("Failed to save RaffleFull event: {}", e);
+            self.dead_letter(
+                "raffle_full",
+                processed_event.raffle_id,
+                processed_event.block_number,
+                processed_event.transaction_hash,
+                0,
+                &processed_event,
+                &e,
+            )
+            .await;
         }
 
         info!(
@@ -447,6 +561,16 @@ impl EventProcessor {
         // Update database
         if let Err(e) = self.save_randomness_requested_event(&processed_event).await {
             error!("Failed to save RandomnessRequested event: {}", e);
+            self.dead_letter(
+                "randomness_requested",
+                processed_event.raffle_id,
+                processed_event.block_number,
+                processed_event.transaction_hash,
+                0,
+                &processed_event,
+                &e,
+            )
+            .await;
         }
 
         info!(
@@ -457,19 +581,263 @@ impl EventProcessor {
         Ok(processed_event)
     }
 
+    /// Handle VoucherRedeemed event
+    async fn handle_voucher_redeemed(&self, event: VoucherRedeemedFilter) -> BlockchainResult<VoucherRedeemedEvent> {
+        let block_info = self.get_block_info(event.meta.block_number).await?;
+
+        let processed_event = VoucherRedeemedEvent {
+            purchase_id: H256::from(event.purchase_id),
+            raffle_id: event.raffle_id,
+            buyer: event.buyer,
+            boxes: event.boxes,
+            block_number: block_info.number,
+            transaction_hash: event.meta.transaction_hash,
+            timestamp: block_info.timestamp,
+        };
+
+        // Update database
+        if let Err(e) = self.save_voucher_redeemed_event(&processed_event).await {
+            error!("Failed to save VoucherRedeemed event: {}", e);
+            self.dead_letter(
+                "voucher_redeemed",
+                processed_event.raffle_id,
+                processed_event.block_number,
+                processed_event.transaction_hash,
+                0,
+                &processed_event,
+                &e,
+            )
+            .await;
+        }
+
+        info!(
+            "Processed VoucherRedeemed event: raffle_id={}, buyer={:?}, boxes={}",
+            processed_event.raffle_id, processed_event.buyer, processed_event.boxes
+        );
+
+        Ok(processed_event)
+    }
+
     /// Get block information
     async fn get_block_info(&self, block_number: Option<U64>) -> BlockchainResult<BlockInfo> {
         let block_num = block_number.unwrap_or_else(|| U64::zero());
         
-        match self.client.provider().get_block(block_num).await? {
-            Some(block) => Ok(BlockInfo {
+        let block_info = match self.client.provider().get_block(block_num).await? {
+            Some(block) => BlockInfo {
                 number: block.number.unwrap_or_default().as_u64(),
                 timestamp: block.timestamp.as_u64(),
                 hash: block.hash.unwrap_or_default(),
-            }),
-            None => Err(BlockchainError::EventProcessing(
+                parent_hash: block.parent_hash,
+            },
+            None => return Err(BlockchainError::EventProcessing(
                 format!("Block {} not found", block_num)
             )),
+        };
+
+        match self.record_block(&block_info).await {
+            Ok(true) => {}
+            Ok(false) => {
+                warn!(
+                    "Block {} does not build on the stored block {}, checking for a reorg",
+                    block_info.number,
+                    block_info.number.saturating_sub(1)
+                );
+                self.reorg_check.notify_one();
+            }
+            Err(e) => error!("Failed to record block {}: {}", block_info.number, e),
+        }
+
+        Ok(block_info)
+    }
+
+    /// Confirm deep-enough events and check for reorgs once per block, or sooner when a
+    /// block with an unexpected parent has been seen
+    async fn chain_watch_loop(&self) {
+        let mut interval = tokio::time::interval(
+            tokio::time::Duration::from_secs(self.client.network_config().block_time.max(1))
+        );
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = self.reorg_check.notified() => {}
+            }
+
+            match self.check_for_reorg().await {
+                Ok(Some(fork)) => {
+                    if let Err(e) = self.roll_back_and_replay(&fork).await {
+                        error!("Failed to roll back reorg from block {}: {}", fork.first_block, e);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Reorg check failed: {}", e),
+            }
+
+            let head = match self.client.get_block_number().await {
+                Ok(head) => head,
+                Err(e) => {
+                    warn!("Failed to read chain head for confirmations: {}", e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = self.confirm_events(head.saturating_sub(self.config.confirmation_blocks)).await {
+                error!("Failed to confirm events: {}", e);
+            }
+            if let Err(e) = self.prune_processed_blocks(head.saturating_sub(self.config.reorg_window_blocks)).await {
+                error!("Failed to prune processed blocks: {}", e);
+            }
+        }
+    }
+
+    /// Compare stored block hashes with the chain, newest first, until one still matches
+    async fn check_for_reorg(&self) -> BlockchainResult<Option<ForkPoint>> {
+        let stored = self.recent_blocks().await
+            .map_err(|e| BlockchainError::EventProcessing(format!("Failed to load processed blocks: {}", e)))?;
+
+        let mut canonical = Vec::with_capacity(stored.len());
+        for (number, stored_hash) in &stored {
+            let hash = self.client.provider().get_block(*number).await?
+                .and_then(|block| block.hash)
+                .map(|hash| format!("{:?}", hash))
+                .unwrap_or_default();
+
+            let matches = &hash == stored_hash;
+            canonical.push((*number, hash));
+            if matches {
+                break;
+            }
+        }
+
+        Ok(find_fork_point(&stored, &canonical))
+    }
+
+    /// Undo everything recorded from the fork on, then process the new chain from there
+    async fn roll_back_and_replay(&self, fork: &ForkPoint) -> BlockchainResult<()> {
+        warn!(
+            "Chain reorg detected from block {}: {} replaced by {}",
+            fork.first_block, fork.old_block_hash, fork.new_block_hash
+        );
+
+        let (rolled_back, affected_raffles) = self.roll_back_from(fork).await
+            .map_err(|e| BlockchainError::EventProcessing(format!("Rollback failed: {}", e)))?;
+
+        {
+            let mut last_block = self.last_processed_block.write().await;
+            *last_block = fork.first_block.saturating_sub(1);
+        }
+
+        info!(
+            "Rolled back {} events for raffles {:?}, replaying from block {}",
+            rolled_back, affected_raffles, fork.first_block
+        );
+
+        self.process_historical_events().await
+    }
+
+    /// Retry dead-lettered events on their backoff schedule
+    async fn dead_letter_retry_loop(&self) {
+        let mut interval = tokio::time::interval(
+            tokio::time::Duration::from_secs(self.config.dead_letter_retry_interval),
+        );
+
+        loop {
+            interval.tick().await;
+
+            let due = match BlockchainEventDeadLetter::due(&self.db_pool, self.config.batch_size as i64).await {
+                Ok(due) => due,
+                Err(e) => {
+                    warn!("Failed to load dead-lettered events: {}", e);
+                    continue;
+                }
+            };
+
+            for letter in due {
+                match self.replay_dead_letter(&letter).await {
+                    Ok(()) => {
+                        info!(
+                            "Replayed dead-lettered {} event for raffle {} from tx {}",
+                            letter.event_type, letter.blockchain_raffle_id, letter.transaction_hash
+                        );
+                        if let Err(e) = BlockchainEventDeadLetter::mark_resolved(&self.db_pool, letter.id).await {
+                            error!("Failed to resolve dead letter {}: {}", letter.id, e);
+                        }
+                    }
+                    Err(reason) => {
+                        warn!("Retry of dead letter {} failed: {}", letter.id, reason);
+                        if let Err(e) = BlockchainEventDeadLetter::mark_failed(
+                            &self.db_pool,
+                            letter.id,
+                            &reason,
+                            self.config.dead_letter_max_attempts,
+                        )
+                        .await
+                        {
+                            error!("Failed to record retry of dead letter {}: {}", letter.id, e);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Persist a dead-lettered event again from its stored payload
+    async fn replay_dead_letter(&self, letter: &BlockchainEventDeadLetter) -> Result<(), String> {
+        fn decode<T: serde::de::DeserializeOwned>(payload: &serde_json::Value) -> Result<T, String> {
+            serde_json::from_value(payload.clone()).map_err(|e| format!("Invalid payload: {}", e))
+        }
+
+        let result = match letter.event_type.as_str() {
+            "raffle_created" => self.save_raffle_created_event(&decode(&letter.payload)?).await,
+            "box_purchased" => {
+                self.save_box_purchased_event(&decode(&letter.payload)?, letter.log_index as u64).await
+            }
+            "winner_selected" => self.save_winner_selected_event(&decode(&letter.payload)?).await,
+            "raffle_cancelled" => self.save_raffle_cancelled_event(&decode(&letter.payload)?).await,
+            "raffle_full" => self.save_raffle_full_event(&decode(&letter.payload)?).await,
+            "randomness_requested" => self.save_randomness_requested_event(&decode(&letter.payload)?).await,
+            "voucher_redeemed" => self.save_voucher_redeemed_event(&decode(&letter.payload)?).await,
+            other => return Err(format!("Unknown event type {}", other)),
+        };
+
+        result.map_err(|e| e.to_string())
+    }
+
+    /// Keep an event whose persistence failed so the retry worker can replay it
+    async fn dead_letter<T: Serialize>(
+        &self,
+        event_type: &str,
+        raffle_id: U256,
+        block_number: u64,
+        transaction_hash: H256,
+        log_index: u64,
+        event: &T,
+        error: &sqlx::Error,
+    ) {
+        let payload = match serde_json::to_value(event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("Failed to serialize {} event for the dead-letter queue: {}", event_type, e);
+                return;
+            }
+        };
+
+        if let Err(e) = BlockchainEventDeadLetter::record(
+            &self.db_pool,
+            event_type,
+            raffle_id.as_u64() as i64,
+            block_number as i64,
+            &format!("{:?}", transaction_hash),
+            log_index as i64,
+            payload,
+            &error.to_string(),
+        )
+        .await
+        {
+            error!(
+                "Failed to dead-letter {} event from tx {:?}: {}",
+                event_type, transaction_hash, e
+            );
         }
     }
 
@@ -525,11 +893,202 @@ impl EventProcessor {
         Ok(())
     }
 
+    /// Store a block's hash; false when its parent differs from the stored block before it
+    async fn record_block(&self, block: &BlockInfo) -> Result<bool, sqlx::Error> {
+        let previous_hash = sqlx::query_scalar!(
+            "SELECT block_hash FROM processed_blocks WHERE block_number = $1",
+            block.number as i64 - 1
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        sqlx::query!(
+            "INSERT INTO processed_blocks (block_number, block_hash, parent_hash) VALUES ($1, $2, $3)
+             ON CONFLICT (block_number) DO UPDATE SET block_hash = $2, parent_hash = $3, processed_at = NOW()",
+            block.number as i64,
+            format!("{:?}", block.hash),
+            format!("{:?}", block.parent_hash)
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(previous_hash.map_or(true, |hash| hash == format!("{:?}", block.parent_hash)))
+    }
+
+    /// Stored block hashes inside the reorg window, newest first
+    async fn recent_blocks(&self) -> Result<Vec<(u64, String)>, sqlx::Error> {
+        let rows = sqlx::query!(
+            "SELECT block_number, block_hash FROM processed_blocks ORDER BY block_number DESC LIMIT $1",
+            self.config.reorg_window_blocks as i64
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| (r.block_number as u64, r.block_hash)).collect())
+    }
+
+    async fn confirm_events(&self, confirmed_through: u64) -> Result<(), sqlx::Error> {
+        let result = sqlx::query!(
+            "UPDATE blockchain_events SET status = 'confirmed', confirmed_at = NOW()
+             WHERE status = 'pending' AND block_number <= $1",
+            confirmed_through as i64
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            debug!("Confirmed {} events through block {}", result.rows_affected(), confirmed_through);
+        }
+
+        Ok(())
+    }
+
+    async fn prune_processed_blocks(&self, below: u64) -> Result<(), sqlx::Error> {
+        sqlx::query!("DELETE FROM processed_blocks WHERE block_number < $1", below as i64)
+            .execute(&self.db_pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete events from the fork on and rebuild the state they wrote from the events that remain.
+    ///
+    /// Returns the number of events removed and the chain ids of the raffles they touched.
+    async fn roll_back_from(&self, fork: &ForkPoint) -> Result<(usize, Vec<i64>), sqlx::Error> {
+        let mut tx = self.db_pool.begin().await?;
+
+        let reverted = sqlx::query!(
+            "DELETE FROM blockchain_events WHERE block_number >= $1
+             RETURNING event_type, raffle_id, transaction_hash",
+            fork.first_block as i64
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let purchase_tx_hashes: Vec<String> = reverted
+            .iter()
+            .filter(|e| e.event_type == "box_purchased")
+            .map(|e| e.transaction_hash.clone())
+            .collect();
+        sqlx::query!(
+            "DELETE FROM box_purchases WHERE transaction_hash = ANY($1)",
+            &purchase_tx_hashes
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Redeemed vouchers go back to pending; if the redemption was re-mined the replay confirms it again
+        let voucher_tx_hashes: Vec<String> = reverted
+            .iter()
+            .filter(|e| e.event_type == "voucher_redeemed")
+            .map(|e| e.transaction_hash.clone())
+            .collect();
+        if !voucher_tx_hashes.is_empty() {
+            sqlx::query(
+                "UPDATE box_purchases SET blockchain_tx_hash = NULL \
+                 WHERE id IN (SELECT unnest(box_purchase_ids) FROM external_purchases WHERE transaction_hash = ANY($1))",
+            )
+            .bind(&voucher_tx_hashes)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(
+                "UPDATE external_purchases \
+                 SET status = 'pending', transaction_hash = NULL, confirmed_at = NULL, updated_at = NOW() \
+                 WHERE transaction_hash = ANY($1)",
+            )
+            .bind(&voucher_tx_hashes)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // Failed events from the replaced blocks are stale; the replay dead-letters them again if needed
+        sqlx::query!(
+            "DELETE FROM blockchain_event_dead_letters WHERE block_number >= $1 AND status <> 'resolved'",
+            fork.first_block as i64
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let mut affected_raffles: Vec<i64> = reverted.iter().filter_map(|e| e.raffle_id).collect();
+        affected_raffles.sort_unstable();
+        affected_raffles.dedup();
+
+        for &raffle_id in &affected_raffles {
+            let remaining = sqlx::query_scalar!(
+                "SELECT event_type FROM blockchain_events WHERE raffle_id = $1 ORDER BY block_number, id",
+                raffle_id
+            )
+            .fetch_all(&mut *tx)
+            .await?;
+
+            let boxes_sold = remaining.iter().filter(|t| t.as_str() == "box_purchased").count() as i32;
+            let drawn = remaining.iter().any(|t| t.as_str() == "winner_selected");
+
+            if !drawn {
+                sqlx::query!(
+                    "DELETE FROM raffle_winners WHERE raffle_id = (SELECT id FROM raffles WHERE blockchain_raffle_id = $1)",
+                    raffle_id
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            match status_from_events(&remaining) {
+                Some(status) => {
+                    sqlx::query!(
+                        "UPDATE raffles SET status = $1::raffle_status, boxes_sold = $2,
+                                completed_at = CASE WHEN $3 THEN completed_at ELSE NULL END
+                         WHERE blockchain_raffle_id = $4",
+                        status as &str,
+                        boxes_sold,
+                        drawn,
+                        raffle_id
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                // Its creation was reorged out too; the replay recreates it if it was re-mined
+                None => warn!("Raffle {} has no remaining on-chain events after the reorg", raffle_id),
+            }
+        }
+
+        sqlx::query!(
+            "DELETE FROM processed_blocks WHERE block_number >= $1",
+            fork.first_block as i64
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "INSERT INTO event_processor_state (id, last_processed_block) VALUES (1, $1)
+             ON CONFLICT (id) DO UPDATE SET last_processed_block = $1",
+            fork.first_block.saturating_sub(1) as i64
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "INSERT INTO blockchain_reorgs (fork_block, old_block_hash, new_block_hash, rolled_back_events, affected_raffle_ids)
+             VALUES ($1, $2, $3, $4, $5)",
+            fork.first_block as i64,
+            fork.old_block_hash,
+            fork.new_block_hash,
+            reverted.len() as i32,
+            &affected_raffles
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok((reverted.len(), affected_raffles))
+    }
+
     async fn save_raffle_created_event(&self, event: &RaffleCreatedEvent) -> Result<(), sqlx::Error> {
         sqlx::query!(
             "INSERT INTO blockchain_events (event_type, raffle_id, block_number, transaction_hash, timestamp, data)
              VALUES ('raffle_created', $1, $2, $3, $4, $5)
-             ON CONFLICT (transaction_hash, event_type, raffle_id) DO NOTHING",
+             ON CONFLICT (transaction_hash, event_type, raffle_id, log_index) DO NOTHING",
             event.raffle_id.as_u64() as i64,
             event.block_number as i64,
             format!("{:?}", event.transaction_hash),
@@ -539,6 +1098,10 @@ impl EventProcessor {
         .execute(&self.db_pool)
         .await?;
 
+        if self.config.events_only {
+            return Ok(());
+        }
+
         // Update raffle status in database
         sqlx::query!(
             "UPDATE raffles SET status = 'open', created_at = $1 WHERE blockchain_raffle_id = $2",
@@ -551,20 +1114,27 @@ impl EventProcessor {
         Ok(())
     }
 
-    async fn save_box_purchased_event(&self, event: &BoxPurchaseEvent) -> Result<(), sqlx::Error> {
+    /// A voucher purchase logs one BoxPurchased per box in a single transaction, so the
+    /// log index tells them apart
+    async fn save_box_purchased_event(&self, event: &BoxPurchaseEvent, log_index: u64) -> Result<(), sqlx::Error> {
         sqlx::query!(
-            "INSERT INTO blockchain_events (event_type, raffle_id, block_number, transaction_hash, timestamp, data)
-             VALUES ('box_purchased', $1, $2, $3, $4, $5)
-             ON CONFLICT (transaction_hash, event_type, raffle_id) DO NOTHING",
+            "INSERT INTO blockchain_events (event_type, raffle_id, block_number, transaction_hash, log_index, timestamp, data)
+             VALUES ('box_purchased', $1, $2, $3, $4, $5, $6)
+             ON CONFLICT (transaction_hash, event_type, raffle_id, log_index) DO NOTHING",
             event.raffle_id.as_u64() as i64,
             event.block_number as i64,
             format!("{:?}", event.transaction_hash),
+            log_index as i64,
             chrono::DateTime::from_timestamp(event.timestamp as i64, 0),
             serde_json::to_value(event).unwrap()
         )
         .execute(&self.db_pool)
         .await?;
 
+        if self.config.events_only {
+            return Ok(());
+        }
+
         // Update raffle boxes sold
         sqlx::query!(
             "UPDATE raffles SET boxes_sold = $1 WHERE blockchain_raffle_id = $2",
@@ -595,7 +1165,7 @@ impl EventProcessor {
         sqlx::query!(
             "INSERT INTO blockchain_events (event_type, raffle_id, block_number, transaction_hash, timestamp, data)
              VALUES ('winner_selected', $1, $2, $3, $4, $5)
-             ON CONFLICT (transaction_hash, event_type, raffle_id) DO NOTHING",
+             ON CONFLICT (transaction_hash, event_type, raffle_id, log_index) DO NOTHING",
             event.raffle_id.as_u64() as i64,
             event.block_number as i64,
             format!("{:?}", event.transaction_hash),
@@ -605,6 +1175,10 @@ impl EventProcessor {
         .execute(&self.db_pool)
         .await?;
 
+        if self.config.events_only {
+            return Ok(());
+        }
+
         // Update raffle status to completed
         sqlx::query!(
             "UPDATE raffles SET status = 'completed', completed_at = $1 WHERE blockchain_raffle_id = $2",
@@ -636,7 +1210,7 @@ impl EventProcessor {
         sqlx::query!(
             "INSERT INTO blockchain_events (event_type, raffle_id, block_number, transaction_hash, timestamp, data)
              VALUES ('raffle_cancelled', $1, $2, $3, $4, $5)
-             ON CONFLICT (transaction_hash, event_type, raffle_id) DO NOTHING",
+             ON CONFLICT (transaction_hash, event_type, raffle_id, log_index) DO NOTHING",
             event.raffle_id.as_u64() as i64,
             event.block_number as i64,
             format!("{:?}", event.transaction_hash),
@@ -646,6 +1220,10 @@ impl EventProcessor {
         .execute(&self.db_pool)
         .await?;
 
+        if self.config.events_only {
+            return Ok(());
+        }
+
         // Update raffle status to cancelled
         sqlx::query!(
             "UPDATE raffles SET status = 'cancelled' WHERE blockchain_raffle_id = $1",
@@ -661,7 +1239,7 @@ impl EventProcessor {
         sqlx::query!(
             "INSERT INTO blockchain_events (event_type, raffle_id, block_number, transaction_hash, timestamp, data)
              VALUES ('raffle_full', $1, $2, $3, $4, $5)
-             ON CONFLICT (transaction_hash, event_type, raffle_id) DO NOTHING",
+             ON CONFLICT (transaction_hash, event_type, raffle_id, log_index) DO NOTHING",
             event.raffle_id.as_u64() as i64,
             event.block_number as i64,
             format!("{:?}", event.transaction_hash),
@@ -671,6 +1249,10 @@ impl EventProcessor {
         .execute(&self.db_pool)
         .await?;
 
+        if self.config.events_only {
+            return Ok(());
+        }
+
         // Update raffle status to full
         sqlx::query!(
             "UPDATE raffles SET status = 'full' WHERE blockchain_raffle_id = $1",
@@ -686,7 +1268,7 @@ impl EventProcessor {
         sqlx::query!(
             "INSERT INTO blockchain_events (event_type, raffle_id, block_number, transaction_hash, timestamp, data)
              VALUES ('randomness_requested', $1, $2, $3, $4, $5)
-             ON CONFLICT (transaction_hash, event_type, raffle_id) DO NOTHING",
+             ON CONFLICT (transaction_hash, event_type, raffle_id, log_index) DO NOTHING",
             event.raffle_id.as_u64() as i64,
             event.block_number as i64,
             format!("{:?}", event.transaction_hash),
@@ -696,6 +1278,10 @@ impl EventProcessor {
         .execute(&self.db_pool)
         .await?;
 
+        if self.config.events_only {
+            return Ok(());
+        }
+
         // Update raffle status to random_requested
         sqlx::query!(
             "UPDATE raffles SET status = 'random_requested' WHERE blockchain_raffle_id = $1",
@@ -706,6 +1292,63 @@ impl EventProcessor {
 
         Ok(())
     }
+
+    async fn save_voucher_redeemed_event(&self, event: &VoucherRedeemedEvent) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "INSERT INTO blockchain_events (event_type, raffle_id, block_number, transaction_hash, timestamp, data)
+             VALUES ('voucher_redeemed', $1, $2, $3, $4, $5)
+             ON CONFLICT (transaction_hash, event_type, raffle_id, log_index) DO NOTHING",
+            event.raffle_id.as_u64() as i64,
+            event.block_number as i64,
+            format!("{:?}", event.transaction_hash),
+            chrono::DateTime::from_timestamp(event.timestamp as i64, 0),
+            serde_json::to_value(event).unwrap()
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        if self.config.events_only {
+            return Ok(());
+        }
+
+        let Some(purchase_id) = purchase_uuid(event.purchase_id.as_fixed_bytes()) else {
+            warn!("Voucher {:?} redeemed by {:?} was not issued by this platform", event.purchase_id, event.buyer);
+            return Ok(());
+        };
+
+        // A voucher names the wallet it was issued for, so the purchase's user is the buyer
+        let mut tx = self.db_pool.begin().await?;
+        let confirmed = sqlx::query_as::<_, (uuid::Uuid, Vec<uuid::Uuid>)>(
+            "UPDATE external_purchases \
+             SET status = 'confirmed', transaction_hash = $2, confirmed_at = $3, updated_at = NOW() \
+             WHERE id = $1 AND lower(address) = lower($4) AND status = 'pending' \
+             RETURNING user_id, box_purchase_ids",
+        )
+        .bind(purchase_id)
+        .bind(format!("{:?}", event.transaction_hash))
+        .bind(chrono::DateTime::from_timestamp(event.timestamp as i64, 0))
+        .bind(format!("{:?}", event.buyer))
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        match confirmed {
+            Some((user_id, box_purchase_ids)) => {
+                sqlx::query("UPDATE box_purchases SET blockchain_tx_hash = $1 WHERE id = ANY($2)")
+                    .bind(format!("{:?}", event.transaction_hash))
+                    .bind(&box_purchase_ids)
+                    .execute(&mut *tx)
+                    .await?;
+                info!(
+                    "Attributed {} boxes bought by {:?} to user {} (purchase {})",
+                    event.boxes, event.buyer, user_id, purchase_id
+                );
+            }
+            None => warn!("No pending external purchase {} for wallet {:?}", purchase_id, event.buyer),
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -713,6 +1356,56 @@ struct BlockInfo {
     number: u64,
     timestamp: u64,
     hash: H256,
+    parent_hash: H256,
+}
+
+/// First block replaced by a reorg
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForkPoint {
+    /// Events from this block on are stale; replay starts here
+    pub first_block: u64,
+    /// Hashes of the oldest replaced block we had stored, before and after the reorg
+    pub old_block_hash: String,
+    pub new_block_hash: String,
+}
+
+/// Where a reorg starts, given stored and canonical hashes of the same blocks, newest first.
+///
+/// `canonical` may be shorter than `stored`; it ends at the first block that still matches.
+/// Returns None when the newest stored block is still canonical.
+pub fn find_fork_point(stored: &[(u64, String)], canonical: &[(u64, String)]) -> Option<ForkPoint> {
+    let mut replaced: Option<(u64, &String, &String)> = None;
+
+    for ((number, stored_hash), (_, canonical_hash)) in stored.iter().zip(canonical) {
+        if stored_hash == canonical_hash {
+            return replaced.map(|(_, old, new)| ForkPoint {
+                first_block: number + 1,
+                old_block_hash: old.clone(),
+                new_block_hash: new.clone(),
+            });
+        }
+        replaced = Some((*number, stored_hash, canonical_hash));
+    }
+
+    // No stored block is canonical any more; roll back everything we still have hashes for
+    replaced.map(|(number, old, new)| ForkPoint {
+        first_block: number,
+        old_block_hash: old.clone(),
+        new_block_hash: new.clone(),
+    })
+}
+
+/// Raffle status implied by a raffle's remaining lifecycle events, oldest first
+pub fn status_from_events(event_types: &[String]) -> Option<&'static str> {
+    event_types.iter().rev().find_map(|event_type| match event_type.as_str() {
+        "winner_selected" => Some("completed"),
+        "raffle_cancelled" => Some("cancelled"),
+        "randomness_requested" => Some("drawing"),
+        "raffle_full" => Some("full"),
+        "raffle_created" => Some("open"),
+        _ => None,
+    })
+
 }
 
 // Event filter types for the contract
@@ -768,6 +1461,18 @@ pub struct RaffleFullFilter {
     pub total_boxes: U256,
 }
 
+#[derive(Debug, Clone, EthEvent)]
+#[ethevent(name = "VoucherRedeemed")]
+pub struct VoucherRedeemedFilter {
+    #[ethevent(indexed)]
+    pub purchase_id: [u8; 32],
+    #[ethevent(indexed)]
+    pub raffle_id: U256,
+    #[ethevent(indexed)]
+    pub buyer: Address,
+    pub boxes: U256,
+}
+
 #[derive(Debug, Clone, EthEvent)]
 #[ethevent(name = "RandomnessRequested")]
 pub struct RandomnessRequestedFilter {
@@ -1,19 +1,103 @@
 use crate::blockchain::types::*;
 use crate::blockchain::client::BlockchainClient;
+use chrono::NaiveDate;
 use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
+/// Priority fee percentiles requested from `eth_feeHistory`, one per strategy
+const REWARD_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+/// Nodes reject a replacement unless both fees rise by at least 10%; bump by 12.5% to be safe
+const REPLACEMENT_BUMP_BPS: u64 = 11_250;
+const BPS: u64 = 10_000;
+
 /// Gas manager handles gas price optimization and estimation
 #[derive(Clone)]
 pub struct GasManager {
     client: Arc<BlockchainClient>,
     gas_history: Arc<RwLock<GasHistory>>,
+    fee_window: Arc<RwLock<FeeWindow>>,
+    spend: Arc<RwLock<GasSpend>>,
     config: GasConfig,
 }
 
+/// How soon a transaction should be mined
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GasStrategy {
+    /// Tips at the 10th percentile and allows little base fee growth
+    Slow,
+    /// Tips at the median and survives several full blocks of base fee growth
+    #[default]
+    Standard,
+    /// Tips at the 90th percentile with the most base fee headroom
+    Fast,
+}
+
+impl GasStrategy {
+    /// Position in `REWARD_PERCENTILES`
+    fn reward_index(&self) -> usize {
+        match self {
+            GasStrategy::Slow => 0,
+            GasStrategy::Standard => 1,
+            GasStrategy::Fast => 2,
+        }
+    }
+
+    /// Max fee as a multiple of the next base fee, in basis points; the base fee
+    /// can rise 12.5% per full block
+    fn base_fee_headroom_bps(&self) -> u64 {
+        match self {
+            GasStrategy::Slow => 12_500,
+            GasStrategy::Standard => 20_000,
+            GasStrategy::Fast => 25_000,
+        }
+    }
+}
+
+/// EIP-1559 fees for one transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeQuote {
+    pub strategy: GasStrategy,
+    /// Expected base fee of the next block
+    pub base_fee: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+/// Recent blocks' base fees and tips, from `eth_feeHistory`
+#[derive(Debug, Clone, Default)]
+struct FeeWindow {
+    samples: VecDeque<FeeSample>,
+    next_base_fee: Option<U256>,
+    last_update: u64,
+}
+
+#[derive(Debug, Clone)]
+struct FeeSample {
+    block_number: u64,
+    /// Priority fees paid at each of `REWARD_PERCENTILES`
+    rewards: [U256; 3],
+}
+
+/// Worst-case cost of transactions sent today, in wei
+#[derive(Debug, Clone)]
+struct GasSpend {
+    day: NaiveDate,
+    spent: U256,
+}
+
+/// Gas spent against the daily cap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GasSpendSummary {
+    pub day: NaiveDate,
+    pub spent: U256,
+    pub daily_cap: Option<U256>,
+}
+
 #[derive(Debug, Clone)]
 struct GasHistory {
     prices: VecDeque<GasPricePoint>,
@@ -29,7 +113,8 @@ struct GasPricePoint {
     block_number: u64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GasConfig {
     pub history_size: usize,
     pub update_interval: u64, // seconds
@@ -38,6 +123,15 @@ pub struct GasConfig {
     pub min_gas_price: U256,
     pub priority_fee_multiplier: f64,
     pub max_priority_fee: U256,
+    pub min_priority_fee: U256,
+    /// Blocks of fee history the oracle looks back over
+    pub fee_history_blocks: u64,
+    /// Strategy for transactions that don't ask for one
+    pub default_strategy: GasStrategy,
+    /// Most one transaction may cost at its max fee, in wei
+    pub max_tx_spend: Option<U256>,
+    /// Most all transactions sent in a UTC day may cost at their max fee, in wei
+    pub daily_spend_cap: Option<U256>,
 }
 
 impl Default for GasConfig {
@@ -50,6 +144,11 @@ impl Default for GasConfig {
             min_gas_price: U256::from(1_000_000_000u64),   // 1 gwei
             priority_fee_multiplier: 1.2,
             max_priority_fee: U256::from(5_000_000_000u64), // 5 gwei
+            min_priority_fee: U256::from(100_000_000u64),   // 0.1 gwei
+            fee_history_blocks: 20,
+            default_strategy: GasStrategy::Standard,
+            max_tx_spend: None,
+            daily_spend_cap: None,
         }
     }
 }
@@ -67,6 +166,11 @@ impl GasManager {
         let manager = Self {
             client,
             gas_history: Arc::new(RwLock::new(gas_history)),
+            fee_window: Arc::new(RwLock::new(FeeWindow::default())),
+            spend: Arc::new(RwLock::new(GasSpend {
+                day: chrono::Utc::now().date_naive(),
+                spent: U256::zero(),
+            })),
             config,
         };
 
@@ -76,6 +180,110 @@ impl GasManager {
         Ok(manager)
     }
 
+    /// Strategy used when a transaction doesn't choose one
+    pub fn default_strategy(&self) -> GasStrategy {
+        self.config.default_strategy
+    }
+
+    /// Quote EIP-1559 fees for a strategy from recent base fees and tips
+    ///
+    /// Falls back to the flat estimate on chains without fee history.
+    pub async fn quote_fees(&self, strategy: GasStrategy) -> BlockchainResult<FeeQuote> {
+        if let Err(e) = self.update_fee_window_if_needed().await {
+            warn!("Failed to read fee history, using flat gas estimate: {}", e);
+        }
+
+        let window = self.fee_window.read().await;
+        if let Some(next_base_fee) = window.next_base_fee {
+            let tips: Vec<U256> = window
+                .samples
+                .iter()
+                .map(|sample| sample.rewards[strategy.reward_index()])
+                .collect();
+            return Ok(fee_quote(strategy, next_base_fee, &tips, &self.config));
+        }
+        drop(window);
+
+        let (max_fee_per_gas, max_priority_fee_per_gas) = self.get_eip1559_gas_params().await?;
+        Ok(FeeQuote {
+            strategy,
+            base_fee: max_fee_per_gas.saturating_sub(max_priority_fee_per_gas) / U256::from(2),
+            max_fee_per_gas: std::cmp::min(max_fee_per_gas, self.config.max_gas_price),
+            max_priority_fee_per_gas,
+        })
+    }
+
+    /// Fees for a replacement of a stuck transaction, or None when the bump needed
+    /// would go past the max gas price
+    pub fn replacement_fees(
+        &self,
+        previous_max_fee: U256,
+        previous_priority_fee: U256,
+        quote: &FeeQuote,
+    ) -> Option<(U256, U256)> {
+        replacement_fees(previous_max_fee, previous_priority_fee, quote, self.config.max_gas_price)
+    }
+
+    /// Count a transaction's worst-case cost against the spend caps
+    ///
+    /// `already_reserved` is what an earlier broadcast of the same transaction reserved;
+    /// only the increase counts against the daily cap. Returns the new reservation.
+    pub async fn reserve_spend(
+        &self,
+        gas_limit: U256,
+        max_fee_per_gas: U256,
+        already_reserved: U256,
+    ) -> BlockchainResult<U256> {
+        let worst_case = gas_limit.saturating_mul(max_fee_per_gas);
+
+        if let Some(cap) = self.config.max_tx_spend {
+            if worst_case > cap {
+                return Err(BlockchainError::GasSpendCap(format!(
+                    "transaction could cost {} wei, over the {} wei per-transaction cap",
+                    worst_case, cap
+                )));
+            }
+        }
+
+        let mut spend = self.spend.write().await;
+        roll_spend_day(&mut spend, chrono::Utc::now().date_naive());
+
+        let increase = worst_case.saturating_sub(already_reserved);
+        if let Some(cap) = self.config.daily_spend_cap {
+            let total = spend.spent.saturating_add(increase);
+            if total > cap {
+                return Err(BlockchainError::GasSpendCap(format!(
+                    "{} wei already committed today, {} more would pass the {} wei daily cap",
+                    spend.spent, increase, cap
+                )));
+            }
+        }
+
+        spend.spent = spend.spent.saturating_add(increase);
+        Ok(std::cmp::max(worst_case, already_reserved))
+    }
+
+    /// Replace a reservation with what the transaction actually cost; zero when it never landed
+    pub async fn settle_spend(&self, reserved: U256, actual: U256) {
+        let mut spend = self.spend.write().await;
+        if !roll_spend_day(&mut spend, chrono::Utc::now().date_naive()) {
+            spend.spent = spend.spent.saturating_sub(reserved);
+        }
+        spend.spent = spend.spent.saturating_add(actual);
+    }
+
+    /// Gas committed today against the daily cap
+    pub async fn get_spend_summary(&self) -> GasSpendSummary {
+        let mut spend = self.spend.write().await;
+        roll_spend_day(&mut spend, chrono::Utc::now().date_naive());
+
+        GasSpendSummary {
+            day: spend.day,
+            spent: spend.spent,
+            daily_cap: self.config.daily_spend_cap,
+        }
+    }
+
     /// Get current optimal gas price
     pub async fn get_optimal_gas_price(&self) -> BlockchainResult<U256> {
         self.update_gas_history_if_needed().await?;
@@ -216,6 +424,62 @@ impl GasManager {
         Ok(())
     }
 
+    async fn update_fee_window_if_needed(&self) -> BlockchainResult<()> {
+        let now = chrono::Utc::now().timestamp() as u64;
+        let should_update = {
+            let window = self.fee_window.read().await;
+            now.saturating_sub(window.last_update) > self.config.update_interval
+        };
+
+        if should_update {
+            self.update_fee_window().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Refresh base fees and tips over the last `fee_history_blocks` blocks
+    async fn update_fee_window(&self) -> BlockchainResult<()> {
+        let history = self
+            .client
+            .provider()
+            .fee_history(self.config.fee_history_blocks, BlockNumber::Latest, &REWARD_PERCENTILES)
+            .await?;
+
+        let oldest_block = history.oldest_block.as_u64();
+        let samples: VecDeque<FeeSample> = history
+            .base_fee_per_gas
+            .iter()
+            .zip(history.reward.iter())
+            .enumerate()
+            .map(|(i, (_, rewards))| FeeSample {
+                block_number: oldest_block + i as u64,
+                rewards: [
+                    rewards.first().copied().unwrap_or_default(),
+                    rewards.get(1).copied().unwrap_or_default(),
+                    rewards.get(2).copied().unwrap_or_default(),
+                ],
+            })
+            .collect();
+
+        // The last base fee returned is for the block after the newest one
+        let next_base_fee = history.base_fee_per_gas.last().copied().filter(|fee| !fee.is_zero());
+
+        let mut window = self.fee_window.write().await;
+        window.samples = samples;
+        window.next_base_fee = next_base_fee;
+        window.last_update = chrono::Utc::now().timestamp() as u64;
+
+        debug!(
+            "Updated fee window: {} blocks up to {:?}, next base fee = {:?}",
+            window.samples.len(),
+            window.samples.back().map(|sample| sample.block_number),
+            next_base_fee
+        );
+
+        Ok(())
+    }
+
     /// Get current EIP-1559 information
     async fn get_current_eip1559_info(&self) -> BlockchainResult<(Option<U256>, Option<U256>)> {
         // Try to get the latest block with full transaction details
@@ -384,6 +648,61 @@ impl GasManager {
     }
 }
 
+/// Tip the median of recent tips at the strategy's percentile, and leave the strategy's
+/// headroom over the next base fee, within the configured bounds
+fn fee_quote(strategy: GasStrategy, next_base_fee: U256, recent_tips: &[U256], config: &GasConfig) -> FeeQuote {
+    // Empty blocks report a zero tip, which says nothing about what gets included
+    let mut tips: Vec<U256> = recent_tips.iter().copied().filter(|tip| !tip.is_zero()).collect();
+    tips.sort();
+    let tip = tips.get(tips.len() / 2).copied().unwrap_or(config.min_priority_fee);
+    let max_priority_fee_per_gas = tip.clamp(config.min_priority_fee, config.max_priority_fee);
+
+    let headroom = next_base_fee.saturating_mul(U256::from(strategy.base_fee_headroom_bps())) / U256::from(BPS);
+    let max_fee_per_gas = std::cmp::min(
+        headroom.saturating_add(max_priority_fee_per_gas),
+        config.max_gas_price,
+    );
+
+    FeeQuote {
+        strategy,
+        base_fee: next_base_fee,
+        max_fee_per_gas,
+        max_priority_fee_per_gas: std::cmp::min(max_priority_fee_per_gas, max_fee_per_gas),
+    }
+}
+
+/// The higher of the current quote and a large enough bump over the fees already sent
+fn replacement_fees(
+    previous_max_fee: U256,
+    previous_priority_fee: U256,
+    quote: &FeeQuote,
+    max_gas_price: U256,
+) -> Option<(U256, U256)> {
+    let bump = |fee: U256| (fee.saturating_mul(U256::from(REPLACEMENT_BUMP_BPS)) + U256::from(BPS - 1)) / U256::from(BPS);
+
+    let priority_fee = std::cmp::max(quote.max_priority_fee_per_gas, bump(previous_priority_fee));
+    let max_fee = std::cmp::max(
+        std::cmp::max(quote.max_fee_per_gas, bump(previous_max_fee)),
+        priority_fee,
+    );
+
+    if max_fee > max_gas_price {
+        return None;
+    }
+
+    Some((max_fee, priority_fee))
+}
+
+/// Start a new day's count when the date changes; true when it did
+fn roll_spend_day(spend: &mut GasSpend, today: NaiveDate) -> bool {
+    if spend.day == today {
+        return false;
+    }
+    spend.day = today;
+    spend.spent = U256::zero();
+    true
+}
+
 #[derive(Debug, Clone)]
 pub struct GasPriorityLevels {
     pub slow: U256,
@@ -430,4 +749,76 @@ pub enum TransactionUrgency {
     Standard, // Normal priority
     High,     // Needs faster confirmation
     Urgent,   // Needs immediate confirmation
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gwei(amount: u64) -> U256 {
+        U256::from(amount) * U256::from(1_000_000_000u64)
+    }
+
+    #[test]
+    fn test_fee_quote_by_strategy() {
+        let config = GasConfig::default();
+        let tips = vec![gwei(1), U256::zero(), gwei(2), gwei(3)];
+
+        let standard = fee_quote(GasStrategy::Standard, gwei(20), &tips, &config);
+        assert_eq!(standard.max_priority_fee_per_gas, gwei(2));
+        assert_eq!(standard.max_fee_per_gas, gwei(42));
+
+        let slow = fee_quote(GasStrategy::Slow, gwei(20), &tips, &config);
+        assert_eq!(slow.max_fee_per_gas, gwei(27));
+
+        let fast = fee_quote(GasStrategy::Fast, gwei(20), &tips, &config);
+        assert_eq!(fast.max_fee_per_gas, gwei(52));
+    }
+
+    #[test]
+    fn test_fee_quote_bounds() {
+        let config = GasConfig::default();
+
+        // No tips seen yet: fall back to the minimum tip
+        let quiet = fee_quote(GasStrategy::Standard, gwei(10), &[], &config);
+        assert_eq!(quiet.max_priority_fee_per_gas, config.min_priority_fee);
+
+        // Tips are capped, and so is the max fee
+        let congested = fee_quote(GasStrategy::Fast, gwei(90), &[gwei(50)], &config);
+        assert_eq!(congested.max_priority_fee_per_gas, config.max_priority_fee);
+        assert_eq!(congested.max_fee_per_gas, config.max_gas_price);
+    }
+
+    #[test]
+    fn test_replacement_fees_bump_at_least_ten_percent() {
+        let quote = FeeQuote {
+            strategy: GasStrategy::Standard,
+            base_fee: gwei(10),
+            max_fee_per_gas: gwei(21),
+            max_priority_fee_per_gas: gwei(1),
+        };
+
+        // The quote hasn't moved: bump the fees already sent
+        let (max_fee, priority_fee) = replacement_fees(gwei(40), gwei(2), &quote, gwei(100)).unwrap();
+        assert_eq!(max_fee, gwei(45));
+        assert_eq!(priority_fee, U256::from(2_250_000_000u64));
+
+        // The market moved past the bump: follow the quote
+        let (max_fee, _) = replacement_fees(gwei(10), gwei(1), &quote, gwei(100)).unwrap();
+        assert_eq!(max_fee, gwei(21));
+
+        assert!(replacement_fees(gwei(95), gwei(2), &quote, gwei(100)).is_none());
+    }
+
+    #[test]
+    fn test_roll_spend_day() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 15).unwrap();
+        let mut spend = GasSpend { day: today, spent: gwei(5) };
+
+        assert!(!roll_spend_day(&mut spend, today));
+        assert_eq!(spend.spent, gwei(5));
+
+        assert!(roll_spend_day(&mut spend, today.succ_opt().unwrap()));
+        assert!(spend.spent.is_zero());
+    }
+}
@@ -1,7 +1,11 @@
+pub mod backfill;
 pub mod client;
 pub mod contract;
 pub mod events;
 pub mod gas;
+pub mod nonce;
+pub mod remote_signer;
+pub mod token;
 pub mod transaction;
 pub mod types;
 pub mod wallet;
@@ -13,6 +17,8 @@ pub use client::BlockchainClient;
 pub use contract::RaffleContractClient;
 pub use events::EventProcessor;
 pub use gas::GasManager;
+pub use nonce::{NonceConfig, NonceManager};
+pub use token::{TokenClient, TokenInfo};
 pub use transaction::TransactionManager;
 pub use types::*;
-pub use wallet::WalletManager;
\ No newline at end of file
+pub use wallet::{PlatformSigner, SignerConfig, WalletManager};
\ No newline at end of file
@@ -0,0 +1,432 @@
+use crate::blockchain::client::BlockchainClient;
+use crate::blockchain::types::*;
+use ethers::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NonceConfig {
+    /// Most transactions a wallet may have broadcast and unmined; further submissions queue
+    pub max_in_flight: usize,
+    /// How long a submission waits in the queue before it is rejected
+    pub queue_timeout: u64, // seconds
+    /// How often nonces are compared with the chain and gaps are looked for
+    pub sync_interval: u64, // seconds
+    /// A released nonce below one in flight is filled after this long if nothing reused it
+    pub gap_fill_after: u64, // seconds
+}
+
+impl Default for NonceConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 16,
+            queue_timeout: 120,
+            sync_interval: 30,
+            gap_fill_after: 60,
+        }
+    }
+}
+
+/// Nonces of one signing wallet as last seen
+#[derive(Debug, Clone, Serialize)]
+pub struct WalletNonceSnapshot {
+    pub address: Address,
+    /// Transactions the chain has mined from the wallet at the last sync
+    pub chain_nonce: u64,
+    /// Next nonce never handed out
+    pub next_nonce: u64,
+    /// Nonces handed out and not yet mined, with the transaction holding each
+    pub in_flight: Vec<InFlightNonce>,
+    /// Nonces below the highest one in flight that nothing will mine; later nonces wait on them
+    pub gaps: Vec<u64>,
+    pub last_synced_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InFlightNonce {
+    pub nonce: u64,
+    pub tx_id: Uuid,
+}
+
+/// Nonce manager hands out nonces per signing wallet so concurrent submissions never share one.
+///
+/// A nonce stays taken from the moment it is handed out until the chain has mined it, even when
+/// the transaction's monitoring gives up: a broadcast that looked dropped may still land. Nonces
+/// that were never broadcast are released and reused first. The manager re-syncs with the chain
+/// periodically; the transaction manager fills the gaps it reports.
+#[derive(Clone)]
+pub struct NonceManager {
+    client: Arc<BlockchainClient>,
+    config: NonceConfig,
+    wallets: Arc<Mutex<HashMap<Address, WalletNonces>>>,
+    slot_freed: Arc<Notify>,
+}
+
+impl NonceManager {
+    pub fn new(client: Arc<BlockchainClient>, config: NonceConfig) -> Self {
+        Self {
+            client,
+            config,
+            wallets: Arc::new(Mutex::new(HashMap::new())),
+            slot_freed: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn with_config(mut self, config: NonceConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &NonceConfig {
+        &self.config
+    }
+
+    /// Hand out the wallet's next nonce to a transaction, waiting while too many are in flight
+    pub async fn allocate(&self, address: Address, tx_id: Uuid) -> BlockchainResult<U256> {
+        let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(self.config.queue_timeout);
+
+        loop {
+            // Registered before checking, so a slot freed in between isn't missed
+            let slot_freed = self.slot_freed.notified();
+            tokio::pin!(slot_freed);
+            slot_freed.as_mut().enable();
+
+            {
+                let mut wallets = self.wallets.lock().await;
+                if !wallets.contains_key(&address) {
+                    let chain_nonce = self.client.get_nonce(Some(address)).await?;
+                    wallets.insert(address, WalletNonces::new(chain_nonce));
+                }
+                let wallet = wallets.get_mut(&address).expect("wallet nonces were just inserted");
+
+                if wallet.in_flight.len() < self.config.max_in_flight {
+                    let nonce = wallet.allocate(tx_id);
+                    debug!("Nonce {} of {:?} handed to transaction {}", nonce, address, tx_id);
+                    return Ok(nonce);
+                }
+            }
+
+            if tokio::time::timeout_at(deadline, slot_freed).await.is_err() {
+                return Err(BlockchainError::Transaction(format!(
+                    "{} transactions from {:?} are already in flight",
+                    self.config.max_in_flight, address
+                )));
+            }
+        }
+    }
+
+    /// Take a specific nonce for a transaction that fills a gap
+    pub async fn claim(&self, address: Address, nonce: U256, tx_id: Uuid) {
+        let mut wallets = self.wallets.lock().await;
+        if let Some(wallet) = wallets.get_mut(&address) {
+            wallet.claim(nonce, tx_id);
+        }
+    }
+
+    /// Give back a nonce whose transaction was never broadcast
+    pub async fn release(&self, address: Address, nonce: U256) {
+        {
+            let mut wallets = self.wallets.lock().await;
+            if let Some(wallet) = wallets.get_mut(&address) {
+                wallet.release(nonce, now());
+            }
+        }
+        self.slot_freed.notify_waiters();
+    }
+
+    /// The chain mined a transaction with this nonce, successfully or not
+    pub async fn complete(&self, address: Address, nonce: U256) {
+        {
+            let mut wallets = self.wallets.lock().await;
+            if let Some(wallet) = wallets.get_mut(&address) {
+                wallet.complete(nonce);
+            }
+        }
+        self.slot_freed.notify_waiters();
+    }
+
+    /// Read the wallet's mined transaction count and forget every nonce below it
+    pub async fn sync(&self, address: Address) -> BlockchainResult<WalletNonceSnapshot> {
+        let chain_nonce = self.client.get_nonce(Some(address)).await?;
+
+        let snapshot = {
+            let mut wallets = self.wallets.lock().await;
+            let wallet = wallets.entry(address).or_insert_with(|| WalletNonces::new(chain_nonce));
+            if chain_nonce > wallet.next {
+                info!(
+                    "{:?} has sent transactions this manager didn't; nonces continue from {}",
+                    address, chain_nonce
+                );
+            }
+            wallet.sync(chain_nonce, now());
+            wallet.snapshot(address)
+        };
+        self.slot_freed.notify_waiters();
+
+        Ok(snapshot)
+    }
+
+    /// Nonces of every wallet this manager has handed out nonces for
+    pub async fn snapshots(&self) -> Vec<WalletNonceSnapshot> {
+        let wallets = self.wallets.lock().await;
+        let mut snapshots: Vec<_> = wallets.iter().map(|(address, wallet)| wallet.snapshot(*address)).collect();
+        snapshots.sort_by_key(|s| s.address);
+        snapshots
+    }
+
+    /// Released nonces that have sat below an in-flight one for `gap_fill_after`
+    pub async fn gaps_to_fill(&self, address: Address) -> Vec<U256> {
+        let wallets = self.wallets.lock().await;
+        match wallets.get(&address) {
+            Some(wallet) => wallet.gaps_older_than(now().saturating_sub(self.config.gap_fill_after)),
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether the transaction still holds an unmined nonce
+    pub async fn holds(&self, address: Address, tx_id: Uuid) -> bool {
+        let wallets = self.wallets.lock().await;
+        wallets
+            .get(&address)
+            .is_some_and(|wallet| wallet.in_flight.values().any(|holder| *holder == tx_id))
+    }
+
+    /// Nonces held by transactions the caller considers stuck, lowest first
+    pub async fn held_by(&self, address: Address, tx_ids: &[Uuid]) -> Vec<(U256, Uuid)> {
+        let wallets = self.wallets.lock().await;
+        let Some(wallet) = wallets.get(&address) else {
+            return Vec::new();
+        };
+
+        wallet
+            .in_flight
+            .iter()
+            .filter(|(_, tx_id)| tx_ids.contains(tx_id))
+            .map(|(nonce, tx_id)| (*nonce, *tx_id))
+            .collect()
+    }
+}
+
+/// Nonce bookkeeping for one wallet
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WalletNonces {
+    /// Next nonce never handed out
+    next: U256,
+    /// Handed out and not yet mined, with the transaction holding each
+    in_flight: BTreeMap<U256, Uuid>,
+    /// Handed out but never broadcast, with when; reused lowest first
+    released: BTreeMap<U256, u64>,
+    /// The wallet's mined transaction count at the last sync
+    chain_nonce: U256,
+    last_synced_at: Option<u64>,
+}
+
+impl WalletNonces {
+    pub(crate) fn new(chain_nonce: U256) -> Self {
+        Self {
+            next: chain_nonce,
+            chain_nonce,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn allocate(&mut self, tx_id: Uuid) -> U256 {
+        let nonce = match self.released.keys().next().copied() {
+            Some(nonce) => {
+                self.released.remove(&nonce);
+                nonce
+            }
+            None => {
+                let nonce = self.next;
+                self.next += U256::one();
+                nonce
+            }
+        };
+        self.in_flight.insert(nonce, tx_id);
+        nonce
+    }
+
+    pub(crate) fn claim(&mut self, nonce: U256, tx_id: Uuid) {
+        self.released.remove(&nonce);
+        self.in_flight.insert(nonce, tx_id);
+        if nonce >= self.next {
+            self.next = nonce + U256::one();
+        }
+    }
+
+    pub(crate) fn release(&mut self, nonce: U256, at: u64) {
+        if self.in_flight.remove(&nonce).is_none() || nonce < self.chain_nonce {
+            return;
+        }
+        self.released.insert(nonce, at);
+        self.trim_released();
+    }
+
+    pub(crate) fn complete(&mut self, nonce: U256) {
+        self.in_flight.remove(&nonce);
+        self.released.remove(&nonce);
+    }
+
+    pub(crate) fn sync(&mut self, chain_nonce: U256, at: u64) {
+        self.chain_nonce = chain_nonce;
+        self.in_flight.retain(|nonce, _| *nonce >= chain_nonce);
+        self.released.retain(|nonce, _| *nonce >= chain_nonce);
+        if self.next < chain_nonce {
+            self.next = chain_nonce;
+        }
+        self.trim_released();
+        self.last_synced_at = Some(at);
+    }
+
+    /// Released nonces below the highest in flight; the chain won't mine anything above one
+    pub(crate) fn gaps(&self) -> Vec<U256> {
+        let Some(highest) = self.in_flight.keys().next_back() else {
+            return Vec::new();
+        };
+        self.released.range(..*highest).map(|(nonce, _)| *nonce).collect()
+    }
+
+    pub(crate) fn gaps_older_than(&self, cutoff: u64) -> Vec<U256> {
+        self.gaps()
+            .into_iter()
+            .filter(|nonce| self.released.get(nonce).is_some_and(|at| *at <= cutoff))
+            .collect()
+    }
+
+    fn snapshot(&self, address: Address) -> WalletNonceSnapshot {
+        WalletNonceSnapshot {
+            address,
+            chain_nonce: self.chain_nonce.low_u64(),
+            next_nonce: self.next.low_u64(),
+            in_flight: self
+                .in_flight
+                .iter()
+                .map(|(nonce, tx_id)| InFlightNonce { nonce: nonce.low_u64(), tx_id: *tx_id })
+                .collect(),
+            gaps: self.gaps().iter().map(U256::low_u64).collect(),
+            last_synced_at: self.last_synced_at,
+        }
+    }
+
+    /// Released nonces at the top are simply handed out again next
+    fn trim_released(&mut self) {
+        while self.next > self.chain_nonce {
+            let top = self.next - U256::one();
+            if self.released.remove(&top).is_none() {
+                break;
+            }
+            self.next = top;
+        }
+    }
+}
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nonces(values: &[u64]) -> Vec<U256> {
+        values.iter().map(|value| U256::from(*value)).collect()
+    }
+
+    #[test]
+    fn test_allocate_hands_out_each_nonce_once() {
+        let mut wallet = WalletNonces::new(U256::from(5));
+        let first = wallet.allocate(Uuid::new_v4());
+        let second = wallet.allocate(Uuid::new_v4());
+
+        assert_eq!(first, U256::from(5));
+        assert_eq!(second, U256::from(6));
+        assert_eq!(wallet.in_flight.len(), 2);
+    }
+
+    #[test]
+    fn test_released_nonce_is_reused_first() {
+        let mut wallet = WalletNonces::new(U256::zero());
+        wallet.allocate(Uuid::new_v4());
+        wallet.allocate(Uuid::new_v4());
+        wallet.allocate(Uuid::new_v4());
+
+        wallet.release(U256::one(), 100);
+        assert_eq!(wallet.gaps(), nonces(&[1]));
+        assert_eq!(wallet.allocate(Uuid::new_v4()), U256::one());
+        assert!(wallet.gaps().is_empty());
+        assert_eq!(wallet.allocate(Uuid::new_v4()), U256::from(3));
+    }
+
+    #[test]
+    fn test_releasing_the_top_nonce_leaves_no_gap() {
+        let mut wallet = WalletNonces::new(U256::zero());
+        wallet.allocate(Uuid::new_v4());
+        wallet.allocate(Uuid::new_v4());
+
+        wallet.release(U256::one(), 100);
+        assert!(wallet.gaps().is_empty());
+        assert!(wallet.released.is_empty());
+        assert_eq!(wallet.next, U256::one());
+    }
+
+    #[test]
+    fn test_completed_nonce_is_never_reused() {
+        let mut wallet = WalletNonces::new(U256::zero());
+        wallet.allocate(Uuid::new_v4());
+        wallet.complete(U256::zero());
+        // A late release of a mined nonce must not hand it out again
+        wallet.release(U256::zero(), 100);
+
+        assert_eq!(wallet.allocate(Uuid::new_v4()), U256::one());
+    }
+
+    #[test]
+    fn test_sync_forgets_mined_nonces_and_skips_past_outside_sends() {
+        let mut wallet = WalletNonces::new(U256::zero());
+        wallet.allocate(Uuid::new_v4());
+        wallet.allocate(Uuid::new_v4());
+        let third = Uuid::new_v4();
+        wallet.allocate(third);
+
+        wallet.sync(U256::from(2), 100);
+        assert_eq!(wallet.in_flight.len(), 1);
+        assert_eq!(wallet.in_flight.get(&U256::from(2)), Some(&third));
+
+        wallet.sync(U256::from(7), 200);
+        assert!(wallet.in_flight.is_empty());
+        assert_eq!(wallet.allocate(Uuid::new_v4()), U256::from(7));
+        assert_eq!(wallet.last_synced_at, Some(200));
+    }
+
+    #[test]
+    fn test_only_old_gaps_are_filled() {
+        let mut wallet = WalletNonces::new(U256::zero());
+        for _ in 0..4 {
+            wallet.allocate(Uuid::new_v4());
+        }
+        wallet.release(U256::zero(), 100);
+        wallet.release(U256::from(2), 160);
+
+        assert_eq!(wallet.gaps(), nonces(&[0, 2]));
+        assert_eq!(wallet.gaps_older_than(120), nonces(&[0]));
+        assert_eq!(wallet.gaps_older_than(160), nonces(&[0, 2]));
+    }
+
+    #[test]
+    fn test_claimed_nonce_leaves_the_gap_list() {
+        let mut wallet = WalletNonces::new(U256::zero());
+        wallet.allocate(Uuid::new_v4());
+        wallet.allocate(Uuid::new_v4());
+        wallet.release(U256::zero(), 100);
+
+        let filler = Uuid::new_v4();
+        wallet.claim(U256::zero(), filler);
+        assert!(wallet.gaps().is_empty());
+        assert_eq!(wallet.in_flight.get(&U256::zero()), Some(&filler));
+    }
+}
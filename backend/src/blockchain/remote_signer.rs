@@ -0,0 +1,558 @@
+use crate::blockchain::wallet::SignerError;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ethers::core::k256::ecdsa::{RecoveryId, Signature as EcdsaSignature, VerifyingKey};
+use ethers::prelude::*;
+use ethers::signers::to_eip155_v;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// Object identifier of the secp256k1 curve as it appears in a SubjectPublicKeyInfo
+const SECP256K1_OID: [u8; 7] = [0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x0a];
+
+const GCP_KMS_API: &str = "https://cloudkms.googleapis.com/v1";
+/// Service account tokens on GCE and GKE with workload identity
+const GCP_METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+/// A key that never leaves its service and signs digests on request
+#[async_trait]
+pub trait DigestSigner: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Public half of the key, read once when the signer connects
+    async fn public_key(&self) -> Result<VerifyingKey, SignerError>;
+
+    /// DER encoded ECDSA signature over a 32-byte digest
+    async fn sign_digest(&self, digest: [u8; 32]) -> Result<Vec<u8>, SignerError>;
+}
+
+/// Ethereum signer backed by a remote key service
+///
+/// Key services return plain ECDSA signatures; the recovery ID Ethereum needs is found by
+/// checking which one recovers the key's known public half.
+#[derive(Clone)]
+pub struct RemoteSigner {
+    backend: Arc<dyn DigestSigner>,
+    public_key: VerifyingKey,
+    address: Address,
+    chain_id: u64,
+}
+
+impl std::fmt::Debug for RemoteSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteSigner")
+            .field("backend", &self.backend.name())
+            .field("address", &self.address)
+            .field("chain_id", &self.chain_id)
+            .finish()
+    }
+}
+
+impl RemoteSigner {
+    pub async fn new(backend: Arc<dyn DigestSigner>) -> Result<Self, SignerError> {
+        let public_key = backend.public_key().await?;
+        let address = ethers::utils::public_key_to_address(&public_key);
+
+        Ok(Self {
+            backend,
+            public_key,
+            address,
+            chain_id: 1,
+        })
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    /// Sign a digest; `v` is the bare recovery ID
+    async fn sign_digest(&self, digest: H256) -> Result<Signature, SignerError> {
+        let der = self.backend.sign_digest(digest.0).await?;
+        debug!("{} signed digest {:?} for {:?}", self.backend.name(), digest, self.address);
+        signature_from_der(&digest.0, &der, &self.public_key)
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    type Error = SignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(&self, message: S) -> Result<Signature, Self::Error> {
+        let mut signature = self.sign_digest(ethers::utils::hash_message(message)).await?;
+        signature.v += 27;
+        Ok(signature)
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        let mut tx = tx.clone();
+        let chain_id = tx.chain_id().map(|id| id.as_u64()).unwrap_or(self.chain_id);
+        tx.set_chain_id(chain_id);
+
+        let mut signature = self.sign_digest(tx.sighash()).await?;
+        signature.v = to_eip155_v(signature.v as u8, chain_id);
+        Ok(signature)
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(&self, payload: &T) -> Result<Signature, Self::Error> {
+        let digest = payload
+            .encode_eip712()
+            .map_err(|e| SignerError::Remote(format!("Typed data could not be encoded: {}", e)))?;
+        let mut signature = self.sign_digest(H256::from(digest)).await?;
+        signature.v += 27;
+        Ok(signature)
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn with_chain_id<T: Into<u64>>(mut self, chain_id: T) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+}
+
+/// AWS KMS asymmetric key with key spec `ECC_SECG_P256K1`
+pub struct AwsKmsSigner {
+    client: aws_sdk_kms::Client,
+    key_id: String,
+}
+
+impl AwsKmsSigner {
+    /// Credentials come from the standard AWS chain: environment, profile or instance role
+    pub async fn connect(key_id: String, region: Option<String>) -> Self {
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest());
+        if let Some(region) = region {
+            loader = loader.region(aws_config::Region::new(region));
+        }
+
+        Self {
+            client: aws_sdk_kms::Client::new(&loader.load().await),
+            key_id,
+        }
+    }
+}
+
+#[async_trait]
+impl DigestSigner for AwsKmsSigner {
+    fn name(&self) -> &'static str {
+        "aws_kms"
+    }
+
+    async fn public_key(&self) -> Result<VerifyingKey, SignerError> {
+        let response = self
+            .client
+            .get_public_key()
+            .key_id(&self.key_id)
+            .send()
+            .await
+            .map_err(|e| SignerError::Remote(format!("AWS KMS: {}", aws_sdk_kms::error::DisplayErrorContext(e))))?;
+        let der = response
+            .public_key()
+            .ok_or_else(|| SignerError::Remote("AWS KMS returned no public key".to_string()))?;
+
+        verifying_key_from_spki(der.as_ref())
+    }
+
+    async fn sign_digest(&self, digest: [u8; 32]) -> Result<Vec<u8>, SignerError> {
+        use aws_sdk_kms::primitives::Blob;
+        use aws_sdk_kms::types::{MessageType, SigningAlgorithmSpec};
+
+        let response = self
+            .client
+            .sign()
+            .key_id(&self.key_id)
+            .message(Blob::new(digest.to_vec()))
+            .message_type(MessageType::Digest)
+            .signing_algorithm(SigningAlgorithmSpec::EcdsaSha256)
+            .send()
+            .await
+            .map_err(|e| SignerError::Remote(format!("AWS KMS: {}", aws_sdk_kms::error::DisplayErrorContext(e))))?;
+
+        response
+            .signature()
+            .map(|signature| signature.as_ref().to_vec())
+            .ok_or_else(|| SignerError::Remote("AWS KMS returned no signature".to_string()))
+    }
+}
+
+/// Cloud KMS key version with algorithm `EC_SIGN_SECP256K1_SHA256`, named
+/// `projects/*/locations/*/keyRings/*/cryptoKeys/*/cryptoKeyVersions/*`
+pub struct GcpKmsSigner {
+    client: reqwest::Client,
+    key_version: String,
+    /// Access token from the metadata server and when it expires
+    token: Mutex<Option<(String, std::time::Instant)>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GcpAccessToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GcpPublicKey {
+    pem: String,
+    algorithm: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GcpSignResponse {
+    signature: String,
+}
+
+impl GcpKmsSigner {
+    pub fn new(key_version: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            key_version,
+            token: Mutex::new(None),
+        }
+    }
+
+    async fn access_token(&self) -> Result<String, SignerError> {
+        let mut token = self.token.lock().await;
+        if let Some((value, expires_at)) = token.as_ref() {
+            if *expires_at > std::time::Instant::now() + std::time::Duration::from_secs(60) {
+                return Ok(value.clone());
+            }
+        }
+
+        let fresh: GcpAccessToken = self
+            .client
+            .get(GCP_METADATA_TOKEN_URL)
+            .header("Metadata-Flavor", "Google")
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| SignerError::Remote(format!("GCP metadata server: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| SignerError::Remote(format!("GCP metadata server: {}", e)))?;
+
+        let expires_at = std::time::Instant::now() + std::time::Duration::from_secs(fresh.expires_in);
+        *token = Some((fresh.access_token.clone(), expires_at));
+        Ok(fresh.access_token)
+    }
+}
+
+#[async_trait]
+impl DigestSigner for GcpKmsSigner {
+    fn name(&self) -> &'static str {
+        "gcp_kms"
+    }
+
+    async fn public_key(&self) -> Result<VerifyingKey, SignerError> {
+        let response: GcpPublicKey = self
+            .client
+            .get(format!("{}/{}/publicKey", GCP_KMS_API, self.key_version))
+            .bearer_auth(self.access_token().await?)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| SignerError::Remote(format!("GCP KMS: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| SignerError::Remote(format!("GCP KMS: {}", e)))?;
+
+        if response.algorithm != "EC_SIGN_SECP256K1_SHA256" {
+            return Err(SignerError::Remote(format!(
+                "GCP KMS key uses {}, EC_SIGN_SECP256K1_SHA256 is needed",
+                response.algorithm
+            )));
+        }
+
+        verifying_key_from_pem(&response.pem)
+    }
+
+    async fn sign_digest(&self, digest: [u8; 32]) -> Result<Vec<u8>, SignerError> {
+        // Cloud KMS signs whatever 32 bytes it is given as the digest, so a Keccak hash works
+        let response: GcpSignResponse = self
+            .client
+            .post(format!("{}/{}:asymmetricSign", GCP_KMS_API, self.key_version))
+            .bearer_auth(self.access_token().await?)
+            .json(&serde_json::json!({ "digest": { "sha256": STANDARD.encode(digest) } }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| SignerError::Remote(format!("GCP KMS: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| SignerError::Remote(format!("GCP KMS: {}", e)))?;
+
+        STANDARD
+            .decode(response.signature)
+            .map_err(|e| SignerError::Remote(format!("GCP KMS returned an invalid signature: {}", e)))
+    }
+}
+
+/// Vault secrets engine with the transit API holding a secp256k1 key
+///
+/// Vault's built-in transit engine has no secp256k1 keys; mount a plugin that serves the same
+/// `keys/:name` and `sign/:name` endpoints for them.
+pub struct VaultSigner {
+    client: reqwest::Client,
+    addr: String,
+    mount: String,
+    key: String,
+    /// Token file kept fresh by Vault Agent; unset reads `VAULT_TOKEN`
+    token_file: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VaultResponse<T> {
+    data: T,
+}
+
+#[derive(Debug, Deserialize)]
+struct VaultKey {
+    latest_version: u64,
+    keys: std::collections::HashMap<String, VaultKeyVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VaultKeyVersion {
+    public_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct VaultSignature {
+    signature: String,
+}
+
+impl VaultSigner {
+    pub fn new(addr: String, mount: String, key: String, token_file: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            addr: addr.trim_end_matches('/').to_string(),
+            mount: mount.trim_matches('/').to_string(),
+            key,
+            token_file,
+        }
+    }
+
+    /// Read on every request so a token renewed by Vault Agent is picked up
+    async fn token(&self) -> Result<String, SignerError> {
+        match &self.token_file {
+            Some(path) => tokio::fs::read_to_string(path)
+                .await
+                .map(|token| token.trim().to_string())
+                .map_err(|e| SignerError::Remote(format!("Vault token file {}: {}", path, e))),
+            None => std::env::var("VAULT_TOKEN")
+                .map_err(|_| SignerError::Remote("Set VAULT_TOKEN_FILE or VAULT_TOKEN".to_string())),
+        }
+    }
+
+    fn url(&self, action: &str) -> String {
+        format!("{}/v1/{}/{}/{}", self.addr, self.mount, action, self.key)
+    }
+}
+
+#[async_trait]
+impl DigestSigner for VaultSigner {
+    fn name(&self) -> &'static str {
+        "vault"
+    }
+
+    async fn public_key(&self) -> Result<VerifyingKey, SignerError> {
+        let response: VaultResponse<VaultKey> = self
+            .client
+            .get(self.url("keys"))
+            .header("X-Vault-Token", self.token().await?)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| SignerError::Remote(format!("Vault: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| SignerError::Remote(format!("Vault: {}", e)))?;
+
+        // Signing uses the latest version, so that is the key whose address the wallet has
+        let version = response
+            .data
+            .keys
+            .get(&response.data.latest_version.to_string())
+            .ok_or_else(|| SignerError::Remote("Vault key has no public key for its latest version".to_string()))?;
+
+        verifying_key_from_pem(&version.public_key)
+    }
+
+    async fn sign_digest(&self, digest: [u8; 32]) -> Result<Vec<u8>, SignerError> {
+        let response: VaultResponse<VaultSignature> = self
+            .client
+            .post(self.url("sign"))
+            .header("X-Vault-Token", self.token().await?)
+            .json(&serde_json::json!({
+                "input": STANDARD.encode(digest),
+                "prehashed": true,
+                "marshaling_algorithm": "asn1",
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| SignerError::Remote(format!("Vault: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| SignerError::Remote(format!("Vault: {}", e)))?;
+
+        // Signatures look like `vault:v1:<base64>`
+        let encoded = response.data.signature.rsplit(':').next().unwrap_or_default();
+        STANDARD
+            .decode(encoded)
+            .map_err(|e| SignerError::Remote(format!("Vault returned an invalid signature: {}", e)))
+    }
+}
+
+/// Turn a key service's DER signature into an Ethereum one: low `s`, and `v` set to the
+/// recovery ID that gives back `public_key`
+pub(crate) fn signature_from_der(
+    digest: &[u8; 32],
+    der: &[u8],
+    public_key: &VerifyingKey,
+) -> Result<Signature, SignerError> {
+    let signature = EcdsaSignature::from_der(der)
+        .map_err(|e| SignerError::Remote(format!("Signature is not valid DER: {}", e)))?;
+    // Ethereum rejects the high-s form, which key services are free to return
+    let signature = signature.normalize_s().unwrap_or(signature);
+
+    for recovery_id in 0..=1u8 {
+        let recovery_id = RecoveryId::from_byte(recovery_id).expect("0 and 1 are valid recovery IDs");
+        let recovered = VerifyingKey::recover_from_prehash(digest, &signature, recovery_id);
+        if matches!(recovered, Ok(key) if key == *public_key) {
+            let bytes = signature.to_bytes();
+            return Ok(Signature {
+                r: U256::from_big_endian(&bytes[..32]),
+                s: U256::from_big_endian(&bytes[32..]),
+                v: recovery_id.to_byte() as u64,
+            });
+        }
+    }
+
+    Err(SignerError::Remote(
+        "Signature doesn't match the signer's public key; was the key rotated?".to_string(),
+    ))
+}
+
+/// Public key from a DER SubjectPublicKeyInfo, which must be for secp256k1
+pub(crate) fn verifying_key_from_spki(der: &[u8]) -> Result<VerifyingKey, SignerError> {
+    if !der.windows(SECP256K1_OID.len()).any(|window| window == SECP256K1_OID) {
+        return Err(SignerError::Remote("Signing key is not a secp256k1 key".to_string()));
+    }
+
+    // The key is the trailing bit string: an uncompressed point of 65 bytes
+    let point = der
+        .len()
+        .checked_sub(65)
+        .map(|start| &der[start..])
+        .filter(|point| point[0] == 0x04)
+        .ok_or_else(|| SignerError::Remote("Public key is not an uncompressed point".to_string()))?;
+
+    VerifyingKey::from_sec1_bytes(point).map_err(|e| SignerError::Remote(format!("Invalid public key: {}", e)))
+}
+
+pub(crate) fn verifying_key_from_pem(pem: &str) -> Result<VerifyingKey, SignerError> {
+    let body: String = pem.lines().filter(|line| !line.starts_with("-----")).collect();
+    let der = STANDARD
+        .decode(body.trim())
+        .map_err(|e| SignerError::Remote(format!("Public key is not valid PEM: {}", e)))?;
+
+    verifying_key_from_spki(&der)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::core::k256::ecdsa::signature::hazmat::PrehashSigner;
+    use ethers::core::k256::ecdsa::SigningKey;
+
+    /// SubjectPublicKeyInfo header for an uncompressed secp256k1 point
+    const SPKI_PREFIX: [u8; 23] = [
+        0x30, 0x56, 0x30, 0x10, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x05, 0x2b, 0x81, 0x04,
+        0x00, 0x0a, 0x03, 0x42, 0x00,
+    ];
+
+    fn spki(key: &VerifyingKey) -> Vec<u8> {
+        let mut der = SPKI_PREFIX.to_vec();
+        der.extend_from_slice(key.to_encoded_point(false).as_bytes());
+        der
+    }
+
+    #[test]
+    fn test_der_signature_matches_local_wallet() {
+        let signing_key = SigningKey::random(&mut rand::thread_rng());
+        let wallet = LocalWallet::from(signing_key.clone());
+        let digest = ethers::utils::keccak256(b"request randomness for raffle 7");
+
+        let signature: EcdsaSignature = signing_key.sign_prehash(&digest).unwrap();
+        let remote = signature_from_der(&digest, signature.to_der().as_bytes(), signing_key.verifying_key()).unwrap();
+        let local = wallet.sign_hash(H256::from(digest)).unwrap();
+
+        assert_eq!(remote.r, local.r);
+        assert_eq!(remote.s, local.s);
+        assert_eq!(remote.v + 27, local.v);
+        assert_eq!(remote.recover(H256::from(digest)).unwrap(), wallet.address());
+    }
+
+    #[test]
+    fn test_high_s_signature_is_normalized() {
+        let signing_key = SigningKey::random(&mut rand::thread_rng());
+        let digest = ethers::utils::keccak256(b"cancel raffle 3");
+        let signature: EcdsaSignature = signing_key.sign_prehash(&digest).unwrap();
+        let high_s = EcdsaSignature::from_scalars(signature.r(), -signature.s()).unwrap();
+
+        let normalized = signature_from_der(&digest, high_s.to_der().as_bytes(), signing_key.verifying_key()).unwrap();
+
+        let bytes = signature.to_bytes();
+        assert_eq!(normalized.s, U256::from_big_endian(&bytes[32..]));
+        assert_eq!(
+            normalized.recover(H256::from(digest)).unwrap(),
+            ethers::utils::public_key_to_address(signing_key.verifying_key())
+        );
+    }
+
+    #[test]
+    fn test_signature_from_another_key_is_rejected() {
+        let signing_key = SigningKey::random(&mut rand::thread_rng());
+        let other_key = SigningKey::random(&mut rand::thread_rng());
+        let digest = ethers::utils::keccak256(b"pause");
+        let signature: EcdsaSignature = other_key.sign_prehash(&digest).unwrap();
+
+        assert!(signature_from_der(&digest, signature.to_der().as_bytes(), signing_key.verifying_key()).is_err());
+    }
+
+    #[test]
+    fn test_public_key_from_spki_and_pem() {
+        let signing_key = SigningKey::random(&mut rand::thread_rng());
+        let der = spki(signing_key.verifying_key());
+
+        assert_eq!(&verifying_key_from_spki(&der).unwrap(), signing_key.verifying_key());
+
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n{}\n-----END PUBLIC KEY-----\n",
+            &STANDARD.encode(&der)[..64],
+            &STANDARD.encode(&der)[64..]
+        );
+        assert_eq!(&verifying_key_from_pem(&pem).unwrap(), signing_key.verifying_key());
+    }
+
+    #[test]
+    fn test_non_secp256k1_key_is_rejected() {
+        let signing_key = SigningKey::random(&mut rand::thread_rng());
+        let mut der = spki(signing_key.verifying_key());
+        // secp384r1 shares the arc, only the last byte differs
+        der[19] = 0x22;
+
+        assert!(verifying_key_from_spki(&der).is_err());
+    }
+}
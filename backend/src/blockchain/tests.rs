@@ -93,6 +93,7 @@ mod tests {
             min_gas_price: U256::from(1_000_000_000u64),
             priority_fee_multiplier: 1.1,
             max_priority_fee: U256::from(2_000_000_000u64),
+            ..Default::default()
         };
         
         // Test gas price calculation
@@ -113,6 +114,8 @@ mod tests {
         assert_eq!(config.confirmations, 3);
         assert_eq!(config.timeout, 300);
         assert!(config.gas_price.is_none());
+        assert!(config.strategy.is_none());
+        assert_eq!(config.max_replacements, 3);
     }
 
     #[tokio::test]
@@ -202,6 +205,111 @@ mod tests {
         assert!(estimate.max_priority_fee_per_gas.is_some());
     }
 
+    #[tokio::test]
+    async fn test_token_unit_conversion() {
+        use crate::blockchain::token::{from_token_units, to_token_units};
+        use rust_decimal::Decimal;
+        use std::str::FromStr;
+
+        let amount = Decimal::from_str("12.5").unwrap();
+        let units = to_token_units(amount, 6).unwrap();
+        assert_eq!(units, U256::from(12_500_000u64));
+        assert_eq!(from_token_units(units, 6).unwrap(), Decimal::from_str("12.500000").unwrap());
+
+        let one_ether = to_token_units(Decimal::ONE, 18).unwrap();
+        assert_eq!(one_ether, U256::from(1_000_000_000_000_000_000u128));
+
+        // More precision than the token supports would silently round
+        assert!(to_token_units(Decimal::from_str("0.0000001").unwrap(), 6).is_err());
+        assert!(to_token_units(Decimal::from_str("-1").unwrap(), 6).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_raffle_data_from_multicall_token() {
+        use crate::blockchain::contract::raffle_data_from_token;
+        use ethers::abi::Token;
+
+        let creator = Address::from_low_u64_be(7);
+        let uint = |v: u64| Token::Uint(U256::from(v));
+        // Field order of the contract's Raffle struct
+        let mut fields = vec![
+            uint(42),
+            Token::Address(creator),
+            Token::String("Sneakers".to_string()),
+            Token::String("Limited edition".to_string()),
+            uint(100),
+            uint(1_000),
+            uint(0),
+            uint(0),
+            uint(1),
+            Token::Address(Address::zero()),
+            uint(12),
+            uint(12_000),
+            Token::String("https://cdn.example.com/1.png".to_string()),
+            Token::String("Size 10".to_string()),
+            uint(1_700_000_000),
+            uint(0),
+            uint(0),
+            Token::Bool(true),
+            Token::FixedBytes(vec![0u8; 32]),
+            uint(5),
+            uint(3),
+            Token::Address(Address::zero()),
+            uint(99),
+            Token::Bool(false),
+        ];
+
+        let raffle = raffle_data_from_token(Token::Tuple(fields.clone())).unwrap();
+        assert_eq!(raffle.id, U256::from(42));
+        assert_eq!(raffle.creator, creator);
+        assert_eq!(raffle.status, RaffleStatus::Completed);
+        assert_eq!(raffle.total_participants, U256::from(12));
+        assert!(raffle.requires_whitelist);
+        assert_eq!(raffle.random_seed, U256::from(99));
+
+        // A short or mistyped tuple is rejected rather than misread
+        fields.pop();
+        assert!(raffle_data_from_token(Token::Tuple(fields)).is_err());
+        assert!(raffle_data_from_token(Token::Uint(U256::one())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_find_fork_point() {
+        use crate::blockchain::events::find_fork_point;
+
+        let blocks = |entries: &[(u64, &str)]| -> Vec<(u64, String)> {
+            entries.iter().map(|(n, h)| (*n, h.to_string())).collect()
+        };
+        let stored = blocks(&[(110, "0xa110"), (105, "0xa105"), (100, "0xa100")]);
+
+        // Newest stored block is still canonical
+        assert_eq!(find_fork_point(&stored, &blocks(&[(110, "0xa110")])), None);
+
+        // 105 and 110 were replaced; 100 is the common ancestor
+        let fork = find_fork_point(&stored, &blocks(&[(110, "0xb110"), (105, "0xb105"), (100, "0xa100")])).unwrap();
+        assert_eq!(fork.first_block, 101);
+        assert_eq!(fork.old_block_hash, "0xa105");
+        assert_eq!(fork.new_block_hash, "0xb105");
+
+        // Deeper than anything stored: roll back from the oldest stored block
+        let fork = find_fork_point(&stored, &blocks(&[(110, "0xb110"), (105, "0xb105"), (100, "0xb100")])).unwrap();
+        assert_eq!(fork.first_block, 100);
+    }
+
+    #[tokio::test]
+    async fn test_status_from_events() {
+        use crate::blockchain::events::status_from_events;
+
+        let events = |types: &[&str]| -> Vec<String> { types.iter().map(|t| t.to_string()).collect() };
+
+        assert_eq!(status_from_events(&events(&["raffle_created", "box_purchased"])), Some("open"));
+        assert_eq!(
+            status_from_events(&events(&["raffle_created", "box_purchased", "raffle_full", "randomness_requested"])),
+            Some("drawing")
+        );
+        assert_eq!(status_from_events(&events(&["box_purchased"])), None);
+    }
+
     // Integration test that would require a running blockchain
     #[tokio::test]
     #[ignore] // Ignore by default since it requires external dependencies
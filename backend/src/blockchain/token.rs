@@ -0,0 +1,226 @@
+use crate::blockchain::client::BlockchainClient;
+use crate::blockchain::gas::GasManager;
+use crate::blockchain::transaction::TransactionManager;
+use crate::blockchain::types::*;
+use ethers::prelude::*;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+abigen!(
+    Erc20Token,
+    r#"[
+        function name() external view returns (string)
+        function symbol() external view returns (string)
+        function decimals() external view returns (uint8)
+        function totalSupply() external view returns (uint256)
+        function balanceOf(address account) external view returns (uint256)
+        function allowance(address owner, address spender) external view returns (uint256)
+        function approve(address spender, uint256 amount) external returns (bool)
+        function transfer(address to, uint256 amount) external returns (bool)
+        function transferFrom(address from, address to, uint256 amount) external returns (bool)
+        event Transfer(address indexed from, address indexed to, uint256 value)
+        event Approval(address indexed owner, address indexed spender, uint256 value)
+    ]"#
+);
+
+/// Largest number of decimals a token amount can be converted with `rust_decimal`
+pub const MAX_TOKEN_DECIMALS: u8 = 28;
+
+/// On-chain metadata of an ERC-20 token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+    pub address: Address,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+/// Transactions submitted to raise an allowance, in submission order
+#[derive(Debug, Clone, Default)]
+pub struct AllowanceUpdate {
+    pub reset_tx_id: Option<Uuid>,
+    pub approve_tx_id: Option<Uuid>,
+}
+
+impl AllowanceUpdate {
+    pub fn is_noop(&self) -> bool {
+        self.reset_tx_id.is_none() && self.approve_tx_id.is_none()
+    }
+}
+
+/// Client for ERC-20 tokens used to pay for raffle boxes.
+///
+/// Token payments are pulled by the raffle contract with `transferFrom`, so the paying
+/// wallet first has to approve the contract for at least the purchase amount.
+#[derive(Clone)]
+pub struct TokenClient {
+    client: Arc<BlockchainClient>,
+    transaction_manager: Arc<TransactionManager>,
+    gas_manager: Arc<GasManager>,
+}
+
+impl TokenClient {
+    pub fn new(
+        client: Arc<BlockchainClient>,
+        transaction_manager: Arc<TransactionManager>,
+        gas_manager: Arc<GasManager>,
+    ) -> Self {
+        Self {
+            client,
+            transaction_manager,
+            gas_manager,
+        }
+    }
+
+    /// Read a token's symbol and decimals from the chain
+    pub async fn token_info(&self, token: Address) -> BlockchainResult<TokenInfo> {
+        let contract = self.contract(token);
+        let (symbol, decimals) = tokio::try_join!(contract.symbol().call(), contract.decimals().call())?;
+
+        if decimals > MAX_TOKEN_DECIMALS {
+            return Err(BlockchainError::Configuration(format!(
+                "Token {:?} uses {} decimals; at most {} are supported",
+                token, decimals, MAX_TOKEN_DECIMALS
+            )));
+        }
+
+        Ok(TokenInfo {
+            address: token,
+            symbol,
+            decimals,
+        })
+    }
+
+    /// Check a registry entry against the chain; a decimals mismatch would misprice every purchase
+    pub async fn verify_token(&self, token: Address, expected_decimals: u8) -> BlockchainResult<TokenInfo> {
+        let info = self.token_info(token).await?;
+
+        if info.decimals != expected_decimals {
+            return Err(BlockchainError::Configuration(format!(
+                "Token {} at {:?} has {} decimals on chain but {} in the registry",
+                info.symbol, token, info.decimals, expected_decimals
+            )));
+        }
+
+        Ok(info)
+    }
+
+    pub async fn balance_of(&self, token: Address, owner: Address) -> BlockchainResult<U256> {
+        Ok(self.contract(token).balance_of(owner).call().await?)
+    }
+
+    pub async fn allowance(&self, token: Address, owner: Address, spender: Address) -> BlockchainResult<U256> {
+        Ok(self.contract(token).allowance(owner, spender).call().await?)
+    }
+
+    /// Approve `spender` to pull up to `amount` from the platform wallet
+    pub async fn approve(&self, token: Address, spender: Address, amount: U256) -> BlockchainResult<Uuid> {
+        info!("Approving {:?} to spend {} of token {:?}", spender, amount, token);
+
+        let call = self.contract(token).approve(spender, amount);
+        self.submit(call.tx).await
+    }
+
+    /// Move `amount` from `from` to `to`, spending the platform wallet's allowance from `from`
+    pub async fn transfer_from(
+        &self,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: U256,
+    ) -> BlockchainResult<Uuid> {
+        info!("Transferring {} of token {:?} from {:?} to {:?}", amount, token, from, to);
+
+        let owner = self.client.signer().address();
+        let allowance = self.allowance(token, from, owner).await?;
+        if allowance < amount {
+            return Err(BlockchainError::Transaction(format!(
+                "Allowance {} from {:?} is below the transfer amount {}",
+                allowance, from, amount
+            )));
+        }
+
+        let call = self.contract(token).transfer_from(from, to, amount);
+        self.submit(call.tx).await
+    }
+
+    /// Make sure `spender` may pull `amount` from the platform wallet, approving it if not.
+    ///
+    /// Tokens like USDT refuse to change a non-zero allowance to another non-zero value,
+    /// so an existing, too-small allowance is reset to zero before the new approval.
+    pub async fn ensure_allowance(
+        &self,
+        token: Address,
+        spender: Address,
+        amount: U256,
+    ) -> BlockchainResult<AllowanceUpdate> {
+        let owner = self.client.signer().address();
+        let current = self.allowance(token, owner, spender).await?;
+
+        let mut update = AllowanceUpdate::default();
+        if current >= amount {
+            debug!("Allowance {} for {:?} already covers {}", current, spender, amount);
+            return Ok(update);
+        }
+
+        if !current.is_zero() {
+            update.reset_tx_id = Some(self.approve(token, spender, U256::zero()).await?);
+        }
+        update.approve_tx_id = Some(self.approve(token, spender, amount).await?);
+
+        Ok(update)
+    }
+
+    // Private helper methods
+
+    fn contract(&self, token: Address) -> Erc20Token<SignedClient> {
+        Erc20Token::new(token, self.client.create_signed_middleware())
+    }
+
+    async fn submit(&self, tx_request: TransactionRequest) -> BlockchainResult<Uuid> {
+        let gas_estimate = self.gas_manager.estimate_gas_with_buffer(&tx_request, Some(20)).await?;
+
+        let tx_config = TransactionConfig {
+            gas_limit: gas_estimate.gas_limit,
+            gas_price: Some(gas_estimate.gas_price),
+            max_fee_per_gas: gas_estimate.max_fee_per_gas,
+            max_priority_fee_per_gas: gas_estimate.max_priority_fee_per_gas,
+            confirmations: self.client.network_config().confirmations,
+            ..Default::default()
+        };
+
+        self.transaction_manager.submit_transaction(tx_request, Some(tx_config)).await
+    }
+}
+
+/// Convert a human amount (e.g. 12.5 USDC) to the token's smallest unit
+pub fn to_token_units(amount: Decimal, decimals: u8) -> BlockchainResult<U256> {
+    if amount.is_sign_negative() {
+        return Err(BlockchainError::Transaction("Token amount cannot be negative".to_string()));
+    }
+    if amount.normalize().scale() > decimals as u32 {
+        return Err(BlockchainError::Transaction(format!(
+            "Amount {} has more precision than the token's {} decimals",
+            amount, decimals
+        )));
+    }
+
+    let scale = Decimal::from_i128_with_scale(10i128.pow(decimals as u32), 0);
+    let units = amount
+        .checked_mul(scale)
+        .ok_or_else(|| BlockchainError::Transaction(format!("Amount {} is too large", amount)))?;
+
+    U256::from_dec_str(&units.trunc().to_string())
+        .map_err(|e| BlockchainError::Transaction(format!("Invalid token amount: {}", e)))
+}
+
+/// Convert an amount in the token's smallest unit back to a human amount
+pub fn from_token_units(units: U256, decimals: u8) -> BlockchainResult<Decimal> {
+    if decimals > MAX_TOKEN_DECIMALS || units > U256::from(i128::MAX as u128) {
+        return Err(BlockchainError::Transaction(format!("Token amount {} is out of range", units)));
+    }
+
+    Decimal::try_from_i128_with_scale(units.as_u128() as i128, decimals as u32)
+        .map_err(|e| BlockchainError::Transaction(format!("Token amount {} is out of range: {}", units, e)))
+}
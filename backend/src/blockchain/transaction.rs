@@ -1,5 +1,7 @@
 use crate::blockchain::types::*;
 use crate::blockchain::client::BlockchainClient;
+use crate::blockchain::gas::GasManager;
+use crate::blockchain::nonce::{NonceConfig, NonceManager, WalletNonceSnapshot};
 use ethers::prelude::*;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -12,7 +14,8 @@ use uuid::Uuid;
 pub struct TransactionManager {
     client: Arc<BlockchainClient>,
     pending_transactions: Arc<RwLock<HashMap<Uuid, PendingTransaction>>>,
-    nonce_manager: Arc<RwLock<NonceManager>>,
+    nonce_manager: NonceManager,
+    gas_manager: Option<Arc<GasManager>>,
 }
 
 #[derive(Debug, Clone)]
@@ -25,33 +28,52 @@ struct PendingTransaction {
     attempts: u32,
     created_at: u64,
     last_attempt: Option<u64>,
+    /// Worst-case cost counted against the gas manager's spend caps
+    reserved_spend: U256,
+    /// Earlier broadcasts with the same nonce; any of them may still be mined
+    replaced_hashes: Vec<H256>,
+    /// Broadcast of an empty self-transfer that takes the nonce instead, if one was sent
+    cancel_hash: Option<H256>,
 }
 
-#[derive(Debug, Clone)]
-struct NonceManager {
-    current_nonce: U256,
-    pending_nonces: std::collections::BTreeSet<U256>,
-    last_update: u64,
+/// How a replacement with the same nonce differs from the transaction it replaces
+#[derive(Debug, Clone, Copy)]
+enum Rebroadcast {
+    /// Fees the network will accept now, bumped enough to replace
+    Reprice,
+    /// As `Reprice`, with at least this max fee per gas
+    SpeedUp(Option<U256>),
+    /// An empty self-transfer, so the nonce is used without the original's effects
+    Cancel,
 }
 
 impl TransactionManager {
     /// Create a new transaction manager
     pub async fn new(client: Arc<BlockchainClient>) -> BlockchainResult<Self> {
-        let current_nonce = client.get_nonce(None).await?;
-        
-        let nonce_manager = NonceManager {
-            current_nonce,
-            pending_nonces: std::collections::BTreeSet::new(),
-            last_update: chrono::Utc::now().timestamp() as u64,
-        };
+        let nonce_manager = NonceManager::new(client.clone(), NonceConfig::default());
+        nonce_manager.sync(client.signer().address()).await?;
 
         Ok(Self {
             client,
             pending_transactions: Arc::new(RwLock::new(HashMap::new())),
-            nonce_manager: Arc::new(RwLock::new(nonce_manager)),
+            nonce_manager,
+            gas_manager: None,
         })
     }
 
+    /// Limit how many transactions each wallet has in flight and how gaps are filled
+    pub fn with_nonce_config(mut self, config: NonceConfig) -> Self {
+        self.nonce_manager = self.nonce_manager.with_config(config);
+        self
+    }
+
+    /// Price transactions with the gas manager's fee oracle, enforce its spend caps
+    /// and re-price transactions that get stuck
+    pub fn with_gas_manager(mut self, gas_manager: Arc<GasManager>) -> Self {
+        self.gas_manager = Some(gas_manager);
+        self
+    }
+
     /// Submit a transaction with automatic retry and monitoring
     pub async fn submit_transaction(
         &self,
@@ -62,7 +84,16 @@ impl TransactionManager {
         let tx_id = Uuid::new_v4();
 
         // Prepare transaction
-        self.prepare_transaction(&mut request, &config).await?;
+        self.prepare_transaction(tx_id, &mut request, &config).await?;
+        let reserved_spend = match self.reserve_spend(&request, &config).await {
+            Ok(reserved_spend) => reserved_spend,
+            Err(e) => {
+                if let Some(nonce) = request.nonce {
+                    self.nonce_manager.release(self.address(), nonce).await;
+                }
+                return Err(e);
+            }
+        };
 
         // Create pending transaction
         let pending_tx = PendingTransaction {
@@ -74,6 +105,9 @@ impl TransactionManager {
             attempts: 0,
             created_at: chrono::Utc::now().timestamp() as u64,
             last_attempt: None,
+            reserved_spend,
+            replaced_hashes: Vec::new(),
+            cancel_hash: None,
         };
 
         // Store pending transaction
@@ -92,75 +126,114 @@ impl TransactionManager {
     /// Prepare transaction with gas estimation and nonce management
     async fn prepare_transaction(
         &self,
+        tx_id: Uuid,
         request: &mut TransactionRequest,
         config: &TransactionConfig,
     ) -> BlockchainResult<()> {
         // Set sender
-        request.from = Some(self.client.signer().address());
+        let address = self.address();
+        request.from = Some(address);
+
+        // Take the requested nonce, or the wallet's next one
+        let nonce = match request.nonce.or(config.nonce) {
+            Some(nonce) => {
+                self.nonce_manager.claim(address, nonce, tx_id).await;
+                nonce
+            }
+            None => self.nonce_manager.allocate(address, tx_id).await?,
+        };
+        request.nonce = Some(nonce);
 
-        // Set nonce if not provided
-        if request.nonce.is_none() {
-            request.nonce = Some(self.get_next_nonce().await?);
+        if let Err(e) = self.price_transaction(request, config).await {
+            self.nonce_manager.release(address, nonce).await;
+            return Err(e);
         }
 
+        Ok(())
+    }
+
+    /// Estimate gas and set fees
+    async fn price_transaction(&self, request: &mut TransactionRequest, config: &TransactionConfig) -> BlockchainResult<()> {
         // Estimate gas if not provided
         if request.gas.is_none() {
             let gas_estimate = self.client.estimate_gas(request).await?;
             request.gas = Some(std::cmp::min(gas_estimate, config.gas_limit));
         }
 
-        // Set gas price if not provided
-        if request.gas_price.is_none() && config.gas_price.is_some() {
-            request.gas_price = config.gas_price;
-        } else if request.gas_price.is_none() {
-            request.gas_price = Some(self.client.get_optimal_gas_price().await?);
-        }
+        let explicit_fees = request.gas_price.is_some()
+            || config.gas_price.is_some()
+            || config.max_fee_per_gas.is_some();
+
+        match &self.gas_manager {
+            // Price from the fee oracle unless the caller set fees
+            Some(gas_manager) if !explicit_fees => {
+                let strategy = config.strategy.unwrap_or_else(|| gas_manager.default_strategy());
+                let quote = gas_manager.quote_fees(strategy).await?;
+                request.max_fee_per_gas = Some(quote.max_fee_per_gas);
+                request.max_priority_fee_per_gas = Some(quote.max_priority_fee_per_gas);
+            }
+            _ => {
+                // Set gas price if not provided
+                if request.gas_price.is_none() && config.gas_price.is_some() {
+                    request.gas_price = config.gas_price;
+                } else if request.gas_price.is_none() {
+                    request.gas_price = Some(self.client.get_optimal_gas_price().await?);
+                }
 
-        // Set EIP-1559 fields if provided
-        if let Some(max_fee) = config.max_fee_per_gas {
-            request.max_fee_per_gas = Some(max_fee);
-        }
-        if let Some(max_priority_fee) = config.max_priority_fee_per_gas {
-            request.max_priority_fee_per_gas = Some(max_priority_fee);
+                // Set EIP-1559 fields if provided
+                if let Some(max_fee) = config.max_fee_per_gas {
+                    request.max_fee_per_gas = Some(max_fee);
+                }
+                if let Some(max_priority_fee) = config.max_priority_fee_per_gas {
+                    request.max_priority_fee_per_gas = Some(max_priority_fee);
+                }
+            }
         }
 
         Ok(())
     }
 
-    /// Get next available nonce
-    async fn get_next_nonce(&self) -> BlockchainResult<U256> {
-        let mut nonce_manager = self.nonce_manager.write().await;
-        
-        // Update nonce from blockchain if it's been a while
-        let now = chrono::Utc::now().timestamp() as u64;
-        if now - nonce_manager.last_update > 60 {
-            let blockchain_nonce = self.client.get_nonce(None).await?;
-            if blockchain_nonce > nonce_manager.current_nonce {
-                nonce_manager.current_nonce = blockchain_nonce;
-                nonce_manager.pending_nonces.clear();
+    /// Count a prepared transaction against the spend caps
+    async fn reserve_spend(&self, request: &TransactionRequest, config: &TransactionConfig) -> BlockchainResult<U256> {
+        match &self.gas_manager {
+            Some(gas_manager) => {
+                gas_manager
+                    .reserve_spend(request.gas.unwrap_or(config.gas_limit), max_fee_of(request), U256::zero())
+                    .await
             }
-            nonce_manager.last_update = now;
+            None => Ok(U256::zero()),
         }
+    }
 
-        // Find next available nonce
-        let mut next_nonce = nonce_manager.current_nonce;
-        while nonce_manager.pending_nonces.contains(&next_nonce) {
-            next_nonce += U256::one();
-        }
+    /// Swap a finished transaction's reservation for what it actually cost
+    async fn settle_spend(&self, tx_id: Uuid, status: Option<&TransactionStatus>) {
+        let gas_manager = match &self.gas_manager {
+            Some(gas_manager) => gas_manager,
+            None => return,
+        };
+
+        let reserved = {
+            let mut pending = self.pending_transactions.write().await;
+            match pending.get_mut(&tx_id) {
+                Some(tx) => std::mem::take(&mut tx.reserved_spend),
+                None => return,
+            }
+        };
 
-        nonce_manager.pending_nonces.insert(next_nonce);
-        Ok(next_nonce)
+        let actual = status
+            .and_then(|status| Some(status.gas_used? * status.effective_gas_price?))
+            .unwrap_or_default();
+        gas_manager.settle_spend(reserved, actual).await;
     }
 
-    /// Release nonce when transaction is confirmed or failed
-    async fn release_nonce(&self, nonce: U256) {
-        let mut nonce_manager = self.nonce_manager.write().await;
-        nonce_manager.pending_nonces.remove(&nonce);
+    /// The wallet every transaction is signed with
+    fn address(&self) -> Address {
+        self.client.signer().address()
     }
 
     /// Attempt to submit transaction
     async fn attempt_transaction_submission(&self, tx_id: Uuid) -> BlockchainResult<()> {
-        let (request, config) = {
+        let request = {
             let mut pending = self.pending_transactions.write().await;
             let tx = pending.get_mut(&tx_id).ok_or_else(|| {
                 BlockchainError::Transaction("Transaction not found".to_string())
@@ -169,7 +242,7 @@ impl TransactionManager {
             tx.attempts += 1;
             tx.last_attempt = Some(chrono::Utc::now().timestamp() as u64);
 
-            (tx.request.clone(), tx.config.clone())
+            tx.request.clone()
         };
 
         // Create signed middleware
@@ -192,12 +265,7 @@ impl TransactionManager {
                 debug!("Transaction {} submitted with hash: {}", tx_id, tx_hash);
 
                 // Start monitoring transaction
-                let manager = self.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = manager.monitor_transaction(tx_id, tx_hash, config).await {
-                        error!("Failed to monitor transaction {}: {}", tx_id, e);
-                    }
-                });
+                self.spawn_monitor(tx_id);
 
                 Ok(())
             }
@@ -224,16 +292,18 @@ impl TransactionManager {
                     tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
                     return self.attempt_transaction_submission(tx_id).await;
                 } else {
-                    // Mark as failed
-                    {
+                    // Mark as failed; the nonce was never broadcast, so it goes to the next transaction
+                    let nonce = {
                         let mut pending = self.pending_transactions.write().await;
-                        if let Some(tx) = pending.get_mut(&tx_id) {
+                        pending.get_mut(&tx_id).and_then(|tx| {
                             tx.status = TransactionState::Failed;
-                            if let Some(nonce) = tx.request.nonce {
-                                self.release_nonce(nonce).await;
-                            }
-                        }
+                            tx.request.nonce
+                        })
+                    };
+                    if let Some(nonce) = nonce {
+                        self.nonce_manager.release(self.address(), nonce).await;
                     }
+                    self.settle_spend(tx_id, None).await;
                     
                     return Err(BlockchainError::Transaction(format!(
                         "Transaction submission failed after retries: {}",
@@ -244,77 +314,81 @@ impl TransactionManager {
         }
     }
 
-    /// Monitor transaction until confirmation or failure
-    async fn monitor_transaction(
-        &self,
-        tx_id: Uuid,
-        tx_hash: H256,
-        config: TransactionConfig,
-    ) -> BlockchainResult<()> {
-        let timeout_duration = tokio::time::Duration::from_secs(config.timeout);
+    fn spawn_monitor(&self, tx_id: Uuid) {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = manager.monitor_transaction(tx_id).await {
+                error!("Failed to monitor transaction {}: {}", tx_id, e);
+            }
+        });
+    }
+
+    /// Monitor transaction until confirmation or failure, replacing it with a better
+    /// priced one while it sits unmined
+    async fn monitor_transaction(&self, tx_id: Uuid) -> BlockchainResult<()> {
         let start_time = std::time::Instant::now();
+        let mut replacements = 0;
 
         loop {
-            // Check timeout
-            if start_time.elapsed() > timeout_duration {
-                warn!("Transaction {} monitoring timeout", tx_id);
-                {
-                    let mut pending = self.pending_transactions.write().await;
-                    if let Some(tx) = pending.get_mut(&tx_id) {
-                        tx.status = TransactionState::Dropped;
-                        if let Some(nonce) = tx.request.nonce {
-                            self.release_nonce(nonce).await;
-                        }
+            // Broadcasts are re-read each time, as a speed-up or cancel may have added one
+            let (hashes, cancel_hash, config, last_attempt) = {
+                let pending = self.pending_transactions.read().await;
+                match pending.get(&tx_id) {
+                    Some(tx) if matches!(tx.status, TransactionState::Pending) => {
+                        let mut hashes = tx.replaced_hashes.clone();
+                        hashes.extend(tx.hash);
+                        (hashes, tx.cancel_hash, tx.config.clone(), tx.last_attempt.unwrap_or(tx.created_at))
                     }
+                    _ => break,
                 }
+            };
+
+            // Check timeout
+            if start_time.elapsed() > tokio::time::Duration::from_secs(config.timeout) {
+                warn!("Transaction {} monitoring timeout", tx_id);
+                self.finish_transaction(tx_id, TransactionState::Dropped, None).await;
                 break;
             }
 
             // Check transaction status
-            match self.client.get_transaction_status(tx_hash).await {
+            match self.latest_status(&hashes).await {
                 Ok(status) => {
                     match status.status {
+                        TransactionState::Confirmed if cancel_hash == Some(status.hash) => {
+                            info!("Transaction {} was cancelled", tx_id);
+                            self.finish_transaction(tx_id, TransactionState::Dropped, Some(&status)).await;
+                            break;
+                        }
                         TransactionState::Confirmed => {
                             info!("Transaction {} confirmed", tx_id);
-                            {
-                                let mut pending = self.pending_transactions.write().await;
-                                if let Some(tx) = pending.get_mut(&tx_id) {
-                                    tx.status = TransactionState::Confirmed;
-                                    if let Some(nonce) = tx.request.nonce {
-                                        self.release_nonce(nonce).await;
-                                    }
-                                }
-                            }
+                            self.finish_transaction(tx_id, TransactionState::Confirmed, Some(&status)).await;
                             break;
                         }
                         TransactionState::Failed => {
                             error!("Transaction {} failed", tx_id);
-                            {
-                                let mut pending = self.pending_transactions.write().await;
-                                if let Some(tx) = pending.get_mut(&tx_id) {
-                                    tx.status = TransactionState::Failed;
-                                    if let Some(nonce) = tx.request.nonce {
-                                        self.release_nonce(nonce).await;
-                                    }
-                                }
-                            }
+                            self.finish_transaction(tx_id, TransactionState::Failed, Some(&status)).await;
                             break;
                         }
                         TransactionState::Pending => {
                             // Continue monitoring
                             debug!("Transaction {} still pending", tx_id);
+
+                            let stuck = status.block_number.is_none()
+                                && config.replace_after > 0
+                                && replacements < config.max_replacements
+                                && now().saturating_sub(last_attempt) >= config.replace_after;
+                            if stuck {
+                                if let Err(e) = self.rebroadcast(tx_id, Rebroadcast::Reprice).await {
+                                    warn!("Failed to re-price stuck transaction {}: {}", tx_id, e);
+                                }
+                                // Counted even when it failed, so a hopeless replacement isn't retried every block
+                                replacements += 1;
+                                self.touch(tx_id).await;
+                            }
                         }
                         TransactionState::Dropped => {
                             warn!("Transaction {} was dropped", tx_id);
-                            {
-                                let mut pending = self.pending_transactions.write().await;
-                                if let Some(tx) = pending.get_mut(&tx_id) {
-                                    tx.status = TransactionState::Dropped;
-                                    if let Some(nonce) = tx.request.nonce {
-                                        self.release_nonce(nonce).await;
-                                    }
-                                }
-                            }
+                            self.finish_transaction(tx_id, TransactionState::Dropped, None).await;
                             break;
                         }
                     }
@@ -334,8 +408,182 @@ impl TransactionManager {
         Ok(())
     }
 
+    /// Status of whichever broadcast was mined, or of the latest one while none has been
+    async fn latest_status(&self, hashes: &[H256]) -> BlockchainResult<TransactionStatus> {
+        let mut latest = None;
+        for hash in hashes.iter().rev() {
+            let status = self.client.get_transaction_status(*hash).await?;
+            if status.block_number.is_some() || status.status != TransactionState::Pending {
+                return Ok(status);
+            }
+            latest.get_or_insert(status);
+        }
+
+        latest.ok_or_else(|| BlockchainError::Transaction("Transaction has not been broadcast".to_string()))
+    }
+
+    /// Restart the wait before the next automatic re-price
+    async fn touch(&self, tx_id: Uuid) {
+        let mut pending = self.pending_transactions.write().await;
+        if let Some(tx) = pending.get_mut(&tx_id) {
+            tx.last_attempt = Some(now());
+        }
+    }
+
+    /// Record the final state, let go of a mined nonce and settle the spend reservation
+    ///
+    /// A transaction given up on without being mined keeps its nonce: the broadcast may still
+    /// land, and until it does or is cancelled every later nonce waits behind it.
+    async fn finish_transaction(&self, tx_id: Uuid, state: TransactionState, status: Option<&TransactionStatus>) {
+        let mined_nonce = {
+            let mut pending = self.pending_transactions.write().await;
+            match pending.get_mut(&tx_id) {
+                Some(tx) => {
+                    tx.status = state;
+                    if let Some(hash) = status.map(|status| status.hash) {
+                        tx.hash = Some(hash);
+                    }
+                    tx.request.nonce.filter(|_| status.is_some_and(|status| status.block_number.is_some()))
+                }
+                None => None,
+            }
+        };
+        if let Some(nonce) = mined_nonce {
+            self.nonce_manager.complete(self.address(), nonce).await;
+        }
+        self.settle_spend(tx_id, status).await;
+    }
+
+    /// Replace-by-fee: resend with the same nonce at fees the network will accept as a
+    /// replacement, if that fits under the max gas price and spend caps
+    ///
+    /// Returns None when an automatic re-price isn't possible. A cancel may also replace a
+    /// transaction that was given up on, and monitors it again.
+    async fn rebroadcast(&self, tx_id: Uuid, kind: Rebroadcast) -> BlockchainResult<Option<H256>> {
+        let gas_manager = match (&self.gas_manager, kind) {
+            (Some(gas_manager), _) => gas_manager.clone(),
+            (None, Rebroadcast::Reprice) => return Ok(None),
+            (None, _) => {
+                return Err(BlockchainError::Transaction(
+                    "Replacing a transaction needs the gas manager".to_string(),
+                ))
+            }
+        };
+
+        let (request, config, reserved_spend, revive) = {
+            let pending = self.pending_transactions.read().await;
+            let tx = pending
+                .get(&tx_id)
+                .ok_or_else(|| BlockchainError::Transaction("Transaction not found".to_string()))?;
+            match (&tx.status, kind) {
+                (TransactionState::Pending, _) if tx.hash.is_some() => {
+                    (tx.request.clone(), tx.config.clone(), tx.reserved_spend, false)
+                }
+                (TransactionState::Dropped, Rebroadcast::Cancel) if tx.hash.is_some() => {
+                    (tx.request.clone(), tx.config.clone(), tx.reserved_spend, true)
+                }
+                (_, Rebroadcast::Reprice) => return Ok(None),
+                _ => {
+                    return Err(BlockchainError::Transaction(
+                        "Only a broadcast transaction that hasn't been mined can be replaced".to_string(),
+                    ))
+                }
+            }
+        };
+        if revive && !self.nonce_manager.holds(self.address(), tx_id).await {
+            return Err(BlockchainError::Transaction(
+                "The transaction's nonce has already been mined".to_string(),
+            ));
+        }
+
+        let strategy = config.strategy.unwrap_or_else(|| gas_manager.default_strategy());
+        let mut quote = gas_manager.quote_fees(strategy).await?;
+        if let Rebroadcast::SpeedUp(Some(max_fee)) = kind {
+            quote.max_fee_per_gas = std::cmp::max(quote.max_fee_per_gas, max_fee);
+        }
+        let previous_max_fee = max_fee_of(&request);
+        let previous_priority_fee = request.max_priority_fee_per_gas.unwrap_or(previous_max_fee);
+
+        let (max_fee, priority_fee) = match gas_manager.replacement_fees(previous_max_fee, previous_priority_fee, &quote) {
+            Some(fees) => fees,
+            None if matches!(kind, Rebroadcast::Reprice) => {
+                warn!("Transaction {} is stuck but a replacement would pass the max gas price", tx_id);
+                return Ok(None);
+            }
+            None => {
+                return Err(BlockchainError::Transaction(
+                    "A replacement would pass the max gas price".to_string(),
+                ))
+            }
+        };
+
+        let mut replacement = match kind {
+            Rebroadcast::Cancel => {
+                let address = self.address();
+                let mut cancel = TransactionRequest::new()
+                    .from(address)
+                    .to(address)
+                    .value(U256::zero())
+                    .gas(CANCEL_GAS);
+                cancel.nonce = request.nonce;
+                cancel.gas_price = request.gas_price;
+                cancel.max_fee_per_gas = request.max_fee_per_gas;
+                cancel
+            }
+            _ => request,
+        };
+
+        let new_reserved = gas_manager
+            .reserve_spend(replacement.gas.unwrap_or(config.gas_limit), max_fee, reserved_spend)
+            .await?;
+
+        if replacement.max_fee_per_gas.is_some() {
+            replacement.max_fee_per_gas = Some(max_fee);
+            replacement.max_priority_fee_per_gas = Some(priority_fee);
+        } else {
+            replacement.gas_price = Some(max_fee);
+        }
+
+        let middleware = self.client.create_signed_middleware();
+        let new_hash = match middleware.send_transaction(replacement.clone(), None).await {
+            Ok(pending_tx) => *pending_tx.tx_hash(),
+            Err(e) => {
+                // Give back the extra reservation
+                gas_manager.settle_spend(new_reserved, reserved_spend).await;
+                return Err(BlockchainError::Transaction(format!("Replacement was rejected: {}", e)));
+            }
+        };
+
+        {
+            let mut pending = self.pending_transactions.write().await;
+            if let Some(tx) = pending.get_mut(&tx_id) {
+                if let Some(previous) = tx.hash.replace(new_hash) {
+                    tx.replaced_hashes.push(previous);
+                }
+                if matches!(kind, Rebroadcast::Cancel) {
+                    tx.cancel_hash = Some(new_hash);
+                }
+                tx.status = TransactionState::Pending;
+                tx.request = replacement;
+                tx.reserved_spend = new_reserved;
+                tx.attempts += 1;
+                tx.last_attempt = Some(now());
+            }
+        }
+        if revive {
+            self.spawn_monitor(tx_id);
+        }
+
+        info!(
+            "Replaced transaction {} ({:?}) with {} at max fee {} (priority {})",
+            tx_id, kind, new_hash, max_fee, priority_fee
+        );
+
+        Ok(Some(new_hash))
+    }
+
     /// Check if error is retryable
-    fn is_retryable_error(&self, error: &ContractError<SignerMiddleware<Provider<Http>, LocalWallet>>) -> bool {
+    fn is_retryable_error(&self, error: &ContractError<SignedClient>) -> bool {
         match error {
             ContractError::ProviderError { e } => {
                 // Network errors are generally retryable
@@ -351,8 +599,10 @@ impl TransactionManager {
         let pending = self.pending_transactions.read().await;
         if let Some(tx) = pending.get(&tx_id) {
             if let Some(hash) = tx.hash {
-                // Try to get detailed status from blockchain
-                if let Ok(status) = self.client.get_transaction_status(hash).await {
+                // Try to get detailed status from blockchain, from whichever broadcast was mined
+                let mut hashes = tx.replaced_hashes.clone();
+                hashes.push(hash);
+                if let Ok(status) = self.latest_status(&hashes).await {
                     return Some(status);
                 }
             }
@@ -387,7 +637,7 @@ impl TransactionManager {
                 block_number: None,
                 timestamp: Some(tx.created_at),
             };
-            result.push(*id, status);
+            result.push((*id, status));
         }
 
         result
@@ -403,60 +653,80 @@ impl TransactionManager {
         });
     }
 
-    /// Cancel a pending transaction (if possible)
-    pub async fn cancel_transaction(&self, tx_id: Uuid) -> BlockchainResult<()> {
-        let mut pending = self.pending_transactions.write().await;
-        if let Some(tx) = pending.get_mut(&tx_id) {
-            if matches!(tx.status, TransactionState::Pending) {
-                tx.status = TransactionState::Dropped;
-                if let Some(nonce) = tx.request.nonce {
-                    self.release_nonce(nonce).await;
-                }
-                info!("Transaction {} cancelled", tx_id);
-                Ok(())
-            } else {
-                Err(BlockchainError::Transaction(
-                    "Cannot cancel non-pending transaction".to_string(),
-                ))
-            }
-        } else {
-            Err(BlockchainError::Transaction(
-                "Transaction not found".to_string(),
-            ))
-        }
+    /// Cancel a broadcast transaction by replacing it with an empty self-transfer at higher fees
+    ///
+    /// The original may still be mined first; the transaction's status shows which one was.
+    pub async fn cancel_transaction(&self, tx_id: Uuid) -> BlockchainResult<H256> {
+        self.rebroadcast(tx_id, Rebroadcast::Cancel)
+            .await?
+            .ok_or_else(|| BlockchainError::Transaction("Transaction could not be cancelled".to_string()))
     }
 
-    /// Speed up a pending transaction by increasing gas price
-    pub async fn speed_up_transaction(
-        &self,
-        tx_id: Uuid,
-        new_gas_price: U256,
-    ) -> BlockchainResult<Uuid> {
-        let original_request = {
+    /// Speed up a pending transaction by replacing it at higher fees, at least `max_fee_per_gas`
+    /// if given; it keeps its transaction ID
+    pub async fn speed_up_transaction(&self, tx_id: Uuid, max_fee_per_gas: Option<U256>) -> BlockchainResult<H256> {
+        self.rebroadcast(tx_id, Rebroadcast::SpeedUp(max_fee_per_gas))
+            .await?
+            .ok_or_else(|| BlockchainError::Transaction("Transaction could not be sped up".to_string()))
+    }
+
+    /// Nonces of every wallet transactions were sent from
+    pub async fn nonce_snapshots(&self) -> Vec<WalletNonceSnapshot> {
+        self.nonce_manager.snapshots().await
+    }
+
+    /// Re-sync the signing wallet's nonces with the chain, cancel transactions that were given
+    /// up on while their nonce still blocks later ones, and fill gaps nothing reused
+    pub async fn maintain_nonces(&self) -> BlockchainResult<WalletNonceSnapshot> {
+        let address = self.address();
+        let snapshot = self.nonce_manager.sync(address).await?;
+
+        let given_up: Vec<Uuid> = {
             let pending = self.pending_transactions.read().await;
-            let tx = pending.get(&tx_id).ok_or_else(|| {
-                BlockchainError::Transaction("Transaction not found".to_string())
-            })?;
+            pending
+                .values()
+                .filter(|tx| {
+                    matches!(tx.status, TransactionState::Dropped)
+                        && now().saturating_sub(tx.last_attempt.unwrap_or(tx.created_at)) >= tx.config.replace_after
+                })
+                .map(|tx| tx.id)
+                .collect()
+        };
+        for (nonce, tx_id) in self.nonce_manager.held_by(address, &given_up).await {
+            match self.rebroadcast(tx_id, Rebroadcast::Cancel).await {
+                Ok(Some(hash)) => info!("Cancelling transaction {} holding nonce {} with {}", tx_id, nonce, hash),
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("Failed to cancel transaction {} holding nonce {}: {}", tx_id, nonce, e);
+                    self.touch(tx_id).await;
+                }
+            }
+        }
 
-            if !matches!(tx.status, TransactionState::Pending) {
-                return Err(BlockchainError::Transaction(
-                    "Can only speed up pending transactions".to_string(),
-                ));
+        for nonce in self.nonce_manager.gaps_to_fill(address).await {
+            let filler = TransactionRequest::new()
+                .to(address)
+                .value(U256::zero())
+                .gas(CANCEL_GAS)
+                .nonce(nonce);
+            match self.submit_transaction(filler, None).await {
+                Ok(filler_id) => info!("Filling nonce gap {} of {:?} with transaction {}", nonce, address, filler_id),
+                Err(e) => warn!("Failed to fill nonce gap {} of {:?}: {}", nonce, address, e),
             }
+        }
 
-            tx.request.clone()
-        };
+        Ok(snapshot)
+    }
+}
 
-        // Create new transaction with higher gas price and same nonce
-        let mut new_request = original_request;
-        new_request.gas_price = Some(new_gas_price);
+/// Gas of a plain transfer, which cancels and gap fillers are
+const CANCEL_GAS: u64 = 21_000;
 
-        let config = TransactionConfig {
-            gas_price: Some(new_gas_price),
-            ..Default::default()
-        };
+fn now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
 
-        // Submit replacement transaction
-        self.submit_transaction(new_request, Some(config)).await
-    }
-}
\ No newline at end of file
+/// Most the request can pay per gas: its EIP-1559 max fee, or its legacy gas price
+fn max_fee_of(request: &TransactionRequest) -> U256 {
+    request.max_fee_per_gas.or(request.gas_price).unwrap_or_default()
+}
@@ -1,12 +1,18 @@
+use crate::blockchain::gas::{GasConfig, GasStrategy};
+use crate::blockchain::nonce::NonceConfig;
+use crate::blockchain::wallet::PlatformSigner;
 use ethers::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use thiserror::Error;
 
+/// Provider that signs with the platform wallet
+pub type SignedClient = SignerMiddleware<Provider<Http>, PlatformSigner>;
+
 #[derive(Debug, Error)]
 pub enum BlockchainError {
     #[error("Contract error: {0}")]
-    Contract(#[from] ContractError<SignerMiddleware<Provider<Http>, LocalWallet>>),
+    Contract(#[from] ContractError<SignedClient>),
     
     #[error("Provider error: {0}")]
     Provider(#[from] ProviderError),
@@ -29,6 +35,12 @@ pub enum BlockchainError {
     #[error("Event processing error: {0}")]
     EventProcessing(String),
     
+    #[error("Multicall error: {0}")]
+    Multicall(String),
+
+    #[error("Gas spend cap exceeded: {0}")]
+    GasSpendCap(String),
+    
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 }
@@ -53,6 +65,7 @@ pub struct NetworkConfig {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TransactionConfig {
     pub gas_limit: U256,
     pub gas_price: Option<U256>,
@@ -61,6 +74,11 @@ pub struct TransactionConfig {
     pub nonce: Option<U256>,
     pub confirmations: usize,
     pub timeout: u64, // seconds
+    /// Fee strategy; None uses the gas manager's default
+    pub strategy: Option<GasStrategy>,
+    /// Re-price with a replacement when still unmined after this long; 0 never replaces
+    pub replace_after: u64, // seconds
+    pub max_replacements: u32,
 }
 
 impl Default for TransactionConfig {
@@ -73,6 +91,9 @@ impl Default for TransactionConfig {
             nonce: None,
             confirmations: 3,
             timeout: 300, // 5 minutes
+            strategy: None,
+            replace_after: 90,
+            max_replacements: 3,
         }
     }
 }
@@ -355,6 +376,10 @@ pub struct BlockchainConfig {
     pub default_network: String,
     pub retry_config: RetryConfig,
     pub monitoring: MonitoringConfig,
+    #[serde(default)]
+    pub gas: GasConfig,
+    #[serde(default)]
+    pub nonce: NonceConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,7 +1,10 @@
+use crate::blockchain::remote_signer::{AwsKmsSigner, DigestSigner, GcpKmsSigner, RemoteSigner, VaultSigner};
 use crate::blockchain::types::*;
 use crate::utils::crypto::{encrypt_data, decrypt_data};
 use ethers::prelude::*;
 use ethers::signers::coins_bip39::{English, Mnemonic as EthersMnemonic};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
 use ethers::utils::keccak256;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -324,34 +327,13 @@ impl WalletManager {
             .map_err(|e| BlockchainError::Wallet(format!("Failed to derive wallet: {}", e)))
     }
 
-    /// Derive wallet from seed using proper BIP44 derivation path
+    /// Derive wallet from seed using proper BIP44 derivation path: m/44'/60'/account'/0/0
     fn derive_wallet_from_seed_bip44(&self, seed: &[u8], account_index: u32) -> BlockchainResult<LocalWallet> {
-        use bip39::{Mnemonic, Language, Seed as BipSeed};
-        use hdwallet::{ExtendedPrivKey, KeyIndex};
-        use secp256k1::Secp256k1;
-
-        // Create secp256k1 context
-        let secp = Secp256k1::new();
-
-        // Create master key from seed
-        let master_key = ExtendedPrivKey::new_master(&secp, seed)
-            .map_err(|e| BlockchainError::Wallet(format!("Failed to create master key: {}", e)))?;
-
-        // Derive using BIP44 path: m/44'/60'/account'/0/0
-        let derived_key = master_key
-            .derive_priv(&secp, &[
-                KeyIndex::Hardened(44),           // Purpose: BIP44
-                KeyIndex::Hardened(60),           // Coin type: Ethereum
-                KeyIndex::Hardened(account_index), // Account
-                KeyIndex::Normal(0),              // Change: 0 (external)
-                KeyIndex::Normal(0),              // Address index: 0
-            ])
-            .map_err(|e| BlockchainError::Wallet(format!("Failed to derive key: {}", e)))?;
-
-        // Create LocalWallet from derived private key
-        let private_key_bytes = derived_key.private_key.secret_bytes();
-        LocalWallet::from_bytes(&private_key_bytes)
-            .map_err(|e| BlockchainError::Wallet(format!("Failed to create wallet: {}", e)))
+        use crate::utils::hd_wallet::{DerivationPath, MasterSeed};
+
+        MasterSeed::from_seed(seed)
+            .and_then(|master| master.derive(&DerivationPath::ethereum(account_index)))
+            .map_err(|e| BlockchainError::Wallet(format!("Failed to derive wallet: {}", e)))
     }
 
     /// Get next available derivation index
@@ -427,5 +409,150 @@ pub struct WalletStatistics {
     pub oldest_wallet_age: u64, // seconds
 }
 
+/// Where the platform wallet's key lives
+#[derive(Clone)]
+pub enum SignerConfig {
+    /// Private key held in process memory; for development and tests
+    Local { private_key: String },
+    /// AWS KMS asymmetric key with key spec `ECC_SECG_P256K1`, by key ID, ARN or alias
+    AwsKms { key_id: String, region: Option<String> },
+    /// Cloud KMS key version with algorithm `EC_SIGN_SECP256K1_SHA256`
+    GcpKms { key_version: String },
+    /// secp256k1 key in a Vault secrets engine with the transit API
+    Vault {
+        addr: String,
+        mount: String,
+        key: String,
+        token_file: Option<String>,
+    },
+}
+
+impl std::fmt::Debug for SignerConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Local { .. } => f.debug_struct("Local").field("private_key", &"[redacted]").finish(),
+            Self::AwsKms { key_id, region } => f
+                .debug_struct("AwsKms")
+                .field("key_id", key_id)
+                .field("region", region)
+                .finish(),
+            Self::GcpKms { key_version } => f.debug_struct("GcpKms").field("key_version", key_version).finish(),
+            Self::Vault { addr, mount, key, .. } => f
+                .debug_struct("Vault")
+                .field("addr", addr)
+                .field("mount", mount)
+                .field("key", key)
+                .finish(),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SignerError {
+    #[error(transparent)]
+    Local(#[from] WalletError),
+    #[error("Remote signer error: {0}")]
+    Remote(String),
+}
+
+/// Signer for the platform wallet that pays for contract writes: a local key or a remote one
+/// that signs without the key ever entering this process
+#[derive(Debug, Clone)]
+pub enum PlatformSigner {
+    Local(LocalWallet),
+    Remote(RemoteSigner),
+}
+
+impl PlatformSigner {
+    pub async fn connect(config: &SignerConfig, chain_id: u64) -> BlockchainResult<Self> {
+        let signer = match config {
+            SignerConfig::Local { private_key } => {
+                let wallet: LocalWallet = private_key
+                    .parse()
+                    .map_err(|e| BlockchainError::Wallet(format!("Invalid private key: {}", e)))?;
+                Self::Local(wallet)
+            }
+            SignerConfig::AwsKms { key_id, region } => {
+                let backend = AwsKmsSigner::connect(key_id.clone(), region.clone()).await;
+                Self::remote(Arc::new(backend)).await?
+            }
+            SignerConfig::GcpKms { key_version } => Self::remote(Arc::new(GcpKmsSigner::new(key_version.clone()))).await?,
+            SignerConfig::Vault {
+                addr,
+                mount,
+                key,
+                token_file,
+            } => {
+                let backend = VaultSigner::new(addr.clone(), mount.clone(), key.clone(), token_file.clone());
+                Self::remote(Arc::new(backend)).await?
+            }
+        };
+
+        info!("Platform wallet {:?} signs with {}", signer.address(), signer.backend_name());
+        Ok(signer.with_chain_id(chain_id))
+    }
+
+    async fn remote(backend: Arc<dyn DigestSigner>) -> BlockchainResult<Self> {
+        RemoteSigner::new(backend)
+            .await
+            .map(Self::Remote)
+            .map_err(|e| BlockchainError::Wallet(format!("Failed to connect to the remote signer: {}", e)))
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        match self {
+            Self::Local(_) => "local",
+            Self::Remote(signer) => signer.backend_name(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Signer for PlatformSigner {
+    type Error = SignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(&self, message: S) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => Ok(wallet.sign_message(message).await?),
+            Self::Remote(signer) => signer.sign_message(message).await,
+        }
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => Ok(wallet.sign_transaction(tx).await?),
+            Self::Remote(signer) => signer.sign_transaction(tx).await,
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(&self, payload: &T) -> Result<Signature, Self::Error> {
+        match self {
+            Self::Local(wallet) => Ok(wallet.sign_typed_data(payload).await?),
+            Self::Remote(signer) => signer.sign_typed_data(payload).await,
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            Self::Local(wallet) => wallet.address(),
+            Self::Remote(signer) => signer.address(),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            Self::Local(wallet) => wallet.chain_id(),
+            Self::Remote(signer) => signer.chain_id(),
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            Self::Local(wallet) => Self::Local(wallet.with_chain_id(chain_id)),
+            Self::Remote(signer) => Self::Remote(signer.with_chain_id(chain_id)),
+        }
+    }
+}
+
 // Additional imports needed
 use sha2::{Digest, Sha256};
\ No newline at end of file
@@ -1,11 +1,65 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, Serializer};
 use std::env;
 
+/// Deployment environment, set with `ENVIRONMENT`; picks the profile defaults below
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    #[default]
+    #[serde(alias = "dev", alias = "local")]
+    Development,
+    #[serde(alias = "stage")]
+    Staging,
+    #[serde(alias = "prod")]
+    Production,
+}
+
+/// Settings that differ per environment unless overridden explicitly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileDefaults {
+    pub log_level: &'static str,
+    pub secure_cookies: bool,
+    pub cors_allowed_origins: Vec<String>,
+    pub allow_mock_services: bool,
+}
+
+impl Environment {
+    pub fn defaults(&self) -> ProfileDefaults {
+        match self {
+            Environment::Development => ProfileDefaults {
+                log_level: "debug",
+                secure_cookies: false,
+                cors_allowed_origins: vec![
+                    "http://localhost:3000".to_string(),
+                    "http://127.0.0.1:3000".to_string(),
+                ],
+                allow_mock_services: true,
+            },
+            Environment::Staging => ProfileDefaults {
+                log_level: "info",
+                secure_cookies: true,
+                cors_allowed_origins: Vec::new(),
+                allow_mock_services: true,
+            },
+            Environment::Production => ProfileDefaults {
+                log_level: "info",
+                secure_cookies: true,
+                cors_allowed_origins: Vec::new(),
+                allow_mock_services: false,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct AppConfig {
+    #[serde(default)]
+    pub environment: Environment,
     pub host: String,
     pub port: u16,
     pub database_url: String,
+    /// Read replica for analytics, search and statistics queries; unset keeps them on the primary
+    pub database_read_url: Option<String>,
     pub redis_url: String,
     pub jwt_secret: String,
     pub stripe_secret_key: String,
@@ -13,7 +67,259 @@ pub struct AppConfig {
     pub blockchain_rpc_url: String,
     pub blockchain_ws_url: String,
     pub contract_address: String,
+    /// Platform wallet key when `SIGNER_BACKEND` is local; not allowed in production
+    #[serde(default)]
     pub deployer_private_key: String,
+    /// Where the platform wallet's key lives; unset means local
+    pub signer_backend: Option<SignerBackendKind>,
+    /// AWS KMS key ID, ARN or alias; Cloud KMS key version name; or Vault key name
+    pub signer_key_id: Option<String>,
+    /// AWS region of the KMS key; unset uses the standard AWS region settings
+    pub signer_region: Option<String>,
+    pub vault_addr: Option<String>,
+    /// Mount of the secrets engine holding the key; defaults to `transit`
+    pub vault_mount: Option<String>,
+    /// Token file kept fresh by Vault Agent; unset reads `VAULT_TOKEN`
+    pub vault_token_file: Option<String>,
+    /// BIP-39 mnemonic every user wallet is derived from; required in production
+    pub wallet_master_seed: Option<String>,
+    pub wallet_master_seed_passphrase: Option<String>,
+    /// Master keys wrapping per-user wallet data keys, `id:base64-key` pairs separated by
+    /// commas; keep a rotated-out key listed until its data keys are rewrapped
+    pub wallet_key_encryption_keys: Option<String>,
+    /// Id of the master key new data keys are wrapped with; defaults to the first listed
+    pub wallet_key_encryption_key_id: Option<String>,
+    pub cdn_base_url: Option<String>,
+    pub cdn_signing_key: Option<String>,
+    pub cdn_url_ttl_seconds: Option<i64>,
+    pub media_origin_url: Option<String>,
+    /// Base URL PURGE requests are sent to when cached listings change; unset skips purging
+    pub edge_cache_purge_url: Option<String>,
+    pub tax_data_encryption_key: Option<String>,
+    pub fx_rates_url: Option<String>,
+    pub fx_rates_api_key: Option<String>,
+    pub fx_base_currency: Option<String>,
+    /// Apple Pay domain association file from the Stripe dashboard; unset leaves Apple Pay unverified
+    pub apple_pay_domain_association_path: Option<String>,
+    /// Plain text list of disposable email domains, one per line
+    pub disposable_email_domains_url: Option<String>,
+    /// IP geolocation lookup for geo-velocity checks; unset disables them
+    pub ip_geo_url: Option<String>,
+    pub ip_geo_api_key: Option<String>,
+    /// Identity verification provider for seller and winner KYC; unset means only admins can verify
+    pub kyc_provider_url: Option<String>,
+    pub kyc_provider_api_key: Option<String>,
+    pub startup_retry_attempts: Option<u32>,
+    pub startup_retry_base_seconds: Option<i64>,
+    pub startup_retry_max_seconds: Option<i64>,
+    /// Profile overrides; unset means the environment's default
+    pub log_level: Option<String>,
+    pub secure_cookies: Option<bool>,
+    /// Comma separated; `*` allows any origin
+    pub cors_allowed_origins: Option<String>,
+    pub allow_mock_services: Option<bool>,
+    /// Public web app URL used for links in emails
+    pub app_base_url: Option<String>,
+    /// Unset means emails are only logged
+    pub email_provider: Option<EmailProviderKind>,
+    pub email_from_address: Option<String>,
+    pub email_from_name: Option<String>,
+    pub smtp_host: Option<String>,
+    pub smtp_port: Option<u16>,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    /// SES credentials come from the standard AWS environment variables or instance role
+    pub ses_region: Option<String>,
+    /// Shared token expected on bounce and complaint notifications from SES
+    pub email_webhook_token: Option<String>,
+    /// Directory of `pg_dump --format=custom` files (`*.dump`); unset disables restore drills
+    pub backup_dir: Option<String>,
+    /// Database each drill restores into; everything in it is dropped first
+    pub backup_scratch_database_url: Option<String>,
+    /// Defaults to `pg_restore` on the PATH
+    pub pg_restore_path: Option<String>,
+    /// Unset means SMS notifications are only logged
+    pub sms_provider: Option<SmsProviderKind>,
+    pub twilio_account_sid: Option<String>,
+    pub twilio_auth_token: Option<String>,
+    /// E.164 number or messaging service SID messages are sent from
+    pub twilio_from_number: Option<String>,
+    /// Firebase service account JSON; unset means Android push notifications are only logged
+    pub fcm_credentials_path: Option<String>,
+    /// APNs `.p8` signing key; unset means iOS push notifications are only logged
+    pub apns_key_path: Option<String>,
+    pub apns_key_id: Option<String>,
+    pub apns_team_id: Option<String>,
+    /// The iOS app's bundle id
+    pub apns_topic: Option<String>,
+    /// Send through the APNs development gateway, for debug builds of the app
+    pub apns_sandbox: Option<bool>,
+}
+
+/// Transport used to send email, set with `EMAIL_PROVIDER`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailProviderKind {
+    Smtp,
+    Ses,
+}
+
+/// Where the platform wallet's key lives, set with `SIGNER_BACKEND`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerBackendKind {
+    Local,
+    AwsKms,
+    GcpKms,
+    Vault,
+}
+
+/// Transport used to send text messages, set with `SMS_PROVIDER`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmsProviderKind {
+    Twilio,
+}
+
+/// HTTP server, logging, cookie and CORS settings
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    pub log_level: String,
+    pub secure_cookies: bool,
+    pub cors_allowed_origins: Vec<String>,
+}
+
+/// Postgres and Redis connections
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageConfig {
+    #[serde(serialize_with = "redact_url")]
+    pub database_url: String,
+    #[serde(serialize_with = "redact_optional_url")]
+    pub database_read_url: Option<String>,
+    #[serde(serialize_with = "redact_url")]
+    pub redis_url: String,
+    pub startup_retry_attempts: Option<u32>,
+    pub startup_retry_base_seconds: Option<i64>,
+    pub startup_retry_max_seconds: Option<i64>,
+    pub backup_dir: Option<String>,
+    #[serde(serialize_with = "redact_optional_url")]
+    pub backup_scratch_database_url: Option<String>,
+    pub pg_restore_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthConfig {
+    #[serde(serialize_with = "redact")]
+    pub jwt_secret: String,
+    pub secure_cookies: bool,
+    pub disposable_email_domains_url: Option<String>,
+    pub ip_geo_url: Option<String>,
+    #[serde(serialize_with = "redact_optional")]
+    pub ip_geo_api_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PaymentsConfig {
+    #[serde(serialize_with = "redact")]
+    pub stripe_secret_key: String,
+    #[serde(serialize_with = "redact")]
+    pub stripe_webhook_secret: String,
+    pub fx_rates_url: Option<String>,
+    #[serde(serialize_with = "redact_optional")]
+    pub fx_rates_api_key: Option<String>,
+    pub fx_base_currency: Option<String>,
+    pub apple_pay_domain_association_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainConfig {
+    pub rpc_url: String,
+    pub ws_url: String,
+    pub contract_address: String,
+    #[serde(serialize_with = "redact")]
+    pub deployer_private_key: String,
+    pub signer_backend: SignerBackendKind,
+    pub signer_key_id: Option<String>,
+    #[serde(serialize_with = "redact_optional")]
+    pub wallet_master_seed: Option<String>,
+    #[serde(serialize_with = "redact_optional")]
+    pub wallet_master_seed_passphrase: Option<String>,
+    #[serde(serialize_with = "redact_optional")]
+    pub wallet_key_encryption_keys: Option<String>,
+    pub wallet_key_encryption_key_id: Option<String>,
+    pub allow_mock_services: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaConfig {
+    pub cdn_base_url: Option<String>,
+    #[serde(serialize_with = "redact_optional")]
+    pub cdn_signing_key: Option<String>,
+    pub cdn_url_ttl_seconds: Option<i64>,
+    pub media_origin_url: Option<String>,
+    pub edge_cache_purge_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmailConfig {
+    pub provider: Option<EmailProviderKind>,
+    pub app_base_url: Option<String>,
+    pub from_address: Option<String>,
+    pub from_name: Option<String>,
+    pub smtp_host: Option<String>,
+    pub smtp_port: Option<u16>,
+    pub smtp_username: Option<String>,
+    #[serde(serialize_with = "redact_optional")]
+    pub smtp_password: Option<String>,
+    pub ses_region: Option<String>,
+    #[serde(serialize_with = "redact_optional")]
+    pub webhook_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NotificationsConfig {
+    pub sms_provider: Option<SmsProviderKind>,
+    pub twilio_account_sid: Option<String>,
+    #[serde(serialize_with = "redact_optional")]
+    pub twilio_auth_token: Option<String>,
+    pub twilio_from_number: Option<String>,
+    pub fcm_credentials_path: Option<String>,
+    pub apns_key_path: Option<String>,
+    pub apns_key_id: Option<String>,
+    pub apns_team_id: Option<String>,
+    pub apns_topic: Option<String>,
+    pub apns_sandbox: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComplianceConfig {
+    pub kyc_provider_url: Option<String>,
+    #[serde(serialize_with = "redact_optional")]
+    pub kyc_provider_api_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaxConfig {
+    #[serde(serialize_with = "redact_optional")]
+    pub tax_data_encryption_key: Option<String>,
+}
+
+/// The configuration the backend is actually running with; secrets serialize redacted
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveConfig {
+    pub environment: Environment,
+    pub server: ServerConfig,
+    pub storage: StorageConfig,
+    pub auth: AuthConfig,
+    pub payments: PaymentsConfig,
+    pub blockchain: ChainConfig,
+    pub media: MediaConfig,
+    pub email: EmailConfig,
+    pub notifications: NotificationsConfig,
+    pub compliance: ComplianceConfig,
+    pub tax: TaxConfig,
 }
 
 impl AppConfig {
@@ -26,6 +332,392 @@ impl AppConfig {
             .add_source(config::Environment::default())
             .build()?;
 
-        config.try_deserialize()
+        let app_config: Self = config.try_deserialize()?;
+        app_config.validate()?;
+
+        Ok(app_config)
+    }
+
+    pub fn server(&self) -> ServerConfig {
+        ServerConfig {
+            host: self.host.clone(),
+            port: self.port,
+            log_level: self.log_level(),
+            secure_cookies: self.secure_cookies(),
+            cors_allowed_origins: self.cors_allowed_origins(),
+        }
+    }
+
+    pub fn storage(&self) -> StorageConfig {
+        StorageConfig {
+            database_url: self.database_url.clone(),
+            database_read_url: self.database_read_url.clone(),
+            redis_url: self.redis_url.clone(),
+            startup_retry_attempts: self.startup_retry_attempts,
+            startup_retry_base_seconds: self.startup_retry_base_seconds,
+            startup_retry_max_seconds: self.startup_retry_max_seconds,
+            backup_dir: self.backup_dir.clone(),
+            backup_scratch_database_url: self.backup_scratch_database_url.clone(),
+            pg_restore_path: self.pg_restore_path.clone(),
+        }
+    }
+
+    pub fn auth(&self) -> AuthConfig {
+        AuthConfig {
+            jwt_secret: self.jwt_secret.clone(),
+            secure_cookies: self.secure_cookies(),
+            disposable_email_domains_url: self.disposable_email_domains_url.clone(),
+            ip_geo_url: self.ip_geo_url.clone(),
+            ip_geo_api_key: self.ip_geo_api_key.clone(),
+        }
+    }
+
+    pub fn payments(&self) -> PaymentsConfig {
+        PaymentsConfig {
+            stripe_secret_key: self.stripe_secret_key.clone(),
+            stripe_webhook_secret: self.stripe_webhook_secret.clone(),
+            fx_rates_url: self.fx_rates_url.clone(),
+            fx_rates_api_key: self.fx_rates_api_key.clone(),
+            fx_base_currency: self.fx_base_currency.clone(),
+            apple_pay_domain_association_path: self.apple_pay_domain_association_path.clone(),
+        }
+    }
+
+    pub fn blockchain(&self) -> ChainConfig {
+        ChainConfig {
+            rpc_url: self.blockchain_rpc_url.clone(),
+            ws_url: self.blockchain_ws_url.clone(),
+            contract_address: self.contract_address.clone(),
+            deployer_private_key: self.deployer_private_key.clone(),
+            signer_backend: self.signer_backend(),
+            signer_key_id: self.signer_key_id.clone(),
+            wallet_master_seed: self.wallet_master_seed.clone(),
+            wallet_master_seed_passphrase: self.wallet_master_seed_passphrase.clone(),
+            wallet_key_encryption_keys: self.wallet_key_encryption_keys.clone(),
+            wallet_key_encryption_key_id: self.wallet_key_encryption_key_id.clone(),
+            allow_mock_services: self.allow_mock_services(),
+        }
+    }
+
+    pub fn media(&self) -> MediaConfig {
+        MediaConfig {
+            cdn_base_url: self.cdn_base_url.clone(),
+            cdn_signing_key: self.cdn_signing_key.clone(),
+            cdn_url_ttl_seconds: self.cdn_url_ttl_seconds,
+            media_origin_url: self.media_origin_url.clone(),
+            edge_cache_purge_url: self.edge_cache_purge_url.clone(),
+        }
     }
-}
\ No newline at end of file
+
+    pub fn email(&self) -> EmailConfig {
+        EmailConfig {
+            provider: self.email_provider,
+            app_base_url: self.app_base_url.clone(),
+            from_address: self.email_from_address.clone(),
+            from_name: self.email_from_name.clone(),
+            smtp_host: self.smtp_host.clone(),
+            smtp_port: self.smtp_port,
+            smtp_username: self.smtp_username.clone(),
+            smtp_password: self.smtp_password.clone(),
+            ses_region: self.ses_region.clone(),
+            webhook_token: self.email_webhook_token.clone(),
+        }
+    }
+
+    pub fn notifications(&self) -> NotificationsConfig {
+        NotificationsConfig {
+            sms_provider: self.sms_provider,
+            twilio_account_sid: self.twilio_account_sid.clone(),
+            twilio_auth_token: self.twilio_auth_token.clone(),
+            twilio_from_number: self.twilio_from_number.clone(),
+            fcm_credentials_path: self.fcm_credentials_path.clone(),
+            apns_key_path: self.apns_key_path.clone(),
+            apns_key_id: self.apns_key_id.clone(),
+            apns_team_id: self.apns_team_id.clone(),
+            apns_topic: self.apns_topic.clone(),
+            apns_sandbox: self.apns_sandbox.unwrap_or(false),
+        }
+    }
+
+    pub fn compliance(&self) -> ComplianceConfig {
+        ComplianceConfig {
+            kyc_provider_url: self.kyc_provider_url.clone(),
+            kyc_provider_api_key: self.kyc_provider_api_key.clone(),
+        }
+    }
+
+    pub fn tax(&self) -> TaxConfig {
+        TaxConfig {
+            tax_data_encryption_key: self.tax_data_encryption_key.clone(),
+        }
+    }
+
+    /// Every section, for the admin configuration endpoint
+    pub fn effective(&self) -> EffectiveConfig {
+        EffectiveConfig {
+            environment: self.environment,
+            server: self.server(),
+            storage: self.storage(),
+            auth: self.auth(),
+            payments: self.payments(),
+            blockchain: self.blockchain(),
+            media: self.media(),
+            email: self.email(),
+            notifications: self.notifications(),
+            compliance: self.compliance(),
+            tax: self.tax(),
+        }
+    }
+
+    pub fn log_level(&self) -> String {
+        self.log_level
+            .clone()
+            .or_else(|| env::var("RUST_LOG").ok())
+            .unwrap_or_else(|| self.environment.defaults().log_level.to_string())
+    }
+
+    pub fn secure_cookies(&self) -> bool {
+        self.secure_cookies
+            .unwrap_or_else(|| self.environment.defaults().secure_cookies)
+    }
+
+    pub fn cors_allowed_origins(&self) -> Vec<String> {
+        match &self.cors_allowed_origins {
+            Some(origins) => parse_origins(origins),
+            None => self.environment.defaults().cors_allowed_origins,
+        }
+    }
+
+    pub fn signer_backend(&self) -> SignerBackendKind {
+        self.signer_backend.unwrap_or(SignerBackendKind::Local)
+    }
+
+    /// Signer for the platform wallet; `validate` has checked the backend's settings are present
+    pub fn platform_signer(&self) -> crate::blockchain::SignerConfig {
+        use crate::blockchain::SignerConfig;
+
+        let key_id = self.signer_key_id.clone().unwrap_or_default();
+        match self.signer_backend() {
+            SignerBackendKind::Local => SignerConfig::Local {
+                private_key: self.deployer_private_key.clone(),
+            },
+            SignerBackendKind::AwsKms => SignerConfig::AwsKms {
+                key_id,
+                region: self.signer_region.clone(),
+            },
+            SignerBackendKind::GcpKms => SignerConfig::GcpKms { key_version: key_id },
+            SignerBackendKind::Vault => SignerConfig::Vault {
+                addr: self.vault_addr.clone().unwrap_or_default(),
+                mount: self.vault_mount.clone().unwrap_or_else(|| "transit".to_string()),
+                key: key_id,
+                token_file: self.vault_token_file.clone(),
+            },
+        }
+    }
+
+    /// Master keys for wallet envelope encryption; `None` leaves secrets encrypted with the
+    /// password only
+    pub fn wallet_key_ring(&self) -> Result<Option<crate::utils::envelope::KeyRing>, crate::error::AppError> {
+        self.wallet_key_encryption_keys
+            .as_deref()
+            .filter(|keys| !keys.trim().is_empty())
+            .map(|keys| crate::utils::envelope::KeyRing::parse(keys, self.wallet_key_encryption_key_id.as_deref()))
+            .transpose()
+    }
+
+    pub fn allow_mock_services(&self) -> bool {
+        self.allow_mock_services
+            .unwrap_or_else(|| self.environment.defaults().allow_mock_services)
+    }
+
+    // Private helper methods
+
+    /// Refuse settings that are only safe outside production
+    fn validate(&self) -> Result<(), config::ConfigError> {
+        if self.email_provider.is_some() && self.email_from_address.is_none() {
+            return Err(config::ConfigError::Message(
+                "EMAIL_FROM_ADDRESS is required when EMAIL_PROVIDER is set".to_string(),
+            ));
+        }
+        if self.email_provider == Some(EmailProviderKind::Smtp) && self.smtp_host.is_none() {
+            return Err(config::ConfigError::Message(
+                "SMTP_HOST is required when EMAIL_PROVIDER is smtp".to_string(),
+            ));
+        }
+        if self.sms_provider == Some(SmsProviderKind::Twilio)
+            && (self.twilio_account_sid.is_none()
+                || self.twilio_auth_token.is_none()
+                || self.twilio_from_number.is_none())
+        {
+            return Err(config::ConfigError::Message(
+                "TWILIO_ACCOUNT_SID, TWILIO_AUTH_TOKEN and TWILIO_FROM_NUMBER are required when SMS_PROVIDER is twilio"
+                    .to_string(),
+            ));
+        }
+        if self.apns_key_path.is_some()
+            && (self.apns_key_id.is_none() || self.apns_team_id.is_none() || self.apns_topic.is_none())
+        {
+            return Err(config::ConfigError::Message(
+                "APNS_KEY_ID, APNS_TEAM_ID and APNS_TOPIC are required when APNS_KEY_PATH is set".to_string(),
+            ));
+        }
+        if self.kyc_provider_url.is_some() && self.kyc_provider_api_key.is_none() {
+            return Err(config::ConfigError::Message(
+                "KYC_PROVIDER_API_KEY is required when KYC_PROVIDER_URL is set".to_string(),
+            ));
+        }
+        match self.signer_backend() {
+            SignerBackendKind::Local if self.deployer_private_key.trim().is_empty() => {
+                return Err(config::ConfigError::Message(
+                    "DEPLOYER_PRIVATE_KEY is required when SIGNER_BACKEND is local".to_string(),
+                ));
+            }
+            SignerBackendKind::Local => {}
+            _ if self.signer_key_id.as_deref().unwrap_or("").trim().is_empty() => {
+                return Err(config::ConfigError::Message(
+                    "SIGNER_KEY_ID is required when SIGNER_BACKEND is a remote signer".to_string(),
+                ));
+            }
+            SignerBackendKind::Vault if self.vault_addr.is_none() => {
+                return Err(config::ConfigError::Message(
+                    "VAULT_ADDR is required when SIGNER_BACKEND is vault".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        if let Err(crate::error::AppError::Internal(message)) = self.wallet_key_ring() {
+            return Err(config::ConfigError::Message(format!(
+                "WALLET_KEY_ENCRYPTION_KEYS is not usable: {}",
+                message
+            )));
+        }
+        if self.backup_dir.is_some() && self.backup_scratch_database_url.is_none() {
+            return Err(config::ConfigError::Message(
+                "BACKUP_SCRATCH_DATABASE_URL is required when BACKUP_DIR is set".to_string(),
+            ));
+        }
+        if self.backup_scratch_database_url.as_deref() == Some(self.database_url.as_str()) {
+            return Err(config::ConfigError::Message(
+                "BACKUP_SCRATCH_DATABASE_URL must not be the main database; drills wipe it".to_string(),
+            ));
+        }
+
+        if self.environment != Environment::Production {
+            return Ok(());
+        }
+
+        if self.allow_mock_services() {
+            return Err(config::ConfigError::Message(
+                "ALLOW_MOCK_SERVICES cannot be enabled in production".to_string(),
+            ));
+        }
+        if !self.secure_cookies() {
+            return Err(config::ConfigError::Message(
+                "SECURE_COOKIES cannot be disabled in production".to_string(),
+            ));
+        }
+        if self.cors_allowed_origins().iter().any(|origin| origin == "*") {
+            return Err(config::ConfigError::Message(
+                "CORS_ALLOWED_ORIGINS cannot allow any origin in production".to_string(),
+            ));
+        }
+        if self.wallet_master_seed.as_deref().unwrap_or("").trim().is_empty() {
+            return Err(config::ConfigError::Message(
+                "WALLET_MASTER_SEED is required in production".to_string(),
+            ));
+        }
+        if self.wallet_key_encryption_keys.as_deref().unwrap_or("").trim().is_empty() {
+            return Err(config::ConfigError::Message(
+                "WALLET_KEY_ENCRYPTION_KEYS is required in production".to_string(),
+            ));
+        }
+        if self.signer_backend() == SignerBackendKind::Local {
+            return Err(config::ConfigError::Message(
+                "SIGNER_BACKEND must be aws_kms, gcp_kms or vault in production; the platform key may not be loaded into the process".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_origins(origins: &str) -> Vec<String> {
+    origins
+        .split(',')
+        .map(|origin| origin.trim().trim_end_matches('/').to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect()
+}
+
+/// Shows whether a secret is set without revealing it
+fn redact<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(if value.is_empty() { "" } else { "[redacted]" })
+}
+
+fn redact_optional<S: Serializer>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => redact(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Keeps scheme, host and path of a connection URL but hides its password
+fn redact_url<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&redact_url_password(value))
+}
+
+fn redact_optional_url<S: Serializer>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => redact_url(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn redact_url_password(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return "[redacted]".to_string();
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => rest.split_at(idx),
+        None => (rest, ""),
+    };
+    let Some((userinfo, host)) = authority.rsplit_once('@') else {
+        return url.to_string();
+    };
+
+    match userinfo.split_once(':') {
+        Some((user, _)) => format!("{}://{}:[redacted]@{}{}", scheme, user, host, path),
+        None => format!("{}://{}@{}{}", scheme, userinfo, host, path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_origins() {
+        assert_eq!(
+            parse_origins(" https://thriftee.com/, https://admin.thriftee.com ,,"),
+            vec!["https://thriftee.com", "https://admin.thriftee.com"]
+        );
+    }
+
+    #[test]
+    fn test_redact_url_password() {
+        assert_eq!(
+            redact_url_password("postgres://raffle:s3cret@db:5432/raffle"),
+            "postgres://raffle:[redacted]@db:5432/raffle"
+        );
+        assert_eq!(redact_url_password("redis://localhost:6379"), "redis://localhost:6379");
+        assert_eq!(redact_url_password("not a url"), "[redacted]");
+    }
+
+    #[test]
+    fn test_production_defaults_are_strict() {
+        let defaults = Environment::Production.defaults();
+        assert!(defaults.secure_cookies);
+        assert!(!defaults.allow_mock_services);
+        assert!(defaults.cors_allowed_origins.is_empty());
+    }
+}
@@ -1,21 +1,108 @@
 use sqlx::{PgPool, Pool, Postgres};
 use std::time::Duration;
+use tracing::{info, warn};
+use crate::config::AppConfig;
 use crate::error::AppError;
+use crate::services::webhook_service::retry_delay_seconds;
 
+const DEFAULT_STARTUP_RETRY_ATTEMPTS: u32 = 10;
+const DEFAULT_STARTUP_RETRY_BASE_SECONDS: i64 = 1;
+const DEFAULT_STARTUP_RETRY_MAX_SECONDS: i64 = 30;
+
+/// How long to keep trying a dependency that isn't up yet when the backend boots
+#[derive(Debug, Clone, Copy)]
+pub struct StartupRetry {
+    pub max_attempts: u32,
+    pub base_seconds: i64,
+    pub max_seconds: i64,
+}
+
+impl StartupRetry {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            max_attempts: config
+                .startup_retry_attempts
+                .filter(|n| *n > 0)
+                .unwrap_or(DEFAULT_STARTUP_RETRY_ATTEMPTS),
+            base_seconds: config
+                .startup_retry_base_seconds
+                .filter(|n| *n > 0)
+                .unwrap_or(DEFAULT_STARTUP_RETRY_BASE_SECONDS),
+            max_seconds: config
+                .startup_retry_max_seconds
+                .filter(|n| *n > 0)
+                .unwrap_or(DEFAULT_STARTUP_RETRY_MAX_SECONDS),
+        }
+    }
+
+    /// Wait after `failed_attempts` failures, doubling up to the maximum
+    pub fn delay(&self, failed_attempts: u32) -> Duration {
+        let seconds = retry_delay_seconds(failed_attempts as i64, self.base_seconds, self.max_seconds);
+        Duration::from_secs(seconds.max(0) as u64)
+    }
+}
+
+/// Primary pool for writes and reads that must see them, plus an optional read replica for
+/// heavy read-only queries (analytics, search, statistics)
 #[derive(Clone)]
 pub struct Database {
     pool: PgPool,
+    read_pool: Option<PgPool>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PoolStatistics {
+    pub size: u32,
+    pub idle: usize,
+    pub max_connections: u32,
 }
 
 impl Database {
     pub async fn new(database_url: &str) -> Result<Self, AppError> {
-        let pool = sqlx::postgres::PgPoolOptions::new()
-            .max_connections(20)
-            .acquire_timeout(Duration::from_secs(30))
-            .connect(database_url)
-            .await?;
+        let pool = connect_pool(database_url).await?;
 
-        Ok(Database { pool })
+        Ok(Database { pool, read_pool: None })
+    }
+
+    /// Send read-only queries to this replica. A replica that can't be reached is logged and
+    /// skipped so reads stay on the primary rather than failing startup.
+    pub async fn with_read_replica(mut self, read_database_url: &str) -> Self {
+        match connect_pool(read_database_url).await {
+            Ok(read_pool) => {
+                info!("Routing read-only queries to the read replica");
+                self.read_pool = Some(read_pool);
+            }
+            Err(e) => warn!("Read replica not reachable, reads stay on the primary: {}", e),
+        }
+        self
+    }
+
+    /// Connect, retrying with backoff while Postgres is still starting
+    pub async fn connect_with_retry(database_url: &str, retry: StartupRetry) -> Result<Self, AppError> {
+        let mut attempt = 1;
+        loop {
+            match Self::new(database_url).await {
+                Ok(database) => {
+                    if attempt > 1 {
+                        info!("Connected to database after {} attempts", attempt);
+                    }
+                    return Ok(database);
+                }
+                Err(e) if attempt < retry.max_attempts => {
+                    let delay = retry.delay(attempt);
+                    warn!(
+                        "Database not reachable (attempt {}/{}): {}; retrying in {}s",
+                        attempt,
+                        retry.max_attempts,
+                        e,
+                        delay.as_secs()
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
     }
 
     pub async fn migrate(&self) -> Result<(), AppError> {
@@ -28,4 +115,32 @@ impl Database {
     pub fn pool(&self) -> &PgPool {
         &self.pool
     }
-}
\ No newline at end of file
+
+    /// Pool for read-only queries that tolerate replication lag; the primary when no replica
+    /// is configured
+    pub fn read_pool(&self) -> &PgPool {
+        self.read_pool.as_ref().unwrap_or(&self.pool)
+    }
+
+    pub fn get_pool_stats(&self) -> PoolStatistics {
+        PoolStatistics {
+            size: self.pool.size(),
+            idle: self.pool.num_idle(),
+            max_connections: self.pool.options().get_max_connections(),
+        }
+    }
+}
+
+async fn connect_pool(database_url: &str) -> Result<PgPool, AppError> {
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(20)
+        .acquire_timeout(Duration::from_secs(30))
+        // Drop connections the server closed (restart, failover) instead of handing them out
+        .test_before_acquire(true)
+        .idle_timeout(Duration::from_secs(600))
+        .max_lifetime(Duration::from_secs(1800))
+        .connect(database_url)
+        .await?;
+
+    Ok(pool)
+}
@@ -18,6 +18,9 @@ pub enum AppError {
     #[error("Validation error: {0}")]
     Validation(String),
     
+    #[error("Validation error: {}", summarize(.0))]
+    InvalidFields(Vec<FieldError>),
+    
     #[error("Authentication error: {0}")]
     Authentication(String),
     
@@ -30,6 +33,9 @@ pub enum AppError {
     #[error("Conflict: {0}")]
     Conflict(String),
     
+    #[error("Rate limited: {message}")]
+    RateLimited { message: String, retry_after_seconds: i64 },
+    
     #[error("Internal server error: {0}")]
     Internal(String),
 }
@@ -41,6 +47,11 @@ impl ResponseError for AppError {
                 error: "validation_error".to_string(),
                 message: msg.clone(),
             }),
+            AppError::InvalidFields(fields) => HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "validation_error",
+                "message": summarize(fields),
+                "fields": fields,
+            })),
             AppError::Authentication(msg) => HttpResponse::Unauthorized().json(ErrorResponse {
                 error: "authentication_error".to_string(),
                 message: msg.clone(),
@@ -57,6 +68,14 @@ impl ResponseError for AppError {
                 error: "conflict".to_string(),
                 message: msg.clone(),
             }),
+            AppError::RateLimited { message, retry_after_seconds } => HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after_seconds.to_string()))
+                .json(serde_json::json!({
+                    "error": "rate_limited",
+                    "message": message,
+                    "retry_after_seconds": retry_after_seconds,
+                    "retry_at": chrono::Utc::now() + chrono::Duration::seconds(*retry_after_seconds)
+                })),
             _ => HttpResponse::InternalServerError().json(ErrorResponse {
                 error: "internal_server_error".to_string(),
                 message: "An internal server error occurred".to_string(),
@@ -65,8 +84,43 @@ impl ResponseError for AppError {
     }
 }
 
-#[derive(serde::Serialize)]
-struct ErrorResponse {
-    error: String,
-    message: String,
-}
\ No newline at end of file
+/// JSON body of every error response
+#[derive(serde::Serialize, utoipa::ToSchema)]
+pub struct ErrorResponse {
+    /// Error kind, e.g. `validation_error`
+    pub error: String,
+    pub message: String,
+}
+
+/// One rejected request field, returned under `fields` of a validation error
+#[derive(Debug, Clone, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct FieldError {
+    /// Path of the field in the request, e.g. `pricing_curve.tiers[1].box_price`
+    pub field: String,
+    /// Machine-readable reason, e.g. `above_maximum`
+    pub code: String,
+    pub message: String,
+    /// The bound the value broke, when there is one
+    #[schema(value_type = Option<Object>)]
+    pub limit: Option<serde_json::Value>,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            code: code.to_string(),
+            message: message.into(),
+            limit: None,
+        }
+    }
+
+    pub fn with_limit(mut self, limit: impl serde::Serialize) -> Self {
+        self.limit = serde_json::to_value(limit).ok();
+        self
+    }
+}
+
+fn summarize(fields: &[FieldError]) -> String {
+    fields.iter().map(|f| f.message.as_str()).collect::<Vec<_>>().join("; ")
+}
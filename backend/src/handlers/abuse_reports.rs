@@ -0,0 +1,170 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::abuse_report_service::{
+    AbuseReportService, CreateReportRequest, DismissReportRequest, ReportStatus, ResolveReportRequest,
+};
+use crate::error::AppError;
+use actix_web::{web, HttpResponse, Result};
+use serde::Deserialize;
+use tracing::info;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReportListQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TriageQueueQuery {
+    /// Defaults to reports still open or in review
+    pub status: Option<ReportStatus>,
+    pub limit: Option<i64>,
+}
+
+/// Report a listing, comment or user
+#[utoipa::path(
+    post,
+    path = "/api/v1/reports",
+    tag = "abuse_reports",
+    request_body = CreateReportRequest,
+    responses((status = 201, description = "Created", body = crate::services::abuse_report_service::AbuseReport)),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_report(
+    user: AuthenticatedUser,
+    request: web::Json<CreateReportRequest>,
+    abuse_report_service: web::Data<AbuseReportService>,
+) -> Result<HttpResponse, AppError> {
+    let report = abuse_report_service
+        .create_report(user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Created().json(report))
+}
+
+/// Get the reports the user has filed
+#[utoipa::path(
+    get,
+    path = "/api/v1/reports/mine",
+    tag = "abuse_reports",
+    params(ReportListQuery),
+    responses((status = 200, description = "Success", body = [crate::services::abuse_report_service::AbuseReport])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_my_reports(
+    user: AuthenticatedUser,
+    query: web::Query<ReportListQuery>,
+    abuse_report_service: web::Data<AbuseReportService>,
+) -> Result<HttpResponse, AppError> {
+    let reports = abuse_report_service
+        .list_for_reporter(user.user_id, query.limit.unwrap_or(50).clamp(1, 200))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(reports))
+}
+
+/// Get reports awaiting triage (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/abuse-reports",
+    tag = "abuse_reports",
+    params(TriageQueueQuery),
+    responses((status = 200, description = "Success", body = [crate::services::abuse_report_service::TriageEntry])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_triage_queue(
+    user: AuthenticatedUser,
+    query: web::Query<TriageQueueQuery>,
+    abuse_report_service: web::Data<AbuseReportService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let queue = abuse_report_service
+        .triage_queue(query.status, query.limit.unwrap_or(50).clamp(1, 200))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(queue))
+}
+
+/// Claim a report for review (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/abuse-reports/{report_id}/review",
+    tag = "abuse_reports",
+    params(("report_id" = Uuid, Path, description = "Report ID")),
+    responses((status = 200, description = "Success", body = crate::services::abuse_report_service::AbuseReport)),
+    security(("bearer_auth" = []))
+)]
+pub async fn start_review(
+    user: AuthenticatedUser,
+    report_id: web::Path<Uuid>,
+    abuse_report_service: web::Data<AbuseReportService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let report = abuse_report_service.start_review(*report_id, user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Act on a report (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/abuse-reports/{report_id}/resolve",
+    tag = "abuse_reports",
+    params(("report_id" = Uuid, Path, description = "Report ID")),
+    request_body = ResolveReportRequest,
+    responses((status = 200, description = "Success", body = crate::services::abuse_report_service::AbuseReport)),
+    security(("bearer_auth" = []))
+)]
+pub async fn resolve_report(
+    user: AuthenticatedUser,
+    report_id: web::Path<Uuid>,
+    request: web::Json<ResolveReportRequest>,
+    abuse_report_service: web::Data<AbuseReportService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!("Admin {} resolving report {} with {:?}", user.user_id, report_id, request.action);
+
+    let report = abuse_report_service
+        .resolve(*report_id, user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Close a report without action (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/abuse-reports/{report_id}/dismiss",
+    tag = "abuse_reports",
+    params(("report_id" = Uuid, Path, description = "Report ID")),
+    request_body = DismissReportRequest,
+    responses((status = 200, description = "Success", body = crate::services::abuse_report_service::AbuseReport)),
+    security(("bearer_auth" = []))
+)]
+pub async fn dismiss_report(
+    user: AuthenticatedUser,
+    report_id: web::Path<Uuid>,
+    request: web::Json<DismissReportRequest>,
+    abuse_report_service: web::Data<AbuseReportService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let report = abuse_report_service
+        .dismiss(*report_id, user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
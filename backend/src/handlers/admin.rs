@@ -0,0 +1,1716 @@
+use crate::config::AppConfig;
+use crate::database::Database;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::rate_limit::{RateLimitBudget, RateLimiter};
+use crate::models::blockchain_event_dead_letter::{BlockchainEventDeadLetter, DeadLetterStatus};
+use crate::models::partner_api_key::PartnerApiKey;
+use crate::models::raffle_policy::{RafflePolicy, RafflePolicyOverrides};
+use crate::models::User;
+use crate::services::account_deletion_service::{AccountDeletionService, DeletionStatus};
+use crate::services::auth_service::{AuthService, LockoutPolicy, LoginLockoutQuery};
+use crate::services::backup_verification_service::BackupVerificationService;
+use crate::services::cleanup_service::CleanupService;
+use crate::services::email_domain_service::{EmailDomainService, SetDomainRuleRequest};
+use crate::services::email_service::{AddSuppressionRequest, EmailMessageQuery, EmailService, SuppressionReason};
+use crate::services::feature_flag_service::{
+    CreateFeatureFlagRequest, FeatureFlagService, UpdateFeatureFlagRequest,
+};
+use crate::services::geo_compliance_service::{CountryRules, GeoComplianceService, GeoRestrictionPolicy};
+use crate::services::geo_velocity_service::{GeoVelocityPolicy, GeoVelocityService};
+use crate::services::notification_service::NotificationService;
+use crate::services::permission_service::{PermissionService, SetRolePermissionsRequest};
+use crate::services::raffle_service::{GridLimits, RaffleService};
+use crate::services::realtime_service::{
+    ChannelBroadcastRequest, ConnectionListQuery, DisconnectRequest, RealtimeService,
+};
+use crate::services::settings_service::{SettingsService, UpdateSettingRequest};
+use crate::services::{BlockchainService, WalletService};
+use crate::error::AppError;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use raffle_platform_shared::UserRole;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::sync::Arc;
+use tracing::{debug, info};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AccountDeletionQuery {
+    pub status: Option<DeletionStatus>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeadLetterQuery {
+    pub status: Option<DeadLetterStatus>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SpeedUpTransactionRequest {
+    /// Lowest max fee per gas to pay; the network's current fees are used when higher
+    pub max_fee_per_gas_gwei: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TransactionReplacementResponse {
+    pub tx_id: Uuid,
+    /// Hash of the replacement; the transaction is done when this or an earlier broadcast is mined
+    pub hash: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateUserRoleRequest {
+    pub role: UserRole,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BanUserRequest {
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EmailDomainCheckQuery {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    /// Seller whose API rate plan the key's usage is billed to
+    pub seller_id: Option<Uuid>,
+}
+
+/// Get credential cleanup metrics and the last run report (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/cleanup",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = crate::services::cleanup_service::CleanupMetrics)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_cleanup_status(
+    user: AuthenticatedUser,
+    cleanup_service: web::Data<CleanupService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    debug!("Admin {} requesting cleanup status", user.user_id);
+
+    Ok(HttpResponse::Ok().json(cleanup_service.get_metrics().await))
+}
+
+/// Trigger a credential cleanup run immediately (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/cleanup/run",
+    tag = "admin",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn run_cleanup(
+    user: AuthenticatedUser,
+    cleanup_service: web::Data<CleanupService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!("Admin {} triggered credential cleanup", user.user_id);
+
+    let report = cleanup_service.run_cleanup().await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Wallets per key source, the master seed in use and chain addresses still missing (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/wallets/derivation",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = crate::services::wallet_service::WalletDerivationReport)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_wallet_derivation_report(
+    user: AuthenticatedUser,
+    wallet_service: web::Data<WalletService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let report = wallet_service.derivation_report().await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Wallet data keys per master key and wallets still on legacy encryption (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/wallets/encryption",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = crate::services::wallet_service::WalletEncryptionStatus)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_wallet_encryption_status(
+    user: AuthenticatedUser,
+    wallet_service: web::Data<WalletService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let status = wallet_service.encryption_status().await?;
+
+    Ok(HttpResponse::Ok().json(status))
+}
+
+/// Rewrap a batch of wallet data keys with the current master key after a rotation (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/wallets/encryption/rewrap",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = crate::services::wallet_service::DataKeyRewrap)),
+    security(("bearer_auth" = []))
+)]
+pub async fn rewrap_wallet_data_keys(
+    user: AuthenticatedUser,
+    wallet_service: web::Data<WalletService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!("Admin {} triggered wallet data key rewrap", user.user_id);
+
+    let report = wallet_service.rewrap_data_keys().await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Give users addresses on newly supported chains, one batch per call (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/wallets/chain-addresses/backfill",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = crate::services::wallet_service::ChainAddressBackfill)),
+    security(("bearer_auth" = []))
+)]
+pub async fn backfill_wallet_chain_addresses(
+    user: AuthenticatedUser,
+    wallet_service: web::Data<WalletService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!("Admin {} triggered wallet chain address backfill", user.user_id);
+
+    let report = wallet_service.backfill_chain_addresses().await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Get batched notification fan-out progress and completion times (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/notifications/fan-outs",
+    tag = "admin",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_notification_fan_outs(
+    user: AuthenticatedUser,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    debug!("Admin {} requesting notification fan-out metrics", user.user_id);
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "queue": notification_service.get_queue_status().await,
+        "fan_outs": notification_service.get_fan_out_metrics().await,
+    })))
+}
+
+/// Get the maximum raffle grid dimensions (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/raffle-grid-limits",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = crate::services::raffle_service::GridLimits)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_raffle_grid_limits(
+    user: AuthenticatedUser,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(raffle_service.get_grid_limits().await?))
+}
+
+/// Set the maximum raffle grid dimensions (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/raffle-grid-limits",
+    tag = "admin",
+    request_body = GridLimits,
+    responses((status = 200, description = "Success", body = crate::services::raffle_service::GridLimits)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_raffle_grid_limits(
+    user: AuthenticatedUser,
+    request: web::Json<GridLimits>,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!(
+        "Admin {} setting raffle grid limits to {}x{}",
+        user.user_id, request.max_rows, request.max_cols
+    );
+
+    let limits = raffle_service.update_grid_limits(request.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(limits))
+}
+
+/// The platform raffle policy and each subscription tier's overrides (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/raffle-policy",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = crate::services::raffle_service::RafflePolicyOverview)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_raffle_policy(
+    user: AuthenticatedUser,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(raffle_service.get_policy_overview().await?))
+}
+
+/// Replace the platform raffle policy; tiers keep their overrides (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/raffle-policy",
+    tag = "admin",
+    request_body = RafflePolicy,
+    responses((status = 200, description = "Success", body = RafflePolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_raffle_policy(
+    user: AuthenticatedUser,
+    request: web::Json<RafflePolicy>,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let policy = raffle_service
+        .update_platform_policy(request.into_inner(), user.user_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(policy))
+}
+
+/// Override platform raffle policy bounds for one subscription tier; returns the tier's policy (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/raffle-policy/tiers/{subscription_id}",
+    tag = "admin",
+    params(("subscription_id" = Uuid, Path, description = "Subscription ID")),
+    request_body = RafflePolicyOverrides,
+    responses((status = 200, description = "Success", body = RafflePolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_tier_raffle_policy(
+    user: AuthenticatedUser,
+    subscription_id: web::Path<Uuid>,
+    request: web::Json<RafflePolicyOverrides>,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let policy = raffle_service
+        .set_tier_policy(*subscription_id, request.into_inner(), user.user_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(policy))
+}
+
+/// Put a subscription tier back on the platform raffle policy (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/raffle-policy/tiers/{subscription_id}",
+    tag = "admin",
+    params(("subscription_id" = Uuid, Path, description = "Subscription ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn remove_tier_raffle_policy(
+    user: AuthenticatedUser,
+    subscription_id: web::Path<Uuid>,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    raffle_service.remove_tier_policy(*subscription_id, user.user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// The geo-velocity policy deciding when users must re-authenticate (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/geo-velocity-policy",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = GeoVelocityPolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_geo_velocity_policy(
+    user: AuthenticatedUser,
+    geo_velocity_service: web::Data<GeoVelocityService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(geo_velocity_service.get_policy().await?))
+}
+
+/// Replace the geo-velocity policy (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/geo-velocity-policy",
+    tag = "admin",
+    request_body = GeoVelocityPolicy,
+    responses((status = 200, description = "Success", body = GeoVelocityPolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_geo_velocity_policy(
+    user: AuthenticatedUser,
+    request: web::Json<GeoVelocityPolicy>,
+    geo_velocity_service: web::Data<GeoVelocityService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!(
+        "Admin {} setting geo-velocity policy: enabled={}, max_speed_kmh={}, min_distance_km={}",
+        user.user_id, request.enabled, request.max_speed_kmh, request.min_distance_km
+    );
+
+    let policy = geo_velocity_service.update_policy(request.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(policy))
+}
+
+/// Failed-login counters for accounts and IP addresses, most recent failure first; only
+/// active lockouts unless `locked=false` (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/login-lockouts",
+    tag = "admin",
+    params(LoginLockoutQuery),
+    responses((status = 200, description = "Success", body = [crate::services::auth_service::LoginLockout])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_login_lockouts(
+    user: AuthenticatedUser,
+    query: web::Query<LoginLockoutQuery>,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(auth_service.login_guard().list(&query).await?))
+}
+
+/// Lift a lockout and reset its failure count (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/login-lockouts/{lockout_id}",
+    tag = "admin",
+    params(("lockout_id" = Uuid, Path, description = "Lockout ID")),
+    responses(
+        (status = 200, description = "Lockout cleared", body = crate::services::auth_service::LoginLockout),
+        (status = 404, description = "Lockout not found")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn clear_login_lockout(
+    user: AuthenticatedUser,
+    lockout_id: web::Path<Uuid>,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!("Admin {} clearing login lockout {}", user.user_id, lockout_id);
+
+    let lockout = auth_service.login_guard().clear(*lockout_id).await?;
+
+    Ok(HttpResponse::Ok().json(lockout))
+}
+
+/// The policy deciding when failed logins lock out an account or IP address (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/login-lockout-policy",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = LockoutPolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_login_lockout_policy(
+    user: AuthenticatedUser,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(auth_service.login_guard().get_policy().await?))
+}
+
+/// Replace the login lockout policy (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/login-lockout-policy",
+    tag = "admin",
+    request_body = LockoutPolicy,
+    responses((status = 200, description = "Success", body = LockoutPolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_login_lockout_policy(
+    user: AuthenticatedUser,
+    request: web::Json<LockoutPolicy>,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!(
+        "Admin {} setting login lockout policy: enabled={}, account_max_failures={}, ip_max_failures={}",
+        user.user_id, request.enabled, request.account_max_failures, request.ip_max_failures
+    );
+
+    let policy = auth_service.login_guard().update_policy(request.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(policy))
+}
+
+/// Countries raffles can be seen and played from, platform-wide and per raffle (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/geo-restrictions",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = GeoRestrictionPolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_geo_restrictions(
+    user: AuthenticatedUser,
+    geo_compliance_service: web::Data<GeoComplianceService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(geo_compliance_service.get_policy().await?))
+}
+
+/// Replace the geo restrictions, including every raffle's (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/geo-restrictions",
+    tag = "admin",
+    request_body = GeoRestrictionPolicy,
+    responses((status = 200, description = "Success", body = GeoRestrictionPolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_geo_restrictions(
+    http_req: HttpRequest,
+    user: AuthenticatedUser,
+    request: web::Json<GeoRestrictionPolicy>,
+    geo_compliance_service: web::Data<GeoComplianceService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let (ip_address, user_agent) = client_info(&http_req);
+    let policy = geo_compliance_service
+        .update_policy(request.into_inner(), user.user_id, ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(policy))
+}
+
+/// Set the countries one raffle can be seen and played from; empty lists clear them (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/geo-restrictions/raffles/{raffle_id}",
+    tag = "admin",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    request_body = CountryRules,
+    responses((status = 200, description = "Success", body = GeoRestrictionPolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_raffle_geo_restrictions(
+    http_req: HttpRequest,
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    request: web::Json<CountryRules>,
+    geo_compliance_service: web::Data<GeoComplianceService>,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    // Only rules for raffles that exist
+    raffle_service.get_raffle(*raffle_id).await?;
+
+    let (ip_address, user_agent) = client_info(&http_req);
+    let policy = geo_compliance_service
+        .set_raffle_rules(*raffle_id, request.into_inner(), user.user_id, ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(policy))
+}
+
+/// Per-route rate limit budgets and the limits in force (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/rate-limits",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = [crate::middleware::rate_limit::RouteBudgetStatus])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_rate_limits(
+    user: AuthenticatedUser,
+    rate_limiter: web::Data<RateLimiter>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(rate_limiter.route_budgets().await))
+}
+
+/// Set a route's rate limit budget (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/rate-limits/{name}",
+    tag = "admin",
+    params(("name" = String, Path, description = "Name")),
+    request_body = RateLimitBudget,
+    responses((status = 200, description = "Success", body = crate::middleware::rate_limit::RouteBudgetStatus)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_rate_limit(
+    user: AuthenticatedUser,
+    path: web::Path<String>,
+    request: web::Json<RateLimitBudget>,
+    rate_limiter: web::Data<RateLimiter>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let name = path.into_inner();
+    info!(
+        "Admin {} setting rate limit budget {} to {} requests per {}s",
+        user.user_id, name, request.requests, request.window_seconds
+    );
+
+    let status = rate_limiter.update_budget(&name, request.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(status))
+}
+
+/// List partner API keys (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/api-keys",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = [crate::models::partner_api_key::PartnerApiKey])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_api_keys(
+    user: AuthenticatedUser,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(PartnerApiKey::list(database.pool()).await?))
+}
+
+/// Issue a partner API key; the key itself is only returned in this response (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/api-keys",
+    tag = "admin",
+    request_body = CreateApiKeyRequest,
+    responses((status = 201, description = "Created")),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_api_key(
+    user: AuthenticatedUser,
+    request: web::Json<CreateApiKeyRequest>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let name = request.name.trim();
+    if name.is_empty() || name.len() > 100 {
+        return Err(AppError::Validation("API key name must be 1 to 100 characters".to_string()));
+    }
+
+    if let Some(seller_id) = request.seller_id {
+        let seller = User::find_by_id(database.pool(), seller_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Seller not found".to_string()))?;
+        if seller.role != UserRole::Seller {
+            return Err(AppError::Validation("API keys can only be issued to sellers".to_string()));
+        }
+    }
+
+    let (api_key, key) =
+        PartnerApiKey::create(database.pool(), name, &request.scopes, request.seller_id, user.user_id).await?;
+
+    info!("Admin {} issued API key {} ({})", user.user_id, api_key.id, api_key.name);
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "api_key": api_key,
+        "key": key,
+    })))
+}
+
+/// Revoke a partner API key (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/api-keys/{key_id}",
+    tag = "admin",
+    params(("key_id" = Uuid, Path, description = "Key ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn revoke_api_key(
+    user: AuthenticatedUser,
+    key_id: web::Path<Uuid>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    if !PartnerApiKey::revoke(database.pool(), *key_id).await? {
+        return Err(AppError::NotFound("API key not found or already revoked".to_string()));
+    }
+
+    info!("Admin {} revoked API key {}", user.user_id, key_id);
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Get the effective configuration for this environment, with secrets redacted (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/config",
+    tag = "admin",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_effective_config(
+    user: AuthenticatedUser,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    debug!("Admin {} requesting effective configuration", user.user_id);
+
+    Ok(HttpResponse::Ok().json(config.effective()))
+}
+
+/// List account deletion requests, newest first (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/account-deletions",
+    tag = "admin",
+    params(AccountDeletionQuery),
+    responses((status = 200, description = "Success", body = [crate::services::account_deletion_service::AccountDeletionRequest])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_account_deletions(
+    user: AuthenticatedUser,
+    query: web::Query<AccountDeletionQuery>,
+    deletion_service: web::Data<AccountDeletionService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let requests = deletion_service.list_requests(query.status, limit).await?;
+
+    Ok(HttpResponse::Ok().json(requests))
+}
+
+/// Restore an account whose deletion is still in its grace period (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/account-deletions/{request_id}/cancel",
+    tag = "admin",
+    params(("request_id" = Uuid, Path, description = "Request ID")),
+    responses((status = 200, description = "Success", body = crate::services::account_deletion_service::AccountDeletionRequest)),
+    security(("bearer_auth" = []))
+)]
+pub async fn cancel_account_deletion(
+    user: AuthenticatedUser,
+    request_id: web::Path<Uuid>,
+    deletion_service: web::Data<AccountDeletionService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!("Admin {} cancelling account deletion {}", user.user_id, request_id);
+
+    let request = deletion_service.cancel(*request_id, user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(request))
+}
+
+/// List blockchain events whose persistence failed, newest first (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/blockchain/dead-letters",
+    tag = "admin",
+    params(DeadLetterQuery),
+    responses((status = 200, description = "Success", body = [crate::models::blockchain_event_dead_letter::BlockchainEventDeadLetter])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_blockchain_dead_letters(
+    user: AuthenticatedUser,
+    query: web::Query<DeadLetterQuery>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let letters = BlockchainEventDeadLetter::list(database.pool(), query.status, limit).await?;
+
+    Ok(HttpResponse::Ok().json(letters))
+}
+
+/// Queue a dead-lettered blockchain event for the next retry run (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/blockchain/dead-letters/{dead_letter_id}/retry",
+    tag = "admin",
+    params(("dead_letter_id" = i64, Path, description = "Dead letter ID")),
+    responses((status = 202, description = "Accepted", body = crate::models::blockchain_event_dead_letter::BlockchainEventDeadLetter)),
+    security(("bearer_auth" = []))
+)]
+pub async fn retry_blockchain_dead_letter(
+    user: AuthenticatedUser,
+    dead_letter_id: web::Path<i64>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let letter = BlockchainEventDeadLetter::schedule_retry(database.pool(), *dead_letter_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Dead letter not found or already resolved".to_string()))?;
+
+    info!("Admin {} queued retry of dead letter {}", user.user_id, letter.id);
+
+    Ok(HttpResponse::Accepted().json(letter))
+}
+
+/// Nonces handed out per signing wallet: in flight, with the transaction holding each, and gaps (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/blockchain/nonces",
+    tag = "admin",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_blockchain_nonces(
+    user: AuthenticatedUser,
+    blockchain_service: web::Data<BlockchainService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(blockchain_service.nonce_snapshots().await))
+}
+
+/// Cancel a stuck contract transaction by sending an empty self-transfer with its nonce (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/blockchain/transactions/{tx_id}/cancel",
+    tag = "admin",
+    params(("tx_id" = Uuid, Path, description = "Transaction manager ID")),
+    responses(
+        (status = 202, description = "Cancel broadcast", body = TransactionReplacementResponse),
+        (status = 404, description = "Transaction not found"),
+        (status = 409, description = "Transaction was already mined or can't be replaced")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn cancel_blockchain_transaction(
+    user: AuthenticatedUser,
+    tx_id: web::Path<Uuid>,
+    blockchain_service: web::Data<BlockchainService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let tx_id = tx_id.into_inner();
+    if blockchain_service.get_transaction_status(tx_id).await.is_none() {
+        return Err(AppError::NotFound("Transaction not found".to_string()));
+    }
+    let hash = blockchain_service
+        .cancel_transaction(tx_id)
+        .await
+        .map_err(|e| AppError::Conflict(e.to_string()))?;
+
+    info!("Admin {} cancelled transaction {} with {:?}", user.user_id, tx_id, hash);
+
+    Ok(HttpResponse::Accepted().json(TransactionReplacementResponse {
+        tx_id,
+        hash: format!("{:?}", hash),
+    }))
+}
+
+/// Re-send a stuck contract transaction with the same nonce at higher fees (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/blockchain/transactions/{tx_id}/speed-up",
+    tag = "admin",
+    params(("tx_id" = Uuid, Path, description = "Transaction manager ID")),
+    request_body = SpeedUpTransactionRequest,
+    responses(
+        (status = 202, description = "Replacement broadcast", body = TransactionReplacementResponse),
+        (status = 404, description = "Transaction not found"),
+        (status = 409, description = "Transaction was already mined or the fees would pass the max gas price")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn speed_up_blockchain_transaction(
+    user: AuthenticatedUser,
+    tx_id: web::Path<Uuid>,
+    request: web::Json<SpeedUpTransactionRequest>,
+    blockchain_service: web::Data<BlockchainService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let tx_id = tx_id.into_inner();
+    if blockchain_service.get_transaction_status(tx_id).await.is_none() {
+        return Err(AppError::NotFound("Transaction not found".to_string()));
+    }
+    let max_fee_per_gas = request
+        .max_fee_per_gas_gwei
+        .map(|gwei| ethers::types::U256::from(gwei) * ethers::types::U256::exp10(9));
+    let hash = blockchain_service
+        .speed_up_transaction(tx_id, max_fee_per_gas)
+        .await
+        .map_err(|e| AppError::Conflict(e.to_string()))?;
+
+    info!("Admin {} sped up transaction {} with {:?}", user.user_id, tx_id, hash);
+
+    Ok(HttpResponse::Accepted().json(TransactionReplacementResponse {
+        tx_id,
+        hash: format!("{:?}", hash),
+    }))
+}
+
+/// Change a user's role (requires the `users.change_role` permission); their existing tokens stop working
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/users/{user_id}/role",
+    tag = "admin",
+    params(("user_id" = Uuid, Path, description = "User ID")),
+    request_body = UpdateUserRoleRequest,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_user_role(
+    user: AuthenticatedUser,
+    user_id: web::Path<Uuid>,
+    request: web::Json<UpdateUserRoleRequest>,
+    http_req: HttpRequest,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse, AppError> {
+    if user.user_id == *user_id {
+        return Err(AppError::Validation("Admins cannot change their own role".to_string()));
+    }
+    if request.role == UserRole::Operator && user.role != UserRole::Operator {
+        return Err(AppError::Forbidden("Only operators can grant the operator role".to_string()));
+    }
+
+    info!("Admin {} changing role of user {} to {:?}", user.user_id, user_id, request.role);
+
+    let (ip_address, user_agent) = client_info(&http_req);
+    auth_service
+        .change_role(user.user_id, *user_id, request.role, ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "user_id": *user_id,
+        "role": request.role
+    })))
+}
+
+/// Ban a user (requires the `users.ban` permission): deactivates the account and revokes its sessions and tokens
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/users/{user_id}/ban",
+    tag = "admin",
+    params(("user_id" = Uuid, Path, description = "User ID")),
+    request_body = BanUserRequest,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn ban_user(
+    user: AuthenticatedUser,
+    user_id: web::Path<Uuid>,
+    request: web::Json<BanUserRequest>,
+    http_req: HttpRequest,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse, AppError> {
+    info!("Admin {} banning user {}", user.user_id, user_id);
+
+    let (ip_address, user_agent) = client_info(&http_req);
+    auth_service
+        .ban_user(user.user_id, *user_id, request.reason.clone(), ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "user_id": *user_id,
+        "banned": true
+    })))
+}
+
+/// Lift a ban (requires the `users.ban` permission); the user signs in again
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/users/{user_id}/unban",
+    tag = "admin",
+    params(("user_id" = Uuid, Path, description = "User ID")),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn unban_user(
+    user: AuthenticatedUser,
+    user_id: web::Path<Uuid>,
+    http_req: HttpRequest,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse, AppError> {
+    info!("Admin {} unbanning user {}", user.user_id, user_id);
+
+    let (ip_address, user_agent) = client_info(&http_req);
+    auth_service.unban_user(user.user_id, *user_id, ip_address, user_agent).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "user_id": *user_id,
+        "banned": false
+    })))
+}
+
+/// Get every permission and the roles granted it (requires the `permissions.manage` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/permissions",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = crate::services::permission_service::PermissionMatrix)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_role_permissions(
+    user: AuthenticatedUser,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse, AppError> {
+    debug!("Admin {} requesting role permissions", user.user_id);
+
+    let matrix = permission_service.get_matrix().await?;
+
+    Ok(HttpResponse::Ok().json(matrix))
+}
+
+/// Replace the permissions granted to a role (requires the `permissions.manage` permission)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/permissions/{role}",
+    tag = "admin",
+    params(("role" = String, Path, description = "Role")),
+    request_body = SetRolePermissionsRequest,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_role_permissions(
+    user: AuthenticatedUser,
+    role: web::Path<String>,
+    request: web::Json<SetRolePermissionsRequest>,
+    http_req: HttpRequest,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse, AppError> {
+    info!("Admin {} setting permissions for role {}", user.user_id, role);
+
+    let (ip_address, user_agent) = client_info(&http_req);
+    let permissions = permission_service
+        .set_role_permissions(user.user_id, &role, request.into_inner(), ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "role": role.trim().to_lowercase(),
+        "permissions": permissions
+    })))
+}
+
+/// List system settings and the kind each value is validated against (requires the `settings.manage` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/settings",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = [crate::services::settings_service::SettingView])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_settings(
+    user: AuthenticatedUser,
+    settings_service: web::Data<SettingsService>,
+) -> Result<HttpResponse, AppError> {
+    debug!("Admin {} listing system settings", user.user_id);
+
+    let settings = settings_service.list().await?;
+
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+/// Get a system setting (requires the `settings.manage` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/settings/{key}",
+    tag = "admin",
+    params(("key" = String, Path, description = "Key")),
+    responses((status = 200, description = "Success", body = crate::services::settings_service::SettingView)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_setting(
+    user: AuthenticatedUser,
+    key: web::Path<String>,
+    settings_service: web::Data<SettingsService>,
+) -> Result<HttpResponse, AppError> {
+    debug!("Admin {} requesting system setting {}", user.user_id, key);
+
+    let setting = settings_service.get(&key).await?;
+
+    Ok(HttpResponse::Ok().json(setting))
+}
+
+/// Change a system setting; every instance applies it without a restart (requires the `settings.manage` permission)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/settings/{key}",
+    tag = "admin",
+    params(("key" = String, Path, description = "Key")),
+    request_body = UpdateSettingRequest,
+    responses((status = 200, description = "Success", body = crate::services::settings_service::SettingView)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_setting(
+    user: AuthenticatedUser,
+    key: web::Path<String>,
+    request: web::Json<UpdateSettingRequest>,
+    http_req: HttpRequest,
+    settings_service: web::Data<SettingsService>,
+) -> Result<HttpResponse, AppError> {
+    let (ip_address, user_agent) = client_info(&http_req);
+    let setting = settings_service
+        .update(&key, request.into_inner(), user.user_id, ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(setting))
+}
+
+/// List feature flags with their targeting (requires the `settings.manage` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/feature-flags",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = [crate::services::feature_flag_service::FeatureFlag])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_feature_flags(
+    user: AuthenticatedUser,
+    feature_flag_service: web::Data<FeatureFlagService>,
+) -> Result<HttpResponse, AppError> {
+    debug!("Admin {} listing feature flags", user.user_id);
+
+    let flags = feature_flag_service.list().await?;
+
+    Ok(HttpResponse::Ok().json(flags))
+}
+
+/// Create a feature flag (requires the `settings.manage` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/feature-flags",
+    tag = "admin",
+    request_body = CreateFeatureFlagRequest,
+    responses((status = 201, description = "Created", body = crate::services::feature_flag_service::FeatureFlag)),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_feature_flag(
+    user: AuthenticatedUser,
+    request: web::Json<CreateFeatureFlagRequest>,
+    http_req: HttpRequest,
+    feature_flag_service: web::Data<FeatureFlagService>,
+) -> Result<HttpResponse, AppError> {
+    let (ip_address, user_agent) = client_info(&http_req);
+    let flag = feature_flag_service
+        .create(user.user_id, request.into_inner(), ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::Created().json(flag))
+}
+
+/// Get a feature flag (requires the `settings.manage` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/feature-flags/{key}",
+    tag = "admin",
+    params(("key" = String, Path, description = "Key")),
+    responses((status = 200, description = "Success", body = crate::services::feature_flag_service::FeatureFlag)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_feature_flag(
+    user: AuthenticatedUser,
+    key: web::Path<String>,
+    feature_flag_service: web::Data<FeatureFlagService>,
+) -> Result<HttpResponse, AppError> {
+    debug!("Admin {} requesting feature flag {}", user.user_id, key);
+
+    let flag = feature_flag_service.get(&key).await?;
+
+    Ok(HttpResponse::Ok().json(flag))
+}
+
+/// Change a feature flag's state, rollout or targeting (requires the `settings.manage` permission)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/feature-flags/{key}",
+    tag = "admin",
+    params(("key" = String, Path, description = "Key")),
+    request_body = UpdateFeatureFlagRequest,
+    responses((status = 200, description = "Success", body = crate::services::feature_flag_service::FeatureFlag)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_feature_flag(
+    user: AuthenticatedUser,
+    key: web::Path<String>,
+    request: web::Json<UpdateFeatureFlagRequest>,
+    http_req: HttpRequest,
+    feature_flag_service: web::Data<FeatureFlagService>,
+) -> Result<HttpResponse, AppError> {
+    let (ip_address, user_agent) = client_info(&http_req);
+    let flag = feature_flag_service
+        .update(user.user_id, &key, request.into_inner(), ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(flag))
+}
+
+/// Delete a feature flag; code checking it sees it as off (requires the `settings.manage` permission)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/feature-flags/{key}",
+    tag = "admin",
+    params(("key" = String, Path, description = "Key")),
+    responses((status = 204, description = "Deleted")),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_feature_flag(
+    user: AuthenticatedUser,
+    key: web::Path<String>,
+    http_req: HttpRequest,
+    feature_flag_service: web::Data<FeatureFlagService>,
+) -> Result<HttpResponse, AppError> {
+    let (ip_address, user_agent) = client_info(&http_req);
+    feature_flag_service
+        .delete(user.user_id, &key, ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Get email domain exceptions and disposable list status (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/email-domains",
+    tag = "admin",
+    responses((status = 200, description = "Success", body = crate::services::email_domain_service::DomainPolicySummary)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_email_domain_policy(
+    user: AuthenticatedUser,
+    email_domain_service: web::Data<EmailDomainService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let policy = email_domain_service.get_policy().await?;
+
+    Ok(HttpResponse::Ok().json(policy))
+}
+
+/// Show how an address would be treated at registration (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/email-domains/check",
+    tag = "admin",
+    params(EmailDomainCheckQuery),
+    responses((status = 200, description = "Success", body = crate::services::email_domain_service::DomainCheck)),
+    security(("bearer_auth" = []))
+)]
+pub async fn check_email_domain(
+    user: AuthenticatedUser,
+    query: web::Query<EmailDomainCheckQuery>,
+    email_domain_service: web::Data<EmailDomainService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let check = email_domain_service.evaluate(&query.email).await?;
+
+    Ok(HttpResponse::Ok().json(check))
+}
+
+/// Allow or block a domain regardless of the disposable list (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/email-domains/{domain}",
+    tag = "admin",
+    params(("domain" = String, Path, description = "Domain")),
+    request_body = SetDomainRuleRequest,
+    responses((status = 200, description = "Success", body = crate::services::email_domain_service::EmailDomainRule)),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_email_domain_rule(
+    user: AuthenticatedUser,
+    domain: web::Path<String>,
+    request: web::Json<SetDomainRuleRequest>,
+    http_req: HttpRequest,
+    email_domain_service: web::Data<EmailDomainService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let (ip_address, user_agent) = client_info(&http_req);
+    let rule = email_domain_service
+        .set_rule(user.user_id, &domain, request.into_inner(), ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(rule))
+}
+
+/// Remove a domain exception (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/email-domains/{domain}",
+    tag = "admin",
+    params(("domain" = String, Path, description = "Domain")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_email_domain_rule(
+    user: AuthenticatedUser,
+    domain: web::Path<String>,
+    http_req: HttpRequest,
+    email_domain_service: web::Data<EmailDomainService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let (ip_address, user_agent) = client_info(&http_req);
+    email_domain_service
+        .delete_rule(user.user_id, &domain, ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Refresh the disposable domain list from its feed now (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/email-domains/refresh",
+    tag = "admin",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn refresh_disposable_domains(
+    user: AuthenticatedUser,
+    email_domain_service: web::Data<EmailDomainService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!("Admin {} refreshing disposable email domains", user.user_id);
+
+    let domains = email_domain_service.refresh_disposable_domains().await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "disposable_domains": domains
+    })))
+}
+
+/// List queued and sent emails; template data is never included (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/emails",
+    tag = "admin",
+    params(EmailMessageQuery),
+    responses((status = 200, description = "Success", body = [crate::services::email_service::EmailMessage])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_email_messages(
+    user: AuthenticatedUser,
+    query: web::Query<EmailMessageQuery>,
+    email_service: web::Data<EmailService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let messages = email_service.list_messages(&query).await?;
+
+    Ok(HttpResponse::Ok().json(messages))
+}
+
+/// List addresses email is no longer sent to (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/email-suppressions",
+    tag = "admin",
+    params(PageQuery),
+    responses((status = 200, description = "Success", body = [crate::services::email_service::EmailSuppression])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_email_suppressions(
+    user: AuthenticatedUser,
+    query: web::Query<PageQuery>,
+    email_service: web::Data<EmailService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let suppressions = email_service.list_suppressions(query.limit, query.offset).await?;
+
+    Ok(HttpResponse::Ok().json(suppressions))
+}
+
+/// Stop sending email to an address (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/email-suppressions",
+    tag = "admin",
+    request_body = AddSuppressionRequest,
+    responses((status = 201, description = "Created", body = crate::services::email_service::EmailSuppression)),
+    security(("bearer_auth" = []))
+)]
+pub async fn add_email_suppression(
+    user: AuthenticatedUser,
+    request: web::Json<AddSuppressionRequest>,
+    email_service: web::Data<EmailService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let request = request.into_inner();
+    let suppression = email_service
+        .suppress(&request.email, SuppressionReason::Manual, request.details, Some(user.user_id))
+        .await?;
+
+    Ok(HttpResponse::Created().json(suppression))
+}
+
+/// Allow email to an address again, e.g. after a user fixed their mailbox (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/email-suppressions/{email}",
+    tag = "admin",
+    params(("email" = String, Path, description = "Email")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn remove_email_suppression(
+    user: AuthenticatedUser,
+    email: web::Path<String>,
+    email_service: web::Data<EmailService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    email_service.remove_suppression(&email, user.user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// List open WebSocket connections (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/websocket/connections",
+    tag = "admin",
+    params(ConnectionListQuery),
+    responses((status = 200, description = "Success", body = [crate::services::realtime_service::ConnectionDetails])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_websocket_connections(
+    user: AuthenticatedUser,
+    query: web::Query<ConnectionListQuery>,
+    realtime_service: web::Data<Arc<RealtimeService>>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let connections = realtime_service.list_connections(&query).await;
+
+    Ok(HttpResponse::Ok().json(connections))
+}
+
+/// Send an announcement to a room or to every connection (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/websocket/broadcast",
+    tag = "admin",
+    request_body = ChannelBroadcastRequest,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn broadcast_websocket_message(
+    user: AuthenticatedUser,
+    request: web::Json<ChannelBroadcastRequest>,
+    http_req: HttpRequest,
+    realtime_service: web::Data<Arc<RealtimeService>>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let (ip_address, user_agent) = client_info(&http_req);
+    let channel = request.channel.clone();
+    let reached = realtime_service
+        .broadcast_to_channel(user.user_id, request.into_inner(), ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "channel": channel,
+        "connections_reached": reached
+    })))
+}
+
+/// Close a WebSocket connection (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/websocket/connections/{connection_id}",
+    tag = "admin",
+    params(("connection_id" = Uuid, Path, description = "Connection ID")),
+    request_body(content = DisconnectRequest, description = "Optional"),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn disconnect_websocket_connection(
+    user: AuthenticatedUser,
+    connection_id: web::Path<Uuid>,
+    request: Option<web::Json<DisconnectRequest>>,
+    http_req: HttpRequest,
+    realtime_service: web::Data<Arc<RealtimeService>>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let (ip_address, user_agent) = client_info(&http_req);
+    let reason = request.and_then(|r| r.into_inner().reason);
+    realtime_service
+        .disconnect_connection(user.user_id, *connection_id, reason, ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Close all of a user's WebSocket connections (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/websocket/users/{user_id}/connections",
+    tag = "admin",
+    params(("user_id" = Uuid, Path, description = "User ID")),
+    request_body(content = DisconnectRequest, description = "Optional"),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn disconnect_websocket_user(
+    user: AuthenticatedUser,
+    user_id: web::Path<Uuid>,
+    request: Option<web::Json<DisconnectRequest>>,
+    http_req: HttpRequest,
+    realtime_service: web::Data<Arc<RealtimeService>>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let (ip_address, user_agent) = client_info(&http_req);
+    let reason = request.and_then(|r| r.into_inner().reason);
+    let disconnected = realtime_service
+        .disconnect_user(user.user_id, *user_id, reason, ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "user_id": *user_id,
+        "connections_closed": disconnected
+    })))
+}
+
+pub(crate) fn client_info(req: &HttpRequest) -> (Option<IpAddr>, Option<String>) {
+    let ip_address = req
+        .connection_info()
+        .realip_remote_addr()
+        .and_then(|ip| ip.parse::<IpAddr>().ok());
+    let user_agent = req
+        .headers()
+        .get("User-Agent")
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.to_string());
+
+    (ip_address, user_agent)
+}
+
+/// List restore drills of database backups, newest first (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/backups/verifications",
+    tag = "admin",
+    params(PageQuery),
+    responses((status = 200, description = "Success", body = [crate::services::backup_verification_service::BackupVerificationRun])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_backup_verifications(
+    user: AuthenticatedUser,
+    query: web::Query<PageQuery>,
+    backup_verification_service: web::Data<BackupVerificationService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let runs = backup_verification_service
+        .list_runs(query.limit.unwrap_or(20), query.offset.unwrap_or(0))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(runs))
+}
+
+/// Get one restore drill with its integrity checks (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/backups/verifications/{run_id}",
+    tag = "admin",
+    params(("run_id" = Uuid, Path, description = "Run ID")),
+    responses((status = 200, description = "Success", body = crate::services::backup_verification_service::BackupVerificationRun)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_backup_verification(
+    user: AuthenticatedUser,
+    run_id: web::Path<Uuid>,
+    backup_verification_service: web::Data<BackupVerificationService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let run = backup_verification_service.get_run(*run_id).await?;
+
+    Ok(HttpResponse::Ok().json(run))
+}
+
+/// Restore the newest backup into the scratch database and check it now (admin only)
+///
+/// Returns the run as soon as it starts; poll it for the outcome.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/backups/verifications",
+    tag = "admin",
+    responses((status = 202, description = "Accepted", body = crate::services::backup_verification_service::BackupVerificationRun)),
+    security(("bearer_auth" = []))
+)]
+pub async fn start_backup_verification(
+    user: AuthenticatedUser,
+    backup_verification_service: web::Data<BackupVerificationService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let run = backup_verification_service.start_drill(user.user_id).await?;
+
+    Ok(HttpResponse::Accepted().json(run))
+}
@@ -0,0 +1,102 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::analytics_service::{anonymous_id_from_request, AnalyticsService, ClientContext, TrackEventRequest};
+use crate::error::AppError;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use serde::Deserialize;
+use std::net::IpAddr;
+use tracing::debug;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct StartSessionRequest {
+    pub landing_page: Option<String>,
+    pub referrer: Option<String>,
+}
+
+fn client_context(http_req: &HttpRequest) -> ClientContext {
+    ClientContext {
+        ip_address: http_req
+            .connection_info()
+            .realip_remote_addr()
+            .and_then(|ip| ip.parse::<IpAddr>().ok()),
+        user_agent: http_req
+            .headers()
+            .get("User-Agent")
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Issue (or refresh) an anonymous session id for an unauthenticated client
+#[utoipa::path(
+    post,
+    path = "/api/v1/analytics/session",
+    tag = "analytics",
+    request_body = StartSessionRequest,
+    responses((status = 200, description = "Success", body = crate::services::analytics_service::AnonymousSession))
+)]
+pub async fn start_session(
+    http_req: HttpRequest,
+    request: web::Json<StartSessionRequest>,
+    analytics_service: web::Data<AnalyticsService>,
+) -> Result<HttpResponse, AppError> {
+    let request = request.into_inner();
+    let context = ClientContext {
+        landing_page: request.landing_page,
+        referrer: request.referrer,
+        ..client_context(&http_req)
+    };
+
+    let session = analytics_service
+        .start_anonymous_session(anonymous_id_from_request(&http_req), context)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(session))
+}
+
+/// Record an analytics event against an anonymous session
+#[utoipa::path(
+    post,
+    path = "/api/v1/analytics/events",
+    tag = "analytics",
+    request_body = TrackEventRequest,
+    responses((status = 202, description = "Accepted"))
+)]
+pub async fn track_event(
+    http_req: HttpRequest,
+    request: web::Json<TrackEventRequest>,
+    analytics_service: web::Data<AnalyticsService>,
+) -> Result<HttpResponse, AppError> {
+    analytics_service
+        .track_event(request.into_inner(), client_context(&http_req))
+        .await?;
+
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Recompute and return the view-to-purchase funnel for a raffle (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/analytics/raffles/{raffle_id}/funnel",
+    tag = "analytics",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success", body = crate::services::analytics_service::RaffleFunnel)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_raffle_funnel(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    analytics_service: web::Data<AnalyticsService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    debug!("Admin {} requesting funnel for raffle {}", user.user_id, raffle_id);
+
+    let funnel = analytics_service.refresh_raffle_funnel(*raffle_id).await?;
+
+    Ok(HttpResponse::Ok().json(funnel))
+}
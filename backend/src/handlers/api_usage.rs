@@ -0,0 +1,212 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::api_usage::ApiRatePlanRequest;
+use crate::services::api_billing_service::ApiBillingService;
+use crate::error::AppError;
+use actix_web::{web, HttpResponse, Result};
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+use tracing::info;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ChargeQuery {
+    pub seller_id: Option<Uuid>,
+    /// First day of the billed month
+    pub period: Option<NaiveDate>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AssignPlanRequest {
+    pub rate_plan_id: Uuid,
+}
+
+fn require_seller(user: &AuthenticatedUser) -> Result<(), AppError> {
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("Only sellers have partner API usage".to_string()));
+    }
+    Ok(())
+}
+
+// Seller endpoints
+
+/// The current seller's rate plan, usage this month and usage per API key
+#[utoipa::path(
+    get,
+    path = "/api/v1/api-usage",
+    tag = "api_usage",
+    responses((status = 200, description = "Success", body = crate::services::api_billing_service::ApiUsageSummary)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_usage(
+    user: AuthenticatedUser,
+    service: web::Data<ApiBillingService>,
+) -> Result<HttpResponse, AppError> {
+    require_seller(&user)?;
+
+    let summary = service.usage_summary(user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+/// Overage charges billed to the current seller, newest month first
+#[utoipa::path(
+    get,
+    path = "/api/v1/api-usage/charges",
+    tag = "api_usage",
+    params(ChargeQuery),
+    responses((status = 200, description = "Success", body = [crate::models::api_usage::ApiUsageCharge])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_my_charges(
+    user: AuthenticatedUser,
+    query: web::Query<ChargeQuery>,
+    service: web::Data<ApiBillingService>,
+) -> Result<HttpResponse, AppError> {
+    require_seller(&user)?;
+
+    let charges = service
+        .list_charges(Some(user.user_id), query.period, query.limit.unwrap_or(24))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(charges))
+}
+
+// Admin endpoints
+
+/// List API rate plans (requires the `api_plans.manage` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/api-plans",
+    tag = "api_usage",
+    responses((status = 200, description = "Success", body = [crate::models::api_usage::ApiRatePlan])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_plans(
+    service: web::Data<ApiBillingService>,
+) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(service.list_plans().await?))
+}
+
+/// Create an API rate plan (requires the `api_plans.manage` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/api-plans",
+    tag = "api_usage",
+    request_body = ApiRatePlanRequest,
+    responses((status = 201, description = "Created", body = crate::models::api_usage::ApiRatePlan)),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_plan(
+    user: AuthenticatedUser,
+    request: web::Json<ApiRatePlanRequest>,
+    service: web::Data<ApiBillingService>,
+) -> Result<HttpResponse, AppError> {
+    let plan = service.create_plan(&request, user.user_id).await?;
+
+    Ok(HttpResponse::Created().json(plan))
+}
+
+/// Replace an API rate plan's quotas and prices; months already closed keep their charges
+/// (requires the `api_plans.manage` permission)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/api-plans/{plan_id}",
+    tag = "api_usage",
+    params(("plan_id" = Uuid, Path, description = "Plan ID")),
+    request_body = ApiRatePlanRequest,
+    responses((status = 200, description = "Success", body = crate::models::api_usage::ApiRatePlan)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_plan(
+    user: AuthenticatedUser,
+    plan_id: web::Path<Uuid>,
+    request: web::Json<ApiRatePlanRequest>,
+    service: web::Data<ApiBillingService>,
+) -> Result<HttpResponse, AppError> {
+    let plan = service.update_plan(*plan_id, &request, user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+/// Move a seller to another API rate plan (requires the `api_plans.manage` permission)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/sellers/{seller_id}/api-plan",
+    tag = "api_usage",
+    params(("seller_id" = Uuid, Path, description = "Seller ID")),
+    request_body = AssignPlanRequest,
+    responses((status = 200, description = "Success", body = crate::models::api_usage::ApiRatePlan)),
+    security(("bearer_auth" = []))
+)]
+pub async fn assign_plan(
+    user: AuthenticatedUser,
+    seller_id: web::Path<Uuid>,
+    request: web::Json<AssignPlanRequest>,
+    service: web::Data<ApiBillingService>,
+) -> Result<HttpResponse, AppError> {
+    let plan = service.assign_plan(*seller_id, request.rate_plan_id, user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+/// A seller's plan and usage this month (requires the `api_plans.manage` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/sellers/{seller_id}/api-usage",
+    tag = "api_usage",
+    params(("seller_id" = Uuid, Path, description = "Seller ID")),
+    responses((status = 200, description = "Success", body = crate::services::api_billing_service::ApiUsageSummary)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_seller_usage(
+    seller_id: web::Path<Uuid>,
+    service: web::Data<ApiBillingService>,
+) -> Result<HttpResponse, AppError> {
+    let summary = service.usage_summary(*seller_id).await?;
+
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+/// List overage charges, optionally for one seller or month
+/// (requires the `api_plans.manage` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/api-usage/charges",
+    tag = "api_usage",
+    params(ChargeQuery),
+    responses((status = 200, description = "Success", body = [crate::models::api_usage::ApiUsageCharge])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_charges(
+    query: web::Query<ChargeQuery>,
+    service: web::Data<ApiBillingService>,
+) -> Result<HttpResponse, AppError> {
+    let charges = service
+        .list_charges(query.seller_id, query.period, query.limit.unwrap_or(100))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(charges))
+}
+
+/// Close last month and post outstanding charges now instead of waiting for the hourly run
+/// (requires the `api_plans.manage` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/api-usage/billing/run",
+    tag = "api_usage",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn run_billing(
+    user: AuthenticatedUser,
+    service: web::Data<ApiBillingService>,
+) -> Result<HttpResponse, AppError> {
+    info!("Admin {} triggered an API usage billing run", user.user_id);
+
+    let report = service.run_billing(Utc::now()).await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
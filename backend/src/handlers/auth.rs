@@ -1,9 +1,15 @@
 use crate::error::AppError;
+use crate::handlers::referrals::referral_signals;
 use crate::middleware::auth::Claims;
 use crate::models::User;
+use crate::services::account_deletion_service::AccountDeletionService;
+use crate::services::analytics_service::{anonymous_id_from_request, AnalyticsService};
 use crate::services::auth_service::AuthService;
+use crate::services::email_verification_service::EmailVerificationService;
+use crate::services::geo_velocity_service::{CompleteStepUpRequest, GeoVelocityService};
+use crate::services::referral_service::ReferralService;
 use crate::services::wallet_service::WalletService;
-use actix_web::{web, HttpRequest, HttpResponse, Result, post, get, put};
+use actix_web::{web, HttpRequest, HttpResponse, Result, post, get, put, delete};
 use raffle_platform_shared::{
     AuthResponse, CreateUserRequest, LoginRequest, UserRole,
     ERROR_INVALID_CREDENTIALS, ERROR_EMAIL_ALREADY_EXISTS, ERROR_USERNAME_ALREADY_EXISTS,
@@ -15,38 +21,46 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use regex;
+use tracing::warn;
+use utoipa::ToSchema;
 
 
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RefreshTokenRequest {
     pub refresh_token: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ChangePasswordRequest {
     pub current_password: String,
     pub new_password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChangeEmailRequest {
+    pub new_email: String,
+    pub current_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ResetPasswordRequest {
     pub email: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ConfirmPasswordResetRequest {
     pub token: String,
     pub new_password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateProfileRequest {
     pub username: Option<String>,
     pub phone_number: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UserProfileResponse {
     pub id: Uuid,
     pub username: String,
@@ -61,14 +75,14 @@ pub struct UserProfileResponse {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RegistrationResponse {
     pub user: UserProfileResponse,
     pub tokens: TokenResponse,
     pub message: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct TokenResponse {
     pub access_token: String,
     pub refresh_token: String,
@@ -76,14 +90,14 @@ pub struct TokenResponse {
     pub token_type: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct LoginResponse {
     pub user: UserProfileResponse,
     pub tokens: TokenResponse,
     pub session_info: SessionInfo,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SessionInfo {
     pub session_id: Uuid,
     pub device_info: Option<String>,
@@ -91,26 +105,52 @@ pub struct SessionInfo {
     pub expires_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct LogoutRequest {
     pub logout_all_devices: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct DeleteAccountRequest {
+    /// Required for password accounts
+    pub password: Option<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct EmailVerificationRequest {
     pub token: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ResendVerificationRequest {
     pub email: String,
 }
 
+/// Attribute the client's guest analytics session to the user; never fails the auth request
+async fn stitch_anonymous_session(analytics_service: &AnalyticsService, http_req: &HttpRequest, user_id: Uuid) {
+    if let Some(anonymous_id) = anonymous_id_from_request(http_req) {
+        if let Err(e) = analytics_service.stitch_identity(anonymous_id, user_id).await {
+            warn!("Failed to stitch anonymous session {} to user {}: {}", anonymous_id, user_id, e);
+        }
+    }
+}
+
 /// Register a new user with comprehensive validation and wallet generation
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/register",
+    tag = "auth",
+    request_body = CreateUserRequest,
+    responses((status = 201, description = "Created", body = RegistrationResponse))
+)]
 #[post(\"/register\")]
 pub async fn register(
     auth_service: web::Data<AuthService>,
+    email_verification_service: web::Data<EmailVerificationService>,
     wallet_service: web::Data<WalletService>,
+    analytics_service: web::Data<AnalyticsService>,
+    referral_service: web::Data<ReferralService>,
     req: web::Json<CreateUserRequest>,
     http_req: HttpRequest,
 ) -> Result<HttpResponse, AppError> {
@@ -127,12 +167,31 @@ pub async fn register(
 
     // Validate request data
     validate_registration_request(&req)?;
+    let referral_code = req.referral_code.clone();
 
     // Register user with enhanced response
     let auth_response = auth_service
         .register(req.into_inner(), ip_address, user_agent)
         .await?;
 
+    stitch_anonymous_session(&analytics_service, &http_req, auth_response.user.id).await;
+
+    // A bad referral code doesn't undo the signup; the user can still add one before buying
+    if let Some(code) = referral_code.filter(|code| !code.trim().is_empty()) {
+        let (_, device_id) = referral_signals(&http_req);
+        if let Err(e) = referral_service
+            .attach(auth_response.user.id, &code, ip_address, device_id.as_deref())
+            .await
+        {
+            warn!("Failed to attach referral code for user {}: {}", auth_response.user.id, e);
+        }
+    }
+
+    // The account stands even if the link can't be sent yet; the user can resend it
+    if let Err(e) = email_verification_service.resend_verification(&auth_response.user.email, ip_address).await {
+        warn!("Failed to send verification email after registration for user {}: {}", auth_response.user.id, e);
+    }
+
     // Convert to enhanced registration response
     let user_profile = UserProfileResponse {
         id: auth_response.user.id,
@@ -165,9 +224,17 @@ pub async fn register(
 }
 
 /// Login user with enhanced session management
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    responses((status = 200, description = "Success", body = LoginResponse))
+)]
 #[post(\"/login\")]
 pub async fn login(
     auth_service: web::Data<AuthService>,
+    analytics_service: web::Data<AnalyticsService>,
     req: web::Json<LoginRequest>,
     http_req: HttpRequest,
 ) -> Result<HttpResponse, AppError> {
@@ -190,6 +257,8 @@ pub async fn login(
         .login(req.into_inner(), ip_address, user_agent)
         .await?;
 
+    stitch_anonymous_session(&analytics_service, &http_req, auth_response.user.id).await;
+
     // Get session information
     let session_info = auth_service
         .get_current_session_info(&auth_response.refresh_token)
@@ -233,6 +302,13 @@ pub async fn login(
 }
 
 /// Refresh access token
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/refresh",
+    tag = "auth",
+    request_body = RefreshTokenRequest,
+    responses((status = 200, description = "Success", body = raffle_platform_shared::AuthResponse))
+)]
 #[post(\"/refresh\")]
 pub async fn refresh_token(
     auth_service: web::Data<AuthService>,
@@ -246,6 +322,13 @@ pub async fn refresh_token(
 }
 
 /// Initiate password reset
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/forgot-password",
+    tag = "auth",
+    request_body = ResetPasswordRequest,
+    responses((status = 200, description = "Success"))
+)]
 #[post(\"/forgot-password\")]
 pub async fn forgot_password(
     auth_service: web::Data<AuthService>,
@@ -273,6 +356,13 @@ pub async fn forgot_password(
 }
 
 /// Complete password reset
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/reset-password",
+    tag = "auth",
+    request_body = ConfirmPasswordResetRequest,
+    responses((status = 200, description = "Success"))
+)]
 #[post(\"/reset-password\")]
 pub async fn reset_password(
     auth_service: web::Data<AuthService>,
@@ -300,19 +390,70 @@ pub async fn reset_password(
 }
 
 /// Verify email address
-#[post(\"/verify-email\")]
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/verify-email",
+    tag = "auth",
+    request_body = EmailVerificationRequest,
+    responses((status = 200, description = "Success"))
+)]
+#[post("/verify-email")]
 pub async fn verify_email(
-    auth_service: web::Data<AuthService>,
+    email_verification_service: web::Data<EmailVerificationService>,
     req: web::Json<EmailVerificationRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let ip_address = http_req
+        .connection_info()
+        .realip_remote_addr()
+        .and_then(|ip| ip.parse::<IpAddr>().ok());
+
+    email_verification_service.verify_email(&req.token, ip_address).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Email verified successfully"
+    })))
+}
+
+/// Resend the email verification link, subject to per-address backoff
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/resend-verification",
+    tag = "auth",
+    request_body = ResendVerificationRequest,
+    responses((status = 200, description = "Success"))
+)]
+#[post("/resend-verification")]
+pub async fn resend_verification(
+    email_verification_service: web::Data<EmailVerificationService>,
+    req: web::Json<ResendVerificationRequest>,
+    http_req: HttpRequest,
 ) -> Result<HttpResponse, AppError> {
-    auth_service.verify_email(&req.token).await?;
+    let ip_address = http_req
+        .connection_info()
+        .realip_remote_addr()
+        .and_then(|ip| ip.parse::<IpAddr>().ok());
+
+    let result = email_verification_service
+        .resend_verification(&req.email, ip_address)
+        .await?;
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
-        \"message\": \"Email verified successfully\"
+        "message": "If the address belongs to an unverified account, a verification email has been sent",
+        "next_resend_at": result.next_resend_at,
+        "retry_after_seconds": result.retry_after_seconds
     })))
 }
 
 /// Logout user
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/logout",
+    tag = "auth",
+    request_body = LogoutRequest,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 #[post(\"/logout\")]
 pub async fn logout(
     claims: Claims,
@@ -337,6 +478,13 @@ pub async fn logout(
 }
 
 /// Get current user profile with enhanced information
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/current-user",
+    tag = "auth",
+    responses((status = 200, description = "Success", body = UserProfileResponse)),
+    security(("bearer_auth" = []))
+)]
 #[get(\"/current-user\")]
 pub async fn get_current_user(
     claims: Claims,
@@ -365,6 +513,14 @@ pub async fn get_current_user(
 }
 
 /// Update current user profile
+#[utoipa::path(
+    put,
+    path = "/api/v1/auth/current-user",
+    tag = "auth",
+    request_body = UpdateProfileRequest,
+    responses((status = 200, description = "Success", body = UserProfileResponse)),
+    security(("bearer_auth" = []))
+)]
 #[put(\"/current-user\")]
 pub async fn update_current_user(
     claims: Claims,
@@ -399,6 +555,14 @@ pub async fn update_current_user(
 }
 
 /// Change user password
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/change-password",
+    tag = "auth",
+    request_body = ChangePasswordRequest,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 #[post(\"/change-password\")]
 pub async fn change_password(
     claims: Claims,
@@ -429,6 +593,83 @@ pub async fn change_password(
     })))
 }
 
+/// Change the user's email; a verification link is sent to the new address
+#[utoipa::path(
+    put,
+    path = "/api/v1/auth/email",
+    tag = "auth",
+    request_body = ChangeEmailRequest,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+#[put("/email")]
+pub async fn change_email(
+    claims: Claims,
+    auth_service: web::Data<AuthService>,
+    email_verification_service: web::Data<EmailVerificationService>,
+    req: web::Json<ChangeEmailRequest>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
+
+    let ip_address = http_req
+        .connection_info()
+        .realip_remote_addr()
+        .and_then(|ip| ip.parse::<IpAddr>().ok());
+
+    let user_agent = http_req
+        .headers()
+        .get("User-Agent")
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.to_string());
+
+    let user = auth_service
+        .change_email(user_id, &req.new_email, &req.current_password, ip_address, user_agent)
+        .await?;
+
+    // The change stands even if the link can't be sent yet; the user can resend it
+    if let Err(e) = email_verification_service.resend_verification(&user.email, ip_address).await {
+        warn!("Failed to send verification email after email change for user {}: {}", user_id, e);
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Email changed. Please check your new address to verify it.",
+        "email": user.email,
+        "email_verified": false
+    })))
+}
+
+/// Answer a step-up challenge with the account password
+///
+/// Issued when the user's requests jump between distant countries; requests to protected
+/// endpoints are refused with `step_up_required` until it is answered.
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/step-up/{challenge_id}",
+    tag = "auth",
+    params(("challenge_id" = Uuid, Path, description = "Challenge ID")),
+    request_body = CompleteStepUpRequest,
+    responses((status = 200, description = "Success", body = crate::services::geo_velocity_service::StepUpChallenge)),
+    security(("bearer_auth" = []))
+)]
+#[post("/step-up/{challenge_id}")]
+pub async fn complete_step_up(
+    claims: Claims,
+    challenge_id: web::Path<Uuid>,
+    geo_velocity_service: web::Data<GeoVelocityService>,
+    req: web::Json<CompleteStepUpRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
+
+    let challenge = geo_velocity_service
+        .complete_step_up(*challenge_id, user_id, &req.password)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(challenge))
+}
+
 // Validation functions
 
 /// Validate registration request
@@ -515,4 +756,50 @@ fn is_valid_email(email: &str) -> bool {
 fn is_valid_phone_number(phone: &str) -> bool {
     let phone_regex = regex::Regex::new(r\"^\\+?[1-9]\\d{1,14}$\").unwrap();
     phone_regex.is_match(phone)
-}
\ No newline at end of file
+}
+
+/// Delete the current account: deactivates it now and erases personal data after the grace period
+#[utoipa::path(
+    delete,
+    path = "/api/v1/auth/me",
+    tag = "auth",
+    request_body(content = DeleteAccountRequest, description = "Optional"),
+    responses((status = 202, description = "Accepted")),
+    security(("bearer_auth" = []))
+)]
+#[delete("/me")]
+pub async fn delete_current_user(
+    claims: Claims,
+    auth_service: web::Data<AuthService>,
+    deletion_service: web::Data<AccountDeletionService>,
+    req: Option<web::Json<DeleteAccountRequest>>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
+    let req = req.map(|r| r.into_inner()).unwrap_or_default();
+
+    let ip_address = http_req
+        .connection_info()
+        .realip_remote_addr()
+        .and_then(|ip| ip.parse::<IpAddr>().ok());
+
+    let user_agent = http_req
+        .headers()
+        .get("User-Agent")
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.to_string());
+
+    let user = auth_service.get_current_user(user_id).await?;
+    let request = deletion_service
+        .request_deletion(&user, req.password.as_deref(), req.reason, ip_address, user_agent)
+        .await?;
+
+    auth_service.logout_all_sessions(user_id).await?;
+
+    Ok(HttpResponse::Accepted().json(serde_json::json!({
+        "message": "Account deactivated and scheduled for deletion",
+        "request_id": request.id,
+        "purge_after": request.purge_after
+    })))
+}
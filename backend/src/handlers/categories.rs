@@ -0,0 +1,247 @@
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::category::{
+    CreateAttributeRequest, CreateCategoryRequest, UpdateAttributeRequest, UpdateCategoryRequest,
+};
+use crate::services::category_service::SetItemCategoryRequest;
+use crate::services::CategoryService;
+use actix_web::{web, HttpResponse, Result};
+use uuid::Uuid;
+
+// Public endpoints
+
+/// Active categories as a tree
+#[utoipa::path(
+    get,
+    path = "/api/v1/items/categories",
+    tag = "categories",
+    responses((status = 200, description = "Success", body = [crate::models::category::CategoryNode]))
+)]
+pub async fn list_categories(
+    category_service: web::Data<CategoryService>,
+) -> Result<HttpResponse, AppError> {
+    let categories = category_service.category_tree().await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "categories": categories })))
+}
+
+/// A category, its ancestors and the attributes its items can carry
+#[utoipa::path(
+    get,
+    path = "/api/v1/items/categories/{slug}",
+    tag = "categories",
+    params(("slug" = String, Path, description = "Slug")),
+    responses((status = 200, description = "Success", body = crate::services::category_service::CategoryDetail))
+)]
+pub async fn get_category(
+    slug: web::Path<String>,
+    category_service: web::Data<CategoryService>,
+) -> Result<HttpResponse, AppError> {
+    let category = category_service.category_detail(&slug).await?;
+
+    Ok(HttpResponse::Ok().json(category))
+}
+
+/// An item's category and attribute values
+#[utoipa::path(
+    get,
+    path = "/api/v1/items/{item_id}/category",
+    tag = "categories",
+    params(("item_id" = Uuid, Path, description = "Item ID")),
+    responses((status = 200, description = "Success", body = crate::services::category_service::ItemCategory))
+)]
+pub async fn get_item_category(
+    item_id: web::Path<Uuid>,
+    category_service: web::Data<CategoryService>,
+) -> Result<HttpResponse, AppError> {
+    let category = category_service.item_category(*item_id).await?;
+
+    Ok(HttpResponse::Ok().json(category))
+}
+
+// Seller endpoints
+
+/// Put one of the seller's items in a category and set its attributes
+#[utoipa::path(
+    put,
+    path = "/api/v1/items/{item_id}/category",
+    tag = "categories",
+    params(("item_id" = Uuid, Path, description = "Item ID")),
+    request_body = SetItemCategoryRequest,
+    responses((status = 200, description = "Success", body = crate::services::category_service::ItemCategory)),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_item_category(
+    user: AuthenticatedUser,
+    item_id: web::Path<Uuid>,
+    request: web::Json<SetItemCategoryRequest>,
+    category_service: web::Data<CategoryService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_seller() && !user.is_admin() {
+        return Err(AppError::Forbidden("Only sellers can categorize items".to_string()));
+    }
+
+    let category = category_service
+        .set_item_category(*item_id, user.user_id, request.into_inner(), user.is_admin())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(category))
+}
+
+// Admin endpoints
+
+/// Every category, inactive ones included (requires the `catalog.manage` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/categories",
+    tag = "categories",
+    responses((status = 200, description = "Success", body = [crate::models::category::Category])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_all_categories(
+    category_service: web::Data<CategoryService>,
+) -> Result<HttpResponse, AppError> {
+    let categories = category_service.list_all_categories().await?;
+
+    Ok(HttpResponse::Ok().json(categories))
+}
+
+/// Create a category, top-level or under another (requires the `catalog.manage` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/categories",
+    tag = "categories",
+    request_body = CreateCategoryRequest,
+    responses((status = 201, description = "Created", body = crate::models::category::Category)),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_category(
+    user: AuthenticatedUser,
+    request: web::Json<CreateCategoryRequest>,
+    category_service: web::Data<CategoryService>,
+) -> Result<HttpResponse, AppError> {
+    let category = category_service.create_category(request.into_inner(), user.user_id).await?;
+
+    Ok(HttpResponse::Created().json(category))
+}
+
+/// Rename, move, reorder or deactivate a category (requires the `catalog.manage` permission)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/categories/{category_id}",
+    tag = "categories",
+    params(("category_id" = i64, Path, description = "Category ID")),
+    request_body = UpdateCategoryRequest,
+    responses((status = 200, description = "Success", body = crate::models::category::Category)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_category(
+    user: AuthenticatedUser,
+    category_id: web::Path<i64>,
+    request: web::Json<UpdateCategoryRequest>,
+    category_service: web::Data<CategoryService>,
+) -> Result<HttpResponse, AppError> {
+    let category = category_service
+        .update_category(*category_id, request.into_inner(), user.user_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(category))
+}
+
+/// Delete a category without subcategories or items (requires the `catalog.manage` permission)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/categories/{category_id}",
+    tag = "categories",
+    params(("category_id" = i64, Path, description = "Category ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_category(
+    user: AuthenticatedUser,
+    category_id: web::Path<i64>,
+    category_service: web::Data<CategoryService>,
+) -> Result<HttpResponse, AppError> {
+    category_service.delete_category(*category_id, user.user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Every category attribute, global ones first (requires the `catalog.manage` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/category-attributes",
+    tag = "categories",
+    responses((status = 200, description = "Success", body = [crate::models::category::CategoryAttribute])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_attributes(
+    category_service: web::Data<CategoryService>,
+) -> Result<HttpResponse, AppError> {
+    let attributes = category_service.list_attributes().await?;
+
+    Ok(HttpResponse::Ok().json(attributes))
+}
+
+/// Add an attribute to a category, or to every category (requires the `catalog.manage` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/category-attributes",
+    tag = "categories",
+    request_body = CreateAttributeRequest,
+    responses((status = 201, description = "Created", body = crate::models::category::CategoryAttribute)),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_attribute(
+    user: AuthenticatedUser,
+    request: web::Json<CreateAttributeRequest>,
+    category_service: web::Data<CategoryService>,
+) -> Result<HttpResponse, AppError> {
+    let attribute = category_service.create_attribute(request.into_inner(), user.user_id).await?;
+
+    Ok(HttpResponse::Created().json(attribute))
+}
+
+/// Change an attribute's label, allowed values, or required and filterable flags
+/// (requires the `catalog.manage` permission)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/category-attributes/{attribute_id}",
+    tag = "categories",
+    params(("attribute_id" = i64, Path, description = "Attribute ID")),
+    request_body = UpdateAttributeRequest,
+    responses((status = 200, description = "Success", body = crate::models::category::CategoryAttribute)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_attribute(
+    user: AuthenticatedUser,
+    attribute_id: web::Path<i64>,
+    request: web::Json<UpdateAttributeRequest>,
+    category_service: web::Data<CategoryService>,
+) -> Result<HttpResponse, AppError> {
+    let attribute = category_service
+        .update_attribute(*attribute_id, request.into_inner(), user.user_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(attribute))
+}
+
+/// Delete an attribute and drop its values from every item
+/// (requires the `catalog.manage` permission)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/category-attributes/{attribute_id}",
+    tag = "categories",
+    params(("attribute_id" = i64, Path, description = "Attribute ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_attribute(
+    user: AuthenticatedUser,
+    attribute_id: web::Path<i64>,
+    category_service: web::Data<CategoryService>,
+) -> Result<HttpResponse, AppError> {
+    category_service.delete_attribute(*attribute_id, user.user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
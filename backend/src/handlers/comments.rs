@@ -0,0 +1,194 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::comment_service::{
+    CommentService, FlagCommentRequest, ModerateCommentRequest, PostCommentRequest,
+};
+use crate::error::AppError;
+use actix_web::{web, HttpResponse, Result};
+use serde::Deserialize;
+use tracing::{debug, info};
+use utoipa::IntoParams;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CommentListQuery {
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+    #[validate(range(min = 0))]
+    pub offset: Option<i64>,
+}
+
+/// Get comment threads for a raffle; signed-in users don't see comments from users they blocked or muted
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}/comments",
+    tag = "comments",
+    params(
+        ("raffle_id" = Uuid, Path, description = "Raffle ID"),
+        CommentListQuery,
+    ),
+    responses((status = 200, description = "Success", body = [crate::services::comment_service::CommentThread])),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn get_raffle_comments(
+    user: Option<AuthenticatedUser>,
+    raffle_id: web::Path<Uuid>,
+    query: web::Query<CommentListQuery>,
+    comment_service: web::Data<CommentService>,
+) -> Result<HttpResponse, AppError> {
+    query.validate()?;
+
+    let threads = comment_service
+        .get_raffle_comments(
+            *raffle_id,
+            user.map(|u| u.user_id),
+            query.limit.unwrap_or(20),
+            query.offset.unwrap_or(0),
+        )
+        .await?;
+
+    Ok(HttpResponse::Ok().json(threads))
+}
+
+/// Post a question or reply on a raffle
+#[utoipa::path(
+    post,
+    path = "/api/v1/raffles/{raffle_id}/comments",
+    tag = "comments",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    request_body = PostCommentRequest,
+    responses((status = 201, description = "Created", body = crate::services::comment_service::RaffleComment)),
+    security(("bearer_auth" = []))
+)]
+pub async fn post_comment(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    request: web::Json<PostCommentRequest>,
+    comment_service: web::Data<CommentService>,
+) -> Result<HttpResponse, AppError> {
+    debug!("User {} commenting on raffle {}", user.user_id, raffle_id);
+
+    let comment = comment_service
+        .post_comment(*raffle_id, user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Created().json(comment))
+}
+
+/// Delete the user's own comment
+#[utoipa::path(
+    delete,
+    path = "/api/v1/comments/{comment_id}",
+    tag = "comments",
+    params(("comment_id" = Uuid, Path, description = "Comment ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_comment(
+    user: AuthenticatedUser,
+    comment_id: web::Path<Uuid>,
+    comment_service: web::Data<CommentService>,
+) -> Result<HttpResponse, AppError> {
+    comment_service.delete_own_comment(*comment_id, user.user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Highlight a reply as the seller's answer (raffle seller only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/comments/{comment_id}/highlight",
+    tag = "comments",
+    params(("comment_id" = Uuid, Path, description = "Comment ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn highlight_answer(
+    user: AuthenticatedUser,
+    comment_id: web::Path<Uuid>,
+    comment_service: web::Data<CommentService>,
+) -> Result<HttpResponse, AppError> {
+    comment_service.highlight_answer(*comment_id, user.user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Report a comment for moderation
+#[utoipa::path(
+    post,
+    path = "/api/v1/comments/{comment_id}/flag",
+    tag = "comments",
+    params(("comment_id" = Uuid, Path, description = "Comment ID")),
+    request_body = FlagCommentRequest,
+    responses((status = 202, description = "Accepted")),
+    security(("bearer_auth" = []))
+)]
+pub async fn flag_comment(
+    user: AuthenticatedUser,
+    comment_id: web::Path<Uuid>,
+    request: web::Json<FlagCommentRequest>,
+    comment_service: web::Data<CommentService>,
+) -> Result<HttpResponse, AppError> {
+    info!("User {} flagged comment {} as {:?}", user.user_id, comment_id, request.reason);
+
+    comment_service
+        .flag_comment(*comment_id, user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Get flagged comments awaiting review (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/comments/moderation-queue",
+    tag = "comments",
+    params(CommentListQuery),
+    responses((status = 200, description = "Success", body = [crate::services::comment_service::ModerationQueueEntry])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_moderation_queue(
+    user: AuthenticatedUser,
+    query: web::Query<CommentListQuery>,
+    comment_service: web::Data<CommentService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    query.validate()?;
+
+    let queue = comment_service
+        .get_moderation_queue(query.limit.unwrap_or(50), query.offset.unwrap_or(0))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(queue))
+}
+
+/// Approve or remove a flagged comment (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/comments/{comment_id}/moderate",
+    tag = "comments",
+    params(("comment_id" = Uuid, Path, description = "Comment ID")),
+    request_body = ModerateCommentRequest,
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn moderate_comment(
+    user: AuthenticatedUser,
+    comment_id: web::Path<Uuid>,
+    request: web::Json<ModerateCommentRequest>,
+    comment_service: web::Data<CommentService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    comment_service
+        .moderate_comment(*comment_id, user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,120 @@
+use crate::middleware::auth::ApiKeyClient;
+use crate::models::partner_api_key::{SCOPE_CREDIT_CAPTURE, SCOPE_CREDIT_HOLDS};
+use crate::services::credit_service::{CaptureCreditHoldRequest, CreditService, PlaceCreditHoldRequest};
+use crate::error::AppError;
+use actix_web::{web, HttpResponse, Result};
+use tracing::info;
+use uuid::Uuid;
+
+/// Place a hold on a user's credits (partner API key with `credit_holds` scope)
+#[utoipa::path(
+    post,
+    path = "/api/v1/partner/credit-holds",
+    tag = "credit_holds",
+    request_body = PlaceCreditHoldRequest,
+    responses((status = 201, description = "Created", body = crate::services::credit_service::CreditHold)),
+    security(("api_key" = []))
+)]
+pub async fn place_credit_hold(
+    client: ApiKeyClient,
+    request: web::Json<PlaceCreditHoldRequest>,
+    credit_service: web::Data<CreditService>,
+) -> Result<HttpResponse, AppError> {
+    client.require_scope(SCOPE_CREDIT_HOLDS)?;
+
+    info!(
+        "API key {} placing credit hold {} for user {}",
+        client.key.id, request.reference, request.user_id
+    );
+
+    let hold = credit_service.place_hold(client.key.id, request.into_inner()).await?;
+
+    Ok(HttpResponse::Created().json(hold))
+}
+
+/// Get a hold placed with the calling key
+#[utoipa::path(
+    get,
+    path = "/api/v1/partner/credit-holds/{hold_id}",
+    tag = "credit_holds",
+    params(("hold_id" = Uuid, Path, description = "Hold ID")),
+    responses((status = 200, description = "Success", body = crate::services::credit_service::CreditHold)),
+    security(("api_key" = []))
+)]
+pub async fn get_credit_hold(
+    client: ApiKeyClient,
+    hold_id: web::Path<Uuid>,
+    credit_service: web::Data<CreditService>,
+) -> Result<HttpResponse, AppError> {
+    client.require_scope(SCOPE_CREDIT_HOLDS)?;
+
+    let hold = credit_service.get_hold(client.key.id, *hold_id).await?;
+
+    Ok(HttpResponse::Ok().json(hold))
+}
+
+/// Release a hold without spending it
+#[utoipa::path(
+    post,
+    path = "/api/v1/partner/credit-holds/{hold_id}/release",
+    tag = "credit_holds",
+    params(("hold_id" = Uuid, Path, description = "Hold ID")),
+    responses((status = 200, description = "Success", body = crate::services::credit_service::CreditHold)),
+    security(("api_key" = []))
+)]
+pub async fn release_credit_hold(
+    client: ApiKeyClient,
+    hold_id: web::Path<Uuid>,
+    credit_service: web::Data<CreditService>,
+) -> Result<HttpResponse, AppError> {
+    client.require_scope(SCOPE_CREDIT_HOLDS)?;
+
+    let hold = credit_service.release_hold(client.key.id, *hold_id).await?;
+
+    Ok(HttpResponse::Ok().json(hold))
+}
+
+/// Capture all or part of a hold (`credit_holds:capture` scope)
+#[utoipa::path(
+    post,
+    path = "/api/v1/partner/credit-holds/{hold_id}/capture",
+    tag = "credit_holds",
+    params(("hold_id" = Uuid, Path, description = "Hold ID")),
+    request_body(content = CaptureCreditHoldRequest, description = "Optional"),
+    responses((status = 200, description = "Success", body = crate::services::credit_service::CreditHold)),
+    security(("api_key" = []))
+)]
+pub async fn capture_credit_hold(
+    client: ApiKeyClient,
+    hold_id: web::Path<Uuid>,
+    request: Option<web::Json<CaptureCreditHoldRequest>>,
+    credit_service: web::Data<CreditService>,
+) -> Result<HttpResponse, AppError> {
+    client.require_scope(SCOPE_CREDIT_CAPTURE)?;
+
+    let request = request.map(|r| r.into_inner()).unwrap_or_default();
+    let hold = credit_service.capture_hold(client.key.id, *hold_id, request).await?;
+
+    Ok(HttpResponse::Ok().json(hold))
+}
+
+/// Void a recent capture and refund the credits (`credit_holds:capture` scope)
+#[utoipa::path(
+    post,
+    path = "/api/v1/partner/credit-holds/{hold_id}/void",
+    tag = "credit_holds",
+    params(("hold_id" = Uuid, Path, description = "Hold ID")),
+    responses((status = 200, description = "Success", body = crate::services::credit_service::CreditHold)),
+    security(("api_key" = []))
+)]
+pub async fn void_credit_hold(
+    client: ApiKeyClient,
+    hold_id: web::Path<Uuid>,
+    credit_service: web::Data<CreditService>,
+) -> Result<HttpResponse, AppError> {
+    client.require_scope(SCOPE_CREDIT_CAPTURE)?;
+
+    let hold = credit_service.void_hold(client.key.id, *hold_id).await?;
+
+    Ok(HttpResponse::Ok().json(hold))
+}
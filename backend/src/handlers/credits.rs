@@ -1,7 +1,8 @@
-use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::auth::{AuthenticatedUser, RequirePermission};
 use crate::services::credit_service::{
-    CreditService, CreditIssuanceRequest, CreditRedemptionRequest, CreditBalance,
+    BalanceReconciliation, CreditService, CreditIssuanceRequest, CreditRedemptionRequest, CreditBalance,
 };
+use crate::services::permission_service::CREDITS_ISSUE;
 use crate::error::AppError;
 use actix_web::{web, HttpResponse, Result};
 use chrono::{DateTime, Utc};
@@ -9,24 +10,30 @@ use raffle_platform_shared::{CreditSource, CreditType, CreditResponse};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 use crate::utils::validation::validation_errors_to_app_error;
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct GetCreditBalanceQuery {
-    #[validate(range(min = 1, max = 1000))]
+    #[validate(range(min = 1, max = 100))]
     pub limit: Option<i64>,
     pub include_used: Option<bool>,
+    /// `next_cursor` from the previous history page
+    pub cursor: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct GetExpiringCreditsQuery {
     #[validate(range(min = 1, max = 365))]
     pub days: Option<i64>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, IntoParams, ToSchema)]
+#[into_params(parameter_in = Query)]
 pub struct RedeemCreditsRequest {
     #[validate(range(min = 0.01))]
     pub amount: Decimal,
@@ -36,7 +43,7 @@ pub struct RedeemCreditsRequest {
     pub description: String,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct IssueCreditsRequest {
     pub user_id: Uuid,
     #[validate(range(min = 0.01))]
@@ -49,7 +56,7 @@ pub struct IssueCreditsRequest {
     pub description: String,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct IssueBonusCreditsRequest {
     #[validate(range(min = 0.01))]
     pub amount: Decimal,
@@ -58,18 +65,27 @@ pub struct IssueBonusCreditsRequest {
     pub description: String,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ReconcileBalanceRequest {
+    /// `version` of the last balance the client had from the server
+    #[validate(range(min = 0))]
+    pub version: i64,
+    /// The client's optimistic spendable balance
+    pub spendable: Option<Decimal>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct RedeemFreeItemRequest {
     pub item_id: Uuid,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CreditBalanceResponse {
     pub balance: CreditBalance,
     pub recent_credits: Vec<CreditResponse>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CreditRedemptionResponse {
     pub success: bool,
     pub total_amount_used: Decimal,
@@ -77,20 +93,28 @@ pub struct CreditRedemptionResponse {
     pub used_credits_count: usize,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ExpiringCreditsResponse {
     pub expiring_credits: Vec<CreditResponse>,
     pub total_amount: Decimal,
     pub days_until_expiry: i64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct FreeItemsResponse {
     pub available_items: Vec<serde_json::Value>, // Will be Item objects
     pub total_expiring_credits: Decimal,
 }
 
 /// Get user's credit balance and recent history
+#[utoipa::path(
+    get,
+    path = "/api/v1/credits/balance",
+    tag = "credits",
+    params(GetCreditBalanceQuery),
+    responses((status = 200, description = "Success", body = CreditBalanceResponse)),
+    security(("bearer_auth" = []))
+)]
 #[actix_web::get("/balance")]
 pub async fn get_credit_balance(
     user: AuthenticatedUser,
@@ -110,8 +134,10 @@ pub async fn get_credit_balance(
             user.user_id,
             query.include_used.unwrap_or(false),
             query.limit.or(Some(10)),
+            None,
         )
-        .await?;
+        .await?
+        .data;
 
     let response = CreditBalanceResponse {
         balance,
@@ -121,7 +147,39 @@ pub async fn get_credit_balance(
     Ok(HttpResponse::Ok().json(response))
 }
 
+/// Check the client's optimistic balance against the server's and return the server's balance
+#[utoipa::path(
+    post,
+    path = "/api/v1/credits/balance/reconcile",
+    tag = "credits",
+    request_body = ReconcileBalanceRequest,
+    responses((status = 200, description = "Success", body = BalanceReconciliation)),
+    security(("bearer_auth" = []))
+)]
+#[actix_web::post("/balance/reconcile")]
+pub async fn reconcile_credit_balance(
+    user: AuthenticatedUser,
+    request: web::Json<ReconcileBalanceRequest>,
+    credit_service: web::Data<CreditService>,
+) -> Result<HttpResponse, AppError> {
+    request.validate().map_err(validation_errors_to_app_error)?;
+
+    let reconciliation = credit_service
+        .reconcile_balance(user.user_id, request.version, request.spendable)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(reconciliation))
+}
+
 /// Get user's credit history
+#[utoipa::path(
+    get,
+    path = "/api/v1/credits/history",
+    tag = "credits",
+    params(GetCreditBalanceQuery),
+    responses((status = 200, description = "Success", body = raffle_platform_shared::CreditCursorPage)),
+    security(("bearer_auth" = []))
+)]
 #[actix_web::get("/history")]
 pub async fn get_credit_history(
     user: AuthenticatedUser,
@@ -137,6 +195,7 @@ pub async fn get_credit_history(
             user.user_id,
             query.include_used.unwrap_or(true),
             query.limit.or(Some(50)),
+            query.cursor.as_deref(),
         )
         .await?;
 
@@ -144,6 +203,14 @@ pub async fn get_credit_history(
 }
 
 /// Get expiring credits for user
+#[utoipa::path(
+    get,
+    path = "/api/v1/credits/expiring",
+    tag = "credits",
+    params(GetExpiringCreditsQuery),
+    responses((status = 200, description = "Success", body = ExpiringCreditsResponse)),
+    security(("bearer_auth" = []))
+)]
 #[actix_web::get("/expiring")]
 pub async fn get_expiring_credits(
     user: AuthenticatedUser,
@@ -171,6 +238,14 @@ pub async fn get_expiring_credits(
 }
 
 /// Redeem credits for a purchase
+#[utoipa::path(
+    post,
+    path = "/api/v1/credits/redeem",
+    tag = "credits",
+    request_body = RedeemCreditsRequest,
+    responses((status = 200, description = "Success", body = CreditRedemptionResponse)),
+    security(("bearer_auth" = []))
+)]
 #[actix_web::post("/redeem")]
 pub async fn redeem_credits(
     user: AuthenticatedUser,
@@ -205,6 +280,14 @@ pub async fn redeem_credits(
 }
 
 /// Check if user has sufficient credits
+#[utoipa::path(
+    get,
+    path = "/api/v1/credits/check-sufficient",
+    tag = "credits",
+    params(RedeemCreditsRequest),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 #[actix_web::get("/check-sufficient")]
 pub async fn check_sufficient_credits(
     user: AuthenticatedUser,
@@ -235,6 +318,13 @@ pub async fn check_sufficient_credits(
 }
 
 /// Get free items available for credit redemption
+#[utoipa::path(
+    get,
+    path = "/api/v1/credits/free-items",
+    tag = "credits",
+    responses((status = 200, description = "Success", body = FreeItemsResponse)),
+    security(("bearer_auth" = []))
+)]
 #[actix_web::get("/free-items")]
 pub async fn get_free_items(
     user: AuthenticatedUser,
@@ -267,6 +357,14 @@ pub async fn get_free_items(
 }
 
 /// Redeem free item with expiring credits
+#[utoipa::path(
+    post,
+    path = "/api/v1/credits/redeem-free-item",
+    tag = "credits",
+    request_body = RedeemFreeItemRequest,
+    responses((status = 200, description = "Success", body = CreditRedemptionResponse)),
+    security(("bearer_auth" = []))
+)]
 #[actix_web::post("/redeem-free-item")]
 pub async fn redeem_free_item(
     user: AuthenticatedUser,
@@ -294,18 +392,21 @@ pub async fn redeem_free_item(
 
 // Admin endpoints
 
-/// Issue credits to a user (admin only)
-#[actix_web::post("/admin/issue")]
+/// Issue credits to a user (requires the `credits.issue` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/credits/admin/issue",
+    tag = "credits",
+    request_body = IssueCreditsRequest,
+    responses((status = 201, description = "Created")),
+    security(("bearer_auth" = []))
+)]
+#[actix_web::post("/admin/issue", wrap = "RequirePermission::new(CREDITS_ISSUE)")]
 pub async fn issue_credits(
     user: AuthenticatedUser,
     request: web::Json<IssueCreditsRequest>,
     credit_service: web::Data<CreditService>,
 ) -> Result<HttpResponse, AppError> {
-    // Check if user is admin
-    if !user.is_admin() {
-        return Err(AppError::Forbidden("Admin access required".to_string()));
-    }
-
     request.validate().map_err(validation_errors_to_app_error)?;
 
     info!(
@@ -328,19 +429,23 @@ pub async fn issue_credits(
     Ok(HttpResponse::Created().json(credit.to_response()))
 }
 
-/// Issue bonus credits to a user (admin only)
-#[actix_web::post("/admin/issue-bonus/{user_id}")]
+/// Issue bonus credits to a user (requires the `credits.issue` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/credits/admin/issue-bonus/{user_id}",
+    tag = "credits",
+    params(("user_id" = Uuid, Path, description = "User ID")),
+    request_body = IssueBonusCreditsRequest,
+    responses((status = 201, description = "Created")),
+    security(("bearer_auth" = []))
+)]
+#[actix_web::post("/admin/issue-bonus/{user_id}", wrap = "RequirePermission::new(CREDITS_ISSUE)")]
 pub async fn issue_bonus_credits(
     user: AuthenticatedUser,
     target_user_id: web::Path<Uuid>,
     request: web::Json<IssueBonusCreditsRequest>,
     credit_service: web::Data<CreditService>,
 ) -> Result<HttpResponse, AppError> {
-    // Check if user is admin
-    if !user.is_admin() {
-        return Err(AppError::Forbidden("Admin access required".to_string()));
-    }
-
     request.validate().map_err(validation_errors_to_app_error)?;
 
     info!(
@@ -361,6 +466,13 @@ pub async fn issue_bonus_credits(
 }
 
 /// Get credit statistics (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/credits/admin/statistics",
+    tag = "credits",
+    responses((status = 200, description = "Success", body = crate::services::credit_service::CreditStatistics)),
+    security(("bearer_auth" = []))
+)]
 #[actix_web::get("/admin/statistics")]
 pub async fn get_credit_statistics(
     user: AuthenticatedUser,
@@ -379,6 +491,14 @@ pub async fn get_credit_statistics(
 }
 
 /// Get users with expiring credits (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/credits/admin/expiring-users",
+    tag = "credits",
+    params(GetExpiringCreditsQuery),
+    responses((status = 200, description = "Success", body = [crate::services::credit_service::ExpirationNotification])),
+    security(("bearer_auth" = []))
+)]
 #[actix_web::get("/admin/expiring-users")]
 pub async fn get_users_with_expiring_credits(
     user: AuthenticatedUser,
@@ -402,7 +522,14 @@ pub async fn get_users_with_expiring_credits(
     Ok(HttpResponse::Ok().json(notifications))
 }
 
-/// Cleanup expired credits (admin only)
+/// Expire credits past their expiry date now instead of waiting for the worker (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/credits/admin/cleanup-expired",
+    tag = "credits",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 #[actix_web::post("/admin/cleanup-expired")]
 pub async fn cleanup_expired_credits(
     user: AuthenticatedUser,
@@ -413,18 +540,31 @@ pub async fn cleanup_expired_credits(
         return Err(AppError::Forbidden("Admin access required".to_string()));
     }
 
-    info!("Admin {} triggering expired credits cleanup", user.user_id);
+    info!("Admin {} triggering credit expiration", user.user_id);
 
-    let deleted_count = credit_service.cleanup_expired_credits().await?;
+    let run = credit_service.expire_credits().await?;
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
-        "deleted_count": deleted_count,
-        "message": format!("Cleaned up {} expired credits", deleted_count)
+        "expired_count": run.credits_expired,
+        "users_affected": run.users_affected,
+        "amount_expired": run.amount_expired,
+        "message": format!("Expired {} credits", run.credits_expired)
     })))
 }
 
 /// Get specific user's credit balance (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/credits/admin/user-balance/{user_id}",
+    tag = "credits",
+    params(
+        ("user_id" = Uuid, Path, description = "User ID"),
+        GetCreditBalanceQuery,
+    ),
+    responses((status = 200, description = "Success", body = CreditBalanceResponse)),
+    security(("bearer_auth" = []))
+)]
 #[actix_web::get("/admin/user-balance/{user_id}")]
 pub async fn get_user_credit_balance(
     user: AuthenticatedUser,
@@ -450,8 +590,10 @@ pub async fn get_user_credit_balance(
             *target_user_id,
             query.include_used.unwrap_or(false),
             query.limit.or(Some(10)),
+            None,
         )
-        .await?;
+        .await?
+        .data;
 
     let response = CreditBalanceResponse {
         balance,
@@ -462,6 +604,13 @@ pub async fn get_user_credit_balance(
 }
 
 /// Health check endpoint for credit service
+#[utoipa::path(
+    get,
+    path = "/api/v1/credits/health",
+    tag = "credits",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 #[actix_web::get("/health")]
 pub async fn credit_service_health(
     credit_service: web::Data<CreditService>,
@@ -0,0 +1,137 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::fx_service::FxService;
+use crate::error::AppError;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use raffle_platform_shared::RaffleResponse;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// Request header that overrides the user's saved display currency
+pub const DISPLAY_CURRENCY_HEADER: &str = "X-Display-Currency";
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct DisplayCurrencyRequest {
+    /// ISO 4217 code; null returns to the settlement currency
+    pub display_currency: Option<String>,
+}
+
+/// Latest exchange rates from the settlement currency and the currencies prices may be in (public endpoint)
+#[utoipa::path(
+    get,
+    path = "/api/v1/currency/rates",
+    tag = "currency",
+    responses((status = 200, description = "Success"))
+)]
+pub async fn get_rates(fx_service: web::Data<FxService>) -> Result<HttpResponse, AppError> {
+    let rates = fx_service.latest_rates().await?;
+    let accepted_currencies = fx_service.accepted_currencies().await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "base_currency": fx_service.base_currency(),
+        "accepted_currencies": accepted_currencies,
+        "rates": rates
+    })))
+}
+
+/// Get the current user's display currency
+#[utoipa::path(
+    get,
+    path = "/api/v1/currency/preference",
+    tag = "currency",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_display_currency(
+    user: AuthenticatedUser,
+    fx_service: web::Data<FxService>,
+) -> Result<HttpResponse, AppError> {
+    let display_currency = fx_service.resolve_display_currency(None, Some(user.user_id)).await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "display_currency": display_currency,
+        "settlement_currency": fx_service.base_currency()
+    })))
+}
+
+/// Set the current user's display currency; payments are still charged in the settlement currency
+#[utoipa::path(
+    put,
+    path = "/api/v1/currency/preference",
+    tag = "currency",
+    request_body = DisplayCurrencyRequest,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_display_currency(
+    user: AuthenticatedUser,
+    request: web::Json<DisplayCurrencyRequest>,
+    fx_service: web::Data<FxService>,
+) -> Result<HttpResponse, AppError> {
+    let display_currency = fx_service
+        .set_display_currency(user.user_id, request.display_currency.as_deref())
+        .await?;
+
+    info!("User {} set display currency to {:?}", user.user_id, display_currency);
+
+    Ok(HttpResponse::Ok().json(json!({
+        "display_currency": display_currency,
+        "settlement_currency": fx_service.base_currency()
+    })))
+}
+
+/// Fetch rates from the feed now (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/fx/refresh",
+    tag = "currency",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn refresh_rates(
+    user: AuthenticatedUser,
+    fx_service: web::Data<FxService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let refreshed = fx_service.refresh_rates().await?;
+
+    info!("Admin {} refreshed {} FX rates", user.user_id, refreshed);
+
+    Ok(HttpResponse::Ok().json(json!({ "refreshed": refreshed })))
+}
+
+/// Add display prices to raffles in the currency asked for by the request or saved by the user
+///
+/// Without either, raffles priced in another currency are shown in the settlement currency.
+/// Display prices are a convenience, so failures are logged and the raffles are left as they are.
+pub async fn localize_raffles(
+    req: &HttpRequest,
+    user: Option<&AuthenticatedUser>,
+    fx_service: &FxService,
+    raffles: &mut [RaffleResponse],
+) {
+    let requested = req
+        .headers()
+        .get(DISPLAY_CURRENCY_HEADER)
+        .and_then(|h| h.to_str().ok());
+
+    let currency = match fx_service
+        .resolve_display_currency(requested, user.map(|u| u.user_id))
+        .await
+    {
+        Ok(Some(currency)) => currency,
+        Ok(None) => fx_service.base_currency().to_string(),
+        Err(e) => {
+            warn!("Ignoring display currency for request: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = fx_service.apply_to_raffles(raffles, &currency).await {
+        warn!("Failed to convert raffle prices to {}: {}", currency, e);
+    }
+}
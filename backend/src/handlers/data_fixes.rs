@@ -0,0 +1,315 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::chain_reconciliation_service::{ChainReconciliationService, StartReconciliationRequest};
+use crate::services::data_fix_service::{DataFixRequest, DataFixService};
+use crate::services::draw_scheduler_service::DrawSchedulerService;
+use crate::services::raffle_runbook_service::RaffleRunbookService;
+use crate::error::AppError;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReconciliationReportQuery {
+    /// Defaults to the latest finished run
+    pub run_id: Option<Uuid>,
+}
+
+fn client_ip(http_req: &HttpRequest) -> Option<IpAddr> {
+    http_req
+        .connection_info()
+        .realip_remote_addr()
+        .and_then(|ip| ip.parse::<IpAddr>().ok())
+}
+
+/// Preview or apply a resync of a raffle's sold count and status from the contract
+/// (requires the `raffles.repair` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/data-fixes/raffles/{raffle_id}/resync-from-chain",
+    tag = "data_fixes",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    request_body(content = DataFixRequest, description = "Optional"),
+    responses((status = 200, description = "Success", body = crate::services::data_fix_service::DataFixPlan)),
+    security(("bearer_auth" = []))
+)]
+pub async fn resync_raffle_from_chain(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    request: Option<web::Json<DataFixRequest>>,
+    http_req: HttpRequest,
+    service: web::Data<DataFixService>,
+) -> Result<HttpResponse, AppError> {
+    let request = request.map(|r| r.into_inner()).unwrap_or_default();
+
+    let plan = service
+        .resync_raffle_from_chain(*raffle_id, request, user.user_id, client_ip(&http_req))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+/// Preview or apply a recount of a raffle's boxes_sold from its purchases
+/// (requires the `raffles.repair` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/data-fixes/raffles/{raffle_id}/recount-boxes",
+    tag = "data_fixes",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    request_body(content = DataFixRequest, description = "Optional"),
+    responses((status = 200, description = "Success", body = crate::services::data_fix_service::DataFixPlan)),
+    security(("bearer_auth" = []))
+)]
+pub async fn recount_boxes_sold(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    request: Option<web::Json<DataFixRequest>>,
+    http_req: HttpRequest,
+    service: web::Data<DataFixService>,
+) -> Result<HttpResponse, AppError> {
+    let request = request.map(|r| r.into_inner()).unwrap_or_default();
+
+    let plan = service
+        .recount_boxes_sold(*raffle_id, request, user.user_id, client_ip(&http_req))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+/// Preview or apply detaching a raffle's orphaned purchases
+/// (requires the `raffles.repair` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/data-fixes/raffles/{raffle_id}/detach-orphaned-purchases",
+    tag = "data_fixes",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    request_body(content = DataFixRequest, description = "Optional"),
+    responses((status = 200, description = "Success", body = crate::services::data_fix_service::DataFixPlan)),
+    security(("bearer_auth" = []))
+)]
+pub async fn detach_orphaned_purchases(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    request: Option<web::Json<DataFixRequest>>,
+    http_req: HttpRequest,
+    service: web::Data<DataFixService>,
+) -> Result<HttpResponse, AppError> {
+    let request = request.map(|r| r.into_inner()).unwrap_or_default();
+
+    let plan = service
+        .detach_orphaned_purchases(*raffle_id, request, user.user_id, client_ip(&http_req))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+/// Preview or apply a rebuild of a user's credit balance from their credits
+/// (requires the `credits.issue` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/data-fixes/users/{user_id}/rebuild-credit-balance",
+    tag = "data_fixes",
+    params(("user_id" = Uuid, Path, description = "User ID")),
+    request_body(content = DataFixRequest, description = "Optional"),
+    responses((status = 200, description = "Success", body = crate::services::data_fix_service::DataFixPlan)),
+    security(("bearer_auth" = []))
+)]
+pub async fn rebuild_credit_balance(
+    user: AuthenticatedUser,
+    user_id: web::Path<Uuid>,
+    request: Option<web::Json<DataFixRequest>>,
+    http_req: HttpRequest,
+    service: web::Data<DataFixService>,
+) -> Result<HttpResponse, AppError> {
+    let request = request.map(|r| r.into_inner()).unwrap_or_default();
+
+    let plan = service
+        .rebuild_credit_balance(*user_id, request, user.user_id, client_ip(&http_req))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+/// Get a chain reconciliation run and the divergences it found
+/// (requires the `raffles.repair` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/reconciliation/report",
+    tag = "data_fixes",
+    params(ReconciliationReportQuery),
+    responses((status = 200, description = "Success", body = crate::services::chain_reconciliation_service::ReconciliationReport)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_reconciliation_report(
+    query: web::Query<ReconciliationReportQuery>,
+    service: web::Data<ChainReconciliationService>,
+) -> Result<HttpResponse, AppError> {
+    let report = service.get_report(query.run_id).await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Compare every raffle in scope with the chain now, optionally repairing what differs (admin only)
+///
+/// Returns the run as soon as it starts; fetch its report for the outcome.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/reconciliation/runs",
+    tag = "data_fixes",
+    request_body(content = StartReconciliationRequest, description = "Optional"),
+    responses((status = 202, description = "Accepted", body = crate::services::chain_reconciliation_service::ReconciliationRun)),
+    security(("bearer_auth" = []))
+)]
+pub async fn start_reconciliation(
+    user: AuthenticatedUser,
+    request: Option<web::Json<StartReconciliationRequest>>,
+    service: web::Data<ChainReconciliationService>,
+) -> Result<HttpResponse, AppError> {
+    let request = request.map(|r| r.into_inner()).unwrap_or_default();
+
+    let run = service.start_run(request, user.user_id).await?;
+
+    Ok(HttpResponse::Accepted().json(run))
+}
+
+/// Diagnose a stuck raffle against the chain and list the remediations that are safe now
+/// (requires the `raffles.repair` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/runbooks/raffles/{raffle_id}",
+    tag = "data_fixes",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success", body = crate::services::raffle_runbook_service::RaffleDiagnosis)),
+    security(("bearer_auth" = []))
+)]
+pub async fn diagnose_raffle(
+    raffle_id: web::Path<Uuid>,
+    service: web::Data<RaffleRunbookService>,
+) -> Result<HttpResponse, AppError> {
+    let diagnosis = service.diagnose(*raffle_id).await?;
+
+    Ok(HttpResponse::Ok().json(diagnosis))
+}
+
+/// Preview or send a new randomness request for a raffle whose draw never happened
+/// (requires the `raffles.repair` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/runbooks/raffles/{raffle_id}/rerequest-randomness",
+    tag = "data_fixes",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    request_body(content = DataFixRequest, description = "Optional"),
+    responses((status = 200, description = "Success", body = crate::services::raffle_runbook_service::RemediationPlan)),
+    security(("bearer_auth" = []))
+)]
+pub async fn rerequest_randomness(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    request: Option<web::Json<DataFixRequest>>,
+    http_req: HttpRequest,
+    service: web::Data<RaffleRunbookService>,
+) -> Result<HttpResponse, AppError> {
+    let request = request.map(|r| r.into_inner()).unwrap_or_default();
+
+    let plan = service
+        .rerequest_randomness(*raffle_id, request, user.user_id, client_ip(&http_req))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+/// Preview or apply a resync of a stuck raffle from the contract
+/// (requires the `raffles.repair` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/runbooks/raffles/{raffle_id}/resync",
+    tag = "data_fixes",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    request_body(content = DataFixRequest, description = "Optional"),
+    responses((status = 200, description = "Success", body = crate::services::raffle_runbook_service::RemediationPlan)),
+    security(("bearer_auth" = []))
+)]
+pub async fn resync_stuck_raffle(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    request: Option<web::Json<DataFixRequest>>,
+    http_req: HttpRequest,
+    service: web::Data<RaffleRunbookService>,
+) -> Result<HttpResponse, AppError> {
+    let request = request.map(|r| r.into_inner()).unwrap_or_default();
+
+    let plan = service
+        .resync(*raffle_id, request, user.user_id, client_ip(&http_req))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+/// Preview or apply completing a stuck raffle with the winners the contract drew
+/// (requires the `raffles.force_complete` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/runbooks/raffles/{raffle_id}/force-complete",
+    tag = "data_fixes",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    request_body(content = DataFixRequest, description = "Optional"),
+    responses((status = 200, description = "Success", body = crate::services::raffle_runbook_service::RemediationPlan)),
+    security(("bearer_auth" = []))
+)]
+pub async fn force_complete_stuck_raffle(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    request: Option<web::Json<DataFixRequest>>,
+    http_req: HttpRequest,
+    service: web::Data<RaffleRunbookService>,
+) -> Result<HttpResponse, AppError> {
+    let request = request.map(|r| r.into_inner()).unwrap_or_default();
+
+    let plan = service
+        .force_complete(*raffle_id, request, user.user_id, client_ip(&http_req))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+/// Full raffles waiting for their draw, longest waiting first
+/// (requires the `raffles.repair` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/runbooks/draws",
+    tag = "data_fixes",
+    responses((status = 200, description = "Success", body = Vec<crate::services::draw_scheduler_service::ScheduledDraw>)),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_scheduled_draws(
+    service: web::Data<DrawSchedulerService>,
+) -> Result<HttpResponse, AppError> {
+    let draws = service.list().await?;
+
+    Ok(HttpResponse::Ok().json(draws))
+}
+
+/// Check a waiting draw on the scheduler's next run instead of after its backoff
+/// (requires the `raffles.repair` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/runbooks/draws/{raffle_id}/retry",
+    tag = "data_fixes",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses(
+        (status = 202, description = "Accepted", body = crate::services::draw_scheduler_service::ScheduledDraw),
+        (status = 404, description = "The raffle is not waiting for its draw")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn retry_scheduled_draw(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    service: web::Data<DrawSchedulerService>,
+) -> Result<HttpResponse, AppError> {
+    let draw = service.retry_now(*raffle_id, user.user_id).await?;
+
+    Ok(HttpResponse::Accepted().json(draw))
+}
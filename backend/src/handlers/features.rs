@@ -0,0 +1,17 @@
+use crate::services::feature_flag_service::EnabledFeatures;
+use crate::error::AppError;
+use actix_web::{HttpResponse, Result};
+
+/// Feature flags on for the caller; signed-in callers may see flags still being rolled out (public endpoint)
+#[utoipa::path(
+    get,
+    path = "/api/v1/features",
+    tag = "features",
+    responses((status = 200, description = "Success", body = EnabledFeatures))
+)]
+pub async fn get_features(features: EnabledFeatures) -> Result<HttpResponse, AppError> {
+    // Depends on the caller's token, so shared caches must not keep it
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "private, no-store"))
+        .json(features))
+}
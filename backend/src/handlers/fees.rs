@@ -0,0 +1,66 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::fee_service::{FeeService, FeeType, NewFeeSchedule};
+use crate::error::AppError;
+use actix_web::{web, HttpResponse, Result};
+use serde::Deserialize;
+use tracing::{debug, info};
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FeeScheduleHistoryQuery {
+    pub fee_type: Option<FeeType>,
+}
+
+/// Get every fee schedule version, newest first (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/fee-schedules",
+    tag = "fees",
+    params(FeeScheduleHistoryQuery),
+    responses((status = 200, description = "Success", body = [crate::services::fee_service::FeeSchedule])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_fee_schedules(
+    user: AuthenticatedUser,
+    query: web::Query<FeeScheduleHistoryQuery>,
+    fee_service: web::Data<FeeService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    debug!("Admin {} requesting fee schedule history", user.user_id);
+
+    let schedules = fee_service.get_schedule_history(query.fee_type).await?;
+
+    Ok(HttpResponse::Ok().json(schedules))
+}
+
+/// Publish a new fee schedule version (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/fee-schedules",
+    tag = "fees",
+    request_body = NewFeeSchedule,
+    responses((status = 201, description = "Created", body = crate::services::fee_service::FeeSchedule)),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_fee_schedule(
+    user: AuthenticatedUser,
+    request: web::Json<NewFeeSchedule>,
+    fee_service: web::Data<FeeService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!(
+        "Admin {} publishing {:?} fee schedule for {:?} effective {}",
+        user.user_id, request.fee_type, request.scope, request.effective_from
+    );
+
+    let schedule = fee_service.create_schedule(request.into_inner(), user.user_id).await?;
+
+    Ok(HttpResponse::Created().json(schedule))
+}
@@ -0,0 +1,231 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::fulfillment_service::{
+    DisputeStatus, FulfillmentService, FulfillmentStatus, OpenDisputeRequest, ResolveDisputeRequest, ShipPrizeRequest,
+};
+use crate::error::AppError;
+use actix_web::{web, HttpResponse, Result};
+use serde::Deserialize;
+use tracing::info;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SellerFulfillmentQuery {
+    /// Defaults to every status
+    pub status: Option<FulfillmentStatus>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DisputeQueueQuery {
+    /// Defaults to open disputes
+    pub status: Option<DisputeStatus>,
+    pub limit: Option<i64>,
+}
+
+/// Get the prizes the user has won and confirmed shipping for
+#[utoipa::path(
+    get,
+    path = "/api/v1/fulfillments/mine",
+    tag = "fulfillments",
+    responses((status = 200, description = "Success", body = [crate::services::fulfillment_service::Fulfillment])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_my_fulfillments(
+    user: AuthenticatedUser,
+    fulfillment_service: web::Data<FulfillmentService>,
+) -> Result<HttpResponse, AppError> {
+    let fulfillments = fulfillment_service.list_for_winner(user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(fulfillments))
+}
+
+/// Confirm the saved shipping address for a won prize
+#[utoipa::path(
+    put,
+    path = "/api/v1/fulfillments/raffles/{raffle_id}/details",
+    tag = "fulfillments",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success", body = crate::services::fulfillment_service::Fulfillment)),
+    security(("bearer_auth" = []))
+)]
+pub async fn confirm_shipping_details(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    fulfillment_service: web::Data<FulfillmentService>,
+) -> Result<HttpResponse, AppError> {
+    let fulfillment = fulfillment_service.confirm_details(*raffle_id, user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(fulfillment))
+}
+
+/// Get the prizes the seller has to ship
+#[utoipa::path(
+    get,
+    path = "/api/v1/fulfillments/selling",
+    tag = "fulfillments",
+    params(SellerFulfillmentQuery),
+    responses((status = 200, description = "Success", body = [crate::services::fulfillment_service::Fulfillment])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_seller_fulfillments(
+    user: AuthenticatedUser,
+    query: web::Query<SellerFulfillmentQuery>,
+    fulfillment_service: web::Data<FulfillmentService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("Only sellers ship prizes".to_string()));
+    }
+
+    let fulfillments = fulfillment_service
+        .list_for_seller(user.user_id, query.status, query.limit.unwrap_or(50).clamp(1, 200))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(fulfillments))
+}
+
+/// Get a fulfillment and its disputes (winner, seller or admin)
+#[utoipa::path(
+    get,
+    path = "/api/v1/fulfillments/{fulfillment_id}",
+    tag = "fulfillments",
+    params(("fulfillment_id" = Uuid, Path, description = "Fulfillment ID")),
+    responses((status = 200, description = "Success", body = crate::services::fulfillment_service::FulfillmentDetails)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_fulfillment(
+    user: AuthenticatedUser,
+    fulfillment_id: web::Path<Uuid>,
+    fulfillment_service: web::Data<FulfillmentService>,
+) -> Result<HttpResponse, AppError> {
+    let details = fulfillment_service
+        .get_details(*fulfillment_id, user.user_id, user.is_admin())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(details))
+}
+
+/// Mark a prize as shipped with its tracking number (seller only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/fulfillments/{fulfillment_id}/ship",
+    tag = "fulfillments",
+    params(("fulfillment_id" = Uuid, Path, description = "Fulfillment ID")),
+    request_body = ShipPrizeRequest,
+    responses((status = 200, description = "Success", body = crate::services::fulfillment_service::Fulfillment)),
+    security(("bearer_auth" = []))
+)]
+pub async fn ship_prize(
+    user: AuthenticatedUser,
+    fulfillment_id: web::Path<Uuid>,
+    request: web::Json<ShipPrizeRequest>,
+    fulfillment_service: web::Data<FulfillmentService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("Only sellers ship prizes".to_string()));
+    }
+
+    let fulfillment = fulfillment_service
+        .mark_shipped(*fulfillment_id, user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(fulfillment))
+}
+
+/// Confirm a shipped prize arrived
+#[utoipa::path(
+    post,
+    path = "/api/v1/fulfillments/{fulfillment_id}/receipt",
+    tag = "fulfillments",
+    params(("fulfillment_id" = Uuid, Path, description = "Fulfillment ID")),
+    responses((status = 200, description = "Success", body = crate::services::fulfillment_service::Fulfillment)),
+    security(("bearer_auth" = []))
+)]
+pub async fn confirm_receipt(
+    user: AuthenticatedUser,
+    fulfillment_id: web::Path<Uuid>,
+    fulfillment_service: web::Data<FulfillmentService>,
+) -> Result<HttpResponse, AppError> {
+    let fulfillment = fulfillment_service.confirm_receipt(*fulfillment_id, user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(fulfillment))
+}
+
+/// Dispute a prize that didn't arrive or wasn't as described
+#[utoipa::path(
+    post,
+    path = "/api/v1/fulfillments/{fulfillment_id}/disputes",
+    tag = "fulfillments",
+    params(("fulfillment_id" = Uuid, Path, description = "Fulfillment ID")),
+    request_body = OpenDisputeRequest,
+    responses((status = 201, description = "Created", body = crate::services::fulfillment_service::Dispute)),
+    security(("bearer_auth" = []))
+)]
+pub async fn open_dispute(
+    user: AuthenticatedUser,
+    fulfillment_id: web::Path<Uuid>,
+    request: web::Json<OpenDisputeRequest>,
+    fulfillment_service: web::Data<FulfillmentService>,
+) -> Result<HttpResponse, AppError> {
+    let dispute = fulfillment_service
+        .open_dispute(*fulfillment_id, user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Created().json(dispute))
+}
+
+/// Get prize disputes (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/disputes",
+    tag = "fulfillments",
+    params(DisputeQueueQuery),
+    responses((status = 200, description = "Success", body = [crate::services::fulfillment_service::Dispute])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_dispute_queue(
+    user: AuthenticatedUser,
+    query: web::Query<DisputeQueueQuery>,
+    fulfillment_service: web::Data<FulfillmentService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let disputes = fulfillment_service
+        .list_disputes(query.status, query.limit.unwrap_or(50).clamp(1, 200))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(disputes))
+}
+
+/// Refund or reject a prize dispute (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/disputes/{dispute_id}/resolve",
+    tag = "fulfillments",
+    params(("dispute_id" = Uuid, Path, description = "Dispute ID")),
+    request_body = ResolveDisputeRequest,
+    responses((status = 200, description = "Success", body = crate::services::fulfillment_service::Dispute)),
+    security(("bearer_auth" = []))
+)]
+pub async fn resolve_dispute(
+    user: AuthenticatedUser,
+    dispute_id: web::Path<Uuid>,
+    request: web::Json<ResolveDisputeRequest>,
+    fulfillment_service: web::Data<FulfillmentService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!("Admin {} resolving dispute {} with {:?}", user.user_id, dispute_id, request.decision);
+
+    let dispute = fulfillment_service
+        .resolve_dispute(*dispute_id, user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(dispute))
+}
@@ -1,6 +1,15 @@
-use actix_web::{get, HttpResponse, Result};
+use crate::error::AppError;
+use crate::services::metrics_service::MetricsService;
+use crate::services::readiness_service::{ReadinessService, ReadinessState};
+use actix_web::{get, web, HttpResponse, Result};
 use serde_json::json;
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/health",
+    tag = "health",
+    responses((status = 200, description = "Success"))
+)]
 #[get("/health")]
 pub async fn health_check() -> Result<HttpResponse> {
     Ok(HttpResponse::Ok().json(json!({
@@ -8,4 +17,39 @@ pub async fn health_check() -> Result<HttpResponse> {
         "service": "raffle-platform-backend",
         "version": env!("CARGO_PKG_VERSION")
     })))
-}
\ No newline at end of file
+}
+
+/// Readiness for load balancers: 503 while the database is down, 200 (possibly degraded) otherwise
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "health",
+    responses((status = 200, description = "Success"))
+)]
+#[get("/ready")]
+pub async fn readiness_check(readiness_service: web::Data<ReadinessService>) -> Result<HttpResponse> {
+    let report = readiness_service.report().await;
+
+    let response = match report.status {
+        ReadinessState::Unavailable => HttpResponse::ServiceUnavailable().json(report),
+        ReadinessState::Ready | ReadinessState::Degraded => HttpResponse::Ok().json(report),
+    };
+
+    Ok(response)
+}
+
+/// Prometheus scrape target
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses((status = 200, description = "Success"))
+)]
+#[get("/metrics")]
+pub async fn metrics(metrics_service: web::Data<MetricsService>) -> Result<HttpResponse, AppError> {
+    let body = metrics_service.render().await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(body))
+}
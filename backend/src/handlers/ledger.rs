@@ -0,0 +1,103 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::ledger_service::{LedgerPostingQuery, LedgerService};
+use crate::error::AppError;
+use actix_web::{web, HttpResponse, Result};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Balances of every ledger account (requires the `payments.view` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/ledger/accounts",
+    tag = "ledger",
+    responses((status = 200, description = "Success", body = [crate::services::ledger_service::LedgerAccountBalance])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_account_balances(
+    user: AuthenticatedUser,
+    ledger_service: web::Data<LedgerService>,
+) -> Result<HttpResponse, AppError> {
+    debug!("Admin {} requesting ledger balances", user.user_id);
+
+    let balances = ledger_service.get_account_balances().await?;
+
+    Ok(HttpResponse::Ok().json(balances))
+}
+
+/// Postings to a ledger account, newest first (requires the `payments.view` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/ledger/accounts/{code}/postings",
+    tag = "ledger",
+    params(
+        ("code" = String, Path, description = "Account code"),
+        LedgerPostingQuery,
+    ),
+    responses((status = 200, description = "Success", body = crate::services::ledger_service::LedgerPostingPage)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_account_postings(
+    code: web::Path<String>,
+    query: web::Query<LedgerPostingQuery>,
+    ledger_service: web::Data<LedgerService>,
+) -> Result<HttpResponse, AppError> {
+    let page = ledger_service.get_account_postings(&code, &query).await?;
+
+    Ok(HttpResponse::Ok().json(page))
+}
+
+/// A journal entry with all of its postings (requires the `payments.view` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/ledger/entries/{entry_id}",
+    tag = "ledger",
+    params(
+        ("entry_id" = Uuid, Path, description = "Journal entry ID"),
+    ),
+    responses((status = 200, description = "Success", body = crate::services::ledger_service::JournalEntryDetail)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_entry(
+    entry_id: web::Path<Uuid>,
+    ledger_service: web::Data<LedgerService>,
+) -> Result<HttpResponse, AppError> {
+    let entry = ledger_service.get_entry(*entry_id).await?;
+
+    Ok(HttpResponse::Ok().json(entry))
+}
+
+/// The latest ledger invariant check; null before the first one has run
+/// (requires the `payments.view` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/ledger/invariants",
+    tag = "ledger",
+    responses((status = 200, description = "Success", body = Option<crate::services::ledger_service::LedgerInvariantRun>)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_latest_invariant_run(
+    ledger_service: web::Data<LedgerService>,
+) -> Result<HttpResponse, AppError> {
+    let run = ledger_service.latest_invariant_run().await?;
+
+    Ok(HttpResponse::Ok().json(run))
+}
+
+/// Check the ledger invariants now (requires the `payments.view` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/ledger/invariants/check",
+    tag = "ledger",
+    responses((status = 200, description = "Success", body = crate::services::ledger_service::LedgerInvariantRun)),
+    security(("bearer_auth" = []))
+)]
+pub async fn check_invariants(
+    user: AuthenticatedUser,
+    ledger_service: web::Data<LedgerService>,
+) -> Result<HttpResponse, AppError> {
+    info!("Admin {} checking ledger invariants", user.user_id);
+
+    let run = ledger_service.check_invariants(Some(user.user_id)).await?;
+
+    Ok(HttpResponse::Ok().json(run))
+}
@@ -0,0 +1,126 @@
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::listing_snapshot::SnapshotEntity;
+use crate::services::ListingSnapshotService;
+use actix_web::{web, HttpResponse, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SnapshotQuery {
+    /// Return the version in effect at this time; defaults to now
+    pub at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SnapshotHistoryQuery {
+    pub limit: Option<i64>,
+}
+
+/// The public fields of an item as they stood at a given time
+#[utoipa::path(
+    get,
+    path = "/api/v1/items/{item_id}/snapshot",
+    tag = "snapshots",
+    params(("item_id" = Uuid, Path, description = "Item ID"), SnapshotQuery),
+    responses(
+        (status = 200, description = "Success", body = crate::models::listing_snapshot::ListingSnapshot),
+        (status = 404, description = "The item had no snapshot at that time")
+    )
+)]
+pub async fn get_item_snapshot(
+    item_id: web::Path<Uuid>,
+    query: web::Query<SnapshotQuery>,
+    snapshot_service: web::Data<ListingSnapshotService>,
+) -> Result<HttpResponse, AppError> {
+    let snapshot = snapshot_service.effective_at(SnapshotEntity::Item, *item_id, query.at).await?;
+
+    Ok(HttpResponse::Ok().json(snapshot))
+}
+
+/// Every recorded version of an item's public fields, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/items/{item_id}/snapshots",
+    tag = "snapshots",
+    params(("item_id" = Uuid, Path, description = "Item ID"), SnapshotHistoryQuery),
+    responses((status = 200, description = "Success"))
+)]
+pub async fn get_item_snapshot_history(
+    item_id: web::Path<Uuid>,
+    query: web::Query<SnapshotHistoryQuery>,
+    snapshot_service: web::Data<ListingSnapshotService>,
+) -> Result<HttpResponse, AppError> {
+    let snapshots = snapshot_service.history(SnapshotEntity::Item, *item_id, query.limit).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "item_id": *item_id, "snapshots": snapshots })))
+}
+
+/// The public fields of a raffle as they stood at a given time
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}/snapshot",
+    tag = "snapshots",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID"), SnapshotQuery),
+    responses(
+        (status = 200, description = "Success", body = crate::models::listing_snapshot::ListingSnapshot),
+        (status = 404, description = "The raffle had no snapshot at that time")
+    )
+)]
+pub async fn get_raffle_snapshot(
+    raffle_id: web::Path<Uuid>,
+    query: web::Query<SnapshotQuery>,
+    snapshot_service: web::Data<ListingSnapshotService>,
+) -> Result<HttpResponse, AppError> {
+    let snapshot = snapshot_service.effective_at(SnapshotEntity::Raffle, *raffle_id, query.at).await?;
+
+    Ok(HttpResponse::Ok().json(snapshot))
+}
+
+/// Every recorded version of a raffle's public fields, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}/snapshots",
+    tag = "snapshots",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID"), SnapshotHistoryQuery),
+    responses((status = 200, description = "Success"))
+)]
+pub async fn get_raffle_snapshot_history(
+    raffle_id: web::Path<Uuid>,
+    query: web::Query<SnapshotHistoryQuery>,
+    snapshot_service: web::Data<ListingSnapshotService>,
+) -> Result<HttpResponse, AppError> {
+    let snapshots = snapshot_service.history(SnapshotEntity::Raffle, *raffle_id, query.limit).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "raffle_id": *raffle_id, "snapshots": snapshots })))
+}
+
+/// The item and raffle versions a box purchase was made against (buyer, seller or admin)
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}/purchases/{purchase_id}/snapshots",
+    tag = "snapshots",
+    params(
+        ("raffle_id" = Uuid, Path, description = "Raffle ID"),
+        ("purchase_id" = Uuid, Path, description = "Box purchase ID")
+    ),
+    responses((status = 200, description = "Success", body = crate::services::listing_snapshot_service::PurchaseSnapshots)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_purchase_snapshots(
+    user: AuthenticatedUser,
+    path: web::Path<(Uuid, Uuid)>,
+    snapshot_service: web::Data<ListingSnapshotService>,
+) -> Result<HttpResponse, AppError> {
+    let (raffle_id, purchase_id) = path.into_inner();
+
+    let snapshots = snapshot_service
+        .purchase_snapshots(user.user_id, user.is_admin(), raffle_id, purchase_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(snapshots))
+}
@@ -1,10 +1,38 @@
+pub mod abuse_reports;
+pub mod admin;
+pub mod analytics;
+pub mod api_usage;
 pub mod auth;
+pub mod categories;
+pub mod comments;
+pub mod credit_holds;
 pub mod credits;
+pub mod currency;
+pub mod data_fixes;
+pub mod features;
+pub mod fees;
+pub mod fulfillments;
 pub mod health;
 pub mod items;
+pub mod ledger;
+pub mod listing_snapshots;
+pub mod moderation;
+pub mod notifications;
 pub mod payments;
 pub mod performance;
+pub mod promotions;
 pub mod raffles;
+pub mod referrals;
+pub mod reports;
+pub mod sandbox;
+pub mod search;
+pub mod sellers;
+pub mod seller_webhooks;
+pub mod shipping;
+pub mod tags;
+pub mod tax;
+pub mod users;
+pub mod verification;
 pub mod wallet;
 pub mod webhooks;
 pub mod websocket;
\ No newline at end of file
@@ -0,0 +1,130 @@
+use crate::handlers::admin::client_info;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::moderation_service::{ModerationDecisionRequest, ModerationService, ModerationStatus};
+use crate::error::AppError;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use serde::Deserialize;
+use tracing::info;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ModerationQueueQuery {
+    /// Defaults to items awaiting review
+    pub status: Option<ModerationStatus>,
+    pub limit: Option<i64>,
+}
+
+/// Get items awaiting review, flagged items first (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/moderation",
+    tag = "moderation",
+    params(ModerationQueueQuery),
+    responses((status = 200, description = "Success", body = [crate::services::moderation_service::ModerationEntry])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_moderation_queue(
+    user: AuthenticatedUser,
+    query: web::Query<ModerationQueueQuery>,
+    moderation_service: web::Data<ModerationService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let queue = moderation_service
+        .queue(query.status, query.limit.unwrap_or(50))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(queue))
+}
+
+/// Get an item's review and what the automated checks found (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/moderation/{item_id}",
+    tag = "moderation",
+    params(("item_id" = Uuid, Path, description = "Item ID")),
+    responses((status = 200, description = "Success", body = crate::services::moderation_service::ModerationEntry)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_moderation_entry(
+    user: AuthenticatedUser,
+    item_id: web::Path<Uuid>,
+    moderation_service: web::Data<ModerationService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let entry = moderation_service.get(*item_id).await?;
+
+    Ok(HttpResponse::Ok().json(entry))
+}
+
+/// Approve an item so it can be raffled; the seller is notified (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/moderation/{item_id}/approve",
+    tag = "moderation",
+    params(("item_id" = Uuid, Path, description = "Item ID")),
+    request_body(content = ModerationDecisionRequest, description = "Optional"),
+    responses((status = 200, description = "Success", body = crate::services::moderation_service::ModerationEntry)),
+    security(("bearer_auth" = []))
+)]
+pub async fn approve_item(
+    user: AuthenticatedUser,
+    item_id: web::Path<Uuid>,
+    request: Option<web::Json<ModerationDecisionRequest>>,
+    http_req: HttpRequest,
+    moderation_service: web::Data<ModerationService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!("Admin {} approving item {}", user.user_id, item_id);
+
+    let request = request
+        .map(|r| r.into_inner())
+        .unwrap_or(ModerationDecisionRequest { reason: None });
+    let (ip_address, user_agent) = client_info(&http_req);
+    let entry = moderation_service
+        .approve(*item_id, user.user_id, request, ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(entry))
+}
+
+/// Reject an item with a reason the seller is sent (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/moderation/{item_id}/reject",
+    tag = "moderation",
+    params(("item_id" = Uuid, Path, description = "Item ID")),
+    request_body = ModerationDecisionRequest,
+    responses((status = 200, description = "Success", body = crate::services::moderation_service::ModerationEntry)),
+    security(("bearer_auth" = []))
+)]
+pub async fn reject_item(
+    user: AuthenticatedUser,
+    item_id: web::Path<Uuid>,
+    request: web::Json<ModerationDecisionRequest>,
+    http_req: HttpRequest,
+    moderation_service: web::Data<ModerationService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!("Admin {} rejecting item {}", user.user_id, item_id);
+
+    let (ip_address, user_agent) = client_info(&http_req);
+    let entry = moderation_service
+        .reject(*item_id, user.user_id, request.into_inner(), ip_address, user_agent)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(entry))
+}
@@ -0,0 +1,82 @@
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::notification_inbox_service::{NotificationInboxService, NotificationListQuery};
+use actix_web::{web, HttpResponse, Result};
+use uuid::Uuid;
+
+/// The current user's notifications, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/notifications",
+    tag = "notifications",
+    params(NotificationListQuery),
+    responses((status = 200, description = "Success", body = crate::services::notification_inbox_service::NotificationPage)),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_notifications(
+    user: AuthenticatedUser,
+    query: web::Query<NotificationListQuery>,
+    inbox_service: web::Data<NotificationInboxService>,
+) -> Result<HttpResponse, AppError> {
+    let page = inbox_service.list(user.user_id, &query).await?;
+
+    Ok(HttpResponse::Ok().json(page))
+}
+
+/// How many of the current user's notifications are unread; changes are also pushed over WebSocket
+#[utoipa::path(
+    get,
+    path = "/api/v1/notifications/unread-count",
+    tag = "notifications",
+    responses((status = 200, description = "Success", body = crate::services::notification_inbox_service::UnreadCount)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_unread_count(
+    user: AuthenticatedUser,
+    inbox_service: web::Data<NotificationInboxService>,
+) -> Result<HttpResponse, AppError> {
+    let count = inbox_service.unread_count(user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(count))
+}
+
+/// Mark one of the current user's notifications read
+#[utoipa::path(
+    post,
+    path = "/api/v1/notifications/{notification_id}/read",
+    tag = "notifications",
+    params(
+        ("notification_id" = Uuid, Path, description = "Notification ID"),
+    ),
+    responses(
+        (status = 200, description = "Success", body = crate::models::notification::Notification),
+        (status = 404, description = "Not one of the user's notifications")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn mark_notification_read(
+    user: AuthenticatedUser,
+    notification_id: web::Path<Uuid>,
+    inbox_service: web::Data<NotificationInboxService>,
+) -> Result<HttpResponse, AppError> {
+    let notification = inbox_service.mark_read(user.user_id, *notification_id).await?;
+
+    Ok(HttpResponse::Ok().json(notification))
+}
+
+/// Mark all of the current user's notifications read
+#[utoipa::path(
+    post,
+    path = "/api/v1/notifications/read-all",
+    tag = "notifications",
+    responses((status = 200, description = "Success", body = crate::services::notification_inbox_service::MarkAllReadResponse)),
+    security(("bearer_auth" = []))
+)]
+pub async fn mark_all_notifications_read(
+    user: AuthenticatedUser,
+    inbox_service: web::Data<NotificationInboxService>,
+) -> Result<HttpResponse, AppError> {
+    let response = inbox_service.mark_all_read(user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
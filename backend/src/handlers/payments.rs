@@ -1,17 +1,23 @@
 use crate::middleware::auth::AuthenticatedUser;
 use crate::services::payment_service::{
     PaymentService, PaymentIntentRequest, SubscriptionRequest, PaymentStatus,
+    RefundDestination, RefundRequest, RefundStatus, WalletType,
 };
+use crate::config::AppConfig;
+use crate::services::payment_search_service::{PaymentSearchFilters, PaymentSearchService};
+use crate::services::webhook_service::{WebhookService, WebhookSource};
+use crate::handlers::webhooks::delivery_accepted;
 use crate::error::AppError;
 use actix_web::{web, HttpRequest, HttpResponse, Result};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tracing::{debug, info, warn};
+use tracing::{debug, info};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CreatePaymentIntentRequest {
     #[validate(range(min = 1.0, max = 10000.0))]
     pub amount: Decimal,
@@ -20,15 +26,17 @@ pub struct CreatePaymentIntentRequest {
     #[validate(length(min = 1, max = 500))]
     pub description: String,
     pub metadata: Option<HashMap<String, String>>,
+    /// Set when paying from an Apple Pay or Google Pay sheet
+    pub wallet: Option<WalletType>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct ConfirmPaymentIntentRequest {
     #[validate(length(min = 1))]
     pub payment_method_id: String,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CreateSubscriptionRequest {
     #[validate(length(min = 1))]
     pub price_id: String,
@@ -36,24 +44,43 @@ pub struct CreateSubscriptionRequest {
     pub metadata: Option<HashMap<String, String>>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct GetPaymentHistoryQuery {
     #[validate(range(min = 1, max = 100))]
     pub limit: Option<i64>,
     pub status: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PaymentSearchPageQuery {
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ProcessRefundRequest {
+    pub destination: RefundDestination,
+    /// Partial refund amount in dollars; omit to refund the remaining balance
+    pub amount: Option<Decimal>,
+    #[validate(length(max = 500))]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PaymentIntentCreatedResponse {
     pub payment_intent_id: String,
     pub client_secret: String,
     pub amount: i64,
     pub currency: String,
     pub status: String,
+    pub payment_method_types: Vec<String>,
     pub next_action: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SubscriptionCreatedResponse {
     pub subscription_id: String,
     pub status: String,
@@ -80,6 +107,16 @@ pub struct CardDetails {
 }
 
 /// Create a payment intent for credit purchase
+///
+/// Accepts an `Idempotency-Key` header; retries with the same key replay the first response.
+#[utoipa::path(
+    post,
+    path = "/api/v1/payments/create-intent",
+    tag = "payments",
+    request_body = CreatePaymentIntentRequest,
+    responses((status = 201, description = "Created", body = PaymentIntentCreatedResponse)),
+    security(("bearer_auth" = []))
+)]
 pub async fn create_payment_intent(
     user: AuthenticatedUser,
     request: web::Json<CreatePaymentIntentRequest>,
@@ -98,6 +135,7 @@ pub async fn create_payment_intent(
         currency: request.currency.clone(),
         description: request.description.clone(),
         metadata: request.metadata.clone().unwrap_or_default(),
+        wallet: request.wallet,
     };
 
     let response = payment_service.create_payment_intent(payment_request).await?;
@@ -113,11 +151,23 @@ pub async fn create_payment_intent(
         amount: response.amount,
         currency: response.currency,
         status: response.status,
+        payment_method_types: response.payment_method_types,
         next_action: None, // Will be populated by Stripe if needed
     }))
 }
 
 /// Confirm a payment intent
+#[utoipa::path(
+    post,
+    path = "/api/v1/payments/confirm-intent/{payment_intent_id}",
+    tag = "payments",
+    params(
+        ("payment_intent_id" = String, Path, description = "Payment intent ID"),
+    ),
+    request_body = ConfirmPaymentIntentRequest,
+    responses((status = 200, description = "Success", body = crate::services::payment_service::PaymentIntentResponse)),
+    security(("bearer_auth" = []))
+)]
 pub async fn confirm_payment_intent(
     user: AuthenticatedUser,
     payment_intent_id: web::Path<String>,
@@ -144,6 +194,16 @@ pub async fn confirm_payment_intent(
 }
 
 /// Get payment intent status
+#[utoipa::path(
+    get,
+    path = "/api/v1/payments/intent-status/{payment_intent_id}",
+    tag = "payments",
+    params(
+        ("payment_intent_id" = String, Path, description = "Payment intent ID"),
+    ),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_payment_intent_status(
     user: AuthenticatedUser,
     payment_intent_id: web::Path<String>,
@@ -177,6 +237,14 @@ pub async fn get_payment_intent_status(
 }
 
 /// Get user's payment history
+#[utoipa::path(
+    get,
+    path = "/api/v1/payments/history",
+    tag = "payments",
+    params(GetPaymentHistoryQuery),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_payment_history(
     user: AuthenticatedUser,
     query: web::Query<GetPaymentHistoryQuery>,
@@ -199,6 +267,7 @@ pub async fn get_payment_history(
             "failed" => PaymentStatus::Failed,
             "cancelled" => PaymentStatus::Cancelled,
             "refunded" => PaymentStatus::Refunded,
+            "partially_refunded" => PaymentStatus::PartiallyRefunded,
             _ => return Err(AppError::Validation("Invalid status filter".to_string())),
         };
 
@@ -218,6 +287,14 @@ pub async fn get_payment_history(
 }
 
 /// Create a subscription for seller plans
+#[utoipa::path(
+    post,
+    path = "/api/v1/payments/subscriptions",
+    tag = "payments",
+    request_body = CreateSubscriptionRequest,
+    responses((status = 201, description = "Created", body = SubscriptionCreatedResponse)),
+    security(("bearer_auth" = []))
+)]
 pub async fn create_subscription(
     user: AuthenticatedUser,
     request: web::Json<CreateSubscriptionRequest>,
@@ -255,6 +332,13 @@ pub async fn create_subscription(
 }
 
 /// Get user's active subscriptions
+#[utoipa::path(
+    get,
+    path = "/api/v1/payments/subscriptions",
+    tag = "payments",
+    responses((status = 200, description = "Success", body = [crate::services::payment_service::SubscriptionRecord])),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_user_subscriptions(
     user: AuthenticatedUser,
     payment_service: web::Data<PaymentService>,
@@ -267,6 +351,14 @@ pub async fn get_user_subscriptions(
 }
 
 /// Cancel a subscription
+#[utoipa::path(
+    post,
+    path = "/api/v1/payments/subscriptions/{subscription_id}/cancel",
+    tag = "payments",
+    params(("subscription_id" = String, Path, description = "Subscription ID")),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 pub async fn cancel_subscription(
     user: AuthenticatedUser,
     subscription_id: web::Path<String>,
@@ -299,6 +391,13 @@ pub async fn cancel_subscription(
 }
 
 /// Get payment statistics for user
+#[utoipa::path(
+    get,
+    path = "/api/v1/payments/statistics",
+    tag = "payments",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_payment_statistics(
     user: AuthenticatedUser,
     payment_service: web::Data<PaymentService>,
@@ -341,6 +440,13 @@ pub async fn get_payment_statistics(
 // Admin endpoints
 
 /// Get payment analytics (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/payments/admin/analytics",
+    tag = "payments",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_payment_analytics(
     user: AuthenticatedUser,
     payment_service: web::Data<PaymentService>,
@@ -363,16 +469,78 @@ pub async fn get_payment_analytics(
     })))
 }
 
-/// Get user payment details (admin only)
+/// Search payments and transactions by email, card last4, amount, Stripe id or free text (requires the `payments.view` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/payments/admin/search",
+    tag = "payments",
+    params(
+        PaymentSearchFilters,
+        PaymentSearchPageQuery,
+    ),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn search_payments(
+    user: AuthenticatedUser,
+    filters: web::Query<PaymentSearchFilters>,
+    page: web::Query<PaymentSearchPageQuery>,
+    search_service: web::Data<PaymentSearchService>,
+) -> Result<HttpResponse, AppError> {
+    page.validate()?;
+
+    debug!("Admin {} searching payments: {:?}", user.user_id, filters);
+
+    let results = search_service
+        .search(&filters, page.limit, page.cursor.as_deref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(results))
+}
+
+/// Export the payments and transactions matching a search as CSV (requires the `payments.view` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/payments/admin/search/export",
+    tag = "payments",
+    params(PaymentSearchFilters),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn export_payment_search(
+    user: AuthenticatedUser,
+    filters: web::Query<PaymentSearchFilters>,
+    search_service: web::Data<PaymentSearchService>,
+) -> Result<HttpResponse, AppError> {
+    info!("Admin {} exporting payment search: {:?}", user.user_id, filters);
+
+    let export = search_service.export_csv(&filters).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv")
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", export.filename),
+        ))
+        .insert_header(("X-Export-Row-Count", export.row_count.to_string()))
+        .insert_header(("X-Export-Truncated", export.truncated.to_string()))
+        .body(export.body))
+}
+
+/// Get user payment details (requires the `payments.view` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/payments/admin/user/{user_id}",
+    tag = "payments",
+    params(("user_id" = Uuid, Path, description = "User ID")),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_user_payment_details(
     user: AuthenticatedUser,
     target_user_id: web::Path<Uuid>,
     payment_service: web::Data<PaymentService>,
 ) -> Result<HttpResponse, AppError> {
-    if !user.is_admin() {
-        return Err(AppError::Forbidden("Admin access required".to_string()));
-    }
-
     debug!(
         "Admin {} requesting payment details for user {}",
         user.user_id, target_user_id
@@ -391,35 +559,88 @@ pub async fn get_user_payment_details(
     })))
 }
 
-/// Process refund (admin only)
+/// Process refund (requires the `payments.refund` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/payments/admin/refund/{payment_id}",
+    tag = "payments",
+    params(("payment_id" = Uuid, Path, description = "Payment ID")),
+    request_body = ProcessRefundRequest,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 pub async fn process_refund(
     user: AuthenticatedUser,
     payment_id: web::Path<Uuid>,
+    request: web::Json<ProcessRefundRequest>,
     payment_service: web::Data<PaymentService>,
 ) -> Result<HttpResponse, AppError> {
-    if !user.is_admin() {
-        return Err(AppError::Forbidden("Admin access required".to_string()));
-    }
+    request.validate()?;
 
     info!(
-        "Admin {} processing refund for payment {}",
-        user.user_id, payment_id
+        "Admin {} processing {:?} refund for payment {} (amount: {:?})",
+        user.user_id, request.destination, payment_id, request.amount
     );
 
-    // This would implement the actual refund logic
-    // For now, return a placeholder response
+    let refund = payment_service
+        .refund_payment(RefundRequest {
+            payment_id: *payment_id,
+            destination: request.destination,
+            amount: request.amount,
+            reason: request.reason.clone(),
+            requested_by: user.user_id,
+        })
+        .await?;
+
+    let message = match refund.status {
+        RefundStatus::Succeeded => "Refund processed successfully",
+        _ => "Refund submitted to payment provider",
+    };
+
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
-        "message": "Refund processed successfully",
-        "payment_id": *payment_id
+        "message": message,
+        "payment_id": *payment_id,
+        "refund": refund
+    })))
+}
+
+/// Get refunds for a payment (requires the `payments.view` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/payments/admin/refunds/{payment_id}",
+    tag = "payments",
+    params(("payment_id" = Uuid, Path, description = "Payment ID")),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_payment_refunds(
+    user: AuthenticatedUser,
+    payment_id: web::Path<Uuid>,
+    payment_service: web::Data<PaymentService>,
+) -> Result<HttpResponse, AppError> {
+    let refunds = payment_service.get_refunds(*payment_id).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "payment_id": *payment_id,
+        "refunds": refunds
     })))
 }
 
 /// Webhook endpoint for Stripe events
+///
+/// The event is stored and processed by the webhook service, which retries failures itself.
+#[utoipa::path(
+    post,
+    path = "/api/payments/webhook",
+    tag = "payments",
+    responses((status = 200, description = "Success"))
+)]
 pub async fn stripe_webhook(
     req: HttpRequest,
     body: web::Bytes,
     payment_service: web::Data<PaymentService>,
+    webhook_service: web::Data<WebhookService>,
 ) -> Result<HttpResponse, AppError> {
     // Get Stripe signature from headers
     let signature = req
@@ -435,78 +656,39 @@ pub async fn stripe_webhook(
     let payload: serde_json::Value = serde_json::from_slice(&body)
         .map_err(|e| AppError::Validation(format!("Invalid JSON: {}", e)))?;
 
-    let event_type = payload["type"]
-        .as_str()
-        .ok_or_else(|| AppError::Validation("Missing event type".to_string()))?;
-
-    info!("Received Stripe webhook: {}", event_type);
-
-    // Process webhook event
-    match event_type {
-        "payment_intent.succeeded" => {
-            let payment_intent_id = payload["data"]["object"]["id"]
-                .as_str()
-                .ok_or_else(|| AppError::Validation("Missing payment intent ID".to_string()))?;
-
-            let amount = payload["data"]["object"]["amount"]
-                .as_u64()
-                .ok_or_else(|| AppError::Validation("Missing amount".to_string()))?;
-
-            payment_service
-                .process_successful_payment(payment_intent_id, amount)
-                .await?;
-        }
-        "payment_intent.payment_failed" => {
-            let payment_intent_id = payload["data"]["object"]["id"]
-                .as_str()
-                .ok_or_else(|| AppError::Validation("Missing payment intent ID".to_string()))?;
-
-            let failure_reason = payload["data"]["object"]["last_payment_error"]["message"]
-                .as_str()
-                .unwrap_or("Unknown error");
-
-            payment_service
-                .process_failed_payment(payment_intent_id, failure_reason)
-                .await?;
-        }
-        "invoice.payment_succeeded" => {
-            let subscription_id = payload["data"]["object"]["subscription"]
-                .as_str()
-                .ok_or_else(|| AppError::Validation("Missing subscription ID".to_string()))?;
-
-            payment_service
-                .process_subscription_payment_success(subscription_id)
-                .await?;
-        }
-        "invoice.payment_failed" => {
-            let subscription_id = payload["data"]["object"]["subscription"]
-                .as_str()
-                .ok_or_else(|| AppError::Validation("Missing subscription ID".to_string()))?;
-
-            payment_service
-                .process_subscription_payment_failure(subscription_id)
-                .await?;
-        }
-        "customer.subscription.deleted" => {
-            let subscription_id = payload["data"]["object"]["id"]
-                .as_str()
-                .ok_or_else(|| AppError::Validation("Missing subscription ID".to_string()))?;
-
-            payment_service
-                .process_subscription_cancellation(subscription_id)
-                .await?;
-        }
-        _ => {
-            warn!("Unhandled Stripe webhook event: {}", event_type);
-        }
+    if payload["type"].as_str().is_none() {
+        return Err(AppError::Validation("Missing event type".to_string()));
     }
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "received": true
-    })))
+    let delivery = webhook_service.receive(WebhookSource::Stripe, payload).await?;
+
+    info!("Received Stripe webhook {}: {:?}", delivery.event_type, delivery.status);
+
+    Ok(delivery_accepted(&delivery))
+}
+
+/// Apple Pay domain verification file, served from the site root where Apple looks for it
+pub async fn apple_pay_domain_association(config: web::Data<AppConfig>) -> Result<HttpResponse, AppError> {
+    let path = config
+        .apple_pay_domain_association_path
+        .as_deref()
+        .ok_or_else(|| AppError::NotFound("Apple Pay is not configured".to_string()))?;
+
+    let body = tokio::fs::read(path).await.map_err(|e| {
+        AppError::Internal(format!("Failed to read Apple Pay domain association file {}: {}", path, e))
+    })?;
+
+    Ok(HttpResponse::Ok().content_type("text/plain").body(body))
 }
 
 /// Health check endpoint for payment service
+#[utoipa::path(
+    get,
+    path = "/api/v1/payments/health",
+    tag = "payments",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 pub async fn payment_service_health(
     payment_service: web::Data<PaymentService>,
 ) -> Result<HttpResponse, AppError> {
@@ -539,6 +721,7 @@ pub fn configure_payment_routes(cfg: &mut web::ServiceConfig) {
             .route("/admin/analytics", web::get().to(get_payment_analytics))
             .route("/admin/user/{user_id}", web::get().to(get_user_payment_details))
             .route("/admin/refund/{payment_id}", web::post().to(process_refund))
+            .route("/admin/refunds/{payment_id}", web::get().to(get_payment_refunds))
             
             // Webhook endpoint
             .route("/webhook", web::post().to(stripe_webhook))
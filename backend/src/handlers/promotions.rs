@@ -0,0 +1,128 @@
+use crate::error::AppError;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::promotion::{NewPromotion, PromotionStatus};
+use crate::services::PromotionService;
+use actix_web::{web, HttpResponse, Result};
+use serde::Deserialize;
+use tracing::info;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PromotionQuery {
+    pub status: Option<PromotionStatus>,
+    pub limit: Option<i64>,
+}
+
+/// Promotions that apply right now, for banners and checkout
+#[utoipa::path(
+    get,
+    path = "/api/v1/promotions/active",
+    tag = "promotions",
+    responses((status = 200, description = "Success", body = [crate::models::promotion::Promotion]))
+)]
+pub async fn get_active_promotions(
+    promotion_service: web::Data<PromotionService>,
+) -> Result<HttpResponse, AppError> {
+    let promotions = promotion_service.active().await?;
+
+    Ok(HttpResponse::Ok().json(promotions))
+}
+
+/// List promotions, latest start first (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/promotions",
+    tag = "promotions",
+    params(PromotionQuery),
+    responses((status = 200, description = "Success", body = [crate::models::promotion::Promotion])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_promotions(
+    user: AuthenticatedUser,
+    query: web::Query<PromotionQuery>,
+    promotion_service: web::Data<PromotionService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let promotions = promotion_service.list(query.status, limit).await?;
+
+    Ok(HttpResponse::Ok().json(promotions))
+}
+
+/// Schedule a credit multiplier or fee discount (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/promotions",
+    tag = "promotions",
+    request_body = NewPromotion,
+    responses((status = 201, description = "Created")),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_promotion(
+    user: AuthenticatedUser,
+    request: web::Json<NewPromotion>,
+    promotion_service: web::Data<PromotionService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let promotion = promotion_service.create(request.into_inner(), user.user_id).await?;
+
+    info!("Admin {} scheduled promotion {}", user.user_id, promotion.id);
+
+    Ok(HttpResponse::Created().json(promotion))
+}
+
+/// Stop a scheduled or running promotion (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/promotions/{promotion_id}/cancel",
+    tag = "promotions",
+    params(("promotion_id" = Uuid, Path, description = "Promotion ID")),
+    responses((status = 200, description = "Success", body = crate::models::promotion::Promotion)),
+    security(("bearer_auth" = []))
+)]
+pub async fn cancel_promotion(
+    user: AuthenticatedUser,
+    promotion_id: web::Path<Uuid>,
+    promotion_service: web::Data<PromotionService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!("Admin {} cancelling promotion {}", user.user_id, promotion_id);
+
+    let promotion = promotion_service.cancel(*promotion_id).await?;
+
+    Ok(HttpResponse::Ok().json(promotion))
+}
+
+/// Get a promotion's uplift against the period before it (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/promotions/{promotion_id}/report",
+    tag = "promotions",
+    params(("promotion_id" = Uuid, Path, description = "Promotion ID")),
+    responses((status = 200, description = "Success", body = crate::services::promotion_service::PromotionReport)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_promotion_report(
+    user: AuthenticatedUser,
+    promotion_id: web::Path<Uuid>,
+    promotion_service: web::Data<PromotionService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let report = promotion_service.report(*promotion_id).await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
@@ -1,16 +1,28 @@
 use crate::middleware::auth::AuthenticatedUser;
-use crate::services::raffle_service::{RaffleService, RaffleSearchParams, BoxPurchaseRequest};
+use crate::models::raffle_prize_tier::{PrizeChoice, RafflePrizeTier};
+use crate::models::winner_display;
+use crate::services::raffle_service::{RaffleService, RaffleSearchParams, BoxPurchaseRequest, BoxPricing};
+use crate::services::receipt_service::ReceiptService;
+use crate::services::external_wallet_service::{ExternalWalletService, PreparedExternalPurchase};
+use crate::services::waitlist_service::WaitlistService;
+use crate::services::fx_service::FxService;
+use crate::services::geo_compliance_service::{GeoComplianceService, RequestCountry};
+use crate::services::shipping_service::ShippingService;
+use crate::handlers::admin::client_info;
+use crate::handlers::currency::localize_raffles;
+use crate::handlers::shipping::estimate_shipping;
 use crate::error::AppError;
-use actix_web::{web, HttpResponse, Result};
-use raffle_platform_shared::{RaffleStatus, CreateRaffleRequest, PaginatedResponse, RaffleResponse, BoxPurchaseResponse};
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use raffle_platform_shared::{RaffleStatus, RaffleDeadlineAction, CreateRaffleRequest, PaginatedResponse, RaffleResponse, BoxPurchaseResponse, GridCell, RaffleLayoutDefinition, PrizeTierDefinition, PricingCurveDefinition};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tracing::{debug, info, warn};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CreateRaffleRequestHandler {
     pub item_id: Uuid,
     #[validate(range(min = 1, max = 10000))]
@@ -19,13 +31,41 @@ pub struct CreateRaffleRequestHandler {
     pub box_price: Decimal,
     #[validate(range(min = 1, max = 100))]
     pub total_winners: i32,
-    #[validate(range(min = 1, max = 100))]
+    // Upper bounds are admin-configurable and checked by the raffle service
+    #[validate(range(min = 1))]
     pub grid_rows: i32,
-    #[validate(range(min = 1, max = 100))]
+    #[validate(range(min = 1))]
     pub grid_cols: i32,
+    pub layout: Option<RaffleLayoutDefinition>,
+    #[serde(default)]
+    pub prize_tiers: Vec<PrizeTierDefinition>,
+    pub pricing_curve: Option<PricingCurveDefinition>,
+    pub starts_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub ends_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub deadline_action: RaffleDeadlineAction,
+    #[validate(range(min = 1))]
+    pub max_boxes_per_user: Option<i32>,
+    pub credit_alternative: Option<Decimal>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GridChangesQuery {
+    /// Grid sequence the client last applied
+    #[validate(range(min = 0))]
+    pub since: i64,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WinnerGalleryQuery {
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct RaffleSearchQuery {
     pub status: Option<String>,
     pub item_id: Option<Uuid>,
@@ -36,24 +76,63 @@ pub struct RaffleSearchQuery {
     pub sort_by: Option<String>,
     #[validate(range(min = 1, max = 100))]
     pub limit: Option<i64>,
+    /// Only used by purchase history, which still pages by offset
     #[validate(range(min = 0))]
     pub offset: Option<i64>,
+    /// `next_cursor` from the previous page of a raffle search
+    pub cursor: Option<String>,
+    /// Comma-separated tags; raffles carrying any of them match
+    pub tags: Option<String>,
+    /// Only match raffles carrying every tag
+    pub match_all_tags: Option<bool>,
+    /// Category slug; items in it or any category below it match
+    pub category: Option<String>,
+    /// Comma-separated `key:value` item attribute filters, e.g. `condition:new,brand:Nike`
+    pub attributes: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+impl RaffleSearchQuery {
+    fn tag_list(&self) -> Vec<String> {
+        split_list(self.tags.as_deref())
+    }
+
+    fn attribute_list(&self) -> Vec<String> {
+        split_list(self.attributes.as_deref())
+    }
+}
+
+fn split_list(list: Option<&str>) -> Vec<String> {
+    list.map(|list| {
+        list.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct BuyBoxRequestHandler {
-    #[validate(length(min = 1, max = 100))]
+    #[serde(default)]
+    #[validate(length(max = 100))]
     pub box_numbers: Vec<i32>,
+    /// Alternative to box numbers for shaped grids
+    #[serde(default)]
+    #[validate(length(max = 100))]
+    pub cells: Vec<GridCell>,
     pub use_credits: bool,
+    /// Linked wallet to record the boxes under on chain; it redeems the returned voucher itself
+    pub external_wallet_id: Option<Uuid>,
 }
 
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct CancelRaffleRequest {
     #[validate(length(min = 1, max = 500))]
     pub reason: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct RaffleCreatedResponse {
     pub raffle: RaffleResponse,
     pub transaction_fee: Option<Decimal>,
@@ -61,18 +140,23 @@ pub struct RaffleCreatedResponse {
     pub message: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct BoxPurchaseSuccessResponse {
     pub purchases: Vec<BoxPurchaseResponse>,
     pub total_cost: Decimal,
     pub raffle_status: RaffleStatus,
     pub boxes_remaining: i32,
     pub message: String,
+    /// Voucher for the external wallet, when one was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_purchase: Option<PreparedExternalPurchase>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct GridStateResponse {
     pub raffle_id: Uuid,
+    /// Grid sequence of this snapshot; apply `grid_diff` messages with a higher sequence
+    pub sequence: i64,
     pub grid_rows: i32,
     pub grid_cols: i32,
     pub purchased_boxes: HashMap<i32, BoxOwnerInfo>,
@@ -80,9 +164,17 @@ pub struct GridStateResponse {
     pub total_boxes: i32,
     pub boxes_sold: i32,
     pub completion_percentage: f64,
+    pub layout_name: Option<String>,
+    pub disabled_cells: Vec<GridCell>,
+    pub layout_mask: Vec<Vec<Option<i32>>>,
+    pub prize_tiers: Vec<RafflePrizeTier>,
+    pub pricing: BoxPricing,
+    pub max_boxes_per_user: Option<i32>,
+    pub user_boxes: Option<i32>,
+    pub remaining_allowance: Option<i32>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct BoxOwnerInfo {
     pub user_id: Uuid,
     pub username: String,
@@ -91,6 +183,14 @@ pub struct BoxOwnerInfo {
 }
 
 /// Create a new raffle (sellers only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/raffles",
+    tag = "raffles",
+    request_body = CreateRaffleRequestHandler,
+    responses((status = 201, description = "Created", body = RaffleCreatedResponse)),
+    security(("bearer_auth" = []))
+)]
 pub async fn create_raffle(
     user: AuthenticatedUser,
     request: web::Json<CreateRaffleRequestHandler>,
@@ -114,6 +214,14 @@ pub async fn create_raffle(
         total_winners: request.total_winners,
         grid_rows: request.grid_rows,
         grid_cols: request.grid_cols,
+        layout: request.layout.clone(),
+        prize_tiers: request.prize_tiers.clone(),
+        pricing_curve: request.pricing_curve.clone(),
+        starts_at: request.starts_at,
+        ends_at: request.ends_at,
+        deadline_action: request.deadline_action,
+        max_boxes_per_user: request.max_boxes_per_user,
+        credit_alternative: request.credit_alternative,
     };
 
     let raffle_response = raffle_service.create_raffle(user.user_id, create_request).await?;
@@ -131,22 +239,118 @@ pub async fn create_raffle(
     }))
 }
 
+/// The bounds the current seller's raffles must keep to, from their subscription tier
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/policy",
+    tag = "raffles",
+    responses((status = 200, description = "Success", body = crate::models::raffle_policy::RafflePolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_raffle_policy(
+    user: AuthenticatedUser,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("Only sellers can create raffles".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(raffle_service.get_raffle_policy(user.user_id).await?))
+}
+
+/// Preview the fees a raffle would be charged under the current fee schedules; the raffle is
+/// checked against the seller's raffle policy first
+#[utoipa::path(
+    post,
+    path = "/api/v1/raffles/fee-preview",
+    tag = "raffles",
+    request_body = CreateRaffleRequestHandler,
+    responses((status = 200, description = "Success", body = crate::services::fee_service::FeeBreakdown)),
+    security(("bearer_auth" = []))
+)]
+pub async fn preview_raffle_fees(
+    user: AuthenticatedUser,
+    request: web::Json<CreateRaffleRequestHandler>,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    request.validate()?;
+
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("Only sellers can create raffles".to_string()));
+    }
+
+    let preview_request = CreateRaffleRequest {
+        item_id: request.item_id,
+        total_boxes: request.total_boxes,
+        box_price: request.box_price,
+        total_winners: request.total_winners,
+        grid_rows: request.grid_rows,
+        grid_cols: request.grid_cols,
+        layout: request.layout.clone(),
+        prize_tiers: request.prize_tiers.clone(),
+        pricing_curve: request.pricing_curve.clone(),
+        starts_at: request.starts_at,
+        ends_at: request.ends_at,
+        deadline_action: request.deadline_action,
+        max_boxes_per_user: request.max_boxes_per_user,
+        credit_alternative: request.credit_alternative,
+    };
+
+    let breakdown = raffle_service.preview_raffle_fees(user.user_id, &preview_request).await?;
+
+    Ok(HttpResponse::Ok().json(breakdown))
+}
+
 /// Get raffle by ID
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success", body = raffle_platform_shared::RaffleResponse)),
+    security((), ("bearer_auth" = []))
+)]
 pub async fn get_raffle(
+    req: HttpRequest,
+    user: Option<AuthenticatedUser>,
+    country: RequestCountry,
     raffle_id: web::Path<Uuid>,
     raffle_service: web::Data<RaffleService>,
+    geo_compliance_service: web::Data<GeoComplianceService>,
+    fx_service: web::Data<FxService>,
+    shipping_service: web::Data<ShippingService>,
 ) -> Result<HttpResponse, AppError> {
     debug!("Getting raffle {}", raffle_id);
 
-    let raffle = raffle_service.get_raffle(*raffle_id).await?;
+    geo_compliance_service
+        .ensure_visible(*raffle_id, &country, user.as_ref().map(|u| u.user_id), client_info(&req).0)
+        .await?;
+
+    let mut raffle = raffle_service.get_raffle(*raffle_id).await?;
+    localize_raffles(&req, user.as_ref(), &fx_service, std::slice::from_mut(&mut raffle)).await;
+    estimate_shipping(&req, user.as_ref(), &shipping_service, std::slice::from_mut(&mut raffle)).await;
 
     Ok(HttpResponse::Ok().json(raffle))
 }
 
 /// Search raffles with filters and pagination
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles",
+    tag = "raffles",
+    params(RaffleSearchQuery),
+    responses((status = 200, description = "Success", body = raffle_platform_shared::RaffleCursorPage)),
+    security((), ("bearer_auth" = []))
+)]
 pub async fn search_raffles(
+    req: HttpRequest,
+    user: Option<AuthenticatedUser>,
+    country: RequestCountry,
     query: web::Query<RaffleSearchQuery>,
     raffle_service: web::Data<RaffleService>,
+    geo_compliance_service: web::Data<GeoComplianceService>,
+    fx_service: web::Data<FxService>,
+    shipping_service: web::Data<ShippingService>,
 ) -> Result<HttpResponse, AppError> {
     query.validate()?;
 
@@ -155,6 +359,7 @@ pub async fn search_raffles(
     // Parse status string to enum
     let status = if let Some(status_str) = &query.status {
         match status_str.as_str() {
+            "scheduled" => Some(RaffleStatus::Scheduled),
             "open" => Some(RaffleStatus::Open),
             "full" => Some(RaffleStatus::Full),
             "drawing" => Some(RaffleStatus::Drawing),
@@ -175,28 +380,58 @@ pub async fn search_raffles(
         completion_max: query.completion_max,
         sort_by: query.sort_by.clone(),
         limit: query.limit,
-        offset: query.offset,
+        cursor: query.cursor.clone(),
+        tags: query.tag_list(),
+        match_all_tags: query.match_all_tags.unwrap_or(false),
+        category: query.category.clone(),
+        attributes: query.attribute_list(),
     };
 
-    let results = raffle_service.search_raffles(search_params).await?;
+    let mut results = raffle_service.search_raffles(search_params).await?;
+    geo_compliance_service.filter_visible(&country, &mut results.data).await;
+    localize_raffles(&req, user.as_ref(), &fx_service, &mut results.data).await;
+    estimate_shipping(&req, user.as_ref(), &shipping_service, &mut results.data).await;
 
     Ok(HttpResponse::Ok().json(results))
 }
 
 /// Purchase boxes in a raffle
+///
+/// Accepts an `Idempotency-Key` header; retries with the same key replay the first response.
+#[utoipa::path(
+    post,
+    path = "/api/v1/raffles/{raffle_id}/buy-boxes",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    request_body = BuyBoxRequestHandler,
+    responses((status = 200, description = "Success", body = BoxPurchaseSuccessResponse)),
+    security(("bearer_auth" = []))
+)]
 pub async fn buy_boxes(
+    req: HttpRequest,
     user: AuthenticatedUser,
+    country: RequestCountry,
     raffle_id: web::Path<Uuid>,
     request: web::Json<BuyBoxRequestHandler>,
     raffle_service: web::Data<RaffleService>,
+    geo_compliance_service: web::Data<GeoComplianceService>,
+    external_wallet_service: web::Data<ExternalWalletService>,
 ) -> Result<HttpResponse, AppError> {
     request.validate()?;
 
+    geo_compliance_service
+        .ensure_can_purchase(*raffle_id, &country, user.user_id, client_info(&req).0)
+        .await?;
+
     debug!(
         "User {} purchasing {} boxes in raffle {}",
-        user.user_id, request.box_numbers.len(), raffle_id
+        user.user_id, request.box_numbers.len() + request.cells.len(), raffle_id
     );
 
+    if request.box_numbers.len() + request.cells.len() > 100 {
+        return Err(AppError::Validation("Cannot purchase more than 100 boxes at once".to_string()));
+    }
+
     // Validate box numbers
     for &box_number in &request.box_numbers {
         if box_number <= 0 {
@@ -204,14 +439,40 @@ pub async fn buy_boxes(
         }
     }
 
+    // Check the wallet before charging, so a bad wallet id doesn't leave boxes bought off chain only
+    let external_wallet = match request.external_wallet_id {
+        Some(wallet_id) => Some(
+            external_wallet_service
+                .check_purchase(user.user_id, wallet_id, *raffle_id)
+                .await?,
+        ),
+        None => None,
+    };
+
     let purchase_request = BoxPurchaseRequest {
         raffle_id: *raffle_id,
         box_numbers: request.box_numbers.clone(),
+        cells: request.cells.clone(),
         use_credits: request.use_credits,
     };
 
     let purchases = raffle_service.purchase_boxes(user.user_id, purchase_request).await?;
 
+    let external_purchase = match external_wallet {
+        Some((wallet, blockchain_raffle_id)) => Some(
+            external_wallet_service
+                .create_purchase(
+                    user.user_id,
+                    &wallet,
+                    *raffle_id,
+                    blockchain_raffle_id,
+                    purchases.iter().map(|p| p.id).collect(),
+                )
+                .await?,
+        ),
+        None => None,
+    };
+
     // Get updated raffle info
     let raffle = raffle_service.get_raffle(*raffle_id).await?;
     let total_cost = purchases.iter()
@@ -229,10 +490,19 @@ pub async fn buy_boxes(
         raffle_status: raffle.status,
         boxes_remaining: raffle.total_boxes - raffle.boxes_sold,
         message: format!("Successfully purchased {} boxes", request.box_numbers.len()),
+        external_purchase,
     }))
 }
 
 /// Get raffle grid state
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}/grid",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success", body = GridStateResponse)),
+    security((), ("bearer_auth" = []))
+)]
 pub async fn get_grid_state(
     raffle_id: web::Path<Uuid>,
     user: Option<AuthenticatedUser>,
@@ -240,7 +510,9 @@ pub async fn get_grid_state(
 ) -> Result<HttpResponse, AppError> {
     debug!("Getting grid state for raffle {}", raffle_id);
 
-    let grid_state = raffle_service.get_grid_state(*raffle_id).await?;
+    let grid_state = raffle_service
+        .get_grid_state(*raffle_id, user.as_ref().map(|u| u.user_id))
+        .await?;
 
     // Convert to response format with additional info
     let mut purchased_boxes = HashMap::new();
@@ -261,6 +533,7 @@ pub async fn get_grid_state(
 
     let response = GridStateResponse {
         raffle_id: grid_state.raffle_id,
+        sequence: grid_state.sequence,
         grid_rows: grid_state.grid_rows,
         grid_cols: grid_state.grid_cols,
         purchased_boxes,
@@ -268,12 +541,71 @@ pub async fn get_grid_state(
         total_boxes: grid_state.total_boxes,
         boxes_sold: grid_state.boxes_sold,
         completion_percentage,
+        layout_name: grid_state.layout_name,
+        disabled_cells: grid_state.disabled_cells,
+        layout_mask: grid_state.layout_mask,
+        prize_tiers: grid_state.prize_tiers,
+        pricing: grid_state.pricing,
+        max_boxes_per_user: grid_state.max_boxes_per_user,
+        user_boxes: grid_state.user_boxes,
+        remaining_allowance: grid_state.remaining_allowance,
     };
 
     Ok(HttpResponse::Ok().json(response))
 }
 
+/// Get the grid changes after a sequence, for a client that missed a `grid_diff` message
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}/grid/changes",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID"), GridChangesQuery),
+    responses((status = 200, description = "Success", body = crate::services::raffle_service::GridChanges))
+)]
+pub async fn get_grid_changes(
+    raffle_id: web::Path<Uuid>,
+    query: web::Query<GridChangesQuery>,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    query.validate()?;
+
+    let changes = raffle_service.get_grid_changes(*raffle_id, query.since).await?;
+
+    Ok(HttpResponse::Ok().json(changes))
+}
+
+/// Get win odds and expected value per box for a raffle
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}/odds",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success", body = crate::services::raffle_service::RaffleOdds)),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn get_raffle_odds(
+    raffle_id: web::Path<Uuid>,
+    user: Option<AuthenticatedUser>,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    debug!("Getting odds for raffle {}", raffle_id);
+
+    let odds = raffle_service
+        .get_raffle_odds(*raffle_id, user.map(|u| u.user_id))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(odds))
+}
+
 /// Get user's purchases for a raffle
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}/my-purchases",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_user_purchases(
     user: AuthenticatedUser,
     raffle_id: web::Path<Uuid>,
@@ -295,7 +627,187 @@ pub async fn get_user_purchases(
     })))
 }
 
+/// Join the waitlist of a sold-out raffle or out-of-stock item
+///
+/// Joining again keeps the place the user already has.
+#[utoipa::path(
+    post,
+    path = "/api/v1/raffles/{raffle_id}/waitlist",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses(
+        (status = 200, description = "Success", body = crate::services::waitlist_service::WaitlistPosition),
+        (status = 400, description = "Boxes are still available")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn join_waitlist(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    waitlist_service: web::Data<WaitlistService>,
+) -> Result<HttpResponse, AppError> {
+    let position = waitlist_service.join(user.user_id, *raffle_id).await?;
+
+    Ok(HttpResponse::Ok().json(position))
+}
+
+/// The user's place on a raffle's waitlist
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}/waitlist",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses(
+        (status = 200, description = "Success", body = crate::services::waitlist_service::WaitlistPosition),
+        (status = 404, description = "Not on the waitlist")
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_waitlist_position(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    waitlist_service: web::Data<WaitlistService>,
+) -> Result<HttpResponse, AppError> {
+    let position = waitlist_service.position(user.user_id, *raffle_id).await?;
+
+    Ok(HttpResponse::Ok().json(position))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/raffles/{raffle_id}/waitlist",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 204, description = "Left the waitlist"), (status = 404, description = "Not on the waitlist")),
+    security(("bearer_auth" = []))
+)]
+pub async fn leave_waitlist(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    waitlist_service: web::Data<WaitlistService>,
+) -> Result<HttpResponse, AppError> {
+    waitlist_service.leave(user.user_id, *raffle_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Places the user won in a raffle and any prize choices still open
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}/prize-choice",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_prize_choices(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    let prizes = raffle_service.get_prize_choices(*raffle_id, user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "raffle_id": *raffle_id,
+        "awaiting_choice": prizes.iter().any(|p| p.awaiting_choice()),
+        "prizes": prizes
+    })))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PrizeChoiceRequest {
+    pub place: i32,
+    pub choice: PrizeChoice,
+}
+
+/// Take an item prize or the credit value offered in its place
+#[utoipa::path(
+    post,
+    path = "/api/v1/raffles/{raffle_id}/prize-choice",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    request_body = PrizeChoiceRequest,
+    responses((status = 200, description = "Success", body = crate::models::raffle_prize_tier::RafflePrizeTier)),
+    security(("bearer_auth" = []))
+)]
+pub async fn choose_prize(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    request: web::Json<PrizeChoiceRequest>,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    info!(
+        "User {} choosing {:?} for place {} in raffle {}",
+        user.user_id, request.choice, request.place, raffle_id
+    );
+
+    let prize = raffle_service
+        .choose_prize(*raffle_id, user.user_id, request.place, request.choice)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(prize))
+}
+
+/// Get the receipt for a box purchase's checkout
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}/purchases/{purchase_id}/receipt",
+    tag = "raffles",
+    params(
+        ("raffle_id" = Uuid, Path, description = "Raffle ID"),
+        ("purchase_id" = Uuid, Path, description = "Purchase ID"),
+    ),
+    responses((status = 200, description = "Success", body = crate::services::receipt_service::PurchaseReceipt)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_purchase_receipt(
+    user: AuthenticatedUser,
+    path: web::Path<(Uuid, Uuid)>,
+    receipt_service: web::Data<ReceiptService>,
+) -> Result<HttpResponse, AppError> {
+    let (raffle_id, purchase_id) = path.into_inner();
+
+    debug!(
+        "Getting receipt for purchase {} in raffle {} for user {}",
+        purchase_id, raffle_id, user.user_id
+    );
+
+    let receipt = receipt_service
+        .get_or_create_receipt(user.user_id, raffle_id, purchase_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(receipt))
+}
+
+/// Check a receipt by its verification code
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/receipts/verify/{verification_code}",
+    tag = "raffles",
+    params(("verification_code" = String, Path, description = "Verification code printed on the receipt")),
+    responses(
+        (status = 200, description = "Success", body = crate::services::receipt_service::ReceiptVerification),
+        (status = 404, description = "No receipt has this code")
+    )
+)]
+pub async fn verify_receipt(
+    path: web::Path<String>,
+    receipt_service: web::Data<ReceiptService>,
+) -> Result<HttpResponse, AppError> {
+    let receipt = receipt_service.verify_receipt(&path.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(receipt))
+}
+
 /// Get user's purchase history
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/my-history",
+    tag = "raffles",
+    params(RaffleSearchQuery),
+    responses((status = 200, description = "Success", body = raffle_platform_shared::BoxPurchasePage)),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_user_purchase_history(
     user: AuthenticatedUser,
     query: web::Query<RaffleSearchQuery>,
@@ -313,6 +825,15 @@ pub async fn get_user_purchase_history(
 }
 
 /// Cancel a raffle (seller/admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/raffles/{raffle_id}/cancel",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    request_body = CancelRaffleRequest,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 pub async fn cancel_raffle(
     user: AuthenticatedUser,
     raffle_id: web::Path<Uuid>,
@@ -342,6 +863,13 @@ pub async fn cancel_raffle(
 }
 
 /// Get raffle statistics
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/statistics",
+    tag = "raffles",
+    responses((status = 200, description = "Success", body = crate::services::raffle_service::RaffleStatistics)),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_raffle_statistics(
     user: AuthenticatedUser,
     raffle_service: web::Data<RaffleService>,
@@ -358,9 +886,23 @@ pub async fn get_raffle_statistics(
 }
 
 /// Get active raffles (public endpoint)
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/active",
+    tag = "raffles",
+    params(RaffleSearchQuery),
+    responses((status = 200, description = "Success", body = raffle_platform_shared::RaffleCursorPage)),
+    security((), ("bearer_auth" = []))
+)]
 pub async fn get_active_raffles(
+    req: HttpRequest,
+    user: Option<AuthenticatedUser>,
+    country: RequestCountry,
     query: web::Query<RaffleSearchQuery>,
     raffle_service: web::Data<RaffleService>,
+    geo_compliance_service: web::Data<GeoComplianceService>,
+    fx_service: web::Data<FxService>,
+    shipping_service: web::Data<ShippingService>,
 ) -> Result<HttpResponse, AppError> {
     debug!("Getting active raffles");
 
@@ -373,18 +915,39 @@ pub async fn get_active_raffles(
         completion_max: query.completion_max,
         sort_by: query.sort_by.clone(),
         limit: query.limit.or(Some(20)),
-        offset: query.offset,
+        cursor: query.cursor.clone(),
+        tags: query.tag_list(),
+        match_all_tags: query.match_all_tags.unwrap_or(false),
+        category: query.category.clone(),
+        attributes: query.attribute_list(),
     };
 
-    let results = raffle_service.search_raffles(search_params).await?;
+    let mut results = raffle_service.search_raffles(search_params).await?;
+    geo_compliance_service.filter_visible(&country, &mut results.data).await;
+    localize_raffles(&req, user.as_ref(), &fx_service, &mut results.data).await;
+    estimate_shipping(&req, user.as_ref(), &shipping_service, &mut results.data).await;
 
     Ok(HttpResponse::Ok().json(results))
 }
 
 /// Get featured raffles (public endpoint)
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/featured",
+    tag = "raffles",
+    params(RaffleSearchQuery),
+    responses((status = 200, description = "Success", body = raffle_platform_shared::RaffleCursorPage)),
+    security((), ("bearer_auth" = []))
+)]
 pub async fn get_featured_raffles(
+    req: HttpRequest,
+    user: Option<AuthenticatedUser>,
+    country: RequestCountry,
     query: web::Query<RaffleSearchQuery>,
     raffle_service: web::Data<RaffleService>,
+    geo_compliance_service: web::Data<GeoComplianceService>,
+    fx_service: web::Data<FxService>,
+    shipping_service: web::Data<ShippingService>,
 ) -> Result<HttpResponse, AppError> {
     debug!("Getting featured raffles");
 
@@ -397,15 +960,30 @@ pub async fn get_featured_raffles(
         completion_max: None,
         sort_by: Some("featured".to_string()),
         limit: query.limit.or(Some(10)),
-        offset: query.offset,
+        cursor: query.cursor.clone(),
+        tags: Vec::new(),
+        match_all_tags: false,
+        category: None,
+        attributes: Vec::new(),
     };
 
-    let results = raffle_service.search_raffles(search_params).await?;
+    let mut results = raffle_service.search_raffles(search_params).await?;
+    geo_compliance_service.filter_visible(&country, &mut results.data).await;
+    localize_raffles(&req, user.as_ref(), &fx_service, &mut results.data).await;
+    estimate_shipping(&req, user.as_ref(), &shipping_service, &mut results.data).await;
 
     Ok(HttpResponse::Ok().json(results))
 }
 
 /// Get raffle winners (public endpoint)
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}/winners",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success")),
+    security((), ("bearer_auth" = []))
+)]
 pub async fn get_raffle_winners(
     raffle_id: web::Path<Uuid>,
     raffle_service: web::Data<RaffleService>,
@@ -418,18 +996,52 @@ pub async fn get_raffle_winners(
         return Err(AppError::Validation("Raffle is not completed yet".to_string()));
     }
 
+    let mut prize_tiers = raffle_service.get_prize_tiers(*raffle_id).await?;
+    winner_display::redact_winner_ids(&mut prize_tiers);
+    let winners = raffle_service.get_public_winners(*raffle_id).await?;
+
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "raffle_id": *raffle_id,
         "status": raffle.status,
-        "winner_user_ids": raffle.winner_user_ids,
         "total_winners": raffle.total_winners,
+        "winners": winners,
+        "prize_tiers": prize_tiers,
         "completed_at": raffle.completed_at
     })))
 }
 
+/// Most recent winners across all raffles, shown by each winner's display rule (public endpoint)
+#[utoipa::path(
+    get,
+    path = "/api/v1/winners",
+    tag = "raffles",
+    params(WinnerGalleryQuery),
+    responses((status = 200, description = "Success", body = [crate::services::raffle_service::WinnerGalleryEntry]))
+)]
+pub async fn get_winner_gallery(
+    query: web::Query<WinnerGalleryQuery>,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    query.validate()?;
+
+    let winners = raffle_service
+        .get_winner_gallery(query.limit.unwrap_or(24))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(winners))
+}
+
 // Admin endpoints
 
 /// Get all raffles (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/admin/all",
+    tag = "raffles",
+    params(RaffleSearchQuery),
+    responses((status = 200, description = "Success", body = raffle_platform_shared::RaffleCursorPage)),
+    security(("bearer_auth" = []))
+)]
 pub async fn get_all_raffles_admin(
     user: AuthenticatedUser,
     query: web::Query<RaffleSearchQuery>,
@@ -444,6 +1056,7 @@ pub async fn get_all_raffles_admin(
     // Parse status string to enum
     let status = if let Some(status_str) = &query.status {
         match status_str.as_str() {
+            "scheduled" => Some(RaffleStatus::Scheduled),
             "open" => Some(RaffleStatus::Open),
             "full" => Some(RaffleStatus::Full),
             "drawing" => Some(RaffleStatus::Drawing),
@@ -464,7 +1077,11 @@ pub async fn get_all_raffles_admin(
         completion_max: query.completion_max,
         sort_by: query.sort_by.clone(),
         limit: query.limit,
-        offset: query.offset,
+        cursor: query.cursor.clone(),
+        tags: query.tag_list(),
+        match_all_tags: query.match_all_tags.unwrap_or(false),
+        category: query.category.clone(),
+        attributes: query.attribute_list(),
     };
 
     let results = raffle_service.search_raffles(search_params).await?;
@@ -472,23 +1089,27 @@ pub async fn get_all_raffles_admin(
     Ok(HttpResponse::Ok().json(results))
 }
 
-/// Force complete a raffle (admin only)
+/// Force complete a raffle (requires the `raffles.force_complete` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/raffles/admin/{raffle_id}/force-complete",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 pub async fn force_complete_raffle(
     user: AuthenticatedUser,
     raffle_id: web::Path<Uuid>,
     raffle_service: web::Data<RaffleService>,
 ) -> Result<HttpResponse, AppError> {
-    if !user.is_admin() {
-        return Err(AppError::Forbidden("Admin access required".to_string()));
-    }
-
     info!(
         "Admin {} force completing raffle {}",
         user.user_id, raffle_id
     );
 
-    // This would implement force completion logic
-    // For now, return a placeholder response
+    raffle_service.force_complete_raffle(*raffle_id).await?;
+
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
         "message": "Raffle force completed successfully",
@@ -496,7 +1117,49 @@ pub async fn force_complete_raffle(
     })))
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetRaffleTagsRequest {
+    pub tags: Vec<String>,
+}
+
+/// Replace a raffle's tags (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/raffles/admin/{raffle_id}/tags",
+    tag = "raffles",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    request_body = SetRaffleTagsRequest,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_raffle_tags(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    request: web::Json<SetRaffleTagsRequest>,
+    raffle_service: web::Data<RaffleService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let tags = raffle_service
+        .set_raffle_tags(*raffle_id, request.into_inner().tags, user.user_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "raffle_id": *raffle_id,
+        "tags": tags
+    })))
+}
+
 /// Health check endpoint for raffle service
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/health",
+    tag = "raffles",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 pub async fn raffle_service_health(
     raffle_service: web::Data<RaffleService>,
 ) -> Result<HttpResponse, AppError> {
@@ -506,4 +1169,4 @@ pub async fn raffle_service_health(
         "service": "raffle",
         "timestamp": chrono::Utc::now()
     })))
-}
\ No newline at end of file
+}
@@ -0,0 +1,167 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::referral_service::{
+    AttachReferralRequest, ReferralPolicy, ReferralService, ReferralStatus, DEVICE_ID_HEADER,
+};
+use crate::error::AppError;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use serde::Deserialize;
+use std::net::IpAddr;
+use tracing::info;
+use utoipa::IntoParams;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReferralQuery {
+    /// Defaults to every status
+    pub status: Option<ReferralStatus>,
+    pub limit: Option<i64>,
+}
+
+/// The client IP and device id the referral anti-abuse checks compare
+pub fn referral_signals(http_req: &HttpRequest) -> (Option<IpAddr>, Option<String>) {
+    let ip_address = http_req
+        .connection_info()
+        .realip_remote_addr()
+        .and_then(|ip| ip.parse::<IpAddr>().ok());
+
+    let device_id = http_req
+        .headers()
+        .get(DEVICE_ID_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.to_string());
+
+    (ip_address, device_id)
+}
+
+/// Get the user's referral code and how their referrals are doing
+#[utoipa::path(
+    get,
+    path = "/api/v1/referrals/me",
+    tag = "referrals",
+    responses((status = 200, description = "Success", body = crate::services::referral_service::UserReferralStats)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_my_referrals(
+    user: AuthenticatedUser,
+    referral_service: web::Data<ReferralService>,
+) -> Result<HttpResponse, AppError> {
+    let stats = referral_service.get_user_stats(user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+/// Add another user's referral code after signing up, before the first purchase
+#[utoipa::path(
+    post,
+    path = "/api/v1/referrals/attach",
+    tag = "referrals",
+    request_body = AttachReferralRequest,
+    responses((status = 201, description = "Created", body = crate::services::referral_service::Referral)),
+    security(("bearer_auth" = []))
+)]
+pub async fn attach_referral(
+    user: AuthenticatedUser,
+    request: web::Json<AttachReferralRequest>,
+    http_req: HttpRequest,
+    referral_service: web::Data<ReferralService>,
+) -> Result<HttpResponse, AppError> {
+    let (ip_address, device_id) = referral_signals(&http_req);
+
+    let referral = referral_service
+        .attach(user.user_id, &request.code, ip_address, device_id.as_deref())
+        .await?;
+
+    Ok(HttpResponse::Created().json(referral))
+}
+
+/// Get referrals, newest first (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/referrals",
+    tag = "referrals",
+    params(ReferralQuery),
+    responses((status = 200, description = "Success", body = [crate::services::referral_service::Referral])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_referrals(
+    user: AuthenticatedUser,
+    query: web::Query<ReferralQuery>,
+    referral_service: web::Data<ReferralService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let referrals = referral_service
+        .list_referrals(query.status, query.limit.unwrap_or(50).clamp(1, 200))
+        .await?;
+
+    Ok(HttpResponse::Ok().json(referrals))
+}
+
+/// Get referral program totals and top referrers (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/referrals/stats",
+    tag = "referrals",
+    responses((status = 200, description = "Success", body = crate::services::referral_service::ReferralProgramStats)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_referral_stats(
+    user: AuthenticatedUser,
+    referral_service: web::Data<ReferralService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let stats = referral_service.get_program_stats().await?;
+
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+/// Get the referral reward policy (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/referral-policy",
+    tag = "referrals",
+    responses((status = 200, description = "Success", body = ReferralPolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_referral_policy(
+    user: AuthenticatedUser,
+    referral_service: web::Data<ReferralService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let policy = referral_service.get_policy().await?;
+
+    Ok(HttpResponse::Ok().json(policy))
+}
+
+/// Replace the referral reward policy (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/referral-policy",
+    tag = "referrals",
+    request_body = ReferralPolicy,
+    responses((status = 200, description = "Success", body = ReferralPolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_referral_policy(
+    user: AuthenticatedUser,
+    request: web::Json<ReferralPolicy>,
+    referral_service: web::Data<ReferralService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!("Admin {} updating referral policy: {:?}", user.user_id, request);
+
+    let policy = referral_service.update_policy(request.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(policy))
+}
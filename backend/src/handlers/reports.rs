@@ -0,0 +1,244 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::reporting_service::{
+    month_range, parse_month, AccountMappingUpdate, AccountingExportFormat, LedgerFormat, ReportingService,
+};
+use crate::error::AppError;
+use actix_web::{web, HttpResponse, Result};
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::TransactionType;
+use serde::Deserialize;
+use tracing::{debug, info};
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AccountingExportQuery {
+    pub format: AccountingExportFormat,
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LedgerQuery {
+    /// First month, `YYYY-MM`
+    pub from: String,
+    /// Last month, `YYYY-MM`; defaults to `from`
+    pub to: Option<String>,
+    /// `csv` or `json`; ignored by the summary
+    pub format: Option<LedgerFormat>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SellerStatementQuery {
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    /// User id of the seller; admins may request another seller's statement
+    pub seller_id: Option<Uuid>,
+}
+
+/// Export completed transactions for QuickBooks/Xero (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/reports/accounting-export",
+    tag = "reports",
+    params(AccountingExportQuery),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn export_accounting(
+    user: AuthenticatedUser,
+    query: web::Query<AccountingExportQuery>,
+    reporting_service: web::Data<ReportingService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!(
+        "Admin {} exporting accounting data ({:?}) from {} to {}",
+        user.user_id, query.format, query.start_date, query.end_date
+    );
+
+    let export = reporting_service
+        .export_accounting(query.format, query.start_date, query.end_date)
+        .await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(export.content_type)
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", export.filename),
+        ))
+        .insert_header(("X-Export-Entry-Count", export.entry_count.to_string()))
+        .insert_header(("X-Export-Skipped-Unmapped", export.skipped_unmapped.to_string()))
+        .body(export.body))
+}
+
+/// Download the monthly ledger of payments, refunds, credits, fees and payouts (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/reports/ledger",
+    tag = "reports",
+    params(LedgerQuery),
+    responses((status = 200, description = "Ledger streamed as CSV or a JSON array of LedgerLine")),
+    security(("bearer_auth" = []))
+)]
+pub async fn export_ledger(
+    user: AuthenticatedUser,
+    query: web::Query<LedgerQuery>,
+    reporting_service: web::Data<ReportingService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let from = parse_month(&query.from)?;
+    let to = query.to.as_deref().map(parse_month).transpose()?.unwrap_or(from);
+    let (start_date, end_date) = month_range(from, to)?;
+    let format = query.format.unwrap_or(LedgerFormat::Csv);
+
+    info!(
+        "Admin {} exporting {:?} ledger from {} to {}",
+        user.user_id, format, start_date, end_date
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"ledger_{}_{}.{}\"",
+                from.format("%Y-%m"),
+                to.format("%Y-%m"),
+                format.file_extension()
+            ),
+        ))
+        .streaming(reporting_service.stream_ledger(format, start_date, end_date)))
+}
+
+/// Monthly ledger totals per category (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/reports/ledger/summary",
+    tag = "reports",
+    params(LedgerQuery),
+    responses((status = 200, description = "Success", body = crate::services::reporting_service::LedgerSummary)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_ledger_summary(
+    user: AuthenticatedUser,
+    query: web::Query<LedgerQuery>,
+    reporting_service: web::Data<ReportingService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let from = parse_month(&query.from)?;
+    let to = query.to.as_deref().map(parse_month).transpose()?.unwrap_or(from);
+    let (start_date, end_date) = month_range(from, to)?;
+
+    debug!("Admin {} requesting ledger summary from {} to {}", user.user_id, start_date, end_date);
+
+    let summary = reporting_service.get_ledger_summary(start_date, end_date).await?;
+
+    Ok(HttpResponse::Ok().json(summary))
+}
+
+/// Get the chart-of-accounts mapping (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/reports/chart-of-accounts",
+    tag = "reports",
+    responses((status = 200, description = "Success", body = [crate::services::reporting_service::AccountMapping])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_chart_of_accounts(
+    user: AuthenticatedUser,
+    reporting_service: web::Data<ReportingService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    debug!("Admin {} requesting chart of accounts", user.user_id);
+
+    let mappings = reporting_service.get_chart_of_accounts().await?;
+
+    Ok(HttpResponse::Ok().json(mappings))
+}
+
+/// Update the account mapping for a transaction type (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/reports/chart-of-accounts/{transaction_type}",
+    tag = "reports",
+    params(
+        ("transaction_type" = TransactionType, Path, description = "Transaction type"),
+    ),
+    request_body = AccountMappingUpdate,
+    responses((status = 200, description = "Success", body = crate::services::reporting_service::AccountMapping)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_account_mapping(
+    user: AuthenticatedUser,
+    transaction_type: web::Path<TransactionType>,
+    request: web::Json<AccountMappingUpdate>,
+    reporting_service: web::Data<ReportingService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!(
+        "Admin {} updating account mapping for {:?}",
+        user.user_id, *transaction_type
+    );
+
+    let mapping = reporting_service
+        .upsert_account_mapping(*transaction_type, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(mapping))
+}
+
+/// Get a seller's fee statement for completed raffles
+#[utoipa::path(
+    get,
+    path = "/api/v1/reports/seller-statement",
+    tag = "reports",
+    params(SellerStatementQuery),
+    responses((status = 200, description = "Success", body = crate::services::reporting_service::SellerStatement)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_seller_statement(
+    user: AuthenticatedUser,
+    query: web::Query<SellerStatementQuery>,
+    reporting_service: web::Data<ReportingService>,
+) -> Result<HttpResponse, AppError> {
+    if query.start_date >= query.end_date {
+        return Err(AppError::Validation("start_date must be before end_date".to_string()));
+    }
+
+    let seller_id = match query.seller_id {
+        Some(seller_id) if user.is_admin() => seller_id,
+        Some(_) => return Err(AppError::Forbidden("Admin access required".to_string())),
+        None => {
+            if !user.is_seller() {
+                return Err(AppError::Forbidden("Only sellers have fee statements".to_string()));
+            }
+            user.user_id
+        }
+    };
+
+    debug!("User {} requesting fee statement for seller {}", user.user_id, seller_id);
+
+    let statement = reporting_service
+        .get_seller_statement(seller_id, query.start_date, query.end_date)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(statement))
+}
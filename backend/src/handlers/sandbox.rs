@@ -0,0 +1,248 @@
+use crate::database::Database;
+use crate::middleware::auth::{ApiKeyClient, AuthenticatedUser};
+use crate::models::partner_api_key::PartnerApiKey;
+use crate::services::sandbox_service::{
+    CreateSandboxItemRequest, CreateSandboxRaffleRequest, SandboxPurchaseRequest, SandboxService,
+};
+use crate::error::AppError;
+use actix_web::{web, HttpResponse, Result};
+use serde::Deserialize;
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateTestKeyRequest {
+    pub name: String,
+}
+
+// Seller endpoints (JWT)
+
+/// List the current seller's test-mode API keys
+#[utoipa::path(
+    get,
+    path = "/api/v1/sandbox/keys",
+    tag = "sandbox",
+    responses((status = 200, description = "Success", body = [crate::models::partner_api_key::PartnerApiKey])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_test_keys(
+    user: AuthenticatedUser,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("Only sellers can use the sandbox".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().json(PartnerApiKey::list_test_keys(database.pool(), user.user_id).await?))
+}
+
+/// Issue a test-mode API key; the key is only shown in this response
+#[utoipa::path(
+    post,
+    path = "/api/v1/sandbox/keys",
+    tag = "sandbox",
+    request_body = CreateTestKeyRequest,
+    responses((status = 201, description = "Created")),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_test_key(
+    user: AuthenticatedUser,
+    request: web::Json<CreateTestKeyRequest>,
+    sandbox_service: web::Data<SandboxService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("Only sellers can use the sandbox".to_string()));
+    }
+
+    let (api_key, key) = sandbox_service.create_test_key(user.user_id, &request.name).await?;
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "api_key": api_key,
+        "key": key,
+    })))
+}
+
+/// Revoke one of the current seller's test-mode API keys
+#[utoipa::path(
+    delete,
+    path = "/api/v1/sandbox/keys/{key_id}",
+    tag = "sandbox",
+    params(("key_id" = Uuid, Path, description = "Key ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn revoke_test_key(
+    user: AuthenticatedUser,
+    key_id: web::Path<Uuid>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("Only sellers can use the sandbox".to_string()));
+    }
+
+    if !PartnerApiKey::revoke_test_key(database.pool(), *key_id, user.user_id).await? {
+        return Err(AppError::NotFound("Test API key not found".to_string()));
+    }
+
+    info!("Seller {} revoked test API key {}", user.user_id, key_id);
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Sandbox endpoints (test-mode API key)
+
+/// Create a sandbox item
+#[utoipa::path(
+    post,
+    path = "/api/v1/sandbox/items",
+    tag = "sandbox",
+    request_body = CreateSandboxItemRequest,
+    responses((status = 201, description = "Created", body = crate::services::sandbox_service::SandboxItem)),
+    security(("api_key" = []))
+)]
+pub async fn create_item(
+    client: ApiKeyClient,
+    request: web::Json<CreateSandboxItemRequest>,
+    sandbox_service: web::Data<SandboxService>,
+) -> Result<HttpResponse, AppError> {
+    let seller_id = client.require_sandbox()?;
+
+    let item = sandbox_service.create_item(seller_id, request.into_inner()).await?;
+
+    Ok(HttpResponse::Created().json(item))
+}
+
+/// List sandbox items
+#[utoipa::path(
+    get,
+    path = "/api/v1/sandbox/items",
+    tag = "sandbox",
+    responses((status = 200, description = "Success", body = [crate::services::sandbox_service::SandboxItem])),
+    security(("api_key" = []))
+)]
+pub async fn list_items(
+    client: ApiKeyClient,
+    sandbox_service: web::Data<SandboxService>,
+) -> Result<HttpResponse, AppError> {
+    let seller_id = client.require_sandbox()?;
+
+    Ok(HttpResponse::Ok().json(sandbox_service.list_items(seller_id).await?))
+}
+
+/// Open a sandbox raffle
+#[utoipa::path(
+    post,
+    path = "/api/v1/sandbox/raffles",
+    tag = "sandbox",
+    request_body = CreateSandboxRaffleRequest,
+    responses((status = 201, description = "Created", body = crate::services::sandbox_service::SandboxRaffle)),
+    security(("api_key" = []))
+)]
+pub async fn create_raffle(
+    client: ApiKeyClient,
+    request: web::Json<CreateSandboxRaffleRequest>,
+    sandbox_service: web::Data<SandboxService>,
+) -> Result<HttpResponse, AppError> {
+    let seller_id = client.require_sandbox()?;
+
+    let raffle = sandbox_service.create_raffle(seller_id, request.into_inner()).await?;
+
+    Ok(HttpResponse::Created().json(raffle))
+}
+
+/// List sandbox raffles
+#[utoipa::path(
+    get,
+    path = "/api/v1/sandbox/raffles",
+    tag = "sandbox",
+    responses((status = 200, description = "Success", body = [crate::services::sandbox_service::SandboxRaffle])),
+    security(("api_key" = []))
+)]
+pub async fn list_raffles(
+    client: ApiKeyClient,
+    sandbox_service: web::Data<SandboxService>,
+) -> Result<HttpResponse, AppError> {
+    let seller_id = client.require_sandbox()?;
+
+    Ok(HttpResponse::Ok().json(sandbox_service.list_raffles(seller_id).await?))
+}
+
+/// Get a sandbox raffle
+#[utoipa::path(
+    get,
+    path = "/api/v1/sandbox/raffles/{raffle_id}",
+    tag = "sandbox",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success", body = crate::services::sandbox_service::SandboxRaffle)),
+    security(("api_key" = []))
+)]
+pub async fn get_raffle(
+    client: ApiKeyClient,
+    raffle_id: web::Path<Uuid>,
+    sandbox_service: web::Data<SandboxService>,
+) -> Result<HttpResponse, AppError> {
+    let seller_id = client.require_sandbox()?;
+
+    Ok(HttpResponse::Ok().json(sandbox_service.get_raffle(seller_id, *raffle_id).await?))
+}
+
+/// Record fake box purchases in a sandbox raffle
+#[utoipa::path(
+    post,
+    path = "/api/v1/sandbox/raffles/{raffle_id}/purchases",
+    tag = "sandbox",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    request_body(content = SandboxPurchaseRequest, description = "Optional"),
+    responses((status = 201, description = "Created", body = crate::services::sandbox_service::SandboxPurchaseResult)),
+    security(("api_key" = []))
+)]
+pub async fn simulate_purchase(
+    client: ApiKeyClient,
+    raffle_id: web::Path<Uuid>,
+    request: Option<web::Json<SandboxPurchaseRequest>>,
+    sandbox_service: web::Data<SandboxService>,
+) -> Result<HttpResponse, AppError> {
+    let seller_id = client.require_sandbox()?;
+    let request = request.map(|r| r.into_inner()).unwrap_or_default();
+
+    let result = sandbox_service.simulate_purchase(seller_id, *raffle_id, request).await?;
+
+    Ok(HttpResponse::Created().json(result))
+}
+
+/// List the fake purchases of a sandbox raffle
+#[utoipa::path(
+    get,
+    path = "/api/v1/sandbox/raffles/{raffle_id}/purchases",
+    tag = "sandbox",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success", body = [crate::services::sandbox_service::SandboxPurchase])),
+    security(("api_key" = []))
+)]
+pub async fn list_purchases(
+    client: ApiKeyClient,
+    raffle_id: web::Path<Uuid>,
+    sandbox_service: web::Data<SandboxService>,
+) -> Result<HttpResponse, AppError> {
+    let seller_id = client.require_sandbox()?;
+
+    Ok(HttpResponse::Ok().json(sandbox_service.list_purchases(seller_id, *raffle_id).await?))
+}
+
+/// Delete all sandbox items, raffles and purchases of the key's seller
+#[utoipa::path(
+    delete,
+    path = "/api/v1/sandbox",
+    tag = "sandbox",
+    responses((status = 200, description = "Success", body = crate::services::sandbox_service::SandboxPurgeReport)),
+    security(("api_key" = []))
+)]
+pub async fn purge(
+    client: ApiKeyClient,
+    sandbox_service: web::Data<SandboxService>,
+) -> Result<HttpResponse, AppError> {
+    let seller_id = client.require_sandbox()?;
+
+    Ok(HttpResponse::Ok().json(sandbox_service.purge(seller_id).await?))
+}
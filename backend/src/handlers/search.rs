@@ -0,0 +1,20 @@
+use crate::services::search_service::{SearchService, UnifiedSearchQuery};
+use crate::error::AppError;
+use actix_web::{web, HttpResponse, Result};
+
+/// Search items, raffles and sellers at once, each group ranked by relevance and paged on its own
+#[utoipa::path(
+    get,
+    path = "/api/v1/search",
+    tag = "search",
+    params(UnifiedSearchQuery),
+    responses((status = 200, description = "Success", body = crate::services::search_service::UnifiedSearchResults))
+)]
+pub async fn search(
+    query: web::Query<UnifiedSearchQuery>,
+    search_service: web::Data<SearchService>,
+) -> Result<HttpResponse, AppError> {
+    let results = search_service.search(&query).await?;
+
+    Ok(HttpResponse::Ok().json(results))
+}
@@ -0,0 +1,302 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::seller_webhook_service::{
+    CreatePlatformWebhookEndpointRequest, CreateWebhookEndpointRequest, EndpointDeliveryQuery, SellerWebhookService,
+    UpdatePlatformWebhookEndpointRequest, UpdateWebhookEndpointRequest,
+};
+use crate::error::AppError;
+use actix_web::{web, HttpResponse, Result};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeliveryPageQuery {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+fn require_seller(user: &AuthenticatedUser) -> Result<(), AppError> {
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("Only sellers can manage webhook endpoints".to_string()));
+    }
+    Ok(())
+}
+
+// Seller endpoints
+
+/// List the current seller's webhook endpoints
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhook-endpoints",
+    tag = "seller_webhooks",
+    responses((status = 200, description = "Success", body = [crate::services::seller_webhook_service::WebhookEndpoint])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_endpoints(
+    user: AuthenticatedUser,
+    service: web::Data<SellerWebhookService>,
+) -> Result<HttpResponse, AppError> {
+    require_seller(&user)?;
+
+    let endpoints = service.list_endpoints(user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(endpoints))
+}
+
+/// Register a webhook endpoint; the signing secret is only shown in this response
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhook-endpoints",
+    tag = "seller_webhooks",
+    request_body = CreateWebhookEndpointRequest,
+    responses((status = 201, description = "Created")),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_endpoint(
+    user: AuthenticatedUser,
+    request: web::Json<CreateWebhookEndpointRequest>,
+    service: web::Data<SellerWebhookService>,
+) -> Result<HttpResponse, AppError> {
+    require_seller(&user)?;
+
+    let endpoint = service.create_endpoint(user.user_id, request.into_inner()).await?;
+
+    Ok(HttpResponse::Created().json(endpoint))
+}
+
+/// Change a webhook endpoint's URL, events or active flag
+#[utoipa::path(
+    put,
+    path = "/api/v1/webhook-endpoints/{endpoint_id}",
+    tag = "seller_webhooks",
+    params(("endpoint_id" = Uuid, Path, description = "Endpoint ID")),
+    request_body = UpdateWebhookEndpointRequest,
+    responses((status = 200, description = "Success", body = crate::services::seller_webhook_service::WebhookEndpoint)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_endpoint(
+    user: AuthenticatedUser,
+    endpoint_id: web::Path<Uuid>,
+    request: web::Json<UpdateWebhookEndpointRequest>,
+    service: web::Data<SellerWebhookService>,
+) -> Result<HttpResponse, AppError> {
+    require_seller(&user)?;
+
+    let endpoint = service.update_endpoint(user.user_id, *endpoint_id, request.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(endpoint))
+}
+
+/// Delete a webhook endpoint
+#[utoipa::path(
+    delete,
+    path = "/api/v1/webhook-endpoints/{endpoint_id}",
+    tag = "seller_webhooks",
+    params(("endpoint_id" = Uuid, Path, description = "Endpoint ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_endpoint(
+    user: AuthenticatedUser,
+    endpoint_id: web::Path<Uuid>,
+    service: web::Data<SellerWebhookService>,
+) -> Result<HttpResponse, AppError> {
+    require_seller(&user)?;
+
+    service.delete_endpoint(user.user_id, *endpoint_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Recent deliveries to one of the current seller's endpoints
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhook-endpoints/{endpoint_id}/deliveries",
+    tag = "seller_webhooks",
+    params(
+        ("endpoint_id" = Uuid, Path, description = "Endpoint ID"),
+        DeliveryPageQuery,
+    ),
+    responses((status = 200, description = "Success", body = [crate::services::seller_webhook_service::EndpointDelivery])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_endpoint_deliveries(
+    user: AuthenticatedUser,
+    endpoint_id: web::Path<Uuid>,
+    query: web::Query<DeliveryPageQuery>,
+    service: web::Data<SellerWebhookService>,
+) -> Result<HttpResponse, AppError> {
+    require_seller(&user)?;
+
+    let deliveries = service
+        .list_endpoint_deliveries(user.user_id, *endpoint_id, query.limit, query.offset)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(deliveries))
+}
+
+// Admin endpoints
+
+/// List seller webhook deliveries across all endpoints (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/seller-webhooks/deliveries",
+    tag = "seller_webhooks",
+    params(EndpointDeliveryQuery),
+    responses((status = 200, description = "Success", body = [crate::services::seller_webhook_service::EndpointDelivery])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_deliveries(
+    user: AuthenticatedUser,
+    query: web::Query<EndpointDeliveryQuery>,
+    service: web::Data<SellerWebhookService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let deliveries = service.list_deliveries(&query).await?;
+
+    Ok(HttpResponse::Ok().json(deliveries))
+}
+
+/// Get a seller webhook delivery with its payload and last attempt (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/seller-webhooks/deliveries/{delivery_id}",
+    tag = "seller_webhooks",
+    params(("delivery_id" = Uuid, Path, description = "Delivery ID")),
+    responses((status = 200, description = "Success", body = crate::services::seller_webhook_service::EndpointDelivery)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_delivery(
+    user: AuthenticatedUser,
+    delivery_id: web::Path<Uuid>,
+    service: web::Data<SellerWebhookService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let delivery = service.get_delivery(*delivery_id).await?;
+
+    Ok(HttpResponse::Ok().json(delivery))
+}
+
+/// Send a seller webhook delivery again now (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/seller-webhooks/deliveries/{delivery_id}/redeliver",
+    tag = "seller_webhooks",
+    params(("delivery_id" = Uuid, Path, description = "Delivery ID")),
+    responses((status = 200, description = "Success", body = crate::services::seller_webhook_service::EndpointDelivery)),
+    security(("bearer_auth" = []))
+)]
+pub async fn redeliver(
+    user: AuthenticatedUser,
+    delivery_id: web::Path<Uuid>,
+    service: web::Data<SellerWebhookService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let delivery = service.redeliver(*delivery_id, user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(delivery))
+}
+
+/// List platform webhook endpoints (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/platform-webhooks",
+    tag = "seller_webhooks",
+    responses((status = 200, description = "Success", body = [crate::services::seller_webhook_service::WebhookEndpoint])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_platform_endpoints(
+    user: AuthenticatedUser,
+    service: web::Data<SellerWebhookService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let endpoints = service.list_platform_endpoints().await?;
+
+    Ok(HttpResponse::Ok().json(endpoints))
+}
+
+/// Register a platform webhook endpoint; the signing secret is only shown in this response (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/platform-webhooks",
+    tag = "seller_webhooks",
+    request_body = CreatePlatformWebhookEndpointRequest,
+    responses((status = 201, description = "Created", body = crate::services::seller_webhook_service::CreatedWebhookEndpoint)),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_platform_endpoint(
+    user: AuthenticatedUser,
+    request: web::Json<CreatePlatformWebhookEndpointRequest>,
+    service: web::Data<SellerWebhookService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let endpoint = service.create_platform_endpoint(user.user_id, request.into_inner()).await?;
+
+    Ok(HttpResponse::Created().json(endpoint))
+}
+
+/// Change a platform webhook endpoint's URL, events, tag filter or active flag (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/platform-webhooks/{endpoint_id}",
+    tag = "seller_webhooks",
+    params(("endpoint_id" = Uuid, Path, description = "Endpoint ID")),
+    request_body = UpdatePlatformWebhookEndpointRequest,
+    responses((status = 200, description = "Success", body = crate::services::seller_webhook_service::WebhookEndpoint)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_platform_endpoint(
+    user: AuthenticatedUser,
+    endpoint_id: web::Path<Uuid>,
+    request: web::Json<UpdatePlatformWebhookEndpointRequest>,
+    service: web::Data<SellerWebhookService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let endpoint = service
+        .update_platform_endpoint(*endpoint_id, request.into_inner(), user.user_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(endpoint))
+}
+
+/// Delete a platform webhook endpoint (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/platform-webhooks/{endpoint_id}",
+    tag = "seller_webhooks",
+    params(("endpoint_id" = Uuid, Path, description = "Endpoint ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_platform_endpoint(
+    user: AuthenticatedUser,
+    endpoint_id: web::Path<Uuid>,
+    service: web::Data<SellerWebhookService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    service.delete_platform_endpoint(*endpoint_id, user.user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
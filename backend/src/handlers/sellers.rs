@@ -0,0 +1,127 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::seller_onboarding_service::{
+    ConnectPayoutRequest, SelectSubscriptionRequest, SellerOnboardingService, SetKycStatusRequest,
+    UpdateSellerProfileRequest,
+};
+use crate::error::AppError;
+use actix_web::{web, HttpResponse, Result};
+use uuid::Uuid;
+
+/// Get the seller's onboarding checklist and the features it has unlocked
+#[utoipa::path(
+    get,
+    path = "/api/v1/sellers/onboarding",
+    tag = "sellers",
+    responses((status = 200, description = "Success", body = crate::models::seller_onboarding::SellerOnboarding)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_onboarding(
+    user: AuthenticatedUser,
+    onboarding_service: web::Data<SellerOnboardingService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("User is not a seller".to_string()));
+    }
+
+    let onboarding = onboarding_service.get_checklist(user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(onboarding))
+}
+
+/// Save the seller's company name and description
+#[utoipa::path(
+    put,
+    path = "/api/v1/sellers/onboarding/profile",
+    tag = "sellers",
+    request_body = UpdateSellerProfileRequest,
+    responses((status = 200, description = "Success", body = crate::models::seller_onboarding::SellerOnboarding)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_profile(
+    user: AuthenticatedUser,
+    request: web::Json<UpdateSellerProfileRequest>,
+    onboarding_service: web::Data<SellerOnboardingService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("User is not a seller".to_string()));
+    }
+
+    let onboarding = onboarding_service.update_profile(user.user_id, request.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(onboarding))
+}
+
+/// Connect the account the seller's payouts go to (after identity verification)
+#[utoipa::path(
+    put,
+    path = "/api/v1/sellers/onboarding/payout",
+    tag = "sellers",
+    request_body = ConnectPayoutRequest,
+    responses((status = 200, description = "Success", body = crate::models::seller_onboarding::SellerOnboarding)),
+    security(("bearer_auth" = []))
+)]
+pub async fn connect_payout(
+    user: AuthenticatedUser,
+    request: web::Json<ConnectPayoutRequest>,
+    onboarding_service: web::Data<SellerOnboardingService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("User is not a seller".to_string()));
+    }
+
+    let onboarding = onboarding_service.connect_payout(user.user_id, request.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(onboarding))
+}
+
+/// Choose the seller's subscription plan
+#[utoipa::path(
+    put,
+    path = "/api/v1/sellers/onboarding/subscription",
+    tag = "sellers",
+    request_body = SelectSubscriptionRequest,
+    responses((status = 200, description = "Success", body = crate::models::seller_onboarding::SellerOnboarding)),
+    security(("bearer_auth" = []))
+)]
+pub async fn select_subscription(
+    user: AuthenticatedUser,
+    request: web::Json<SelectSubscriptionRequest>,
+    onboarding_service: web::Data<SellerOnboardingService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("User is not a seller".to_string()));
+    }
+
+    let onboarding = onboarding_service
+        .select_subscription(user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(onboarding))
+}
+
+/// Record whether a seller passed identity verification (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/sellers/{user_id}/kyc",
+    tag = "sellers",
+    params(("user_id" = Uuid, Path, description = "Seller's user ID")),
+    request_body = SetKycStatusRequest,
+    responses((status = 200, description = "Success", body = crate::models::seller_onboarding::SellerOnboarding)),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_kyc_status(
+    user: AuthenticatedUser,
+    user_id: web::Path<Uuid>,
+    request: web::Json<SetKycStatusRequest>,
+    onboarding_service: web::Data<SellerOnboardingService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let onboarding = onboarding_service
+        .set_kyc_status(*user_id, user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(onboarding))
+}
@@ -0,0 +1,233 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::shipping_service::{
+    ShippingService, UpdateItemShippingRequest, UpdateShippingAddressRequest, UpdateShippingPolicyRequest,
+};
+use crate::error::AppError;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use raffle_platform_shared::RaffleResponse;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Request header naming the destination to quote shipping for, e.g. `US` or `US-CA`
+pub const SHIP_TO_HEADER: &str = "X-Ship-To";
+
+fn require_seller(user: &AuthenticatedUser) -> Result<(), AppError> {
+    if !user.is_seller() {
+        return Err(AppError::Forbidden("Only sellers can manage shipping policies".to_string()));
+    }
+    Ok(())
+}
+
+// Seller endpoints
+
+/// Get the current seller's shipping policy
+#[utoipa::path(
+    get,
+    path = "/api/v1/shipping/policy",
+    tag = "shipping",
+    responses((status = 200, description = "Success", body = crate::services::shipping_service::SellerShippingPolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_shipping_policy(
+    user: AuthenticatedUser,
+    shipping_service: web::Data<ShippingService>,
+) -> Result<HttpResponse, AppError> {
+    require_seller(&user)?;
+
+    let policy = shipping_service
+        .get_seller_policy(user.user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("No shipping policy has been set".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(policy))
+}
+
+/// Set the regions the seller ships to, their costs and the seller's handling time
+#[utoipa::path(
+    put,
+    path = "/api/v1/shipping/policy",
+    tag = "shipping",
+    request_body = UpdateShippingPolicyRequest,
+    responses((status = 200, description = "Success", body = crate::services::shipping_service::SellerShippingPolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_shipping_policy(
+    user: AuthenticatedUser,
+    request: web::Json<UpdateShippingPolicyRequest>,
+    shipping_service: web::Data<ShippingService>,
+) -> Result<HttpResponse, AppError> {
+    require_seller(&user)?;
+
+    let policy = shipping_service
+        .set_seller_policy(user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(policy))
+}
+
+/// Get an item's shipping override
+#[utoipa::path(
+    get,
+    path = "/api/v1/shipping/items/{item_id}",
+    tag = "shipping",
+    params(("item_id" = Uuid, Path, description = "Item ID")),
+    responses((status = 200, description = "Success", body = crate::services::shipping_service::ItemShippingOverride)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_item_shipping(
+    user: AuthenticatedUser,
+    item_id: web::Path<Uuid>,
+    shipping_service: web::Data<ShippingService>,
+) -> Result<HttpResponse, AppError> {
+    require_seller(&user)?;
+
+    let shipping = shipping_service
+        .get_item_override(user.user_id, *item_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Item has no shipping override".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(shipping))
+}
+
+/// Set an item's weight and any shipping settings that differ from the seller's policy
+#[utoipa::path(
+    put,
+    path = "/api/v1/shipping/items/{item_id}",
+    tag = "shipping",
+    params(("item_id" = Uuid, Path, description = "Item ID")),
+    request_body = UpdateItemShippingRequest,
+    responses((status = 200, description = "Success", body = crate::services::shipping_service::ItemShippingOverride)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_item_shipping(
+    user: AuthenticatedUser,
+    item_id: web::Path<Uuid>,
+    request: web::Json<UpdateItemShippingRequest>,
+    shipping_service: web::Data<ShippingService>,
+) -> Result<HttpResponse, AppError> {
+    require_seller(&user)?;
+
+    let shipping = shipping_service
+        .set_item_override(user.user_id, *item_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(shipping))
+}
+
+/// Remove an item's shipping override
+#[utoipa::path(
+    delete,
+    path = "/api/v1/shipping/items/{item_id}",
+    tag = "shipping",
+    params(("item_id" = Uuid, Path, description = "Item ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_item_shipping(
+    user: AuthenticatedUser,
+    item_id: web::Path<Uuid>,
+    shipping_service: web::Data<ShippingService>,
+) -> Result<HttpResponse, AppError> {
+    require_seller(&user)?;
+
+    shipping_service.delete_item_override(user.user_id, *item_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// User endpoints
+
+/// Get the current user's shipping address
+#[utoipa::path(
+    get,
+    path = "/api/v1/shipping/address",
+    tag = "shipping",
+    responses((status = 200, description = "Success", body = crate::services::shipping_service::ShippingAddress)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_shipping_address(
+    user: AuthenticatedUser,
+    shipping_service: web::Data<ShippingService>,
+) -> Result<HttpResponse, AppError> {
+    let address = shipping_service
+        .get_address(user.user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("No shipping address has been set".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(address))
+}
+
+/// Set the address the current user's prizes are shipped to
+#[utoipa::path(
+    put,
+    path = "/api/v1/shipping/address",
+    tag = "shipping",
+    request_body = UpdateShippingAddressRequest,
+    responses((status = 200, description = "Success", body = crate::services::shipping_service::ShippingAddress)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_shipping_address(
+    user: AuthenticatedUser,
+    request: web::Json<UpdateShippingAddressRequest>,
+    shipping_service: web::Data<ShippingService>,
+) -> Result<HttpResponse, AppError> {
+    let address = shipping_service
+        .set_address(user.user_id, request.into_inner())
+        .await?;
+
+    info!("User {} updated their shipping address", user.user_id);
+
+    Ok(HttpResponse::Ok().json(address))
+}
+
+/// Check whether a prize the current user won ships to their address
+#[utoipa::path(
+    get,
+    path = "/api/v1/shipping/prizes/{raffle_id}",
+    tag = "shipping",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success", body = crate::services::shipping_service::ShippingFulfillmentCheck)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_my_prize_shipping(
+    user: AuthenticatedUser,
+    raffle_id: web::Path<Uuid>,
+    shipping_service: web::Data<ShippingService>,
+) -> Result<HttpResponse, AppError> {
+    let check = shipping_service
+        .check_fulfillment(*raffle_id, user.user_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(check))
+}
+
+/// Add shipping estimates to raffles for the destination asked for by the request or saved by the user
+///
+/// Estimates are a convenience, so failures are logged and the raffles are left as they are.
+pub async fn estimate_shipping(
+    req: &HttpRequest,
+    user: Option<&AuthenticatedUser>,
+    shipping_service: &ShippingService,
+    raffles: &mut [RaffleResponse],
+) {
+    let requested = req
+        .headers()
+        .get(SHIP_TO_HEADER)
+        .and_then(|h| h.to_str().ok());
+
+    let destination = match shipping_service
+        .resolve_destination(requested, user.map(|u| u.user_id))
+        .await
+    {
+        Ok(Some(destination)) => destination,
+        Ok(None) => return,
+        Err(e) => {
+            warn!("Ignoring shipping destination for request: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = shipping_service.apply_to_raffles(raffles, &destination).await {
+        warn!("Failed to estimate shipping to {}: {}", destination, e);
+    }
+}
@@ -0,0 +1,432 @@
+use crate::error::AppError;
+use crate::handlers::currency::localize_raffles;
+use crate::handlers::shipping::estimate_shipping;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::tag::{CreateTagCollectionRequest, CreateTagRequest, TagCollection, UpdateTagCollectionRequest, UpdateTagRequest};
+use crate::services::fx_service::FxService;
+use crate::services::raffle_service::{RaffleSearchParams, RaffleService};
+use crate::services::shipping_service::ShippingService;
+use crate::services::TagService;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use raffle_platform_shared::{CursorPage, RaffleResponse, RaffleStatus};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TagListQuery {
+    /// Start of the tag, for autocomplete
+    pub prefix: Option<String>,
+    pub curated: Option<bool>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TrendingTagsQuery {
+    pub days: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetTagsRequest {
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AddSynonymRequest {
+    pub synonym: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CollectionPageQuery {
+    pub sort_by: Option<String>,
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+/// A discovery page: the collection and one page of its open raffles
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CollectionPage {
+    pub collection: TagCollection,
+    #[schema(value_type = raffle_platform_shared::RaffleCursorPage)]
+    pub raffles: CursorPage<RaffleResponse>,
+}
+
+// Public endpoints
+
+/// Tags for autocomplete, curated first
+#[utoipa::path(
+    get,
+    path = "/api/v1/tags",
+    tag = "tags",
+    params(TagListQuery),
+    responses((status = 200, description = "Success", body = [crate::models::tag::Tag])),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn list_tags(
+    query: web::Query<TagListQuery>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let tags = tag_service
+        .list_tags(query.prefix.as_deref(), query.curated.unwrap_or(false), limit)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+/// Tags ranked by recent box purchases
+#[utoipa::path(
+    get,
+    path = "/api/v1/tags/trending",
+    tag = "tags",
+    params(TrendingTagsQuery),
+    responses((status = 200, description = "Success", body = [crate::services::tag_service::TrendingTag])),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn get_trending_tags(
+    query: web::Query<TrendingTagsQuery>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+    let tags = tag_service.trending_tags(query.days, limit).await?;
+
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+/// Published collections in discovery order
+#[utoipa::path(
+    get,
+    path = "/api/v1/tags/collections",
+    tag = "tags",
+    responses((status = 200, description = "Success", body = [crate::models::tag::TagCollection])),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn list_collections(
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    let collections = tag_service.list_collections(true).await?;
+
+    Ok(HttpResponse::Ok().json(collections))
+}
+
+/// A collection's discovery page, listing open raffles carrying any of its tags
+#[utoipa::path(
+    get,
+    path = "/api/v1/tags/collections/{slug}",
+    tag = "tags",
+    params(
+        ("slug" = String, Path, description = "Slug"),
+        CollectionPageQuery,
+    ),
+    responses((status = 200, description = "Success", body = CollectionPage)),
+    security((), ("bearer_auth" = []))
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_collection_page(
+    req: HttpRequest,
+    user: Option<AuthenticatedUser>,
+    slug: web::Path<String>,
+    query: web::Query<CollectionPageQuery>,
+    tag_service: web::Data<TagService>,
+    raffle_service: web::Data<RaffleService>,
+    fx_service: web::Data<FxService>,
+    shipping_service: web::Data<ShippingService>,
+) -> Result<HttpResponse, AppError> {
+    let collection = tag_service.published_collection(&slug).await?;
+
+    let search_params = RaffleSearchParams {
+        status: Some(RaffleStatus::Open),
+        item_id: None,
+        min_price: None,
+        max_price: None,
+        completion_min: None,
+        completion_max: None,
+        sort_by: query.sort_by.clone().or_else(|| Some("featured".to_string())),
+        limit: query.limit.or(Some(20)),
+        cursor: query.cursor.clone(),
+        tags: collection.tags.clone(),
+        match_all_tags: false,
+        category: None,
+        attributes: Vec::new(),
+    };
+
+    let mut raffles = raffle_service.search_raffles(search_params).await?;
+    localize_raffles(&req, user.as_ref(), &fx_service, &mut raffles.data).await;
+    estimate_shipping(&req, user.as_ref(), &shipping_service, &mut raffles.data).await;
+
+    Ok(HttpResponse::Ok().json(CollectionPage { collection, raffles }))
+}
+
+/// A raffle's tags and its item's
+#[utoipa::path(
+    get,
+    path = "/api/v1/raffles/{raffle_id}/tags",
+    tag = "tags",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success", body = crate::services::tag_service::RaffleTags)),
+    security((), ("bearer_auth" = []))
+)]
+pub async fn get_raffle_tags(
+    raffle_id: web::Path<Uuid>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    let tags = tag_service.raffle_tags(*raffle_id).await?;
+
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+/// An item's tags
+#[utoipa::path(
+    get,
+    path = "/api/v1/items/{item_id}/tags",
+    tag = "tags",
+    params(("item_id" = Uuid, Path, description = "Item ID")),
+    responses((status = 200, description = "Success"))
+)]
+pub async fn get_item_tags(
+    item_id: web::Path<Uuid>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    let tags = tag_service.item_tags(*item_id).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "item_id": *item_id, "tags": tags })))
+}
+
+// Seller endpoints
+
+/// Replace the tags on one of the seller's items
+#[utoipa::path(
+    put,
+    path = "/api/v1/items/{item_id}/tags",
+    tag = "tags",
+    params(("item_id" = Uuid, Path, description = "Item ID")),
+    request_body = SetTagsRequest,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_item_tags(
+    user: AuthenticatedUser,
+    item_id: web::Path<Uuid>,
+    request: web::Json<SetTagsRequest>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_seller() && !user.is_admin() {
+        return Err(AppError::Forbidden("Only sellers can tag items".to_string()));
+    }
+
+    let tags = tag_service
+        .set_item_tags(*item_id, user.user_id, request.into_inner().tags, user.is_admin())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "item_id": *item_id, "tags": tags })))
+}
+
+// Admin endpoints
+
+/// Register a tag, or curate one sellers already use
+/// (requires the `catalog.manage` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/tags",
+    tag = "tags",
+    request_body = CreateTagRequest,
+    responses((status = 201, description = "Created", body = crate::models::tag::Tag)),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_tag(
+    user: AuthenticatedUser,
+    request: web::Json<CreateTagRequest>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    let tag = tag_service.create_tag(request.into_inner(), user.user_id).await?;
+
+    Ok(HttpResponse::Created().json(tag))
+}
+
+/// Change a tag's display name, description or curated flag
+/// (requires the `catalog.manage` permission)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/tags/{slug}",
+    tag = "tags",
+    params(("slug" = String, Path, description = "Slug")),
+    request_body = UpdateTagRequest,
+    responses((status = 200, description = "Success", body = crate::models::tag::Tag)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_tag(
+    user: AuthenticatedUser,
+    slug: web::Path<String>,
+    request: web::Json<UpdateTagRequest>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    let tag = tag_service.update_tag(&slug, request.into_inner(), user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(tag))
+}
+
+/// Delete a tag and take it off every item and raffle
+/// (requires the `catalog.manage` permission)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/tags/{slug}",
+    tag = "tags",
+    params(("slug" = String, Path, description = "Slug")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_tag(
+    user: AuthenticatedUser,
+    slug: web::Path<String>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    tag_service.delete_tag(&slug, user.user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Spellings rewritten to a tag
+/// (requires the `catalog.manage` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/tags/{slug}/synonyms",
+    tag = "tags",
+    params(("slug" = String, Path, description = "Slug")),
+    responses((status = 200, description = "Success", body = [crate::models::tag::TagSynonym])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_synonyms(
+    slug: web::Path<String>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    let synonyms = tag_service.list_synonyms(&slug).await?;
+
+    Ok(HttpResponse::Ok().json(synonyms))
+}
+
+/// Add a synonym, merging it into the tag if it was a tag of its own
+/// (requires the `catalog.manage` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/tags/{slug}/synonyms",
+    tag = "tags",
+    params(("slug" = String, Path, description = "Slug")),
+    request_body = AddSynonymRequest,
+    responses((status = 201, description = "Created", body = crate::models::tag::TagSynonym)),
+    security(("bearer_auth" = []))
+)]
+pub async fn add_synonym(
+    user: AuthenticatedUser,
+    slug: web::Path<String>,
+    request: web::Json<AddSynonymRequest>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    let synonym = tag_service.add_synonym(&slug, &request.synonym, user.user_id).await?;
+
+    Ok(HttpResponse::Created().json(synonym))
+}
+
+/// Stop rewriting a synonym
+/// (requires the `catalog.manage` permission)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/tag-synonyms/{synonym}",
+    tag = "tags",
+    params(("synonym" = String, Path, description = "Synonym")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn remove_synonym(
+    user: AuthenticatedUser,
+    synonym: web::Path<String>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    tag_service.remove_synonym(&synonym, user.user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Every collection, drafts included
+/// (requires the `catalog.manage` permission)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/tag-collections",
+    tag = "tags",
+    responses((status = 200, description = "Success", body = [crate::models::tag::TagCollection])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_all_collections(
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    let collections = tag_service.list_collections(false).await?;
+
+    Ok(HttpResponse::Ok().json(collections))
+}
+
+/// Create a tag collection
+/// (requires the `catalog.manage` permission)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/tag-collections",
+    tag = "tags",
+    request_body = CreateTagCollectionRequest,
+    responses((status = 201, description = "Created", body = crate::models::tag::TagCollection)),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_collection(
+    user: AuthenticatedUser,
+    request: web::Json<CreateTagCollectionRequest>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    let collection = tag_service.create_collection(request.into_inner(), user.user_id).await?;
+
+    Ok(HttpResponse::Created().json(collection))
+}
+
+/// Change a collection's content, tags, position or published flag
+/// (requires the `catalog.manage` permission)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/tag-collections/{collection_id}",
+    tag = "tags",
+    params(("collection_id" = Uuid, Path, description = "Collection ID")),
+    request_body = UpdateTagCollectionRequest,
+    responses((status = 200, description = "Success", body = crate::models::tag::TagCollection)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_collection(
+    user: AuthenticatedUser,
+    collection_id: web::Path<Uuid>,
+    request: web::Json<UpdateTagCollectionRequest>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    let collection = tag_service
+        .update_collection(*collection_id, request.into_inner(), user.user_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(collection))
+}
+
+/// Delete a tag collection
+/// (requires the `catalog.manage` permission)
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/tag-collections/{collection_id}",
+    tag = "tags",
+    params(("collection_id" = Uuid, Path, description = "Collection ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_collection(
+    user: AuthenticatedUser,
+    collection_id: web::Path<Uuid>,
+    tag_service: web::Data<TagService>,
+) -> Result<HttpResponse, AppError> {
+    tag_service.delete_collection(*collection_id, user.user_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,243 @@
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::shipping_service::ShippingService;
+use crate::services::tax_service::{
+    ReviewTaxFormRequest, TaxInfoSubmission, TaxJurisdictionUpdate, TaxService,
+};
+use crate::error::AppError;
+use actix_web::{web, HttpResponse, Result};
+use serde::Deserialize;
+use tracing::{debug, info};
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaxFilingExportQuery {
+    pub tax_year: i32,
+    pub country_code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FulfillmentCheckPath {
+    pub raffle_id: Uuid,
+    pub user_id: Uuid,
+}
+
+/// Get the current user's tax forms
+#[utoipa::path(
+    get,
+    path = "/api/v1/tax/forms",
+    tag = "tax",
+    responses((status = 200, description = "Success", body = [crate::services::tax_service::WinnerTaxForm])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_my_tax_forms(
+    user: AuthenticatedUser,
+    tax_service: web::Data<TaxService>,
+) -> Result<HttpResponse, AppError> {
+    let forms = tax_service.get_user_forms(user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(forms))
+}
+
+/// Submit tax information for a required form
+#[utoipa::path(
+    post,
+    path = "/api/v1/tax/forms/{form_id}/submit",
+    tag = "tax",
+    params(("form_id" = Uuid, Path, description = "Form ID")),
+    request_body = TaxInfoSubmission,
+    responses((status = 200, description = "Success", body = crate::services::tax_service::WinnerTaxForm)),
+    security(("bearer_auth" = []))
+)]
+pub async fn submit_tax_form(
+    user: AuthenticatedUser,
+    form_id: web::Path<Uuid>,
+    request: web::Json<TaxInfoSubmission>,
+    tax_service: web::Data<TaxService>,
+) -> Result<HttpResponse, AppError> {
+    let form = tax_service
+        .submit_form(*form_id, user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(form))
+}
+
+/// Get submitted forms awaiting review (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/tax/forms/review-queue",
+    tag = "tax",
+    responses((status = 200, description = "Success", body = [crate::services::tax_service::WinnerTaxForm])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_tax_review_queue(
+    user: AuthenticatedUser,
+    tax_service: web::Data<TaxService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    debug!("Admin {} requesting tax form review queue", user.user_id);
+
+    let forms = tax_service.get_review_queue().await?;
+
+    Ok(HttpResponse::Ok().json(forms))
+}
+
+/// Verify or reject a submitted tax form (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/tax/forms/{form_id}/review",
+    tag = "tax",
+    params(("form_id" = Uuid, Path, description = "Form ID")),
+    request_body = ReviewTaxFormRequest,
+    responses((status = 200, description = "Success", body = crate::services::tax_service::WinnerTaxForm)),
+    security(("bearer_auth" = []))
+)]
+pub async fn review_tax_form(
+    user: AuthenticatedUser,
+    form_id: web::Path<Uuid>,
+    request: web::Json<ReviewTaxFormRequest>,
+    tax_service: web::Data<TaxService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let form = tax_service
+        .review_form(*form_id, user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(form))
+}
+
+/// Get reporting thresholds per jurisdiction (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/tax/jurisdictions",
+    tag = "tax",
+    responses((status = 200, description = "Success", body = [crate::services::tax_service::TaxJurisdiction])),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_tax_jurisdictions(
+    user: AuthenticatedUser,
+    tax_service: web::Data<TaxService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let jurisdictions = tax_service.get_jurisdictions().await?;
+
+    Ok(HttpResponse::Ok().json(jurisdictions))
+}
+
+/// Create or update the reporting rules for a country (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/tax/jurisdictions/{country_code}",
+    tag = "tax",
+    params(("country_code" = String, Path, description = "Country code")),
+    request_body = TaxJurisdictionUpdate,
+    responses((status = 200, description = "Success", body = crate::services::tax_service::TaxJurisdiction)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_tax_jurisdiction(
+    user: AuthenticatedUser,
+    country_code: web::Path<String>,
+    request: web::Json<TaxJurisdictionUpdate>,
+    tax_service: web::Data<TaxService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!("Admin {} updating tax jurisdiction {}", user.user_id, country_code);
+
+    let jurisdiction = tax_service
+        .upsert_jurisdiction(&country_code, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(jurisdiction))
+}
+
+/// Check whether a won prize may be fulfilled: the tax form is complete, the winner has made
+/// any prize choice, and the seller ships to the winner's address (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/tax/fulfillment/{raffle_id}/{user_id}",
+    tag = "tax",
+    params(
+        ("raffle_id" = Uuid, Path, description = "Raffle ID"),
+        ("user_id" = Uuid, Path, description = "User ID"),
+    ),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_fulfillment_status(
+    user: AuthenticatedUser,
+    path: web::Path<FulfillmentCheckPath>,
+    tax_service: web::Data<TaxService>,
+    shipping_service: web::Data<ShippingService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    let tax_cleared = tax_service
+        .is_fulfillment_allowed(path.raffle_id, path.user_id)
+        .await?;
+    let shipping = shipping_service
+        .check_fulfillment(path.raffle_id, path.user_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "raffle_id": path.raffle_id,
+        "user_id": path.user_id,
+        "fulfillment_allowed": tax_cleared
+            && !shipping.awaiting_choice
+            && (!shipping.needs_shipping || shipping.ships_to_address),
+        "tax_cleared": tax_cleared,
+        "shipping": shipping
+    })))
+}
+
+/// Export required tax filings for a year as CSV (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/tax/filings",
+    tag = "tax",
+    params(TaxFilingExportQuery),
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn export_tax_filings(
+    user: AuthenticatedUser,
+    query: web::Query<TaxFilingExportQuery>,
+    tax_service: web::Data<TaxService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
+
+    info!(
+        "Admin {} exporting {} tax filings for {}",
+        user.user_id, query.country_code, query.tax_year
+    );
+
+    let export = tax_service
+        .export_filings(query.tax_year, &query.country_code)
+        .await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv")
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", export.filename),
+        ))
+        .insert_header(("X-Export-Filing-Count", export.filing_count.to_string()))
+        .insert_header(("X-Export-Missing-Forms", export.missing_forms.to_string()))
+        .body(export.body))
+}
@@ -0,0 +1,426 @@
+use crate::error::AppError;
+use crate::database::Database;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::models::favorite::{AddFavoriteRequest, Favorites};
+use crate::models::notification_preferences::{
+    NotificationPreferences, PushDevice, RegisterPushDeviceRequest, UpdateNotificationPreferencesRequest,
+};
+use crate::models::saved_search::{SavedSearch, SavedSearchRequest};
+use crate::models::user_block::{BlockUserRequest, UserBlock};
+use crate::models::winner_display::{UpdateWinnerDisplayRequest, WinnerDisplaySettings};
+use crate::services::compliance_service::StartVerificationRequest;
+use crate::services::{ComplianceService, FavoritesService, PendingActionsService};
+use actix_web::{web, HttpResponse, Result};
+use tracing::info;
+use uuid::Uuid;
+
+/// Get everything the current user is waiting on; updates are also pushed over WebSocket
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/pending-actions",
+    tag = "users",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_pending_actions(
+    user: AuthenticatedUser,
+    pending_actions_service: web::Data<PendingActionsService>,
+) -> Result<HttpResponse, AppError> {
+    let pending = pending_actions_service.list_for_user(user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(pending))
+}
+
+/// Get how the current user's name is shown when they win or buy boxes
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/winner-display",
+    tag = "users",
+    responses((status = 200, description = "Success", body = crate::models::winner_display::WinnerDisplaySettings)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_winner_display(
+    user: AuthenticatedUser,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let settings = WinnerDisplaySettings::find(database.pool(), user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+/// Choose how the current user's name is shown on winner lists, the grid and realtime events
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/me/winner-display",
+    tag = "users",
+    request_body = UpdateWinnerDisplayRequest,
+    responses((status = 200, description = "Success", body = crate::models::winner_display::WinnerDisplaySettings)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_winner_display(
+    user: AuthenticatedUser,
+    request: web::Json<UpdateWinnerDisplayRequest>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let settings = WinnerDisplaySettings::update(database.pool(), user.user_id, request.into_inner()).await?;
+
+    info!("User {} set winner display to {:?}", user.user_id, settings.winner_display);
+
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+/// Get which channels and kinds of notification the current user receives
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/notification-preferences",
+    tag = "users",
+    responses((status = 200, description = "Success", body = crate::models::notification_preferences::NotificationPreferences)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_notification_preferences(
+    user: AuthenticatedUser,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let preferences = NotificationPreferences::find(database.pool(), user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+/// Turn notification channels and kinds on or off; fields left out are unchanged
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/me/notification-preferences",
+    tag = "users",
+    request_body = UpdateNotificationPreferencesRequest,
+    responses((status = 200, description = "Success", body = crate::models::notification_preferences::NotificationPreferences)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_notification_preferences(
+    user: AuthenticatedUser,
+    request: web::Json<UpdateNotificationPreferencesRequest>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let preferences = NotificationPreferences::update(database.pool(), user.user_id, request.into_inner()).await?;
+
+    info!("User {} updated notification preferences", user.user_id);
+
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+/// List the devices the current user receives push notifications on
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/push-devices",
+    tag = "users",
+    responses((status = 200, description = "Success", body = [crate::models::notification_preferences::PushDevice])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_push_devices(
+    user: AuthenticatedUser,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let devices = PushDevice::list_for_user(database.pool(), user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(devices))
+}
+
+/// Register a device token from the mobile app
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/push-devices",
+    tag = "users",
+    request_body = RegisterPushDeviceRequest,
+    responses((status = 201, description = "Created", body = crate::models::notification_preferences::PushDevice)),
+    security(("bearer_auth" = []))
+)]
+pub async fn register_push_device(
+    user: AuthenticatedUser,
+    request: web::Json<RegisterPushDeviceRequest>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let device = PushDevice::register(database.pool(), user.user_id, request.into_inner()).await?;
+
+    info!("User {} registered {:?} push device {}", user.user_id, device.platform, device.id);
+
+    Ok(HttpResponse::Created().json(device))
+}
+
+/// Stop sending push notifications to one of the current user's devices
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/push-devices/{device_id}",
+    tag = "users",
+    params(("device_id" = Uuid, Path, description = "Device ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn remove_push_device(
+    user: AuthenticatedUser,
+    path: web::Path<Uuid>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let device_id = path.into_inner();
+    PushDevice::remove(database.pool(), user.user_id, device_id).await?;
+
+    info!("User {} removed push device {}", user.user_id, device_id);
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// List the users the current user has blocked or muted
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/blocks",
+    tag = "users",
+    responses((status = 200, description = "Success", body = [UserBlock])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_blocks(
+    user: AuthenticatedUser,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let blocks = UserBlock::list_for_user(database.pool(), user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(blocks))
+}
+
+/// Block or mute a user: both hide their comments, and a block also stops them replying to
+/// the current user or commenting on their raffles
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/blocks",
+    tag = "users",
+    request_body = BlockUserRequest,
+    responses((status = 201, description = "Created", body = UserBlock)),
+    security(("bearer_auth" = []))
+)]
+pub async fn block_user(
+    user: AuthenticatedUser,
+    request: web::Json<BlockUserRequest>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let block = UserBlock::create(database.pool(), user.user_id, request.into_inner()).await?;
+
+    info!("User {} set {:?} on user {}", user.user_id, block.kind, block.blocked_id);
+
+    Ok(HttpResponse::Created().json(block))
+}
+
+/// Unblock or unmute a user
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/blocks/{user_id}",
+    tag = "users",
+    params(("user_id" = Uuid, Path, description = "User ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn unblock_user(
+    user: AuthenticatedUser,
+    path: web::Path<Uuid>,
+    database: web::Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    let blocked_id = path.into_inner();
+    UserBlock::remove(database.pool(), user.user_id, blocked_id).await?;
+
+    info!("User {} unblocked user {}", user.user_id, blocked_id);
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// List the raffles and items the current user has favorited
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/favorites",
+    tag = "users",
+    responses((status = 200, description = "Success", body = Favorites)),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_favorites(
+    user: AuthenticatedUser,
+    favorites_service: web::Data<FavoritesService>,
+) -> Result<HttpResponse, AppError> {
+    let favorites = favorites_service.list_favorites(user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(favorites))
+}
+
+/// Favorite a raffle or an item. A favorite raffle alerts the user once when it is nearly
+/// sold out or about to end.
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/favorites",
+    tag = "users",
+    request_body = AddFavoriteRequest,
+    responses((status = 200, description = "Success", body = Favorites)),
+    security(("bearer_auth" = []))
+)]
+pub async fn add_favorite(
+    user: AuthenticatedUser,
+    request: web::Json<AddFavoriteRequest>,
+    favorites_service: web::Data<FavoritesService>,
+) -> Result<HttpResponse, AppError> {
+    let favorites = favorites_service
+        .add_favorite(user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(favorites))
+}
+
+/// Remove a raffle from the current user's favorites
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/favorites/raffles/{raffle_id}",
+    tag = "users",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn remove_favorite_raffle(
+    user: AuthenticatedUser,
+    path: web::Path<Uuid>,
+    favorites_service: web::Data<FavoritesService>,
+) -> Result<HttpResponse, AppError> {
+    favorites_service.remove_raffle(user.user_id, path.into_inner()).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Remove an item from the current user's favorites
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/favorites/items/{item_id}",
+    tag = "users",
+    params(("item_id" = Uuid, Path, description = "Item ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn remove_favorite_item(
+    user: AuthenticatedUser,
+    path: web::Path<Uuid>,
+    favorites_service: web::Data<FavoritesService>,
+) -> Result<HttpResponse, AppError> {
+    favorites_service.remove_item(user.user_id, path.into_inner()).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// List the current user's saved searches
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/saved-searches",
+    tag = "users",
+    responses((status = 200, description = "Success", body = [SavedSearch])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_saved_searches(
+    user: AuthenticatedUser,
+    favorites_service: web::Data<FavoritesService>,
+) -> Result<HttpResponse, AppError> {
+    let searches = favorites_service.list_saved_searches(user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(searches))
+}
+
+/// Save a search. With alerts on, the user is told about new raffles that match it.
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/saved-searches",
+    tag = "users",
+    request_body = SavedSearchRequest,
+    responses((status = 201, description = "Created", body = SavedSearch)),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_saved_search(
+    user: AuthenticatedUser,
+    request: web::Json<SavedSearchRequest>,
+    favorites_service: web::Data<FavoritesService>,
+) -> Result<HttpResponse, AppError> {
+    let search = favorites_service
+        .create_saved_search(user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Created().json(search))
+}
+
+/// Replace one of the current user's saved searches
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/me/saved-searches/{search_id}",
+    tag = "users",
+    params(("search_id" = Uuid, Path, description = "Saved search ID")),
+    request_body = SavedSearchRequest,
+    responses((status = 200, description = "Success", body = SavedSearch)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_saved_search(
+    user: AuthenticatedUser,
+    path: web::Path<Uuid>,
+    request: web::Json<SavedSearchRequest>,
+    favorites_service: web::Data<FavoritesService>,
+) -> Result<HttpResponse, AppError> {
+    let search = favorites_service
+        .update_saved_search(user.user_id, path.into_inner(), request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(search))
+}
+
+/// Delete one of the current user's saved searches
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/saved-searches/{search_id}",
+    tag = "users",
+    params(("search_id" = Uuid, Path, description = "Saved search ID")),
+    responses((status = 204, description = "No content")),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_saved_search(
+    user: AuthenticatedUser,
+    path: web::Path<Uuid>,
+    favorites_service: web::Data<FavoritesService>,
+) -> Result<HttpResponse, AppError> {
+    favorites_service
+        .delete_saved_search(user.user_id, path.into_inner())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Get the current user's identity verification status and latest session
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/kyc",
+    tag = "users",
+    responses((status = 200, description = "Success", body = crate::services::compliance_service::KycOverview)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_kyc_status(
+    user: AuthenticatedUser,
+    compliance_service: web::Data<ComplianceService>,
+) -> Result<HttpResponse, AppError> {
+    let overview = compliance_service.get_overview(user.user_id).await?;
+
+    Ok(HttpResponse::Ok().json(overview))
+}
+
+/// Start identity verification; returns the provider's hosted flow to send the user to
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/kyc",
+    tag = "users",
+    request_body = StartVerificationRequest,
+    responses((status = 200, description = "Success", body = crate::services::compliance_service::IdentityVerification)),
+    security(("bearer_auth" = []))
+)]
+pub async fn start_kyc_verification(
+    user: AuthenticatedUser,
+    request: web::Json<StartVerificationRequest>,
+    compliance_service: web::Data<ComplianceService>,
+) -> Result<HttpResponse, AppError> {
+    let verification = compliance_service
+        .start_verification(user.user_id, request.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(verification))
+}
@@ -0,0 +1,58 @@
+use crate::error::AppError;
+use crate::services::verification_service::ReplayOutcome;
+use crate::services::VerificationService;
+use actix_web::{web, HttpResponse, Result};
+use tracing::debug;
+use uuid::Uuid;
+
+/// Public, versioned record of a completed draw for third-party verification tools
+#[utoipa::path(
+    get,
+    path = "/api/v1/verify/{raffle_id}",
+    tag = "verification",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success", body = crate::services::verification_service::RaffleVerification))
+)]
+pub async fn verify_raffle(
+    raffle_id: web::Path<Uuid>,
+    verification_service: web::Data<VerificationService>,
+) -> Result<HttpResponse, AppError> {
+    debug!("Building verification record for raffle {}", raffle_id);
+
+    let verification = verification_service.verify_raffle(*raffle_id).await?;
+
+    // Completed draws never change, so caches and verifiers can keep the record
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "public, max-age=300"))
+        .insert_header(("X-Verification-Schema-Version", verification.schema_version))
+        .json(verification))
+}
+
+/// Re-derive the winners of a completed draw from its on-chain inputs and compare them with the recorded ones
+#[utoipa::path(
+    get,
+    path = "/api/v1/verify/{raffle_id}/replay",
+    tag = "verification",
+    params(("raffle_id" = Uuid, Path, description = "Raffle ID")),
+    responses((status = 200, description = "Success", body = crate::services::verification_service::DrawReplay))
+)]
+pub async fn replay_draw(
+    raffle_id: web::Path<Uuid>,
+    verification_service: web::Data<VerificationService>,
+) -> Result<HttpResponse, AppError> {
+    debug!("Replaying draw for raffle {}", raffle_id);
+
+    let replay = verification_service.replay_draw(*raffle_id).await?;
+
+    // A replay missing chain inputs may complete once the indexer catches up, so only settled
+    // results are cached
+    let cache_control = match replay.outcome {
+        ReplayOutcome::Incomplete => "no-store",
+        ReplayOutcome::Verified | ReplayOutcome::Mismatch => "public, max-age=300",
+    };
+
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", cache_control))
+        .insert_header(("X-Verification-Schema-Version", replay.schema_version))
+        .json(replay))
+}
@@ -1,16 +1,20 @@
-use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Result};
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::PgPool;
 use uuid::Uuid;
 use ethers::types::{Address, U256, TransactionRequest};
+use utoipa::ToSchema;
 use std::str::FromStr;
 
 use crate::error::AppError;
 use crate::middleware::auth::{extract_user_id, Claims};
-use crate::services::WalletService;
+use crate::models::WalletDerivation;
+use crate::services::external_wallet_service::{LinkChallengeRequest, LinkWalletRequest};
+use crate::services::{ExternalWalletService, WalletService};
+use crate::utils::hd_wallet::{DerivationPath, COIN_TYPE_ETHEREUM};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SignMessageRequest {
     pub message: String,
     pub password: String,
@@ -38,9 +42,15 @@ pub struct SendTransactionRequest {
     pub chain_id: u64,
 }
 
+/// Import either a private key, or a mnemonic with the path to derive from
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ImportWalletRequest {
-    pub private_key: String,
+    pub private_key: Option<String>,
+    pub mnemonic: Option<String>,
+    /// BIP-44 path such as `m/44'/60'/0'/0/0`; takes precedence over the indices
+    pub derivation_path: Option<String>,
+    pub account_index: Option<u32>,
+    pub address_index: Option<u32>,
     pub password: String,
 }
 
@@ -49,6 +59,11 @@ pub struct ExportWalletRequest {
     pub password: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpgradeWalletRequest {
+    pub password: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RotateEncryptionRequest {
     pub old_password: String,
@@ -86,7 +101,7 @@ pub struct WalletInfoResponse {
     pub transaction_count: Option<u64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SignatureResponse {
     pub signature: String,
     pub r: String,
@@ -101,6 +116,13 @@ pub struct TransactionResponse {
 }
 
 /// Get wallet address for authenticated user
+#[utoipa::path(
+    get,
+    path = "/api/v1/wallet/address",
+    tag = "wallet",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 #[get("/address")]
 pub async fn get_wallet_address(
     claims: Claims,
@@ -151,6 +173,14 @@ pub async fn get_wallet_info(
 }
 
 /// Sign a message with user's wallet
+#[utoipa::path(
+    post,
+    path = "/api/v1/wallet/sign-message",
+    tag = "wallet",
+    request_body = SignMessageRequest,
+    responses((status = 200, description = "Success", body = SignatureResponse)),
+    security(("bearer_auth" = []))
+)]
 #[post("/sign-message")]
 pub async fn sign_message(
     claims: Claims,
@@ -285,6 +315,14 @@ pub async fn send_transaction(
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/wallet/verify-signature",
+    tag = "wallet",
+    request_body = Object,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 #[post("/verify-signature")]
 pub async fn verify_signature(
     pool: web::Data<PgPool>,
@@ -321,71 +359,90 @@ pub async fn verify_signature(
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/wallet/export-private-key",
+    tag = "wallet",
+    request_body = Object,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 #[post("/export-private-key")]
 pub async fn export_private_key(
-    pool: web::Data<PgPool>,
+    wallet_service: web::Data<WalletService>,
     request: HttpRequest,
     export_request: web::Json<serde_json::Value>,
 ) -> Result<HttpResponse, AppError> {
     let user_id = extract_user_id(&request.into())?;
-    let wallet_service = WalletService::new(pool.get_ref().clone());
 
     let password = export_request
         .get("password")
         .and_then(|v| v.as_str())
         .ok_or_else(|| AppError::Validation("Password is required".to_string()))?;
 
-    let private_key = wallet_service
-        .export_private_key(user_id, password)
+    let export = wallet_service
+        .export_wallet(user_id, password)
         .await?;
 
     Ok(HttpResponse::Ok().json(json!({
         "data": {
-            "private_key": private_key,
+            "private_key": export.private_key,
+            "address": export.address,
+            "key_source": export.derivation.key_source,
+            "derivation_path": export.derivation.derivation_path,
+            "account_index": export.derivation.account_index,
+            "chain_addresses": export.chain_addresses,
             "warning": "Keep this private key secure and never share it with anyone!"
         }
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/wallet/import-private-key",
+    tag = "wallet",
+    request_body = Object,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 #[post("/import-private-key")]
 pub async fn import_private_key(
-    pool: web::Data<PgPool>,
+    wallet_service: web::Data<WalletService>,
     request: HttpRequest,
     import_request: web::Json<serde_json::Value>,
 ) -> Result<HttpResponse, AppError> {
     let user_id = extract_user_id(&request.into())?;
-    let wallet_service = WalletService::new(pool.get_ref().clone());
 
-    let password = import_request
-        .get("password")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| AppError::Validation("Password is required".to_string()))?;
+    let import_request: ImportWalletRequest = serde_json::from_value(import_request.into_inner())
+        .map_err(|e| AppError::Validation(format!("Invalid import request: {}", e)))?;
 
-    let private_key = import_request
-        .get("private_key")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| AppError::Validation("Private key is required".to_string()))?;
-
-    let new_address = wallet_service
-        .import_wallet(user_id, password, private_key)
-        .await?;
+    let (new_address, derivation) = import_into_wallet(&wallet_service, user_id, &import_request).await?;
 
     Ok(HttpResponse::Ok().json(json!({
         "message": "Wallet imported successfully",
         "data": {
-            "address": new_address
+            "address": new_address,
+            "key_source": derivation.key_source,
+            "derivation_path": derivation.derivation_path
         }
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/wallet/rotate-encryption",
+    tag = "wallet",
+    request_body = Object,
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 #[post("/rotate-encryption")]
 pub async fn rotate_wallet_encryption(
-    pool: web::Data<PgPool>,
+    wallet_service: web::Data<WalletService>,
     request: HttpRequest,
     rotate_request: web::Json<serde_json::Value>,
 ) -> Result<HttpResponse, AppError> {
     let user_id = extract_user_id(&request.into())?;
-    let wallet_service = WalletService::new(pool.get_ref().clone());
 
     let old_password = rotate_request
         .get("old_password")
@@ -406,6 +463,13 @@ pub async fn rotate_wallet_encryption(
     })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/wallet/balance",
+    tag = "wallet",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
 #[get("/balance")]
 pub async fn get_wallet_balance(
     pool: web::Data<PgPool>,
@@ -440,17 +504,22 @@ pub async fn export_private_key(
     let user_id = Uuid::parse_str(&claims.sub)
         .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
 
-    let private_key = wallet_service
-        .export_private_key(user_id, &req.password)
+    let export = wallet_service
+        .export_wallet(user_id, &req.password)
         .await?;
 
     Ok(HttpResponse::Ok().json(json!({
-        "private_key": format!("0x{}", private_key),
+        "private_key": format!("0x{}", export.private_key),
+        "address": export.address,
+        "key_source": export.derivation.key_source,
+        "derivation_path": export.derivation.derivation_path,
+        "account_index": export.derivation.account_index,
+        "chain_addresses": export.chain_addresses,
         "warning": "Keep this private key secure and never share it with anyone"
     })))
 }
 
-/// Import wallet from private key
+/// Import wallet from a private key or mnemonic, retiring the current one
 #[post("/import")]
 pub async fn import_wallet(
     claims: Claims,
@@ -460,19 +529,243 @@ pub async fn import_wallet(
     let user_id = Uuid::parse_str(&claims.sub)
         .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
 
-    // Remove 0x prefix if present
-    let private_key = req.private_key.strip_prefix("0x").unwrap_or(&req.private_key);
-
-    let address = wallet_service
-        .import_wallet(user_id, &req.password, private_key)
-        .await?;
+    let (address, derivation) = import_into_wallet(&wallet_service, user_id, &req).await?;
 
     Ok(HttpResponse::Ok().json(json!({
         "address": address,
+        "key_source": derivation.key_source,
+        "derivation_path": derivation.derivation_path,
         "message": "Wallet imported successfully"
     })))
 }
 
+/// Wallet derivation and the user's address on each supported chain
+#[utoipa::path(
+    get,
+    path = "/api/v1/wallet/addresses",
+    tag = "wallet",
+    responses((status = 200, description = "Success")),
+    security(("bearer_auth" = []))
+)]
+#[get("/addresses")]
+pub async fn get_chain_addresses(
+    claims: Claims,
+    wallet_service: web::Data<WalletService>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
+
+    let derivation = wallet_service.wallet_derivation(user_id).await?;
+    let addresses = wallet_service.chain_addresses(user_id).await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "key_source": derivation.key_source,
+        "derivation_path": derivation.derivation_path,
+        "account_index": derivation.account_index,
+        "addresses": addresses
+    })))
+}
+
+/// Move the wallet onto an account under the platform master seed
+#[utoipa::path(
+    post,
+    path = "/api/v1/wallet/upgrade",
+    tag = "wallet",
+    request_body = UpgradeWalletRequest,
+    responses((status = 200, description = "Success", body = crate::services::wallet_service::WalletUpgrade)),
+    security(("bearer_auth" = []))
+)]
+#[post("/upgrade")]
+pub async fn upgrade_wallet(
+    claims: Claims,
+    wallet_service: web::Data<WalletService>,
+    req: web::Json<UpgradeWalletRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
+
+    let upgrade = wallet_service
+        .upgrade_to_platform_wallet(user_id, &req.password)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(upgrade))
+}
+
+/// Start linking a wallet the user holds: returns the message it must sign
+#[utoipa::path(
+    post,
+    path = "/api/v1/wallet/external/challenge",
+    tag = "wallet",
+    request_body = LinkChallengeRequest,
+    responses((status = 200, description = "Success", body = crate::services::external_wallet_service::LinkChallenge)),
+    security(("bearer_auth" = []))
+)]
+#[post("/external/challenge")]
+pub async fn create_external_wallet_challenge(
+    claims: Claims,
+    external_wallet_service: web::Data<ExternalWalletService>,
+    req: web::Json<LinkChallengeRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
+
+    let challenge = external_wallet_service
+        .create_challenge(user_id, req.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(challenge))
+}
+
+/// Link a wallet with its signature of the challenge message
+#[utoipa::path(
+    post,
+    path = "/api/v1/wallet/external",
+    tag = "wallet",
+    request_body = LinkWalletRequest,
+    responses((status = 201, description = "Linked", body = crate::models::ExternalWallet)),
+    security(("bearer_auth" = []))
+)]
+#[post("/external")]
+pub async fn link_external_wallet(
+    claims: Claims,
+    external_wallet_service: web::Data<ExternalWalletService>,
+    req: web::Json<LinkWalletRequest>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
+
+    let wallet = external_wallet_service
+        .link_wallet(user_id, req.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Created().json(wallet))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/wallet/external",
+    tag = "wallet",
+    responses((status = 200, description = "Success", body = [crate::models::ExternalWallet])),
+    security(("bearer_auth" = []))
+)]
+#[get("/external")]
+pub async fn list_external_wallets(
+    claims: Claims,
+    external_wallet_service: web::Data<ExternalWalletService>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
+
+    let wallets = external_wallet_service.list_wallets(user_id).await?;
+
+    Ok(HttpResponse::Ok().json(json!({ "wallets": wallets })))
+}
+
+/// Stop using a wallet for new purchases; boxes it already holds stay with the user
+#[utoipa::path(
+    delete,
+    path = "/api/v1/wallet/external/{wallet_id}",
+    tag = "wallet",
+    params(("wallet_id" = Uuid, Path, description = "External wallet ID")),
+    responses((status = 204, description = "Unlinked")),
+    security(("bearer_auth" = []))
+)]
+#[delete("/external/{wallet_id}")]
+pub async fn unlink_external_wallet(
+    claims: Claims,
+    external_wallet_service: web::Data<ExternalWalletService>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
+
+    external_wallet_service
+        .unlink_wallet(user_id, path.into_inner())
+        .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/wallet/external/purchases",
+    tag = "wallet",
+    responses((status = 200, description = "Success", body = [crate::services::external_wallet_service::ExternalPurchase])),
+    security(("bearer_auth" = []))
+)]
+#[get("/external/purchases")]
+pub async fn list_external_purchases(
+    claims: Claims,
+    external_wallet_service: web::Data<ExternalWalletService>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
+
+    let purchases = external_wallet_service.list_purchases(user_id).await?;
+
+    Ok(HttpResponse::Ok().json(json!({ "purchases": purchases })))
+}
+
+/// A fresh voucher for a purchase the wallet hasn't redeemed before the last one expired
+#[utoipa::path(
+    post,
+    path = "/api/v1/wallet/external/purchases/{purchase_id}/voucher",
+    tag = "wallet",
+    params(("purchase_id" = Uuid, Path, description = "External purchase ID")),
+    responses(
+        (status = 200, description = "Success", body = crate::services::external_wallet_service::PreparedExternalPurchase),
+        (status = 404, description = "No pending purchase")
+    ),
+    security(("bearer_auth" = []))
+)]
+#[post("/external/purchases/{purchase_id}/voucher")]
+pub async fn renew_external_purchase_voucher(
+    claims: Claims,
+    external_wallet_service: web::Data<ExternalWalletService>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
+
+    let prepared = external_wallet_service
+        .renew_voucher(user_id, path.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(prepared))
+}
+
+/// Import the key the request carries; a mnemonic is derived at the requested path
+async fn import_into_wallet(
+    wallet_service: &WalletService,
+    user_id: Uuid,
+    req: &ImportWalletRequest,
+) -> Result<(String, WalletDerivation), AppError> {
+    match (req.private_key.as_deref(), req.mnemonic.as_deref()) {
+        (Some(private_key), None) => {
+            let address = wallet_service
+                .import_wallet(user_id, &req.password, private_key)
+                .await?;
+            Ok((address, WalletDerivation::imported()))
+        }
+        (None, Some(mnemonic)) => {
+            let path = match req.derivation_path.as_deref() {
+                Some(path) => path.parse::<DerivationPath>()?,
+                None => DerivationPath::new(
+                    COIN_TYPE_ETHEREUM,
+                    req.account_index.unwrap_or(0),
+                    req.address_index.unwrap_or(0),
+                ),
+            };
+            wallet_service
+                .import_mnemonic_wallet(user_id, &req.password, mnemonic, path)
+                .await
+        }
+        _ => Err(AppError::Validation(
+            "Provide either a private key or a mnemonic".to_string(),
+        )),
+    }
+}
+
 /// Rotate wallet encryption (change password)
 #[put("/rotate-encryption")]
 pub async fn rotate_encryption(
@@ -3,18 +3,31 @@ use serde_json::Value;
 use tracing::{error, info, warn};
 
 use crate::error::AppError;
-use crate::services::payment_service::PaymentService;
-use crate::services::blockchain_service::BlockchainService;
+use crate::middleware::auth::AuthenticatedUser;
+use crate::services::email_service::EmailService;
+use crate::services::webhook_service::{
+    WebhookDelivery, WebhookDeliveryQuery, WebhookRetryPolicy, WebhookService, WebhookSource,
+};
+use crate::utils::crypto::hash_token;
 use crate::utils::webhook_verification;
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
 
 /// Stripe webhook handler
-/// Handles payment events from Stripe including successful payments,
-/// failed payments, and subscription changes
+/// Stores payment events from Stripe and processes them; failures are retried
+/// internally, so the event is acknowledged once it is stored
+#[utoipa::path(
+    post,
+    path = "/webhooks/webhooks/stripe",
+    tag = "webhooks",
+    responses((status = 200, description = "Success"))
+)]
 #[post("/webhooks/stripe")]
 pub async fn stripe_webhook(
     req: HttpRequest,
     body: web::Bytes,
-    payment_service: web::Data<PaymentService>,
+    webhook_service: web::Data<WebhookService>,
 ) -> Result<HttpResponse, AppError> {
     // Verify webhook signature
     let signature = req
@@ -29,47 +42,31 @@ pub async fn stripe_webhook(
     let payload: Value = serde_json::from_slice(&body)
         .map_err(|e| AppError::Validation(format!("Invalid JSON: {}", e)))?;
 
-    let event_type = payload["type"]
-        .as_str()
-        .ok_or_else(|| AppError::Validation("Missing event type".to_string()))?;
+    if payload["type"].as_str().is_none() {
+        return Err(AppError::Validation("Missing event type".to_string()));
+    }
 
-    info!("Received Stripe webhook: {}", event_type);
+    let delivery = webhook_service.receive(WebhookSource::Stripe, payload).await?;
 
-    match event_type {
-        "payment_intent.succeeded" => {
-            handle_payment_success(&payment_service, &payload).await?;
-        }
-        "payment_intent.payment_failed" => {
-            handle_payment_failure(&payment_service, &payload).await?;
-        }
-        "invoice.payment_succeeded" => {
-            handle_subscription_payment_success(&payment_service, &payload).await?;
-        }
-        "invoice.payment_failed" => {
-            handle_subscription_payment_failure(&payment_service, &payload).await?;
-        }
-        "customer.subscription.deleted" => {
-            handle_subscription_cancelled(&payment_service, &payload).await?;
-        }
-        _ => {
-            warn!("Unhandled Stripe webhook event: {}", event_type);
-        }
-    }
+    info!("Received Stripe webhook {}: {:?}", delivery.event_type, delivery.status);
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": "Webhook processed successfully"
-    })))
+    Ok(delivery_accepted(&delivery))
 }
 
 /// Blockchain webhook handler
-/// Handles events from the smart contract including box purchases,
-/// raffle completions, and winner selections
+/// Stores events from the smart contract including box purchases,
+/// raffle completions, and winner selections, then processes them
+#[utoipa::path(
+    post,
+    path = "/webhooks/webhooks/blockchain",
+    tag = "webhooks",
+    responses((status = 200, description = "Success"))
+)]
 #[post("/webhooks/blockchain")]
 pub async fn blockchain_webhook(
     req: HttpRequest,
     body: web::Bytes,
-    blockchain_service: web::Data<BlockchainService>,
+    webhook_service: web::Data<WebhookService>,
 ) -> Result<HttpResponse, AppError> {
     // Verify webhook signature (if using a service like Alchemy)
     if let Some(signature) = req.headers().get("x-alchemy-signature") {
@@ -82,42 +79,62 @@ pub async fn blockchain_webhook(
     let payload: Value = serde_json::from_slice(&body)
         .map_err(|e| AppError::Validation(format!("Invalid JSON: {}", e)))?;
 
-    let event_type = payload["type"]
-        .as_str()
-        .unwrap_or("unknown");
+    let delivery = webhook_service.receive(WebhookSource::Blockchain, payload).await?;
 
-    info!("Received blockchain webhook: {}", event_type);
+    info!("Received blockchain webhook {}: {:?}", delivery.event_type, delivery.status);
 
-    // Process blockchain events
-    match event_type {
-        "ADDRESS_ACTIVITY" => {
-            handle_address_activity(&blockchain_service, &payload).await?;
-        }
-        "MINED_TRANSACTION" => {
-            handle_mined_transaction(&blockchain_service, &payload).await?;
-        }
-        _ => {
-            // Handle direct contract events
-            if let Some(logs) = payload["logs"].as_array() {
-                for log in logs {
-                    handle_contract_event(&blockchain_service, log).await?;
-                }
-            }
-        }
+    Ok(delivery_accepted(&delivery))
+}
+
+/// Identity verification webhook handler
+/// Stores verification results from the KYC provider, then processes them
+#[utoipa::path(
+    post,
+    path = "/webhooks/webhooks/identity",
+    tag = "webhooks",
+    responses((status = 200, description = "Success"))
+)]
+#[post("/webhooks/identity")]
+pub async fn identity_webhook(
+    req: HttpRequest,
+    body: web::Bytes,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse, AppError> {
+    let signature = req
+        .headers()
+        .get("x-kyc-signature")
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| AppError::Validation("Missing identity verification signature".to_string()))?;
+
+    webhook_verification::verify_kyc_signature(&body, signature)?;
+
+    let payload: Value = serde_json::from_slice(&body)
+        .map_err(|e| AppError::Validation(format!("Invalid JSON: {}", e)))?;
+
+    if payload["type"].as_str().is_none() {
+        return Err(AppError::Validation("Missing event type".to_string()));
     }
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": "Blockchain webhook processed successfully"
-    })))
+    let delivery = webhook_service.receive(WebhookSource::Identity, payload).await?;
+
+    info!("Received identity webhook {}: {:?}", delivery.event_type, delivery.status);
+
+    Ok(delivery_accepted(&delivery))
 }
 
 /// Email/SMS notification webhook handler
 /// Handles delivery status updates from notification services
+#[utoipa::path(
+    post,
+    path = "/webhooks/webhooks/notifications",
+    tag = "webhooks",
+    responses((status = 200, description = "Success"))
+)]
 #[post("/webhooks/notifications")]
 pub async fn notification_webhook(
     req: HttpRequest,
     body: web::Bytes,
+    email_service: web::Data<EmailService>,
 ) -> Result<HttpResponse, AppError> {
     // Parse webhook payload
     let payload: Value = serde_json::from_slice(&body)
@@ -137,7 +154,10 @@ pub async fn notification_webhook(
             handle_notification_failed(&payload).await?;
         }
         "bounced" => {
-            handle_notification_bounced(&payload).await?;
+            handle_notification_bounced(&payload, &email_service).await?;
+        }
+        "complained" => {
+            handle_notification_complained(&payload, &email_service).await?;
         }
         _ => {
             warn!("Unhandled notification webhook event: {}", event_type);
@@ -152,181 +172,289 @@ pub async fn notification_webhook(
 
 // Helper functions for handling specific webhook events
 
-async fn handle_payment_success(
-    payment_service: &PaymentService,
-    payload: &Value,
-) -> Result<(), AppError> {
-    let payment_intent_id = payload["data"]["object"]["id"]
+/// Response for a stored webhook; the sender does not need to retry even if processing failed
+pub(crate) fn delivery_accepted(delivery: &WebhookDelivery) -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": "accepted",
+        "delivery_id": delivery.id,
+        "processing_status": delivery.status
+    }))
+}
+
+async fn handle_notification_delivered(payload: &Value) -> Result<(), AppError> {
+    let message_id = payload["message_id"]
         .as_str()
-        .ok_or_else(|| AppError::Validation("Missing payment intent ID".to_string()))?;
+        .ok_or_else(|| AppError::Validation("Missing message ID".to_string()))?;
 
-    let amount = payload["data"]["object"]["amount"]
-        .as_u64()
-        .ok_or_else(|| AppError::Validation("Missing amount".to_string()))?;
+    info!("Notification delivered: {}", message_id);
 
-    info!("Processing successful payment: {} for amount: {}", payment_intent_id, amount);
+    // Update notification status in database
+    // TODO: Implement notification status tracking
 
-    payment_service.process_successful_payment(payment_intent_id, amount).await?;
     Ok(())
 }
 
-async fn handle_payment_failure(
-    payment_service: &PaymentService,
-    payload: &Value,
-) -> Result<(), AppError> {
-    let payment_intent_id = payload["data"]["object"]["id"]
+async fn handle_notification_failed(payload: &Value) -> Result<(), AppError> {
+    let message_id = payload["message_id"]
         .as_str()
-        .ok_or_else(|| AppError::Validation("Missing payment intent ID".to_string()))?;
+        .ok_or_else(|| AppError::Validation("Missing message ID".to_string()))?;
 
-    let failure_reason = payload["data"]["object"]["last_payment_error"]["message"]
+    let reason = payload["reason"]
         .as_str()
-        .unwrap_or("Unknown error");
+        .unwrap_or("Unknown reason");
 
-    error!("Payment failed: {} - {}", payment_intent_id, failure_reason);
+    error!("Notification failed: {} - {}", message_id, reason);
+
+    // Update notification status and potentially retry
+    // TODO: Implement notification retry logic
 
-    payment_service.process_failed_payment(payment_intent_id, failure_reason).await?;
     Ok(())
 }
 
-async fn handle_subscription_payment_success(
-    payment_service: &PaymentService,
-    payload: &Value,
-) -> Result<(), AppError> {
-    let subscription_id = payload["data"]["object"]["subscription"]
+async fn handle_notification_bounced(payload: &Value, email_service: &EmailService) -> Result<(), AppError> {
+    let message_id = payload["message_id"]
         .as_str()
-        .ok_or_else(|| AppError::Validation("Missing subscription ID".to_string()))?;
+        .ok_or_else(|| AppError::Validation("Missing message ID".to_string()))?;
+
+    warn!("Notification bounced: {}", message_id);
 
-    info!("Processing successful subscription payment: {}", subscription_id);
+    let Some(email) = report_address(payload, message_id, email_service).await? else {
+        warn!("Bounce for unknown message {}", message_id);
+        return Ok(());
+    };
 
-    payment_service.process_subscription_payment_success(subscription_id).await?;
-    Ok(())
+    // Hard bounces stop further mail; soft bounces are left to the send queue's retries
+    let permanent = payload["bounce_type"].as_str() != Some("soft");
+    let reason = payload["reason"].as_str().map(str::to_string);
+    email_service.record_bounce(&email, permanent, reason).await
 }
 
-async fn handle_subscription_payment_failure(
-    payment_service: &PaymentService,
-    payload: &Value,
-) -> Result<(), AppError> {
-    let subscription_id = payload["data"]["object"]["subscription"]
+async fn handle_notification_complained(payload: &Value, email_service: &EmailService) -> Result<(), AppError> {
+    let message_id = payload["message_id"]
         .as_str()
-        .ok_or_else(|| AppError::Validation("Missing subscription ID".to_string()))?;
+        .ok_or_else(|| AppError::Validation("Missing message ID".to_string()))?;
 
-    error!("Subscription payment failed: {}", subscription_id);
+    warn!("Notification marked as spam: {}", message_id);
 
-    payment_service.process_subscription_payment_failure(subscription_id).await?;
-    Ok(())
+    let Some(email) = report_address(payload, message_id, email_service).await? else {
+        warn!("Complaint for unknown message {}", message_id);
+        return Ok(());
+    };
+
+    email_service
+        .record_complaint(&email, payload["reason"].as_str().map(str::to_string))
+        .await
 }
 
-async fn handle_subscription_cancelled(
-    payment_service: &PaymentService,
+/// Address a delivery report is about: given outright, or found from the message it refers to
+async fn report_address(
     payload: &Value,
-) -> Result<(), AppError> {
-    let subscription_id = payload["data"]["object"]["id"]
-        .as_str()
-        .ok_or_else(|| AppError::Validation("Missing subscription ID".to_string()))?;
-
-    info!("Processing subscription cancellation: {}", subscription_id);
+    message_id: &str,
+    email_service: &EmailService,
+) -> Result<Option<String>, AppError> {
+    match payload["email"].as_str() {
+        Some(email) => Ok(Some(email.to_string())),
+        None => email_service.address_for_provider_message(message_id).await,
+    }
+}
 
-    payment_service.process_subscription_cancellation(subscription_id).await?;
-    Ok(())
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WebhookTokenQuery {
+    pub token: String,
 }
 
-async fn handle_address_activity(
-    blockchain_service: &BlockchainService,
-    payload: &Value,
-) -> Result<(), AppError> {
-    let activity = &payload["activity"];
-    if let Some(activities) = activity.as_array() {
-        for activity in activities {
-            blockchain_service.process_address_activity(activity).await?;
+/// Amazon SES bounce and complaint handler
+/// SES publishes to an SNS topic whose HTTPS subscription points here with
+/// `?token=<EMAIL_WEBHOOK_TOKEN>`; bounced and complaining addresses are suppressed
+#[utoipa::path(
+    post,
+    path = "/webhooks/webhooks/email/ses",
+    tag = "webhooks",
+    params(WebhookTokenQuery),
+    responses((status = 200, description = "Success"))
+)]
+#[post("/webhooks/email/ses")]
+pub async fn ses_email_webhook(
+    query: web::Query<WebhookTokenQuery>,
+    body: web::Bytes,
+    email_service: web::Data<EmailService>,
+) -> Result<HttpResponse, AppError> {
+    let expected = email_service
+        .webhook_token()
+        .ok_or_else(|| AppError::NotFound("SES webhook is not configured".to_string()))?;
+
+    // Compare digests so the check takes the same time however much of the token matches
+    if hash_token(&query.token) != hash_token(expected) {
+        return Err(AppError::Authentication("Invalid SES webhook token".to_string()));
+    }
+
+    // SNS posts JSON with a text/plain content type
+    let envelope: Value = serde_json::from_slice(&body)
+        .map_err(|e| AppError::Validation(format!("Invalid JSON: {}", e)))?;
+
+    match envelope["Type"].as_str() {
+        Some("SubscriptionConfirmation") => {
+            let subscribe_url = envelope["SubscribeURL"]
+                .as_str()
+                .ok_or_else(|| AppError::Validation("Missing SubscribeURL".to_string()))?;
+            confirm_sns_subscription(subscribe_url).await?;
+            info!("Confirmed SNS subscription for SES notifications");
+        }
+        Some("Notification") => {
+            let message = envelope["Message"]
+                .as_str()
+                .ok_or_else(|| AppError::Validation("Missing Message".to_string()))?;
+            let event: Value = serde_json::from_str(message)
+                .map_err(|e| AppError::Validation(format!("Invalid SES event: {}", e)))?;
+
+            let suppressed = email_service.handle_ses_event(&event).await?;
+            if suppressed > 0 {
+                info!("Suppressed {} addresses from an SES report", suppressed);
+            }
         }
+        other => warn!("Unhandled SNS message type: {:?}", other),
     }
-    Ok(())
+
+    Ok(HttpResponse::Ok().finish())
 }
 
-async fn handle_mined_transaction(
-    blockchain_service: &BlockchainService,
-    payload: &Value,
-) -> Result<(), AppError> {
-    let tx_hash = payload["data"]["transaction"]["hash"]
-        .as_str()
-        .ok_or_else(|| AppError::Validation("Missing transaction hash".to_string()))?;
+/// Visit the confirmation link SNS sends, which must point at AWS
+async fn confirm_sns_subscription(subscribe_url: &str) -> Result<(), AppError> {
+    let url = reqwest::Url::parse(subscribe_url)
+        .map_err(|_| AppError::Validation("Invalid SubscribeURL".to_string()))?;
+    let is_aws = url.scheme() == "https"
+        && url.host_str().is_some_and(|host| host.ends_with(".amazonaws.com"));
+    if !is_aws {
+        return Err(AppError::Validation("SubscribeURL is not an AWS endpoint".to_string()));
+    }
 
-    info!("Processing mined transaction: {}", tx_hash);
+    let response = reqwest::get(url)
+        .await
+        .map_err(|e| AppError::Internal(format!("SNS subscription confirmation failed: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(AppError::Internal(format!(
+            "SNS subscription confirmation returned {}",
+            response.status()
+        )));
+    }
 
-    blockchain_service.process_mined_transaction(tx_hash).await?;
     Ok(())
 }
 
-async fn handle_contract_event(
-    blockchain_service: &BlockchainService,
-    log: &Value,
-) -> Result<(), AppError> {
-    let topics = log["topics"]
-        .as_array()
-        .ok_or_else(|| AppError::Validation("Missing topics".to_string()))?;
-
-    if let Some(event_signature) = topics.get(0).and_then(|t| t.as_str()) {
-        match event_signature {
-            // BoxPurchased event signature
-            "0x..." => {
-                blockchain_service.handle_box_purchased_event(log).await?;
-            }
-            // WinnerSelected event signature
-            "0x..." => {
-                blockchain_service.handle_winner_selected_event(log).await?;
-            }
-            // RaffleFull event signature
-            "0x..." => {
-                blockchain_service.handle_raffle_full_event(log).await?;
-            }
-            _ => {
-                warn!("Unhandled contract event: {}", event_signature);
-            }
-        }
+// Admin endpoints
+
+/// List stored webhook deliveries (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/webhooks/deliveries",
+    tag = "webhooks",
+    params(WebhookDeliveryQuery),
+    responses((status = 200, description = "Success", body = [crate::services::webhook_service::WebhookDeliverySummary])),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_webhook_deliveries(
+    user: AuthenticatedUser,
+    query: web::Query<WebhookDeliveryQuery>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
     }
 
-    Ok(())
-}
+    let deliveries = webhook_service.list_deliveries(&query).await?;
 
-async fn handle_notification_delivered(payload: &Value) -> Result<(), AppError> {
-    let message_id = payload["message_id"]
-        .as_str()
-        .ok_or_else(|| AppError::Validation("Missing message ID".to_string()))?;
+    Ok(HttpResponse::Ok().json(deliveries))
+}
 
-    info!("Notification delivered: {}", message_id);
+/// Get a stored webhook delivery with its payload (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/webhooks/deliveries/{delivery_id}",
+    tag = "webhooks",
+    params(("delivery_id" = Uuid, Path, description = "Delivery ID")),
+    responses((status = 200, description = "Success", body = crate::services::webhook_service::WebhookDelivery)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_webhook_delivery(
+    user: AuthenticatedUser,
+    delivery_id: web::Path<Uuid>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
 
-    // Update notification status in database
-    // TODO: Implement notification status tracking
+    let delivery = webhook_service.get_delivery(*delivery_id).await?;
 
-    Ok(())
+    Ok(HttpResponse::Ok().json(delivery))
 }
 
-async fn handle_notification_failed(payload: &Value) -> Result<(), AppError> {
-    let message_id = payload["message_id"]
-        .as_str()
-        .ok_or_else(|| AppError::Validation("Missing message ID".to_string()))?;
+/// Process a stored webhook delivery again (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/webhooks/deliveries/{delivery_id}/redeliver",
+    tag = "webhooks",
+    params(("delivery_id" = Uuid, Path, description = "Delivery ID")),
+    responses((status = 200, description = "Success", body = crate::services::webhook_service::WebhookDelivery)),
+    security(("bearer_auth" = []))
+)]
+pub async fn redeliver_webhook(
+    user: AuthenticatedUser,
+    delivery_id: web::Path<Uuid>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
 
-    let reason = payload["reason"]
-        .as_str()
-        .unwrap_or("Unknown reason");
+    let delivery = webhook_service.redeliver(*delivery_id, user.user_id).await?;
 
-    error!("Notification failed: {} - {}", message_id, reason);
+    Ok(HttpResponse::Ok().json(delivery))
+}
 
-    // Update notification status and potentially retry
-    // TODO: Implement notification retry logic
+/// Get the webhook retry policy (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/webhooks/retry-policy",
+    tag = "webhooks",
+    responses((status = 200, description = "Success", body = crate::services::webhook_service::WebhookRetryPolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_webhook_retry_policy(
+    user: AuthenticatedUser,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
 
-    Ok(())
+    let policy = webhook_service.get_retry_policy().await?;
+
+    Ok(HttpResponse::Ok().json(policy))
 }
 
-async fn handle_notification_bounced(payload: &Value) -> Result<(), AppError> {
-    let message_id = payload["message_id"]
-        .as_str()
-        .ok_or_else(|| AppError::Validation("Missing message ID".to_string()))?;
+/// Update the webhook retry policy (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/webhooks/retry-policy",
+    tag = "webhooks",
+    request_body = WebhookRetryPolicy,
+    responses((status = 200, description = "Success", body = crate::services::webhook_service::WebhookRetryPolicy)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_webhook_retry_policy(
+    user: AuthenticatedUser,
+    request: web::Json<WebhookRetryPolicy>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse, AppError> {
+    if !user.is_admin() {
+        return Err(AppError::Forbidden("Admin access required".to_string()));
+    }
 
-    warn!("Notification bounced: {}", message_id);
+    info!("Admin {} updating webhook retry policy", user.user_id);
 
-    // Mark email/phone as invalid
-    // TODO: Implement bounce handling
+    let policy = webhook_service.update_retry_policy(request.into_inner()).await?;
 
-    Ok(())
-}
\ No newline at end of file
+    Ok(HttpResponse::Ok().json(policy))
+}
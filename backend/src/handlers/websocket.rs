@@ -1,4 +1,6 @@
-use crate::services::realtime_service::{RealtimeService, WebSocketMessage, ClientMessage, SubscribeMessage, AuthMessage, EventSubscription};
+use crate::error::AppError;
+use crate::services::realtime_protocol::{self, Frame, Protocol, ProtocolQuery, SUPPORTED_PROTOCOL_VERSIONS};
+use crate::services::realtime_service::{RealtimeService, WebSocketMessage, ClientMessage, SubscribeMessage, AuthMessage, EventSubscription, JoinRoomMessage, LeaveRoomMessage, ConnectionCounters, Disconnect};
 use actix::prelude::*;
 use actix_web::{web, Error, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
@@ -17,14 +19,18 @@ pub type WebSocketManager = RealtimeService;
 pub struct WebSocketActor {
     id: Uuid,
     manager: Arc<RealtimeService>,
+    counters: Arc<ConnectionCounters>,
+    protocol: Protocol,
     hb: Instant,
 }
 
 impl WebSocketActor {
-    pub fn new(manager: Arc<RealtimeService>) -> Self {
+    pub fn new(manager: Arc<RealtimeService>, protocol: Protocol) -> Self {
         Self {
             id: Uuid::new_v4(),
             manager,
+            counters: Arc::new(ConnectionCounters::default()),
+            protocol,
             hb: Instant::now(),
         }
     }
@@ -41,8 +47,44 @@ impl WebSocketActor {
         });
     }
 
-    fn handle_client_message(&self, msg: &str, ctx: &mut ws::WebsocketContext<Self>) {
-        match serde_json::from_str::<ClientMessage>(msg) {
+    fn send(&self, ctx: &mut ws::WebsocketContext<Self>, message_type: &str, data: serde_json::Value) {
+        let msg = WebSocketMessage {
+            message_type: message_type.to_string(),
+            data,
+            timestamp: chrono::Utc::now(),
+        };
+
+        self.write(ctx, &msg);
+    }
+
+    /// Write a message in the envelope and encoding agreed at connect time
+    fn write(&self, ctx: &mut ws::WebsocketContext<Self>, msg: &WebSocketMessage) {
+        match self.protocol.encode(msg) {
+            Ok(Frame::Text(text)) => ctx.text(text),
+            Ok(Frame::Binary(bytes)) => ctx.binary(bytes),
+            Err(e) => error!("Failed to encode {} message for connection {}: {}", msg.message_type, self.id, e),
+        }
+    }
+
+    /// Confirm a join or leave with the connection's rooms, or report why it was refused
+    fn send_room_result(
+        &self,
+        ctx: &mut ws::WebsocketContext<Self>,
+        message_type: &str,
+        room: &str,
+        result: Result<Vec<String>, AppError>,
+    ) {
+        match result {
+            Ok(rooms) => self.send(ctx, message_type, serde_json::json!({"room": room, "rooms": rooms})),
+            Err(e) => {
+                debug!("Room request for {} refused on connection {}: {}", room, self.id, e);
+                self.send(ctx, "error", serde_json::json!({"room": room, "message": e.to_string()}));
+            }
+        }
+    }
+
+    fn handle_client_message(&self, msg: Result<ClientMessage, String>, ctx: &mut ws::WebsocketContext<Self>) {
+        match msg {
             Ok(client_msg) => {
                 match client_msg.message_type.as_str() {
                     "subscribe" => {
@@ -69,6 +111,38 @@ impl WebSocketActor {
                             }.into_actor(self));
                         }
                     }
+                    "join_room" => {
+                        match serde_json::from_value::<JoinRoomMessage>(client_msg.data) {
+                            Ok(join_msg) => {
+                                let manager = self.manager.clone();
+                                let connection_id = self.id;
+                                let room = join_msg.room.clone();
+
+                                ctx.spawn(async move {
+                                    manager.join_room(&connection_id, &join_msg.room).await
+                                }.into_actor(self).map(move |result, act, ctx| {
+                                    act.send_room_result(ctx, "room_joined", &room, result);
+                                }));
+                            }
+                            Err(e) => self.send(ctx, "error", serde_json::json!({"message": format!("Invalid join_room message: {}", e)})),
+                        }
+                    }
+                    "leave_room" => {
+                        match serde_json::from_value::<LeaveRoomMessage>(client_msg.data) {
+                            Ok(leave_msg) => {
+                                let manager = self.manager.clone();
+                                let connection_id = self.id;
+                                let room = leave_msg.room.clone();
+
+                                ctx.spawn(async move {
+                                    manager.leave_room(&connection_id, &leave_msg.room).await
+                                }.into_actor(self).map(move |result, act, ctx| {
+                                    act.send_room_result(ctx, "room_left", &room, result);
+                                }));
+                            }
+                            Err(e) => self.send(ctx, "error", serde_json::json!({"message": format!("Invalid leave_room message: {}", e)})),
+                        }
+                    }
                     "ping" => {
                         let manager = self.manager.clone();
                         let connection_id = self.id;
@@ -78,15 +152,7 @@ impl WebSocketActor {
                         }.into_actor(self));
                         
                         // Send pong response
-                        let pong_msg = WebSocketMessage {
-                            message_type: "pong".to_string(),
-                            data: serde_json::json!({"timestamp": chrono::Utc::now().timestamp()}),
-                            timestamp: chrono::Utc::now(),
-                        };
-                        
-                        if let Ok(json) = serde_json::to_string(&pong_msg) {
-                            ctx.text(json);
-                        }
+                        self.send(ctx, "pong", serde_json::json!({"timestamp": chrono::Utc::now().timestamp()}));
                     }
                     _ => {
                         warn!("Unknown message type: {}", client_msg.message_type);
@@ -109,12 +175,24 @@ impl Actor for WebSocketActor {
         let manager = self.manager.clone();
         let connection_id = self.id;
         let addr = ctx.address().recipient();
+        let closer = ctx.address().recipient();
+        let counters = self.counters.clone();
         
         ctx.spawn(async move {
-            manager.add_connection(connection_id, addr, None, None).await;
+            manager.add_connection(connection_id, addr, closer, counters, None, None).await;
         }.into_actor(self));
-        
-        info!("WebSocket connection started: {}", self.id);
+
+        // Versioned clients learn what was agreed; legacy clients never expected this message
+        if self.protocol.version > realtime_protocol::LEGACY_PROTOCOL_VERSION {
+            self.send(ctx, "connected", serde_json::json!({
+                "connection_id": self.id,
+                "version": self.protocol.version,
+                "encoding": self.protocol.encoding,
+                "supported_versions": SUPPORTED_PROTOCOL_VERSIONS,
+            }));
+        }
+
+        info!("WebSocket connection started: {} (protocol v{}, {:?})", self.id, self.protocol.version, self.protocol.encoding);
     }
 
     fn stopped(&mut self, _ctx: &mut Self::Context) {
@@ -141,10 +219,13 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WebSocketActor {
             }
             Ok(ws::Message::Text(text)) => {
                 self.hb = Instant::now();
-                self.handle_client_message(&text, ctx);
+                self.manager.record_received(&self.counters);
+                self.handle_client_message(realtime_protocol::decode_text(&text), ctx);
             }
-            Ok(ws::Message::Binary(_)) => {
-                warn!("Binary messages not supported");
+            Ok(ws::Message::Binary(bytes)) => {
+                self.hb = Instant::now();
+                self.manager.record_received(&self.counters);
+                self.handle_client_message(realtime_protocol::decode_binary(&bytes), ctx);
             }
             Ok(ws::Message::Close(reason)) => {
                 info!("WebSocket connection closed: {:?}", reason);
@@ -159,23 +240,52 @@ impl Handler<WebSocketMessage> for WebSocketActor {
     type Result = ();
 
     fn handle(&mut self, msg: WebSocketMessage, ctx: &mut Self::Context) {
-        if let Ok(json) = serde_json::to_string(&msg) {
-            ctx.text(json);
-        }
+        self.write(ctx, &msg);
+    }
+}
+
+impl Handler<Disconnect> for WebSocketActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: Disconnect, ctx: &mut Self::Context) {
+        info!("Closing WebSocket connection {}: {}", self.id, msg.reason);
+        ctx.close(Some(ws::CloseReason {
+            code: ws::CloseCode::Policy,
+            description: Some(msg.reason),
+        }));
+        ctx.stop();
     }
 }
 
-/// WebSocket endpoint handler
+/// WebSocket endpoint handler; the protocol version and encoding are chosen with query parameters
+#[utoipa::path(
+    get,
+    path = "/api/v1/ws",
+    tag = "websocket",
+    params(ProtocolQuery),
+    responses(
+        (status = 101, description = "Switching protocols to a WebSocket connection"),
+        (status = 400, description = "Unsupported protocol version or encoding")
+    )
+)]
 pub async fn websocket_handler(
     req: HttpRequest,
     stream: web::Payload,
+    query: web::Query<ProtocolQuery>,
     manager: web::Data<Arc<RealtimeService>>,
 ) -> Result<HttpResponse, Error> {
-    let actor = WebSocketActor::new(manager.get_ref().clone());
+    let protocol = Protocol::negotiate(&query)?;
+    let actor = WebSocketActor::new(manager.get_ref().clone(), protocol);
     ws::start(actor, &req, stream)
 }
 
 /// Get WebSocket connection statistics
+#[utoipa::path(
+    get,
+    path = "/api/v1/ws/stats",
+    tag = "websocket",
+    responses((status = 200, description = "Success", body = crate::services::realtime_service::ConnectionStats))
+)]
 pub async fn websocket_stats(
     manager: web::Data<Arc<RealtimeService>>,
 ) -> Result<HttpResponse, Error> {
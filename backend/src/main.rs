@@ -1,3 +1,4 @@
+use actix_cors::Cors;
 use actix_web::{web, App, HttpServer, Result};
 use tracing::{info, Level};
 use tracing_subscriber;
@@ -10,77 +11,354 @@ mod blockchain;
 mod handlers;
 mod middleware;
 mod models;
+mod openapi;
+mod repositories;
 mod services;
 mod utils;
 
 use config::AppConfig;
-use database::Database;
+use database::{Database, StartupRetry};
 use error::AppError;
-use middleware::auth::AuthMiddleware;
+use middleware::api_usage::ApiUsageMiddleware;
+use middleware::auth::{AuthMiddleware, OptionalAuthMiddleware, RequirePermission, SecureAuthMiddleware};
+use middleware::feature_flags::FeatureFlagMiddleware;
+use middleware::geo_restriction::GeoRestrictionMiddleware;
+use middleware::idempotency::IdempotencyMiddleware;
+use middleware::metrics::MetricsMiddleware;
+use middleware::rate_limit::{RateLimitMiddleware, RateLimiter};
+use services::permission_service::{
+    API_PLANS_MANAGE, CATALOG_MANAGE, CREDITS_ISSUE, PAYMENTS_REFUND, PAYMENTS_VIEW,
+    PERMISSIONS_MANAGE, RAFFLES_FORCE_COMPLETE, RAFFLES_REPAIR, SETTINGS_MANAGE, USERS_BAN,
+    USERS_CHANGE_ROLE,
+};
 use utils::jwt::JwtService;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 #[actix_web::main]
 async fn main() -> Result<(), AppError> {
-    // Initialize tracing
+    // Load configuration
+    let config = AppConfig::from_env()?;
+
+    // Initialize tracing at the profile's level
     tracing_subscriber::fmt()
-        .with_max_level(Level::INFO)
+        .with_max_level(config.log_level().parse::<Level>().unwrap_or(Level::INFO))
         .init();
 
-    // Load configuration
-    let config = AppConfig::from_env()?;
-    info!("Starting Raffle Platform Backend on {}:{}", config.host, config.port);
+    info!(
+        "Starting Raffle Platform Backend on {}:{} ({:?})",
+        config.host, config.port, config.environment
+    );
 
-    // Initialize database
-    let database = Database::new(&config.database_url).await?;
+    // Initialize database, waiting for it to come up
+    let startup_retry = StartupRetry::from_config(&config);
+    let mut database = Database::connect_with_retry(&config.database_url, startup_retry).await?;
+    if let Some(read_url) = &config.database_read_url {
+        database = database.with_read_replica(read_url).await;
+    }
     
     // Run migrations
     database.migrate().await?;
 
+    // One-off commands run against the migrated database instead of starting the server
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some(blockchain::backfill::COMMAND) {
+        return backfill_events(&args[2..], &config, database.pool().clone()).await;
+    }
+
+    // Redis is optional; without it the backend starts degraded
+    let readiness_service = services::ReadinessService::new(database.pool().clone(), &config.redis_url);
+    readiness_service.wait_for_redis(startup_retry).await;
+    readiness_service.start_background_tasks().await;
+
     // Initialize JWT service
     let jwt_service = Arc::new(JwtService::new()?);
 
     // Initialize services
-    let auth_service = services::AuthService::new(database.pool().clone(), jwt_service.clone());
-    let wallet_service = services::WalletService::new(database.pool().clone());
-    let credit_service = services::CreditService::new(database.pool().clone());
-    let item_service = services::ItemService::with_realtime(database.pool().clone(), realtime_service.clone());
+    let email_service = services::EmailService::from_config(database.pool().clone(), &config).await?;
+    email_service.start_background_tasks().await;
+    let wallet_service = services::WalletService::from_config(database.pool().clone(), &config)?;
+    wallet_service.start_background_tasks().await;
+    let auth_service = services::AuthService::new(database.pool().clone(), jwt_service.clone())
+        .with_email_service(email_service.clone())
+        .with_wallet_service(wallet_service.clone());
+    let permission_service = services::PermissionService::new(database.pool().clone());
+    let email_verification_service = services::EmailVerificationService::new(database.pool().clone())
+        .with_email_service(email_service.clone());
+    // Events reach connections on every replica over Redis pub/sub; without it only this one's
+    let mut realtime_service = services::RealtimeService::new(database.pool().clone());
+    match redis::Client::open(config.redis_url.as_str()) {
+        Ok(client) => realtime_service = realtime_service.with_relay(services::RealtimeRelay::new(client)),
+        Err(e) => tracing::warn!("Realtime relay disabled, invalid Redis URL: {}", e),
+    }
+    realtime_service.start_relay().await;
+    // Cached reads fall through to the database when the Redis URL is unusable
+    let mut tagged_cache = match redis::Client::open(config.redis_url.as_str()) {
+        Ok(client) => services::TaggedCache::new(client),
+        Err(e) => {
+            tracing::warn!("Read caching disabled, invalid Redis URL: {}", e);
+            services::TaggedCache::default()
+        }
+    };
+    if let Some(purge_url) = config.edge_cache_purge_url.clone() {
+        tagged_cache = tagged_cache.with_edge_purge(purge_url);
+    }
+    tagged_cache.start_background_tasks(&realtime_service).await;
+    let credit_service = services::CreditService::new(database.pool().clone())
+        .with_read_pool(database.read_pool().clone())
+        .with_realtime(realtime_service.clone())
+        .with_cache(tagged_cache.clone());
+    credit_service.start_background_tasks().await;
+    let media_service = services::MediaService::from_config(&config);
+    let fx_service = services::FxService::from_config(database.pool().clone(), &config);
+    let moderation_service = services::ModerationService::new(database.pool().clone())
+        .with_media_service(media_service.clone())
+        .with_cache(tagged_cache.clone());
+    moderation_service.start_background_tasks().await;
+    let item_service = services::ItemService::with_realtime(database.pool().clone(), realtime_service.clone())
+        .with_moderation_service(moderation_service.clone())
+        .with_media_service(media_service.clone())
+        .with_fx_service(fx_service.clone())
+        .with_read_pool(database.read_pool().clone())
+        .with_cache(tagged_cache.clone());
     let blockchain_service = services::BlockchainService::new(
         config.blockchain_rpc_url.clone(),
         config.blockchain_ws_url.clone(),
         config.contract_address.clone(),
-        config.deployer_private_key.clone(),
+        config.platform_signer(),
     ).await?;
-    let notification_service = services::NotificationService::new();
-    let realtime_service = services::RealtimeService::new(database.pool().clone());
+    blockchain_service.start_background_tasks().await;
+    let seller_webhook_service = services::SellerWebhookService::new(database.pool().clone());
+    seller_webhook_service.start_background_tasks().await;
+    let notification_channels = services::NotificationChannels::from_config(&config)?;
+    let notification_service = services::NotificationService::new()
+        .with_database(database.pool().clone())
+        .with_seller_webhooks(seller_webhook_service.clone())
+        .with_email_service(email_service.clone())
+        .with_channels(notification_channels);
+    let outbox_service = services::OutboxService::new(
+        database.pool().clone(),
+        realtime_service.clone(),
+        notification_service.clone(),
+        seller_webhook_service.clone(),
+    );
+    outbox_service.start_background_tasks().await;
+    let fee_service = services::FeeService::new(database.pool().clone());
+    let compliance_service = services::ComplianceService::from_config(database.pool().clone(), &config)
+        .with_cache(tagged_cache.clone());
+    let referral_service = services::ReferralService::new(database.pool().clone(), credit_service.clone());
     let raffle_service = services::RaffleService::new(
         database.pool().clone(),
         credit_service.clone(),
         blockchain_service.clone(),
         notification_service.clone(),
         realtime_service.clone(),
+        fee_service.clone(),
+        media_service.clone(),
+    )
+    .with_read_pool(database.read_pool().clone())
+    .with_fx_service(fx_service.clone())
+    .with_compliance_service(compliance_service.clone())
+    .with_cache(tagged_cache.clone());
+    raffle_service.start_background_tasks().await;
+    let sandbox_service = services::SandboxService::new(database.pool().clone(), notification_service.clone());
+    let api_billing_service = services::ApiBillingService::new(database.pool().clone());
+    api_billing_service.start_background_tasks().await;
+    let receipt_service = services::ReceiptService::new(database.pool().clone());
+    let reporting_service = services::ReportingService::new(database.pool().clone(), fee_service.clone())
+        .with_read_pool(database.read_pool().clone());
+    let ledger_service = services::LedgerService::new(database.pool().clone())
+        .with_read_pool(database.read_pool().clone());
+    ledger_service.start_background_tasks().await;
+    let analytics_service =
+        services::AnalyticsService::new(database.pool().clone()).with_read_pool(database.read_pool().clone());
+    let comment_service = services::CommentService::new(database.pool().clone(), realtime_service.clone());
+    let abuse_report_service = services::AbuseReportService::new(
+        database.pool().clone(),
+        raffle_service.clone(),
+        comment_service.clone(),
+        auth_service.clone(),
     );
+    let cleanup_service = services::CleanupService::new(database.pool().clone());
+    let account_deletion_service = services::AccountDeletionService::new(database.pool().clone());
+    account_deletion_service.start_background_tasks().await;
+    cleanup_service.start_background_tasks().await;
+    let tax_service = services::TaxService::from_config(database.pool().clone(), &config);
+    tax_service.start_background_tasks().await;
+    let shipping_service = services::ShippingService::new(database.pool().clone());
+    let fulfillment_service = services::FulfillmentService::new(
+        database.pool().clone(),
+        credit_service.clone(),
+        shipping_service.clone(),
+        tax_service.clone(),
+        notification_service.clone(),
+    )
+    .with_compliance_service(compliance_service.clone());
+    fx_service.start_background_tasks().await;
+    let seller_onboarding_service = services::SellerOnboardingService::new(database.pool().clone())
+        .with_cache(tagged_cache.clone());
+    let search_service = services::SearchService::new(database.read_pool().clone(), media_service.clone())
+        .with_cache(tagged_cache.clone());
+    let geo_velocity_service = services::GeoVelocityService::from_config(database.pool().clone(), &config);
+    let geo_compliance_service = services::GeoComplianceService::from_config(database.pool().clone(), &config);
+    let email_domain_service = services::EmailDomainService::from_config(database.pool().clone(), &config);
+    email_domain_service.start_background_tasks().await;
     let payment_service = services::PaymentService::new(
         config.stripe_secret_key.clone(),
         config.stripe_webhook_secret.clone(),
         database.pool().clone(),
         credit_service.clone(),
+    )
+    .with_fx_service(fx_service.clone());
+    let webhook_service = services::WebhookService::new(
+        database.pool().clone(),
+        payment_service.clone(),
+        blockchain_service.clone(),
+    )
+    .with_compliance_service(compliance_service.clone());
+    webhook_service.start_background_tasks().await;
+    let data_fix_service = services::DataFixService::new(database.pool().clone(), blockchain_service.clone());
+    let raffle_runbook_service = services::RaffleRunbookService::new(
+        database.pool().clone(),
+        blockchain_service.clone(),
+        data_fix_service.clone(),
+        raffle_service.clone(),
     );
+    let chain_reconciliation_service =
+        services::ChainReconciliationService::new(database.pool().clone(), blockchain_service.clone());
+    chain_reconciliation_service.start_background_tasks().await;
+    let draw_scheduler_service =
+        services::DrawSchedulerService::new(database.pool().clone(), blockchain_service.clone());
+    draw_scheduler_service.start_background_tasks().await;
+    let external_wallet_service =
+        services::ExternalWalletService::new(database.pool().clone(), blockchain_service.clone());
+    let waitlist_service = services::WaitlistService::new(database.pool().clone());
+    waitlist_service.start_background_tasks().await;
+    let favorites_service = services::FavoritesService::new(database.pool().clone());
+    favorites_service.start_background_tasks().await;
+    let payment_search_service = services::PaymentSearchService::new(database.read_pool().clone());
+    let tag_service = services::TagService::new(database.pool().clone());
+    let category_service = services::CategoryService::new(database.pool().clone());
+    let listing_snapshot_service = services::ListingSnapshotService::new(database.pool().clone());
+    let promotion_service = services::PromotionService::new(database.pool().clone(), realtime_service.clone());
+    promotion_service.start_background_tasks().await;
+    let network_config = blockchain_service.client().network_config().clone();
+    let pending_actions_service = services::PendingActionsService::new(database.pool().clone(), realtime_service.clone())
+        .with_chain_timing(network_config.block_time, network_config.confirmations);
+    pending_actions_service.start_background_tasks().await;
+    let notification_inbox_service = services::NotificationInboxService::new(database.pool().clone())
+        .with_realtime(realtime_service.clone());
+    notification_inbox_service.start_background_tasks().await;
+    let verification_service = services::VerificationService::new(database.pool().clone())
+        .with_contract_address(config.contract_address.clone());
+    let backup_verification_service = services::BackupVerificationService::from_config(database.pool().clone(), &config);
+    backup_verification_service.start_background_tasks().await;
+    // Shared across workers; budgets are kept in Redis so they hold across instances
+    let rate_limiter = RateLimiter::from_config(database.pool().clone(), jwt_service.clone(), &config);
+    // Settings changed by an admin apply on every instance without a restart
+    let settings_service = services::SettingsService::new(database.pool().clone());
+    settings_service.start_background_tasks().await;
+    rate_limiter.watch_settings(&settings_service);
+    let feature_flag_service = services::FeatureFlagService::new(database.pool().clone());
+
+    // Metrics; the cache is only reported when the Redis URL is usable
+    let mut metrics_service = services::MetricsService::new(database.clone())?
+        .with_blockchain_service(blockchain_service.clone())
+        .with_tagged_cache(tagged_cache.clone());
+    match redis::Client::open(config.redis_url.as_str()) {
+        Ok(client) => {
+            let cache_service = services::CacheService::new(client, services::cache_service::CacheConfig::default());
+            metrics_service = metrics_service.with_cache_service(cache_service);
+        }
+        Err(e) => tracing::warn!("Cache metrics disabled, invalid Redis URL: {}", e),
+    }
+
+    let openapi = openapi::ApiDoc::openapi();
+    let app_config = config.clone();
+    let cors_allowed_origins = config.cors_allowed_origins();
 
     // Start HTTP server
     HttpServer::new(move || {
         App::new()
+            .wrap(build_cors(&cors_allowed_origins))
+            .wrap(MetricsMiddleware::new(metrics_service.clone()))
+            .app_data(web::Data::new(app_config.clone()))
             .app_data(web::Data::new(database.clone()))
             .app_data(web::Data::new(jwt_service.clone()))
             .app_data(web::Data::new(auth_service.clone()))
+            .app_data(web::Data::new(permission_service.clone()))
+            .app_data(web::Data::new(email_verification_service.clone()))
+            .app_data(web::Data::new(email_service.clone()))
             .app_data(web::Data::new(wallet_service.clone()))
             .app_data(web::Data::new(credit_service.clone()))
             .app_data(web::Data::new(item_service.clone()))
             .app_data(web::Data::new(raffle_service.clone()))
+            .app_data(web::Data::new(notification_service.clone()))
             .app_data(web::Data::new(payment_service.clone()))
+            .app_data(web::Data::new(receipt_service.clone()))
+            .app_data(web::Data::new(reporting_service.clone()))
+            .app_data(web::Data::new(ledger_service.clone()))
+            .app_data(web::Data::new(cleanup_service.clone()))
+            .app_data(web::Data::new(account_deletion_service.clone()))
+            .app_data(web::Data::new(fee_service.clone()))
+            .app_data(web::Data::new(analytics_service.clone()))
+            .app_data(web::Data::new(comment_service.clone()))
+            .app_data(web::Data::new(abuse_report_service.clone()))
+            .app_data(web::Data::new(moderation_service.clone()))
+            .app_data(web::Data::new(tax_service.clone()))
+            .app_data(web::Data::new(webhook_service.clone()))
+            .app_data(web::Data::new(fx_service.clone()))
+            .app_data(web::Data::new(shipping_service.clone()))
+            .app_data(web::Data::new(fulfillment_service.clone()))
+            .app_data(web::Data::new(compliance_service.clone()))
+            .app_data(web::Data::new(seller_onboarding_service.clone()))
+            .app_data(web::Data::new(search_service.clone()))
+            .app_data(web::Data::new(referral_service.clone()))
+            .app_data(web::Data::new(geo_velocity_service.clone()))
+            .app_data(web::Data::new(geo_compliance_service.clone()))
+            .app_data(web::Data::new(email_domain_service.clone()))
+            .app_data(web::Data::new(seller_webhook_service.clone()))
+            .app_data(web::Data::new(sandbox_service.clone()))
+            .app_data(web::Data::new(api_billing_service.clone()))
+            .app_data(web::Data::new(data_fix_service.clone()))
+            .app_data(web::Data::new(raffle_runbook_service.clone()))
+            .app_data(web::Data::new(chain_reconciliation_service.clone()))
+            .app_data(web::Data::new(draw_scheduler_service.clone()))
+            .app_data(web::Data::new(external_wallet_service.clone()))
+            .app_data(web::Data::new(waitlist_service.clone()))
+            .app_data(web::Data::new(favorites_service.clone()))
+            .app_data(web::Data::new(blockchain_service.clone()))
+            .app_data(web::Data::new(payment_search_service.clone()))
+            .app_data(web::Data::new(pending_actions_service.clone()))
+            .app_data(web::Data::new(notification_inbox_service.clone()))
+            .app_data(web::Data::new(promotion_service.clone()))
+            .app_data(web::Data::new(tag_service.clone()))
+            .app_data(web::Data::new(category_service.clone()))
+            .app_data(web::Data::new(listing_snapshot_service.clone()))
+            .app_data(web::Data::new(verification_service.clone()))
+            .app_data(web::Data::new(readiness_service.clone()))
+            .app_data(web::Data::new(backup_verification_service.clone()))
+            .app_data(web::Data::new(metrics_service.clone()))
+            .app_data(web::Data::new(rate_limiter.clone()))
+            .app_data(web::Data::new(settings_service.clone()))
+            .app_data(web::Data::new(feature_flag_service.clone()))
             .app_data(web::Data::new(std::sync::Arc::new(realtime_service.clone())))
+            // Probed by the load balancer outside the versioned API
+            .service(handlers::health::readiness_check)
+            .service(handlers::health::metrics)
+            .route(
+                "/.well-known/apple-developer-merchantid-domain-association",
+                web::get().to(handlers::payments::apple_pay_domain_association),
+            )
+            // Generated spec and Swagger UI, see openapi.rs
+            .service(SwaggerUi::new("/api/docs/{_:.*}").url("/api/docs/openapi.json", openapi.clone()))
             .service(
                 web::scope("/api/v1")
+                    .wrap(FeatureFlagMiddleware::new(feature_flag_service.clone(), jwt_service.clone()))
+                    .wrap(GeoRestrictionMiddleware::new(geo_compliance_service.clone()))
+                    .wrap(RateLimitMiddleware::new(rate_limiter.clone()))
                     .service(handlers::health::health_check)
+                    .service(handlers::health::readiness_check)
                     .service(
                         web::scope("/auth")
                             .service(handlers::auth::register)
@@ -89,6 +367,7 @@ async fn main() -> Result<(), AppError> {
                             .service(handlers::auth::forgot_password)
                             .service(handlers::auth::reset_password)
                             .service(handlers::auth::verify_email)
+                            .service(handlers::auth::resend_verification)
                             // Protected auth endpoints
                             .service(
                                 web::scope("")
@@ -97,23 +376,38 @@ async fn main() -> Result<(), AppError> {
                                     .service(handlers::auth::get_current_user)
                                     .service(handlers::auth::update_current_user)
                                     .service(handlers::auth::change_password)
+                                    .service(handlers::auth::change_email)
+                                    .service(handlers::auth::complete_step_up)
+                                    .service(handlers::auth::delete_current_user)
                             )
                     )
                     .service(
                         web::scope("/wallet")
-                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            .wrap(
+                                SecureAuthMiddleware::new(jwt_service.clone(), Arc::new(database.pool().clone()))
+                                    .with_geo_velocity(geo_velocity_service.clone())
+                            )
                             .service(handlers::wallet::get_wallet_address)
                             .service(handlers::wallet::sign_message)
                             .service(handlers::wallet::verify_signature)
                             .service(handlers::wallet::export_private_key)
                             .service(handlers::wallet::import_private_key)
+                            .service(handlers::wallet::get_chain_addresses)
+                            .service(handlers::wallet::upgrade_wallet)
                             .service(handlers::wallet::rotate_wallet_encryption)
                             .service(handlers::wallet::get_wallet_balance)
+                            .service(handlers::wallet::create_external_wallet_challenge)
+                            .service(handlers::wallet::list_external_purchases)
+                            .service(handlers::wallet::renew_external_purchase_voucher)
+                            .service(handlers::wallet::list_external_wallets)
+                            .service(handlers::wallet::link_external_wallet)
+                            .service(handlers::wallet::unlink_external_wallet)
                     )
                     .service(
                         web::scope("/credits")
                             .wrap(AuthMiddleware::new(jwt_service.clone()))
                             .service(handlers::credits::get_credit_balance)
+                            .service(handlers::credits::reconcile_credit_balance)
                             .service(handlers::credits::get_credit_history)
                             .service(handlers::credits::get_expiring_credits)
                             .service(handlers::credits::redeem_credits)
@@ -132,9 +426,16 @@ async fn main() -> Result<(), AppError> {
                     )
                     .service(
                         web::scope("/payments")
-                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            .wrap(
+                                SecureAuthMiddleware::new(jwt_service.clone(), Arc::new(database.pool().clone()))
+                                    .with_geo_velocity(geo_velocity_service.clone())
+                            )
                             // User endpoints
-                            .route("/create-intent", web::post().to(handlers::payments::create_payment_intent))
+                            .service(
+                                web::resource("/create-intent")
+                                    .wrap(IdempotencyMiddleware::new(database.pool().clone()))
+                                    .route(web::post().to(handlers::payments::create_payment_intent))
+                            )
                             .route("/confirm-intent/{payment_intent_id}", web::post().to(handlers::payments::confirm_payment_intent))
                             .route("/intent-status/{payment_intent_id}", web::get().to(handlers::payments::get_payment_intent_status))
                             .route("/history", web::get().to(handlers::payments::get_payment_history))
@@ -147,8 +448,31 @@ async fn main() -> Result<(), AppError> {
                             
                             // Admin endpoints
                             .route("/admin/analytics", web::get().to(handlers::payments::get_payment_analytics))
-                            .route("/admin/user/{user_id}", web::get().to(handlers::payments::get_user_payment_details))
-                            .route("/admin/refund/{payment_id}", web::post().to(handlers::payments::process_refund))
+                            .service(
+                                web::resource("/admin/search")
+                                    .wrap(RequirePermission::new(PAYMENTS_VIEW))
+                                    .route(web::get().to(handlers::payments::search_payments))
+                            )
+                            .service(
+                                web::resource("/admin/search/export")
+                                    .wrap(RequirePermission::new(PAYMENTS_VIEW))
+                                    .route(web::get().to(handlers::payments::export_payment_search))
+                            )
+                            .service(
+                                web::resource("/admin/user/{user_id}")
+                                    .wrap(RequirePermission::new(PAYMENTS_VIEW))
+                                    .route(web::get().to(handlers::payments::get_user_payment_details))
+                            )
+                            .service(
+                                web::resource("/admin/refund/{payment_id}")
+                                    .wrap(RequirePermission::new(PAYMENTS_REFUND))
+                                    .route(web::post().to(handlers::payments::process_refund))
+                            )
+                            .service(
+                                web::resource("/admin/refunds/{payment_id}")
+                                    .wrap(RequirePermission::new(PAYMENTS_VIEW))
+                                    .route(web::get().to(handlers::payments::get_payment_refunds))
+                            )
                             
                             // Health check
                             .route("/health", web::get().to(handlers::payments::payment_service_health))
@@ -158,8 +482,13 @@ async fn main() -> Result<(), AppError> {
                             // Public endpoints
                             .route("", web::get().to(handlers::items::search_items))
                             .route("/popular", web::get().to(handlers::items::get_popular_items))
-                            .route("/categories", web::get().to(handlers::items::get_item_categories))
+                            .route("/categories", web::get().to(handlers::categories::list_categories))
+                            .route("/categories/{slug}", web::get().to(handlers::categories::get_category))
                             .route("/{item_id}", web::get().to(handlers::items::get_item))
+                            .route("/{item_id}/tags", web::get().to(handlers::tags::get_item_tags))
+                            .route("/{item_id}/category", web::get().to(handlers::categories::get_item_category))
+                            .route("/{item_id}/snapshot", web::get().to(handlers::listing_snapshots::get_item_snapshot))
+                            .route("/{item_id}/snapshots", web::get().to(handlers::listing_snapshots::get_item_snapshot_history))
                             
                             // Protected endpoints
                             .service(
@@ -172,6 +501,8 @@ async fn main() -> Result<(), AppError> {
                                     .route("/{item_id}", web::delete().to(handlers::items::delete_item))
                                     .route("/{item_id}/status", web::put().to(handlers::items::update_item_status))
                                     .route("/{item_id}/stock", web::put().to(handlers::items::update_item_stock))
+                                    .route("/{item_id}/tags", web::put().to(handlers::tags::set_item_tags))
+                                    .route("/{item_id}/category", web::put().to(handlers::categories::set_item_category))
                                     .route("/{item_id}/analytics", web::get().to(handlers::items::get_item_analytics))
                                     .route("/bulk-operation", web::post().to(handlers::items::bulk_operation))
                                     .route("/statistics", web::get().to(handlers::items::get_item_statistics))
@@ -186,36 +517,582 @@ async fn main() -> Result<(), AppError> {
                     )
                     .service(
                         web::scope("/raffles")
+                            // Signed-in visitors see prices in their saved display currency
+                            .wrap(OptionalAuthMiddleware::new(jwt_service.clone()))
                             // Public endpoints
                             .route("", web::get().to(handlers::raffles::search_raffles))
                             .route("/active", web::get().to(handlers::raffles::get_active_raffles))
                             .route("/featured", web::get().to(handlers::raffles::get_featured_raffles))
+                            // Seller only; registered ahead of /{raffle_id} so the path isn't read as an id
+                            .route("/policy", web::get().to(handlers::raffles::get_raffle_policy))
+                            .route("/receipts/verify/{verification_code}", web::get().to(handlers::raffles::verify_receipt))
                             .route("/{raffle_id}", web::get().to(handlers::raffles::get_raffle))
                             .route("/{raffle_id}/grid", web::get().to(handlers::raffles::get_grid_state))
+                            .route("/{raffle_id}/grid/changes", web::get().to(handlers::raffles::get_grid_changes))
+                            .route("/{raffle_id}/odds", web::get().to(handlers::raffles::get_raffle_odds))
                             .route("/{raffle_id}/winners", web::get().to(handlers::raffles::get_raffle_winners))
+                            .route("/{raffle_id}/tags", web::get().to(handlers::tags::get_raffle_tags))
+                            .route("/{raffle_id}/snapshot", web::get().to(handlers::listing_snapshots::get_raffle_snapshot))
+                            .route("/{raffle_id}/snapshots", web::get().to(handlers::listing_snapshots::get_raffle_snapshot_history))
+                            .route("/{raffle_id}/comments", web::get().to(handlers::comments::get_raffle_comments))
                             
                             // Protected endpoints
                             .service(
                                 web::scope("")
                                     .wrap(AuthMiddleware::new(jwt_service.clone()))
                                     // User endpoints
-                                    .route("/{raffle_id}/buy-boxes", web::post().to(handlers::raffles::buy_boxes))
+                                    .service(
+                                        web::resource("/{raffle_id}/buy-boxes")
+                                            .wrap(IdempotencyMiddleware::new(database.pool().clone()))
+                                            .route(web::post().to(handlers::raffles::buy_boxes))
+                                    )
                                     .route("/{raffle_id}/my-purchases", web::get().to(handlers::raffles::get_user_purchases))
+                                    .route("/{raffle_id}/waitlist", web::post().to(handlers::raffles::join_waitlist))
+                                    .route("/{raffle_id}/waitlist", web::get().to(handlers::raffles::get_waitlist_position))
+                                    .route("/{raffle_id}/waitlist", web::delete().to(handlers::raffles::leave_waitlist))
+                                    .route("/{raffle_id}/purchases/{purchase_id}/receipt", web::get().to(handlers::raffles::get_purchase_receipt))
+                                    .route("/{raffle_id}/purchases/{purchase_id}/snapshots", web::get().to(handlers::listing_snapshots::get_purchase_snapshots))
+                                    .route("/{raffle_id}/prize-choice", web::get().to(handlers::raffles::get_prize_choices))
+                                    .route("/{raffle_id}/prize-choice", web::post().to(handlers::raffles::choose_prize))
+                                    .route("/{raffle_id}/comments", web::post().to(handlers::comments::post_comment))
                                     .route("/my-history", web::get().to(handlers::raffles::get_user_purchase_history))
                                     
                                     // Seller endpoints
                                     .route("", web::post().to(handlers::raffles::create_raffle))
+                                    .route("/fee-preview", web::post().to(handlers::raffles::preview_raffle_fees))
                                     .route("/{raffle_id}/cancel", web::post().to(handlers::raffles::cancel_raffle))
                                     .route("/statistics", web::get().to(handlers::raffles::get_raffle_statistics))
                                     
                                     // Admin endpoints
                                     .route("/admin/all", web::get().to(handlers::raffles::get_all_raffles_admin))
-                                    .route("/admin/{raffle_id}/force-complete", web::post().to(handlers::raffles::force_complete_raffle))
+                                    .route("/admin/{raffle_id}/tags", web::put().to(handlers::raffles::set_raffle_tags))
+                                    .service(
+                                        web::resource("/admin/{raffle_id}/force-complete")
+                                            .wrap(RequirePermission::new(RAFFLES_FORCE_COMPLETE))
+                                            .route(web::post().to(handlers::raffles::force_complete_raffle))
+                                    )
                                     
                                     // Health check
                                     .route("/health", web::get().to(handlers::raffles::raffle_service_health))
                             )
                     )
+                    .service(
+                        web::scope("/comments")
+                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            .route("/{comment_id}", web::delete().to(handlers::comments::delete_comment))
+                            .route("/{comment_id}/highlight", web::post().to(handlers::comments::highlight_answer))
+                            .route("/{comment_id}/flag", web::post().to(handlers::comments::flag_comment))
+                    )
+                    .service(
+                        web::scope("/analytics")
+                            // Public endpoints (guest clients)
+                            .route("/session", web::post().to(handlers::analytics::start_session))
+                            .route("/events", web::post().to(handlers::analytics::track_event))
+                            
+                            // Protected endpoints
+                            .service(
+                                web::scope("")
+                                    .wrap(AuthMiddleware::new(jwt_service.clone()))
+                                    .route("/raffles/{raffle_id}/funnel", web::get().to(handlers::analytics::get_raffle_funnel))
+                            )
+                    )
+                    .service(
+                        web::scope("/api-usage")
+                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            .route("", web::get().to(handlers::api_usage::get_usage))
+                            .route("/charges", web::get().to(handlers::api_usage::list_my_charges))
+                    )
+                    .service(
+                        web::scope("/webhook-endpoints")
+                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            // Seller endpoints
+                            .route("", web::get().to(handlers::seller_webhooks::list_endpoints))
+                            .route("", web::post().to(handlers::seller_webhooks::create_endpoint))
+                            .route("/{endpoint_id}", web::put().to(handlers::seller_webhooks::update_endpoint))
+                            .route("/{endpoint_id}", web::delete().to(handlers::seller_webhooks::delete_endpoint))
+                            .route("/{endpoint_id}/deliveries", web::get().to(handlers::seller_webhooks::list_endpoint_deliveries))
+                    )
+                    .route("/winners", web::get().to(handlers::raffles::get_winner_gallery))
+                    .route("/search", web::get().to(handlers::search::search))
+                    .service(
+                        web::scope("/tags")
+                            .wrap(OptionalAuthMiddleware::new(jwt_service.clone()))
+                            .route("", web::get().to(handlers::tags::list_tags))
+                            .route("/trending", web::get().to(handlers::tags::get_trending_tags))
+                            .route("/collections", web::get().to(handlers::tags::list_collections))
+                            .route("/collections/{slug}", web::get().to(handlers::tags::get_collection_page))
+                    )
+                    .route("/features", web::get().to(handlers::features::get_features))
+                    .service(
+                        web::scope("/promotions")
+                            .route("/active", web::get().to(handlers::promotions::get_active_promotions))
+                    )
+                    .service(
+                        web::scope("/currency")
+                            // Public endpoints
+                            .route("/rates", web::get().to(handlers::currency::get_rates))
+
+                            // Protected endpoints
+                            .service(
+                                web::scope("")
+                                    .wrap(AuthMiddleware::new(jwt_service.clone()))
+                                    .route("/preference", web::get().to(handlers::currency::get_display_currency))
+                                    .route("/preference", web::put().to(handlers::currency::set_display_currency))
+                            )
+                    )
+                    .service(
+                        web::scope("/shipping")
+                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            // Seller endpoints
+                            .route("/policy", web::get().to(handlers::shipping::get_shipping_policy))
+                            .route("/policy", web::put().to(handlers::shipping::update_shipping_policy))
+                            .route("/items/{item_id}", web::get().to(handlers::shipping::get_item_shipping))
+                            .route("/items/{item_id}", web::put().to(handlers::shipping::update_item_shipping))
+                            .route("/items/{item_id}", web::delete().to(handlers::shipping::delete_item_shipping))
+                            // User endpoints
+                            .route("/address", web::get().to(handlers::shipping::get_shipping_address))
+                            .route("/address", web::put().to(handlers::shipping::update_shipping_address))
+                            .route("/prizes/{raffle_id}", web::get().to(handlers::shipping::get_my_prize_shipping))
+                    )
+                    .service(
+                        web::scope("/fulfillments")
+                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            // Winner endpoints
+                            .route("/mine", web::get().to(handlers::fulfillments::get_my_fulfillments))
+                            .route("/raffles/{raffle_id}/details", web::put().to(handlers::fulfillments::confirm_shipping_details))
+                            .route("/{fulfillment_id}/receipt", web::post().to(handlers::fulfillments::confirm_receipt))
+                            .route("/{fulfillment_id}/disputes", web::post().to(handlers::fulfillments::open_dispute))
+                            // Seller endpoints
+                            .route("/selling", web::get().to(handlers::fulfillments::get_seller_fulfillments))
+                            .route("/{fulfillment_id}/ship", web::post().to(handlers::fulfillments::ship_prize))
+                            // Winner, seller or admin
+                            .route("/{fulfillment_id}", web::get().to(handlers::fulfillments::get_fulfillment))
+                    )
+                    .service(
+                        web::scope("/referrals")
+                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            .route("/me", web::get().to(handlers::referrals::get_my_referrals))
+                            .route("/attach", web::post().to(handlers::referrals::attach_referral))
+                    )
+                    .service(
+                        web::scope("/sellers")
+                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            .route("/onboarding", web::get().to(handlers::sellers::get_onboarding))
+                            .route("/onboarding/profile", web::put().to(handlers::sellers::update_profile))
+                            .route("/onboarding/payout", web::put().to(handlers::sellers::connect_payout))
+                            .route("/onboarding/subscription", web::put().to(handlers::sellers::select_subscription))
+                    )
+                    .service(
+                        web::scope("/tax")
+                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            .route("/forms", web::get().to(handlers::tax::get_my_tax_forms))
+                            .route("/forms/{form_id}/submit", web::post().to(handlers::tax::submit_tax_form))
+                    )
+                    .service(
+                        web::scope("/notifications")
+                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            .route("", web::get().to(handlers::notifications::list_notifications))
+                            .route("/unread-count", web::get().to(handlers::notifications::get_unread_count))
+                            .route("/read-all", web::post().to(handlers::notifications::mark_all_notifications_read))
+                            .route("/{notification_id}/read", web::post().to(handlers::notifications::mark_notification_read))
+                    )
+                    .service(
+                        web::scope("/users")
+                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            .route("/me/pending-actions", web::get().to(handlers::users::get_pending_actions))
+                            .route("/me/winner-display", web::get().to(handlers::users::get_winner_display))
+                            .route("/me/winner-display", web::put().to(handlers::users::update_winner_display))
+                            .route("/me/notification-preferences", web::get().to(handlers::users::get_notification_preferences))
+                            .route("/me/notification-preferences", web::put().to(handlers::users::update_notification_preferences))
+                            .route("/me/push-devices", web::get().to(handlers::users::list_push_devices))
+                            .route("/me/push-devices", web::post().to(handlers::users::register_push_device))
+                            .route("/me/push-devices/{device_id}", web::delete().to(handlers::users::remove_push_device))
+                            .route("/me/blocks", web::get().to(handlers::users::list_blocks))
+                            .route("/me/blocks", web::post().to(handlers::users::block_user))
+                            .route("/me/blocks/{user_id}", web::delete().to(handlers::users::unblock_user))
+                            .route("/me/favorites", web::get().to(handlers::users::list_favorites))
+                            .route("/me/favorites", web::post().to(handlers::users::add_favorite))
+                            .route("/me/favorites/raffles/{raffle_id}", web::delete().to(handlers::users::remove_favorite_raffle))
+                            .route("/me/favorites/items/{item_id}", web::delete().to(handlers::users::remove_favorite_item))
+                            .route("/me/saved-searches", web::get().to(handlers::users::list_saved_searches))
+                            .route("/me/saved-searches", web::post().to(handlers::users::create_saved_search))
+                            .route("/me/saved-searches/{search_id}", web::put().to(handlers::users::update_saved_search))
+                            .route("/me/saved-searches/{search_id}", web::delete().to(handlers::users::delete_saved_search))
+                            .route("/me/kyc", web::get().to(handlers::users::get_kyc_status))
+                            .route("/me/kyc", web::post().to(handlers::users::start_kyc_verification))
+                    )
+                    .service(
+                        web::scope("/reports")
+                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            // Admin endpoints
+                            .route("/accounting-export", web::get().to(handlers::reports::export_accounting))
+                            .route("/ledger", web::get().to(handlers::reports::export_ledger))
+                            .route("/ledger/summary", web::get().to(handlers::reports::get_ledger_summary))
+                            .route("/chart-of-accounts", web::get().to(handlers::reports::get_chart_of_accounts))
+                            .route("/chart-of-accounts/{transaction_type}", web::put().to(handlers::reports::update_account_mapping))
+                            // Seller endpoints
+                            .route("/seller-statement", web::get().to(handlers::reports::get_seller_statement))
+                            // Abuse reports
+                            .route("", web::post().to(handlers::abuse_reports::create_report))
+                            .route("/mine", web::get().to(handlers::abuse_reports::get_my_reports))
+                    )
+                    .service(
+                        web::scope("/admin")
+                            .wrap(AuthMiddleware::new(jwt_service.clone()))
+                            .route("/config", web::get().to(handlers::admin::get_effective_config))
+                            .route("/account-deletions", web::get().to(handlers::admin::list_account_deletions))
+                            .route("/account-deletions/{request_id}/cancel", web::post().to(handlers::admin::cancel_account_deletion))
+                            .route("/cleanup", web::get().to(handlers::admin::get_cleanup_status))
+                            .route("/cleanup/run", web::post().to(handlers::admin::run_cleanup))
+                            .route("/wallets/derivation", web::get().to(handlers::admin::get_wallet_derivation_report))
+                            .route("/wallets/chain-addresses/backfill", web::post().to(handlers::admin::backfill_wallet_chain_addresses))
+                            .route("/wallets/encryption", web::get().to(handlers::admin::get_wallet_encryption_status))
+                            .route("/wallets/encryption/rewrap", web::post().to(handlers::admin::rewrap_wallet_data_keys))
+                            .route("/notifications/fan-outs", web::get().to(handlers::admin::get_notification_fan_outs))
+                            .route("/raffle-grid-limits", web::get().to(handlers::admin::get_raffle_grid_limits))
+                            .route("/raffle-grid-limits", web::put().to(handlers::admin::update_raffle_grid_limits))
+                            .route("/raffle-policy", web::get().to(handlers::admin::get_raffle_policy))
+                            .route("/raffle-policy", web::put().to(handlers::admin::update_raffle_policy))
+                            .route("/raffle-policy/tiers/{subscription_id}", web::put().to(handlers::admin::set_tier_raffle_policy))
+                            .route("/raffle-policy/tiers/{subscription_id}", web::delete().to(handlers::admin::remove_tier_raffle_policy))
+                            .route("/geo-velocity-policy", web::get().to(handlers::admin::get_geo_velocity_policy))
+                            .route("/geo-velocity-policy", web::put().to(handlers::admin::update_geo_velocity_policy))
+                            .route("/login-lockouts", web::get().to(handlers::admin::list_login_lockouts))
+                            .route("/login-lockouts/{lockout_id}", web::delete().to(handlers::admin::clear_login_lockout))
+                            .route("/login-lockout-policy", web::get().to(handlers::admin::get_login_lockout_policy))
+                            .route("/login-lockout-policy", web::put().to(handlers::admin::update_login_lockout_policy))
+                            .route("/geo-restrictions", web::get().to(handlers::admin::get_geo_restrictions))
+                            .route("/geo-restrictions", web::put().to(handlers::admin::update_geo_restrictions))
+                            .route("/geo-restrictions/raffles/{raffle_id}", web::put().to(handlers::admin::set_raffle_geo_restrictions))
+                            .route("/referral-policy", web::get().to(handlers::referrals::get_referral_policy))
+                            .route("/referral-policy", web::put().to(handlers::referrals::update_referral_policy))
+                            .route("/referrals", web::get().to(handlers::referrals::list_referrals))
+                            .route("/referrals/stats", web::get().to(handlers::referrals::get_referral_stats))
+                            .route("/rate-limits", web::get().to(handlers::admin::get_rate_limits))
+                            .route("/rate-limits/{name}", web::put().to(handlers::admin::update_rate_limit))
+                            .route("/fee-schedules", web::get().to(handlers::fees::get_fee_schedules))
+                            .route("/fee-schedules", web::post().to(handlers::fees::create_fee_schedule))
+                            .route("/comments/moderation-queue", web::get().to(handlers::comments::get_moderation_queue))
+                            .route("/comments/{comment_id}/moderate", web::post().to(handlers::comments::moderate_comment))
+                            .route("/email-domains", web::get().to(handlers::admin::get_email_domain_policy))
+                            .route("/email-domains/check", web::get().to(handlers::admin::check_email_domain))
+                            .route("/email-domains/refresh", web::post().to(handlers::admin::refresh_disposable_domains))
+                            .route("/email-domains/{domain}", web::put().to(handlers::admin::set_email_domain_rule))
+                            .route("/email-domains/{domain}", web::delete().to(handlers::admin::delete_email_domain_rule))
+                            .route("/emails", web::get().to(handlers::admin::list_email_messages))
+                            .route("/email-suppressions", web::get().to(handlers::admin::list_email_suppressions))
+                            .route("/email-suppressions", web::post().to(handlers::admin::add_email_suppression))
+                            .route("/email-suppressions/{email}", web::delete().to(handlers::admin::remove_email_suppression))
+                            .route("/backups/verifications", web::get().to(handlers::admin::list_backup_verifications))
+                            .route("/backups/verifications", web::post().to(handlers::admin::start_backup_verification))
+                            .route("/backups/verifications/{run_id}", web::get().to(handlers::admin::get_backup_verification))
+                            .route("/websocket/connections", web::get().to(handlers::admin::list_websocket_connections))
+                            .route("/websocket/connections/{connection_id}", web::delete().to(handlers::admin::disconnect_websocket_connection))
+                            .route("/websocket/users/{user_id}/connections", web::delete().to(handlers::admin::disconnect_websocket_user))
+                            .route("/websocket/broadcast", web::post().to(handlers::admin::broadcast_websocket_message))
+                            .route("/abuse-reports", web::get().to(handlers::abuse_reports::get_triage_queue))
+                            .route("/abuse-reports/{report_id}/review", web::post().to(handlers::abuse_reports::start_review))
+                            .route("/abuse-reports/{report_id}/resolve", web::post().to(handlers::abuse_reports::resolve_report))
+                            .route("/abuse-reports/{report_id}/dismiss", web::post().to(handlers::abuse_reports::dismiss_report))
+                            .route("/moderation", web::get().to(handlers::moderation::get_moderation_queue))
+                            .route("/moderation/{item_id}", web::get().to(handlers::moderation::get_moderation_entry))
+                            .route("/moderation/{item_id}/approve", web::post().to(handlers::moderation::approve_item))
+                            .route("/moderation/{item_id}/reject", web::post().to(handlers::moderation::reject_item))
+                            .route("/tax/forms/review-queue", web::get().to(handlers::tax::get_tax_review_queue))
+                            .route("/tax/forms/{form_id}/review", web::post().to(handlers::tax::review_tax_form))
+                            .route("/tax/jurisdictions", web::get().to(handlers::tax::get_tax_jurisdictions))
+                            .route("/tax/jurisdictions/{country_code}", web::put().to(handlers::tax::update_tax_jurisdiction))
+                            .route("/tax/filings", web::get().to(handlers::tax::export_tax_filings))
+                            .route("/tax/fulfillment/{raffle_id}/{user_id}", web::get().to(handlers::tax::get_fulfillment_status))
+                            .route("/disputes", web::get().to(handlers::fulfillments::get_dispute_queue))
+                            .route("/disputes/{dispute_id}/resolve", web::post().to(handlers::fulfillments::resolve_dispute))
+                            .route("/sellers/{user_id}/kyc", web::post().to(handlers::sellers::set_kyc_status))
+                            .route("/webhooks/deliveries", web::get().to(handlers::webhooks::list_webhook_deliveries))
+                            .route("/webhooks/deliveries/{delivery_id}", web::get().to(handlers::webhooks::get_webhook_delivery))
+                            .route("/webhooks/deliveries/{delivery_id}/redeliver", web::post().to(handlers::webhooks::redeliver_webhook))
+                            .route("/webhooks/retry-policy", web::get().to(handlers::webhooks::get_webhook_retry_policy))
+                            .route("/webhooks/retry-policy", web::put().to(handlers::webhooks::update_webhook_retry_policy))
+                            .route("/api-keys", web::get().to(handlers::admin::list_api_keys))
+                            .route("/api-keys", web::post().to(handlers::admin::create_api_key))
+                            .route("/api-keys/{key_id}", web::delete().to(handlers::admin::revoke_api_key))
+                            .service(
+                                web::resource("/api-plans")
+                                    .wrap(RequirePermission::new(API_PLANS_MANAGE))
+                                    .route(web::get().to(handlers::api_usage::list_plans))
+                                    .route(web::post().to(handlers::api_usage::create_plan))
+                            )
+                            .service(
+                                web::resource("/api-plans/{plan_id}")
+                                    .wrap(RequirePermission::new(API_PLANS_MANAGE))
+                                    .route(web::put().to(handlers::api_usage::update_plan))
+                            )
+                            .service(
+                                web::resource("/sellers/{seller_id}/api-plan")
+                                    .wrap(RequirePermission::new(API_PLANS_MANAGE))
+                                    .route(web::put().to(handlers::api_usage::assign_plan))
+                            )
+                            .service(
+                                web::resource("/sellers/{seller_id}/api-usage")
+                                    .wrap(RequirePermission::new(API_PLANS_MANAGE))
+                                    .route(web::get().to(handlers::api_usage::get_seller_usage))
+                            )
+                            .service(
+                                web::resource("/api-usage/charges")
+                                    .wrap(RequirePermission::new(API_PLANS_MANAGE))
+                                    .route(web::get().to(handlers::api_usage::list_charges))
+                            )
+                            .service(
+                                web::resource("/api-usage/billing/run")
+                                    .wrap(RequirePermission::new(API_PLANS_MANAGE))
+                                    .route(web::post().to(handlers::api_usage::run_billing))
+                            )
+                            .route("/fx/refresh", web::post().to(handlers::currency::refresh_rates))
+                            .route("/seller-webhooks/deliveries", web::get().to(handlers::seller_webhooks::list_deliveries))
+                            .route("/seller-webhooks/deliveries/{delivery_id}", web::get().to(handlers::seller_webhooks::get_delivery))
+                            .route("/seller-webhooks/deliveries/{delivery_id}/redeliver", web::post().to(handlers::seller_webhooks::redeliver))
+                            .route("/platform-webhooks", web::get().to(handlers::seller_webhooks::list_platform_endpoints))
+                            .route("/platform-webhooks", web::post().to(handlers::seller_webhooks::create_platform_endpoint))
+                            .route("/platform-webhooks/{endpoint_id}", web::put().to(handlers::seller_webhooks::update_platform_endpoint))
+                            .route("/platform-webhooks/{endpoint_id}", web::delete().to(handlers::seller_webhooks::delete_platform_endpoint))
+                            .service(
+                                web::resource("/data-fixes/raffles/{raffle_id}/resync-from-chain")
+                                    .wrap(RequirePermission::new(RAFFLES_REPAIR))
+                                    .route(web::post().to(handlers::data_fixes::resync_raffle_from_chain))
+                            )
+                            .service(
+                                web::resource("/data-fixes/raffles/{raffle_id}/recount-boxes")
+                                    .wrap(RequirePermission::new(RAFFLES_REPAIR))
+                                    .route(web::post().to(handlers::data_fixes::recount_boxes_sold))
+                            )
+                            .service(
+                                web::resource("/data-fixes/raffles/{raffle_id}/detach-orphaned-purchases")
+                                    .wrap(RequirePermission::new(RAFFLES_REPAIR))
+                                    .route(web::post().to(handlers::data_fixes::detach_orphaned_purchases))
+                            )
+                            .service(
+                                web::resource("/data-fixes/users/{user_id}/rebuild-credit-balance")
+                                    .wrap(RequirePermission::new(CREDITS_ISSUE))
+                                    .route(web::post().to(handlers::data_fixes::rebuild_credit_balance))
+                            )
+                            .service(
+                                web::resource("/reconciliation/report")
+                                    .wrap(RequirePermission::new(RAFFLES_REPAIR))
+                                    .route(web::get().to(handlers::data_fixes::get_reconciliation_report))
+                            )
+                            .service(
+                                web::resource("/reconciliation/runs")
+                                    .wrap(RequirePermission::new(RAFFLES_REPAIR))
+                                    .route(web::post().to(handlers::data_fixes::start_reconciliation))
+                            )
+                            .service(
+                                web::resource("/ledger/accounts")
+                                    .wrap(RequirePermission::new(PAYMENTS_VIEW))
+                                    .route(web::get().to(handlers::ledger::get_account_balances))
+                            )
+                            .service(
+                                web::resource("/ledger/accounts/{code}/postings")
+                                    .wrap(RequirePermission::new(PAYMENTS_VIEW))
+                                    .route(web::get().to(handlers::ledger::get_account_postings))
+                            )
+                            .service(
+                                web::resource("/ledger/entries/{entry_id}")
+                                    .wrap(RequirePermission::new(PAYMENTS_VIEW))
+                                    .route(web::get().to(handlers::ledger::get_entry))
+                            )
+                            .service(
+                                web::resource("/ledger/invariants")
+                                    .wrap(RequirePermission::new(PAYMENTS_VIEW))
+                                    .route(web::get().to(handlers::ledger::get_latest_invariant_run))
+                            )
+                            .service(
+                                web::resource("/ledger/invariants/check")
+                                    .wrap(RequirePermission::new(PAYMENTS_VIEW))
+                                    .route(web::post().to(handlers::ledger::check_invariants))
+                            )
+                            .service(
+                                web::resource("/runbooks/draws")
+                                    .wrap(RequirePermission::new(RAFFLES_REPAIR))
+                                    .route(web::get().to(handlers::data_fixes::list_scheduled_draws))
+                            )
+                            .service(
+                                web::resource("/runbooks/draws/{raffle_id}/retry")
+                                    .wrap(RequirePermission::new(RAFFLES_REPAIR))
+                                    .route(web::post().to(handlers::data_fixes::retry_scheduled_draw))
+                            )
+                            .service(
+                                web::resource("/runbooks/raffles/{raffle_id}")
+                                    .wrap(RequirePermission::new(RAFFLES_REPAIR))
+                                    .route(web::get().to(handlers::data_fixes::diagnose_raffle))
+                            )
+                            .service(
+                                web::resource("/runbooks/raffles/{raffle_id}/rerequest-randomness")
+                                    .wrap(RequirePermission::new(RAFFLES_REPAIR))
+                                    .route(web::post().to(handlers::data_fixes::rerequest_randomness))
+                            )
+                            .service(
+                                web::resource("/runbooks/raffles/{raffle_id}/resync")
+                                    .wrap(RequirePermission::new(RAFFLES_REPAIR))
+                                    .route(web::post().to(handlers::data_fixes::resync_stuck_raffle))
+                            )
+                            .service(
+                                web::resource("/runbooks/raffles/{raffle_id}/force-complete")
+                                    .wrap(RequirePermission::new(RAFFLES_FORCE_COMPLETE))
+                                    .route(web::post().to(handlers::data_fixes::force_complete_stuck_raffle))
+                            )
+                            .route("/promotions", web::get().to(handlers::promotions::list_promotions))
+                            .route("/promotions", web::post().to(handlers::promotions::create_promotion))
+                            .route("/promotions/{promotion_id}/cancel", web::post().to(handlers::promotions::cancel_promotion))
+                            .route("/promotions/{promotion_id}/report", web::get().to(handlers::promotions::get_promotion_report))
+                            .service(
+                                web::resource("/tags")
+                                    .wrap(RequirePermission::new(CATALOG_MANAGE))
+                                    .route(web::post().to(handlers::tags::create_tag))
+                            )
+                            .service(
+                                web::resource("/tags/{slug}")
+                                    .wrap(RequirePermission::new(CATALOG_MANAGE))
+                                    .route(web::put().to(handlers::tags::update_tag))
+                                    .route(web::delete().to(handlers::tags::delete_tag))
+                            )
+                            .service(
+                                web::resource("/tags/{slug}/synonyms")
+                                    .wrap(RequirePermission::new(CATALOG_MANAGE))
+                                    .route(web::get().to(handlers::tags::list_synonyms))
+                                    .route(web::post().to(handlers::tags::add_synonym))
+                            )
+                            .service(
+                                web::resource("/tag-synonyms/{synonym}")
+                                    .wrap(RequirePermission::new(CATALOG_MANAGE))
+                                    .route(web::delete().to(handlers::tags::remove_synonym))
+                            )
+                            .service(
+                                web::resource("/tag-collections")
+                                    .wrap(RequirePermission::new(CATALOG_MANAGE))
+                                    .route(web::get().to(handlers::tags::list_all_collections))
+                                    .route(web::post().to(handlers::tags::create_collection))
+                            )
+                            .service(
+                                web::resource("/tag-collections/{collection_id}")
+                                    .wrap(RequirePermission::new(CATALOG_MANAGE))
+                                    .route(web::put().to(handlers::tags::update_collection))
+                                    .route(web::delete().to(handlers::tags::delete_collection))
+                            )
+                            .service(
+                                web::resource("/categories")
+                                    .wrap(RequirePermission::new(CATALOG_MANAGE))
+                                    .route(web::get().to(handlers::categories::list_all_categories))
+                                    .route(web::post().to(handlers::categories::create_category))
+                            )
+                            .service(
+                                web::resource("/categories/{category_id}")
+                                    .wrap(RequirePermission::new(CATALOG_MANAGE))
+                                    .route(web::put().to(handlers::categories::update_category))
+                                    .route(web::delete().to(handlers::categories::delete_category))
+                            )
+                            .service(
+                                web::resource("/category-attributes")
+                                    .wrap(RequirePermission::new(CATALOG_MANAGE))
+                                    .route(web::get().to(handlers::categories::list_attributes))
+                                    .route(web::post().to(handlers::categories::create_attribute))
+                            )
+                            .service(
+                                web::resource("/category-attributes/{attribute_id}")
+                                    .wrap(RequirePermission::new(CATALOG_MANAGE))
+                                    .route(web::put().to(handlers::categories::update_attribute))
+                                    .route(web::delete().to(handlers::categories::delete_attribute))
+                            )
+                            .route("/blockchain/dead-letters", web::get().to(handlers::admin::list_blockchain_dead_letters))
+                            .route("/blockchain/dead-letters/{dead_letter_id}/retry", web::post().to(handlers::admin::retry_blockchain_dead_letter))
+                            .route("/blockchain/nonces", web::get().to(handlers::admin::list_blockchain_nonces))
+                            .route("/blockchain/transactions/{tx_id}/cancel", web::post().to(handlers::admin::cancel_blockchain_transaction))
+                            .route("/blockchain/transactions/{tx_id}/speed-up", web::post().to(handlers::admin::speed_up_blockchain_transaction))
+                            .service(
+                                web::resource("/users/{user_id}/role")
+                                    .wrap(RequirePermission::new(USERS_CHANGE_ROLE))
+                                    .route(web::put().to(handlers::admin::update_user_role))
+                            )
+                            .service(
+                                web::resource("/users/{user_id}/ban")
+                                    .wrap(RequirePermission::new(USERS_BAN))
+                                    .route(web::post().to(handlers::admin::ban_user))
+                            )
+                            .service(
+                                web::resource("/users/{user_id}/unban")
+                                    .wrap(RequirePermission::new(USERS_BAN))
+                                    .route(web::post().to(handlers::admin::unban_user))
+                            )
+                            .service(
+                                web::resource("/permissions")
+                                    .wrap(RequirePermission::new(PERMISSIONS_MANAGE))
+                                    .route(web::get().to(handlers::admin::get_role_permissions))
+                            )
+                            .service(
+                                web::resource("/permissions/{role}")
+                                    .wrap(RequirePermission::new(PERMISSIONS_MANAGE))
+                                    .route(web::put().to(handlers::admin::set_role_permissions))
+                            )
+                            .service(
+                                web::resource("/settings")
+                                    .wrap(RequirePermission::new(SETTINGS_MANAGE))
+                                    .route(web::get().to(handlers::admin::list_settings))
+                            )
+                            .service(
+                                web::resource("/settings/{key}")
+                                    .wrap(RequirePermission::new(SETTINGS_MANAGE))
+                                    .route(web::get().to(handlers::admin::get_setting))
+                                    .route(web::put().to(handlers::admin::update_setting))
+                            )
+                            .service(
+                                web::resource("/feature-flags")
+                                    .wrap(RequirePermission::new(SETTINGS_MANAGE))
+                                    .route(web::get().to(handlers::admin::list_feature_flags))
+                                    .route(web::post().to(handlers::admin::create_feature_flag))
+                            )
+                            .service(
+                                web::resource("/feature-flags/{key}")
+                                    .wrap(RequirePermission::new(SETTINGS_MANAGE))
+                                    .route(web::get().to(handlers::admin::get_feature_flag))
+                                    .route(web::put().to(handlers::admin::update_feature_flag))
+                                    .route(web::delete().to(handlers::admin::delete_feature_flag))
+                            )
+                    )
+                    // Partner endpoints authenticate with an X-Api-Key header instead of a user token
+                    // and are metered against the key owner's API rate plan
+                    .service(
+                        web::scope("/partner/credit-holds")
+                            .wrap(ApiUsageMiddleware::new(database.pool().clone()))
+                            .route("", web::post().to(handlers::credit_holds::place_credit_hold))
+                            .route("/{hold_id}", web::get().to(handlers::credit_holds::get_credit_hold))
+                            .route("/{hold_id}/capture", web::post().to(handlers::credit_holds::capture_credit_hold))
+                            .route("/{hold_id}/release", web::post().to(handlers::credit_holds::release_credit_hold))
+                            .route("/{hold_id}/void", web::post().to(handlers::credit_holds::void_credit_hold))
+                    )
+                    // Public draw verification for third-party tools, limited per client IP
+                    .service(
+                        web::scope("/verify")
+                            .route("/{raffle_id}", web::get().to(handlers::verification::verify_raffle))
+                            .route("/{raffle_id}/replay", web::get().to(handlers::verification::replay_draw))
+                    )
+                    // Sandbox endpoints authenticate with a seller's test-mode API key
+                    .service(
+                        web::scope("/sandbox")
+                            .wrap(ApiUsageMiddleware::new(database.pool().clone()))
+                            .service(
+                                web::scope("/keys")
+                                    .wrap(AuthMiddleware::new(jwt_service.clone()))
+                                    .route("", web::get().to(handlers::sandbox::list_test_keys))
+                                    .route("", web::post().to(handlers::sandbox::create_test_key))
+                                    .route("/{key_id}", web::delete().to(handlers::sandbox::revoke_test_key))
+                            )
+                            .route("", web::delete().to(handlers::sandbox::purge))
+                            .route("/items", web::get().to(handlers::sandbox::list_items))
+                            .route("/items", web::post().to(handlers::sandbox::create_item))
+                            .route("/raffles", web::get().to(handlers::sandbox::list_raffles))
+                            .route("/raffles", web::post().to(handlers::sandbox::create_raffle))
+                            .route("/raffles/{raffle_id}", web::get().to(handlers::sandbox::get_raffle))
+                            .route("/raffles/{raffle_id}/purchases", web::get().to(handlers::sandbox::list_purchases))
+                            .route("/raffles/{raffle_id}/purchases", web::post().to(handlers::sandbox::simulate_purchase))
+                    )
                     // WebSocket endpoints (no auth required for connection, auth happens after connection)
                     .route("/ws", web::get().to(handlers::websocket::websocket_handler))
                     .route("/ws/stats", web::get().to(handlers::websocket::websocket_stats))
@@ -224,7 +1101,9 @@ async fn main() -> Result<(), AppError> {
                 web::scope("/webhooks")
                     .service(handlers::webhooks::stripe_webhook)
                     .service(handlers::webhooks::blockchain_webhook)
+                    .service(handlers::webhooks::identity_webhook)
                     .service(handlers::webhooks::notification_webhook)
+                    .service(handlers::webhooks::ses_email_webhook)
             )
             // Additional webhook endpoint for payments (no auth required)
             .route("/api/payments/webhook", web::post().to(handlers::payments::stripe_webhook))
@@ -233,4 +1112,68 @@ async fn main() -> Result<(), AppError> {
     .run()
     .await
     .map_err(AppError::from)
-}
\ No newline at end of file
+}
+
+/// Rebuild `blockchain_events` over a block range on the configured network
+async fn backfill_events(args: &[String], config: &AppConfig, pool: sqlx::PgPool) -> Result<(), AppError> {
+    use blockchain::backfill::{BackfillOptions, EventBackfill, USAGE};
+    use blockchain::events::EventProcessorConfig;
+
+    let options = BackfillOptions::parse(args)
+        .map_err(|e| AppError::Validation(format!("{}\n{}", e, USAGE)))?;
+
+    let blockchain_config = services::blockchain_service::load_blockchain_config()
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    let network_config = blockchain_config
+        .networks
+        .get(&blockchain_config.default_network)
+        .cloned()
+        .ok_or_else(|| {
+            AppError::Internal(format!("Network {} not found in configuration", blockchain_config.default_network))
+        })?;
+
+    let client = Arc::new(
+        blockchain::BlockchainClient::new(
+            network_config,
+            &config.platform_signer(),
+            Some(blockchain_config.retry_config.clone()),
+        )
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to create blockchain client: {}", e)))?,
+    );
+    let processor = blockchain::EventProcessor::new(
+        client.clone(),
+        pool.clone(),
+        Some(EventProcessorConfig {
+            max_blocks_per_query: options.blocks_per_query,
+            events_only: true,
+            ..Default::default()
+        }),
+    )
+    .await
+    .map_err(|e| AppError::Internal(format!("Failed to create event processor: {}", e)))?;
+
+    EventBackfill::new(client, processor, pool)
+        .run(&options)
+        .await
+        .map_err(|e| AppError::Internal(format!("Backfill failed: {}", e)))?;
+
+    Ok(())
+}
+
+/// CORS from the profile's allowed origins; `*` allows any origin
+fn build_cors(allowed_origins: &[String]) -> Cors {
+    let cors = Cors::default()
+        .allow_any_method()
+        .allow_any_header()
+        .supports_credentials()
+        .max_age(3600);
+
+    if allowed_origins.iter().any(|origin| origin == "*") {
+        return cors.allow_any_origin();
+    }
+
+    allowed_origins
+        .iter()
+        .fold(cors, |cors, origin| cors.allowed_origin(origin))
+}
@@ -0,0 +1,161 @@
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderMap, HeaderName, HeaderValue},
+    Error, HttpMessage, ResponseError,
+};
+use chrono::Utc;
+use futures_util::future::LocalBoxFuture;
+use sqlx::PgPool;
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+use tracing::{debug, warn};
+
+use crate::error::AppError;
+use crate::middleware::auth::ApiKeyClient;
+use crate::models::api_usage::{period_end, period_start, ApiRatePlan, QuotaOutcome, SellerApiUsage};
+use crate::models::partner_api_key::PartnerApiKey;
+
+pub const API_KEY_HEADER: &str = "X-Api-Key";
+pub const QUOTA_LIMIT_HEADER: &str = "x-api-quota-limit";
+pub const QUOTA_REMAINING_HEADER: &str = "x-api-quota-remaining";
+
+/// Meters partner API requests against the key owner's rate plan
+///
+/// Authenticates the `X-Api-Key` header once and hands the key to handlers through the
+/// request extensions. Every request is counted per key; production keys that belong to a
+/// seller are also counted against the seller's monthly quota, and refused with 429 once a
+/// hard-limited plan's quota is used up. Metering failures never block a request.
+pub struct ApiUsageMiddleware {
+    pool: PgPool,
+}
+
+impl ApiUsageMiddleware {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ApiUsageMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ApiUsageMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ApiUsageMiddlewareService {
+            service: Rc::new(service),
+            pool: self.pool.clone(),
+        }))
+    }
+}
+
+pub struct ApiUsageMiddlewareService<S> {
+    service: Rc<S>,
+    pool: PgPool,
+}
+
+impl<S, B> Service<ServiceRequest> for ApiUsageMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let pool = self.pool.clone();
+
+        Box::pin(async move {
+            // Routes that also take user tokens are left to their own authentication
+            let Some(raw_key) = req
+                .headers()
+                .get(API_KEY_HEADER)
+                .and_then(|h| h.to_str().ok())
+                .map(str::to_string)
+            else {
+                return Ok(service.call(req).await?.map_into_left_body());
+            };
+
+            let key = match PartnerApiKey::authenticate(&pool, &raw_key).await {
+                Ok(Some(key)) => key,
+                Ok(None) => {
+                    let response = AppError::Authentication("Invalid API key".to_string()).error_response();
+                    return Ok(req.into_response(response).map_into_right_body());
+                }
+                Err(e) => return Ok(req.into_response(e.error_response()).map_into_right_body()),
+            };
+
+            req.extensions_mut().insert(ApiKeyClient { key: key.clone() });
+
+            let now = Utc::now();
+            let period = period_start(now);
+
+            // Test-mode keys and platform keys are tracked but never billed
+            let seller_id = match key.seller_id {
+                Some(seller_id) if !key.is_test => seller_id,
+                _ => {
+                    if let Err(e) = SellerApiUsage::record_key_request(&pool, key.id, period).await {
+                        warn!("Failed to meter API key {}: {}", key.key_prefix, e);
+                    }
+                    return Ok(service.call(req).await?.map_into_left_body());
+                }
+            };
+
+            let metered = async {
+                let used = SellerApiUsage::record_request(&pool, key.id, seller_id, period).await?;
+                let plan = ApiRatePlan::for_seller(&pool, seller_id).await?;
+                Ok::<_, AppError>((used, plan))
+            };
+
+            let (used, plan) = match metered.await {
+                Ok(metered) => metered,
+                Err(e) => {
+                    warn!("Failed to meter API key {}, allowing request: {}", key.key_prefix, e);
+                    return Ok(service.call(req).await?.map_into_left_body());
+                }
+            };
+
+            if plan.check_requests(used) == QuotaOutcome::Exceeded {
+                debug!("Seller {} is over the {} plan quota", seller_id, plan.name);
+
+                let mut response = AppError::RateLimited {
+                    message: format!(
+                        "Monthly API quota of {} requests on the {} plan is used up",
+                        plan.included_requests, plan.name
+                    ),
+                    retry_after_seconds: (period_end(period) - now).num_seconds().max(1),
+                }
+                .error_response();
+                insert_quota_headers(response.headers_mut(), &plan, used);
+
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+
+            let mut res = service.call(req).await?;
+            insert_quota_headers(res.headers_mut(), &plan, used);
+
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+fn insert_quota_headers(headers: &mut HeaderMap, plan: &ApiRatePlan, used: i64) {
+    let remaining = (plan.included_requests - used).max(0);
+    for (name, value) in [(QUOTA_LIMIT_HEADER, plan.included_requests), (QUOTA_REMAINING_HEADER, remaining)] {
+        headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+    }
+}
@@ -5,11 +5,17 @@ use actix_web::{
 use futures_util::future::LocalBoxFuture;
 use std::{
     future::{ready, Ready},
+    net::{IpAddr, SocketAddr},
     rc::Rc,
+    sync::Arc,
 };
 
+use crate::database::Database;
 use crate::error::AppError;
+use crate::models::partner_api_key::{PartnerApiKey, SCOPE_SANDBOX};
 use crate::models::User;
+use crate::services::geo_velocity_service::GeoVelocityService;
+use crate::services::permission_service::PermissionService;
 use crate::utils::jwt::{Claims, JwtService};
 use raffle_platform_shared::UserRole;
 
@@ -71,6 +77,62 @@ impl actix_web::FromRequest for AuthenticatedUser {
     }
 }
 
+/// Partner integration authenticated by the `X-Api-Key` header
+#[derive(Debug, Clone)]
+pub struct ApiKeyClient {
+    pub key: PartnerApiKey,
+}
+
+impl ApiKeyClient {
+    /// Fail unless the key was granted the scope
+    pub fn require_scope(&self, scope: &str) -> Result<(), AppError> {
+        if self.key.has_scope(scope) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!("API key is missing the {} scope", scope)))
+        }
+    }
+
+    /// Fail unless this is a test-mode key; returns the seller whose sandbox it reaches
+    pub fn require_sandbox(&self) -> Result<uuid::Uuid, AppError> {
+        match self.key.seller_id {
+            Some(seller_id) if self.key.is_test && self.key.has_scope(SCOPE_SANDBOX) => Ok(seller_id),
+            _ => Err(AppError::Forbidden("Sandbox endpoints need a test-mode API key".to_string())),
+        }
+    }
+}
+
+impl actix_web::FromRequest for ApiKeyClient {
+    type Error = AppError;
+    type Future = std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &actix_web::HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        let req = req.clone();
+        Box::pin(async move {
+            // Already authenticated by the API usage middleware
+            if let Some(client) = req.extensions().get::<ApiKeyClient>().cloned() {
+                return Ok(client);
+            }
+
+            let key = req
+                .headers()
+                .get("X-Api-Key")
+                .and_then(|h| h.to_str().ok())
+                .ok_or_else(|| AppError::Authentication("Missing API key".to_string()))?;
+
+            let database = req
+                .app_data::<actix_web::web::Data<Database>>()
+                .ok_or_else(|| AppError::Internal("Database not configured".to_string()))?;
+
+            let key = PartnerApiKey::authenticate(database.pool(), key)
+                .await?
+                .ok_or_else(|| AppError::Authentication("Invalid API key".to_string()))?;
+
+            Ok(Self { key })
+        })
+    }
+}
+
 pub struct AuthMiddleware {
     jwt_service: Rc<JwtService>,
     required_role: Option<UserRole>,
@@ -176,6 +238,16 @@ where
                 return Ok(req.into_response(response));
             }
 
+            // Reject tokens revoked by a password change, role change or ban
+            if let Err(e) = ensure_token_current(&req, &jwt_service, &claims).await {
+                let response = HttpResponse::Unauthorized()
+                    .json(serde_json::json!({
+                        "error": "token_revoked",
+                        "message": e.to_string()
+                    }));
+                return Ok(req.into_response(response));
+            }
+
             // Check role requirements
             if let Some(required_role) = required_role {
                 if !has_required_role(&claims.role, required_role) {
@@ -198,11 +270,117 @@ where
     }
 }
 
+/// Requires a permission of the signed-in user's role; wrap it inside `AuthMiddleware`
+pub struct RequirePermission {
+    permission: &'static str,
+}
+
+impl RequirePermission {
+    pub fn new(permission: &'static str) -> Self {
+        Self { permission }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequirePermissionService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequirePermissionService {
+            service: Rc::new(service),
+            permission: self.permission,
+        }))
+    }
+}
+
+pub struct RequirePermissionService<S> {
+    service: Rc<S>,
+    permission: &'static str,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let permission = self.permission;
+
+        Box::pin(async move {
+            let role = match extract_user_role(&req) {
+                Ok(role) => role,
+                Err(_) => {
+                    let response = HttpResponse::Unauthorized()
+                        .json(serde_json::json!({
+                            "error": "missing_token",
+                            "message": "Authorization token is required"
+                        }));
+                    return Ok(req.into_response(response));
+                }
+            };
+
+            let allowed = role_has_permission(&req, role, permission).await?;
+
+            if !allowed {
+                let response = HttpResponse::Forbidden()
+                    .json(serde_json::json!({
+                        "error": "insufficient_permissions",
+                        "message": format!("The {} permission is required for this operation", permission)
+                    }));
+                return Ok(req.into_response(response));
+            }
+
+            service.call(req).await
+        })
+    }
+}
+
+/// Look the permission up for the role
+///
+/// Apps without a permission service registered (unit tests of the middleware alone) only admit
+/// admins and operators.
+async fn role_has_permission(req: &ServiceRequest, role: UserRole, permission: &str) -> Result<bool, AppError> {
+    let Some(permission_service) = req.app_data::<actix_web::web::Data<PermissionService>>() else {
+        return Ok(has_required_role(&role, UserRole::Admin));
+    };
+
+    permission_service.has_permission(role, permission).await
+}
+
+/// Check the token against the user's current token version
+///
+/// Apps without a database registered (unit tests of the middleware alone) skip the check.
+async fn ensure_token_current(req: &ServiceRequest, jwt_service: &JwtService, claims: &Claims) -> Result<(), AppError> {
+    let Some(database) = req.app_data::<actix_web::web::Data<Database>>() else {
+        return Ok(());
+    };
+
+    jwt_service.ensure_token_current(claims, database.pool()).await?;
+    Ok(())
+}
+
 /// Check if user has required role or higher
 fn has_required_role(user_role: &UserRole, required_role: UserRole) -> bool {
     match required_role {
         UserRole::User => true, // All roles can access user-level endpoints
         UserRole::Seller => matches!(user_role, UserRole::Seller | UserRole::Admin | UserRole::Operator),
+        UserRole::Support => matches!(user_role, UserRole::Support | UserRole::Admin | UserRole::Operator),
         UserRole::Admin => matches!(user_role, UserRole::Admin | UserRole::Operator),
         UserRole::Operator => matches!(user_role, UserRole::Operator),
     }
@@ -291,7 +469,9 @@ where
                 .and_then(|h| h.strip_prefix("Bearer "))
             {
                 if let Ok(claims) = jwt_service.validate_token(auth_header) {
-                    if claims.token_type == "access" {
+                    if claims.token_type == "access"
+                        && ensure_token_current(&req, &jwt_service, &claims).await.is_ok()
+                    {
                         req.extensions_mut().insert(claims);
                     }
                 }
@@ -310,6 +490,7 @@ pub struct SecureAuthMiddleware {
     pool: Arc<sqlx::PgPool>,
     required_role: Option<UserRole>,
     enable_security_checks: bool,
+    geo_velocity: Option<GeoVelocityService>,
 }
 
 impl SecureAuthMiddleware {
@@ -319,9 +500,16 @@ impl SecureAuthMiddleware {
             pool,
             required_role: None,
             enable_security_checks: true,
+            geo_velocity: None,
         }
     }
 
+    /// Challenge users whose requests jump between distant countries to re-authenticate
+    pub fn with_geo_velocity(mut self, geo_velocity: GeoVelocityService) -> Self {
+        self.geo_velocity = Some(geo_velocity);
+        self
+    }
+
     pub fn require_role(mut self, role: UserRole) -> Self {
         self.required_role = Some(role);
         self
@@ -352,6 +540,7 @@ where
             pool: self.pool.clone(),
             required_role: self.required_role,
             enable_security_checks: self.enable_security_checks,
+            geo_velocity: self.geo_velocity.clone(),
         }))
     }
 }
@@ -362,6 +551,7 @@ pub struct SecureAuthMiddlewareService<S> {
     pool: Arc<sqlx::PgPool>,
     required_role: Option<UserRole>,
     enable_security_checks: bool,
+    geo_velocity: Option<GeoVelocityService>,
 }
 
 impl<S, B> Service<ServiceRequest> for SecureAuthMiddlewareService<S>
@@ -381,6 +571,7 @@ where
         let pool = self.pool.clone();
         let required_role = self.required_role;
         let enable_security_checks = self.enable_security_checks;
+        let geo_velocity = self.geo_velocity.clone();
 
         Box::pin(async move {
             // Extract Authorization header
@@ -404,7 +595,7 @@ where
 
             // Extract client information for security checks
             let ip_address = req.connection_info().realip_remote_addr()
-                .and_then(|ip| ip.parse().ok());
+                .and_then(parse_client_ip);
             let user_agent = req.headers()
                 .get("User-Agent")
                 .and_then(|h| h.to_str().ok());
@@ -463,152 +654,30 @@ where
                 }
             }
 
-            // Add claims to request extensions
-            req.extensions_mut().insert(claims);
-
-            // Continue with the request
-            let res = self.service.call(req).await?;
-            Ok(res)
-        })
-    }
-}
-
-/// Rate limiting middleware for authentication endpoints
-pub struct RateLimitMiddleware {
-    max_requests: u32,
-    window_seconds: u64,
-    // In production, you'd use Redis or another distributed cache
-    request_counts: Arc<std::sync::Mutex<std::collections::HashMap<String, (u32, std::time::Instant)>>>,
-}
-
-impl RateLimitMiddleware {
-    pub fn new(max_requests: u32, window_seconds: u64) -> Self {
-        Self {
-            max_requests,
-            window_seconds,
-            request_counts: Arc::new(std::sync::Mutex::new(std::collections::HashMap::new())),
-        }
-    }
-
-    fn is_rate_limited(&self, client_id: &str) -> bool {
-        let mut counts = self.request_counts.lock().unwrap();
-        let now = std::time::Instant::now();
-        
-        // Clean up old entries
-        counts.retain(|_, (_, timestamp)| {
-            now.duration_since(*timestamp).as_secs() < self.window_seconds
-        });
-
-        // Check current count
-        match counts.get_mut(client_id) {
-            Some((count, timestamp)) => {
-                if now.duration_since(*timestamp).as_secs() < self.window_seconds {
-                    *count += 1;
-                    *count > self.max_requests
-                } else {
-                    *count = 1;
-                    *timestamp = now;
-                    false
-                }
-            }
-            None => {
-                counts.insert(client_id.to_string(), (1, now));
-                false
-            }
-        }
-    }
-}
-
-impl<S, B> Transform<S, ServiceRequest> for RateLimitMiddleware
-where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
-    S::Future: 'static,
-    B: 'static,
-{
-    type Response = ServiceResponse<B>;
-    type Error = Error;
-    type InitError = ();
-    type Transform = RateLimitMiddlewareService<S>;
-    type Future = Ready<Result<Self::Transform, Self::InitError>>;
-
-    fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(RateLimitMiddlewareService {
-            service,
-            max_requests: self.max_requests,
-            window_seconds: self.window_seconds,
-            request_counts: self.request_counts.clone(),
-        }))
-    }
-}
-
-pub struct RateLimitMiddlewareService<S> {
-    service: S,
-    max_requests: u32,
-    window_seconds: u64,
-    request_counts: Arc<std::sync::Mutex<std::collections::HashMap<String, (u32, std::time::Instant)>>>,
-}
-
-impl<S, B> Service<ServiceRequest> for RateLimitMiddlewareService<S>
-where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
-    S::Future: 'static,
-    B: 'static,
-{
-    type Response = ServiceResponse<B>;
-    type Error = Error;
-    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
-
-    forward_ready!(service);
-
-    fn call(&self, req: ServiceRequest) -> Self::Future {
-        let max_requests = self.max_requests;
-        let window_seconds = self.window_seconds;
-        let request_counts = self.request_counts.clone();
-
-        Box::pin(async move {
-            // Use IP address as client identifier
-            let client_id = req.connection_info().realip_remote_addr()
-                .unwrap_or("unknown")
-                .to_string();
-
-            // Check rate limit
-            let is_limited = {
-                let mut counts = request_counts.lock().unwrap();
-                let now = std::time::Instant::now();
-                
-                // Clean up old entries
-                counts.retain(|_, (_, timestamp)| {
-                    now.duration_since(*timestamp).as_secs() < window_seconds
-                });
-
-                // Check current count
-                match counts.get_mut(&client_id) {
-                    Some((count, timestamp)) => {
-                        if now.duration_since(*timestamp).as_secs() < window_seconds {
-                            *count += 1;
-                            *count > max_requests
-                        } else {
-                            *count = 1;
-                            *timestamp = now;
-                            false
+            // Make the user re-authenticate when their requests move between countries too fast
+            if let (Some(geo_velocity), Some(ip)) = (&geo_velocity, ip_address) {
+                if let Ok(user_id) = uuid::Uuid::parse_str(&claims.sub) {
+                    match geo_velocity.check_request(user_id, ip).await {
+                        Ok(None) => {}
+                        Ok(Some(challenge)) => {
+                            let response = HttpResponse::Unauthorized()
+                                .json(serde_json::json!({
+                                    "error": "step_up_required",
+                                    "message": "Confirm your password to continue",
+                                    "challenge_id": challenge.id,
+                                    "expires_at": challenge.expires_at
+                                }));
+                            return Ok(req.into_response(response));
                         }
-                    }
-                    None => {
-                        counts.insert(client_id.clone(), (1, now));
-                        false
+                        // A failing geo lookup must not lock every user out
+                        Err(e) => tracing::warn!("Geo-velocity check failed for user {}: {}", user_id, e),
                     }
                 }
-            };
-
-            if is_limited {
-                let response = HttpResponse::TooManyRequests()
-                    .json(serde_json::json!({
-                        "error": "rate_limit_exceeded",
-                        "message": format!("Rate limit exceeded. Maximum {} requests per {} seconds", max_requests, window_seconds)
-                    }));
-                return Ok(req.into_response(response));
             }
 
+            // Add claims to request extensions
+            req.extensions_mut().insert(claims);
+
             // Continue with the request
             let res = self.service.call(req).await?;
             Ok(res)
@@ -616,6 +685,13 @@ where
     }
 }
 
+/// Client address from the forwarded headers, or the peer address without its port
+pub(crate) fn parse_client_ip(addr: &str) -> Option<IpAddr> {
+    addr.parse::<IpAddr>()
+        .ok()
+        .or_else(|| addr.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -659,6 +735,7 @@ mod tests {
                 "testuser".to_string(),
                 "test@example.com".to_string(),
                 UserRole::User,
+                0,
             )
             .expect("Failed to generate token");
 
@@ -688,6 +765,7 @@ mod tests {
                 "testuser".to_string(),
                 "test@example.com".to_string(),
                 UserRole::User,
+                0,
             )
             .expect("Failed to generate token");
 
@@ -707,6 +785,44 @@ mod tests {
         assert_eq!(resp.status(), 403); // Forbidden - user doesn't have admin role
     }
 
+    #[actix_web::test]
+    async fn test_require_permission_without_permission_service() {
+        let jwt_service = setup_jwt_service();
+        let token_for = |role| {
+            jwt_service
+                .generate_access_token(
+                    Uuid::new_v4(),
+                    "testuser".to_string(),
+                    "test@example.com".to_string(),
+                    role,
+                    0,
+                )
+                .expect("Failed to generate token")
+        };
+        let support_token = token_for(UserRole::Support);
+        let admin_token = token_for(UserRole::Admin);
+
+        let app = test::init_service(
+            App::new()
+                .wrap(RequirePermission::new("payments.refund"))
+                .wrap(AuthMiddleware::new(jwt_service.clone()))
+                .route("/refund", web::post().to(test_handler)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/refund")
+            .insert_header(("Authorization", format!("Bearer {}", support_token)))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 403);
+
+        let req = test::TestRequest::post()
+            .uri("/refund")
+            .insert_header(("Authorization", format!("Bearer {}", admin_token)))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+    }
+
     #[test]
     fn test_role_hierarchy() {
         assert!(has_required_role(&UserRole::User, UserRole::User));
@@ -719,8 +835,15 @@ mod tests {
         assert!(has_required_role(&UserRole::Admin, UserRole::Seller));
         assert!(has_required_role(&UserRole::Operator, UserRole::Seller));
 
+        assert!(!has_required_role(&UserRole::User, UserRole::Support));
+        assert!(!has_required_role(&UserRole::Seller, UserRole::Support));
+        assert!(has_required_role(&UserRole::Support, UserRole::Support));
+        assert!(has_required_role(&UserRole::Admin, UserRole::Support));
+        assert!(!has_required_role(&UserRole::Support, UserRole::Seller));
+
         assert!(!has_required_role(&UserRole::User, UserRole::Admin));
         assert!(!has_required_role(&UserRole::Seller, UserRole::Admin));
+        assert!(!has_required_role(&UserRole::Support, UserRole::Admin));
         assert!(has_required_role(&UserRole::Admin, UserRole::Admin));
         assert!(has_required_role(&UserRole::Operator, UserRole::Admin));
 
@@ -729,4 +852,12 @@ mod tests {
         assert!(!has_required_role(&UserRole::Admin, UserRole::Operator));
         assert!(has_required_role(&UserRole::Operator, UserRole::Operator));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_client_ip() {
+        assert_eq!(parse_client_ip("203.0.113.7"), Some("203.0.113.7".parse().unwrap()));
+        assert_eq!(parse_client_ip("203.0.113.7:51234"), Some("203.0.113.7".parse().unwrap()));
+        assert_eq!(parse_client_ip("[2001:db8::1]:443"), Some("2001:db8::1".parse().unwrap()));
+        assert_eq!(parse_client_ip("unknown"), None);
+    }
+}
@@ -0,0 +1,99 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+    sync::Arc,
+};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::services::feature_flag_service::{EnabledFeatures, FeatureFlagService};
+use crate::utils::jwt::JwtService;
+
+/// Evaluates feature flags once per request and hands them to handlers through the
+/// request extensions. The caller is taken from a valid bearer token when there is one;
+/// anything else is evaluated as anonymous, and authentication is left to the routes.
+pub struct FeatureFlagMiddleware {
+    flags: FeatureFlagService,
+    jwt_service: Arc<JwtService>,
+}
+
+impl FeatureFlagMiddleware {
+    pub fn new(flags: FeatureFlagService, jwt_service: Arc<JwtService>) -> Self {
+        Self { flags, jwt_service }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for FeatureFlagMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = FeatureFlagMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(FeatureFlagMiddlewareService {
+            service: Rc::new(service),
+            flags: self.flags.clone(),
+            jwt_service: self.jwt_service.clone(),
+        }))
+    }
+}
+
+pub struct FeatureFlagMiddlewareService<S> {
+    service: Rc<S>,
+    flags: FeatureFlagService,
+    jwt_service: Arc<JwtService>,
+}
+
+impl<S, B> Service<ServiceRequest> for FeatureFlagMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let flags = self.flags.clone();
+
+        let user = req
+            .headers()
+            .get("Authorization")
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .and_then(|token| self.jwt_service.validate_token(token).ok())
+            .and_then(|claims| Some((Uuid::parse_str(&claims.sub).ok()?, claims.role)));
+
+        Box::pin(async move {
+            let enabled = flags.evaluate(user).await;
+            req.extensions_mut().insert(enabled);
+
+            service.call(req).await
+        })
+    }
+}
+
+/// Flags on for the caller; none when the middleware didn't run
+impl actix_web::FromRequest for EnabledFeatures {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &actix_web::HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        ready(Ok(req.extensions().get::<EnabledFeatures>().cloned().unwrap_or_default()))
+    }
+}
@@ -0,0 +1,87 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use crate::error::AppError;
+use crate::middleware::auth::parse_client_ip;
+use crate::services::geo_compliance_service::{GeoComplianceService, RequestCountry};
+
+/// Works out the country each request comes from and hands it to handlers through the
+/// request extensions. Nothing is refused here; the raffle handlers decide what the
+/// country may see and buy.
+pub struct GeoRestrictionMiddleware {
+    geo_compliance: GeoComplianceService,
+}
+
+impl GeoRestrictionMiddleware {
+    pub fn new(geo_compliance: GeoComplianceService) -> Self {
+        Self { geo_compliance }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for GeoRestrictionMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = GeoRestrictionMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(GeoRestrictionMiddlewareService {
+            service: Rc::new(service),
+            geo_compliance: self.geo_compliance.clone(),
+        }))
+    }
+}
+
+pub struct GeoRestrictionMiddlewareService<S> {
+    service: Rc<S>,
+    geo_compliance: GeoComplianceService,
+}
+
+impl<S, B> Service<ServiceRequest> for GeoRestrictionMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let geo_compliance = self.geo_compliance.clone();
+        let ip = req.connection_info().realip_remote_addr().and_then(parse_client_ip);
+
+        Box::pin(async move {
+            let country = geo_compliance.resolve_country(ip).await;
+            req.extensions_mut().insert(country);
+
+            service.call(req).await
+        })
+    }
+}
+
+/// Country of the request; unknown when the middleware didn't run
+impl actix_web::FromRequest for RequestCountry {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &actix_web::HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        ready(Ok(req.extensions().get::<RequestCountry>().cloned().unwrap_or_default()))
+    }
+}
@@ -0,0 +1,248 @@
+use actix_web::{
+    body::{to_bytes, BoxBody, MessageBody},
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    error::PayloadError,
+    http::{header, StatusCode},
+    web, Error, HttpMessage, HttpResponse, ResponseError,
+};
+use futures_util::future::LocalBoxFuture;
+use futures_util::Stream;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::{
+    future::{ready, Ready},
+    pin::Pin,
+    rc::Rc,
+};
+use tracing::{debug, error, warn};
+
+use crate::error::AppError;
+use crate::models::idempotency_key::{IdempotencyClaim, IdempotencyKey, StoredResponse};
+use crate::models::system_settings::SystemSetting;
+use crate::utils::jwt::Claims;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+const MAX_KEY_LENGTH: usize = 255;
+const DEFAULT_TTL_HOURS: i64 = 24;
+
+/// Replays the stored response when a request is retried with the same `Idempotency-Key`
+///
+/// Must sit inside `AuthMiddleware`; keys are scoped to the authenticated user. Requests
+/// without the header pass through untouched. Server errors are not stored, so the
+/// request can be retried with the same key.
+pub struct IdempotencyMiddleware {
+    db_pool: PgPool,
+}
+
+impl IdempotencyMiddleware {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for IdempotencyMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = IdempotencyMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(IdempotencyMiddlewareService {
+            service: Rc::new(service),
+            db_pool: self.db_pool.clone(),
+        }))
+    }
+}
+
+pub struct IdempotencyMiddlewareService<S> {
+    service: Rc<S>,
+    db_pool: PgPool,
+}
+
+impl<S, B> Service<ServiceRequest> for IdempotencyMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let db_pool = self.db_pool.clone();
+
+        Box::pin(async move {
+            let key = match req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+                Some(value) => match value.to_str().ok().and_then(|k| validate_key(k).ok()) {
+                    Some(key) => key.to_string(),
+                    None => {
+                        let error = AppError::Validation(format!(
+                            "{} must be 1 to {} visible ASCII characters",
+                            IDEMPOTENCY_KEY_HEADER, MAX_KEY_LENGTH
+                        ));
+                        return Ok(req.into_response(error.error_response()));
+                    }
+                },
+                None => return Ok(service.call(req).await?.map_into_boxed_body()),
+            };
+
+            let user_id = match req
+                .extensions()
+                .get::<Claims>()
+                .and_then(|claims| uuid::Uuid::parse_str(&claims.sub).ok())
+            {
+                Some(user_id) => user_id,
+                None => {
+                    let error = AppError::Authentication("Authentication required".to_string());
+                    return Ok(req.into_response(error.error_response()));
+                }
+            };
+
+            // Read the body to fingerprint it, then put it back for the handler
+            let body = req.extract::<web::Bytes>().await?;
+            let request_hash = hex::encode(Sha256::digest(&body));
+            req.set_payload(bytes_to_payload(body));
+
+            let method = req.method().to_string();
+            let path = req.path().to_string();
+            let ttl_hours = get_ttl_hours(&db_pool).await;
+
+            let claim = match IdempotencyKey::claim(
+                &db_pool, user_id, &key, &method, &path, &request_hash, ttl_hours,
+            ).await {
+                Ok(claim) => claim,
+                Err(e) => return Ok(req.into_response(e.error_response())),
+            };
+
+            let claim_id = match claim {
+                IdempotencyClaim::Claimed(id) => id,
+                IdempotencyClaim::Replay(stored) => {
+                    debug!("Replaying stored response for idempotency key {} of user {}", key, user_id);
+                    return Ok(req.into_response(replay_response(stored)));
+                }
+                IdempotencyClaim::InProgress => {
+                    let error = AppError::Conflict(
+                        "A request with this Idempotency-Key is still being processed".to_string(),
+                    );
+                    return Ok(req.into_response(error.error_response()));
+                }
+                IdempotencyClaim::Mismatch => {
+                    let error = AppError::Validation(
+                        "Idempotency-Key was already used for a different request".to_string(),
+                    );
+                    return Ok(req.into_response(error.error_response()));
+                }
+            };
+
+            let res = match service.call(req).await {
+                Ok(res) => res,
+                Err(e) => {
+                    release(&db_pool, claim_id).await;
+                    return Err(e);
+                }
+            };
+
+            let (req, res) = res.into_parts();
+            let status = res.status();
+            let content_type = res
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|h| h.to_str().ok())
+                .map(|s| s.to_string());
+            let (res, body) = res.into_parts();
+
+            let body = match to_bytes(body).await {
+                Ok(body) => body,
+                Err(_) => {
+                    release(&db_pool, claim_id).await;
+                    let error = AppError::Internal("Failed to read response body".to_string());
+                    return Ok(ServiceResponse::new(req, error.error_response()));
+                }
+            };
+
+            if status.is_server_error() {
+                release(&db_pool, claim_id).await;
+            } else if let Err(e) = IdempotencyKey::complete(
+                &db_pool,
+                claim_id,
+                status.as_u16(),
+                content_type.as_deref(),
+                &body,
+            ).await {
+                // The response still goes out; a retry will get a conflict until the claim is abandoned
+                error!("Failed to store response for idempotency key {}: {}", key, e);
+            }
+
+            Ok(ServiceResponse::new(req, res.set_body(body).map_into_boxed_body()))
+        })
+    }
+}
+
+async fn get_ttl_hours(db_pool: &PgPool) -> i64 {
+    match SystemSetting::get_by_key(db_pool, "idempotency_key_ttl_hours").await {
+        Ok(setting) => setting
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_TTL_HOURS),
+        Err(e) => {
+            warn!("Failed to read idempotency key TTL, using default: {}", e);
+            DEFAULT_TTL_HOURS
+        }
+    }
+}
+
+async fn release(db_pool: &PgPool, claim_id: uuid::Uuid) {
+    if let Err(e) = IdempotencyKey::release(db_pool, claim_id).await {
+        error!("Failed to release idempotency key claim {}: {}", claim_id, e);
+    }
+}
+
+fn replay_response(stored: StoredResponse) -> HttpResponse {
+    let status = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    let mut builder = HttpResponse::build(status);
+    builder.insert_header((IDEMPOTENT_REPLAYED_HEADER, "true"));
+    if let Some(content_type) = stored.content_type {
+        builder.insert_header((header::CONTENT_TYPE, content_type));
+    }
+    builder.body(stored.body)
+}
+
+fn bytes_to_payload(body: web::Bytes) -> Payload {
+    let stream: Pin<Box<dyn Stream<Item = Result<web::Bytes, PayloadError>>>> =
+        Box::pin(futures_util::stream::once(async move { Ok(body) }));
+    Payload::from(stream)
+}
+
+/// Keys are opaque client-chosen strings such as UUIDs
+fn validate_key(key: &str) -> Result<&str, ()> {
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH || !key.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(());
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_key() {
+        assert!(validate_key("3f2b8c1e-8d4a-4a53-9a0e-2f1c7d5b9e10").is_ok());
+        assert!(validate_key("order_42:retry").is_ok());
+        assert!(validate_key("").is_err());
+        assert!(validate_key("has space").is_err());
+        assert!(validate_key(&"k".repeat(MAX_KEY_LENGTH + 1)).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+    time::Instant,
+};
+
+use crate::services::metrics_service::{MetricsService, UNMATCHED_ROUTE};
+
+/// Records request count and latency per route pattern for the `/metrics` endpoint
+pub struct MetricsMiddleware {
+    service: MetricsService,
+}
+
+impl MetricsMiddleware {
+    pub fn new(service: MetricsService) -> Self {
+        Self { service }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for MetricsMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = MetricsMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MetricsMiddlewareService {
+            service: Rc::new(service),
+            metrics_service: self.service.clone(),
+        }))
+    }
+}
+
+pub struct MetricsMiddlewareService<S> {
+    service: Rc<S>,
+    metrics_service: MetricsService,
+}
+
+impl<S, B> Service<ServiceRequest> for MetricsMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let metrics_service = self.metrics_service.clone();
+
+        Box::pin(async move {
+            let start_time = Instant::now();
+            let method = req.method().to_string();
+
+            let res = service.call(req).await?;
+
+            // The route pattern is only known once routing has run
+            let route = res
+                .request()
+                .match_pattern()
+                .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+            metrics_service.observe_request(&method, &route, res.status().as_u16(), start_time.elapsed());
+
+            Ok(res)
+        })
+    }
+}
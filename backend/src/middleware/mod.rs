@@ -1,4 +1,10 @@
+pub mod api_usage;
 pub mod auth;
 pub mod cors;
+pub mod feature_flags;
+pub mod geo_restriction;
+pub mod idempotency;
 pub mod logging;
-pub mod performance;
\ No newline at end of file
+pub mod metrics;
+pub mod performance;
+pub mod rate_limit;
\ No newline at end of file
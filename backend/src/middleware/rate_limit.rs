@@ -0,0 +1,778 @@
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    Error, ResponseError,
+};
+use futures_util::future::LocalBoxFuture;
+use redis::Client as RedisClient;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::{
+    collections::HashMap,
+    future::{ready, Ready},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use crate::services::settings_service::SettingsService;
+use crate::utils::jwt::JwtService;
+
+pub const RATE_LIMIT_LIMIT_HEADER: &str = "ratelimit-limit";
+pub const RATE_LIMIT_REMAINING_HEADER: &str = "ratelimit-remaining";
+pub const RATE_LIMIT_RESET_HEADER: &str = "ratelimit-reset";
+pub const RATE_LIMIT_POLICY_HEADER: &str = "ratelimit-policy";
+
+/// How long budgets read from system settings are used before being read again
+const SETTINGS_REFRESH: Duration = Duration::from_secs(30);
+
+/// The memory store drops finished entries once it holds this many keys
+const MEMORY_STORE_PRUNE_THRESHOLD: usize = 10_000;
+
+const MAX_BUDGET_REQUESTS: u32 = 100_000;
+const MAX_BUDGET_WINDOW_SECONDS: u64 = 24 * 60 * 60;
+
+/// Requests a client may make per window. Limiting uses GCRA: requests are spaced one
+/// `window / requests` apart, with up to a full window's worth allowed back to back,
+/// so a client can never make more than `requests` in any window, however it is aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RateLimitBudget {
+    pub requests: u32,
+    pub window_seconds: u64,
+}
+
+impl RateLimitBudget {
+    pub const fn new(requests: u32, window_seconds: u64) -> Self {
+        Self { requests, window_seconds }
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.requests == 0 || self.requests > MAX_BUDGET_REQUESTS {
+            return Err(AppError::Validation(format!(
+                "requests must be between 1 and {}",
+                MAX_BUDGET_REQUESTS
+            )));
+        }
+        if self.window_seconds == 0 || self.window_seconds > MAX_BUDGET_WINDOW_SECONDS {
+            return Err(AppError::Validation(format!(
+                "window_seconds must be between 1 and {}",
+                MAX_BUDGET_WINDOW_SECONDS
+            )));
+        }
+        Ok(())
+    }
+
+    fn window_ms(&self) -> u64 {
+        self.window_seconds * 1000
+    }
+
+    /// Time one request uses up
+    fn emission_interval_ms(&self) -> u64 {
+        (self.window_ms() / u64::from(self.requests.max(1))).max(1)
+    }
+
+    /// `RateLimit-Policy` value, e.g. `5;w=60`
+    fn policy(&self) -> String {
+        format!("{};w={}", self.requests, self.window_seconds)
+    }
+}
+
+/// Outcome of counting one request against a budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Until the full budget is available again
+    pub reset_after: Duration,
+    /// Until the next request would be allowed, when this one was refused
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimitDecision {
+    /// Decision from the GCRA state: `reset_after_ms` is how far the theoretical arrival
+    /// time is ahead of now, `retry_after_ms` is non-zero when the request was refused
+    fn new(budget: &RateLimitBudget, allowed: bool, reset_after_ms: u64, retry_after_ms: u64) -> Self {
+        let remaining = if allowed {
+            budget.window_ms().saturating_sub(reset_after_ms) / budget.emission_interval_ms()
+        } else {
+            0
+        };
+
+        Self {
+            allowed,
+            limit: budget.requests,
+            remaining: remaining.min(u64::from(budget.requests)) as u32,
+            reset_after: Duration::from_millis(reset_after_ms),
+            retry_after: (!allowed).then(|| Duration::from_millis(retry_after_ms)),
+        }
+    }
+
+    fn insert_headers(&self, headers: &mut actix_web::http::header::HeaderMap, budget: &RateLimitBudget) {
+        let mut insert = |name: &'static str, value: String| {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                headers.insert(HeaderName::from_static(name), value);
+            }
+        };
+
+        insert(RATE_LIMIT_LIMIT_HEADER, self.limit.to_string());
+        insert(RATE_LIMIT_REMAINING_HEADER, self.remaining.to_string());
+        insert(RATE_LIMIT_RESET_HEADER, ceil_seconds(self.reset_after).to_string());
+        insert(RATE_LIMIT_POLICY_HEADER, budget.policy());
+    }
+}
+
+fn ceil_seconds(duration: Duration) -> u64 {
+    (duration.as_millis() as u64).div_ceil(1000)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// One GCRA step. `tat` is the stored theoretical arrival time; returns the decision and,
+/// when the request is allowed, the new arrival time to store.
+fn gcra(tat: Option<u64>, now: u64, budget: &RateLimitBudget) -> (RateLimitDecision, Option<u64>) {
+    let tat = tat.unwrap_or(now).max(now);
+    let new_tat = tat + budget.emission_interval_ms();
+    let allow_at = new_tat.saturating_sub(budget.window_ms());
+
+    if now < allow_at {
+        (RateLimitDecision::new(budget, false, tat - now, allow_at - now), None)
+    } else {
+        (RateLimitDecision::new(budget, true, new_tat - now, 0), Some(new_tat))
+    }
+}
+
+/// Where rate limit state is kept
+pub trait RateLimitStore: Send + Sync {
+    /// Count a request for `key` against `budget`
+    fn check(&self, key: &str, budget: &RateLimitBudget) -> LocalBoxFuture<'_, Result<RateLimitDecision, AppError>>;
+}
+
+/// Per-process store, for development and as the fallback when Redis is unreachable
+#[derive(Default)]
+pub struct MemoryRateLimitStore {
+    /// Theoretical arrival time per key, in Unix milliseconds
+    entries: Mutex<HashMap<String, u64>>,
+}
+
+impl MemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn check_at(&self, key: &str, budget: &RateLimitBudget, now: u64) -> RateLimitDecision {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        if entries.len() >= MEMORY_STORE_PRUNE_THRESHOLD {
+            // An arrival time in the past means the key has its full budget back
+            entries.retain(|_, tat| *tat > now);
+        }
+
+        let (decision, new_tat) = gcra(entries.get(key).copied(), now, budget);
+        if let Some(new_tat) = new_tat {
+            entries.insert(key.to_string(), new_tat);
+        }
+
+        decision
+    }
+}
+
+impl RateLimitStore for MemoryRateLimitStore {
+    fn check(&self, key: &str, budget: &RateLimitBudget) -> LocalBoxFuture<'_, Result<RateLimitDecision, AppError>> {
+        let decision = self.check_at(key, budget, now_ms());
+        Box::pin(async move { Ok(decision) })
+    }
+}
+
+/// GCRA in one round trip. Uses the Redis clock so every instance agrees on the time.
+/// Returns {allowed, reset_after_ms, retry_after_ms}.
+const GCRA_SCRIPT: &str = r#"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local interval = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local tat = tonumber(redis.call('GET', KEYS[1]) or now)
+if tat < now then tat = now end
+local new_tat = tat + interval
+local allow_at = new_tat - window
+if now < allow_at then
+    return {0, tat - now, allow_at - now}
+end
+redis.call('SET', KEYS[1], new_tat, 'PX', new_tat - now)
+return {1, new_tat - now, 0}
+"#;
+
+/// Store shared by every instance. Falls back to a per-process store while Redis is
+/// unreachable, so limits still hold on each instance.
+pub struct RedisRateLimitStore {
+    client: RedisClient,
+    script: redis::Script,
+    fallback: MemoryRateLimitStore,
+}
+
+impl RedisRateLimitStore {
+    pub fn new(redis_url: &str) -> Result<Self, redis::RedisError> {
+        Ok(Self {
+            client: RedisClient::open(redis_url)?,
+            script: redis::Script::new(GCRA_SCRIPT),
+            fallback: MemoryRateLimitStore::new(),
+        })
+    }
+
+    async fn check_redis(&self, key: &str, budget: &RateLimitBudget) -> Result<RateLimitDecision, redis::RedisError> {
+        let mut conn = self.client.get_async_connection().await?;
+
+        let (allowed, reset_after_ms, retry_after_ms): (i64, i64, i64) = self
+            .script
+            .key(format!("rate_limit:{}", key))
+            .arg(budget.emission_interval_ms())
+            .arg(budget.window_ms())
+            .invoke_async(&mut conn)
+            .await?;
+
+        Ok(RateLimitDecision::new(
+            budget,
+            allowed == 1,
+            reset_after_ms.max(0) as u64,
+            retry_after_ms.max(0) as u64,
+        ))
+    }
+}
+
+impl RateLimitStore for RedisRateLimitStore {
+    fn check(&self, key: &str, budget: &RateLimitBudget) -> LocalBoxFuture<'_, Result<RateLimitDecision, AppError>> {
+        let key = key.to_string();
+        let budget = *budget;
+
+        Box::pin(async move {
+            match self.check_redis(&key, &budget).await {
+                Ok(decision) => Ok(decision),
+                Err(e) => {
+                    warn!("Rate limit store unavailable, limiting in memory: {}", e);
+                    Ok(self.fallback.check_at(&key, &budget, now_ms()))
+                }
+            }
+        })
+    }
+}
+
+/// Budget for a group of routes. Requests are matched against `ROUTE_BUDGETS` in order;
+/// the first match wins.
+#[derive(Debug, Clone, Copy)]
+pub struct RouteBudget {
+    pub name: &'static str,
+    pub method: Option<&'static str>,
+    /// Path with `{}` for a single variable segment, or a prefix ending in `/*`
+    pub path: &'static str,
+    /// Count authenticated requests per user rather than per client IP
+    pub per_user: bool,
+    pub default: RateLimitBudget,
+}
+
+pub const ROUTE_BUDGETS: &[RouteBudget] = &[
+    RouteBudget {
+        name: "login",
+        method: Some("POST"),
+        path: "/api/v1/auth/login",
+        per_user: false,
+        default: RateLimitBudget::new(5, 60),
+    },
+    RouteBudget {
+        name: "register",
+        method: Some("POST"),
+        path: "/api/v1/auth/register",
+        per_user: false,
+        default: RateLimitBudget::new(5, 3600),
+    },
+    RouteBudget {
+        name: "password_reset",
+        method: Some("POST"),
+        path: "/api/v1/auth/forgot-password",
+        per_user: false,
+        default: RateLimitBudget::new(5, 3600),
+    },
+    RouteBudget {
+        name: "buy_boxes",
+        method: Some("POST"),
+        path: "/api/v1/raffles/{}/buy-boxes",
+        per_user: true,
+        default: RateLimitBudget::new(10, 60),
+    },
+    RouteBudget {
+        name: "verify",
+        method: Some("GET"),
+        path: "/api/v1/verify/*",
+        per_user: false,
+        default: RateLimitBudget::new(30, 60),
+    },
+    RouteBudget {
+        name: "api",
+        method: None,
+        path: "/api/v1/*",
+        per_user: false,
+        default: RateLimitBudget::new(300, 60),
+    },
+];
+
+impl RouteBudget {
+    pub fn find(name: &str) -> Option<&'static RouteBudget> {
+        ROUTE_BUDGETS.iter().find(|route| route.name == name)
+    }
+
+    /// Budget for a request
+    pub fn matching(method: &str, path: &str) -> Option<&'static RouteBudget> {
+        ROUTE_BUDGETS.iter().find(|route| route.matches(method, path))
+    }
+
+    fn matches(&self, method: &str, path: &str) -> bool {
+        if self.method.is_some_and(|m| !m.eq_ignore_ascii_case(method)) {
+            return false;
+        }
+
+        let path = path.trim_end_matches('/');
+        if let Some(prefix) = self.path.strip_suffix("/*") {
+            return path == prefix || path.starts_with(&format!("{}/", prefix));
+        }
+
+        let mut pattern = self.path.split('/');
+        let mut segments = path.split('/');
+        loop {
+            match (pattern.next(), segments.next()) {
+                (None, None) => return true,
+                (Some("{}"), Some(segment)) if !segment.is_empty() => continue,
+                (Some(expected), Some(segment)) if expected == segment => continue,
+                _ => return false,
+            }
+        }
+    }
+
+    fn requests_setting(&self) -> String {
+        format!("rate_limit_{}_requests", self.name)
+    }
+
+    fn window_setting(&self) -> String {
+        format!("rate_limit_{}_window_seconds", self.name)
+    }
+
+    /// Budget from the settings; missing or invalid values use the default
+    fn budget_from(&self, requests: Option<i64>, window_seconds: Option<i64>) -> RateLimitBudget {
+        let budget = RateLimitBudget {
+            requests: requests
+                .and_then(|v| u32::try_from(v).ok())
+                .unwrap_or(self.default.requests),
+            window_seconds: window_seconds
+                .and_then(|v| u64::try_from(v).ok())
+                .unwrap_or(self.default.window_seconds),
+        };
+
+        if budget.validate().is_ok() {
+            budget
+        } else {
+            self.default
+        }
+    }
+}
+
+/// A route budget and the limit in force
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RouteBudgetStatus {
+    #[schema(value_type = String)]
+    pub name: &'static str,
+    #[schema(value_type = Option<String>)]
+    pub method: Option<&'static str>,
+    #[schema(value_type = String)]
+    pub path: &'static str,
+    pub per_user: bool,
+    pub requests: u32,
+    pub window_seconds: u64,
+    pub default_requests: u32,
+    pub default_window_seconds: u64,
+}
+
+impl RouteBudgetStatus {
+    fn new(route: &'static RouteBudget, budget: RateLimitBudget) -> Self {
+        Self {
+            name: route.name,
+            method: route.method,
+            path: route.path,
+            per_user: route.per_user,
+            requests: budget.requests,
+            window_seconds: budget.window_seconds,
+            default_requests: route.default.requests,
+            default_window_seconds: route.default.window_seconds,
+        }
+    }
+}
+
+#[derive(Default)]
+struct BudgetCache {
+    loaded_at: Option<Instant>,
+    budgets: HashMap<&'static str, RateLimitBudget>,
+}
+
+/// Per-route rate limiting, shared by every worker. Budgets are read from system
+/// settings and re-read every `SETTINGS_REFRESH`, so limits can be tuned without a
+/// restart.
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    pool: PgPool,
+    jwt_service: Arc<JwtService>,
+    budgets: Arc<RwLock<BudgetCache>>,
+}
+
+impl RateLimiter {
+    pub fn new(store: Arc<dyn RateLimitStore>, pool: PgPool, jwt_service: Arc<JwtService>) -> Self {
+        Self {
+            store,
+            pool,
+            jwt_service,
+            budgets: Arc::new(RwLock::new(BudgetCache::default())),
+        }
+    }
+
+    /// Redis-backed limiter, or a per-process one when the Redis URL is unusable
+    pub fn from_config(pool: PgPool, jwt_service: Arc<JwtService>, config: &AppConfig) -> Self {
+        let store: Arc<dyn RateLimitStore> = match RedisRateLimitStore::new(&config.redis_url) {
+            Ok(store) => Arc::new(store),
+            Err(e) => {
+                warn!("Invalid Redis URL, rate limits will be kept per instance: {}", e);
+                Arc::new(MemoryRateLimitStore::new())
+            }
+        };
+
+        Self::new(store, pool, jwt_service)
+    }
+
+    /// Budget in force for a route
+    pub async fn budget(&self, route: &RouteBudget) -> RateLimitBudget {
+        {
+            let cache = self.budgets.read().await;
+            if cache.loaded_at.is_some_and(|at| at.elapsed() < SETTINGS_REFRESH) {
+                return cache.budgets.get(route.name).copied().unwrap_or(route.default);
+            }
+        }
+
+        let mut cache = self.budgets.write().await;
+        if !cache.loaded_at.is_some_and(|at| at.elapsed() < SETTINGS_REFRESH) {
+            match self.load_budgets().await {
+                Ok(budgets) => cache.budgets = budgets,
+                // Keep the budgets we have rather than failing requests
+                Err(e) => warn!("Failed to load rate limit settings: {}", e),
+            }
+            cache.loaded_at = Some(Instant::now());
+        }
+
+        cache.budgets.get(route.name).copied().unwrap_or(route.default)
+    }
+
+    async fn load_budgets(&self) -> Result<HashMap<&'static str, RateLimitBudget>, AppError> {
+        let settings: HashMap<String, serde_json::Value> = sqlx::query_as::<_, (String, serde_json::Value)>(
+            "SELECT key, value FROM system_settings WHERE key LIKE 'rate\\_limit\\_%'",
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .collect();
+
+        Ok(ROUTE_BUDGETS
+            .iter()
+            .map(|route| {
+                let requests = settings.get(&route.requests_setting()).and_then(|v| v.as_i64());
+                let window = settings.get(&route.window_setting()).and_then(|v| v.as_i64());
+                (route.name, route.budget_from(requests, window))
+            })
+            .collect())
+    }
+
+    /// Re-read budgets whenever a rate limit setting changes, rather than waiting out
+    /// `SETTINGS_REFRESH`
+    pub fn watch_settings(&self, settings: &SettingsService) {
+        let mut changes = settings.subscribe();
+        let budgets = self.budgets.clone();
+
+        tokio::spawn(async move {
+            loop {
+                match changes.recv().await {
+                    Ok(change) if change.key.starts_with("rate_limit_") => {
+                        debug!("Rate limit setting {} changed, reloading budgets", change.key);
+                        budgets.write().await.loaded_at = None;
+                    }
+                    Ok(_) => {}
+                    // Some changes were missed; one of them may have been a budget
+                    Err(broadcast::error::RecvError::Lagged(_)) => budgets.write().await.loaded_at = None,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Every route budget with the limit in force
+    pub async fn route_budgets(&self) -> Vec<RouteBudgetStatus> {
+        let mut statuses = Vec::with_capacity(ROUTE_BUDGETS.len());
+        for route in ROUTE_BUDGETS {
+            statuses.push(RouteBudgetStatus::new(route, self.budget(route).await));
+        }
+        statuses
+    }
+
+    /// Change a route's budget. Takes effect here at once, and on other instances as soon
+    /// as their settings service sees the change (within `SETTINGS_REFRESH` otherwise).
+    pub async fn update_budget(&self, name: &str, budget: RateLimitBudget) -> Result<RouteBudgetStatus, AppError> {
+        let route = RouteBudget::find(name)
+            .ok_or_else(|| AppError::NotFound(format!("Unknown rate limit budget: {}", name)))?;
+        budget.validate()?;
+
+        SystemSetting::upsert(
+            &self.pool,
+            route.requests_setting(),
+            serde_json::json!(budget.requests),
+            Some(format!("Requests allowed per window on the {} rate limit budget", route.name)),
+            false,
+        )
+        .await?;
+        SystemSetting::upsert(
+            &self.pool,
+            route.window_setting(),
+            serde_json::json!(budget.window_seconds),
+            Some(format!("Window in seconds of the {} rate limit budget", route.name)),
+            false,
+        )
+        .await?;
+
+        self.budgets.write().await.budgets.insert(route.name, budget);
+
+        info!(
+            "Rate limit budget {} set to {} requests per {}s",
+            route.name, budget.requests, budget.window_seconds
+        );
+
+        Ok(RouteBudgetStatus::new(route, budget))
+    }
+
+    /// Who a request is counted against: the user for per-user budgets when the bearer
+    /// token is valid, otherwise the client IP
+    fn client_key(&self, req: &ServiceRequest, route: &RouteBudget) -> String {
+        if route.per_user {
+            let user_id = req
+                .headers()
+                .get("Authorization")
+                .and_then(|h| h.to_str().ok())
+                .and_then(|h| h.strip_prefix("Bearer "))
+                .and_then(|token| self.jwt_service.validate_token(token).ok())
+                .map(|claims| claims.sub);
+            if let Some(user_id) = user_id {
+                return format!("{}:user:{}", route.name, user_id);
+            }
+        }
+
+        let ip = req.connection_info().realip_remote_addr().unwrap_or("unknown").to_string();
+        format!("{}:ip:{}", route.name, ip)
+    }
+}
+
+/// Applies `ROUTE_BUDGETS` and sets `RateLimit-*` headers on every limited response.
+/// Refused requests get 429 with `Retry-After`. If the store fails the request is let
+/// through.
+pub struct RateLimitMiddleware {
+    limiter: RateLimiter,
+}
+
+impl RateLimitMiddleware {
+    pub fn new(limiter: RateLimiter) -> Self {
+        Self { limiter }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimitMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddlewareService {
+            service: Rc::new(service),
+            limiter: self.limiter.clone(),
+        }))
+    }
+}
+
+pub struct RateLimitMiddlewareService<S> {
+    service: Rc<S>,
+    limiter: RateLimiter,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let limiter = self.limiter.clone();
+
+        Box::pin(async move {
+            let Some(route) = RouteBudget::matching(req.method().as_str(), req.path()) else {
+                return Ok(service.call(req).await?.map_into_left_body());
+            };
+
+            let budget = limiter.budget(route).await;
+            let key = limiter.client_key(&req, route);
+
+            let decision = match limiter.store.check(&key, &budget).await {
+                Ok(decision) => decision,
+                Err(e) => {
+                    warn!("Rate limit check failed, allowing request: {}", e);
+                    return Ok(service.call(req).await?.map_into_left_body());
+                }
+            };
+
+            if !decision.allowed {
+                debug!("Rate limit budget {} exhausted for {}", route.name, key);
+
+                let retry_after = decision.retry_after.map(ceil_seconds).unwrap_or(1).max(1);
+                let mut response = AppError::RateLimited {
+                    message: "Too many requests. Please try again later.".to_string(),
+                    retry_after_seconds: retry_after as i64,
+                }
+                .error_response();
+                decision.insert_headers(response.headers_mut(), &budget);
+
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+
+            let mut res = service.call(req).await?;
+            decision.insert_headers(res.headers_mut(), &budget);
+
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcra_allows_a_full_window_then_spaces_requests() {
+        let budget = RateLimitBudget::new(5, 60);
+        let store = MemoryRateLimitStore::new();
+        let now = 1_000_000;
+
+        for expected_remaining in (0..5).rev() {
+            let decision = store.check_at("client", &budget, now);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, expected_remaining);
+        }
+
+        let refused = store.check_at("client", &budget, now);
+        assert!(!refused.allowed);
+        assert_eq!(refused.remaining, 0);
+        assert_eq!(refused.retry_after, Some(Duration::from_secs(12)));
+        assert_eq!(refused.reset_after, Duration::from_secs(60));
+
+        // One emission interval later exactly one request is available again
+        assert!(store.check_at("client", &budget, now + 12_000).allowed);
+        assert!(!store.check_at("client", &budget, now + 12_000).allowed);
+
+        // Other clients are unaffected
+        assert!(store.check_at("other", &budget, now).allowed);
+    }
+
+    #[test]
+    fn test_gcra_has_no_window_boundary_burst() {
+        let budget = RateLimitBudget::new(10, 60);
+        let store = MemoryRateLimitStore::new();
+        let start = 5_000_000;
+
+        let allowed_before = (0..10).filter(|_| store.check_at("client", &budget, start + 59_000).allowed).count();
+        let allowed_after = (0..10).filter(|_| store.check_at("client", &budget, start + 61_000).allowed).count();
+
+        // A fixed window would allow all 20 around the boundary
+        assert_eq!(allowed_before, 10);
+        assert_eq!(allowed_after, 0);
+    }
+
+    #[test]
+    fn test_refused_requests_do_not_use_the_budget() {
+        let budget = RateLimitBudget::new(2, 10);
+        let now = 0;
+
+        let (_, tat) = gcra(None, now, &budget);
+        let (_, tat) = gcra(tat, now, &budget);
+        let (refused, unchanged) = gcra(tat, now, &budget);
+        assert!(!refused.allowed);
+        assert_eq!(unchanged, None);
+
+        let (later, _) = gcra(tat, now + 5_000, &budget);
+        assert!(later.allowed);
+    }
+
+    #[test]
+    fn test_route_budgets_match_in_order() {
+        let name = |method, path| RouteBudget::matching(method, path).map(|route| route.name);
+
+        assert_eq!(name("POST", "/api/v1/auth/login"), Some("login"));
+        assert_eq!(name("GET", "/api/v1/auth/login"), Some("api"));
+        assert_eq!(name("POST", "/api/v1/raffles/4f7c/buy-boxes"), Some("buy_boxes"));
+        assert_eq!(name("POST", "/api/v1/raffles/4f7c/buy-boxes/"), Some("buy_boxes"));
+        assert_eq!(name("POST", "/api/v1/raffles//buy-boxes"), Some("api"));
+        assert_eq!(name("GET", "/api/v1/verify/4f7c"), Some("verify"));
+        assert_eq!(name("GET", "/api/v1/verify/4f7c/replay"), Some("verify"));
+        assert_eq!(name("GET", "/api/v1/raffles"), Some("api"));
+        assert_eq!(name("GET", "/api/v1"), Some("api"));
+        assert_eq!(name("GET", "/api/v10/raffles"), None);
+        assert_eq!(name("GET", "/health"), None);
+    }
+
+    #[test]
+    fn test_budget_settings_fall_back_to_defaults() {
+        let login = RouteBudget::find("login").unwrap();
+
+        assert_eq!(login.budget_from(None, None), login.default);
+        assert_eq!(login.budget_from(Some(20), Some(300)), RateLimitBudget::new(20, 300));
+        assert_eq!(login.budget_from(Some(20), None), RateLimitBudget::new(20, 60));
+        assert_eq!(login.budget_from(Some(0), Some(300)), login.default);
+        assert_eq!(login.budget_from(Some(-1), None), login.default);
+        assert_eq!(login.budget_from(Some(10), Some(7 * 24 * 3600)), login.default);
+    }
+
+    #[test]
+    fn test_headers_round_reset_up() {
+        let budget = RateLimitBudget::new(3, 10);
+        let decision = RateLimitDecision::new(&budget, true, 3_334, 0);
+        let mut headers = actix_web::http::header::HeaderMap::new();
+        decision.insert_headers(&mut headers, &budget);
+
+        assert_eq!(headers.get("RateLimit-Limit").unwrap(), "3");
+        assert_eq!(headers.get("RateLimit-Remaining").unwrap(), "2");
+        assert_eq!(headers.get("RateLimit-Reset").unwrap(), "4");
+        assert_eq!(headers.get("RateLimit-Policy").unwrap(), "3;w=10");
+    }
+}
@@ -688,7 +688,7 @@ impl SellerMetrics {
             SELECT COALESCE(SUM(amount), 0)
             FROM transactions
             WHERE seller_id = $1 
-            AND type IN ('seller_subscription_fee', 'seller_listing_fee', 'seller_transaction_fee')
+            AND type IN ('seller_subscription_fee', 'seller_listing_fee', 'seller_transaction_fee', 'seller_api_usage_fee')
             AND created_at >= $2 AND created_at <= $3
             \"#,
             seller_id,
@@ -0,0 +1,573 @@
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+
+/// Monthly partner API quotas and overage pricing for a seller
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ApiRatePlan {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub included_requests: i64,
+    pub included_webhooks: i64,
+    pub request_overage_per_1000: Decimal,
+    pub webhook_overage_per_1000: Decimal,
+    /// Refuse requests past the quota instead of billing them
+    pub hard_limit: bool,
+    pub is_default: bool,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiRatePlanRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub included_requests: i64,
+    pub included_webhooks: i64,
+    pub request_overage_per_1000: Decimal,
+    pub webhook_overage_per_1000: Decimal,
+    #[serde(default)]
+    pub hard_limit: bool,
+    #[serde(default)]
+    pub is_default: bool,
+    #[serde(default = "default_true")]
+    pub is_active: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Where a metered request stands against the seller's monthly request quota
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaOutcome {
+    Within,
+    /// Past the quota; billed as overage
+    Overage,
+    /// Past the quota of a hard-limited plan; refused
+    Exceeded,
+}
+
+/// Overage of a month's usage on a plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct OverageCharge {
+    pub overage_requests: i64,
+    pub overage_webhooks: i64,
+    pub amount: Decimal,
+}
+
+/// A seller's billable usage in one month
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SellerApiUsage {
+    pub seller_id: Uuid,
+    pub period_start: NaiveDate,
+    pub requests: i64,
+    pub webhooks_delivered: i64,
+}
+
+/// Requests made with one API key in one month
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ApiKeyUsage {
+    pub api_key_id: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    pub period_start: NaiveDate,
+    pub requests: i64,
+}
+
+/// Overage billed to a seller for a closed month
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ApiUsageCharge {
+    pub id: Uuid,
+    pub seller_id: Uuid,
+    pub period_start: NaiveDate,
+    pub rate_plan_id: Uuid,
+    pub plan_name: String,
+    pub requests: i64,
+    pub webhooks_delivered: i64,
+    pub included_requests: i64,
+    pub included_webhooks: i64,
+    pub overage_requests: i64,
+    pub overage_webhooks: i64,
+    pub amount: Decimal,
+    pub transaction_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+const PLAN_COLUMNS: &str = "id, name, description, included_requests, included_webhooks, request_overage_per_1000, \
+    webhook_overage_per_1000, hard_limit, is_default, is_active, created_at, updated_at";
+
+const CHARGE_COLUMNS: &str = "id, seller_id, period_start, rate_plan_id, plan_name, requests, webhooks_delivered, \
+    included_requests, included_webhooks, overage_requests, overage_webhooks, amount, transaction_id, created_at";
+
+/// First day of the calendar month (UTC) containing `at`
+pub fn period_start(at: DateTime<Utc>) -> NaiveDate {
+    NaiveDate::from_ymd_opt(at.year(), at.month(), 1).expect("first of the month is a valid date")
+}
+
+/// First day of the month after `period`
+pub fn next_period(period: NaiveDate) -> NaiveDate {
+    let (year, month) = if period.month() == 12 { (period.year() + 1, 1) } else { (period.year(), period.month() + 1) };
+    NaiveDate::from_ymd_opt(year, month, 1).expect("first of the month is a valid date")
+}
+
+/// First day of the month before `period`
+pub fn previous_period(period: NaiveDate) -> NaiveDate {
+    let (year, month) = if period.month() == 1 { (period.year() - 1, 12) } else { (period.year(), period.month() - 1) };
+    NaiveDate::from_ymd_opt(year, month, 1).expect("first of the month is a valid date")
+}
+
+/// When a month's quota resets
+pub fn period_end(period: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&next_period(period).and_hms_opt(0, 0, 0).expect("midnight is a valid time"))
+}
+
+impl ApiRatePlanRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        let name = self.name.trim();
+        if name.is_empty() || name.len() > 100 {
+            return Err(AppError::Validation("Plan name must be 1 to 100 characters".to_string()));
+        }
+        if self.included_requests < 0 || self.included_webhooks < 0 {
+            return Err(AppError::Validation("Included quotas cannot be negative".to_string()));
+        }
+        if self.request_overage_per_1000.is_sign_negative() || self.webhook_overage_per_1000.is_sign_negative() {
+            return Err(AppError::Validation("Overage prices cannot be negative".to_string()));
+        }
+        if self.is_default && !self.is_active {
+            return Err(AppError::Validation("The default plan must be active".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl ApiRatePlan {
+    /// Where the seller stands after `used` requests this month, this one included
+    pub fn check_requests(&self, used: i64) -> QuotaOutcome {
+        if used <= self.included_requests {
+            QuotaOutcome::Within
+        } else if self.hard_limit {
+            QuotaOutcome::Exceeded
+        } else {
+            QuotaOutcome::Overage
+        }
+    }
+
+    /// Overage for a month's usage. Requests refused by a hard limit are never billed.
+    pub fn overage(&self, requests: i64, webhooks: i64) -> OverageCharge {
+        let overage_requests = if self.hard_limit { 0 } else { (requests - self.included_requests).max(0) };
+        let overage_webhooks = (webhooks - self.included_webhooks).max(0);
+        let per_thousand = Decimal::from(1000);
+
+        let amount = (Decimal::from(overage_requests) * self.request_overage_per_1000 / per_thousand
+            + Decimal::from(overage_webhooks) * self.webhook_overage_per_1000 / per_thousand)
+            .round_dp(2);
+
+        OverageCharge { overage_requests, overage_webhooks, amount }
+    }
+
+    pub async fn list(pool: &PgPool) -> Result<Vec<Self>, AppError> {
+        let plans = sqlx::query_as::<_, ApiRatePlan>(&format!(
+            "SELECT {} FROM api_rate_plans ORDER BY included_requests, name",
+            PLAN_COLUMNS
+        ))
+        .fetch_all(pool)
+        .await?;
+
+        Ok(plans)
+    }
+
+    pub async fn find(pool: &PgPool, id: Uuid) -> Result<Option<Self>, AppError> {
+        let plan = sqlx::query_as::<_, ApiRatePlan>(&format!("SELECT {} FROM api_rate_plans WHERE id = $1", PLAN_COLUMNS))
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(plan)
+    }
+
+    /// The seller's assigned plan, or the default plan
+    pub async fn for_seller(pool: &PgPool, seller_id: Uuid) -> Result<Self, AppError> {
+        let plan = sqlx::query_as::<_, ApiRatePlan>(&format!(
+            "SELECT {} FROM api_rate_plans WHERE id = COALESCE( \
+                 (SELECT rate_plan_id FROM seller_api_plans WHERE seller_id = $1), \
+                 (SELECT id FROM api_rate_plans WHERE is_default) \
+             )",
+            PLAN_COLUMNS
+        ))
+        .bind(seller_id)
+        .fetch_optional(pool)
+        .await?;
+
+        plan.ok_or_else(|| AppError::Internal("No default API rate plan is configured".to_string()))
+    }
+
+    pub async fn create(pool: &PgPool, request: &ApiRatePlanRequest) -> Result<Self, AppError> {
+        request.validate()?;
+
+        let mut tx = pool.begin().await?;
+        if request.is_default {
+            clear_default(&mut tx).await?;
+        }
+
+        let plan = sqlx::query_as::<_, ApiRatePlan>(&format!(
+            "INSERT INTO api_rate_plans (name, description, included_requests, included_webhooks, \
+             request_overage_per_1000, webhook_overage_per_1000, hard_limit, is_default, is_active) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING {}",
+            PLAN_COLUMNS
+        ))
+        .bind(request.name.trim())
+        .bind(&request.description)
+        .bind(request.included_requests)
+        .bind(request.included_webhooks)
+        .bind(request.request_overage_per_1000)
+        .bind(request.webhook_overage_per_1000)
+        .bind(request.hard_limit)
+        .bind(request.is_default)
+        .bind(request.is_active)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(plan_conflict)?;
+
+        tx.commit().await?;
+
+        Ok(plan)
+    }
+
+    /// Replace a plan's terms. Applies to months not yet closed.
+    pub async fn update(pool: &PgPool, id: Uuid, request: &ApiRatePlanRequest) -> Result<Self, AppError> {
+        request.validate()?;
+
+        let mut tx = pool.begin().await?;
+
+        let current = sqlx::query_scalar::<_, bool>("SELECT is_default FROM api_rate_plans WHERE id = $1 FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Rate plan not found".to_string()))?;
+
+        if current && !request.is_default {
+            return Err(AppError::Validation(
+                "Make another plan the default instead of unsetting the default plan".to_string(),
+            ));
+        }
+        if request.is_default && !current {
+            clear_default(&mut tx).await?;
+        }
+        if !request.is_active {
+            let assigned = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM seller_api_plans WHERE rate_plan_id = $1")
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?;
+            if assigned > 0 {
+                return Err(AppError::Conflict(format!(
+                    "{} seller(s) are still on this plan; move them before deactivating it",
+                    assigned
+                )));
+            }
+        }
+
+        let plan = sqlx::query_as::<_, ApiRatePlan>(&format!(
+            "UPDATE api_rate_plans SET name = $2, description = $3, included_requests = $4, included_webhooks = $5, \
+             request_overage_per_1000 = $6, webhook_overage_per_1000 = $7, hard_limit = $8, is_default = $9, \
+             is_active = $10 WHERE id = $1 RETURNING {}",
+            PLAN_COLUMNS
+        ))
+        .bind(id)
+        .bind(request.name.trim())
+        .bind(&request.description)
+        .bind(request.included_requests)
+        .bind(request.included_webhooks)
+        .bind(request.request_overage_per_1000)
+        .bind(request.webhook_overage_per_1000)
+        .bind(request.hard_limit)
+        .bind(request.is_default)
+        .bind(request.is_active)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(plan_conflict)?;
+
+        tx.commit().await?;
+
+        Ok(plan)
+    }
+
+    /// Put a seller on a plan
+    pub async fn assign(pool: &PgPool, seller_id: Uuid, plan_id: Uuid, assigned_by: Uuid) -> Result<(), AppError> {
+        sqlx::query(
+            "INSERT INTO seller_api_plans (seller_id, rate_plan_id, assigned_by) VALUES ($1, $2, $3) \
+             ON CONFLICT (seller_id) DO UPDATE SET rate_plan_id = EXCLUDED.rate_plan_id, \
+             assigned_by = EXCLUDED.assigned_by, assigned_at = NOW()",
+        )
+        .bind(seller_id)
+        .bind(plan_id)
+        .bind(assigned_by)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+async fn clear_default(tx: &mut Transaction<'_, Postgres>) -> Result<(), AppError> {
+    sqlx::query("UPDATE api_rate_plans SET is_default = FALSE WHERE is_default")
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+fn plan_conflict(e: sqlx::Error) -> AppError {
+    match &e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            AppError::Conflict("A rate plan with that name already exists".to_string())
+        }
+        _ => e.into(),
+    }
+}
+
+impl SellerApiUsage {
+    /// Count a request made with a production key; returns the seller's requests this month
+    pub async fn record_request(
+        pool: &PgPool,
+        api_key_id: Uuid,
+        seller_id: Uuid,
+        period: NaiveDate,
+    ) -> Result<i64, AppError> {
+        let requests = sqlx::query_scalar::<_, i64>(
+            "WITH key_usage AS ( \
+                 INSERT INTO api_key_usage (api_key_id, period_start, requests) VALUES ($1, $3, 1) \
+                 ON CONFLICT (api_key_id, period_start) DO UPDATE SET requests = api_key_usage.requests + 1 \
+             ) \
+             INSERT INTO seller_api_usage (seller_id, period_start, requests) VALUES ($2, $3, 1) \
+             ON CONFLICT (seller_id, period_start) DO UPDATE SET requests = seller_api_usage.requests + 1 \
+             RETURNING requests",
+        )
+        .bind(api_key_id)
+        .bind(seller_id)
+        .bind(period)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(requests)
+    }
+
+    /// Count a request made with a key that is not billed to a seller
+    pub async fn record_key_request(pool: &PgPool, api_key_id: Uuid, period: NaiveDate) -> Result<(), AppError> {
+        sqlx::query(
+            "INSERT INTO api_key_usage (api_key_id, period_start, requests) VALUES ($1, $2, 1) \
+             ON CONFLICT (api_key_id, period_start) DO UPDATE SET requests = api_key_usage.requests + 1",
+        )
+        .bind(api_key_id)
+        .bind(period)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Count a webhook delivered to one of the seller's endpoints
+    pub async fn record_webhook(pool: &PgPool, seller_id: Uuid, period: NaiveDate) -> Result<(), AppError> {
+        sqlx::query(
+            "INSERT INTO seller_api_usage (seller_id, period_start, webhooks_delivered) VALUES ($1, $2, 1) \
+             ON CONFLICT (seller_id, period_start) DO UPDATE SET webhooks_delivered = seller_api_usage.webhooks_delivered + 1",
+        )
+        .bind(seller_id)
+        .bind(period)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn find(pool: &PgPool, seller_id: Uuid, period: NaiveDate) -> Result<Self, AppError> {
+        let usage = sqlx::query_as::<_, SellerApiUsage>(
+            "SELECT seller_id, period_start, requests, webhooks_delivered FROM seller_api_usage \
+             WHERE seller_id = $1 AND period_start = $2",
+        )
+        .bind(seller_id)
+        .bind(period)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(usage.unwrap_or(SellerApiUsage { seller_id, period_start: period, requests: 0, webhooks_delivered: 0 }))
+    }
+
+    /// Sellers with usage in a month and no charge for it yet
+    pub async fn unbilled(pool: &PgPool, period: NaiveDate, limit: i64) -> Result<Vec<Self>, AppError> {
+        let usage = sqlx::query_as::<_, SellerApiUsage>(
+            "SELECT u.seller_id, u.period_start, u.requests, u.webhooks_delivered FROM seller_api_usage u \
+             WHERE u.period_start = $1 AND NOT EXISTS ( \
+                 SELECT 1 FROM seller_api_usage_charges c WHERE c.seller_id = u.seller_id AND c.period_start = u.period_start \
+             ) \
+             ORDER BY u.seller_id LIMIT $2",
+        )
+        .bind(period)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(usage)
+    }
+}
+
+impl ApiKeyUsage {
+    /// Requests per key for a seller's keys in a month
+    pub async fn list_for_seller(pool: &PgPool, seller_id: Uuid, period: NaiveDate) -> Result<Vec<Self>, AppError> {
+        let usage = sqlx::query_as::<_, ApiKeyUsage>(
+            "SELECT k.id AS api_key_id, k.name, k.key_prefix, u.period_start, u.requests \
+             FROM api_key_usage u JOIN partner_api_keys k ON k.id = u.api_key_id \
+             WHERE k.seller_id = $1 AND u.period_start = $2 \
+             ORDER BY u.requests DESC",
+        )
+        .bind(seller_id)
+        .bind(period)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(usage)
+    }
+}
+
+impl ApiUsageCharge {
+    /// Record a seller's charge for a month; None if the month was already charged
+    pub async fn create(
+        pool: &PgPool,
+        usage: &SellerApiUsage,
+        plan: &ApiRatePlan,
+        charge: &OverageCharge,
+    ) -> Result<Option<Self>, AppError> {
+        let created = sqlx::query_as::<_, ApiUsageCharge>(&format!(
+            "INSERT INTO seller_api_usage_charges (seller_id, period_start, rate_plan_id, plan_name, requests, \
+             webhooks_delivered, included_requests, included_webhooks, overage_requests, overage_webhooks, amount) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) \
+             ON CONFLICT (seller_id, period_start) DO NOTHING RETURNING {}",
+            CHARGE_COLUMNS
+        ))
+        .bind(usage.seller_id)
+        .bind(usage.period_start)
+        .bind(plan.id)
+        .bind(&plan.name)
+        .bind(usage.requests)
+        .bind(usage.webhooks_delivered)
+        .bind(plan.included_requests)
+        .bind(plan.included_webhooks)
+        .bind(charge.overage_requests)
+        .bind(charge.overage_webhooks)
+        .bind(charge.amount)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(created)
+    }
+
+    /// Charges with an amount whose fee transaction has not been posted
+    pub async fn unposted(pool: &PgPool, limit: i64) -> Result<Vec<Self>, AppError> {
+        let charges = sqlx::query_as::<_, ApiUsageCharge>(&format!(
+            "SELECT {} FROM seller_api_usage_charges WHERE transaction_id IS NULL AND amount > 0 \
+             ORDER BY created_at LIMIT $1",
+            CHARGE_COLUMNS
+        ))
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(charges)
+    }
+
+    pub async fn set_transaction(pool: &PgPool, id: Uuid, transaction_id: Uuid) -> Result<(), AppError> {
+        sqlx::query("UPDATE seller_api_usage_charges SET transaction_id = $2 WHERE id = $1 AND transaction_id IS NULL")
+            .bind(id)
+            .bind(transaction_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Charges, newest month first, optionally for one seller or month
+    pub async fn list(
+        pool: &PgPool,
+        seller_id: Option<Uuid>,
+        period: Option<NaiveDate>,
+        limit: i64,
+    ) -> Result<Vec<Self>, AppError> {
+        let charges = sqlx::query_as::<_, ApiUsageCharge>(&format!(
+            "SELECT {} FROM seller_api_usage_charges \
+             WHERE ($1::uuid IS NULL OR seller_id = $1) AND ($2::date IS NULL OR period_start = $2) \
+             ORDER BY period_start DESC, amount DESC LIMIT $3",
+            CHARGE_COLUMNS
+        ))
+        .bind(seller_id)
+        .bind(period)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(charges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(hard_limit: bool) -> ApiRatePlan {
+        ApiRatePlan {
+            id: Uuid::new_v4(),
+            name: "Growth".to_string(),
+            description: None,
+            included_requests: 1000,
+            included_webhooks: 100,
+            request_overage_per_1000: Decimal::new(50, 2),
+            webhook_overage_per_1000: Decimal::new(100, 2),
+            hard_limit,
+            is_default: false,
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_request_quota_outcomes() {
+        assert_eq!(plan(false).check_requests(1000), QuotaOutcome::Within);
+        assert_eq!(plan(false).check_requests(1001), QuotaOutcome::Overage);
+        assert_eq!(plan(true).check_requests(1000), QuotaOutcome::Within);
+        assert_eq!(plan(true).check_requests(1001), QuotaOutcome::Exceeded);
+    }
+
+    #[test]
+    fn test_overage_is_priced_per_thousand() {
+        let charge = plan(false).overage(3500, 150);
+        assert_eq!(charge.overage_requests, 2500);
+        assert_eq!(charge.overage_webhooks, 50);
+        // 2500 * 0.50 / 1000 + 50 * 1.00 / 1000
+        assert_eq!(charge.amount, Decimal::new(130, 2));
+
+        assert_eq!(plan(false).overage(900, 10).amount, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_hard_limited_plans_never_bill_requests() {
+        let charge = plan(true).overage(5000, 100);
+        assert_eq!(charge.overage_requests, 0);
+        assert_eq!(charge.amount, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_periods_are_calendar_months() {
+        let at = Utc.with_ymd_and_hms(2026, 12, 31, 23, 59, 59).unwrap();
+        let period = period_start(at);
+        assert_eq!(period, NaiveDate::from_ymd_opt(2026, 12, 1).unwrap());
+        assert_eq!(next_period(period), NaiveDate::from_ymd_opt(2027, 1, 1).unwrap());
+        assert_eq!(previous_period(NaiveDate::from_ymd_opt(2027, 1, 1).unwrap()), period);
+        assert_eq!(period_end(period), Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap());
+    }
+}
@@ -0,0 +1,195 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use uuid::Uuid;
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use crate::utils::crypto::{generate_secure_token, hash_token, secure_compare};
+
+/// Single-use tokens mailed to users. Only the SHA-256 hash of a token is stored, and
+/// issuing a token retires the user's earlier ones of the same kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthTokenKind {
+    PasswordReset,
+    EmailVerification,
+}
+
+/// Why a stored token can no longer be used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenRejection {
+    Used,
+    /// A newer token of the same kind was issued
+    Superseded,
+    Expired,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AuthToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// SHA-256 of the token; the token itself is never stored
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    pub is_used: bool,
+    pub invalidated_at: Option<DateTime<Utc>>,
+}
+
+impl AuthTokenKind {
+    fn table(&self) -> &'static str {
+        match self {
+            Self::PasswordReset => "password_reset_tokens",
+            Self::EmailVerification => "email_verification_tokens",
+        }
+    }
+
+    /// Setting holding the token lifetime, its default and the longest lifetime accepted
+    fn ttl_setting(&self) -> (&'static str, i64, i64) {
+        match self {
+            // Minutes
+            Self::PasswordReset => ("password_reset_token_ttl_minutes", 60, 24 * 60),
+            // Hours
+            Self::EmailVerification => ("email_verification_token_ttl_hours", 24, 7 * 24),
+        }
+    }
+
+    /// Lifetime for a setting value; missing or non-positive values use the default
+    fn ttl_from(&self, value: Option<i64>) -> Duration {
+        let (_, default, max) = self.ttl_setting();
+        let value = value.filter(|v| *v > 0).unwrap_or(default).min(max);
+        match self {
+            Self::PasswordReset => Duration::minutes(value),
+            Self::EmailVerification => Duration::hours(value),
+        }
+    }
+
+    /// How long newly issued tokens stay valid
+    pub async fn ttl(&self, pool: &PgPool) -> Result<Duration, AppError> {
+        let (key, _, _) = self.ttl_setting();
+        let setting = SystemSetting::get_by_key(pool, key).await?;
+        Ok(self.ttl_from(setting.and_then(|s| s.get_int_value())))
+    }
+}
+
+impl AuthToken {
+    /// Issue a token for the user, retiring their outstanding ones. Returns the token to
+    /// send; only its hash is kept.
+    pub async fn issue(
+        tx: &mut Transaction<'_, Postgres>,
+        kind: AuthTokenKind,
+        user_id: Uuid,
+        ttl: Duration,
+    ) -> Result<String, AppError> {
+        // Concurrent requests would both find nothing to retire and trip the one-live-token index
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(format!("{}:{}", kind.table(), user_id))
+            .execute(&mut **tx)
+            .await?;
+
+        sqlx::query(&format!(
+            "UPDATE {} SET invalidated_at = NOW() WHERE user_id = $1 AND is_used = FALSE AND invalidated_at IS NULL",
+            kind.table()
+        ))
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+        let token = generate_secure_token();
+
+        sqlx::query(&format!(
+            "INSERT INTO {} (user_id, token, expires_at) VALUES ($1, $2, $3)",
+            kind.table()
+        ))
+        .bind(user_id)
+        .bind(hash_token(&token))
+        .bind(Utc::now() + ttl)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(token)
+    }
+
+    /// Find and lock the stored token matching `token`, whatever its state
+    pub async fn find_for_update(
+        tx: &mut Transaction<'_, Postgres>,
+        kind: AuthTokenKind,
+        token: &str,
+    ) -> Result<Option<Self>, AppError> {
+        let token_hash = hash_token(token.trim());
+
+        let record = sqlx::query_as::<_, AuthToken>(&format!(
+            "SELECT id, user_id, token, expires_at, COALESCE(is_used, FALSE) AS is_used, invalidated_at \
+             FROM {} WHERE token = $1 FOR UPDATE",
+            kind.table()
+        ))
+        .bind(&token_hash)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        // The lookup is by hash; compare again without an early exit before trusting the row
+        Ok(record.filter(|record| secure_compare(&record.token, &token_hash)))
+    }
+
+    /// Whether the token can still be used at `now`
+    pub fn check(&self, now: DateTime<Utc>) -> Result<(), TokenRejection> {
+        if self.is_used {
+            Err(TokenRejection::Used)
+        } else if self.invalidated_at.is_some() {
+            Err(TokenRejection::Superseded)
+        } else if self.expires_at <= now {
+            Err(TokenRejection::Expired)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Use the token up; fails if another request used it first
+    pub async fn mark_used(&self, tx: &mut Transaction<'_, Postgres>, kind: AuthTokenKind) -> Result<(), AppError> {
+        let result = sqlx::query(&format!(
+            "UPDATE {} SET is_used = TRUE WHERE id = $1 AND is_used = FALSE AND invalidated_at IS NULL",
+            kind.table()
+        ))
+        .bind(self.id)
+        .execute(&mut **tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::Authentication("Token has already been used".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(is_used: bool, invalidated: bool, expires_in_minutes: i64) -> AuthToken {
+        AuthToken {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            token: hash_token("token"),
+            expires_at: Utc::now() + Duration::minutes(expires_in_minutes),
+            is_used,
+            invalidated_at: invalidated.then(Utc::now),
+        }
+    }
+
+    #[test]
+    fn test_ttl_defaults_and_caps() {
+        assert_eq!(AuthTokenKind::PasswordReset.ttl_from(None), Duration::minutes(60));
+        assert_eq!(AuthTokenKind::PasswordReset.ttl_from(Some(15)), Duration::minutes(15));
+        assert_eq!(AuthTokenKind::PasswordReset.ttl_from(Some(0)), Duration::minutes(60));
+        assert_eq!(AuthTokenKind::PasswordReset.ttl_from(Some(100_000)), Duration::hours(24));
+        assert_eq!(AuthTokenKind::EmailVerification.ttl_from(None), Duration::hours(24));
+        assert_eq!(AuthTokenKind::EmailVerification.ttl_from(Some(1_000)), Duration::days(7));
+    }
+
+    #[test]
+    fn test_check_reports_why_a_token_is_refused() {
+        let now = Utc::now();
+        assert_eq!(token(false, false, 30).check(now), Ok(()));
+        assert_eq!(token(true, false, 30).check(now), Err(TokenRejection::Used));
+        assert_eq!(token(false, true, 30).check(now), Err(TokenRejection::Superseded));
+        assert_eq!(token(false, false, -1).check(now), Err(TokenRejection::Expired));
+    }
+}
@@ -0,0 +1,218 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use crate::error::AppError;
+
+/// First retry waits this long; each further attempt doubles it
+const BASE_RETRY_DELAY_SECONDS: i64 = 30;
+const MAX_RETRY_DELAY_SECONDS: i64 = 6 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DeadLetterStatus {
+    /// Waiting for the retry worker
+    Pending,
+    /// Persisted on a later attempt
+    Resolved,
+    /// Out of automatic attempts; only an admin retry picks it up again
+    Abandoned,
+}
+
+/// A blockchain event whose persistence failed, kept so it can be replayed
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BlockchainEventDeadLetter {
+    pub id: i64,
+    pub event_type: String,
+    pub blockchain_raffle_id: i64,
+    pub block_number: i64,
+    pub transaction_hash: String,
+    /// Position of the log in its block; several events of a type can share a transaction
+    pub log_index: i64,
+    pub payload: serde_json::Value,
+    pub last_error: String,
+    pub attempts: i32,
+    pub status: DeadLetterStatus,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_attempted_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+const COLUMNS: &str = "id, event_type, blockchain_raffle_id, block_number, transaction_hash, log_index, payload, \
+    last_error, attempts, status, next_attempt_at, last_attempted_at, resolved_at, created_at";
+
+impl BlockchainEventDeadLetter {
+    /// Store a failed event; an event that is already dead-lettered counts another attempt
+    pub async fn record(
+        pool: &PgPool,
+        event_type: &str,
+        blockchain_raffle_id: i64,
+        block_number: i64,
+        transaction_hash: &str,
+        log_index: i64,
+        payload: serde_json::Value,
+        error: &str,
+    ) -> Result<Self, AppError> {
+        let letter = sqlx::query_as::<_, Self>(&format!(
+            r#"
+            INSERT INTO blockchain_event_dead_letters
+                (event_type, blockchain_raffle_id, block_number, transaction_hash, log_index, payload, last_error,
+                 next_attempt_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW() + make_interval(secs => $8))
+            ON CONFLICT (transaction_hash, event_type, blockchain_raffle_id, log_index) DO UPDATE
+            SET payload = EXCLUDED.payload,
+                last_error = EXCLUDED.last_error,
+                attempts = blockchain_event_dead_letters.attempts + 1,
+                status = 'pending',
+                next_attempt_at = EXCLUDED.next_attempt_at,
+                last_attempted_at = NOW(),
+                resolved_at = NULL
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(event_type)
+        .bind(blockchain_raffle_id)
+        .bind(block_number)
+        .bind(transaction_hash)
+        .bind(log_index)
+        .bind(payload)
+        .bind(error)
+        .bind(retry_delay_seconds(1) as f64)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(letter)
+    }
+
+    /// Dead letters, newest first, optionally by status
+    pub async fn list(
+        pool: &PgPool,
+        status: Option<DeadLetterStatus>,
+        limit: i64,
+    ) -> Result<Vec<Self>, AppError> {
+        let letters = sqlx::query_as::<_, Self>(&format!(
+            r#"
+            SELECT {}
+            FROM blockchain_event_dead_letters
+            WHERE ($1::varchar IS NULL OR status = $1)
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+            COLUMNS
+        ))
+        .bind(status)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(letters)
+    }
+
+    /// Pending dead letters whose next attempt is due, oldest block first
+    pub async fn due(pool: &PgPool, limit: i64) -> Result<Vec<Self>, AppError> {
+        let letters = sqlx::query_as::<_, Self>(&format!(
+            r#"
+            SELECT {}
+            FROM blockchain_event_dead_letters
+            WHERE status = 'pending' AND next_attempt_at <= NOW()
+            ORDER BY block_number, id
+            LIMIT $1
+            "#,
+            COLUMNS
+        ))
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(letters)
+    }
+
+    pub async fn mark_resolved(pool: &PgPool, id: i64) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE blockchain_event_dead_letters
+            SET status = 'resolved', resolved_at = NOW(), last_attempted_at = NOW(), attempts = attempts + 1
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a failed retry; the letter is abandoned once it has used `max_attempts`
+    pub async fn mark_failed(pool: &PgPool, id: i64, error: &str, max_attempts: i32) -> Result<(), AppError> {
+        let attempts = sqlx::query_scalar::<_, i32>(
+            "SELECT attempts + 1 FROM blockchain_event_dead_letters WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+
+        let status = if attempts >= max_attempts {
+            DeadLetterStatus::Abandoned
+        } else {
+            DeadLetterStatus::Pending
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE blockchain_event_dead_letters
+            SET attempts = $2, status = $3, last_error = $4, last_attempted_at = NOW(),
+                next_attempt_at = NOW() + make_interval(secs => $5)
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(attempts)
+        .bind(status)
+        .bind(error)
+        .bind(retry_delay_seconds(attempts) as f64)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Queue a dead letter for the next retry run, even if it was abandoned
+    pub async fn schedule_retry(pool: &PgPool, id: i64) -> Result<Option<Self>, AppError> {
+        let letter = sqlx::query_as::<_, Self>(&format!(
+            r#"
+            UPDATE blockchain_event_dead_letters
+            SET status = 'pending', next_attempt_at = NOW()
+            WHERE id = $1 AND status <> 'resolved'
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(letter)
+    }
+}
+
+/// Wait before the next attempt once `attempts` have failed
+fn retry_delay_seconds(attempts: i32) -> i64 {
+    let doublings = (attempts.max(1) - 1).min(20) as u32;
+    (BASE_RETRY_DELAY_SECONDS << doublings).min(MAX_RETRY_DELAY_SECONDS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off_to_cap() {
+        assert_eq!(retry_delay_seconds(1), 30);
+        assert_eq!(retry_delay_seconds(2), 60);
+        assert_eq!(retry_delay_seconds(4), 240);
+        assert_eq!(retry_delay_seconds(30), MAX_RETRY_DELAY_SECONDS);
+    }
+}
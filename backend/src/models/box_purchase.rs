@@ -313,6 +313,7 @@ mod tests {
             username: "testuser".to_string(),
             email: "test@example.com".to_string(),
             phone_number: None,
+            referral_code: None,
         };
 
         let user = User::create(
@@ -334,6 +335,7 @@ mod tests {
             retail_price: Decimal::new(10000, 2), // $100.00
             cost_of_goods: Decimal::new(5000, 2), // $50.00
             stock_quantity: 1,
+            currency: None,
         };
 
         let item = Item::create(pool, None, item_request, None, None)
@@ -348,6 +350,14 @@ mod tests {
             total_winners: 1,
             grid_rows: 10,
             grid_cols: 10,
+            layout: None,
+            prize_tiers: Vec::new(),
+            pricing_curve: None,
+            starts_at: None,
+            ends_at: None,
+            deadline_action: Default::default(),
+            max_boxes_per_user: None,
+            credit_alternative: None,
         };
 
         let raffle = Raffle::create(pool, raffle_request, None)
@@ -0,0 +1,165 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+use crate::error::AppError;
+
+/// Boxes a reservation could not hold, and why
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BoxConflicts {
+    pub sold: Vec<i32>,
+    /// Held by another buyer's unexpired reservation
+    pub held: Vec<i32>,
+}
+
+impl BoxConflicts {
+    pub fn is_empty(&self) -> bool {
+        self.sold.is_empty() && self.held.is_empty()
+    }
+
+    /// Error naming every box that was taken
+    pub fn to_error(&self) -> AppError {
+        let mut reasons = Vec::new();
+        if !self.sold.is_empty() {
+            reasons.push(format!("{} already sold", describe_boxes(&self.sold)));
+        }
+        if !self.held.is_empty() {
+            reasons.push(format!("{} being bought by someone else", describe_boxes(&self.held)));
+        }
+
+        let mut message = reasons.join("; ");
+        if let Some(first) = message.get_mut(0..1) {
+            first.make_ascii_uppercase();
+        }
+
+        AppError::Conflict(message)
+    }
+}
+
+/// Holds on boxes while a purchase is paid for. A box is held by at most one reservation; an
+/// expired hold is taken over by the next buyer to reserve the box.
+pub struct BoxReservation;
+
+impl BoxReservation {
+    /// Hold every box for `reservation_id` until `expires_at`, or none of them. Returns the
+    /// boxes that were sold or held by someone else when the reservation failed.
+    pub async fn reserve(
+        pool: &PgPool,
+        reservation_id: Uuid,
+        raffle_id: Uuid,
+        user_id: Uuid,
+        box_numbers: &[i32],
+        expires_at: DateTime<Utc>,
+    ) -> Result<BoxConflicts, AppError> {
+        let mut tx = pool.begin().await?;
+
+        // Abandoned holds in this raffle are dropped as it is bought from
+        sqlx::query!(
+            "DELETE FROM box_reservations WHERE raffle_id = $1 AND expires_at <= NOW()",
+            raffle_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Concurrent reservations of a box queue on its key; the loser sees the winner's
+        // unexpired hold and reserves nothing for it
+        let reserved = sqlx::query_scalar!(
+            r#"
+            INSERT INTO box_reservations (raffle_id, box_number, reservation_id, user_id, expires_at)
+            SELECT $1, n, $2, $3, $4
+            FROM unnest($5::int[]) AS n
+            WHERE NOT EXISTS (SELECT 1 FROM box_purchases bp WHERE bp.raffle_id = $1 AND bp.box_number = n)
+            ON CONFLICT (raffle_id, box_number) DO UPDATE SET
+                reservation_id = EXCLUDED.reservation_id,
+                user_id = EXCLUDED.user_id,
+                expires_at = EXCLUDED.expires_at,
+                created_at = NOW()
+            WHERE box_reservations.expires_at <= NOW()
+            RETURNING box_number
+            "#,
+            raffle_id,
+            reservation_id,
+            user_id,
+            expires_at,
+            box_numbers
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let missing: Vec<i32> = box_numbers
+            .iter()
+            .copied()
+            .filter(|n| !reserved.contains(n))
+            .collect();
+
+        if missing.is_empty() {
+            tx.commit().await?;
+            return Ok(BoxConflicts::default());
+        }
+
+        tx.rollback().await?;
+
+        let sold = sqlx::query_scalar!(
+            "SELECT box_number FROM box_purchases WHERE raffle_id = $1 AND box_number = ANY($2) ORDER BY box_number",
+            raffle_id,
+            &missing
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let held = missing.into_iter().filter(|n| !sold.contains(n)).collect();
+
+        Ok(BoxConflicts { sold, held })
+    }
+
+    /// Turn a reservation into the purchase being written in the caller's transaction,
+    /// returning the boxes it still held
+    pub async fn confirm(executor: impl PgExecutor<'_>, reservation_id: Uuid) -> Result<Vec<i32>, AppError> {
+        let boxes = sqlx::query_scalar!(
+            "DELETE FROM box_reservations WHERE reservation_id = $1 RETURNING box_number",
+            reservation_id
+        )
+        .fetch_all(executor)
+        .await?;
+
+        Ok(boxes)
+    }
+
+    /// Free the boxes of a purchase that failed
+    pub async fn release(pool: &PgPool, reservation_id: Uuid) -> Result<(), AppError> {
+        sqlx::query!("DELETE FROM box_reservations WHERE reservation_id = $1", reservation_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// "box 4 is" or "boxes 4, 7 are"
+fn describe_boxes(boxes: &[i32]) -> String {
+    let numbers: Vec<String> = boxes.iter().map(|n| n.to_string()).collect();
+    match numbers.as_slice() {
+        [one] => format!("box {} is", one),
+        _ => format!("boxes {} are", numbers.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflict_message_names_every_box() {
+        let conflicts = BoxConflicts { sold: vec![3, 7], held: vec![9] };
+        assert!(!conflicts.is_empty());
+
+        match conflicts.to_error() {
+            AppError::Conflict(message) => assert_eq!(
+                message,
+                "Boxes 3, 7 are already sold; box 9 is being bought by someone else"
+            ),
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+
+        assert!(BoxConflicts::default().is_empty());
+    }
+}
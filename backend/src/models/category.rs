@@ -0,0 +1,811 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+
+pub const MAX_SLUG_LENGTH: usize = 60;
+const MAX_NAME_LENGTH: usize = 100;
+const MAX_ATTRIBUTE_KEY_LENGTH: usize = 40;
+const MAX_ATTRIBUTE_LABEL_LENGTH: usize = 60;
+const MAX_TEXT_VALUE_LENGTH: usize = 100;
+const MAX_ALLOWED_VALUES: usize = 50;
+/// Attribute filters on one raffle search
+pub const MAX_ATTRIBUTE_FILTERS: usize = 10;
+
+const CATEGORY_COLUMNS: &str = "id, slug, name, description, parent_category_id AS parent_id, display_order, \
+    is_active, created_at, updated_at";
+
+const ATTRIBUTE_COLUMNS: &str = "id, category_id, key, label, attribute_type, allowed_values, is_required, \
+    is_filterable, display_order, created_at, updated_at";
+
+/// Categories and their ancestors, walking up from the ids in `$1`
+const ANCESTORS_CTE: &str = "WITH RECURSIVE ancestors AS ( \
+        SELECT id, parent_category_id FROM item_categories WHERE id = ANY($1) \
+        UNION \
+        SELECT c.id, c.parent_category_id FROM item_categories c JOIN ancestors a ON c.id = a.parent_category_id \
+    )";
+
+/// A node of the category tree
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Category {
+    pub id: i64,
+    pub slug: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub parent_id: Option<i64>,
+    /// Lower comes first among siblings
+    pub display_order: i32,
+    /// Inactive categories and everything below them are hidden and can't be assigned
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A category with its subcategories, as the public taxonomy lists it
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CategoryNode {
+    pub id: i64,
+    pub slug: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub children: Vec<CategoryNode>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AttributeType {
+    Text,
+    Number,
+    Boolean,
+    /// One of the attribute's `allowed_values`
+    Enum,
+}
+
+/// A typed attribute items in a category can carry, such as brand, size or condition
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct CategoryAttribute {
+    pub id: i64,
+    /// None applies to every category
+    pub category_id: Option<i64>,
+    pub key: String,
+    pub label: String,
+    pub attribute_type: AttributeType,
+    pub allowed_values: Vec<String>,
+    pub is_required: bool,
+    /// Whether raffle search can filter on it
+    pub is_filterable: bool,
+    pub display_order: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateCategoryRequest {
+    pub name: String,
+    /// Derived from the name when left out
+    pub slug: Option<String>,
+    pub description: Option<String>,
+    pub parent_id: Option<i64>,
+    #[serde(default)]
+    pub display_order: i32,
+}
+
+/// Fields left out keep their current value
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateCategoryRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Move the category under another one
+    pub parent_id: Option<i64>,
+    /// Make the category top-level; wins over `parent_id`
+    #[serde(default)]
+    pub make_root: bool,
+    pub display_order: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateAttributeRequest {
+    /// None applies the attribute to every category
+    pub category_id: Option<i64>,
+    pub key: String,
+    pub label: String,
+    pub attribute_type: AttributeType,
+    #[serde(default)]
+    pub allowed_values: Vec<String>,
+    #[serde(default)]
+    pub is_required: bool,
+    #[serde(default)]
+    pub is_filterable: bool,
+    #[serde(default)]
+    pub display_order: i32,
+}
+
+/// Fields left out keep their current value. The key and type can't change, as items already
+/// carry values under them.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateAttributeRequest {
+    pub label: Option<String>,
+    pub allowed_values: Option<Vec<String>>,
+    pub is_required: Option<bool>,
+    pub is_filterable: Option<bool>,
+    pub display_order: Option<i32>,
+}
+
+impl Category {
+    /// Every category, parents before their children and siblings in display order
+    pub async fn list(pool: &PgPool, active_only: bool) -> Result<Vec<Self>, AppError> {
+        let categories = sqlx::query_as::<_, Category>(&format!(
+            "SELECT {} FROM item_categories WHERE (NOT $1 OR is_active) ORDER BY display_order, name",
+            CATEGORY_COLUMNS
+        ))
+        .bind(active_only)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(categories)
+    }
+
+    pub async fn find_by_id(pool: &PgPool, id: i64) -> Result<Option<Self>, AppError> {
+        let category = sqlx::query_as::<_, Category>(&format!(
+            "SELECT {} FROM item_categories WHERE id = $1",
+            CATEGORY_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(category)
+    }
+
+    pub async fn find_by_slug(pool: &PgPool, slug: &str) -> Result<Option<Self>, AppError> {
+        let category = sqlx::query_as::<_, Category>(&format!(
+            "SELECT {} FROM item_categories WHERE slug = $1",
+            CATEGORY_COLUMNS
+        ))
+        .bind(slug)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(category)
+    }
+
+    /// Whether the category and every ancestor are active
+    pub async fn is_assignable(pool: &PgPool, id: i64) -> Result<bool, AppError> {
+        let assignable = sqlx::query_scalar::<_, bool>(&format!(
+            "{} SELECT COUNT(*) > 0 AND bool_and(c.is_active) \
+             FROM ancestors a JOIN item_categories c ON c.id = a.id",
+            ANCESTORS_CTE
+        ))
+        .bind(vec![id])
+        .fetch_one(pool)
+        .await?;
+
+        Ok(assignable)
+    }
+
+    /// The category's id followed by those of every category below it
+    pub async fn subtree_ids(pool: &PgPool, id: i64) -> Result<Vec<i64>, AppError> {
+        let ids = sqlx::query_scalar::<_, i64>(
+            "WITH RECURSIVE subtree AS ( \
+                SELECT id FROM item_categories WHERE id = $1 \
+                UNION \
+                SELECT c.id FROM item_categories c JOIN subtree s ON c.parent_category_id = s.id \
+             ) \
+             SELECT id FROM subtree",
+        )
+        .bind(id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    pub async fn create(pool: &PgPool, request: CreateCategoryRequest) -> Result<Self, AppError> {
+        let name = category_name(&request.name)?;
+        let slug = category_slug(request.slug.as_deref().unwrap_or(&name))?;
+
+        if let Some(parent_id) = request.parent_id {
+            Self::find_by_id(pool, parent_id)
+                .await?
+                .ok_or_else(|| AppError::NotFound("Parent category not found".to_string()))?;
+        }
+
+        let category = sqlx::query_as::<_, Category>(&format!(
+            "INSERT INTO item_categories (slug, name, description, parent_category_id, display_order) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT DO NOTHING \
+             RETURNING {}",
+            CATEGORY_COLUMNS
+        ))
+        .bind(&slug)
+        .bind(&name)
+        .bind(request.description)
+        .bind(request.parent_id)
+        .bind(request.display_order)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| {
+            AppError::Conflict(format!(
+                "A category with slug '{}', or named '{}' under the same parent, already exists",
+                slug, name
+            ))
+        })?;
+
+        Ok(category)
+    }
+
+    /// Update a category. Renaming it renames the category on its items too.
+    pub async fn update(pool: &PgPool, id: i64, request: UpdateCategoryRequest) -> Result<Self, AppError> {
+        let current = Self::find_by_id(pool, id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Category not found".to_string()))?;
+        let name = request.name.as_deref().map(category_name).transpose()?;
+
+        let parent_id = if request.make_root {
+            None
+        } else {
+            request.parent_id.or(current.parent_id)
+        };
+        if let Some(parent_id) = parent_id.filter(|p| Some(*p) != current.parent_id) {
+            if Self::subtree_ids(pool, id).await?.contains(&parent_id) {
+                return Err(AppError::Validation(
+                    "A category can't be moved under itself or one of its subcategories".to_string(),
+                ));
+            }
+            Self::find_by_id(pool, parent_id)
+                .await?
+                .ok_or_else(|| AppError::NotFound("Parent category not found".to_string()))?;
+        }
+
+        let mut tx = pool.begin().await?;
+
+        let category = sqlx::query_as::<_, Category>(&format!(
+            "UPDATE item_categories SET \
+                name = COALESCE($2, name), \
+                description = COALESCE($3, description), \
+                parent_category_id = $4, \
+                display_order = COALESCE($5, display_order), \
+                is_active = COALESCE($6, is_active), \
+                updated_at = NOW() \
+             WHERE id = $1 \
+             RETURNING {}",
+            CATEGORY_COLUMNS
+        ))
+        .bind(id)
+        .bind(&name)
+        .bind(request.description)
+        .bind(parent_id)
+        .bind(request.display_order)
+        .bind(request.is_active)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                AppError::Conflict("Another category under the same parent has that name".to_string())
+            }
+            e => AppError::from(e),
+        })?;
+
+        if category.name != current.name {
+            sqlx::query("UPDATE items SET category = $2, updated_at = NOW() WHERE category_id = $1")
+                .bind(id)
+                .bind(&category.name)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(category)
+    }
+
+    /// Delete a category nothing uses; one with subcategories or items should be deactivated instead
+    pub async fn delete(pool: &PgPool, id: i64) -> Result<(), AppError> {
+        let (children, items) = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT (SELECT COUNT(*) FROM item_categories WHERE parent_category_id = $1), \
+                    (SELECT COUNT(*) FROM items WHERE category_id = $1)",
+        )
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+        if children > 0 || items > 0 {
+            return Err(AppError::Conflict(format!(
+                "The category has {} subcategories and {} items; move them or deactivate it instead",
+                children, items
+            )));
+        }
+
+        let result = sqlx::query("DELETE FROM item_categories WHERE id = $1")
+            .bind(id)
+            .execute(pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Category not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Put an item in a category with attribute values already checked against it, or take
+    /// it out of any category. The item's category name follows.
+    pub async fn assign_item(
+        pool: &PgPool,
+        item_id: Uuid,
+        category: Option<&Category>,
+        attributes: &Map<String, Value>,
+    ) -> Result<(), AppError> {
+        sqlx::query("UPDATE items SET category_id = $2, category = $3, attributes = $4, updated_at = NOW() WHERE id = $1")
+            .bind(item_id)
+            .bind(category.map(|c| c.id))
+            .bind(category.map(|c| c.name.clone()))
+            .bind(Value::Object(attributes.clone()))
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl CategoryAttribute {
+    /// Attributes an item in the category can carry: its own, its ancestors' and the global ones
+    pub async fn for_category(pool: &PgPool, category_id: Option<i64>) -> Result<Vec<Self>, AppError> {
+        let category_ids: Vec<i64> = category_id.into_iter().collect();
+
+        let attributes = sqlx::query_as::<_, CategoryAttribute>(&format!(
+            "{} SELECT {} FROM category_attributes \
+             WHERE category_id IS NULL OR category_id IN (SELECT id FROM ancestors) \
+             ORDER BY display_order, key",
+            ANCESTORS_CTE, ATTRIBUTE_COLUMNS
+        ))
+        .bind(&category_ids)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(attributes)
+    }
+
+    /// Every attribute, global ones first
+    pub async fn list(pool: &PgPool) -> Result<Vec<Self>, AppError> {
+        let attributes = sqlx::query_as::<_, CategoryAttribute>(&format!(
+            "SELECT {} FROM category_attributes ORDER BY category_id NULLS FIRST, display_order, key",
+            ATTRIBUTE_COLUMNS
+        ))
+        .fetch_all(pool)
+        .await?;
+
+        Ok(attributes)
+    }
+
+    pub async fn find_by_keys(pool: &PgPool, keys: &[String]) -> Result<Vec<Self>, AppError> {
+        let attributes = sqlx::query_as::<_, CategoryAttribute>(&format!(
+            "SELECT {} FROM category_attributes WHERE key = ANY($1)",
+            ATTRIBUTE_COLUMNS
+        ))
+        .bind(keys)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(attributes)
+    }
+
+    pub async fn create(pool: &PgPool, request: CreateAttributeRequest) -> Result<Self, AppError> {
+        let key = attribute_key(&request.key)?;
+        let label = attribute_label(&request.label)?;
+        let allowed_values = allowed_values(request.attribute_type, &request.allowed_values)?;
+
+        if let Some(category_id) = request.category_id {
+            Category::find_by_id(pool, category_id)
+                .await?
+                .ok_or_else(|| AppError::NotFound("Category not found".to_string()))?;
+        }
+
+        let attribute = sqlx::query_as::<_, CategoryAttribute>(&format!(
+            "INSERT INTO category_attributes \
+                (category_id, key, label, attribute_type, allowed_values, is_required, is_filterable, display_order) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+             ON CONFLICT (key) DO NOTHING \
+             RETURNING {}",
+            ATTRIBUTE_COLUMNS
+        ))
+        .bind(request.category_id)
+        .bind(&key)
+        .bind(label)
+        .bind(request.attribute_type)
+        .bind(&allowed_values)
+        .bind(request.is_required)
+        .bind(request.is_filterable)
+        .bind(request.display_order)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::Conflict(format!("An attribute with key '{}' already exists", key)))?;
+
+        Ok(attribute)
+    }
+
+    /// Update an attribute. Items keep values an enum no longer allows until they are next edited.
+    pub async fn update(pool: &PgPool, id: i64, request: UpdateAttributeRequest) -> Result<Self, AppError> {
+        let label = request.label.as_deref().map(attribute_label).transpose()?;
+        let allowed = match &request.allowed_values {
+            Some(values) => {
+                let attribute_type = sqlx::query_scalar::<_, AttributeType>(
+                    "SELECT attribute_type FROM category_attributes WHERE id = $1",
+                )
+                .bind(id)
+                .fetch_optional(pool)
+                .await?
+                .ok_or_else(|| AppError::NotFound("Category attribute not found".to_string()))?;
+                Some(allowed_values(attribute_type, values)?)
+            }
+            None => None,
+        };
+
+        sqlx::query_as::<_, CategoryAttribute>(&format!(
+            "UPDATE category_attributes SET \
+                label = COALESCE($2, label), \
+                allowed_values = COALESCE($3, allowed_values), \
+                is_required = COALESCE($4, is_required), \
+                is_filterable = COALESCE($5, is_filterable), \
+                display_order = COALESCE($6, display_order), \
+                updated_at = NOW() \
+             WHERE id = $1 \
+             RETURNING {}",
+            ATTRIBUTE_COLUMNS
+        ))
+        .bind(id)
+        .bind(label)
+        .bind(allowed)
+        .bind(request.is_required)
+        .bind(request.is_filterable)
+        .bind(request.display_order)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Category attribute not found".to_string()))
+    }
+
+    /// Delete an attribute and drop its values from every item
+    pub async fn delete(pool: &PgPool, id: i64) -> Result<(), AppError> {
+        let mut tx = pool.begin().await?;
+
+        let key = sqlx::query_scalar::<_, String>("DELETE FROM category_attributes WHERE id = $1 RETURNING key")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Category attribute not found".to_string()))?;
+
+        sqlx::query("UPDATE items SET attributes = attributes - $1, updated_at = NOW() WHERE attributes ? $1")
+            .bind(&key)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+/// Arrange categories into trees under their roots. Categories whose parent isn't in the list,
+/// such as those under an inactive one, are left out.
+pub fn build_tree(categories: &[Category]) -> Vec<CategoryNode> {
+    fn children_of(categories: &[Category], parent_id: Option<i64>, depth: usize) -> Vec<CategoryNode> {
+        // Moves that would make a cycle are refused; the bound guards against one left by hand
+        if depth > categories.len() {
+            return Vec::new();
+        }
+
+        categories
+            .iter()
+            .filter(|category| category.parent_id == parent_id)
+            .map(|category| CategoryNode {
+                id: category.id,
+                slug: category.slug.clone(),
+                name: category.name.clone(),
+                description: category.description.clone(),
+                children: children_of(categories, Some(category.id), depth + 1),
+            })
+            .collect()
+    }
+
+    children_of(categories, None, 0)
+}
+
+/// Check attribute values against the attributes the item's category allows. Nulls are dropped,
+/// text is trimmed, and required attributes must be present.
+pub fn normalize_attributes(
+    definitions: &[CategoryAttribute],
+    values: &Map<String, Value>,
+) -> Result<Map<String, Value>, AppError> {
+    let mut normalized = Map::new();
+
+    for (key, value) in values {
+        let definition = definitions
+            .iter()
+            .find(|d| d.key == *key)
+            .ok_or_else(|| AppError::Validation(format!("'{}' is not an attribute of this category", key)))?;
+        if value.is_null() {
+            continue;
+        }
+
+        let invalid = || AppError::Validation(format!("'{}' must be {}", key, expected(definition)));
+        let value = match definition.attribute_type {
+            AttributeType::Text => {
+                let text = value.as_str().map(str::trim).filter(|t| !t.is_empty()).ok_or_else(invalid)?;
+                if text.chars().count() > MAX_TEXT_VALUE_LENGTH {
+                    return Err(AppError::Validation(format!(
+                        "'{}' can be at most {} characters",
+                        key, MAX_TEXT_VALUE_LENGTH
+                    )));
+                }
+                Value::String(text.to_string())
+            }
+            AttributeType::Number if value.is_number() => value.clone(),
+            AttributeType::Boolean if value.is_boolean() => value.clone(),
+            AttributeType::Enum => {
+                let choice = value
+                    .as_str()
+                    .filter(|v| definition.allowed_values.iter().any(|a| a == v))
+                    .ok_or_else(invalid)?;
+                Value::String(choice.to_string())
+            }
+            _ => return Err(invalid()),
+        };
+        normalized.insert(key.clone(), value);
+    }
+
+    if let Some(missing) = definitions.iter().find(|d| d.is_required && !normalized.contains_key(&d.key)) {
+        return Err(AppError::Validation(format!("'{}' is required in this category", missing.key)));
+    }
+
+    Ok(normalized)
+}
+
+/// Turn `key:value` search filters into a JSON object listings' attributes must contain.
+/// Each key must be a filterable attribute, given once, with a value of its type.
+pub fn attribute_filter(definitions: &[CategoryAttribute], filters: &[String]) -> Result<Option<Value>, AppError> {
+    if filters.len() > MAX_ATTRIBUTE_FILTERS {
+        return Err(AppError::Validation(format!(
+            "Filter on at most {} attributes",
+            MAX_ATTRIBUTE_FILTERS
+        )));
+    }
+
+    let mut filter = Map::new();
+    for pair in filters {
+        let (key, raw) = pair
+            .split_once(':')
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .filter(|(key, value)| !key.is_empty() && !value.is_empty())
+            .ok_or_else(|| AppError::Validation(format!("Attribute filter '{}' must be key:value", pair)))?;
+
+        let definition = definitions
+            .iter()
+            .find(|d| d.key == key && d.is_filterable)
+            .ok_or_else(|| AppError::Validation(format!("Raffles can't be filtered on '{}'", key)))?;
+        if filter.contains_key(key) {
+            return Err(AppError::Validation(format!("Filter on '{}' once", key)));
+        }
+
+        let invalid = || AppError::Validation(format!("'{}' must be {}", key, expected(definition)));
+        let value = match definition.attribute_type {
+            AttributeType::Text => Value::String(raw.to_string()),
+            AttributeType::Number => raw
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(invalid)?,
+            AttributeType::Boolean => Value::Bool(raw.parse::<bool>().map_err(|_| invalid())?),
+            AttributeType::Enum => {
+                if !definition.allowed_values.iter().any(|a| a == raw) {
+                    return Err(invalid());
+                }
+                Value::String(raw.to_string())
+            }
+        };
+        filter.insert(key.to_string(), value);
+    }
+
+    Ok((!filter.is_empty()).then_some(Value::Object(filter)))
+}
+
+/// Canonical form of a category slug: lowercase words joined by single dashes, e.g. "Home & Garden" is `home-garden`
+pub fn category_slug(input: &str) -> Result<String, AppError> {
+    let mut slug = String::with_capacity(input.len());
+    for c in input.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-').to_string();
+
+    if slug.is_empty() || slug.len() > MAX_SLUG_LENGTH {
+        return Err(AppError::Validation(format!(
+            "Category slugs must be 1 to {} letters, digits and dashes",
+            MAX_SLUG_LENGTH
+        )));
+    }
+    Ok(slug)
+}
+
+fn category_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(AppError::Validation(format!(
+            "Category names must be 1 to {} characters",
+            MAX_NAME_LENGTH
+        )));
+    }
+    Ok(name.to_string())
+}
+
+fn attribute_key(key: &str) -> Result<String, AppError> {
+    let key = key.trim().to_lowercase();
+    if key.is_empty()
+        || key.len() > MAX_ATTRIBUTE_KEY_LENGTH
+        || !key.starts_with(|c: char| c.is_ascii_lowercase())
+        || !key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err(AppError::Validation(format!(
+            "Attribute keys are 1 to {} lowercase letters, digits and underscores, starting with a letter",
+            MAX_ATTRIBUTE_KEY_LENGTH
+        )));
+    }
+    Ok(key)
+}
+
+fn attribute_label(label: &str) -> Result<String, AppError> {
+    let label = label.trim();
+    if label.is_empty() || label.chars().count() > MAX_ATTRIBUTE_LABEL_LENGTH {
+        return Err(AppError::Validation(format!(
+            "Attribute labels must be 1 to {} characters",
+            MAX_ATTRIBUTE_LABEL_LENGTH
+        )));
+    }
+    Ok(label.to_string())
+}
+
+/// Enum attributes need a list of values; other types take none
+fn allowed_values(attribute_type: AttributeType, values: &[String]) -> Result<Vec<String>, AppError> {
+    let mut cleaned: Vec<String> = values
+        .iter()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect();
+    cleaned.dedup();
+
+    match attribute_type {
+        AttributeType::Enum if cleaned.is_empty() || cleaned.len() > MAX_ALLOWED_VALUES => Err(AppError::Validation(
+            format!("Enum attributes need 1 to {} allowed values", MAX_ALLOWED_VALUES),
+        )),
+        AttributeType::Enum => Ok(cleaned),
+        _ if !cleaned.is_empty() => Err(AppError::Validation(
+            "Only enum attributes take allowed values".to_string(),
+        )),
+        _ => Ok(cleaned),
+    }
+}
+
+fn expected(definition: &CategoryAttribute) -> String {
+    match definition.attribute_type {
+        AttributeType::Text => "text".to_string(),
+        AttributeType::Number => "a number".to_string(),
+        AttributeType::Boolean => "true or false".to_string(),
+        AttributeType::Enum => format!("one of {}", definition.allowed_values.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn attribute(key: &str, attribute_type: AttributeType, allowed: &[&str], required: bool) -> CategoryAttribute {
+        CategoryAttribute {
+            id: 1,
+            category_id: None,
+            key: key.to_string(),
+            label: key.to_string(),
+            attribute_type,
+            allowed_values: allowed.iter().map(|v| v.to_string()).collect(),
+            is_required: required,
+            is_filterable: true,
+            display_order: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn definitions() -> Vec<CategoryAttribute> {
+        vec![
+            attribute("brand", AttributeType::Text, &[], false),
+            attribute("size", AttributeType::Number, &[], false),
+            attribute("boxed", AttributeType::Boolean, &[], false),
+            attribute("condition", AttributeType::Enum, &["new", "good"], true),
+        ]
+    }
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_normalize_attributes() {
+        let values = object(json!({ "brand": " Nike ", "size": 10.5, "boxed": true, "condition": "new" }));
+        let normalized = normalize_attributes(&definitions(), &values).unwrap();
+        assert_eq!(Value::Object(normalized), json!({ "brand": "Nike", "size": 10.5, "boxed": true, "condition": "new" }));
+
+        // Nulls are dropped
+        let normalized = normalize_attributes(&definitions(), &object(json!({ "condition": "good", "brand": null }))).unwrap();
+        assert!(!normalized.contains_key("brand"));
+    }
+
+    #[test]
+    fn test_normalize_attributes_rejects_bad_values() {
+        let check = |value: Value| normalize_attributes(&definitions(), &object(value));
+
+        assert!(check(json!({ "brand": "Nike" })).is_err(), "condition is required");
+        assert!(check(json!({ "condition": "mint" })).is_err());
+        assert!(check(json!({ "condition": "new", "size": "10" })).is_err());
+        assert!(check(json!({ "condition": "new", "boxed": "yes" })).is_err());
+        assert!(check(json!({ "condition": "new", "brand": "  " })).is_err());
+        assert!(check(json!({ "condition": "new", "color": "red" })).is_err());
+    }
+
+    #[test]
+    fn test_attribute_filter() {
+        let filters = vec!["condition:new".to_string(), "size: 10".to_string(), "boxed:true".to_string()];
+        let filter = attribute_filter(&definitions(), &filters).unwrap().unwrap();
+        assert_eq!(filter, json!({ "condition": "new", "size": 10.0, "boxed": true }));
+
+        assert!(attribute_filter(&definitions(), &[]).unwrap().is_none());
+        assert!(attribute_filter(&definitions(), &["condition".to_string()]).is_err());
+        assert!(attribute_filter(&definitions(), &["condition:mint".to_string()]).is_err());
+        assert!(attribute_filter(&definitions(), &["size:large".to_string()]).is_err());
+        assert!(attribute_filter(&definitions(), &["color:red".to_string()]).is_err());
+        assert!(attribute_filter(&definitions(), &["brand:a".to_string(), "brand:b".to_string()]).is_err());
+
+        let mut hidden = definitions();
+        hidden[0].is_filterable = false;
+        assert!(attribute_filter(&hidden, &["brand:Nike".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let category = |id: i64, parent_id: Option<i64>| Category {
+            id,
+            slug: format!("c{}", id),
+            name: format!("C{}", id),
+            description: None,
+            parent_id,
+            display_order: 0,
+            is_active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        // 4 sits under a category missing from the list, as if it were inactive
+        let categories = vec![category(1, None), category(2, Some(1)), category(3, Some(2)), category(4, Some(9))];
+
+        let tree = build_tree(&categories);
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].id, 1);
+        assert_eq!(tree[0].children[0].id, 2);
+        assert_eq!(tree[0].children[0].children[0].id, 3);
+    }
+
+    #[test]
+    fn test_category_slug() {
+        assert_eq!(category_slug("Home & Garden").unwrap(), "home-garden");
+        assert_eq!(category_slug("  Sneakers / Men's ").unwrap(), "sneakers-men-s");
+        assert!(category_slug("&&").is_err());
+        assert!(attribute_key("Shoe_Size").is_ok());
+        assert!(attribute_key("2nd").is_err());
+        assert!(allowed_values(AttributeType::Enum, &[]).is_err());
+        assert!(allowed_values(AttributeType::Text, &["a".to_string()]).is_err());
+    }
+}
@@ -2,11 +2,12 @@ use chrono::{DateTime, Utc};
 use raffle_platform_shared::{CreditSource, CreditType, CreditResponse};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, PgPool};
+use sqlx::{FromRow, PgExecutor, PgPool, Postgres, Transaction};
+use utoipa::ToSchema;
 use uuid::Uuid;
 use crate::error::AppError;
 
-#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct UserCredit {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -24,7 +25,7 @@ pub struct UserCredit {
 impl UserCredit {
     /// Create new credits for a user
     pub async fn create(
-        pool: &PgPool,
+        executor: impl PgExecutor<'_>,
         user_id: Uuid,
         amount: Decimal,
         source: CreditSource,
@@ -50,60 +51,50 @@ impl UserCredit {
             redeemable_on_item_id,
             expires_at
         )
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await?;
 
         Ok(credit)
     }
 
-    /// Find credits by user
+    /// Find credits by user, newest first, after a `(created_at, id)` position
     pub async fn find_by_user(
         pool: &PgPool,
         user_id: Uuid,
         include_used: bool,
+        limit: i64,
+        after: (Option<DateTime<Utc>>, Option<Uuid>),
     ) -> Result<Vec<Self>, AppError> {
-        let credits = if include_used {
-            sqlx::query_as!(
-                UserCredit,
-                r#"
-                SELECT 
-                    id, user_id, amount, 
-                    source as "source: CreditSource", 
-                    credit_type as "credit_type: CreditType",
-                    redeemable_on_item_id, expires_at, is_transferable, is_used, used_at, created_at
-                FROM user_credits 
-                WHERE user_id = $1
-                ORDER BY created_at DESC
-                "#,
-                user_id
-            )
-            .fetch_all(pool)
-            .await?
-        } else {
-            sqlx::query_as!(
-                UserCredit,
-                r#"
-                SELECT 
-                    id, user_id, amount, 
-                    source as "source: CreditSource", 
-                    credit_type as "credit_type: CreditType",
-                    redeemable_on_item_id, expires_at, is_transferable, is_used, used_at, created_at
-                FROM user_credits 
-                WHERE user_id = $1 AND is_used = false
-                ORDER BY created_at DESC
-                "#,
-                user_id
-            )
-            .fetch_all(pool)
-            .await?
-        };
+        let credits = sqlx::query_as!(
+            UserCredit,
+            r#"
+            SELECT 
+                id, user_id, amount, 
+                source as "source: CreditSource", 
+                credit_type as "credit_type: CreditType",
+                redeemable_on_item_id, expires_at, is_transferable, is_used, used_at, created_at
+            FROM user_credits 
+            WHERE user_id = $1
+            AND ($2 OR is_used = false)
+            AND ($3::timestamptz IS NULL OR (created_at, id) < ($3, $4))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $5
+            "#,
+            user_id,
+            include_used,
+            after.0,
+            after.1,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
 
         Ok(credits)
     }
 
     /// Find available credits for a user (unused and not expired)
     pub async fn find_available_by_user(
-        pool: &PgPool,
+        executor: impl PgExecutor<'_>,
         user_id: Uuid,
         credit_type: Option<CreditType>,
         item_id: Option<Uuid>,
@@ -129,7 +120,7 @@ impl UserCredit {
                     ct as CreditType,
                     item
                 )
-                .fetch_all(pool)
+                .fetch_all(executor)
                 .await?
             }
             (Some(ct), None) => {
@@ -150,7 +141,7 @@ impl UserCredit {
                     user_id,
                     ct as CreditType
                 )
-                .fetch_all(pool)
+                .fetch_all(executor)
                 .await?
             }
             (None, Some(item)) => {
@@ -171,7 +162,7 @@ impl UserCredit {
                     user_id,
                     item
                 )
-                .fetch_all(pool)
+                .fetch_all(executor)
                 .await?
             }
             (None, None) => {
@@ -190,7 +181,7 @@ impl UserCredit {
                     "#,
                     user_id
                 )
-                .fetch_all(pool)
+                .fetch_all(executor)
                 .await?
             }
         };
@@ -228,9 +219,9 @@ impl UserCredit {
         Ok(credits)
     }
 
-    /// Mark credits as used
+    /// Mark credits as used, splitting the last one if only part of it is needed
     pub async fn mark_as_used(
-        pool: &PgPool,
+        tx: &mut Transaction<'_, Postgres>,
         credit_ids: &[Uuid],
         used_amount: Decimal,
     ) -> Result<Vec<Self>, AppError> {
@@ -252,10 +243,11 @@ impl UserCredit {
                     redeemable_on_item_id, expires_at, is_transferable, is_used, used_at, created_at
                 FROM user_credits 
                 WHERE id = $1 AND is_used = false
+                FOR UPDATE
                 "#,
                 credit_id
             )
-            .fetch_optional(pool)
+            .fetch_optional(&mut **tx)
             .await?;
 
             if let Some(mut credit) = credit {
@@ -265,7 +257,7 @@ impl UserCredit {
                         "UPDATE user_credits SET is_used = true, used_at = NOW() WHERE id = $1",
                         credit.id
                     )
-                    .execute(pool)
+                    .execute(&mut **tx)
                     .await?;
 
                     remaining_amount -= credit.amount;
@@ -283,7 +275,7 @@ impl UserCredit {
                         used_portion,
                         credit.id
                     )
-                    .execute(pool)
+                    .execute(&mut **tx)
                     .await?;
 
                     // Create new credit for remaining amount
@@ -300,7 +292,7 @@ impl UserCredit {
                         credit.expires_at,
                         credit.is_transferable
                     )
-                    .execute(pool)
+                    .execute(&mut **tx)
                     .await?;
 
                     credit.amount = used_portion;
@@ -321,7 +313,7 @@ impl UserCredit {
 
     /// Calculate total available credits for a user
     pub async fn calculate_total_available(
-        pool: &PgPool,
+        executor: impl PgExecutor<'_>,
         user_id: Uuid,
         credit_type: Option<CreditType>,
         item_id: Option<Uuid>,
@@ -341,7 +333,7 @@ impl UserCredit {
                     ct as CreditType,
                     item
                 )
-                .fetch_one(pool)
+                .fetch_one(executor)
                 .await?
             }
             (Some(ct), None) => {
@@ -356,7 +348,7 @@ impl UserCredit {
                     user_id,
                     ct as CreditType
                 )
-                .fetch_one(pool)
+                .fetch_one(executor)
                 .await?
             }
             (None, Some(item)) => {
@@ -371,7 +363,7 @@ impl UserCredit {
                     user_id,
                     item
                 )
-                .fetch_one(pool)
+                .fetch_one(executor)
                 .await?
             }
             (None, None) => {
@@ -384,7 +376,7 @@ impl UserCredit {
                     "#,
                     user_id
                 )
-                .fetch_one(pool)
+                .fetch_one(executor)
                 .await?
             }
         };
@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+
+const WALLET_COLUMNS: &str = "id, user_id, address, label, verified_at, unlinked_at, created_at";
+
+/// A wallet the user holds the keys to, proven by signing a challenge
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ExternalWallet {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub address: String,
+    pub label: Option<String>,
+    pub verified_at: DateTime<Utc>,
+    pub unlinked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ExternalWallet {
+    /// The user's linked wallets, oldest first
+    pub async fn list_for_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<Self>, AppError> {
+        let wallets = sqlx::query_as::<_, ExternalWallet>(&format!(
+            "SELECT {} FROM external_wallets WHERE user_id = $1 AND unlinked_at IS NULL ORDER BY created_at",
+            WALLET_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(wallets)
+    }
+
+    /// One of the user's linked wallets
+    pub async fn find_linked(pool: &PgPool, user_id: Uuid, wallet_id: Uuid) -> Result<Self, AppError> {
+        sqlx::query_as::<_, ExternalWallet>(&format!(
+            "SELECT {} FROM external_wallets WHERE id = $1 AND user_id = $2 AND unlinked_at IS NULL",
+            WALLET_COLUMNS
+        ))
+        .bind(wallet_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("External wallet not found".to_string()))
+    }
+
+    /// Link an address to a user, or link it again if the same user unlinked it
+    pub async fn link(pool: &PgPool, user_id: Uuid, address: &str, label: Option<&str>) -> Result<Self, AppError> {
+        sqlx::query_as::<_, ExternalWallet>(&format!(
+            "INSERT INTO external_wallets (user_id, address, label) VALUES ($1, $2, $3) \
+             ON CONFLICT ((lower(address))) DO UPDATE \
+                SET label = EXCLUDED.label, verified_at = NOW(), unlinked_at = NULL \
+                WHERE external_wallets.user_id = EXCLUDED.user_id \
+             RETURNING {}",
+            WALLET_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(address)
+        .bind(label)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::Conflict("This wallet belongs to another account".to_string()))
+    }
+
+    /// Stop using a wallet for new purchases; boxes it already holds stay with the user
+    pub async fn unlink(pool: &PgPool, user_id: Uuid, wallet_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query(
+            "UPDATE external_wallets SET unlinked_at = NOW() \
+             WHERE id = $1 AND user_id = $2 AND unlinked_at IS NULL",
+        )
+        .bind(wallet_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("External wallet not found".to_string()));
+        }
+        Ok(())
+    }
+
+    /// The user an address belongs to: its custodial wallet, or an external wallet it linked,
+    /// even one since unlinked
+    pub async fn owner_of(pool: &PgPool, address: &str) -> Result<Option<Uuid>, AppError> {
+        let user_id = sqlx::query_scalar::<_, Uuid>(
+            "SELECT id FROM users WHERE lower(internal_wallet_address) = lower($1) \
+             UNION ALL \
+             SELECT user_id FROM external_wallets WHERE lower(address) = lower($1) \
+             LIMIT 1",
+        )
+        .bind(address)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(user_id)
+    }
+}
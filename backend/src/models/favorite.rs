@@ -0,0 +1,170 @@
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::RaffleStatus;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+
+/// Favorites of each kind beyond this many per user are refused
+const MAX_FAVORITES_PER_USER: i64 = 500;
+
+/// A raffle the user favorited, with enough of it to show in a list
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct FavoriteRaffle {
+    pub raffle_id: Uuid,
+    pub item_id: Uuid,
+    pub item_name: String,
+    pub status: RaffleStatus,
+    pub boxes_sold: i32,
+    pub total_boxes: i32,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub favorited_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct FavoriteItem {
+    pub item_id: Uuid,
+    pub item_name: String,
+    pub favorited_at: Option<DateTime<Utc>>,
+}
+
+/// Favorite a raffle or an item; give exactly one
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AddFavoriteRequest {
+    pub raffle_id: Option<Uuid>,
+    pub item_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Favorites {
+    pub raffles: Vec<FavoriteRaffle>,
+    pub items: Vec<FavoriteItem>,
+}
+
+impl FavoriteRaffle {
+    /// The user's favorite raffles, most recent first
+    pub async fn list_for_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<Self>, AppError> {
+        let favorites = sqlx::query_as::<_, FavoriteRaffle>(
+            "SELECT r.id AS raffle_id, r.item_id, i.name AS item_name, r.status, r.boxes_sold, r.total_boxes, \
+                    r.ends_at, f.created_at AS favorited_at \
+             FROM raffle_favorites f \
+             JOIN raffles r ON r.id = f.raffle_id \
+             JOIN items i ON i.id = r.item_id \
+             WHERE f.user_id = $1 \
+             ORDER BY f.created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(favorites)
+    }
+
+    /// Favorite a raffle; favoriting it again changes nothing
+    pub async fn add(pool: &PgPool, user_id: Uuid, raffle_id: Uuid) -> Result<(), AppError> {
+        let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM raffles WHERE id = $1)")
+            .bind(raffle_id)
+            .fetch_one(pool)
+            .await?;
+        if !exists {
+            return Err(AppError::NotFound("Raffle not found".to_string()));
+        }
+
+        check_limit(pool, "SELECT COUNT(*) FROM raffle_favorites WHERE user_id = $1", user_id).await?;
+
+        sqlx::query(
+            "INSERT INTO raffle_favorites (user_id, raffle_id) VALUES ($1, $2) \
+             ON CONFLICT (user_id, raffle_id) DO NOTHING",
+        )
+        .bind(user_id)
+        .bind(raffle_id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(pool: &PgPool, user_id: Uuid, raffle_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM raffle_favorites WHERE user_id = $1 AND raffle_id = $2")
+            .bind(user_id)
+            .bind(raffle_id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Raffle is not in your favorites".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl FavoriteItem {
+    /// The user's favorite items, most recent first
+    pub async fn list_for_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<Self>, AppError> {
+        let favorites = sqlx::query_as::<_, FavoriteItem>(
+            "SELECT i.id AS item_id, i.name AS item_name, f.created_at AS favorited_at \
+             FROM item_favorites f \
+             JOIN items i ON i.id = f.item_id \
+             WHERE f.user_id = $1 \
+             ORDER BY f.created_at DESC NULLS LAST",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(favorites)
+    }
+
+    /// Favorite an item; favoriting it again changes nothing
+    pub async fn add(pool: &PgPool, user_id: Uuid, item_id: Uuid) -> Result<(), AppError> {
+        let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM items WHERE id = $1)")
+            .bind(item_id)
+            .fetch_one(pool)
+            .await?;
+        if !exists {
+            return Err(AppError::NotFound("Item not found".to_string()));
+        }
+
+        check_limit(pool, "SELECT COUNT(*) FROM item_favorites WHERE user_id = $1", user_id).await?;
+
+        sqlx::query(
+            "INSERT INTO item_favorites (user_id, item_id) VALUES ($1, $2) \
+             ON CONFLICT (user_id, item_id) DO NOTHING",
+        )
+        .bind(user_id)
+        .bind(item_id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(pool: &PgPool, user_id: Uuid, item_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM item_favorites WHERE user_id = $1 AND item_id = $2")
+            .bind(user_id)
+            .bind(item_id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Item is not in your favorites".to_string()));
+        }
+        Ok(())
+    }
+}
+
+async fn check_limit(pool: &PgPool, count_sql: &str, user_id: Uuid) -> Result<(), AppError> {
+    let count = sqlx::query_scalar::<_, i64>(count_sql)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+
+    if count >= MAX_FAVORITES_PER_USER {
+        return Err(AppError::Validation(format!(
+            "You can have at most {} favorites of each kind",
+            MAX_FAVORITES_PER_USER
+        )));
+    }
+    Ok(())
+}
@@ -54,7 +54,7 @@ impl FreeRedeemableItem {
                 fri.id, fri.item_id, fri.required_credit_amount, fri.available_quantity, 
                 fri.is_active, fri.created_at as fri_created_at, fri.updated_at as fri_updated_at,
                 i.id as item_id, i.seller_id, i.name, i.description, i.images, 
-                i.retail_price, i.cost_of_goods, i.status as "item_status: crate::models::item::ItemStatus", 
+                i.retail_price, i.currency as item_currency, i.cost_of_goods, i.status as "item_status: crate::models::item::ItemStatus", 
                 i.stock_quantity, i.listing_fee_applied, i.listing_fee_type,
                 i.created_at as item_created_at, i.updated_at as item_updated_at
             FROM free_redeemable_items fri
@@ -89,6 +89,7 @@ impl FreeRedeemableItem {
                 description: row.description,
                 images: row.images.unwrap(),
                 retail_price: row.retail_price.unwrap(),
+                currency: row.item_currency.unwrap(),
                 cost_of_goods: row.cost_of_goods.unwrap(),
                 status: row.item_status.unwrap(),
                 stock_quantity: row.stock_quantity.unwrap(),
@@ -116,7 +117,7 @@ impl FreeRedeemableItem {
                 fri.id, fri.item_id, fri.required_credit_amount, fri.available_quantity, 
                 fri.is_active, fri.created_at as fri_created_at, fri.updated_at as fri_updated_at,
                 i.id as item_id, i.seller_id, i.name, i.description, i.images, 
-                i.retail_price, i.cost_of_goods, i.status as "item_status: crate::models::item::ItemStatus", 
+                i.retail_price, i.currency as item_currency, i.cost_of_goods, i.status as "item_status: crate::models::item::ItemStatus", 
                 i.stock_quantity, i.listing_fee_applied, i.listing_fee_type,
                 i.created_at as item_created_at, i.updated_at as item_updated_at
             FROM free_redeemable_items fri
@@ -152,6 +153,7 @@ impl FreeRedeemableItem {
                 description: row.description,
                 images: row.images.unwrap(),
                 retail_price: row.retail_price.unwrap(),
+                currency: row.item_currency.unwrap(),
                 cost_of_goods: row.cost_of_goods.unwrap(),
                 status: row.item_status.unwrap(),
                 stock_quantity: row.stock_quantity.unwrap(),
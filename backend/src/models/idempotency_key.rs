@@ -0,0 +1,138 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+use crate::error::AppError;
+
+/// Requests still running after this long are assumed abandoned and may be retried
+const ABANDONED_AFTER_MINUTES: i64 = 5;
+
+/// Response recorded for the first request sent with a key
+#[derive(Debug, Clone)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of claiming a key for a request
+#[derive(Debug, Clone)]
+pub enum IdempotencyClaim {
+    /// First use of the key; run the request and record its response under this id
+    Claimed(Uuid),
+    /// The key already has a response for an identical request
+    Replay(StoredResponse),
+    /// An identical request with this key is still running
+    InProgress,
+    /// The key was used for a different endpoint or request body
+    Mismatch,
+}
+
+pub struct IdempotencyKey;
+
+impl IdempotencyKey {
+    /// Claim a key for a request, or report what the earlier request with it did
+    pub async fn claim(
+        pool: &PgPool,
+        user_id: Uuid,
+        key: &str,
+        method: &str,
+        path: &str,
+        request_hash: &str,
+        ttl_hours: i64,
+    ) -> Result<IdempotencyClaim, AppError> {
+        // Expired and abandoned keys are taken over by the new request
+        let claimed = sqlx::query_scalar!(
+            r#"
+            INSERT INTO idempotency_keys (user_id, idempotency_key, method, path, request_hash, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (user_id, idempotency_key) DO UPDATE
+            SET method = EXCLUDED.method,
+                path = EXCLUDED.path,
+                request_hash = EXCLUDED.request_hash,
+                response_status = NULL,
+                response_content_type = NULL,
+                response_body = NULL,
+                completed_at = NULL,
+                created_at = NOW(),
+                expires_at = EXCLUDED.expires_at
+            WHERE idempotency_keys.expires_at <= NOW()
+               OR (idempotency_keys.completed_at IS NULL AND idempotency_keys.created_at <= $7)
+            RETURNING id
+            "#,
+            user_id,
+            key,
+            method,
+            path,
+            request_hash,
+            Utc::now() + Duration::hours(ttl_hours),
+            Utc::now() - Duration::minutes(ABANDONED_AFTER_MINUTES)
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        if let Some(id) = claimed {
+            return Ok(IdempotencyClaim::Claimed(id));
+        }
+
+        let existing = sqlx::query!(
+            r#"
+            SELECT method, path, request_hash, response_status, response_content_type, response_body, completed_at
+            FROM idempotency_keys
+            WHERE user_id = $1 AND idempotency_key = $2
+            "#,
+            user_id,
+            key
+        )
+        .fetch_one(pool)
+        .await?;
+
+        if existing.method != method || existing.path != path || existing.request_hash != request_hash {
+            return Ok(IdempotencyClaim::Mismatch);
+        }
+
+        Ok(match existing.response_status {
+            Some(status) => IdempotencyClaim::Replay(StoredResponse {
+                status: status as u16,
+                content_type: existing.response_content_type,
+                body: existing.response_body.unwrap_or_default(),
+                completed_at: existing.completed_at,
+            }),
+            None => IdempotencyClaim::InProgress,
+        })
+    }
+
+    /// Record the response to replay for later requests with the key
+    pub async fn complete(
+        pool: &PgPool,
+        id: Uuid,
+        status: u16,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            UPDATE idempotency_keys
+            SET response_status = $2, response_content_type = $3, response_body = $4, completed_at = NOW()
+            WHERE id = $1
+            "#,
+            id,
+            status as i32,
+            content_type,
+            body
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Forget a claim so the request can be retried with the same key
+    pub async fn release(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+        sqlx::query!("DELETE FROM idempotency_keys WHERE id = $1", id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -14,6 +14,8 @@ pub struct Item {
     pub description: Option<String>,
     pub images: Vec<String>,
     pub retail_price: Decimal,
+    /// Currency the prices are in
+    pub currency: String,
     pub cost_of_goods: Decimal,
     pub status: ItemStatus,
     pub stock_quantity: i32,
@@ -35,10 +37,10 @@ impl Item {
         let item = sqlx::query_as!(
             Item,
             r#"
-            INSERT INTO items (seller_id, name, description, images, retail_price, cost_of_goods, stock_quantity, listing_fee_applied, listing_fee_type)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            INSERT INTO items (seller_id, name, description, images, retail_price, cost_of_goods, stock_quantity, listing_fee_applied, listing_fee_type, currency)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, COALESCE($10, 'USD'))
             RETURNING 
-                id, seller_id, name, description, images, retail_price, cost_of_goods,
+                id, seller_id, name, description, images, retail_price, currency, cost_of_goods,
                 status as "status: ItemStatus", stock_quantity, listing_fee_applied, listing_fee_type,
                 created_at, updated_at
             "#,
@@ -50,7 +52,8 @@ impl Item {
             request.cost_of_goods,
             request.stock_quantity,
             listing_fee,
-            listing_fee_type
+            listing_fee_type,
+            request.currency
         )
         .fetch_one(pool)
         .await?;
@@ -64,7 +67,7 @@ impl Item {
             Item,
             r#"
             SELECT 
-                id, seller_id, name, description, images, retail_price, cost_of_goods,
+                id, seller_id, name, description, images, retail_price, currency, cost_of_goods,
                 status as "status: ItemStatus", stock_quantity, listing_fee_applied, listing_fee_type,
                 created_at, updated_at
             FROM items 
@@ -89,7 +92,7 @@ impl Item {
             Item,
             r#"
             SELECT 
-                id, seller_id, name, description, images, retail_price, cost_of_goods,
+                id, seller_id, name, description, images, retail_price, currency, cost_of_goods,
                 status as "status: ItemStatus", stock_quantity, listing_fee_applied, listing_fee_type,
                 created_at, updated_at
             FROM items 
@@ -107,55 +110,34 @@ impl Item {
         Ok(items)
     }
 
-    /// Find available items
+    /// Find available items, newest first, after a `(created_at, id)` position
     pub async fn find_available(
         pool: &PgPool,
         limit: i64,
-        offset: i64,
+        after: (Option<DateTime<Utc>>, Option<Uuid>),
         search: Option<&str>,
     ) -> Result<Vec<Self>, AppError> {
-        let items = match search {
-            Some(search_term) => {
-                sqlx::query_as!(
-                    Item,
-                    r#"
-                    SELECT 
-                        id, seller_id, name, description, images, retail_price, cost_of_goods,
-                        status as "status: ItemStatus", stock_quantity, listing_fee_applied, listing_fee_type,
-                        created_at, updated_at
-                    FROM items 
-                    WHERE status = 'available' AND stock_quantity > 0
-                    AND (name ILIKE $1 OR description ILIKE $1)
-                    ORDER BY created_at DESC
-                    LIMIT $2 OFFSET $3
-                    "#,
-                    format!("%{}%", search_term),
-                    limit,
-                    offset
-                )
-                .fetch_all(pool)
-                .await?
-            }
-            None => {
-                sqlx::query_as!(
-                    Item,
-                    r#"
-                    SELECT 
-                        id, seller_id, name, description, images, retail_price, cost_of_goods,
-                        status as "status: ItemStatus", stock_quantity, listing_fee_applied, listing_fee_type,
-                        created_at, updated_at
-                    FROM items 
-                    WHERE status = 'available' AND stock_quantity > 0
-                    ORDER BY created_at DESC
-                    LIMIT $1 OFFSET $2
-                    "#,
-                    limit,
-                    offset
-                )
-                .fetch_all(pool)
-                .await?
-            }
-        };
+        let items = sqlx::query_as!(
+            Item,
+            r#"
+            SELECT 
+                id, seller_id, name, description, images, retail_price, currency, cost_of_goods,
+                status as "status: ItemStatus", stock_quantity, listing_fee_applied, listing_fee_type,
+                created_at, updated_at
+            FROM items 
+            WHERE status = 'available' AND stock_quantity > 0
+            AND ($1::text IS NULL OR name ILIKE $1 OR description ILIKE $1)
+            AND ($2::timestamptz IS NULL OR (created_at, id) < ($2, $3))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $4
+            "#,
+            search.map(|term| format!("%{}%", term)),
+            after.0,
+            after.1,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
 
         Ok(items)
     }
@@ -194,6 +176,28 @@ impl Item {
         Ok(())
     }
 
+    /// Replace the item's tags; callers resolve them through the tag registry first
+    pub async fn set_tags(pool: &PgPool, id: Uuid, tags: &[String]) -> Result<Vec<String>, AppError> {
+        let tags = sqlx::query_scalar!(
+            "UPDATE items SET tags = $1, updated_at = NOW() WHERE id = $2 RETURNING tags",
+            tags,
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
+
+        Ok(tags)
+    }
+
+    /// The item's tags
+    pub async fn tags(pool: &PgPool, id: Uuid) -> Result<Vec<String>, AppError> {
+        sqlx::query_scalar!("SELECT tags FROM items WHERE id = $1", id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Item not found".to_string()))
+    }
+
     /// Decrease stock quantity (for purchases)
     pub async fn decrease_stock(pool: &PgPool, id: Uuid, amount: i32) -> Result<bool, AppError> {
         let result = sqlx::query!(
@@ -323,7 +327,10 @@ impl Item {
             name: self.name.clone(),
             description: self.description.clone(),
             images: self.images.clone(),
+            image_variants: Vec::new(),
             retail_price: self.retail_price,
+            currency: self.currency.clone(),
+            display_retail_price: None,
             cost_of_goods: self.cost_of_goods,
             status: self.status,
             stock_quantity: self.stock_quantity,
@@ -0,0 +1,271 @@
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::CreditSource;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgExecutor};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+
+/// Ledger account codes the platform posts to. Seller fees and payouts post to whatever
+/// finance has mapped in the chart of accounts.
+pub mod accounts {
+    pub const OPERATING_BANK: &str = "1000";
+    pub const STRIPE_CLEARING: &str = "1010";
+    /// Credits clawed back for card refunds that haven't reached the card yet
+    pub const REFUNDS_CLEARING: &str = "2060";
+    pub const CUSTOMER_CREDITS: &str = "2100";
+    pub const SELLER_PAYABLES: &str = "2200";
+    pub const OPENING_BALANCE: &str = "3000";
+    pub const RAFFLE_REVENUE: &str = "4000";
+    pub const EXPIRED_CREDITS: &str = "4200";
+    pub const RAFFLE_CREDIT_REBATES: &str = "5000";
+    pub const FREE_ITEM_COST: &str = "5010";
+    pub const PROMOTIONAL_CREDITS: &str = "5020";
+    pub const REFUND_CREDITS: &str = "5030";
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum LedgerAccountType {
+    Asset,
+    Liability,
+    Equity,
+    Revenue,
+    Expense,
+}
+
+impl LedgerAccountType {
+    /// Chart of accounts convention: 1xxx assets, 2xxx liabilities, 3xxx equity, 4xxx revenue, the rest expenses
+    pub fn from_code(code: &str) -> Self {
+        match code.chars().next() {
+            Some('1') => LedgerAccountType::Asset,
+            Some('2') => LedgerAccountType::Liability,
+            Some('3') => LedgerAccountType::Equity,
+            Some('4') => LedgerAccountType::Revenue,
+            _ => LedgerAccountType::Expense,
+        }
+    }
+
+    /// Assets and expenses grow with debits; everything else grows with credits
+    pub fn is_debit_normal(&self) -> bool {
+        matches!(self, LedgerAccountType::Asset | LedgerAccountType::Expense)
+    }
+
+    /// A signed posting total (debits positive) expressed in the account's normal direction
+    pub fn normal_balance(&self, net: Decimal) -> Decimal {
+        if self.is_debit_normal() { net } else { -net }
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct LedgerAccount {
+    pub id: Uuid,
+    pub code: String,
+    pub name: String,
+    pub account_type: LedgerAccountType,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LedgerAccount {
+    /// Add an account for a code finance mapped in the chart of accounts; existing accounts are kept
+    pub async fn ensure<'c, E: PgExecutor<'c>>(executor: E, code: &str, name: &str) -> Result<(), AppError> {
+        sqlx::query(
+            "INSERT INTO ledger_accounts (code, name, account_type) VALUES ($1, $2, $3) ON CONFLICT (code) DO NOTHING",
+        )
+        .bind(code)
+        .bind(name)
+        .bind(LedgerAccountType::from_code(code))
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct JournalEntry {
+    pub id: Uuid,
+    pub source_type: String,
+    pub source_id: Option<Uuid>,
+    pub description: String,
+    pub occurred_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct LedgerPosting {
+    pub id: i64,
+    pub journal_entry_id: Uuid,
+    pub account_code: String,
+    /// Debits are positive, credits negative
+    pub amount: Decimal,
+    pub user_id: Option<Uuid>,
+    pub seller_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One side of a journal entry, before it is posted
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostingLine {
+    pub account_code: String,
+    pub amount: Decimal,
+    pub user_id: Option<Uuid>,
+    pub seller_id: Option<Uuid>,
+}
+
+impl PostingLine {
+    pub fn debit(account_code: &str, amount: Decimal) -> Self {
+        Self { account_code: account_code.to_string(), amount: amount.abs(), user_id: None, seller_id: None }
+    }
+
+    pub fn credit(account_code: &str, amount: Decimal) -> Self {
+        Self { account_code: account_code.to_string(), amount: -amount.abs(), user_id: None, seller_id: None }
+    }
+
+    pub fn for_user(mut self, user_id: Uuid) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+
+    pub fn for_seller(mut self, seller_id: Uuid) -> Self {
+        self.seller_id = Some(seller_id);
+        self
+    }
+}
+
+/// A journal entry waiting to be posted
+#[derive(Debug, Clone)]
+pub struct NewJournalEntry {
+    pub source_type: &'static str,
+    pub source_id: Option<Uuid>,
+    pub description: String,
+    pub lines: Vec<PostingLine>,
+}
+
+impl NewJournalEntry {
+    pub fn new(source_type: &'static str, source_id: Option<Uuid>, description: impl Into<String>) -> Self {
+        Self { source_type, source_id, description: description.into(), lines: Vec::new() }
+    }
+
+    pub fn line(mut self, line: PostingLine) -> Self {
+        self.lines.push(line);
+        self
+    }
+
+    /// How far debits exceed credits; zero when the entry balances
+    pub fn imbalance(&self) -> Decimal {
+        self.lines.iter().map(|l| l.amount).sum()
+    }
+}
+
+/// The account that pays for credits issued from a source
+pub fn funding_account(source: CreditSource) -> &'static str {
+    match source {
+        CreditSource::Deposit => accounts::STRIPE_CLEARING,
+        CreditSource::RaffleLoss | CreditSource::RafflePrize => accounts::RAFFLE_CREDIT_REBATES,
+        CreditSource::Bonus | CreditSource::Referral => accounts::PROMOTIONAL_CREDITS,
+        CreditSource::Refund => accounts::REFUND_CREDITS,
+    }
+}
+
+impl JournalEntry {
+    /// Post an entry and its lines in one statement.
+    ///
+    /// Returns the new entry's id, or None when there was nothing to post or the source row
+    /// already has an entry. Unknown account codes and unbalanced lines are rejected.
+    pub async fn post<'c, E: PgExecutor<'c>>(executor: E, entry: &NewJournalEntry) -> Result<Option<Uuid>, AppError> {
+        let lines: Vec<&PostingLine> = entry.lines.iter().filter(|l| !l.amount.is_zero()).collect();
+        if lines.is_empty() {
+            return Ok(None);
+        }
+
+        if !entry.imbalance().is_zero() {
+            return Err(AppError::Internal(format!(
+                "Refusing to post {} entry unbalanced by {}",
+                entry.source_type,
+                entry.imbalance()
+            )));
+        }
+
+        let codes: Vec<&str> = lines.iter().map(|l| l.account_code.as_str()).collect();
+        let amounts: Vec<Decimal> = lines.iter().map(|l| l.amount).collect();
+        let user_ids: Vec<Option<Uuid>> = lines.iter().map(|l| l.user_id).collect();
+        let seller_ids: Vec<Option<Uuid>> = lines.iter().map(|l| l.seller_id).collect();
+
+        // An unknown code leaves account_id NULL, which fails the whole statement
+        let entry_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            WITH entry AS (
+                INSERT INTO journal_entries (source_type, source_id, description)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (source_type, source_id) WHERE source_id IS NOT NULL DO NOTHING
+                RETURNING id
+            )
+            INSERT INTO ledger_postings (journal_entry_id, account_id, amount, user_id, seller_id)
+            SELECT entry.id, a.id, p.amount, p.user_id, p.seller_id
+            FROM entry
+            CROSS JOIN UNNEST($4::text[], $5::numeric[], $6::uuid[], $7::uuid[]) AS p(code, amount, user_id, seller_id)
+            LEFT JOIN ledger_accounts a ON a.code = p.code
+            RETURNING journal_entry_id
+            "#,
+        )
+        .bind(entry.source_type)
+        .bind(entry.source_id)
+        .bind(&entry.description)
+        .bind(&codes)
+        .bind(&amounts)
+        .bind(&user_ids)
+        .bind(&seller_ids)
+        .fetch_optional(executor)
+        .await?;
+
+        Ok(entry_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_take_their_sign_from_the_side() {
+        let entry = NewJournalEntry::new("test", None, "Deposit")
+            .line(PostingLine::debit(accounts::STRIPE_CLEARING, Decimal::new(-2500, 2)))
+            .line(PostingLine::credit(accounts::CUSTOMER_CREDITS, Decimal::new(2500, 2)));
+
+        assert_eq!(entry.lines[0].amount, Decimal::new(2500, 2));
+        assert_eq!(entry.lines[1].amount, Decimal::new(-2500, 2));
+        assert!(entry.imbalance().is_zero());
+    }
+
+    #[test]
+    fn test_imbalance_reports_the_difference() {
+        let entry = NewJournalEntry::new("test", None, "Refund")
+            .line(PostingLine::debit(accounts::REFUNDS_CLEARING, Decimal::from(8)))
+            .line(PostingLine::credit(accounts::STRIPE_CLEARING, Decimal::from(10)));
+
+        assert_eq!(entry.imbalance(), Decimal::from(-2));
+    }
+
+    #[test]
+    fn test_account_types_follow_code_ranges() {
+        assert_eq!(LedgerAccountType::from_code("1010"), LedgerAccountType::Asset);
+        assert_eq!(LedgerAccountType::from_code("2100"), LedgerAccountType::Liability);
+        assert_eq!(LedgerAccountType::from_code("4130"), LedgerAccountType::Revenue);
+        assert_eq!(LedgerAccountType::from_code("5030"), LedgerAccountType::Expense);
+
+        // Credit liability grows with credits, so a net credit reads as a positive balance
+        assert_eq!(LedgerAccountType::Liability.normal_balance(Decimal::from(-40)), Decimal::from(40));
+        assert_eq!(LedgerAccountType::Asset.normal_balance(Decimal::from(40)), Decimal::from(40));
+    }
+
+    #[test]
+    fn test_funding_accounts() {
+        assert_eq!(funding_account(CreditSource::Deposit), accounts::STRIPE_CLEARING);
+        assert_eq!(funding_account(CreditSource::RafflePrize), accounts::RAFFLE_CREDIT_REBATES);
+        assert_eq!(funding_account(CreditSource::Referral), accounts::PROMOTIONAL_CREDITS);
+        assert_eq!(funding_account(CreditSource::Refund), accounts::REFUND_CREDITS);
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+
+/// The kind of listing a snapshot captures
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotEntity {
+    Item,
+    Raffle,
+}
+
+/// One version of the public fields of an item or raffle. Versions are written by database
+/// triggers whenever those fields change, and each box purchase references the versions in
+/// effect when it was made.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ListingSnapshot {
+    pub id: Uuid,
+    pub entity_type: SnapshotEntity,
+    pub entity_id: Uuid,
+    pub version: i32,
+    pub data: serde_json::Value,
+    pub captured_at: DateTime<Utc>,
+}
+
+const COLUMNS: &str = "id, entity_type, entity_id, version, data, captured_at";
+
+impl ListingSnapshot {
+    /// The version that was current at `at`, or None if the listing didn't exist yet
+    pub async fn find_effective_at(
+        pool: &PgPool,
+        entity_type: SnapshotEntity,
+        entity_id: Uuid,
+        at: DateTime<Utc>,
+    ) -> Result<Option<Self>, AppError> {
+        let snapshot = sqlx::query_as::<_, Self>(&format!(
+            r#"
+            SELECT {}
+            FROM listing_snapshots
+            WHERE entity_type = $1 AND entity_id = $2 AND captured_at <= $3
+            ORDER BY version DESC
+            LIMIT 1
+            "#,
+            COLUMNS
+        ))
+        .bind(entity_type)
+        .bind(entity_id)
+        .bind(at)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(snapshot)
+    }
+
+    /// Every version of a listing, newest first
+    pub async fn list_versions(
+        pool: &PgPool,
+        entity_type: SnapshotEntity,
+        entity_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Self>, AppError> {
+        let snapshots = sqlx::query_as::<_, Self>(&format!(
+            r#"
+            SELECT {}
+            FROM listing_snapshots
+            WHERE entity_type = $1 AND entity_id = $2
+            ORDER BY version DESC
+            LIMIT $3
+            "#,
+            COLUMNS
+        ))
+        .bind(entity_type)
+        .bind(entity_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(snapshots)
+    }
+
+    pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Self>, AppError> {
+        let snapshot = sqlx::query_as::<_, Self>(&format!(
+            "SELECT {} FROM listing_snapshots WHERE id = $1",
+            COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(snapshot)
+    }
+}
@@ -4,35 +4,84 @@
 //! Each model corresponds to a database table and provides type-safe interactions
 //! with the database using sqlx.
 
+pub mod api_usage;
 pub mod audit;
+pub mod auth_token;
+pub mod blockchain_event_dead_letter;
 pub mod box_purchase;
+pub mod box_reservation;
+pub mod category;
 pub mod credit;
+pub mod external_wallet;
+pub mod favorite;
 pub mod free_item;
+pub mod idempotency_key;
 pub mod item;
+pub mod ledger;
+pub mod listing_snapshot;
 pub mod notification;
+pub mod notification_preferences;
+pub mod partner_api_key;
+pub mod promotion;
 pub mod raffle;
+pub mod raffle_layout;
+pub mod raffle_listing;
+pub mod raffle_policy;
+pub mod raffle_pricing_curve;
+pub mod raffle_prize_tier;
+pub mod raffle_waitlist;
+pub mod saved_search;
 pub mod seller;
+pub mod seller_onboarding;
 pub mod seller_subscription;
 pub mod system_settings;
+pub mod tag;
 pub mod transaction;
 pub mod user;
+pub mod user_block;
+pub mod user_wallet;
+pub mod winner_display;
 
 #[cfg(test)]
 pub mod tests;
 
 // Re-export commonly used models
+pub use api_usage::{ApiKeyUsage, ApiRatePlan, ApiUsageCharge, SellerApiUsage};
 pub use audit::AuditLog;
+pub use auth_token::{AuthToken, AuthTokenKind};
+pub use blockchain_event_dead_letter::{BlockchainEventDeadLetter, DeadLetterStatus};
 pub use box_purchase::{BoxPurchase, BoxPurchaseStatistics};
+pub use box_reservation::{BoxConflicts, BoxReservation};
 pub use credit::UserCredit;
+pub use external_wallet::ExternalWallet;
+pub use favorite::{FavoriteItem, FavoriteRaffle};
 pub use free_item::FreeRedeemableItem;
+pub use idempotency_key::{IdempotencyClaim, IdempotencyKey};
 pub use item::Item;
+pub use ledger::{JournalEntry, LedgerAccount, LedgerPosting, NewJournalEntry, PostingLine};
+pub use listing_snapshot::{ListingSnapshot, SnapshotEntity};
 pub use notification::{Notification, NotificationType};
+pub use notification_preferences::{NotificationPreferences, PushDevice, PushPlatform};
+pub use partner_api_key::PartnerApiKey;
+pub use promotion::{Promotion, PromotionKind};
 pub use raffle::Raffle;
+pub use raffle_layout::{GridLayout, RaffleLayout};
+pub use raffle_listing::{ListingCursor, ListingFilter, ListingSort, RaffleListing};
+pub use raffle_policy::{RafflePolicy, RafflePolicyOverrides};
+pub use raffle_pricing_curve::{PricingCurve, RafflePricingCurve};
+pub use raffle_prize_tier::RafflePrizeTier;
+pub use raffle_waitlist::{WaitlistEntry, WaitlistOpening, WaitlistReason, WaitlistStatus};
+pub use saved_search::{SavedSearch, SavedSearchFilter};
 pub use seller::Seller;
+pub use seller_onboarding::{OnboardingStep, SellerFeature, SellerOnboarding};
 pub use seller_subscription::{SellerSubscription, SubscriptionStatistics};
 pub use system_settings::{SystemSetting, SystemSettings};
+pub use tag::{Tag, TagCollection, TagSynonym};
 pub use transaction::{Transaction, TransactionSummary};
-pub use user::{User, UserSession};
+pub use user::{TokenState, User, UserSession};
+pub use user_block::{BlockKind, UserBlock};
+pub use user_wallet::{RetiredWallet, WalletChainAddress, WalletDerivation, WalletKeySource};
+pub use winner_display::{PublicWinner, WinnerDisplay, WinnerDisplaySettings};
 
 /// Common database operations trait
 pub trait DatabaseModel {
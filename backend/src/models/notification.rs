@@ -1,18 +1,24 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
 use uuid::Uuid;
 use crate::error::AppError;
 
-#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+const NOTIFICATION_COLUMNS: &str =
+    "id, user_id, title, message, type AS notification_type, data, is_read, read_at, created_at";
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
 pub struct Notification {
     pub id: Uuid,
     pub user_id: Uuid,
     pub title: String,
     pub message: String,
     pub notification_type: String,
+    #[schema(value_type = Option<Object>)]
     pub data: Option<serde_json::Value>,
     pub is_read: bool,
+    pub read_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -21,12 +27,18 @@ pub enum NotificationType {
     RaffleWin,
     RaffleLoss,
     CreditExpiring,
+    CreditExpired,
     CreditIssued,
     PaymentReceived,
     PaymentFailed,
     SellerVerification,
     ItemSold,
     RaffleCompleted,
+    BoxPurchased,
+    PurchaseReceipt,
+    PrizeFulfillment,
+    RaffleWaitlist,
+    RaffleAlert,
     SystemAlert,
     SecurityAlert,
 }
@@ -37,12 +49,18 @@ impl NotificationType {
             NotificationType::RaffleWin => "raffle_win",
             NotificationType::RaffleLoss => "raffle_loss",
             NotificationType::CreditExpiring => "credit_expiring",
+            NotificationType::CreditExpired => "credit_expired",
             NotificationType::CreditIssued => "credit_issued",
             NotificationType::PaymentReceived => "payment_received",
             NotificationType::PaymentFailed => "payment_failed",
             NotificationType::SellerVerification => "seller_verification",
             NotificationType::ItemSold => "item_sold",
             NotificationType::RaffleCompleted => "raffle_completed",
+            NotificationType::BoxPurchased => "box_purchased",
+            NotificationType::PurchaseReceipt => "purchase_receipt",
+            NotificationType::PrizeFulfillment => "prize_fulfillment",
+            NotificationType::RaffleWaitlist => "raffle_waitlist",
+            NotificationType::RaffleAlert => "raffle_alert",
             NotificationType::SystemAlert => "system_alert",
             NotificationType::SecurityAlert => "security_alert",
         }
@@ -64,7 +82,7 @@ impl Notification {
             r#"
             INSERT INTO notifications (user_id, title, message, type, data)
             VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, user_id, title, message, type as notification_type, data, is_read, created_at
+            RETURNING id, user_id, title, message, type as notification_type, data, is_read, read_at, created_at
             "#,
             user_id,
             title,
@@ -90,7 +108,7 @@ impl Notification {
             sqlx::query_as!(
                 Notification,
                 r#"
-                SELECT id, user_id, title, message, type as notification_type, data, is_read, created_at
+                SELECT id, user_id, title, message, type as notification_type, data, is_read, read_at, created_at
                 FROM notifications 
                 WHERE user_id = $1 AND is_read = false
                 ORDER BY created_at DESC
@@ -106,7 +124,7 @@ impl Notification {
             sqlx::query_as!(
                 Notification,
                 r#"
-                SELECT id, user_id, title, message, type as notification_type, data, is_read, created_at
+                SELECT id, user_id, title, message, type as notification_type, data, is_read, read_at, created_at
                 FROM notifications 
                 WHERE user_id = $1
                 ORDER BY created_at DESC
@@ -126,7 +144,7 @@ impl Notification {
     /// Mark notification as read
     pub async fn mark_as_read(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
         sqlx::query!(
-            "UPDATE notifications SET is_read = true WHERE id = $1",
+            "UPDATE notifications SET is_read = true, read_at = COALESCE(read_at, NOW()) WHERE id = $1",
             id
         )
         .execute(pool)
@@ -138,7 +156,7 @@ impl Notification {
     /// Mark all notifications as read for a user
     pub async fn mark_all_as_read(pool: &PgPool, user_id: Uuid) -> Result<u64, AppError> {
         let result = sqlx::query!(
-            "UPDATE notifications SET is_read = true WHERE user_id = $1 AND is_read = false",
+            "UPDATE notifications SET is_read = true, read_at = NOW() WHERE user_id = $1 AND is_read = false",
             user_id
         )
         .execute(pool)
@@ -147,6 +165,51 @@ impl Notification {
         Ok(result.rows_affected())
     }
 
+    /// Find one of a user's notifications
+    pub async fn find_for_user(pool: &PgPool, user_id: Uuid, id: Uuid) -> Result<Option<Self>, AppError> {
+        let notification = sqlx::query_as::<_, Notification>(&format!(
+            "SELECT {} FROM notifications WHERE id = $1 AND user_id = $2",
+            NOTIFICATION_COLUMNS
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(notification)
+    }
+
+    /// Mark one of a user's notifications read; None when the user has no such notification
+    pub async fn mark_read_for_user(pool: &PgPool, user_id: Uuid, id: Uuid) -> Result<Option<Self>, AppError> {
+        let notification = sqlx::query_as::<_, Notification>(&format!(
+            r#"
+            UPDATE notifications SET is_read = true, read_at = COALESCE(read_at, NOW())
+            WHERE id = $1 AND user_id = $2
+            RETURNING {}
+            "#,
+            NOTIFICATION_COLUMNS
+        ))
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(notification)
+    }
+
+    /// Count a user's notifications
+    pub async fn count_for_user(pool: &PgPool, user_id: Uuid, unread_only: bool) -> Result<i64, AppError> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM notifications WHERE user_id = $1 AND ($2 = false OR is_read = false)",
+        )
+        .bind(user_id)
+        .bind(unread_only)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
     /// Get unread count for a user
     pub async fn get_unread_count(pool: &PgPool, user_id: Uuid) -> Result<i64, AppError> {
         let count = sqlx::query_scalar!(
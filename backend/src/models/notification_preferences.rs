@@ -0,0 +1,321 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+
+/// Devices beyond this many per user are dropped, least recently used first
+const MAX_PUSH_DEVICES_PER_USER: i64 = 10;
+
+const MAX_TOKEN_LENGTH: usize = 512;
+const MAX_DEVICE_NAME_LENGTH: usize = 100;
+
+const PREFERENCE_COLUMNS: &str = "user_id, in_app_enabled, email_enabled, push_enabled, sms_enabled, \
+    credit_expiry_notifications, raffle_notifications, payment_notifications, marketing_notifications";
+
+const DEVICE_COLUMNS: &str = "id, user_id, platform, token, device_name, created_at, last_used_at";
+
+/// Which channels a user is notified through, and which kinds of notification they want
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct NotificationPreferences {
+    pub user_id: Uuid,
+    pub in_app_enabled: bool,
+    pub email_enabled: bool,
+    pub push_enabled: bool,
+    pub sms_enabled: bool,
+    pub credit_expiry_notifications: bool,
+    pub raffle_notifications: bool,
+    pub payment_notifications: bool,
+    pub marketing_notifications: bool,
+}
+
+/// Fields left out keep their current value
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateNotificationPreferencesRequest {
+    pub in_app_enabled: Option<bool>,
+    pub email_enabled: Option<bool>,
+    pub push_enabled: Option<bool>,
+    pub sms_enabled: Option<bool>,
+    pub credit_expiry_notifications: Option<bool>,
+    pub raffle_notifications: Option<bool>,
+    pub payment_notifications: Option<bool>,
+    pub marketing_notifications: Option<bool>,
+}
+
+/// Push service a device token belongs to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PushPlatform {
+    /// Firebase Cloud Messaging, for Android
+    Fcm,
+    /// Apple Push Notification service, for iOS
+    Apns,
+}
+
+/// A device that receives a user's push notifications
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PushDevice {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub platform: PushPlatform,
+    #[serde(skip_serializing)]
+    pub token: String,
+    pub device_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct RegisterPushDeviceRequest {
+    pub platform: PushPlatform,
+    pub token: String,
+    pub device_name: Option<String>,
+}
+
+impl NotificationPreferences {
+    /// Preferences for a user who has never changed them
+    pub fn defaults_for(user_id: Uuid) -> Self {
+        Self {
+            user_id,
+            in_app_enabled: true,
+            email_enabled: true,
+            push_enabled: true,
+            sms_enabled: false,
+            credit_expiry_notifications: true,
+            raffle_notifications: true,
+            payment_notifications: true,
+            marketing_notifications: false,
+        }
+    }
+
+    /// Get the user's preferences, or the defaults when they have none saved
+    pub async fn find(pool: &PgPool, user_id: Uuid) -> Result<Self, AppError> {
+        let preferences = sqlx::query_as::<_, NotificationPreferences>(&format!(
+            "SELECT {} FROM notification_preferences WHERE user_id = $1",
+            PREFERENCE_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(preferences.unwrap_or_else(|| Self::defaults_for(user_id)))
+    }
+
+    /// Save the given fields; turning on SMS needs a phone number on the account
+    pub async fn update(
+        pool: &PgPool,
+        user_id: Uuid,
+        request: UpdateNotificationPreferencesRequest,
+    ) -> Result<Self, AppError> {
+        if request.sms_enabled == Some(true) {
+            let phone_number: Option<Option<String>> =
+                sqlx::query_scalar("SELECT phone_number FROM users WHERE id = $1")
+                    .bind(user_id)
+                    .fetch_optional(pool)
+                    .await?;
+            match phone_number {
+                None => return Err(AppError::NotFound("User not found".to_string())),
+                Some(phone) if phone.map_or(true, |phone| phone.trim().is_empty()) => {
+                    return Err(AppError::Validation(
+                        "Add a phone number to your profile before turning on SMS notifications".to_string(),
+                    ));
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("INSERT INTO notification_preferences (user_id) VALUES ($1) ON CONFLICT (user_id) DO NOTHING")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let preferences = sqlx::query_as::<_, NotificationPreferences>(&format!(
+            "UPDATE notification_preferences SET \
+                in_app_enabled = COALESCE($2, in_app_enabled), \
+                email_enabled = COALESCE($3, email_enabled), \
+                push_enabled = COALESCE($4, push_enabled), \
+                sms_enabled = COALESCE($5, sms_enabled), \
+                credit_expiry_notifications = COALESCE($6, credit_expiry_notifications), \
+                raffle_notifications = COALESCE($7, raffle_notifications), \
+                payment_notifications = COALESCE($8, payment_notifications), \
+                marketing_notifications = COALESCE($9, marketing_notifications) \
+             WHERE user_id = $1 \
+             RETURNING {}",
+            PREFERENCE_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(request.in_app_enabled)
+        .bind(request.email_enabled)
+        .bind(request.push_enabled)
+        .bind(request.sms_enabled)
+        .bind(request.credit_expiry_notifications)
+        .bind(request.raffle_notifications)
+        .bind(request.payment_notifications)
+        .bind(request.marketing_notifications)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(preferences)
+    }
+}
+
+impl RegisterPushDeviceRequest {
+    pub fn validate(&self) -> Result<(), AppError> {
+        let token = self.token.trim();
+        if token.is_empty() {
+            return Err(AppError::Validation("Device token is required".to_string()));
+        }
+        if token.len() > MAX_TOKEN_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Device token must be at most {} characters",
+                MAX_TOKEN_LENGTH
+            )));
+        }
+        if token.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(AppError::Validation("Device token contains invalid characters".to_string()));
+        }
+        if self.device_name.as_deref().map_or(false, |name| name.chars().count() > MAX_DEVICE_NAME_LENGTH) {
+            return Err(AppError::Validation(format!(
+                "Device name must be at most {} characters",
+                MAX_DEVICE_NAME_LENGTH
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl PushDevice {
+    /// Register a device for the user; a token already registered moves to this user
+    pub async fn register(pool: &PgPool, user_id: Uuid, request: RegisterPushDeviceRequest) -> Result<Self, AppError> {
+        request.validate()?;
+
+        let device_name = request
+            .device_name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+
+        let mut tx = pool.begin().await?;
+
+        let device = sqlx::query_as::<_, PushDevice>(&format!(
+            "INSERT INTO push_devices (user_id, platform, token, device_name) \
+             VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (token) DO UPDATE SET \
+                user_id = EXCLUDED.user_id, \
+                platform = EXCLUDED.platform, \
+                device_name = EXCLUDED.device_name, \
+                last_used_at = NOW() \
+             RETURNING {}",
+            DEVICE_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(request.platform)
+        .bind(request.token.trim())
+        .bind(device_name)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "DELETE FROM push_devices WHERE user_id = $1 AND id NOT IN ( \
+                SELECT id FROM push_devices WHERE user_id = $1 ORDER BY last_used_at DESC LIMIT $2 \
+             )",
+        )
+        .bind(user_id)
+        .bind(MAX_PUSH_DEVICES_PER_USER)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(device)
+    }
+
+    /// The user's devices, most recently used first
+    pub async fn list_for_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<Self>, AppError> {
+        let devices = sqlx::query_as::<_, PushDevice>(&format!(
+            "SELECT {} FROM push_devices WHERE user_id = $1 ORDER BY last_used_at DESC",
+            DEVICE_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(devices)
+    }
+
+    /// Remove one of the user's devices
+    pub async fn remove(pool: &PgPool, user_id: Uuid, device_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM push_devices WHERE id = $1 AND user_id = $2")
+            .bind(device_id)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Push device not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Forget a token the push service reported as no longer valid
+    pub async fn remove_token(pool: &PgPool, token: &str) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM push_devices WHERE token = $1")
+            .bind(token)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Record a successful delivery
+    pub async fn mark_used(pool: &PgPool, device_id: Uuid) -> Result<(), AppError> {
+        sqlx::query("UPDATE push_devices SET last_used_at = NOW() WHERE id = $1")
+            .bind(device_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn register(token: &str, device_name: Option<&str>) -> RegisterPushDeviceRequest {
+        RegisterPushDeviceRequest {
+            platform: PushPlatform::Fcm,
+            token: token.to_string(),
+            device_name: device_name.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_defaults_leave_sms_off() {
+        let preferences = NotificationPreferences::defaults_for(Uuid::new_v4());
+        assert!(preferences.in_app_enabled && preferences.email_enabled && preferences.push_enabled);
+        assert!(!preferences.sms_enabled);
+        assert!(!preferences.marketing_notifications);
+    }
+
+    #[test]
+    fn test_register_request_validation() {
+        assert!(register("dGVzdC10b2tlbg:APA91b", Some("Pixel 8")).validate().is_ok());
+        assert!(register("  ", None).validate().is_err());
+        assert!(register("has space", None).validate().is_err());
+        assert!(register(&"a".repeat(MAX_TOKEN_LENGTH + 1), None).validate().is_err());
+        assert!(register("token", Some(&"n".repeat(MAX_DEVICE_NAME_LENGTH + 1))).validate().is_err());
+    }
+
+    #[test]
+    fn test_push_platform_serialization() {
+        assert_eq!(serde_json::to_string(&PushPlatform::Apns).unwrap(), "\"apns\"");
+        assert_eq!(serde_json::from_str::<PushPlatform>("\"fcm\"").unwrap(), PushPlatform::Fcm);
+    }
+}
@@ -0,0 +1,216 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+use crate::utils::crypto::{generate_api_key, hash_token};
+
+/// Place, read and release credit holds
+pub const SCOPE_CREDIT_HOLDS: &str = "credit_holds";
+/// Capture credit holds and void captures
+pub const SCOPE_CREDIT_CAPTURE: &str = "credit_holds:capture";
+/// Use the seller sandbox; only granted to test-mode keys
+pub const SCOPE_SANDBOX: &str = "sandbox";
+
+/// Scopes an admin may grant to a production key
+pub const KNOWN_SCOPES: [&str; 2] = [SCOPE_CREDIT_HOLDS, SCOPE_CREDIT_CAPTURE];
+
+const TEST_KEY_PREFIX: &str = "rp_test_";
+
+const KEY_PREFIX_LENGTH: usize = 10;
+
+/// API key issued to a partner integration; only the hash of the key is stored
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PartnerApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub created_by: Option<Uuid>,
+    /// Seller the key belongs to; required for test-mode keys
+    pub seller_id: Option<Uuid>,
+    /// Test-mode keys only reach the caller's sandbox, never production data
+    pub is_test: bool,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl PartnerApiKey {
+    /// Issue a new key; the plaintext key is returned once and never stored. Usage of a
+    /// key issued to a seller counts against the seller's API rate plan.
+    pub async fn create(
+        pool: &PgPool,
+        name: &str,
+        scopes: &[String],
+        seller_id: Option<Uuid>,
+        created_by: Uuid,
+    ) -> Result<(Self, String), AppError> {
+        if let Some(unknown) = scopes.iter().find(|s| !KNOWN_SCOPES.contains(&s.as_str())) {
+            return Err(AppError::Validation(format!("Unknown API key scope: {}", unknown)));
+        }
+
+        let key = generate_api_key();
+        let key_prefix: String = key.chars().take(KEY_PREFIX_LENGTH).collect();
+
+        let api_key = sqlx::query_as!(
+            PartnerApiKey,
+            r#"
+            INSERT INTO partner_api_keys (name, key_prefix, key_hash, scopes, seller_id, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, name, key_prefix, scopes, created_by, seller_id, is_test, last_used_at, revoked_at, created_at
+            "#,
+            name,
+            key_prefix,
+            hash_token(&key),
+            scopes,
+            seller_id,
+            created_by
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok((api_key, key))
+    }
+
+    /// Issue a test-mode key for a seller's sandbox; the plaintext key is returned once
+    pub async fn create_test_key(
+        pool: &PgPool,
+        name: &str,
+        seller_id: Uuid,
+    ) -> Result<(Self, String), AppError> {
+        let key = test_key(&generate_api_key());
+        let key_prefix: String = key.chars().take(KEY_PREFIX_LENGTH).collect();
+        let scopes = vec![SCOPE_SANDBOX.to_string()];
+
+        let api_key = sqlx::query_as!(
+            PartnerApiKey,
+            r#"
+            INSERT INTO partner_api_keys (name, key_prefix, key_hash, scopes, created_by, seller_id, is_test)
+            VALUES ($1, $2, $3, $4, $5, $5, TRUE)
+            RETURNING id, name, key_prefix, scopes, created_by, seller_id, is_test, last_used_at, revoked_at, created_at
+            "#,
+            name,
+            key_prefix,
+            hash_token(&key),
+            &scopes,
+            seller_id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok((api_key, key))
+    }
+
+    /// Find an unrevoked key from its plaintext and mark it used
+    pub async fn authenticate(pool: &PgPool, key: &str) -> Result<Option<Self>, AppError> {
+        let api_key = sqlx::query_as!(
+            PartnerApiKey,
+            r#"
+            UPDATE partner_api_keys
+            SET last_used_at = NOW()
+            WHERE key_hash = $1 AND revoked_at IS NULL
+            RETURNING id, name, key_prefix, scopes, created_by, seller_id, is_test, last_used_at, revoked_at, created_at
+            "#,
+            hash_token(key)
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(api_key)
+    }
+
+    /// All keys, newest first
+    pub async fn list(pool: &PgPool) -> Result<Vec<Self>, AppError> {
+        let keys = sqlx::query_as!(
+            PartnerApiKey,
+            r#"
+            SELECT id, name, key_prefix, scopes, created_by, seller_id, is_test, last_used_at, revoked_at, created_at
+            FROM partner_api_keys
+            ORDER BY created_at DESC
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(keys)
+    }
+
+    /// A seller's test-mode keys, newest first
+    pub async fn list_test_keys(pool: &PgPool, seller_id: Uuid) -> Result<Vec<Self>, AppError> {
+        let keys = sqlx::query_as!(
+            PartnerApiKey,
+            r#"
+            SELECT id, name, key_prefix, scopes, created_by, seller_id, is_test, last_used_at, revoked_at, created_at
+            FROM partner_api_keys
+            WHERE seller_id = $1 AND is_test
+            ORDER BY created_at DESC
+            "#,
+            seller_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(keys)
+    }
+
+    /// Number of a seller's test-mode keys that are not revoked
+    pub async fn count_active_test_keys(pool: &PgPool, seller_id: Uuid) -> Result<i64, AppError> {
+        let count = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM partner_api_keys WHERE seller_id = $1 AND is_test AND revoked_at IS NULL",
+            seller_id
+        )
+        .fetch_one(pool)
+        .await?
+        .unwrap_or(0);
+
+        Ok(count)
+    }
+
+    /// Revoke one of a seller's test-mode keys; returns false if it is not theirs or already revoked
+    pub async fn revoke_test_key(pool: &PgPool, id: Uuid, seller_id: Uuid) -> Result<bool, AppError> {
+        let result = sqlx::query!(
+            "UPDATE partner_api_keys SET revoked_at = NOW() WHERE id = $1 AND seller_id = $2 AND is_test AND revoked_at IS NULL",
+            id,
+            seller_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Revoke a key; returns false if it was not found or already revoked
+    pub async fn revoke(pool: &PgPool, id: Uuid) -> Result<bool, AppError> {
+        let result = sqlx::query!(
+            "UPDATE partner_api_keys SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL",
+            id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+/// Test keys are told apart from production keys at a glance by their prefix
+fn test_key(key: &str) -> String {
+    format!("{}{}", TEST_KEY_PREFIX, key.trim_start_matches("rp_"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_test_keys_are_prefixed() {
+        let key = test_key("rp_abc123");
+        assert_eq!(key, "rp_test_abc123");
+        assert!(!KNOWN_SCOPES.contains(&SCOPE_SANDBOX));
+    }
+}
@@ -0,0 +1,370 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+
+const MAX_CREDIT_MULTIPLIER: i64 = 10;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PromotionKind {
+    /// Purchased credits are multiplied by `value`
+    CreditMultiplier,
+    /// Platform fees are reduced by `value` percent
+    FeeDiscount,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PromotionStatus {
+    Scheduled,
+    Active,
+    Ended,
+    Cancelled,
+}
+
+/// A credit multiplier or fee discount that applies between `starts_at` and `ends_at`
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Promotion {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: PromotionKind,
+    pub value: Decimal,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub status: PromotionStatus,
+    pub activated_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewPromotion {
+    pub name: String,
+    pub kind: PromotionKind,
+    pub value: Decimal,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+const COLUMNS: &str =
+    "id, name, kind, value, starts_at, ends_at, status, activated_at, ended_at, created_by, created_at";
+
+impl Promotion {
+    /// Schedule a promotion; promotions of the same kind may not overlap
+    pub async fn create(pool: &PgPool, new: &NewPromotion, created_by: Uuid) -> Result<Self, AppError> {
+        validate(new, Utc::now())?;
+
+        let overlapping = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM promotions
+            WHERE kind = $1 AND status <> 'cancelled' AND starts_at < $3 AND ends_at > $2
+            "#,
+        )
+        .bind(new.kind)
+        .bind(new.starts_at)
+        .bind(new.ends_at)
+        .fetch_one(pool)
+        .await?;
+
+        if overlapping > 0 {
+            return Err(AppError::Conflict(
+                "Another promotion of this kind overlaps that window".to_string(),
+            ));
+        }
+
+        let promotion = sqlx::query_as::<_, Self>(&format!(
+            r#"
+            INSERT INTO promotions (name, kind, value, starts_at, ends_at, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(new.name.trim())
+        .bind(new.kind)
+        .bind(new.value)
+        .bind(new.starts_at)
+        .bind(new.ends_at)
+        .bind(created_by)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(promotion)
+    }
+
+    pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Self>, AppError> {
+        let promotion = sqlx::query_as::<_, Self>(&format!("SELECT {} FROM promotions WHERE id = $1", COLUMNS))
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(promotion)
+    }
+
+    /// Promotions, latest start first, optionally by status
+    pub async fn list(pool: &PgPool, status: Option<PromotionStatus>, limit: i64) -> Result<Vec<Self>, AppError> {
+        let promotions = sqlx::query_as::<_, Self>(&format!(
+            r#"
+            SELECT {}
+            FROM promotions
+            WHERE ($1::varchar IS NULL OR status = $1)
+            ORDER BY starts_at DESC
+            LIMIT $2
+            "#,
+            COLUMNS
+        ))
+        .bind(status)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(promotions)
+    }
+
+    /// The promotion of a kind whose window contains `at`, whether or not it was announced yet
+    pub async fn active_at(pool: &PgPool, kind: PromotionKind, at: DateTime<Utc>) -> Result<Option<Self>, AppError> {
+        let promotion = sqlx::query_as::<_, Self>(&format!(
+            r#"
+            SELECT {}
+            FROM promotions
+            WHERE kind = $1 AND status <> 'cancelled' AND starts_at <= $2 AND ends_at > $2
+            ORDER BY starts_at DESC
+            LIMIT 1
+            "#,
+            COLUMNS
+        ))
+        .bind(kind)
+        .bind(at)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(promotion)
+    }
+
+    /// Every promotion whose window contains `at`
+    pub async fn all_active_at(pool: &PgPool, at: DateTime<Utc>) -> Result<Vec<Self>, AppError> {
+        let promotions = sqlx::query_as::<_, Self>(&format!(
+            r#"
+            SELECT {}
+            FROM promotions
+            WHERE status <> 'cancelled' AND starts_at <= $1 AND ends_at > $1
+            ORDER BY ends_at
+            "#,
+            COLUMNS
+        ))
+        .bind(at)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(promotions)
+    }
+
+    /// Promotions whose announced status is behind their window
+    pub async fn due_transitions(pool: &PgPool, at: DateTime<Utc>) -> Result<Vec<Self>, AppError> {
+        let promotions = sqlx::query_as::<_, Self>(&format!(
+            r#"
+            SELECT {}
+            FROM promotions
+            WHERE (status = 'scheduled' AND starts_at <= $1) OR (status = 'active' AND ends_at <= $1)
+            ORDER BY starts_at
+            "#,
+            COLUMNS
+        ))
+        .bind(at)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(promotions)
+    }
+
+    /// Move a promotion to a new status if it is still in `from`; returns false if another run got there first
+    pub async fn transition(
+        pool: &PgPool,
+        id: Uuid,
+        from: PromotionStatus,
+        to: PromotionStatus,
+    ) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE promotions
+            SET status = $3,
+                activated_at = CASE WHEN $3 = 'active' THEN NOW() ELSE activated_at END,
+                ended_at = CASE WHEN $3 IN ('ended', 'cancelled') THEN NOW() ELSE ended_at END
+            WHERE id = $1 AND status = $2
+            "#,
+        )
+        .bind(id)
+        .bind(from)
+        .bind(to)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record a bonus or discount the promotion granted
+    pub async fn record_redemption(
+        pool: &PgPool,
+        promotion_id: Uuid,
+        redemption: PromotionRedemption,
+    ) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO promotion_redemptions (promotion_id, user_id, payment_id, raffle_id, base_amount, benefit_amount)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(promotion_id)
+        .bind(redemption.user_id)
+        .bind(redemption.payment_id)
+        .bind(redemption.raffle_id)
+        .bind(redemption.base_amount)
+        .bind(redemption.benefit_amount)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Bonus credits issued for a payment, by any promotion
+    pub async fn bonus_for_payment(pool: &PgPool, payment_id: Uuid) -> Result<Decimal, AppError> {
+        let bonus = sqlx::query_scalar::<_, Option<Decimal>>(
+            "SELECT SUM(benefit_amount) FROM promotion_redemptions WHERE payment_id = $1",
+        )
+        .bind(payment_id)
+        .fetch_one(pool)
+        .await?
+        .unwrap_or(Decimal::ZERO);
+
+        Ok(bonus)
+    }
+
+    /// Bonus credits on top of a purchase under a credit multiplier
+    pub fn bonus_credits(&self, purchased: Decimal) -> Decimal {
+        match self.kind {
+            PromotionKind::CreditMultiplier => (purchased * (self.value - Decimal::ONE)).round_dp(2),
+            PromotionKind::FeeDiscount => Decimal::ZERO,
+        }
+    }
+
+    /// A fee after a fee discount
+    pub fn discount_fee(&self, fee: Decimal) -> Decimal {
+        match self.kind {
+            PromotionKind::FeeDiscount => discounted_fee(fee, self.value),
+            PromotionKind::CreditMultiplier => fee,
+        }
+    }
+}
+
+/// What a single application of a promotion granted
+#[derive(Debug, Clone, Default)]
+pub struct PromotionRedemption {
+    pub user_id: Option<Uuid>,
+    pub payment_id: Option<Uuid>,
+    pub raffle_id: Option<Uuid>,
+    pub base_amount: Decimal,
+    pub benefit_amount: Decimal,
+}
+
+/// A fee reduced by a percentage discount
+pub fn discounted_fee(fee: Decimal, discount_percentage: Decimal) -> Decimal {
+    let discount = discount_percentage.min(Decimal::from(100));
+    (fee * (Decimal::from(100) - discount) / Decimal::from(100)).round_dp(2)
+}
+
+fn validate(new: &NewPromotion, now: DateTime<Utc>) -> Result<(), AppError> {
+    let name = new.name.trim();
+    if name.is_empty() || name.len() > 100 {
+        return Err(AppError::Validation("Promotion name must be 1 to 100 characters".to_string()));
+    }
+    if new.ends_at <= new.starts_at {
+        return Err(AppError::Validation("Promotion must end after it starts".to_string()));
+    }
+    if new.ends_at <= now {
+        return Err(AppError::Validation("Promotion window is already over".to_string()));
+    }
+
+    match new.kind {
+        PromotionKind::CreditMultiplier => {
+            if new.value <= Decimal::ONE || new.value > Decimal::from(MAX_CREDIT_MULTIPLIER) {
+                return Err(AppError::Validation(format!(
+                    "Credit multiplier must be above 1 and at most {}",
+                    MAX_CREDIT_MULTIPLIER
+                )));
+            }
+        }
+        PromotionKind::FeeDiscount => {
+            if new.value <= Decimal::ZERO || new.value > Decimal::from(100) {
+                return Err(AppError::Validation(
+                    "Fee discount must be above 0 and at most 100 percent".to_string(),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn new_promotion(kind: PromotionKind, value: Decimal) -> NewPromotion {
+        let starts_at = Utc::now() + Duration::hours(1);
+        NewPromotion {
+            name: "Happy hour".to_string(),
+            kind,
+            value,
+            starts_at,
+            ends_at: starts_at + Duration::hours(2),
+        }
+    }
+
+    #[test]
+    fn test_validate_values() {
+        let now = Utc::now();
+        assert!(validate(&new_promotion(PromotionKind::CreditMultiplier, Decimal::from(2)), now).is_ok());
+        assert!(validate(&new_promotion(PromotionKind::CreditMultiplier, Decimal::ONE), now).is_err());
+        assert!(validate(&new_promotion(PromotionKind::CreditMultiplier, Decimal::from(11)), now).is_err());
+        assert!(validate(&new_promotion(PromotionKind::FeeDiscount, Decimal::from(50)), now).is_ok());
+        assert!(validate(&new_promotion(PromotionKind::FeeDiscount, Decimal::from(101)), now).is_err());
+
+        let mut reversed = new_promotion(PromotionKind::FeeDiscount, Decimal::from(10));
+        reversed.ends_at = reversed.starts_at - Duration::minutes(1);
+        assert!(validate(&reversed, now).is_err());
+    }
+
+    #[test]
+    fn test_bonus_and_discount() {
+        let new = new_promotion(PromotionKind::CreditMultiplier, Decimal::new(150, 2));
+        let promotion = Promotion {
+            id: Uuid::new_v4(),
+            name: new.name,
+            kind: new.kind,
+            value: new.value,
+            starts_at: new.starts_at,
+            ends_at: new.ends_at,
+            status: PromotionStatus::Scheduled,
+            activated_at: None,
+            ended_at: None,
+            created_by: None,
+            created_at: None,
+        };
+
+        // 1.5x on 25.00 purchased adds 12.50
+        assert_eq!(promotion.bonus_credits(Decimal::from(25)), Decimal::new(1250, 2));
+        assert_eq!(promotion.discount_fee(Decimal::from(10)), Decimal::from(10));
+
+        assert_eq!(discounted_fee(Decimal::new(1000, 2), Decimal::from(25)), Decimal::new(750, 2));
+        assert_eq!(discounted_fee(Decimal::new(1000, 2), Decimal::from(100)), Decimal::ZERO);
+    }
+}
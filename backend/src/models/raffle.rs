@@ -1,11 +1,13 @@
 use chrono::{DateTime, Utc};
-use raffle_platform_shared::{RaffleStatus, CreateRaffleRequest, RaffleResponse, BoxPurchaseResponse};
+use raffle_platform_shared::{RaffleStatus, RaffleDeadlineAction, CreateRaffleRequest, RaffleResponse, BoxPurchaseResponse};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, PgPool};
+use sqlx::{FromRow, PgExecutor, PgPool, Postgres, Transaction};
 use uuid::Uuid;
 use crate::error::AppError;
 use crate::models::item::Item;
+use crate::models::raffle_prize_tier::{default_prize_tiers, validate_prize_tiers, RafflePrizeTier};
+use crate::models::tag::{normalize_tags, MAX_TAGS};
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct Raffle {
@@ -13,6 +15,8 @@ pub struct Raffle {
     pub item_id: Uuid,
     pub total_boxes: i32,
     pub box_price: Decimal,
+    /// Always the item's currency
+    pub currency: String,
     pub boxes_sold: i32,
     pub total_winners: i32,
     pub status: RaffleStatus,
@@ -21,6 +25,11 @@ pub struct Raffle {
     pub grid_rows: i32,
     pub grid_cols: i32,
     pub transaction_fee_applied: Option<Decimal>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub deadline_action: RaffleDeadlineAction,
+    /// Most boxes one user may hold; None leaves the raffle uncapped
+    pub max_boxes_per_user: Option<i32>,
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
@@ -48,16 +57,31 @@ impl Raffle {
             ));
         }
 
+        // Every winner gets the raffle item unless tiers say otherwise
+        let prize_tiers = if request.prize_tiers.is_empty() {
+            default_prize_tiers(request.item_id, request.total_winners, request.credit_alternative)
+        } else {
+            request.prize_tiers.clone()
+        };
+        validate_prize_tiers(&prize_tiers, request.total_winners)?;
+
+        let mut tx = pool.begin().await?;
+
         let raffle = sqlx::query_as!(
             Raffle,
             r#"
-            INSERT INTO raffles (item_id, total_boxes, box_price, total_winners, grid_rows, grid_cols, transaction_fee_applied)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            INSERT INTO raffles (
+                item_id, total_boxes, box_price, total_winners, grid_rows, grid_cols,
+                transaction_fee_applied, status, starts_at, ends_at, deadline_action,
+                max_boxes_per_user, currency
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, (SELECT currency FROM items WHERE id = $1))
             RETURNING 
-                id, item_id, total_boxes, box_price, boxes_sold, total_winners,
+                id, item_id, total_boxes, box_price, currency, boxes_sold, total_winners,
                 status as "status: RaffleStatus", winner_user_ids, blockchain_tx_hash,
-                grid_rows, grid_cols, transaction_fee_applied, started_at, completed_at,
-                created_at, updated_at
+                grid_rows, grid_cols, transaction_fee_applied,
+                starts_at, ends_at, deadline_action as "deadline_action: RaffleDeadlineAction",
+                max_boxes_per_user, started_at, completed_at, created_at, updated_at
             "#,
             request.item_id,
             request.total_boxes,
@@ -65,11 +89,20 @@ impl Raffle {
             request.total_winners,
             request.grid_rows,
             request.grid_cols,
-            transaction_fee
+            transaction_fee,
+            initial_status(request.starts_at, Utc::now()) as RaffleStatus,
+            request.starts_at,
+            request.ends_at,
+            request.deadline_action as RaffleDeadlineAction,
+            request.max_boxes_per_user
         )
-        .fetch_one(pool)
+        .fetch_one(&mut *tx)
         .await?;
 
+        RafflePrizeTier::create_all(&mut tx, raffle.id, &prize_tiers).await?;
+
+        tx.commit().await?;
+
         Ok(raffle)
     }
 
@@ -79,10 +112,11 @@ impl Raffle {
             Raffle,
             r#"
             SELECT 
-                id, item_id, total_boxes, box_price, boxes_sold, total_winners,
+                id, item_id, total_boxes, box_price, currency, boxes_sold, total_winners,
                 status as "status: RaffleStatus", winner_user_ids, blockchain_tx_hash,
-                grid_rows, grid_cols, transaction_fee_applied, started_at, completed_at,
-                created_at, updated_at
+                grid_rows, grid_cols, transaction_fee_applied,
+                starts_at, ends_at, deadline_action as "deadline_action: RaffleDeadlineAction",
+                max_boxes_per_user, started_at, completed_at, created_at, updated_at
             FROM raffles 
             WHERE id = $1
             "#,
@@ -100,10 +134,11 @@ impl Raffle {
             Raffle,
             r#"
             SELECT 
-                id, item_id, total_boxes, box_price, boxes_sold, total_winners,
+                id, item_id, total_boxes, box_price, currency, boxes_sold, total_winners,
                 status as "status: RaffleStatus", winner_user_ids, blockchain_tx_hash,
-                grid_rows, grid_cols, transaction_fee_applied, started_at, completed_at,
-                created_at, updated_at
+                grid_rows, grid_cols, transaction_fee_applied,
+                starts_at, ends_at, deadline_action as "deadline_action: RaffleDeadlineAction",
+                max_boxes_per_user, started_at, completed_at, created_at, updated_at
             FROM raffles 
             WHERE item_id = $1
             ORDER BY created_at DESC
@@ -116,27 +151,30 @@ impl Raffle {
         Ok(raffles)
     }
 
-    /// Find active raffles
+    /// Find active raffles, newest first, after a `(created_at, id)` position
     pub async fn find_active(
         pool: &PgPool,
         limit: i64,
-        offset: i64,
+        after: (Option<DateTime<Utc>>, Option<Uuid>),
     ) -> Result<Vec<Self>, AppError> {
         let raffles = sqlx::query_as!(
             Raffle,
             r#"
             SELECT 
-                id, item_id, total_boxes, box_price, boxes_sold, total_winners,
+                id, item_id, total_boxes, box_price, currency, boxes_sold, total_winners,
                 status as "status: RaffleStatus", winner_user_ids, blockchain_tx_hash,
-                grid_rows, grid_cols, transaction_fee_applied, started_at, completed_at,
-                created_at, updated_at
+                grid_rows, grid_cols, transaction_fee_applied,
+                starts_at, ends_at, deadline_action as "deadline_action: RaffleDeadlineAction",
+                max_boxes_per_user, started_at, completed_at, created_at, updated_at
             FROM raffles 
             WHERE status IN ('open', 'full', 'drawing')
-            ORDER BY created_at DESC
-            LIMIT $1 OFFSET $2
+            AND ($1::timestamptz IS NULL OR (created_at, id) < ($1, $2))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $3
             "#,
-            limit,
-            offset
+            after.0,
+            after.1,
+            limit
         )
         .fetch_all(pool)
         .await?;
@@ -146,7 +184,7 @@ impl Raffle {
 
     /// Update raffle status
     pub async fn update_status(
-        pool: &PgPool,
+        executor: impl PgExecutor<'_>,
         id: Uuid,
         status: RaffleStatus,
     ) -> Result<(), AppError> {
@@ -160,7 +198,7 @@ impl Raffle {
                     now,
                     id
                 )
-                .execute(pool)
+                .execute(executor)
                 .await?;
             }
             RaffleStatus::Completed => {
@@ -170,7 +208,7 @@ impl Raffle {
                     now,
                     id
                 )
-                .execute(pool)
+                .execute(executor)
                 .await?;
             }
             _ => {
@@ -179,7 +217,7 @@ impl Raffle {
                     status as RaffleStatus,
                     id
                 )
-                .execute(pool)
+                .execute(executor)
                 .await?;
             }
         }
@@ -187,26 +225,112 @@ impl Raffle {
         Ok(())
     }
 
-    /// Update boxes sold count
-    pub async fn update_boxes_sold(
-        pool: &PgPool,
+    /// Scheduled raffles whose start time has come
+    pub async fn find_due_to_open(pool: &PgPool, now: DateTime<Utc>) -> Result<Vec<Self>, AppError> {
+        let raffles = sqlx::query_as!(
+            Raffle,
+            r#"
+            SELECT 
+                id, item_id, total_boxes, box_price, currency, boxes_sold, total_winners,
+                status as "status: RaffleStatus", winner_user_ids, blockchain_tx_hash,
+                grid_rows, grid_cols, transaction_fee_applied,
+                starts_at, ends_at, deadline_action as "deadline_action: RaffleDeadlineAction",
+                max_boxes_per_user, started_at, completed_at, created_at, updated_at
+            FROM raffles 
+            WHERE status = 'scheduled' AND starts_at <= $1
+            ORDER BY starts_at
+            "#,
+            now
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(raffles)
+    }
+
+    /// Scheduled or open raffles whose end time has passed without filling
+    pub async fn find_past_deadline(pool: &PgPool, now: DateTime<Utc>) -> Result<Vec<Self>, AppError> {
+        let raffles = sqlx::query_as!(
+            Raffle,
+            r#"
+            SELECT 
+                id, item_id, total_boxes, box_price, currency, boxes_sold, total_winners,
+                status as "status: RaffleStatus", winner_user_ids, blockchain_tx_hash,
+                grid_rows, grid_cols, transaction_fee_applied,
+                starts_at, ends_at, deadline_action as "deadline_action: RaffleDeadlineAction",
+                max_boxes_per_user, started_at, completed_at, created_at, updated_at
+            FROM raffles 
+            WHERE status IN ('scheduled', 'open') AND ends_at <= $1
+            ORDER BY ends_at
+            "#,
+            now
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(raffles)
+    }
+
+    /// Move a raffle between statuses only if it is still in `from`; false if it had already moved on
+    pub async fn transition_status(
+        executor: impl PgExecutor<'_>,
         id: Uuid,
-        boxes_sold: i32,
-    ) -> Result<(), AppError> {
-        sqlx::query!(
-            "UPDATE raffles SET boxes_sold = $1, updated_at = NOW() WHERE id = $2",
-            boxes_sold,
+        from: RaffleStatus,
+        to: RaffleStatus,
+    ) -> Result<bool, AppError> {
+        let result = sqlx::query!(
+            "UPDATE raffles SET status = $1, updated_at = NOW() WHERE id = $2 AND status = $3",
+            to as RaffleStatus,
+            id,
+            from as RaffleStatus
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Count newly sold boxes and advance the grid sequence, unless that would sell more boxes
+    /// than the raffle has. Returns the new boxes sold and grid sequence, or None when full.
+    ///
+    /// The count is incremented in place, so concurrent purchases never overwrite each other's.
+    pub async fn add_boxes_sold(
+        executor: impl PgExecutor<'_>,
+        id: Uuid,
+        count: i32,
+    ) -> Result<Option<(i32, i64)>, AppError> {
+        let row = sqlx::query!(
+            r#"
+            UPDATE raffles
+            SET boxes_sold = boxes_sold + $1, grid_sequence = grid_sequence + 1, updated_at = NOW()
+            WHERE id = $2 AND boxes_sold + $1 <= total_boxes
+            RETURNING boxes_sold AS "boxes_sold!", grid_sequence
+            "#,
+            count,
             id
         )
-        .execute(pool)
+        .fetch_optional(executor)
         .await?;
 
-        Ok(())
+        Ok(row.map(|row| (row.boxes_sold, row.grid_sequence)))
     }
 
-    /// Set winners
+    /// Current grid sequence and the last one that removed boxes
+    pub async fn grid_sequences(pool: &PgPool, id: Uuid) -> Result<Option<GridSequences>, AppError> {
+        let sequences = sqlx::query_as!(
+            GridSequences,
+            "SELECT grid_sequence AS current, grid_reset_sequence AS reset FROM raffles WHERE id = $1",
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(sequences)
+    }
+
+    /// Set winners, in draw order, and award their prize tiers in the caller's transaction
     pub async fn set_winners(
-        pool: &PgPool,
+        tx: &mut Transaction<'_, Postgres>,
         id: Uuid,
         winner_user_ids: Vec<Uuid>,
         blockchain_tx_hash: Option<String>,
@@ -222,9 +346,12 @@ impl Raffle {
             blockchain_tx_hash,
             id
         )
-        .execute(pool)
+        .execute(&mut **tx)
         .await?;
 
+        // Winners are in draw order, so the first one takes first place
+        RafflePrizeTier::assign_winners(tx, id, &winner_user_ids).await?;
+
         Ok(())
     }
 
@@ -233,12 +360,14 @@ impl Raffle {
         let result = sqlx::query!(
             r#"
             SELECT 
-                r.id, r.item_id, r.total_boxes, r.box_price, r.boxes_sold, r.total_winners,
+                r.id, r.item_id, r.total_boxes, r.box_price, r.currency, r.boxes_sold, r.total_winners,
                 r.status as "raffle_status: RaffleStatus", r.winner_user_ids, r.blockchain_tx_hash,
-                r.grid_rows, r.grid_cols, r.transaction_fee_applied, r.started_at, r.completed_at,
+                r.grid_rows, r.grid_cols, r.transaction_fee_applied,
+                r.starts_at, r.ends_at, r.deadline_action as "deadline_action: RaffleDeadlineAction",
+                r.max_boxes_per_user, r.started_at, r.completed_at,
                 r.created_at as raffle_created_at, r.updated_at as raffle_updated_at,
                 i.id as item_id, i.seller_id, i.name, i.description, i.images, 
-                i.retail_price, i.cost_of_goods, i.status as "item_status: crate::models::item::ItemStatus", 
+                i.retail_price, i.currency as item_currency, i.cost_of_goods, i.status as "item_status: crate::models::item::ItemStatus", 
                 i.stock_quantity, i.listing_fee_applied, i.listing_fee_type,
                 i.created_at as item_created_at, i.updated_at as item_updated_at
             FROM raffles r
@@ -256,6 +385,7 @@ impl Raffle {
                 item_id: row.item_id,
                 total_boxes: row.total_boxes,
                 box_price: row.box_price,
+                currency: row.currency,
                 boxes_sold: row.boxes_sold,
                 total_winners: row.total_winners,
                 status: row.raffle_status,
@@ -264,6 +394,10 @@ impl Raffle {
                 grid_rows: row.grid_rows,
                 grid_cols: row.grid_cols,
                 transaction_fee_applied: row.transaction_fee_applied,
+                starts_at: row.starts_at,
+                ends_at: row.ends_at,
+                deadline_action: row.deadline_action,
+                max_boxes_per_user: row.max_boxes_per_user,
                 started_at: row.started_at,
                 completed_at: row.completed_at,
                 created_at: row.raffle_created_at,
@@ -277,6 +411,7 @@ impl Raffle {
                 description: row.description,
                 images: row.images.unwrap(),
                 retail_price: row.retail_price.unwrap(),
+                currency: row.item_currency.unwrap(),
                 cost_of_goods: row.cost_of_goods.unwrap(),
                 status: row.item_status.unwrap(),
                 stock_quantity: row.stock_quantity.unwrap(),
@@ -303,6 +438,28 @@ impl Raffle {
         Ok(count.unwrap_or(0))
     }
 
+    /// Replace the raffle's tags
+    pub async fn set_tags(pool: &PgPool, id: Uuid, tags: &[String]) -> Result<Vec<String>, AppError> {
+        let tags = normalize_tags(tags)?;
+        if tags.len() > MAX_TAGS {
+            return Err(AppError::Validation(format!(
+                "A raffle can have at most {} tags",
+                MAX_TAGS
+            )));
+        }
+
+        let tags = sqlx::query_scalar!(
+            "UPDATE raffles SET tags = $1, updated_at = NOW() WHERE id = $2 RETURNING tags",
+            &tags,
+            id
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
+
+        Ok(tags)
+    }
+
     /// Convert to response DTO
     pub fn to_response(&self, item: Option<crate::models::item::Item>) -> RaffleResponse {
         RaffleResponse {
@@ -311,12 +468,18 @@ impl Raffle {
             item: item.map(|i| i.to_response()),
             total_boxes: self.total_boxes,
             box_price: self.box_price,
+            currency: self.currency.clone(),
+            display_box_price: None,
+            shipping_estimate: None,
             boxes_sold: self.boxes_sold,
             total_winners: self.total_winners,
             status: self.status,
             winner_user_ids: self.winner_user_ids.clone(),
             grid_rows: self.grid_rows,
             grid_cols: self.grid_cols,
+            starts_at: self.starts_at,
+            ends_at: self.ends_at,
+            deadline_action: self.deadline_action,
             started_at: self.started_at,
             completed_at: self.completed_at,
             created_at: self.created_at,
@@ -348,6 +511,31 @@ impl Raffle {
     }
 }
 
+/// Raffles with a future start time begin scheduled
+pub fn initial_status(starts_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> RaffleStatus {
+    match starts_at {
+        Some(starts_at) if starts_at > now => RaffleStatus::Scheduled,
+        _ => RaffleStatus::Open,
+    }
+}
+
+/// Where a raffle's grid is in its sequence of changes
+#[derive(Debug, Clone, Copy, FromRow)]
+pub struct GridSequences {
+    pub current: i64,
+    /// Diffs can't replay changes up to this one, which removed boxes
+    pub reset: i64,
+}
+
+/// A box sold after a given grid sequence
+#[derive(Debug, Clone, FromRow)]
+pub struct GridSale {
+    pub grid_sequence: i64,
+    pub box_number: i32,
+    pub user_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct BoxPurchase {
     pub id: Uuid,
@@ -357,13 +545,16 @@ pub struct BoxPurchase {
     pub purchase_price_in_credits: Decimal,
     pub transaction_id: Option<Uuid>,
     pub blockchain_tx_hash: Option<String>,
+    /// Item and raffle listing versions in effect at purchase time, set by the insert trigger
+    pub item_snapshot_id: Option<Uuid>,
+    pub raffle_snapshot_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
 }
 
 impl BoxPurchase {
     /// Create a new box purchase
     pub async fn create(
-        pool: &PgPool,
+        executor: impl PgExecutor<'_>,
         raffle_id: Uuid,
         user_id: Uuid,
         box_number: i32,
@@ -375,7 +566,7 @@ impl BoxPurchase {
             r#"
             INSERT INTO box_purchases (raffle_id, user_id, box_number, purchase_price_in_credits, transaction_id)
             VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, raffle_id, user_id, box_number, purchase_price_in_credits, transaction_id, blockchain_tx_hash, created_at
+            RETURNING id, raffle_id, user_id, box_number, purchase_price_in_credits, transaction_id, blockchain_tx_hash, item_snapshot_id, raffle_snapshot_id, created_at
             "#,
             raffle_id,
             user_id,
@@ -383,17 +574,59 @@ impl BoxPurchase {
             purchase_price_in_credits,
             transaction_id
         )
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await?;
 
         Ok(purchase)
     }
 
+    /// Record the grid sequence of the purchase that sold these boxes
+    pub async fn set_grid_sequence(
+        executor: impl PgExecutor<'_>,
+        ids: &[Uuid],
+        grid_sequence: i64,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE box_purchases SET grid_sequence = $1 WHERE id = ANY($2)",
+            grid_sequence,
+            ids
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Boxes sold in a raffle after a grid sequence, oldest first, up to `through`
+    pub async fn grid_sales_since(
+        pool: &PgPool,
+        raffle_id: Uuid,
+        since: i64,
+        through: i64,
+    ) -> Result<Vec<GridSale>, AppError> {
+        let sales = sqlx::query_as!(
+            GridSale,
+            r#"
+            SELECT grid_sequence AS "grid_sequence!", box_number, user_id, created_at
+            FROM box_purchases
+            WHERE raffle_id = $1 AND grid_sequence > $2 AND grid_sequence <= $3
+            ORDER BY grid_sequence, box_number
+            "#,
+            raffle_id,
+            since,
+            through
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(sales)
+    }
+
     /// Find purchases by raffle
     pub async fn find_by_raffle(pool: &PgPool, raffle_id: Uuid) -> Result<Vec<Self>, AppError> {
         let purchases = sqlx::query_as!(
             BoxPurchase,
-            "SELECT id, raffle_id, user_id, box_number, purchase_price_in_credits, transaction_id, blockchain_tx_hash, created_at FROM box_purchases WHERE raffle_id = $1 ORDER BY box_number",
+            "SELECT id, raffle_id, user_id, box_number, purchase_price_in_credits, transaction_id, blockchain_tx_hash, item_snapshot_id, raffle_snapshot_id, created_at FROM box_purchases WHERE raffle_id = $1 ORDER BY box_number",
             raffle_id
         )
         .fetch_all(pool)
@@ -411,7 +644,7 @@ impl BoxPurchase {
     ) -> Result<Vec<Self>, AppError> {
         let purchases = sqlx::query_as!(
             BoxPurchase,
-            "SELECT id, raffle_id, user_id, box_number, purchase_price_in_credits, transaction_id, blockchain_tx_hash, created_at FROM box_purchases WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3",
+            "SELECT id, raffle_id, user_id, box_number, purchase_price_in_credits, transaction_id, blockchain_tx_hash, item_snapshot_id, raffle_snapshot_id, created_at FROM box_purchases WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2 OFFSET $3",
             user_id,
             limit,
             offset
@@ -456,6 +689,23 @@ impl BoxPurchase {
         Ok(count.unwrap_or(0) > 0)
     }
 
+    /// Count the boxes a user holds in a raffle
+    pub async fn count_user_boxes(
+        executor: impl PgExecutor<'_>,
+        user_id: Uuid,
+        raffle_id: Uuid,
+    ) -> Result<i32, AppError> {
+        let count = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM box_purchases WHERE user_id = $1 AND raffle_id = $2",
+            user_id,
+            raffle_id
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(count.unwrap_or(0) as i32)
+    }
+
     /// Get user's purchases for a specific raffle
     pub async fn find_user_purchases_for_raffle(
         pool: &PgPool,
@@ -464,7 +714,7 @@ impl BoxPurchase {
     ) -> Result<Vec<Self>, AppError> {
         let purchases = sqlx::query_as!(
             BoxPurchase,
-            "SELECT id, raffle_id, user_id, box_number, purchase_price_in_credits, transaction_id, blockchain_tx_hash, created_at FROM box_purchases WHERE user_id = $1 AND raffle_id = $2 ORDER BY box_number",
+            "SELECT id, raffle_id, user_id, box_number, purchase_price_in_credits, transaction_id, blockchain_tx_hash, item_snapshot_id, raffle_snapshot_id, created_at FROM box_purchases WHERE user_id = $1 AND raffle_id = $2 ORDER BY box_number",
             user_id,
             raffle_id
         )
@@ -483,6 +733,8 @@ impl BoxPurchase {
             box_number: self.box_number,
             purchase_price_in_credits: self.purchase_price_in_credits,
             blockchain_tx_hash: self.blockchain_tx_hash.clone(),
+            item_snapshot_id: self.item_snapshot_id,
+            raffle_snapshot_id: self.raffle_snapshot_id,
             created_at: self.created_at,
         }
     }
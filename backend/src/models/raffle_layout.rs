@@ -0,0 +1,251 @@
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::{GridCell, RaffleLayoutDefinition};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use uuid::Uuid;
+use crate::error::AppError;
+
+const MAX_LAYOUT_NAME_LENGTH: usize = 50;
+
+/// Stored layout of a non-rectangular raffle; rectangular raffles have no row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaffleLayout {
+    pub raffle_id: Uuid,
+    pub name: Option<String>,
+    pub disabled_cells: Vec<GridCell>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl RaffleLayout {
+    /// Store the layout for a raffle
+    pub async fn create(
+        pool: &PgPool,
+        raffle_id: Uuid,
+        definition: &RaffleLayoutDefinition,
+    ) -> Result<Self, AppError> {
+        let disabled_cells = serde_json::to_value(&definition.disabled_cells)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize layout: {}", e)))?;
+
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO raffle_layouts (raffle_id, name, disabled_cells)
+            VALUES ($1, $2, $3)
+            RETURNING raffle_id, name, disabled_cells, created_at
+            "#,
+            raffle_id,
+            definition.name,
+            disabled_cells
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(Self {
+            raffle_id: row.raffle_id,
+            name: row.name,
+            disabled_cells: parse_cells(row.disabled_cells)?,
+            created_at: row.created_at,
+        })
+    }
+
+    /// Find the layout of a raffle
+    pub async fn find_by_raffle(pool: &PgPool, raffle_id: Uuid) -> Result<Option<Self>, AppError> {
+        let row = sqlx::query!(
+            "SELECT raffle_id, name, disabled_cells, created_at FROM raffle_layouts WHERE raffle_id = $1",
+            raffle_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(|row| {
+            Ok(Self {
+                raffle_id: row.raffle_id,
+                name: row.name,
+                disabled_cells: parse_cells(row.disabled_cells)?,
+                created_at: row.created_at,
+            })
+        })
+        .transpose()
+    }
+}
+
+fn parse_cells(value: serde_json::Value) -> Result<Vec<GridCell>, AppError> {
+    serde_json::from_value(value)
+        .map_err(|e| AppError::Internal(format!("Invalid stored raffle layout: {}", e)))
+}
+
+/// Rows x cols grid with optional disabled cells, mapping boxes to cells
+///
+/// Boxes are numbered 1..=purchasable_boxes across the enabled cells in row-major order,
+/// so a rectangular grid keeps the numbering it always had.
+#[derive(Debug, Clone)]
+pub struct GridLayout {
+    rows: i32,
+    cols: i32,
+    disabled: HashSet<i32>,
+}
+
+impl GridLayout {
+    /// Build and validate a layout; disabled cells must lie inside the grid and be unique
+    pub fn new(rows: i32, cols: i32, definition: Option<&RaffleLayoutDefinition>) -> Result<Self, AppError> {
+        if rows <= 0 || cols <= 0 {
+            return Err(AppError::Validation("Grid dimensions must be positive".to_string()));
+        }
+
+        rows.checked_mul(cols)
+            .ok_or_else(|| AppError::Validation("Grid is too large".to_string()))?;
+
+        let mut disabled = HashSet::new();
+        if let Some(definition) = definition {
+            if definition.name.as_ref().map_or(false, |n| n.chars().count() > MAX_LAYOUT_NAME_LENGTH) {
+                return Err(AppError::Validation(format!(
+                    "Layout name cannot exceed {} characters",
+                    MAX_LAYOUT_NAME_LENGTH
+                )));
+            }
+
+            for cell in &definition.disabled_cells {
+                if cell.row < 0 || cell.row >= rows || cell.col < 0 || cell.col >= cols {
+                    return Err(AppError::Validation(format!(
+                        "Disabled cell ({}, {}) is outside the {}x{} grid",
+                        cell.row, cell.col, rows, cols
+                    )));
+                }
+
+                if !disabled.insert(cell.row * cols + cell.col) {
+                    return Err(AppError::Validation(format!(
+                        "Disabled cell ({}, {}) is listed more than once",
+                        cell.row, cell.col
+                    )));
+                }
+            }
+        }
+
+        Ok(Self { rows, cols, disabled })
+    }
+
+    /// Layout of a stored raffle
+    pub fn for_raffle(rows: i32, cols: i32, layout: Option<&RaffleLayout>) -> Result<Self, AppError> {
+        let definition = layout.map(|l| RaffleLayoutDefinition {
+            name: l.name.clone(),
+            disabled_cells: l.disabled_cells.clone(),
+        });
+        Self::new(rows, cols, definition.as_ref())
+    }
+
+    /// Cells that hold a box
+    pub fn purchasable_boxes(&self) -> i32 {
+        self.rows * self.cols - self.disabled.len() as i32
+    }
+
+    /// Box number at a cell, or None for disabled and out-of-grid cells
+    pub fn box_at(&self, cell: GridCell) -> Option<i32> {
+        if cell.row < 0 || cell.row >= self.rows || cell.col < 0 || cell.col >= self.cols {
+            return None;
+        }
+
+        let index = cell.row * self.cols + cell.col;
+        if self.disabled.contains(&index) {
+            return None;
+        }
+
+        let disabled_before = self.disabled.iter().filter(|&&d| d < index).count() as i32;
+        Some(index - disabled_before + 1)
+    }
+
+    /// Cell holding a box number
+    pub fn cell_of(&self, box_number: i32) -> Option<GridCell> {
+        if box_number < 1 || box_number > self.purchasable_boxes() {
+            return None;
+        }
+
+        let mut remaining = box_number;
+        for index in 0..self.rows * self.cols {
+            if self.disabled.contains(&index) {
+                continue;
+            }
+            remaining -= 1;
+            if remaining == 0 {
+                return Some(GridCell { row: index / self.cols, col: index % self.cols });
+            }
+        }
+
+        None
+    }
+
+    /// Row-major mask of box numbers, with None for disabled cells
+    pub fn mask(&self) -> Vec<Vec<Option<i32>>> {
+        let mut next_box = 1;
+        (0..self.rows)
+            .map(|row| {
+                (0..self.cols)
+                    .map(|col| {
+                        if self.disabled.contains(&(row * self.cols + col)) {
+                            None
+                        } else {
+                            next_box += 1;
+                            Some(next_box - 1)
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heart() -> RaffleLayoutDefinition {
+        // 3x3 with both top corners cut off
+        RaffleLayoutDefinition {
+            name: Some("heart".to_string()),
+            disabled_cells: vec![GridCell { row: 0, col: 0 }, GridCell { row: 0, col: 2 }],
+        }
+    }
+
+    #[test]
+    fn test_rectangular_layout_keeps_numbering() {
+        let layout = GridLayout::new(2, 3, None).unwrap();
+
+        assert_eq!(layout.purchasable_boxes(), 6);
+        assert_eq!(layout.box_at(GridCell { row: 1, col: 0 }), Some(4));
+        assert_eq!(layout.cell_of(6), Some(GridCell { row: 1, col: 2 }));
+    }
+
+    #[test]
+    fn test_disabled_cells_are_skipped() {
+        let layout = GridLayout::new(3, 3, Some(&heart())).unwrap();
+
+        assert_eq!(layout.purchasable_boxes(), 7);
+        assert_eq!(layout.box_at(GridCell { row: 0, col: 0 }), None);
+        assert_eq!(layout.box_at(GridCell { row: 0, col: 1 }), Some(1));
+        assert_eq!(layout.box_at(GridCell { row: 1, col: 0 }), Some(2));
+        assert_eq!(layout.cell_of(7), Some(GridCell { row: 2, col: 2 }));
+        assert_eq!(layout.cell_of(8), None);
+        assert_eq!(
+            layout.mask(),
+            vec![
+                vec![None, Some(1), None],
+                vec![Some(2), Some(3), Some(4)],
+                vec![Some(5), Some(6), Some(7)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_disabled_cells_are_rejected() {
+        let outside = RaffleLayoutDefinition {
+            name: None,
+            disabled_cells: vec![GridCell { row: 3, col: 0 }],
+        };
+        assert!(GridLayout::new(3, 3, Some(&outside)).is_err());
+
+        let duplicate = RaffleLayoutDefinition {
+            name: None,
+            disabled_cells: vec![GridCell { row: 1, col: 1 }, GridCell { row: 1, col: 1 }],
+        };
+        assert!(GridLayout::new(3, 3, Some(&duplicate)).is_err());
+    }
+}
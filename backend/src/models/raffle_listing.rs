@@ -0,0 +1,411 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use raffle_platform_shared::{ItemResponse, ItemStatus, RaffleDeadlineAction, RaffleResponse, RaffleStatus};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+use crate::error::AppError;
+
+/// Sort orders of the raffle listing endpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListingSort {
+    Newest,
+    Oldest,
+    PriceAsc,
+    PriceDesc,
+    /// Fullest first
+    Completion,
+    /// Soonest deadline first; raffles without one are left out
+    EndingSoon,
+    /// Verified sellers first, then fullest
+    Featured,
+}
+
+impl ListingSort {
+    /// Parse a `sort_by` value; none means newest first
+    pub fn parse(value: Option<&str>) -> Result<Self, AppError> {
+        match value.map(str::trim).filter(|v| !v.is_empty()) {
+            None | Some("created_desc") | Some("newest") => Ok(Self::Newest),
+            Some("created_asc") | Some("oldest") => Ok(Self::Oldest),
+            Some("price_asc") => Ok(Self::PriceAsc),
+            Some("price_desc") => Ok(Self::PriceDesc),
+            Some("completion") => Ok(Self::Completion),
+            Some("ending_soon") => Ok(Self::EndingSoon),
+            Some("featured") => Ok(Self::Featured),
+            Some(other) => Err(AppError::Validation(format!("Invalid sort order: {}", other))),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Newest => "created_desc",
+            Self::Oldest => "created_asc",
+            Self::PriceAsc => "price_asc",
+            Self::PriceDesc => "price_desc",
+            Self::Completion => "completion",
+            Self::EndingSoon => "ending_soon",
+            Self::Featured => "featured",
+        }
+    }
+
+    /// Sort column and its SQL type, for the keyset comparison
+    fn column(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::Newest | Self::Oldest => ("created_at", "timestamptz"),
+            Self::PriceAsc | Self::PriceDesc => ("box_price", "numeric"),
+            Self::Completion => ("completion_percentage", "numeric"),
+            Self::EndingSoon => ("ends_at", "timestamptz"),
+            Self::Featured => ("featured_score", "numeric"),
+        }
+    }
+
+    fn ascending(&self) -> bool {
+        matches!(self, Self::Oldest | Self::PriceAsc | Self::EndingSoon)
+    }
+
+    /// `WHERE` and `ORDER BY` fragments; the cursor key and id bind to `$key` and `$key + 1`
+    fn keyset_sql(&self, key: usize) -> (String, String) {
+        let (column, sql_type) = self.column();
+        let (op, direction) = if self.ascending() { (">", "ASC") } else { ("<", "DESC") };
+
+        let bound = format!(
+            "(${key}::text IS NULL OR ({column}, raffle_id) {op} (${key}::{sql_type}, ${id}))",
+            key = key,
+            id = key + 1,
+            column = column,
+            op = op,
+            sql_type = sql_type,
+        );
+        let order = format!("{column} {direction}, raffle_id {direction}", column = column, direction = direction);
+
+        (bound, order)
+    }
+}
+
+/// Position in a listing: the sort key and id of the last row seen, tied to one sort order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingCursor {
+    pub sort: ListingSort,
+    pub key: String,
+    pub id: Uuid,
+}
+
+impl ListingCursor {
+    /// Encode as the token handed to clients
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}|{}|{}", self.sort.as_str(), self.key, self.id))
+    }
+
+    /// Decode a token produced by `encode` for the same sort order
+    pub fn decode(token: &str, sort: ListingSort) -> Result<Self, AppError> {
+        let invalid = || AppError::Validation("Invalid pagination cursor".to_string());
+
+        let bytes = URL_SAFE_NO_PAD.decode(token.trim()).map_err(|_| invalid())?;
+        let raw = String::from_utf8(bytes).map_err(|_| invalid())?;
+        let mut parts = raw.splitn(3, '|');
+        let (encoded_sort, key, id) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(s), Some(k), Some(i)) => (s, k, i),
+            _ => return Err(invalid()),
+        };
+
+        if ListingSort::parse(Some(encoded_sort)).ok() != Some(sort) {
+            return Err(AppError::Validation(
+                "Pagination cursor belongs to a different sort order".to_string(),
+            ));
+        }
+
+        let id = Uuid::parse_str(id).map_err(|_| invalid())?;
+        // A malformed key would only fail inside Postgres; report it as a bad cursor instead
+        let valid_key = match sort.column().1 {
+            "timestamptz" => DateTime::parse_from_rfc3339(key).is_ok(),
+            _ => key.parse::<Decimal>().is_ok(),
+        };
+        if !valid_key {
+            return Err(invalid());
+        }
+
+        Ok(Self { sort, key: key.to_string(), id })
+    }
+}
+
+/// Filters of a listing page
+#[derive(Debug, Clone, Default)]
+pub struct ListingFilter {
+    /// None lists open, full and drawing raffles
+    pub status: Option<RaffleStatus>,
+    pub item_id: Option<Uuid>,
+    pub min_price: Option<Decimal>,
+    pub max_price: Option<Decimal>,
+    pub completion_min: Option<Decimal>,
+    pub completion_max: Option<Decimal>,
+    /// Canonical tag slugs; empty leaves tags unfiltered
+    pub tags: Vec<String>,
+    /// Require every tag rather than any of them
+    pub match_all_tags: bool,
+    /// Item categories, a category and those below it; empty leaves categories unfiltered
+    pub category_ids: Vec<i64>,
+    /// JSON object the item's attributes must contain
+    pub attributes: Option<serde_json::Value>,
+}
+
+/// A raffle as public listings show it, denormalized with its item and seller
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct RaffleListing {
+    pub raffle_id: Uuid,
+    pub item_id: Uuid,
+    pub total_boxes: i32,
+    pub box_price: Decimal,
+    pub currency: String,
+    pub boxes_sold: i32,
+    pub total_winners: i32,
+    pub status: RaffleStatus,
+    pub winner_user_ids: Vec<Uuid>,
+    pub grid_rows: i32,
+    pub grid_cols: i32,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub deadline_action: RaffleDeadlineAction,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub completion_percentage: Decimal,
+    pub featured_score: Decimal,
+    pub item_seller_id: Option<Uuid>,
+    pub item_name: String,
+    pub item_description: Option<String>,
+    pub item_images: Vec<String>,
+    pub item_retail_price: Decimal,
+    pub item_cost_of_goods: Decimal,
+    pub item_status: ItemStatus,
+    pub item_stock_quantity: i32,
+    pub item_listing_fee_applied: Option<Decimal>,
+    pub item_category: Option<String>,
+    pub item_created_at: DateTime<Utc>,
+    pub item_updated_at: DateTime<Utc>,
+    pub seller_company_name: Option<String>,
+    pub seller_is_verified: bool,
+    /// The raffle's tags together with its item's
+    pub tags: Vec<String>,
+}
+
+/// A listing matching a full-text query
+#[derive(Debug, Clone, FromRow)]
+pub struct RankedListing {
+    #[sqlx(flatten)]
+    pub listing: RaffleListing,
+    /// Relevance between 0 and 1
+    pub score: f32,
+    /// Listings matching the query across every page
+    pub total_matches: i64,
+}
+
+const COLUMNS: &str = "raffle_id, item_id, total_boxes, box_price, currency, boxes_sold, total_winners, status, \
+    winner_user_ids, grid_rows, grid_cols, starts_at, ends_at, deadline_action, started_at, completed_at, \
+    created_at, completion_percentage, featured_score, item_seller_id, item_name, item_description, \
+    item_images, item_retail_price, item_cost_of_goods, item_status, item_stock_quantity, \
+    item_listing_fee_applied, item_category, item_created_at, item_updated_at, seller_company_name, \
+    seller_is_verified, tags";
+
+impl RaffleListing {
+    /// One page of listings in `sort` order, after `cursor`
+    pub async fn search(
+        pool: &PgPool,
+        filter: &ListingFilter,
+        sort: ListingSort,
+        cursor: Option<&ListingCursor>,
+        limit: i64,
+    ) -> Result<Vec<Self>, AppError> {
+        let (bound, order) = sort.keyset_sql(6);
+        let ending = if sort == ListingSort::EndingSoon { "AND ends_at IS NOT NULL" } else { "" };
+        let tag_match = if filter.match_all_tags { "@>" } else { "&&" };
+
+        let listings = sqlx::query_as::<_, Self>(&format!(
+            r#"
+            SELECT {}
+            FROM raffle_listings
+            WHERE {}
+            AND ($1::uuid IS NULL OR item_id = $1)
+            AND ($2::numeric IS NULL OR box_price >= $2)
+            AND ($3::numeric IS NULL OR box_price <= $3)
+            AND ($4::numeric IS NULL OR completion_percentage >= $4)
+            AND ($5::numeric IS NULL OR completion_percentage <= $5)
+            AND (cardinality($9::text[]) = 0 OR tags {} $9)
+            AND (cardinality($10::bigint[]) = 0 OR item_category_id = ANY($10))
+            AND ($11::jsonb IS NULL OR item_attributes @> $11)
+            {}
+            AND {}
+            ORDER BY {}
+            LIMIT $8
+            "#,
+            COLUMNS,
+            status_predicate(filter.status),
+            tag_match,
+            ending,
+            bound,
+            order
+        ))
+        .bind(filter.item_id)
+        .bind(filter.min_price)
+        .bind(filter.max_price)
+        .bind(filter.completion_min)
+        .bind(filter.completion_max)
+        .bind(cursor.map(|c| c.key.clone()))
+        .bind(cursor.map(|c| c.id))
+        .bind(limit)
+        .bind(&filter.tags)
+        .bind(&filter.category_ids)
+        .bind(&filter.attributes)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(listings)
+    }
+
+    /// Active listings matching a `to_tsquery` query, most relevant first
+    pub async fn full_text_search(
+        pool: &PgPool,
+        tsquery: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RankedListing>, AppError> {
+        let listings = sqlx::query_as::<_, RankedListing>(&format!(
+            r#"
+            SELECT {}, ts_rank(search_vector, query, 32) AS score, COUNT(*) OVER () AS total_matches
+            FROM raffle_listings
+            CROSS JOIN to_tsquery('english', $1) query
+            WHERE {}
+            AND search_vector @@ query
+            ORDER BY score DESC, featured_score DESC, raffle_id
+            LIMIT $2 OFFSET $3
+            "#,
+            COLUMNS,
+            status_predicate(None)
+        ))
+        .bind(tsquery)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(listings)
+    }
+
+    /// Cursor pointing just past this row in `sort` order
+    pub fn cursor(&self, sort: ListingSort) -> ListingCursor {
+        let timestamp = |at: DateTime<Utc>| at.to_rfc3339_opts(SecondsFormat::Micros, true);
+        let key = match sort {
+            ListingSort::Newest | ListingSort::Oldest => timestamp(self.created_at),
+            ListingSort::PriceAsc | ListingSort::PriceDesc => self.box_price.to_string(),
+            ListingSort::Completion => self.completion_percentage.to_string(),
+            // Rows without a deadline never reach an ending-soon page
+            ListingSort::EndingSoon => timestamp(self.ends_at.unwrap_or(self.created_at)),
+            ListingSort::Featured => self.featured_score.to_string(),
+        };
+
+        ListingCursor { sort, key, id: self.raffle_id }
+    }
+
+    /// Convert to the raffle response the listing endpoints return
+    pub fn to_response(&self) -> RaffleResponse {
+        RaffleResponse {
+            id: self.raffle_id,
+            item_id: self.item_id,
+            item: Some(ItemResponse {
+                id: self.item_id,
+                seller_id: self.item_seller_id,
+                name: self.item_name.clone(),
+                description: self.item_description.clone(),
+                images: self.item_images.clone(),
+                image_variants: Vec::new(),
+                retail_price: self.item_retail_price,
+                currency: self.currency.clone(),
+                display_retail_price: None,
+                cost_of_goods: self.item_cost_of_goods,
+                status: self.item_status,
+                stock_quantity: self.item_stock_quantity,
+                listing_fee_applied: self.item_listing_fee_applied,
+                created_at: self.item_created_at,
+                updated_at: self.item_updated_at,
+            }),
+            total_boxes: self.total_boxes,
+            box_price: self.box_price,
+            currency: self.currency.clone(),
+            display_box_price: None,
+            shipping_estimate: None,
+            boxes_sold: self.boxes_sold,
+            total_winners: self.total_winners,
+            status: self.status,
+            winner_user_ids: self.winner_user_ids.clone(),
+            grid_rows: self.grid_rows,
+            grid_cols: self.grid_cols,
+            starts_at: self.starts_at,
+            ends_at: self.ends_at,
+            deadline_action: self.deadline_action,
+            started_at: self.started_at,
+            completed_at: self.completed_at,
+            created_at: self.created_at,
+        }
+    }
+}
+
+/// Status condition of a listing query, written out so Postgres can match the partial indexes
+fn status_predicate(status: Option<RaffleStatus>) -> &'static str {
+    match status {
+        None => "status IN ('open', 'full', 'drawing')",
+        Some(RaffleStatus::Scheduled) => "status = 'scheduled'",
+        Some(RaffleStatus::Open) => "status = 'open'",
+        Some(RaffleStatus::Full) => "status = 'full'",
+        Some(RaffleStatus::Drawing) => "status = 'drawing'",
+        Some(RaffleStatus::Completed) => "status = 'completed'",
+        Some(RaffleStatus::Cancelled) => "status = 'cancelled'",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_parsing() {
+        assert_eq!(ListingSort::parse(None).unwrap(), ListingSort::Newest);
+        assert_eq!(ListingSort::parse(Some("price_desc")).unwrap(), ListingSort::PriceDesc);
+        assert_eq!(ListingSort::parse(Some("featured")).unwrap(), ListingSort::Featured);
+        assert!(ListingSort::parse(Some("random")).is_err());
+    }
+
+    #[test]
+    fn test_keyset_direction_follows_sort() {
+        let (bound, order) = ListingSort::PriceAsc.keyset_sql(6);
+        assert_eq!(bound, "($6::text IS NULL OR (box_price, raffle_id) > ($6::numeric, $7))");
+        assert_eq!(order, "box_price ASC, raffle_id ASC");
+
+        let (bound, order) = ListingSort::Newest.keyset_sql(6);
+        assert!(bound.contains("(created_at, raffle_id) < ($6::timestamptz, $7)"));
+        assert_eq!(order, "created_at DESC, raffle_id DESC");
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = ListingCursor {
+            sort: ListingSort::Completion,
+            key: "42.50".to_string(),
+            id: Uuid::new_v4(),
+        };
+
+        let decoded = ListingCursor::decode(&cursor.encode(), ListingSort::Completion).unwrap();
+        assert_eq!(decoded, cursor);
+    }
+
+    #[test]
+    fn test_cursor_rejects_other_sort_and_bad_keys() {
+        let cursor = ListingCursor { sort: ListingSort::PriceAsc, key: "5.00".to_string(), id: Uuid::new_v4() };
+        assert!(ListingCursor::decode(&cursor.encode(), ListingSort::PriceDesc).is_err());
+
+        let malformed = ListingCursor { sort: ListingSort::PriceAsc, key: "cheap".to_string(), id: Uuid::new_v4() };
+        assert!(ListingCursor::decode(&malformed.encode(), ListingSort::PriceAsc).is_err());
+
+        assert!(ListingCursor::decode("not-a-cursor", ListingSort::Newest).is_err());
+    }
+}
@@ -0,0 +1,409 @@
+use raffle_platform_shared::CreateRaffleRequest;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::{AppError, FieldError};
+use crate::models::system_settings::SystemSetting;
+
+/// Setting holding the platform-wide policy
+const POLICY_KEY: &str = "raffle_policy";
+/// Prefix of the settings holding one subscription tier's overrides, followed by the tier id
+const TIER_POLICY_KEY_PREFIX: &str = "raffle_policy_tier:";
+
+/// Platform bounds on the raffles sellers may create, checked at creation and fee preview
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RafflePolicy {
+    pub min_boxes: i32,
+    pub max_boxes: i32,
+    /// Bounds on the base box price and on every price of a pricing curve
+    pub min_box_price: Decimal,
+    pub max_box_price: Decimal,
+    /// Most winners per box sold, e.g. 0.5 allows one winner for every two boxes
+    pub max_winners_ratio: Decimal,
+    pub max_grid_rows: i32,
+    pub max_grid_cols: i32,
+}
+
+impl Default for RafflePolicy {
+    fn default() -> Self {
+        Self {
+            min_boxes: 2,
+            max_boxes: 10000,
+            min_box_price: Decimal::new(100, 2),
+            max_box_price: Decimal::new(10000, 0),
+            max_winners_ratio: Decimal::new(5, 1),
+            max_grid_rows: 100,
+            max_grid_cols: 100,
+        }
+    }
+}
+
+/// A subscription tier's departures from the platform policy; fields left out follow it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RafflePolicyOverrides {
+    pub min_boxes: Option<i32>,
+    pub max_boxes: Option<i32>,
+    pub min_box_price: Option<Decimal>,
+    pub max_box_price: Option<Decimal>,
+    pub max_winners_ratio: Option<Decimal>,
+    pub max_grid_rows: Option<i32>,
+    pub max_grid_cols: Option<i32>,
+}
+
+/// One subscription tier's overrides and the policy its sellers get
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TierRafflePolicy {
+    pub subscription_id: Uuid,
+    pub subscription_name: String,
+    pub overrides: RafflePolicyOverrides,
+    pub effective: RafflePolicy,
+}
+
+impl RafflePolicy {
+    /// The platform policy; a missing or unreadable setting falls back to the defaults
+    pub async fn platform(pool: &PgPool) -> Result<Self, AppError> {
+        let Some(setting) = SystemSetting::get_by_key(pool, POLICY_KEY).await? else {
+            return Ok(Self::default());
+        };
+
+        match serde_json::from_value(setting.value) {
+            Ok(policy) => Ok(policy),
+            Err(e) => {
+                warn!("Invalid {} setting, using defaults: {}", POLICY_KEY, e);
+                Ok(Self::default())
+            }
+        }
+    }
+
+    /// The policy for sellers on a subscription tier, or the platform policy without one
+    pub async fn for_tier(pool: &PgPool, subscription_id: Option<Uuid>) -> Result<Self, AppError> {
+        let platform = Self::platform(pool).await?;
+        let Some(subscription_id) = subscription_id else {
+            return Ok(platform);
+        };
+
+        let overrides = RafflePolicyOverrides::find(pool, subscription_id).await?.unwrap_or_default();
+        Ok(platform.with_overrides(&overrides))
+    }
+
+    /// Replace the platform policy; raffles already created are unaffected
+    pub async fn set_platform(pool: &PgPool, policy: RafflePolicy) -> Result<Self, AppError> {
+        policy.validate()?;
+
+        SystemSetting::upsert(
+            pool,
+            POLICY_KEY.to_string(),
+            serde_json::to_value(&policy).map_err(|e| AppError::Internal(e.to_string()))?,
+            Some("Bounds on raffle size, price, winners and grid for every seller".to_string()),
+            false,
+        )
+        .await?;
+
+        Ok(policy)
+    }
+
+    pub fn with_overrides(&self, overrides: &RafflePolicyOverrides) -> Self {
+        Self {
+            min_boxes: overrides.min_boxes.unwrap_or(self.min_boxes),
+            max_boxes: overrides.max_boxes.unwrap_or(self.max_boxes),
+            min_box_price: overrides.min_box_price.unwrap_or(self.min_box_price),
+            max_box_price: overrides.max_box_price.unwrap_or(self.max_box_price),
+            max_winners_ratio: overrides.max_winners_ratio.unwrap_or(self.max_winners_ratio),
+            max_grid_rows: overrides.max_grid_rows.unwrap_or(self.max_grid_rows),
+            max_grid_cols: overrides.max_grid_cols.unwrap_or(self.max_grid_cols),
+        }
+    }
+
+    /// Reject a policy no raffle could satisfy
+    pub fn validate(&self) -> Result<(), AppError> {
+        let mut errors = Vec::new();
+
+        if self.min_boxes < 1 {
+            errors.push(FieldError::new("min_boxes", "below_minimum", "Minimum boxes must be at least 1").with_limit(1));
+        }
+        if self.max_boxes < self.min_boxes {
+            errors.push(
+                FieldError::new("max_boxes", "below_minimum", "Maximum boxes cannot be below minimum boxes")
+                    .with_limit(self.min_boxes),
+            );
+        }
+        if self.min_box_price <= Decimal::ZERO {
+            errors.push(FieldError::new("min_box_price", "below_minimum", "Minimum box price must be positive"));
+        }
+        if self.max_box_price < self.min_box_price {
+            errors.push(
+                FieldError::new("max_box_price", "below_minimum", "Maximum box price cannot be below minimum box price")
+                    .with_limit(self.min_box_price),
+            );
+        }
+        if self.max_winners_ratio <= Decimal::ZERO || self.max_winners_ratio > Decimal::ONE {
+            errors.push(
+                FieldError::new("max_winners_ratio", "out_of_range", "Winners ratio must be above 0 and at most 1")
+                    .with_limit(Decimal::ONE),
+            );
+        }
+        if self.max_grid_rows < 1 {
+            errors.push(FieldError::new("max_grid_rows", "below_minimum", "Grid rows must be at least 1").with_limit(1));
+        }
+        if self.max_grid_cols < 1 {
+            errors.push(FieldError::new("max_grid_cols", "below_minimum", "Grid columns must be at least 1").with_limit(1));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::InvalidFields(errors))
+        }
+    }
+
+    /// Every way the request breaks the policy; empty when it complies
+    pub fn check(&self, request: &CreateRaffleRequest) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        if request.total_boxes < self.min_boxes {
+            errors.push(
+                FieldError::new(
+                    "total_boxes",
+                    "below_minimum",
+                    format!("A raffle needs at least {} boxes", self.min_boxes),
+                )
+                .with_limit(self.min_boxes),
+            );
+        }
+        if request.total_boxes > self.max_boxes {
+            errors.push(
+                FieldError::new(
+                    "total_boxes",
+                    "above_maximum",
+                    format!("A raffle can have at most {} boxes", self.max_boxes),
+                )
+                .with_limit(self.max_boxes),
+            );
+        }
+
+        let mut prices = vec![("box_price".to_string(), request.box_price)];
+        if let Some(curve) = &request.pricing_curve {
+            prices.extend(
+                curve
+                    .tiers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, tier)| (format!("pricing_curve.tiers[{}].box_price", i), tier.box_price)),
+            );
+        }
+        for (field, price) in prices {
+            if price < self.min_box_price {
+                errors.push(
+                    FieldError::new(
+                        field,
+                        "below_minimum",
+                        format!("Box prices must be at least {}", self.min_box_price),
+                    )
+                    .with_limit(self.min_box_price),
+                );
+            } else if price > self.max_box_price {
+                errors.push(
+                    FieldError::new(
+                        field,
+                        "above_maximum",
+                        format!("Box prices can be at most {}", self.max_box_price),
+                    )
+                    .with_limit(self.max_box_price),
+                );
+            }
+        }
+
+        let max_winners = self.max_winners(request.total_boxes);
+        if request.total_winners > max_winners {
+            errors.push(
+                FieldError::new(
+                    "total_winners",
+                    "above_maximum",
+                    format!("A raffle of {} boxes can have at most {} winners", request.total_boxes, max_winners),
+                )
+                .with_limit(max_winners),
+            );
+        }
+
+        if request.grid_rows > self.max_grid_rows {
+            errors.push(
+                FieldError::new(
+                    "grid_rows",
+                    "above_maximum",
+                    format!("Grids can have at most {} rows", self.max_grid_rows),
+                )
+                .with_limit(self.max_grid_rows),
+            );
+        }
+        if request.grid_cols > self.max_grid_cols {
+            errors.push(
+                FieldError::new(
+                    "grid_cols",
+                    "above_maximum",
+                    format!("Grids can have at most {} columns", self.max_grid_cols),
+                )
+                .with_limit(self.max_grid_cols),
+            );
+        }
+
+        errors
+    }
+
+    /// Most winners allowed for a raffle of this size; always at least one
+    pub fn max_winners(&self, total_boxes: i32) -> i32 {
+        let max = (Decimal::from(total_boxes.max(0)) * self.max_winners_ratio).floor();
+        max.to_i32().unwrap_or(i32::MAX).max(1)
+    }
+}
+
+impl RafflePolicyOverrides {
+    pub async fn find(pool: &PgPool, subscription_id: Uuid) -> Result<Option<Self>, AppError> {
+        let Some(setting) = SystemSetting::get_by_key(pool, &tier_key(subscription_id)).await? else {
+            return Ok(None);
+        };
+
+        match serde_json::from_value(setting.value) {
+            Ok(overrides) => Ok(Some(overrides)),
+            Err(e) => {
+                warn!("Invalid raffle policy overrides for tier {}, ignoring: {}", subscription_id, e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Replace a tier's overrides; the policy they produce must itself be valid
+    pub async fn set(pool: &PgPool, subscription_id: Uuid, overrides: RafflePolicyOverrides) -> Result<RafflePolicy, AppError> {
+        let effective = RafflePolicy::platform(pool).await?.with_overrides(&overrides);
+        effective.validate()?;
+
+        SystemSetting::upsert(
+            pool,
+            tier_key(subscription_id),
+            serde_json::to_value(&overrides).map_err(|e| AppError::Internal(e.to_string()))?,
+            Some("Raffle policy overrides for one seller subscription tier".to_string()),
+            false,
+        )
+        .await?;
+
+        Ok(effective)
+    }
+
+    pub async fn remove(pool: &PgPool, subscription_id: Uuid) -> Result<(), AppError> {
+        if !SystemSetting::delete_by_key(pool, &tier_key(subscription_id)).await? {
+            return Err(AppError::NotFound("Subscription tier has no raffle policy overrides".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn tier_key(subscription_id: Uuid) -> String {
+    format!("{}{}", TIER_POLICY_KEY_PREFIX, subscription_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raffle_platform_shared::{PricingCurveBasis, PricingCurveDefinition, PricingTierDefinition};
+
+    fn request(total_boxes: i32, box_price: Decimal, total_winners: i32, rows: i32, cols: i32) -> CreateRaffleRequest {
+        serde_json::from_value(serde_json::json!({
+            "item_id": Uuid::new_v4(),
+            "total_boxes": total_boxes,
+            "box_price": box_price,
+            "total_winners": total_winners,
+            "grid_rows": rows,
+            "grid_cols": cols,
+        }))
+        .unwrap()
+    }
+
+    fn codes(errors: &[FieldError]) -> Vec<(&str, &str)> {
+        errors.iter().map(|e| (e.field.as_str(), e.code.as_str())).collect()
+    }
+
+    #[test]
+    fn test_compliant_request_passes() {
+        let policy = RafflePolicy::default();
+        assert!(policy.check(&request(100, Decimal::new(500, 2), 5, 10, 10)).is_empty());
+    }
+
+    #[test]
+    fn test_reports_every_violation() {
+        let policy = RafflePolicy::default();
+        let errors = policy.check(&request(100_000_000, Decimal::new(1, 4), 60_000_000, 10_000, 10_000));
+
+        assert_eq!(
+            codes(&errors),
+            vec![
+                ("total_boxes", "above_maximum"),
+                ("box_price", "below_minimum"),
+                ("total_winners", "above_maximum"),
+                ("grid_rows", "above_maximum"),
+                ("grid_cols", "above_maximum"),
+            ]
+        );
+        assert_eq!(errors[0].limit, Some(serde_json::json!(10000)));
+    }
+
+    #[test]
+    fn test_pricing_curve_prices_are_bounded() {
+        let policy = RafflePolicy::default();
+        let mut req = request(100, Decimal::new(500, 2), 5, 10, 10);
+        req.pricing_curve = Some(PricingCurveDefinition {
+            basis: PricingCurveBasis::FillPercentage,
+            tiers: vec![
+                PricingTierDefinition { from: 50, box_price: Decimal::new(800, 2) },
+                PricingTierDefinition { from: 90, box_price: Decimal::new(20000, 0) },
+            ],
+        });
+
+        let errors = policy.check(&req);
+        assert_eq!(codes(&errors), vec![("pricing_curve.tiers[1].box_price", "above_maximum")]);
+    }
+
+    #[test]
+    fn test_max_winners_follows_ratio() {
+        let policy = RafflePolicy::default();
+        assert_eq!(policy.max_winners(100), 50);
+        assert_eq!(policy.max_winners(3), 1);
+        assert_eq!(policy.max_winners(1), 1);
+    }
+
+    #[test]
+    fn test_tier_overrides_replace_only_given_fields() {
+        let platform = RafflePolicy::default();
+        let overrides = RafflePolicyOverrides {
+            max_boxes: Some(50000),
+            max_grid_rows: Some(250),
+            ..Default::default()
+        };
+
+        let effective = platform.with_overrides(&overrides);
+        assert_eq!(effective.max_boxes, 50000);
+        assert_eq!(effective.max_grid_rows, 250);
+        assert_eq!(effective.min_box_price, platform.min_box_price);
+        assert_eq!(effective.max_grid_cols, platform.max_grid_cols);
+    }
+
+    #[test]
+    fn test_validate_rejects_impossible_policy() {
+        let policy = RafflePolicy {
+            min_boxes: 100,
+            max_boxes: 10,
+            max_winners_ratio: Decimal::new(2, 0),
+            ..Default::default()
+        };
+
+        match policy.validate() {
+            Err(AppError::InvalidFields(errors)) => {
+                assert_eq!(codes(&errors), vec![("max_boxes", "below_minimum"), ("max_winners_ratio", "out_of_range")]);
+            }
+            other => panic!("expected field errors, got {:?}", other.map(|_| ())),
+        }
+        assert!(RafflePolicy::default().validate().is_ok());
+    }
+}
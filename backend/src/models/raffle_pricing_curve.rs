@@ -0,0 +1,302 @@
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::{PricingCurveBasis, PricingCurveDefinition, PricingTierDefinition};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+use crate::error::AppError;
+
+const MAX_PRICING_TIERS: usize = 10;
+
+/// Stored pricing curve of a raffle; flat-priced raffles have no row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RafflePricingCurve {
+    pub raffle_id: Uuid,
+    pub basis: PricingCurveBasis,
+    pub tiers: Vec<PricingTierDefinition>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl RafflePricingCurve {
+    /// Store the pricing curve for a raffle
+    pub async fn create(
+        pool: &PgPool,
+        raffle_id: Uuid,
+        definition: &PricingCurveDefinition,
+    ) -> Result<Self, AppError> {
+        let tiers = serde_json::to_value(&definition.tiers)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize pricing curve: {}", e)))?;
+
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO raffle_pricing_curves (raffle_id, basis, tiers)
+            VALUES ($1, $2, $3)
+            RETURNING raffle_id, basis, tiers, created_at
+            "#,
+            raffle_id,
+            basis_str(definition.basis),
+            tiers
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(Self {
+            raffle_id: row.raffle_id,
+            basis: parse_basis(&row.basis)?,
+            tiers: parse_tiers(row.tiers)?,
+            created_at: row.created_at,
+        })
+    }
+
+    /// Find the pricing curve of a raffle
+    pub async fn find_by_raffle(pool: &PgPool, raffle_id: Uuid) -> Result<Option<Self>, AppError> {
+        let row = sqlx::query!(
+            "SELECT raffle_id, basis, tiers, created_at FROM raffle_pricing_curves WHERE raffle_id = $1",
+            raffle_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(|row| {
+            Ok(Self {
+                raffle_id: row.raffle_id,
+                basis: parse_basis(&row.basis)?,
+                tiers: parse_tiers(row.tiers)?,
+                created_at: row.created_at,
+            })
+        })
+        .transpose()
+    }
+}
+
+fn basis_str(basis: PricingCurveBasis) -> &'static str {
+    match basis {
+        PricingCurveBasis::FillPercentage => "fill_percentage",
+        PricingCurveBasis::Time => "time",
+    }
+}
+
+fn parse_basis(value: &str) -> Result<PricingCurveBasis, AppError> {
+    match value {
+        "fill_percentage" => Ok(PricingCurveBasis::FillPercentage),
+        "time" => Ok(PricingCurveBasis::Time),
+        other => Err(AppError::Internal(format!("Invalid stored pricing curve basis: {}", other))),
+    }
+}
+
+fn parse_tiers(value: serde_json::Value) -> Result<Vec<PricingTierDefinition>, AppError> {
+    serde_json::from_value(value)
+        .map_err(|e| AppError::Internal(format!("Invalid stored pricing curve: {}", e)))
+}
+
+/// The next price change on a curve
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceStep {
+    pub box_price: Decimal,
+    /// Boxes sold when a fill-percentage tier starts
+    pub at_boxes_sold: Option<i32>,
+    /// Minutes after opening when a time tier starts
+    pub after_minutes: Option<i64>,
+}
+
+/// Resolves box prices from a raffle's base price and optional curve
+///
+/// Fill tiers price each box by the share of boxes sold before it, so a purchase that
+/// crosses a threshold pays the old price for the boxes before it and the new one after.
+#[derive(Debug, Clone)]
+pub struct PricingCurve {
+    base_price: Decimal,
+    basis: PricingCurveBasis,
+    tiers: Vec<PricingTierDefinition>,
+}
+
+impl PricingCurve {
+    /// Build and validate a curve; thresholds must be positive and strictly ascending
+    pub fn new(base_price: Decimal, definition: Option<&PricingCurveDefinition>) -> Result<Self, AppError> {
+        let Some(definition) = definition else {
+            return Ok(Self::flat(base_price));
+        };
+
+        if definition.tiers.is_empty() {
+            return Err(AppError::Validation("Pricing curve needs at least one tier".to_string()));
+        }
+        if definition.tiers.len() > MAX_PRICING_TIERS {
+            return Err(AppError::Validation(format!(
+                "Pricing curve cannot have more than {} tiers",
+                MAX_PRICING_TIERS
+            )));
+        }
+
+        let mut previous = 0;
+        for tier in &definition.tiers {
+            if tier.from <= previous {
+                return Err(AppError::Validation(
+                    "Pricing tier thresholds must be positive and ascending".to_string(),
+                ));
+            }
+            if definition.basis == PricingCurveBasis::FillPercentage && tier.from >= 100 {
+                return Err(AppError::Validation(format!(
+                    "Fill percentage tier at {}% would never apply",
+                    tier.from
+                )));
+            }
+            if tier.box_price <= Decimal::ZERO {
+                return Err(AppError::Validation("Pricing tier box price must be positive".to_string()));
+            }
+            previous = tier.from;
+        }
+
+        Ok(Self {
+            base_price,
+            basis: definition.basis,
+            tiers: definition.tiers.clone(),
+        })
+    }
+
+    /// Curve of a stored raffle
+    pub fn for_raffle(base_price: Decimal, curve: Option<&RafflePricingCurve>) -> Result<Self, AppError> {
+        let definition = curve.map(|c| PricingCurveDefinition {
+            basis: c.basis,
+            tiers: c.tiers.clone(),
+        });
+        Self::new(base_price, definition.as_ref())
+    }
+
+    fn flat(base_price: Decimal) -> Self {
+        Self {
+            base_price,
+            basis: PricingCurveBasis::FillPercentage,
+            tiers: Vec::new(),
+        }
+    }
+
+    /// Whether every box sells at the base price
+    pub fn is_flat(&self) -> bool {
+        self.tiers.is_empty()
+    }
+
+    /// Price of the next box sold
+    pub fn price_at(&self, boxes_sold: i32, total_boxes: i32, elapsed_minutes: i64) -> Decimal {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| self.reached(tier, boxes_sold, total_boxes, elapsed_minutes))
+            .map(|tier| tier.box_price)
+            .unwrap_or(self.base_price)
+    }
+
+    /// Prices of the next `count` boxes, in sale order
+    pub fn quote(&self, boxes_sold: i32, total_boxes: i32, count: usize, elapsed_minutes: i64) -> Vec<Decimal> {
+        (0..count as i32)
+            .map(|i| self.price_at(boxes_sold + i, total_boxes, elapsed_minutes))
+            .collect()
+    }
+
+    /// The first tier that hasn't started yet
+    pub fn next_step(&self, boxes_sold: i32, total_boxes: i32, elapsed_minutes: i64) -> Option<PriceStep> {
+        let tier = self
+            .tiers
+            .iter()
+            .find(|tier| !self.reached(tier, boxes_sold, total_boxes, elapsed_minutes))?;
+
+        Some(match self.basis {
+            PricingCurveBasis::FillPercentage => PriceStep {
+                box_price: tier.box_price,
+                at_boxes_sold: Some(fill_threshold(tier.from, total_boxes)),
+                after_minutes: None,
+            },
+            PricingCurveBasis::Time => PriceStep {
+                box_price: tier.box_price,
+                at_boxes_sold: None,
+                after_minutes: Some(tier.from as i64),
+            },
+        })
+    }
+
+    fn reached(&self, tier: &PricingTierDefinition, boxes_sold: i32, total_boxes: i32, elapsed_minutes: i64) -> bool {
+        match self.basis {
+            PricingCurveBasis::FillPercentage => boxes_sold >= fill_threshold(tier.from, total_boxes),
+            PricingCurveBasis::Time => elapsed_minutes >= tier.from as i64,
+        }
+    }
+}
+
+/// Boxes that must be sold before a fill percentage tier applies, rounded up
+fn fill_threshold(percentage: i32, total_boxes: i32) -> i32 {
+    let scaled = percentage as i64 * total_boxes as i64;
+    ((scaled + 99) / 100) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn early_bird() -> PricingCurveDefinition {
+        // 4.00 for the first 20%, 5.00 until 90%, then 6.00
+        PricingCurveDefinition {
+            basis: PricingCurveBasis::FillPercentage,
+            tiers: vec![
+                PricingTierDefinition { from: 20, box_price: Decimal::new(500, 2) },
+                PricingTierDefinition { from: 90, box_price: Decimal::new(600, 2) },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_fill_percentage_tiers() {
+        let curve = PricingCurve::new(Decimal::new(400, 2), Some(&early_bird())).unwrap();
+
+        assert_eq!(curve.price_at(0, 100, 0), Decimal::new(400, 2));
+        assert_eq!(curve.price_at(19, 100, 0), Decimal::new(400, 2));
+        assert_eq!(curve.price_at(20, 100, 0), Decimal::new(500, 2));
+        assert_eq!(curve.price_at(95, 100, 0), Decimal::new(600, 2));
+        assert_eq!(
+            curve.next_step(10, 100, 0),
+            Some(PriceStep { box_price: Decimal::new(500, 2), at_boxes_sold: Some(20), after_minutes: None })
+        );
+        assert_eq!(curve.next_step(90, 100, 0), None);
+    }
+
+    #[test]
+    fn test_quote_crosses_thresholds() {
+        let curve = PricingCurve::new(Decimal::new(400, 2), Some(&early_bird())).unwrap();
+
+        assert_eq!(
+            curve.quote(18, 100, 3, 0),
+            vec![Decimal::new(400, 2), Decimal::new(400, 2), Decimal::new(500, 2)]
+        );
+    }
+
+    #[test]
+    fn test_time_tiers() {
+        let definition = PricingCurveDefinition {
+            basis: PricingCurveBasis::Time,
+            tiers: vec![PricingTierDefinition { from: 60, box_price: Decimal::new(300, 2) }],
+        };
+        let curve = PricingCurve::new(Decimal::new(200, 2), Some(&definition)).unwrap();
+
+        assert_eq!(curve.price_at(50, 100, 59), Decimal::new(200, 2));
+        assert_eq!(curve.price_at(0, 100, 60), Decimal::new(300, 2));
+        assert_eq!(curve.next_step(0, 100, 10).and_then(|s| s.after_minutes), Some(60));
+    }
+
+    #[test]
+    fn test_invalid_curves_are_rejected() {
+        let base = Decimal::new(100, 2);
+        let tier = |from, cents| PricingTierDefinition { from, box_price: Decimal::new(cents, 2) };
+
+        let descending = PricingCurveDefinition {
+            basis: PricingCurveBasis::FillPercentage,
+            tiers: vec![tier(50, 200), tier(20, 300)],
+        };
+        assert!(PricingCurve::new(base, Some(&descending)).is_err());
+
+        let full = PricingCurveDefinition { basis: PricingCurveBasis::FillPercentage, tiers: vec![tier(100, 200)] };
+        assert!(PricingCurve::new(base, Some(&full)).is_err());
+
+        let free = PricingCurveDefinition { basis: PricingCurveBasis::Time, tiers: vec![tier(10, 0)] };
+        assert!(PricingCurve::new(base, Some(&free)).is_err());
+
+        assert!(PricingCurve::new(base, None).unwrap().is_flat());
+    }
+}
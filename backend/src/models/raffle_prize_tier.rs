@@ -0,0 +1,326 @@
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::{PrizeTierDefinition, PrizeType};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+
+/// How the winner of an item prize with a credit alternative wants it paid
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PrizeChoice {
+    /// Ship the item
+    Item,
+    /// Take the credit alternative; the seller keeps the item
+    Credits,
+}
+
+/// Prize for one winning place of a raffle
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RafflePrizeTier {
+    pub id: Uuid,
+    pub raffle_id: Uuid,
+    pub place: i32,
+    pub prize_type: PrizeType,
+    pub item_id: Option<Uuid>,
+    pub credit_amount: Option<Decimal>,
+    pub credit_alternative: Option<Decimal>,
+    pub winner_user_id: Option<Uuid>,
+    pub winner_choice: Option<PrizeChoice>,
+    pub choice_made_at: Option<DateTime<Utc>>,
+    pub awarded_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl RafflePrizeTier {
+    /// Store the tiers of a new raffle
+    pub async fn create_all(
+        tx: &mut Transaction<'_, Postgres>,
+        raffle_id: Uuid,
+        tiers: &[PrizeTierDefinition],
+    ) -> Result<Vec<Self>, AppError> {
+        let mut created = Vec::with_capacity(tiers.len());
+
+        for tier in tiers {
+            let row = sqlx::query_as!(
+                RafflePrizeTier,
+                r#"
+                INSERT INTO raffle_prize_tiers (raffle_id, place, prize_type, item_id, credit_amount, credit_alternative)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                RETURNING id, raffle_id, place, prize_type as "prize_type: PrizeType", item_id,
+                          credit_amount, credit_alternative, winner_user_id,
+                          winner_choice as "winner_choice: PrizeChoice", choice_made_at, awarded_at, created_at
+                "#,
+                raffle_id,
+                tier.place,
+                tier.prize_type as PrizeType,
+                tier.item_id,
+                tier.credit_amount,
+                tier.credit_alternative
+            )
+            .fetch_one(&mut **tx)
+            .await?;
+
+            created.push(row);
+        }
+
+        Ok(created)
+    }
+
+    /// Tiers of a raffle, first place first; empty for raffles created before tiers existed
+    pub async fn find_by_raffle(executor: impl PgExecutor<'_>, raffle_id: Uuid) -> Result<Vec<Self>, AppError> {
+        let tiers = sqlx::query_as!(
+            RafflePrizeTier,
+            r#"
+            SELECT id, raffle_id, place, prize_type as "prize_type: PrizeType", item_id,
+                   credit_amount, credit_alternative, winner_user_id,
+                   winner_choice as "winner_choice: PrizeChoice", choice_made_at, awarded_at, created_at
+            FROM raffle_prize_tiers
+            WHERE raffle_id = $1
+            ORDER BY place
+            "#,
+            raffle_id
+        )
+        .fetch_all(executor)
+        .await?;
+
+        Ok(tiers)
+    }
+
+    /// Places a user won in a raffle, first place first
+    pub async fn find_for_winner(pool: &PgPool, raffle_id: Uuid, user_id: Uuid) -> Result<Vec<Self>, AppError> {
+        let tiers = sqlx::query_as!(
+            RafflePrizeTier,
+            r#"
+            SELECT id, raffle_id, place, prize_type as "prize_type: PrizeType", item_id,
+                   credit_amount, credit_alternative, winner_user_id,
+                   winner_choice as "winner_choice: PrizeChoice", choice_made_at, awarded_at, created_at
+            FROM raffle_prize_tiers
+            WHERE raffle_id = $1 AND winner_user_id = $2
+            ORDER BY place
+            "#,
+            raffle_id,
+            user_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(tiers)
+    }
+
+    /// Record the winner's choice; None if a choice was already made
+    pub async fn record_choice(pool: &PgPool, tier_id: Uuid, choice: PrizeChoice) -> Result<Option<Self>, AppError> {
+        let tier = sqlx::query_as!(
+            RafflePrizeTier,
+            r#"
+            UPDATE raffle_prize_tiers
+            SET winner_choice = $2, choice_made_at = NOW()
+            WHERE id = $1 AND winner_choice IS NULL
+            RETURNING id, raffle_id, place, prize_type as "prize_type: PrizeType", item_id,
+                      credit_amount, credit_alternative, winner_user_id,
+                      winner_choice as "winner_choice: PrizeChoice", choice_made_at, awarded_at, created_at
+            "#,
+            tier_id,
+            choice as PrizeChoice
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(tier)
+    }
+
+    /// Undo a choice whose payout could not be made, so the winner can choose again
+    pub async fn clear_choice(pool: &PgPool, tier_id: Uuid) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE raffle_prize_tiers SET winner_choice = NULL, choice_made_at = NULL WHERE id = $1",
+            tier_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The winner still has to pick between the item and its credit value
+    pub fn awaiting_choice(&self) -> bool {
+        self.winner_user_id.is_some() && self.credit_alternative.is_some() && self.winner_choice.is_none()
+    }
+
+    /// The prize is an item the winner is taking, or may still take
+    pub fn ships_item(&self) -> bool {
+        self.prize_type == PrizeType::Item && self.winner_choice != Some(PrizeChoice::Credits)
+    }
+
+    /// Give place n to the nth winner drawn
+    pub async fn assign_winners(
+        tx: &mut Transaction<'_, Postgres>,
+        raffle_id: Uuid,
+        winner_user_ids: &[Uuid],
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            UPDATE raffle_prize_tiers t
+            SET winner_user_id = w.user_id, awarded_at = NOW()
+            FROM unnest($2::uuid[]) WITH ORDINALITY AS w(user_id, place)
+            WHERE t.raffle_id = $1 AND t.place = w.place
+            "#,
+            raffle_id,
+            winner_user_ids
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Tiers used when a raffle is created without any: every winner gets the raffle item,
+/// or its credit alternative if the seller offered one
+pub fn default_prize_tiers(item_id: Uuid, total_winners: i32, credit_alternative: Option<Decimal>) -> Vec<PrizeTierDefinition> {
+    (1..=total_winners)
+        .map(|place| PrizeTierDefinition {
+            place,
+            prize_type: PrizeType::Item,
+            item_id: Some(item_id),
+            credit_amount: None,
+            credit_alternative,
+        })
+        .collect()
+}
+
+/// Tiers must cover places 1..=total_winners exactly once, each with a single prize
+pub fn validate_prize_tiers(tiers: &[PrizeTierDefinition], total_winners: i32) -> Result<(), AppError> {
+    if tiers.len() as i32 != total_winners {
+        return Err(AppError::Validation(format!(
+            "Expected {} prize tiers, one per winner, but got {}",
+            total_winners,
+            tiers.len()
+        )));
+    }
+
+    let mut places: Vec<i32> = tiers.iter().map(|t| t.place).collect();
+    places.sort_unstable();
+    if places.iter().enumerate().any(|(i, &place)| place != i as i32 + 1) {
+        return Err(AppError::Validation(format!(
+            "Prize tier places must be 1 to {} with no gaps or repeats",
+            total_winners
+        )));
+    }
+
+    for tier in tiers {
+        match tier.prize_type {
+            PrizeType::Item => {
+                if tier.item_id.is_none() || tier.credit_amount.is_some() {
+                    return Err(AppError::Validation(format!(
+                        "Item prize for place {} needs an item and no credit amount",
+                        tier.place
+                    )));
+                }
+            }
+            PrizeType::Credits => {
+                if tier.item_id.is_some() || tier.credit_amount.map_or(true, |a| a <= Decimal::ZERO) {
+                    return Err(AppError::Validation(format!(
+                        "Credit prize for place {} needs a positive credit amount and no item",
+                        tier.place
+                    )));
+                }
+                if tier.credit_alternative.is_some() {
+                    return Err(AppError::Validation(format!(
+                        "Credit prize for place {} cannot have a credit alternative",
+                        tier.place
+                    )));
+                }
+            }
+        }
+
+        if tier.credit_alternative.is_some_and(|a| a <= Decimal::ZERO) {
+            return Err(AppError::Validation(format!(
+                "Credit alternative for place {} must be positive",
+                tier.place
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credits(place: i32, amount: i64) -> PrizeTierDefinition {
+        PrizeTierDefinition {
+            place,
+            prize_type: PrizeType::Credits,
+            item_id: None,
+            credit_amount: Some(Decimal::from(amount)),
+            credit_alternative: None,
+        }
+    }
+
+    #[test]
+    fn test_valid_tiers_are_accepted() {
+        let item_id = Uuid::new_v4();
+        let mut tiers = default_prize_tiers(item_id, 1, None);
+        tiers.push(credits(3, 10));
+        tiers.push(credits(2, 50));
+
+        assert!(validate_prize_tiers(&tiers, 3).is_ok());
+        assert!(validate_prize_tiers(&default_prize_tiers(item_id, 5, Some(Decimal::from(40))), 5).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_tiers_are_rejected() {
+        // Count does not match the winners
+        assert!(validate_prize_tiers(&[credits(1, 10)], 2).is_err());
+        // Gap and repeat in places
+        assert!(validate_prize_tiers(&[credits(1, 10), credits(3, 10)], 2).is_err());
+        assert!(validate_prize_tiers(&[credits(1, 10), credits(1, 10)], 2).is_err());
+        // Prize fields that don't match the type
+        assert!(validate_prize_tiers(&[credits(1, 0)], 1).is_err());
+        let item_without_id = PrizeTierDefinition {
+            place: 1,
+            prize_type: PrizeType::Item,
+            item_id: None,
+            credit_amount: None,
+            credit_alternative: None,
+        };
+        assert!(validate_prize_tiers(&[item_without_id], 1).is_err());
+        // Credit alternatives are only for item prizes, and must be positive
+        let mut credits_with_alternative = credits(1, 10);
+        credits_with_alternative.credit_alternative = Some(Decimal::from(5));
+        assert!(validate_prize_tiers(&[credits_with_alternative], 1).is_err());
+        assert!(validate_prize_tiers(&default_prize_tiers(Uuid::new_v4(), 1, Some(Decimal::ZERO)), 1).is_err());
+    }
+
+    #[test]
+    fn test_prize_choice_state() {
+        let mut tier = RafflePrizeTier {
+            id: Uuid::new_v4(),
+            raffle_id: Uuid::new_v4(),
+            place: 1,
+            prize_type: PrizeType::Item,
+            item_id: Some(Uuid::new_v4()),
+            credit_amount: None,
+            credit_alternative: Some(Decimal::from(40)),
+            winner_user_id: None,
+            winner_choice: None,
+            choice_made_at: None,
+            awarded_at: None,
+            created_at: None,
+        };
+        // Nothing to choose before the place is won
+        assert!(!tier.awaiting_choice());
+
+        tier.winner_user_id = Some(Uuid::new_v4());
+        assert!(tier.awaiting_choice());
+        assert!(tier.ships_item());
+
+        tier.winner_choice = Some(PrizeChoice::Credits);
+        assert!(!tier.awaiting_choice());
+        assert!(!tier.ships_item());
+    }
+}
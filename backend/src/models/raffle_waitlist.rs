@@ -0,0 +1,173 @@
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::RaffleStatus;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+
+const COLUMNS: &str = "id, raffle_id, item_id, user_id, reason, status, notified_raffle_id, notified_at, created_at";
+
+/// Why the user couldn't buy when they joined
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WaitlistReason {
+    /// Every box was sold, or the raffle closed
+    SoldOut,
+    /// The seller has none of the item left
+    OutOfStock,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WaitlistStatus {
+    Waiting,
+    Notified,
+}
+
+/// What a notified user can now buy
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WaitlistOpening {
+    /// Boxes in the same raffle came free
+    BoxesFreed,
+    /// The item is in a new raffle
+    Relisted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct WaitlistEntry {
+    pub id: Uuid,
+    pub raffle_id: Uuid,
+    pub item_id: Uuid,
+    pub user_id: Uuid,
+    pub reason: WaitlistReason,
+    pub status: WaitlistStatus,
+    pub notified_raffle_id: Option<Uuid>,
+    pub notified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WaitlistEntry {
+    /// Join the line, or keep the place the user already has
+    pub async fn join(
+        pool: &PgPool,
+        raffle_id: Uuid,
+        item_id: Uuid,
+        user_id: Uuid,
+        reason: WaitlistReason,
+    ) -> Result<Self, AppError> {
+        sqlx::query(
+            "INSERT INTO raffle_waitlist_entries (raffle_id, item_id, user_id, reason) VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (raffle_id, user_id) WHERE status = 'waiting' DO NOTHING",
+        )
+        .bind(raffle_id)
+        .bind(item_id)
+        .bind(user_id)
+        .bind(reason)
+        .execute(pool)
+        .await?;
+
+        Self::latest(pool, raffle_id, user_id)
+            .await?
+            .ok_or_else(|| AppError::Internal("Waitlist entry vanished after joining".to_string()))
+    }
+
+    /// The user's most recent entry for the raffle, waiting or notified
+    pub async fn latest(pool: &PgPool, raffle_id: Uuid, user_id: Uuid) -> Result<Option<Self>, AppError> {
+        let entry = sqlx::query_as::<_, WaitlistEntry>(&format!(
+            "SELECT {} FROM raffle_waitlist_entries WHERE raffle_id = $1 AND user_id = $2 \
+             ORDER BY created_at DESC LIMIT 1",
+            COLUMNS
+        ))
+        .bind(raffle_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(entry)
+    }
+
+    /// Give up a place in line; returns whether the user was waiting
+    pub async fn leave(pool: &PgPool, raffle_id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+        let result = sqlx::query(
+            "DELETE FROM raffle_waitlist_entries WHERE raffle_id = $1 AND user_id = $2 AND status = 'waiting'",
+        )
+        .bind(raffle_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Place in line, counting from 1, and how many are waiting in all
+    pub async fn position(&self, pool: &PgPool) -> Result<(Option<i64>, i64), AppError> {
+        let (ahead_or_at, waiting) = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT COUNT(*) FILTER (WHERE (created_at, id) <= ($2, $3)), COUNT(*) \
+             FROM raffle_waitlist_entries WHERE raffle_id = $1 AND status = 'waiting'",
+        )
+        .bind(self.raffle_id)
+        .bind(self.created_at)
+        .bind(self.id)
+        .fetch_one(pool)
+        .await?;
+
+        let position = (self.status == WaitlistStatus::Waiting).then_some(ahead_or_at);
+        Ok((position, waiting))
+    }
+}
+
+/// Why a user may join a raffle's waitlist, or why they should buy instead
+pub fn waitlist_reason(
+    status: RaffleStatus,
+    boxes_sold: i32,
+    total_boxes: i32,
+    stock_quantity: i32,
+) -> Result<WaitlistReason, AppError> {
+    if stock_quantity <= 0 {
+        return Ok(WaitlistReason::OutOfStock);
+    }
+
+    match status {
+        RaffleStatus::Scheduled => Err(AppError::Validation("This raffle hasn't opened yet".to_string())),
+        RaffleStatus::Open if boxes_sold < total_boxes => {
+            Err(AppError::Validation("Boxes are still available in this raffle".to_string()))
+        }
+        _ => Ok(WaitlistReason::SoldOut),
+    }
+}
+
+/// Users to tell about freed boxes: one per box, less those told recently who may still buy
+pub fn users_to_notify(free_boxes: i64, recently_notified: i64) -> i64 {
+    (free_boxes - recently_notified).max(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_waitlist_reason() {
+        assert_eq!(waitlist_reason(RaffleStatus::Full, 10, 10, 1).unwrap(), WaitlistReason::SoldOut);
+        assert_eq!(waitlist_reason(RaffleStatus::Completed, 10, 10, 1).unwrap(), WaitlistReason::SoldOut);
+        assert_eq!(waitlist_reason(RaffleStatus::Cancelled, 3, 10, 1).unwrap(), WaitlistReason::SoldOut);
+        // Open but filled by a purchase the status hasn't caught up with
+        assert_eq!(waitlist_reason(RaffleStatus::Open, 10, 10, 1).unwrap(), WaitlistReason::SoldOut);
+        assert_eq!(waitlist_reason(RaffleStatus::Open, 3, 10, 0).unwrap(), WaitlistReason::OutOfStock);
+
+        assert!(waitlist_reason(RaffleStatus::Open, 3, 10, 1).is_err());
+        assert!(waitlist_reason(RaffleStatus::Scheduled, 0, 10, 1).is_err());
+    }
+
+    #[test]
+    fn test_users_to_notify() {
+        assert_eq!(users_to_notify(3, 0), 3);
+        assert_eq!(users_to_notify(3, 2), 1);
+        // Users told about boxes that have since sold still hold their turn until the window ends
+        assert_eq!(users_to_notify(1, 4), 0);
+        assert_eq!(users_to_notify(0, 0), 0);
+    }
+}
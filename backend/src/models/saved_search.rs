@@ -0,0 +1,248 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+use crate::error::AppError;
+
+const MAX_SAVED_SEARCHES_PER_USER: i64 = 20;
+const MAX_QUERY_LENGTH: usize = 200;
+const MAX_FILTER_TAGS: usize = 10;
+
+const COLUMNS: &str = "id, user_id, name, filter, alerts_enabled, last_checked_at, created_at, updated_at";
+
+/// What a saved search matches; every criterion given must hold
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct SavedSearchFilter {
+    /// Words in the item's name or description, each matched as a prefix
+    pub query: Option<String>,
+    pub min_price: Option<Decimal>,
+    pub max_price: Option<Decimal>,
+    /// Canonical tag slugs
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Require every tag rather than any of them
+    #[serde(default)]
+    pub match_all_tags: bool,
+}
+
+impl SavedSearchFilter {
+    /// A filter that narrows the listings at all, with sensible bounds
+    pub fn check(&self) -> Result<(), AppError> {
+        let query = self.query.as_deref().map(str::trim).filter(|q| !q.is_empty());
+        if query.is_none() && self.min_price.is_none() && self.max_price.is_none() && self.tags.is_empty() {
+            return Err(AppError::Validation(
+                "A saved search needs a query, a price range or tags".to_string(),
+            ));
+        }
+
+        if let Some(query) = query {
+            if query.len() > MAX_QUERY_LENGTH {
+                return Err(AppError::Validation(format!(
+                    "Search query can be at most {} characters",
+                    MAX_QUERY_LENGTH
+                )));
+            }
+            if !query.chars().any(char::is_alphanumeric) {
+                return Err(AppError::Validation("Search query has no words to match".to_string()));
+            }
+        }
+
+        if self.min_price.map_or(false, |p| p < Decimal::ZERO) || self.max_price.map_or(false, |p| p < Decimal::ZERO) {
+            return Err(AppError::Validation("Prices can't be negative".to_string()));
+        }
+        if let (Some(min), Some(max)) = (self.min_price, self.max_price) {
+            if min > max {
+                return Err(AppError::Validation("Minimum price is above the maximum".to_string()));
+            }
+        }
+
+        if self.tags.len() > MAX_FILTER_TAGS {
+            return Err(AppError::Validation(format!("Filter on at most {} tags", MAX_FILTER_TAGS)));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SavedSearch {
+    pub id: Uuid,
+    #[serde(skip)]
+    pub user_id: Uuid,
+    pub name: String,
+    pub filter: SavedSearchFilter,
+    /// Whether new raffles matching the search are announced
+    pub alerts_enabled: bool,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate, ToSchema)]
+pub struct SavedSearchRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub filter: SavedSearchFilter,
+    #[serde(default = "default_alerts_enabled")]
+    pub alerts_enabled: bool,
+}
+
+fn default_alerts_enabled() -> bool {
+    true
+}
+
+#[derive(FromRow)]
+struct SavedSearchRow {
+    id: Uuid,
+    user_id: Uuid,
+    name: String,
+    filter: serde_json::Value,
+    alerts_enabled: bool,
+    last_checked_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<SavedSearchRow> for SavedSearch {
+    type Error = AppError;
+
+    fn try_from(row: SavedSearchRow) -> Result<Self, AppError> {
+        let filter = serde_json::from_value(row.filter)
+            .map_err(|e| AppError::Internal(format!("Saved search {} has a malformed filter: {}", row.id, e)))?;
+
+        Ok(SavedSearch {
+            id: row.id,
+            user_id: row.user_id,
+            name: row.name,
+            filter,
+            alerts_enabled: row.alerts_enabled,
+            last_checked_at: row.last_checked_at,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+impl SavedSearch {
+    /// The user's saved searches, oldest first
+    pub async fn list_for_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<Self>, AppError> {
+        let rows = sqlx::query_as::<_, SavedSearchRow>(&format!(
+            "SELECT {} FROM saved_searches WHERE user_id = $1 ORDER BY created_at",
+            COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(SavedSearch::try_from).collect()
+    }
+
+    /// Save a search; the filter's tags must already be canonical
+    pub async fn create(pool: &PgPool, user_id: Uuid, request: &SavedSearchRequest) -> Result<Self, AppError> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM saved_searches WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?;
+        if count >= MAX_SAVED_SEARCHES_PER_USER {
+            return Err(AppError::Validation(format!(
+                "You can save at most {} searches",
+                MAX_SAVED_SEARCHES_PER_USER
+            )));
+        }
+
+        sqlx::query_as::<_, SavedSearchRow>(&format!(
+            "INSERT INTO saved_searches (user_id, name, filter, alerts_enabled) VALUES ($1, $2, $3, $4) \
+             RETURNING {}",
+            COLUMNS
+        ))
+        .bind(user_id)
+        .bind(request.name.trim())
+        .bind(filter_value(&request.filter)?)
+        .bind(request.alerts_enabled)
+        .fetch_one(pool)
+        .await?
+        .try_into()
+    }
+
+    /// Replace a saved search. Raffles it already alerted on are not announced again.
+    pub async fn update(
+        pool: &PgPool,
+        user_id: Uuid,
+        search_id: Uuid,
+        request: &SavedSearchRequest,
+    ) -> Result<Self, AppError> {
+        sqlx::query_as::<_, SavedSearchRow>(&format!(
+            "UPDATE saved_searches SET name = $3, filter = $4, alerts_enabled = $5, updated_at = NOW() \
+             WHERE id = $1 AND user_id = $2 \
+             RETURNING {}",
+            COLUMNS
+        ))
+        .bind(search_id)
+        .bind(user_id)
+        .bind(request.name.trim())
+        .bind(filter_value(&request.filter)?)
+        .bind(request.alerts_enabled)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Saved search not found".to_string()))?
+        .try_into()
+    }
+
+    pub async fn delete(pool: &PgPool, user_id: Uuid, search_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM saved_searches WHERE id = $1 AND user_id = $2")
+            .bind(search_id)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Saved search not found".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Searches with alerts on, least recently checked first
+    pub async fn due_for_alerts(pool: &PgPool, limit: i64) -> Result<Vec<Self>, AppError> {
+        let rows = sqlx::query_as::<_, SavedSearchRow>(&format!(
+            "SELECT {} FROM saved_searches WHERE alerts_enabled \
+             ORDER BY last_checked_at NULLS FIRST LIMIT $1",
+            COLUMNS
+        ))
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(SavedSearch::try_from).collect()
+    }
+}
+
+fn filter_value(filter: &SavedSearchFilter) -> Result<serde_json::Value, AppError> {
+    serde_json::to_value(filter).map_err(|e| AppError::Internal(format!("Failed to serialize search filter: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_needs_a_criterion() {
+        assert!(SavedSearchFilter::default().check().is_err());
+        assert!(SavedSearchFilter { query: Some("   ".to_string()), ..Default::default() }.check().is_err());
+        assert!(SavedSearchFilter { query: Some("?!".to_string()), ..Default::default() }.check().is_err());
+
+        assert!(SavedSearchFilter { query: Some("air jordan".to_string()), ..Default::default() }.check().is_ok());
+        assert!(SavedSearchFilter { tags: vec!["sneakers".to_string()], ..Default::default() }.check().is_ok());
+        assert!(SavedSearchFilter { max_price: Some(Decimal::from(5)), ..Default::default() }.check().is_ok());
+    }
+
+    #[test]
+    fn test_filter_price_range() {
+        let range = |min, max| SavedSearchFilter { min_price: min, max_price: max, ..Default::default() };
+
+        assert!(range(Some(Decimal::from(1)), Some(Decimal::from(5))).check().is_ok());
+        assert!(range(Some(Decimal::from(5)), Some(Decimal::from(1))).check().is_err());
+        assert!(range(Some(Decimal::from(-1)), None).check().is_err());
+    }
+}
@@ -0,0 +1,301 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+
+/// A step a new seller completes before being able to sell, in the order they are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    ProfileComplete,
+    KycPassed,
+    PayoutConnected,
+    FirstItemCreated,
+    SubscriptionSelected,
+}
+
+impl OnboardingStep {
+    pub const ALL: [OnboardingStep; 5] = [
+        OnboardingStep::ProfileComplete,
+        OnboardingStep::KycPassed,
+        OnboardingStep::PayoutConnected,
+        OnboardingStep::FirstItemCreated,
+        OnboardingStep::SubscriptionSelected,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OnboardingStep::ProfileComplete => "profile_complete",
+            OnboardingStep::KycPassed => "kyc_passed",
+            OnboardingStep::PayoutConnected => "payout_connected",
+            OnboardingStep::FirstItemCreated => "first_item_created",
+            OnboardingStep::SubscriptionSelected => "subscription_selected",
+        }
+    }
+
+    /// What the seller has to do to complete the step
+    pub fn description(&self) -> &'static str {
+        match self {
+            OnboardingStep::ProfileComplete => "Add your company name and description",
+            OnboardingStep::KycPassed => "Pass identity verification",
+            OnboardingStep::PayoutConnected => "Connect a payout account",
+            OnboardingStep::FirstItemCreated => "Create your first item",
+            OnboardingStep::SubscriptionSelected => "Choose a subscription plan",
+        }
+    }
+
+    /// Steps that must be complete before this one can be started
+    pub fn prerequisites(&self) -> &'static [OnboardingStep] {
+        match self {
+            OnboardingStep::ProfileComplete => &[],
+            OnboardingStep::KycPassed => &[OnboardingStep::ProfileComplete],
+            OnboardingStep::PayoutConnected => &[OnboardingStep::KycPassed],
+            OnboardingStep::FirstItemCreated => &[OnboardingStep::ProfileComplete],
+            OnboardingStep::SubscriptionSelected => &[OnboardingStep::ProfileComplete],
+        }
+    }
+}
+
+/// Something a seller can do once the steps it requires are complete
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SellerFeature {
+    ListItems,
+    RunRaffles,
+}
+
+impl SellerFeature {
+    pub const ALL: [SellerFeature; 2] = [SellerFeature::ListItems, SellerFeature::RunRaffles];
+
+    pub fn required_steps(&self) -> &'static [OnboardingStep] {
+        match self {
+            SellerFeature::ListItems => &[OnboardingStep::ProfileComplete, OnboardingStep::KycPassed],
+            SellerFeature::RunRaffles => &OnboardingStep::ALL,
+        }
+    }
+
+    fn action(&self) -> &'static str {
+        match self {
+            SellerFeature::ListItems => "list items",
+            SellerFeature::RunRaffles => "run raffles",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OnboardingStepStatus {
+    pub step: OnboardingStep,
+    pub description: String,
+    pub complete: bool,
+    /// Whether its prerequisites are complete so the seller can work on it now
+    pub available: bool,
+}
+
+/// A seller's onboarding checklist and the features it has unlocked
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SellerOnboarding {
+    pub user_id: Uuid,
+    pub steps: Vec<OnboardingStepStatus>,
+    /// First available step that is not yet complete
+    pub next_step: Option<OnboardingStep>,
+    pub unlocked_features: Vec<SellerFeature>,
+    pub complete: bool,
+}
+
+#[derive(Debug, FromRow)]
+struct OnboardingFacts {
+    profile_complete: bool,
+    kyc_passed: bool,
+    payout_connected: bool,
+    first_item_created: bool,
+    subscription_selected: bool,
+}
+
+impl SellerOnboarding {
+    /// Work out a seller's checklist from their seller profile and items
+    pub async fn load(pool: &PgPool, user_id: Uuid) -> Result<Self, AppError> {
+        let facts = sqlx::query_as::<_, OnboardingFacts>(
+            r#"
+            SELECT
+                COALESCE(NULLIF(TRIM(s.company_name), '') IS NOT NULL
+                    AND NULLIF(TRIM(s.description), '') IS NOT NULL, FALSE) AS profile_complete,
+                COALESCE(s.is_verified, FALSE) AS kyc_passed,
+                COALESCE(s.payout_details IS NOT NULL AND s.payout_details <> '{}'::jsonb, FALSE) AS payout_connected,
+                EXISTS (SELECT 1 FROM items i WHERE i.seller_id = u.id) AS first_item_created,
+                COALESCE(s.current_subscription_id IS NOT NULL, FALSE) AS subscription_selected
+            FROM users u
+            LEFT JOIN sellers s ON s.user_id = u.id
+            WHERE u.id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        let completed: Vec<OnboardingStep> = OnboardingStep::ALL
+            .into_iter()
+            .filter(|step| match step {
+                OnboardingStep::ProfileComplete => facts.profile_complete,
+                OnboardingStep::KycPassed => facts.kyc_passed,
+                OnboardingStep::PayoutConnected => facts.payout_connected,
+                OnboardingStep::FirstItemCreated => facts.first_item_created,
+                OnboardingStep::SubscriptionSelected => facts.subscription_selected,
+            })
+            .collect();
+
+        Ok(Self::from_completed(user_id, &completed))
+    }
+
+    pub fn from_completed(user_id: Uuid, completed: &[OnboardingStep]) -> Self {
+        let is_complete = |step: &OnboardingStep| completed.contains(step);
+
+        let steps: Vec<OnboardingStepStatus> = OnboardingStep::ALL
+            .iter()
+            .map(|step| OnboardingStepStatus {
+                step: *step,
+                description: step.description().to_string(),
+                complete: is_complete(step),
+                available: step.prerequisites().iter().all(is_complete),
+            })
+            .collect();
+
+        let next_step = steps
+            .iter()
+            .find(|status| status.available && !status.complete)
+            .map(|status| status.step);
+
+        let unlocked_features = SellerFeature::ALL
+            .into_iter()
+            .filter(|feature| feature.required_steps().iter().all(is_complete))
+            .collect();
+
+        Self {
+            user_id,
+            complete: steps.iter().all(|status| status.complete),
+            steps,
+            next_step,
+            unlocked_features,
+        }
+    }
+
+    pub fn is_complete(&self, step: OnboardingStep) -> bool {
+        self.steps.iter().any(|status| status.step == step && status.complete)
+    }
+
+    /// Refuse a feature the seller hasn't unlocked, naming the steps still to do
+    pub fn require(&self, feature: SellerFeature) -> Result<(), AppError> {
+        let missing: Vec<&str> = feature
+            .required_steps()
+            .iter()
+            .filter(|step| !self.is_complete(**step))
+            .map(|step| step.as_str())
+            .collect();
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        Err(AppError::Forbidden(format!(
+            "Complete seller onboarding to {}: {} still to do",
+            feature.action(),
+            missing.join(", ")
+        )))
+    }
+
+    /// Refuse to start a step whose prerequisites aren't complete
+    pub fn require_available(&self, step: OnboardingStep) -> Result<(), AppError> {
+        let missing: Vec<&str> = step
+            .prerequisites()
+            .iter()
+            .filter(|prerequisite| !self.is_complete(**prerequisite))
+            .map(|prerequisite| prerequisite.as_str())
+            .collect();
+
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        Err(AppError::Conflict(format!(
+            "Complete {} before {}",
+            missing.join(", "),
+            step.as_str()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_seller_can_only_start_profile() {
+        let onboarding = SellerOnboarding::from_completed(Uuid::new_v4(), &[]);
+
+        assert_eq!(onboarding.next_step, Some(OnboardingStep::ProfileComplete));
+        assert!(onboarding.unlocked_features.is_empty());
+        assert!(!onboarding.complete);
+        let available: Vec<OnboardingStep> = onboarding
+            .steps
+            .iter()
+            .filter(|status| status.available)
+            .map(|status| status.step)
+            .collect();
+        assert_eq!(available, vec![OnboardingStep::ProfileComplete]);
+    }
+
+    #[test]
+    fn test_profile_alone_unlocks_nothing() {
+        let onboarding = SellerOnboarding::from_completed(Uuid::new_v4(), &[OnboardingStep::ProfileComplete]);
+
+        assert!(onboarding.unlocked_features.is_empty());
+        assert_eq!(onboarding.next_step, Some(OnboardingStep::KycPassed));
+
+        let err = onboarding.require(SellerFeature::ListItems).unwrap_err().to_string();
+        assert!(err.contains("kyc_passed"));
+        assert!(!err.contains("profile_complete"));
+    }
+
+    #[test]
+    fn test_kyc_unlocks_listing_but_not_raffles() {
+        let onboarding = SellerOnboarding::from_completed(
+            Uuid::new_v4(),
+            &[OnboardingStep::ProfileComplete, OnboardingStep::KycPassed],
+        );
+
+        assert_eq!(onboarding.unlocked_features, vec![SellerFeature::ListItems]);
+        assert!(onboarding.require(SellerFeature::ListItems).is_ok());
+
+        let err = onboarding.require(SellerFeature::RunRaffles).unwrap_err().to_string();
+        assert!(err.contains("payout_connected"));
+        assert!(err.contains("subscription_selected"));
+        assert!(!err.contains("kyc_passed"));
+    }
+
+    #[test]
+    fn test_payout_waits_for_kyc() {
+        let onboarding = SellerOnboarding::from_completed(Uuid::new_v4(), &[OnboardingStep::ProfileComplete]);
+        assert!(matches!(
+            onboarding.require_available(OnboardingStep::PayoutConnected),
+            Err(AppError::Conflict(_))
+        ));
+        assert!(onboarding.require_available(OnboardingStep::SubscriptionSelected).is_ok());
+
+        let verified = SellerOnboarding::from_completed(
+            Uuid::new_v4(),
+            &[OnboardingStep::ProfileComplete, OnboardingStep::KycPassed],
+        );
+        assert!(verified.require_available(OnboardingStep::PayoutConnected).is_ok());
+    }
+
+    #[test]
+    fn test_all_steps_unlock_raffles() {
+        let onboarding = SellerOnboarding::from_completed(Uuid::new_v4(), &OnboardingStep::ALL);
+
+        assert!(onboarding.complete);
+        assert_eq!(onboarding.next_step, None);
+        assert_eq!(onboarding.unlocked_features, SellerFeature::ALL.to_vec());
+        assert!(onboarding.require(SellerFeature::RunRaffles).is_ok());
+    }
+}
@@ -129,6 +129,21 @@ impl SystemSetting {
     }
 }
 
+/// An ERC-20 token raffles may be priced in, from the `supported_payment_tokens` setting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupportedPaymentToken {
+    pub symbol: String,
+    /// Checksummed or lowercase hex contract address
+    pub address: String,
+    pub decimals: u8,
+    #[serde(default = "default_token_enabled")]
+    pub enabled: bool,
+}
+
+fn default_token_enabled() -> bool {
+    true
+}
+
 /// Helper struct for common system settings
 pub struct SystemSettings;
 
@@ -141,22 +156,6 @@ impl SystemSettings {
             .unwrap_or_else(|| "Raffle Shopping Platform".to_string()))
     }
 
-    /// Get maximum boxes per raffle
-    pub async fn get_max_boxes_per_raffle(pool: &PgPool) -> Result<i32, AppError> {
-        let setting = SystemSetting::get_by_key(pool, "max_boxes_per_raffle").await?;
-        Ok(setting
-            .and_then(|s| s.get_int_value())
-            .unwrap_or(10000) as i32)
-    }
-
-    /// Get minimum box price
-    pub async fn get_min_box_price(pool: &PgPool) -> Result<rust_decimal::Decimal, AppError> {
-        let setting = SystemSetting::get_by_key(pool, "min_box_price").await?;
-        Ok(setting
-            .and_then(|s| s.get_decimal_value())
-            .unwrap_or_else(|| rust_decimal::Decimal::new(100, 2))) // $1.00
-    }
-
     /// Get credit expiration days
     pub async fn get_credit_expiration_days(pool: &PgPool) -> Result<i32, AppError> {
         let setting = SystemSetting::get_by_key(pool, "credit_expiration_days").await?;
@@ -181,6 +180,35 @@ impl SystemSettings {
             .unwrap_or_else(|| rust_decimal::Decimal::new(500, 2))) // 5.00%
     }
 
+    /// Get sales tax percentage applied to box purchase receipts
+    pub async fn get_sales_tax_percentage(pool: &PgPool) -> Result<rust_decimal::Decimal, AppError> {
+        let setting = SystemSetting::get_by_key(pool, "sales_tax_percentage").await?;
+        Ok(setting
+            .and_then(|s| s.get_decimal_value())
+            .unwrap_or(rust_decimal::Decimal::ZERO))
+    }
+
+    /// Get ERC-20 tokens accepted for box purchases; disabled entries are left out
+    pub async fn get_supported_payment_tokens(pool: &PgPool) -> Result<Vec<SupportedPaymentToken>, AppError> {
+        let setting = SystemSetting::get_by_key(pool, "supported_payment_tokens").await?;
+        let tokens: Vec<SupportedPaymentToken> = setting
+            .and_then(|s| serde_json::from_value(s.value).ok())
+            .unwrap_or_default();
+
+        Ok(tokens.into_iter().filter(|t| t.enabled).collect())
+    }
+
+    /// Find an enabled payment token by contract address
+    pub async fn find_supported_payment_token(
+        pool: &PgPool,
+        address: &str,
+    ) -> Result<Option<SupportedPaymentToken>, AppError> {
+        let tokens = Self::get_supported_payment_tokens(pool).await?;
+        Ok(tokens
+            .into_iter()
+            .find(|t| t.address.eq_ignore_ascii_case(address.trim())))
+    }
+
     /// Set platform name
     pub async fn set_platform_name(pool: &PgPool, name: String) -> Result<SystemSetting, AppError> {
         SystemSetting::upsert(
@@ -192,28 +220,6 @@ impl SystemSettings {
         ).await
     }
 
-    /// Set maximum boxes per raffle
-    pub async fn set_max_boxes_per_raffle(pool: &PgPool, max_boxes: i32) -> Result<SystemSetting, AppError> {
-        SystemSetting::upsert(
-            pool,
-            "max_boxes_per_raffle".to_string(),
-            serde_json::Value::Number(serde_json::Number::from(max_boxes)),
-            Some("Maximum number of boxes allowed per raffle".to_string()),
-            false,
-        ).await
-    }
-
-    /// Set minimum box price
-    pub async fn set_min_box_price(pool: &PgPool, min_price: rust_decimal::Decimal) -> Result<SystemSetting, AppError> {
-        SystemSetting::upsert(
-            pool,
-            "min_box_price".to_string(),
-            serde_json::Value::String(min_price.to_string()),
-            Some("Minimum price per box in credits".to_string()),
-            false,
-        ).await
-    }
-
     /// Set credit expiration days
     pub async fn set_credit_expiration_days(pool: &PgPool, days: i32) -> Result<SystemSetting, AppError> {
         SystemSetting::upsert(
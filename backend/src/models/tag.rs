@@ -0,0 +1,607 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+
+/// Most tags on one item or raffle
+pub const MAX_TAGS: usize = 20;
+pub const MAX_TAG_LENGTH: usize = 40;
+
+const MAX_DISPLAY_NAME_LENGTH: usize = 60;
+const MAX_COLLECTION_SLUG_LENGTH: usize = 60;
+const MAX_COLLECTION_TITLE_LENGTH: usize = 120;
+
+const TAG_COLUMNS: &str = "id, slug, display_name, description, is_curated, created_by, created_at, updated_at";
+
+const COLLECTION_COLUMNS: &str = "id, slug, title, description, image_url, tags, is_published, position, \
+    created_by, created_at, updated_at";
+
+/// A tag in the registry; free-form tags are registered the first time a seller uses them
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Tag {
+    pub id: Uuid,
+    pub slug: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    /// Curated tags are only applied by admins
+    pub is_curated: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Another spelling of a tag, rewritten to it when tags are saved or searched
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TagSynonym {
+    pub synonym: String,
+    pub tag_slug: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A themed discovery page listing the raffles that carry any of its tags
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TagCollection {
+    pub id: Uuid,
+    pub slug: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub tags: Vec<String>,
+    pub is_published: bool,
+    pub position: i32,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateTagRequest {
+    pub name: String,
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub is_curated: bool,
+}
+
+/// Fields left out keep their current value
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateTagRequest {
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    pub is_curated: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateTagCollectionRequest {
+    pub slug: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub is_published: bool,
+    #[serde(default)]
+    pub position: i32,
+}
+
+/// Fields left out keep their current value
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateTagCollectionRequest {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub is_published: Option<bool>,
+    pub position: Option<i32>,
+}
+
+impl Tag {
+    /// Normalize tags, rewrite synonyms and register tags not seen before as free-form.
+    /// Without `allow_curated`, curated tags are refused.
+    pub async fn resolve(pool: &PgPool, tags: &[String], allow_curated: bool) -> Result<Vec<String>, AppError> {
+        let tags = Self::canonicalize(pool, tags).await?;
+        if tags.len() > MAX_TAGS {
+            return Err(AppError::Validation(format!("At most {} tags are allowed", MAX_TAGS)));
+        }
+        if tags.is_empty() {
+            return Ok(tags);
+        }
+
+        if !allow_curated {
+            let curated: Option<String> =
+                sqlx::query_scalar("SELECT slug FROM tags WHERE slug = ANY($1) AND is_curated ORDER BY slug LIMIT 1")
+                    .bind(&tags)
+                    .fetch_optional(pool)
+                    .await?;
+            if let Some(slug) = curated {
+                return Err(AppError::Forbidden(format!("Tag '{}' can only be applied by admins", slug)));
+            }
+        }
+
+        sqlx::query(
+            "INSERT INTO tags (slug, display_name) SELECT t, t FROM unnest($1::text[]) AS t \
+             ON CONFLICT (slug) DO NOTHING",
+        )
+        .bind(&tags)
+        .execute(pool)
+        .await?;
+
+        Ok(tags)
+    }
+
+    /// Normalize tags and rewrite synonyms, without registering anything
+    pub async fn canonicalize(pool: &PgPool, tags: &[String]) -> Result<Vec<String>, AppError> {
+        let tags = normalize_tags(tags)?;
+        if tags.is_empty() {
+            return Ok(tags);
+        }
+
+        let synonyms: Vec<(String, String)> =
+            sqlx::query_as("SELECT synonym, tag_slug FROM tag_synonyms WHERE synonym = ANY($1)")
+                .bind(&tags)
+                .fetch_all(pool)
+                .await?;
+
+        Ok(apply_synonyms(tags, &synonyms))
+    }
+
+    pub async fn find_by_slug(pool: &PgPool, slug: &str) -> Result<Option<Self>, AppError> {
+        let tag = sqlx::query_as::<_, Tag>(&format!("SELECT {} FROM tags WHERE slug = $1", TAG_COLUMNS))
+            .bind(slug)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(tag)
+    }
+
+    /// Tags whose slug starts with `prefix`, curated first
+    pub async fn list(
+        pool: &PgPool,
+        prefix: Option<&str>,
+        curated_only: bool,
+        limit: i64,
+    ) -> Result<Vec<Self>, AppError> {
+        let tags = sqlx::query_as::<_, Tag>(&format!(
+            "SELECT {} FROM tags \
+             WHERE ($1::text IS NULL OR slug LIKE $1 || '%') AND (NOT $2 OR is_curated) \
+             ORDER BY is_curated DESC, slug \
+             LIMIT $3",
+            TAG_COLUMNS
+        ))
+        .bind(prefix)
+        .bind(curated_only)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(tags)
+    }
+
+    /// Register a tag; registering a free-form tag that exists curates it
+    pub async fn create(pool: &PgPool, request: CreateTagRequest, created_by: Uuid) -> Result<Self, AppError> {
+        let slug = normalize_tag(&request.name)?;
+        let display_name = display_name(request.display_name.as_deref(), &slug)?;
+
+        let is_synonym: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM tag_synonyms WHERE synonym = $1)")
+            .bind(&slug)
+            .fetch_one(pool)
+            .await?;
+        if is_synonym {
+            return Err(AppError::Conflict(format!("'{}' is a synonym of another tag", slug)));
+        }
+
+        let tag = sqlx::query_as::<_, Tag>(&format!(
+            "INSERT INTO tags (slug, display_name, description, is_curated, created_by) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (slug) DO UPDATE SET \
+                display_name = EXCLUDED.display_name, \
+                description = COALESCE(EXCLUDED.description, tags.description), \
+                is_curated = tags.is_curated OR EXCLUDED.is_curated, \
+                updated_at = NOW() \
+             RETURNING {}",
+            TAG_COLUMNS
+        ))
+        .bind(&slug)
+        .bind(display_name)
+        .bind(request.description)
+        .bind(request.is_curated)
+        .bind(created_by)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(tag)
+    }
+
+    pub async fn update(pool: &PgPool, slug: &str, request: UpdateTagRequest) -> Result<Self, AppError> {
+        let display_name = request
+            .display_name
+            .as_deref()
+            .map(|name| display_name(Some(name), slug))
+            .transpose()?;
+
+        sqlx::query_as::<_, Tag>(&format!(
+            "UPDATE tags SET \
+                display_name = COALESCE($2, display_name), \
+                description = COALESCE($3, description), \
+                is_curated = COALESCE($4, is_curated), \
+                updated_at = NOW() \
+             WHERE slug = $1 \
+             RETURNING {}",
+            TAG_COLUMNS
+        ))
+        .bind(slug)
+        .bind(display_name)
+        .bind(request.description)
+        .bind(request.is_curated)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tag not found".to_string()))
+    }
+
+    /// Delete a tag and take it off every item, raffle and collection
+    pub async fn delete(pool: &PgPool, slug: &str) -> Result<(), AppError> {
+        let mut tx = pool.begin().await?;
+
+        let deleted = sqlx::query("DELETE FROM tags WHERE slug = $1")
+            .bind(slug)
+            .execute(&mut *tx)
+            .await?;
+        if deleted.rows_affected() == 0 {
+            return Err(AppError::NotFound("Tag not found".to_string()));
+        }
+
+        sqlx::query("UPDATE items SET tags = array_remove(tags, $1), updated_at = NOW() WHERE $1 = ANY(tags)")
+            .bind(slug)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE raffles SET tags = array_remove(tags, $1), updated_at = NOW() WHERE $1 = ANY(tags)")
+            .bind(slug)
+            .execute(&mut *tx)
+            .await?;
+        // A collection left without tags would match nothing; it keeps the tag until edited
+        sqlx::query(
+            "UPDATE tag_collections SET tags = array_remove(tags, $1), updated_at = NOW() \
+             WHERE $1 = ANY(tags) AND cardinality(tags) > 1",
+        )
+        .bind(slug)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Make `synonym` another spelling of the tag. A synonym that was a tag of its own is
+    /// merged into this one everywhere it was used.
+    pub async fn add_synonym(pool: &PgPool, slug: &str, synonym: &str) -> Result<TagSynonym, AppError> {
+        let synonym = normalize_tag(synonym)?;
+        if synonym == slug {
+            return Err(AppError::Validation("A tag cannot be its own synonym".to_string()));
+        }
+
+        let mut tx = pool.begin().await?;
+
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM tags WHERE slug = $1)")
+            .bind(slug)
+            .fetch_one(&mut *tx)
+            .await?;
+        if !exists {
+            return Err(AppError::NotFound("Tag not found".to_string()));
+        }
+
+        // Synonyms of the merged tag would otherwise go with it
+        sqlx::query("UPDATE tag_synonyms SET tag_slug = $2 WHERE tag_slug = $1")
+            .bind(&synonym)
+            .bind(slug)
+            .execute(&mut *tx)
+            .await?;
+
+        let merged = sqlx::query("DELETE FROM tags WHERE slug = $1")
+            .bind(&synonym)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+            > 0;
+
+        if merged {
+            for table in ["items", "raffles", "tag_collections"] {
+                sqlx::query(&format!(
+                    "UPDATE {} SET tags = ARRAY(SELECT DISTINCT t FROM unnest(array_replace(tags, $1, $2)) AS t ORDER BY t), \
+                        updated_at = NOW() \
+                     WHERE $1 = ANY(tags)",
+                    table
+                ))
+                .bind(&synonym)
+                .bind(slug)
+                .execute(&mut *tx)
+                .await?;
+            }
+            sqlx::query(
+                "UPDATE webhook_endpoints \
+                 SET raffle_tags = ARRAY(SELECT DISTINCT t FROM unnest(array_replace(raffle_tags, $1, $2)) AS t ORDER BY t) \
+                 WHERE $1 = ANY(raffle_tags)",
+            )
+            .bind(&synonym)
+            .bind(slug)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let added = sqlx::query_as::<_, TagSynonym>(
+            "INSERT INTO tag_synonyms (synonym, tag_slug) VALUES ($1, $2) \
+             ON CONFLICT (synonym) DO UPDATE SET tag_slug = EXCLUDED.tag_slug \
+             RETURNING synonym, tag_slug, created_at",
+        )
+        .bind(&synonym)
+        .bind(slug)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(added)
+    }
+
+    pub async fn list_synonyms(pool: &PgPool, slug: &str) -> Result<Vec<TagSynonym>, AppError> {
+        let synonyms = sqlx::query_as::<_, TagSynonym>(
+            "SELECT synonym, tag_slug, created_at FROM tag_synonyms WHERE tag_slug = $1 ORDER BY synonym",
+        )
+        .bind(slug)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(synonyms)
+    }
+
+    /// Stop rewriting a synonym; tags already rewritten stay as they are
+    pub async fn remove_synonym(pool: &PgPool, synonym: &str) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM tag_synonyms WHERE synonym = $1")
+            .bind(synonym)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Tag synonym not found".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+impl TagCollection {
+    pub async fn create(
+        pool: &PgPool,
+        request: CreateTagCollectionRequest,
+        tags: Vec<String>,
+        created_by: Uuid,
+    ) -> Result<Self, AppError> {
+        let slug = collection_slug(&request.slug)?;
+        let title = collection_title(&request.title)?;
+
+        let collection = sqlx::query_as::<_, TagCollection>(&format!(
+            "INSERT INTO tag_collections (slug, title, description, image_url, tags, is_published, position, created_by) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+             ON CONFLICT (slug) DO NOTHING \
+             RETURNING {}",
+            COLLECTION_COLUMNS
+        ))
+        .bind(&slug)
+        .bind(title)
+        .bind(request.description)
+        .bind(request.image_url)
+        .bind(&tags)
+        .bind(request.is_published)
+        .bind(request.position)
+        .bind(created_by)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::Conflict(format!("A collection with slug '{}' already exists", slug)))?;
+
+        Ok(collection)
+    }
+
+    pub async fn update(
+        pool: &PgPool,
+        id: Uuid,
+        request: UpdateTagCollectionRequest,
+        tags: Option<Vec<String>>,
+    ) -> Result<Self, AppError> {
+        let title = request.title.as_deref().map(collection_title).transpose()?;
+
+        sqlx::query_as::<_, TagCollection>(&format!(
+            "UPDATE tag_collections SET \
+                title = COALESCE($2, title), \
+                description = COALESCE($3, description), \
+                image_url = COALESCE($4, image_url), \
+                tags = COALESCE($5, tags), \
+                is_published = COALESCE($6, is_published), \
+                position = COALESCE($7, position), \
+                updated_at = NOW() \
+             WHERE id = $1 \
+             RETURNING {}",
+            COLLECTION_COLUMNS
+        ))
+        .bind(id)
+        .bind(title)
+        .bind(request.description)
+        .bind(request.image_url)
+        .bind(tags)
+        .bind(request.is_published)
+        .bind(request.position)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tag collection not found".to_string()))
+    }
+
+    pub async fn delete(pool: &PgPool, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM tag_collections WHERE id = $1")
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Tag collection not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Every collection in discovery order, drafts included
+    pub async fn list(pool: &PgPool, published_only: bool) -> Result<Vec<Self>, AppError> {
+        let collections = sqlx::query_as::<_, TagCollection>(&format!(
+            "SELECT {} FROM tag_collections WHERE (NOT $1 OR is_published) ORDER BY position, created_at",
+            COLLECTION_COLUMNS
+        ))
+        .bind(published_only)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(collections)
+    }
+
+    pub async fn find_published(pool: &PgPool, slug: &str) -> Result<Option<Self>, AppError> {
+        let collection = sqlx::query_as::<_, TagCollection>(&format!(
+            "SELECT {} FROM tag_collections WHERE slug = $1 AND is_published",
+            COLLECTION_COLUMNS
+        ))
+        .bind(slug)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(collection)
+    }
+}
+
+/// Canonical form of a tag: lowercase words joined by single dashes, e.g. "Board Games" is `board-games`
+pub fn normalize_tag(tag: &str) -> Result<String, AppError> {
+    let mut normalized = String::with_capacity(tag.len());
+    for c in tag.trim().trim_start_matches('#').chars() {
+        if c.is_whitespace() || c == '_' || c == '-' {
+            if !normalized.is_empty() && !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else if c.is_ascii_alphanumeric() {
+            normalized.push(c.to_ascii_lowercase());
+        } else {
+            return Err(AppError::Validation(format!(
+                "Tag '{}' may only contain letters, digits, spaces and dashes",
+                tag.trim()
+            )));
+        }
+    }
+    let normalized = normalized.trim_end_matches('-').to_string();
+
+    if normalized.is_empty() || normalized.len() > MAX_TAG_LENGTH {
+        return Err(AppError::Validation(format!("Tags must be 1 to {} characters", MAX_TAG_LENGTH)));
+    }
+
+    Ok(normalized)
+}
+
+/// Normalize each tag, sorted without duplicates
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, AppError> {
+    let mut normalized = tags.iter().map(|tag| normalize_tag(tag)).collect::<Result<Vec<_>, _>>()?;
+    normalized.sort();
+    normalized.dedup();
+    Ok(normalized)
+}
+
+/// Rewrite each `(synonym, tag)` pair, keeping the tags sorted without duplicates
+fn apply_synonyms(tags: Vec<String>, synonyms: &[(String, String)]) -> Vec<String> {
+    let mut canonical: Vec<String> = tags
+        .into_iter()
+        .map(|tag| {
+            synonyms
+                .iter()
+                .find(|(synonym, _)| *synonym == tag)
+                .map(|(_, slug)| slug.clone())
+                .unwrap_or(tag)
+        })
+        .collect();
+    canonical.sort();
+    canonical.dedup();
+    canonical
+}
+
+fn display_name(name: Option<&str>, slug: &str) -> Result<String, AppError> {
+    let name = name.map(str::trim).filter(|name| !name.is_empty()).unwrap_or(slug);
+    if name.chars().count() > MAX_DISPLAY_NAME_LENGTH {
+        return Err(AppError::Validation(format!(
+            "Tag display names must be at most {} characters",
+            MAX_DISPLAY_NAME_LENGTH
+        )));
+    }
+    Ok(name.to_string())
+}
+
+fn collection_slug(slug: &str) -> Result<String, AppError> {
+    let slug = slug.trim().to_lowercase();
+    if slug.is_empty() || slug.len() > MAX_COLLECTION_SLUG_LENGTH {
+        return Err(AppError::Validation(format!(
+            "Collection slugs must be 1 to {} characters",
+            MAX_COLLECTION_SLUG_LENGTH
+        )));
+    }
+    if !slug.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(AppError::Validation(
+            "Collection slugs may only contain letters, digits and dashes".to_string(),
+        ));
+    }
+    Ok(slug)
+}
+
+fn collection_title(title: &str) -> Result<String, AppError> {
+    let title = title.trim();
+    if title.is_empty() || title.chars().count() > MAX_COLLECTION_TITLE_LENGTH {
+        return Err(AppError::Validation(format!(
+            "Collection titles must be 1 to {} characters",
+            MAX_COLLECTION_TITLE_LENGTH
+        )));
+    }
+    Ok(title.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(normalize_tag(" Board Games ").unwrap(), "board-games");
+        assert_eq!(normalize_tag("#Holiday_2026").unwrap(), "holiday-2026");
+        assert_eq!(normalize_tag("retro -- gaming-").unwrap(), "retro-gaming");
+        assert!(normalize_tag("   ").is_err());
+        assert!(normalize_tag("---").is_err());
+        assert!(normalize_tag("50% off").is_err());
+        assert!(normalize_tag(&"a".repeat(MAX_TAG_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_normalize_tags_sorts_and_dedupes() {
+        let tags = normalize_tags(&["Sneakers".to_string(), "retro".to_string(), "sneakers ".to_string()]).unwrap();
+        assert_eq!(tags, vec!["retro", "sneakers"]);
+        assert!(normalize_tags(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_apply_synonyms() {
+        let synonyms = vec![
+            ("tees".to_string(), "t-shirts".to_string()),
+            ("tshirts".to_string(), "t-shirts".to_string()),
+        ];
+        let tags = vec!["t-shirts".to_string(), "tees".to_string(), "tshirts".to_string(), "vintage".to_string()];
+        assert_eq!(apply_synonyms(tags, &synonyms), vec!["t-shirts", "vintage"]);
+    }
+
+    #[test]
+    fn test_collection_fields() {
+        assert_eq!(collection_slug(" Summer-Sale ").unwrap(), "summer-sale");
+        assert!(collection_slug("summer sale").is_err());
+        assert!(collection_title("  ").is_err());
+        assert_eq!(display_name(None, "board-games").unwrap(), "board-games");
+        assert_eq!(display_name(Some(" Board Games "), "board-games").unwrap(), "Board Games");
+    }
+}
@@ -26,6 +26,7 @@ mod tests {
             email: "test@example.com".to_string(),
             password: "password123".to_string(),
             phone_number: None,
+            referral_code: None,
         };
 
         User::create(
@@ -92,6 +93,7 @@ mod tests {
             retail_price: Decimal::new(10000, 2), // $100.00
             cost_of_goods: Decimal::new(5000, 2), // $50.00
             stock_quantity: 10,
+            currency: None,
         };
 
         let item = Item::create(&pool, None, request, None, None)
@@ -101,7 +103,8 @@ mod tests {
         assert_eq!(item.name, "Test Item");
         assert_eq!(item.retail_price, Decimal::new(10000, 2));
         assert_eq!(item.stock_quantity, 10);
-        assert_eq!(item.status, raffle_platform_shared::ItemStatus::Available);
+        // New items wait for review before they can be raffled
+        assert_eq!(item.status, raffle_platform_shared::ItemStatus::PendingReview);
     }
 
     #[tokio::test]
@@ -115,6 +118,7 @@ mod tests {
             retail_price: Decimal::new(10000, 2),
             cost_of_goods: Decimal::new(5000, 2),
             stock_quantity: 5,
+            currency: None,
         };
 
         let item = Item::create(&pool, None, request, None, None)
@@ -148,6 +152,7 @@ mod tests {
             retail_price: Decimal::new(10000, 2),
             cost_of_goods: Decimal::new(5000, 2),
             stock_quantity: 1,
+            currency: None,
         };
 
         let item = Item::create(&pool, None, item_request, None, None)
@@ -161,6 +166,14 @@ mod tests {
             total_winners: 1,
             grid_rows: 10,
             grid_cols: 10,
+            layout: None,
+            prize_tiers: Vec::new(),
+            pricing_curve: None,
+            starts_at: None,
+            ends_at: None,
+            deadline_action: Default::default(),
+            max_boxes_per_user: None,
+            credit_alternative: None,
         };
 
         let raffle = Raffle::create(&pool, raffle_request, None)
@@ -185,6 +198,7 @@ mod tests {
             retail_price: Decimal::new(10000, 2),
             cost_of_goods: Decimal::new(5000, 2),
             stock_quantity: 1,
+            currency: None,
         };
 
         let item = Item::create(&pool, None, item_request, None, None)
@@ -199,6 +213,14 @@ mod tests {
             total_winners: 1,
             grid_rows: 5,  // 5 * 5 = 25 < 100 boxes
             grid_cols: 5,
+            layout: None,
+            prize_tiers: Vec::new(),
+            pricing_curve: None,
+            starts_at: None,
+            ends_at: None,
+            deadline_action: Default::default(),
+            max_boxes_per_user: None,
+            credit_alternative: None,
         };
 
         let result = Raffle::create(&pool, invalid_request, None).await;
@@ -219,6 +241,7 @@ mod tests {
             retail_price: Decimal::new(10000, 2),
             cost_of_goods: Decimal::new(5000, 2),
             stock_quantity: 1,
+            currency: None,
         };
 
         let item = Item::create(&pool, None, item_request, None, None)
@@ -232,6 +255,14 @@ mod tests {
             total_winners: 1,
             grid_rows: 2,
             grid_cols: 5,
+            layout: None,
+            prize_tiers: Vec::new(),
+            pricing_curve: None,
+            starts_at: None,
+            ends_at: None,
+            deadline_action: Default::default(),
+            max_boxes_per_user: None,
+            credit_alternative: None,
         };
 
         let raffle = Raffle::create(&pool, raffle_request, None)
@@ -267,6 +298,7 @@ mod tests {
             retail_price: Decimal::new(10000, 2),
             cost_of_goods: Decimal::new(5000, 2),
             stock_quantity: 1,
+            currency: None,
         };
 
         let item = Item::create(&pool, None, item_request, None, None)
@@ -280,6 +312,14 @@ mod tests {
             total_winners: 1,
             grid_rows: 2,
             grid_cols: 5,
+            layout: None,
+            prize_tiers: Vec::new(),
+            pricing_curve: None,
+            starts_at: None,
+            ends_at: None,
+            deadline_action: Default::default(),
+            max_boxes_per_user: None,
+            credit_alternative: None,
         };
 
         let raffle = Raffle::create(&pool, raffle_request, None)
@@ -334,11 +374,13 @@ mod tests {
         assert_eq!(available, Decimal::new(1000, 2));
 
         // Use credits
+        let mut tx = pool.begin().await.unwrap();
         let used_credits = UserCredit::mark_as_used(
-            &pool,
+            &mut tx,
             &[credit.id],
             Decimal::new(500, 2), // Use $5.00
         ).await.unwrap();
+        tx.commit().await.unwrap();
 
         assert_eq!(used_credits.len(), 1);
         assert_eq!(used_credits[0].amount, Decimal::new(500, 2));
@@ -462,11 +504,15 @@ mod tests {
             retail_price: Decimal::new(1000, 2),
             cost_of_goods: Decimal::new(500, 2),
             stock_quantity: 5,
+            currency: None,
         };
 
         let item = Item::create(&pool, None, item_request, None, None)
             .await
             .unwrap();
+        Item::update_status(&pool, item.id, raffle_platform_shared::ItemStatus::Available)
+            .await
+            .unwrap();
 
         // Create free redeemable item
         let free_item = FreeRedeemableItem::create(
@@ -57,6 +57,7 @@ pub async fn create_test_user(pool: &PgPool, email: &str) -> User {
         username: format!("user_{}", Uuid::new_v4().to_string()[..8].to_string()),
         email: email.to_string(),
         phone_number: None,
+        referral_code: None,
     };
 
     User::create(
@@ -93,6 +94,7 @@ pub async fn create_test_item(pool: &PgPool, seller_id: Option<Uuid>) -> Item {
         retail_price: Decimal::new(10000, 2), // $100.00
         cost_of_goods: Decimal::new(5000, 2), // $50.00
         stock_quantity: 1,
+        currency: None,
     };
 
     Item::create(pool, seller_id, request, None, None)
@@ -109,6 +111,14 @@ pub async fn create_test_raffle(pool: &PgPool, item_id: Uuid) -> Raffle {
         total_winners: 1,
         grid_rows: 10,
         grid_cols: 10,
+        layout: None,
+        prize_tiers: Vec::new(),
+        pricing_curve: None,
+        starts_at: None,
+        ends_at: None,
+        deadline_action: Default::default(),
+        max_boxes_per_user: None,
+        credit_alternative: None,
     };
 
     Raffle::create(pool, request, None)
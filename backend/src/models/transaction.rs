@@ -2,9 +2,10 @@ use chrono::{DateTime, Utc};
 use raffle_platform_shared::{TransactionType, Status};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, PgPool};
+use sqlx::{FromRow, PgExecutor, PgPool};
 use uuid::Uuid;
 use crate::error::AppError;
+use crate::models::ledger::{JournalEntry, NewJournalEntry, PostingLine};
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct Transaction {
@@ -22,8 +23,8 @@ pub struct Transaction {
 
 impl Transaction {
     /// Create a new transaction
-    pub async fn create(
-        pool: &PgPool,
+    pub async fn create<'c, E: PgExecutor<'c>>(
+        executor: E,
         user_id: Option<Uuid>,
         seller_id: Option<Uuid>,
         amount: Decimal,
@@ -48,12 +49,59 @@ impl Transaction {
             payment_gateway_ref,
             metadata
         )
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await?;
 
         Ok(transaction)
     }
 
+    /// Create a seller transaction and its journal entry together, posted to the accounts
+    /// finance mapped for the transaction type
+    async fn create_posted(
+        pool: &PgPool,
+        seller_id: Uuid,
+        amount: Decimal,
+        transaction_type: TransactionType,
+        payment_gateway_ref: Option<String>,
+        metadata: Option<serde_json::Value>,
+    ) -> Result<Self, AppError> {
+        let mut tx = pool.begin().await?;
+
+        let transaction = Self::create(
+            &mut *tx,
+            None,
+            Some(seller_id),
+            amount,
+            transaction_type,
+            payment_gateway_ref,
+            metadata,
+        ).await?;
+
+        let (debit_account, credit_account) = sqlx::query_as::<_, (String, String)>(
+            "SELECT debit_account_code, credit_account_code FROM chart_of_accounts WHERE transaction_type = $1",
+        )
+        .bind(transaction_type)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::Internal(format!(
+            "No chart of accounts mapping for {}",
+            transaction_type.to_string()
+        )))?;
+
+        let entry = NewJournalEntry::new(
+            "transaction",
+            Some(transaction.id),
+            format!("{} for seller {}", transaction_type.to_string(), seller_id),
+        )
+        .line(PostingLine::debit(&debit_account, amount).for_seller(seller_id))
+        .line(PostingLine::credit(&credit_account, amount).for_seller(seller_id));
+        JournalEntry::post(&mut *tx, &entry).await?;
+
+        tx.commit().await?;
+
+        Ok(transaction)
+    }
+
     /// Find transaction by ID
     pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Self>, AppError> {
         let transaction = sqlx::query_as!(
@@ -330,10 +378,9 @@ impl Transaction {
         payment_gateway_ref: Option<String>,
         metadata: Option<serde_json::Value>,
     ) -> Result<Self, AppError> {
-        Self::create(
+        Self::create_posted(
             pool,
-            None,
-            Some(seller_id),
+            seller_id,
             amount,
             TransactionType::Payout,
             payment_gateway_ref,
@@ -353,14 +400,14 @@ impl Transaction {
         match fee_type {
             TransactionType::SellerSubscriptionFee 
             | TransactionType::SellerListingFee 
-            | TransactionType::SellerTransactionFee => {},
+            | TransactionType::SellerTransactionFee
+            | TransactionType::SellerApiUsageFee => {},
             _ => return Err(AppError::Validation("Invalid seller fee type".to_string())),
         }
 
-        Self::create(
+        Self::create_posted(
             pool,
-            None,
-            Some(seller_id),
+            seller_id,
             -amount, // Negative amount for fee deduction
             fee_type,
             None,
@@ -439,6 +486,7 @@ impl TransactionType {
             TransactionType::SellerSubscriptionFee => "seller_subscription_fee".to_string(),
             TransactionType::SellerListingFee => "seller_listing_fee".to_string(),
             TransactionType::SellerTransactionFee => "seller_transaction_fee".to_string(),
+            TransactionType::SellerApiUsageFee => "seller_api_usage_fee".to_string(),
         }
     }
 }
\ No newline at end of file
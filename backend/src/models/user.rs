@@ -174,6 +174,48 @@ impl User {
         Ok(())
     }
 
+    /// Change the user's email; the new address starts unverified
+    pub async fn update_email(pool: &PgPool, user_id: Uuid, email: &str) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE users SET email = $1, email_verified = false, updated_at = NOW() WHERE id = $2",
+            email,
+            user_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Currency the user wants prices shown in; None means the settlement currency
+    pub async fn get_display_currency(pool: &PgPool, user_id: Uuid) -> Result<Option<String>, AppError> {
+        let currency = sqlx::query_scalar!(
+            "SELECT display_currency FROM users WHERE id = $1",
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(currency.flatten())
+    }
+
+    /// Set or clear the user's display currency
+    pub async fn set_display_currency(
+        pool: &PgPool,
+        user_id: Uuid,
+        currency: Option<&str>,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE users SET display_currency = $1, updated_at = NOW() WHERE id = $2",
+            currency,
+            user_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
     /// Deactivate user account
     pub async fn deactivate(pool: &PgPool, user_id: Uuid) -> Result<(), AppError> {
         sqlx::query!(
@@ -455,6 +497,32 @@ impl User {
 
         Ok(())
     }
+
+    /// Current token version and whether the account may still authenticate
+    pub async fn token_state(pool: &PgPool, user_id: Uuid) -> Result<Option<TokenState>, AppError> {
+        let state = sqlx::query_as::<_, TokenState>(
+            "SELECT token_version, COALESCE(is_active, false) AS is_active FROM users WHERE id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(state)
+    }
+}
+
+/// What token validation needs to know about a user
+#[derive(Debug, Clone, Copy, FromRow)]
+pub struct TokenState {
+    pub token_version: i32,
+    pub is_active: bool,
+}
+
+impl TokenState {
+    /// Whether a token issued at `token_version` is still honoured
+    pub fn accepts(&self, token_version: i32) -> bool {
+        self.is_active && token_version == self.token_version
+    }
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
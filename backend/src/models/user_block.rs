@@ -0,0 +1,169 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+
+/// Blocks and mutes beyond this many per user are refused
+const MAX_BLOCKS_PER_USER: i64 = 1000;
+
+const BLOCK_COLUMNS: &str = "b.id, b.blocked_id, u.username AS blocked_username, b.kind, b.created_at";
+
+/// How far a block goes
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum BlockKind {
+    /// Hide their comments, and stop them replying to you or commenting on your raffles
+    #[default]
+    Block,
+    /// Only hide their comments
+    Mute,
+}
+
+/// A user the current user has blocked or muted
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct UserBlock {
+    pub id: Uuid,
+    pub blocked_id: Uuid,
+    pub blocked_username: String,
+    pub kind: BlockKind,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct BlockUserRequest {
+    pub user_id: Uuid,
+    /// Defaults to a full block
+    #[serde(default)]
+    pub kind: BlockKind,
+}
+
+impl UserBlock {
+    /// Block or mute a user; blocking someone already blocked or muted changes the kind
+    pub async fn create(pool: &PgPool, blocker_id: Uuid, request: BlockUserRequest) -> Result<Self, AppError> {
+        validate_target(blocker_id, request.user_id)?;
+
+        let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS(SELECT 1 FROM users WHERE id = $1)")
+            .bind(request.user_id)
+            .fetch_one(pool)
+            .await?;
+        if !exists {
+            return Err(AppError::NotFound("User not found".to_string()));
+        }
+
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM user_blocks WHERE blocker_id = $1 AND blocked_id <> $2",
+        )
+        .bind(blocker_id)
+        .bind(request.user_id)
+        .fetch_one(pool)
+        .await?;
+        if count >= MAX_BLOCKS_PER_USER {
+            return Err(AppError::Validation(format!(
+                "You can block or mute at most {} users",
+                MAX_BLOCKS_PER_USER
+            )));
+        }
+
+        sqlx::query_as::<_, UserBlock>(&format!(
+            "WITH b AS ( \
+                INSERT INTO user_blocks (blocker_id, blocked_id, kind) \
+                VALUES ($1, $2, $3) \
+                ON CONFLICT (blocker_id, blocked_id) DO UPDATE SET kind = EXCLUDED.kind \
+                RETURNING * \
+             ) \
+             SELECT {} FROM b JOIN users u ON u.id = b.blocked_id",
+            BLOCK_COLUMNS
+        ))
+        .bind(blocker_id)
+        .bind(request.user_id)
+        .bind(request.kind)
+        .fetch_one(pool)
+        .await
+        .map_err(AppError::from)
+    }
+
+    /// Users the user has blocked or muted, most recent first
+    pub async fn list_for_user(pool: &PgPool, blocker_id: Uuid) -> Result<Vec<Self>, AppError> {
+        let blocks = sqlx::query_as::<_, UserBlock>(&format!(
+            "SELECT {} FROM user_blocks b JOIN users u ON u.id = b.blocked_id \
+             WHERE b.blocker_id = $1 \
+             ORDER BY b.created_at DESC",
+            BLOCK_COLUMNS
+        ))
+        .bind(blocker_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(blocks)
+    }
+
+    /// Unblock or unmute a user
+    pub async fn remove(pool: &PgPool, blocker_id: Uuid, blocked_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM user_blocks WHERE blocker_id = $1 AND blocked_id = $2")
+            .bind(blocker_id)
+            .bind(blocked_id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("You have not blocked or muted this user".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Whether `blocker_id` has fully blocked `blocked_id`; mutes don't count
+    pub async fn is_blocked(pool: &PgPool, blocker_id: Uuid, blocked_id: Uuid) -> Result<bool, AppError> {
+        let blocked = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM user_blocks WHERE blocker_id = $1 AND blocked_id = $2 AND kind = 'block')",
+        )
+        .bind(blocker_id)
+        .bind(blocked_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(blocked)
+    }
+
+    /// Refuse anything `sender_id` tries to send to or post at `recipient_id` if the
+    /// recipient has blocked them
+    pub async fn ensure_not_blocked(pool: &PgPool, recipient_id: Uuid, sender_id: Uuid) -> Result<(), AppError> {
+        if Self::is_blocked(pool, recipient_id, sender_id).await? {
+            return Err(AppError::Forbidden("This user has blocked you".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn validate_target(blocker_id: Uuid, blocked_id: Uuid) -> Result<(), AppError> {
+    if blocker_id == blocked_id {
+        return Err(AppError::Validation("You cannot block yourself".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cannot_block_self() {
+        let user_id = Uuid::new_v4();
+        assert!(validate_target(user_id, user_id).is_err());
+        assert!(validate_target(user_id, Uuid::new_v4()).is_ok());
+    }
+
+    #[test]
+    fn test_request_defaults_to_full_block() {
+        let request: BlockUserRequest =
+            serde_json::from_value(serde_json::json!({ "user_id": Uuid::new_v4() })).unwrap();
+        assert_eq!(request.kind, BlockKind::Block);
+
+        let request: BlockUserRequest =
+            serde_json::from_value(serde_json::json!({ "user_id": Uuid::new_v4(), "kind": "mute" })).unwrap();
+        assert_eq!(request.kind, BlockKind::Mute);
+    }
+}
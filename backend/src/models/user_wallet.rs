@@ -0,0 +1,238 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use crate::error::AppError;
+use crate::utils::hd_wallet::{EvmChain, EVM_CHAINS};
+
+/// Where a user's wallet key comes from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WalletKeySource {
+    /// Derived from the platform master seed
+    Platform,
+    /// Derived from the user's own mnemonic
+    Mnemonic,
+    /// A private key the user imported
+    Imported,
+    /// Created before derivation metadata was recorded
+    Legacy,
+}
+
+/// How a user's wallet key was derived
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct WalletDerivation {
+    pub key_source: WalletKeySource,
+    pub derivation_path: Option<String>,
+    /// BIP-44 account under the master seed, for platform wallets
+    pub account_index: Option<i32>,
+    /// Master seed a platform wallet was derived from
+    pub seed_id: Option<String>,
+}
+
+/// A user's address on one EVM chain
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WalletChainAddress {
+    pub chain_id: i64,
+    pub address: String,
+    pub derivation_path: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A wallet key replaced by an import or an upgrade
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RetiredWallet {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub address: String,
+    #[serde(skip_serializing)]
+    pub private_key_encrypted: String,
+    #[serde(skip_serializing)]
+    pub mnemonic_encrypted: Option<String>,
+    pub key_source: WalletKeySource,
+    pub derivation_path: Option<String>,
+    pub reason: String,
+    pub retired_at: DateTime<Utc>,
+}
+
+const DERIVATION_COLUMNS: &str = "wallet_key_source AS key_source, wallet_derivation_path AS derivation_path, \
+    wallet_account_index AS account_index, wallet_seed_id AS seed_id";
+
+const RETIRED_COLUMNS: &str = "id, user_id, address, private_key_encrypted, mnemonic_encrypted, key_source, \
+    derivation_path, reason, retired_at";
+
+impl WalletDerivation {
+    pub fn imported() -> Self {
+        Self { key_source: WalletKeySource::Imported, derivation_path: None, account_index: None, seed_id: None }
+    }
+
+    pub async fn find(pool: &PgPool, user_id: Uuid) -> Result<Option<Self>, AppError> {
+        let derivation = sqlx::query_as::<_, WalletDerivation>(&format!(
+            "SELECT {} FROM users WHERE id = $1",
+            DERIVATION_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(derivation)
+    }
+
+    /// Record the derivation of the user's current wallet
+    pub async fn save(&self, tx: &mut Transaction<'_, Postgres>, user_id: Uuid) -> Result<(), AppError> {
+        sqlx::query(
+            "UPDATE users SET wallet_key_source = $1, wallet_derivation_path = $2, wallet_account_index = $3, \
+             wallet_seed_id = $4, updated_at = NOW() WHERE id = $5",
+        )
+        .bind(self.key_source)
+        .bind(&self.derivation_path)
+        .bind(self.account_index)
+        .bind(&self.seed_id)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Wallets per key source, for the admin derivation report
+    pub async fn count_by_source(pool: &PgPool) -> Result<Vec<(WalletKeySource, i64)>, AppError> {
+        let counts = sqlx::query_as::<_, (WalletKeySource, i64)>(
+            "SELECT wallet_key_source, COUNT(*) FROM users GROUP BY wallet_key_source ORDER BY wallet_key_source",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(counts)
+    }
+}
+
+impl WalletChainAddress {
+    pub fn chain(&self) -> Option<&'static EvmChain> {
+        EvmChain::find(self.chain_id as u64)
+    }
+
+    pub async fn list_for_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<Self>, AppError> {
+        let addresses = sqlx::query_as::<_, WalletChainAddress>(
+            "SELECT chain_id, address, derivation_path, created_at FROM wallet_chain_addresses \
+             WHERE user_id = $1 ORDER BY chain_id",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(addresses)
+    }
+
+    /// Replace the user's addresses with `addresses`, as (chain id, address, path)
+    pub async fn replace_for_user(
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+        addresses: &[(u64, String, Option<String>)],
+    ) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM wallet_chain_addresses WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await?;
+
+        Self::insert_missing(tx, user_id, addresses).await?;
+
+        Ok(())
+    }
+
+    /// Add addresses for chains the user has none on yet; returns how many were added
+    pub async fn insert_missing(
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+        addresses: &[(u64, String, Option<String>)],
+    ) -> Result<u64, AppError> {
+        let chain_ids: Vec<i64> = addresses.iter().map(|(chain_id, _, _)| *chain_id as i64).collect();
+        let values: Vec<String> = addresses.iter().map(|(_, address, _)| address.clone()).collect();
+        let paths: Vec<Option<String>> = addresses.iter().map(|(_, _, path)| path.clone()).collect();
+
+        let result = sqlx::query(
+            "INSERT INTO wallet_chain_addresses (user_id, chain_id, address, derivation_path) \
+             SELECT $1, * FROM UNNEST($2::bigint[], $3::varchar[], $4::varchar[]) \
+             ON CONFLICT (user_id, chain_id) DO NOTHING",
+        )
+        .bind(user_id)
+        .bind(&chain_ids)
+        .bind(&values)
+        .bind(&paths)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Users missing an address on one of the registered chains, oldest first
+    pub async fn users_missing_chains(pool: &PgPool, limit: i64) -> Result<Vec<Uuid>, AppError> {
+        let chain_ids: Vec<i64> = EVM_CHAINS.iter().map(|chain| chain.chain_id as i64).collect();
+
+        let users = sqlx::query_scalar::<_, Uuid>(
+            "SELECT u.id FROM users u \
+             WHERE (SELECT COUNT(*) FROM wallet_chain_addresses a \
+                    WHERE a.user_id = u.id AND a.chain_id = ANY($1)) < cardinality($1) \
+             ORDER BY u.created_at, u.id LIMIT $2",
+        )
+        .bind(&chain_ids)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(users)
+    }
+}
+
+impl RetiredWallet {
+    /// Keep the user's current wallet key before it is replaced
+    pub async fn retire_current(
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+        reason: &str,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            "INSERT INTO retired_wallets (user_id, address, private_key_encrypted, mnemonic_encrypted, key_source, derivation_path, reason) \
+             SELECT id, internal_wallet_address, internal_wallet_private_key_encrypted, internal_wallet_mnemonic_encrypted, \
+                    wallet_key_source, wallet_derivation_path, $2 \
+             FROM users WHERE id = $1",
+        )
+        .bind(user_id)
+        .bind(reason)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_for_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<Self>, AppError> {
+        let wallets = sqlx::query_as::<_, RetiredWallet>(&format!(
+            "SELECT {} FROM retired_wallets WHERE user_id = $1 ORDER BY retired_at DESC",
+            RETIRED_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(wallets)
+    }
+
+    /// Store a key re-encrypted under a new wallet password
+    pub async fn update_encryption(
+        tx: &mut Transaction<'_, Postgres>,
+        id: Uuid,
+        private_key_encrypted: &str,
+        mnemonic_encrypted: Option<&str>,
+    ) -> Result<(), AppError> {
+        sqlx::query("UPDATE retired_wallets SET private_key_encrypted = $1, mnemonic_encrypted = $2 WHERE id = $3")
+            .bind(private_key_encrypted)
+            .bind(mnemonic_encrypted)
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+}
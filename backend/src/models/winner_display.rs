@@ -0,0 +1,237 @@
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::PrizeType;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::error::AppError;
+use crate::models::raffle_prize_tier::RafflePrizeTier;
+
+/// Shown for users who chose to stay anonymous, or who can no longer be found
+pub const ANONYMOUS_NAME: &str = "Anonymous";
+
+const MAX_NAME_LENGTH: usize = 50;
+
+/// How a user's name is shown wherever they appear publicly as a winner or buyer
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WinnerDisplay {
+    /// "Jane D."
+    FirstNameInitial,
+    /// "ja***e"
+    #[default]
+    MaskedUsername,
+    /// "Anonymous"
+    Anonymous,
+}
+
+/// A user's winner display choice and the names it draws on
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct WinnerDisplaySettings {
+    pub winner_display: WinnerDisplay,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateWinnerDisplayRequest {
+    pub winner_display: WinnerDisplay,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+}
+
+/// One winning place as shown to the public
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicWinner {
+    pub place: i32,
+    pub prize_type: PrizeType,
+    pub item_id: Option<Uuid>,
+    pub credit_amount: Option<Decimal>,
+    pub display_name: String,
+    pub awarded_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, FromRow)]
+struct DisplayNameRow {
+    id: Uuid,
+    username: String,
+    first_name: Option<String>,
+    last_name: Option<String>,
+    winner_display: WinnerDisplay,
+}
+
+impl WinnerDisplaySettings {
+    /// Get the user's winner display settings
+    pub async fn find(pool: &PgPool, user_id: Uuid) -> Result<Self, AppError> {
+        sqlx::query_as::<_, WinnerDisplaySettings>(
+            "SELECT winner_display, first_name, last_name FROM users WHERE id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+    }
+
+    /// Save the user's choice; showing a first name needs both names
+    pub async fn update(
+        pool: &PgPool,
+        user_id: Uuid,
+        request: UpdateWinnerDisplayRequest,
+    ) -> Result<Self, AppError> {
+        let first_name = normalize_name(request.first_name.as_deref(), "First name")?;
+        let last_name = normalize_name(request.last_name.as_deref(), "Last name")?;
+
+        if request.winner_display == WinnerDisplay::FirstNameInitial && (first_name.is_none() || last_name.is_none()) {
+            return Err(AppError::Validation(
+                "First and last name are required to show your first name".to_string(),
+            ));
+        }
+
+        sqlx::query_as::<_, WinnerDisplaySettings>(
+            r#"
+            UPDATE users
+            SET winner_display = $2, first_name = $3, last_name = $4, updated_at = NOW()
+            WHERE id = $1
+            RETURNING winner_display, first_name, last_name
+            "#,
+        )
+        .bind(user_id)
+        .bind(request.winner_display)
+        .bind(first_name)
+        .bind(last_name)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+    }
+}
+
+impl PublicWinner {
+    /// Awarded tiers with each winner shown by their own display rule
+    pub fn from_tiers(tiers: &[RafflePrizeTier], names: &HashMap<Uuid, String>) -> Vec<Self> {
+        tiers
+            .iter()
+            .filter_map(|tier| {
+                let winner_id = tier.winner_user_id?;
+                Some(Self {
+                    place: tier.place,
+                    prize_type: tier.prize_type,
+                    item_id: tier.item_id,
+                    credit_amount: tier.credit_amount,
+                    display_name: names.get(&winner_id).cloned().unwrap_or_else(|| ANONYMOUS_NAME.to_string()),
+                    awarded_at: tier.awarded_at,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Clear winner ids from tiers sent to the public; winners are listed by display name instead.
+/// How a winner chose to take their prize is theirs alone, so that is cleared too.
+pub fn redact_winner_ids(tiers: &mut [RafflePrizeTier]) {
+    for tier in tiers {
+        tier.winner_user_id = None;
+        tier.winner_choice = None;
+        tier.choice_made_at = None;
+    }
+}
+
+/// Public names of the given users, following each user's display rule
+pub async fn display_names(pool: &PgPool, user_ids: &[Uuid]) -> Result<HashMap<Uuid, String>, AppError> {
+    if user_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let rows = sqlx::query_as::<_, DisplayNameRow>(
+        "SELECT id, username, first_name, last_name, winner_display FROM users WHERE id = ANY($1)",
+    )
+    .bind(user_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let name = display_name(
+                row.winner_display,
+                &row.username,
+                row.first_name.as_deref(),
+                row.last_name.as_deref(),
+            );
+            (row.id, name)
+        })
+        .collect())
+}
+
+/// Apply a display rule; a missing first or last name falls back to the masked username
+pub fn display_name(
+    rule: WinnerDisplay,
+    username: &str,
+    first_name: Option<&str>,
+    last_name: Option<&str>,
+) -> String {
+    match rule {
+        WinnerDisplay::Anonymous => ANONYMOUS_NAME.to_string(),
+        WinnerDisplay::FirstNameInitial => match (first_name, last_name.and_then(|l| l.chars().next())) {
+            (Some(first), Some(initial)) => format!("{} {}.", first, initial.to_uppercase()),
+            _ => mask_username(username),
+        },
+        WinnerDisplay::MaskedUsername => mask_username(username),
+    }
+}
+
+/// Keep the first two and last characters of a username; shorter names keep only the first
+pub fn mask_username(username: &str) -> String {
+    let chars: Vec<char> = username.chars().collect();
+    match chars.len() {
+        0 => ANONYMOUS_NAME.to_string(),
+        1..=4 => format!("{}***", chars[0]),
+        len => format!("{}{}***{}", chars[0], chars[1], chars[len - 1]),
+    }
+}
+
+fn normalize_name(name: Option<&str>, field: &str) -> Result<Option<String>, AppError> {
+    let Some(name) = name.map(str::trim).filter(|n| !n.is_empty()) else {
+        return Ok(None);
+    };
+
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(AppError::Validation(format!("{} must be at most {} characters", field, MAX_NAME_LENGTH)));
+    }
+
+    Ok(Some(name.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_username() {
+        assert_eq!(mask_username("janedoe"), "ja***e");
+        assert_eq!(mask_username("joey"), "j***");
+        assert_eq!(mask_username("x"), "x***");
+        assert_eq!(mask_username(""), ANONYMOUS_NAME);
+    }
+
+    #[test]
+    fn test_display_name() {
+        assert_eq!(
+            display_name(WinnerDisplay::FirstNameInitial, "janedoe", Some("Jane"), Some("doe")),
+            "Jane D."
+        );
+        assert_eq!(display_name(WinnerDisplay::FirstNameInitial, "janedoe", Some("Jane"), None), "ja***e");
+        assert_eq!(display_name(WinnerDisplay::MaskedUsername, "janedoe", Some("Jane"), Some("Doe")), "ja***e");
+        assert_eq!(display_name(WinnerDisplay::Anonymous, "janedoe", Some("Jane"), Some("Doe")), ANONYMOUS_NAME);
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name(Some("  Jane "), "First name").unwrap(), Some("Jane".to_string()));
+        assert_eq!(normalize_name(Some("   "), "First name").unwrap(), None);
+        assert_eq!(normalize_name(None, "First name").unwrap(), None);
+        assert!(normalize_name(Some(&"a".repeat(51)), "First name").is_err());
+    }
+}
@@ -0,0 +1,988 @@
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{
+    Components, ContentBuilder, HeaderBuilder, ObjectBuilder, Ref, RefOr, Response,
+    ResponseBuilder, SchemaType,
+};
+use utoipa::{Modify, OpenApi};
+
+use crate::error::{ErrorResponse, FieldError};
+use crate::handlers;
+
+/// OpenAPI document for the REST API, served at `/api/docs/openapi.json` with Swagger UI at `/api/docs`
+///
+/// Operations come from the `#[utoipa::path]` annotations on each handler, so a new route is
+/// documented by annotating its handler and listing it here.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Unit Shopping Platform API",
+        description = "REST API for the Unit Shopping Platform. User endpoints take a bearer access token from `/api/v1/auth/login`; partner endpoints take an `X-Api-Key` header."
+    ),
+    paths(
+        handlers::health::readiness_check,
+        handlers::health::metrics,
+        handlers::health::health_check,
+
+        handlers::auth::register,
+        handlers::auth::login,
+        handlers::auth::refresh_token,
+        handlers::auth::forgot_password,
+        handlers::auth::reset_password,
+        handlers::auth::verify_email,
+        handlers::auth::resend_verification,
+        handlers::auth::logout,
+        handlers::auth::get_current_user,
+        handlers::auth::update_current_user,
+        handlers::auth::change_password,
+        handlers::auth::change_email,
+        handlers::auth::complete_step_up,
+        handlers::auth::delete_current_user,
+
+        handlers::wallet::get_wallet_address,
+        handlers::wallet::sign_message,
+        handlers::wallet::verify_signature,
+        handlers::wallet::export_private_key,
+        handlers::wallet::import_private_key,
+        handlers::wallet::get_chain_addresses,
+        handlers::wallet::upgrade_wallet,
+        handlers::wallet::rotate_wallet_encryption,
+        handlers::wallet::get_wallet_balance,
+        handlers::wallet::create_external_wallet_challenge,
+        handlers::wallet::link_external_wallet,
+        handlers::wallet::list_external_wallets,
+        handlers::wallet::unlink_external_wallet,
+        handlers::wallet::list_external_purchases,
+        handlers::wallet::renew_external_purchase_voucher,
+
+        handlers::credits::get_credit_balance,
+        handlers::credits::reconcile_credit_balance,
+        handlers::credits::get_credit_history,
+        handlers::credits::get_expiring_credits,
+        handlers::credits::redeem_credits,
+        handlers::credits::check_sufficient_credits,
+        handlers::credits::get_free_items,
+        handlers::credits::redeem_free_item,
+        handlers::credits::issue_credits,
+        handlers::credits::issue_bonus_credits,
+        handlers::credits::get_credit_statistics,
+        handlers::credits::get_users_with_expiring_credits,
+        handlers::credits::cleanup_expired_credits,
+        handlers::credits::get_user_credit_balance,
+        handlers::credits::credit_service_health,
+
+        handlers::payments::create_payment_intent,
+        handlers::payments::confirm_payment_intent,
+        handlers::payments::get_payment_intent_status,
+        handlers::payments::get_payment_history,
+        handlers::payments::get_payment_statistics,
+        handlers::payments::create_subscription,
+        handlers::payments::get_user_subscriptions,
+        handlers::payments::cancel_subscription,
+        handlers::payments::get_payment_analytics,
+        handlers::payments::search_payments,
+        handlers::payments::export_payment_search,
+        handlers::payments::get_user_payment_details,
+        handlers::payments::process_refund,
+        handlers::payments::get_payment_refunds,
+        handlers::payments::payment_service_health,
+        handlers::payments::stripe_webhook,
+
+        handlers::tags::get_item_tags,
+        handlers::tags::set_item_tags,
+        handlers::tags::get_raffle_tags,
+        handlers::tags::list_tags,
+        handlers::tags::get_trending_tags,
+        handlers::tags::list_collections,
+        handlers::tags::get_collection_page,
+        handlers::tags::create_tag,
+        handlers::tags::update_tag,
+        handlers::tags::delete_tag,
+        handlers::tags::list_synonyms,
+        handlers::tags::add_synonym,
+        handlers::tags::remove_synonym,
+        handlers::tags::list_all_collections,
+        handlers::tags::create_collection,
+        handlers::tags::update_collection,
+        handlers::tags::delete_collection,
+
+        handlers::categories::list_categories,
+        handlers::categories::get_category,
+        handlers::categories::get_item_category,
+        handlers::categories::set_item_category,
+        handlers::categories::list_all_categories,
+        handlers::categories::create_category,
+        handlers::categories::update_category,
+        handlers::categories::delete_category,
+        handlers::categories::list_attributes,
+        handlers::categories::create_attribute,
+        handlers::categories::update_attribute,
+        handlers::categories::delete_attribute,
+
+        handlers::listing_snapshots::get_item_snapshot,
+        handlers::listing_snapshots::get_item_snapshot_history,
+        handlers::listing_snapshots::get_raffle_snapshot,
+        handlers::listing_snapshots::get_raffle_snapshot_history,
+        handlers::listing_snapshots::get_purchase_snapshots,
+
+        handlers::raffles::search_raffles,
+        handlers::raffles::get_active_raffles,
+        handlers::raffles::get_featured_raffles,
+        handlers::raffles::get_raffle,
+        handlers::raffles::get_grid_state,
+        handlers::raffles::get_grid_changes,
+        handlers::raffles::get_raffle_odds,
+        handlers::raffles::get_raffle_winners,
+        handlers::raffles::buy_boxes,
+        handlers::raffles::join_waitlist,
+        handlers::raffles::get_waitlist_position,
+        handlers::raffles::leave_waitlist,
+        handlers::raffles::get_user_purchases,
+        handlers::raffles::get_purchase_receipt,
+        handlers::raffles::verify_receipt,
+        handlers::raffles::get_prize_choices,
+        handlers::raffles::choose_prize,
+        handlers::raffles::get_user_purchase_history,
+        handlers::raffles::create_raffle,
+        handlers::raffles::get_raffle_policy,
+        handlers::raffles::preview_raffle_fees,
+        handlers::raffles::cancel_raffle,
+        handlers::raffles::get_raffle_statistics,
+        handlers::raffles::get_all_raffles_admin,
+        handlers::raffles::set_raffle_tags,
+        handlers::raffles::force_complete_raffle,
+        handlers::raffles::raffle_service_health,
+        handlers::raffles::get_winner_gallery,
+
+        handlers::comments::get_raffle_comments,
+        handlers::comments::post_comment,
+        handlers::comments::delete_comment,
+        handlers::comments::highlight_answer,
+        handlers::comments::flag_comment,
+        handlers::comments::get_moderation_queue,
+        handlers::comments::moderate_comment,
+
+        handlers::analytics::start_session,
+        handlers::analytics::track_event,
+        handlers::analytics::get_raffle_funnel,
+
+        handlers::api_usage::get_usage,
+        handlers::api_usage::list_my_charges,
+        handlers::api_usage::list_plans,
+        handlers::api_usage::create_plan,
+        handlers::api_usage::update_plan,
+        handlers::api_usage::assign_plan,
+        handlers::api_usage::get_seller_usage,
+        handlers::api_usage::list_charges,
+        handlers::api_usage::run_billing,
+
+        handlers::seller_webhooks::list_endpoints,
+        handlers::seller_webhooks::create_endpoint,
+        handlers::seller_webhooks::update_endpoint,
+        handlers::seller_webhooks::delete_endpoint,
+        handlers::seller_webhooks::list_endpoint_deliveries,
+        handlers::seller_webhooks::list_deliveries,
+        handlers::seller_webhooks::get_delivery,
+        handlers::seller_webhooks::redeliver,
+        handlers::seller_webhooks::list_platform_endpoints,
+        handlers::seller_webhooks::create_platform_endpoint,
+        handlers::seller_webhooks::update_platform_endpoint,
+        handlers::seller_webhooks::delete_platform_endpoint,
+
+        handlers::promotions::get_active_promotions,
+        handlers::promotions::list_promotions,
+        handlers::promotions::create_promotion,
+        handlers::promotions::cancel_promotion,
+        handlers::promotions::get_promotion_report,
+
+        handlers::currency::get_rates,
+        handlers::currency::get_display_currency,
+        handlers::currency::set_display_currency,
+        handlers::currency::refresh_rates,
+
+        handlers::shipping::get_shipping_policy,
+        handlers::shipping::update_shipping_policy,
+        handlers::shipping::get_item_shipping,
+        handlers::shipping::update_item_shipping,
+        handlers::shipping::delete_item_shipping,
+        handlers::shipping::get_shipping_address,
+        handlers::shipping::update_shipping_address,
+        handlers::shipping::get_my_prize_shipping,
+
+        handlers::fulfillments::get_my_fulfillments,
+        handlers::fulfillments::confirm_shipping_details,
+        handlers::fulfillments::get_seller_fulfillments,
+        handlers::fulfillments::get_fulfillment,
+        handlers::fulfillments::ship_prize,
+        handlers::fulfillments::confirm_receipt,
+        handlers::fulfillments::open_dispute,
+        handlers::fulfillments::get_dispute_queue,
+        handlers::fulfillments::resolve_dispute,
+        handlers::sellers::get_onboarding,
+        handlers::sellers::update_profile,
+        handlers::sellers::connect_payout,
+        handlers::sellers::select_subscription,
+        handlers::sellers::set_kyc_status,
+        handlers::referrals::get_my_referrals,
+        handlers::referrals::attach_referral,
+        handlers::referrals::list_referrals,
+        handlers::referrals::get_referral_stats,
+        handlers::referrals::get_referral_policy,
+        handlers::referrals::update_referral_policy,
+        handlers::search::search,
+
+        handlers::tax::get_my_tax_forms,
+        handlers::tax::submit_tax_form,
+        handlers::tax::get_tax_review_queue,
+        handlers::tax::review_tax_form,
+        handlers::tax::get_tax_jurisdictions,
+        handlers::tax::update_tax_jurisdiction,
+        handlers::tax::export_tax_filings,
+        handlers::tax::get_fulfillment_status,
+
+        handlers::notifications::list_notifications,
+        handlers::notifications::get_unread_count,
+        handlers::notifications::mark_notification_read,
+        handlers::notifications::mark_all_notifications_read,
+
+        handlers::users::get_pending_actions,
+        handlers::users::get_winner_display,
+        handlers::users::update_winner_display,
+        handlers::users::get_notification_preferences,
+        handlers::users::update_notification_preferences,
+        handlers::users::list_push_devices,
+        handlers::users::register_push_device,
+        handlers::users::remove_push_device,
+        handlers::users::list_blocks,
+        handlers::users::block_user,
+        handlers::users::unblock_user,
+        handlers::users::list_favorites,
+        handlers::users::add_favorite,
+        handlers::users::remove_favorite_raffle,
+        handlers::users::remove_favorite_item,
+        handlers::users::list_saved_searches,
+        handlers::users::create_saved_search,
+        handlers::users::update_saved_search,
+        handlers::users::delete_saved_search,
+        handlers::users::get_kyc_status,
+        handlers::users::start_kyc_verification,
+
+        handlers::reports::export_accounting,
+        handlers::reports::export_ledger,
+        handlers::reports::get_ledger_summary,
+        handlers::reports::get_chart_of_accounts,
+        handlers::reports::update_account_mapping,
+        handlers::reports::get_seller_statement,
+
+        handlers::abuse_reports::create_report,
+        handlers::abuse_reports::get_my_reports,
+        handlers::abuse_reports::get_triage_queue,
+        handlers::abuse_reports::start_review,
+        handlers::abuse_reports::resolve_report,
+        handlers::abuse_reports::dismiss_report,
+        handlers::moderation::get_moderation_queue,
+        handlers::moderation::get_moderation_entry,
+        handlers::moderation::approve_item,
+        handlers::moderation::reject_item,
+
+        handlers::admin::get_effective_config,
+        handlers::admin::list_account_deletions,
+        handlers::admin::cancel_account_deletion,
+        handlers::admin::get_cleanup_status,
+        handlers::admin::run_cleanup,
+        handlers::admin::get_wallet_derivation_report,
+        handlers::admin::backfill_wallet_chain_addresses,
+        handlers::admin::get_wallet_encryption_status,
+        handlers::admin::rewrap_wallet_data_keys,
+        handlers::admin::get_notification_fan_outs,
+        handlers::admin::get_raffle_grid_limits,
+        handlers::admin::update_raffle_grid_limits,
+        handlers::admin::get_raffle_policy,
+        handlers::admin::update_raffle_policy,
+        handlers::admin::set_tier_raffle_policy,
+        handlers::admin::remove_tier_raffle_policy,
+        handlers::admin::get_geo_velocity_policy,
+        handlers::admin::update_geo_velocity_policy,
+        handlers::admin::list_login_lockouts,
+        handlers::admin::clear_login_lockout,
+        handlers::admin::get_login_lockout_policy,
+        handlers::admin::update_login_lockout_policy,
+        handlers::admin::get_geo_restrictions,
+        handlers::admin::update_geo_restrictions,
+        handlers::admin::set_raffle_geo_restrictions,
+        handlers::admin::get_rate_limits,
+        handlers::admin::update_rate_limit,
+        handlers::admin::get_email_domain_policy,
+        handlers::admin::check_email_domain,
+        handlers::admin::refresh_disposable_domains,
+        handlers::admin::set_email_domain_rule,
+        handlers::admin::delete_email_domain_rule,
+        handlers::admin::list_email_messages,
+        handlers::admin::list_email_suppressions,
+        handlers::admin::add_email_suppression,
+        handlers::admin::remove_email_suppression,
+        handlers::admin::list_backup_verifications,
+        handlers::admin::start_backup_verification,
+        handlers::admin::get_backup_verification,
+        handlers::admin::list_websocket_connections,
+        handlers::admin::disconnect_websocket_connection,
+        handlers::admin::disconnect_websocket_user,
+        handlers::admin::broadcast_websocket_message,
+        handlers::admin::list_api_keys,
+        handlers::admin::create_api_key,
+        handlers::admin::revoke_api_key,
+        handlers::admin::list_blockchain_dead_letters,
+        handlers::admin::retry_blockchain_dead_letter,
+        handlers::admin::list_blockchain_nonces,
+        handlers::admin::cancel_blockchain_transaction,
+        handlers::admin::speed_up_blockchain_transaction,
+        handlers::admin::update_user_role,
+        handlers::admin::ban_user,
+        handlers::admin::unban_user,
+        handlers::admin::get_role_permissions,
+        handlers::admin::set_role_permissions,
+        handlers::admin::list_settings,
+        handlers::admin::get_setting,
+        handlers::admin::update_setting,
+        handlers::admin::list_feature_flags,
+        handlers::admin::create_feature_flag,
+        handlers::admin::get_feature_flag,
+        handlers::admin::update_feature_flag,
+        handlers::admin::delete_feature_flag,
+        handlers::features::get_features,
+
+        handlers::fees::get_fee_schedules,
+        handlers::fees::create_fee_schedule,
+
+        handlers::webhooks::list_webhook_deliveries,
+        handlers::webhooks::get_webhook_delivery,
+        handlers::webhooks::redeliver_webhook,
+        handlers::webhooks::get_webhook_retry_policy,
+        handlers::webhooks::update_webhook_retry_policy,
+        handlers::webhooks::stripe_webhook,
+        handlers::webhooks::blockchain_webhook,
+        handlers::webhooks::identity_webhook,
+        handlers::webhooks::notification_webhook,
+        handlers::webhooks::ses_email_webhook,
+
+        handlers::data_fixes::resync_raffle_from_chain,
+        handlers::data_fixes::recount_boxes_sold,
+        handlers::data_fixes::detach_orphaned_purchases,
+        handlers::data_fixes::rebuild_credit_balance,
+        handlers::data_fixes::get_reconciliation_report,
+        handlers::data_fixes::start_reconciliation,
+        handlers::ledger::get_account_balances,
+        handlers::ledger::get_account_postings,
+        handlers::ledger::get_entry,
+        handlers::ledger::get_latest_invariant_run,
+        handlers::ledger::check_invariants,
+        handlers::data_fixes::diagnose_raffle,
+        handlers::data_fixes::rerequest_randomness,
+        handlers::data_fixes::resync_stuck_raffle,
+        handlers::data_fixes::force_complete_stuck_raffle,
+        handlers::data_fixes::list_scheduled_draws,
+        handlers::data_fixes::retry_scheduled_draw,
+
+        handlers::credit_holds::place_credit_hold,
+        handlers::credit_holds::get_credit_hold,
+        handlers::credit_holds::capture_credit_hold,
+        handlers::credit_holds::release_credit_hold,
+        handlers::credit_holds::void_credit_hold,
+
+        handlers::verification::verify_raffle,
+        handlers::verification::replay_draw,
+
+        handlers::sandbox::list_test_keys,
+        handlers::sandbox::create_test_key,
+        handlers::sandbox::revoke_test_key,
+        handlers::sandbox::purge,
+        handlers::sandbox::list_items,
+        handlers::sandbox::create_item,
+        handlers::sandbox::list_raffles,
+        handlers::sandbox::create_raffle,
+        handlers::sandbox::get_raffle,
+        handlers::sandbox::list_purchases,
+        handlers::sandbox::simulate_purchase,
+
+        handlers::websocket::websocket_handler,
+        handlers::websocket::websocket_stats,
+    ),
+    components(schemas(
+        ErrorResponse,
+        FieldError,
+        raffle_platform_shared::CreateUserRequest,
+        handlers::auth::RegistrationResponse,
+        handlers::auth::TokenResponse,
+        handlers::auth::UserProfileResponse,
+        raffle_platform_shared::UserRole,
+        raffle_platform_shared::LoginRequest,
+        handlers::auth::LoginResponse,
+        handlers::auth::SessionInfo,
+        handlers::auth::RefreshTokenRequest,
+        raffle_platform_shared::AuthResponse,
+        raffle_platform_shared::UserResponse,
+        handlers::auth::ResetPasswordRequest,
+        handlers::auth::ConfirmPasswordResetRequest,
+        handlers::auth::EmailVerificationRequest,
+        handlers::auth::ResendVerificationRequest,
+        handlers::auth::LogoutRequest,
+        handlers::auth::UpdateProfileRequest,
+        handlers::auth::ChangePasswordRequest,
+        handlers::auth::ChangeEmailRequest,
+        handlers::auth::DeleteAccountRequest,
+        handlers::wallet::SignMessageRequest,
+        handlers::wallet::SignatureResponse,
+        handlers::wallet::UpgradeWalletRequest,
+        services::wallet_service::WalletUpgrade,
+        models::user_wallet::WalletDerivation,
+        models::user_wallet::WalletKeySource,
+        models::ExternalWallet,
+        services::external_wallet_service::LinkChallengeRequest,
+        services::external_wallet_service::LinkChallenge,
+        services::external_wallet_service::LinkWalletRequest,
+        services::external_wallet_service::ExternalPurchaseStatus,
+        services::external_wallet_service::ExternalPurchase,
+        services::external_wallet_service::UnsignedTransaction,
+        services::external_wallet_service::PreparedExternalPurchase,
+        handlers::credits::CreditBalanceResponse,
+        raffle_platform_shared::CreditResponse,
+        raffle_platform_shared::CreditSource,
+        raffle_platform_shared::CreditType,
+        services::credit_service::CreditBalance,
+        services::credit_service::BalanceReconciliation,
+        handlers::credits::ExpiringCreditsResponse,
+        handlers::credits::RedeemCreditsRequest,
+        handlers::credits::CreditRedemptionResponse,
+        handlers::credits::FreeItemsResponse,
+        handlers::credits::RedeemFreeItemRequest,
+        handlers::credits::ReconcileBalanceRequest,
+        handlers::credits::IssueCreditsRequest,
+        handlers::credits::IssueBonusCreditsRequest,
+        services::credit_service::CreditStatistics,
+        services::credit_service::ExpirationNotification,
+        models::credit::UserCredit,
+        handlers::payments::CreatePaymentIntentRequest,
+        handlers::payments::PaymentIntentCreatedResponse,
+        handlers::payments::ConfirmPaymentIntentRequest,
+        services::payment_service::PaymentIntentResponse,
+        handlers::payments::CreateSubscriptionRequest,
+        handlers::payments::SubscriptionCreatedResponse,
+        services::payment_service::SubscriptionRecord,
+        services::payment_search_service::PaymentSearchSource,
+        handlers::payments::ProcessRefundRequest,
+        services::payment_service::RefundDestination,
+        services::payment_service::WalletType,
+        handlers::tags::SetTagsRequest,
+        raffle_platform_shared::RaffleResponse,
+        raffle_platform_shared::ItemResponse,
+        raffle_platform_shared::DisplayAmount,
+        raffle_platform_shared::ImageVariants,
+        raffle_platform_shared::ItemStatus,
+        raffle_platform_shared::RaffleStatus,
+        raffle_platform_shared::RaffleDeadlineAction,
+        raffle_platform_shared::ShippingEstimate,
+        handlers::raffles::GridStateResponse,
+        services::raffle_service::GridChanges,
+        services::raffle_service::GridDiff,
+        services::realtime_service::GridBoxChange,
+        services::raffle_service::BoxPricing,
+        raffle_platform_shared::GridCell,
+        handlers::raffles::BoxOwnerInfo,
+        models::raffle_prize_tier::RafflePrizeTier,
+        raffle_platform_shared::PrizeType,
+        models::raffle_prize_tier::PrizeChoice,
+        services::raffle_service::RaffleOdds,
+        services::tag_service::RaffleTags,
+        services::comment_service::CommentThread,
+        services::comment_service::RaffleComment,
+        handlers::raffles::BuyBoxRequestHandler,
+        services::waitlist_service::WaitlistPosition,
+        models::raffle_waitlist::WaitlistReason,
+        models::raffle_waitlist::WaitlistStatus,
+        handlers::raffles::BoxPurchaseSuccessResponse,
+        raffle_platform_shared::BoxPurchaseResponse,
+        services::receipt_service::PurchaseReceipt,
+        services::receipt_service::ReceiptVerification,
+        handlers::raffles::PrizeChoiceRequest,
+        services::comment_service::PostCommentRequest,
+        handlers::raffles::CreateRaffleRequestHandler,
+        raffle_platform_shared::RaffleLayoutDefinition,
+        raffle_platform_shared::PricingCurveDefinition,
+        raffle_platform_shared::PricingCurveBasis,
+        raffle_platform_shared::PricingTierDefinition,
+        raffle_platform_shared::PrizeTierDefinition,
+        handlers::raffles::RaffleCreatedResponse,
+        services::fee_service::FeeBreakdown,
+        services::fee_service::FeeLine,
+        services::fee_service::FeeType,
+        handlers::raffles::CancelRaffleRequest,
+        services::raffle_service::RaffleStatistics,
+        handlers::raffles::SetRaffleTagsRequest,
+        services::comment_service::FlagCommentRequest,
+        services::comment_service::FlagReason,
+        handlers::analytics::StartSessionRequest,
+        services::analytics_service::AnonymousSession,
+        services::analytics_service::TrackEventRequest,
+        services::analytics_service::RaffleFunnel,
+        services::api_billing_service::ApiUsageSummary,
+        models::api_usage::ApiKeyUsage,
+        models::api_usage::ApiRatePlan,
+        models::api_usage::OverageCharge,
+        models::api_usage::ApiUsageCharge,
+        services::seller_webhook_service::WebhookEndpoint,
+        services::seller_webhook_service::CreateWebhookEndpointRequest,
+        services::seller_webhook_service::SellerWebhookEvent,
+        services::seller_webhook_service::UpdateWebhookEndpointRequest,
+        services::seller_webhook_service::EndpointDelivery,
+        services::seller_webhook_service::EndpointDeliveryStatus,
+        services::raffle_service::WinnerGalleryEntry,
+        models::tag::Tag,
+        services::tag_service::TrendingTag,
+        models::tag::TagCollection,
+        handlers::tags::CollectionPage,
+        models::promotion::Promotion,
+        models::promotion::PromotionKind,
+        models::promotion::PromotionStatus,
+        handlers::currency::DisplayCurrencyRequest,
+        services::shipping_service::SellerShippingPolicy,
+        services::shipping_service::ShippingZone,
+        services::shipping_service::ShippingRate,
+        services::shipping_service::UpdateShippingPolicyRequest,
+        services::shipping_service::ItemShippingOverride,
+        services::shipping_service::UpdateItemShippingRequest,
+        services::shipping_service::ShippingAddress,
+        services::shipping_service::UpdateShippingAddressRequest,
+        services::shipping_service::ShippingFulfillmentCheck,
+        services::fulfillment_service::FulfillmentStatus,
+        services::fulfillment_service::Fulfillment,
+        services::fulfillment_service::FulfillmentDetails,
+        services::fulfillment_service::DisputeReason,
+        services::fulfillment_service::DisputeStatus,
+        services::fulfillment_service::DisputeDecision,
+        services::fulfillment_service::Dispute,
+        services::fulfillment_service::ShipPrizeRequest,
+        services::fulfillment_service::OpenDisputeRequest,
+        services::fulfillment_service::ResolveDisputeRequest,
+        models::seller_onboarding::OnboardingStep,
+        models::seller_onboarding::SellerFeature,
+        models::seller_onboarding::OnboardingStepStatus,
+        models::seller_onboarding::SellerOnboarding,
+        services::seller_onboarding_service::UpdateSellerProfileRequest,
+        services::seller_onboarding_service::ConnectPayoutRequest,
+        services::seller_onboarding_service::SelectSubscriptionRequest,
+        services::seller_onboarding_service::SetKycStatusRequest,
+        services::compliance_service::KycStatus,
+        services::compliance_service::KycOverview,
+        services::compliance_service::IdentityVerification,
+        services::compliance_service::VerificationPurpose,
+        services::compliance_service::VerificationStatus,
+        services::compliance_service::StartVerificationRequest,
+        services::referral_service::ReferralStatus,
+        services::referral_service::RejectionReason,
+        services::referral_service::Referral,
+        services::referral_service::ReferralPolicy,
+        services::referral_service::AttachReferralRequest,
+        services::referral_service::UserReferralStats,
+        services::referral_service::TopReferrer,
+        services::referral_service::ReferralProgramStats,
+        services::search_service::SearchType,
+        services::search_service::ItemSearchHit,
+        services::search_service::RaffleSearchHit,
+        services::search_service::SellerSearchHit,
+        services::search_service::ItemSearchGroup,
+        services::search_service::RaffleSearchGroup,
+        services::search_service::SellerSearchGroup,
+        services::search_service::UnifiedSearchResults,
+        services::tax_service::WinnerTaxForm,
+        services::tax_service::TaxFormStatus,
+        services::tax_service::TaxInfoSubmission,
+        models::winner_display::WinnerDisplaySettings,
+        models::winner_display::WinnerDisplay,
+        models::winner_display::UpdateWinnerDisplayRequest,
+        models::notification_preferences::NotificationPreferences,
+        models::notification_preferences::UpdateNotificationPreferencesRequest,
+        models::notification_preferences::PushDevice,
+        models::notification_preferences::PushPlatform,
+        models::notification_preferences::RegisterPushDeviceRequest,
+        models::user_block::UserBlock,
+        models::user_block::BlockKind,
+        models::user_block::BlockUserRequest,
+        models::favorite::Favorites,
+        models::favorite::FavoriteRaffle,
+        models::favorite::FavoriteItem,
+        models::favorite::AddFavoriteRequest,
+        models::saved_search::SavedSearch,
+        models::saved_search::SavedSearchFilter,
+        models::saved_search::SavedSearchRequest,
+        services::reporting_service::AccountingExportFormat,
+        services::reporting_service::AccountMapping,
+        raffle_platform_shared::TransactionType,
+        services::reporting_service::AccountMappingUpdate,
+        services::reporting_service::LedgerCategory,
+        services::reporting_service::LedgerFormat,
+        services::reporting_service::LedgerLine,
+        services::reporting_service::LedgerSummary,
+        services::reporting_service::LedgerTotal,
+        services::reporting_service::SellerStatement,
+        services::reporting_service::SellerStatementLine,
+        services::abuse_report_service::CreateReportRequest,
+        services::abuse_report_service::ReportCategory,
+        services::abuse_report_service::ReportTargetType,
+        services::abuse_report_service::AbuseReport,
+        services::abuse_report_service::ResolutionAction,
+        services::abuse_report_service::ReportStatus,
+        services::account_deletion_service::DeletionStatus,
+        services::account_deletion_service::AccountDeletionRequest,
+        services::cleanup_service::CleanupMetrics,
+        services::cleanup_service::CleanupReport,
+        services::cleanup_service::CleanupTargetResult,
+        services::cleanup_service::CleanupTarget,
+        services::wallet_service::WalletDerivationReport,
+        services::wallet_service::KeySourceCount,
+        utils::hd_wallet::EvmChain,
+        services::wallet_service::ChainAddressBackfill,
+        services::wallet_service::WalletEncryptionStatus,
+        services::wallet_service::MasterKeyCount,
+        services::wallet_service::DataKeyRewrap,
+        services::raffle_service::GridLimits,
+        services::raffle_service::RafflePolicyOverview,
+        models::raffle_policy::RafflePolicy,
+        services::geo_velocity_service::GeoVelocityPolicy,
+        services::geo_velocity_service::CompleteStepUpRequest,
+        services::geo_velocity_service::ChallengeStatus,
+        services::geo_velocity_service::StepUpChallenge,
+        services::auth_service::login_guard::LockoutPolicy,
+        services::auth_service::login_guard::LockoutScope,
+        services::auth_service::login_guard::LoginLockout,
+        services::geo_compliance_service::GeoRestrictionPolicy,
+        services::geo_compliance_service::CountryRules,
+        models::raffle_policy::RafflePolicyOverrides,
+        models::raffle_policy::TierRafflePolicy,
+        middleware::rate_limit::RouteBudgetStatus,
+        middleware::rate_limit::RateLimitBudget,
+        services::fee_service::FeeSchedule,
+        services::fee_service::FeeScope,
+        services::fee_service::NewFeeSchedule,
+        services::comment_service::ModerationQueueEntry,
+        services::comment_service::ModerateCommentRequest,
+        services::comment_service::ModerationAction,
+        services::email_domain_service::DomainPolicySummary,
+        services::email_domain_service::EmailDomainRule,
+        services::email_domain_service::DomainRule,
+        services::email_domain_service::DomainCheck,
+        services::email_domain_service::DomainDecisionReason,
+        services::email_domain_service::SetDomainRuleRequest,
+        services::email_service::EmailTemplate,
+        services::email_service::EmailStatus,
+        services::email_service::EmailMessage,
+        services::email_service::EmailSuppression,
+        services::email_service::SuppressionReason,
+        services::email_service::AddSuppressionRequest,
+        services::backup_verification_service::BackupVerificationRun,
+        services::backup_verification_service::IntegrityCheck,
+        services::backup_verification_service::DrillStatus,
+        services::realtime_service::ConnectionDetails,
+        services::realtime_service::DisconnectRequest,
+        services::realtime_service::ChannelBroadcastRequest,
+        services::abuse_report_service::TriageEntry,
+        services::abuse_report_service::ResolveReportRequest,
+        services::abuse_report_service::DismissReportRequest,
+        services::tax_service::ReviewTaxFormRequest,
+        services::tax_service::TaxFormDecision,
+        services::tax_service::TaxJurisdiction,
+        services::tax_service::ThresholdPeriod,
+        services::tax_service::TaxJurisdictionUpdate,
+        services::webhook_service::WebhookStatus,
+        services::webhook_service::WebhookSource,
+        services::webhook_service::WebhookDeliverySummary,
+        services::webhook_service::WebhookDelivery,
+        services::webhook_service::WebhookRetryPolicy,
+        models::partner_api_key::PartnerApiKey,
+        handlers::admin::CreateApiKeyRequest,
+        models::api_usage::ApiRatePlanRequest,
+        handlers::api_usage::AssignPlanRequest,
+        services::seller_webhook_service::CreatePlatformWebhookEndpointRequest,
+        services::seller_webhook_service::CreatedWebhookEndpoint,
+        services::seller_webhook_service::UpdatePlatformWebhookEndpointRequest,
+        services::data_fix_service::DataFixRequest,
+        services::data_fix_service::DataFixPlan,
+        services::data_fix_service::DataFixOperation,
+        services::data_fix_service::FieldChange,
+        services::chain_reconciliation_service::ReconciliationReport,
+        services::chain_reconciliation_service::ReconciliationFinding,
+        services::chain_reconciliation_service::DivergenceKind,
+        services::chain_reconciliation_service::ReconciliationRun,
+        services::chain_reconciliation_service::ReconciliationStatus,
+        services::chain_reconciliation_service::StartReconciliationRequest,
+        services::chain_reconciliation_service::Divergence,
+        models::ledger::JournalEntry,
+        models::ledger::LedgerAccountType,
+        models::ledger::LedgerPosting,
+        services::ledger_service::JournalEntryDetail,
+        services::ledger_service::LedgerAccountBalance,
+        services::ledger_service::LedgerInvariantReport,
+        services::ledger_service::LedgerInvariantRun,
+        services::ledger_service::LedgerPostingDetail,
+        services::ledger_service::LedgerPostingPage,
+        services::ledger_service::UserCreditMismatch,
+        services::raffle_runbook_service::RaffleDiagnosis,
+        services::raffle_runbook_service::StuckSymptom,
+        services::raffle_runbook_service::RemediationAction,
+        services::raffle_runbook_service::ChainState,
+        services::raffle_runbook_service::RaffleChainEvent,
+        services::raffle_runbook_service::EventProcessorState,
+        services::raffle_runbook_service::RaffleRemediation,
+        services::raffle_runbook_service::RemediationTransaction,
+        services::raffle_runbook_service::RemediationPlan,
+        services::draw_scheduler_service::ScheduledDraw,
+        models::promotion::NewPromotion,
+        services::promotion_service::PromotionReport,
+        services::promotion_service::PromotionWindowMetrics,
+        services::promotion_service::PromotionUplift,
+        models::tag::CreateTagRequest,
+        models::tag::UpdateTagRequest,
+        models::tag::TagSynonym,
+        handlers::tags::AddSynonymRequest,
+        models::tag::CreateTagCollectionRequest,
+        models::tag::UpdateTagCollectionRequest,
+        models::category::Category,
+        models::category::CategoryNode,
+        models::category::CategoryAttribute,
+        models::category::AttributeType,
+        models::category::CreateCategoryRequest,
+        models::category::UpdateCategoryRequest,
+        models::category::CreateAttributeRequest,
+        models::category::UpdateAttributeRequest,
+        services::category_service::CategoryDetail,
+        services::category_service::ItemCategory,
+        services::category_service::SetItemCategoryRequest,
+        models::blockchain_event_dead_letter::DeadLetterStatus,
+        models::blockchain_event_dead_letter::BlockchainEventDeadLetter,
+        handlers::admin::UpdateUserRoleRequest,
+        handlers::admin::BanUserRequest,
+        handlers::admin::SpeedUpTransactionRequest,
+        handlers::admin::TransactionReplacementResponse,
+        services::permission_service::PermissionMatrix,
+        services::permission_service::Permission,
+        services::permission_service::SetRolePermissionsRequest,
+        services::settings_service::SettingKind,
+        services::settings_service::SettingView,
+        services::settings_service::UpdateSettingRequest,
+        services::feature_flag_service::FeatureFlag,
+        services::feature_flag_service::CreateFeatureFlagRequest,
+        services::feature_flag_service::UpdateFeatureFlagRequest,
+        services::feature_flag_service::EnabledFeatures,
+        models::notification::Notification,
+        services::notification_inbox_service::NotificationPage,
+        services::notification_inbox_service::UnreadCount,
+        services::notification_inbox_service::MarkAllReadResponse,
+        services::moderation_service::ModerationStatus,
+        services::moderation_service::ModerationCheck,
+        services::moderation_service::ModerationFinding,
+        services::moderation_service::ModerationEntry,
+        services::moderation_service::ModerationDecisionRequest,
+        services::credit_service::PlaceCreditHoldRequest,
+        services::credit_service::CreditHold,
+        services::credit_service::CreditHoldStatus,
+        services::credit_service::CaptureCreditHoldRequest,
+        services::verification_service::RaffleVerification,
+        services::verification_service::ParticipantCommitment,
+        services::verification_service::DrawRecord,
+        services::verification_service::OnchainReferences,
+        services::verification_service::OnchainEvent,
+        services::verification_service::DrawReplay,
+        services::verification_service::ReplayOutcome,
+        services::verification_service::ReplayInputs,
+        handlers::sandbox::CreateTestKeyRequest,
+        services::sandbox_service::SandboxPurgeReport,
+        services::sandbox_service::SandboxItem,
+        services::sandbox_service::CreateSandboxItemRequest,
+        services::sandbox_service::SandboxRaffle,
+        services::sandbox_service::SandboxRaffleStatus,
+        services::sandbox_service::CreateSandboxRaffleRequest,
+        services::sandbox_service::SandboxPurchase,
+        services::sandbox_service::SandboxPurchaseRequest,
+        services::sandbox_service::SandboxPurchaseResult,
+        services::realtime_service::ConnectionStats,
+        services::realtime_service::InstanceConnectionStats,
+        raffle_platform_shared::CreateItemRequest,
+        raffle_platform_shared::CreateRaffleRequest,
+        raffle_platform_shared::PurchaseBoxRequest,
+        raffle_platform_shared::RedeemCreditRequest,
+        raffle_platform_shared::CreateSellerRequest,
+        raffle_platform_shared::SellerResponse,
+        raffle_platform_shared::RaffleMetricsResponse,
+        raffle_platform_shared::PlatformStatsResponse,
+        raffle_platform_shared::PaginationParams,
+        raffle_platform_shared::FilterParams,
+        raffle_platform_shared::AuditAction,
+        raffle_platform_shared::Status,
+        raffle_platform_shared::RaffleCursorPage,
+        raffle_platform_shared::CreditCursorPage,
+        raffle_platform_shared::BoxPurchasePage,
+        models::listing_snapshot::ListingSnapshot,
+        models::listing_snapshot::SnapshotEntity,
+        services::listing_snapshot_service::PurchaseSnapshots,
+    )),
+    modifiers(&SecuritySchemes, &ErrorResponses),
+    tags(
+        (name = "abuse_reports", description = "Abuse reports and moderation"),
+        (name = "admin", description = "Platform administration"),
+        (name = "analytics", description = "Seller and platform analytics"),
+        (name = "api_usage", description = "Partner API rate plans and usage"),
+        (name = "auth", description = "Registration, login and the current user"),
+        (name = "comments", description = "Raffle comments"),
+        (name = "credit_holds", description = "Partner credit holds"),
+        (name = "credits", description = "Platform credits"),
+        (name = "currency", description = "Currencies and exchange rates"),
+        (name = "data_fixes", description = "Audited data fixes"),
+        (name = "features", description = "Feature flags for the caller"),
+        (name = "fees", description = "Fee schedules"),
+        (name = "fulfillments", description = "Prize shipping, receipt and disputes"),
+        (name = "health", description = "Health, readiness and metrics"),
+        (name = "ledger", description = "Double-entry ledger and invariant checks"),
+        (name = "moderation", description = "Review of new items before they go live"),
+        (name = "notifications", description = "The user's notification inbox"),
+        (name = "payments", description = "Payments, subscriptions and payouts"),
+        (name = "promotions", description = "Promotions and promo codes"),
+        (name = "raffles", description = "Raffles, boxes and draws"),
+        (name = "referrals", description = "Referral codes and rewards"),
+        (name = "reports", description = "Reports and exports"),
+        (name = "sandbox", description = "Partner sandbox"),
+        (name = "search", description = "Unified search across items, raffles and sellers"),
+        (name = "seller_webhooks", description = "Seller webhook endpoints"),
+        (name = "sellers", description = "Seller onboarding"),
+        (name = "shipping", description = "Shipping regions and addresses"),
+        (name = "snapshots", description = "Versioned item and raffle listing snapshots"),
+        (name = "tags", description = "Tags and collections"),
+        (name = "categories", description = "Item categories and attributes"),
+        (name = "tax", description = "Tax forms and prize fulfillment"),
+        (name = "users", description = "User profiles"),
+        (name = "verification", description = "Identity verification"),
+        (name = "wallet", description = "Custodial wallets and signing"),
+        (name = "webhooks", description = "Inbound provider webhooks"),
+        (name = "websocket", description = "Real-time updates"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Registers the two ways callers authenticate
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Components::new);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Api-Key"))),
+        );
+    }
+}
+
+/// Adds the error responses `AppError` can produce to every operation, so handlers only
+/// annotate their success responses
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for (path, item) in openapi.paths.paths.iter_mut() {
+            for operation in item.operations.values_mut() {
+                let takes_input = operation.request_body.is_some()
+                    || operation
+                        .parameters
+                        .as_ref()
+                        .is_some_and(|params| !params.is_empty());
+                let authenticated = operation
+                    .security
+                    .as_ref()
+                    .is_some_and(|security| !security.is_empty());
+
+                let mut errors = Vec::new();
+                if takes_input {
+                    errors.push(("400", error_response("Invalid request")));
+                }
+                if authenticated {
+                    errors.push(("401", error_response("Missing or invalid credentials")));
+                    errors.push(("403", error_response("Not allowed for this user or key")));
+                }
+                if path.contains('{') {
+                    errors.push(("404", error_response("Not found")));
+                }
+                // Rate limiting and partner API quotas only apply to the versioned API
+                if path.starts_with("/api/v1") {
+                    errors.push(("429", rate_limited_response()));
+                }
+                errors.push(("500", error_response("Internal server error")));
+
+                for (status, response) in errors {
+                    operation
+                        .responses
+                        .responses
+                        .entry(status.to_string())
+                        .or_insert_with(|| RefOr::T(response));
+                }
+            }
+        }
+    }
+}
+
+fn error_response(description: &str) -> Response {
+    ResponseBuilder::new()
+        .description(description)
+        .content(
+            "application/json",
+            ContentBuilder::new()
+                .schema(Ref::from_schema_name("ErrorResponse"))
+                .build(),
+        )
+        .build()
+}
+
+fn rate_limited_response() -> Response {
+    ResponseBuilder::new()
+        .description("Rate limit or monthly API quota exceeded; the body also carries `retry_after_seconds` and `retry_at`")
+        .content(
+            "application/json",
+            ContentBuilder::new().schema(Ref::from_schema_name("ErrorResponse")).build(),
+        )
+        .header(
+            "Retry-After",
+            HeaderBuilder::new()
+                .schema(ObjectBuilder::new().schema_type(SchemaType::Integer))
+                .description(Some("Seconds until the request may be retried"))
+                .build(),
+        )
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_documents_security_and_errors() {
+        let spec = ApiDoc::openapi();
+        let components = spec.components.as_ref().unwrap();
+        assert!(components.security_schemes.contains_key("bearer_auth"));
+        assert!(components.security_schemes.contains_key("api_key"));
+        assert!(components.schemas.contains_key("ErrorResponse"));
+
+        for (path, item) in &spec.paths.paths {
+            for operation in item.operations.values() {
+                assert!(
+                    operation.responses.responses.contains_key("500"),
+                    "{} lacks error responses",
+                    path
+                );
+            }
+        }
+    }
+}
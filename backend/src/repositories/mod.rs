@@ -4,40 +4,30 @@
 //! implementing the repository pattern for better separation of concerns
 //! and easier testing.
 
+use crate::error::AppError;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::CursorPage;
 use sqlx::PgPool;
 use std::sync::Arc;
+use uuid::Uuid;
 
 pub mod user_repository;
-pub mod item_repository;
-pub mod raffle_repository;
-pub mod credit_repository;
-pub mod transaction_repository;
 
 pub use user_repository::UserRepository;
-pub use item_repository::ItemRepository;
-pub use raffle_repository::RaffleRepository;
-pub use credit_repository::CreditRepository;
-pub use transaction_repository::TransactionRepository;
 
 /// Repository container that holds all repositories
 #[derive(Clone)]
 pub struct Repositories {
     pub users: UserRepository,
-    pub items: ItemRepository,
-    pub raffles: RaffleRepository,
-    pub credits: CreditRepository,
-    pub transactions: TransactionRepository,
 }
 
 impl Repositories {
     /// Create a new repository container
     pub fn new(pool: Arc<PgPool>) -> Self {
         Self {
-            users: UserRepository::new(pool.clone()),
-            items: ItemRepository::new(pool.clone()),
-            raffles: RaffleRepository::new(pool.clone()),
-            credits: CreditRepository::new(pool.clone()),
-            transactions: TransactionRepository::new(pool),
+            users: UserRepository::new(pool),
         }
     }
 }
@@ -62,59 +52,119 @@ pub trait Repository {
     async fn delete(&self, id: Self::Id) -> Result<bool, crate::error::AppError>;
 }
 
-/// Pagination parameters
-#[derive(Debug, Clone)]
-pub struct PaginationParams {
-    pub limit: i64,
-    pub offset: i64,
+/// Position in a newest-first listing: the `created_at` and `id` of the last row seen.
+///
+/// Clients only ever see it as an opaque token. Paging on the key instead of an offset
+/// keeps deep pages fast and stops rows inserted mid-scroll from repeating on the next page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
 }
 
-impl PaginationParams {
-    pub fn new(limit: Option<i64>, offset: Option<i64>) -> Self {
-        Self {
-            limit: limit.unwrap_or(20).min(100).max(1),
-            offset: offset.unwrap_or(0).max(0),
-        }
+impl Cursor {
+    pub fn new(created_at: DateTime<Utc>, id: Uuid) -> Self {
+        Self { created_at, id }
     }
 
-    pub fn from_page(page: i64, per_page: i64) -> Self {
-        let per_page = per_page.min(100).max(1);
-        let page = page.max(1);
-        Self {
-            limit: per_page,
-            offset: (page - 1) * per_page,
-        }
+    /// Encode as the token handed to clients
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}|{}", self.created_at.timestamp_micros(), self.id))
+    }
+
+    /// Decode a token produced by `encode`
+    pub fn decode(token: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::Validation("Invalid pagination cursor".to_string());
+
+        let bytes = URL_SAFE_NO_PAD.decode(token.trim()).map_err(|_| invalid())?;
+        let raw = String::from_utf8(bytes).map_err(|_| invalid())?;
+        let (micros, id) = raw.split_once('|').ok_or_else(invalid)?;
+
+        let micros = micros.parse::<i64>().map_err(|_| invalid())?;
+        let created_at = DateTime::from_timestamp_micros(micros).ok_or_else(invalid)?;
+        let id = Uuid::parse_str(id).map_err(|_| invalid())?;
+
+        Ok(Self { created_at, id })
+    }
+
+    /// The key as bind parameters for `(created_at, id) < ($n, $n+1)`
+    pub fn bounds(cursor: Option<&Cursor>) -> (Option<DateTime<Utc>>, Option<Uuid>) {
+        (cursor.map(|c| c.created_at), cursor.map(|c| c.id))
     }
 }
 
-/// Paginated result wrapper
+/// Cursor pagination parameters
 #[derive(Debug, Clone)]
-pub struct PaginatedResult<T> {
-    pub data: Vec<T>,
-    pub total: i64,
+pub struct CursorParams {
     pub limit: i64,
-    pub offset: i64,
+    pub after: Option<Cursor>,
 }
 
-impl<T> PaginatedResult<T> {
-    pub fn new(data: Vec<T>, total: i64, limit: i64, offset: i64) -> Self {
-        Self {
-            data,
-            total,
-            limit,
-            offset,
-        }
+impl CursorParams {
+    pub fn new(limit: Option<i64>, cursor: Option<&str>) -> Result<Self, AppError> {
+        Ok(Self {
+            limit: limit.unwrap_or(20).min(100).max(1),
+            after: cursor.filter(|c| !c.is_empty()).map(Cursor::decode).transpose()?,
+        })
     }
 
-    pub fn has_more(&self) -> bool {
-        self.offset + self.limit < self.total
+    /// Rows to fetch: one past the page, to learn whether another page follows
+    pub fn fetch_limit(&self) -> i64 {
+        self.limit + 1
     }
 
-    pub fn current_page(&self) -> i64 {
-        (self.offset / self.limit) + 1
+    /// Build the page from up to `fetch_limit` rows, dropping the look-ahead row
+    pub fn page<T>(&self, mut rows: Vec<T>, key: impl Fn(&T) -> Cursor) -> CursorPage<T> {
+        let has_more = rows.len() as i64 > self.limit;
+        rows.truncate(self.limit as usize);
+
+        let next_cursor = if has_more {
+            rows.last().map(|row| key(row).encode())
+        } else {
+            None
+        };
+
+        CursorPage {
+            data: rows,
+            next_cursor,
+            has_more,
+        }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor::new(
+            DateTime::from_timestamp_micros(1_705_314_600_123_456).unwrap(),
+            Uuid::new_v4(),
+        );
 
-    pub fn total_pages(&self) -> i64 {
-        (self.total + self.limit - 1) / self.limit
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(Cursor::decode("not a cursor").is_err());
+        assert!(Cursor::decode(&URL_SAFE_NO_PAD.encode("123|nope")).is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_page_uses_look_ahead_row() {
+        let params = CursorParams::new(Some(2), None).unwrap();
+        assert_eq!(params.fetch_limit(), 3);
+
+        let base = Utc::now();
+        let rows: Vec<Cursor> = (0..3)
+            .map(|i| Cursor::new(base - chrono::Duration::seconds(i), Uuid::new_v4()))
+            .collect();
+
+        let page = params.page(rows.clone(), |c| *c);
+        assert_eq!(page.data.len(), 2);
+        assert!(page.has_more);
+        assert_eq!(Cursor::decode(page.next_cursor.as_deref().unwrap()).unwrap(), rows[1]);
+
+        let last = params.page(rows[..2].to_vec(), |c| *c);
+        assert!(!last.has_more);
+        assert!(last.next_cursor.is_none());
+    }
+}
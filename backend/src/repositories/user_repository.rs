@@ -1,7 +1,7 @@
 use crate::error::AppError;
 use crate::models::{User, UserSession};
-use crate::repositories::{PaginatedResult, PaginationParams, Repository};
-use raffle_platform_shared::{CreateUserRequest, UserResponse};
+use crate::repositories::{Cursor, CursorParams, Repository};
+use raffle_platform_shared::{CreateUserRequest, CursorPage, UserResponse};
 use rust_decimal::Decimal;
 use sqlx::PgPool;
 use std::sync::Arc;
@@ -52,13 +52,20 @@ impl UserRepository {
         User::update_password(&self.pool, user_id, new_password_hash).await
     }
 
+    /// Update user's email; the new address starts unverified
+    pub async fn update_email(&self, user_id: Uuid, email: &str) -> Result<(), AppError> {
+        User::update_email(&self.pool, user_id, email).await
+    }
+
     /// Deactivate user account
     pub async fn deactivate(&self, user_id: Uuid) -> Result<(), AppError> {
         User::deactivate(&self.pool, user_id).await
     }
 
-    /// Get all users with pagination
-    pub async fn find_all(&self, pagination: PaginationParams) -> Result<PaginatedResult<User>, AppError> {
+    /// Get all users, newest first
+    pub async fn find_all(&self, pagination: CursorParams) -> Result<CursorPage<User>, AppError> {
+        let (after_created_at, after_id) = Cursor::bounds(pagination.after.as_ref());
+
         let users = sqlx::query_as!(
             User,
             r#"
@@ -70,28 +77,24 @@ impl UserRepository {
                 created_at, updated_at
             FROM users 
             WHERE is_active = true
-            ORDER BY created_at DESC
-            LIMIT $1 OFFSET $2
+            AND ($1::timestamptz IS NULL OR (created_at, id) < ($1, $2))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $3
             "#,
-            pagination.limit,
-            pagination.offset
+            after_created_at,
+            after_id,
+            pagination.fetch_limit()
         )
         .fetch_all(&*self.pool)
         .await?;
 
-        let total = sqlx::query_scalar!(
-            "SELECT COUNT(*) FROM users WHERE is_active = true"
-        )
-        .fetch_one(&*self.pool)
-        .await?
-        .unwrap_or(0);
-
-        Ok(PaginatedResult::new(users, total, pagination.limit, pagination.offset))
+        Ok(pagination.page(users, |u| Cursor::new(u.created_at, u.id)))
     }
 
-    /// Search users by username or email
-    pub async fn search(&self, query: &str, pagination: PaginationParams) -> Result<PaginatedResult<User>, AppError> {
+    /// Search users by username or email, newest first
+    pub async fn search(&self, query: &str, pagination: CursorParams) -> Result<CursorPage<User>, AppError> {
         let search_pattern = format!("%{}%", query);
+        let (after_created_at, after_id) = Cursor::bounds(pagination.after.as_ref());
         
         let users = sqlx::query_as!(
             User,
@@ -105,25 +108,19 @@ impl UserRepository {
             FROM users 
             WHERE is_active = true 
             AND (username ILIKE $1 OR email ILIKE $1)
-            ORDER BY created_at DESC
-            LIMIT $2 OFFSET $3
+            AND ($2::timestamptz IS NULL OR (created_at, id) < ($2, $3))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $4
             "#,
             search_pattern,
-            pagination.limit,
-            pagination.offset
+            after_created_at,
+            after_id,
+            pagination.fetch_limit()
         )
         .fetch_all(&*self.pool)
         .await?;
 
-        let total = sqlx::query_scalar!(
-            "SELECT COUNT(*) FROM users WHERE is_active = true AND (username ILIKE $1 OR email ILIKE $1)",
-            search_pattern
-        )
-        .fetch_one(&*self.pool)
-        .await?
-        .unwrap_or(0);
-
-        Ok(PaginatedResult::new(users, total, pagination.limit, pagination.offset))
+        Ok(pagination.page(users, |u| Cursor::new(u.created_at, u.id)))
     }
 
     /// Get user statistics
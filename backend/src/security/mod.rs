@@ -1,6 +1,5 @@
 pub mod audit_logging;
 pub mod input_validation;
-pub mod rate_limiting;
 pub mod encryption;
 pub mod session_management;
 pub mod monitoring;
@@ -11,7 +10,7 @@ use std::sync::Arc;
 
 use audit_logging::AuditLogger;
 use input_validation::InputValidator;
-use rate_limiting::{RateLimitMiddleware, MemoryRateLimitStore, RedisRateLimitStore, RateLimitStore};
+use crate::middleware::rate_limit::{MemoryRateLimitStore, RedisRateLimitStore, RateLimitStore};
 
 pub struct SecurityConfig {
     pub audit_logger: Arc<AuditLogger>,
@@ -58,11 +57,6 @@ impl SecurityConfig {
         cfg.app_data(web::Data::new(self.audit_logger.clone()))
            .app_data(web::Data::new(self.input_validator.clone()));
     }
-
-    pub fn create_rate_limit_middleware(&self) -> RateLimitMiddleware<()> {
-        RateLimitMiddleware::new(self.rate_limit_store.clone())
-            .with_default_api_rules()
-    }
 }
 
 // Security headers middleware
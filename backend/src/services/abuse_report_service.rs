@@ -0,0 +1,622 @@
+use crate::error::AppError;
+use crate::models::item::Item;
+use crate::models::notification::{Notification, NotificationType};
+use crate::models::system_settings::SystemSetting;
+use crate::services::auth_service::AuthService;
+use crate::services::comment_service::{CommentService, ModerateCommentRequest, ModerationAction};
+use crate::services::raffle_service::RaffleService;
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::ItemStatus;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const MAX_DESCRIPTION_LENGTH: usize = 2000;
+const MAX_EVIDENCE_URLS: usize = 5;
+const MAX_EVIDENCE_URL_LENGTH: usize = 2048;
+
+/// Abuse report service takes user reports of listings, comments and users and
+/// carries out the admin's resolution
+#[derive(Clone)]
+pub struct AbuseReportService {
+    db_pool: PgPool,
+    raffle_service: RaffleService,
+    comment_service: CommentService,
+    auth_service: AuthService,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ReportTargetType {
+    Raffle,
+    Item,
+    Comment,
+    User,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReportCategory {
+    Spam,
+    Scam,
+    Counterfeit,
+    ProhibitedItem,
+    Harassment,
+    Impersonation,
+    Offensive,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ReportStatus {
+    Open,
+    /// An admin has picked it up
+    Reviewing,
+    /// Acted on
+    Resolved,
+    /// Closed without action
+    Dismissed,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ResolutionAction {
+    /// Cancel the reported raffle with refunds, or take the reported item off sale
+    RemoveListing,
+    RemoveComment,
+    /// Send the reported account a warning notification
+    Warn,
+    /// Deactivate the reported account and revoke its sessions
+    Ban,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AbuseReport {
+    pub id: Uuid,
+    pub reporter_id: Uuid,
+    pub target_type: ReportTargetType,
+    pub target_id: Uuid,
+    pub reported_user_id: Option<Uuid>,
+    pub category: ReportCategory,
+    pub description: Option<String>,
+    pub evidence_urls: Vec<String>,
+    pub status: ReportStatus,
+    pub resolution_action: Option<ResolutionAction>,
+    pub resolution_note: Option<String>,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// A report in the admin triage queue
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TriageEntry {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub report: AbuseReport,
+    /// Open reports against the same target, from any reporter
+    pub reports_on_target: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateReportRequest {
+    pub target_type: ReportTargetType,
+    pub target_id: Uuid,
+    pub category: ReportCategory,
+    pub description: Option<String>,
+    /// Links to screenshots or other evidence
+    #[serde(default)]
+    pub evidence_urls: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResolveReportRequest {
+    pub action: ResolutionAction,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DismissReportRequest {
+    pub note: Option<String>,
+}
+
+const COLUMNS: &str = "id, reporter_id, target_type, target_id, reported_user_id, category, description, \
+    evidence_urls, status, resolution_action, resolution_note, reviewed_by, reviewed_at, resolved_at, created_at";
+
+impl AbuseReportService {
+    /// Create a new abuse report service
+    pub fn new(
+        db_pool: PgPool,
+        raffle_service: RaffleService,
+        comment_service: CommentService,
+        auth_service: AuthService,
+    ) -> Self {
+        Self {
+            db_pool,
+            raffle_service,
+            comment_service,
+            auth_service,
+        }
+    }
+
+    /// File a report against a listing, comment or user
+    pub async fn create_report(
+        &self,
+        reporter_id: Uuid,
+        request: CreateReportRequest,
+    ) -> Result<AbuseReport, AppError> {
+        let description = request
+            .description
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty());
+        if description.as_ref().map_or(false, |d| d.chars().count() > MAX_DESCRIPTION_LENGTH) {
+            return Err(AppError::Validation(format!(
+                "Description cannot exceed {} characters",
+                MAX_DESCRIPTION_LENGTH
+            )));
+        }
+        if request.category == ReportCategory::Other && description.is_none() {
+            return Err(AppError::Validation("Describe the problem when reporting for another reason".to_string()));
+        }
+        validate_evidence_urls(&request.evidence_urls)?;
+
+        let reported_user_id = self.target_owner(request.target_type, request.target_id).await?;
+        if reported_user_id == Some(reporter_id) {
+            return Err(AppError::Validation("You cannot report yourself or your own content".to_string()));
+        }
+
+        self.check_rate_limit(reporter_id).await?;
+
+        let report = sqlx::query_as::<_, AbuseReport>(&format!(
+            r#"
+            INSERT INTO abuse_reports
+                (reporter_id, target_type, target_id, reported_user_id, category, description, evidence_urls)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (reporter_id, target_type, target_id) WHERE status IN ('open', 'reviewing') DO NOTHING
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(reporter_id)
+        .bind(request.target_type)
+        .bind(request.target_id)
+        .bind(reported_user_id)
+        .bind(request.category)
+        .bind(description)
+        .bind(&request.evidence_urls)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::Conflict("You already have an open report about this".to_string()))?;
+
+        info!(
+            "User {} reported {:?} {} for {:?}",
+            reporter_id, report.target_type, report.target_id, report.category
+        );
+
+        Ok(report)
+    }
+
+    /// Reports the user has filed, newest first
+    pub async fn list_for_reporter(&self, reporter_id: Uuid, limit: i64) -> Result<Vec<AbuseReport>, AppError> {
+        let reports = sqlx::query_as::<_, AbuseReport>(&format!(
+            "SELECT {} FROM abuse_reports WHERE reporter_id = $1 ORDER BY created_at DESC LIMIT $2",
+            COLUMNS
+        ))
+        .bind(reporter_id)
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(reports)
+    }
+
+    /// Reports for admins to work through: by default everything not yet closed,
+    /// targets with the most open reports first, then oldest first
+    pub async fn triage_queue(&self, status: Option<ReportStatus>, limit: i64) -> Result<Vec<TriageEntry>, AppError> {
+        let entries = sqlx::query_as::<_, TriageEntry>(&format!(
+            r#"
+            SELECT {},
+                   COUNT(*) FILTER (WHERE status IN ('open', 'reviewing'))
+                       OVER (PARTITION BY target_type, target_id) AS reports_on_target
+            FROM abuse_reports
+            WHERE ($1::varchar IS NULL AND status IN ('open', 'reviewing')) OR status = $1
+            ORDER BY reports_on_target DESC, created_at ASC
+            LIMIT $2
+            "#,
+            COLUMNS
+        ))
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Claim an open report for review (admin)
+    pub async fn start_review(&self, report_id: Uuid, admin_id: Uuid) -> Result<AbuseReport, AppError> {
+        let report = sqlx::query_as::<_, AbuseReport>(&format!(
+            r#"
+            UPDATE abuse_reports
+            SET status = 'reviewing', reviewed_by = $2, reviewed_at = NOW()
+            WHERE id = $1 AND status = 'open'
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(report_id)
+        .bind(admin_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        match report {
+            Some(report) => Ok(report),
+            None => {
+                self.get_report(report_id).await?;
+                Err(AppError::Conflict("Report is not open".to_string()))
+            }
+        }
+    }
+
+    /// Act on a report (admin); every open report against the same target is closed with it
+    pub async fn resolve(
+        &self,
+        report_id: Uuid,
+        admin_id: Uuid,
+        request: ResolveReportRequest,
+    ) -> Result<AbuseReport, AppError> {
+        let report = self.get_report(report_id).await?;
+        if !is_pending(report.status) {
+            return Err(AppError::Conflict("Report is already closed".to_string()));
+        }
+        if !action_applies_to(request.action, report.target_type) {
+            return Err(AppError::Validation(format!(
+                "{:?} does not apply to a reported {:?}",
+                request.action, report.target_type
+            )));
+        }
+
+        self.apply_action(&report, admin_id, request.action, request.note.as_deref()).await?;
+
+        let closed = self
+            .close_target_reports(&report, admin_id, ReportStatus::Resolved, Some(request.action), request.note)
+            .await?;
+
+        info!(
+            "Admin {} resolved {} reports on {:?} {} with {:?}",
+            admin_id,
+            closed.len(),
+            report.target_type,
+            report.target_id,
+            request.action
+        );
+
+        self.notify_reporters(&closed).await;
+
+        closed
+            .into_iter()
+            .find(|r| r.id == report_id)
+            .ok_or_else(|| AppError::Internal("Resolved report disappeared".to_string()))
+    }
+
+    /// Close a report without action (admin)
+    pub async fn dismiss(
+        &self,
+        report_id: Uuid,
+        admin_id: Uuid,
+        request: DismissReportRequest,
+    ) -> Result<AbuseReport, AppError> {
+        let report = sqlx::query_as::<_, AbuseReport>(&format!(
+            r#"
+            UPDATE abuse_reports
+            SET status = 'dismissed', resolution_note = $3, resolved_at = NOW(),
+                reviewed_by = $2, reviewed_at = COALESCE(reviewed_at, NOW())
+            WHERE id = $1 AND status IN ('open', 'reviewing')
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(report_id)
+        .bind(admin_id)
+        .bind(request.note)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        let Some(report) = report else {
+            self.get_report(report_id).await?;
+            return Err(AppError::Conflict("Report is already closed".to_string()));
+        };
+
+        info!("Admin {} dismissed report {}", admin_id, report_id);
+
+        self.notify_reporters(std::slice::from_ref(&report)).await;
+
+        Ok(report)
+    }
+
+    // Private helper methods
+
+    async fn get_report(&self, report_id: Uuid) -> Result<AbuseReport, AppError> {
+        sqlx::query_as::<_, AbuseReport>(&format!("SELECT {} FROM abuse_reports WHERE id = $1", COLUMNS))
+            .bind(report_id)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Report not found".to_string()))
+    }
+
+    /// Account behind the target; fails if the target doesn't exist
+    async fn target_owner(&self, target_type: ReportTargetType, target_id: Uuid) -> Result<Option<Uuid>, AppError> {
+        let (sql, missing) = match target_type {
+            ReportTargetType::Raffle => (
+                r#"
+                SELECT s.user_id FROM raffles r
+                JOIN items i ON i.id = r.item_id
+                LEFT JOIN sellers s ON s.id = i.seller_id
+                WHERE r.id = $1
+                "#,
+                "Raffle not found",
+            ),
+            ReportTargetType::Item => (
+                "SELECT s.user_id FROM items i LEFT JOIN sellers s ON s.id = i.seller_id WHERE i.id = $1",
+                "Item not found",
+            ),
+            ReportTargetType::Comment => (
+                "SELECT author_id AS user_id FROM raffle_comments WHERE id = $1 AND status <> 'removed'",
+                "Comment not found",
+            ),
+            ReportTargetType::User => (
+                "SELECT id AS user_id FROM users WHERE id = $1 AND is_active = true",
+                "User not found",
+            ),
+        };
+
+        sqlx::query_scalar::<_, Option<Uuid>>(sql)
+            .bind(target_id)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound(missing.to_string()))
+    }
+
+    async fn apply_action(
+        &self,
+        report: &AbuseReport,
+        admin_id: Uuid,
+        action: ResolutionAction,
+        note: Option<&str>,
+    ) -> Result<(), AppError> {
+        let reason = format!("Removed after abuse report: {:?}", report.category);
+
+        match action {
+            ResolutionAction::RemoveListing => match report.target_type {
+                ReportTargetType::Raffle => {
+                    self.raffle_service.cancel_raffle(report.target_id, admin_id, reason).await?;
+                }
+                _ => {
+                    Item::update_status(&self.db_pool, report.target_id, ItemStatus::Inactive).await?;
+                }
+            },
+            ResolutionAction::RemoveComment => {
+                self.comment_service
+                    .moderate_comment(
+                        report.target_id,
+                        admin_id,
+                        ModerateCommentRequest {
+                            action: ModerationAction::Remove,
+                            reason: Some(reason),
+                        },
+                    )
+                    .await?;
+            }
+            ResolutionAction::Warn => {
+                let user_id = self.reported_user(report)?;
+                Notification::create(
+                    &self.db_pool,
+                    user_id,
+                    "Warning about your account".to_string(),
+                    format!(
+                        "We received a report about your {} and found it breaks our rules ({}). \
+                         Further violations may lead to suspension.",
+                        target_label(report.target_type),
+                        category_label(report.category)
+                    ),
+                    NotificationType::SystemAlert,
+                    Some(serde_json::json!({
+                        "event": "abuse_warning",
+                        "report_id": report.id,
+                        "note": note
+                    })),
+                )
+                .await?;
+            }
+            ResolutionAction::Ban => {
+                let user_id = self.reported_user(report)?;
+                self.auth_service
+                    .ban_user(admin_id, user_id, Some(format!("Abuse report {}", report.id)), None, None)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn reported_user(&self, report: &AbuseReport) -> Result<Uuid, AppError> {
+        report
+            .reported_user_id
+            .ok_or_else(|| AppError::Validation("No account is behind the reported content".to_string()))
+    }
+
+    async fn close_target_reports(
+        &self,
+        report: &AbuseReport,
+        admin_id: Uuid,
+        status: ReportStatus,
+        action: Option<ResolutionAction>,
+        note: Option<String>,
+    ) -> Result<Vec<AbuseReport>, AppError> {
+        let closed = sqlx::query_as::<_, AbuseReport>(&format!(
+            r#"
+            UPDATE abuse_reports
+            SET status = $3, resolution_action = $4, resolution_note = $5, resolved_at = NOW(),
+                reviewed_by = $6, reviewed_at = COALESCE(reviewed_at, NOW())
+            WHERE target_type = $1 AND target_id = $2 AND status IN ('open', 'reviewing')
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(report.target_type)
+        .bind(report.target_id)
+        .bind(status)
+        .bind(action)
+        .bind(note)
+        .bind(admin_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(closed)
+    }
+
+    /// Tell reporters how their reports ended; the outcome stands if a notice fails
+    async fn notify_reporters(&self, reports: &[AbuseReport]) {
+        for report in reports {
+            let message = match report.status {
+                ReportStatus::Resolved => format!(
+                    "Thanks for your report about a {}. We reviewed it and took action.",
+                    target_label(report.target_type)
+                ),
+                _ => format!(
+                    "Thanks for your report about a {}. We reviewed it and found no rule was broken.",
+                    target_label(report.target_type)
+                ),
+            };
+
+            if let Err(e) = Notification::create(
+                &self.db_pool,
+                report.reporter_id,
+                "Update on your report".to_string(),
+                message,
+                NotificationType::SystemAlert,
+                Some(serde_json::json!({
+                    "event": "abuse_report_closed",
+                    "report_id": report.id,
+                    "status": report.status
+                })),
+            )
+            .await
+            {
+                warn!("Failed to notify reporter of report {}: {}", report.id, e);
+            }
+        }
+    }
+
+    async fn check_rate_limit(&self, reporter_id: Uuid) -> Result<(), AppError> {
+        let per_day = SystemSetting::get_by_key(&self.db_pool, "abuse_report_limit_per_day")
+            .await?
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(20);
+
+        let filed = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM abuse_reports WHERE reporter_id = $1 AND created_at > NOW() - INTERVAL '1 day'",
+        )
+        .bind(reporter_id)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        if filed >= per_day {
+            return Err(AppError::Validation(format!(
+                "Report limit reached: at most {} reports per day",
+                per_day
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+fn is_pending(status: ReportStatus) -> bool {
+    matches!(status, ReportStatus::Open | ReportStatus::Reviewing)
+}
+
+/// Whether a resolution action can be carried out on a kind of target
+pub fn action_applies_to(action: ResolutionAction, target_type: ReportTargetType) -> bool {
+    match action {
+        ResolutionAction::RemoveListing => matches!(target_type, ReportTargetType::Raffle | ReportTargetType::Item),
+        ResolutionAction::RemoveComment => target_type == ReportTargetType::Comment,
+        ResolutionAction::Warn | ResolutionAction::Ban => true,
+    }
+}
+
+/// Evidence must be a handful of https links
+pub fn validate_evidence_urls(urls: &[String]) -> Result<(), AppError> {
+    if urls.len() > MAX_EVIDENCE_URLS {
+        return Err(AppError::Validation(format!(
+            "At most {} evidence links can be attached",
+            MAX_EVIDENCE_URLS
+        )));
+    }
+
+    for url in urls {
+        let valid = url.len() <= MAX_EVIDENCE_URL_LENGTH
+            && url
+                .strip_prefix("https://")
+                .map_or(false, |rest| !rest.is_empty() && !rest.contains(char::is_whitespace));
+        if !valid {
+            return Err(AppError::Validation(format!("Invalid evidence link: {}", url)));
+        }
+    }
+
+    Ok(())
+}
+
+fn target_label(target_type: ReportTargetType) -> &'static str {
+    match target_type {
+        ReportTargetType::Raffle => "raffle",
+        ReportTargetType::Item => "listing",
+        ReportTargetType::Comment => "comment",
+        ReportTargetType::User => "account",
+    }
+}
+
+fn category_label(category: ReportCategory) -> &'static str {
+    match category {
+        ReportCategory::Spam => "spam",
+        ReportCategory::Scam => "scam",
+        ReportCategory::Counterfeit => "counterfeit goods",
+        ReportCategory::ProhibitedItem => "prohibited item",
+        ReportCategory::Harassment => "harassment",
+        ReportCategory::Impersonation => "impersonation",
+        ReportCategory::Offensive => "offensive content",
+        ReportCategory::Other => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_applies_to() {
+        assert!(action_applies_to(ResolutionAction::RemoveListing, ReportTargetType::Raffle));
+        assert!(action_applies_to(ResolutionAction::RemoveListing, ReportTargetType::Item));
+        assert!(!action_applies_to(ResolutionAction::RemoveListing, ReportTargetType::User));
+        assert!(!action_applies_to(ResolutionAction::RemoveComment, ReportTargetType::Raffle));
+        assert!(action_applies_to(ResolutionAction::Ban, ReportTargetType::Comment));
+    }
+
+    #[test]
+    fn test_validate_evidence_urls() {
+        assert!(validate_evidence_urls(&[]).is_ok());
+        assert!(validate_evidence_urls(&["https://example.com/shot.png".to_string()]).is_ok());
+        assert!(validate_evidence_urls(&["http://example.com/shot.png".to_string()]).is_err());
+        assert!(validate_evidence_urls(&["https://".to_string()]).is_err());
+        assert!(validate_evidence_urls(&vec!["https://example.com".to_string(); 6]).is_err());
+    }
+}
@@ -0,0 +1,598 @@
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use crate::models::{AuditLog, User};
+use crate::utils::crypto::verify_password;
+use chrono::{DateTime, Duration, Utc};
+use raffle_platform_shared::AuditAction;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use tracing::{error, info};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const DEFAULT_GRACE_DAYS: i64 = 30;
+const PURGE_INTERVAL_SECONDS: u64 = 3600;
+/// Requests purged per run; the rest wait for the next run
+const PURGE_BATCH_SIZE: i64 = 50;
+
+/// Account deletion service handles users' right-to-erasure requests.
+///
+/// A request deactivates the account straight away and starts a grace period during which
+/// an admin can still restore it. Once the grace period ends the background job erases the
+/// user's personal data: behavioural and credential rows are deleted outright, while
+/// payments, ledger and audit rows the platform must keep are stripped of anything that
+/// identifies the person. The user row stays behind as an anonymized tombstone so those
+/// records keep their foreign keys.
+#[derive(Clone)]
+pub struct AccountDeletionService {
+    db_pool: PgPool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DeletionStatus {
+    Pending,
+    Cancelled,
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct AccountDeletionRequest {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub status: DeletionStatus,
+    pub reason: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    pub purge_after: DateTime<Utc>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    pub cancelled_by: Option<Uuid>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub purge_summary: Option<serde_json::Value>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AccountPurgeReport {
+    pub completed: usize,
+    pub failed: usize,
+}
+
+/// How a table is handled when an account is purged
+enum PurgeStep {
+    /// Remove the user's rows entirely
+    Delete(&'static str, &'static str),
+    /// Keep the rows but overwrite identifying columns
+    Anonymize(&'static str, &'static str),
+}
+
+impl PurgeStep {
+    fn table(&self) -> &'static str {
+        match self {
+            PurgeStep::Delete(table, _) | PurgeStep::Anonymize(table, _) => table,
+        }
+    }
+
+    fn sql(&self) -> &'static str {
+        match self {
+            PurgeStep::Delete(_, sql) | PurgeStep::Anonymize(_, sql) => sql,
+        }
+    }
+}
+
+/// Tables holding the user's data, in purge order; `$1` is the user id
+const PURGE_STEPS: &[PurgeStep] = &[
+    PurgeStep::Delete("user_sessions", "DELETE FROM user_sessions WHERE user_id = $1"),
+    PurgeStep::Delete("password_reset_tokens", "DELETE FROM password_reset_tokens WHERE user_id = $1"),
+    PurgeStep::Delete("email_verification_tokens", "DELETE FROM email_verification_tokens WHERE user_id = $1"),
+    PurgeStep::Delete("notifications", "DELETE FROM notifications WHERE user_id = $1"),
+    PurgeStep::Delete("user_notification_preferences", "DELETE FROM user_notification_preferences WHERE user_id = $1"),
+    PurgeStep::Delete("notification_preferences", "DELETE FROM notification_preferences WHERE user_id = $1"),
+    PurgeStep::Delete("push_devices", "DELETE FROM push_devices WHERE user_id = $1"),
+    PurgeStep::Delete("user_payment_methods", "DELETE FROM user_payment_methods WHERE user_id = $1"),
+    PurgeStep::Delete("item_favorites", "DELETE FROM item_favorites WHERE user_id = $1"),
+    PurgeStep::Delete("raffle_favorites", "DELETE FROM raffle_favorites WHERE user_id = $1"),
+    PurgeStep::Delete("saved_searches", "DELETE FROM saved_searches WHERE user_id = $1"),
+    PurgeStep::Delete("item_views", "DELETE FROM item_views WHERE user_id = $1"),
+    PurgeStep::Delete("anonymous_sessions", "DELETE FROM anonymous_sessions WHERE user_id = $1"),
+    PurgeStep::Delete("email_verification_attempts", "DELETE FROM email_verification_attempts WHERE user_id = $1"),
+    PurgeStep::Delete("idempotency_keys", "DELETE FROM idempotency_keys WHERE user_id = $1"),
+    PurgeStep::Delete("user_risk_profiles", "DELETE FROM user_risk_profiles WHERE user_id = $1"),
+    PurgeStep::Anonymize(
+        "user_activity_logs",
+        "UPDATE user_activity_logs
+         SET user_id = NULL, ip_address = NULL, user_agent = NULL, referrer = NULL, city = NULL, metadata = NULL
+         WHERE user_id = $1",
+    ),
+    PurgeStep::Anonymize(
+        "audit_logs",
+        "UPDATE audit_logs
+         SET ip_address = NULL, user_agent = NULL,
+             old_values = old_values - ARRAY['email', 'username', 'phone_number'],
+             new_values = new_values - ARRAY['email', 'username', 'phone_number']
+         WHERE user_id = $1",
+    ),
+    // Payments are kept for bookkeeping and chargebacks, without card or free-text details
+    PurgeStep::Anonymize(
+        "payments",
+        "UPDATE payments
+         SET card_last4 = NULL, description = 'Redacted', metadata = '{}'::jsonb
+         WHERE user_id = $1",
+    ),
+    PurgeStep::Delete("user_geo_sightings", "DELETE FROM user_geo_sightings WHERE user_id = $1"),
+    PurgeStep::Delete("step_up_challenges", "DELETE FROM step_up_challenges WHERE user_id = $1"),
+    PurgeStep::Delete("user_shipping_addresses", "DELETE FROM user_shipping_addresses WHERE user_id = $1"),
+    PurgeStep::Delete("email_messages", "DELETE FROM email_messages WHERE user_id = $1"),
+    PurgeStep::Delete("user_blocks", "DELETE FROM user_blocks WHERE blocker_id = $1 OR blocked_id = $1"),
+    // Fulfillments and disputes stay for the seller's records, without the winner's address or free text
+    PurgeStep::Anonymize(
+        "fulfillments",
+        "UPDATE fulfillments
+         SET recipient_name = 'Redacted', line1 = 'Redacted', line2 = NULL, city = 'Redacted',
+             region_code = NULL, postal_code = NULL, phone = NULL
+         WHERE winner_id = $1",
+    ),
+    PurgeStep::Anonymize("disputes", "UPDATE disputes SET description = NULL WHERE opened_by = $1"),
+    PurgeStep::Delete("referral_codes", "DELETE FROM referral_codes WHERE user_id = $1"),
+    // Referrals stay so the other side's reward history holds up, without the referee's network details
+    PurgeStep::Anonymize(
+        "referrals",
+        "UPDATE referrals SET ip_address = NULL, device_hash = NULL WHERE referee_id = $1",
+    ),
+    PurgeStep::Delete("box_reservations", "DELETE FROM box_reservations WHERE user_id = $1"),
+    // The provider reference stays so the provider's own record can still be located
+    PurgeStep::Anonymize(
+        "identity_verifications",
+        "UPDATE identity_verifications SET session_url = NULL, decline_reason = NULL WHERE user_id = $1",
+    ),
+    PurgeStep::Delete("login_devices", "DELETE FROM login_devices WHERE user_id = $1"),
+    PurgeStep::Delete("login_lockouts", "DELETE FROM login_lockouts WHERE user_id = $1"),
+    PurgeStep::Delete("external_wallet_challenges", "DELETE FROM external_wallet_challenges WHERE user_id = $1"),
+    // Linked wallets are unlinked rather than deleted: on-chain purchases reference them and the
+    // address stays claimed by this account
+    PurgeStep::Anonymize(
+        "external_wallets",
+        "UPDATE external_wallets SET label = NULL, unlinked_at = COALESCE(unlinked_at, NOW()) WHERE user_id = $1",
+    ),
+    PurgeStep::Delete("raffle_waitlist_entries", "DELETE FROM raffle_waitlist_entries WHERE user_id = $1"),
+];
+
+impl AccountDeletionService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+
+    /// Deactivate the account and schedule its erasure; password accounts must confirm the password
+    pub async fn request_deletion(
+        &self,
+        user: &User,
+        password: Option<&str>,
+        reason: Option<String>,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<AccountDeletionRequest, AppError> {
+        if let Some(password_hash) = &user.password_hash {
+            let password = password
+                .ok_or_else(|| AppError::Validation("Password confirmation is required".to_string()))?;
+            if !verify_password(password, password_hash)? {
+                return Err(AppError::Authentication("Invalid password".to_string()));
+            }
+        }
+
+        let grace_days = self.get_grace_days().await?;
+        let now = Utc::now();
+
+        let mut tx = self.db_pool.begin().await?;
+
+        let request = sqlx::query_as::<_, AccountDeletionRequest>(
+            r#"
+            INSERT INTO account_deletion_requests (user_id, reason, requested_at, purge_after)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id) WHERE status = 'pending' DO NOTHING
+            RETURNING id, user_id, status, reason, requested_at, purge_after, cancelled_at,
+                      cancelled_by, completed_at, purge_summary, last_error
+            "#,
+        )
+        .bind(user.id)
+        .bind(&reason)
+        .bind(now)
+        .bind(purge_after(now, grace_days))
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Account deletion is already scheduled".to_string()))?;
+
+        sqlx::query("UPDATE users SET is_active = FALSE, deleted_at = $2 WHERE id = $1")
+            .bind(user.id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        AuditLog::create(
+            &self.db_pool,
+            Some(user.id),
+            AuditAction::Delete,
+            Some("user".to_string()),
+            Some(user.id),
+            None,
+            Some(serde_json::json!({
+                "event": "account_deletion_requested",
+                "request_id": request.id,
+                "purge_after": request.purge_after,
+            })),
+            ip_address,
+            user_agent,
+        )
+        .await?;
+
+        info!(
+            "User {} requested account deletion; data will be erased after {}",
+            user.id, request.purge_after
+        );
+
+        Ok(request)
+    }
+
+    /// Restore an account during its grace period (admin only)
+    pub async fn cancel(&self, request_id: Uuid, admin_id: Uuid) -> Result<AccountDeletionRequest, AppError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        let request = sqlx::query_as::<_, AccountDeletionRequest>(
+            r#"
+            UPDATE account_deletion_requests
+            SET status = 'cancelled', cancelled_at = NOW(), cancelled_by = $2
+            WHERE id = $1 AND status = 'pending'
+            RETURNING id, user_id, status, reason, requested_at, purge_after, cancelled_at,
+                      cancelled_by, completed_at, purge_summary, last_error
+            "#,
+        )
+        .bind(request_id)
+        .bind(admin_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("No pending deletion request with that id".to_string()))?;
+
+        sqlx::query("UPDATE users SET is_active = TRUE, deleted_at = NULL WHERE id = $1")
+            .bind(request.user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        AuditLog::create(
+            &self.db_pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("user".to_string()),
+            Some(request.user_id),
+            None,
+            Some(serde_json::json!({
+                "event": "account_deletion_cancelled",
+                "request_id": request.id,
+            })),
+            None,
+            None,
+        )
+        .await?;
+
+        info!("Admin {} cancelled deletion of user {}", admin_id, request.user_id);
+
+        Ok(request)
+    }
+
+    /// Deletion requests, newest first, optionally by status
+    pub async fn list_requests(
+        &self,
+        status: Option<DeletionStatus>,
+        limit: i64,
+    ) -> Result<Vec<AccountDeletionRequest>, AppError> {
+        let requests = sqlx::query_as::<_, AccountDeletionRequest>(
+            r#"
+            SELECT id, user_id, status, reason, requested_at, purge_after, cancelled_at,
+                   cancelled_by, completed_at, purge_summary, last_error
+            FROM account_deletion_requests
+            WHERE ($1::varchar IS NULL OR status = $1)
+            ORDER BY requested_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(requests)
+    }
+
+    /// Start the scheduled purge of accounts whose grace period has ended
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(PURGE_INTERVAL_SECONDS));
+            loop {
+                interval.tick().await;
+                match service.purge_due().await {
+                    Ok(report) if report.completed > 0 || report.failed > 0 => info!(
+                        "Account purge run: {} erased, {} failed",
+                        report.completed, report.failed
+                    ),
+                    Ok(_) => {}
+                    Err(e) => error!("Account purge run failed: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Erase every account whose grace period has ended
+    pub async fn purge_due(&self) -> Result<AccountPurgeReport, AppError> {
+        let due = sqlx::query_as::<_, AccountDeletionRequest>(
+            r#"
+            SELECT id, user_id, status, reason, requested_at, purge_after, cancelled_at,
+                   cancelled_by, completed_at, purge_summary, last_error
+            FROM account_deletion_requests
+            WHERE status = 'pending' AND purge_after <= NOW()
+            ORDER BY purge_after
+            LIMIT $1
+            "#,
+        )
+        .bind(PURGE_BATCH_SIZE)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let mut report = AccountPurgeReport::default();
+        for request in due {
+            match self.purge(&request).await {
+                Ok(()) => report.completed += 1,
+                Err(e) => {
+                    report.failed += 1;
+                    error!("Failed to purge user {} (request {}): {}", request.user_id, request.id, e);
+                    sqlx::query("UPDATE account_deletion_requests SET last_error = $2 WHERE id = $1")
+                        .bind(request.id)
+                        .bind(e.to_string())
+                        .execute(&self.db_pool)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    // Private helper methods
+
+    /// Erase one account in a single transaction, so a failure leaves nothing half-done
+    async fn purge(&self, request: &AccountDeletionRequest) -> Result<(), AppError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        // Re-check under lock: an admin may have cancelled since the batch was read
+        let still_pending: Option<Uuid> = sqlx::query_scalar(
+            "SELECT id FROM account_deletion_requests WHERE id = $1 AND status = 'pending' FOR UPDATE",
+        )
+        .bind(request.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if still_pending.is_none() {
+            return Ok(());
+        }
+
+        let mut summary = BTreeMap::new();
+        for step in PURGE_STEPS {
+            let result = sqlx::query(step.sql()).bind(request.user_id).execute(&mut *tx).await?;
+            summary.insert(step.table(), result.rows_affected());
+        }
+
+        summary.insert("users", self.anonymize_user(&mut tx, request.user_id).await?);
+
+        sqlx::query(
+            r#"
+            UPDATE account_deletion_requests
+            SET status = 'completed', completed_at = NOW(), purge_summary = $2, last_error = NULL
+            WHERE id = $1
+            "#,
+        )
+        .bind(request.id)
+        .bind(serde_json::to_value(&summary)?)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        info!("Erased personal data of user {} (request {})", request.user_id, request.id);
+        Ok(())
+    }
+
+    async fn anonymize_user(&self, tx: &mut Transaction<'_, Postgres>, user_id: Uuid) -> Result<u64, AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET username = $2,
+                email = $3,
+                password_hash = NULL,
+                phone_number = NULL,
+                google_id = NULL,
+                apple_id = NULL,
+                first_name = NULL,
+                last_name = NULL,
+                winner_display = 'anonymous',
+                email_verified = FALSE,
+                is_active = FALSE,
+                anonymized_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(anonymized_username(user_id))
+        .bind(anonymized_email(user_id))
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_grace_days(&self) -> Result<i64, AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, "account_deletion_grace_days").await?;
+        Ok(setting
+            .and_then(|s| s.get_int_value())
+            .filter(|days| *days >= 0)
+            .unwrap_or(DEFAULT_GRACE_DAYS))
+    }
+}
+
+/// Stable, unique placeholder that fits the 50-character username column
+fn anonymized_username(user_id: Uuid) -> String {
+    format!("deleted-{}", user_id.simple())
+}
+
+/// Unique, undeliverable address (`.invalid` is reserved and never resolves)
+fn anonymized_email(user_id: Uuid) -> String {
+    format!("deleted+{}@deleted.invalid", user_id.simple())
+}
+
+fn purge_after(requested_at: DateTime<Utc>, grace_days: i64) -> DateTime<Utc> {
+    requested_at + Duration::days(grace_days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::collections::BTreeSet;
+    use std::path::Path;
+
+    /// User-keyed tables the purge leaves alone, and why
+    const RETAINED: &[(&str, &str)] = &[
+        ("account_deletion_requests", "the erasure record itself"),
+        ("abuse_reports", "trust and safety record"),
+        // Draw history must stay verifiable
+        ("box_purchases", "draw history"),
+        ("detached_box_purchases", "draw history"),
+        ("raffle_prize_tiers", "draw history"),
+        // Bookkeeping, refunds and chargebacks
+        ("credit_holds", "financial record"),
+        ("credit_transactions", "financial record"),
+        ("user_credits", "financial record"),
+        ("transactions", "financial record"),
+        ("free_item_redemptions", "financial record"),
+        ("promotion_redemptions", "financial record"),
+        ("purchase_receipts", "financial record"),
+        ("refunds", "financial record"),
+        ("subscriptions", "financial record"),
+        ("external_purchases", "financial record"),
+        ("user_stripe_customers", "financial record"),
+        // Tax law sets how long these are kept
+        ("winner_tax_forms", "legal retention"),
+        ("prize_tax_assessments", "legal retention"),
+        ("security_alerts", "security record"),
+        ("security_incidents", "security record"),
+        ("ip_blocklist", "security record"),
+        // Keys and addresses for funds the platform still holds on chain
+        ("retired_wallets", "custody"),
+        ("wallet_chain_addresses", "custody"),
+        ("wallet_data_keys", "custody"),
+        ("sellers", "seller business record"),
+        ("seller_shipping_policies", "seller business record"),
+        ("seller_api_plans", "seller business record"),
+        ("seller_api_usage", "seller business record"),
+        ("seller_api_usage_charges", "seller business record"),
+        ("sandbox_items", "seller business record"),
+        ("sandbox_raffles", "seller business record"),
+        ("partner_api_keys", "seller business record"),
+        ("webhook_endpoints", "seller business record"),
+        // Public content, shown under the anonymized tombstone
+        ("raffle_comments", "public content"),
+        ("raffle_comment_flags", "public content"),
+        ("item_reviews", "public content"),
+        ("item_activity_log", "item history"),
+        ("item_inventory_log", "item history"),
+        // Only the id of the admin who acted
+        ("backup_verification_runs", "admin actor"),
+        ("chain_reconciliation_runs", "admin actor"),
+        ("email_domain_rules", "admin actor"),
+        ("email_suppressions", "admin actor"),
+        ("feature_flags", "admin actor"),
+        ("fee_schedules", "admin actor"),
+        ("item_moderation", "admin actor"),
+        ("ledger_invariant_runs", "admin actor"),
+        ("promotions", "admin actor"),
+        ("raffle_remediations", "admin actor"),
+        ("role_permissions", "admin actor"),
+        ("tags", "admin actor"),
+        ("tag_collections", "admin actor"),
+        ("webhook_deliveries", "admin actor"),
+    ];
+
+    /// Tables with a foreign key to `users`, read from the migrations
+    fn user_keyed_tables() -> BTreeSet<String> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations");
+        let mut tables = BTreeSet::new();
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            // Rollback scripts only undo numbered migrations
+            if !name.ends_with(".sql") || !name.starts_with(|c: char| c.is_ascii_digit()) {
+                continue;
+            }
+
+            let sql = std::fs::read_to_string(&path)
+                .unwrap()
+                .lines()
+                .map(|line| line.split("--").next().unwrap_or(""))
+                .collect::<Vec<_>>()
+                .join("\n");
+            for statement in sql.split(';').filter(|s| s.contains("REFERENCES users(")) {
+                let words: Vec<&str> = statement
+                    .split_whitespace()
+                    .filter(|w| !matches!(w.to_uppercase().as_str(), "IF" | "NOT" | "EXISTS" | "ONLY"))
+                    .collect();
+                let table = words
+                    .windows(3)
+                    .find(|w| {
+                        matches!(w[0].to_uppercase().as_str(), "CREATE" | "ALTER") && w[1].eq_ignore_ascii_case("TABLE")
+                    })
+                    .map(|w| w[2].trim_end_matches('(').to_string())
+                    .unwrap_or_else(|| panic!("{}: can't tell which table references users", name));
+                tables.insert(table);
+            }
+        }
+        tables
+    }
+
+    #[test]
+    fn test_every_user_keyed_table_is_purged_or_retained() {
+        let tables = user_keyed_tables();
+        let purged: BTreeSet<&str> = PURGE_STEPS.iter().map(PurgeStep::table).collect();
+        let retained: BTreeSet<&str> = RETAINED.iter().map(|(table, _)| *table).collect();
+
+        let uncovered: Vec<&String> = tables
+            .iter()
+            .filter(|t| !purged.contains(t.as_str()) && !retained.contains(t.as_str()))
+            .collect();
+        assert!(uncovered.is_empty(), "no purge step or retention reason for {:?}", uncovered);
+
+        let unknown: Vec<&&str> = purged.union(&retained).filter(|t| !tables.contains(**t)).collect();
+        assert!(unknown.is_empty(), "not a user-keyed table: {:?}", unknown);
+        assert!(purged.is_disjoint(&retained));
+    }
+
+    #[test]
+    fn test_anonymized_identity_fits_columns() {
+        let user_id = Uuid::new_v4();
+        let username = anonymized_username(user_id);
+
+        assert!(username.len() <= 50);
+        assert_ne!(username, anonymized_username(Uuid::new_v4()));
+        assert!(anonymized_email(user_id).ends_with("@deleted.invalid"));
+    }
+
+    #[test]
+    fn test_purge_after() {
+        let requested_at = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            purge_after(requested_at, 30),
+            Utc.with_ymd_and_hms(2024, 1, 31, 12, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,315 @@
+use crate::error::AppError;
+use actix_web::HttpRequest;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::net::IpAddr;
+use tracing::{debug, info};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Header carrying the anonymous session id issued to unauthenticated clients
+pub const ANONYMOUS_ID_HEADER: &str = "X-Anonymous-Id";
+
+/// Analytics service tracks guest sessions and stitches them to users on signup/login
+#[derive(Clone)]
+pub struct AnalyticsService {
+    db_pool: PgPool,
+    read_pool: PgPool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AnonymousSession {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub first_seen_at: Option<DateTime<Utc>>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub stitched_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientContext {
+    pub ip_address: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    pub landing_page: Option<String>,
+    pub referrer: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TrackEventRequest {
+    pub anonymous_id: Uuid,
+    pub activity_type: String,
+    pub page_url: Option<String>,
+    pub referrer: Option<String>,
+    pub duration_seconds: Option<i32>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StitchResult {
+    pub anonymous_id: Uuid,
+    pub user_id: Uuid,
+    pub events_attributed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RaffleFunnel {
+    pub raffle_id: Uuid,
+    /// Distinct visitors, counting a stitched guest and its user once
+    pub unique_viewers: i64,
+    pub purchasers: i64,
+    /// Purchasers who viewed the raffle, including views made before signing up
+    pub converted_viewers: i64,
+    pub conversion_rate: Decimal,
+}
+
+impl AnalyticsService {
+    /// Create a new analytics service
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { read_pool: db_pool.clone(), db_pool }
+    }
+
+    /// Run funnel queries on a read replica
+    pub fn with_read_pool(mut self, read_pool: PgPool) -> Self {
+        self.read_pool = read_pool;
+        self
+    }
+
+    /// Issue a new anonymous session, or refresh an existing one the client still holds
+    pub async fn start_anonymous_session(
+        &self,
+        existing_id: Option<Uuid>,
+        context: ClientContext,
+    ) -> Result<AnonymousSession, AppError> {
+        if let Some(id) = existing_id {
+            let refreshed = sqlx::query_as!(
+                AnonymousSession,
+                r#"
+                UPDATE anonymous_sessions SET last_seen_at = NOW()
+                WHERE id = $1
+                RETURNING id, user_id, first_seen_at, last_seen_at, stitched_at
+                "#,
+                id
+            )
+            .fetch_optional(&self.db_pool)
+            .await?;
+
+            if let Some(session) = refreshed {
+                return Ok(session);
+            }
+        }
+
+        let session = sqlx::query_as!(
+            AnonymousSession,
+            r#"
+            INSERT INTO anonymous_sessions (ip_address, user_agent, landing_page, referrer)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, user_id, first_seen_at, last_seen_at, stitched_at
+            "#,
+            context.ip_address,
+            context.user_agent,
+            context.landing_page,
+            context.referrer
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        debug!("Issued anonymous session {}", session.id);
+        Ok(session)
+    }
+
+    /// Log an event against an anonymous session; stitched sessions are attributed to their user
+    pub async fn track_event(
+        &self,
+        request: TrackEventRequest,
+        context: ClientContext,
+    ) -> Result<(), AppError> {
+        if request.activity_type.trim().is_empty() || request.activity_type.len() > 50 {
+            return Err(AppError::Validation("activity_type must be 1-50 characters".to_string()));
+        }
+
+        let session = sqlx::query_as!(
+            AnonymousSession,
+            r#"
+            UPDATE anonymous_sessions SET last_seen_at = NOW()
+            WHERE id = $1
+            RETURNING id, user_id, first_seen_at, last_seen_at, stitched_at
+            "#,
+            request.anonymous_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Anonymous session not found".to_string()))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO user_activity_logs (
+                user_id, anonymous_id, activity_type, page_url, referrer,
+                ip_address, user_agent, duration_seconds, metadata
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            session.user_id,
+            session.id,
+            request.activity_type,
+            request.page_url,
+            request.referrer,
+            context.ip_address,
+            context.user_agent,
+            request.duration_seconds,
+            request.metadata
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Attribute a guest session and its pre-signup events to a user
+    pub async fn stitch_identity(&self, anonymous_id: Uuid, user_id: Uuid) -> Result<StitchResult, AppError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        // A session belongs to the first user it was stitched to; a shared device
+        // logging into a second account must not move the earlier history
+        let claimed = sqlx::query!(
+            r#"
+            UPDATE anonymous_sessions
+            SET user_id = $2, stitched_at = COALESCE(stitched_at, NOW()), last_seen_at = NOW()
+            WHERE id = $1 AND (user_id IS NULL OR user_id = $2)
+            "#,
+            anonymous_id,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if claimed.rows_affected() == 0 {
+            tx.rollback().await?;
+            return Ok(StitchResult {
+                anonymous_id,
+                user_id,
+                events_attributed: 0,
+            });
+        }
+
+        let attributed = sqlx::query!(
+            "UPDATE user_activity_logs SET user_id = $2 WHERE anonymous_id = $1 AND user_id IS NULL",
+            anonymous_id,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        info!(
+            "Stitched anonymous session {} to user {} ({} events)",
+            anonymous_id, user_id, attributed.rows_affected()
+        );
+
+        Ok(StitchResult {
+            anonymous_id,
+            user_id,
+            events_attributed: attributed.rows_affected(),
+        })
+    }
+
+    /// Compute the view-to-purchase funnel for a raffle and store it on RaffleMetrics
+    pub async fn refresh_raffle_funnel(&self, raffle_id: Uuid) -> Result<RaffleFunnel, AppError> {
+        let raffle_key = raffle_id.to_string();
+
+        let row = sqlx::query!(
+            r#"
+            WITH viewers AS (
+                SELECT DISTINCT COALESCE(l.user_id, s.user_id)::text AS user_key,
+                       COALESCE(l.user_id, s.user_id, l.anonymous_id)::text AS identity
+                FROM user_activity_logs l
+                LEFT JOIN anonymous_sessions s ON s.id = l.anonymous_id
+                WHERE l.activity_type = 'raffle_view' AND l.metadata->>'raffle_id' = $2
+            ),
+            purchasers AS (
+                SELECT DISTINCT user_id::text AS user_key FROM box_purchases WHERE raffle_id = $1
+            )
+            SELECT
+                (SELECT COUNT(DISTINCT identity) FROM viewers) AS "unique_viewers!",
+                (SELECT COUNT(*) FROM purchasers) AS "purchasers!",
+                (SELECT COUNT(*) FROM purchasers p
+                    WHERE EXISTS (SELECT 1 FROM viewers v WHERE v.user_key = p.user_key)) AS "converted_viewers!"
+            "#,
+            raffle_id,
+            raffle_key
+        )
+        .fetch_one(&self.read_pool)
+        .await?;
+
+        let conversion_rate = conversion_rate(row.converted_viewers, row.unique_viewers);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO raffle_metrics (raffle_id, unique_viewers, conversion_rate)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (raffle_id) DO UPDATE SET
+                unique_viewers = EXCLUDED.unique_viewers,
+                conversion_rate = EXCLUDED.conversion_rate,
+                updated_at = NOW()
+            "#,
+            raffle_id,
+            row.unique_viewers as i32,
+            conversion_rate
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(RaffleFunnel {
+            raffle_id,
+            unique_viewers: row.unique_viewers,
+            purchasers: row.purchasers,
+            converted_viewers: row.converted_viewers,
+            conversion_rate,
+        })
+    }
+}
+
+/// Read the anonymous session id a client sends alongside signup/login
+pub fn anonymous_id_from_request(req: &HttpRequest) -> Option<Uuid> {
+    req.headers()
+        .get(ANONYMOUS_ID_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|v| Uuid::parse_str(v.trim()).ok())
+}
+
+/// Share of viewers that converted, as a fraction with four decimal places (raffle_metrics.conversion_rate)
+pub fn conversion_rate(converted: i64, viewers: i64) -> Decimal {
+    if viewers <= 0 {
+        return Decimal::ZERO;
+    }
+    (Decimal::from(converted.min(viewers)) / Decimal::from(viewers)).round_dp(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_conversion_rate() {
+        assert_eq!(conversion_rate(0, 0), Decimal::ZERO);
+        assert_eq!(conversion_rate(1, 3), Decimal::new(3333, 4));
+        assert_eq!(conversion_rate(5, 4), Decimal::ONE);
+    }
+
+    #[test]
+    fn test_anonymous_id_from_request() {
+        let id = Uuid::new_v4();
+        let req = TestRequest::default()
+            .insert_header((ANONYMOUS_ID_HEADER, id.to_string()))
+            .to_http_request();
+        assert_eq!(anonymous_id_from_request(&req), Some(id));
+
+        let req = TestRequest::default()
+            .insert_header((ANONYMOUS_ID_HEADER, "not-a-uuid"))
+            .to_http_request();
+        assert_eq!(anonymous_id_from_request(&req), None);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::error::AppError;
+use crate::models::api_usage::{
+    period_end, period_start, previous_period, ApiKeyUsage, ApiRatePlan, ApiRatePlanRequest, ApiUsageCharge,
+    OverageCharge, SellerApiUsage,
+};
+use crate::models::{Seller, Transaction, User};
+use chrono::{DateTime, NaiveDate, Utc};
+use raffle_platform_shared::{TransactionType, UserRole};
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const BILLING_INTERVAL_SECONDS: u64 = 3600;
+const BILLING_BATCH_SIZE: i64 = 200;
+
+/// Rate plans, usage metering reports and monthly overage billing for the partner API
+///
+/// Usage is counted by the API usage middleware and the seller webhook service. Once a
+/// month closes, each seller's usage is priced against their plan and any overage is
+/// posted as a seller fee, like subscription and listing fees.
+#[derive(Clone)]
+pub struct ApiBillingService {
+    db_pool: PgPool,
+}
+
+/// A seller's plan and usage for the current month
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ApiUsageSummary {
+    pub plan: ApiRatePlan,
+    pub period_start: NaiveDate,
+    pub resets_at: DateTime<Utc>,
+    pub requests: i64,
+    pub webhooks_delivered: i64,
+    pub requests_remaining: i64,
+    pub webhooks_remaining: i64,
+    /// Overage billed if the month closed now
+    pub projected_overage: OverageCharge,
+    pub keys: Vec<ApiKeyUsage>,
+}
+
+/// Outcome of one billing run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BillingRunReport {
+    pub period_start: Option<NaiveDate>,
+    pub charges_created: usize,
+    pub charges_posted: usize,
+    pub failures: usize,
+}
+
+impl ApiBillingService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+
+    /// Close the previous month and post its charges every hour
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(BILLING_INTERVAL_SECONDS));
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.run_billing(Utc::now()).await {
+                    error!("API usage billing run failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Charge every seller with usage in the month before `now`, then post unposted charges.
+    /// Safe to repeat: a month is charged at most once per seller.
+    pub async fn run_billing(&self, now: DateTime<Utc>) -> Result<BillingRunReport, AppError> {
+        let period = previous_period(period_start(now));
+        let mut report = BillingRunReport { period_start: Some(period), ..Default::default() };
+
+        loop {
+            let batch = SellerApiUsage::unbilled(&self.db_pool, period, BILLING_BATCH_SIZE).await?;
+            if batch.is_empty() {
+                break;
+            }
+
+            for usage in batch {
+                // Priced on the plan the seller is on when the month is closed
+                let plan = ApiRatePlan::for_seller(&self.db_pool, usage.seller_id).await?;
+                let charge = plan.overage(usage.requests, usage.webhooks_delivered);
+                if ApiUsageCharge::create(&self.db_pool, &usage, &plan, &charge).await?.is_some() {
+                    report.charges_created += 1;
+                }
+            }
+        }
+
+        loop {
+            let charges = ApiUsageCharge::unposted(&self.db_pool, BILLING_BATCH_SIZE).await?;
+            if charges.is_empty() {
+                break;
+            }
+
+            let batch_size = charges.len();
+            let mut failures = 0;
+            for charge in charges {
+                match self.post_charge(&charge).await {
+                    Ok(()) => report.charges_posted += 1,
+                    Err(e) => {
+                        warn!("Failed to post API usage charge {}: {}", charge.id, e);
+                        failures += 1;
+                    }
+                }
+            }
+
+            report.failures += failures;
+            // Failed charges stay unposted and are retried on the next run
+            if failures == batch_size {
+                break;
+            }
+        }
+
+        if report.charges_created > 0 || report.charges_posted > 0 {
+            info!(
+                "API usage billing for {}: {} charges created, {} posted, {} failed",
+                period, report.charges_created, report.charges_posted, report.failures
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// The seller's plan, usage this month and usage per key
+    pub async fn usage_summary(&self, seller_id: Uuid) -> Result<ApiUsageSummary, AppError> {
+        let period = period_start(Utc::now());
+        let plan = ApiRatePlan::for_seller(&self.db_pool, seller_id).await?;
+        let usage = SellerApiUsage::find(&self.db_pool, seller_id, period).await?;
+        let keys = ApiKeyUsage::list_for_seller(&self.db_pool, seller_id, period).await?;
+
+        Ok(ApiUsageSummary {
+            period_start: period,
+            resets_at: period_end(period),
+            requests: usage.requests,
+            webhooks_delivered: usage.webhooks_delivered,
+            requests_remaining: (plan.included_requests - usage.requests).max(0),
+            webhooks_remaining: (plan.included_webhooks - usage.webhooks_delivered).max(0),
+            projected_overage: plan.overage(usage.requests, usage.webhooks_delivered),
+            plan,
+            keys,
+        })
+    }
+
+    pub async fn list_plans(&self) -> Result<Vec<ApiRatePlan>, AppError> {
+        ApiRatePlan::list(&self.db_pool).await
+    }
+
+    pub async fn create_plan(&self, request: &ApiRatePlanRequest, admin_id: Uuid) -> Result<ApiRatePlan, AppError> {
+        let plan = ApiRatePlan::create(&self.db_pool, request).await?;
+        info!("Admin {} created API rate plan {} ({})", admin_id, plan.name, plan.id);
+        Ok(plan)
+    }
+
+    pub async fn update_plan(
+        &self,
+        plan_id: Uuid,
+        request: &ApiRatePlanRequest,
+        admin_id: Uuid,
+    ) -> Result<ApiRatePlan, AppError> {
+        let plan = ApiRatePlan::update(&self.db_pool, plan_id, request).await?;
+        info!("Admin {} updated API rate plan {} ({})", admin_id, plan.name, plan.id);
+        Ok(plan)
+    }
+
+    /// Move a seller to an active plan
+    pub async fn assign_plan(&self, seller_id: Uuid, plan_id: Uuid, admin_id: Uuid) -> Result<ApiRatePlan, AppError> {
+        let user = User::find_by_id(&self.db_pool, seller_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+        if user.role != UserRole::Seller {
+            return Err(AppError::Validation("Rate plans can only be assigned to sellers".to_string()));
+        }
+
+        let plan = ApiRatePlan::find(&self.db_pool, plan_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Rate plan not found".to_string()))?;
+        if !plan.is_active {
+            return Err(AppError::Validation("Rate plan is not active".to_string()));
+        }
+
+        ApiRatePlan::assign(&self.db_pool, seller_id, plan_id, admin_id).await?;
+        info!("Admin {} moved seller {} to API rate plan {}", admin_id, seller_id, plan.name);
+
+        Ok(plan)
+    }
+
+    pub async fn list_charges(
+        &self,
+        seller_id: Option<Uuid>,
+        period: Option<NaiveDate>,
+        limit: i64,
+    ) -> Result<Vec<ApiUsageCharge>, AppError> {
+        ApiUsageCharge::list(&self.db_pool, seller_id, period, limit.clamp(1, 500)).await
+    }
+
+    // Private helper methods
+
+    /// Post a charge as a seller fee against the seller's account
+    async fn post_charge(&self, charge: &ApiUsageCharge) -> Result<(), AppError> {
+        let seller = Seller::find_by_user_id(&self.db_pool, charge.seller_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("No seller account for user {}", charge.seller_id)))?;
+
+        let transaction = Transaction::create_seller_fee(
+            &self.db_pool,
+            seller.id,
+            charge.amount,
+            TransactionType::SellerApiUsageFee,
+            Some(serde_json::json!({
+                "api_usage_charge_id": charge.id,
+                "period_start": charge.period_start,
+                "plan": charge.plan_name,
+                "overage_requests": charge.overage_requests,
+                "overage_webhooks": charge.overage_webhooks,
+            })),
+        )
+        .await?;
+
+        ApiUsageCharge::set_transaction(&self.db_pool, charge.id, transaction.id).await
+    }
+}
@@ -1,8 +1,12 @@
 use crate::error::AppError;
+use crate::models::auth_token::{AuthToken, AuthTokenKind};
 use crate::models::{User, UserSession, AuditLog};
 use crate::repositories::UserRepository;
+use crate::services::email_domain_service::EmailDomainService;
+use crate::services::email_service::EmailService;
+use crate::services::wallet_service::WalletService;
 use crate::utils::{
-    crypto::{hash_password, verify_password, hash_token, generate_secure_token, validate_password_strength},
+    crypto::{hash_password, verify_password, hash_token, validate_password_strength},
     jwt::{JwtService, TokenPair},
     validation::{validate_email, validate_username},
 };
@@ -16,14 +20,20 @@ use std::net::IpAddr;
 use std::sync::Arc;
 use uuid::Uuid;
 
+pub mod login_guard;
 pub mod session_manager;
-pub use session_manager::{SessionManager, SessionConfig, SessionInfo, DeviceInfo};
+pub use login_guard::{LockoutPolicy, LockoutScope, LoginGuard, LoginLockout, LoginLockoutQuery};
+pub use session_manager::{SessionManager, SessionConfig, SessionInfo, DeviceInfo, RevocationReason};
 
 #[derive(Clone)]
 pub struct AuthService {
     user_repository: UserRepository,
     jwt_service: Arc<JwtService>,
     session_manager: SessionManager,
+    login_guard: LoginGuard,
+    email_domain_service: EmailDomainService,
+    email_service: Option<EmailService>,
+    wallet_service: Option<WalletService>,
     pool: Arc<PgPool>,
 }
 
@@ -32,10 +42,16 @@ impl AuthService {
         let user_repository = UserRepository::new(pool.clone());
         let session_manager = SessionManager::new(pool.clone(), SessionConfig::default());
         
+        let email_domain_service = EmailDomainService::new((*pool).clone());
+
         Self {
             user_repository,
             jwt_service,
             session_manager,
+            login_guard: LoginGuard::new(pool.clone()),
+            email_domain_service,
+            email_service: None,
+            wallet_service: None,
             pool,
         }
     }
@@ -44,14 +60,37 @@ impl AuthService {
         let user_repository = UserRepository::new(pool.clone());
         let session_manager = SessionManager::new(pool.clone(), session_config);
         
+        let email_domain_service = EmailDomainService::new((*pool).clone());
+
         Self {
             user_repository,
             jwt_service,
             session_manager,
+            login_guard: LoginGuard::new(pool.clone()),
+            email_domain_service,
+            email_service: None,
+            wallet_service: None,
             pool,
         }
     }
 
+    /// Failed login counters, lockouts and known devices
+    pub fn login_guard(&self) -> &LoginGuard {
+        &self.login_guard
+    }
+
+    /// Email password reset links and new device alerts instead of only logging them
+    pub fn with_email_service(mut self, email_service: EmailService) -> Self {
+        self.email_service = Some(email_service);
+        self
+    }
+
+    /// Generate new users' wallets with this service, so they derive from its master seed
+    pub fn with_wallet_service(mut self, wallet_service: WalletService) -> Self {
+        self.wallet_service = Some(wallet_service);
+        self
+    }
+
     /// Register a new user
     pub async fn register(
         &self,
@@ -63,6 +102,7 @@ impl AuthService {
         validate_email(&request.email)?;
         validate_username(&request.username)?;
         validate_password_strength(&request.password)?;
+        self.email_domain_service.check_email(&request.email).await?;
 
         // Check if email already exists
         if self.user_repository.email_exists(&request.email).await? {
@@ -78,9 +118,12 @@ impl AuthService {
         let password_hash = hash_password(&request.password)?;
 
         // Generate wallet for user
-        let wallet_service = crate::services::WalletService::new(self.pool.clone());
+        let wallet_service = self
+            .wallet_service
+            .clone()
+            .unwrap_or_else(|| WalletService::new(self.pool.clone()));
         let temp_user_id = Uuid::new_v4();
-        let (wallet_address, encrypted_private_key, encrypted_mnemonic) = wallet_service
+        let wallet = wallet_service
             .generate_wallet_for_user(temp_user_id, &request.password)
             .await?;
 
@@ -89,17 +132,19 @@ impl AuthService {
             &self.pool,
             request.clone(),
             password_hash,
-            wallet_address,
-            encrypted_private_key,
-            Some(encrypted_mnemonic),
+            wallet.address.clone(),
+            wallet.encrypted_private_key.clone(),
+            wallet.encrypted_mnemonic.clone(),
         ).await?;
+        wallet_service.record_new_wallet(user.id, &wallet).await?;
 
-        // Generate tokens
+        // Generate tokens; new accounts start at token version 0
         let token_pair = self.jwt_service.create_token_pair(
             user.id,
             user.username.clone(),
             user.email.clone(),
             user.role,
+            0,
         )?;
 
         // Create session
@@ -152,10 +197,20 @@ impl AuthService {
         // Validate input
         validate_email(&request.email)?;
 
-        // Find user by email
-        let user = self.user_repository.find_by_email(&request.email)
-            .await?
-            .ok_or_else(|| AppError::Authentication(ERROR_INVALID_CREDENTIALS.to_string()))?;
+        // Addresses locked out for guessing are refused before any account is looked up
+        if let Some(lockout) = self.login_guard.check(None, ip_address).await? {
+            return Err(locked_out(&lockout));
+        }
+
+        // Find user by email; unknown emails still count against the address
+        let Some(user) = self.user_repository.find_by_email(&request.email).await? else {
+            self.login_guard.record_failure(None, ip_address).await?;
+            return Err(AppError::Authentication(ERROR_INVALID_CREDENTIALS.to_string()));
+        };
+
+        if let Some(lockout) = self.login_guard.check(Some(user.id), None).await? {
+            return Err(locked_out(&lockout));
+        }
 
         // Check if user is active
         if !user.is_active {
@@ -168,6 +223,8 @@ impl AuthService {
             .ok_or_else(|| AppError::Authentication("Account uses social login".to_string()))?;
 
         if !verify_password(&request.password, password_hash)? {
+            let lockout = self.login_guard.record_failure(Some(user.id), ip_address).await?;
+
             // Log failed login attempt
             AuditLog::create(
                 &self.pool,
@@ -179,21 +236,29 @@ impl AuthService {
                 Some(serde_json::json!({
                     "event": "failed_login",
                     "email": request.email,
-                    "reason": "invalid_password"
+                    "reason": "invalid_password",
+                    "locked_until": lockout.as_ref().and_then(|lockout| lockout.locked_until)
                 })),
                 ip_address,
                 user_agent.clone(),
             ).await?;
 
-            return Err(AppError::Authentication(ERROR_INVALID_CREDENTIALS.to_string()));
+            return Err(match lockout {
+                Some(lockout) => locked_out(&lockout),
+                None => AppError::Authentication(ERROR_INVALID_CREDENTIALS.to_string()),
+            });
         }
 
+        self.login_guard.record_success(user.id).await?;
+        self.check_device(user.id, user_agent.as_deref(), ip_address).await;
+
         // Generate tokens
         let token_pair = self.jwt_service.create_token_pair(
             user.id,
             user.username.clone(),
             user.email.clone(),
             user.role,
+            self.token_version(user.id).await?,
         )?;
 
         // Create session
@@ -258,12 +323,16 @@ impl AuthService {
         // Revoke old refresh token
         self.jwt_service.revoke_token(&claims.jti)?;
 
+        // A refresh token from before the last revocation can't mint new tokens
+        let token_version = self.jwt_service.ensure_token_current(&claims, &self.pool).await?;
+
         // Generate new tokens
         let token_pair = self.jwt_service.create_token_pair(
             user.id,
             user.username.clone(),
             user.email.clone(),
             user.role,
+            token_version,
         )?;
 
         // Deactivate old session and create new one
@@ -347,8 +416,8 @@ impl AuthService {
         // Update password
         self.user_repository.update_password(user_id, new_password_hash).await?;
 
-        // Deactivate all sessions for security (user will need to log in again)
-        self.user_repository.deactivate_all_sessions(user_id).await?;
+        // Revoke every session and outstanding token (user will need to log in again)
+        self.session_manager.invalidate_all(user_id, RevocationReason::PasswordChanged).await?;
 
         // Log password change
         AuditLog::log_password_change(&self.pool, user_id, ip_address, user_agent).await?;
@@ -356,6 +425,57 @@ impl AuthService {
         Ok(())
     }
 
+    /// Change the user's email after confirming their password; the new address must be verified again
+    pub async fn change_email(
+        &self,
+        user_id: Uuid,
+        new_email: &str,
+        current_password: &str,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<User, AppError> {
+        let new_email = new_email.trim();
+        validate_email(new_email)?;
+
+        let user = self.user_repository.find_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        if user.email.eq_ignore_ascii_case(new_email) {
+            return Err(AppError::Validation("New email is the same as the current one".to_string()));
+        }
+
+        let password_hash = user.password_hash
+            .as_ref()
+            .ok_or_else(|| AppError::Authentication("Account uses social login".to_string()))?;
+
+        if !verify_password(current_password, password_hash)? {
+            return Err(AppError::Authentication("Current password is incorrect".to_string()));
+        }
+
+        self.email_domain_service.check_email(new_email).await?;
+
+        if self.user_repository.email_exists(new_email).await? {
+            return Err(AppError::Conflict(ERROR_EMAIL_ALREADY_EXISTS.to_string()));
+        }
+
+        self.user_repository.update_email(user_id, new_email).await?;
+
+        AuditLog::create(
+            &self.pool,
+            Some(user_id),
+            raffle_platform_shared::AuditAction::EmailChange,
+            Some("user".to_string()),
+            Some(user_id),
+            Some(serde_json::json!({ "email": user.email })),
+            Some(serde_json::json!({ "email": new_email })),
+            ip_address,
+            user_agent,
+        ).await?;
+
+        self.get_current_user(user_id).await
+    }
+
     /// Initiate password reset
     pub async fn initiate_password_reset(
         &self,
@@ -367,25 +487,11 @@ impl AuthService {
         // But only send email if user exists
         
         if let Some(user) = self.user_repository.find_by_email(email).await? {
-            // Generate reset token
-            let reset_token = generate_secure_token();
-            let token_hash = hash_token(&reset_token);
-            let expires_at = Utc::now() + Duration::hours(1); // 1 hour expiry
-
-            // Store reset token in database
-            sqlx::query!(
-                r#"
-                INSERT INTO password_reset_tokens (user_id, token, expires_at)
-                VALUES ($1, $2, $3)
-                ON CONFLICT (user_id) 
-                DO UPDATE SET token = EXCLUDED.token, expires_at = EXCLUDED.expires_at, is_used = false
-                "#,
-                user.id,
-                token_hash,
-                expires_at
-            )
-            .execute(&*self.pool)
-            .await?;
+            // Only the newest reset link works
+            let ttl = AuthTokenKind::PasswordReset.ttl(&self.pool).await?;
+            let mut tx = self.pool.begin().await?;
+            let reset_token = AuthToken::issue(&mut tx, AuthTokenKind::PasswordReset, user.id, ttl).await?;
+            tx.commit().await?;
 
             // Log password reset request
             AuditLog::create(
@@ -403,9 +509,10 @@ impl AuthService {
                 user_agent,
             ).await?;
 
-            // TODO: Send email with reset token
-            // For now, we'll just log it (in production, use proper email service)
-            tracing::info!("Password reset token for {}: {}", email, reset_token);
+            match &self.email_service {
+                Some(email_service) => email_service.send_password_reset(user.id, &reset_token, ttl.num_minutes()).await?,
+                None => tracing::info!("Password reset token for {}: {}", email, reset_token),
+            }
         }
 
         Ok(())
@@ -422,26 +529,18 @@ impl AuthService {
         // Validate new password
         validate_password_strength(new_password)?;
 
-        // Find and validate reset token
-        let token_hash = hash_token(token);
-        let reset_record = sqlx::query!(
-            r#"
-            SELECT user_id, expires_at, is_used 
-            FROM password_reset_tokens 
-            WHERE token = $1 AND expires_at > NOW() AND is_used = false
-            "#,
-            token_hash
-        )
-        .fetch_optional(&*self.pool)
-        .await?
-        .ok_or_else(|| AppError::Authentication("Invalid or expired reset token".to_string()))?;
-
         // Hash new password
         let password_hash = hash_password(new_password)?;
 
-        // Update password and mark token as used
+        // Use up the reset token and update the password together
         let mut tx = self.pool.begin().await?;
 
+        let reset_record = AuthToken::find_for_update(&mut tx, AuthTokenKind::PasswordReset, token)
+            .await?
+            .filter(|record| record.check(Utc::now()).is_ok())
+            .ok_or_else(|| AppError::Authentication("Invalid or expired reset token".to_string()))?;
+        reset_record.mark_used(&mut tx, AuthTokenKind::PasswordReset).await?;
+
         sqlx::query!(
             "UPDATE users SET password_hash = $1, updated_at = NOW() WHERE id = $2",
             password_hash,
@@ -450,17 +549,12 @@ impl AuthService {
         .execute(&mut *tx)
         .await?;
 
-        sqlx::query!(
-            "UPDATE password_reset_tokens SET is_used = true WHERE token = $1",
-            token_hash
-        )
-        .execute(&mut *tx)
-        .await?;
-
         tx.commit().await?;
 
-        // Deactivate all sessions for security
-        self.user_repository.deactivate_all_sessions(reset_record.user_id).await?;
+        // Revoke every session and outstanding token for security
+        self.session_manager.invalidate_all(reset_record.user_id, RevocationReason::PasswordReset).await?;
+        // The owner proved themselves, so a lockout from someone guessing is lifted
+        self.login_guard.record_success(reset_record.user_id).await?;
 
         // Log password reset completion
         AuditLog::create(
@@ -480,35 +574,132 @@ impl AuthService {
         Ok(())
     }
 
+    /// Change a user's role (admin only); the user signs in again to pick up the new role
+    pub async fn change_role(
+        &self,
+        admin_id: Uuid,
+        user_id: Uuid,
+        new_role: UserRole,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<(), AppError> {
+        let user = self.user_repository.find_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        if user.role == new_role {
+            return Ok(());
+        }
+
+        User::update_role(&self.pool, user_id, new_role).await?;
+        self.session_manager.invalidate_all(user_id, RevocationReason::RoleChanged).await?;
+
+        AuditLog::create(
+            &self.pool,
+            Some(admin_id),
+            raffle_platform_shared::AuditAction::RoleChange,
+            Some("user".to_string()),
+            Some(user_id),
+            Some(serde_json::json!({ "role": user.role })),
+            Some(serde_json::json!({ "role": new_role })),
+            ip_address,
+            user_agent,
+        ).await?;
+
+        Ok(())
+    }
+
+    /// Ban a user (admin only): deactivate the account and revoke everything it holds
+    pub async fn ban_user(
+        &self,
+        admin_id: Uuid,
+        user_id: Uuid,
+        reason: Option<String>,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<(), AppError> {
+        if admin_id == user_id {
+            return Err(AppError::Validation("Admins cannot ban themselves".to_string()));
+        }
+
+        self.user_repository.find_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        User::deactivate(&self.pool, user_id).await?;
+        self.session_manager.invalidate_all(user_id, RevocationReason::Banned).await?;
+
+        AuditLog::create(
+            &self.pool,
+            Some(admin_id),
+            raffle_platform_shared::AuditAction::SecurityEvent,
+            Some("user".to_string()),
+            Some(user_id),
+            None,
+            Some(serde_json::json!({
+                "event": "user_banned",
+                "reason": reason
+            })),
+            ip_address,
+            user_agent,
+        ).await?;
+
+        Ok(())
+    }
+
+    /// Lift a ban (admin only); tokens revoked by the ban stay revoked
+    pub async fn unban_user(
+        &self,
+        admin_id: Uuid,
+        user_id: Uuid,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query(
+            "UPDATE users SET is_active = true, updated_at = NOW() WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(user_id)
+        .execute(&*self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("User not found".to_string()));
+        }
+
+        AuditLog::create(
+            &self.pool,
+            Some(admin_id),
+            raffle_platform_shared::AuditAction::SecurityEvent,
+            Some("user".to_string()),
+            Some(user_id),
+            None,
+            Some(serde_json::json!({ "event": "user_unbanned" })),
+            ip_address,
+            user_agent,
+        ).await?;
+
+        Ok(())
+    }
+
     /// Verify email address
     pub async fn verify_email(
         &self,
         user_id: Uuid,
         token: &str,
     ) -> Result<(), AppError> {
-        // Find and validate verification token
-        let token_hash = hash_token(token);
-        let verification_record = sqlx::query!(
-            r#"
-            SELECT user_id, expires_at, is_used 
-            FROM email_verification_tokens 
-            WHERE token = $1 AND user_id = $2 AND expires_at > NOW() AND is_used = false
-            "#,
-            token_hash,
-            user_id
-        )
-        .fetch_optional(&*self.pool)
-        .await?
-        .ok_or_else(|| AppError::Authentication("Invalid or expired verification token".to_string()))?;
-
-        // Update user email verification status and mark token as used
+        // Find and use up the verification token, and mark the user verified
         let mut tx = self.pool.begin().await?;
 
-        self.user_repository.verify_email(user_id).await?;
+        AuthToken::find_for_update(&mut tx, AuthTokenKind::EmailVerification, token)
+            .await?
+            .filter(|record| record.user_id == user_id && record.check(Utc::now()).is_ok())
+            .ok_or_else(|| AppError::Authentication("Invalid or expired verification token".to_string()))?
+            .mark_used(&mut tx, AuthTokenKind::EmailVerification)
+            .await?;
 
         sqlx::query!(
-            "UPDATE email_verification_tokens SET is_used = true WHERE token = $1",
-            token_hash
+            "UPDATE users SET email_verified = true, updated_at = NOW() WHERE id = $1",
+            user_id
         )
         .execute(&mut *tx)
         .await?;
@@ -538,11 +729,55 @@ impl AuthService {
         match required_role {
             UserRole::User => true, // All roles can access user-level endpoints
             UserRole::Seller => matches!(user_role, UserRole::Seller | UserRole::Admin | UserRole::Operator),
+            UserRole::Support => matches!(user_role, UserRole::Support | UserRole::Admin | UserRole::Operator),
             UserRole::Admin => matches!(user_role, UserRole::Admin | UserRole::Operator),
             UserRole::Operator => matches!(user_role, UserRole::Operator),
         }
     }
 
+    /// Version new tokens for a user are issued at
+    async fn token_version(&self, user_id: Uuid) -> Result<i32, AppError> {
+        let state = User::token_state(&self.pool, user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+        Ok(state.token_version)
+    }
+
+    /// Remember the device and alert the owner when it is new to the account. Never fails
+    /// the login it is part of.
+    async fn check_device(&self, user_id: Uuid, user_agent: Option<&str>, ip_address: Option<IpAddr>) {
+        let new_device = match self.login_guard.record_device(user_id, user_agent, ip_address).await {
+            Ok(new_device) => new_device,
+            Err(e) => {
+                tracing::warn!("Failed to record login device of user {}: {}", user_id, e);
+                return;
+            }
+        };
+        let alerts_enabled = match self.login_guard.get_policy().await {
+            Ok(policy) => policy.new_device_alerts,
+            Err(_) => true,
+        };
+        if !new_device || !alerts_enabled {
+            return;
+        }
+
+        let device = login_guard::device_label(user_agent);
+        let ip = ip_address.map(|ip| ip.to_string());
+        tracing::info!("User {} signed in from a new device: {}", user_id, device);
+
+        match &self.email_service {
+            Some(email_service) => {
+                if let Err(e) = email_service
+                    .send_new_device_login(user_id, &device, ip.as_deref(), Utc::now())
+                    .await
+                {
+                    tracing::warn!("Failed to queue new device alert for user {}: {}", user_id, e);
+                }
+            }
+            None => tracing::info!("New device alert for user {}: {} from {:?}", user_id, device, ip),
+        }
+    }
+
     /// Create device info from user agent
     fn create_device_info(&self, user_agent: &Option<String>) -> Option<serde_json::Value> {
         user_agent.as_ref().map(|ua| {
@@ -624,23 +859,15 @@ impl AuthService {
 
     /// Verify email with token (public method)
     pub async fn verify_email(&self, token: &str) -> Result<(), AppError> {
-        // Find and validate verification token
-        let token_hash = hash_token(token);
-        let verification_record = sqlx::query!(
-            r#"
-            SELECT user_id, expires_at, is_used 
-            FROM email_verification_tokens 
-            WHERE token = $1 AND expires_at > NOW() AND is_used = false
-            "#,
-            token_hash
-        )
-        .fetch_optional(&*self.pool)
-        .await?
-        .ok_or_else(|| AppError::Authentication("Invalid or expired verification token".to_string()))?;
-
-        // Update user as verified and mark token as used
+        // Find and use up the verification token, and mark the user verified
         let mut tx = self.pool.begin().await?;
 
+        let verification_record = AuthToken::find_for_update(&mut tx, AuthTokenKind::EmailVerification, token)
+            .await?
+            .filter(|record| record.check(Utc::now()).is_ok())
+            .ok_or_else(|| AppError::Authentication("Invalid or expired verification token".to_string()))?;
+        verification_record.mark_used(&mut tx, AuthTokenKind::EmailVerification).await?;
+
         sqlx::query!(
             "UPDATE users SET email_verified = true, updated_at = NOW() WHERE id = $1",
             verification_record.user_id
@@ -648,13 +875,6 @@ impl AuthService {
         .execute(&mut *tx)
         .await?;
 
-        sqlx::query!(
-            "UPDATE email_verification_tokens SET is_used = true WHERE token = $1",
-            token_hash
-        )
-        .execute(&mut *tx)
-        .await?;
-
         tx.commit().await?;
 
         // Log email verification
@@ -675,45 +895,6 @@ impl AuthService {
         Ok(())
     }
 
-    /// Resend email verification
-    pub async fn resend_email_verification(&self, email: &str) -> Result<(), AppError> {
-        // Find user by email
-        let user = User::find_by_email(&self.pool, email)
-            .await?
-            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
-
-        // Check if already verified
-        if user.email_verified {
-            return Err(AppError::Validation("Email is already verified".to_string()));
-        }
-
-        // Generate new verification token
-        let verification_token = generate_secure_token();
-        let token_hash = hash_token(&verification_token);
-        let expires_at = Utc::now() + Duration::hours(24); // 24 hour expiry
-
-        // Store verification token in database
-        sqlx::query!(
-            r#"
-            INSERT INTO email_verification_tokens (user_id, token, expires_at)
-            VALUES ($1, $2, $3)
-            ON CONFLICT (user_id) 
-            DO UPDATE SET token = EXCLUDED.token, expires_at = EXCLUDED.expires_at, is_used = false
-            "#,
-            user.id,
-            token_hash,
-            expires_at
-        )
-        .execute(&*self.pool)
-        .await?;
-
-        // TODO: Send email with verification token
-        // For now, we'll just log it (in production, use proper email service)
-        tracing::info!("Email verification token for {}: {}", email, verification_token);
-
-        Ok(())
-    }
-
     /// Get user sessions
     pub async fn get_user_sessions(&self, user_id: Uuid) -> Result<Vec<SessionInfo>, AppError> {
         self.session_manager.get_user_sessions(user_id).await
@@ -722,4 +903,17 @@ impl AuthService {
     /// Revoke a specific session
     pub async fn revoke_session(&self, session_id: Uuid, user_id: Uuid) -> Result<(), AppError> {
         self.session_manager.revoke_session(session_id, user_id).await
-    }
\ No newline at end of file
+    }
+
+/// The error a locked out login gets, telling the client when to try again
+fn locked_out(lockout: &LoginLockout) -> AppError {
+    let retry_after_seconds = lockout
+        .locked_until
+        .map(|until| (until - Utc::now()).num_seconds().max(1))
+        .unwrap_or(1);
+
+    AppError::RateLimited {
+        message: "Too many failed sign-in attempts; try again later".to_string(),
+        retry_after_seconds,
+    }
+}
@@ -0,0 +1,574 @@
+use crate::error::{AppError, FieldError};
+use crate::models::system_settings::SystemSetting;
+use super::session_manager::parse_user_agent;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Setting holding the lockout policy
+const POLICY_KEY: &str = "login_lockout_policy";
+const POLICY_CACHE_SECONDS: u64 = 60;
+
+/// When repeated failed logins lock an account or an IP address out, and whether logins
+/// from unknown devices are reported to the account owner
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LockoutPolicy {
+    pub enabled: bool,
+    /// Failed logins to one account within the window that lock it
+    pub account_max_failures: i32,
+    /// Failed logins from one IP address, to any account, within the window that lock it
+    pub ip_max_failures: i32,
+    pub failure_window_minutes: i64,
+    /// Length of the first lockout; each further one doubles it
+    pub base_lockout_seconds: i64,
+    pub max_lockout_minutes: i64,
+    /// After this long without failures, the next lockout is a first one again
+    pub reset_after_hours: i64,
+    /// Email the account owner when a login comes from a device not seen before
+    pub new_device_alerts: bool,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            account_max_failures: 5,
+            ip_max_failures: 20,
+            failure_window_minutes: 15,
+            base_lockout_seconds: 60,
+            max_lockout_minutes: 1440,
+            reset_after_hours: 24,
+            new_device_alerts: true,
+        }
+    }
+}
+
+impl LockoutPolicy {
+    pub fn validate(&self) -> Result<(), AppError> {
+        let mut errors = Vec::new();
+
+        if self.account_max_failures < 1 {
+            errors.push(
+                FieldError::new("account_max_failures", "below_minimum", "Must allow at least one failure").with_limit(1),
+            );
+        }
+        if self.ip_max_failures < 1 {
+            errors.push(FieldError::new("ip_max_failures", "below_minimum", "Must allow at least one failure").with_limit(1));
+        }
+        if !(1..=1440).contains(&self.failure_window_minutes) {
+            errors.push(
+                FieldError::new(
+                    "failure_window_minutes",
+                    "out_of_range",
+                    "The failure window must be between 1 minute and 1 day",
+                )
+                .with_limit(1440),
+            );
+        }
+        if self.base_lockout_seconds < 1 {
+            errors.push(
+                FieldError::new("base_lockout_seconds", "below_minimum", "Lockouts must last at least a second")
+                    .with_limit(1),
+            );
+        }
+        if self.max_lockout_minutes * 60 < self.base_lockout_seconds {
+            errors.push(FieldError::new(
+                "max_lockout_minutes",
+                "below_minimum",
+                "The longest lockout cannot be shorter than the first",
+            ));
+        }
+        if self.reset_after_hours < 1 {
+            errors.push(FieldError::new("reset_after_hours", "below_minimum", "Must be at least an hour").with_limit(1));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::InvalidFields(errors))
+        }
+    }
+
+    /// Length of the `lockouts`-th lockout in a row: the base doubled each time, up to the maximum
+    pub fn lockout_duration(&self, lockouts: i32) -> Duration {
+        let doublings = lockouts.saturating_sub(1).clamp(0, 30) as u32;
+        let seconds = self.base_lockout_seconds.saturating_mul(1i64 << doublings);
+        Duration::seconds(seconds.min(self.max_lockout_minutes * 60))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum LockoutScope {
+    Account,
+    Ip,
+}
+
+/// Failed logins counted against one account or one IP address
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LoginLockout {
+    pub id: Uuid,
+    pub scope: LockoutScope,
+    /// The user id for account counters, the address for IP counters
+    pub subject: String,
+    pub user_id: Option<Uuid>,
+    /// Failures since the last lockout, within the failure window
+    pub failures: i32,
+    /// Lockouts in a row; the next one lasts twice as long
+    pub lockouts: i32,
+    pub last_failure_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LoginLockout {
+    pub fn is_locked(&self, now: DateTime<Utc>) -> bool {
+        self.locked_until.is_some_and(|until| until > now)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+pub struct LoginLockoutQuery {
+    pub scope: Option<LockoutScope>,
+    /// Only counters that are locked right now; defaults to true
+    pub locked: Option<bool>,
+    pub limit: Option<i64>,
+}
+
+/// A counter after one more failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FailureCount {
+    pub failures: i32,
+    pub lockouts: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+/// Count one more failure against a counter. Failures older than the window are forgotten,
+/// and so are earlier lockouts after `reset_after_hours` without failures. Reaching
+/// `max_failures` locks the subject and starts the count again.
+pub fn count_failure(
+    previous: &LoginLockout,
+    max_failures: i32,
+    policy: &LockoutPolicy,
+    now: DateTime<Utc>,
+) -> FailureCount {
+    let quiet_for = now - previous.last_failure_at;
+
+    let mut failures = if quiet_for > Duration::minutes(policy.failure_window_minutes) {
+        1
+    } else {
+        previous.failures + 1
+    };
+    let mut lockouts = if quiet_for > Duration::hours(policy.reset_after_hours) {
+        0
+    } else {
+        previous.lockouts
+    };
+    let mut locked_until = previous.locked_until;
+
+    if failures >= max_failures {
+        lockouts += 1;
+        failures = 0;
+        locked_until = Some(now + policy.lockout_duration(lockouts));
+    }
+
+    FailureCount { failures, lockouts, locked_until }
+}
+
+/// Stable id of a device from its user agent. Digits are dropped so browser and OS updates
+/// don't make a known device look new.
+pub fn device_fingerprint(user_agent: Option<&str>) -> String {
+    let normalized = user_agent
+        .unwrap_or("")
+        .chars()
+        .filter(|c| !c.is_ascii_digit())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+    hex::encode(&Sha256::digest(normalized.as_bytes())[..16])
+}
+
+/// A readable name for a device, e.g. "Chrome on Windows"
+pub fn device_label(user_agent: Option<&str>) -> String {
+    let Some(user_agent) = user_agent.filter(|ua| !ua.trim().is_empty()) else {
+        return "Unknown device".to_string();
+    };
+
+    let device = parse_user_agent(user_agent);
+    match (device.browser, device.os) {
+        (Some(browser), Some(os)) => format!("{} on {}", browser, os),
+        (Some(browser), None) => format!("{} ({})", browser, device.device_type),
+        (None, Some(os)) => format!("{} {}", os, device.device_type),
+        (None, None) => format!("Unknown {}", device.device_type),
+    }
+}
+
+const LOCKOUT_COLUMNS: &str = "id, scope, subject, user_id, failures, lockouts, last_failure_at, locked_until, created_at";
+
+/// Login guard counts failed logins per account and per IP address and locks either out
+/// for exponentially longer after repeated failures, so credential stuffing gets a handful
+/// of guesses per account and per address. It also remembers the devices each user signed
+/// in from.
+#[derive(Clone)]
+pub struct LoginGuard {
+    pool: Arc<PgPool>,
+    policy_cache: Arc<RwLock<Option<(Instant, LockoutPolicy)>>>,
+}
+
+impl LoginGuard {
+    pub fn new(pool: Arc<PgPool>) -> Self {
+        Self {
+            pool,
+            policy_cache: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// The lockout in force for the account or the address, if any
+    pub async fn check(
+        &self,
+        user_id: Option<Uuid>,
+        ip_address: Option<IpAddr>,
+    ) -> Result<Option<LoginLockout>, AppError> {
+        if !self.get_policy().await?.enabled {
+            return Ok(None);
+        }
+
+        let lockout = sqlx::query_as::<_, LoginLockout>(&format!(
+            r#"
+            SELECT {} FROM login_lockouts
+            WHERE ((scope = 'account' AND subject = $1) OR (scope = 'ip' AND subject = $2))
+              AND locked_until > NOW()
+            ORDER BY locked_until DESC
+            LIMIT 1
+            "#,
+            LOCKOUT_COLUMNS
+        ))
+        .bind(user_id.map(|user_id| user_id.to_string()))
+        .bind(ip_address.map(|ip| ip.to_string()))
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        Ok(lockout)
+    }
+
+    /// Count a failed login against the account, when the email matched one, and the
+    /// address. Returns the lockout it caused, if any.
+    pub async fn record_failure(
+        &self,
+        user_id: Option<Uuid>,
+        ip_address: Option<IpAddr>,
+    ) -> Result<Option<LoginLockout>, AppError> {
+        let policy = self.get_policy().await?;
+        if !policy.enabled {
+            return Ok(None);
+        }
+
+        let mut caused = None;
+        for (scope, subject) in subjects(user_id, ip_address) {
+            let max_failures = match scope {
+                LockoutScope::Account => policy.account_max_failures,
+                LockoutScope::Ip => policy.ip_max_failures,
+            };
+            let owner = user_id.filter(|_| scope == LockoutScope::Account);
+            let counter = self.count(scope, &subject, owner, max_failures, &policy).await?;
+
+            // Reaching the limit starts the count again, so a fresh lockout has no failures
+            if counter.failures == 0 && counter.is_locked(Utc::now()) {
+                warn!(
+                    "Locked out {:?} {} until {} after repeated failed logins (lockout {} in a row)",
+                    scope,
+                    subject,
+                    counter.locked_until.map(|until| until.to_rfc3339()).unwrap_or_default(),
+                    counter.lockouts
+                );
+                caused = Some(counter);
+            }
+        }
+
+        Ok(caused)
+    }
+
+    /// A successful login clears the account's failures; the address keeps its count, since
+    /// one hit during credential stuffing must not reset it
+    pub async fn record_success(&self, user_id: Uuid) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM login_lockouts WHERE scope = 'account' AND subject = $1")
+            .bind(user_id.to_string())
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Counters, most recently failed first (admin)
+    pub async fn list(&self, query: &LoginLockoutQuery) -> Result<Vec<LoginLockout>, AppError> {
+        let lockouts = sqlx::query_as::<_, LoginLockout>(&format!(
+            r#"
+            SELECT {} FROM login_lockouts
+            WHERE ($1::varchar IS NULL OR scope = $1)
+              AND (NOT $2 OR locked_until > NOW())
+            ORDER BY last_failure_at DESC
+            LIMIT $3
+            "#,
+            LOCKOUT_COLUMNS
+        ))
+        .bind(query.scope)
+        .bind(query.locked.unwrap_or(true))
+        .bind(query.limit.unwrap_or(50).clamp(1, 200))
+        .fetch_all(&*self.pool)
+        .await?;
+
+        Ok(lockouts)
+    }
+
+    /// Lift a lockout and forget its failures, so the next one is a first one again (admin)
+    pub async fn clear(&self, lockout_id: Uuid) -> Result<LoginLockout, AppError> {
+        let lockout = sqlx::query_as::<_, LoginLockout>(&format!(
+            "DELETE FROM login_lockouts WHERE id = $1 RETURNING {}",
+            LOCKOUT_COLUMNS
+        ))
+        .bind(lockout_id)
+        .fetch_optional(&*self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Lockout not found".to_string()))?;
+
+        info!("Cleared login lockout of {:?} {}", lockout.scope, lockout.subject);
+
+        Ok(lockout)
+    }
+
+    /// Remember the device a user signed in from. Returns whether it is new to an account
+    /// that already had known devices; the first device recorded for an account is not.
+    pub async fn record_device(
+        &self,
+        user_id: Uuid,
+        user_agent: Option<&str>,
+        ip_address: Option<IpAddr>,
+    ) -> Result<bool, AppError> {
+        let has_devices: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM login_devices WHERE user_id = $1)")
+            .bind(user_id)
+            .fetch_one(&*self.pool)
+            .await?;
+
+        let inserted: bool = sqlx::query_scalar(
+            r#"
+            INSERT INTO login_devices (user_id, fingerprint, label, last_ip)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (user_id, fingerprint) DO UPDATE SET last_ip = EXCLUDED.last_ip, last_seen_at = NOW()
+            RETURNING (xmax = 0)
+            "#,
+        )
+        .bind(user_id)
+        .bind(device_fingerprint(user_agent))
+        .bind(device_label(user_agent))
+        .bind(ip_address)
+        .fetch_one(&*self.pool)
+        .await?;
+
+        Ok(inserted && has_devices)
+    }
+
+    /// The policy in force; a missing or unreadable setting falls back to the defaults
+    pub async fn get_policy(&self) -> Result<LockoutPolicy, AppError> {
+        if let Some((loaded_at, policy)) = self.policy_cache.read().await.as_ref() {
+            if loaded_at.elapsed().as_secs() < POLICY_CACHE_SECONDS {
+                return Ok(policy.clone());
+            }
+        }
+
+        let policy = match SystemSetting::get_by_key(&self.pool, POLICY_KEY).await? {
+            Some(setting) => serde_json::from_value(setting.value).unwrap_or_else(|e| {
+                warn!("Invalid {} setting, using defaults: {}", POLICY_KEY, e);
+                LockoutPolicy::default()
+            }),
+            None => LockoutPolicy::default(),
+        };
+
+        *self.policy_cache.write().await = Some((Instant::now(), policy.clone()));
+        Ok(policy)
+    }
+
+    /// Replace the policy; lockouts already in force keep their end time
+    pub async fn update_policy(&self, policy: LockoutPolicy) -> Result<LockoutPolicy, AppError> {
+        policy.validate()?;
+
+        SystemSetting::upsert(
+            &self.pool,
+            POLICY_KEY.to_string(),
+            serde_json::to_value(&policy).map_err(|e| AppError::Internal(e.to_string()))?,
+            Some("Lockout of accounts and IP addresses after repeated failed logins, and new device alerts".to_string()),
+            false,
+        )
+        .await?;
+
+        *self.policy_cache.write().await = Some((Instant::now(), policy.clone()));
+        Ok(policy)
+    }
+
+    // Private helper methods
+
+    async fn count(
+        &self,
+        scope: LockoutScope,
+        subject: &str,
+        user_id: Option<Uuid>,
+        max_failures: i32,
+        policy: &LockoutPolicy,
+    ) -> Result<LoginLockout, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        // A new counter starts with no failures, as of now
+        sqlx::query(
+            "INSERT INTO login_lockouts (scope, subject, user_id) VALUES ($1, $2, $3) ON CONFLICT (scope, subject) DO NOTHING",
+        )
+        .bind(scope)
+        .bind(subject)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let previous = sqlx::query_as::<_, LoginLockout>(&format!(
+            "SELECT {} FROM login_lockouts WHERE scope = $1 AND subject = $2 FOR UPDATE",
+            LOCKOUT_COLUMNS
+        ))
+        .bind(scope)
+        .bind(subject)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let now = Utc::now();
+        let count = count_failure(&previous, max_failures, policy, now);
+
+        let counter = sqlx::query_as::<_, LoginLockout>(&format!(
+            r#"
+            UPDATE login_lockouts SET failures = $1, lockouts = $2, locked_until = $3, last_failure_at = $4
+            WHERE id = $5
+            RETURNING {}
+            "#,
+            LOCKOUT_COLUMNS
+        ))
+        .bind(count.failures)
+        .bind(count.lockouts)
+        .bind(count.locked_until)
+        .bind(now)
+        .bind(previous.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(counter)
+    }
+}
+
+/// Counters a login attempt is checked and counted against
+fn subjects(user_id: Option<Uuid>, ip_address: Option<IpAddr>) -> Vec<(LockoutScope, String)> {
+    user_id
+        .map(|user_id| (LockoutScope::Account, user_id.to_string()))
+        .into_iter()
+        .chain(ip_address.map(|ip| (LockoutScope::Ip, ip.to_string())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter(failures: i32, lockouts: i32, last_failure_minutes_ago: i64) -> LoginLockout {
+        let now = Utc::now();
+        LoginLockout {
+            id: Uuid::new_v4(),
+            scope: LockoutScope::Account,
+            subject: Uuid::new_v4().to_string(),
+            user_id: None,
+            failures,
+            lockouts,
+            last_failure_at: now - Duration::minutes(last_failure_minutes_ago),
+            locked_until: None,
+            created_at: now,
+        }
+    }
+
+    #[test]
+    fn test_lockout_duration_doubles_up_to_the_maximum() {
+        let policy = LockoutPolicy::default();
+
+        assert_eq!(policy.lockout_duration(1), Duration::seconds(60));
+        assert_eq!(policy.lockout_duration(2), Duration::seconds(120));
+        assert_eq!(policy.lockout_duration(5), Duration::seconds(960));
+        assert_eq!(policy.lockout_duration(20), Duration::minutes(1440));
+        assert_eq!(policy.lockout_duration(1000), Duration::minutes(1440));
+    }
+
+    #[test]
+    fn test_count_failure_locks_at_the_limit() {
+        let policy = LockoutPolicy::default();
+        let now = Utc::now();
+
+        let below = count_failure(&counter(3, 0, 1), 5, &policy, now);
+        assert_eq!(below, FailureCount { failures: 4, lockouts: 0, locked_until: None });
+
+        let locked = count_failure(&counter(4, 0, 1), 5, &policy, now);
+        assert_eq!(locked.failures, 0);
+        assert_eq!(locked.lockouts, 1);
+        assert_eq!(locked.locked_until, Some(now + Duration::seconds(60)));
+
+        let again = count_failure(&counter(4, 2, 1), 5, &policy, now);
+        assert_eq!(again.lockouts, 3);
+        assert_eq!(again.locked_until, Some(now + Duration::seconds(240)));
+    }
+
+    #[test]
+    fn test_count_failure_forgets_old_failures_and_lockouts() {
+        let policy = LockoutPolicy::default();
+        let now = Utc::now();
+
+        // Outside the 15 minute window, counting starts again
+        assert_eq!(count_failure(&counter(4, 2, 30), 5, &policy, now).failures, 1);
+        assert_eq!(count_failure(&counter(4, 2, 30), 5, &policy, now).lockouts, 2);
+
+        // A day without failures makes the next lockout a first one
+        let fresh = count_failure(&counter(4, 3, 25 * 60), 1, &policy, now);
+        assert_eq!(fresh.lockouts, 1);
+        assert_eq!(fresh.locked_until, Some(now + Duration::seconds(60)));
+    }
+
+    #[test]
+    fn test_device_fingerprint_survives_version_updates() {
+        let old = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36";
+        let new = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36";
+        let firefox = "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:127.0) Gecko/20100101 Firefox/127.0";
+
+        assert_eq!(device_fingerprint(Some(old)), device_fingerprint(Some(new)));
+        assert_ne!(device_fingerprint(Some(old)), device_fingerprint(Some(firefox)));
+        assert_eq!(device_fingerprint(None), device_fingerprint(Some("")));
+
+        assert_eq!(device_label(Some(old)), "Chrome on Windows");
+        assert_eq!(device_label(None), "Unknown device");
+    }
+
+    #[test]
+    fn test_policy_validation() {
+        assert!(LockoutPolicy::default().validate().is_ok());
+
+        let policy = LockoutPolicy { account_max_failures: 0, max_lockout_minutes: 0, ..LockoutPolicy::default() };
+        match policy.validate() {
+            Err(AppError::InvalidFields(fields)) => {
+                let names: Vec<&str> = fields.iter().map(|f| f.field.as_str()).collect();
+                assert_eq!(names, vec!["account_max_failures", "max_lockout_minutes"]);
+            }
+            other => panic!("expected invalid fields, got {:?}", other),
+        }
+    }
+}
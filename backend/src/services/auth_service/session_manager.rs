@@ -1,5 +1,5 @@
 use crate::error::AppError;
-use crate::models::UserSession;
+use crate::models::{Notification, NotificationType, UserSession};
 use crate::utils::crypto::{hash_token, generate_secure_token};
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
@@ -188,6 +188,55 @@ impl SessionManager {
         Ok(result.rows_affected())
     }
 
+    /// Revoke every session and token of a user and tell them why
+    ///
+    /// Bumping the token version rejects access tokens that are still within their expiry,
+    /// which deactivating sessions alone would leave usable. Returns the sessions ended.
+    pub async fn invalidate_all(&self, user_id: Uuid, reason: RevocationReason) -> Result<u64, AppError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE users SET token_version = token_version + 1, updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let result = sqlx::query(
+            "UPDATE user_sessions SET is_active = false, updated_at = NOW() WHERE user_id = $1 AND is_active = true",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        // The revocation stands even if the notice can't be stored
+        if let Err(e) = Notification::create(
+            &self.pool,
+            user_id,
+            "You've been signed out".to_string(),
+            reason.message().to_string(),
+            NotificationType::SecurityAlert,
+            Some(serde_json::json!({
+                "event": "sessions_revoked",
+                "reason": reason.as_str(),
+                "sessions_revoked": result.rows_affected(),
+            })),
+        )
+        .await
+        {
+            tracing::warn!("Failed to notify user {} of session revocation: {}", user_id, e);
+        }
+
+        tracing::info!(
+            "Revoked {} sessions and all tokens for user {} ({})",
+            result.rows_affected(),
+            user_id,
+            reason.as_str()
+        );
+
+        Ok(result.rows_affected())
+    }
+
     /// Update session activity
     pub async fn update_session_activity(
         &self,
@@ -275,45 +324,7 @@ impl SessionManager {
 
     /// Parse device information from user agent
     fn parse_device_info(&self, user_agent: &str) -> DeviceInfo {
-        // This is a simplified parser. In production, you'd use a proper user agent parser
-        let device_type = if user_agent.contains("Mobile") {
-            "mobile".to_string()
-        } else if user_agent.contains("Tablet") {
-            "tablet".to_string()
-        } else {
-            "desktop".to_string()
-        };
-
-        let browser = if user_agent.contains("Chrome") {
-            Some("Chrome".to_string())
-        } else if user_agent.contains("Firefox") {
-            Some("Firefox".to_string())
-        } else if user_agent.contains("Safari") {
-            Some("Safari".to_string())
-        } else {
-            None
-        };
-
-        let os = if user_agent.contains("Windows") {
-            Some("Windows".to_string())
-        } else if user_agent.contains("Mac") {
-            Some("macOS".to_string())
-        } else if user_agent.contains("Linux") {
-            Some("Linux".to_string())
-        } else if user_agent.contains("Android") {
-            Some("Android".to_string())
-        } else if user_agent.contains("iOS") {
-            Some("iOS".to_string())
-        } else {
-            None
-        };
-
-        DeviceInfo {
-            device_type,
-            browser,
-            os,
-            user_agent: user_agent.to_string(),
-        }
+        parse_user_agent(user_agent)
     }
 
     /// Start background cleanup task
@@ -374,6 +385,37 @@ impl SessionManager {
     }
 }
 
+/// Why all of a user's sessions were revoked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationReason {
+    PasswordChanged,
+    PasswordReset,
+    RoleChanged,
+    Banned,
+}
+
+impl RevocationReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PasswordChanged => "password_changed",
+            Self::PasswordReset => "password_reset",
+            Self::RoleChanged => "role_changed",
+            Self::Banned => "banned",
+        }
+    }
+
+    /// Notification text shown to the user
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::PasswordChanged => "Your password was changed, so every device was signed out. Sign in again with the new password.",
+            Self::PasswordReset => "Your password was reset, so every device was signed out. If this wasn't you, contact support.",
+            Self::RoleChanged => "Your account permissions changed. Sign in again to continue.",
+            Self::Banned => "Your account has been suspended and every device was signed out.",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionStatistics {
     pub total_sessions: u32,
@@ -382,6 +424,49 @@ pub struct SessionStatistics {
     pub average_session_duration: u64, // in seconds
 }
 
+/// Device type, browser and OS named by a user agent
+pub fn parse_user_agent(user_agent: &str) -> DeviceInfo {
+    // This is a simplified parser. In production, you'd use a proper user agent parser
+    let device_type = if user_agent.contains("Mobile") {
+        "mobile".to_string()
+    } else if user_agent.contains("Tablet") {
+        "tablet".to_string()
+    } else {
+        "desktop".to_string()
+    };
+
+    let browser = if user_agent.contains("Chrome") {
+        Some("Chrome".to_string())
+    } else if user_agent.contains("Firefox") {
+        Some("Firefox".to_string())
+    } else if user_agent.contains("Safari") {
+        Some("Safari".to_string())
+    } else {
+        None
+    };
+
+    let os = if user_agent.contains("Windows") {
+        Some("Windows".to_string())
+    } else if user_agent.contains("Mac") {
+        Some("macOS".to_string())
+    } else if user_agent.contains("Linux") {
+        Some("Linux".to_string())
+    } else if user_agent.contains("Android") {
+        Some("Android".to_string())
+    } else if user_agent.contains("iOS") {
+        Some("iOS".to_string())
+    } else {
+        None
+    };
+
+    DeviceInfo {
+        device_type,
+        browser,
+        os,
+        user_agent: user_agent.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -417,6 +502,15 @@ mod tests {
         assert!(session.is_active);
     }
 
+    #[test]
+    fn test_revocation_reason_names() {
+        assert_eq!(RevocationReason::RoleChanged.as_str(), "role_changed");
+        assert_eq!(
+            serde_json::to_value(RevocationReason::PasswordReset).unwrap(),
+            serde_json::json!("password_reset")
+        );
+    }
+
     #[tokio::test]
     async fn test_device_info_parsing() {
         let pool = Arc::new(setup_test_db().await);
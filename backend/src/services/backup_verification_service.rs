@@ -0,0 +1,565 @@
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const DEFAULT_MAX_AGE_HOURS: i64 = 26;
+const DEFAULT_RESTORE_TIMEOUT_MINUTES: i64 = 60;
+const DEFAULT_ROW_COUNT_TOLERANCE_PERCENT: i64 = 1;
+/// How often the backup directory is checked for a dump that hasn't been drilled yet
+const CHECK_INTERVAL_SECONDS: u64 = 900;
+const BACKUP_EXTENSION: &str = "dump";
+/// Tail of pg_restore's stderr kept on a failed run
+const MAX_ERROR_CHARS: usize = 2000;
+
+/// Tables whose restored row counts are compared with the source; all have `created_at`
+const ROW_COUNT_TABLES: &[&str] = &[
+    "users",
+    "items",
+    "raffles",
+    "box_purchases",
+    "raffle_prize_tiers",
+    "user_credits",
+    "credit_transactions",
+    "credit_holds",
+    "transactions",
+];
+
+/// Ledger invariants checked on the restored copy; each query counts the rows breaking it
+const LEDGER_INVARIANTS: &[(&str, &str)] = &[
+    (
+        "raffle_box_counts_match_purchases",
+        "SELECT COUNT(*) FROM raffles r
+         WHERE r.boxes_sold <> (SELECT COUNT(*) FROM box_purchases bp WHERE bp.raffle_id = r.id)",
+    ),
+    (
+        "raffle_boxes_within_capacity",
+        "SELECT COUNT(*) FROM raffles WHERE boxes_sold > total_boxes",
+    ),
+    (
+        "credit_holds_covered_by_credits",
+        "SELECT COUNT(*) FROM (
+             SELECT user_id, SUM(amount) AS held FROM credit_holds WHERE status = 'held' GROUP BY user_id
+         ) h
+         WHERE h.held > (
+             SELECT COALESCE(SUM(c.amount), 0) FROM user_credits c
+             WHERE c.user_id = h.user_id AND c.is_used = false AND c.expired_at IS NULL
+         )",
+    ),
+    (
+        "prize_winners_drawn",
+        "SELECT COUNT(*) FROM raffle_prize_tiers t
+         JOIN raffles r ON r.id = t.raffle_id
+         WHERE t.winner_user_id IS NOT NULL AND r.status NOT IN ('completed', 'drawing')",
+    ),
+];
+
+const RUN_COLUMNS: &str = "id, backup_file, backup_size_bytes, backup_taken_at, status, checks, error, \
+    triggered_by, started_at, finished_at";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DrillStatus {
+    Running,
+    /// Restored cleanly and every check passed
+    Passed,
+    /// The restore failed, the backup was stale, or a check did not pass
+    Failed,
+}
+
+/// Outcome of one integrity check on a restored backup
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct IntegrityCheck {
+    pub name: String,
+    pub passed: bool,
+    /// Row counts: the source's rows as of the backup. Invariants: zero violations.
+    pub expected: i64,
+    pub actual: i64,
+}
+
+/// One restore drill of a backup file
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BackupVerificationRun {
+    pub id: Uuid,
+    pub backup_file: String,
+    pub backup_size_bytes: i64,
+    pub backup_taken_at: DateTime<Utc>,
+    pub status: DrillStatus,
+    #[schema(value_type = Vec<IntegrityCheck>)]
+    pub checks: Json<Vec<IntegrityCheck>>,
+    pub error: Option<String>,
+    pub triggered_by: Option<Uuid>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// A dump file found in the backup directory
+#[derive(Debug, Clone)]
+struct BackupFile {
+    name: String,
+    path: PathBuf,
+    size_bytes: i64,
+    taken_at: DateTime<Utc>,
+}
+
+/// Where backups come from and where drills restore them
+#[derive(Debug, Clone)]
+struct DrillTarget {
+    backup_dir: PathBuf,
+    scratch_database_url: String,
+    pg_restore_path: String,
+}
+
+/// Backup verification service restores each new logical backup into a scratch database
+/// and checks it against the live one, so a backup is only trusted once it has restored.
+///
+/// Results are kept in `backup_verification_runs` and exported as metrics for alerting.
+#[derive(Clone)]
+pub struct BackupVerificationService {
+    db_pool: PgPool,
+    target: Option<DrillTarget>,
+}
+
+impl BackupVerificationService {
+    /// Drills are disabled unless `BACKUP_DIR` and `BACKUP_SCRATCH_DATABASE_URL` are set
+    pub fn from_config(db_pool: PgPool, config: &AppConfig) -> Self {
+        let target = match (&config.backup_dir, &config.backup_scratch_database_url) {
+            (Some(backup_dir), Some(scratch_database_url)) => Some(DrillTarget {
+                backup_dir: PathBuf::from(backup_dir),
+                scratch_database_url: scratch_database_url.clone(),
+                pg_restore_path: config.pg_restore_path.clone().unwrap_or_else(|| "pg_restore".to_string()),
+            }),
+            _ => None,
+        };
+
+        Self { db_pool, target }
+    }
+
+    /// Start the loop that drills each new backup as it appears
+    pub async fn start_background_tasks(&self) {
+        let Some(target) = &self.target else {
+            warn!("BACKUP_DIR is not set; backups will not be verified");
+            return;
+        };
+
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(CHECK_INTERVAL_SECONDS));
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.verify_new_backup().await {
+                    error!("Scheduled backup verification failed: {}", e);
+                }
+            }
+        });
+
+        info!("Backup verification started for {}", target.backup_dir.display());
+    }
+
+    /// Drill the newest backup unless it has been drilled already
+    pub async fn verify_new_backup(&self) -> Result<Option<BackupVerificationRun>, AppError> {
+        let target = self.target()?;
+        let Some(backup) = latest_backup(&target.backup_dir).await? else {
+            warn!("No backups found in {}", target.backup_dir.display());
+            return Ok(None);
+        };
+
+        let drilled = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM backup_verification_runs WHERE backup_file = $1)",
+        )
+        .bind(&backup.name)
+        .fetch_one(&self.db_pool)
+        .await?;
+        if drilled {
+            return Ok(None);
+        }
+
+        let run = self.begin(&backup, None).await?;
+        self.complete(run, backup).await.map(Some)
+    }
+
+    /// Drill the newest backup now, even if it was drilled before; the drill runs in the background
+    pub async fn start_drill(&self, triggered_by: Uuid) -> Result<BackupVerificationRun, AppError> {
+        let target = self.target()?;
+        let backup = latest_backup(&target.backup_dir)
+            .await?
+            .ok_or_else(|| AppError::NotFound("No backups found".to_string()))?;
+
+        let run = self.begin(&backup, Some(triggered_by)).await?;
+        info!("Admin {} started a restore drill of {}", triggered_by, backup.name);
+
+        let service = self.clone();
+        let started = run.clone();
+        tokio::spawn(async move {
+            if let Err(e) = service.complete(started, backup).await {
+                error!("Restore drill failed: {}", e);
+            }
+        });
+
+        Ok(run)
+    }
+
+    /// Past drills, newest first
+    pub async fn list_runs(&self, limit: i64, offset: i64) -> Result<Vec<BackupVerificationRun>, AppError> {
+        let runs = sqlx::query_as::<_, BackupVerificationRun>(&format!(
+            "SELECT {} FROM backup_verification_runs ORDER BY started_at DESC LIMIT $1 OFFSET $2",
+            RUN_COLUMNS
+        ))
+        .bind(limit.clamp(1, 200))
+        .bind(offset.max(0))
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(runs)
+    }
+
+    pub async fn get_run(&self, run_id: Uuid) -> Result<BackupVerificationRun, AppError> {
+        sqlx::query_as::<_, BackupVerificationRun>(&format!(
+            "SELECT {} FROM backup_verification_runs WHERE id = $1",
+            RUN_COLUMNS
+        ))
+        .bind(run_id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Backup verification run not found".to_string()))
+    }
+
+    // Private helper methods
+
+    fn target(&self) -> Result<&DrillTarget, AppError> {
+        self.target
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("Backup verification is not configured".to_string()))
+    }
+
+    /// Record the start of a drill; only one may use the scratch database at a time
+    async fn begin(&self, backup: &BackupFile, triggered_by: Option<Uuid>) -> Result<BackupVerificationRun, AppError> {
+        let timeout_minutes = self
+            .get_setting("backup_restore_timeout_minutes", DEFAULT_RESTORE_TIMEOUT_MINUTES)
+            .await?;
+
+        // A drill still running well past the timeout died with its process
+        sqlx::query(
+            r#"
+            UPDATE backup_verification_runs
+            SET status = 'failed', error = 'Drill did not finish', finished_at = NOW()
+            WHERE status = 'running' AND started_at < NOW() - make_interval(mins => $1)
+            "#,
+        )
+        .bind((timeout_minutes * 2) as i32)
+        .execute(&self.db_pool)
+        .await?;
+
+        let run = sqlx::query_as::<_, BackupVerificationRun>(&format!(
+            r#"
+            INSERT INTO backup_verification_runs (backup_file, backup_size_bytes, backup_taken_at, triggered_by)
+            SELECT $1, $2, $3, $4
+            WHERE NOT EXISTS (SELECT 1 FROM backup_verification_runs WHERE status = 'running')
+            RETURNING {}
+            "#,
+            RUN_COLUMNS
+        ))
+        .bind(&backup.name)
+        .bind(backup.size_bytes)
+        .bind(backup.taken_at)
+        .bind(triggered_by)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        run.ok_or_else(|| AppError::Conflict("A restore drill is already running".to_string()))
+    }
+
+    /// Restore, check and record the outcome; a failed drill is a result, not an error
+    async fn complete(&self, run: BackupVerificationRun, backup: BackupFile) -> Result<BackupVerificationRun, AppError> {
+        let (checks, failure) = match self.drill(&backup).await {
+            Ok(checks) => {
+                let failed: Vec<&str> = checks.iter().filter(|c| !c.passed).map(|c| c.name.as_str()).collect();
+                let failure = (!failed.is_empty()).then(|| format!("Checks failed: {}", failed.join(", ")));
+                (checks, failure)
+            }
+            Err(e) => (Vec::new(), Some(e)),
+        };
+
+        let status = if failure.is_some() { DrillStatus::Failed } else { DrillStatus::Passed };
+        let finished = sqlx::query_as::<_, BackupVerificationRun>(&format!(
+            r#"
+            UPDATE backup_verification_runs
+            SET status = $2, checks = $3, error = $4, finished_at = NOW()
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            RUN_COLUMNS
+        ))
+        .bind(run.id)
+        .bind(status)
+        .bind(Json(&checks))
+        .bind(&failure)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        match &failure {
+            Some(reason) => error!("Backup {} failed verification: {}", backup.name, reason),
+            None => info!("Backup {} restored and passed {} checks", backup.name, checks.len()),
+        }
+
+        Ok(finished)
+    }
+
+    async fn drill(&self, backup: &BackupFile) -> Result<Vec<IntegrityCheck>, String> {
+        let target = self.target().map_err(|e| e.to_string())?;
+        let max_age_hours = self
+            .get_setting("backup_max_age_hours", DEFAULT_MAX_AGE_HOURS)
+            .await
+            .map_err(|e| e.to_string())?;
+        let timeout_minutes = self
+            .get_setting("backup_restore_timeout_minutes", DEFAULT_RESTORE_TIMEOUT_MINUTES)
+            .await
+            .map_err(|e| e.to_string())?;
+        let tolerance_percent = self
+            .get_setting("backup_row_count_tolerance_percent", DEFAULT_ROW_COUNT_TOLERANCE_PERCENT)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let age_hours = (Utc::now() - backup.taken_at).num_hours();
+        if age_hours > max_age_hours {
+            return Err(format!(
+                "Newest backup is {} hours old; backups are expected at least every {} hours",
+                age_hours, max_age_hours
+            ));
+        }
+
+        let scratch = PgPoolOptions::new()
+            .max_connections(2)
+            .connect(&target.scratch_database_url)
+            .await
+            .map_err(|e| format!("Could not connect to the scratch database: {}", e))?;
+
+        let result = async {
+            reset_scratch(&scratch).await?;
+            restore(target, &backup.path, Duration::from_secs(timeout_minutes as u64 * 60)).await?;
+
+            let mut checks = self.row_count_checks(&scratch, tolerance_percent).await?;
+            checks.extend(ledger_checks(&scratch).await?);
+            Ok(checks)
+        }
+        .await;
+
+        // Leave nothing readable behind: the scratch copy holds the full production dataset
+        if let Err(e) = reset_scratch(&scratch).await {
+            warn!("Failed to clear the scratch database after a drill: {}", e);
+        }
+        scratch.close().await;
+
+        result
+    }
+
+    /// Compare restored row counts with the source's rows created up to the backup
+    async fn row_count_checks(&self, scratch: &PgPool, tolerance_percent: i64) -> Result<Vec<IntegrityCheck>, String> {
+        let latest_row = ROW_COUNT_TABLES
+            .iter()
+            .map(|table| format!("SELECT MAX(created_at) AS created_at FROM {}", table))
+            .collect::<Vec<_>>()
+            .join(" UNION ALL ");
+        let snapshot_at = sqlx::query_scalar::<_, Option<DateTime<Utc>>>(&format!(
+            "SELECT MAX(created_at) FROM ({}) latest",
+            latest_row
+        ))
+        .fetch_one(scratch)
+        .await
+        .map_err(|e| format!("Restored database is missing core tables: {}", e))?
+        .unwrap_or_else(Utc::now);
+
+        let mut checks = Vec::with_capacity(ROW_COUNT_TABLES.len());
+        for table in ROW_COUNT_TABLES {
+            let restored = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {}", table))
+                .fetch_one(scratch)
+                .await
+                .map_err(|e| format!("Could not count restored {}: {}", table, e))?;
+            let source = sqlx::query_scalar::<_, i64>(&format!(
+                "SELECT COUNT(*) FROM {} WHERE created_at <= $1 OR created_at IS NULL",
+                table
+            ))
+            .bind(snapshot_at)
+            .fetch_one(&self.db_pool)
+            .await
+            .map_err(|e| format!("Could not count source {}: {}", table, e))?;
+
+            checks.push(IntegrityCheck {
+                name: format!("row_count:{}", table),
+                passed: within_tolerance(source, restored, tolerance_percent),
+                expected: source,
+                actual: restored,
+            });
+        }
+
+        Ok(checks)
+    }
+
+    async fn get_setting(&self, key: &str, default: i64) -> Result<i64, AppError> {
+        Ok(SystemSetting::get_by_key(&self.db_pool, key)
+            .await?
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default))
+    }
+}
+
+async fn ledger_checks(scratch: &PgPool) -> Result<Vec<IntegrityCheck>, String> {
+    let mut checks = Vec::with_capacity(LEDGER_INVARIANTS.len());
+    for (name, query) in LEDGER_INVARIANTS {
+        let violations = sqlx::query_scalar::<_, i64>(query)
+            .fetch_one(scratch)
+            .await
+            .map_err(|e| format!("Could not check {}: {}", name, e))?;
+
+        checks.push(IntegrityCheck {
+            name: format!("invariant:{}", name),
+            passed: violations == 0,
+            expected: 0,
+            actual: violations,
+        });
+    }
+
+    Ok(checks)
+}
+
+async fn reset_scratch(scratch: &PgPool) -> Result<(), String> {
+    for statement in ["DROP SCHEMA IF EXISTS public CASCADE", "CREATE SCHEMA public"] {
+        sqlx::query(statement)
+            .execute(scratch)
+            .await
+            .map_err(|e| format!("Could not reset the scratch database: {}", e))?;
+    }
+
+    Ok(())
+}
+
+async fn restore(target: &DrillTarget, path: &Path, timeout: Duration) -> Result<(), String> {
+    let child = Command::new(&target.pg_restore_path)
+        .arg("--no-owner")
+        .arg("--no-privileges")
+        .arg("--exit-on-error")
+        .arg("--dbname")
+        .arg(&target.scratch_database_url)
+        .arg(path)
+        .kill_on_drop(true)
+        .output();
+
+    let output = tokio::time::timeout(timeout, child)
+        .await
+        .map_err(|_| format!("Restore did not finish within {} minutes", timeout.as_secs() / 60))?
+        .map_err(|e| format!("Could not run {}: {}", target.pg_restore_path, e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "pg_restore exited with {}: {}",
+            output.status,
+            error_tail(&String::from_utf8_lossy(&output.stderr))
+        ));
+    }
+
+    Ok(())
+}
+
+/// Newest `*.dump` file in the backup directory
+async fn latest_backup(dir: &Path) -> Result<Option<BackupFile>, AppError> {
+    let mut entries = tokio::fs::read_dir(dir)
+        .await
+        .map_err(|e| AppError::Internal(format!("Could not read backup directory {}: {}", dir.display(), e)))?;
+
+    let mut latest: Option<BackupFile> = None;
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| AppError::Internal(format!("Could not read backup directory: {}", e)))?
+    {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !is_backup_file(&name) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+        let Ok(modified) = metadata.modified() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+
+        let taken_at = DateTime::<Utc>::from(modified);
+        if latest.as_ref().map_or(true, |l| taken_at > l.taken_at) {
+            latest = Some(BackupFile {
+                name,
+                path: entry.path(),
+                size_bytes: metadata.len() as i64,
+                taken_at,
+            });
+        }
+    }
+
+    Ok(latest)
+}
+
+fn is_backup_file(name: &str) -> bool {
+    !name.starts_with('.')
+        && Path::new(name).extension().and_then(|e| e.to_str()) == Some(BACKUP_EXTENSION)
+}
+
+/// Restored rows may fall short of the source only by rows deleted since the backup
+fn within_tolerance(source: i64, restored: i64, tolerance_percent: i64) -> bool {
+    let allowed = (source.max(restored) * tolerance_percent + 99) / 100;
+    (source - restored).abs() <= allowed
+}
+
+fn error_tail(stderr: &str) -> String {
+    let stderr = stderr.trim();
+    let skip = stderr.chars().count().saturating_sub(MAX_ERROR_CHARS);
+    stderr.chars().skip(skip).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_backup_file() {
+        assert!(is_backup_file("thriftee-2024-01-15T0300.dump"));
+        assert!(!is_backup_file("thriftee-2024-01-15T0300.sql"));
+        assert!(!is_backup_file(".thriftee-2024-01-15T0300.dump"));
+        assert!(!is_backup_file("dump"));
+    }
+
+    #[test]
+    fn test_within_tolerance() {
+        assert!(within_tolerance(1000, 1000, 1));
+        // Ten rows deleted since the backup is within 1%
+        assert!(within_tolerance(990, 1000, 1));
+        assert!(!within_tolerance(900, 1000, 1));
+        // Missing rows in the restore are as bad as extra ones
+        assert!(!within_tolerance(1000, 500, 1));
+        // Small tables get at least one row of slack
+        assert!(within_tolerance(4, 5, 1));
+        assert!(within_tolerance(0, 0, 1));
+    }
+
+    #[test]
+    fn test_error_tail_keeps_the_end() {
+        let stderr = format!("{}pg_restore: error: could not execute query", "x".repeat(5000));
+        let tail = error_tail(&stderr);
+        assert_eq!(tail.chars().count(), MAX_ERROR_CHARS);
+        assert!(tail.ends_with("could not execute query"));
+    }
+}
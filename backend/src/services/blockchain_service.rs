@@ -20,7 +20,7 @@ impl BlockchainService {
     /// Initialize the blockchain service
     pub async fn new(
         network_name: &str,
-        private_key: &str,
+        signer: &SignerConfig,
         master_seed: &[u8],
         config: BlockchainConfig,
     ) -> Result<Self> {
@@ -35,25 +35,27 @@ impl BlockchainService {
 
         // Initialize blockchain client
         let client = Arc::new(
-            BlockchainClient::new(network_config, private_key, Some(config.retry_config.clone()))
+            BlockchainClient::new(network_config, signer, Some(config.retry_config.clone()))
                 .await
                 .map_err(|e| anyhow::anyhow!("Failed to create blockchain client: {}", e))?,
         );
 
-        // Initialize transaction manager
-        let transaction_manager = Arc::new(
-            TransactionManager::new(client.clone())
-                .await
-                .map_err(|e| anyhow::anyhow!("Failed to create transaction manager: {}", e))?,
-        );
-
         // Initialize gas manager
         let gas_manager = Arc::new(
-            GasManager::new(client.clone(), None)
+            GasManager::new(client.clone(), Some(config.gas.clone()))
                 .await
                 .map_err(|e| anyhow::anyhow!("Failed to create gas manager: {}", e))?,
         );
 
+        // Initialize transaction manager
+        let transaction_manager = Arc::new(
+            TransactionManager::new(client.clone())
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to create transaction manager: {}", e))?
+                .with_gas_manager(gas_manager.clone())
+                .with_nonce_config(config.nonce.clone()),
+        );
+
         // Initialize wallet manager
         let wallet_manager = Arc::new(WalletManager::new());
         wallet_manager
@@ -162,6 +164,14 @@ impl BlockchainService {
             .map_err(|e| anyhow::anyhow!("Failed to get raffle: {}", e))
     }
 
+    /// Get a raffle's sold, revenue and winner summary
+    pub async fn get_raffle_stats(&self, raffle_id: u64) -> Result<RaffleStats> {
+        self.contract_client
+            .get_raffle_stats(raffle_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to get raffle stats: {}", e))
+    }
+
     /// Get multiple raffles
     pub async fn get_raffles(&self, raffle_ids: Vec<u64>) -> Result<Vec<RaffleData>> {
         self.contract_client
@@ -235,6 +245,14 @@ impl BlockchainService {
             .map_err(|e| anyhow::anyhow!("Failed to cancel raffle: {}", e))
     }
 
+    /// Ask the contract to request randomness and draw a raffle's winners
+    pub async fn request_random_winner(&self, raffle_id: u64) -> Result<uuid::Uuid> {
+        self.contract_client
+            .request_random_winner(raffle_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to request random winner: {}", e))
+    }
+
     /// Pause the contract (admin only)
     pub async fn pause_contract(&self) -> Result<uuid::Uuid> {
         self.contract_client
@@ -264,6 +282,31 @@ impl BlockchainService {
         self.contract_client.contract_address()
     }
 
+    /// Cancel a stuck transaction by taking its nonce with an empty self-transfer
+    pub async fn cancel_transaction(&self, tx_id: uuid::Uuid) -> Result<ethers::types::H256> {
+        self.transaction_manager
+            .cancel_transaction(tx_id)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to cancel transaction: {}", e))
+    }
+
+    /// Re-send a stuck transaction at higher fees, at least `max_fee_per_gas` if given
+    pub async fn speed_up_transaction(
+        &self,
+        tx_id: uuid::Uuid,
+        max_fee_per_gas: Option<ethers::types::U256>,
+    ) -> Result<ethers::types::H256> {
+        self.transaction_manager
+            .speed_up_transaction(tx_id, max_fee_per_gas)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to speed up transaction: {}", e))
+    }
+
+    /// Nonces handed out per signing wallet
+    pub async fn nonce_snapshots(&self) -> Vec<crate::blockchain::nonce::WalletNonceSnapshot> {
+        self.transaction_manager.nonce_snapshots().await
+    }
+
     /// Cleanup old transactions
     pub async fn cleanup_old_transactions(&self, max_age_hours: u64) {
         self.transaction_manager
@@ -279,12 +322,14 @@ impl BlockchainService {
             .get_gas_statistics()
             .await
             .map_err(|e| anyhow::anyhow!("Failed to get gas statistics: {}", e))?;
+        let gas_spend = self.gas_manager.get_spend_summary().await;
         let wallet_stats = self.wallet_manager.get_wallet_statistics().await;
         let pending_transactions = self.transaction_manager.get_pending_transactions().await;
 
         Ok(BlockchainStatistics {
             network_health,
             gas_statistics: gas_stats,
+            gas_spend,
             wallet_statistics: wallet_stats,
             pending_transaction_count: pending_transactions.len(),
             contract_address: self.get_contract_address(),
@@ -303,6 +348,23 @@ impl BlockchainService {
             }
         });
 
+        // Start nonce maintenance task: re-sync with the chain, cancel given-up transactions, fill gaps
+        let tx_manager = self.transaction_manager.clone();
+        let sync_interval = self.config.nonce.sync_interval.max(1);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(sync_interval));
+            loop {
+                interval.tick().await;
+                match tx_manager.maintain_nonces().await {
+                    Ok(snapshot) if !snapshot.gaps.is_empty() => {
+                        warn!("Nonces {:?} of {:?} are gaps holding back later transactions", snapshot.gaps, snapshot.address);
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!("Nonce maintenance failed: {}", e),
+                }
+            }
+        });
+
         // Start wallet cleanup task
         let wallet_manager = self.wallet_manager.clone();
         tokio::spawn(async move {
@@ -324,6 +386,7 @@ impl BlockchainService {
 pub struct BlockchainStatistics {
     pub network_health: NetworkHealth,
     pub gas_statistics: crate::blockchain::gas::GasStatistics,
+    pub gas_spend: crate::blockchain::gas::GasSpendSummary,
     pub wallet_statistics: crate::blockchain::wallet::WalletStatistics,
     pub pending_transaction_count: usize,
     pub contract_address: ethers::types::Address,
@@ -415,5 +478,63 @@ pub fn load_blockchain_config() -> Result<BlockchainConfig> {
         default_network,
         retry_config: RetryConfig::default(),
         monitoring: MonitoringConfig::default(),
+        gas: load_gas_config()?,
+        nonce: load_nonce_config()?,
     })
+}
+
+/// Fee strategy and spend caps; caps are in wei and unset means uncapped
+fn load_gas_config() -> Result<crate::blockchain::gas::GasConfig> {
+    let mut gas = crate::blockchain::gas::GasConfig::default();
+
+    if let Ok(strategy) = std::env::var("GAS_DEFAULT_STRATEGY") {
+        gas.default_strategy = serde_json::from_value(serde_json::Value::String(strategy.to_lowercase()))
+            .map_err(|_| anyhow::anyhow!("GAS_DEFAULT_STRATEGY must be slow, standard or fast"))?;
+    }
+    if let Ok(blocks) = std::env::var("GAS_FEE_HISTORY_BLOCKS") {
+        gas.fee_history_blocks = blocks
+            .parse()
+            .map_err(|_| anyhow::anyhow!("GAS_FEE_HISTORY_BLOCKS must be a number of blocks"))?;
+    }
+    gas.max_tx_spend = wei_from_env("GAS_MAX_TX_SPEND_WEI")?;
+    gas.daily_spend_cap = wei_from_env("GAS_DAILY_SPEND_CAP_WEI")?;
+
+    Ok(gas)
+}
+
+/// How many transactions each signing wallet may have in flight and how nonce gaps are handled
+fn load_nonce_config() -> Result<crate::blockchain::nonce::NonceConfig> {
+    let mut nonce = crate::blockchain::nonce::NonceConfig::default();
+
+    if let Ok(max_in_flight) = std::env::var("TX_MAX_IN_FLIGHT") {
+        nonce.max_in_flight = max_in_flight
+            .parse()
+            .ok()
+            .filter(|max: &usize| *max > 0)
+            .ok_or_else(|| anyhow::anyhow!("TX_MAX_IN_FLIGHT must be a positive number of transactions"))?;
+    }
+    nonce.queue_timeout = secs_from_env("TX_NONCE_QUEUE_TIMEOUT_SECS", nonce.queue_timeout)?;
+    nonce.sync_interval = secs_from_env("TX_NONCE_SYNC_INTERVAL_SECS", nonce.sync_interval)?;
+    nonce.gap_fill_after = secs_from_env("TX_NONCE_GAP_FILL_AFTER_SECS", nonce.gap_fill_after)?;
+
+    Ok(nonce)
+}
+
+fn secs_from_env(key: &str, default: u64) -> Result<u64> {
+    match std::env::var(key) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("{} must be a number of seconds", key)),
+        Err(_) => Ok(default),
+    }
+}
+
+fn wei_from_env(key: &str) -> Result<Option<ethers::types::U256>> {
+    match std::env::var(key) {
+        Ok(value) => ethers::types::U256::from_dec_str(value.trim())
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("{} must be a whole number of wei", key)),
+        Err(_) => Ok(None),
+    }
 }
\ No newline at end of file
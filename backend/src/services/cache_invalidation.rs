@@ -0,0 +1,644 @@
+use crate::error::AppError;
+use crate::services::cache_service::{CacheConfig, CacheService};
+use crate::services::realtime_service::{RealtimeEvent, RealtimeService};
+use futures_util::StreamExt;
+use redis::{AsyncCommands, Client as RedisClient};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Replicas announce invalidations to each other on this Redis channel
+const INVALIDATION_CHANNEL: &str = "raffle_platform:cache_invalidation";
+/// Local copies are kept at most this long, which bounds staleness if a pub/sub message is lost
+const LOCAL_TTL: Duration = Duration::from_secs(5);
+/// Past this many local copies new ones aren't kept until expired ones are swept
+const MAX_LOCAL_ENTRIES: usize = 10_000;
+/// Wait before resubscribing after the pub/sub connection drops
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Data a cached read depends on. Writes invalidate the tags they touch and every entry read
+/// under an older version of one of those tags stops being served.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheTag {
+    /// A raffle's grid, boxes sold and status
+    Raffle(Uuid),
+    /// Raffle search pages
+    RaffleListings,
+    /// An item's detail
+    Item(Uuid),
+    /// Item search pages
+    ItemListings,
+    /// A seller's onboarding checklist, by the seller's user ID
+    Seller(Uuid),
+    /// Seller search pages
+    SellerListings,
+    /// A user's credit balance
+    UserCredits(Uuid),
+}
+
+impl CacheTag {
+    /// Metrics label; IDs are left out to keep cardinality bounded
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CacheTag::Raffle(_) => "raffle",
+            CacheTag::RaffleListings => "raffle_listings",
+            CacheTag::Item(_) => "item",
+            CacheTag::ItemListings => "item_listings",
+            CacheTag::Seller(_) => "seller",
+            CacheTag::SellerListings => "seller_listings",
+            CacheTag::UserCredits(_) => "user_credits",
+        }
+    }
+
+    /// Redis counter holding the tag's current version
+    fn version_key(&self) -> String {
+        match self {
+            CacheTag::Raffle(id) | CacheTag::Item(id) | CacheTag::Seller(id) | CacheTag::UserCredits(id) => {
+                format!("tag_version:{}:{}", self.kind(), id)
+            }
+            CacheTag::RaffleListings | CacheTag::ItemListings | CacheTag::SellerListings => {
+                format!("tag_version:{}", self.kind())
+            }
+        }
+    }
+
+    /// Unparameterized API route the edge cache (Varnish) keeps a copy of, if any
+    fn edge_path(&self) -> Option<&'static str> {
+        match self {
+            CacheTag::RaffleListings => Some("/api/v1/raffles"),
+            CacheTag::ItemListings => Some("/api/v1/items"),
+            _ => None,
+        }
+    }
+}
+
+/// Tags a domain event makes stale. Services invalidate their own writes as they commit; this
+/// catches writes whose only trace is the event, such as an item created for a seller whose
+/// checklist another service caches.
+pub fn tags_for_event(event: &RealtimeEvent) -> Vec<CacheTag> {
+    match event {
+        RealtimeEvent::RaffleCreated { raffle_id, item_id, seller_id, .. } => vec![
+            CacheTag::Raffle(*raffle_id),
+            CacheTag::RaffleListings,
+            CacheTag::Item(*item_id),
+            CacheTag::ItemListings,
+            CacheTag::Seller(*seller_id),
+        ],
+        RealtimeEvent::RaffleOpened { raffle_id, item_id, .. }
+        | RealtimeEvent::RaffleFull { raffle_id, item_id, .. }
+        | RealtimeEvent::WinnerSelected { raffle_id, item_id, .. }
+        | RealtimeEvent::RaffleCancelled { raffle_id, item_id, .. } => vec![
+            CacheTag::Raffle(*raffle_id),
+            CacheTag::RaffleListings,
+            CacheTag::Item(*item_id),
+        ],
+        RealtimeEvent::BoxPurchased { raffle_id, .. } => vec![CacheTag::Raffle(*raffle_id), CacheTag::RaffleListings],
+        RealtimeEvent::ItemCreated { item_id, seller_id, .. } => vec![
+            CacheTag::Item(*item_id),
+            CacheTag::ItemListings,
+            CacheTag::Seller(*seller_id),
+        ],
+        // Raffle listings carry their item
+        RealtimeEvent::ItemUpdated { item_id, .. } | RealtimeEvent::ItemStockChanged { item_id, .. } => vec![
+            CacheTag::Item(*item_id),
+            CacheTag::ItemListings,
+            CacheTag::RaffleListings,
+        ],
+        RealtimeEvent::CreditsIssued { user_id, .. }
+        | RealtimeEvent::CreditsRedeemed { user_id, .. }
+        | RealtimeEvent::CreditBalanceChanged { user_id, .. } => vec![CacheTag::UserCredits(*user_id)],
+        // Sent with the purchase's box_purchased events, which invalidate the raffle
+        RealtimeEvent::GridChanged { .. }
+        | RealtimeEvent::OddsUpdated { .. }
+        | RealtimeEvent::CommentPosted { .. }
+        | RealtimeEvent::CommentRemoved { .. }
+        | RealtimeEvent::UserJoined { .. }
+        | RealtimeEvent::UserLeft { .. }
+        | RealtimeEvent::PromotionStarted { .. }
+        | RealtimeEvent::PromotionEnded { .. }
+        | RealtimeEvent::SystemMaintenance { .. }
+        | RealtimeEvent::SystemAlert { .. } => Vec::new(),
+    }
+}
+
+/// Read-through cache whose entries are invalidated by tag.
+///
+/// Entries live in Redis under keys embedding the current version of every tag they were read
+/// under, so invalidating is a single counter bump and a read racing a write is stored under a
+/// version nobody asks for again. Each replica also keeps a short-lived local copy of what it
+/// read; invalidations purge those here and, over Redis pub/sub, on every other replica.
+/// Without Redis every read goes to the database and invalidation is a no-op; cache errors are
+/// logged and never fail the read or write that hit them.
+#[derive(Clone, Default)]
+pub struct TaggedCache {
+    redis: Option<RedisBackend>,
+    edge_purge: Option<EdgePurge>,
+    local: Arc<LocalCache>,
+    stats: Arc<InvalidationStats>,
+}
+
+#[derive(Clone)]
+struct RedisBackend {
+    cache: Arc<CacheService>,
+    client: RedisClient,
+    /// Tells this replica's own pub/sub messages apart from the others'
+    instance_id: Uuid,
+}
+
+#[derive(Clone)]
+struct EdgePurge {
+    http_client: reqwest::Client,
+    base_url: String,
+}
+
+#[derive(Default)]
+struct LocalCache {
+    entries: RwLock<HashMap<String, LocalEntry>>,
+    /// Bumped by every purge, so a load that started before one isn't kept after it
+    generation: AtomicU64,
+}
+
+struct LocalEntry {
+    value: serde_json::Value,
+    tags: Vec<CacheTag>,
+    expires_at: Instant,
+}
+
+/// Where an invalidation came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InvalidationOrigin {
+    /// A write on this replica
+    Write,
+    /// A domain event on this replica
+    Event,
+    /// Another replica, over pub/sub
+    Remote,
+}
+
+impl InvalidationOrigin {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InvalidationOrigin::Write => "write",
+            InvalidationOrigin::Event => "event",
+            InvalidationOrigin::Remote => "remote",
+        }
+    }
+}
+
+#[derive(Default)]
+struct InvalidationStats {
+    counts: Mutex<HashMap<(&'static str, InvalidationOrigin), u64>>,
+}
+
+/// Tag invalidations since startup, by tag kind and origin
+#[derive(Debug, Clone)]
+pub struct InvalidationCount {
+    pub tag: &'static str,
+    pub origin: InvalidationOrigin,
+    pub count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InvalidationMessage {
+    origin: Uuid,
+    tags: Vec<CacheTag>,
+}
+
+impl TaggedCache {
+    pub fn new(client: RedisClient) -> Self {
+        Self {
+            redis: Some(RedisBackend {
+                cache: Arc::new(CacheService::new(client.clone(), CacheConfig::default())),
+                client,
+                instance_id: Uuid::new_v4(),
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Send PURGE to the edge cache for listing routes it keeps a copy of
+    pub fn with_edge_purge(mut self, base_url: String) -> Self {
+        self.edge_purge = Some(EdgePurge {
+            http_client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(2))
+                .build()
+                .unwrap_or_default(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        });
+        self
+    }
+
+    /// Purge on other replicas' invalidations and on this replica's domain events
+    pub async fn start_background_tasks(&self, realtime_service: &RealtimeService) {
+        let Some(redis) = self.redis.clone() else { return };
+
+        let cache = self.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = cache.follow_remote_invalidations(&redis).await {
+                    warn!("Cache invalidation subscription dropped, resubscribing: {}", e);
+                }
+                // Messages may have been missed while disconnected
+                cache.local.clear();
+                tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+            }
+        });
+
+        let cache = self.clone();
+        let mut events = realtime_service.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        let tags = tags_for_event(&event);
+                        if !tags.is_empty() {
+                            cache.invalidate_from(&tags, InvalidationOrigin::Event).await;
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Cache invalidation fell {} domain events behind; dropping local copies", missed);
+                        cache.local.clear();
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        info!("Cache invalidation background tasks started");
+    }
+
+    /// Serve `key` from the cache while none of `tags` has been invalidated, loading and caching
+    /// it for `ttl` otherwise
+    pub async fn get_or_load<T, F, Fut>(
+        &self,
+        key: &str,
+        tags: &[CacheTag],
+        ttl: Duration,
+        load: F,
+    ) -> Result<T, AppError>
+    where
+        T: Serialize + for<'de> Deserialize<'de> + Debug,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        self.get_or_load_with_ttl(key, tags, |_| ttl, load).await
+    }
+
+    /// Like `get_or_load`, for values that go stale on their own at a time they carry
+    pub async fn get_or_load_with_ttl<T, L, F, Fut>(
+        &self,
+        key: &str,
+        tags: &[CacheTag],
+        ttl: L,
+        load: F,
+    ) -> Result<T, AppError>
+    where
+        T: Serialize + for<'de> Deserialize<'de> + Debug,
+        L: FnOnce(&T) -> Duration,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        let Some(redis) = &self.redis else {
+            return load().await;
+        };
+
+        if let Some(value) = self.local.get(key) {
+            return Ok(value);
+        }
+        let generation = self.local.generation();
+
+        let Some(entry_key) = redis.entry_key(key, tags).await else {
+            return load().await;
+        };
+
+        let value = match redis.cache.get::<T>(&entry_key).await {
+            Ok(Some(value)) => value,
+            Ok(None) => {
+                let value = load().await?;
+                let ttl = ttl(&value);
+                if ttl.is_zero() {
+                    return Ok(value);
+                }
+                if let Err(e) = redis.cache.set(&entry_key, &value, Some(ttl)).await {
+                    warn!("Failed to cache {}: {}", key, e);
+                }
+                self.local.insert(key, &value, tags, ttl.min(LOCAL_TTL), generation);
+                return Ok(value);
+            }
+            Err(e) => {
+                warn!("Cache read failed for {}, loading from the database: {}", key, e);
+                return load().await;
+            }
+        };
+
+        // The Redis entry outlives nothing it depends on, so the local copy can't either
+        if let Ok(Some(remaining)) = redis.cache.ttl(&entry_key).await {
+            self.local.insert(key, &value, tags, remaining.min(LOCAL_TTL), generation);
+        }
+
+        Ok(value)
+    }
+
+    /// Stop serving every entry read under these tags, on every replica. Call after the write
+    /// has committed.
+    pub async fn invalidate(&self, tags: &[CacheTag]) {
+        self.invalidate_from(tags, InvalidationOrigin::Write).await;
+    }
+
+    /// Tag invalidations since startup, for the metrics endpoint
+    pub fn invalidation_counts(&self) -> Vec<InvalidationCount> {
+        self.stats.snapshot()
+    }
+
+    async fn invalidate_from(&self, tags: &[CacheTag], origin: InvalidationOrigin) {
+        if let Some(redis) = &self.redis {
+            self.local.purge(tags);
+            self.stats.record(tags, origin);
+
+            for tag in tags {
+                let version_key = tag.version_key();
+                match redis.cache.increment(&version_key, 1).await {
+                    Ok(version) => debug!("Invalidated cache tag {} (now version {})", version_key, version),
+                    Err(e) => warn!("Failed to invalidate cache tag {}: {}", version_key, e),
+                }
+            }
+
+            redis.announce(tags).await;
+        }
+
+        if let Some(edge_purge) = &self.edge_purge {
+            for path in tags.iter().filter_map(CacheTag::edge_path) {
+                edge_purge.purge(path).await;
+            }
+        }
+    }
+
+    async fn follow_remote_invalidations(&self, redis: &RedisBackend) -> Result<(), redis::RedisError> {
+        let mut pubsub = redis.client.get_async_connection().await?.into_pubsub();
+        pubsub.subscribe(INVALIDATION_CHANNEL).await?;
+        debug!("Subscribed to {}", INVALIDATION_CHANNEL);
+
+        let mut messages = pubsub.on_message();
+        while let Some(message) = messages.next().await {
+            let payload: String = message.get_payload()?;
+            match serde_json::from_str::<InvalidationMessage>(&payload) {
+                Ok(message) if message.origin != redis.instance_id => {
+                    self.local.purge(&message.tags);
+                    self.stats.record(&message.tags, InvalidationOrigin::Remote);
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Ignoring malformed cache invalidation message: {}", e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl RedisBackend {
+    async fn entry_key(&self, key: &str, tags: &[CacheTag]) -> Option<String> {
+        let mut versions = Vec::with_capacity(tags.len());
+        for tag in tags {
+            match self.cache.get::<i64>(&tag.version_key()).await {
+                Ok(version) => versions.push(version.unwrap_or(0)),
+                Err(e) => {
+                    warn!("Failed to read cache tag {}, loading from the database: {}", tag.version_key(), e);
+                    return None;
+                }
+            }
+        }
+
+        Some(versioned_key(key, &versions))
+    }
+
+    /// Tell the other replicas to drop their local copies
+    async fn announce(&self, tags: &[CacheTag]) {
+        let message = InvalidationMessage {
+            origin: self.instance_id,
+            tags: tags.to_vec(),
+        };
+        let Ok(payload) = serde_json::to_string(&message) else { return };
+
+        let published = async {
+            let mut conn = self.client.get_async_connection().await?;
+            conn.publish::<_, _, ()>(INVALIDATION_CHANNEL, payload).await
+        };
+        if let Err(e) = published.await {
+            warn!("Failed to announce cache invalidation to other replicas: {}", e);
+        }
+    }
+}
+
+impl LocalCache {
+    fn get<T: for<'de> Deserialize<'de>>(&self, key: &str) -> Option<T> {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        let entry = entries.get(key).filter(|entry| entry.expires_at > Instant::now())?;
+        serde_json::from_value(entry.value.clone()).ok()
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Keep a copy unless a purge happened since `generation` was read
+    fn insert<T: Serialize>(&self, key: &str, value: &T, tags: &[CacheTag], ttl: Duration, generation: u64) {
+        let Ok(value) = serde_json::to_value(value) else { return };
+
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        if self.generation() != generation || ttl.is_zero() {
+            return;
+        }
+        if entries.len() >= MAX_LOCAL_ENTRIES {
+            let now = Instant::now();
+            entries.retain(|_, entry| entry.expires_at > now);
+            if entries.len() >= MAX_LOCAL_ENTRIES {
+                return;
+            }
+        }
+
+        entries.insert(
+            key.to_string(),
+            LocalEntry {
+                value,
+                tags: tags.to_vec(),
+                expires_at: Instant::now() + ttl,
+            },
+        );
+    }
+
+    fn purge(&self, tags: &[CacheTag]) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        entries.retain(|_, entry| !entry.tags.iter().any(|tag| tags.contains(tag)));
+    }
+
+    fn clear(&self) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        entries.clear();
+    }
+}
+
+impl InvalidationStats {
+    fn record(&self, tags: &[CacheTag], origin: InvalidationOrigin) {
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        for tag in tags {
+            *counts.entry((tag.kind(), origin)).or_default() += 1;
+        }
+    }
+
+    fn snapshot(&self) -> Vec<InvalidationCount> {
+        let counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        counts
+            .iter()
+            .map(|((tag, origin), count)| InvalidationCount {
+                tag,
+                origin: *origin,
+                count: *count,
+            })
+            .collect()
+    }
+}
+
+impl EdgePurge {
+    async fn purge(&self, path: &str) {
+        let url = format!("{}{}", self.base_url, path);
+        let method = reqwest::Method::from_bytes(b"PURGE").expect("PURGE is a valid method token");
+
+        match self.http_client.request(method, &url).send().await {
+            Ok(response) if response.status().is_success() => debug!("Purged {} from the edge cache", url),
+            Ok(response) => warn!("Edge cache refused to purge {}: {}", url, response.status()),
+            Err(e) => warn!("Failed to purge {} from the edge cache: {}", url, e),
+        }
+    }
+}
+
+/// Cache key for a query, stable for equal parameters
+pub fn query_key<P: Serialize>(prefix: &str, params: &P) -> String {
+    let params = serde_json::to_vec(params).unwrap_or_default();
+    format!("{}:{}", prefix, hex::encode(Sha256::digest(&params)))
+}
+
+fn versioned_key(key: &str, versions: &[i64]) -> String {
+    let versions: Vec<String> = versions.iter().map(|version| version.to_string()).collect();
+    format!("{}@{}", key, versions.join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_versioned_key_changes_with_any_tag() {
+        let before = versioned_key("raffle_grid:1", &[3, 7]);
+
+        assert_eq!(before, "raffle_grid:1@3.7");
+        assert_ne!(before, versioned_key("raffle_grid:1", &[4, 7]));
+        assert_ne!(before, versioned_key("raffle_grid:1", &[3, 8]));
+    }
+
+    #[test]
+    fn test_query_key_is_stable_per_params() {
+        let first = query_key("item_search", &("shoes", 20));
+
+        assert_eq!(first, query_key("item_search", &("shoes", 20)));
+        assert_ne!(first, query_key("item_search", &("shoes", 50)));
+        assert_ne!(first, query_key("raffle_search", &("shoes", 20)));
+    }
+
+    #[test]
+    fn test_tags_are_distinct() {
+        let id = Uuid::new_v4();
+
+        assert_ne!(CacheTag::Raffle(id).version_key(), CacheTag::UserCredits(id).version_key());
+        assert_ne!(CacheTag::Item(id).version_key(), CacheTag::Seller(id).version_key());
+        assert_ne!(CacheTag::RaffleListings.version_key(), CacheTag::ItemListings.version_key());
+        assert_eq!(CacheTag::Raffle(id).edge_path(), None);
+        assert_eq!(CacheTag::ItemListings.edge_path(), Some("/api/v1/items"));
+    }
+
+    #[test]
+    fn test_item_created_invalidates_seller_checklist() {
+        let (item_id, seller_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let tags = tags_for_event(&RealtimeEvent::ItemCreated {
+            item_id,
+            seller_id,
+            name: "Lamp".to_string(),
+            category: None,
+            created_at: Utc::now(),
+        });
+
+        assert!(tags.contains(&CacheTag::Item(item_id)));
+        assert!(tags.contains(&CacheTag::ItemListings));
+        assert!(tags.contains(&CacheTag::Seller(seller_id)));
+    }
+
+    #[test]
+    fn test_purchase_invalidates_grid_but_not_items() {
+        let raffle_id = Uuid::new_v4();
+        let tags = tags_for_event(&RealtimeEvent::BoxPurchased {
+            raffle_id,
+            user_id: Uuid::new_v4(),
+            box_number: 7,
+            boxes_remaining: 3,
+            completion_percentage: 70.0,
+            purchased_at: Utc::now(),
+        });
+
+        assert_eq!(tags, vec![CacheTag::Raffle(raffle_id), CacheTag::RaffleListings]);
+    }
+
+    #[test]
+    fn test_local_purge_by_tag() {
+        let local = LocalCache::default();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        local.insert("grid:a", &1, &[CacheTag::Raffle(a)], LOCAL_TTL, local.generation());
+        local.insert("grid:b", &2, &[CacheTag::Raffle(b)], LOCAL_TTL, local.generation());
+
+        local.purge(&[CacheTag::Raffle(a)]);
+
+        assert_eq!(local.get::<i32>("grid:a"), None);
+        assert_eq!(local.get::<i32>("grid:b"), Some(2));
+    }
+
+    #[test]
+    fn test_local_insert_skipped_after_purge() {
+        let local = LocalCache::default();
+        let raffle_id = Uuid::new_v4();
+        let generation = local.generation();
+
+        // An invalidation lands while the value is loading
+        local.purge(&[CacheTag::Raffle(raffle_id)]);
+        local.insert("grid", &1, &[CacheTag::Raffle(raffle_id)], LOCAL_TTL, generation);
+
+        assert_eq!(local.get::<i32>("grid"), None);
+    }
+
+    #[test]
+    fn test_invalidation_counts() {
+        let stats = InvalidationStats::default();
+        stats.record(&[CacheTag::RaffleListings, CacheTag::Raffle(Uuid::new_v4())], InvalidationOrigin::Write);
+        stats.record(&[CacheTag::RaffleListings], InvalidationOrigin::Remote);
+
+        let count = |tag: &str, origin| {
+            stats
+                .snapshot()
+                .into_iter()
+                .find(|c| c.tag == tag && c.origin == origin)
+                .map(|c| c.count)
+        };
+        assert_eq!(count("raffle_listings", InvalidationOrigin::Write), Some(1));
+        assert_eq!(count("raffle_listings", InvalidationOrigin::Remote), Some(1));
+        assert_eq!(count("raffle", InvalidationOrigin::Write), Some(1));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::error::AppError;
+use crate::models::category::{
+    build_tree, normalize_attributes, Category, CategoryAttribute, CategoryNode, CreateAttributeRequest,
+    CreateCategoryRequest, UpdateAttributeRequest, UpdateCategoryRequest,
+};
+use crate::models::item::Item;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A category with the attributes its items can carry, inherited ones included
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CategoryDetail {
+    pub category: Category,
+    /// From the root down to the category's parent
+    pub ancestors: Vec<Category>,
+    pub attributes: Vec<CategoryAttribute>,
+}
+
+/// An item's place in the taxonomy
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ItemCategory {
+    pub item_id: Uuid,
+    pub category: Option<Category>,
+    #[schema(value_type = Object)]
+    pub attributes: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SetItemCategoryRequest {
+    /// Leave out to take the item out of any category
+    pub category_id: Option<i64>,
+    /// Values for the category's attributes, keyed by attribute key
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub attributes: Map<String, Value>,
+}
+
+/// Category service keeps the item taxonomy: a tree of categories, the typed attributes
+/// (brand, size, condition and so on) items in each can carry, and items' place in it.
+///
+/// Items keep the category's name in their free-text `category` column too, which fee
+/// schedules and full-text search already read.
+#[derive(Clone)]
+pub struct CategoryService {
+    db_pool: PgPool,
+}
+
+impl CategoryService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+
+    /// Active categories as a tree
+    pub async fn category_tree(&self) -> Result<Vec<CategoryNode>, AppError> {
+        let categories = Category::list(&self.db_pool, true).await?;
+        Ok(build_tree(&categories))
+    }
+
+    /// An active category, where it sits and its attributes
+    pub async fn category_detail(&self, slug: &str) -> Result<CategoryDetail, AppError> {
+        let category = Category::find_by_slug(&self.db_pool, slug)
+            .await?
+            .filter(|category| category.is_active)
+            .ok_or_else(|| AppError::NotFound("Category not found".to_string()))?;
+
+        self.detail(category).await
+    }
+
+    pub async fn item_category(&self, item_id: Uuid) -> Result<ItemCategory, AppError> {
+        let (category_id, attributes) = sqlx::query_as::<_, (Option<i64>, Value)>(
+            "SELECT category_id, attributes FROM items WHERE id = $1",
+        )
+        .bind(item_id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
+
+        let category = match category_id {
+            Some(id) => Category::find_by_id(&self.db_pool, id).await?,
+            None => None,
+        };
+
+        Ok(ItemCategory {
+            item_id,
+            category,
+            attributes: match attributes {
+                Value::Object(attributes) => attributes,
+                _ => Map::new(),
+            },
+        })
+    }
+
+    /// Put one of the seller's items in a category, with attribute values checked against it
+    pub async fn set_item_category(
+        &self,
+        item_id: Uuid,
+        seller_id: Uuid,
+        request: SetItemCategoryRequest,
+        is_admin: bool,
+    ) -> Result<ItemCategory, AppError> {
+        let item = Item::find_by_id(&self.db_pool, item_id).await?
+            .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
+
+        if !is_admin && item.seller_id != Some(seller_id) {
+            return Err(AppError::Forbidden("Item does not belong to seller".to_string()));
+        }
+
+        let category = match request.category_id {
+            Some(category_id) => {
+                let category = Category::find_by_id(&self.db_pool, category_id)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound("Category not found".to_string()))?;
+                if !Category::is_assignable(&self.db_pool, category_id).await? {
+                    return Err(AppError::Validation(format!("Category '{}' is not active", category.slug)));
+                }
+                Some(category)
+            }
+            None => None,
+        };
+
+        let definitions = CategoryAttribute::for_category(&self.db_pool, request.category_id).await?;
+        let attributes = normalize_attributes(&definitions, &request.attributes)?;
+
+        Category::assign_item(&self.db_pool, item_id, category.as_ref(), &attributes).await?;
+
+        info!(
+            "User {} put item {} in category {:?}",
+            seller_id,
+            item_id,
+            category.as_ref().map(|c| c.slug.as_str())
+        );
+
+        Ok(ItemCategory { item_id, category, attributes })
+    }
+
+    /// Every category, inactive ones included (admin)
+    pub async fn list_all_categories(&self) -> Result<Vec<Category>, AppError> {
+        Category::list(&self.db_pool, false).await
+    }
+
+    pub async fn create_category(&self, request: CreateCategoryRequest, admin_id: Uuid) -> Result<Category, AppError> {
+        let category = Category::create(&self.db_pool, request).await?;
+
+        info!("Admin {} created category {} ({})", admin_id, category.slug, category.id);
+
+        Ok(category)
+    }
+
+    pub async fn update_category(
+        &self,
+        category_id: i64,
+        request: UpdateCategoryRequest,
+        admin_id: Uuid,
+    ) -> Result<Category, AppError> {
+        let category = Category::update(&self.db_pool, category_id, request).await?;
+
+        info!("Admin {} updated category {}", admin_id, category.slug);
+
+        Ok(category)
+    }
+
+    pub async fn delete_category(&self, category_id: i64, admin_id: Uuid) -> Result<(), AppError> {
+        Category::delete(&self.db_pool, category_id).await?;
+
+        info!("Admin {} deleted category {}", admin_id, category_id);
+
+        Ok(())
+    }
+
+    pub async fn list_attributes(&self) -> Result<Vec<CategoryAttribute>, AppError> {
+        CategoryAttribute::list(&self.db_pool).await
+    }
+
+    pub async fn create_attribute(
+        &self,
+        request: CreateAttributeRequest,
+        admin_id: Uuid,
+    ) -> Result<CategoryAttribute, AppError> {
+        let attribute = CategoryAttribute::create(&self.db_pool, request).await?;
+
+        info!(
+            "Admin {} added {:?} attribute {} to category {:?}",
+            admin_id, attribute.attribute_type, attribute.key, attribute.category_id
+        );
+
+        Ok(attribute)
+    }
+
+    pub async fn update_attribute(
+        &self,
+        attribute_id: i64,
+        request: UpdateAttributeRequest,
+        admin_id: Uuid,
+    ) -> Result<CategoryAttribute, AppError> {
+        let attribute = CategoryAttribute::update(&self.db_pool, attribute_id, request).await?;
+
+        info!("Admin {} updated category attribute {}", admin_id, attribute.key);
+
+        Ok(attribute)
+    }
+
+    pub async fn delete_attribute(&self, attribute_id: i64, admin_id: Uuid) -> Result<(), AppError> {
+        CategoryAttribute::delete(&self.db_pool, attribute_id).await?;
+
+        info!("Admin {} deleted category attribute {}", admin_id, attribute_id);
+
+        Ok(())
+    }
+
+    // Private helper methods
+
+    async fn detail(&self, category: Category) -> Result<CategoryDetail, AppError> {
+        let mut ancestors = Vec::new();
+        let mut parent_id = category.parent_id;
+        while let Some(id) = parent_id {
+            // Moves that would make a cycle are refused; the bound guards against one left by hand
+            if ancestors.len() > 50 {
+                break;
+            }
+            let Some(parent) = Category::find_by_id(&self.db_pool, id).await? else {
+                break;
+            };
+            parent_id = parent.parent_id;
+            ancestors.push(parent);
+        }
+        ancestors.reverse();
+
+        let attributes = CategoryAttribute::for_category(&self.db_pool, Some(category.id)).await?;
+
+        Ok(CategoryDetail { category, ancestors, attributes })
+    }
+}
@@ -0,0 +1,586 @@
+use crate::blockchain::types::RaffleStats;
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use crate::services::blockchain_service::BlockchainService;
+use crate::services::data_fix_service::status_for_count;
+use chrono::{DateTime, Utc};
+use ethers::types::Address;
+use raffle_platform_shared::RaffleStatus;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// How often the scheduled run compares the chain with the database
+const RECONCILE_INTERVAL_SECONDS: u64 = 600;
+const DEFAULT_LOOKBACK_DAYS: i64 = 7;
+const DEFAULT_MAX_RAFFLES: i64 = 500;
+
+const RUN_COLUMNS: &str = "id, status, repair, raffles_checked, divergences, repaired, error, \
+    triggered_by, started_at, finished_at";
+
+const FINDING_COLUMNS: &str = "id, run_id, raffle_id, blockchain_raffle_id, kind, db_value, chain_value, \
+    repaired, created_at";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ReconciliationStatus {
+    Running,
+    Completed,
+    /// The run stopped early; findings recorded before the failure are kept
+    Failed,
+}
+
+/// Ways a raffle row can disagree with its contract
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DivergenceKind {
+    /// `boxes_sold` differs from the boxes the contract has sold
+    BoxesSoldMismatch,
+    /// The contract has settled the raffle but the row has a different status
+    StatusMismatch,
+    /// The contract picked a winner but the row has none
+    MissingWinner,
+    /// The row's first winner has a different wallet than the contract's winner; never repaired automatically
+    WinnerMismatch,
+    /// The contract could not be read
+    ChainReadFailed,
+}
+
+impl DivergenceKind {
+    /// Whether reconciliation may write the chain's value back
+    pub fn repairable(&self) -> bool {
+        matches!(
+            self,
+            DivergenceKind::BoxesSoldMismatch | DivergenceKind::StatusMismatch | DivergenceKind::MissingWinner
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ReconciliationRun {
+    pub id: Uuid,
+    pub status: ReconciliationStatus,
+    pub repair: bool,
+    pub raffles_checked: i32,
+    pub divergences: i32,
+    pub repaired: i32,
+    pub error: Option<String>,
+    pub triggered_by: Option<Uuid>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ReconciliationFinding {
+    pub id: Uuid,
+    pub run_id: Uuid,
+    pub raffle_id: Uuid,
+    pub blockchain_raffle_id: i64,
+    pub kind: DivergenceKind,
+    pub db_value: Option<String>,
+    pub chain_value: Option<String>,
+    pub repaired: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A run and everything it found
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReconciliationReport {
+    pub run: Option<ReconciliationRun>,
+    pub findings: Vec<ReconciliationFinding>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct StartReconciliationRequest {
+    /// Write repairable divergences back straight away
+    #[serde(default)]
+    pub repair: bool,
+}
+
+/// A raffle row as reconciliation sees it
+#[derive(Debug, Clone, FromRow)]
+pub struct RaffleSnapshot {
+    pub id: Uuid,
+    pub blockchain_raffle_id: i64,
+    pub total_boxes: i32,
+    pub boxes_sold: i32,
+    pub status: RaffleStatus,
+    /// Wallet address of the first user in `winner_user_ids`: the external wallet that recorded
+    /// their boxes on chain, if any, else their custodial wallet
+    pub recorded_winner: Option<String>,
+}
+
+/// What the contract reports for a raffle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainSnapshot {
+    pub boxes_sold: i32,
+    pub completed: bool,
+    /// None until a winner is drawn
+    pub winner: Option<String>,
+}
+
+impl ChainSnapshot {
+    pub fn from_stats(stats: &RaffleStats, total_boxes: i32) -> Self {
+        let remaining = i32::try_from(stats.boxes_remaining.low_u64()).unwrap_or(i32::MAX);
+        Self {
+            boxes_sold: (total_boxes - remaining).max(0),
+            completed: stats.is_completed,
+            winner: (stats.winner != Address::zero()).then(|| format!("{:?}", stats.winner)),
+        }
+    }
+}
+
+/// One way a raffle row disagrees with the chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Divergence {
+    pub kind: DivergenceKind,
+    pub db_value: Option<String>,
+    pub chain_value: Option<String>,
+}
+
+/// Chain reconciliation service compares on-chain raffle state with the raffles table.
+///
+/// The event processor keeps the two in step, but anything emitted while it is down is only
+/// caught here. Each run records its findings. Scheduled runs repair a divergence only when
+/// `chain_reconciliation_auto_repair` is on and the previous run saw the same thing, so events
+/// the processor is about to apply aren't raced; admins can ask for an immediate repair.
+#[derive(Clone)]
+pub struct ChainReconciliationService {
+    db_pool: PgPool,
+    blockchain_service: BlockchainService,
+}
+
+impl ChainReconciliationService {
+    pub fn new(db_pool: PgPool, blockchain_service: BlockchainService) -> Self {
+        Self {
+            db_pool,
+            blockchain_service,
+        }
+    }
+
+    /// Start the scheduled reconciliation loop
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(RECONCILE_INTERVAL_SECONDS));
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.run_scheduled().await {
+                    error!("Scheduled chain reconciliation failed: {}", e);
+                }
+            }
+        });
+
+        info!("Chain reconciliation started");
+    }
+
+    /// One scheduled run; repairs only divergences the previous run also saw
+    pub async fn run_scheduled(&self) -> Result<ReconciliationRun, AppError> {
+        let auto_repair = SystemSetting::get_by_key(&self.db_pool, "chain_reconciliation_auto_repair")
+            .await?
+            .and_then(|s| s.get_bool_value())
+            .unwrap_or(false);
+
+        let run = self.begin(auto_repair, None).await?;
+        self.complete(run, false).await
+    }
+
+    /// Reconcile now; the run continues in the background
+    pub async fn start_run(
+        &self,
+        request: StartReconciliationRequest,
+        triggered_by: Uuid,
+    ) -> Result<ReconciliationRun, AppError> {
+        let run = self.begin(request.repair, Some(triggered_by)).await?;
+        info!("Admin {} started chain reconciliation (repair: {})", triggered_by, request.repair);
+
+        let service = self.clone();
+        let started = run.clone();
+        tokio::spawn(async move {
+            if let Err(e) = service.complete(started, true).await {
+                error!("Chain reconciliation failed: {}", e);
+            }
+        });
+
+        Ok(run)
+    }
+
+    /// The given run, or the latest finished one, with its findings
+    pub async fn get_report(&self, run_id: Option<Uuid>) -> Result<ReconciliationReport, AppError> {
+        let run = match run_id {
+            Some(run_id) => Some(
+                sqlx::query_as::<_, ReconciliationRun>(&format!(
+                    "SELECT {} FROM chain_reconciliation_runs WHERE id = $1",
+                    RUN_COLUMNS
+                ))
+                .bind(run_id)
+                .fetch_optional(&self.db_pool)
+                .await?
+                .ok_or_else(|| AppError::NotFound("Reconciliation run not found".to_string()))?,
+            ),
+            None => self.latest_finished_run().await?,
+        };
+
+        let findings = match &run {
+            Some(run) => self.findings(run.id).await?,
+            None => Vec::new(),
+        };
+
+        Ok(ReconciliationReport { run, findings })
+    }
+
+    // Private helper methods
+
+    async fn begin(&self, repair: bool, triggered_by: Option<Uuid>) -> Result<ReconciliationRun, AppError> {
+        // A run left running for an hour died with its process
+        sqlx::query(
+            "UPDATE chain_reconciliation_runs SET status = 'failed', error = 'Run did not finish', finished_at = NOW() \
+             WHERE status = 'running' AND started_at < NOW() - INTERVAL '1 hour'",
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        let run = sqlx::query_as::<_, ReconciliationRun>(&format!(
+            "INSERT INTO chain_reconciliation_runs (repair, triggered_by) VALUES ($1, $2) RETURNING {}",
+            RUN_COLUMNS
+        ))
+        .bind(repair)
+        .bind(triggered_by)
+        .fetch_one(&self.db_pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                AppError::Conflict("A reconciliation run is already in progress".to_string())
+            }
+            other => other.into(),
+        })?;
+
+        Ok(run)
+    }
+
+    /// Check every raffle in scope, record findings, repair if asked, and close the run
+    async fn complete(&self, run: ReconciliationRun, repair_immediately: bool) -> Result<ReconciliationRun, AppError> {
+        let outcome = self.reconcile(&run, repair_immediately).await;
+
+        let (status, checked, divergences, repaired, error) = match outcome {
+            Ok((checked, divergences, repaired)) => (ReconciliationStatus::Completed, checked, divergences, repaired, None),
+            Err(e) => (ReconciliationStatus::Failed, 0, 0, 0, Some(e.to_string())),
+        };
+
+        let finished = sqlx::query_as::<_, ReconciliationRun>(&format!(
+            "UPDATE chain_reconciliation_runs \
+             SET status = $2, raffles_checked = $3, divergences = $4, repaired = $5, error = $6, finished_at = NOW() \
+             WHERE id = $1 RETURNING {}",
+            RUN_COLUMNS
+        ))
+        .bind(run.id)
+        .bind(status)
+        .bind(checked)
+        .bind(divergences)
+        .bind(repaired)
+        .bind(&error)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        match &error {
+            Some(error) => error!("Chain reconciliation {} failed: {}", run.id, error),
+            None if divergences > 0 => warn!(
+                "Chain reconciliation {} found {} divergences in {} raffles, repaired {}",
+                run.id, divergences, checked, repaired
+            ),
+            None => info!("Chain reconciliation {}: {} raffles match the chain", run.id, checked),
+        }
+
+        Ok(finished)
+    }
+
+    async fn reconcile(&self, run: &ReconciliationRun, repair_immediately: bool) -> Result<(i32, i32, i32), AppError> {
+        let lookback_days = self.get_setting("chain_reconciliation_lookback_days", DEFAULT_LOOKBACK_DAYS).await?;
+        let max_raffles = self.get_setting("chain_reconciliation_max_raffles", DEFAULT_MAX_RAFFLES).await?;
+
+        // Divergences the previous run already saw, by raffle, kind and chain value
+        let seen_before: HashSet<(Uuid, DivergenceKind, Option<String>)> = match self.latest_finished_run().await? {
+            Some(previous) if run.repair && !repair_immediately => self
+                .findings(previous.id)
+                .await?
+                .into_iter()
+                .map(|f| (f.raffle_id, f.kind, f.chain_value))
+                .collect(),
+            _ => HashSet::new(),
+        };
+
+        let raffles = sqlx::query_as::<_, RaffleSnapshot>(
+            r#"
+            SELECT r.id, r.blockchain_raffle_id, r.total_boxes, r.boxes_sold, r.status,
+                   COALESCE(
+                       (SELECT ep.address FROM external_purchases ep
+                        WHERE ep.raffle_id = r.id AND ep.user_id = r.winner_user_ids[1]
+                          AND ep.status = 'confirmed'
+                        ORDER BY ep.confirmed_at DESC LIMIT 1),
+                       (SELECT u.internal_wallet_address FROM users u
+                        WHERE u.id = r.winner_user_ids[1])
+                   ) AS recorded_winner
+            FROM raffles r
+            WHERE r.blockchain_raffle_id IS NOT NULL
+              AND (r.status NOT IN ('completed', 'cancelled')
+                   OR r.updated_at > NOW() - make_interval(days => $1))
+            ORDER BY r.updated_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(lookback_days as i32)
+        .bind(max_raffles)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let mut divergences = 0;
+        let mut repaired = 0;
+        for raffle in &raffles {
+            let found = match self.blockchain_service.get_raffle_stats(raffle.blockchain_raffle_id as u64).await {
+                Ok(stats) => compare(raffle, &ChainSnapshot::from_stats(&stats, raffle.total_boxes)),
+                Err(e) => vec![Divergence {
+                    kind: DivergenceKind::ChainReadFailed,
+                    db_value: None,
+                    chain_value: Some(e.to_string()),
+                }],
+            };
+
+            for divergence in found {
+                divergences += 1;
+                let should_repair = run.repair
+                    && divergence.kind.repairable()
+                    && (repair_immediately
+                        || seen_before.contains(&(raffle.id, divergence.kind, divergence.chain_value.clone())));
+
+                let was_repaired = should_repair && self.repair(raffle, &divergence).await?;
+                if was_repaired {
+                    repaired += 1;
+                }
+
+                sqlx::query(
+                    "INSERT INTO chain_reconciliation_findings \
+                        (run_id, raffle_id, blockchain_raffle_id, kind, db_value, chain_value, repaired) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                )
+                .bind(run.id)
+                .bind(raffle.id)
+                .bind(raffle.blockchain_raffle_id)
+                .bind(divergence.kind)
+                .bind(&divergence.db_value)
+                .bind(&divergence.chain_value)
+                .bind(was_repaired)
+                .execute(&self.db_pool)
+                .await?;
+            }
+        }
+
+        Ok((raffles.len() as i32, divergences, repaired))
+    }
+
+    /// Write the chain's value back; guarded on the value we read, so a concurrent event update wins
+    async fn repair(&self, raffle: &RaffleSnapshot, divergence: &Divergence) -> Result<bool, AppError> {
+        let result = match divergence.kind {
+            DivergenceKind::BoxesSoldMismatch => {
+                let Some(chain_sold) = divergence.chain_value.as_deref().and_then(|v| v.parse::<i32>().ok()) else {
+                    return Ok(false);
+                };
+                let status = status_for_count(raffle.status, chain_sold, raffle.total_boxes);
+                sqlx::query(
+                    "UPDATE raffles SET boxes_sold = $1, status = $2, updated_at = NOW() \
+                     WHERE id = $3 AND boxes_sold = $4",
+                )
+                .bind(chain_sold)
+                .bind(status)
+                .bind(raffle.id)
+                .bind(raffle.boxes_sold)
+                .execute(&self.db_pool)
+                .await?
+            }
+            DivergenceKind::StatusMismatch => {
+                sqlx::query(
+                    "UPDATE raffles SET status = 'completed', completed_at = COALESCE(completed_at, NOW()), \
+                        updated_at = NOW() \
+                     WHERE id = $1 AND status = $2",
+                )
+                .bind(raffle.id)
+                .bind(raffle.status)
+                .execute(&self.db_pool)
+                .await?
+            }
+            DivergenceKind::MissingWinner => {
+                // Only a winner whose wallet belongs to one of our users can be recorded
+                sqlx::query(
+                    "UPDATE raffles SET winner_user_ids = ARRAY[owner.user_id], updated_at = NOW() \
+                     FROM (SELECT id AS user_id FROM users WHERE lower(internal_wallet_address) = lower($2) \
+                           UNION ALL \
+                           SELECT user_id FROM external_wallets WHERE lower(address) = lower($2) \
+                           LIMIT 1) owner \
+                     WHERE raffles.id = $1 AND cardinality(raffles.winner_user_ids) = 0",
+                )
+                .bind(raffle.id)
+                .bind(&divergence.chain_value)
+                .execute(&self.db_pool)
+                .await?
+            }
+            DivergenceKind::WinnerMismatch | DivergenceKind::ChainReadFailed => return Ok(false),
+        };
+
+        let repaired = result.rows_affected() > 0;
+        if repaired {
+            info!(
+                "Repaired {:?} on raffle {}: {:?} -> {:?}",
+                divergence.kind, raffle.id, divergence.db_value, divergence.chain_value
+            );
+        }
+        Ok(repaired)
+    }
+
+    async fn latest_finished_run(&self) -> Result<Option<ReconciliationRun>, AppError> {
+        let run = sqlx::query_as::<_, ReconciliationRun>(&format!(
+            "SELECT {} FROM chain_reconciliation_runs WHERE status <> 'running' ORDER BY started_at DESC LIMIT 1",
+            RUN_COLUMNS
+        ))
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(run)
+    }
+
+    async fn findings(&self, run_id: Uuid) -> Result<Vec<ReconciliationFinding>, AppError> {
+        let findings = sqlx::query_as::<_, ReconciliationFinding>(&format!(
+            "SELECT {} FROM chain_reconciliation_findings WHERE run_id = $1 ORDER BY created_at, id",
+            FINDING_COLUMNS
+        ))
+        .bind(run_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(findings)
+    }
+
+    async fn get_setting(&self, key: &str, default: i64) -> Result<i64, AppError> {
+        Ok(SystemSetting::get_by_key(&self.db_pool, key)
+            .await?
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default))
+    }
+}
+
+/// Every way the raffle row disagrees with what the contract reports
+pub fn compare(raffle: &RaffleSnapshot, chain: &ChainSnapshot) -> Vec<Divergence> {
+    let mut divergences = Vec::new();
+
+    if raffle.boxes_sold != chain.boxes_sold {
+        divergences.push(Divergence {
+            kind: DivergenceKind::BoxesSoldMismatch,
+            db_value: Some(raffle.boxes_sold.to_string()),
+            chain_value: Some(chain.boxes_sold.to_string()),
+        });
+    }
+
+    if chain.completed && raffle.status != RaffleStatus::Completed {
+        divergences.push(Divergence {
+            kind: DivergenceKind::StatusMismatch,
+            db_value: Some(raffle.status.to_string()),
+            chain_value: Some(RaffleStatus::Completed.to_string()),
+        });
+    }
+
+    if let Some(chain_winner) = &chain.winner {
+        match &raffle.recorded_winner {
+            None => divergences.push(Divergence {
+                kind: DivergenceKind::MissingWinner,
+                db_value: None,
+                chain_value: Some(chain_winner.clone()),
+            }),
+            Some(recorded) if !recorded.eq_ignore_ascii_case(chain_winner) => divergences.push(Divergence {
+                kind: DivergenceKind::WinnerMismatch,
+                db_value: Some(recorded.clone()),
+                chain_value: Some(chain_winner.clone()),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    divergences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::U256;
+
+    fn raffle(boxes_sold: i32, status: RaffleStatus, recorded_winner: Option<&str>) -> RaffleSnapshot {
+        RaffleSnapshot {
+            id: Uuid::new_v4(),
+            blockchain_raffle_id: 7,
+            total_boxes: 100,
+            boxes_sold,
+            status,
+            recorded_winner: recorded_winner.map(str::to_string),
+        }
+    }
+
+    fn chain(boxes_sold: i32, completed: bool, winner: Option<&str>) -> ChainSnapshot {
+        ChainSnapshot {
+            boxes_sold,
+            completed,
+            winner: winner.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_matching_raffle_has_no_divergences() {
+        assert!(compare(&raffle(40, RaffleStatus::Open, None), &chain(40, false, None)).is_empty());
+    }
+
+    #[test]
+    fn test_boxes_sold_mismatch() {
+        let found = compare(&raffle(38, RaffleStatus::Open, None), &chain(40, false, None));
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, DivergenceKind::BoxesSoldMismatch);
+        assert_eq!(found[0].db_value.as_deref(), Some("38"));
+        assert_eq!(found[0].chain_value.as_deref(), Some("40"));
+    }
+
+    #[test]
+    fn test_missed_draw_reports_status_and_winner() {
+        let found = compare(&raffle(100, RaffleStatus::Full, None), &chain(100, true, Some("0xabc")));
+        let kinds: Vec<_> = found.iter().map(|d| d.kind).collect();
+
+        assert_eq!(kinds, vec![DivergenceKind::StatusMismatch, DivergenceKind::MissingWinner]);
+    }
+
+    #[test]
+    fn test_winner_comparison_ignores_case() {
+        let recorded = raffle(100, RaffleStatus::Completed, Some("0xABC"));
+        assert!(compare(&recorded, &chain(100, true, Some("0xabc"))).is_empty());
+
+        let found = compare(&recorded, &chain(100, true, Some("0xdef")));
+        assert_eq!(found[0].kind, DivergenceKind::WinnerMismatch);
+        assert!(!found[0].kind.repairable());
+    }
+
+    #[test]
+    fn test_chain_snapshot_from_stats() {
+        let stats = RaffleStats {
+            total_participants: U256::from(12),
+            total_revenue: U256::zero(),
+            boxes_remaining: U256::from(60),
+            is_completed: false,
+            winner: Address::zero(),
+        };
+
+        let snapshot = ChainSnapshot::from_stats(&stats, 100);
+        assert_eq!(snapshot, chain(40, false, None));
+    }
+}
@@ -0,0 +1,471 @@
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
+
+/// Rows deleted per statement; keeps lock times short on large tables
+const DELETE_BATCH_SIZE: i64 = 1000;
+
+/// Default safety limit on rows purged per target per run
+const DEFAULT_MAX_ROWS_PER_RUN: i64 = 10_000;
+
+/// Cleanup service purges expired credentials, tokens and stale guest sessions on a schedule
+#[derive(Clone)]
+pub struct CleanupService {
+    db_pool: PgPool,
+    metrics: Arc<RwLock<CleanupMetrics>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupTarget {
+    ExpiredSessions,
+    PasswordResetTokens,
+    EmailVerificationTokens,
+    AnonymousSessions,
+    VerificationAttempts,
+    IdempotencyKeys,
+    LoginLockouts,
+    WalletLinkChallenges,
+    BoxReservations,
+}
+
+impl CleanupTarget {
+    pub const ALL: [CleanupTarget; 9] = [
+        CleanupTarget::ExpiredSessions,
+        CleanupTarget::PasswordResetTokens,
+        CleanupTarget::EmailVerificationTokens,
+        CleanupTarget::AnonymousSessions,
+        CleanupTarget::VerificationAttempts,
+        CleanupTarget::IdempotencyKeys,
+        CleanupTarget::LoginLockouts,
+        CleanupTarget::WalletLinkChallenges,
+        CleanupTarget::BoxReservations,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CleanupTarget::ExpiredSessions => "expired_sessions",
+            CleanupTarget::PasswordResetTokens => "password_reset_tokens",
+            CleanupTarget::EmailVerificationTokens => "email_verification_tokens",
+            CleanupTarget::AnonymousSessions => "anonymous_sessions",
+            CleanupTarget::VerificationAttempts => "email_verification_attempts",
+            CleanupTarget::IdempotencyKeys => "idempotency_keys",
+            CleanupTarget::LoginLockouts => "login_lockouts",
+            CleanupTarget::WalletLinkChallenges => "external_wallet_challenges",
+            CleanupTarget::BoxReservations => "box_reservations",
+        }
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            CleanupTarget::ExpiredSessions => "user_sessions",
+            CleanupTarget::PasswordResetTokens => "password_reset_tokens",
+            CleanupTarget::EmailVerificationTokens => "email_verification_tokens",
+            CleanupTarget::AnonymousSessions => "anonymous_sessions",
+            CleanupTarget::VerificationAttempts => "email_verification_attempts",
+            CleanupTarget::IdempotencyKeys => "idempotency_keys",
+            CleanupTarget::LoginLockouts => "login_lockouts",
+            CleanupTarget::WalletLinkChallenges => "external_wallet_challenges",
+            CleanupTarget::BoxReservations => "box_reservations",
+        }
+    }
+
+    /// Columns identifying a row of the target's table
+    fn key(&self) -> &'static str {
+        match self {
+            CleanupTarget::BoxReservations => "raffle_id, box_number",
+            _ => "id",
+        }
+    }
+
+    /// Rows eligible for purge once they are older than the retention cutoff ($1)
+    fn condition(&self) -> &'static str {
+        match self {
+            CleanupTarget::ExpiredSessions => "(expires_at < $1 OR (is_active = FALSE AND updated_at < $1))",
+            CleanupTarget::PasswordResetTokens | CleanupTarget::EmailVerificationTokens => {
+                "(expires_at < $1 OR ((is_used = TRUE OR invalidated_at IS NOT NULL) AND created_at < $1))"
+            }
+            // Stitched sessions are kept; their events are already attributed to a user
+            CleanupTarget::AnonymousSessions => "(user_id IS NULL AND last_seen_at < $1)",
+            CleanupTarget::VerificationAttempts => "(created_at < $1)",
+            CleanupTarget::IdempotencyKeys | CleanupTarget::WalletLinkChallenges | CleanupTarget::BoxReservations => {
+                "(expires_at < $1)"
+            }
+            CleanupTarget::LoginLockouts => "(last_failure_at < $1 AND (locked_until IS NULL OR locked_until < $1))",
+        }
+    }
+
+    /// Days an expired/used row is kept before it is purged
+    fn default_retention_days(&self) -> i64 {
+        match self {
+            CleanupTarget::ExpiredSessions => 30,
+            CleanupTarget::PasswordResetTokens => 7,
+            CleanupTarget::EmailVerificationTokens => 14,
+            CleanupTarget::AnonymousSessions => 90,
+            // Must outlive the resend backoff window
+            CleanupTarget::VerificationAttempts => 30,
+            // Expired keys are never replayed; keep them briefly for support lookups
+            CleanupTarget::IdempotencyKeys => 1,
+            // Must outlive the lockout policy's reset_after_hours, or repeat offenders start over
+            CleanupTarget::LoginLockouts => 7,
+            CleanupTarget::WalletLinkChallenges => 1,
+            // Abandoned purchase holds; a new reservation already takes over an expired one
+            CleanupTarget::BoxReservations => 1,
+        }
+    }
+
+    fn retention_setting_key(&self) -> String {
+        format!("cleanup_retention_days_{}", self.as_str())
+    }
+
+    /// Retention from the target's setting; a missing or negative one falls back to the default
+    fn retention_days(&self, setting: Option<i64>) -> i64 {
+        setting
+            .filter(|days| *days >= 0)
+            .unwrap_or_else(|| self.default_retention_days())
+    }
+
+    /// One batch of the purge: deletes up to $2 rows older than the cutoff ($1)
+    fn purge_sql(&self) -> String {
+        format!(
+            "DELETE FROM {table} WHERE ({key}) IN (SELECT {key} FROM {table} WHERE {condition} LIMIT $2)",
+            table = self.table(),
+            key = self.key(),
+            condition = self.condition(),
+        )
+    }
+}
+
+/// Rows to delete in the next batch, or 0 once the run limit is reached
+fn next_batch_size(purged: u64, max_rows: i64) -> i64 {
+    DELETE_BATCH_SIZE.min(max_rows - purged as i64).max(0)
+}
+
+/// Per-run row limit from its setting; a missing or non-positive one falls back to the default
+fn max_rows_per_run(setting: Option<i64>) -> i64 {
+    setting.filter(|rows| *rows > 0).unwrap_or(DEFAULT_MAX_ROWS_PER_RUN)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CleanupTargetResult {
+    pub target: CleanupTarget,
+    pub retention_days: i64,
+    pub rows_purged: u64,
+    pub hit_run_limit: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CleanupReport {
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub max_rows_per_target: i64,
+    pub results: Vec<CleanupTargetResult>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct CleanupMetrics {
+    pub runs: u64,
+    pub total_rows_purged: HashMap<CleanupTarget, u64>,
+    pub last_report: Option<CleanupReport>,
+}
+
+impl CleanupTargetResult {
+    fn purged(target: CleanupTarget, retention_days: i64, rows_purged: u64, max_rows: i64) -> Self {
+        Self {
+            target,
+            retention_days,
+            rows_purged,
+            hit_run_limit: rows_purged as i64 >= max_rows,
+            error: None,
+        }
+    }
+
+    fn failed(target: CleanupTarget, retention_days: i64, error: &AppError) -> Self {
+        Self {
+            target,
+            retention_days,
+            rows_purged: 0,
+            hit_run_limit: false,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+impl CleanupMetrics {
+    /// Add a run's report to the running totals
+    fn record(&mut self, report: &CleanupReport) {
+        self.runs += 1;
+        for result in &report.results {
+            *self.total_rows_purged.entry(result.target).or_insert(0) += result.rows_purged;
+        }
+        self.last_report = Some(report.clone());
+    }
+}
+
+impl CleanupService {
+    /// Create a new cleanup service
+    pub fn new(db_pool: PgPool) -> Self {
+        Self {
+            db_pool,
+            metrics: Arc::new(RwLock::new(CleanupMetrics::default())),
+        }
+    }
+
+    /// Start the scheduled cleanup task
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3600)); // Every hour
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.run_cleanup().await {
+                    error!("Scheduled credential cleanup failed: {}", e);
+                }
+            }
+        });
+
+        info!("Cleanup service background tasks started");
+    }
+
+    /// Purge expired rows across all targets, honoring retention and per-run limits
+    pub async fn run_cleanup(&self) -> Result<CleanupReport, AppError> {
+        let started_at = Utc::now();
+        let start_time = std::time::Instant::now();
+        let max_rows = self.get_max_rows_per_run().await?;
+
+        let mut results = Vec::new();
+        for target in CleanupTarget::ALL {
+            let retention_days = self.get_retention_days(target).await?;
+
+            let result = match self.purge_target(target, retention_days, max_rows).await {
+                Ok(rows_purged) => CleanupTargetResult::purged(target, retention_days, rows_purged, max_rows),
+                Err(e) => {
+                    warn!("Cleanup of {} failed: {}", target.as_str(), e);
+                    CleanupTargetResult::failed(target, retention_days, &e)
+                }
+            };
+
+            if result.hit_run_limit {
+                warn!(
+                    "Cleanup of {} reached the per-run limit of {} rows; remaining rows will be purged next run",
+                    target.as_str(), max_rows
+                );
+            }
+
+            results.push(result);
+        }
+
+        let report = CleanupReport {
+            started_at,
+            duration_ms: start_time.elapsed().as_millis() as u64,
+            max_rows_per_target: max_rows,
+            results,
+        };
+
+        self.metrics.write().await.record(&report);
+
+        let total: u64 = report.results.iter().map(|r| r.rows_purged).sum();
+        if total > 0 {
+            info!("Credential cleanup purged {} rows in {}ms", total, report.duration_ms);
+        }
+
+        Ok(report)
+    }
+
+    /// Get cumulative cleanup metrics
+    pub async fn get_metrics(&self) -> CleanupMetrics {
+        self.metrics.read().await.clone()
+    }
+
+    // Private helper methods
+
+    async fn purge_target(
+        &self,
+        target: CleanupTarget,
+        retention_days: i64,
+        max_rows: i64,
+    ) -> Result<u64, AppError> {
+        let cutoff = Utc::now() - chrono::Duration::days(retention_days);
+        let sql = target.purge_sql();
+
+        let mut purged: u64 = 0;
+        loop {
+            let batch = next_batch_size(purged, max_rows);
+            if batch == 0 {
+                break;
+            }
+
+            let result = sqlx::query(&sql)
+                .bind(cutoff)
+                .bind(batch)
+                .execute(&self.db_pool)
+                .await?;
+
+            purged += result.rows_affected();
+            if (result.rows_affected() as i64) < batch {
+                break;
+            }
+        }
+
+        debug!("Purged {} rows from {}", purged, target.table());
+        Ok(purged)
+    }
+
+    async fn get_retention_days(&self, target: CleanupTarget) -> Result<i64, AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, &target.retention_setting_key()).await?;
+        Ok(target.retention_days(setting.and_then(|s| s.get_int_value())))
+    }
+
+    async fn get_max_rows_per_run(&self) -> Result<i64, AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, "cleanup_max_rows_per_run").await?;
+        Ok(max_rows_per_run(setting.and_then(|s| s.get_int_value())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn report(results: Vec<CleanupTargetResult>) -> CleanupReport {
+        CleanupReport {
+            started_at: Utc::now(),
+            duration_ms: 5,
+            max_rows_per_target: 100,
+            results,
+        }
+    }
+
+    #[test]
+    fn test_each_target_has_its_own_retention_setting() {
+        let keys: HashSet<String> = CleanupTarget::ALL.iter().map(|t| t.retention_setting_key()).collect();
+        assert_eq!(keys.len(), CleanupTarget::ALL.len());
+
+        assert_eq!(
+            CleanupTarget::BoxReservations.retention_setting_key(),
+            "cleanup_retention_days_box_reservations"
+        );
+        assert_eq!(
+            CleanupTarget::VerificationAttempts.retention_setting_key(),
+            "cleanup_retention_days_email_verification_attempts"
+        );
+    }
+
+    #[test]
+    fn test_retention_setting_overrides_default() {
+        let target = CleanupTarget::AnonymousSessions;
+
+        assert_eq!(target.retention_days(None), 90);
+        assert_eq!(target.retention_days(Some(14)), 14);
+        // Zero purges as soon as a row qualifies
+        assert_eq!(target.retention_days(Some(0)), 0);
+        assert_eq!(target.retention_days(Some(-3)), 90);
+    }
+
+    #[test]
+    fn test_max_rows_per_run_setting() {
+        assert_eq!(max_rows_per_run(None), DEFAULT_MAX_ROWS_PER_RUN);
+        assert_eq!(max_rows_per_run(Some(250)), 250);
+        assert_eq!(max_rows_per_run(Some(0)), DEFAULT_MAX_ROWS_PER_RUN);
+        assert_eq!(max_rows_per_run(Some(-1)), DEFAULT_MAX_ROWS_PER_RUN);
+    }
+
+    #[test]
+    fn test_batches_stop_at_run_limit() {
+        assert_eq!(next_batch_size(0, 10_000), DELETE_BATCH_SIZE);
+        assert_eq!(next_batch_size(9_500, 10_000), 500);
+        assert_eq!(next_batch_size(0, 300), 300);
+        assert_eq!(next_batch_size(300, 300), 0);
+
+        // Full batches add up to exactly the limit
+        let mut purged = 0u64;
+        let mut batches = 0;
+        while next_batch_size(purged, 2_500) > 0 {
+            purged += next_batch_size(purged, 2_500) as u64;
+            batches += 1;
+        }
+        assert_eq!((purged, batches), (2_500, 3));
+    }
+
+    #[test]
+    fn test_purge_sql_uses_each_tables_key() {
+        let sessions = CleanupTarget::ExpiredSessions.purge_sql();
+        assert!(sessions.starts_with("DELETE FROM user_sessions WHERE (id) IN (SELECT id FROM user_sessions"));
+        assert!(sessions.ends_with("LIMIT $2)"));
+
+        let reservations = CleanupTarget::BoxReservations.purge_sql();
+        assert!(reservations.contains("WHERE (raffle_id, box_number) IN (SELECT raffle_id, box_number FROM box_reservations"));
+        assert!(reservations.contains("(expires_at < $1)"));
+    }
+
+    #[test]
+    fn test_target_results_flag_run_limit_and_errors() {
+        let below = CleanupTargetResult::purged(CleanupTarget::IdempotencyKeys, 1, 99, 100);
+        assert!(!below.hit_run_limit);
+        assert!(below.error.is_none());
+
+        let at_limit = CleanupTargetResult::purged(CleanupTarget::IdempotencyKeys, 1, 100, 100);
+        assert!(at_limit.hit_run_limit);
+
+        let failed = CleanupTargetResult::failed(
+            CleanupTarget::LoginLockouts,
+            7,
+            &AppError::Internal("statement timeout".to_string()),
+        );
+        assert_eq!(failed.rows_purged, 0);
+        assert!(!failed.hit_run_limit);
+        assert!(failed.error.unwrap().contains("statement timeout"));
+    }
+
+    #[test]
+    fn test_metrics_accumulate_reports() {
+        let mut metrics = CleanupMetrics::default();
+
+        metrics.record(&report(vec![
+            CleanupTargetResult::purged(CleanupTarget::ExpiredSessions, 30, 40, 100),
+            CleanupTargetResult::purged(CleanupTarget::BoxReservations, 1, 3, 100),
+        ]));
+        metrics.record(&report(vec![
+            CleanupTargetResult::purged(CleanupTarget::ExpiredSessions, 30, 2, 100),
+            CleanupTargetResult::failed(
+                CleanupTarget::BoxReservations,
+                1,
+                &AppError::Internal("lock timeout".to_string()),
+            ),
+        ]));
+
+        assert_eq!(metrics.runs, 2);
+        assert_eq!(metrics.total_rows_purged[&CleanupTarget::ExpiredSessions], 42);
+        assert_eq!(metrics.total_rows_purged[&CleanupTarget::BoxReservations], 3);
+
+        let last = metrics.last_report.unwrap();
+        assert_eq!(last.results[0].rows_purged, 2);
+        assert!(last.results[1].error.is_some());
+    }
+
+    #[test]
+    fn test_report_serializes_target_names() {
+        let json = serde_json::to_value(report(vec![CleanupTargetResult::purged(
+            CleanupTarget::BoxReservations,
+            1,
+            3,
+            100,
+        )]))
+        .unwrap();
+
+        assert_eq!(json["results"][0]["target"], "box_reservations");
+        assert_eq!(json["results"][0]["rows_purged"], 3);
+        assert_eq!(json["max_rows_per_target"], 100);
+    }
+}
@@ -0,0 +1,614 @@
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use crate::models::user_block::UserBlock;
+use crate::services::realtime_service::{RealtimeEvent, RealtimeService};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const MAX_COMMENT_LENGTH: usize = 2000;
+
+/// Comment service manages per-raffle Q&A threads and their moderation
+#[derive(Clone)]
+pub struct CommentService {
+    db_pool: PgPool,
+    realtime_service: RealtimeService,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct RaffleComment {
+    pub id: Uuid,
+    pub raffle_id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub author_id: Uuid,
+    pub author_username: String,
+    pub body: String,
+    pub is_seller_answer: bool,
+    pub is_highlighted: bool,
+    pub status: String,
+    pub flag_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CommentThread {
+    pub comment: RaffleComment,
+    pub replies: Vec<RaffleComment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PostCommentRequest {
+    pub body: String,
+    pub parent_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagReason {
+    Spam,
+    Abuse,
+    OffTopic,
+    Scam,
+    Other,
+}
+
+impl FlagReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlagReason::Spam => "spam",
+            FlagReason::Abuse => "abuse",
+            FlagReason::OffTopic => "off_topic",
+            FlagReason::Scam => "scam",
+            FlagReason::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FlagCommentRequest {
+    pub reason: FlagReason,
+    pub details: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationAction {
+    /// Dismiss open flags and make the comment visible again
+    Approve,
+    Remove,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ModerateCommentRequest {
+    pub action: ModerationAction,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ModerationQueueEntry {
+    pub comment: RaffleComment,
+    pub open_flags: i64,
+    pub reasons: Vec<String>,
+    pub first_flagged_at: Option<DateTime<Utc>>,
+}
+
+impl CommentService {
+    /// Create a new comment service
+    pub fn new(db_pool: PgPool, realtime_service: RealtimeService) -> Self {
+        Self {
+            db_pool,
+            realtime_service,
+        }
+    }
+
+    /// Get visible comment threads for a raffle, newest threads first. Comments by users the
+    /// viewer has blocked or muted are left out, along with the replies under them.
+    pub async fn get_raffle_comments(
+        &self,
+        raffle_id: Uuid,
+        viewer_id: Option<Uuid>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CommentThread>, AppError> {
+        let top_level = sqlx::query_as!(
+            RaffleComment,
+            r#"
+            SELECT c.id, c.raffle_id, c.parent_id, c.author_id, u.username as author_username, c.body,
+                   c.is_seller_answer as "is_seller_answer!", c.is_highlighted as "is_highlighted!",
+                   c.status, c.flag_count as "flag_count!", c.created_at as "created_at!"
+            FROM raffle_comments c
+            JOIN users u ON u.id = c.author_id
+            WHERE c.raffle_id = $1 AND c.parent_id IS NULL AND c.status = 'visible'
+              AND NOT EXISTS (SELECT 1 FROM user_blocks b WHERE b.blocker_id = $4 AND b.blocked_id = c.author_id)
+            ORDER BY c.created_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            raffle_id,
+            limit,
+            offset,
+            viewer_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let parent_ids: Vec<Uuid> = top_level.iter().map(|c| c.id).collect();
+        let replies = sqlx::query_as!(
+            RaffleComment,
+            r#"
+            SELECT c.id, c.raffle_id, c.parent_id, c.author_id, u.username as author_username, c.body,
+                   c.is_seller_answer as "is_seller_answer!", c.is_highlighted as "is_highlighted!",
+                   c.status, c.flag_count as "flag_count!", c.created_at as "created_at!"
+            FROM raffle_comments c
+            JOIN users u ON u.id = c.author_id
+            WHERE c.parent_id = ANY($1) AND c.status = 'visible'
+              AND NOT EXISTS (SELECT 1 FROM user_blocks b WHERE b.blocker_id = $2 AND b.blocked_id = c.author_id)
+            "#,
+            &parent_ids,
+            viewer_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(build_threads(top_level, replies))
+    }
+
+    /// Post a question or a reply on a raffle
+    pub async fn post_comment(
+        &self,
+        raffle_id: Uuid,
+        author_id: Uuid,
+        request: PostCommentRequest,
+    ) -> Result<RaffleComment, AppError> {
+        let body = request.body.trim().to_string();
+        if body.is_empty() || body.chars().count() > MAX_COMMENT_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Comment must be between 1 and {} characters",
+                MAX_COMMENT_LENGTH
+            )));
+        }
+
+        self.check_rate_limit(author_id).await?;
+
+        let seller_user_id = self.get_raffle_seller(raffle_id).await?;
+        if let Some(seller_user_id) = seller_user_id {
+            UserBlock::ensure_not_blocked(&self.db_pool, seller_user_id, author_id).await?;
+        }
+
+        if let Some(parent_id) = request.parent_id {
+            let parent = sqlx::query!(
+                "SELECT raffle_id, parent_id, author_id, status FROM raffle_comments WHERE id = $1",
+                parent_id
+            )
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Parent comment not found".to_string()))?;
+
+            if parent.raffle_id != raffle_id {
+                return Err(AppError::Validation("Parent comment belongs to a different raffle".to_string()));
+            }
+            if parent.parent_id.is_some() {
+                return Err(AppError::Validation("Replies can only be made to top-level comments".to_string()));
+            }
+            if parent.status != "visible" {
+                return Err(AppError::Validation("Cannot reply to a hidden comment".to_string()));
+            }
+            UserBlock::ensure_not_blocked(&self.db_pool, parent.author_id, author_id).await?;
+        }
+
+        let is_seller_answer = request.parent_id.is_some() && seller_user_id == Some(author_id);
+
+        let comment = sqlx::query_as!(
+            RaffleComment,
+            r#"
+            WITH inserted AS (
+                INSERT INTO raffle_comments (raffle_id, parent_id, author_id, body, is_seller_answer)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING *
+            )
+            SELECT c.id, c.raffle_id, c.parent_id, c.author_id, u.username as author_username, c.body,
+                   c.is_seller_answer as "is_seller_answer!", c.is_highlighted as "is_highlighted!",
+                   c.status, c.flag_count as "flag_count!", c.created_at as "created_at!"
+            FROM inserted c
+            JOIN users u ON u.id = c.author_id
+            "#,
+            raffle_id,
+            request.parent_id,
+            author_id,
+            body,
+            is_seller_answer
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        if let Err(e) = self
+            .realtime_service
+            .broadcast_event(RealtimeEvent::CommentPosted {
+                raffle_id,
+                comment_id: comment.id,
+                parent_id: comment.parent_id,
+                author_id,
+                author_username: comment.author_username.clone(),
+                body: comment.body.clone(),
+                is_seller_answer,
+                created_at: comment.created_at,
+            })
+            .await
+        {
+            warn!("Failed to broadcast comment {}: {}", comment.id, e);
+        }
+
+        Ok(comment)
+    }
+
+    /// Delete a comment written by the user
+    pub async fn delete_own_comment(&self, comment_id: Uuid, user_id: Uuid) -> Result<(), AppError> {
+        let comment = self.get_comment_row(comment_id).await?;
+        if comment.author_id != user_id {
+            return Err(AppError::Forbidden("You can only delete your own comments".to_string()));
+        }
+
+        sqlx::query!(
+            "UPDATE raffle_comments SET status = 'removed' WHERE id = $1",
+            comment_id
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        self.broadcast_removed(comment.raffle_id, comment_id).await;
+        Ok(())
+    }
+
+    /// Mark a reply as the seller's highlighted answer (raffle seller only)
+    pub async fn highlight_answer(&self, comment_id: Uuid, user_id: Uuid) -> Result<(), AppError> {
+        let comment = self.get_comment_row(comment_id).await?;
+        let parent_id = comment
+            .parent_id
+            .ok_or_else(|| AppError::Validation("Only replies can be highlighted".to_string()))?;
+
+        if self.get_raffle_seller(comment.raffle_id).await? != Some(user_id) {
+            return Err(AppError::Forbidden("Only the raffle's seller can highlight answers".to_string()));
+        }
+
+        let mut tx = self.db_pool.begin().await?;
+
+        // One highlighted answer per thread
+        sqlx::query!(
+            "UPDATE raffle_comments SET is_highlighted = FALSE WHERE parent_id = $1 AND is_highlighted = TRUE",
+            parent_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE raffle_comments SET is_highlighted = TRUE WHERE id = $1",
+            comment_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Report a comment; enough open flags hide it until a moderator reviews it
+    pub async fn flag_comment(
+        &self,
+        comment_id: Uuid,
+        reporter_id: Uuid,
+        request: FlagCommentRequest,
+    ) -> Result<(), AppError> {
+        let comment = self.get_comment_row(comment_id).await?;
+        if comment.author_id == reporter_id {
+            return Err(AppError::Validation("You cannot flag your own comment".to_string()));
+        }
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO raffle_comment_flags (comment_id, reporter_id, reason, details)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (comment_id, reporter_id) DO NOTHING
+            "#,
+            comment_id,
+            reporter_id,
+            request.reason.as_str(),
+            request.details
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        if inserted.rows_affected() == 0 {
+            return Err(AppError::Conflict("You have already flagged this comment".to_string()));
+        }
+
+        let threshold = self.get_setting("comment_auto_hide_flag_threshold", 3).await?;
+
+        let updated = sqlx::query!(
+            r#"
+            UPDATE raffle_comments SET
+                flag_count = flag_count + 1,
+                status = CASE WHEN status = 'visible' AND flag_count + 1 >= $2 THEN 'hidden' ELSE status END
+            WHERE id = $1
+            RETURNING status
+            "#,
+            comment_id,
+            threshold as i32
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        if updated.status == "hidden" && comment.status == "visible" {
+            info!("Comment {} hidden pending moderation after {} flags", comment_id, threshold);
+            self.broadcast_removed(comment.raffle_id, comment_id).await;
+        }
+
+        Ok(())
+    }
+
+    /// Comments with open flags, most flagged first (admin)
+    pub async fn get_moderation_queue(&self, limit: i64, offset: i64) -> Result<Vec<ModerationQueueEntry>, AppError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT c.id, c.raffle_id, c.parent_id, c.author_id, u.username as author_username, c.body,
+                   c.is_seller_answer as "is_seller_answer!", c.is_highlighted as "is_highlighted!",
+                   c.status, c.flag_count as "flag_count!", c.created_at as "created_at!",
+                   COUNT(f.id) as "open_flags!",
+                   ARRAY_AGG(DISTINCT f.reason) as "reasons!",
+                   MIN(f.created_at) as first_flagged_at
+            FROM raffle_comments c
+            JOIN users u ON u.id = c.author_id
+            JOIN raffle_comment_flags f ON f.comment_id = c.id AND f.resolved_at IS NULL
+            WHERE c.status <> 'removed'
+            GROUP BY c.id, u.username
+            ORDER BY COUNT(f.id) DESC, MIN(f.created_at) ASC
+            LIMIT $1 OFFSET $2
+            "#,
+            limit,
+            offset
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ModerationQueueEntry {
+                comment: RaffleComment {
+                    id: row.id,
+                    raffle_id: row.raffle_id,
+                    parent_id: row.parent_id,
+                    author_id: row.author_id,
+                    author_username: row.author_username,
+                    body: row.body,
+                    is_seller_answer: row.is_seller_answer,
+                    is_highlighted: row.is_highlighted,
+                    status: row.status,
+                    flag_count: row.flag_count,
+                    created_at: row.created_at,
+                },
+                open_flags: row.open_flags,
+                reasons: row.reasons,
+                first_flagged_at: row.first_flagged_at,
+            })
+            .collect())
+    }
+
+    /// Resolve a comment's open flags by approving or removing it (admin)
+    pub async fn moderate_comment(
+        &self,
+        comment_id: Uuid,
+        moderator_id: Uuid,
+        request: ModerateCommentRequest,
+    ) -> Result<(), AppError> {
+        let comment = self.get_comment_row(comment_id).await?;
+        let new_status = match request.action {
+            ModerationAction::Approve => "visible",
+            ModerationAction::Remove => "removed",
+        };
+
+        let mut tx = self.db_pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE raffle_comments SET
+                status = $2, flag_count = 0,
+                moderated_by = $3, moderated_at = NOW(), moderation_reason = $4
+            WHERE id = $1
+            "#,
+            comment_id,
+            new_status,
+            moderator_id,
+            request.reason
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE raffle_comment_flags SET resolved_at = NOW() WHERE comment_id = $1 AND resolved_at IS NULL",
+            comment_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        info!(
+            "Moderator {} {:?} comment {} on raffle {}",
+            moderator_id, request.action, comment_id, comment.raffle_id
+        );
+
+        if request.action == ModerationAction::Remove && comment.status == "visible" {
+            self.broadcast_removed(comment.raffle_id, comment_id).await;
+        }
+
+        Ok(())
+    }
+
+    // Private helper methods
+
+    async fn get_comment_row(&self, comment_id: Uuid) -> Result<RaffleComment, AppError> {
+        sqlx::query_as!(
+            RaffleComment,
+            r#"
+            SELECT c.id, c.raffle_id, c.parent_id, c.author_id, u.username as author_username, c.body,
+                   c.is_seller_answer as "is_seller_answer!", c.is_highlighted as "is_highlighted!",
+                   c.status, c.flag_count as "flag_count!", c.created_at as "created_at!"
+            FROM raffle_comments c
+            JOIN users u ON u.id = c.author_id
+            WHERE c.id = $1 AND c.status <> 'removed'
+            "#,
+            comment_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Comment not found".to_string()))
+    }
+
+    /// User id of the seller running the raffle
+    async fn get_raffle_seller(&self, raffle_id: Uuid) -> Result<Option<Uuid>, AppError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT s.user_id
+            FROM raffles r
+            JOIN items i ON i.id = r.item_id
+            LEFT JOIN sellers s ON s.id = i.seller_id
+            WHERE r.id = $1
+            "#,
+            raffle_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
+
+        Ok(row.user_id)
+    }
+
+    async fn check_rate_limit(&self, author_id: Uuid) -> Result<(), AppError> {
+        let per_hour = self.get_setting("comment_rate_limit_per_hour", 20).await?;
+        let min_interval = self.get_setting("comment_min_interval_seconds", 10).await?;
+
+        let recent = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "count!", MAX(created_at) as last_posted_at
+            FROM raffle_comments
+            WHERE author_id = $1 AND created_at > NOW() - INTERVAL '1 hour'
+            "#,
+            author_id
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        if recent.count >= per_hour {
+            return Err(AppError::Validation(format!(
+                "Comment limit reached: at most {} comments per hour",
+                per_hour
+            )));
+        }
+
+        if let Some(last) = recent.last_posted_at {
+            if Utc::now() - last < chrono::Duration::seconds(min_interval) {
+                return Err(AppError::Validation(format!(
+                    "Please wait {} seconds between comments",
+                    min_interval
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get_setting(&self, key: &str, default: i64) -> Result<i64, AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, key).await?;
+        Ok(setting
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default))
+    }
+
+    async fn broadcast_removed(&self, raffle_id: Uuid, comment_id: Uuid) {
+        if let Err(e) = self
+            .realtime_service
+            .broadcast_event(RealtimeEvent::CommentRemoved {
+                raffle_id,
+                comment_id,
+                removed_at: Utc::now(),
+            })
+            .await
+        {
+            warn!("Failed to broadcast removal of comment {}: {}", comment_id, e);
+        }
+    }
+}
+
+/// Attach replies to their threads; the highlighted answer leads, then seller answers, then oldest first
+pub fn build_threads(top_level: Vec<RaffleComment>, replies: Vec<RaffleComment>) -> Vec<CommentThread> {
+    let mut by_parent: HashMap<Uuid, Vec<RaffleComment>> = HashMap::new();
+    for reply in replies {
+        if let Some(parent_id) = reply.parent_id {
+            by_parent.entry(parent_id).or_default().push(reply);
+        }
+    }
+
+    top_level
+        .into_iter()
+        .map(|comment| {
+            let mut replies = by_parent.remove(&comment.id).unwrap_or_default();
+            replies.sort_by(|a, b| {
+                b.is_highlighted
+                    .cmp(&a.is_highlighted)
+                    .then(b.is_seller_answer.cmp(&a.is_seller_answer))
+                    .then(a.created_at.cmp(&b.created_at))
+            });
+            CommentThread { comment, replies }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(parent_id: Option<Uuid>, minutes: i64, seller: bool, highlighted: bool) -> RaffleComment {
+        RaffleComment {
+            id: Uuid::new_v4(),
+            raffle_id: Uuid::nil(),
+            parent_id,
+            author_id: Uuid::new_v4(),
+            author_username: "user".to_string(),
+            body: "body".to_string(),
+            is_seller_answer: seller,
+            is_highlighted: highlighted,
+            status: "visible".to_string(),
+            flag_count: 0,
+            created_at: DateTime::from_timestamp(minutes * 60, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_build_threads_orders_replies() {
+        let question = comment(None, 0, false, false);
+        let early = comment(Some(question.id), 1, false, false);
+        let seller = comment(Some(question.id), 3, true, false);
+        let highlighted = comment(Some(question.id), 5, false, true);
+
+        let threads = build_threads(
+            vec![question.clone()],
+            vec![seller.clone(), early.clone(), highlighted.clone()],
+        );
+
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].replies, vec![highlighted, seller, early]);
+    }
+
+    #[test]
+    fn test_build_threads_drops_orphan_replies() {
+        let question = comment(None, 0, false, false);
+        let orphan = comment(Some(Uuid::new_v4()), 1, false, false);
+
+        let threads = build_threads(vec![question], vec![orphan]);
+
+        assert!(threads[0].replies.is_empty());
+    }
+}
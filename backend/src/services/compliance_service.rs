@@ -0,0 +1,560 @@
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::models::notification::{Notification, NotificationType};
+use crate::models::system_settings::SystemSetting;
+use crate::services::cache_invalidation::{CacheTag, TaggedCache};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Setting holding the prize value above which winners must verify
+const WINNER_THRESHOLD_KEY: &str = "kyc_winner_prize_threshold";
+const DEFAULT_WINNER_THRESHOLD: i64 = 600;
+
+pub type VerificationSessionFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<VerificationSession, AppError>> + Send + 'a>>;
+
+/// Identity verification provider running the hosted document and selfie checks
+pub trait IdentityProvider: Send + Sync {
+    /// Open a verification session for the user
+    fn create_session<'a>(&'a self, user_id: Uuid, purpose: VerificationPurpose) -> VerificationSessionFuture<'a>;
+}
+
+/// A session opened with the provider
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct VerificationSession {
+    /// The provider's id for the session, echoed back in its webhooks
+    pub id: String,
+    /// Hosted flow the user completes
+    pub url: Option<String>,
+}
+
+/// Provider answering `POST <url>/verifications` with `{"id": "ver_123", "url": "https://..."}`
+pub struct HttpIdentityProvider {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+}
+
+impl HttpIdentityProvider {
+    pub fn new(url: String, api_key: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+}
+
+impl IdentityProvider for HttpIdentityProvider {
+    fn create_session<'a>(&'a self, user_id: Uuid, purpose: VerificationPurpose) -> VerificationSessionFuture<'a> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .post(format!("{}/verifications", self.url))
+                .bearer_auth(&self.api_key)
+                .json(&serde_json::json!({
+                    "reference_id": user_id,
+                    "purpose": purpose,
+                }))
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(format!("Identity verification request failed: {}", e)))?;
+
+            if !response.status().is_success() {
+                return Err(AppError::Internal(format!(
+                    "Identity verification provider returned {}",
+                    response.status()
+                )));
+            }
+
+            response
+                .json()
+                .await
+                .map_err(|e| AppError::Internal(format!("Invalid identity verification response: {}", e)))
+        })
+    }
+}
+
+/// Where a user stands with identity verification
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum KycStatus {
+    Unverified,
+    /// A verification session is open with the provider
+    Pending,
+    Verified,
+    /// The last verification was declined; the user may try again
+    Rejected,
+}
+
+/// Why the user was asked to verify
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationPurpose {
+    /// Required before listing items
+    Seller,
+    /// Required before a valuable prize ships
+    Winner,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationStatus {
+    Pending,
+    Approved,
+    Declined,
+    /// The user didn't finish the session in time
+    Expired,
+}
+
+/// One verification session with the provider
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct IdentityVerification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub purpose: VerificationPurpose,
+    pub provider_reference: String,
+    pub session_url: Option<String>,
+    pub status: VerificationStatus,
+    pub decline_reason: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A user's verification status and their most recent session
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct KycOverview {
+    pub status: KycStatus,
+    pub verified_at: Option<DateTime<Utc>>,
+    /// Prizes worth more than this need a verified identity before they ship
+    pub winner_prize_threshold: Decimal,
+    pub latest_verification: Option<IdentityVerification>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct StartVerificationRequest {
+    pub purpose: VerificationPurpose,
+}
+
+/// A finished session reported by the provider's webhook
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderOutcome {
+    pub reference: String,
+    pub status: VerificationStatus,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+struct UserKyc {
+    kyc_status: KycStatus,
+    kyc_verified_at: Option<DateTime<Utc>>,
+}
+
+const COLUMNS: &str =
+    "id, user_id, purpose, provider_reference, session_url, status, decline_reason, completed_at, created_at";
+
+/// Compliance service verifies the identity of sellers and of winners of valuable prizes
+/// through an external provider, and holds back what they can't do until verified
+#[derive(Clone)]
+pub struct ComplianceService {
+    db_pool: PgPool,
+    provider: Option<Arc<dyn IdentityProvider>>,
+    cache: TaggedCache,
+}
+
+impl ComplianceService {
+    /// Create a new compliance service; without a provider only admins can verify sellers
+    pub fn new(db_pool: PgPool, provider: Option<Arc<dyn IdentityProvider>>) -> Self {
+        Self {
+            db_pool,
+            provider,
+            cache: TaggedCache::default(),
+        }
+    }
+
+    pub fn from_config(db_pool: PgPool, config: &AppConfig) -> Self {
+        let provider = match (&config.kyc_provider_url, &config.kyc_provider_api_key) {
+            (Some(url), Some(api_key)) => Some(
+                Arc::new(HttpIdentityProvider::new(url.clone(), api_key.clone())) as Arc<dyn IdentityProvider>,
+            ),
+            _ => None,
+        };
+
+        Self::new(db_pool, provider)
+    }
+
+    /// Invalidate seller checklists and listings when a seller's verification changes
+    pub fn with_cache(mut self, cache: TaggedCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// The user's verification status and latest session
+    pub async fn get_overview(&self, user_id: Uuid) -> Result<KycOverview, AppError> {
+        let user = self.user_kyc(user_id).await?;
+
+        let latest_verification = sqlx::query_as::<_, IdentityVerification>(&format!(
+            "SELECT {} FROM identity_verifications WHERE user_id = $1 ORDER BY created_at DESC LIMIT 1",
+            COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(KycOverview {
+            status: user.kyc_status,
+            verified_at: user.kyc_verified_at,
+            winner_prize_threshold: self.winner_prize_threshold().await,
+            latest_verification,
+        })
+    }
+
+    /// Open a verification session with the provider, or return the one still open
+    pub async fn start_verification(
+        &self,
+        user_id: Uuid,
+        request: StartVerificationRequest,
+    ) -> Result<IdentityVerification, AppError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or_else(|| AppError::Conflict("Identity verification is not available".to_string()))?;
+
+        if self.user_kyc(user_id).await?.kyc_status == KycStatus::Verified {
+            return Err(AppError::Conflict("Your identity is already verified".to_string()));
+        }
+
+        if let Some(open) = sqlx::query_as::<_, IdentityVerification>(&format!(
+            "SELECT {} FROM identity_verifications WHERE user_id = $1 AND status = 'pending'",
+            COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        {
+            return Ok(open);
+        }
+
+        let session = provider.create_session(user_id, request.purpose).await?;
+
+        let mut tx = self.db_pool.begin().await?;
+        let verification = sqlx::query_as::<_, IdentityVerification>(&format!(
+            r#"
+            INSERT INTO identity_verifications (user_id, purpose, provider_reference, session_url)
+            VALUES ($1, $2, $3, $4)
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(user_id)
+        .bind(request.purpose)
+        .bind(&session.id)
+        .bind(&session.url)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE users SET kyc_status = 'pending', updated_at = NOW() WHERE id = $1 AND kyc_status <> 'verified'")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        info!("Started {:?} identity verification {} for user {}", request.purpose, verification.id, user_id);
+
+        Ok(verification)
+    }
+
+    /// Apply a provider webhook delivered through the webhook service
+    pub async fn handle_provider_event(&self, payload: &Value) -> Result<(), AppError> {
+        let Some(outcome) = parse_provider_event(payload)? else {
+            return Ok(());
+        };
+
+        let mut tx = self.db_pool.begin().await?;
+        let verification = sqlx::query_as::<_, IdentityVerification>(&format!(
+            r#"
+            UPDATE identity_verifications
+            SET status = $2, decline_reason = $3, completed_at = NOW(), updated_at = NOW()
+            WHERE provider_reference = $1 AND status = 'pending'
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(&outcome.reference)
+        .bind(outcome.status)
+        .bind(&outcome.reason)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        // Unknown sessions and repeat deliveries change nothing
+        let Some(verification) = verification else {
+            warn!("Ignoring identity verification result for unknown or finished session {}", outcome.reference);
+            return Ok(());
+        };
+
+        let current = sqlx::query_as::<_, UserKyc>(
+            "SELECT kyc_status, kyc_verified_at FROM users WHERE id = $1 FOR UPDATE",
+        )
+        .bind(verification.user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let status = kyc_status_after(current.kyc_status, outcome.status);
+
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET kyc_status = $2,
+                kyc_verified_at = CASE WHEN $2 = 'verified' THEN COALESCE(kyc_verified_at, NOW()) ELSE kyc_verified_at END,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(verification.user_id)
+        .bind(status)
+        .execute(&mut *tx)
+        .await?;
+
+        if status == KycStatus::Verified {
+            sqlx::query("UPDATE sellers SET is_verified = TRUE, updated_at = NOW() WHERE user_id = $1")
+                .bind(verification.user_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        self.cache
+            .invalidate(&[CacheTag::Seller(verification.user_id), CacheTag::SellerListings])
+            .await;
+
+        info!(
+            "Identity verification {} for user {} finished as {:?}",
+            verification.id, verification.user_id, verification.status
+        );
+
+        self.notify_outcome(&verification).await;
+
+        Ok(())
+    }
+
+    pub async fn is_verified(&self, user_id: Uuid) -> Result<bool, AppError> {
+        Ok(self.user_kyc(user_id).await?.kyc_status == KycStatus::Verified)
+    }
+
+    /// Fail with a conflict while a prize worth more than the threshold waits on the
+    /// winner's verification
+    pub async fn ensure_fulfillment_allowed(&self, winner_id: Uuid, prize_value: Decimal) -> Result<(), AppError> {
+        if !requires_verification(prize_value, self.winner_prize_threshold().await) {
+            return Ok(());
+        }
+        if self.is_verified(winner_id).await? {
+            return Ok(());
+        }
+
+        Err(AppError::Conflict(
+            "Prize fulfillment is on hold until the winner verifies their identity".to_string(),
+        ))
+    }
+
+    /// Ask the unverified winners of a raffle's valuable item prizes to verify
+    pub async fn prompt_winners(&self, raffle_id: Uuid) -> Result<usize, AppError> {
+        let threshold = self.winner_prize_threshold().await;
+
+        let winners = sqlx::query_as::<_, (Uuid, Decimal)>(
+            r#"
+            SELECT t.winner_user_id, COALESCE(SUM(i.retail_price), 0)
+            FROM raffle_prize_tiers t
+            JOIN items i ON i.id = t.item_id
+            JOIN users u ON u.id = t.winner_user_id
+            WHERE t.raffle_id = $1 AND t.prize_type = 'item' AND u.kyc_status <> 'verified'
+            GROUP BY t.winner_user_id
+            "#,
+        )
+        .bind(raffle_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let mut prompted = 0;
+        for (winner_id, prize_value) in winners {
+            if !requires_verification(prize_value, threshold) {
+                continue;
+            }
+
+            if let Err(e) = Notification::create(
+                &self.db_pool,
+                winner_id,
+                "Verify your identity to receive your prize".to_string(),
+                "Your prize is valuable enough that we need to verify your identity before it ships.".to_string(),
+                NotificationType::SystemAlert,
+                Some(serde_json::json!({
+                    "event": "kyc_required",
+                    "raffle_id": raffle_id,
+                    "prize_value": prize_value
+                })),
+            )
+            .await
+            {
+                warn!("Failed to ask winner {} to verify: {}", winner_id, e);
+                continue;
+            }
+            prompted += 1;
+        }
+
+        Ok(prompted)
+    }
+
+    async fn winner_prize_threshold(&self) -> Decimal {
+        SystemSetting::get_by_key(&self.db_pool, WINNER_THRESHOLD_KEY)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|s| s.get_decimal_value())
+            .filter(|v| *v >= Decimal::ZERO)
+            .unwrap_or(Decimal::from(DEFAULT_WINNER_THRESHOLD))
+    }
+
+    async fn user_kyc(&self, user_id: Uuid) -> Result<UserKyc, AppError> {
+        sqlx::query_as::<_, UserKyc>("SELECT kyc_status, kyc_verified_at FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+    }
+
+    async fn notify_outcome(&self, verification: &IdentityVerification) {
+        let (title, message) = match verification.status {
+            VerificationStatus::Approved => (
+                "Identity verified",
+                match verification.purpose {
+                    VerificationPurpose::Seller => "Your identity is verified. You can now list items.",
+                    VerificationPurpose::Winner => "Your identity is verified. Your prize can now ship.",
+                },
+            ),
+            VerificationStatus::Declined => (
+                "Identity verification declined",
+                "We couldn't verify your identity. Check the reason and try again.",
+            ),
+            VerificationStatus::Expired => (
+                "Identity verification expired",
+                "Your identity verification wasn't finished in time. Start it again when you're ready.",
+            ),
+            VerificationStatus::Pending => return,
+        };
+
+        if let Err(e) = Notification::create(
+            &self.db_pool,
+            verification.user_id,
+            title.to_string(),
+            message.to_string(),
+            NotificationType::SystemAlert,
+            Some(serde_json::json!({
+                "event": "kyc_result",
+                "verification_id": verification.id,
+                "status": verification.status,
+                "reason": verification.decline_reason
+            })),
+        )
+        .await
+        {
+            warn!("Failed to notify user {} of their verification result: {}", verification.user_id, e);
+        }
+    }
+}
+
+/// Read a provider webhook, `{"type": "verification.completed", "data": {"reference": "ver_123",
+/// "status": "approved", "reason": null}}`; None for events that don't finish a session
+pub fn parse_provider_event(payload: &Value) -> Result<Option<ProviderOutcome>, AppError> {
+    let data = &payload["data"];
+    let reference = data["reference"]
+        .as_str()
+        .filter(|reference| !reference.is_empty())
+        .ok_or_else(|| AppError::Validation("Missing verification reference".to_string()))?;
+
+    let status = match data["status"].as_str() {
+        Some("approved") => VerificationStatus::Approved,
+        Some("declined") => VerificationStatus::Declined,
+        Some("expired") => VerificationStatus::Expired,
+        _ => return Ok(None),
+    };
+
+    Ok(Some(ProviderOutcome {
+        reference: reference.to_string(),
+        status,
+        reason: data["reason"].as_str().map(|reason| reason.to_string()),
+    }))
+}
+
+/// A verified user stays verified; otherwise the user takes the session's outcome
+pub fn kyc_status_after(current: KycStatus, outcome: VerificationStatus) -> KycStatus {
+    match (current, outcome) {
+        (KycStatus::Verified, _) | (_, VerificationStatus::Approved) => KycStatus::Verified,
+        (_, VerificationStatus::Declined) => KycStatus::Rejected,
+        (_, VerificationStatus::Expired) => KycStatus::Unverified,
+        (current, VerificationStatus::Pending) => current,
+    }
+}
+
+/// Prizes worth more than the threshold need a verified winner
+pub fn requires_verification(prize_value: Decimal, threshold: Decimal) -> bool {
+    prize_value > threshold
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_provider_event() {
+        let payload = serde_json::json!({
+            "id": "evt_1",
+            "type": "verification.completed",
+            "data": {"reference": "ver_123", "status": "declined", "reason": "Document expired"}
+        });
+
+        assert_eq!(
+            parse_provider_event(&payload).unwrap(),
+            Some(ProviderOutcome {
+                reference: "ver_123".to_string(),
+                status: VerificationStatus::Declined,
+                reason: Some("Document expired".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_provider_event_ignores_unfinished_sessions() {
+        let payload = serde_json::json!({"type": "verification.started", "data": {"reference": "ver_123", "status": "needs_review"}});
+        assert_eq!(parse_provider_event(&payload).unwrap(), None);
+
+        let missing = serde_json::json!({"type": "verification.completed", "data": {"status": "approved"}});
+        assert!(parse_provider_event(&missing).is_err());
+    }
+
+    #[test]
+    fn test_kyc_status_after() {
+        assert_eq!(kyc_status_after(KycStatus::Pending, VerificationStatus::Approved), KycStatus::Verified);
+        assert_eq!(kyc_status_after(KycStatus::Pending, VerificationStatus::Declined), KycStatus::Rejected);
+        assert_eq!(kyc_status_after(KycStatus::Pending, VerificationStatus::Expired), KycStatus::Unverified);
+        // A late decline of an older session doesn't undo a verification
+        assert_eq!(kyc_status_after(KycStatus::Verified, VerificationStatus::Declined), KycStatus::Verified);
+    }
+
+    #[test]
+    fn test_requires_verification_above_threshold_only() {
+        let threshold = Decimal::from(600);
+        assert!(!requires_verification(Decimal::from(600), threshold));
+        assert!(requires_verification(Decimal::new(60001, 2), threshold));
+        assert!(requires_verification(Decimal::ONE, Decimal::ZERO));
+    }
+}
@@ -1,29 +1,55 @@
 use crate::models::credit::UserCredit;
 use crate::models::item::Item;
+use crate::models::ledger::{accounts, funding_account, JournalEntry, NewJournalEntry, PostingLine};
+use crate::models::promotion::{Promotion, PromotionRedemption};
+use crate::models::system_settings::SystemSetting;
 use crate::models::user::User;
 use crate::error::AppError;
+use crate::repositories::{Cursor, CursorParams};
+use crate::services::cache_invalidation::{CacheTag, TaggedCache};
+use crate::services::realtime_service::{RealtimeEvent, RealtimeService};
 use chrono::{DateTime, Duration, Utc};
-use raffle_platform_shared::{CreditSource, CreditType, CreditResponse};
+use raffle_platform_shared::{CreditSource, CreditType, CreditResponse, CursorPage};
 use rust_decimal::Decimal;
-use sqlx::PgPool;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
 use std::collections::HashMap;
 use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 #[cfg(test)]
 mod tests;
 
+const DEFAULT_HOLD_MINUTES: i64 = 60;
+const DEFAULT_MAX_HOLD_MINUTES: i64 = 10080;
+const DEFAULT_VOID_WINDOW_HOURS: i64 = 24;
+const DEFAULT_EXPIRATION_INTERVAL_MINUTES: i64 = 60;
+const DEFAULT_EXPIRY_WARNING_DAYS: [i64; 3] = [7, 3, 1];
+const EXPIRATION_BATCH_SIZE: i64 = 1000;
+/// Bounds how stale `expiring_soon` gets; every change to the balance itself moves the cache key
+const BALANCE_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
 /// Credit management service handles all credit-related operations
 #[derive(Clone)]
 pub struct CreditService {
     db_pool: PgPool,
+    read_pool: PgPool,
+    realtime_service: Option<RealtimeService>,
+    cache: TaggedCache,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreditBalance {
+    /// Increases with every change to the user's credits or holds
+    pub version: i64,
     pub total_general: Decimal,
     pub total_item_specific: Decimal,
     pub total_available: Decimal,
+    /// General credits reserved by open partner holds
+    pub held: Decimal,
+    /// Available credits not reserved by holds
+    pub spendable: Decimal,
     pub expiring_soon: Decimal,
     pub expired: Decimal,
 }
@@ -55,7 +81,7 @@ pub struct CreditIssuanceRequest {
     pub description: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct ExpirationNotification {
     pub user_id: Uuid,
     pub credits: Vec<UserCredit>,
@@ -63,7 +89,74 @@ pub struct ExpirationNotification {
     pub days_until_expiry: i64,
 }
 
-#[derive(Debug, Clone)]
+/// Outcome of one expiration pass
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CreditExpirationRun {
+    pub credits_expired: u64,
+    pub users_affected: usize,
+    pub amount_expired: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum CreditHoldStatus {
+    Held,
+    Captured,
+    Released,
+    /// A capture that was reversed and refunded
+    Voided,
+    Expired,
+}
+
+/// Temporary reservation of a user's general credits by a partner integration
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreditHold {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub api_key_id: Uuid,
+    pub reference: String,
+    pub amount: Decimal,
+    pub captured_amount: Option<Decimal>,
+    pub status: CreditHoldStatus,
+    pub description: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub captured_at: Option<DateTime<Utc>>,
+    pub released_at: Option<DateTime<Utc>>,
+    pub voided_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct PlaceCreditHoldRequest {
+    pub user_id: Uuid,
+    pub amount: Decimal,
+    pub reference: String,
+    pub description: Option<String>,
+    /// Defaults to the `credit_hold_default_minutes` setting
+    pub expires_in_minutes: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct CaptureCreditHoldRequest {
+    /// Defaults to the full held amount; the rest of the hold is released
+    pub amount: Option<Decimal>,
+}
+
+/// A client's optimistic balance checked against the server's
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BalanceReconciliation {
+    /// The server's balance; clients should replace their optimistic balance with it
+    pub balance: CreditBalance,
+    /// The client's balance matches the server's version and spendable amount
+    pub in_sync: bool,
+    /// Balance changes the client has not seen
+    pub missed_versions: i64,
+    /// Server spendable minus the client's, when the client sent one
+    pub spendable_difference: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, ToSchema)]
 pub struct CreditStatistics {
     pub total_credits_issued: Decimal,
     pub total_credits_used: Decimal,
@@ -76,13 +169,41 @@ pub struct CreditStatistics {
 impl CreditService {
     /// Create a new credit service
     pub fn new(db_pool: PgPool) -> Self {
-        Self { db_pool }
+        Self { read_pool: db_pool.clone(), db_pool, realtime_service: None, cache: TaggedCache::default() }
     }
 
-    /// Issue credits to a user
+    /// Push balance changes to each user's realtime channel
+    pub fn with_realtime(mut self, realtime_service: RealtimeService) -> Self {
+        self.realtime_service = Some(realtime_service);
+        self
+    }
+
+    /// Run statistics queries on a read replica
+    pub fn with_read_pool(mut self, read_pool: PgPool) -> Self {
+        self.read_pool = read_pool;
+        self
+    }
+
+    /// Cache balances and invalidate them on credit writes
+    pub fn with_cache(mut self, cache: TaggedCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Issue credits to a user, funded by the ledger account for their source
     pub async fn issue_credits(
         &self,
         request: CreditIssuanceRequest,
+    ) -> Result<UserCredit, AppError> {
+        let funding = funding_account(request.source);
+        self.issue_credits_against(request, funding).await
+    }
+
+    /// Issue credits to a user, debiting `funding` in the ledger
+    pub async fn issue_credits_against(
+        &self,
+        request: CreditIssuanceRequest,
+        funding: &str,
     ) -> Result<UserCredit, AppError> {
         // Validate user exists
         let user = User::find_by_id(&self.db_pool, request.user_id).await?
@@ -99,9 +220,12 @@ impl CreditService {
             return Err(AppError::Validation("Credit amount must be positive".to_string()));
         }
 
+        // The credit, its audit row and its ledger entry commit together
+        let mut tx = self.db_pool.begin().await?;
+
         // Create the credit
         let credit = UserCredit::create(
-            &self.db_pool,
+            &mut *tx,
             request.user_id,
             request.amount,
             request.source,
@@ -111,7 +235,8 @@ impl CreditService {
         ).await?;
 
         // Log the credit issuance
-        self.log_credit_transaction(
+        log_credit_transaction(
+            &mut tx,
             request.user_id,
             request.amount,
             "credit_issued",
@@ -119,11 +244,22 @@ impl CreditService {
             Some(credit.id),
         ).await?;
 
+        JournalEntry::post(
+            &mut *tx,
+            &NewJournalEntry::new("user_credit", Some(credit.id), request.description.clone())
+                .line(PostingLine::debit(funding, credit.amount))
+                .line(PostingLine::credit(accounts::CUSTOMER_CREDITS, credit.amount).for_user(credit.user_id)),
+        ).await?;
+
+        tx.commit().await?;
+
         info!(
             "Issued {} credits to user {} (source: {:?}, type: {:?})",
             request.amount, request.user_id, request.source, request.credit_type
         );
 
+        self.balance_changed(request.user_id, "credits_issued").await;
+
         Ok(credit)
     }
 
@@ -171,6 +307,41 @@ impl CreditService {
         self.issue_credits(request).await
     }
 
+    /// Issue the bonus a credit multiplier adds to a credit purchase, if any
+    pub async fn issue_promotion_bonus(
+        &self,
+        promotion: &Promotion,
+        user_id: Uuid,
+        payment_id: Uuid,
+        purchased: Decimal,
+    ) -> Result<Option<UserCredit>, AppError> {
+        let bonus = promotion.bonus_credits(purchased);
+        if bonus <= Decimal::ZERO {
+            return Ok(None);
+        }
+
+        let credit = self.issue_bonus_credits(
+            user_id,
+            bonus,
+            None,
+            format!("{} bonus on a {} credit purchase", promotion.name, purchased),
+        ).await?;
+
+        Promotion::record_redemption(
+            &self.db_pool,
+            promotion.id,
+            PromotionRedemption {
+                user_id: Some(user_id),
+                payment_id: Some(payment_id),
+                raffle_id: None,
+                base_amount: purchased,
+                benefit_amount: bonus,
+            },
+        ).await?;
+
+        Ok(Some(credit))
+    }
+
     /// Issue refund credits
     pub async fn issue_refund_credits(
         &self,
@@ -198,6 +369,32 @@ impl CreditService {
     pub async fn redeem_credits(
         &self,
         request: CreditRedemptionRequest,
+    ) -> Result<CreditRedemptionResult, AppError> {
+        self.redeem_credits_against(request, accounts::RAFFLE_REVENUE).await
+    }
+
+    /// Redeem credits, crediting `counter_account` in the ledger with what was used
+    pub async fn redeem_credits_against(
+        &self,
+        request: CreditRedemptionRequest,
+        counter_account: &str,
+    ) -> Result<CreditRedemptionResult, AppError> {
+        let mut tx = self.db_pool.begin().await?;
+        let result = self.redeem_credits_in(&mut tx, &request, counter_account).await?;
+        tx.commit().await?;
+
+        self.credits_redeemed(&request, &result).await;
+
+        Ok(result)
+    }
+
+    /// Redeem credits in the caller's transaction, so the redemption is undone if anything
+    /// else in it fails. Call `credits_redeemed` once it commits.
+    pub async fn redeem_credits_in(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        request: &CreditRedemptionRequest,
+        counter_account: &str,
     ) -> Result<CreditRedemptionResult, AppError> {
         // Validate user exists
         User::find_by_id(&self.db_pool, request.user_id).await?
@@ -208,16 +405,19 @@ impl CreditService {
             return Err(AppError::Validation("Redemption amount must be positive".to_string()));
         }
 
+        // Serialize with holds and other redemptions so two can't both spend the same balance
+        lock_user_credits(tx, request.user_id).await?;
+
         // Find available credits
         let available_credits = UserCredit::find_available_by_user(
-            &self.db_pool,
+            &mut **tx,
             request.user_id,
             request.credit_type,
             request.item_id,
         ).await?;
 
-        // Check if sufficient credits are available
-        let total_available: Decimal = available_credits.iter().map(|c| c.amount).sum();
+        // Check if sufficient credits are available; credits under a partner hold are not spendable
+        let total_available = spendable_after_holds(&available_credits, held_amount(tx, request.user_id).await?);
         if total_available < request.amount {
             return Err(AppError::Validation(format!(
                 "Insufficient credits. Available: {}, Required: {}",
@@ -242,7 +442,7 @@ impl CreditService {
 
         // Use the credits
         let used_credits = UserCredit::mark_as_used(
-            &self.db_pool,
+            tx,
             &credits_to_use,
             request.amount,
         ).await?;
@@ -251,14 +451,15 @@ impl CreditService {
 
         // Calculate remaining balance
         let remaining_balance = UserCredit::calculate_total_available(
-            &self.db_pool,
+            &mut **tx,
             request.user_id,
             request.credit_type,
             request.item_id,
         ).await?;
 
         // Log the redemption
-        self.log_credit_transaction(
+        log_credit_transaction(
+            tx,
             request.user_id,
             total_amount_used,
             "credit_redeemed",
@@ -266,10 +467,12 @@ impl CreditService {
             None,
         ).await?;
 
-        info!(
-            "Redeemed {} credits for user {} (item_id: {:?})",
-            total_amount_used, request.user_id, request.item_id
-        );
+        JournalEntry::post(
+            &mut **tx,
+            &NewJournalEntry::new("credit_redemption", None, request.description.clone())
+                .line(PostingLine::debit(accounts::CUSTOMER_CREDITS, total_amount_used).for_user(request.user_id))
+                .line(PostingLine::credit(counter_account, total_amount_used)),
+        ).await?;
 
         Ok(CreditRedemptionResult {
             used_credits,
@@ -278,8 +481,39 @@ impl CreditService {
         })
     }
 
+    /// Announce a redemption made with `redeem_credits_in` once its transaction has committed
+    pub async fn credits_redeemed(&self, request: &CreditRedemptionRequest, result: &CreditRedemptionResult) {
+        info!(
+            "Redeemed {} credits for user {} (item_id: {:?})",
+            result.total_amount_used, request.user_id, request.item_id
+        );
+
+        self.balance_changed(request.user_id, "credits_redeemed").await;
+    }
+
     /// Get user's credit balance
+    ///
+    /// Cached per balance version, so a change made anywhere, not just through this service,
+    /// is never served from the cache.
     pub async fn get_user_balance(&self, user_id: Uuid) -> Result<CreditBalance, AppError> {
+        // Read the version first so a change racing this read leaves it behind the balance, never ahead
+        let version = sqlx::query_scalar::<_, i64>("SELECT credit_balance_version FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        self.cache
+            .get_or_load(
+                &format!("credit_balance:{}:{}", user_id, version),
+                &[CacheTag::UserCredits(user_id)],
+                BALANCE_CACHE_TTL,
+                || self.load_balance(user_id, version),
+            )
+            .await
+    }
+
+    async fn load_balance(&self, user_id: Uuid, version: i64) -> Result<CreditBalance, AppError> {
         // Get all available credits
         let available_credits = UserCredit::find_available_by_user(
             &self.db_pool,
@@ -310,33 +544,69 @@ impl CreditService {
         }
 
         let total_available = total_general + total_item_specific;
+        let held = self.get_held_amount(user_id).await?;
 
         // Get expired credits
         let expired = self.get_expired_credits_amount(user_id).await?;
 
         Ok(CreditBalance {
+            version,
             total_general,
             total_item_specific,
             total_available,
+            held,
+            spendable: (total_available - held).max(Decimal::ZERO),
             expiring_soon,
             expired,
         })
     }
 
-    /// Get user's credit history
+    /// Compare a client's optimistic balance with the server's
+    pub async fn reconcile_balance(
+        &self,
+        user_id: Uuid,
+        client_version: i64,
+        client_spendable: Option<Decimal>,
+    ) -> Result<BalanceReconciliation, AppError> {
+        let balance = self.get_user_balance(user_id).await?;
+        let reconciliation = reconcile(balance, client_version, client_spendable);
+
+        if !reconciliation.in_sync {
+            debug!(
+                "User {} balance out of sync: client version {}, server version {}",
+                user_id, client_version, reconciliation.balance.version
+            );
+        }
+
+        Ok(reconciliation)
+    }
+
+    /// Get user's credit history, newest first, one cursor page at a time
     pub async fn get_user_credit_history(
         &self,
         user_id: Uuid,
         include_used: bool,
         limit: Option<i64>,
-    ) -> Result<Vec<CreditResponse>, AppError> {
-        let mut credits = UserCredit::find_by_user(&self.db_pool, user_id, include_used).await?;
+        cursor: Option<&str>,
+    ) -> Result<CursorPage<CreditResponse>, AppError> {
+        let pagination = CursorParams::new(limit, cursor)?;
 
-        if let Some(limit) = limit {
-            credits.truncate(limit as usize);
-        }
+        let credits = UserCredit::find_by_user(
+            &self.db_pool,
+            user_id,
+            include_used,
+            pagination.fetch_limit(),
+            Cursor::bounds(pagination.after.as_ref()),
+        )
+        .await?;
 
-        Ok(credits.into_iter().map(|c| c.to_response()).collect())
+        let page = pagination.page(credits, |c| Cursor::new(c.created_at, c.id));
+
+        Ok(CursorPage {
+            data: page.data.into_iter().map(|c| c.to_response()).collect(),
+            next_cursor: page.next_cursor,
+            has_more: page.has_more,
+        })
     }
 
     /// Get expiring credits for a user
@@ -403,15 +673,100 @@ impl CreditService {
         Ok(notifications)
     }
 
-    /// Clean up expired credits
-    pub async fn cleanup_expired_credits(&self) -> Result<u64, AppError> {
-        let deleted_count = UserCredit::cleanup_expired(&self.db_pool).await?;
-        
-        if deleted_count > 0 {
-            info!("Cleaned up {} expired credits", deleted_count);
+    /// Mark unused credits past their expiry as expired and record each in the credit ledger.
+    /// Runs in batches, each in its own transaction, until none are left.
+    pub async fn expire_credits(&self) -> Result<CreditExpirationRun, AppError> {
+        let mut run = CreditExpirationRun::default();
+        let mut users = std::collections::HashSet::new();
+
+        loop {
+            let mut tx = self.db_pool.begin().await?;
+
+            let expired = sqlx::query_as::<_, (Uuid, Uuid, Decimal)>(
+                r#"
+                UPDATE user_credits SET expired_at = NOW()
+                WHERE id IN (
+                    SELECT id FROM user_credits
+                    WHERE is_used = false AND expired_at IS NULL AND expires_at <= NOW()
+                    ORDER BY expires_at
+                    LIMIT $1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id, user_id, amount
+                "#,
+            )
+            .bind(EXPIRATION_BATCH_SIZE)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            if expired.is_empty() {
+                tx.commit().await?;
+                break;
+            }
+
+            let credit_ids: Vec<Uuid> = expired.iter().map(|(id, _, _)| *id).collect();
+            let user_ids: Vec<Uuid> = expired.iter().map(|(_, user_id, _)| *user_id).collect();
+            let amounts: Vec<Decimal> = expired.iter().map(|(_, _, amount)| *amount).collect();
+
+            sqlx::query(
+                r#"
+                INSERT INTO credit_transactions (user_id, amount, transaction_type, description, credit_id)
+                SELECT user_id, amount, 'credit_expired', 'Credit expired unused', credit_id
+                FROM UNNEST($1::uuid[], $2::uuid[], $3::numeric[]) AS e(credit_id, user_id, amount)
+                WHERE amount > 0
+                "#,
+            )
+            .bind(&credit_ids)
+            .bind(&user_ids)
+            .bind(&amounts)
+            .execute(&mut *tx)
+            .await?;
+
+            JournalEntry::post(&mut *tx, &expiry_entry(&expired)).await?;
+
+            tx.commit().await?;
+
+            run.credits_expired += expired.len() as u64;
+            run.amount_expired += amounts.iter().copied().sum::<Decimal>();
+            users.extend(user_ids);
+
+            if (expired.len() as i64) < EXPIRATION_BATCH_SIZE {
+                break;
+            }
         }
 
-        Ok(deleted_count)
+        run.users_affected = users.len();
+        if run.credits_expired > 0 {
+            info!(
+                "Expired {} credits worth {} for {} users",
+                run.credits_expired, run.amount_expired, run.users_affected
+            );
+        }
+
+        for user_id in users {
+            self.balance_changed(user_id, "credits_expired").await;
+        }
+
+        Ok(run)
+    }
+
+    /// Record that an expiry warning went out so it isn't sent again
+    pub async fn record_expiry_warning(&self, notification: &ExpirationNotification) -> Result<(), AppError> {
+        let credit_ids: Vec<Uuid> = notification.credits.iter().map(|c| c.id).collect();
+
+        sqlx::query(
+            r#"
+            INSERT INTO credit_expiry_warnings (credit_id, days_before)
+            SELECT UNNEST($1::uuid[]), $2
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(&credit_ids)
+        .bind(notification.days_until_expiry as i32)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
     }
 
     /// Get credit statistics
@@ -420,7 +775,7 @@ impl CreditService {
         let total_issued = sqlx::query_scalar!(
             "SELECT COALESCE(SUM(amount), 0) FROM user_credits"
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(Decimal::ZERO);
 
@@ -428,7 +783,7 @@ impl CreditService {
         let total_used = sqlx::query_scalar!(
             "SELECT COALESCE(SUM(amount), 0) FROM user_credits WHERE is_used = true"
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(Decimal::ZERO);
 
@@ -436,7 +791,7 @@ impl CreditService {
         let total_expired = sqlx::query_scalar!(
             "SELECT COALESCE(SUM(amount), 0) FROM user_credits WHERE expires_at < NOW() AND is_used = false"
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(Decimal::ZERO);
 
@@ -449,7 +804,7 @@ impl CreditService {
             AND (expires_at IS NULL OR expires_at > NOW())
             "#
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(0);
 
@@ -461,7 +816,7 @@ impl CreditService {
             GROUP BY source
             "#
         )
-        .fetch_all(&self.db_pool)
+        .fetch_all(&self.read_pool)
         .await?;
 
         let mut credits_by_source = HashMap::new();
@@ -477,7 +832,7 @@ impl CreditService {
             GROUP BY credit_type
             "#
         )
-        .fetch_all(&self.db_pool)
+        .fetch_all(&self.read_pool)
         .await?;
 
         let mut credits_by_type = HashMap::new();
@@ -495,6 +850,302 @@ impl CreditService {
         })
     }
 
+    /// Reserve a user's general credits for a partner until captured, released or expired
+    ///
+    /// Repeating a request with the same reference returns the hold already placed.
+    pub async fn place_hold(
+        &self,
+        api_key_id: Uuid,
+        request: PlaceCreditHoldRequest,
+    ) -> Result<CreditHold, AppError> {
+        if request.amount <= Decimal::ZERO {
+            return Err(AppError::Validation("Hold amount must be positive".to_string()));
+        }
+
+        let reference = request.reference.trim();
+        if reference.is_empty() || reference.len() > 255 {
+            return Err(AppError::Validation("Hold reference must be 1 to 255 characters".to_string()));
+        }
+
+        User::find_by_id(&self.db_pool, request.user_id).await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        let minutes = hold_duration_minutes(
+            request.expires_in_minutes,
+            self.get_setting("credit_hold_default_minutes", DEFAULT_HOLD_MINUTES).await?,
+            self.get_setting("credit_hold_max_minutes", DEFAULT_MAX_HOLD_MINUTES).await?,
+        )?;
+
+        let mut tx = self.db_pool.begin().await?;
+
+        // Serialize with redemptions and other holds so two cannot both pass the balance check
+        lock_user_credits(&mut tx, request.user_id).await?;
+
+        let existing = sqlx::query_as!(
+            CreditHold,
+            r#"
+            SELECT id, user_id, api_key_id, reference, amount, captured_amount,
+                   status as "status: CreditHoldStatus", description, expires_at,
+                   captured_at, released_at, voided_at, created_at
+            FROM credit_holds
+            WHERE api_key_id = $1 AND reference = $2
+            "#,
+            api_key_id,
+            reference
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(existing) = existing {
+            if existing.user_id != request.user_id || existing.amount != request.amount {
+                return Err(AppError::Conflict("Hold reference is already used for a different hold".to_string()));
+            }
+            return Ok(existing);
+        }
+
+        let available = UserCredit::calculate_total_available(
+            &mut *tx,
+            request.user_id,
+            Some(CreditType::General),
+            None,
+        ).await?;
+        let spendable = available - held_amount(&mut tx, request.user_id).await?;
+
+        if spendable < request.amount {
+            return Err(AppError::Validation(format!(
+                "Insufficient credits. Available: {}, Required: {}",
+                spendable, request.amount
+            )));
+        }
+
+        let hold = sqlx::query_as!(
+            CreditHold,
+            r#"
+            INSERT INTO credit_holds (user_id, api_key_id, reference, amount, description, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, user_id, api_key_id, reference, amount, captured_amount,
+                      status as "status: CreditHoldStatus", description, expires_at,
+                      captured_at, released_at, voided_at, created_at
+            "#,
+            request.user_id,
+            api_key_id,
+            reference,
+            request.amount,
+            request.description,
+            Utc::now() + Duration::minutes(minutes)
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        info!(
+            "Placed credit hold {} of {} on user {} for API key {}",
+            hold.id, hold.amount, hold.user_id, api_key_id
+        );
+
+        self.balance_changed(hold.user_id, "hold_placed").await;
+
+        Ok(hold)
+    }
+
+    /// Get a hold placed with an API key
+    pub async fn get_hold(&self, api_key_id: Uuid, hold_id: Uuid) -> Result<CreditHold, AppError> {
+        sqlx::query_as!(
+            CreditHold,
+            r#"
+            SELECT id, user_id, api_key_id, reference, amount, captured_amount,
+                   status as "status: CreditHoldStatus", description, expires_at,
+                   captured_at, released_at, voided_at, created_at
+            FROM credit_holds
+            WHERE id = $1 AND api_key_id = $2
+            "#,
+            hold_id,
+            api_key_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Credit hold not found".to_string()))
+    }
+
+    /// Spend all or part of a hold; whatever is not captured goes back to the user
+    pub async fn capture_hold(
+        &self,
+        api_key_id: Uuid,
+        hold_id: Uuid,
+        request: CaptureCreditHoldRequest,
+    ) -> Result<CreditHold, AppError> {
+        let current = self.get_hold(api_key_id, hold_id).await?;
+        let amount = request.amount.unwrap_or(current.amount);
+
+        if amount <= Decimal::ZERO || amount > current.amount {
+            return Err(AppError::Validation(format!(
+                "Capture amount must be between 0 and the held {}",
+                current.amount
+            )));
+        }
+
+        // The claim and the redemption commit together, so a failed redemption leaves the hold open
+        let mut tx = self.db_pool.begin().await?;
+        lock_user_credits(&mut tx, current.user_id).await?;
+
+        // Claim the hold first so its amount no longer counts as held during redemption
+        let hold = sqlx::query_as!(
+            CreditHold,
+            r#"
+            UPDATE credit_holds
+            SET status = 'captured', captured_amount = $3, captured_at = NOW()
+            WHERE id = $1 AND api_key_id = $2 AND status = 'held' AND expires_at > NOW()
+            RETURNING id, user_id, api_key_id, reference, amount, captured_amount,
+                      status as "status: CreditHoldStatus", description, expires_at,
+                      captured_at, released_at, voided_at, created_at
+            "#,
+            hold_id,
+            api_key_id,
+            amount
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| hold_not_open(&current))?;
+
+        let redemption_request = CreditRedemptionRequest {
+            user_id: hold.user_id,
+            amount,
+            item_id: None,
+            credit_type: Some(CreditType::General),
+            description: format!("Capture of credit hold {}", hold.reference),
+        };
+        let redemption = self
+            .redeem_credits_in(&mut tx, &redemption_request, accounts::RAFFLE_REVENUE)
+            .await?;
+
+        tx.commit().await?;
+
+        self.credits_redeemed(&redemption_request, &redemption).await;
+
+        info!("Captured {} of credit hold {}", amount, hold_id);
+
+        Ok(hold)
+    }
+
+    /// End a hold without spending it
+    pub async fn release_hold(&self, api_key_id: Uuid, hold_id: Uuid) -> Result<CreditHold, AppError> {
+        let hold = sqlx::query_as!(
+            CreditHold,
+            r#"
+            UPDATE credit_holds
+            SET status = 'released', released_at = NOW()
+            WHERE id = $1 AND api_key_id = $2 AND status = 'held'
+            RETURNING id, user_id, api_key_id, reference, amount, captured_amount,
+                      status as "status: CreditHoldStatus", description, expires_at,
+                      captured_at, released_at, voided_at, created_at
+            "#,
+            hold_id,
+            api_key_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        match hold {
+            Some(hold) => {
+                info!("Released credit hold {}", hold_id);
+                self.balance_changed(hold.user_id, "hold_released").await;
+                Ok(hold)
+            }
+            None => Err(hold_not_open(&self.get_hold(api_key_id, hold_id).await?)),
+        }
+    }
+
+    /// Reverse a recent capture and refund the captured credits
+    pub async fn void_hold(&self, api_key_id: Uuid, hold_id: Uuid) -> Result<CreditHold, AppError> {
+        let window_hours = self.get_setting("credit_hold_void_window_hours", DEFAULT_VOID_WINDOW_HOURS).await?;
+
+        let hold = sqlx::query_as!(
+            CreditHold,
+            r#"
+            UPDATE credit_holds
+            SET status = 'voided', voided_at = NOW()
+            WHERE id = $1 AND api_key_id = $2 AND status = 'captured'
+            AND captured_at > NOW() - make_interval(hours => $3)
+            RETURNING id, user_id, api_key_id, reference, amount, captured_amount,
+                      status as "status: CreditHoldStatus", description, expires_at,
+                      captured_at, released_at, voided_at, created_at
+            "#,
+            hold_id,
+            api_key_id,
+            window_hours as i32
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        let hold = match hold {
+            Some(hold) => hold,
+            None => {
+                let current = self.get_hold(api_key_id, hold_id).await?;
+                return Err(match current.status {
+                    CreditHoldStatus::Captured => AppError::Conflict(format!(
+                        "Captures can only be voided within {} hours",
+                        window_hours
+                    )),
+                    status => AppError::Conflict(format!("Only captured holds can be voided; hold is {:?}", status)),
+                });
+            }
+        };
+
+        if let Some(captured) = hold.captured_amount {
+            self.issue_credits(CreditIssuanceRequest {
+                user_id: hold.user_id,
+                amount: captured,
+                source: CreditSource::Refund,
+                credit_type: CreditType::General,
+                redeemable_on_item_id: None,
+                expires_at: None,
+                description: format!("Voided capture of credit hold {}", hold.reference),
+            }).await?;
+        }
+
+        info!("Voided credit hold {}", hold_id);
+
+        Ok(hold)
+    }
+
+    /// Mark holds past their expiry as expired
+    pub async fn expire_holds(&self) -> Result<u64, AppError> {
+        let user_ids = sqlx::query_scalar::<_, Uuid>(
+            "UPDATE credit_holds SET status = 'expired' WHERE status = 'held' AND expires_at <= NOW() RETURNING user_id",
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let expired = user_ids.len() as u64;
+        if expired > 0 {
+            info!("Expired {} credit holds", expired);
+        }
+
+        let users: std::collections::HashSet<Uuid> = user_ids.into_iter().collect();
+        for user_id in users {
+            self.balance_changed(user_id, "hold_expired").await;
+        }
+
+        Ok(expired)
+    }
+
+    /// General credits currently reserved by open holds
+    pub async fn get_held_amount(&self, user_id: Uuid) -> Result<Decimal, AppError> {
+        let held = sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(amount), 0) as "held!"
+            FROM credit_holds
+            WHERE user_id = $1 AND status = 'held' AND expires_at > NOW()
+            "#,
+            user_id
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(held)
+    }
+
     /// Check if user has sufficient credits for a purchase
     pub async fn check_sufficient_credits(
         &self,
@@ -509,8 +1160,9 @@ impl CreditService {
             credit_type,
             item_id,
         ).await?;
+        let held = self.get_held_amount(user_id).await?;
 
-        Ok(available_amount >= required_amount)
+        Ok(available_amount - held >= required_amount)
     }
 
     /// Get free items available for credit redemption
@@ -527,7 +1179,7 @@ impl CreditService {
             Item,
             r#"
             SELECT 
-                id, seller_id, name, description, images, retail_price, cost_of_goods,
+                id, seller_id, name, description, images, retail_price, currency, cost_of_goods,
                 status as "status: ItemStatus", stock_quantity, listing_fee_applied, listing_fee_type,
                 created_at, updated_at
             FROM items 
@@ -573,7 +1225,7 @@ impl CreditService {
             description: format!("Free item redemption: {}", item.name),
         };
 
-        self.redeem_credits(request).await
+        self.redeem_credits_against(request, accounts::FREE_ITEM_COST).await
     }
 
     /// Get expired credits amount for a user
@@ -595,52 +1247,70 @@ impl CreditService {
         Ok(expired_amount)
     }
 
-    /// Log credit transaction for audit purposes
-    async fn log_credit_transaction(
-        &self,
-        user_id: Uuid,
-        amount: Decimal,
-        transaction_type: &str,
-        description: &str,
-        credit_id: Option<Uuid>,
-    ) -> Result<(), AppError> {
-        sqlx::query!(
-            r#"
-            INSERT INTO credit_transactions (user_id, amount, transaction_type, description, credit_id)
-            VALUES ($1, $2, $3, $4, $5)
-            "#,
-            user_id,
-            amount,
-            transaction_type,
-            description,
-            credit_id
-        )
-        .execute(&self.db_pool)
-        .await?;
+    async fn get_setting(&self, key: &str, default: i64) -> Result<i64, AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, key).await?;
+        Ok(setting
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default))
+    }
 
-        Ok(())
+    /// Drop the user's cached balance, then push the new one to their realtime channel. Only
+    /// connected users are pushed to, and a failed push never fails the change that caused it.
+    async fn balance_changed(&self, user_id: Uuid, reason: &str) {
+        self.cache.invalidate(&[CacheTag::UserCredits(user_id)]).await;
+
+        let Some(realtime_service) = &self.realtime_service else { return };
+        if !realtime_service.is_user_connected(user_id).await {
+            return;
+        }
+
+        match self.get_user_balance(user_id).await {
+            Ok(balance) => {
+                let _ = realtime_service.broadcast_event(RealtimeEvent::CreditBalanceChanged {
+                    user_id,
+                    reason: reason.to_string(),
+                    balance,
+                    changed_at: Utc::now(),
+                }).await;
+            }
+            Err(e) => warn!("Failed to push credit balance to user {}: {}", user_id, e),
+        }
     }
 
     /// Start background tasks for credit management
     pub async fn start_background_tasks(&self) {
         let service = self.clone();
         
-        // Daily cleanup of expired credits
+        let interval_minutes = self
+            .get_setting("credit_expiration_interval_minutes", DEFAULT_EXPIRATION_INTERVAL_MINUTES)
+            .await
+            .unwrap_or(DEFAULT_EXPIRATION_INTERVAL_MINUTES);
+
+        // Expire credits past their expiry date
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(86400)); // 24 hours
-            
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_minutes as u64 * 60));
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.expire_credits().await {
+                    error!("Failed to expire credits: {}", e);
+                }
+            }
+        });
+
+        let service = self.clone();
+
+        // Expire credit holds that were neither captured nor released
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+
             loop {
                 interval.tick().await;
-                
-                match service.cleanup_expired_credits().await {
-                    Ok(count) => {
-                        if count > 0 {
-                            info!("Daily cleanup: removed {} expired credits", count);
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to cleanup expired credits: {}", e);
-                    }
+
+                if let Err(e) = service.expire_holds().await {
+                    error!("Failed to expire credit holds: {}", e);
                 }
             }
         });
@@ -648,31 +1318,215 @@ impl CreditService {
         info!("Credit service background tasks started");
     }
 
-    /// Send expiration notifications (to be called by notification service)
+    /// Expiry warnings that are due, one per user and threshold (to be called by notification service).
+    /// A credit is warned at the smallest `credit_expiry_warning_days` threshold it has reached,
+    /// once per threshold.
     pub async fn get_expiration_notifications(&self) -> Result<Vec<ExpirationNotification>, AppError> {
-        // Get notifications for credits expiring in 7 days
-        let seven_day_notifications = self.get_users_with_expiring_credits(7).await?;
-        
-        // Get notifications for credits expiring in 1 day
-        let one_day_notifications = self.get_users_with_expiring_credits(1).await?;
-
-        // Combine and deduplicate
-        let mut all_notifications = seven_day_notifications;
-        all_notifications.extend(one_day_notifications);
-
-        // Remove duplicates (keep the one with shorter expiry)
-        let mut unique_notifications: HashMap<Uuid, ExpirationNotification> = HashMap::new();
-        for notification in all_notifications {
-            match unique_notifications.get(&notification.user_id) {
-                Some(existing) if existing.days_until_expiry <= notification.days_until_expiry => {
-                    // Keep existing (shorter expiry)
-                }
-                _ => {
-                    unique_notifications.insert(notification.user_id, notification);
-                }
+        let thresholds = self.expiry_warning_days().await?;
+        let Some(max_days) = thresholds.iter().copied().max() else {
+            return Ok(Vec::new());
+        };
+
+        let credits = sqlx::query_as::<_, UserCredit>(
+            r#"
+            SELECT id, user_id, amount, source, credit_type, redeemable_on_item_id, expires_at,
+                   is_transferable, is_used, used_at, created_at
+            FROM user_credits
+            WHERE is_used = false AND expired_at IS NULL
+            AND expires_at > NOW() AND expires_at <= NOW() + make_interval(days => $1)
+            ORDER BY user_id, expires_at
+            "#,
+        )
+        .bind(max_days as i32)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        if credits.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let credit_ids: Vec<Uuid> = credits.iter().map(|c| c.id).collect();
+        let warned: std::collections::HashSet<(Uuid, i32)> = sqlx::query_as::<_, (Uuid, i32)>(
+            "SELECT credit_id, days_before FROM credit_expiry_warnings WHERE credit_id = ANY($1)",
+        )
+        .bind(&credit_ids)
+        .fetch_all(&self.db_pool)
+        .await?
+        .into_iter()
+        .collect();
+
+        let now = Utc::now();
+        let mut due: HashMap<(Uuid, i64), Vec<UserCredit>> = HashMap::new();
+        for credit in credits {
+            let Some(expires_at) = credit.expires_at else { continue };
+            let Some(days) = warning_threshold(expires_at - now, &thresholds) else { continue };
+            if warned.contains(&(credit.id, days as i32)) {
+                continue;
             }
+            due.entry((credit.user_id, days)).or_default().push(credit);
         }
 
-        Ok(unique_notifications.into_values().collect())
+        Ok(due
+            .into_iter()
+            .map(|((user_id, days_until_expiry), credits)| ExpirationNotification {
+                user_id,
+                total_amount: credits.iter().map(|c| c.amount).sum(),
+                credits,
+                days_until_expiry,
+            })
+            .collect())
+    }
+
+    async fn expiry_warning_days(&self) -> Result<Vec<i64>, AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, "credit_expiry_warning_days").await?;
+        Ok(setting
+            .and_then(|s| parse_warning_days(&s.value))
+            .unwrap_or_else(|| DEFAULT_EXPIRY_WARNING_DAYS.to_vec()))
+    }
+}
+
+/// Log credit transaction for audit purposes
+async fn log_credit_transaction(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    amount: Decimal,
+    transaction_type: &str,
+    description: &str,
+    credit_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO credit_transactions (user_id, amount, transaction_type, description, credit_id)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        user_id,
+        amount,
+        transaction_type,
+        description,
+        credit_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Hold the user's credit lock until the transaction ends. Anything that checks a balance
+/// and then spends or reserves it takes this first.
+async fn lock_user_credits(tx: &mut Transaction<'_, Postgres>, user_id: Uuid) -> Result<(), AppError> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(user_id.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+async fn held_amount(tx: &mut Transaction<'_, Postgres>, user_id: Uuid) -> Result<Decimal, AppError> {
+    let held = sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(SUM(amount), 0) as "held!"
+        FROM credit_holds
+        WHERE user_id = $1 AND status = 'held' AND expires_at > NOW()
+        "#,
+        user_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(held)
+}
+
+fn hold_not_open(hold: &CreditHold) -> AppError {
+    if hold.status == CreditHoldStatus::Held {
+        AppError::Conflict("Credit hold has expired".to_string())
+    } else {
+        AppError::Conflict(format!("Credit hold is no longer open; it is {:?}", hold.status))
+    }
+}
+
+/// One entry per expiry batch: each user's liability goes down, expired credit revenue goes up
+pub fn expiry_entry(expired: &[(Uuid, Uuid, Decimal)]) -> NewJournalEntry {
+    let mut by_user: HashMap<Uuid, Decimal> = HashMap::new();
+    for (_, user_id, amount) in expired {
+        *by_user.entry(*user_id).or_default() += *amount;
+    }
+
+    let total: Decimal = by_user.values().copied().sum();
+    let mut entry = NewJournalEntry::new(
+        "credit_expiry",
+        None,
+        format!("{} credits expired unused", expired.len()),
+    );
+    for (user_id, amount) in by_user {
+        entry = entry.line(PostingLine::debit(accounts::CUSTOMER_CREDITS, amount).for_user(user_id));
+    }
+
+    entry.line(PostingLine::credit(accounts::EXPIRED_CREDITS, total))
+}
+
+/// Check a client's version and spendable amount against the server's balance
+pub fn reconcile(balance: CreditBalance, client_version: i64, client_spendable: Option<Decimal>) -> BalanceReconciliation {
+    let missed_versions = (balance.version - client_version).max(0);
+    let spendable_difference = client_spendable.map(|spendable| balance.spendable - spendable);
+
+    BalanceReconciliation {
+        in_sync: client_version == balance.version
+            && spendable_difference.map_or(true, |difference| difference.is_zero()),
+        missed_versions,
+        spendable_difference,
+        balance,
+    }
+}
+
+/// What a pool of credits can still pay once `held` is set aside. Holds reserve general
+/// credits only, so they take nothing from a pool of item-specific credits.
+pub fn spendable_after_holds(credits: &[UserCredit], held: Decimal) -> Decimal {
+    let total: Decimal = credits.iter().map(|c| c.amount).sum();
+    let general: Decimal = credits
+        .iter()
+        .filter(|c| c.credit_type == CreditType::General)
+        .map(|c| c.amount)
+        .sum();
+
+    total - held.min(general)
+}
+
+/// Requested hold duration, falling back to the default and capped at the maximum
+pub fn hold_duration_minutes(requested: Option<i64>, default_minutes: i64, max_minutes: i64) -> Result<i64, AppError> {
+    match requested {
+        Some(minutes) if minutes <= 0 => Err(AppError::Validation("Hold duration must be positive".to_string())),
+        Some(minutes) if minutes > max_minutes => Err(AppError::Validation(format!(
+            "Holds cannot last longer than {} minutes",
+            max_minutes
+        ))),
+        Some(minutes) => Ok(minutes),
+        None => Ok(default_minutes.min(max_minutes)),
+    }
+}
+
+/// Smallest warning threshold, in days, that the time left has reached
+pub fn warning_threshold(time_left: Duration, thresholds: &[i64]) -> Option<i64> {
+    if time_left <= Duration::zero() {
+        return None;
     }
-}
\ No newline at end of file
+
+    thresholds
+        .iter()
+        .copied()
+        .filter(|days| time_left <= Duration::days(*days))
+        .min()
+}
+
+/// Warning thresholds from a JSON array of day counts; an empty array turns warnings off
+pub fn parse_warning_days(value: &serde_json::Value) -> Option<Vec<i64>> {
+    let mut days: Vec<i64> = value
+        .as_array()?
+        .iter()
+        .filter_map(|v| v.as_i64())
+        .filter(|d| *d > 0)
+        .collect();
+    days.sort_unstable_by(|a, b| b.cmp(a));
+    days.dedup();
+    Some(days)
+}
@@ -137,6 +137,49 @@ mod tests {
         assert_eq!(result.used_credits.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_credit_changes_post_to_ledger() {
+        let pool = setup_test_pool().await;
+        let credit_service = CreditService::new(pool.clone());
+        let (user_id, _, _) = setup_test_data(&pool).await;
+
+        let credit = credit_service.issue_credits(CreditIssuanceRequest {
+            user_id,
+            amount: Decimal::from(100),
+            source: CreditSource::Bonus,
+            credit_type: CreditType::General,
+            redeemable_on_item_id: None,
+            expires_at: None,
+            description: "Ledger test credits".to_string(),
+        }).await.unwrap();
+
+        credit_service.redeem_credits(CreditRedemptionRequest {
+            user_id,
+            amount: Decimal::from(30),
+            item_id: None,
+            credit_type: None,
+            description: "Ledger test redemption".to_string(),
+        }).await.unwrap();
+
+        let issued: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM journal_entries WHERE source_type = 'user_credit' AND source_id = $1",
+        )
+        .bind(credit.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(issued, 1);
+
+        let redeemed: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM journal_entries WHERE source_type = 'credit_redemption' AND description = $1",
+        )
+        .bind("Ledger test redemption")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(redeemed, 1);
+    }
+
     #[tokio::test]
     async fn test_insufficient_credits() {
         let pool = setup_test_pool().await;
@@ -169,6 +212,42 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_concurrent_redemptions_cannot_overspend() {
+        let pool = setup_test_pool().await;
+        let credit_service = CreditService::new(pool.clone());
+        let (user_id, _, _) = setup_test_data(&pool).await;
+
+        credit_service.issue_credits(CreditIssuanceRequest {
+            user_id,
+            amount: Decimal::from(100),
+            source: CreditSource::Bonus,
+            credit_type: CreditType::General,
+            redeemable_on_item_id: None,
+            expires_at: None,
+            description: "Test credits".to_string(),
+        }).await.unwrap();
+
+        let redemption = || CreditRedemptionRequest {
+            user_id,
+            amount: Decimal::from(60),
+            item_id: None,
+            credit_type: Some(CreditType::General),
+            description: "Concurrent redemption".to_string(),
+        };
+
+        // Each passes the balance check alone; only one may spend
+        let (first, second) = tokio::join!(
+            credit_service.redeem_credits(redemption()),
+            credit_service.redeem_credits(redemption()),
+        );
+
+        assert_eq!([first.is_ok(), second.is_ok()].iter().filter(|ok| **ok).count(), 1);
+
+        let balance = credit_service.get_user_balance(user_id).await.unwrap();
+        assert_eq!(balance.total_available, Decimal::from(40));
+    }
+
     #[tokio::test]
     async fn test_expiring_credits() {
         let pool = setup_test_pool().await;
@@ -312,6 +391,110 @@ mod tests {
         assert_eq!(balance.total_available, Decimal::from(70));
     }
 
+    #[test]
+    fn test_hold_duration_minutes() {
+        assert_eq!(hold_duration_minutes(None, 60, 10080).unwrap(), 60);
+        assert_eq!(hold_duration_minutes(Some(15), 60, 10080).unwrap(), 15);
+        assert_eq!(hold_duration_minutes(None, 120, 90).unwrap(), 90);
+        assert!(hold_duration_minutes(Some(0), 60, 10080).is_err());
+        assert!(hold_duration_minutes(Some(20000), 60, 10080).is_err());
+    }
+
+    #[test]
+    fn test_warning_threshold() {
+        let thresholds = vec![7, 3, 1];
+        assert_eq!(warning_threshold(Duration::days(10), &thresholds), None);
+        assert_eq!(warning_threshold(Duration::days(7), &thresholds), Some(7));
+        assert_eq!(warning_threshold(Duration::hours(60), &thresholds), Some(3));
+        assert_eq!(warning_threshold(Duration::hours(5), &thresholds), Some(1));
+        assert_eq!(warning_threshold(Duration::zero(), &thresholds), None);
+        assert_eq!(warning_threshold(Duration::hours(5), &[]), None);
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let balance = |version| CreditBalance {
+            version,
+            total_general: Decimal::from(30),
+            total_item_specific: Decimal::ZERO,
+            total_available: Decimal::from(30),
+            held: Decimal::from(10),
+            spendable: Decimal::from(20),
+            expiring_soon: Decimal::ZERO,
+            expired: Decimal::ZERO,
+        };
+
+        let result = reconcile(balance(4), 4, Some(Decimal::from(20)));
+        assert!(result.in_sync);
+        assert_eq!(result.missed_versions, 0);
+        assert_eq!(result.spendable_difference, Some(Decimal::ZERO));
+
+        let result = reconcile(balance(7), 4, None);
+        assert!(!result.in_sync);
+        assert_eq!(result.missed_versions, 3);
+        assert_eq!(result.spendable_difference, None);
+
+        // Same version but the client's optimistic spend was wrong
+        let result = reconcile(balance(4), 4, Some(Decimal::from(15)));
+        assert!(!result.in_sync);
+        assert_eq!(result.spendable_difference, Some(Decimal::from(5)));
+
+        // A client ahead of the server never missed anything but is still out of sync
+        let result = reconcile(balance(4), 9, None);
+        assert!(!result.in_sync);
+        assert_eq!(result.missed_versions, 0);
+    }
+
+    #[test]
+    fn test_parse_warning_days() {
+        assert_eq!(parse_warning_days(&serde_json::json!([1, 7, 3, 3, 0])), Some(vec![7, 3, 1]));
+        assert_eq!(parse_warning_days(&serde_json::json!([])), Some(vec![]));
+        assert_eq!(parse_warning_days(&serde_json::json!("7")), None);
+    }
+
+    #[test]
+    fn test_expiry_entry_balances_per_user() {
+        let (alice, bob) = (Uuid::new_v4(), Uuid::new_v4());
+        let expired = vec![
+            (Uuid::new_v4(), alice, Decimal::from(5)),
+            (Uuid::new_v4(), bob, Decimal::from(3)),
+            (Uuid::new_v4(), alice, Decimal::from(2)),
+        ];
+
+        let entry = expiry_entry(&expired);
+
+        assert!(entry.imbalance().is_zero());
+        assert_eq!(entry.lines.len(), 3);
+        let alice_line = entry.lines.iter().find(|l| l.user_id == Some(alice)).unwrap();
+        assert_eq!(alice_line.amount, Decimal::from(7));
+        assert_eq!(entry.lines.last().unwrap().amount, Decimal::from(-10));
+    }
+
+    #[test]
+    fn test_holds_only_reduce_general_credits() {
+        let credit = |credit_type, amount| UserCredit {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            amount: Decimal::from(amount),
+            source: CreditSource::Deposit,
+            credit_type,
+            redeemable_on_item_id: None,
+            expires_at: None,
+            is_transferable: false,
+            is_used: false,
+            used_at: None,
+            created_at: Utc::now(),
+        };
+        let general = credit(CreditType::General, 30);
+        let item_specific = credit(CreditType::ItemSpecific, 20);
+
+        assert_eq!(spendable_after_holds(&[general.clone(), item_specific.clone()], Decimal::from(10)), Decimal::from(40));
+        // A pool without general credits is untouched by holds
+        assert_eq!(spendable_after_holds(&[item_specific.clone()], Decimal::from(10)), Decimal::from(20));
+        // Holds never take more than the general credits in the pool
+        assert_eq!(spendable_after_holds(&[general, item_specific], Decimal::from(50)), Decimal::from(20));
+    }
+
     // Helper function to set up test database pool
     async fn setup_test_pool() -> PgPool {
         // This would typically connect to a test database
@@ -0,0 +1,661 @@
+use crate::blockchain::types::RaffleStatus as ChainRaffleStatus;
+use crate::error::AppError;
+use crate::models::audit::AuditLog;
+use crate::services::blockchain_service::BlockchainService;
+use raffle_platform_shared::{AuditAction, RaffleStatus};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::net::IpAddr;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Data-fix service runs curated repairs for drifted raffle and credit data.
+///
+/// Every fix is previewed first: a dry run returns the diff and a plan hash, and the fix
+/// is only applied when the caller sends that hash back with a reason. If the data moved
+/// in between, the hash no longer matches and nothing is written.
+#[derive(Clone)]
+pub struct DataFixService {
+    db_pool: PgPool,
+    blockchain_service: BlockchainService,
+}
+
+/// The repairs on offer
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DataFixOperation {
+    ResyncRaffleFromChain,
+    RecountBoxesSold,
+    RebuildCreditBalance,
+    DetachOrphanedPurchases,
+}
+
+impl DataFixOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataFixOperation::ResyncRaffleFromChain => "resync_raffle_from_chain",
+            DataFixOperation::RecountBoxesSold => "recount_boxes_sold",
+            DataFixOperation::RebuildCreditBalance => "rebuild_credit_balance",
+            DataFixOperation::DetachOrphanedPurchases => "detach_orphaned_purchases",
+        }
+    }
+
+    fn resource_type(&self) -> &'static str {
+        match self {
+            DataFixOperation::RebuildCreditBalance => "user",
+            _ => "raffle",
+        }
+    }
+}
+
+/// One value a fix would change
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// Result of a data fix: the diff and whether it was written
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DataFixPlan {
+    pub operation: DataFixOperation,
+    pub target_id: Uuid,
+    pub changes: Vec<FieldChange>,
+    /// Send back with `dry_run: false` to apply exactly this diff
+    pub plan_hash: String,
+    pub applied: bool,
+}
+
+/// Dry run unless `dry_run` is false; applying needs the dry run's plan hash and a reason
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct DataFixRequest {
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+    pub plan_hash: Option<String>,
+    pub reason: Option<String>,
+}
+
+impl Default for DataFixRequest {
+    fn default() -> Self {
+        Self {
+            dry_run: true,
+            plan_hash: None,
+            reason: None,
+        }
+    }
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+/// Why a purchase counts as orphaned
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanReason {
+    /// The box number no longer exists on the raffle's grid
+    OutsideGrid,
+    /// Neither a credit redemption nor an on-chain purchase backs it
+    Unpaid,
+}
+
+impl OrphanReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrphanReason::OutsideGrid => "outside_grid",
+            OrphanReason::Unpaid => "unpaid",
+        }
+    }
+}
+
+/// Raffle counters as they are in the database, locked for the fix
+struct RaffleCounts {
+    total_boxes: i32,
+    boxes_sold: i32,
+    status: RaffleStatus,
+}
+
+struct OrphanedPurchase {
+    id: Uuid,
+    user_id: Uuid,
+    box_number: i32,
+    reason: OrphanReason,
+}
+
+/// What a planned fix writes when applied
+enum FixWrite {
+    Raffle {
+        boxes_sold: i32,
+        status: RaffleStatus,
+        detach: Vec<OrphanedPurchase>,
+    },
+    CreditBalance(Decimal),
+}
+
+impl DataFixService {
+    pub fn new(db_pool: PgPool, blockchain_service: BlockchainService) -> Self {
+        Self {
+            db_pool,
+            blockchain_service,
+        }
+    }
+
+    /// Set a raffle's sold count and status to what the contract reports
+    pub async fn resync_raffle_from_chain(
+        &self,
+        raffle_id: Uuid,
+        request: DataFixRequest,
+        admin_id: Uuid,
+        ip_address: Option<IpAddr>,
+    ) -> Result<DataFixPlan, AppError> {
+        let blockchain_raffle_id = sqlx::query_scalar!(
+            "SELECT blockchain_raffle_id FROM raffles WHERE id = $1",
+            raffle_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?
+        .ok_or_else(|| AppError::Validation("Raffle has no on-chain counterpart".to_string()))?;
+
+        // Read the chain before taking any row locks
+        let chain = self
+            .blockchain_service
+            .get_raffle(blockchain_raffle_id as u64)
+            .await
+            .map_err(|e| AppError::External(format!("Blockchain error: {}", e)))?;
+        let chain_sold = i32::try_from(chain.total_participants.low_u64())
+            .map_err(|_| AppError::External("On-chain participant count out of range".to_string()))?;
+
+        let mut tx = self.db_pool.begin().await?;
+        let counts = self.lock_raffle(&mut tx, raffle_id).await?;
+
+        if chain_sold > counts.total_boxes {
+            return Err(AppError::Validation(format!(
+                "Chain reports {} boxes sold but the raffle only has {}",
+                chain_sold, counts.total_boxes
+            )));
+        }
+
+        let status = status_from_chain(chain.status, counts.status, chain_sold, counts.total_boxes);
+        let changes = raffle_changes(&counts, chain_sold, status);
+        let write = FixWrite::Raffle {
+            boxes_sold: chain_sold,
+            status,
+            detach: Vec::new(),
+        };
+
+        self.finish(tx, DataFixOperation::ResyncRaffleFromChain, raffle_id, changes, write, request, admin_id, ip_address)
+            .await
+    }
+
+    /// Set a raffle's boxes_sold to the number of purchases on its grid
+    pub async fn recount_boxes_sold(
+        &self,
+        raffle_id: Uuid,
+        request: DataFixRequest,
+        admin_id: Uuid,
+        ip_address: Option<IpAddr>,
+    ) -> Result<DataFixPlan, AppError> {
+        let mut tx = self.db_pool.begin().await?;
+        let counts = self.lock_raffle(&mut tx, raffle_id).await?;
+
+        let counted = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM box_purchases
+            WHERE raffle_id = $1 AND box_number BETWEEN 1 AND $2
+            "#,
+            raffle_id,
+            counts.total_boxes
+        )
+        .fetch_one(&mut *tx)
+        .await? as i32;
+
+        let status = status_for_count(counts.status, counted, counts.total_boxes);
+        let changes = raffle_changes(&counts, counted, status);
+        let write = FixWrite::Raffle {
+            boxes_sold: counted,
+            status,
+            detach: Vec::new(),
+        };
+
+        self.finish(tx, DataFixOperation::RecountBoxesSold, raffle_id, changes, write, request, admin_id, ip_address)
+            .await
+    }
+
+    /// Set a user's cached credit balance to the sum of their available credits
+    pub async fn rebuild_credit_balance(
+        &self,
+        user_id: Uuid,
+        request: DataFixRequest,
+        admin_id: Uuid,
+        ip_address: Option<IpAddr>,
+    ) -> Result<DataFixPlan, AppError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        let cached = sqlx::query_scalar!(
+            "SELECT credit_balance FROM users WHERE id = $1 FOR UPDATE",
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        let ledger = sqlx::query_scalar!(
+            r#"SELECT get_user_credit_balance($1, NULL, NULL) as "balance!""#,
+            user_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let changes = diff_field("credit_balance", json!(cached), json!(ledger))
+            .into_iter()
+            .collect();
+
+        self.finish(
+            tx,
+            DataFixOperation::RebuildCreditBalance,
+            user_id,
+            changes,
+            FixWrite::CreditBalance(ledger),
+            request,
+            admin_id,
+            ip_address,
+        )
+        .await
+    }
+
+    /// Move purchases off the raffle that are outside its grid or have no payment behind them
+    pub async fn detach_orphaned_purchases(
+        &self,
+        raffle_id: Uuid,
+        request: DataFixRequest,
+        admin_id: Uuid,
+        ip_address: Option<IpAddr>,
+    ) -> Result<DataFixPlan, AppError> {
+        let mut tx = self.db_pool.begin().await?;
+        let counts = self.lock_raffle(&mut tx, raffle_id).await?;
+
+        let purchases = sqlx::query!(
+            r#"
+            SELECT id, user_id, box_number,
+                   transaction_id IS NOT NULL OR blockchain_tx_hash IS NOT NULL as "paid!"
+            FROM box_purchases
+            WHERE raffle_id = $1
+            ORDER BY box_number
+            FOR UPDATE
+            "#,
+            raffle_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut kept = 0;
+        let mut orphans = Vec::new();
+        for purchase in purchases {
+            match orphan_reason(purchase.box_number, counts.total_boxes, purchase.paid) {
+                Some(reason) => orphans.push(OrphanedPurchase {
+                    id: purchase.id,
+                    user_id: purchase.user_id,
+                    box_number: purchase.box_number,
+                    reason,
+                }),
+                None => kept += 1,
+            }
+        }
+
+        let status = status_for_count(counts.status, kept, counts.total_boxes);
+        let mut changes: Vec<FieldChange> = orphans
+            .iter()
+            .map(|orphan| FieldChange {
+                field: format!("box_purchases.{}", orphan.id),
+                before: json!({
+                    "user_id": orphan.user_id,
+                    "box_number": orphan.box_number,
+                    "orphan_reason": orphan.reason.as_str(),
+                }),
+                after: Value::Null,
+            })
+            .collect();
+        changes.extend(raffle_changes(&counts, kept, status));
+
+        let write = FixWrite::Raffle {
+            boxes_sold: kept,
+            status,
+            detach: orphans,
+        };
+
+        self.finish(tx, DataFixOperation::DetachOrphanedPurchases, raffle_id, changes, write, request, admin_id, ip_address)
+            .await
+    }
+
+    // Private helper methods
+
+    async fn lock_raffle(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        raffle_id: Uuid,
+    ) -> Result<RaffleCounts, AppError> {
+        sqlx::query_as!(
+            RaffleCounts,
+            r#"
+            SELECT total_boxes, boxes_sold, status as "status!: RaffleStatus"
+            FROM raffles
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            raffle_id
+        )
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))
+    }
+
+    /// Return the dry run, or check the guard, write, commit and audit the fix
+    #[allow(clippy::too_many_arguments)]
+    async fn finish(
+        &self,
+        mut tx: Transaction<'_, Postgres>,
+        operation: DataFixOperation,
+        target_id: Uuid,
+        changes: Vec<FieldChange>,
+        write: FixWrite,
+        request: DataFixRequest,
+        admin_id: Uuid,
+        ip_address: Option<IpAddr>,
+    ) -> Result<DataFixPlan, AppError> {
+        let hash = plan_hash(operation, target_id, &changes);
+        let mut plan = DataFixPlan {
+            operation,
+            target_id,
+            changes,
+            plan_hash: hash,
+            applied: false,
+        };
+
+        // Dry runs and no-op fixes end here; dropping the transaction releases the locks
+        if request.dry_run || plan.changes.is_empty() {
+            return Ok(plan);
+        }
+
+        let reason = check_apply_guard(&request, &plan.plan_hash)?;
+
+        match write {
+            FixWrite::Raffle { boxes_sold, status, detach } => {
+                for orphan in &detach {
+                    self.archive_purchase(&mut tx, orphan, admin_id, &reason).await?;
+                }
+
+                sqlx::query!(
+                    "UPDATE raffles SET boxes_sold = $1, status = $2, updated_at = NOW() WHERE id = $3",
+                    boxes_sold,
+                    status as RaffleStatus,
+                    target_id
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+            FixWrite::CreditBalance(balance) => {
+                sqlx::query!(
+                    "UPDATE users SET credit_balance = $1, updated_at = NOW() WHERE id = $2",
+                    balance,
+                    target_id
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+        plan.applied = true;
+
+        info!(
+            "Admin {} applied data fix {} to {} ({} changes): {}",
+            admin_id,
+            operation.as_str(),
+            target_id,
+            plan.changes.len(),
+            reason
+        );
+
+        let (old_values, new_values) = audit_values(&plan, &reason);
+        if let Err(e) = AuditLog::create(
+            &self.db_pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some(operation.resource_type().to_string()),
+            Some(target_id),
+            Some(old_values),
+            Some(new_values),
+            ip_address,
+            None,
+        )
+        .await
+        {
+            warn!("Failed to audit data fix {} on {}: {}", operation.as_str(), target_id, e);
+        }
+
+        Ok(plan)
+    }
+
+    /// Copy a purchase and its receipt into the archive, then remove it from the raffle
+    async fn archive_purchase(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        orphan: &OrphanedPurchase,
+        admin_id: Uuid,
+        note: &str,
+    ) -> Result<(), AppError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO detached_box_purchases (
+                purchase_id, raffle_id, user_id, box_number, purchase_price_in_credits,
+                transaction_id, blockchain_tx_hash, purchased_at, receipt,
+                orphan_reason, detached_by, detach_note
+            )
+            SELECT bp.id, bp.raffle_id, bp.user_id, bp.box_number, bp.purchase_price_in_credits,
+                   bp.transaction_id, bp.blockchain_tx_hash, bp.created_at,
+                   (SELECT to_jsonb(pr) FROM purchase_receipts pr WHERE pr.transaction_id = COALESCE(bp.transaction_id, bp.id)),
+                   $2, $3, $4
+            FROM box_purchases bp
+            WHERE bp.id = $1
+            "#,
+            orphan.id,
+            orphan.reason.as_str(),
+            admin_id,
+            note
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query!("DELETE FROM box_purchases WHERE id = $1", orphan.id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Deterministic hash of a planned diff, so an apply can prove it saw the same data
+pub fn plan_hash(operation: DataFixOperation, target_id: Uuid, changes: &[FieldChange]) -> String {
+    let canonical = json!({
+        "operation": operation.as_str(),
+        "target_id": target_id,
+        "changes": changes,
+    });
+    hex::encode(Sha256::digest(canonical.to_string().as_bytes()))
+}
+
+/// The reason to record, once the request is allowed to apply this plan
+pub(crate) fn check_apply_guard(request: &DataFixRequest, expected_hash: &str) -> Result<String, AppError> {
+    let reason = request
+        .reason
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .ok_or_else(|| AppError::Validation("A reason is required to apply a data fix".to_string()))?;
+
+    match request.plan_hash.as_deref() {
+        None => Err(AppError::Validation(
+            "plan_hash from a dry run is required to apply a data fix".to_string(),
+        )),
+        Some(hash) if hash != expected_hash => Err(AppError::Conflict(
+            "Data changed since the dry run; preview the fix again".to_string(),
+        )),
+        Some(_) => Ok(reason.to_string()),
+    }
+}
+
+fn diff_field(field: &str, before: Value, after: Value) -> Option<FieldChange> {
+    (before != after).then(|| FieldChange {
+        field: field.to_string(),
+        before,
+        after,
+    })
+}
+
+fn raffle_changes(counts: &RaffleCounts, boxes_sold: i32, status: RaffleStatus) -> Vec<FieldChange> {
+    [
+        diff_field("boxes_sold", json!(counts.boxes_sold), json!(boxes_sold)),
+        diff_field("status", json!(counts.status.to_string()), json!(status.to_string())),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Open and full follow the sold count; every other status is left alone
+pub(crate) fn status_for_count(current: RaffleStatus, boxes_sold: i32, total_boxes: i32) -> RaffleStatus {
+    match current {
+        RaffleStatus::Open | RaffleStatus::Full if boxes_sold >= total_boxes => RaffleStatus::Full,
+        RaffleStatus::Open | RaffleStatus::Full => RaffleStatus::Open,
+        other => other,
+    }
+}
+
+/// A settled contract wins; while it is still active the database status stands
+fn status_from_chain(
+    chain: ChainRaffleStatus,
+    current: RaffleStatus,
+    boxes_sold: i32,
+    total_boxes: i32,
+) -> RaffleStatus {
+    match chain {
+        ChainRaffleStatus::Completed => RaffleStatus::Completed,
+        ChainRaffleStatus::Cancelled => RaffleStatus::Cancelled,
+        ChainRaffleStatus::Active => status_for_count(current, boxes_sold, total_boxes),
+    }
+}
+
+fn orphan_reason(box_number: i32, total_boxes: i32, paid: bool) -> Option<OrphanReason> {
+    if box_number < 1 || box_number > total_boxes {
+        Some(OrphanReason::OutsideGrid)
+    } else if !paid {
+        Some(OrphanReason::Unpaid)
+    } else {
+        None
+    }
+}
+
+fn audit_values(plan: &DataFixPlan, reason: &str) -> (Value, Value) {
+    let before = plan
+        .changes
+        .iter()
+        .map(|c| (c.field.clone(), c.before.clone()))
+        .collect::<serde_json::Map<_, _>>();
+    let after = plan
+        .changes
+        .iter()
+        .map(|c| (c.field.clone(), c.after.clone()))
+        .collect::<serde_json::Map<_, _>>();
+
+    (
+        Value::Object(before),
+        json!({
+            "event": "data_fix",
+            "operation": plan.operation.as_str(),
+            "reason": reason,
+            "plan_hash": plan.plan_hash,
+            "values": after,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(field: &str, before: i32, after: i32) -> FieldChange {
+        FieldChange {
+            field: field.to_string(),
+            before: json!(before),
+            after: json!(after),
+        }
+    }
+
+    #[test]
+    fn test_plan_hash_tracks_the_diff() {
+        let target = Uuid::new_v4();
+        let changes = vec![change("boxes_sold", 10, 12)];
+
+        let hash = plan_hash(DataFixOperation::RecountBoxesSold, target, &changes);
+        assert_eq!(hash, plan_hash(DataFixOperation::RecountBoxesSold, target, &changes));
+        assert_ne!(hash, plan_hash(DataFixOperation::RecountBoxesSold, target, &[change("boxes_sold", 11, 12)]));
+        assert_ne!(hash, plan_hash(DataFixOperation::ResyncRaffleFromChain, target, &changes));
+        assert_ne!(hash, plan_hash(DataFixOperation::RecountBoxesSold, Uuid::new_v4(), &changes));
+    }
+
+    #[test]
+    fn test_apply_guard() {
+        let mut request = DataFixRequest {
+            dry_run: false,
+            plan_hash: Some("abc".to_string()),
+            reason: Some("  stuck after outage ".to_string()),
+        };
+        assert_eq!(check_apply_guard(&request, "abc").unwrap(), "stuck after outage");
+        assert!(matches!(check_apply_guard(&request, "def"), Err(AppError::Conflict(_))));
+
+        request.plan_hash = None;
+        assert!(matches!(check_apply_guard(&request, "abc"), Err(AppError::Validation(_))));
+
+        request.plan_hash = Some("abc".to_string());
+        request.reason = Some(" ".to_string());
+        assert!(matches!(check_apply_guard(&request, "abc"), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn test_status_follows_count() {
+        assert_eq!(status_for_count(RaffleStatus::Full, 9, 10), RaffleStatus::Open);
+        assert_eq!(status_for_count(RaffleStatus::Open, 10, 10), RaffleStatus::Full);
+        assert_eq!(status_for_count(RaffleStatus::Drawing, 9, 10), RaffleStatus::Drawing);
+        assert_eq!(status_for_count(RaffleStatus::Completed, 3, 10), RaffleStatus::Completed);
+
+        assert_eq!(
+            status_from_chain(ChainRaffleStatus::Completed, RaffleStatus::Drawing, 10, 10),
+            RaffleStatus::Completed
+        );
+        assert_eq!(
+            status_from_chain(ChainRaffleStatus::Active, RaffleStatus::Full, 8, 10),
+            RaffleStatus::Open
+        );
+    }
+
+    #[test]
+    fn test_orphan_reason() {
+        assert_eq!(orphan_reason(5, 10, true), None);
+        assert_eq!(orphan_reason(11, 10, true), Some(OrphanReason::OutsideGrid));
+        assert_eq!(orphan_reason(0, 10, false), Some(OrphanReason::OutsideGrid));
+        assert_eq!(orphan_reason(5, 10, false), Some(OrphanReason::Unpaid));
+    }
+
+    #[test]
+    fn test_no_change_no_diff() {
+        assert!(diff_field("credit_balance", json!("12.50"), json!("12.50")).is_none());
+        assert!(diff_field("credit_balance", json!("12.50"), json!("10.00")).is_some());
+    }
+}
@@ -0,0 +1,507 @@
+use crate::blockchain::types::TransactionState;
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use crate::services::blockchain_service::BlockchainService;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// How often full raffles are enrolled and due draws are requested
+const SCHEDULE_INTERVAL_SECONDS: u64 = 60;
+/// Draws claimed per tick
+const BATCH_SIZE: i64 = 20;
+/// Time the contract's own request from the last box purchase has to show up as an event
+const ENROL_GRACE_SECONDS: i64 = 300;
+/// A claimed draw is left alone by other instances for this long
+const CLAIM_LEASE_SECONDS: i64 = 300;
+/// After a request is sent, check back this soon whether its transaction went through
+const CONFIRM_CHECK_SECONDS: i64 = 300;
+const RETRY_BASE_SECONDS: i64 = 60;
+const RETRY_MAX_SECONDS: i64 = 3600;
+const DEFAULT_RANDOMNESS_TIMEOUT_MINUTES: i64 = 30;
+const DEFAULT_ALERT_MINUTES: i64 = 60;
+/// Longest error kept on a draw
+const MAX_ERROR_CHARS: usize = 1000;
+
+const DRAW_SELECT: &str = r#"
+    SELECT s.raffle_id, s.blockchain_raffle_id, s.attempts, s.consecutive_failures, s.next_attempt_at,
+           s.last_attempt_at, s.last_error, s.requested_at, s.blockchain_tx_id, s.alerted_at, s.created_at,
+           e.randomness_requested_at,
+           LEAST(e.first_requested_at, s.created_at) AS waiting_since,
+           e.winner_drawn
+    FROM raffle_draw_schedule s
+    CROSS JOIN LATERAL (
+        SELECT MAX(be.timestamp) FILTER (WHERE be.event_type = 'randomness_requested') AS randomness_requested_at,
+               MIN(be.timestamp) FILTER (WHERE be.event_type = 'randomness_requested') AS first_requested_at,
+               COALESCE(BOOL_OR(be.event_type = 'winner_selected'), false) AS winner_drawn
+        FROM blockchain_events be
+        WHERE be.raffle_id = s.blockchain_raffle_id
+    ) e
+"#;
+
+/// A full raffle waiting for its draw
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct ScheduledDraw {
+    pub raffle_id: Uuid,
+    pub blockchain_raffle_id: i64,
+    /// Randomness requests the scheduler has sent, successful or not
+    pub attempts: i32,
+    pub consecutive_failures: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    /// When the scheduler's latest request was accepted
+    pub requested_at: Option<DateTime<Utc>>,
+    pub blockchain_tx_id: Option<Uuid>,
+    /// Set once the draw was reported overdue
+    pub alerted_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    /// Latest `RandomnessRequested` event stored for the raffle
+    pub randomness_requested_at: Option<DateTime<Utc>>,
+    /// The first randomness request, or when the scheduler found the raffle full
+    pub waiting_since: DateTime<Utc>,
+    /// The contract drew winners; the event processor or the runbook completes the raffle
+    pub winner_drawn: bool,
+}
+
+/// What to do with a draw that has come due
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DrawDecision {
+    Request,
+    WaitUntil(DateTime<Utc>),
+}
+
+/// Settings read once per tick
+#[derive(Debug, Clone, Copy)]
+struct DrawPolicy {
+    auto_request: bool,
+    randomness_timeout: Duration,
+    alert_after: Duration,
+}
+
+/// Draw scheduler service requests randomness for full raffles so draws don't wait for an operator.
+///
+/// The contract requests randomness itself when the last box sells. Each full raffle on chain is
+/// enrolled in `raffle_draw_schedule`; when no request shows up, or the latest one has gone
+/// unanswered for `raffle_randomness_timeout_minutes`, the scheduler calls `requestRandomWinner`,
+/// backing off after failures. Instances claim due draws with a lease, so only one sends each
+/// request. Draws pending past `raffle_draw_alert_minutes` are logged once and exported as a
+/// metric. Chainlink Automation on the contract is the fallback trigger if the backend is down.
+#[derive(Clone)]
+pub struct DrawSchedulerService {
+    db_pool: PgPool,
+    blockchain_service: BlockchainService,
+}
+
+impl DrawSchedulerService {
+    pub fn new(db_pool: PgPool, blockchain_service: BlockchainService) -> Self {
+        Self {
+            db_pool,
+            blockchain_service,
+        }
+    }
+
+    /// Start the scheduling loop
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(SCHEDULE_INTERVAL_SECONDS));
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.run_once().await {
+                    error!("Draw scheduling failed: {}", e);
+                }
+            }
+        });
+
+        info!("Draw scheduler started");
+    }
+
+    /// Enrol full raffles, request due draws and report overdue ones
+    pub async fn run_once(&self) -> Result<(), AppError> {
+        let policy = self.policy().await?;
+
+        self.sync_schedule().await?;
+
+        for draw in self.claim_due().await? {
+            let raffle_id = draw.raffle_id;
+            if let Err(e) = self.process(draw, &policy).await {
+                warn!("Failed to schedule the draw of raffle {}: {}", raffle_id, e);
+            }
+        }
+
+        self.report_overdue(&policy).await
+    }
+
+    /// Draws still waiting, longest waiting first
+    pub async fn list(&self) -> Result<Vec<ScheduledDraw>, AppError> {
+        let draws = sqlx::query_as::<_, ScheduledDraw>(&format!("{} ORDER BY waiting_since", DRAW_SELECT))
+            .fetch_all(&self.db_pool)
+            .await?;
+
+        Ok(draws)
+    }
+
+    /// Check the draw on the next tick, without waiting out its backoff
+    pub async fn retry_now(&self, raffle_id: Uuid, admin_id: Uuid) -> Result<ScheduledDraw, AppError> {
+        let updated = sqlx::query(
+            "UPDATE raffle_draw_schedule SET next_attempt_at = NOW(), consecutive_failures = 0, updated_at = NOW() \
+             WHERE raffle_id = $1",
+        )
+        .bind(raffle_id)
+        .execute(&self.db_pool)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Err(AppError::NotFound("Raffle is not waiting for its draw".to_string()));
+        }
+
+        info!("Admin {} asked for the draw of raffle {} to be retried", admin_id, raffle_id);
+        self.get(raffle_id).await
+    }
+
+    // Private helper methods
+
+    async fn policy(&self) -> Result<DrawPolicy, AppError> {
+        let auto_request = SystemSetting::get_by_key(&self.db_pool, "raffle_draw_auto_request")
+            .await?
+            .and_then(|s| s.get_bool_value())
+            .unwrap_or(true);
+        let timeout_minutes = self
+            .get_setting("raffle_randomness_timeout_minutes", DEFAULT_RANDOMNESS_TIMEOUT_MINUTES)
+            .await?;
+        let alert_minutes = self.get_setting("raffle_draw_alert_minutes", DEFAULT_ALERT_MINUTES).await?;
+
+        Ok(DrawPolicy {
+            auto_request,
+            randomness_timeout: Duration::minutes(timeout_minutes),
+            alert_after: Duration::minutes(alert_minutes),
+        })
+    }
+
+    async fn get_setting(&self, key: &str, default: i64) -> Result<i64, AppError> {
+        Ok(SystemSetting::get_by_key(&self.db_pool, key)
+            .await?
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default))
+    }
+
+    async fn get(&self, raffle_id: Uuid) -> Result<ScheduledDraw, AppError> {
+        sqlx::query_as::<_, ScheduledDraw>(&format!("{} WHERE s.raffle_id = $1", DRAW_SELECT))
+            .bind(raffle_id)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Raffle is not waiting for its draw".to_string()))
+    }
+
+    /// Enrol raffles that became full and drop the ones that were drawn or cancelled
+    async fn sync_schedule(&self) -> Result<(), AppError> {
+        let enrolled = sqlx::query(
+            r#"
+            INSERT INTO raffle_draw_schedule (raffle_id, blockchain_raffle_id, next_attempt_at)
+            SELECT id, blockchain_raffle_id, NOW() + make_interval(secs => $1)
+            FROM raffles
+            WHERE status IN ('full', 'drawing') AND blockchain_raffle_id IS NOT NULL
+            ON CONFLICT (raffle_id) DO NOTHING
+            "#,
+        )
+        .bind(ENROL_GRACE_SECONDS as f64)
+        .execute(&self.db_pool)
+        .await?;
+
+        let finished = sqlx::query(
+            r#"
+            DELETE FROM raffle_draw_schedule s
+            USING raffles r
+            WHERE r.id = s.raffle_id AND r.status NOT IN ('full', 'drawing')
+            "#,
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        if enrolled.rows_affected() > 0 || finished.rows_affected() > 0 {
+            info!(
+                "Draw schedule: {} raffles enrolled, {} finished",
+                enrolled.rows_affected(),
+                finished.rows_affected()
+            );
+        }
+        Ok(())
+    }
+
+    /// Take a lease on due draws so other instances skip them
+    async fn claim_due(&self) -> Result<Vec<ScheduledDraw>, AppError> {
+        let claimed = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE raffle_draw_schedule
+            SET next_attempt_at = NOW() + make_interval(secs => $2), updated_at = NOW()
+            WHERE raffle_id IN (
+                SELECT raffle_id FROM raffle_draw_schedule
+                WHERE next_attempt_at <= NOW()
+                ORDER BY next_attempt_at
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING raffle_id
+            "#,
+        )
+        .bind(BATCH_SIZE)
+        .bind(CLAIM_LEASE_SECONDS as f64)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        if claimed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let draws = sqlx::query_as::<_, ScheduledDraw>(&format!("{} WHERE s.raffle_id = ANY($1)", DRAW_SELECT))
+            .bind(&claimed)
+            .fetch_all(&self.db_pool)
+            .await?;
+
+        Ok(draws)
+    }
+
+    async fn process(&self, draw: ScheduledDraw, policy: &DrawPolicy) -> Result<(), AppError> {
+        let now = Utc::now();
+
+        if draw.winner_drawn {
+            // Nothing to request; the winners only have to be recorded
+            return self.reschedule(draw.raffle_id, now + policy.randomness_timeout).await;
+        }
+
+        if let Some(tx_id) = draw.blockchain_tx_id {
+            if self.transaction_failed(tx_id).await && !request_confirmed(&draw) {
+                self.record_failure(&draw, "Randomness request transaction failed").await?;
+                return Ok(());
+            }
+        }
+
+        let decision = decide(pending_since(&draw), now, policy.randomness_timeout);
+        match decision {
+            DrawDecision::WaitUntil(at) => self.reschedule(draw.raffle_id, at).await,
+            DrawDecision::Request if !policy.auto_request => {
+                self.reschedule(draw.raffle_id, now + policy.randomness_timeout).await
+            }
+            DrawDecision::Request => self.request(&draw).await,
+        }
+    }
+
+    async fn request(&self, draw: &ScheduledDraw) -> Result<(), AppError> {
+        match self
+            .blockchain_service
+            .request_random_winner(draw.blockchain_raffle_id as u64)
+            .await
+        {
+            Ok(tx_id) => {
+                sqlx::query(
+                    r#"
+                    UPDATE raffle_draw_schedule
+                    SET attempts = attempts + 1, consecutive_failures = 0, last_attempt_at = NOW(),
+                        last_error = NULL, requested_at = NOW(), blockchain_tx_id = $2,
+                        next_attempt_at = NOW() + make_interval(secs => $3), updated_at = NOW()
+                    WHERE raffle_id = $1
+                    "#,
+                )
+                .bind(draw.raffle_id)
+                .bind(tx_id)
+                .bind(CONFIRM_CHECK_SECONDS as f64)
+                .execute(&self.db_pool)
+                .await?;
+
+                info!(
+                    "Requested randomness for raffle {} (attempt {}, tx {})",
+                    draw.raffle_id,
+                    draw.attempts + 1,
+                    tx_id
+                );
+                Ok(())
+            }
+            Err(e) => self.record_failure(draw, &e.to_string()).await,
+        }
+    }
+
+    async fn record_failure(&self, draw: &ScheduledDraw, error: &str) -> Result<(), AppError> {
+        let failures = draw.consecutive_failures + 1;
+        let delay = retry_delay(failures);
+        let error: String = error.chars().take(MAX_ERROR_CHARS).collect();
+
+        sqlx::query(
+            r#"
+            UPDATE raffle_draw_schedule
+            SET attempts = attempts + 1, consecutive_failures = $2, last_attempt_at = NOW(), last_error = $3,
+                requested_at = NULL, blockchain_tx_id = NULL,
+                next_attempt_at = NOW() + make_interval(secs => $4), updated_at = NOW()
+            WHERE raffle_id = $1
+            "#,
+        )
+        .bind(draw.raffle_id)
+        .bind(failures)
+        .bind(&error)
+        .bind(delay.num_seconds() as f64)
+        .execute(&self.db_pool)
+        .await?;
+
+        warn!(
+            "Randomness request for raffle {} failed ({} in a row), retrying in {}s: {}",
+            draw.raffle_id,
+            failures,
+            delay.num_seconds(),
+            error
+        );
+        Ok(())
+    }
+
+    async fn reschedule(&self, raffle_id: Uuid, at: DateTime<Utc>) -> Result<(), AppError> {
+        sqlx::query("UPDATE raffle_draw_schedule SET next_attempt_at = $2, updated_at = NOW() WHERE raffle_id = $1")
+            .bind(raffle_id)
+            .bind(at)
+            .execute(&self.db_pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Failed or dropped; a transaction the manager no longer knows about is given the benefit of the doubt
+    async fn transaction_failed(&self, tx_id: Uuid) -> bool {
+        self.blockchain_service
+            .get_transaction_status(tx_id)
+            .await
+            .is_some_and(|s| matches!(s.status, TransactionState::Failed | TransactionState::Dropped))
+    }
+
+    /// Mark draws pending past the alert threshold; each is reported once, by one instance
+    async fn report_overdue(&self, policy: &DrawPolicy) -> Result<(), AppError> {
+        let overdue = sqlx::query_as::<_, ScheduledDraw>(&format!(
+            r#"
+            WITH overdue AS (
+                {}
+                WHERE s.alerted_at IS NULL
+            ),
+            marked AS (
+                UPDATE raffle_draw_schedule s SET alerted_at = NOW(), updated_at = NOW()
+                FROM overdue o
+                WHERE s.raffle_id = o.raffle_id AND s.alerted_at IS NULL AND o.waiting_since < $1
+                RETURNING s.raffle_id
+            )
+            SELECT o.* FROM overdue o JOIN marked m ON m.raffle_id = o.raffle_id
+            "#,
+            DRAW_SELECT
+        ))
+        .bind(Utc::now() - policy.alert_after)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        for draw in overdue {
+            error!(
+                "Raffle {} (on chain {}) has waited for its draw since {}: {} requests sent, last error: {}",
+                draw.raffle_id,
+                draw.blockchain_raffle_id,
+                draw.waiting_since,
+                draw.attempts,
+                draw.last_error.as_deref().unwrap_or("none")
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The latest request that may still be answered: the contract's, or the scheduler's own
+fn pending_since(draw: &ScheduledDraw) -> Option<DateTime<Utc>> {
+    draw.randomness_requested_at.max(draw.requested_at)
+}
+
+/// The chain has shown the scheduler's latest request as a `RandomnessRequested` event
+fn request_confirmed(draw: &ScheduledDraw) -> bool {
+    matches!(
+        (draw.randomness_requested_at, draw.requested_at),
+        (Some(event), Some(requested)) if event >= requested
+    )
+}
+
+/// Request when nothing is pending or the pending request has timed out; otherwise wait it out
+fn decide(pending_since: Option<DateTime<Utc>>, now: DateTime<Utc>, randomness_timeout: Duration) -> DrawDecision {
+    match pending_since {
+        None => DrawDecision::Request,
+        Some(requested_at) if now - requested_at >= randomness_timeout => DrawDecision::Request,
+        Some(requested_at) => DrawDecision::WaitUntil(requested_at + randomness_timeout),
+    }
+}
+
+/// Doubles from a minute after each failure in a row, up to an hour
+fn retry_delay(consecutive_failures: i32) -> Duration {
+    let exponent = consecutive_failures.saturating_sub(1).clamp(0, 16) as u32;
+    Duration::seconds((RETRY_BASE_SECONDS << exponent).min(RETRY_MAX_SECONDS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(randomness_requested_at: Option<DateTime<Utc>>, requested_at: Option<DateTime<Utc>>) -> ScheduledDraw {
+        let now = Utc::now();
+        ScheduledDraw {
+            raffle_id: Uuid::new_v4(),
+            blockchain_raffle_id: 7,
+            attempts: 0,
+            consecutive_failures: 0,
+            next_attempt_at: now,
+            last_attempt_at: None,
+            last_error: None,
+            requested_at,
+            blockchain_tx_id: None,
+            alerted_at: None,
+            created_at: now,
+            randomness_requested_at,
+            waiting_since: now,
+            winner_drawn: false,
+        }
+    }
+
+    #[test]
+    fn test_decide() {
+        let now = Utc::now();
+        let timeout = Duration::minutes(30);
+
+        assert_eq!(decide(None, now, timeout), DrawDecision::Request);
+        assert_eq!(decide(Some(now - Duration::minutes(31)), now, timeout), DrawDecision::Request);
+
+        let requested_at = now - Duration::minutes(10);
+        assert_eq!(
+            decide(Some(requested_at), now, timeout),
+            DrawDecision::WaitUntil(requested_at + timeout)
+        );
+    }
+
+    #[test]
+    fn test_pending_since_takes_the_latest_request() {
+        let now = Utc::now();
+        let event = now - Duration::minutes(40);
+        let own = now - Duration::minutes(5);
+
+        assert_eq!(pending_since(&draw(Some(event), None)), Some(event));
+        assert_eq!(pending_since(&draw(Some(event), Some(own))), Some(own));
+        assert_eq!(pending_since(&draw(None, None)), None);
+    }
+
+    #[test]
+    fn test_request_confirmed() {
+        let now = Utc::now();
+
+        assert!(request_confirmed(&draw(Some(now), Some(now - Duration::seconds(30)))));
+        assert!(!request_confirmed(&draw(Some(now - Duration::minutes(40)), Some(now))));
+        assert!(!request_confirmed(&draw(None, Some(now))));
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), Duration::minutes(1));
+        assert_eq!(retry_delay(2), Duration::minutes(2));
+        assert_eq!(retry_delay(4), Duration::minutes(8));
+        assert_eq!(retry_delay(7), Duration::minutes(60));
+        assert_eq!(retry_delay(100), Duration::minutes(60));
+    }
+}
@@ -0,0 +1,474 @@
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use crate::models::AuditLog;
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::AuditAction;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+use tracing::{error, info};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const DEFAULT_REFRESH_INTERVAL_HOURS: i64 = 24;
+const MAX_DOMAIN_LENGTH: usize = 253;
+
+/// Email domain service decides which address domains may be used for an account
+#[derive(Clone)]
+pub struct EmailDomainService {
+    db_pool: PgPool,
+    client: reqwest::Client,
+    /// Plain text list of disposable domains, one per line
+    feed_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DomainRule {
+    Block,
+    Allow,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct EmailDomainRule {
+    pub domain: String,
+    pub rule: DomainRule,
+    pub reason: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SetDomainRuleRequest {
+    pub rule: DomainRule,
+    pub reason: Option<String>,
+}
+
+/// Admin view of the domain policy
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DomainPolicySummary {
+    pub blocking_enabled: bool,
+    pub feed_configured: bool,
+    pub disposable_domains: i64,
+    pub last_refreshed_at: Option<DateTime<Utc>>,
+    pub rules: Vec<EmailDomainRule>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DomainDecisionReason {
+    AllowRule,
+    BlockRule,
+    Disposable,
+    Default,
+}
+
+/// Outcome for one address domain, and the entry that decided it
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, ToSchema)]
+pub struct DomainCheck {
+    pub domain: String,
+    pub allowed: bool,
+    pub reason: DomainDecisionReason,
+    pub matched_domain: Option<String>,
+}
+
+impl EmailDomainService {
+    /// Create a new email domain service; without a feed the stored list is used as is
+    pub fn new(db_pool: PgPool) -> Self {
+        Self {
+            db_pool,
+            client: reqwest::Client::new(),
+            feed_url: None,
+        }
+    }
+
+    pub fn from_config(db_pool: PgPool, config: &AppConfig) -> Self {
+        Self {
+            feed_url: config.disposable_email_domains_url.clone(),
+            ..Self::new(db_pool)
+        }
+    }
+
+    /// Start the periodic disposable domain refresh
+    pub async fn start_background_tasks(&self) {
+        if self.feed_url.is_none() {
+            info!("No disposable email domain feed configured; using the stored list");
+            return;
+        }
+
+        let service = self.clone();
+        let interval_hours = self
+            .get_setting("disposable_domains_refresh_interval_hours", DEFAULT_REFRESH_INTERVAL_HOURS)
+            .await
+            .unwrap_or(DEFAULT_REFRESH_INTERVAL_HOURS);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_hours as u64 * 3600));
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.refresh_disposable_domains().await {
+                    error!("Failed to refresh disposable email domains: {}", e);
+                }
+            }
+        });
+
+        info!("Email domain service background tasks started");
+    }
+
+    /// Reject addresses whose domain is blocked
+    pub async fn check_email(&self, email: &str) -> Result<(), AppError> {
+        let check = self.evaluate(email).await?;
+        if check.allowed {
+            return Ok(());
+        }
+
+        info!("Rejected email domain {} ({:?})", check.domain, check.reason);
+
+        Err(AppError::Validation(match check.reason {
+            DomainDecisionReason::Disposable => "Disposable email addresses are not accepted".to_string(),
+            _ => "Email addresses from this domain are not accepted".to_string(),
+        }))
+    }
+
+    /// Decide an address without enforcing it
+    pub async fn evaluate(&self, email: &str) -> Result<DomainCheck, AppError> {
+        let domain = email_domain(email)
+            .ok_or_else(|| AppError::Validation("Invalid email format".to_string()))?;
+        let candidates = candidate_domains(&domain);
+
+        let rules = sqlx::query_as::<_, (String, DomainRule)>(
+            "SELECT domain, rule FROM email_domain_rules WHERE domain = ANY($1)",
+        )
+        .bind(&candidates)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let blocking_enabled = self.blocking_enabled().await?;
+        let disposable = if blocking_enabled {
+            sqlx::query_scalar::<_, String>("SELECT domain FROM disposable_email_domains WHERE domain = ANY($1)")
+                .bind(&candidates)
+                .fetch_all(&self.db_pool)
+                .await?
+        } else {
+            Vec::new()
+        };
+
+        Ok(decide(domain, &rules, &disposable))
+    }
+
+    /// Replace the disposable domain list with the feed's; returns how many domains were stored
+    pub async fn refresh_disposable_domains(&self) -> Result<usize, AppError> {
+        let feed_url = self.feed_url.as_ref()
+            .ok_or_else(|| AppError::Validation("No disposable email domain feed is configured".to_string()))?;
+
+        let response = self.client
+            .get(feed_url)
+            .send()
+            .await
+            .map_err(|e| AppError::Internal(format!("Disposable domain feed request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::Internal(format!("Disposable domain feed returned {}", response.status())));
+        }
+
+        let body = response
+            .text()
+            .await
+            .map_err(|e| AppError::Internal(format!("Invalid disposable domain feed response: {}", e)))?;
+
+        let domains = parse_domain_list(&body);
+        // An empty feed is far more likely an outage than the end of disposable email
+        if domains.is_empty() {
+            return Err(AppError::Internal("Disposable domain feed returned no domains".to_string()));
+        }
+
+        let mut tx = self.db_pool.begin().await?;
+
+        sqlx::query("DELETE FROM disposable_email_domains")
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO disposable_email_domains (domain, source) SELECT UNNEST($1::varchar[]), 'feed'",
+        )
+        .bind(&domains)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        info!("Refreshed disposable email domain list: {} domains", domains.len());
+
+        Ok(domains.len())
+    }
+
+    /// Exceptions and dataset status (admin)
+    pub async fn get_policy(&self) -> Result<DomainPolicySummary, AppError> {
+        let rules = sqlx::query_as::<_, EmailDomainRule>(
+            "SELECT domain, rule, reason, created_by, created_at, updated_at FROM email_domain_rules ORDER BY domain",
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let (disposable_domains, last_refreshed_at) = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(
+            "SELECT COUNT(*), MAX(refreshed_at) FROM disposable_email_domains",
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(DomainPolicySummary {
+            blocking_enabled: self.blocking_enabled().await?,
+            feed_configured: self.feed_url.is_some(),
+            disposable_domains,
+            last_refreshed_at,
+            rules,
+        })
+    }
+
+    /// Add or replace the exception for a domain (admin)
+    pub async fn set_rule(
+        &self,
+        admin_id: Uuid,
+        domain: &str,
+        request: SetDomainRuleRequest,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<EmailDomainRule, AppError> {
+        let domain = normalize_domain(domain)?;
+
+        let rule = sqlx::query_as::<_, EmailDomainRule>(
+            r#"
+            INSERT INTO email_domain_rules (domain, rule, reason, created_by)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (domain) DO UPDATE SET rule = $2, reason = $3, created_by = $4
+            RETURNING domain, rule, reason, created_by, created_at, updated_at
+            "#,
+        )
+        .bind(&domain)
+        .bind(request.rule)
+        .bind(&request.reason)
+        .bind(admin_id)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        AuditLog::create(
+            &self.db_pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("email_domain_rule".to_string()),
+            None,
+            None,
+            Some(serde_json::json!({
+                "domain": rule.domain,
+                "rule": rule.rule,
+                "reason": rule.reason
+            })),
+            ip_address,
+            user_agent,
+        ).await?;
+
+        info!("Admin {} set email domain rule {:?} for {}", admin_id, rule.rule, rule.domain);
+
+        Ok(rule)
+    }
+
+    /// Remove the exception for a domain (admin)
+    pub async fn delete_rule(
+        &self,
+        admin_id: Uuid,
+        domain: &str,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<(), AppError> {
+        let domain = normalize_domain(domain)?;
+
+        let removed = sqlx::query_as::<_, EmailDomainRule>(
+            r#"
+            DELETE FROM email_domain_rules WHERE domain = $1
+            RETURNING domain, rule, reason, created_by, created_at, updated_at
+            "#,
+        )
+        .bind(&domain)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("No rule for this domain".to_string()))?;
+
+        AuditLog::create(
+            &self.db_pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("email_domain_rule".to_string()),
+            None,
+            Some(serde_json::json!({
+                "domain": removed.domain,
+                "rule": removed.rule,
+                "reason": removed.reason
+            })),
+            None,
+            ip_address,
+            user_agent,
+        ).await?;
+
+        info!("Admin {} removed email domain rule for {}", admin_id, domain);
+
+        Ok(())
+    }
+
+    // Private helper methods
+
+    async fn blocking_enabled(&self) -> Result<bool, AppError> {
+        Ok(SystemSetting::get_by_key(&self.db_pool, "disposable_email_blocking_enabled")
+            .await?
+            .and_then(|s| s.get_bool_value())
+            .unwrap_or(true))
+    }
+
+    async fn get_setting(&self, key: &str, default: i64) -> Result<i64, AppError> {
+        Ok(SystemSetting::get_by_key(&self.db_pool, key)
+            .await?
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default))
+    }
+}
+
+/// Lowercased domain part of an address
+pub fn email_domain(email: &str) -> Option<String> {
+    let (local, domain) = email.trim().rsplit_once('@')?;
+    if local.is_empty() {
+        return None;
+    }
+
+    normalize_domain(domain).ok()
+}
+
+/// Lowercase a domain and check it is a plausible hostname
+pub fn normalize_domain(domain: &str) -> Result<String, AppError> {
+    let domain = domain.trim().trim_start_matches('@').trim_end_matches('.').to_lowercase();
+
+    let valid = domain.len() <= MAX_DOMAIN_LENGTH
+        && domain.contains('.')
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+
+    if valid {
+        Ok(domain)
+    } else {
+        Err(AppError::Validation(format!("Invalid domain: {}", domain)))
+    }
+}
+
+/// The domain and each parent above the top level: `a.b.example.com`, `b.example.com`, `example.com`
+pub fn candidate_domains(domain: &str) -> Vec<String> {
+    let labels: Vec<&str> = domain.split('.').collect();
+    (0..labels.len().saturating_sub(1))
+        .map(|start| labels[start..].join("."))
+        .collect()
+}
+
+/// The most specific admin rule decides; otherwise a disposable match blocks
+pub fn decide(domain: String, rules: &[(String, DomainRule)], disposable: &[String]) -> DomainCheck {
+    if let Some((matched, rule)) = rules.iter().max_by_key(|(d, _)| d.len()) {
+        return DomainCheck {
+            domain,
+            allowed: *rule == DomainRule::Allow,
+            reason: match rule {
+                DomainRule::Allow => DomainDecisionReason::AllowRule,
+                DomainRule::Block => DomainDecisionReason::BlockRule,
+            },
+            matched_domain: Some(matched.clone()),
+        };
+    }
+
+    if let Some(matched) = disposable.iter().max_by_key(|d| d.len()) {
+        return DomainCheck {
+            domain,
+            allowed: false,
+            reason: DomainDecisionReason::Disposable,
+            matched_domain: Some(matched.clone()),
+        };
+    }
+
+    DomainCheck {
+        domain,
+        allowed: true,
+        reason: DomainDecisionReason::Default,
+        matched_domain: None,
+    }
+}
+
+/// One domain per line; blank lines, `#` comments and invalid entries are skipped
+pub fn parse_domain_list(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| line.split('#').next())
+        .filter_map(|line| normalize_domain(line).ok())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_email_domain() {
+        assert_eq!(email_domain(" Jane@Mail.Example.COM "), Some("mail.example.com".to_string()));
+        assert_eq!(email_domain("jane@localhost"), None);
+        assert_eq!(email_domain("@example.com"), None);
+        assert_eq!(email_domain("jane"), None);
+    }
+
+    #[test]
+    fn test_candidate_domains() {
+        assert_eq!(
+            candidate_domains("a.b.example.com"),
+            vec!["a.b.example.com", "b.example.com", "example.com"]
+        );
+        assert_eq!(candidate_domains("example.com"), vec!["example.com"]);
+    }
+
+    #[test]
+    fn test_decide_prefers_most_specific_rule() {
+        let rules = vec![
+            ("example.com".to_string(), DomainRule::Block),
+            ("staff.example.com".to_string(), DomainRule::Allow),
+        ];
+        let check = decide("staff.example.com".to_string(), &rules, &[]);
+        assert!(check.allowed);
+        assert_eq!(check.matched_domain.as_deref(), Some("staff.example.com"));
+
+        let check = decide("example.com".to_string(), &rules[..1], &[]);
+        assert_eq!(check.reason, DomainDecisionReason::BlockRule);
+    }
+
+    #[test]
+    fn test_decide_allow_rule_overrides_disposable() {
+        let disposable = vec!["mailinator.com".to_string()];
+        assert_eq!(
+            decide("mailinator.com".to_string(), &[], &disposable).reason,
+            DomainDecisionReason::Disposable
+        );
+
+        let rules = vec![("mailinator.com".to_string(), DomainRule::Allow)];
+        assert!(decide("mailinator.com".to_string(), &rules, &disposable).allowed);
+        assert!(decide("gmail.com".to_string(), &[], &[]).allowed);
+    }
+
+    #[test]
+    fn test_parse_domain_list() {
+        let list = "# disposable\nYopmail.com\n\nmailinator.com # popular\nnot a domain\nyopmail.com\n";
+        assert_eq!(parse_domain_list(list), vec!["mailinator.com", "yopmail.com"]);
+    }
+}
@@ -0,0 +1,1150 @@
+use crate::config::{AppConfig, EmailProviderKind};
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use crate::services::webhook_service::retry_delay_seconds;
+use chrono::{DateTime, Duration, Utc};
+use handlebars::Handlebars;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+const DEFAULT_MAX_ATTEMPTS: i64 = 8;
+const DEFAULT_RETRY_BASE_SECONDS: i64 = 60;
+const DEFAULT_RETRY_MAX_SECONDS: i64 = 3600;
+const DEFAULT_FROM_NAME: &str = "Thriftee";
+const DEFAULT_APP_BASE_URL: &str = "http://localhost:3000";
+const SEND_BATCH_SIZE: i64 = 50;
+/// A message left in `sending` this long is assumed to have died with its worker
+const STALE_SENDING_MINUTES: i32 = 10;
+
+const MESSAGE_COLUMNS: &str = "id, user_id, to_address, template, payload, status, attempts, provider, \
+    provider_message_id, last_error, next_attempt_at, last_attempt_at, sent_at, created_at";
+
+pub type EmailSendFuture<'a> = Pin<Box<dyn Future<Output = Result<String, SendFailure>> + Send + 'a>>;
+
+/// Why a provider did not accept a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendFailure {
+    /// Worth retrying: timeouts, throttling, 4xx SMTP replies
+    Transient(String),
+    /// Retrying won't help: the message or recipient was rejected outright
+    Permanent(String),
+}
+
+/// A rendered message ready to hand to a provider
+#[derive(Debug, Clone)]
+pub struct OutgoingEmail {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+/// Transport that delivers rendered messages
+pub trait EmailProvider: Send + Sync {
+    /// Recorded with each sent message
+    fn name(&self) -> &'static str;
+
+    /// Hand a message to the provider; returns the id the provider assigned to it
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> EmailSendFuture<'a>;
+}
+
+/// Sends through an SMTP relay with STARTTLS
+pub struct SmtpEmailProvider {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpEmailProvider {
+    pub fn new(
+        host: &str,
+        port: Option<u16>,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Result<Self, AppError> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(|e| AppError::Internal(format!("Invalid SMTP relay {}: {}", host, e)))?;
+        if let Some(port) = port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (username, password) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+        })
+    }
+}
+
+impl EmailProvider for SmtpEmailProvider {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> EmailSendFuture<'a> {
+        Box::pin(async move {
+            let from: Mailbox = email
+                .from
+                .parse()
+                .map_err(|e| SendFailure::Permanent(format!("Invalid sender address: {}", e)))?;
+            let to: Mailbox = email
+                .to
+                .parse()
+                .map_err(|e| SendFailure::Permanent(format!("Invalid recipient address: {}", e)))?;
+
+            let message = lettre::Message::builder()
+                .from(from)
+                .to(to)
+                .subject(email.subject.as_str())
+                .multipart(MultiPart::alternative_plain_html(email.text.clone(), email.html.clone()))
+                .map_err(|e| SendFailure::Permanent(format!("Could not build message: {}", e)))?;
+
+            match self.transport.send(message).await {
+                Ok(response) => Ok(response.message().collect::<Vec<_>>().join(" ")),
+                Err(e) if e.is_permanent() => Err(SendFailure::Permanent(e.to_string())),
+                Err(e) => Err(SendFailure::Transient(e.to_string())),
+            }
+        })
+    }
+}
+
+/// Sends through the Amazon SES v2 API
+pub struct SesEmailProvider {
+    client: aws_sdk_sesv2::Client,
+}
+
+impl SesEmailProvider {
+    /// Credentials come from the standard AWS chain: environment, profile or instance role
+    pub async fn new(region: Option<String>) -> Self {
+        let mut loader = aws_config::defaults(aws_config::BehaviorVersion::latest());
+        if let Some(region) = region {
+            loader = loader.region(aws_config::Region::new(region));
+        }
+
+        Self {
+            client: aws_sdk_sesv2::Client::new(&loader.load().await),
+        }
+    }
+}
+
+impl EmailProvider for SesEmailProvider {
+    fn name(&self) -> &'static str {
+        "ses"
+    }
+
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> EmailSendFuture<'a> {
+        use aws_sdk_sesv2::types::{Body, Content, Destination, EmailContent, Message};
+
+        Box::pin(async move {
+            let content = |data: &str| {
+                Content::builder()
+                    .data(data)
+                    .charset("UTF-8")
+                    .build()
+                    .map_err(|e| SendFailure::Permanent(format!("Could not build message: {}", e)))
+            };
+
+            let message = Message::builder()
+                .subject(content(&email.subject)?)
+                .body(Body::builder().html(content(&email.html)?).text(content(&email.text)?).build())
+                .build();
+
+            let result = self
+                .client
+                .send_email()
+                .from_email_address(&email.from)
+                .destination(Destination::builder().to_addresses(&email.to).build())
+                .content(EmailContent::builder().simple(message).build())
+                .send()
+                .await;
+
+            match result {
+                Ok(output) => Ok(output.message_id().unwrap_or_default().to_string()),
+                Err(e) => {
+                    let error = e.into_service_error();
+                    if error.is_message_rejected() || error.is_bad_request_exception() {
+                        Err(SendFailure::Permanent(error.to_string()))
+                    } else {
+                        Err(SendFailure::Transient(error.to_string()))
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Writes messages to the log instead of sending them; used when no provider is configured
+pub struct LogEmailProvider;
+
+impl EmailProvider for LogEmailProvider {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn send<'a>(&'a self, email: &'a OutgoingEmail) -> EmailSendFuture<'a> {
+        Box::pin(async move {
+            info!("Email to {}: {}", email.to, email.subject);
+            debug!("{}", email.text);
+            Ok(Uuid::new_v4().to_string())
+        })
+    }
+}
+
+/// Kinds of email we send; each has an MJML body, a plain text body and a subject line
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum EmailTemplate {
+    Verification,
+    PasswordReset,
+    /// A login from a device the account had not been used on
+    NewDeviceLogin,
+    RaffleWon,
+    Receipt,
+    /// Any other notification, showing its title and message
+    Notification,
+}
+
+impl EmailTemplate {
+    pub const ALL: [EmailTemplate; 6] = [
+        EmailTemplate::Verification,
+        EmailTemplate::PasswordReset,
+        EmailTemplate::NewDeviceLogin,
+        EmailTemplate::RaffleWon,
+        EmailTemplate::Receipt,
+        EmailTemplate::Notification,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailTemplate::Verification => "verification",
+            EmailTemplate::PasswordReset => "password_reset",
+            EmailTemplate::NewDeviceLogin => "new_device_login",
+            EmailTemplate::RaffleWon => "raffle_won",
+            EmailTemplate::Receipt => "receipt",
+            EmailTemplate::Notification => "notification",
+        }
+    }
+
+    fn subject(&self) -> &'static str {
+        match self {
+            EmailTemplate::Verification => "Verify your Thriftee email",
+            EmailTemplate::PasswordReset => "Reset your Thriftee password",
+            EmailTemplate::NewDeviceLogin => "New sign-in to your Thriftee account",
+            EmailTemplate::RaffleWon => "You won {{item_title}}!",
+            EmailTemplate::Receipt => "Your receipt {{receipt.receipt_number}}",
+            EmailTemplate::Notification => "{{title}}",
+        }
+    }
+
+    fn mjml(&self) -> &'static str {
+        match self {
+            EmailTemplate::Verification => include_str!("../../templates/email/verification.mjml"),
+            EmailTemplate::PasswordReset => include_str!("../../templates/email/password_reset.mjml"),
+            EmailTemplate::NewDeviceLogin => include_str!("../../templates/email/new_device_login.mjml"),
+            EmailTemplate::RaffleWon => include_str!("../../templates/email/raffle_won.mjml"),
+            EmailTemplate::Receipt => include_str!("../../templates/email/receipt.mjml"),
+            EmailTemplate::Notification => include_str!("../../templates/email/notification.mjml"),
+        }
+    }
+
+    fn text(&self) -> &'static str {
+        match self {
+            EmailTemplate::Verification => include_str!("../../templates/email/verification.txt"),
+            EmailTemplate::PasswordReset => include_str!("../../templates/email/password_reset.txt"),
+            EmailTemplate::NewDeviceLogin => include_str!("../../templates/email/new_device_login.txt"),
+            EmailTemplate::RaffleWon => include_str!("../../templates/email/raffle_won.txt"),
+            EmailTemplate::Receipt => include_str!("../../templates/email/receipt.txt"),
+            EmailTemplate::Notification => include_str!("../../templates/email/notification.txt"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+/// Compiled templates; MJML bodies are filled in with HTML escaping, subjects and text bodies without
+pub struct EmailTemplates {
+    html: Handlebars<'static>,
+    text: Handlebars<'static>,
+}
+
+impl EmailTemplates {
+    pub fn new() -> Self {
+        let mut html = Handlebars::new();
+        let mut text = Handlebars::new();
+        text.register_escape_fn(handlebars::no_escape);
+
+        // Templates are compiled into the binary, so a bad one is a build mistake; the tests render every one
+        for template in EmailTemplate::ALL {
+            let name = template.as_str();
+            html.register_template_string(name, template.mjml())
+                .unwrap_or_else(|e| panic!("Invalid MJML email template {}: {}", name, e));
+            text.register_template_string(name, template.text())
+                .unwrap_or_else(|e| panic!("Invalid text email template {}: {}", name, e));
+            text.register_template_string(&subject_key(template), template.subject())
+                .unwrap_or_else(|e| panic!("Invalid email subject {}: {}", name, e));
+        }
+
+        Self { html, text }
+    }
+
+    pub fn render(&self, template: EmailTemplate, data: &Value) -> Result<RenderedEmail, AppError> {
+        let name = template.as_str();
+        let render_error = |e: handlebars::RenderError| AppError::Internal(format!("Could not render email {}: {}", name, e));
+
+        let subject = self.text.render(&subject_key(template), data).map_err(render_error)?;
+        let text = self.text.render(name, data).map_err(render_error)?;
+        let mjml = self.html.render(name, data).map_err(render_error)?;
+
+        let html = mrml::parse(&mjml)
+            .map_err(|e| AppError::Internal(format!("Invalid MJML in email {}: {}", name, e)))?
+            .render(&mrml::prelude::render::RenderOptions::default())
+            .map_err(|e| AppError::Internal(format!("Could not render MJML for email {}: {}", name, e)))?;
+
+        Ok(RenderedEmail {
+            subject: subject.trim().to_string(),
+            html,
+            text,
+        })
+    }
+}
+
+impl Default for EmailTemplates {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EmailStatus {
+    Pending,
+    Sending,
+    Sent,
+    /// The provider did not accept the message and a retry is scheduled at `next_attempt_at`
+    Failed,
+    /// Retries are exhausted or the provider rejected the message outright
+    Dead,
+    /// Not sent because the recipient is on the suppression list
+    Suppressed,
+}
+
+/// One queued message and the outcome of its latest attempt
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct EmailMessage {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub to_address: String,
+    pub template: EmailTemplate,
+    /// Can carry one-time tokens, so it never leaves the server
+    #[serde(skip_serializing, default)]
+    pub payload: Value,
+    pub status: EmailStatus,
+    pub attempts: i32,
+    pub provider: Option<String>,
+    pub provider_message_id: Option<String>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EmailMessageQuery {
+    pub user_id: Option<Uuid>,
+    pub status: Option<EmailStatus>,
+    pub template: Option<EmailTemplate>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SuppressionReason {
+    HardBounce,
+    Complaint,
+    Manual,
+}
+
+/// An address we no longer send to
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct EmailSuppression {
+    pub email: String,
+    pub reason: SuppressionReason,
+    pub details: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AddSuppressionRequest {
+    pub email: String,
+    pub details: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+struct Recipient {
+    email: String,
+    username: String,
+}
+
+/// Email service renders templated messages and sends them through the configured provider,
+/// retrying failures with backoff and skipping suppressed addresses
+#[derive(Clone)]
+pub struct EmailService {
+    db_pool: PgPool,
+    provider: Arc<dyn EmailProvider>,
+    templates: Arc<EmailTemplates>,
+    from: String,
+    app_base_url: String,
+    webhook_token: Option<String>,
+}
+
+impl EmailService {
+    /// Create a new email service; `from` is a mailbox such as `Thriftee <no-reply@thriftee.com>`
+    pub fn new(db_pool: PgPool, provider: Arc<dyn EmailProvider>, from: String, app_base_url: String) -> Self {
+        Self {
+            db_pool,
+            provider,
+            templates: Arc::new(EmailTemplates::new()),
+            from,
+            app_base_url: app_base_url.trim_end_matches('/').to_string(),
+            webhook_token: None,
+        }
+    }
+
+    pub async fn from_config(db_pool: PgPool, config: &AppConfig) -> Result<Self, AppError> {
+        let provider: Arc<dyn EmailProvider> = match config.email_provider {
+            Some(EmailProviderKind::Smtp) => Arc::new(SmtpEmailProvider::new(
+                config.smtp_host.as_deref().unwrap_or_default(),
+                config.smtp_port,
+                config.smtp_username.clone(),
+                config.smtp_password.clone(),
+            )?),
+            Some(EmailProviderKind::Ses) => Arc::new(SesEmailProvider::new(config.ses_region.clone()).await),
+            None => {
+                warn!("EMAIL_PROVIDER is not set; emails will only be logged");
+                Arc::new(LogEmailProvider)
+            }
+        };
+
+        let from_address = config.email_from_address.as_deref().unwrap_or("no-reply@localhost");
+        let from_name = config.email_from_name.as_deref().unwrap_or(DEFAULT_FROM_NAME);
+        let app_base_url = config.app_base_url.clone().unwrap_or_else(|| DEFAULT_APP_BASE_URL.to_string());
+
+        let mut service = Self::new(db_pool, provider, format!("{} <{}>", from_name, from_address), app_base_url);
+        service.webhook_token = config.email_webhook_token.clone();
+        Ok(service)
+    }
+
+    /// Start the send loop for pending and failed messages
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.send_due().await {
+                    error!("Scheduled email sending failed: {}", e);
+                }
+            }
+        });
+
+        info!("Email service background tasks started (provider: {})", self.provider.name());
+    }
+
+    /// Token SES bounce and complaint notifications must carry; None disables that endpoint
+    pub fn webhook_token(&self) -> Option<&str> {
+        self.webhook_token.as_deref()
+    }
+
+    /// Queue a verification link for a user
+    pub async fn send_verification(&self, user_id: Uuid, token: &str, expires_hours: i64) -> Result<(), AppError> {
+        let data = serde_json::json!({
+            "link": format!("{}/verify-email?token={}", self.app_base_url, token),
+            "expires_hours": expires_hours
+        });
+
+        self.queue_for_user(user_id, EmailTemplate::Verification, data).await?;
+        Ok(())
+    }
+
+    /// Queue a password reset link for a user
+    pub async fn send_password_reset(&self, user_id: Uuid, token: &str, expires_minutes: i64) -> Result<(), AppError> {
+        let data = serde_json::json!({
+            "link": format!("{}/reset-password?token={}", self.app_base_url, token),
+            "expires_minutes": expires_minutes
+        });
+
+        self.queue_for_user(user_id, EmailTemplate::PasswordReset, data).await?;
+        Ok(())
+    }
+
+    /// Tell a user their account was signed in to from a device it had not been used on
+    pub async fn send_new_device_login(
+        &self,
+        user_id: Uuid,
+        device: &str,
+        ip_address: Option<&str>,
+        signed_in_at: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        let data = serde_json::json!({
+            "device": device,
+            "ip_address": ip_address.unwrap_or("an unknown address"),
+            "signed_in_at": signed_in_at.format("%B %-d, %Y at %H:%M UTC").to_string(),
+            "link": format!("{}/forgot-password", self.app_base_url)
+        });
+
+        self.queue_for_user(user_id, EmailTemplate::NewDeviceLogin, data).await?;
+        Ok(())
+    }
+
+    /// Queue a message to a user's current address; None if the account is gone or being deleted
+    pub async fn queue_for_user(
+        &self,
+        user_id: Uuid,
+        template: EmailTemplate,
+        data: Value,
+    ) -> Result<Option<EmailMessage>, AppError> {
+        let recipient = sqlx::query_as::<_, Recipient>(
+            "SELECT email, username FROM users WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        let Some(recipient) = recipient else {
+            debug!("Not emailing user {}: account not found or deleted", user_id);
+            return Ok(None);
+        };
+
+        let data = template_data(data, &recipient.username, &self.app_base_url);
+        self.queue(Some(user_id), &recipient.email, template, data).await.map(Some)
+    }
+
+    /// Queue a message and try to send it right away; failures are retried by the send loop
+    ///
+    /// Messages to suppressed addresses are recorded as `suppressed` and never sent.
+    pub async fn queue(
+        &self,
+        user_id: Option<Uuid>,
+        to_address: &str,
+        template: EmailTemplate,
+        data: Value,
+    ) -> Result<EmailMessage, AppError> {
+        let to_address = normalize_address(to_address)?;
+        let suppressed = self.is_suppressed(&to_address).await?;
+        let status = if suppressed { EmailStatus::Suppressed } else { EmailStatus::Pending };
+        let payload = if suppressed { serde_json::json!({}) } else { data };
+
+        let message = sqlx::query_as::<_, EmailMessage>(&format!(
+            r#"
+            INSERT INTO email_messages (user_id, to_address, template, payload, status, next_attempt_at)
+            VALUES ($1, $2, $3, $4, $5, CASE WHEN $5 = 'pending' THEN NOW() END)
+            RETURNING {}
+            "#,
+            MESSAGE_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(&to_address)
+        .bind(template)
+        .bind(payload)
+        .bind(status)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        if suppressed {
+            info!("Skipped {} email {} to suppressed address", template.as_str(), message.id);
+            return Ok(message);
+        }
+
+        let service = self.clone();
+        let message_id = message.id;
+        tokio::spawn(async move {
+            match service.claim(message_id).await {
+                Ok(Some(message)) => {
+                    if let Err(e) = service.attempt(message).await {
+                        error!("Failed to send email {}: {}", message_id, e);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => error!("Failed to claim email {}: {}", message_id, e),
+            }
+        });
+
+        Ok(message)
+    }
+
+    /// Send messages whose first attempt or retry is due
+    pub async fn send_due(&self) -> Result<usize, AppError> {
+        let messages = sqlx::query_as::<_, EmailMessage>(&format!(
+            r#"
+            UPDATE email_messages
+            SET status = 'sending', last_attempt_at = NOW()
+            WHERE id IN (
+                SELECT id FROM email_messages
+                WHERE (status IN ('pending', 'failed') AND next_attempt_at <= NOW())
+                   OR (status = 'sending' AND last_attempt_at < NOW() - make_interval(mins => $2))
+                ORDER BY next_attempt_at NULLS FIRST
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING {}
+            "#,
+            MESSAGE_COLUMNS
+        ))
+        .bind(SEND_BATCH_SIZE)
+        .bind(STALE_SENDING_MINUTES)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let count = messages.len();
+        for message in messages {
+            self.attempt(message).await?;
+        }
+
+        if count > 0 {
+            info!("Sent {} queued emails", count);
+        }
+
+        Ok(count)
+    }
+
+    /// List queued and sent messages, newest first
+    pub async fn list_messages(&self, query: &EmailMessageQuery) -> Result<Vec<EmailMessage>, AppError> {
+        let limit = query.limit.unwrap_or(50).clamp(1, 200);
+        let offset = query.offset.unwrap_or(0).max(0);
+
+        let messages = sqlx::query_as::<_, EmailMessage>(&format!(
+            r#"
+            SELECT {}
+            FROM email_messages
+            WHERE ($1::uuid IS NULL OR user_id = $1)
+            AND ($2::varchar IS NULL OR status = $2)
+            AND ($3::varchar IS NULL OR template = $3)
+            ORDER BY created_at DESC
+            LIMIT $4 OFFSET $5
+            "#,
+            MESSAGE_COLUMNS
+        ))
+        .bind(query.user_id)
+        .bind(query.status)
+        .bind(query.template)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(messages)
+    }
+
+    /// Whether mail to this address is blocked
+    pub async fn is_suppressed(&self, email: &str) -> Result<bool, AppError> {
+        let suppressed = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS(SELECT 1 FROM email_suppressions WHERE email = $1)",
+        )
+        .bind(email.trim().to_lowercase())
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(suppressed)
+    }
+
+    /// Stop sending to an address; an existing entry keeps its original reason
+    pub async fn suppress(
+        &self,
+        email: &str,
+        reason: SuppressionReason,
+        details: Option<String>,
+        created_by: Option<Uuid>,
+    ) -> Result<EmailSuppression, AppError> {
+        let email = normalize_address(email)?;
+
+        let suppression = sqlx::query_as::<_, EmailSuppression>(
+            r#"
+            INSERT INTO email_suppressions (email, reason, details, created_by)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (email) DO UPDATE SET email = EXCLUDED.email
+            RETURNING email, reason, details, created_by, created_at
+            "#,
+        )
+        .bind(&email)
+        .bind(reason)
+        .bind(details)
+        .bind(created_by)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        // Nothing still waiting in the queue should go out either
+        sqlx::query(
+            r#"
+            UPDATE email_messages
+            SET status = 'suppressed', payload = '{}', next_attempt_at = NULL
+            WHERE to_address = $1 AND status IN ('pending', 'failed')
+            "#,
+        )
+        .bind(&email)
+        .execute(&self.db_pool)
+        .await?;
+
+        info!("Suppressed email address ({:?})", suppression.reason);
+
+        Ok(suppression)
+    }
+
+    /// Suppressed addresses, newest first
+    pub async fn list_suppressions(&self, limit: Option<i64>, offset: Option<i64>) -> Result<Vec<EmailSuppression>, AppError> {
+        let suppressions = sqlx::query_as::<_, EmailSuppression>(
+            r#"
+            SELECT email, reason, details, created_by, created_at
+            FROM email_suppressions
+            ORDER BY created_at DESC
+            LIMIT $1 OFFSET $2
+            "#,
+        )
+        .bind(limit.unwrap_or(50).clamp(1, 200))
+        .bind(offset.unwrap_or(0).max(0))
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(suppressions)
+    }
+
+    /// Allow sending to an address again
+    pub async fn remove_suppression(&self, email: &str, admin_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM email_suppressions WHERE email = $1")
+            .bind(email.trim().to_lowercase())
+            .execute(&self.db_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Address is not suppressed".to_string()));
+        }
+
+        info!("Admin {} lifted an email suppression", admin_id);
+
+        Ok(())
+    }
+
+    /// Recipient of the message a provider assigned this id to, for reports that only carry the id
+    pub async fn address_for_provider_message(&self, provider_message_id: &str) -> Result<Option<String>, AppError> {
+        let address = sqlx::query_scalar::<_, String>(
+            "SELECT to_address FROM email_messages WHERE provider_message_id = $1 LIMIT 1",
+        )
+        .bind(provider_message_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(address)
+    }
+
+    /// Record a bounce report; only permanent bounces suppress the address
+    pub async fn record_bounce(&self, email: &str, permanent: bool, details: Option<String>) -> Result<(), AppError> {
+        if !permanent {
+            debug!("Ignoring transient bounce: {:?}", details);
+            return Ok(());
+        }
+
+        self.suppress(email, SuppressionReason::HardBounce, details, None).await?;
+        Ok(())
+    }
+
+    /// Record a spam complaint; the address is suppressed
+    pub async fn record_complaint(&self, email: &str, details: Option<String>) -> Result<(), AppError> {
+        self.suppress(email, SuppressionReason::Complaint, details, None).await?;
+        Ok(())
+    }
+
+    /// Apply an SES event (the `Message` of an SNS notification); returns how many addresses were suppressed
+    pub async fn handle_ses_event(&self, event: &Value) -> Result<usize, AppError> {
+        let reports = ses_event_reports(event);
+        for report in &reports {
+            match report.kind {
+                ReportKind::Bounce { permanent } => {
+                    self.record_bounce(&report.email, permanent, report.details.clone()).await?
+                }
+                ReportKind::Complaint => self.record_complaint(&report.email, report.details.clone()).await?,
+            }
+        }
+
+        Ok(reports
+            .iter()
+            .filter(|r| !matches!(r.kind, ReportKind::Bounce { permanent: false }))
+            .count())
+    }
+
+    // Private helper methods
+
+    /// Mark a pending message as sending; None when another worker got to it first
+    async fn claim(&self, message_id: Uuid) -> Result<Option<EmailMessage>, AppError> {
+        let message = sqlx::query_as::<_, EmailMessage>(&format!(
+            r#"
+            UPDATE email_messages
+            SET status = 'sending', last_attempt_at = NOW()
+            WHERE id = $1 AND status = 'pending'
+            RETURNING {}
+            "#,
+            MESSAGE_COLUMNS
+        ))
+        .bind(message_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(message)
+    }
+
+    /// Render a claimed message, hand it to the provider and record the outcome
+    async fn attempt(&self, message: EmailMessage) -> Result<EmailMessage, AppError> {
+        // The address may have bounced since the message was queued
+        if self.is_suppressed(&message.to_address).await? {
+            return self.record_final(&message, EmailStatus::Suppressed, None).await;
+        }
+
+        let rendered = match self.templates.render(message.template, &message.payload) {
+            Ok(rendered) => rendered,
+            Err(e) => return self.record_final(&message, EmailStatus::Dead, Some(e.to_string())).await,
+        };
+
+        let outgoing = OutgoingEmail {
+            from: self.from.clone(),
+            to: message.to_address.clone(),
+            subject: rendered.subject,
+            html: rendered.html,
+            text: rendered.text,
+        };
+
+        let failure = match self.provider.send(&outgoing).await {
+            Ok(provider_message_id) => {
+                let sent = sqlx::query_as::<_, EmailMessage>(&format!(
+                    r#"
+                    UPDATE email_messages
+                    SET status = 'sent', attempts = attempts + 1, provider = $2, provider_message_id = $3,
+                        payload = '{{}}', last_error = NULL, next_attempt_at = NULL, sent_at = NOW()
+                    WHERE id = $1
+                    RETURNING {}
+                    "#,
+                    MESSAGE_COLUMNS
+                ))
+                .bind(message.id)
+                .bind(self.provider.name())
+                .bind(provider_message_id)
+                .fetch_one(&self.db_pool)
+                .await?;
+
+                return Ok(sent);
+            }
+            Err(failure) => failure,
+        };
+
+        let attempts = message.attempts as i64 + 1;
+        let reason = match failure {
+            SendFailure::Permanent(reason) => {
+                error!("Email {} ({}) was rejected, giving up: {}", message.id, message.template.as_str(), reason);
+                return self.record_final(&message, EmailStatus::Dead, Some(reason)).await;
+            }
+            SendFailure::Transient(reason) => reason,
+        };
+
+        let max_attempts = self.get_setting("email_max_attempts", DEFAULT_MAX_ATTEMPTS).await?;
+        let next_attempt_at = if attempts < max_attempts {
+            let base_seconds = self.get_setting("email_retry_base_seconds", DEFAULT_RETRY_BASE_SECONDS).await?;
+            let max_seconds = self.get_setting("email_retry_max_seconds", DEFAULT_RETRY_MAX_SECONDS).await?;
+            Some(Utc::now() + Duration::seconds(retry_delay_seconds(attempts, base_seconds, max_seconds)))
+        } else {
+            None
+        };
+        let status = if next_attempt_at.is_some() { EmailStatus::Failed } else { EmailStatus::Dead };
+
+        match status {
+            EmailStatus::Dead => error!(
+                "Email {} ({}) failed after {} attempts, giving up: {}",
+                message.id, message.template.as_str(), attempts, reason
+            ),
+            _ => warn!(
+                "Email {} ({}) failed on attempt {}, retrying: {}",
+                message.id, message.template.as_str(), attempts, reason
+            ),
+        }
+
+        let failed = sqlx::query_as::<_, EmailMessage>(&format!(
+            r#"
+            UPDATE email_messages
+            SET status = $2, attempts = attempts + 1, provider = $3, last_error = $4, next_attempt_at = $5
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            MESSAGE_COLUMNS
+        ))
+        .bind(message.id)
+        .bind(status)
+        .bind(self.provider.name())
+        .bind(reason)
+        .bind(next_attempt_at)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(failed)
+    }
+
+    /// Close out a message that will not be sent; suppressed messages also drop their payload
+    async fn record_final(
+        &self,
+        message: &EmailMessage,
+        status: EmailStatus,
+        reason: Option<String>,
+    ) -> Result<EmailMessage, AppError> {
+        let updated = sqlx::query_as::<_, EmailMessage>(&format!(
+            r#"
+            UPDATE email_messages
+            SET status = $2, last_error = $3, next_attempt_at = NULL,
+                payload = CASE WHEN $2 = 'suppressed' THEN '{{}}'::jsonb ELSE payload END
+            WHERE id = $1
+            RETURNING {}
+            "#,
+            MESSAGE_COLUMNS
+        ))
+        .bind(message.id)
+        .bind(status)
+        .bind(reason)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(updated)
+    }
+
+    async fn get_setting(&self, key: &str, default: i64) -> Result<i64, AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, key).await?;
+        Ok(setting
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ReportKind {
+    Bounce { permanent: bool },
+    Complaint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DeliveryReport {
+    email: String,
+    kind: ReportKind,
+    details: Option<String>,
+}
+
+/// Addresses named in an SES bounce or complaint event; other event types yield nothing
+fn ses_event_reports(event: &Value) -> Vec<DeliveryReport> {
+    // SES uses `notificationType` for identity notifications and `eventType` for configuration set events
+    let event_type = event["notificationType"].as_str().or_else(|| event["eventType"].as_str());
+
+    let (key, recipients_key) = match event_type {
+        Some("Bounce") => ("bounce", "bouncedRecipients"),
+        Some("Complaint") => ("complaint", "complainedRecipients"),
+        _ => return Vec::new(),
+    };
+    let report = &event[key];
+
+    let kind = match key {
+        // Only "Permanent" bounces are final; "Transient" and "Undetermined" may clear up
+        "bounce" => ReportKind::Bounce {
+            permanent: report["bounceType"].as_str() == Some("Permanent"),
+        },
+        _ => ReportKind::Complaint,
+    };
+
+    report[recipients_key]
+        .as_array()
+        .map(|recipients| {
+            recipients
+                .iter()
+                .filter_map(|recipient| {
+                    let email = recipient["emailAddress"].as_str()?.to_string();
+                    let details = recipient["diagnosticCode"]
+                        .as_str()
+                        .or_else(|| report["complaintFeedbackType"].as_str())
+                        .map(str::to_string);
+                    Some(DeliveryReport { email, kind: kind.clone(), details })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Notification data plus the fields every template can use
+fn template_data(data: Value, username: &str, app_base_url: &str) -> Value {
+    let mut data = match data {
+        Value::Object(map) => map,
+        _ => serde_json::Map::new(),
+    };
+    data.insert("username".to_string(), Value::String(username.to_string()));
+    data.insert("app_url".to_string(), Value::String(app_base_url.to_string()));
+    Value::Object(data)
+}
+
+fn subject_key(template: EmailTemplate) -> String {
+    format!("{}.subject", template.as_str())
+}
+
+/// Lowercase and check an address well enough to keep junk out of the queue
+fn normalize_address(email: &str) -> Result<String, AppError> {
+    let email = email.trim().to_lowercase();
+    match email.split_once('@') {
+        Some((local, domain)) if !local.is_empty() && domain.contains('.') && !email.contains(char::is_whitespace) => Ok(email),
+        _ => Err(AppError::Validation("Invalid email address".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_data(template: EmailTemplate) -> Value {
+        let data = match template {
+            EmailTemplate::Verification => serde_json::json!({
+                "link": "https://thriftee.com/verify-email?token=abc",
+                "expires_hours": 24
+            }),
+            EmailTemplate::PasswordReset => serde_json::json!({
+                "link": "https://thriftee.com/reset-password?token=abc",
+                "expires_minutes": 60
+            }),
+            EmailTemplate::NewDeviceLogin => serde_json::json!({
+                "device": "Chrome on Windows",
+                "ip_address": "203.0.113.7",
+                "signed_in_at": "June 1, 2025 at 09:30 UTC",
+                "link": "https://thriftee.com/forgot-password"
+            }),
+            EmailTemplate::RaffleWon => serde_json::json!({
+                "raffle_id": Uuid::nil(),
+                "item_title": "Vintage <Camera>"
+            }),
+            EmailTemplate::Receipt => serde_json::json!({
+                "item_title": "Vintage Camera",
+                "receipt": {
+                    "receipt_number": "R-1001",
+                    "verification_code": "ABCD",
+                    "box_numbers": [3, 7],
+                    "subtotal": 9.24,
+                    "tax_lines": [{ "name": "Sales tax (included)", "rate_percentage": 8.25, "amount": 0.76 }],
+                    "credits_used": 10.0,
+                    "total_amount": 10.0,
+                    "currency": "USD"
+                }
+            }),
+            EmailTemplate::Notification => serde_json::json!({
+                "title": "Raffle Results",
+                "message": "The raffle has ended."
+            }),
+        };
+        template_data(data, "janedoe", "https://thriftee.com")
+    }
+
+    #[test]
+    fn test_every_template_renders() {
+        let templates = EmailTemplates::new();
+
+        for template in EmailTemplate::ALL {
+            let rendered = templates.render(template, &sample_data(template)).unwrap();
+            assert!(!rendered.subject.is_empty(), "{} has no subject", template.as_str());
+            assert!(rendered.html.contains("<html"), "{} did not render to HTML", template.as_str());
+            assert!(rendered.text.contains("janedoe"), "{} does not greet the user", template.as_str());
+        }
+    }
+
+    #[test]
+    fn test_render_escapes_html_but_not_text() {
+        let templates = EmailTemplates::new();
+        let rendered = templates.render(EmailTemplate::RaffleWon, &sample_data(EmailTemplate::RaffleWon)).unwrap();
+
+        assert_eq!(rendered.subject, "You won Vintage <Camera>!");
+        assert!(rendered.text.contains("Vintage <Camera>"));
+        assert!(rendered.html.contains("Vintage &lt;Camera&gt;"));
+        assert!(!rendered.html.contains("<Camera>"));
+    }
+
+    #[test]
+    fn test_receipt_lists_boxes() {
+        let templates = EmailTemplates::new();
+        let rendered = templates.render(EmailTemplate::Receipt, &sample_data(EmailTemplate::Receipt)).unwrap();
+
+        assert_eq!(rendered.subject, "Your receipt R-1001");
+        assert!(rendered.text.contains("#3 #7"));
+    }
+
+    #[test]
+    fn test_normalize_address() {
+        assert_eq!(normalize_address("  Jane@Example.COM ").unwrap(), "jane@example.com");
+        assert!(normalize_address("jane").is_err());
+        assert!(normalize_address("@example.com").is_err());
+        assert!(normalize_address("jane@localhost").is_err());
+        assert!(normalize_address("ja ne@example.com").is_err());
+    }
+
+    #[test]
+    fn test_ses_bounce_reports() {
+        let event = serde_json::json!({
+            "notificationType": "Bounce",
+            "bounce": {
+                "bounceType": "Permanent",
+                "bouncedRecipients": [
+                    {"emailAddress": "gone@example.com", "diagnosticCode": "smtp; 550 5.1.1 user unknown"},
+                    {"emailAddress": "also-gone@example.com"}
+                ]
+            }
+        });
+
+        let reports = ses_event_reports(&event);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].email, "gone@example.com");
+        assert_eq!(reports[0].kind, ReportKind::Bounce { permanent: true });
+        assert_eq!(reports[0].details.as_deref(), Some("smtp; 550 5.1.1 user unknown"));
+
+        let transient = serde_json::json!({
+            "eventType": "Bounce",
+            "bounce": {"bounceType": "Transient", "bouncedRecipients": [{"emailAddress": "full@example.com"}]}
+        });
+        assert_eq!(ses_event_reports(&transient)[0].kind, ReportKind::Bounce { permanent: false });
+    }
+
+    #[test]
+    fn test_ses_complaint_reports() {
+        let event = serde_json::json!({
+            "notificationType": "Complaint",
+            "complaint": {
+                "complaintFeedbackType": "abuse",
+                "complainedRecipients": [{"emailAddress": "angry@example.com"}]
+            }
+        });
+
+        let reports = ses_event_reports(&event);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].kind, ReportKind::Complaint);
+        assert_eq!(reports[0].details.as_deref(), Some("abuse"));
+
+        assert!(ses_event_reports(&serde_json::json!({"notificationType": "Delivery"})).is_empty());
+    }
+}
@@ -0,0 +1,330 @@
+use crate::error::AppError;
+use crate::models::auth_token::{AuthToken, AuthTokenKind, TokenRejection};
+use crate::models::system_settings::SystemSetting;
+use crate::models::AuditLog;
+use crate::services::email_service::EmailService;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::net::IpAddr;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+const DEFAULT_RESEND_BASE_SECONDS: i64 = 60;
+const DEFAULT_RESEND_MAX_SECONDS: i64 = 3600;
+const DEFAULT_RESEND_WINDOW_HOURS: i64 = 24;
+
+/// Email verification service issues verification links with per-address resend backoff
+#[derive(Clone)]
+pub struct EmailVerificationService {
+    db_pool: PgPool,
+    email_service: Option<EmailService>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationAction {
+    Resend,
+    Verify,
+}
+
+impl VerificationAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationAction::Resend => "resend",
+            VerificationAction::Verify => "verify",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationOutcome {
+    Sent,
+    Throttled,
+    UnknownEmail,
+    AlreadyVerified,
+    Verified,
+    InvalidToken,
+    ExpiredToken,
+    SupersededToken,
+}
+
+impl VerificationOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationOutcome::Sent => "sent",
+            VerificationOutcome::Throttled => "throttled",
+            VerificationOutcome::UnknownEmail => "unknown_email",
+            VerificationOutcome::AlreadyVerified => "already_verified",
+            VerificationOutcome::Verified => "verified",
+            VerificationOutcome::InvalidToken => "invalid_token",
+            VerificationOutcome::ExpiredToken => "expired_token",
+            VerificationOutcome::SupersededToken => "superseded_token",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResendVerificationResult {
+    /// Earliest time another verification email may be requested
+    pub next_resend_at: DateTime<Utc>,
+    pub retry_after_seconds: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BackoffPolicy {
+    base_seconds: i64,
+    max_seconds: i64,
+    window_hours: i64,
+}
+
+impl EmailVerificationService {
+    /// Create a new email verification service
+    pub fn new(db_pool: PgPool) -> Self {
+        Self {
+            db_pool,
+            email_service: None,
+        }
+    }
+
+    /// Email verification links instead of only logging them
+    pub fn with_email_service(mut self, email_service: EmailService) -> Self {
+        self.email_service = Some(email_service);
+        self
+    }
+
+    /// Issue a new verification link, unless the address is still backing off from earlier sends.
+    ///
+    /// Unknown addresses are answered exactly like known ones so the endpoint cannot be used
+    /// to discover accounts.
+    pub async fn resend_verification(
+        &self,
+        email: &str,
+        ip_address: Option<IpAddr>,
+    ) -> Result<ResendVerificationResult, AppError> {
+        let email = email.trim().to_lowercase();
+        let policy = self.get_backoff_policy().await?;
+        let now = Utc::now();
+
+        let mut tx = self.db_pool.begin().await?;
+
+        // Serialize resends per address so concurrent clicks cannot both pass the throttle
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(&email)
+            .execute(&mut *tx)
+            .await?;
+
+        let history = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "sends!", MAX(created_at) as last_sent_at
+            FROM email_verification_attempts
+            WHERE email = $1 AND action = 'resend' AND outcome IN ('sent', 'unknown_email')
+            AND created_at > $2
+            "#,
+            email,
+            now - Duration::hours(policy.window_hours)
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if let Some(next_allowed) = history
+            .last_sent_at
+            .map(|last| next_resend_at(last, history.sends, policy.base_seconds, policy.max_seconds))
+            .filter(|next| *next > now)
+        {
+            record_attempt(&mut tx, Some(&email), None, VerificationAction::Resend, VerificationOutcome::Throttled, ip_address).await?;
+            tx.commit().await?;
+
+            let retry_after_seconds = (next_allowed - now).num_seconds().max(1);
+            return Err(AppError::RateLimited {
+                message: "A verification email was sent recently. Please wait before requesting another.".to_string(),
+                retry_after_seconds,
+            });
+        }
+
+        let user = sqlx::query!(
+            "SELECT id, email_verified FROM users WHERE LOWER(email) = $1",
+            email
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(user) = user else {
+            record_attempt(&mut tx, Some(&email), None, VerificationAction::Resend, VerificationOutcome::UnknownEmail, ip_address).await?;
+            tx.commit().await?;
+            return Ok(resend_result(now, history.sends + 1, policy));
+        };
+
+        if user.email_verified.unwrap_or(false) {
+            record_attempt(&mut tx, Some(&email), Some(user.id), VerificationAction::Resend, VerificationOutcome::AlreadyVerified, ip_address).await?;
+            tx.commit().await?;
+            return Err(AppError::Validation("Email is already verified".to_string()));
+        }
+
+        // Only the newest link works; older ones are superseded
+        let ttl = AuthTokenKind::EmailVerification.ttl(&self.db_pool).await?;
+        let verification_token = AuthToken::issue(&mut tx, AuthTokenKind::EmailVerification, user.id, ttl).await?;
+
+        record_attempt(&mut tx, Some(&email), Some(user.id), VerificationAction::Resend, VerificationOutcome::Sent, ip_address).await?;
+        tx.commit().await?;
+
+        match &self.email_service {
+            Some(email_service) => email_service.send_verification(user.id, &verification_token, ttl.num_hours()).await?,
+            None => info!("Email verification token for {}: {}", email, verification_token),
+        }
+
+        Ok(resend_result(now, history.sends + 1, policy))
+    }
+
+    /// Verify an email address with a token from a verification link
+    pub async fn verify_email(&self, token: &str, ip_address: Option<IpAddr>) -> Result<Uuid, AppError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        let record = AuthToken::find_for_update(&mut tx, AuthTokenKind::EmailVerification, token).await?;
+
+        let Some(record) = record else {
+            record_attempt(&mut tx, None, None, VerificationAction::Verify, VerificationOutcome::InvalidToken, ip_address).await?;
+            tx.commit().await?;
+            return Err(AppError::Authentication("Invalid or expired verification token".to_string()));
+        };
+
+        let email: String = sqlx::query_scalar("SELECT LOWER(email) FROM users WHERE id = $1")
+            .bind(record.user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        if let Err(rejection) = record.check(Utc::now()) {
+            let (outcome, message) = match rejection {
+                TokenRejection::Used => (VerificationOutcome::InvalidToken, "Invalid or expired verification token"),
+                TokenRejection::Superseded => (
+                    VerificationOutcome::SupersededToken,
+                    "This verification link was replaced by a newer one; use the latest email",
+                ),
+                TokenRejection::Expired => (VerificationOutcome::ExpiredToken, "Invalid or expired verification token"),
+            };
+            record_attempt(&mut tx, Some(&email), Some(record.user_id), VerificationAction::Verify, outcome, ip_address).await?;
+            tx.commit().await?;
+            return Err(AppError::Authentication(message.to_string()));
+        }
+
+        record.mark_used(&mut tx, AuthTokenKind::EmailVerification).await?;
+
+        sqlx::query!(
+            "UPDATE users SET email_verified = true, updated_at = NOW() WHERE id = $1",
+            record.user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        record_attempt(&mut tx, Some(&email), Some(record.user_id), VerificationAction::Verify, VerificationOutcome::Verified, ip_address).await?;
+        tx.commit().await?;
+
+        // Log email verification
+        if let Err(e) = AuditLog::create(
+            &self.db_pool,
+            Some(record.user_id),
+            raffle_platform_shared::AuditAction::UserAction,
+            Some("auth".to_string()),
+            Some(record.user_id),
+            None,
+            Some(serde_json::json!({
+                "event": "email_verified"
+            })),
+            ip_address,
+            None,
+        ).await {
+            warn!("Failed to write audit log for email verification of {}: {}", record.user_id, e);
+        }
+
+        Ok(record.user_id)
+    }
+
+    // Private helper methods
+
+    async fn get_backoff_policy(&self) -> Result<BackoffPolicy, AppError> {
+        Ok(BackoffPolicy {
+            base_seconds: self.get_setting("email_verification_resend_base_seconds", DEFAULT_RESEND_BASE_SECONDS).await?,
+            max_seconds: self.get_setting("email_verification_resend_max_seconds", DEFAULT_RESEND_MAX_SECONDS).await?,
+            window_hours: self.get_setting("email_verification_resend_window_hours", DEFAULT_RESEND_WINDOW_HOURS).await?,
+        })
+    }
+
+    async fn get_setting(&self, key: &str, default: i64) -> Result<i64, AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, key).await?;
+        Ok(setting
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default))
+    }
+}
+
+async fn record_attempt(
+    tx: &mut Transaction<'_, Postgres>,
+    email: Option<&str>,
+    user_id: Option<Uuid>,
+    action: VerificationAction,
+    outcome: VerificationOutcome,
+    ip_address: Option<IpAddr>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO email_verification_attempts (email, user_id, action, outcome, ip_address)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        email,
+        user_id,
+        action.as_str(),
+        outcome.as_str(),
+        ip_address
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+fn resend_result(sent_at: DateTime<Utc>, sends: i64, policy: BackoffPolicy) -> ResendVerificationResult {
+    let next_resend_at = next_resend_at(sent_at, sends, policy.base_seconds, policy.max_seconds);
+
+    ResendVerificationResult {
+        next_resend_at,
+        retry_after_seconds: (next_resend_at - sent_at).num_seconds(),
+    }
+}
+
+/// Wait before the next resend after `sends` emails in the current window: base, 2×base, 4×base, ... capped
+pub fn resend_backoff_seconds(sends: i64, base_seconds: i64, max_seconds: i64) -> i64 {
+    if sends <= 0 {
+        return 0;
+    }
+
+    let exponent = (sends - 1).min(32) as u32;
+    base_seconds.saturating_mul(2i64.saturating_pow(exponent)).min(max_seconds)
+}
+
+fn next_resend_at(last_sent_at: DateTime<Utc>, sends: i64, base_seconds: i64, max_seconds: i64) -> DateTime<Utc> {
+    last_sent_at + Duration::seconds(resend_backoff_seconds(sends, base_seconds, max_seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resend_backoff_doubles_until_cap() {
+        let backoff: Vec<i64> = (0..=7).map(|n| resend_backoff_seconds(n, 60, 3600)).collect();
+        assert_eq!(backoff, vec![0, 60, 120, 240, 480, 960, 1920, 3600]);
+        assert_eq!(resend_backoff_seconds(1_000, 60, 3600), 3600);
+    }
+
+    #[test]
+    fn test_resend_result_reports_wait() {
+        let policy = BackoffPolicy { base_seconds: 60, max_seconds: 3600, window_hours: 24 };
+        let sent_at = Utc::now();
+        let result = resend_result(sent_at, 3, policy);
+
+        assert_eq!(result.retry_after_seconds, 240);
+        assert_eq!(result.next_resend_at, sent_at + Duration::seconds(240));
+    }
+}
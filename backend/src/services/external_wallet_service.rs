@@ -0,0 +1,511 @@
+use crate::error::AppError;
+use crate::models::external_wallet::ExternalWallet;
+use crate::models::User;
+use crate::services::blockchain_service::BlockchainService;
+use crate::utils::validation::validation_errors_to_app_error;
+use chrono::{DateTime, Duration, Utc};
+use ethers::signers::Signer;
+use ethers::types::transaction::eip712::TypedData;
+use ethers::types::{Address, Bytes, Signature, U256};
+use ethers::utils::to_checksum;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::str::FromStr;
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+/// EIP-712 domain the contract checks purchase vouchers under
+const VOUCHER_DOMAIN_NAME: &str = "Thriftee Raffle";
+const VOUCHER_DOMAIN_VERSION: &str = "1";
+/// Time a wallet has to sign the link challenge
+const CHALLENGE_TTL_MINUTES: i64 = 10;
+/// Time a voucher can be redeemed in; a pending purchase can be given a new one
+const VOUCHER_TTL_MINUTES: i64 = 60;
+const MAX_WALLETS_PER_USER: usize = 10;
+
+const PURCHASE_COLUMNS: &str = "id, raffle_id, wallet_id, address, blockchain_raffle_id, box_purchase_ids, boxes, \
+                                status, deadline, transaction_hash, confirmed_at, created_at";
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LinkChallengeRequest {
+    pub address: String,
+}
+
+/// Text for the wallet to sign with `personal_sign` to prove it belongs to the user
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LinkChallenge {
+    pub challenge_id: Uuid,
+    pub address: String,
+    pub message: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct LinkWalletRequest {
+    pub challenge_id: Uuid,
+    /// 65-byte signature of the challenge message, hex encoded
+    pub signature: String,
+    #[validate(length(max = 100))]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ExternalPurchaseStatus {
+    /// Paid for; waiting for the wallet to redeem the voucher on chain
+    Pending,
+    /// The voucher was redeemed and the boxes are held by the wallet on chain
+    Confirmed,
+}
+
+/// Boxes bought through the platform and recorded on chain under the user's own wallet
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ExternalPurchase {
+    pub id: Uuid,
+    pub raffle_id: Uuid,
+    pub wallet_id: Uuid,
+    pub address: String,
+    pub blockchain_raffle_id: i64,
+    pub box_purchase_ids: Vec<Uuid>,
+    pub boxes: i32,
+    pub status: ExternalPurchaseStatus,
+    /// When the latest voucher stops being accepted
+    pub deadline: DateTime<Utc>,
+    pub transaction_hash: Option<String>,
+    pub confirmed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A transaction for the user's wallet to sign and send; gas is left to the wallet
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UnsignedTransaction {
+    pub from: String,
+    pub to: String,
+    pub data: String,
+    pub value: String,
+    pub chain_id: u64,
+}
+
+/// A purchase voucher, both as typed data and as the transaction redeeming it
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PreparedExternalPurchase {
+    pub purchase: ExternalPurchase,
+    /// EIP-712 typed data of the voucher, as for `eth_signTypedData_v4`
+    #[schema(value_type = Object)]
+    pub typed_data: serde_json::Value,
+    /// The platform's signature of the voucher
+    pub voucher_signature: String,
+    /// `buyBoxesWithVoucher` call from the user's wallet
+    pub transaction: UnsignedTransaction,
+}
+
+/// Links wallets users hold themselves and issues vouchers that let those wallets record
+/// boxes paid for on the platform. The event processor confirms redeemed vouchers.
+#[derive(Clone)]
+pub struct ExternalWalletService {
+    db_pool: PgPool,
+    blockchain_service: BlockchainService,
+}
+
+impl ExternalWalletService {
+    pub fn new(db_pool: PgPool, blockchain_service: BlockchainService) -> Self {
+        Self {
+            db_pool,
+            blockchain_service,
+        }
+    }
+
+    /// Start linking a wallet: the user signs the returned message with it
+    pub async fn create_challenge(&self, user_id: Uuid, request: LinkChallengeRequest) -> Result<LinkChallenge, AppError> {
+        let address = parse_address(&request.address)?;
+
+        let user = User::find_by_id(&self.db_pool, user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+        if user.internal_wallet_address.eq_ignore_ascii_case(&address) {
+            return Err(AppError::Validation("This is your platform wallet".to_string()));
+        }
+        if let Some(owner) = ExternalWallet::owner_of(&self.db_pool, &address).await? {
+            if owner != user_id {
+                return Err(AppError::Conflict("This wallet belongs to another account".to_string()));
+            }
+        }
+        if ExternalWallet::list_for_user(&self.db_pool, user_id).await?.len() >= MAX_WALLETS_PER_USER {
+            return Err(AppError::Validation(format!(
+                "You can link at most {} wallets",
+                MAX_WALLETS_PER_USER
+            )));
+        }
+
+        let now = Utc::now();
+        let expires_at = now + Duration::minutes(CHALLENGE_TTL_MINUTES);
+        let nonce = hex::encode(rand::thread_rng().gen::<[u8; 16]>());
+        let message = link_message(&address, &nonce, self.chain_id(), now, expires_at);
+
+        let challenge_id = sqlx::query_scalar::<_, Uuid>(
+            "INSERT INTO external_wallet_challenges (user_id, address, message, expires_at) \
+             VALUES ($1, $2, $3, $4) RETURNING id",
+        )
+        .bind(user_id)
+        .bind(&address)
+        .bind(&message)
+        .bind(expires_at)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(LinkChallenge {
+            challenge_id,
+            address,
+            message,
+            expires_at,
+        })
+    }
+
+    /// Link the wallet that signed a challenge
+    pub async fn link_wallet(&self, user_id: Uuid, request: LinkWalletRequest) -> Result<ExternalWallet, AppError> {
+        request.validate().map_err(validation_errors_to_app_error)?;
+
+        // Claiming the challenge first means each one can be tried once
+        let (address, message) = sqlx::query_as::<_, (String, String)>(
+            "UPDATE external_wallet_challenges SET used_at = NOW() \
+             WHERE id = $1 AND user_id = $2 AND used_at IS NULL AND expires_at > NOW() \
+             RETURNING address, message",
+        )
+        .bind(request.challenge_id)
+        .bind(user_id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::Validation("The challenge has expired or was already used".to_string()))?;
+
+        let signature = Signature::from_str(request.signature.trim())
+            .map_err(|_| AppError::Validation("Invalid signature".to_string()))?;
+        let signer = signature
+            .recover(message.as_str())
+            .map_err(|_| AppError::Validation("Invalid signature".to_string()))?;
+        if !to_checksum(&signer, None).eq_ignore_ascii_case(&address) {
+            return Err(AppError::Validation("The signature is not from this wallet".to_string()));
+        }
+
+        let label = request.label.as_deref().map(str::trim).filter(|label| !label.is_empty());
+        let wallet = ExternalWallet::link(&self.db_pool, user_id, &address, label).await?;
+
+        info!("User {} linked external wallet {}", user_id, wallet.address);
+        Ok(wallet)
+    }
+
+    pub async fn list_wallets(&self, user_id: Uuid) -> Result<Vec<ExternalWallet>, AppError> {
+        ExternalWallet::list_for_user(&self.db_pool, user_id).await
+    }
+
+    pub async fn unlink_wallet(&self, user_id: Uuid, wallet_id: Uuid) -> Result<(), AppError> {
+        ExternalWallet::unlink(&self.db_pool, user_id, wallet_id).await?;
+
+        info!("User {} unlinked external wallet {}", user_id, wallet_id);
+        Ok(())
+    }
+
+    /// Check a purchase can be recorded from the wallet before the boxes are paid for.
+    /// Returns the wallet and the raffle's on-chain id.
+    pub async fn check_purchase(
+        &self,
+        user_id: Uuid,
+        wallet_id: Uuid,
+        raffle_id: Uuid,
+    ) -> Result<(ExternalWallet, i64), AppError> {
+        let wallet = ExternalWallet::find_linked(&self.db_pool, user_id, wallet_id).await?;
+
+        let blockchain_raffle_id =
+            sqlx::query_scalar::<_, Option<i64>>("SELECT blockchain_raffle_id FROM raffles WHERE id = $1")
+                .bind(raffle_id)
+                .fetch_optional(&self.db_pool)
+                .await?
+                .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?
+                .ok_or_else(|| {
+                    AppError::Validation("This raffle is not on chain yet; buy with your platform wallet".to_string())
+                })?;
+
+        Ok((wallet, blockchain_raffle_id))
+    }
+
+    /// Record paid-for boxes as an external purchase and issue its first voucher
+    pub async fn create_purchase(
+        &self,
+        user_id: Uuid,
+        wallet: &ExternalWallet,
+        raffle_id: Uuid,
+        blockchain_raffle_id: i64,
+        box_purchase_ids: Vec<Uuid>,
+    ) -> Result<PreparedExternalPurchase, AppError> {
+        let purchase = sqlx::query_as::<_, ExternalPurchase>(&format!(
+            "INSERT INTO external_purchases \
+                (user_id, raffle_id, wallet_id, address, blockchain_raffle_id, box_purchase_ids, boxes, deadline) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+             RETURNING {}",
+            PURCHASE_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(raffle_id)
+        .bind(wallet.id)
+        .bind(&wallet.address)
+        .bind(blockchain_raffle_id)
+        .bind(&box_purchase_ids)
+        .bind(box_purchase_ids.len() as i32)
+        .bind(Utc::now() + Duration::minutes(VOUCHER_TTL_MINUTES))
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        info!(
+            "User {} bought {} boxes in raffle {} for external wallet {}",
+            user_id, purchase.boxes, raffle_id, purchase.address
+        );
+        self.issue_voucher(purchase).await
+    }
+
+    /// A new voucher for a purchase whose wallet hasn't redeemed one yet
+    pub async fn renew_voucher(&self, user_id: Uuid, purchase_id: Uuid) -> Result<PreparedExternalPurchase, AppError> {
+        let purchase = sqlx::query_as::<_, ExternalPurchase>(&format!(
+            "UPDATE external_purchases SET deadline = $3, updated_at = NOW() \
+             WHERE id = $1 AND user_id = $2 AND status = 'pending' \
+             RETURNING {}",
+            PURCHASE_COLUMNS
+        ))
+        .bind(purchase_id)
+        .bind(user_id)
+        .bind(Utc::now() + Duration::minutes(VOUCHER_TTL_MINUTES))
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("No pending external purchase found".to_string()))?;
+
+        self.issue_voucher(purchase).await
+    }
+
+    /// The user's external purchases, newest first
+    pub async fn list_purchases(&self, user_id: Uuid) -> Result<Vec<ExternalPurchase>, AppError> {
+        let purchases = sqlx::query_as::<_, ExternalPurchase>(&format!(
+            "SELECT {} FROM external_purchases WHERE user_id = $1 ORDER BY created_at DESC LIMIT 100",
+            PURCHASE_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(purchases)
+    }
+
+    // Private helper methods
+
+    async fn issue_voucher(&self, purchase: ExternalPurchase) -> Result<PreparedExternalPurchase, AppError> {
+        let contract_client = self.blockchain_service.contract_client();
+        let contract_address = self.blockchain_service.get_contract_address();
+        let buyer = parse_address(&purchase.address)?;
+        let purchase_id = purchase_id_bytes(purchase.id);
+        let deadline = purchase.deadline.timestamp() as u64;
+
+        let typed_data = voucher_typed_data(
+            self.chain_id(),
+            &to_checksum(&contract_address, None),
+            purchase.blockchain_raffle_id as u64,
+            &buyer,
+            purchase.boxes as u64,
+            &purchase_id,
+            deadline,
+        );
+        let voucher: TypedData = serde_json::from_value(typed_data.clone())
+            .map_err(|e| AppError::Internal(format!("Invalid voucher typed data: {}", e)))?;
+        let signature = self
+            .blockchain_service
+            .client()
+            .signer()
+            .sign_typed_data(&voucher)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to sign purchase voucher: {}", e)))?;
+
+        let data = contract_client
+            .contract()
+            .buy_boxes_with_voucher(
+                U256::from(purchase.blockchain_raffle_id as u64),
+                U256::from(purchase.boxes as u64),
+                purchase_id,
+                U256::from(deadline),
+                Bytes::from(signature.to_vec()),
+            )
+            .calldata()
+            .ok_or_else(|| AppError::Internal("Failed to encode the voucher call".to_string()))?;
+
+        Ok(PreparedExternalPurchase {
+            transaction: UnsignedTransaction {
+                from: purchase.address.clone(),
+                to: to_checksum(&contract_address, None),
+                data: format!("0x{}", hex::encode(&data)),
+                value: "0x0".to_string(),
+                chain_id: self.chain_id(),
+            },
+            voucher_signature: format!("0x{}", hex::encode(signature.to_vec())),
+            typed_data,
+            purchase,
+        })
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.blockchain_service.client().network_config().chain_id
+    }
+}
+
+/// Checksummed form of an address
+fn parse_address(address: &str) -> Result<String, AppError> {
+    let parsed = Address::from_str(address.trim())
+        .map_err(|_| AppError::Validation(format!("Invalid wallet address: {}", address)))?;
+    if parsed.is_zero() {
+        return Err(AppError::Validation("Invalid wallet address: the zero address".to_string()));
+    }
+    Ok(to_checksum(&parsed, None))
+}
+
+/// The challenge text a wallet signs to be linked
+pub fn link_message(
+    address: &str,
+    nonce: &str,
+    chain_id: u64,
+    issued_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+) -> String {
+    format!(
+        "Link this wallet to your Thriftee account.\n\n\
+         Address: {}\n\
+         Chain ID: {}\n\
+         Nonce: {}\n\
+         Issued At: {}\n\
+         Expiration Time: {}\n\n\
+         Signing this message does not send a transaction or cost gas.",
+        address,
+        chain_id,
+        nonce,
+        issued_at.to_rfc3339(),
+        expires_at.to_rfc3339()
+    )
+}
+
+/// A purchase id as the voucher's bytes32: the UUID right-aligned, like a uint128
+pub fn purchase_id_bytes(purchase_id: Uuid) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[16..].copy_from_slice(purchase_id.as_bytes());
+    bytes
+}
+
+/// The purchase a voucher's bytes32 id refers to; None for ids the platform didn't issue
+pub fn purchase_uuid(purchase_id: &[u8; 32]) -> Option<Uuid> {
+    if purchase_id[..16].iter().any(|b| *b != 0) {
+        return None;
+    }
+    Uuid::from_slice(&purchase_id[16..]).ok()
+}
+
+/// EIP-712 typed data of a `BoxPurchase` voucher, in the shape `eth_signTypedData_v4` takes
+pub fn voucher_typed_data(
+    chain_id: u64,
+    contract_address: &str,
+    raffle_id: u64,
+    buyer: &str,
+    boxes: u64,
+    purchase_id: &[u8; 32],
+    deadline: u64,
+) -> serde_json::Value {
+    serde_json::json!({
+        "types": {
+            "EIP712Domain": [
+                {"name": "name", "type": "string"},
+                {"name": "version", "type": "string"},
+                {"name": "chainId", "type": "uint256"},
+                {"name": "verifyingContract", "type": "address"}
+            ],
+            "BoxPurchase": [
+                {"name": "raffleId", "type": "uint256"},
+                {"name": "buyer", "type": "address"},
+                {"name": "boxes", "type": "uint256"},
+                {"name": "purchaseId", "type": "bytes32"},
+                {"name": "deadline", "type": "uint256"}
+            ]
+        },
+        "primaryType": "BoxPurchase",
+        "domain": {
+            "name": VOUCHER_DOMAIN_NAME,
+            "version": VOUCHER_DOMAIN_VERSION,
+            "chainId": chain_id,
+            "verifyingContract": contract_address
+        },
+        "message": {
+            "raffleId": raffle_id.to_string(),
+            "buyer": buyer,
+            "boxes": boxes.to_string(),
+            "purchaseId": format!("0x{}", hex::encode(purchase_id)),
+            "deadline": deadline.to_string()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::signers::LocalWallet;
+    use ethers::types::transaction::eip712::Eip712;
+    use ethers::types::H256;
+
+    #[test]
+    fn test_purchase_id_round_trip() {
+        let id = Uuid::new_v4();
+        let bytes = purchase_id_bytes(id);
+        assert!(bytes[..16].iter().all(|b| *b == 0));
+        assert_eq!(purchase_uuid(&bytes), Some(id));
+
+        let mut foreign = bytes;
+        foreign[0] = 1;
+        assert_eq!(purchase_uuid(&foreign), None);
+    }
+
+    #[test]
+    fn test_parse_address_checksums() {
+        assert_eq!(
+            parse_address("0x742d35cc6634c0532925a3b8d4c9db96c4b4d8b6").unwrap(),
+            "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6"
+        );
+        assert!(parse_address("0x1234").is_err());
+        assert!(parse_address("0x0000000000000000000000000000000000000000").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_voucher_signature_recovers_platform_signer() {
+        let platform: LocalWallet = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse()
+            .unwrap();
+        let typed_data = voucher_typed_data(
+            137,
+            "0x742d35Cc6634C0532925a3b8D4C9db96C4b4d8b6",
+            7,
+            "0x2c7536E3605D9C16a7a3D7b1898e529396a65c23",
+            3,
+            &purchase_id_bytes(Uuid::new_v4()),
+            1_900_000_000,
+        );
+        let voucher: TypedData = serde_json::from_value(typed_data).unwrap();
+
+        let signature = platform.sign_typed_data(&voucher).await.unwrap();
+        let digest = voucher.encode_eip712().unwrap();
+        assert_eq!(signature.recover(H256::from(digest)).unwrap(), platform.address());
+    }
+
+    #[tokio::test]
+    async fn test_link_message_signature_recovers_wallet() {
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let address = to_checksum(&wallet.address(), None);
+        let now = Utc::now();
+        let message = link_message(&address, "00ff", 1, now, now + Duration::minutes(10));
+        assert!(message.contains(&address));
+
+        let signature = wallet.sign_message(message.as_str()).await.unwrap();
+        assert_eq!(signature.recover(message.as_str()).unwrap(), wallet.address());
+    }
+}
@@ -0,0 +1,291 @@
+use crate::error::AppError;
+use crate::models::favorite::{AddFavoriteRequest, FavoriteItem, FavoriteRaffle, Favorites};
+use crate::models::saved_search::{SavedSearch, SavedSearchRequest};
+use crate::models::system_settings::SystemSetting;
+use crate::models::tag::Tag;
+use crate::services::outbox_service::OutboxMessage;
+use crate::services::search_service::prefix_tsquery;
+use crate::utils::validation::validation_errors_to_app_error;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use tracing::{error, info};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+const ALERT_INTERVAL_SECONDS: u64 = 60;
+/// Saved searches checked per run; the least recently checked go first
+const SEARCH_BATCH_SIZE: i64 = 200;
+/// New raffles announced per saved search per run
+const MAX_MATCHES_PER_ALERT: i64 = 20;
+const DEFAULT_NEAR_COMPLETION_PERCENT: i64 = 80;
+const DEFAULT_ENDING_SOON_MINUTES: i64 = 60;
+
+/// Alerts queued by one run
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct FavoriteAlertReport {
+    pub saved_search_alerts: u64,
+    pub favorite_alerts: u64,
+}
+
+#[derive(Debug, FromRow)]
+struct NearlyDoneFavorite {
+    user_id: Uuid,
+    raffle_id: Uuid,
+    item_title: String,
+    boxes_remaining: i32,
+    ends_at: Option<DateTime<Utc>>,
+}
+
+/// Favorites service manages the raffles and items users favorite and the searches they save,
+/// and alerts them when a new raffle matches a saved search or a favorite raffle is nearly done.
+#[derive(Clone)]
+pub struct FavoritesService {
+    db_pool: PgPool,
+}
+
+impl FavoritesService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+
+    /// Check saved searches and favorites for alerts
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(ALERT_INTERVAL_SECONDS));
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.run_alerts().await {
+                    error!("Favorite and saved search alerts failed: {}", e);
+                }
+            }
+        });
+    }
+
+    pub async fn list_favorites(&self, user_id: Uuid) -> Result<Favorites, AppError> {
+        Ok(Favorites {
+            raffles: FavoriteRaffle::list_for_user(&self.db_pool, user_id).await?,
+            items: FavoriteItem::list_for_user(&self.db_pool, user_id).await?,
+        })
+    }
+
+    pub async fn add_favorite(&self, user_id: Uuid, request: AddFavoriteRequest) -> Result<Favorites, AppError> {
+        match (request.raffle_id, request.item_id) {
+            (Some(raffle_id), None) => {
+                FavoriteRaffle::add(&self.db_pool, user_id, raffle_id).await?;
+                info!("User {} favorited raffle {}", user_id, raffle_id);
+            }
+            (None, Some(item_id)) => {
+                FavoriteItem::add(&self.db_pool, user_id, item_id).await?;
+                info!("User {} favorited item {}", user_id, item_id);
+            }
+            _ => {
+                return Err(AppError::Validation(
+                    "Give either a raffle_id or an item_id to favorite".to_string(),
+                ))
+            }
+        }
+
+        self.list_favorites(user_id).await
+    }
+
+    pub async fn remove_raffle(&self, user_id: Uuid, raffle_id: Uuid) -> Result<(), AppError> {
+        FavoriteRaffle::remove(&self.db_pool, user_id, raffle_id).await?;
+        info!("User {} unfavorited raffle {}", user_id, raffle_id);
+        Ok(())
+    }
+
+    pub async fn remove_item(&self, user_id: Uuid, item_id: Uuid) -> Result<(), AppError> {
+        FavoriteItem::remove(&self.db_pool, user_id, item_id).await?;
+        info!("User {} unfavorited item {}", user_id, item_id);
+        Ok(())
+    }
+
+    pub async fn list_saved_searches(&self, user_id: Uuid) -> Result<Vec<SavedSearch>, AppError> {
+        SavedSearch::list_for_user(&self.db_pool, user_id).await
+    }
+
+    pub async fn create_saved_search(
+        &self,
+        user_id: Uuid,
+        request: SavedSearchRequest,
+    ) -> Result<SavedSearch, AppError> {
+        let request = self.prepare(request).await?;
+        let search = SavedSearch::create(&self.db_pool, user_id, &request).await?;
+
+        info!("User {} saved search {}", user_id, search.id);
+        Ok(search)
+    }
+
+    pub async fn update_saved_search(
+        &self,
+        user_id: Uuid,
+        search_id: Uuid,
+        request: SavedSearchRequest,
+    ) -> Result<SavedSearch, AppError> {
+        let request = self.prepare(request).await?;
+        SavedSearch::update(&self.db_pool, user_id, search_id, &request).await
+    }
+
+    pub async fn delete_saved_search(&self, user_id: Uuid, search_id: Uuid) -> Result<(), AppError> {
+        SavedSearch::delete(&self.db_pool, user_id, search_id).await?;
+        info!("User {} deleted saved search {}", user_id, search_id);
+        Ok(())
+    }
+
+    /// Queue alerts for saved searches with new matches and favorites nearly done
+    pub async fn run_alerts(&self) -> Result<FavoriteAlertReport, AppError> {
+        let report = FavoriteAlertReport {
+            saved_search_alerts: self.alert_saved_searches().await?,
+            favorite_alerts: self.alert_favorites().await?,
+        };
+
+        if report.saved_search_alerts > 0 || report.favorite_alerts > 0 {
+            info!(
+                "Queued {} saved search alerts and {} favorite raffle alerts",
+                report.saved_search_alerts, report.favorite_alerts
+            );
+        }
+        Ok(report)
+    }
+
+    // Private helper methods
+
+    /// Validate a saved search and store its tags in canonical form
+    async fn prepare(&self, mut request: SavedSearchRequest) -> Result<SavedSearchRequest, AppError> {
+        request.validate().map_err(validation_errors_to_app_error)?;
+        request.filter.check()?;
+        request.filter.query = request.filter.query.map(|q| q.trim().to_string()).filter(|q| !q.is_empty());
+        request.filter.tags = Tag::canonicalize(&self.db_pool, &request.filter.tags).await?;
+        Ok(request)
+    }
+
+    /// Announce open raffles created since each search was saved that it hasn't alerted on yet
+    async fn alert_saved_searches(&self) -> Result<u64, AppError> {
+        let searches = SavedSearch::due_for_alerts(&self.db_pool, SEARCH_BATCH_SIZE).await?;
+
+        let mut alerts = 0;
+        for search in searches {
+            let filter = &search.filter;
+            let tsquery = filter.query.as_deref().and_then(prefix_tsquery);
+            let tag_match = if filter.match_all_tags { "@>" } else { "&&" };
+
+            let mut tx = self.db_pool.begin().await?;
+            let matches = sqlx::query_as::<_, (Uuid, String)>(&format!(
+                r#"
+                WITH matched AS (
+                    SELECT l.raffle_id, l.item_name, l.created_at
+                    FROM raffle_listings l
+                    WHERE l.status = 'open' AND l.created_at > $2
+                      AND ($3::numeric IS NULL OR l.box_price >= $3)
+                      AND ($4::numeric IS NULL OR l.box_price <= $4)
+                      AND (cardinality($5::text[]) = 0 OR l.tags {} $5)
+                      AND ($6::text IS NULL OR l.search_vector @@ to_tsquery('english', $6))
+                      AND NOT EXISTS (
+                          SELECT 1 FROM saved_search_matches m
+                          WHERE m.saved_search_id = $1 AND m.raffle_id = l.raffle_id
+                      )
+                    ORDER BY l.created_at
+                    LIMIT $7
+                ),
+                inserted AS (
+                    INSERT INTO saved_search_matches (saved_search_id, raffle_id)
+                    SELECT $1, raffle_id FROM matched
+                    ON CONFLICT DO NOTHING
+                    RETURNING raffle_id
+                )
+                SELECT matched.raffle_id, matched.item_name
+                FROM matched JOIN inserted ON inserted.raffle_id = matched.raffle_id
+                ORDER BY matched.created_at
+                "#,
+                tag_match
+            ))
+            .bind(search.id)
+            .bind(search.created_at)
+            .bind(filter.min_price)
+            .bind(filter.max_price)
+            .bind(&filter.tags)
+            .bind(tsquery)
+            .bind(MAX_MATCHES_PER_ALERT)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            sqlx::query("UPDATE saved_searches SET last_checked_at = NOW() WHERE id = $1")
+                .bind(search.id)
+                .execute(&mut *tx)
+                .await?;
+
+            if !matches.is_empty() {
+                let (raffle_ids, item_titles) = matches.into_iter().unzip();
+                OutboxMessage::enqueue(
+                    &mut tx,
+                    &[OutboxMessage::SavedSearchAlert {
+                        user_id: search.user_id,
+                        saved_search_id: search.id,
+                        search_name: search.name.clone(),
+                        raffle_ids,
+                        item_titles,
+                    }],
+                )
+                .await?;
+                alerts += 1;
+            }
+            tx.commit().await?;
+        }
+
+        Ok(alerts)
+    }
+
+    /// Tell users once when a raffle they favorited is nearly sold out or about to end
+    async fn alert_favorites(&self) -> Result<u64, AppError> {
+        let percent = self.int_setting("favorite_near_completion_percent", DEFAULT_NEAR_COMPLETION_PERCENT).await;
+        let minutes = self.int_setting("favorite_ending_soon_minutes", DEFAULT_ENDING_SOON_MINUTES).await;
+
+        let mut tx = self.db_pool.begin().await?;
+        let favorites = sqlx::query_as::<_, NearlyDoneFavorite>(
+            r#"
+            UPDATE raffle_favorites f
+            SET near_completion_notified_at = NOW()
+            FROM raffles r
+            JOIN items i ON i.id = r.item_id
+            WHERE f.raffle_id = r.id AND f.near_completion_notified_at IS NULL
+              AND r.status = 'open' AND r.boxes_sold < r.total_boxes
+              AND (r.boxes_sold::BIGINT * 100 >= r.total_boxes::BIGINT * $1
+                   OR (r.ends_at > NOW() AND r.ends_at <= NOW() + make_interval(mins => $2)))
+            RETURNING f.user_id, f.raffle_id, i.name AS item_title,
+                      r.total_boxes - r.boxes_sold AS boxes_remaining, r.ends_at
+            "#,
+        )
+        .bind(percent)
+        .bind(minutes as i32)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let messages: Vec<OutboxMessage> = favorites
+            .iter()
+            .map(|favorite| OutboxMessage::FavoriteRaffleAlert {
+                user_id: favorite.user_id,
+                raffle_id: favorite.raffle_id,
+                item_title: favorite.item_title.clone(),
+                boxes_remaining: favorite.boxes_remaining,
+                ends_at: favorite.ends_at,
+            })
+            .collect();
+        OutboxMessage::enqueue(&mut tx, &messages).await?;
+        tx.commit().await?;
+
+        Ok(favorites.len() as u64)
+    }
+
+    async fn int_setting(&self, key: &str, default: i64) -> i64 {
+        SystemSetting::get_by_key(&self.db_pool, key)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default)
+    }
+}
@@ -0,0 +1,439 @@
+use crate::error::AppError;
+use crate::models::AuditLog;
+use crate::services::permission_service::parse_role;
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::{AuditAction, UserRole};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeSet, HashSet};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Flags are re-read from the database this often, so changes reach every instance
+const CACHE_TTL: Duration = Duration::from_secs(30);
+const MAX_KEY_LENGTH: usize = 100;
+
+/// A feature that can be turned on for some users before everyone
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct FeatureFlag {
+    pub key: String,
+    pub description: Option<String>,
+    /// Off for everyone when false, whatever the targeting
+    pub enabled: bool,
+    /// Share of signed-in users, 0 to 100, the flag is on for
+    pub rollout_percentage: i16,
+    /// Roles the flag is always on for
+    pub target_roles: Vec<String>,
+    /// Users the flag is always on for
+    pub target_user_ids: Vec<Uuid>,
+    pub updated_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FeatureFlag {
+    /// Whether the flag is on for a caller; anonymous callers only get fully rolled out flags
+    pub fn is_enabled_for(&self, user: Option<(Uuid, UserRole)>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        if self.rollout_percentage >= 100 {
+            return true;
+        }
+
+        let Some((user_id, role)) = user else {
+            return false;
+        };
+
+        self.target_user_ids.contains(&user_id)
+            || self.target_roles.iter().any(|r| *r == role.to_string())
+            || rollout_bucket(&self.key, user_id) < self.rollout_percentage as u8
+    }
+}
+
+/// Which of a user's 100 buckets a flag falls in. Stable per user and flag, so a user stays
+/// in a rollout as it grows, and independent between flags.
+fn rollout_bucket(flag_key: &str, user_id: Uuid) -> u8 {
+    let digest = Sha256::digest(format!("{}:{}", flag_key, user_id).as_bytes());
+    let value = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+    (value % 100) as u8
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateFeatureFlagRequest {
+    pub key: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub rollout_percentage: i16,
+    #[serde(default)]
+    pub target_roles: Vec<String>,
+    #[serde(default)]
+    pub target_user_ids: Vec<Uuid>,
+}
+
+/// Changes to a flag; omitted fields are kept
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateFeatureFlagRequest {
+    pub description: Option<String>,
+    pub enabled: Option<bool>,
+    pub rollout_percentage: Option<i16>,
+    pub target_roles: Option<Vec<String>>,
+    pub target_user_ids: Option<Vec<Uuid>>,
+}
+
+/// Flags on for the caller of a request, evaluated once by `FeatureFlagMiddleware`
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct EnabledFeatures {
+    pub flags: BTreeSet<String>,
+}
+
+impl EnabledFeatures {
+    pub fn is_enabled(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+}
+
+struct CachedFlags {
+    loaded_at: Instant,
+    flags: Vec<FeatureFlag>,
+}
+
+/// Feature flag service decides which features each user sees
+#[derive(Clone)]
+pub struct FeatureFlagService {
+    db_pool: PgPool,
+    cache: Arc<RwLock<Option<CachedFlags>>>,
+}
+
+impl FeatureFlagService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self {
+            db_pool,
+            cache: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Flags on for a caller. If flags can't be loaded every flag is off rather than failing
+    /// the request.
+    pub async fn evaluate(&self, user: Option<(Uuid, UserRole)>) -> EnabledFeatures {
+        let flags = match self.flags().await {
+            Ok(flags) => flags,
+            Err(e) => {
+                warn!("Failed to load feature flags, treating all as off: {}", e);
+                return EnabledFeatures::default();
+            }
+        };
+
+        EnabledFeatures {
+            flags: flags
+                .iter()
+                .filter(|flag| flag.is_enabled_for(user))
+                .map(|flag| flag.key.clone())
+                .collect(),
+        }
+    }
+
+    /// Every flag, by key
+    pub async fn list(&self) -> Result<Vec<FeatureFlag>, AppError> {
+        let flags = sqlx::query_as::<_, FeatureFlag>("SELECT * FROM feature_flags ORDER BY key")
+            .fetch_all(&self.db_pool)
+            .await?;
+
+        Ok(flags)
+    }
+
+    pub async fn get(&self, key: &str) -> Result<FeatureFlag, AppError> {
+        sqlx::query_as::<_, FeatureFlag>("SELECT * FROM feature_flags WHERE key = $1")
+            .bind(key)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Unknown feature flag: {}", key)))
+    }
+
+    pub async fn create(
+        &self,
+        admin_id: Uuid,
+        request: CreateFeatureFlagRequest,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<FeatureFlag, AppError> {
+        let key = validate_key(&request.key)?;
+        validate_rollout(request.rollout_percentage)?;
+        let target_roles = normalize_roles(&request.target_roles)?;
+
+        let flag = sqlx::query_as::<_, FeatureFlag>(
+            r#"
+            INSERT INTO feature_flags (key, description, enabled, rollout_percentage, target_roles, target_user_ids, updated_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (key) DO NOTHING
+            RETURNING *
+            "#,
+        )
+        .bind(&key)
+        .bind(&request.description)
+        .bind(request.enabled)
+        .bind(request.rollout_percentage)
+        .bind(&target_roles)
+        .bind(dedup_users(&request.target_user_ids))
+        .bind(admin_id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::Conflict(format!("Feature flag {} already exists", key)))?;
+
+        self.audit(admin_id, None, Some(&flag), ip_address, user_agent).await;
+        *self.cache.write().await = None;
+
+        info!("Admin {} created feature flag {}", admin_id, flag.key);
+
+        Ok(flag)
+    }
+
+    pub async fn update(
+        &self,
+        admin_id: Uuid,
+        key: &str,
+        request: UpdateFeatureFlagRequest,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<FeatureFlag, AppError> {
+        let previous = self.get(key).await?;
+
+        let rollout_percentage = request.rollout_percentage.unwrap_or(previous.rollout_percentage);
+        validate_rollout(rollout_percentage)?;
+        let target_roles = match &request.target_roles {
+            Some(roles) => normalize_roles(roles)?,
+            None => previous.target_roles.clone(),
+        };
+        let target_user_ids = match &request.target_user_ids {
+            Some(users) => dedup_users(users),
+            None => previous.target_user_ids.clone(),
+        };
+
+        let flag = sqlx::query_as::<_, FeatureFlag>(
+            r#"
+            UPDATE feature_flags SET
+                description = $2,
+                enabled = $3,
+                rollout_percentage = $4,
+                target_roles = $5,
+                target_user_ids = $6,
+                updated_by = $7,
+                updated_at = NOW()
+            WHERE key = $1
+            RETURNING *
+            "#,
+        )
+        .bind(key)
+        .bind(request.description.or_else(|| previous.description.clone()))
+        .bind(request.enabled.unwrap_or(previous.enabled))
+        .bind(rollout_percentage)
+        .bind(&target_roles)
+        .bind(&target_user_ids)
+        .bind(admin_id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Unknown feature flag: {}", key)))?;
+
+        self.audit(admin_id, Some(&previous), Some(&flag), ip_address, user_agent).await;
+        *self.cache.write().await = None;
+
+        info!(
+            "Admin {} updated feature flag {} (enabled: {}, rollout: {}%)",
+            admin_id, flag.key, flag.enabled, flag.rollout_percentage
+        );
+
+        Ok(flag)
+    }
+
+    pub async fn delete(
+        &self,
+        admin_id: Uuid,
+        key: &str,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<(), AppError> {
+        let flag = sqlx::query_as::<_, FeatureFlag>("DELETE FROM feature_flags WHERE key = $1 RETURNING *")
+            .bind(key)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Unknown feature flag: {}", key)))?;
+
+        self.audit(admin_id, Some(&flag), None, ip_address, user_agent).await;
+        *self.cache.write().await = None;
+
+        info!("Admin {} deleted feature flag {}", admin_id, flag.key);
+
+        Ok(())
+    }
+
+    // Private helper methods
+
+    async fn flags(&self) -> Result<Vec<FeatureFlag>, AppError> {
+        {
+            let cache = self.cache.read().await;
+            if let Some(cached) = cache.as_ref().filter(|c| c.loaded_at.elapsed() < CACHE_TTL) {
+                return Ok(cached.flags.clone());
+            }
+        }
+
+        let flags = self.list().await?;
+        *self.cache.write().await = Some(CachedFlags {
+            loaded_at: Instant::now(),
+            flags: flags.clone(),
+        });
+
+        Ok(flags)
+    }
+
+    async fn audit(
+        &self,
+        admin_id: Uuid,
+        previous: Option<&FeatureFlag>,
+        current: Option<&FeatureFlag>,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) {
+        let key = current.or(previous).map(|f| f.key.clone()).unwrap_or_default();
+        let to_json = |flag: Option<&FeatureFlag>| flag.and_then(|f| serde_json::to_value(f).ok());
+
+        if let Err(e) = AuditLog::create(
+            &self.db_pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("feature_flag".to_string()),
+            None,
+            to_json(previous),
+            to_json(current),
+            ip_address,
+            user_agent,
+        )
+        .await
+        {
+            warn!("Failed to audit change to feature flag {}: {}", key, e);
+        }
+    }
+}
+
+/// Flag keys are lowercase snake_case
+fn validate_key(key: &str) -> Result<String, AppError> {
+    let key = key.trim();
+    let valid = !key.is_empty()
+        && key.len() <= MAX_KEY_LENGTH
+        && key.starts_with(|c: char| c.is_ascii_lowercase())
+        && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+
+    if !valid {
+        return Err(AppError::Validation(
+            "Feature flag keys must be lowercase letters, digits and underscores".to_string(),
+        ));
+    }
+
+    Ok(key.to_string())
+}
+
+fn validate_rollout(percentage: i16) -> Result<(), AppError> {
+    if !(0..=100).contains(&percentage) {
+        return Err(AppError::Validation(
+            "rollout_percentage must be between 0 and 100".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Role names as stored, deduplicated and sorted; unknown roles are rejected
+fn normalize_roles(roles: &[String]) -> Result<Vec<String>, AppError> {
+    let roles: BTreeSet<String> = roles
+        .iter()
+        .map(|role| parse_role(role).map(|r| r.to_string()))
+        .collect::<Result<_, _>>()?;
+    Ok(roles.into_iter().collect())
+}
+
+fn dedup_users(users: &[Uuid]) -> Vec<Uuid> {
+    let mut seen = HashSet::new();
+    users.iter().copied().filter(|id| seen.insert(*id)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(rollout_percentage: i16) -> FeatureFlag {
+        FeatureFlag {
+            key: "new_grid_ui".to_string(),
+            description: None,
+            enabled: true,
+            rollout_percentage,
+            target_roles: vec![],
+            target_user_ids: vec![],
+            updated_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_targeting_and_kill_switch() {
+        let user = Uuid::new_v4();
+        let mut flag = flag(0);
+        assert!(!flag.is_enabled_for(Some((user, UserRole::User))));
+
+        flag.target_user_ids = vec![user];
+        assert!(flag.is_enabled_for(Some((user, UserRole::User))));
+        assert!(!flag.is_enabled_for(Some((Uuid::new_v4(), UserRole::User))));
+
+        flag.target_roles = vec!["admin".to_string()];
+        assert!(flag.is_enabled_for(Some((Uuid::new_v4(), UserRole::Admin))));
+
+        flag.enabled = false;
+        assert!(!flag.is_enabled_for(Some((user, UserRole::Admin))));
+    }
+
+    #[test]
+    fn test_anonymous_callers_only_get_full_rollouts() {
+        assert!(!flag(99).is_enabled_for(None));
+        assert!(flag(100).is_enabled_for(None));
+    }
+
+    #[test]
+    fn test_rollout_is_stable_and_proportional() {
+        let users: Vec<Uuid> = (0..10_000).map(|_| Uuid::new_v4()).collect();
+
+        let five = flag(5);
+        let in_five: Vec<&Uuid> = users
+            .iter()
+            .filter(|u| five.is_enabled_for(Some((**u, UserRole::User))))
+            .collect();
+        assert!((350..=650).contains(&in_five.len()), "got {}", in_five.len());
+
+        // Growing the rollout keeps everyone who already had the flag
+        let twenty = flag(20);
+        assert!(in_five
+            .iter()
+            .all(|u| twenty.is_enabled_for(Some((**u, UserRole::User)))));
+
+        assert_eq!(rollout_bucket("new_grid_ui", users[0]), rollout_bucket("new_grid_ui", users[0]));
+    }
+
+    #[test]
+    fn test_validation() {
+        assert_eq!(validate_key(" new_grid_ui ").unwrap(), "new_grid_ui");
+        assert!(validate_key("New-Grid").is_err());
+        assert!(validate_key("1flag").is_err());
+        assert!(validate_rollout(101).is_err());
+        assert!(validate_rollout(-1).is_err());
+        assert_eq!(
+            normalize_roles(&["Admin".to_string(), "admin".to_string()]).unwrap(),
+            vec!["admin".to_string()]
+        );
+        assert!(normalize_roles(&["superuser".to_string()]).is_err());
+    }
+}
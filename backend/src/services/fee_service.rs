@@ -0,0 +1,392 @@
+use crate::error::AppError;
+use crate::models::promotion::{Promotion, PromotionKind};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Fee service resolves platform and creator fees from versioned fee schedules
+#[derive(Clone)]
+pub struct FeeService {
+    db_pool: PgPool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum FeeType {
+    Platform,
+    Creator,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, sqlx::Type, ToSchema)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum FeeScope {
+    Default,
+    Category,
+    Tier,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeeSchedule {
+    pub id: Uuid,
+    pub fee_type: FeeType,
+    pub scope: FeeScope,
+    pub scope_value: Option<String>,
+    pub percentage: Decimal,
+    pub fixed_amount: Decimal,
+    pub effective_from: DateTime<Utc>,
+    pub effective_to: Option<DateTime<Utc>>,
+    pub notes: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl FeeSchedule {
+    pub fn is_effective_at(&self, at: DateTime<Utc>) -> bool {
+        self.effective_from <= at && self.effective_to.map_or(true, |end| at < end)
+    }
+
+    /// Fee charged on a gross amount
+    pub fn apply(&self, gross: Decimal) -> Decimal {
+        (gross * self.percentage / Decimal::from(100) + self.fixed_amount).round_dp(2)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct NewFeeSchedule {
+    pub fee_type: FeeType,
+    pub scope: FeeScope,
+    pub scope_value: Option<String>,
+    pub percentage: Decimal,
+    pub fixed_amount: Option<Decimal>,
+    pub effective_from: DateTime<Utc>,
+    pub notes: Option<String>,
+}
+
+/// What a fee is being resolved for
+#[derive(Debug, Clone, Default)]
+pub struct FeeContext {
+    pub category: Option<String>,
+    pub subscription_tier_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeeLine {
+    pub fee_type: FeeType,
+    pub schedule_id: Option<Uuid>,
+    pub percentage: Decimal,
+    pub fixed_amount: Decimal,
+    pub amount: Decimal,
+    /// Fee discount promotion already taken off `amount`
+    #[serde(default)]
+    pub promotion_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FeeBreakdown {
+    pub gross_amount: Decimal,
+    pub fees: Vec<FeeLine>,
+    pub total_fees: Decimal,
+    pub net_to_seller: Decimal,
+    pub evaluated_at: DateTime<Utc>,
+}
+
+impl FeeBreakdown {
+    pub fn schedule_for(&self, fee_type: FeeType) -> Option<Uuid> {
+        self.fees.iter().find(|f| f.fee_type == fee_type).and_then(|f| f.schedule_id)
+    }
+
+    pub fn promotion_for(&self, fee_type: FeeType) -> Option<Uuid> {
+        self.fees.iter().find(|f| f.fee_type == fee_type).and_then(|f| f.promotion_id)
+    }
+}
+
+impl FeeService {
+    /// Create a new fee service
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+
+    /// Compute all fees on a gross amount as of a point in time
+    pub async fn calculate_fees(
+        &self,
+        gross_amount: Decimal,
+        context: &FeeContext,
+        at: DateTime<Utc>,
+    ) -> Result<FeeBreakdown, AppError> {
+        let mut fees = Vec::new();
+        // Discounts only ever reduce the platform's own fee, never the creator's
+        let discount = Promotion::active_at(&self.db_pool, PromotionKind::FeeDiscount, at).await?;
+
+        for fee_type in [FeeType::Platform, FeeType::Creator] {
+            let schedules = self.get_candidate_schedules(fee_type, at).await?;
+
+            if let Some(schedule) = select_schedule(&schedules, context, at) {
+                let promotion = discount.as_ref().filter(|_| fee_type == FeeType::Platform);
+                let amount = schedule.apply(gross_amount);
+
+                fees.push(FeeLine {
+                    fee_type,
+                    schedule_id: Some(schedule.id),
+                    percentage: schedule.percentage,
+                    fixed_amount: schedule.fixed_amount,
+                    amount: promotion.map_or(amount, |p| p.discount_fee(amount)),
+                    promotion_id: promotion.map(|p| p.id),
+                });
+            }
+        }
+
+        let total_fees: Decimal = fees.iter().map(|f| f.amount).sum();
+
+        Ok(FeeBreakdown {
+            gross_amount,
+            fees,
+            total_fees,
+            net_to_seller: gross_amount - total_fees,
+            evaluated_at: at,
+        })
+    }
+
+    /// Get a schedule by ID (used to re-price historical raffles exactly)
+    pub async fn get_schedule(&self, schedule_id: Uuid) -> Result<FeeSchedule, AppError> {
+        let schedule = sqlx::query_as!(
+            FeeSchedule,
+            r#"
+            SELECT id, fee_type as "fee_type: FeeType", scope as "scope: FeeScope", scope_value,
+                   percentage, fixed_amount, effective_from, effective_to, notes, created_by, created_at
+            FROM fee_schedules
+            WHERE id = $1
+            "#,
+            schedule_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Fee schedule not found".to_string()))?;
+
+        Ok(schedule)
+    }
+
+    /// Full schedule history, newest first
+    pub async fn get_schedule_history(&self, fee_type: Option<FeeType>) -> Result<Vec<FeeSchedule>, AppError> {
+        let schedules = sqlx::query_as!(
+            FeeSchedule,
+            r#"
+            SELECT id, fee_type as "fee_type: FeeType", scope as "scope: FeeScope", scope_value,
+                   percentage, fixed_amount, effective_from, effective_to, notes, created_by, created_at
+            FROM fee_schedules
+            WHERE ($1::varchar IS NULL OR fee_type = $1)
+            ORDER BY effective_from DESC, created_at DESC
+            "#,
+            fee_type as Option<FeeType>
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(schedules)
+    }
+
+    /// Add a new schedule version, closing the currently open version for the same scope
+    pub async fn create_schedule(
+        &self,
+        request: NewFeeSchedule,
+        created_by: Uuid,
+    ) -> Result<FeeSchedule, AppError> {
+        if request.percentage < Decimal::ZERO || request.percentage > Decimal::from(100) {
+            return Err(AppError::Validation("Fee percentage must be between 0 and 100".to_string()));
+        }
+
+        if request.fixed_amount.unwrap_or(Decimal::ZERO) < Decimal::ZERO {
+            return Err(AppError::Validation("Fixed fee cannot be negative".to_string()));
+        }
+
+        match (request.scope, &request.scope_value) {
+            (FeeScope::Default, Some(_)) => {
+                return Err(AppError::Validation("Default schedules cannot have a scope value".to_string()));
+            }
+            (FeeScope::Category | FeeScope::Tier, None) => {
+                return Err(AppError::Validation("Override schedules require a scope value".to_string()));
+            }
+            _ => {}
+        }
+
+        let mut tx = self.db_pool.begin().await?;
+
+        let later_version = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) FROM fee_schedules
+            WHERE fee_type = $1 AND scope = $2 AND scope_value IS NOT DISTINCT FROM $3
+            AND effective_from >= $4
+            "#,
+            request.fee_type as FeeType,
+            request.scope as FeeScope,
+            request.scope_value,
+            request.effective_from
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .unwrap_or(0);
+
+        if later_version > 0 {
+            return Err(AppError::Conflict(
+                "A schedule for this scope already starts on or after the requested date".to_string(),
+            ));
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE fee_schedules SET effective_to = $4
+            WHERE fee_type = $1 AND scope = $2 AND scope_value IS NOT DISTINCT FROM $3
+            AND (effective_to IS NULL OR effective_to > $4)
+            "#,
+            request.fee_type as FeeType,
+            request.scope as FeeScope,
+            request.scope_value,
+            request.effective_from
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let schedule = sqlx::query_as!(
+            FeeSchedule,
+            r#"
+            INSERT INTO fee_schedules (fee_type, scope, scope_value, percentage, fixed_amount, effective_from, notes, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, fee_type as "fee_type: FeeType", scope as "scope: FeeScope", scope_value,
+                      percentage, fixed_amount, effective_from, effective_to, notes, created_by, created_at
+            "#,
+            request.fee_type as FeeType,
+            request.scope as FeeScope,
+            request.scope_value,
+            request.percentage,
+            request.fixed_amount.unwrap_or(Decimal::ZERO),
+            request.effective_from,
+            request.notes,
+            created_by
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        info!(
+            "Created {:?} fee schedule {} ({:?} {:?}) at {}% effective {}",
+            schedule.fee_type, schedule.id, schedule.scope, schedule.scope_value,
+            schedule.percentage, schedule.effective_from
+        );
+
+        Ok(schedule)
+    }
+
+    async fn get_candidate_schedules(
+        &self,
+        fee_type: FeeType,
+        at: DateTime<Utc>,
+    ) -> Result<Vec<FeeSchedule>, AppError> {
+        let schedules = sqlx::query_as!(
+            FeeSchedule,
+            r#"
+            SELECT id, fee_type as "fee_type: FeeType", scope as "scope: FeeScope", scope_value,
+                   percentage, fixed_amount, effective_from, effective_to, notes, created_by, created_at
+            FROM fee_schedules
+            WHERE fee_type = $1 AND effective_from <= $2 AND (effective_to IS NULL OR effective_to > $2)
+            "#,
+            fee_type as FeeType,
+            at
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(schedules)
+    }
+}
+
+/// Pick the most specific schedule in effect: tier override, then category override, then default
+pub fn select_schedule<'a>(
+    schedules: &'a [FeeSchedule],
+    context: &FeeContext,
+    at: DateTime<Utc>,
+) -> Option<&'a FeeSchedule> {
+    let tier = context.subscription_tier_id.map(|id| id.to_string());
+
+    schedules
+        .iter()
+        .filter(|s| s.is_effective_at(at))
+        .filter(|s| match s.scope {
+            FeeScope::Default => true,
+            FeeScope::Category => s.scope_value.is_some() && s.scope_value == context.category,
+            FeeScope::Tier => s.scope_value.is_some() && s.scope_value == tier,
+        })
+        .max_by(|a, b| a.scope.cmp(&b.scope).then(a.effective_from.cmp(&b.effective_from)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn schedule(scope: FeeScope, scope_value: Option<&str>, percentage: i64, from: (i32, u32)) -> FeeSchedule {
+        FeeSchedule {
+            id: Uuid::new_v4(),
+            fee_type: FeeType::Platform,
+            scope,
+            scope_value: scope_value.map(|v| v.to_string()),
+            percentage: Decimal::from(percentage),
+            fixed_amount: Decimal::ZERO,
+            effective_from: Utc.with_ymd_and_hms(from.0, from.1, 1, 0, 0, 0).unwrap(),
+            effective_to: None,
+            notes: None,
+            created_by: None,
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn test_override_precedence() {
+        let tier_id = Uuid::new_v4();
+        let schedules = vec![
+            schedule(FeeScope::Default, None, 5, (2024, 1)),
+            schedule(FeeScope::Category, Some("electronics"), 7, (2024, 1)),
+            schedule(FeeScope::Tier, Some(&tier_id.to_string()), 3, (2024, 1)),
+        ];
+        let at = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+
+        let none = FeeContext::default();
+        assert_eq!(select_schedule(&schedules, &none, at).unwrap().percentage, Decimal::from(5));
+
+        let category = FeeContext { category: Some("electronics".to_string()), subscription_tier_id: None };
+        assert_eq!(select_schedule(&schedules, &category, at).unwrap().percentage, Decimal::from(7));
+
+        let both = FeeContext { category: Some("electronics".to_string()), subscription_tier_id: Some(tier_id) };
+        assert_eq!(select_schedule(&schedules, &both, at).unwrap().percentage, Decimal::from(3));
+    }
+
+    #[test]
+    fn test_effective_dates() {
+        let mut old = schedule(FeeScope::Default, None, 5, (2024, 1));
+        old.effective_to = Some(Utc.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap());
+        let new = schedule(FeeScope::Default, None, 6, (2024, 7));
+        let schedules = vec![old, new];
+        let context = FeeContext::default();
+
+        let before = Utc.with_ymd_and_hms(2024, 6, 30, 23, 59, 59).unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap();
+        let too_early = Utc.with_ymd_and_hms(2023, 12, 31, 0, 0, 0).unwrap();
+
+        assert_eq!(select_schedule(&schedules, &context, before).unwrap().percentage, Decimal::from(5));
+        assert_eq!(select_schedule(&schedules, &context, after).unwrap().percentage, Decimal::from(6));
+        assert!(select_schedule(&schedules, &context, too_early).is_none());
+    }
+
+    #[test]
+    fn test_apply_rounds_to_cents() {
+        let mut s = schedule(FeeScope::Default, None, 0, (2024, 1));
+        s.percentage = Decimal::new(250, 2); // 2.50%
+        s.fixed_amount = Decimal::new(30, 2); // $0.30
+        assert_eq!(s.apply(Decimal::new(3333, 2)), Decimal::new(113, 2)); // 0.833 + 0.30
+    }
+}
@@ -0,0 +1,745 @@
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use crate::services::compliance_service::ComplianceService;
+use crate::services::credit_service::CreditService;
+use crate::services::notification_service::NotificationService;
+use crate::services::shipping_service::ShippingService;
+use crate::services::tax_service::TaxService;
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use tracing::{error, info};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const MAX_DESCRIPTION_LENGTH: usize = 2000;
+const MAX_TRACKING_LENGTH: usize = 100;
+const DEFAULT_DISPUTE_WINDOW_DAYS: i64 = 14;
+
+/// Fulfillment service takes a won prize from the winner confirming where it goes, through
+/// the seller shipping it, to the winner confirming receipt or disputing it
+#[derive(Clone)]
+pub struct FulfillmentService {
+    db_pool: PgPool,
+    credit_service: CreditService,
+    shipping_service: ShippingService,
+    tax_service: TaxService,
+    notification_service: NotificationService,
+    compliance_service: Option<ComplianceService>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FulfillmentStatus {
+    /// The winner has confirmed their address; the seller has yet to ship
+    AwaitingShipment,
+    Shipped,
+    /// The winner confirmed the prize arrived
+    Received,
+    /// A dispute is open
+    Disputed,
+    /// A dispute was settled with a credit refund to the winner
+    Refunded,
+}
+
+impl FulfillmentStatus {
+    /// Whether the winner may open a dispute; received prizes only within the dispute window
+    pub fn can_dispute(self) -> bool {
+        matches!(
+            self,
+            FulfillmentStatus::AwaitingShipment | FulfillmentStatus::Shipped | FulfillmentStatus::Received
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeReason {
+    NotReceived,
+    Damaged,
+    NotAsDescribed,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DisputeStatus {
+    Open,
+    /// Settled with a credit refund to the winner
+    Refunded,
+    /// Closed in the seller's favour; the fulfillment goes back to where it was
+    Rejected,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DisputeDecision {
+    Refund,
+    Reject,
+}
+
+/// Shipping of one winner's prizes from one raffle
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Fulfillment {
+    pub id: Uuid,
+    pub raffle_id: Uuid,
+    pub winner_id: Uuid,
+    pub seller_id: Option<Uuid>,
+    pub status: FulfillmentStatus,
+    /// Retail value of the item prizes being shipped
+    pub prize_value: Decimal,
+    pub recipient_name: String,
+    pub line1: String,
+    pub line2: Option<String>,
+    pub city: String,
+    pub region_code: Option<String>,
+    pub postal_code: Option<String>,
+    pub country_code: String,
+    pub phone: Option<String>,
+    pub carrier: Option<String>,
+    pub tracking_number: Option<String>,
+    pub details_confirmed_at: DateTime<Utc>,
+    pub shipped_at: Option<DateTime<Utc>>,
+    pub received_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Dispute {
+    pub id: Uuid,
+    pub fulfillment_id: Uuid,
+    pub opened_by: Uuid,
+    pub reason: DisputeReason,
+    pub description: Option<String>,
+    pub status: DisputeStatus,
+    /// Fulfillment status when the dispute was opened
+    pub fulfillment_status: FulfillmentStatus,
+    pub refund_amount: Option<Decimal>,
+    pub resolution_note: Option<String>,
+    pub resolved_by: Option<Uuid>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// A fulfillment with every dispute opened on it, newest first
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FulfillmentDetails {
+    #[serde(flatten)]
+    pub fulfillment: Fulfillment,
+    pub disputes: Vec<Dispute>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ShipPrizeRequest {
+    pub carrier: String,
+    pub tracking_number: String,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct OpenDisputeRequest {
+    pub reason: DisputeReason,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ResolveDisputeRequest {
+    pub decision: DisputeDecision,
+    /// Credits refunded to the winner; defaults to the prize value, which is also the most allowed
+    pub refund_amount: Option<Decimal>,
+    pub note: Option<String>,
+}
+
+const COLUMNS: &str = "id, raffle_id, winner_id, seller_id, status, prize_value, recipient_name, line1, line2, \
+    city, region_code, postal_code, country_code, phone, carrier, tracking_number, details_confirmed_at, \
+    shipped_at, received_at, created_at";
+
+const DISPUTE_COLUMNS: &str = "id, fulfillment_id, opened_by, reason, description, status, fulfillment_status, \
+    refund_amount, resolution_note, resolved_by, resolved_at, created_at";
+
+impl FulfillmentService {
+    /// Create a new fulfillment service
+    pub fn new(
+        db_pool: PgPool,
+        credit_service: CreditService,
+        shipping_service: ShippingService,
+        tax_service: TaxService,
+        notification_service: NotificationService,
+    ) -> Self {
+        Self {
+            db_pool,
+            credit_service,
+            shipping_service,
+            tax_service,
+            notification_service,
+            compliance_service: None,
+        }
+    }
+
+    /// Hold valuable prizes until the winner has verified their identity
+    pub fn with_compliance_service(mut self, compliance_service: ComplianceService) -> Self {
+        self.compliance_service = Some(compliance_service);
+        self
+    }
+
+    /// Confirm the winner's saved address as where the prize goes. Can be repeated to
+    /// change the address until the prize ships.
+    pub async fn confirm_details(&self, raffle_id: Uuid, winner_id: Uuid) -> Result<Fulfillment, AppError> {
+        let check = self.shipping_service.check_fulfillment(raffle_id, winner_id).await?;
+        if !check.needs_shipping {
+            return Err(AppError::Validation("Your prize was taken as credits and has nothing to ship".to_string()));
+        }
+        self.shipping_service.ensure_address_served(raffle_id, winner_id).await?;
+
+        let address = self
+            .shipping_service
+            .get_address(winner_id)
+            .await?
+            .ok_or_else(|| AppError::Conflict("Add a shipping address first".to_string()))?;
+
+        let fulfillment = sqlx::query_as::<_, Fulfillment>(&format!(
+            r#"
+            INSERT INTO fulfillments
+                (raffle_id, winner_id, seller_id, prize_value, recipient_name, line1, line2, city,
+                 region_code, postal_code, country_code, phone)
+            SELECT r.id, $2, s.user_id,
+                   COALESCE(
+                       (SELECT SUM(pi.retail_price) FROM raffle_prize_tiers t
+                        JOIN items pi ON pi.id = t.item_id
+                        WHERE t.raffle_id = r.id AND t.winner_user_id = $2 AND t.prize_type = 'item'
+                        AND t.winner_choice IS DISTINCT FROM 'credits'),
+                       i.retail_price
+                   ),
+                   $3, $4, $5, $6, $7, $8, $9, $10
+            FROM raffles r
+            JOIN items i ON i.id = r.item_id
+            LEFT JOIN sellers s ON s.id = i.seller_id
+            WHERE r.id = $1
+            ON CONFLICT (raffle_id, winner_id) DO UPDATE SET
+                recipient_name = EXCLUDED.recipient_name,
+                line1 = EXCLUDED.line1,
+                line2 = EXCLUDED.line2,
+                city = EXCLUDED.city,
+                region_code = EXCLUDED.region_code,
+                postal_code = EXCLUDED.postal_code,
+                country_code = EXCLUDED.country_code,
+                phone = EXCLUDED.phone,
+                details_confirmed_at = NOW()
+            WHERE fulfillments.status = 'awaiting_shipment'
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(raffle_id)
+        .bind(winner_id)
+        .bind(&address.recipient_name)
+        .bind(&address.line1)
+        .bind(&address.line2)
+        .bind(&address.city)
+        .bind(&address.region_code)
+        .bind(&address.postal_code)
+        .bind(&address.country_code)
+        .bind(&address.phone)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::Conflict("The prize has already shipped; its address can no longer change".to_string()))?;
+
+        info!("Winner {} confirmed shipping details for raffle {}", winner_id, raffle_id);
+
+        if let Some(seller_id) = fulfillment.seller_id {
+            self.notify(
+                seller_id,
+                "A prize is ready to ship",
+                "A winner confirmed where to send their prize. Ship it and add the tracking number.".to_string(),
+                &fulfillment,
+                "details_confirmed",
+            )
+            .await;
+        }
+
+        Ok(fulfillment)
+    }
+
+    /// Prizes the user has won and confirmed shipping for, newest first
+    pub async fn list_for_winner(&self, winner_id: Uuid) -> Result<Vec<Fulfillment>, AppError> {
+        let fulfillments = sqlx::query_as::<_, Fulfillment>(&format!(
+            "SELECT {} FROM fulfillments WHERE winner_id = $1 ORDER BY created_at DESC",
+            COLUMNS
+        ))
+        .bind(winner_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(fulfillments)
+    }
+
+    /// Prizes the seller has to ship or has shipped, oldest first
+    pub async fn list_for_seller(
+        &self,
+        seller_id: Uuid,
+        status: Option<FulfillmentStatus>,
+        limit: i64,
+    ) -> Result<Vec<Fulfillment>, AppError> {
+        let fulfillments = sqlx::query_as::<_, Fulfillment>(&format!(
+            r#"
+            SELECT {} FROM fulfillments
+            WHERE seller_id = $1 AND ($2::varchar IS NULL OR status = $2)
+            ORDER BY created_at ASC
+            LIMIT $3
+            "#,
+            COLUMNS
+        ))
+        .bind(seller_id)
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(fulfillments)
+    }
+
+    /// A fulfillment and its disputes, for its winner, its seller or an admin
+    pub async fn get_details(
+        &self,
+        fulfillment_id: Uuid,
+        viewer_id: Uuid,
+        is_admin: bool,
+    ) -> Result<FulfillmentDetails, AppError> {
+        let fulfillment = self.get_fulfillment(fulfillment_id).await?;
+        if !is_admin && fulfillment.winner_id != viewer_id && fulfillment.seller_id != Some(viewer_id) {
+            return Err(AppError::NotFound("Fulfillment not found".to_string()));
+        }
+
+        let disputes = sqlx::query_as::<_, Dispute>(&format!(
+            "SELECT {} FROM disputes WHERE fulfillment_id = $1 ORDER BY created_at DESC",
+            DISPUTE_COLUMNS
+        ))
+        .bind(fulfillment_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(FulfillmentDetails { fulfillment, disputes })
+    }
+
+    /// Record that the seller shipped the prize; waits on the winner's tax form and, for valuable
+    /// prizes, their identity verification
+    pub async fn mark_shipped(
+        &self,
+        fulfillment_id: Uuid,
+        seller_id: Uuid,
+        request: ShipPrizeRequest,
+    ) -> Result<Fulfillment, AppError> {
+        let carrier = required_text("carrier", &request.carrier, MAX_TRACKING_LENGTH)?;
+        let tracking_number = required_text("tracking_number", &request.tracking_number, MAX_TRACKING_LENGTH)?;
+
+        let current = self.get_fulfillment(fulfillment_id).await?;
+        if current.seller_id != Some(seller_id) {
+            return Err(AppError::NotFound("Fulfillment not found".to_string()));
+        }
+        self.tax_service
+            .ensure_fulfillment_allowed(current.raffle_id, current.winner_id)
+            .await?;
+        if let Some(compliance_service) = &self.compliance_service {
+            compliance_service
+                .ensure_fulfillment_allowed(current.winner_id, current.prize_value)
+                .await?;
+        }
+
+        let fulfillment = sqlx::query_as::<_, Fulfillment>(&format!(
+            r#"
+            UPDATE fulfillments
+            SET status = 'shipped', carrier = $2, tracking_number = $3, shipped_at = NOW()
+            WHERE id = $1 AND status = 'awaiting_shipment'
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(fulfillment_id)
+        .bind(carrier)
+        .bind(tracking_number)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::Conflict(format!("Only prizes awaiting shipment can be shipped; this one is {:?}", current.status)))?;
+
+        info!("Seller {} shipped fulfillment {}", seller_id, fulfillment_id);
+
+        self.notify(
+            fulfillment.winner_id,
+            "Your prize has shipped",
+            format!(
+                "Your prize is on its way with {} (tracking number {}). Confirm receipt once it arrives.",
+                carrier, tracking_number
+            ),
+            &fulfillment,
+            "shipped",
+        )
+        .await;
+
+        Ok(fulfillment)
+    }
+
+    /// The winner confirms the prize arrived
+    pub async fn confirm_receipt(&self, fulfillment_id: Uuid, winner_id: Uuid) -> Result<Fulfillment, AppError> {
+        let fulfillment = sqlx::query_as::<_, Fulfillment>(&format!(
+            r#"
+            UPDATE fulfillments
+            SET status = 'received', received_at = NOW()
+            WHERE id = $1 AND winner_id = $2 AND status = 'shipped'
+            RETURNING {}
+            "#,
+            COLUMNS
+        ))
+        .bind(fulfillment_id)
+        .bind(winner_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        let Some(fulfillment) = fulfillment else {
+            let current = self.get_fulfillment(fulfillment_id).await?;
+            if current.winner_id != winner_id {
+                return Err(AppError::NotFound("Fulfillment not found".to_string()));
+            }
+            return Err(AppError::Conflict(format!(
+                "Only shipped prizes can be confirmed as received; this one is {:?}",
+                current.status
+            )));
+        };
+
+        info!("Winner {} confirmed receipt of fulfillment {}", winner_id, fulfillment_id);
+
+        if let Some(seller_id) = fulfillment.seller_id {
+            self.notify(
+                seller_id,
+                "Prize delivered",
+                "The winner confirmed their prize arrived.".to_string(),
+                &fulfillment,
+                "received",
+            )
+            .await;
+        }
+
+        Ok(fulfillment)
+    }
+
+    /// The winner disputes a prize that hasn't shipped, hasn't arrived, or arrived recently
+    pub async fn open_dispute(
+        &self,
+        fulfillment_id: Uuid,
+        winner_id: Uuid,
+        request: OpenDisputeRequest,
+    ) -> Result<Dispute, AppError> {
+        let description = request
+            .description
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty());
+        if description.as_ref().map_or(false, |d| d.chars().count() > MAX_DESCRIPTION_LENGTH) {
+            return Err(AppError::Validation(format!(
+                "Description cannot exceed {} characters",
+                MAX_DESCRIPTION_LENGTH
+            )));
+        }
+        if request.reason == DisputeReason::Other && description.is_none() {
+            return Err(AppError::Validation("Describe the problem when disputing for another reason".to_string()));
+        }
+
+        let current = self.get_fulfillment(fulfillment_id).await?;
+        if current.winner_id != winner_id {
+            return Err(AppError::NotFound("Fulfillment not found".to_string()));
+        }
+        if !current.status.can_dispute() {
+            return Err(AppError::Conflict(format!("A {:?} prize cannot be disputed", current.status)));
+        }
+        let window_days = self.dispute_window_days().await?;
+        if !within_dispute_window(current.received_at, Utc::now(), window_days) {
+            return Err(AppError::Conflict(format!(
+                "Received prizes can only be disputed within {} days",
+                window_days
+            )));
+        }
+
+        let mut tx = self.db_pool.begin().await?;
+
+        // Only move on from the status checked above, so racing changes can't be overwritten
+        let moved = sqlx::query("UPDATE fulfillments SET status = 'disputed' WHERE id = $1 AND status = $2")
+            .bind(fulfillment_id)
+            .bind(current.status)
+            .execute(&mut *tx)
+            .await?;
+        if moved.rows_affected() == 0 {
+            return Err(AppError::Conflict("The prize changed while the dispute was being opened; try again".to_string()));
+        }
+
+        let dispute = sqlx::query_as::<_, Dispute>(&format!(
+            r#"
+            INSERT INTO disputes (fulfillment_id, opened_by, reason, description, fulfillment_status)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING {}
+            "#,
+            DISPUTE_COLUMNS
+        ))
+        .bind(fulfillment_id)
+        .bind(winner_id)
+        .bind(request.reason)
+        .bind(description)
+        .bind(current.status)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        info!(
+            "Winner {} opened dispute {} on fulfillment {} ({:?})",
+            winner_id, dispute.id, fulfillment_id, dispute.reason
+        );
+
+        if let Some(seller_id) = current.seller_id {
+            self.notify(
+                seller_id,
+                "A winner opened a dispute",
+                format!(
+                    "A winner reported a problem with their prize ({}). Our team will review it.",
+                    reason_label(dispute.reason)
+                ),
+                &current,
+                "dispute_opened",
+            )
+            .await;
+        }
+
+        Ok(dispute)
+    }
+
+    /// Disputes for admins, oldest first; by default the open ones
+    pub async fn list_disputes(&self, status: Option<DisputeStatus>, limit: i64) -> Result<Vec<Dispute>, AppError> {
+        let disputes = sqlx::query_as::<_, Dispute>(&format!(
+            r#"
+            SELECT {} FROM disputes
+            WHERE status = COALESCE($1, 'open')
+            ORDER BY created_at ASC
+            LIMIT $2
+            "#,
+            DISPUTE_COLUMNS
+        ))
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(disputes)
+    }
+
+    /// Settle a dispute (admin): refund the winner in credits, or reject it and put the
+    /// fulfillment back where it was
+    pub async fn resolve_dispute(
+        &self,
+        dispute_id: Uuid,
+        admin_id: Uuid,
+        request: ResolveDisputeRequest,
+    ) -> Result<Dispute, AppError> {
+        let dispute = self.get_dispute(dispute_id).await?;
+        if dispute.status != DisputeStatus::Open {
+            return Err(AppError::Conflict("Dispute is already resolved".to_string()));
+        }
+        let fulfillment = self.get_fulfillment(dispute.fulfillment_id).await?;
+        let note = request.note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+
+        let (status, refund, fulfillment_status) = match request.decision {
+            DisputeDecision::Refund => (
+                DisputeStatus::Refunded,
+                Some(refund_amount(request.refund_amount, fulfillment.prize_value)?),
+                FulfillmentStatus::Refunded,
+            ),
+            DisputeDecision::Reject => (DisputeStatus::Rejected, None, dispute.fulfillment_status),
+        };
+
+        let mut tx = self.db_pool.begin().await?;
+
+        // Claiming the open dispute first means it can only ever be refunded once
+        let resolved = sqlx::query_as::<_, Dispute>(&format!(
+            r#"
+            UPDATE disputes
+            SET status = $2, refund_amount = $3, resolution_note = $4, resolved_by = $5, resolved_at = NOW()
+            WHERE id = $1 AND status = 'open'
+            RETURNING {}
+            "#,
+            DISPUTE_COLUMNS
+        ))
+        .bind(dispute_id)
+        .bind(status)
+        .bind(refund)
+        .bind(&note)
+        .bind(admin_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Dispute is already resolved".to_string()))?;
+
+        sqlx::query("UPDATE fulfillments SET status = $2 WHERE id = $1")
+            .bind(fulfillment.id)
+            .bind(fulfillment_status)
+            .execute(&mut *tx)
+            .await?;
+
+        if let Some(amount) = refund {
+            self.credit_service
+                .issue_refund_credits(
+                    fulfillment.winner_id,
+                    amount,
+                    None,
+                    format!("Refund for disputed prize (raffle_id: {})", fulfillment.raffle_id),
+                )
+                .await?;
+        }
+
+        if let Err(e) = tx.commit().await {
+            error!("Dispute {} refund was issued but the resolution failed to save: {}", dispute_id, e);
+            return Err(e.into());
+        }
+
+        info!(
+            "Admin {} resolved dispute {} as {:?} (refund: {:?})",
+            admin_id, dispute_id, resolved.status, resolved.refund_amount
+        );
+
+        let (winner_message, seller_message) = match refund {
+            Some(amount) => (
+                format!("We resolved your dispute and refunded {} credits to your account.", amount),
+                format!("A dispute on a prize you shipped was resolved with a {} credit refund to the winner.", amount),
+            ),
+            None => (
+                "We reviewed your dispute and found the prize was fulfilled as promised.".to_string(),
+                "A dispute on a prize you shipped was resolved in your favour.".to_string(),
+            ),
+        };
+        self.notify(fulfillment.winner_id, "Your dispute was resolved", winner_message, &fulfillment, "dispute_resolved")
+            .await;
+        if let Some(seller_id) = fulfillment.seller_id {
+            self.notify(seller_id, "Dispute resolved", seller_message, &fulfillment, "dispute_resolved")
+                .await;
+        }
+
+        Ok(resolved)
+    }
+
+    // Private helper methods
+
+    async fn get_fulfillment(&self, fulfillment_id: Uuid) -> Result<Fulfillment, AppError> {
+        sqlx::query_as::<_, Fulfillment>(&format!("SELECT {} FROM fulfillments WHERE id = $1", COLUMNS))
+            .bind(fulfillment_id)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Fulfillment not found".to_string()))
+    }
+
+    async fn get_dispute(&self, dispute_id: Uuid) -> Result<Dispute, AppError> {
+        sqlx::query_as::<_, Dispute>(&format!("SELECT {} FROM disputes WHERE id = $1", DISPUTE_COLUMNS))
+            .bind(dispute_id)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Dispute not found".to_string()))
+    }
+
+    async fn dispute_window_days(&self) -> Result<i64, AppError> {
+        Ok(SystemSetting::get_by_key(&self.db_pool, "fulfillment_dispute_window_days")
+            .await?
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_DISPUTE_WINDOW_DAYS))
+    }
+
+    /// Queue a fulfillment update for the user; the change stands if the notice fails
+    async fn notify(&self, user_id: Uuid, title: &str, message: String, fulfillment: &Fulfillment, event: &str) {
+        self.notification_service
+            .send_fulfillment_notification(
+                user_id,
+                title.to_string(),
+                message,
+                serde_json::json!({
+                    "event": event,
+                    "fulfillment_id": fulfillment.id,
+                    "raffle_id": fulfillment.raffle_id
+                }),
+            )
+            .await;
+    }
+}
+
+/// Whether a dispute can still be opened; prizes not yet received can always be disputed
+pub fn within_dispute_window(received_at: Option<DateTime<Utc>>, now: DateTime<Utc>, window_days: i64) -> bool {
+    received_at.map_or(true, |received_at| now <= received_at + Duration::days(window_days))
+}
+
+/// Refund for a dispute: the requested amount, or the whole prize value
+pub fn refund_amount(requested: Option<Decimal>, prize_value: Decimal) -> Result<Decimal, AppError> {
+    match requested {
+        None if prize_value > Decimal::ZERO => Ok(prize_value),
+        None => Err(AppError::Validation("The prize has no value; give a refund amount".to_string())),
+        Some(amount) if amount <= Decimal::ZERO => Err(AppError::Validation("Refund amount must be positive".to_string())),
+        Some(amount) if amount > prize_value => Err(AppError::Validation(format!(
+            "Refund cannot exceed the prize value of {}",
+            prize_value
+        ))),
+        Some(amount) => Ok(amount),
+    }
+}
+
+fn required_text<'a>(field: &str, value: &'a str, max_length: usize) -> Result<&'a str, AppError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(AppError::Validation(format!("{} is required", field)));
+    }
+    if value.chars().count() > max_length {
+        return Err(AppError::Validation(format!("{} cannot exceed {} characters", field, max_length)));
+    }
+    Ok(value)
+}
+
+fn reason_label(reason: DisputeReason) -> &'static str {
+    match reason {
+        DisputeReason::NotReceived => "not received",
+        DisputeReason::Damaged => "damaged",
+        DisputeReason::NotAsDescribed => "not as described",
+        DisputeReason::Other => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_dispute() {
+        assert!(FulfillmentStatus::AwaitingShipment.can_dispute());
+        assert!(FulfillmentStatus::Shipped.can_dispute());
+        assert!(FulfillmentStatus::Received.can_dispute());
+        assert!(!FulfillmentStatus::Disputed.can_dispute());
+        assert!(!FulfillmentStatus::Refunded.can_dispute());
+    }
+
+    #[test]
+    fn test_within_dispute_window() {
+        let now = Utc::now();
+        assert!(within_dispute_window(None, now, 14));
+        assert!(within_dispute_window(Some(now - Duration::days(14)), now, 14));
+        assert!(!within_dispute_window(Some(now - Duration::days(15)), now, 14));
+    }
+
+    #[test]
+    fn test_refund_amount() {
+        let value = Decimal::from(80);
+        assert_eq!(refund_amount(None, value).unwrap(), value);
+        assert_eq!(refund_amount(Some(Decimal::from(30)), value).unwrap(), Decimal::from(30));
+        assert!(refund_amount(Some(Decimal::ZERO), value).is_err());
+        assert!(refund_amount(Some(Decimal::from(81)), value).is_err());
+        assert!(refund_amount(None, Decimal::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_required_text() {
+        assert_eq!(required_text("carrier", "  UPS ", 100).unwrap(), "UPS");
+        assert!(required_text("carrier", "   ", 100).is_err());
+        assert!(required_text("tracking_number", &"1".repeat(101), 100).is_err());
+    }
+}
@@ -0,0 +1,547 @@
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use crate::models::user::User;
+use chrono::{DateTime, Duration, Utc};
+use raffle_platform_shared::{DisplayAmount, RaffleResponse};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+pub const DEFAULT_BASE_CURRENCY: &str = "USD";
+const DEFAULT_REFRESH_INTERVAL_MINUTES: i64 = 60;
+const DEFAULT_MAX_RATE_AGE_HOURS: i64 = 24;
+
+pub type FxRatesFuture<'a> = Pin<Box<dyn Future<Output = Result<HashMap<String, Decimal>, AppError>> + Send + 'a>>;
+
+/// Source of exchange rates quoted against a base currency
+pub trait FxRateProvider: Send + Sync {
+    /// Recorded with each stored rate
+    fn name(&self) -> &'static str;
+
+    /// Units of each quote currency per one unit of `base`
+    fn fetch_rates<'a>(&'a self, base: &'a str) -> FxRatesFuture<'a>;
+}
+
+/// Rate feed answering `GET <url>?base=USD` with `{"rates": {"EUR": 0.92, ...}}`
+pub struct HttpFxRateProvider {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RatesPayload {
+    rates: HashMap<String, Decimal>,
+}
+
+impl HttpFxRateProvider {
+    pub fn new(url: String, api_key: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            api_key,
+        }
+    }
+}
+
+impl FxRateProvider for HttpFxRateProvider {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    fn fetch_rates<'a>(&'a self, base: &'a str) -> FxRatesFuture<'a> {
+        Box::pin(async move {
+            let mut request = self.client.get(&self.url).query(&[("base", base)]);
+            if let Some(api_key) = &self.api_key {
+                request = request.bearer_auth(api_key);
+            }
+
+            let response = request
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(format!("Rate feed request failed: {}", e)))?;
+
+            if !response.status().is_success() {
+                return Err(AppError::Internal(format!("Rate feed returned {}", response.status())));
+            }
+
+            let payload: RatesPayload = response
+                .json()
+                .await
+                .map_err(|e| AppError::Internal(format!("Invalid rate feed response: {}", e)))?;
+
+            Ok(payload.rates)
+        })
+    }
+}
+
+/// Stored exchange rate from the settlement currency to a display currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FxRate {
+    pub id: Uuid,
+    pub base_currency: String,
+    pub quote_currency: String,
+    pub rate: Decimal,
+    pub provider: String,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// Conversion stored with a payment so history shows the rate the user saw
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FxSnapshot {
+    pub display_currency: String,
+    pub display_amount: Decimal,
+    pub rate: Decimal,
+    pub rate_id: Uuid,
+}
+
+/// FX service converts prices for display and listing-currency charges into the base currency.
+///
+/// Listings may be priced in any enabled currency, but credits and charges always settle in the
+/// base currency; nothing is ever charged in a display currency.
+#[derive(Clone)]
+pub struct FxService {
+    db_pool: PgPool,
+    provider: Option<Arc<dyn FxRateProvider>>,
+    base_currency: String,
+    cache: Arc<RwLock<HashMap<String, FxRate>>>,
+}
+
+impl FxService {
+    /// Create a new FX service; without a provider only rates already stored are used
+    pub fn new(db_pool: PgPool, provider: Option<Arc<dyn FxRateProvider>>, base_currency: Option<String>) -> Self {
+        let base_currency = base_currency
+            .and_then(|c| normalize_currency(&c).ok())
+            .unwrap_or_else(|| DEFAULT_BASE_CURRENCY.to_string());
+
+        Self {
+            db_pool,
+            provider,
+            base_currency,
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn from_config(db_pool: PgPool, config: &AppConfig) -> Self {
+        let provider = config.fx_rates_url.clone().map(|url| {
+            Arc::new(HttpFxRateProvider::new(url, config.fx_rates_api_key.clone())) as Arc<dyn FxRateProvider>
+        });
+
+        Self::new(db_pool, provider, config.fx_base_currency.clone())
+    }
+
+    /// Currency every price is stored and charged in
+    pub fn base_currency(&self) -> &str {
+        &self.base_currency
+    }
+
+    /// Start the periodic rate refresh
+    pub async fn start_background_tasks(&self) {
+        if self.provider.is_none() {
+            info!("No FX rate feed configured; prices are shown in {} only", self.base_currency);
+            return;
+        }
+
+        let service = self.clone();
+        let interval_minutes = self
+            .get_setting("fx_refresh_interval_minutes", DEFAULT_REFRESH_INTERVAL_MINUTES)
+            .await
+            .unwrap_or(DEFAULT_REFRESH_INTERVAL_MINUTES);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_minutes as u64 * 60));
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.refresh_rates().await {
+                    error!("Failed to refresh FX rates: {}", e);
+                }
+            }
+        });
+
+        info!("FX service background tasks started");
+    }
+
+    /// Fetch and store the latest rates from the feed; returns how many were stored
+    pub async fn refresh_rates(&self) -> Result<usize, AppError> {
+        let provider = self.provider.as_ref()
+            .ok_or_else(|| AppError::Validation("No FX rate feed is configured".to_string()))?;
+
+        let rates = provider.fetch_rates(&self.base_currency).await?;
+
+        let mut stored = Vec::new();
+        for (quote, rate) in rates {
+            let quote = match normalize_currency(&quote) {
+                Ok(quote) if quote != self.base_currency && rate > Decimal::ZERO => quote,
+                _ => {
+                    debug!("Skipping FX rate {} = {} from {}", quote, rate, provider.name());
+                    continue;
+                }
+            };
+
+            let row = sqlx::query_as!(
+                FxRate,
+                r#"
+                INSERT INTO fx_rates (base_currency, quote_currency, rate, provider)
+                VALUES ($1, $2, $3, $4)
+                RETURNING id, base_currency as "base_currency!", quote_currency as "quote_currency!",
+                          rate, provider, fetched_at
+                "#,
+                self.base_currency,
+                quote,
+                rate,
+                provider.name()
+            )
+            .fetch_one(&self.db_pool)
+            .await?;
+
+            stored.push(row);
+        }
+
+        let count = stored.len();
+        {
+            let mut cache = self.cache.write().await;
+            for rate in stored {
+                cache.insert(rate.quote_currency.clone(), rate);
+            }
+        }
+
+        info!("Refreshed {} FX rates against {}", count, self.base_currency);
+
+        Ok(count)
+    }
+
+    /// Latest usable rate to a currency; None when unknown or too old
+    pub async fn get_rate(&self, currency: &str) -> Result<Option<FxRate>, AppError> {
+        let max_age_hours = self.get_setting("fx_max_rate_age_hours", DEFAULT_MAX_RATE_AGE_HOURS).await?;
+        let now = Utc::now();
+
+        if let Some(rate) = self.cache.read().await.get(currency) {
+            if is_fresh(rate.fetched_at, now, max_age_hours) {
+                return Ok(Some(rate.clone()));
+            }
+        }
+
+        let rate = sqlx::query_as!(
+            FxRate,
+            r#"
+            SELECT id, base_currency as "base_currency!", quote_currency as "quote_currency!",
+                   rate, provider, fetched_at
+            FROM fx_rates
+            WHERE base_currency = $1 AND quote_currency = $2
+            ORDER BY fetched_at DESC
+            LIMIT 1
+            "#,
+            self.base_currency,
+            currency
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        match rate {
+            Some(rate) if is_fresh(rate.fetched_at, now, max_age_hours) => {
+                self.cache.write().await.insert(currency.to_string(), rate.clone());
+                Ok(Some(rate))
+            }
+            Some(rate) => {
+                warn!("FX rate {}/{} from {} is stale", self.base_currency, currency, rate.fetched_at);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Latest rate of every currency that has one, for currency pickers
+    pub async fn latest_rates(&self) -> Result<Vec<FxRate>, AppError> {
+        let rates = sqlx::query_as!(
+            FxRate,
+            r#"
+            SELECT DISTINCT ON (quote_currency)
+                   id, base_currency as "base_currency!", quote_currency as "quote_currency!",
+                   rate, provider, fetched_at
+            FROM fx_rates
+            WHERE base_currency = $1
+            ORDER BY quote_currency, fetched_at DESC
+            "#,
+            self.base_currency
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rates)
+    }
+
+    /// Convert a settlement amount; None for the settlement currency itself or when no rate is usable
+    pub async fn convert(&self, amount: Decimal, currency: &str) -> Result<Option<DisplayAmount>, AppError> {
+        if currency == self.base_currency {
+            return Ok(None);
+        }
+
+        Ok(self.get_rate(currency).await?.map(|rate| display_amount(amount, &rate)))
+    }
+
+    /// Fill in display prices on raffles and their items priced in another currency
+    pub async fn apply_to_raffles(&self, raffles: &mut [RaffleResponse], currency: &str) -> Result<(), AppError> {
+        let target = match self.rate_from_base(currency).await? {
+            Some(rate) => rate,
+            None => return Ok(()),
+        };
+
+        let mut sources: HashMap<String, Option<FxRate>> = HashMap::new();
+        for raffle in raffles {
+            if raffle.currency == currency {
+                continue;
+            }
+
+            if !sources.contains_key(&raffle.currency) {
+                let rate = self.rate_from_base(&raffle.currency).await?;
+                sources.insert(raffle.currency.clone(), rate);
+            }
+            let source = match sources.get(&raffle.currency) {
+                Some(Some(rate)) => rate,
+                _ => continue,
+            };
+
+            raffle.display_box_price = Some(cross_display_amount(raffle.box_price, source, &target));
+            if let Some(item) = raffle.item.as_mut() {
+                item.display_retail_price = Some(cross_display_amount(item.retail_price, source, &target));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Currencies items may be priced and credits bought in: the base currency and those enabled in settings
+    pub async fn accepted_currencies(&self) -> Result<Vec<String>, AppError> {
+        let configured = SystemSetting::get_by_key(&self.db_pool, "accepted_currencies")
+            .await?
+            .and_then(|s| serde_json::from_value::<Vec<String>>(s.value).ok())
+            .unwrap_or_default();
+
+        Ok(accepted_currencies(&self.base_currency, &configured))
+    }
+
+    /// Validate the currency a listing is priced or a payment made in; none means the base currency
+    pub async fn accepted_currency(&self, requested: Option<&str>) -> Result<String, AppError> {
+        let currency = match requested {
+            Some(requested) => normalize_currency(requested)?,
+            None => return Ok(self.base_currency.clone()),
+        };
+
+        if !self.accepted_currencies().await?.contains(&currency) {
+            return Err(AppError::Validation(format!("{} is not an accepted currency", currency)));
+        }
+
+        // Amounts in a currency without a rate could not be settled
+        self.settlement_rate(&currency).await?;
+
+        Ok(currency)
+    }
+
+    /// Units of `currency` per unit of the base currency, for settling a charge made in it
+    pub async fn settlement_rate(&self, currency: &str) -> Result<Decimal, AppError> {
+        if currency == self.base_currency {
+            return Ok(Decimal::ONE);
+        }
+
+        self.get_rate(currency)
+            .await?
+            .map(|rate| rate.rate)
+            .ok_or_else(|| AppError::Validation(format!("No exchange rate available for {}", currency)))
+    }
+
+    /// Display currency for a request: an explicit request wins over the user's preference
+    pub async fn resolve_display_currency(
+        &self,
+        requested: Option<&str>,
+        user_id: Option<Uuid>,
+    ) -> Result<Option<String>, AppError> {
+        if let Some(requested) = requested {
+            return normalize_currency(requested).map(Some);
+        }
+
+        match user_id {
+            Some(user_id) => Ok(User::get_display_currency(&self.db_pool, user_id).await?),
+            None => Ok(None),
+        }
+    }
+
+    /// Set a user's display currency; only currencies with a usable rate are accepted
+    pub async fn set_display_currency(&self, user_id: Uuid, currency: Option<&str>) -> Result<Option<String>, AppError> {
+        let currency = currency.map(normalize_currency).transpose()?;
+
+        if let Some(currency) = &currency {
+            if *currency != self.base_currency && self.get_rate(currency).await?.is_none() {
+                return Err(AppError::Validation(format!("No exchange rate available for {}", currency)));
+            }
+        }
+
+        User::set_display_currency(&self.db_pool, user_id, currency.as_deref()).await?;
+
+        Ok(currency)
+    }
+
+    /// Rate snapshot for a settlement amount in the user's display currency, if they have one
+    pub async fn snapshot_for_user(
+        &self,
+        user_id: Uuid,
+        amount: Decimal,
+        amount_currency: &str,
+    ) -> Result<Option<FxSnapshot>, AppError> {
+        if !amount_currency.eq_ignore_ascii_case(&self.base_currency) {
+            return Ok(None);
+        }
+
+        let currency = match User::get_display_currency(&self.db_pool, user_id).await? {
+            Some(currency) if currency != self.base_currency => currency,
+            _ => return Ok(None),
+        };
+
+        Ok(self.get_rate(&currency).await?.map(|rate| FxSnapshot {
+            display_currency: currency,
+            display_amount: convert_amount(amount, rate.rate),
+            rate: rate.rate,
+            rate_id: rate.id,
+        }))
+    }
+
+    // Private helper methods
+
+    /// Rate from the base currency, which converts to itself at exactly 1
+    async fn rate_from_base(&self, currency: &str) -> Result<Option<FxRate>, AppError> {
+        if currency != self.base_currency {
+            return self.get_rate(currency).await;
+        }
+
+        Ok(Some(FxRate {
+            id: Uuid::nil(),
+            base_currency: self.base_currency.clone(),
+            quote_currency: self.base_currency.clone(),
+            rate: Decimal::ONE,
+            provider: "identity".to_string(),
+            fetched_at: Utc::now(),
+        }))
+    }
+
+    async fn get_setting(&self, key: &str, default: i64) -> Result<i64, AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, key).await?;
+        Ok(setting
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default))
+    }
+}
+
+fn display_amount(amount: Decimal, rate: &FxRate) -> DisplayAmount {
+    DisplayAmount {
+        currency: rate.quote_currency.clone(),
+        amount: convert_amount(amount, rate.rate),
+        rate: rate.rate,
+        rate_as_of: rate.fetched_at,
+    }
+}
+
+/// Amount in `to.quote_currency` for one in `from.quote_currency`, both rates being from the base currency
+fn cross_display_amount(amount: Decimal, from: &FxRate, to: &FxRate) -> DisplayAmount {
+    let rate = cross_rate(from.rate, to.rate);
+    DisplayAmount {
+        currency: to.quote_currency.clone(),
+        amount: convert_amount(amount, rate),
+        rate,
+        rate_as_of: from.fetched_at.min(to.fetched_at),
+    }
+}
+
+/// The base currency first, then the configured codes that are valid, without duplicates
+fn accepted_currencies(base: &str, configured: &[String]) -> Vec<String> {
+    let mut currencies = vec![base.to_string()];
+    for code in configured {
+        if let Ok(code) = normalize_currency(code) {
+            if !currencies.contains(&code) {
+                currencies.push(code);
+            }
+        }
+    }
+    currencies
+}
+
+/// Base currency amount of a charge made at `rate` units per unit of the base currency
+pub fn settle_amount(amount: Decimal, rate: Decimal) -> Decimal {
+    (amount / rate).round_dp(2)
+}
+
+fn cross_rate(from: Decimal, to: Decimal) -> Decimal {
+    (to / from).round_dp(8)
+}
+
+/// ISO 4217 style code: three ASCII letters, returned uppercase
+pub fn normalize_currency(code: &str) -> Result<String, AppError> {
+    let code = code.trim();
+    if code.len() != 3 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(AppError::Validation(format!("Invalid currency code: {}", code)));
+    }
+    Ok(code.to_ascii_uppercase())
+}
+
+fn convert_amount(amount: Decimal, rate: Decimal) -> Decimal {
+    (amount * rate).round_dp(2)
+}
+
+fn is_fresh(fetched_at: DateTime<Utc>, now: DateTime<Utc>, max_age_hours: i64) -> bool {
+    now - fetched_at <= Duration::hours(max_age_hours)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_currency() {
+        assert_eq!(normalize_currency("eur").unwrap(), "EUR");
+        assert_eq!(normalize_currency(" GBP ").unwrap(), "GBP");
+        assert!(normalize_currency("EURO").is_err());
+        assert!(normalize_currency("E1R").is_err());
+        assert!(normalize_currency("").is_err());
+    }
+
+    #[test]
+    fn test_conversion_and_freshness() {
+        assert_eq!(convert_amount(Decimal::new(250, 2), Decimal::new(92, 2)), Decimal::new(230, 2));
+        assert_eq!(convert_amount(Decimal::new(999, 2), Decimal::new(15012345, 5)), Decimal::new(149973, 2));
+
+        let now = Utc::now();
+        assert!(is_fresh(now - Duration::hours(23), now, 24));
+        assert!(!is_fresh(now - Duration::hours(25), now, 24));
+    }
+
+    #[test]
+    fn test_settlement_and_cross_rates() {
+        // EUR 2.30 at 0.92 EUR per USD settles as USD 2.50
+        assert_eq!(settle_amount(Decimal::new(230, 2), Decimal::new(92, 2)), Decimal::new(250, 2));
+        assert_eq!(settle_amount(Decimal::new(999, 2), Decimal::ONE), Decimal::new(999, 2));
+
+        // Base currency listings keep the plain rate
+        assert_eq!(cross_rate(Decimal::ONE, Decimal::new(92, 2)), Decimal::new(92, 2));
+
+        // EUR listing shown in GBP
+        let rate = cross_rate(Decimal::new(92, 2), Decimal::new(79, 2));
+        assert_eq!(rate, Decimal::new(85869565, 8));
+        assert_eq!(convert_amount(Decimal::from(10), rate), Decimal::new(859, 2));
+    }
+
+    #[test]
+    fn test_accepted_currencies() {
+        let configured = vec!["eur".to_string(), "USD".to_string(), "EURO".to_string(), "EUR".to_string()];
+        assert_eq!(accepted_currencies("USD", &configured), vec!["USD", "EUR"]);
+        assert_eq!(accepted_currencies("USD", &[]), vec!["USD"]);
+    }
+}
@@ -0,0 +1,430 @@
+use crate::config::AppConfig;
+use crate::error::{AppError, FieldError};
+use crate::models::system_settings::SystemSetting;
+use crate::models::AuditLog;
+use crate::services::geo_velocity_service::{is_public, HttpIpGeoProvider, IpGeoProvider};
+use raffle_platform_shared::{AuditAction, RaffleResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Setting holding the platform and per-raffle country lists
+const POLICY_KEY: &str = "geo_restrictions";
+const POLICY_CACHE_SECONDS: u64 = 60;
+const COUNTRY_CACHE_SECONDS: u64 = 3600;
+const MAX_CACHED_COUNTRIES: usize = 10_000;
+const MAX_COUNTRIES: usize = 250;
+
+/// Countries a raffle can or can't be seen and played from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CountryRules {
+    /// ISO 3166-1 alpha-2 codes; empty allows every country not blocked
+    #[serde(default)]
+    pub allowed_countries: Vec<String>,
+    #[serde(default)]
+    pub blocked_countries: Vec<String>,
+}
+
+impl CountryRules {
+    /// Blocked countries lose even when also allowed
+    pub fn permits(&self, country: &str) -> bool {
+        !self.blocked_countries.iter().any(|c| c == country)
+            && (self.allowed_countries.is_empty() || self.allowed_countries.iter().any(|c| c == country))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.allowed_countries.is_empty() && self.blocked_countries.is_empty()
+    }
+
+    /// Uppercase the codes and refuse anything that isn't two letters
+    fn normalize(&mut self, field_prefix: &str, errors: &mut Vec<FieldError>) {
+        for (field, countries) in [
+            ("allowed_countries", &mut self.allowed_countries),
+            ("blocked_countries", &mut self.blocked_countries),
+        ] {
+            let field = format!("{}{}", field_prefix, field);
+            for country in countries.iter_mut() {
+                *country = country.trim().to_ascii_uppercase();
+                if country.len() != 2 || !country.chars().all(|c| c.is_ascii_uppercase()) {
+                    errors.push(FieldError::new(
+                        &field,
+                        "invalid_format",
+                        &format!("{:?} is not a two-letter country code", country),
+                    ));
+                }
+            }
+            countries.sort();
+            countries.dedup();
+            if countries.len() > MAX_COUNTRIES {
+                errors.push(
+                    FieldError::new(&field, "too_many", "Too many countries listed").with_limit(MAX_COUNTRIES as i64),
+                );
+            }
+        }
+    }
+}
+
+/// Where raffles may be seen and played, platform-wide and per raffle
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct GeoRestrictionPolicy {
+    /// Nothing is restricted when false, whatever the lists say
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub platform: CountryRules,
+    /// Also refuse requests whose country can't be worked out, such as from private networks
+    #[serde(default)]
+    pub block_unknown_countries: bool,
+    /// Extra rules for single raffles, on top of the platform's
+    #[serde(default)]
+    pub raffles: BTreeMap<Uuid, CountryRules>,
+}
+
+impl GeoRestrictionPolicy {
+    pub fn validate(&mut self) -> Result<(), AppError> {
+        let mut errors = Vec::new();
+
+        self.platform.normalize("", &mut errors);
+        for (raffle_id, rules) in self.raffles.iter_mut() {
+            rules.normalize(&format!("raffles.{}.", raffle_id), &mut errors);
+        }
+        self.raffles.retain(|_, rules| !rules.is_empty());
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::InvalidFields(errors))
+        }
+    }
+
+    /// Whether anything would be refused, so requests only need locating when it is
+    pub fn is_active(&self) -> bool {
+        self.enabled && (self.block_unknown_countries || !self.platform.is_empty() || !self.raffles.is_empty())
+    }
+
+    /// Whether a request from the country may see and play the raffle, or any raffle when None
+    pub fn permits(&self, raffle_id: Option<Uuid>, country: Option<&str>) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        let Some(country) = country else {
+            return !self.block_unknown_countries;
+        };
+
+        self.platform.permits(country)
+            && raffle_id
+                .and_then(|id| self.raffles.get(&id))
+                .map_or(true, |rules| rules.permits(country))
+    }
+}
+
+/// Country the request came from, set by the geo-restriction middleware; None when unknown
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestCountry(pub Option<String>);
+
+impl RequestCountry {
+    pub fn code(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+}
+
+/// Geo-compliance service keeps raffles away from countries where they aren't allowed,
+/// hiding them and refusing purchases
+#[derive(Clone)]
+pub struct GeoComplianceService {
+    db_pool: PgPool,
+    provider: Option<Arc<dyn IpGeoProvider>>,
+    policy_cache: Arc<RwLock<Option<(Instant, GeoRestrictionPolicy)>>>,
+    country_cache: Arc<RwLock<HashMap<IpAddr, (Instant, Option<String>)>>>,
+}
+
+impl GeoComplianceService {
+    /// Create a new geo-compliance service; without a provider every country is unknown
+    pub fn new(db_pool: PgPool, provider: Option<Arc<dyn IpGeoProvider>>) -> Self {
+        Self {
+            db_pool,
+            provider,
+            policy_cache: Arc::new(RwLock::new(None)),
+            country_cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn from_config(db_pool: PgPool, config: &AppConfig) -> Self {
+        let provider = config.ip_geo_url.clone().map(|url| {
+            Arc::new(HttpIpGeoProvider::new(url, config.ip_geo_api_key.clone())) as Arc<dyn IpGeoProvider>
+        });
+
+        Self::new(db_pool, provider)
+    }
+
+    /// Country of the request's address; lookups only happen while a restriction is in force
+    pub async fn resolve_country(&self, ip: Option<IpAddr>) -> RequestCountry {
+        let (Some(provider), Some(ip)) = (&self.provider, ip) else {
+            return RequestCountry(None);
+        };
+        if !is_public(ip) {
+            return RequestCountry(None);
+        }
+        match self.get_policy().await {
+            Ok(policy) if policy.is_active() => {}
+            Ok(_) => return RequestCountry(None),
+            Err(e) => warn!("Failed to load geo restrictions: {}", e),
+        }
+
+        if let Some((looked_up_at, country)) = self.country_cache.read().await.get(&ip) {
+            if looked_up_at.elapsed().as_secs() < COUNTRY_CACHE_SECONDS {
+                return RequestCountry(country.clone());
+            }
+        }
+
+        let country = match provider.lookup(ip).await {
+            Ok(location) => location.map(|location| location.country_code.to_ascii_uppercase()),
+            Err(e) => {
+                // Not cached, so the next request tries again
+                warn!("Country lookup for {} failed: {}", ip, e);
+                return RequestCountry(None);
+            }
+        };
+
+        let mut cache = self.country_cache.write().await;
+        if cache.len() >= MAX_CACHED_COUNTRIES {
+            cache.clear();
+        }
+        cache.insert(ip, (Instant::now(), country.clone()));
+
+        RequestCountry(country)
+    }
+
+    /// The restrictions in force; a missing or unreadable setting restricts nothing
+    pub async fn get_policy(&self) -> Result<GeoRestrictionPolicy, AppError> {
+        if let Some((loaded_at, policy)) = self.policy_cache.read().await.as_ref() {
+            if loaded_at.elapsed().as_secs() < POLICY_CACHE_SECONDS {
+                return Ok(policy.clone());
+            }
+        }
+
+        let policy = match SystemSetting::get_by_key(&self.db_pool, POLICY_KEY).await? {
+            Some(setting) => serde_json::from_value(setting.value).unwrap_or_else(|e| {
+                warn!("Invalid {} setting, restricting nothing: {}", POLICY_KEY, e);
+                GeoRestrictionPolicy::default()
+            }),
+            None => GeoRestrictionPolicy::default(),
+        };
+
+        *self.policy_cache.write().await = Some((Instant::now(), policy.clone()));
+        Ok(policy)
+    }
+
+    /// Replace the platform-wide restrictions and every raffle's
+    pub async fn update_policy(
+        &self,
+        mut policy: GeoRestrictionPolicy,
+        admin_id: Uuid,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<GeoRestrictionPolicy, AppError> {
+        policy.validate()?;
+        let previous = self.get_policy().await?;
+
+        SystemSetting::upsert(
+            &self.db_pool,
+            POLICY_KEY.to_string(),
+            serde_json::to_value(&policy).map_err(|e| AppError::Internal(e.to_string()))?,
+            Some("Countries raffles can be seen and played from, platform-wide and per raffle".to_string()),
+            false,
+        )
+        .await?;
+        *self.policy_cache.write().await = Some((Instant::now(), policy.clone()));
+
+        if let Err(e) = AuditLog::create(
+            &self.db_pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("geo_restrictions".to_string()),
+            None,
+            serde_json::to_value(&previous).ok(),
+            serde_json::to_value(&policy).ok(),
+            ip_address,
+            user_agent,
+        )
+        .await
+        {
+            warn!("Failed to audit change to geo restrictions: {}", e);
+        }
+
+        info!("Admin {} updated geo restrictions", admin_id);
+
+        Ok(policy)
+    }
+
+    /// Set one raffle's rules, or clear them with empty lists
+    pub async fn set_raffle_rules(
+        &self,
+        raffle_id: Uuid,
+        rules: CountryRules,
+        admin_id: Uuid,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<GeoRestrictionPolicy, AppError> {
+        let mut policy = self.get_policy().await?;
+        policy.raffles.insert(raffle_id, rules);
+
+        self.update_policy(policy, admin_id, ip_address, user_agent).await
+    }
+
+    /// Drop raffles the request's country may not see
+    pub async fn filter_visible(&self, country: &RequestCountry, raffles: &mut Vec<RaffleResponse>) {
+        match self.get_policy().await {
+            Ok(policy) => raffles.retain(|raffle| policy.permits(Some(raffle.id), country.code())),
+            Err(e) => warn!("Failed to load geo restrictions, showing raffles unfiltered: {}", e),
+        }
+    }
+
+    /// Refuse a raffle the request's country may not see; shown as missing so its
+    /// existence isn't given away
+    pub async fn ensure_visible(
+        &self,
+        raffle_id: Uuid,
+        country: &RequestCountry,
+        user_id: Option<Uuid>,
+        ip_address: Option<IpAddr>,
+    ) -> Result<(), AppError> {
+        if self.get_policy().await?.permits(Some(raffle_id), country.code()) {
+            return Ok(());
+        }
+
+        self.audit_blocked("geo_blocked_view", raffle_id, country, user_id, ip_address).await;
+        Err(AppError::NotFound("Raffle not found".to_string()))
+    }
+
+    /// Refuse a purchase from a country the raffle isn't allowed in
+    pub async fn ensure_can_purchase(
+        &self,
+        raffle_id: Uuid,
+        country: &RequestCountry,
+        user_id: Uuid,
+        ip_address: Option<IpAddr>,
+    ) -> Result<(), AppError> {
+        if self.get_policy().await?.permits(Some(raffle_id), country.code()) {
+            return Ok(());
+        }
+
+        self.audit_blocked("geo_blocked_purchase", raffle_id, country, Some(user_id), ip_address).await;
+        Err(AppError::Authorization(
+            "This raffle is not available in your country".to_string(),
+        ))
+    }
+
+    async fn audit_blocked(
+        &self,
+        event: &str,
+        raffle_id: Uuid,
+        country: &RequestCountry,
+        user_id: Option<Uuid>,
+        ip_address: Option<IpAddr>,
+    ) {
+        warn!(
+            "Blocked {} of raffle {} from country {:?} (user {:?})",
+            event, raffle_id, country.code(), user_id
+        );
+
+        if let Err(e) = AuditLog::create(
+            &self.db_pool,
+            user_id,
+            AuditAction::SecurityEvent,
+            Some("raffle".to_string()),
+            Some(raffle_id),
+            None,
+            Some(serde_json::json!({ "event": event, "country_code": country.code() })),
+            ip_address,
+            None,
+        )
+        .await
+        {
+            warn!("Failed to audit blocked access to raffle {}: {}", raffle_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(allowed: &[&str], blocked: &[&str]) -> CountryRules {
+        CountryRules {
+            allowed_countries: allowed.iter().map(|c| c.to_string()).collect(),
+            blocked_countries: blocked.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_country_rules_block_wins_over_allow() {
+        assert!(rules(&[], &[]).permits("US"));
+        assert!(!rules(&[], &["US"]).permits("US"));
+        assert!(rules(&["US", "CA"], &[]).permits("CA"));
+        assert!(!rules(&["US", "CA"], &[]).permits("GB"));
+        assert!(!rules(&["US"], &["US"]).permits("US"));
+    }
+
+    #[test]
+    fn test_policy_combines_platform_and_raffle_rules() {
+        let raffle_id = Uuid::new_v4();
+        let policy = GeoRestrictionPolicy {
+            enabled: true,
+            platform: rules(&[], &["KP"]),
+            block_unknown_countries: false,
+            raffles: BTreeMap::from([(raffle_id, rules(&["US"], &[]))]),
+        };
+
+        assert!(!policy.permits(None, Some("KP")));
+        assert!(policy.permits(None, Some("GB")));
+        assert!(policy.permits(Some(raffle_id), Some("US")));
+        assert!(!policy.permits(Some(raffle_id), Some("GB")));
+        assert!(policy.permits(Some(Uuid::new_v4()), Some("GB")));
+        // Unknown countries are let through unless the policy says otherwise
+        assert!(policy.permits(Some(raffle_id), None));
+        assert!(!GeoRestrictionPolicy { block_unknown_countries: true, ..policy.clone() }.permits(None, None));
+    }
+
+    #[test]
+    fn test_disabled_policy_permits_everything() {
+        let policy = GeoRestrictionPolicy {
+            enabled: false,
+            platform: rules(&[], &["US"]),
+            block_unknown_countries: true,
+            raffles: BTreeMap::new(),
+        };
+
+        assert!(!policy.is_active());
+        assert!(policy.permits(None, Some("US")));
+        assert!(policy.permits(None, None));
+    }
+
+    #[test]
+    fn test_validate_normalizes_codes() {
+        let raffle_id = Uuid::new_v4();
+        let mut policy = GeoRestrictionPolicy {
+            enabled: true,
+            platform: rules(&[], &[" us", "US", "ca"]),
+            block_unknown_countries: false,
+            raffles: BTreeMap::from([(raffle_id, rules(&[], &[]))]),
+        };
+
+        policy.validate().unwrap();
+        assert_eq!(policy.platform.blocked_countries, vec!["CA", "US"]);
+        assert!(policy.raffles.is_empty());
+
+        let mut invalid = GeoRestrictionPolicy {
+            enabled: true,
+            platform: rules(&["USA"], &["1A"]),
+            ..Default::default()
+        };
+        assert!(matches!(invalid.validate(), Err(AppError::InvalidFields(errors)) if errors.len() == 2));
+    }
+}
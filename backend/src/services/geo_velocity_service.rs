@@ -0,0 +1,650 @@
+use crate::config::AppConfig;
+use crate::error::{AppError, FieldError};
+use crate::models::notification::{Notification, NotificationType};
+use crate::models::system_settings::SystemSetting;
+use crate::utils::crypto::verify_password;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Setting holding the geo-velocity policy
+const POLICY_KEY: &str = "geo_velocity_policy";
+const POLICY_CACHE_SECONDS: u64 = 60;
+const LOCATION_CACHE_SECONDS: u64 = 3600;
+const MAX_CACHED_LOCATIONS: usize = 10_000;
+/// Requests closer together than this are treated as this far apart, so speeds stay finite
+const MIN_ELAPSED_MINUTES: f64 = 1.0;
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+pub type GeoLookupFuture<'a> = Pin<Box<dyn Future<Output = Result<Option<GeoLocation>, AppError>> + Send + 'a>>;
+
+/// Source of the country and rough position of an IP address
+pub trait IpGeoProvider: Send + Sync {
+    /// None when the provider doesn't know the address
+    fn lookup<'a>(&'a self, ip: IpAddr) -> GeoLookupFuture<'a>;
+}
+
+/// Geo lookup answering `GET <url>/<ip>` with `{"country_code": "US", "latitude": 37.77, "longitude": -122.42}`
+pub struct HttpIpGeoProvider {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+}
+
+impl HttpIpGeoProvider {
+    pub fn new(url: String, api_key: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+}
+
+impl IpGeoProvider for HttpIpGeoProvider {
+    fn lookup<'a>(&'a self, ip: IpAddr) -> GeoLookupFuture<'a> {
+        Box::pin(async move {
+            let mut request = self.client.get(format!("{}/{}", self.url, ip));
+            if let Some(api_key) = &self.api_key {
+                request = request.bearer_auth(api_key);
+            }
+
+            let response = request
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(format!("IP geo lookup failed: {}", e)))?;
+
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            if !response.status().is_success() {
+                return Err(AppError::Internal(format!("IP geo lookup returned {}", response.status())));
+            }
+
+            let location: GeoLocation = response
+                .json()
+                .await
+                .map_err(|e| AppError::Internal(format!("Invalid IP geo response: {}", e)))?;
+
+            Ok(Some(location))
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeoLocation {
+    pub country_code: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// When a user's requests moving between countries call for re-authentication
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct GeoVelocityPolicy {
+    pub enabled: bool,
+    /// Moving between countries faster than this needs re-authentication; lower is stricter
+    pub max_speed_kmh: f64,
+    /// Shorter jumps are ignored, since IP locations are approximate; lower is stricter
+    pub min_distance_km: f64,
+    /// How long the user has to answer a challenge
+    pub challenge_ttl_minutes: i64,
+}
+
+impl Default for GeoVelocityPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_speed_kmh: 900.0,
+            min_distance_km: 500.0,
+            challenge_ttl_minutes: 15,
+        }
+    }
+}
+
+impl GeoVelocityPolicy {
+    pub fn validate(&self) -> Result<(), AppError> {
+        let mut errors = Vec::new();
+
+        if self.max_speed_kmh <= 0.0 {
+            errors.push(FieldError::new("max_speed_kmh", "below_minimum", "Maximum speed must be positive"));
+        }
+        if self.min_distance_km < 0.0 {
+            errors.push(FieldError::new("min_distance_km", "below_minimum", "Minimum distance cannot be negative"));
+        }
+        if !(1..=1440).contains(&self.challenge_ttl_minutes) {
+            errors.push(
+                FieldError::new(
+                    "challenge_ttl_minutes",
+                    "out_of_range",
+                    "Challenges must last between 1 minute and 1 day",
+                )
+                .with_limit(1440),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::InvalidFields(errors))
+        }
+    }
+}
+
+/// Where a user was last seen
+#[derive(Debug, Clone, FromRow)]
+pub struct GeoSighting {
+    pub country_code: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub seen_at: DateTime<Utc>,
+}
+
+/// Travel between two sightings too fast to be one person
+#[derive(Debug, Clone, PartialEq)]
+pub struct VelocityViolation {
+    pub from_country_code: String,
+    pub distance_km: f64,
+    pub speed_kmh: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ChallengeStatus {
+    Pending,
+    Completed,
+    Expired,
+}
+
+/// A demand that the user re-enter their password before their requests are served again
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct StepUpChallenge {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub reason: String,
+    pub status: ChallengeStatus,
+    pub from_country_code: String,
+    pub country_code: String,
+    pub distance_km: f64,
+    pub speed_kmh: f64,
+    pub expires_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CompleteStepUpRequest {
+    pub password: String,
+}
+
+const CHALLENGE_COLUMNS: &str = "id, user_id, reason, status, from_country_code, country_code, distance_km, \
+    speed_kmh, expires_at, completed_at, created_at";
+
+/// Geo-velocity service spots a user's requests jumping between distant countries within
+/// minutes and challenges them to re-authenticate
+#[derive(Clone)]
+pub struct GeoVelocityService {
+    db_pool: PgPool,
+    provider: Option<Arc<dyn IpGeoProvider>>,
+    policy_cache: Arc<RwLock<Option<(Instant, GeoVelocityPolicy)>>>,
+    location_cache: Arc<RwLock<HashMap<IpAddr, (Instant, Option<GeoLocation>)>>>,
+}
+
+impl GeoVelocityService {
+    /// Create a new geo-velocity service; without a provider no request is checked
+    pub fn new(db_pool: PgPool, provider: Option<Arc<dyn IpGeoProvider>>) -> Self {
+        Self {
+            db_pool,
+            provider,
+            policy_cache: Arc::new(RwLock::new(None)),
+            location_cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn from_config(db_pool: PgPool, config: &AppConfig) -> Self {
+        let provider = config.ip_geo_url.clone().map(|url| {
+            Arc::new(HttpIpGeoProvider::new(url, config.ip_geo_api_key.clone())) as Arc<dyn IpGeoProvider>
+        });
+
+        Self::new(db_pool, provider)
+    }
+
+    /// Check a request against where the user was last seen. Returns the challenge the user
+    /// must answer before being served, which is issued here when the request moved too fast.
+    pub async fn check_request(&self, user_id: Uuid, ip: IpAddr) -> Result<Option<StepUpChallenge>, AppError> {
+        let Some(provider) = &self.provider else {
+            return Ok(None);
+        };
+        let policy = self.get_policy().await?;
+        if !policy.enabled {
+            return Ok(None);
+        }
+
+        if let Some(pending) = self.pending_challenge(user_id).await? {
+            return Ok(Some(pending));
+        }
+
+        let Some(location) = self.locate(provider.as_ref(), ip).await? else {
+            return Ok(None);
+        };
+
+        let previous = sqlx::query_as::<_, GeoSighting>(
+            "SELECT country_code, latitude, longitude, seen_at FROM user_geo_sightings WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        let violation = previous
+            .as_ref()
+            .and_then(|previous| assess(previous, &location, Utc::now(), &policy));
+
+        match violation {
+            // The last sighting stays the baseline until the user proves it's them
+            Some(violation) => Ok(Some(self.issue_challenge(user_id, ip, &location, &violation, &policy).await?)),
+            None => {
+                self.record_sighting(user_id, ip, &location).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Answer a challenge with the account password; the challenged location becomes the
+    /// user's new baseline
+    pub async fn complete_step_up(
+        &self,
+        challenge_id: Uuid,
+        user_id: Uuid,
+        password: &str,
+    ) -> Result<StepUpChallenge, AppError> {
+        let challenge = sqlx::query_as::<_, StepUpChallenge>(&format!(
+            "SELECT {} FROM step_up_challenges WHERE id = $1 AND user_id = $2",
+            CHALLENGE_COLUMNS
+        ))
+        .bind(challenge_id)
+        .bind(user_id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Challenge not found".to_string()))?;
+
+        if challenge.status != ChallengeStatus::Pending || challenge.expires_at <= Utc::now() {
+            return Err(AppError::Conflict("Challenge is no longer open".to_string()));
+        }
+
+        let password_hash: Option<String> = sqlx::query_scalar("SELECT password_hash FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .flatten();
+        let password_hash =
+            password_hash.ok_or_else(|| AppError::Authentication("Account has no password to confirm".to_string()))?;
+        if !verify_password(password, &password_hash)? {
+            warn!("Failed step-up for user {} on challenge {}", user_id, challenge_id);
+            return Err(AppError::Authentication("Password is incorrect".to_string()));
+        }
+
+        let mut tx = self.db_pool.begin().await?;
+
+        let completed = sqlx::query_as::<_, StepUpChallenge>(&format!(
+            r#"
+            UPDATE step_up_challenges SET status = 'completed', completed_at = NOW()
+            WHERE id = $1 AND status = 'pending'
+            RETURNING {}
+            "#,
+            CHALLENGE_COLUMNS
+        ))
+        .bind(challenge_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Challenge is no longer open".to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO user_geo_sightings (user_id, ip_address, country_code, latitude, longitude, seen_at)
+            SELECT user_id, ip_address, country_code, latitude, longitude, NOW()
+            FROM step_up_challenges WHERE id = $1
+            ON CONFLICT (user_id) DO UPDATE SET
+                ip_address = EXCLUDED.ip_address,
+                country_code = EXCLUDED.country_code,
+                latitude = EXCLUDED.latitude,
+                longitude = EXCLUDED.longitude,
+                seen_at = EXCLUDED.seen_at
+            "#,
+        )
+        .bind(challenge_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        info!("User {} completed step-up challenge {}", user_id, challenge_id);
+
+        Ok(completed)
+    }
+
+    /// The policy in force; a missing or unreadable setting falls back to the defaults
+    pub async fn get_policy(&self) -> Result<GeoVelocityPolicy, AppError> {
+        if let Some((loaded_at, policy)) = self.policy_cache.read().await.as_ref() {
+            if loaded_at.elapsed().as_secs() < POLICY_CACHE_SECONDS {
+                return Ok(policy.clone());
+            }
+        }
+
+        let policy = match SystemSetting::get_by_key(&self.db_pool, POLICY_KEY).await? {
+            Some(setting) => serde_json::from_value(setting.value).unwrap_or_else(|e| {
+                warn!("Invalid {} setting, using defaults: {}", POLICY_KEY, e);
+                GeoVelocityPolicy::default()
+            }),
+            None => GeoVelocityPolicy::default(),
+        };
+
+        *self.policy_cache.write().await = Some((Instant::now(), policy.clone()));
+        Ok(policy)
+    }
+
+    /// Replace the policy; challenges already issued keep their expiry
+    pub async fn update_policy(&self, policy: GeoVelocityPolicy) -> Result<GeoVelocityPolicy, AppError> {
+        policy.validate()?;
+
+        SystemSetting::upsert(
+            &self.db_pool,
+            POLICY_KEY.to_string(),
+            serde_json::to_value(&policy).map_err(|e| AppError::Internal(e.to_string()))?,
+            Some("Re-authentication when a user's requests move between countries faster than max_speed_kmh".to_string()),
+            false,
+        )
+        .await?;
+
+        *self.policy_cache.write().await = Some((Instant::now(), policy.clone()));
+        Ok(policy)
+    }
+
+    // Private helper methods
+
+    async fn pending_challenge(&self, user_id: Uuid) -> Result<Option<StepUpChallenge>, AppError> {
+        let challenge = sqlx::query_as::<_, StepUpChallenge>(&format!(
+            "SELECT {} FROM step_up_challenges WHERE user_id = $1 AND status = 'pending' AND expires_at > NOW()",
+            CHALLENGE_COLUMNS
+        ))
+        .bind(user_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(challenge)
+    }
+
+    async fn issue_challenge(
+        &self,
+        user_id: Uuid,
+        ip: IpAddr,
+        location: &GeoLocation,
+        violation: &VelocityViolation,
+        policy: &GeoVelocityPolicy,
+    ) -> Result<StepUpChallenge, AppError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        sqlx::query(
+            "UPDATE step_up_challenges SET status = 'expired' WHERE user_id = $1 AND status = 'pending' AND expires_at <= NOW()",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let issued = sqlx::query_as::<_, StepUpChallenge>(&format!(
+            r#"
+            INSERT INTO step_up_challenges
+                (user_id, from_country_code, ip_address, country_code, latitude, longitude,
+                 distance_km, speed_kmh, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (user_id) WHERE status = 'pending' DO NOTHING
+            RETURNING {}
+            "#,
+            CHALLENGE_COLUMNS
+        ))
+        .bind(user_id)
+        .bind(&violation.from_country_code)
+        .bind(ip)
+        .bind(&location.country_code)
+        .bind(location.latitude)
+        .bind(location.longitude)
+        .bind(violation.distance_km)
+        .bind(violation.speed_kmh)
+        .bind(Utc::now() + Duration::minutes(policy.challenge_ttl_minutes))
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        // Another request raised it first
+        let Some(challenge) = issued else {
+            return self
+                .pending_challenge(user_id)
+                .await?
+                .ok_or_else(|| AppError::Conflict("Re-authentication is required; try again".to_string()));
+        };
+
+        warn!(
+            "Step-up challenge {} for user {}: {} -> {} ({:.0} km at {:.0} km/h)",
+            challenge.id, user_id, violation.from_country_code, location.country_code, violation.distance_km,
+            violation.speed_kmh
+        );
+
+        // The challenge stands even if the notice can't be stored
+        if let Err(e) = Notification::create(
+            &self.db_pool,
+            user_id,
+            "Confirm it's you".to_string(),
+            format!(
+                "Your account was used from {} shortly after {}. Enter your password to keep going. \
+                 If this wasn't you, change your password now.",
+                location.country_code, violation.from_country_code
+            ),
+            NotificationType::SecurityAlert,
+            Some(serde_json::json!({
+                "event": "step_up_required",
+                "challenge_id": challenge.id,
+                "from_country_code": violation.from_country_code,
+                "country_code": location.country_code,
+            })),
+        )
+        .await
+        {
+            warn!("Failed to notify user {} of step-up challenge: {}", user_id, e);
+        }
+
+        Ok(challenge)
+    }
+
+    async fn record_sighting(&self, user_id: Uuid, ip: IpAddr, location: &GeoLocation) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_geo_sightings (user_id, ip_address, country_code, latitude, longitude, seen_at)
+            VALUES ($1, $2, $3, $4, $5, NOW())
+            ON CONFLICT (user_id) DO UPDATE SET
+                ip_address = EXCLUDED.ip_address,
+                country_code = EXCLUDED.country_code,
+                latitude = EXCLUDED.latitude,
+                longitude = EXCLUDED.longitude,
+                seen_at = EXCLUDED.seen_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(ip)
+        .bind(&location.country_code)
+        .bind(location.latitude)
+        .bind(location.longitude)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Look the address up, remembering answers for an hour; private addresses aren't looked up
+    async fn locate(&self, provider: &dyn IpGeoProvider, ip: IpAddr) -> Result<Option<GeoLocation>, AppError> {
+        if !is_public(ip) {
+            return Ok(None);
+        }
+
+        if let Some((looked_up_at, location)) = self.location_cache.read().await.get(&ip) {
+            if looked_up_at.elapsed().as_secs() < LOCATION_CACHE_SECONDS {
+                return Ok(location.clone());
+            }
+        }
+
+        let location = provider.lookup(ip).await?.map(|mut location| {
+            location.country_code = location.country_code.to_uppercase();
+            location
+        });
+
+        let mut cache = self.location_cache.write().await;
+        if cache.len() >= MAX_CACHED_LOCATIONS {
+            cache.clear();
+        }
+        cache.insert(ip, (Instant::now(), location.clone()));
+
+        Ok(location)
+    }
+}
+
+/// Whether moving from the last sighting to this location by now is too fast to be one person
+pub fn assess(
+    previous: &GeoSighting,
+    current: &GeoLocation,
+    now: DateTime<Utc>,
+    policy: &GeoVelocityPolicy,
+) -> Option<VelocityViolation> {
+    if previous.country_code == current.country_code {
+        return None;
+    }
+
+    let distance_km = distance_km(previous.latitude, previous.longitude, current.latitude, current.longitude);
+    if distance_km < policy.min_distance_km {
+        return None;
+    }
+
+    let elapsed_minutes = ((now - previous.seen_at).num_seconds() as f64 / 60.0).max(MIN_ELAPSED_MINUTES);
+    let speed_kmh = distance_km / (elapsed_minutes / 60.0);
+    if speed_kmh <= policy.max_speed_kmh {
+        return None;
+    }
+
+    Some(VelocityViolation {
+        from_country_code: previous.country_code.clone(),
+        distance_km,
+        speed_kmh,
+    })
+}
+
+/// Great-circle distance between two points
+pub fn distance_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+pub(crate) fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast())
+        }
+        IpAddr::V6(ip) => !(ip.is_loopback() || ip.is_unspecified() || (ip.segments()[0] & 0xfe00) == 0xfc00),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sighting(country_code: &str, latitude: f64, longitude: f64, minutes_ago: i64) -> GeoSighting {
+        GeoSighting {
+            country_code: country_code.to_string(),
+            latitude,
+            longitude,
+            seen_at: Utc::now() - Duration::minutes(minutes_ago),
+        }
+    }
+
+    fn location(country_code: &str, latitude: f64, longitude: f64) -> GeoLocation {
+        GeoLocation {
+            country_code: country_code.to_string(),
+            latitude,
+            longitude,
+        }
+    }
+
+    #[test]
+    fn test_distance_km() {
+        // New York to London is about 5570 km
+        let distance = distance_km(40.71, -74.01, 51.51, -0.13);
+        assert!((distance - 5570.0).abs() < 20.0);
+        assert_eq!(distance_km(10.0, 10.0, 10.0, 10.0), 0.0);
+    }
+
+    #[test]
+    fn test_assess_flags_impossible_travel() {
+        let policy = GeoVelocityPolicy::default();
+        let violation = assess(&sighting("US", 40.71, -74.01, 10), &location("GB", 51.51, -0.13), Utc::now(), &policy)
+            .expect("New York to London in 10 minutes is impossible");
+        assert_eq!(violation.from_country_code, "US");
+        assert!(violation.speed_kmh > 30_000.0);
+    }
+
+    #[test]
+    fn test_assess_allows_plausible_travel() {
+        let policy = GeoVelocityPolicy::default();
+        // A transatlantic flight
+        assert!(assess(&sighting("US", 40.71, -74.01, 8 * 60), &location("GB", 51.51, -0.13), Utc::now(), &policy).is_none());
+        // Same country, however far
+        assert!(assess(&sighting("US", 40.71, -74.01, 1), &location("US", 37.77, -122.42), Utc::now(), &policy).is_none());
+        // Across a nearby border
+        assert!(assess(&sighting("FR", 48.86, 2.35, 1), &location("BE", 50.85, 4.35), Utc::now(), &policy).is_none());
+    }
+
+    #[test]
+    fn test_sensitivity() {
+        let strict = GeoVelocityPolicy {
+            min_distance_km: 100.0,
+            ..GeoVelocityPolicy::default()
+        };
+        assert!(assess(&sighting("FR", 48.86, 2.35, 1), &location("BE", 50.85, 4.35), Utc::now(), &strict).is_some());
+
+        let lax = GeoVelocityPolicy {
+            max_speed_kmh: 100_000.0,
+            ..GeoVelocityPolicy::default()
+        };
+        assert!(assess(&sighting("US", 40.71, -74.01, 10), &location("GB", 51.51, -0.13), Utc::now(), &lax).is_none());
+    }
+
+    #[test]
+    fn test_policy_validate() {
+        assert!(GeoVelocityPolicy::default().validate().is_ok());
+        let invalid = GeoVelocityPolicy {
+            max_speed_kmh: 0.0,
+            challenge_ttl_minutes: 0,
+            ..GeoVelocityPolicy::default()
+        };
+        assert!(matches!(invalid.validate(), Err(AppError::InvalidFields(errors)) if errors.len() == 2));
+    }
+
+    #[test]
+    fn test_is_public() {
+        assert!(is_public("8.8.8.8".parse().unwrap()));
+        assert!(!is_public("10.0.0.1".parse().unwrap()));
+        assert!(!is_public("127.0.0.1".parse().unwrap()));
+        assert!(!is_public("fd00::1".parse().unwrap()));
+        assert!(is_public("2001:4860:4860::8888".parse().unwrap()));
+    }
+}
@@ -1,21 +1,38 @@
 use crate::models::item::Item;
+use crate::models::seller_onboarding::{SellerFeature, SellerOnboarding};
 use crate::models::user::User;
 use crate::error::AppError;
+use crate::repositories::{Cursor, CursorParams};
+use crate::services::cache_invalidation::{self, CacheTag, TaggedCache};
+use crate::services::fx_service::{normalize_currency, FxService, DEFAULT_BASE_CURRENCY};
+use crate::services::media_service::MediaService;
+use crate::services::moderation_service::{self, ModerationService};
 use crate::services::realtime_service::RealtimeService;
 use chrono::{DateTime, Utc};
-use raffle_platform_shared::{ItemStatus, CreateItemRequest, ItemResponse, PaginatedResponse};
+use raffle_platform_shared::{ItemStatus, CreateItemRequest, ItemResponse, CursorPage, PaginatedResponse, UserRole};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use std::collections::HashMap;
+use std::time::Duration;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+/// How long a search page is served from the cache; item writes invalidate it sooner
+const ITEM_SEARCH_CACHE_TTL: Duration = Duration::from_secs(60);
+/// How long an item's detail is served from the cache; item writes invalidate it sooner
+const ITEM_DETAIL_CACHE_TTL: Duration = Duration::from_secs(300);
+
 /// Item management service handles all item-related operations
 #[derive(Clone)]
 pub struct ItemService {
     db_pool: PgPool,
+    read_pool: PgPool,
     realtime_service: Option<RealtimeService>,
+    media_service: Option<MediaService>,
+    fx_service: Option<FxService>,
+    moderation_service: Option<ModerationService>,
+    cache: TaggedCache,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,7 +45,8 @@ pub struct ItemSearchParams {
     pub seller_id: Option<Uuid>,
     pub sort_by: Option<String>, // "price_asc", "price_desc", "created_asc", "created_desc", "name"
     pub limit: Option<i64>,
-    pub offset: Option<i64>,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -84,24 +102,64 @@ impl ItemService {
     /// Create a new item service
     pub fn new(db_pool: PgPool) -> Self {
         Self { 
+            read_pool: db_pool.clone(),
             db_pool,
             realtime_service: None,
+            media_service: None,
+            fx_service: None,
+            moderation_service: None,
+            cache: TaggedCache::default(),
         }
     }
 
     /// Create a new item service with realtime updates
     pub fn with_realtime(db_pool: PgPool, realtime_service: RealtimeService) -> Self {
         Self { 
+            read_pool: db_pool.clone(),
             db_pool,
             realtime_service: Some(realtime_service),
+            media_service: None,
+            fx_service: None,
+            moderation_service: None,
+            cache: TaggedCache::default(),
         }
     }
 
+    /// Serve item images through the CDN with responsive variants
+    pub fn with_media_service(mut self, media_service: MediaService) -> Self {
+        self.media_service = Some(media_service);
+        self
+    }
+
+    /// Let sellers price items in the accepted currencies
+    pub fn with_fx_service(mut self, fx_service: FxService) -> Self {
+        self.fx_service = Some(fx_service);
+        self
+    }
+
+    /// Hold new items for review before they can be raffled
+    pub fn with_moderation_service(mut self, moderation_service: ModerationService) -> Self {
+        self.moderation_service = Some(moderation_service);
+        self
+    }
+
+    /// Run search, statistics and analytics queries on a read replica
+    pub fn with_read_pool(mut self, read_pool: PgPool) -> Self {
+        self.read_pool = read_pool;
+        self
+    }
+
+    /// Cache item details and search pages and invalidate them on item writes
+    pub fn with_cache(mut self, cache: TaggedCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
     /// Create a new item
     pub async fn create_item(
         &self,
         seller_id: Uuid,
-        request: CreateItemRequest,
+        mut request: CreateItemRequest,
     ) -> Result<ItemResponse, AppError> {
         // Validate seller exists and is active
         let seller = User::find_by_id(&self.db_pool, seller_id).await?
@@ -111,6 +169,13 @@ impl ItemService {
             return Err(AppError::Forbidden("User is not a seller".to_string()));
         }
 
+        // Sellers list once their profile is complete and their identity verified; staff aren't onboarded
+        if !matches!(seller.role, UserRole::Admin | UserRole::Operator) {
+            SellerOnboarding::load(&self.db_pool, seller_id)
+                .await?
+                .require(SellerFeature::ListItems)?;
+        }
+
         // Validate images
         if request.images.is_empty() {
             return Err(AppError::Validation("At least one image is required".to_string()));
@@ -121,11 +186,14 @@ impl ItemService {
             return Err(AppError::Validation("Retail price must be greater than cost of goods".to_string()));
         }
 
+        // Prices stay in the listing currency; purchases settle in the base currency
+        request.currency = Some(self.listing_currency(request.currency.as_deref()).await?);
+
         // Calculate listing fee (this would be based on seller's subscription tier)
         let listing_fee = self.calculate_listing_fee(&seller, &request).await?;
 
         // Create the item
-        let item = Item::create(
+        let mut item = Item::create(
             &self.db_pool,
             Some(seller_id),
             request,
@@ -133,6 +201,13 @@ impl ItemService {
             Some(listing_fee.fee_type),
         ).await?;
 
+        // New items wait for review; staff listings go live at once
+        if let Some(moderation_service) = &self.moderation_service {
+            let staff = matches!(seller.role, UserRole::Admin | UserRole::Operator);
+            moderation_service.submit(&item, staff).await?;
+            item.status = if staff { ItemStatus::Available } else { ItemStatus::PendingReview };
+        }
+
         // Log item creation
         self.log_item_activity(
             item.id,
@@ -141,6 +216,11 @@ impl ItemService {
             &format!("Item '{}' created", item.name),
         ).await?;
 
+        // Creating an item can complete the seller's onboarding step
+        self.cache
+            .invalidate(&[CacheTag::Item(item.id), CacheTag::ItemListings, CacheTag::Seller(seller_id)])
+            .await;
+
         // Broadcast item creation event
         if let Some(realtime_service) = &self.realtime_service {
             let _ = realtime_service.broadcast_event(
@@ -159,102 +239,69 @@ impl ItemService {
             item.id, seller_id, item.name
         );
 
-        Ok(item.to_response())
+        Ok(self.item_response(&item))
     }
 
     /// Get item by ID
     pub async fn get_item(&self, item_id: Uuid) -> Result<ItemResponse, AppError> {
-        let item = Item::find_by_id(&self.db_pool, item_id).await?
-            .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
+        let response = self.cache
+            .get_or_load(
+                &format!("item_detail:{}", item_id),
+                &[CacheTag::Item(item_id)],
+                ITEM_DETAIL_CACHE_TTL,
+                || self.load_item(item_id),
+            )
+            .await?;
 
         // Increment view count
         self.increment_item_views(item_id).await?;
 
-        Ok(item.to_response())
+        Ok(response)
     }
 
-    /// Search items with filters and pagination
+    async fn load_item(&self, item_id: Uuid) -> Result<ItemResponse, AppError> {
+        let item = Item::find_by_id(&self.db_pool, item_id).await?
+            .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
+
+        Ok(self.item_response(&item))
+    }
+
+    /// Search items with filters, newest first, one cursor page at a time
     pub async fn search_items(
         &self,
         params: ItemSearchParams,
-    ) -> Result<PaginatedResponse<ItemResponse>, AppError> {
-        let limit = params.limit.unwrap_or(20).min(100);
-        let offset = params.offset.unwrap_or(0);
-
-        // Build dynamic query
-        let mut query = "SELECT id, seller_id, name, description, images, retail_price, cost_of_goods, status, stock_quantity, listing_fee_applied, listing_fee_type, created_at, updated_at FROM items WHERE 1=1".to_string();
-        let mut conditions = Vec::new();
-        let mut param_count = 1;
-
-        // Add search condition
-        if let Some(search) = &params.search {
-            conditions.push(format!("AND (name ILIKE ${} OR description ILIKE ${})", param_count, param_count));
-            param_count += 1;
-        }
-
-        // Add category filter
-        if let Some(category) = &params.category {
-            conditions.push(format!("AND category = ${}", param_count));
-            param_count += 1;
-        }
-
-        // Add price range filters
-        if let Some(min_price) = params.min_price {
-            conditions.push(format!("AND retail_price >= ${}", param_count));
-            param_count += 1;
-        }
-
-        if let Some(max_price) = params.max_price {
-            conditions.push(format!("AND retail_price <= ${}", param_count));
-            param_count += 1;
-        }
-
-        // Add status filter
-        if let Some(status) = params.status {
-            conditions.push(format!("AND status = ${}", param_count));
-            param_count += 1;
-        }
-
-        // Add seller filter
-        if let Some(seller_id) = params.seller_id {
-            conditions.push(format!("AND seller_id = ${}", param_count));
-            param_count += 1;
-        }
-
-        // Add conditions to query
-        for condition in conditions {
-            query.push_str(&condition);
-        }
+    ) -> Result<CursorPage<ItemResponse>, AppError> {
+        let key = cache_invalidation::query_key("item_search", &params);
+
+        self.cache
+            .get_or_load(&key, &[CacheTag::ItemListings], ITEM_SEARCH_CACHE_TTL, || {
+                self.load_search_page(params)
+            })
+            .await
+    }
 
-        // Add sorting
-        let sort_clause = match params.sort_by.as_deref() {
-            Some("price_asc") => "ORDER BY retail_price ASC",
-            Some("price_desc") => "ORDER BY retail_price DESC",
-            Some("created_asc") => "ORDER BY created_at ASC",
-            Some("name") => "ORDER BY name ASC",
-            _ => "ORDER BY created_at DESC", // default
-        };
-        query.push_str(&format!(" {} LIMIT ${} OFFSET ${}", sort_clause, param_count, param_count + 1));
+    async fn load_search_page(&self, params: ItemSearchParams) -> Result<CursorPage<ItemResponse>, AppError> {
+        let pagination = CursorParams::new(params.limit, params.cursor.as_deref())?;
 
-        // For simplicity, we'll use the existing find_available method
-        // In a real implementation, you'd build and execute the dynamic query
+        // Only available items are searchable for now; other statuses need their own queries
         let items = if params.status == Some(ItemStatus::Available) || params.status.is_none() {
-            Item::find_available(&self.db_pool, limit, offset, params.search.as_deref()).await?
+            Item::find_available(
+                &self.read_pool,
+                pagination.fetch_limit(),
+                Cursor::bounds(pagination.after.as_ref()),
+                params.search.as_deref(),
+            )
+            .await?
         } else {
-            // For other statuses, we'd need additional methods
             Vec::new()
         };
 
-        let total = Item::count_available(&self.db_pool, params.search.as_deref()).await?;
+        let page = pagination.page(items, |item| Cursor::new(item.created_at, item.id));
 
-        let item_responses: Vec<ItemResponse> = items.into_iter().map(|item| item.to_response()).collect();
-
-        Ok(PaginatedResponse {
-            data: item_responses,
-            total,
-            limit,
-            offset,
-            has_more: offset + limit < total,
+        Ok(CursorPage {
+            data: page.data.iter().map(|item| self.item_response(item)).collect(),
+            next_cursor: page.next_cursor,
+            has_more: page.has_more,
         })
     }
 
@@ -296,6 +343,17 @@ impl ItemService {
             Item::update_stock_quantity(&self.db_pool, item_id, stock_quantity).await?;
         }
 
+        // Editing a rejected item sends it back for review
+        if item.status == ItemStatus::Rejected {
+            if let Some(moderation_service) = &self.moderation_service {
+                let edited = Item::find_by_id(&self.db_pool, item_id).await?
+                    .ok_or_else(|| AppError::Internal("Item disappeared after update".to_string()))?;
+                moderation_service.submit(&edited, false).await?;
+            }
+        }
+
+        self.cache.invalidate(&[CacheTag::Item(item_id), CacheTag::ItemListings]).await;
+
         // Log update
         self.log_item_activity(
             item_id,
@@ -310,7 +368,7 @@ impl ItemService {
         let updated_item = Item::find_by_id(&self.db_pool, item_id).await?
             .ok_or_else(|| AppError::Internal("Item disappeared after update".to_string()))?;
 
-        Ok(updated_item.to_response())
+        Ok(self.item_response(&updated_item))
     }
 
     /// Delete item (soft delete)
@@ -331,6 +389,9 @@ impl ItemService {
 
         // Soft delete
         Item::delete(&self.db_pool, item_id).await?;
+        self.cache
+            .invalidate(&[CacheTag::Item(item_id), CacheTag::ItemListings, CacheTag::Seller(seller_id)])
+            .await;
 
         // Log deletion
         self.log_item_activity(
@@ -358,7 +419,7 @@ impl ItemService {
         let items = Item::find_by_seller(&self.db_pool, seller_id, limit, offset).await?;
         let total = Item::count_by_seller(&self.db_pool, seller_id).await?;
 
-        let item_responses: Vec<ItemResponse> = items.into_iter().map(|item| item.to_response()).collect();
+        let item_responses: Vec<ItemResponse> = items.into_iter().map(|item| self.item_response(&item)).collect();
 
         Ok(PaginatedResponse {
             data: item_responses,
@@ -384,8 +445,11 @@ impl ItemService {
             return Err(AppError::Forbidden("Item does not belong to seller".to_string()));
         }
 
+        moderation_service::check_seller_status_change(item.status, status)?;
+
         // Update status
         Item::update_status(&self.db_pool, item_id, status).await?;
+        self.cache.invalidate(&[CacheTag::Item(item_id), CacheTag::ItemListings]).await;
 
         // Log status change
         self.log_item_activity(
@@ -424,6 +488,7 @@ impl ItemService {
 
         // Update stock
         Item::update_stock_quantity(&self.db_pool, item_id, quantity).await?;
+        self.cache.invalidate(&[CacheTag::Item(item_id), CacheTag::ItemListings]).await;
 
         // Broadcast stock change event
         if let Some(realtime_service) = &self.realtime_service {
@@ -460,21 +525,21 @@ impl ItemService {
         // For simplicity, we'll calculate basic statistics
         // In a real implementation, you'd use the dynamic query above
         let total_items = if let Some(seller_id) = seller_id {
-            Item::count_by_seller(&self.db_pool, seller_id).await?
+            Item::count_by_seller(&self.read_pool, seller_id).await?
         } else {
             sqlx::query_scalar!("SELECT COUNT(*) FROM items")
-                .fetch_one(&self.db_pool)
+                .fetch_one(&self.read_pool)
                 .await?
                 .unwrap_or(0)
         };
 
-        let available_items = Item::count_available(&self.db_pool, None).await?;
+        let available_items = Item::count_available(&self.read_pool, None).await?;
 
         // Calculate other statistics
         let total_value = sqlx::query_scalar!(
             "SELECT COALESCE(SUM(retail_price), 0) FROM items WHERE status = 'available'"
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(Decimal::ZERO);
 
@@ -499,7 +564,7 @@ impl ItemService {
     /// Get item analytics
     pub async fn get_item_analytics(&self, item_id: Uuid) -> Result<ItemAnalytics, AppError> {
         // Verify item exists
-        Item::find_by_id(&self.db_pool, item_id).await?
+        Item::find_by_id(&self.read_pool, item_id).await?
             .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
 
         // Get analytics data
@@ -541,8 +606,15 @@ impl ItemService {
             if item.seller_id != Some(seller_id) {
                 return Err(AppError::Forbidden(format!("Item {} does not belong to seller", item_id)));
             }
+
+            if let BulkOperation::UpdateStatus(status) = &operation.operation {
+                moderation_service::check_seller_status_change(item.status, *status)?;
+            }
         }
 
+        let mut tags: Vec<CacheTag> = operation.item_ids.iter().map(|item_id| CacheTag::Item(*item_id)).collect();
+        tags.extend([CacheTag::ItemListings, CacheTag::Seller(seller_id)]);
+
         let mut updated_count = 0;
 
         match operation.operation {
@@ -575,6 +647,10 @@ impl ItemService {
             }
         }
 
+        if updated_count > 0 {
+            self.cache.invalidate(&tags).await;
+        }
+
         info!(
             "Bulk operation completed by seller {}: {:?} on {} items",
             seller_id, operation.operation, updated_count
@@ -585,6 +661,27 @@ impl ItemService {
 
     // Private helper methods
 
+    async fn listing_currency(&self, requested: Option<&str>) -> Result<String, AppError> {
+        if let Some(fx_service) = &self.fx_service {
+            return fx_service.accepted_currency(requested).await;
+        }
+
+        match requested.map(normalize_currency).transpose()? {
+            Some(currency) if currency != DEFAULT_BASE_CURRENCY => {
+                Err(AppError::Validation(format!("{} is not an accepted currency", currency)))
+            }
+            _ => Ok(DEFAULT_BASE_CURRENCY.to_string()),
+        }
+    }
+
+    fn item_response(&self, item: &Item) -> ItemResponse {
+        let mut response = item.to_response();
+        if let Some(media_service) = &self.media_service {
+            media_service.apply_to_item(&mut response);
+        }
+        response
+    }
+
     async fn calculate_listing_fee(&self, _seller: &User, _request: &CreateItemRequest) -> Result<ListingFee, AppError> {
         // This would calculate the listing fee based on the seller's subscription tier
         // For now, return a default fee
@@ -631,7 +728,7 @@ impl ItemService {
             "SELECT COALESCE(SUM(view_count), 0) FROM item_views WHERE item_id = $1",
             item_id
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(0);
 
@@ -643,7 +740,7 @@ impl ItemService {
             "SELECT COUNT(DISTINCT user_id) FROM item_views WHERE item_id = $1 AND user_id IS NOT NULL",
             item_id
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(0);
 
@@ -666,7 +763,7 @@ impl ItemService {
             "SELECT COUNT(*) FROM raffles WHERE item_id = $1",
             item_id
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(0);
 
@@ -678,7 +775,7 @@ impl ItemService {
             "SELECT COALESCE(SUM(boxes_sold), 0) FROM raffles WHERE item_id = $1",
             item_id
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(0);
 
@@ -687,10 +784,10 @@ impl ItemService {
 
     async fn get_total_revenue_for_item(&self, item_id: Uuid) -> Result<Decimal, AppError> {
         let revenue = sqlx::query_scalar!(
-            "SELECT COALESCE(SUM(r.boxes_sold * r.box_price), 0) FROM raffles r WHERE r.item_id = $1",
+            "SELECT COALESCE(SUM(bp.purchase_price_in_credits), 0) FROM box_purchases bp JOIN raffles r ON r.id = bp.raffle_id WHERE r.item_id = $1",
             item_id
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(Decimal::ZERO);
 
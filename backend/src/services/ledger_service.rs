@@ -0,0 +1,452 @@
+use crate::error::AppError;
+use crate::models::ledger::{accounts, JournalEntry, LedgerAccountType, LedgerPosting};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::{FromRow, PgPool};
+use std::time::Duration;
+use tracing::{error, info};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// How often the scheduled invariant check runs
+const INVARIANT_INTERVAL_SECONDS: u64 = 3600;
+/// Offending entries and users listed per check
+const MAX_REPORTED: i64 = 50;
+const DEFAULT_POSTING_LIMIT: i64 = 100;
+const MAX_POSTING_LIMIT: i64 = 500;
+
+const POSTING_COLUMNS: &str = "p.id, p.journal_entry_id, a.code AS account_code, p.amount, p.user_id, p.seller_id, p.created_at";
+
+#[derive(Debug, Clone, FromRow)]
+struct AccountTotals {
+    code: String,
+    name: String,
+    account_type: LedgerAccountType,
+    debits: Decimal,
+    credits: Decimal,
+    posting_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LedgerAccountBalance {
+    pub code: String,
+    pub name: String,
+    pub account_type: LedgerAccountType,
+    pub debits: Decimal,
+    pub credits: Decimal,
+    /// Debits less credits for assets and expenses, credits less debits for everything else
+    pub balance: Decimal,
+    pub posting_count: i64,
+}
+
+impl From<AccountTotals> for LedgerAccountBalance {
+    fn from(totals: AccountTotals) -> Self {
+        Self {
+            balance: totals.account_type.normal_balance(totals.debits - totals.credits),
+            code: totals.code,
+            name: totals.name,
+            account_type: totals.account_type,
+            debits: totals.debits,
+            credits: totals.credits,
+            posting_count: totals.posting_count,
+        }
+    }
+}
+
+/// A posting with the entry it belongs to
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct LedgerPostingDetail {
+    pub id: i64,
+    pub journal_entry_id: Uuid,
+    pub source_type: String,
+    pub source_id: Option<Uuid>,
+    pub description: String,
+    pub occurred_at: DateTime<Utc>,
+    /// Debits are positive, credits negative
+    pub amount: Decimal,
+    pub user_id: Option<Uuid>,
+    pub seller_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LedgerPostingPage {
+    pub postings: Vec<LedgerPostingDetail>,
+    /// Pass as `before` for the next page; None on the last page
+    pub next_before: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LedgerPostingQuery {
+    pub user_id: Option<Uuid>,
+    /// Seller account id
+    pub seller_id: Option<Uuid>,
+    /// Only postings older than this posting id
+    pub before: Option<i64>,
+    /// Defaults to 100, at most 500
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JournalEntryDetail {
+    pub entry: JournalEntry,
+    pub postings: Vec<LedgerPosting>,
+}
+
+/// A user whose credits in the ledger differ from the credits they hold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct UserCreditMismatch {
+    pub user_id: Uuid,
+    pub ledger_balance: Decimal,
+    pub outstanding_credits: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LedgerInvariantReport {
+    /// Entries whose postings don't sum to zero; the database refuses these, so any here is a bug
+    pub unbalanced_entries: Vec<Uuid>,
+    /// Sum of every posting; zero when the books balance
+    pub trial_balance: Decimal,
+    /// Balance of the customer credit liability account
+    pub credit_liability: Decimal,
+    /// Unused, unexpired credits users hold
+    pub outstanding_credits: Decimal,
+    /// Largest per-user differences first
+    pub user_mismatches: Vec<UserCreditMismatch>,
+}
+
+impl LedgerInvariantReport {
+    pub fn passed(&self) -> bool {
+        self.unbalanced_entries.is_empty()
+            && self.trial_balance.is_zero()
+            && self.credit_liability == self.outstanding_credits
+            && self.user_mismatches.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LedgerInvariantRun {
+    pub id: Uuid,
+    pub passed: bool,
+    pub report: LedgerInvariantReport,
+    /// Admin who asked for the check; None for scheduled runs
+    pub triggered_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+type InvariantRunRow = (Uuid, bool, Json<LedgerInvariantReport>, Option<Uuid>, DateTime<Utc>);
+
+impl From<InvariantRunRow> for LedgerInvariantRun {
+    fn from((id, passed, report, triggered_by, created_at): InvariantRunRow) -> Self {
+        Self { id, passed, report: report.0, triggered_by, created_at }
+    }
+}
+
+/// Ledger service lets finance drill from account balances down to single postings, and
+/// checks that the books balance and agree with the credits users actually hold.
+#[derive(Clone)]
+pub struct LedgerService {
+    db_pool: PgPool,
+    read_pool: PgPool,
+}
+
+impl LedgerService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { read_pool: db_pool.clone(), db_pool }
+    }
+
+    /// Run balance and drill-down queries on a read replica
+    pub fn with_read_pool(mut self, read_pool: PgPool) -> Self {
+        self.read_pool = read_pool;
+        self
+    }
+
+    /// Start the scheduled invariant check
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(INVARIANT_INTERVAL_SECONDS));
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.check_invariants(None).await {
+                    error!("Scheduled ledger invariant check failed: {}", e);
+                }
+            }
+        });
+
+        info!("Ledger invariant checks started");
+    }
+
+    /// Every account with its debit, credit and balance totals
+    pub async fn get_account_balances(&self) -> Result<Vec<LedgerAccountBalance>, AppError> {
+        let totals = sqlx::query_as::<_, AccountTotals>(
+            r#"
+            SELECT a.code, a.name, a.account_type,
+                   COALESCE(SUM(p.amount) FILTER (WHERE p.amount > 0), 0) AS debits,
+                   COALESCE(-SUM(p.amount) FILTER (WHERE p.amount < 0), 0) AS credits,
+                   COUNT(p.id) AS posting_count
+            FROM ledger_accounts a
+            LEFT JOIN ledger_postings p ON p.account_id = a.id
+            GROUP BY a.id
+            ORDER BY a.code
+            "#,
+        )
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        Ok(totals.into_iter().map(LedgerAccountBalance::from).collect())
+    }
+
+    /// Postings to an account, newest first
+    pub async fn get_account_postings(
+        &self,
+        code: &str,
+        query: &LedgerPostingQuery,
+    ) -> Result<LedgerPostingPage, AppError> {
+        let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM ledger_accounts WHERE code = $1)")
+            .bind(code)
+            .fetch_one(&self.read_pool)
+            .await?;
+        if !exists {
+            return Err(AppError::NotFound(format!("Ledger account {} not found", code)));
+        }
+
+        let limit = query.limit.unwrap_or(DEFAULT_POSTING_LIMIT).clamp(1, MAX_POSTING_LIMIT);
+
+        let mut postings = sqlx::query_as::<_, LedgerPostingDetail>(
+            r#"
+            SELECT p.id, p.journal_entry_id, e.source_type, e.source_id, e.description, e.occurred_at,
+                   p.amount, p.user_id, p.seller_id
+            FROM ledger_postings p
+            JOIN ledger_accounts a ON a.id = p.account_id
+            JOIN journal_entries e ON e.id = p.journal_entry_id
+            WHERE a.code = $1
+            AND ($2::uuid IS NULL OR p.user_id = $2)
+            AND ($3::uuid IS NULL OR p.seller_id = $3)
+            AND ($4::bigint IS NULL OR p.id < $4)
+            ORDER BY p.id DESC
+            LIMIT $5
+            "#,
+        )
+        .bind(code)
+        .bind(query.user_id)
+        .bind(query.seller_id)
+        .bind(query.before)
+        .bind(limit + 1)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        let next_before = if postings.len() as i64 > limit {
+            postings.truncate(limit as usize);
+            postings.last().map(|p| p.id)
+        } else {
+            None
+        };
+
+        Ok(LedgerPostingPage { postings, next_before })
+    }
+
+    /// A journal entry with all of its postings
+    pub async fn get_entry(&self, entry_id: Uuid) -> Result<JournalEntryDetail, AppError> {
+        let entry = sqlx::query_as::<_, JournalEntry>(
+            "SELECT id, source_type, source_id, description, occurred_at, created_at FROM journal_entries WHERE id = $1",
+        )
+        .bind(entry_id)
+        .fetch_optional(&self.read_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Journal entry not found".to_string()))?;
+
+        let postings = sqlx::query_as::<_, LedgerPosting>(&format!(
+            "SELECT {} FROM ledger_postings p JOIN ledger_accounts a ON a.id = p.account_id \
+             WHERE p.journal_entry_id = $1 ORDER BY p.id",
+            POSTING_COLUMNS
+        ))
+        .bind(entry_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        Ok(JournalEntryDetail { entry, postings })
+    }
+
+    /// The most recent invariant check, if any has run
+    pub async fn latest_invariant_run(&self) -> Result<Option<LedgerInvariantRun>, AppError> {
+        let row = sqlx::query_as::<_, InvariantRunRow>(
+            "SELECT id, passed, report, triggered_by, created_at FROM ledger_invariant_runs ORDER BY created_at DESC LIMIT 1",
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(row.map(LedgerInvariantRun::from))
+    }
+
+    /// Check the ledger against itself and against user credits, and record the outcome.
+    ///
+    /// Everything is read from one snapshot. Credit changes are posted just after they are
+    /// written, so a user caught in between can show up once; a mismatch that persists is real.
+    pub async fn check_invariants(&self, triggered_by: Option<Uuid>) -> Result<LedgerInvariantRun, AppError> {
+        let mut tx = self.db_pool.begin().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY")
+            .execute(&mut *tx)
+            .await?;
+
+        let unbalanced_entries = sqlx::query_scalar::<_, Uuid>(
+            "SELECT journal_entry_id FROM ledger_postings GROUP BY journal_entry_id HAVING SUM(amount) <> 0 LIMIT $1",
+        )
+        .bind(MAX_REPORTED)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let trial_balance = sqlx::query_scalar::<_, Decimal>("SELECT COALESCE(SUM(amount), 0) FROM ledger_postings")
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let credit_liability = sqlx::query_scalar::<_, Decimal>(
+            r#"
+            SELECT COALESCE(-SUM(p.amount), 0)
+            FROM ledger_postings p
+            JOIN ledger_accounts a ON a.id = p.account_id
+            WHERE a.code = $1
+            "#,
+        )
+        .bind(accounts::CUSTOMER_CREDITS)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let outstanding_credits = sqlx::query_scalar::<_, Decimal>(
+            "SELECT COALESCE(SUM(amount), 0) FROM user_credits WHERE is_used = false AND expired_at IS NULL",
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let user_mismatches = sqlx::query_as::<_, UserCreditMismatch>(
+            r#"
+            WITH ledger AS (
+                SELECT p.user_id, -SUM(p.amount) AS balance
+                FROM ledger_postings p
+                JOIN ledger_accounts a ON a.id = p.account_id
+                WHERE a.code = $1 AND p.user_id IS NOT NULL
+                GROUP BY p.user_id
+            ), credits AS (
+                SELECT user_id, SUM(amount) AS balance
+                FROM user_credits
+                WHERE is_used = false AND expired_at IS NULL
+                GROUP BY user_id
+            )
+            SELECT COALESCE(l.user_id, c.user_id) AS user_id,
+                   COALESCE(l.balance, 0) AS ledger_balance,
+                   COALESCE(c.balance, 0) AS outstanding_credits
+            FROM ledger l
+            FULL OUTER JOIN credits c ON c.user_id = l.user_id
+            WHERE COALESCE(l.balance, 0) <> COALESCE(c.balance, 0)
+            ORDER BY ABS(COALESCE(l.balance, 0) - COALESCE(c.balance, 0)) DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(accounts::CUSTOMER_CREDITS)
+        .bind(MAX_REPORTED)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        let report = LedgerInvariantReport {
+            unbalanced_entries,
+            trial_balance,
+            credit_liability,
+            outstanding_credits,
+            user_mismatches,
+        };
+        let passed = report.passed();
+
+        if !passed {
+            error!(
+                "Ledger invariants broken: {} unbalanced entries, trial balance {}, credit liability {} vs {} outstanding, {} users differ",
+                report.unbalanced_entries.len(),
+                report.trial_balance,
+                report.credit_liability,
+                report.outstanding_credits,
+                report.user_mismatches.len()
+            );
+        }
+
+        let row = sqlx::query_as::<_, InvariantRunRow>(
+            r#"
+            INSERT INTO ledger_invariant_runs (passed, report, triggered_by)
+            VALUES ($1, $2, $3)
+            RETURNING id, passed, report, triggered_by, created_at
+            "#,
+        )
+        .bind(passed)
+        .bind(Json(&report))
+        .bind(triggered_by)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(LedgerInvariantRun::from(row))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> LedgerInvariantReport {
+        LedgerInvariantReport {
+            unbalanced_entries: Vec::new(),
+            trial_balance: Decimal::ZERO,
+            credit_liability: Decimal::from(120),
+            outstanding_credits: Decimal::from(120),
+            user_mismatches: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_clean_report_passes() {
+        assert!(report().passed());
+    }
+
+    #[test]
+    fn test_any_broken_invariant_fails() {
+        let mut unbalanced = report();
+        unbalanced.trial_balance = Decimal::new(1, 2);
+        assert!(!unbalanced.passed());
+
+        let mut drifted = report();
+        drifted.outstanding_credits = Decimal::from(100);
+        assert!(!drifted.passed());
+
+        let mut mismatched = report();
+        mismatched.user_mismatches.push(UserCreditMismatch {
+            user_id: Uuid::nil(),
+            ledger_balance: Decimal::from(20),
+            outstanding_credits: Decimal::ZERO,
+        });
+        assert!(!mismatched.passed());
+    }
+
+    #[test]
+    fn test_account_balance_uses_normal_side() {
+        let liability = LedgerAccountBalance::from(AccountTotals {
+            code: accounts::CUSTOMER_CREDITS.to_string(),
+            name: "Customer Credit Liability".to_string(),
+            account_type: LedgerAccountType::Liability,
+            debits: Decimal::from(30),
+            credits: Decimal::from(100),
+            posting_count: 4,
+        });
+        assert_eq!(liability.balance, Decimal::from(70));
+
+        let clearing = LedgerAccountBalance::from(AccountTotals {
+            code: accounts::STRIPE_CLEARING.to_string(),
+            name: "Stripe Clearing".to_string(),
+            account_type: LedgerAccountType::Asset,
+            debits: Decimal::from(100),
+            credits: Decimal::from(20),
+            posting_count: 3,
+        });
+        assert_eq!(clearing.balance, Decimal::from(80));
+    }
+}
@@ -0,0 +1,201 @@
+use crate::error::AppError;
+use crate::models::listing_snapshot::{ListingSnapshot, SnapshotEntity};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const DEFAULT_HISTORY_LIMIT: i64 = 50;
+const MAX_HISTORY_LIMIT: i64 = 200;
+
+/// The listing versions a box purchase was made against
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PurchaseSnapshots {
+    pub purchase_id: Uuid,
+    pub raffle_id: Uuid,
+    pub purchased_at: DateTime<Utc>,
+    /// None for purchases made before snapshots were captured
+    pub item: Option<ListingSnapshot>,
+    pub raffle: Option<ListingSnapshot>,
+}
+
+#[derive(Debug, FromRow)]
+struct PurchaseParties {
+    raffle_id: Uuid,
+    buyer_id: Uuid,
+    seller_user_id: Option<Uuid>,
+    item_snapshot_id: Option<Uuid>,
+    raffle_snapshot_id: Option<Uuid>,
+    created_at: DateTime<Utc>,
+}
+
+/// Listing snapshot service answers "what did the listing say at the time?" for disputes.
+///
+/// Snapshots themselves are written by database triggers on items and raffles, so every write
+/// path is covered; this service only reads them back.
+#[derive(Clone)]
+pub struct ListingSnapshotService {
+    db_pool: PgPool,
+}
+
+impl ListingSnapshotService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+
+    /// The version of a listing in effect at `at`, or the current one without a timestamp
+    pub async fn effective_at(
+        &self,
+        entity_type: SnapshotEntity,
+        entity_id: Uuid,
+        at: Option<DateTime<Utc>>,
+    ) -> Result<ListingSnapshot, AppError> {
+        ListingSnapshot::find_effective_at(&self.db_pool, entity_type, entity_id, at.unwrap_or_else(Utc::now))
+            .await?
+            .ok_or_else(|| AppError::NotFound("No snapshot of this listing at that time".to_string()))
+    }
+
+    /// Versions of a listing, newest first
+    pub async fn history(
+        &self,
+        entity_type: SnapshotEntity,
+        entity_id: Uuid,
+        limit: Option<i64>,
+    ) -> Result<Vec<ListingSnapshot>, AppError> {
+        let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, MAX_HISTORY_LIMIT);
+        ListingSnapshot::list_versions(&self.db_pool, entity_type, entity_id, limit).await
+    }
+
+    /// The snapshots attached to a purchase; visible to the buyer, the raffle's seller and admins
+    pub async fn purchase_snapshots(
+        &self,
+        user_id: Uuid,
+        is_admin: bool,
+        raffle_id: Uuid,
+        purchase_id: Uuid,
+    ) -> Result<PurchaseSnapshots, AppError> {
+        let parties = sqlx::query_as::<_, PurchaseParties>(
+            r#"
+            SELECT bp.raffle_id, bp.user_id AS buyer_id, s.user_id AS seller_user_id,
+                   bp.item_snapshot_id, bp.raffle_snapshot_id, bp.created_at
+            FROM box_purchases bp
+            JOIN raffles r ON r.id = bp.raffle_id
+            JOIN items i ON i.id = r.item_id
+            LEFT JOIN sellers s ON s.id = i.seller_id
+            WHERE bp.id = $1 AND bp.raffle_id = $2
+            "#,
+        )
+        .bind(purchase_id)
+        .bind(raffle_id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Purchase not found".to_string()))?;
+
+        if !is_admin && parties.buyer_id != user_id && parties.seller_user_id != Some(user_id) {
+            return Err(AppError::NotFound("Purchase not found".to_string()));
+        }
+
+        let item = match parties.item_snapshot_id {
+            Some(id) => ListingSnapshot::find_by_id(&self.db_pool, id).await?,
+            None => None,
+        };
+        let raffle = match parties.raffle_snapshot_id {
+            Some(id) => ListingSnapshot::find_by_id(&self.db_pool, id).await?,
+            None => None,
+        };
+
+        Ok(PurchaseSnapshots {
+            purchase_id,
+            raffle_id: parties.raffle_id,
+            purchased_at: parties.created_at,
+            item,
+            raffle,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::tests::{create_test_item, create_test_raffle, create_test_seller, create_test_user, setup_test_db};
+    use crate::models::BoxPurchase;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    fn decimal_field(snapshot: &ListingSnapshot, field: &str) -> Decimal {
+        Decimal::from_str(&snapshot.data[field].to_string()).expect("snapshot field is not a number")
+    }
+
+    async fn rename_item(pool: &PgPool, item_id: Uuid, name: &str) {
+        sqlx::query("UPDATE items SET name = $2, updated_at = NOW() WHERE id = $1")
+            .bind(item_id)
+            .bind(name)
+            .execute(pool)
+            .await
+            .expect("Failed to rename item");
+    }
+
+    #[tokio::test]
+    async fn test_purchase_keeps_the_listing_as_it_was_when_bought() {
+        let pool = setup_test_db().await;
+        let service = ListingSnapshotService::new(pool.clone());
+
+        let seller_user = create_test_user(&pool, &format!("seller_{}@example.com", Uuid::new_v4())).await;
+        let seller = create_test_seller(&pool, seller_user.id).await;
+        let item = create_test_item(&pool, Some(seller.id)).await;
+        let raffle = create_test_raffle(&pool, item.id).await;
+        let buyer = create_test_user(&pool, &format!("buyer_{}@example.com", Uuid::new_v4())).await;
+
+        // Creating the listing captures its first version
+        let first = service.effective_at(SnapshotEntity::Item, item.id, None).await.unwrap();
+        assert_eq!(first.version, 1);
+        assert_eq!(first.data["name"], item.name.as_str());
+        assert_eq!(decimal_field(&first, "retail_price"), item.retail_price);
+
+        let raffle_snapshot = service.effective_at(SnapshotEntity::Raffle, raffle.id, None).await.unwrap();
+        assert_eq!(raffle_snapshot.data["total_boxes"], raffle.total_boxes);
+        assert_eq!(decimal_field(&raffle_snapshot, "box_price"), raffle.box_price);
+
+        rename_item(&pool, item.id, "Renamed Before Purchase").await;
+
+        let purchase = BoxPurchase::create(&pool, raffle.id, buyer.id, 1, raffle.box_price, None)
+            .await
+            .expect("Failed to create box purchase");
+
+        // Changes after the purchase mint a new version but don't move the purchase's link
+        rename_item(&pool, item.id, "Renamed After Purchase").await;
+
+        let snapshots = service
+            .purchase_snapshots(buyer.id, false, raffle.id, purchase.id)
+            .await
+            .unwrap();
+        let bought = snapshots.item.expect("purchase has no item snapshot");
+        assert_eq!(bought.version, 2);
+        assert_eq!(bought.data["name"], "Renamed Before Purchase");
+        assert_eq!(snapshots.raffle.expect("purchase has no raffle snapshot").id, raffle_snapshot.id);
+
+        let history = service.history(SnapshotEntity::Item, item.id, None).await.unwrap();
+        let names: Vec<_> = history.iter().map(|s| s.data["name"].as_str().unwrap().to_string()).collect();
+        assert_eq!(names, vec!["Renamed After Purchase", "Renamed Before Purchase", item.name.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn test_counter_updates_do_not_mint_a_version() {
+        let pool = setup_test_db().await;
+        let service = ListingSnapshotService::new(pool.clone());
+
+        let item = create_test_item(&pool, None).await;
+        let raffle = create_test_raffle(&pool, item.id).await;
+
+        sqlx::query("UPDATE raffles SET boxes_sold = boxes_sold + 1 WHERE id = $1")
+            .bind(raffle.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let history = service.history(SnapshotEntity::Raffle, raffle.id, None).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].data["max_boxes_per_user"], serde_json::Value::Null);
+    }
+}
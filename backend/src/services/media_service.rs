@@ -0,0 +1,239 @@
+use crate::config::AppConfig;
+use chrono::{DateTime, TimeZone, Utc};
+use hmac::{Hmac, Mac};
+use raffle_platform_shared::{ImageVariants, ItemResponse, RaffleResponse};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Default lifetime of signed media URLs
+const DEFAULT_URL_TTL_SECONDS: i64 = 3600;
+
+/// Responsive variants rendered by the CDN's image transformer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageVariant {
+    Thumb,
+    Medium,
+    Large,
+    Webp,
+}
+
+impl ImageVariant {
+    /// Transformation parameters understood by the CDN
+    fn transform_params(&self) -> &'static str {
+        match self {
+            ImageVariant::Thumb => "w=160&h=160&fit=cover",
+            ImageVariant::Medium => "w=640&fit=scale-down",
+            ImageVariant::Large => "w=1280&fit=scale-down",
+            ImageVariant::Webp => "w=1280&fit=scale-down&format=webp",
+        }
+    }
+}
+
+/// Media service rewrites stored image references to signed CDN URLs with size variants
+#[derive(Clone)]
+pub struct MediaService {
+    cdn_base_url: Option<String>,
+    signing_key: Option<String>,
+    origin_url: Option<String>,
+    url_ttl_seconds: i64,
+}
+
+impl MediaService {
+    /// Create a new media service; without a CDN configured images are served from origin unchanged
+    pub fn new(
+        cdn_base_url: Option<String>,
+        signing_key: Option<String>,
+        origin_url: Option<String>,
+        url_ttl_seconds: Option<i64>,
+    ) -> Self {
+        Self {
+            cdn_base_url: cdn_base_url.map(|u| u.trim_end_matches('/').to_string()),
+            signing_key,
+            origin_url: origin_url.map(|u| u.trim_end_matches('/').to_string()),
+            url_ttl_seconds: url_ttl_seconds
+                .filter(|ttl| *ttl > 0)
+                .unwrap_or(DEFAULT_URL_TTL_SECONDS),
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::new(
+            config.cdn_base_url.clone(),
+            config.cdn_signing_key.clone(),
+            config.media_origin_url.clone(),
+            config.cdn_url_ttl_seconds,
+        )
+    }
+
+    /// Fill in CDN variants for every image on an item
+    pub fn apply_to_item(&self, item: &mut ItemResponse) {
+        let now = Utc::now();
+        item.image_variants = item.images.iter().map(|image| self.variants_at(image, now)).collect();
+    }
+
+    /// Fill in CDN variants for the raffle's item, if it was loaded
+    pub fn apply_to_raffle(&self, raffle: &mut RaffleResponse) {
+        if let Some(item) = raffle.item.as_mut() {
+            self.apply_to_item(item);
+        }
+    }
+
+    /// Build all variants for a stored image reference
+    pub fn variants(&self, image: &str) -> ImageVariants {
+        self.variants_at(image, Utc::now())
+    }
+
+    /// Signed CDN URL for a stored image reference with optional transformation parameters
+    pub fn signed_url(&self, image: &str, params: Option<&str>) -> String {
+        self.signed_url_at(image, params, Utc::now()).0
+    }
+
+    // Private helper methods
+
+    fn variants_at(&self, image: &str, now: DateTime<Utc>) -> ImageVariants {
+        let (original, expires_at) = self.signed_url_at(image, None, now);
+        let variant = |v: ImageVariant| self.signed_url_at(image, Some(v.transform_params()), now).0;
+
+        ImageVariants {
+            original,
+            thumb: variant(ImageVariant::Thumb),
+            medium: variant(ImageVariant::Medium),
+            large: variant(ImageVariant::Large),
+            webp: variant(ImageVariant::Webp),
+            expires_at,
+        }
+    }
+
+    fn signed_url_at(
+        &self,
+        image: &str,
+        params: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> (String, Option<DateTime<Utc>>) {
+        let (cdn_base_url, path) = match (&self.cdn_base_url, self.cdn_path(image)) {
+            (Some(base), Some(path)) => (base, path),
+            // No CDN, or an external image we don't host
+            _ => return (image.to_string(), None),
+        };
+
+        let mut query = params.unwrap_or_default().to_string();
+
+        let Some(signing_key) = &self.signing_key else {
+            let url = if query.is_empty() {
+                format!("{}{}", cdn_base_url, path)
+            } else {
+                format!("{}{}?{}", cdn_base_url, path, query)
+            };
+            return (url, None);
+        };
+
+        let expires = signature_expiry(now.timestamp(), self.url_ttl_seconds);
+        if !query.is_empty() {
+            query.push('&');
+        }
+        query.push_str(&format!("exp={}", expires));
+
+        let signature = sign(signing_key, &path, &query);
+
+        (
+            format!("{}{}?{}&sig={}", cdn_base_url, path, query, signature),
+            Utc.timestamp_opt(expires, 0).single(),
+        )
+    }
+
+    /// Path of an image on the CDN; relative references and origin URLs are ours to serve
+    fn cdn_path(&self, image: &str) -> Option<String> {
+        let path = if let Some(origin) = self.origin_url.as_deref().filter(|o| image.starts_with(*o)) {
+            &image[origin.len()..]
+        } else if image.contains("://") {
+            return None;
+        } else {
+            image
+        };
+
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        if path.is_empty() {
+            return None;
+        }
+
+        Some(if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("/{}", path)
+        })
+    }
+}
+
+/// Expiry aligned to TTL buckets so repeated responses share one cacheable URL,
+/// while every URL stays valid for at least one full TTL
+fn signature_expiry(now: i64, ttl: i64) -> i64 {
+    (now / ttl + 2) * ttl
+}
+
+/// HMAC-SHA256 over the path and query the edge will see, excluding the signature itself
+fn sign(key: &str, path: &str, query: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(path.as_bytes());
+    mac.update(b"?");
+    mac.update(query.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> MediaService {
+        MediaService::new(
+            Some("https://cdn.example.com/".to_string()),
+            Some("secret".to_string()),
+            Some("https://origin.example.com".to_string()),
+            Some(600),
+        )
+    }
+
+    #[test]
+    fn test_unconfigured_service_passes_images_through() {
+        let media = MediaService::new(None, None, None, None);
+        let variants = media.variants("items/abc.jpg");
+
+        assert_eq!(variants.original, "items/abc.jpg");
+        assert_eq!(variants.thumb, "items/abc.jpg");
+        assert!(variants.expires_at.is_none());
+    }
+
+    #[test]
+    fn test_external_images_are_not_rewritten() {
+        let url = "https://images.other-site.com/a.png";
+        assert_eq!(service().signed_url(url, None), url);
+    }
+
+    #[test]
+    fn test_signed_variant_urls() {
+        let media = service();
+        let now = Utc.timestamp_opt(1_000, 0).unwrap();
+        let variants = media.variants_at("https://origin.example.com/items/abc.jpg?v=2", now);
+
+        let expires = signature_expiry(1_000, 600);
+        assert_eq!(expires, 1_800);
+        assert_eq!(variants.expires_at, Utc.timestamp_opt(expires, 0).single());
+
+        let expected_sig = sign("secret", "/items/abc.jpg", "w=160&h=160&fit=cover&exp=1800");
+        assert_eq!(
+            variants.thumb,
+            format!("https://cdn.example.com/items/abc.jpg?w=160&h=160&fit=cover&exp=1800&sig={}", expected_sig)
+        );
+        assert!(variants.webp.contains("format=webp"));
+        assert!(variants.original.starts_with("https://cdn.example.com/items/abc.jpg?exp=1800&sig="));
+    }
+
+    #[test]
+    fn test_signature_expiry_gives_at_least_one_ttl() {
+        for now in [0, 1, 599, 600, 1_199] {
+            let expires = signature_expiry(now, 600);
+            assert!(expires - now >= 600);
+            assert_eq!(expires % 600, 0);
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use crate::database::Database;
+use crate::error::AppError;
+use crate::services::blockchain_service::BlockchainService;
+use crate::services::cache_invalidation::TaggedCache;
+use crate::services::cache_service::CacheService;
+use prometheus::{
+    Encoder, Gauge, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::time::Duration;
+use tracing::warn;
+
+/// Chain head lookups during a scrape give up after this, well inside Prometheus' scrape timeout
+const CHAIN_HEAD_TIMEOUT_SECS: u64 = 2;
+/// Upper bounds of the HTTP latency histogram, in seconds
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+/// Route label for requests that matched no route, so unknown paths can't blow up cardinality
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Metrics service exposes request, pool, cache and blockchain metrics in Prometheus format.
+///
+/// HTTP metrics are recorded as requests complete; the rest are sampled on each scrape.
+#[derive(Clone)]
+pub struct MetricsService {
+    registry: Registry,
+    database: Database,
+    cache_service: Option<CacheService>,
+    tagged_cache: Option<TaggedCache>,
+    blockchain_service: Option<BlockchainService>,
+    http_requests_total: IntCounterVec,
+    http_request_duration_seconds: HistogramVec,
+    db_pool_connections: IntGaugeVec,
+    db_pool_max_connections: IntGauge,
+    cache_hits: IntGauge,
+    cache_misses: IntGauge,
+    cache_errors: IntGauge,
+    cache_hit_ratio: Gauge,
+    cache_invalidations: IntGaugeVec,
+    blockchain_last_processed_block: IntGauge,
+    blockchain_event_lag_blocks: IntGauge,
+    blockchain_connection_errors_total: IntCounter,
+    backup_verification_last_run_passed: IntGauge,
+    backup_verification_last_success_timestamp_seconds: IntGauge,
+    backup_verified_taken_timestamp_seconds: IntGauge,
+    raffle_draws_pending: IntGauge,
+    raffle_draws_overdue: IntGauge,
+    login_lockouts_active: IntGaugeVec,
+}
+
+impl MetricsService {
+    pub fn new(database: Database) -> Result<Self, AppError> {
+        let registry = Registry::new();
+
+        let http_requests_total = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by method, route and status"),
+            &["method", "route", "status"],
+        )
+        .map_err(metrics_error)?;
+        let http_request_duration_seconds = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by method and route")
+                .buckets(LATENCY_BUCKETS.to_vec()),
+            &["method", "route"],
+        )
+        .map_err(metrics_error)?;
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state"),
+            &["state"],
+        )
+        .map_err(metrics_error)?;
+        let db_pool_max_connections =
+            IntGauge::new("db_pool_max_connections", "Database pool size limit").map_err(metrics_error)?;
+        let cache_hits = IntGauge::new("cache_hits", "Cache hits since the last metrics reset").map_err(metrics_error)?;
+        let cache_misses =
+            IntGauge::new("cache_misses", "Cache misses since the last metrics reset").map_err(metrics_error)?;
+        let cache_errors =
+            IntGauge::new("cache_errors", "Cache errors since the last metrics reset").map_err(metrics_error)?;
+        let cache_hit_ratio =
+            Gauge::new("cache_hit_ratio", "Share of cache reads that hit, 0 to 1").map_err(metrics_error)?;
+        let cache_invalidations = IntGaugeVec::new(
+            Opts::new("cache_invalidations", "Cache tag invalidations since startup by tag kind and origin"),
+            &["tag", "origin"],
+        )
+        .map_err(metrics_error)?;
+        let blockchain_last_processed_block = IntGauge::new(
+            "blockchain_last_processed_block",
+            "Last block the event processor has handled",
+        )
+        .map_err(metrics_error)?;
+        let blockchain_event_lag_blocks = IntGauge::new(
+            "blockchain_event_lag_blocks",
+            "Blocks between the chain head and the last processed block",
+        )
+        .map_err(metrics_error)?;
+        let blockchain_connection_errors_total = IntCounter::new(
+            "blockchain_connection_errors_total",
+            "Failed chain head lookups while collecting metrics",
+        )
+        .map_err(metrics_error)?;
+        let backup_verification_last_run_passed = IntGauge::new(
+            "backup_verification_last_run_passed",
+            "1 if the latest finished restore drill passed, 0 if it failed, -1 before the first drill",
+        )
+        .map_err(metrics_error)?;
+        let backup_verification_last_success_timestamp_seconds = IntGauge::new(
+            "backup_verification_last_success_timestamp_seconds",
+            "When the latest passing restore drill finished",
+        )
+        .map_err(metrics_error)?;
+        let backup_verified_taken_timestamp_seconds = IntGauge::new(
+            "backup_verified_taken_timestamp_seconds",
+            "When the newest backup known to restore was taken",
+        )
+        .map_err(metrics_error)?;
+
+        registry.register(Box::new(http_requests_total.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(http_request_duration_seconds.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(db_pool_connections.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(db_pool_max_connections.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(cache_hits.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(cache_misses.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(cache_errors.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(cache_hit_ratio.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(cache_invalidations.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(blockchain_last_processed_block.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(blockchain_event_lag_blocks.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(blockchain_connection_errors_total.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(backup_verification_last_run_passed.clone())).map_err(metrics_error)?;
+        let raffle_draws_pending = IntGauge::new(
+            "raffle_draws_pending",
+            "Full raffles on chain waiting for their draw",
+        )
+        .map_err(metrics_error)?;
+        let raffle_draws_overdue = IntGauge::new(
+            "raffle_draws_overdue",
+            "Full raffles waiting for their draw longer than raffle_draw_alert_minutes",
+        )
+        .map_err(metrics_error)?;
+        registry
+            .register(Box::new(backup_verification_last_success_timestamp_seconds.clone()))
+            .map_err(metrics_error)?;
+        registry.register(Box::new(backup_verified_taken_timestamp_seconds.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(raffle_draws_pending.clone())).map_err(metrics_error)?;
+        registry.register(Box::new(raffle_draws_overdue.clone())).map_err(metrics_error)?;
+        let login_lockouts_active = IntGaugeVec::new(
+            Opts::new("login_lockouts_active", "Accounts and IP addresses locked out after failed logins, by scope"),
+            &["scope"],
+        )
+        .map_err(metrics_error)?;
+        registry.register(Box::new(login_lockouts_active.clone())).map_err(metrics_error)?;
+
+        Ok(Self {
+            registry,
+            database,
+            cache_service: None,
+            tagged_cache: None,
+            blockchain_service: None,
+            http_requests_total,
+            http_request_duration_seconds,
+            db_pool_connections,
+            db_pool_max_connections,
+            cache_hits,
+            cache_misses,
+            cache_errors,
+            cache_hit_ratio,
+            cache_invalidations,
+            blockchain_last_processed_block,
+            blockchain_event_lag_blocks,
+            blockchain_connection_errors_total,
+            backup_verification_last_run_passed,
+            backup_verification_last_success_timestamp_seconds,
+            backup_verified_taken_timestamp_seconds,
+            raffle_draws_pending,
+            raffle_draws_overdue,
+            login_lockouts_active,
+        })
+    }
+
+    /// Report cache hit rate from this cache's metrics
+    pub fn with_cache_service(mut self, cache_service: CacheService) -> Self {
+        self.cache_service = Some(cache_service);
+        self
+    }
+
+    /// Report invalidation volume from this read cache
+    pub fn with_tagged_cache(mut self, tagged_cache: TaggedCache) -> Self {
+        self.tagged_cache = Some(tagged_cache);
+        self
+    }
+
+    /// Report blockchain event lag against this chain's head
+    pub fn with_blockchain_service(mut self, blockchain_service: BlockchainService) -> Self {
+        self.blockchain_service = Some(blockchain_service);
+        self
+    }
+
+    /// Record a finished HTTP request; `route` is the matched route pattern, not the raw path
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_requests_total
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_request_duration_seconds
+            .with_label_values(&[method, route])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Sample the pool, cache, blockchain, backup drills, draws and login lockouts, then encode
+    /// everything in the text format
+    pub async fn render(&self) -> Result<String, AppError> {
+        self.sample_pool();
+        self.sample_cache();
+        self.sample_blockchain().await;
+        self.sample_backups().await;
+        self.sample_draws().await;
+        self.sample_lockouts().await;
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(metrics_error)?;
+
+        String::from_utf8(buffer).map_err(|e| AppError::Internal(format!("Invalid metrics output: {}", e)))
+    }
+
+    // Private helper methods
+
+    fn sample_pool(&self) {
+        let stats = self.database.get_pool_stats();
+        let idle = stats.idle as i64;
+
+        self.db_pool_connections.with_label_values(&["idle"]).set(idle);
+        self.db_pool_connections
+            .with_label_values(&["active"])
+            .set((stats.size as i64 - idle).max(0));
+        self.db_pool_max_connections.set(stats.max_connections as i64);
+    }
+
+    fn sample_cache(&self) {
+        if let Some(tagged_cache) = &self.tagged_cache {
+            for count in tagged_cache.invalidation_counts() {
+                self.cache_invalidations
+                    .with_label_values(&[count.tag, count.origin.as_str()])
+                    .set(count.count as i64);
+            }
+        }
+
+        let Some(cache_service) = &self.cache_service else {
+            return;
+        };
+
+        let metrics = cache_service.get_metrics();
+        self.cache_hits.set(metrics.hits as i64);
+        self.cache_misses.set(metrics.misses as i64);
+        self.cache_errors.set(metrics.errors as i64);
+        // CacheMetrics reports a percentage
+        self.cache_hit_ratio.set(metrics.hit_rate() / 100.0);
+    }
+
+    async fn sample_blockchain(&self) {
+        let last_processed = match sqlx::query_scalar!(
+            "SELECT last_processed_block FROM event_processor_state WHERE id = 1"
+        )
+        .fetch_optional(self.database.pool())
+        .await
+        {
+            Ok(block) => block.unwrap_or(0),
+            Err(e) => {
+                warn!("Failed to read event processor state for metrics: {}", e);
+                return;
+            }
+        };
+        self.blockchain_last_processed_block.set(last_processed);
+
+        let Some(blockchain_service) = &self.blockchain_service else {
+            return;
+        };
+
+        let head = tokio::time::timeout(
+            Duration::from_secs(CHAIN_HEAD_TIMEOUT_SECS),
+            blockchain_service.client().get_block_number(),
+        )
+        .await;
+
+        match head {
+            Ok(Ok(head)) => self.blockchain_event_lag_blocks.set(event_lag(head, last_processed)),
+            Ok(Err(e)) => {
+                self.blockchain_connection_errors_total.inc();
+                warn!("Failed to read chain head for metrics: {}", e);
+            }
+            Err(_) => {
+                self.blockchain_connection_errors_total.inc();
+                warn!("Timed out reading chain head for metrics");
+            }
+        }
+    }
+
+    /// Timestamps stay 0 until a drill passes, so "older than a day" alerts fire from the start
+    async fn sample_backups(&self) {
+        let row = sqlx::query_as::<_, (Option<bool>, Option<i64>, Option<i64>)>(
+            r#"
+            SELECT
+                (SELECT status = 'passed' FROM backup_verification_runs
+                 WHERE status <> 'running' ORDER BY started_at DESC LIMIT 1),
+                (SELECT EXTRACT(EPOCH FROM MAX(finished_at))::BIGINT FROM backup_verification_runs
+                 WHERE status = 'passed'),
+                (SELECT EXTRACT(EPOCH FROM MAX(backup_taken_at))::BIGINT FROM backup_verification_runs
+                 WHERE status = 'passed')
+            "#,
+        )
+        .fetch_one(self.database.pool())
+        .await;
+
+        match row {
+            Ok((last_passed, last_success, verified_taken)) => {
+                self.backup_verification_last_run_passed.set(last_passed.map_or(-1, i64::from));
+                self.backup_verification_last_success_timestamp_seconds.set(last_success.unwrap_or(0));
+                self.backup_verified_taken_timestamp_seconds.set(verified_taken.unwrap_or(0));
+            }
+            Err(e) => warn!("Failed to read backup verification runs for metrics: {}", e),
+        }
+    }
+
+    /// Overdue draws were reported by the draw scheduler, which marks them
+    async fn sample_draws(&self) {
+        let row = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT COUNT(*), COUNT(*) FILTER (WHERE alerted_at IS NOT NULL) FROM raffle_draw_schedule",
+        )
+        .fetch_one(self.database.pool())
+        .await;
+
+        match row {
+            Ok((pending, overdue)) => {
+                self.raffle_draws_pending.set(pending);
+                self.raffle_draws_overdue.set(overdue);
+            }
+            Err(e) => warn!("Failed to read the draw schedule for metrics: {}", e),
+        }
+    }
+
+    async fn sample_lockouts(&self) {
+        let row = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT COUNT(*) FILTER (WHERE scope = 'account'), COUNT(*) FILTER (WHERE scope = 'ip') \
+             FROM login_lockouts WHERE locked_until > NOW()",
+        )
+        .fetch_one(self.database.pool())
+        .await;
+
+        match row {
+            Ok((accounts, ips)) => {
+                self.login_lockouts_active.with_label_values(&["account"]).set(accounts);
+                self.login_lockouts_active.with_label_values(&["ip"]).set(ips);
+            }
+            Err(e) => warn!("Failed to read login lockouts for metrics: {}", e),
+        }
+    }
+}
+
+fn metrics_error(e: prometheus::Error) -> AppError {
+    AppError::Internal(format!("Metrics error: {}", e))
+}
+
+/// Blocks the event processor is behind; never negative while the head is read from a lagging node
+fn event_lag(head: u64, last_processed: i64) -> i64 {
+    (head as i64 - last_processed).max(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_lag() {
+        assert_eq!(event_lag(1_000, 990), 10);
+        assert_eq!(event_lag(1_000, 1_000), 0);
+        assert_eq!(event_lag(995, 1_000), 0);
+    }
+}
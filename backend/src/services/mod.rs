@@ -1,25 +1,132 @@
+pub mod abuse_report_service;
+pub mod account_deletion_service;
+pub mod analytics_service;
+pub mod api_billing_service;
+pub mod backup_verification_service;
 pub mod blockchain_service;
+pub mod cache_invalidation;
 pub mod cache_service;
+pub mod category_service;
+pub mod chain_reconciliation_service;
+pub mod cleanup_service;
+pub mod comment_service;
+pub mod compliance_service;
 pub mod connection_pool;
 pub mod credit_service;
+pub mod data_fix_service;
 pub mod database_optimization;
+pub mod draw_scheduler_service;
+pub mod email_domain_service;
+pub mod email_service;
+pub mod email_verification_service;
+pub mod external_wallet_service;
+pub mod favorites_service;
+pub mod feature_flag_service;
+pub mod fee_service;
+pub mod fulfillment_service;
+pub mod fx_service;
+pub mod geo_compliance_service;
+pub mod geo_velocity_service;
 pub mod item_service;
+pub mod ledger_service;
+pub mod listing_snapshot_service;
+pub mod media_service;
+pub mod metrics_service;
+pub mod moderation_service;
+pub mod notification_channels;
+pub mod notification_inbox_service;
 pub mod notification_service;
+pub mod outbox_service;
+pub mod payment_search_service;
 pub mod payment_service;
+pub mod pending_actions_service;
 pub mod performance_service;
+pub mod permission_service;
+pub mod promotion_service;
+pub mod raffle_runbook_service;
 pub mod raffle_service;
+pub mod readiness_service;
+pub mod realtime_protocol;
+pub mod realtime_relay;
 pub mod realtime_service;
+pub mod receipt_service;
+pub mod referral_service;
+pub mod reporting_service;
+pub mod sandbox_service;
+pub mod search_service;
+pub mod seller_onboarding_service;
+pub mod seller_webhook_service;
+pub mod settings_service;
+pub mod shipping_service;
+pub mod tag_service;
+pub mod tax_service;
+pub mod verification_service;
+pub mod waitlist_service;
 pub mod wallet_service;
+pub mod webhook_service;
 
+pub use abuse_report_service::AbuseReportService;
+pub use account_deletion_service::AccountDeletionService;
+pub use analytics_service::AnalyticsService;
+pub use api_billing_service::ApiBillingService;
+pub use backup_verification_service::BackupVerificationService;
 pub use blockchain_service::BlockchainService;
+pub use cache_invalidation::{CacheTag, TaggedCache};
 pub use cache_service::CacheService;
+pub use category_service::CategoryService;
+pub use chain_reconciliation_service::ChainReconciliationService;
+pub use cleanup_service::CleanupService;
+pub use comment_service::CommentService;
+pub use compliance_service::ComplianceService;
 pub use connection_pool::OptimizedConnectionPool;
 pub use credit_service::CreditService;
+pub use data_fix_service::DataFixService;
 pub use database_optimization::DatabaseOptimizationService;
+pub use draw_scheduler_service::DrawSchedulerService;
+pub use email_domain_service::EmailDomainService;
+pub use email_service::EmailService;
+pub use email_verification_service::EmailVerificationService;
+pub use external_wallet_service::ExternalWalletService;
+pub use favorites_service::FavoritesService;
+pub use feature_flag_service::FeatureFlagService;
+pub use fee_service::FeeService;
+pub use fulfillment_service::FulfillmentService;
+pub use fx_service::FxService;
+pub use geo_compliance_service::GeoComplianceService;
+pub use geo_velocity_service::GeoVelocityService;
 pub use item_service::ItemService;
+pub use ledger_service::LedgerService;
+pub use listing_snapshot_service::ListingSnapshotService;
+pub use media_service::MediaService;
+pub use metrics_service::MetricsService;
+pub use moderation_service::ModerationService;
+pub use notification_channels::NotificationChannels;
+pub use notification_inbox_service::NotificationInboxService;
 pub use notification_service::NotificationService;
+pub use outbox_service::{OutboxMessage, OutboxService};
+pub use payment_search_service::PaymentSearchService;
 pub use payment_service::PaymentService;
+pub use pending_actions_service::PendingActionsService;
 pub use performance_service::PerformanceService;
+pub use permission_service::PermissionService;
+pub use promotion_service::PromotionService;
+pub use raffle_runbook_service::RaffleRunbookService;
 pub use raffle_service::RaffleService;
+pub use readiness_service::ReadinessService;
+pub use realtime_relay::RealtimeRelay;
 pub use realtime_service::RealtimeService;
-pub use wallet_service::WalletService;
\ No newline at end of file
+pub use receipt_service::ReceiptService;
+pub use referral_service::ReferralService;
+pub use reporting_service::ReportingService;
+pub use sandbox_service::SandboxService;
+pub use search_service::SearchService;
+pub use seller_onboarding_service::SellerOnboardingService;
+pub use seller_webhook_service::SellerWebhookService;
+pub use settings_service::SettingsService;
+pub use shipping_service::ShippingService;
+pub use tag_service::TagService;
+pub use tax_service::TaxService;
+pub use verification_service::VerificationService;
+pub use waitlist_service::WaitlistService;
+pub use wallet_service::WalletService;
+pub use webhook_service::WebhookService;
\ No newline at end of file
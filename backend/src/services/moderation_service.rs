@@ -0,0 +1,749 @@
+use crate::error::AppError;
+use crate::models::item::Item;
+use crate::models::notification::{Notification, NotificationType};
+use crate::models::system_settings::SystemSetting;
+use crate::models::AuditLog;
+use crate::services::cache_invalidation::{CacheTag, TaggedCache};
+use crate::services::media_service::MediaService;
+use chrono::{DateTime, Utc};
+use image::imageops::FilterType;
+use image::DynamicImage;
+use raffle_platform_shared::{AuditAction, ItemStatus};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::net::IpAddr;
+use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+/// Images larger than this are not downloaded for duplicate checks
+const MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+/// Items whose checks never ran (e.g. the instance restarted) are picked up this often
+const SWEEP_INTERVAL_SECS: u64 = 300;
+const SWEEP_BATCH: i64 = 50;
+const MAX_REASON_LENGTH: usize = 1000;
+
+const DEFAULT_MAX_RETAIL_PRICE: i64 = 100_000;
+const DEFAULT_MAX_PRICE_TO_COST_RATIO: i64 = 20;
+/// Hashes this many bits apart or fewer are treated as the same picture
+const DEFAULT_DUPLICATE_IMAGE_DISTANCE: i64 = 6;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationCheck {
+    BannedKeyword,
+    PriceSanity,
+    DuplicateImage,
+    /// An image couldn't be fetched or decoded, so it wasn't compared
+    UnreadableImage,
+}
+
+/// Something an automated check found for a reviewer to look at
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ModerationFinding {
+    pub check: ModerationCheck,
+    pub detail: String,
+    /// Another seller's item with a matching image
+    pub matching_item_id: Option<Uuid>,
+}
+
+impl ModerationFinding {
+    fn new(check: ModerationCheck, detail: String) -> Self {
+        Self {
+            check,
+            detail,
+            matching_item_id: None,
+        }
+    }
+}
+
+/// An item in the moderation queue
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ModerationEntry {
+    pub item_id: Uuid,
+    pub seller_id: Option<Uuid>,
+    pub item_name: String,
+    pub status: ModerationStatus,
+    pub findings: Vec<ModerationFinding>,
+    /// When the automated checks finished; None while they are running
+    pub checked_at: Option<DateTime<Utc>>,
+    /// Reviewer's reason, sent to the seller
+    pub reason: Option<String>,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub submitted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+struct ModerationRow {
+    item_id: Uuid,
+    seller_id: Option<Uuid>,
+    item_name: String,
+    status: ModerationStatus,
+    findings: serde_json::Value,
+    checked_at: Option<DateTime<Utc>>,
+    reason: Option<String>,
+    reviewed_by: Option<Uuid>,
+    reviewed_at: Option<DateTime<Utc>>,
+    submitted_at: DateTime<Utc>,
+}
+
+impl From<ModerationRow> for ModerationEntry {
+    fn from(row: ModerationRow) -> Self {
+        Self {
+            item_id: row.item_id,
+            seller_id: row.seller_id,
+            item_name: row.item_name,
+            status: row.status,
+            findings: serde_json::from_value(row.findings).unwrap_or_default(),
+            checked_at: row.checked_at,
+            reason: row.reason,
+            reviewed_by: row.reviewed_by,
+            reviewed_at: row.reviewed_at,
+            submitted_at: row.submitted_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ModerationDecisionRequest {
+    /// Required when rejecting; shown to the seller
+    pub reason: Option<String>,
+}
+
+const COLUMNS: &str = "m.item_id, i.seller_id, i.name AS item_name, m.status, m.findings, m.checked_at, \
+    m.reason, m.reviewed_by, m.reviewed_at, m.submitted_at";
+
+/// Limits the automated checks apply, from system settings
+struct CheckSettings {
+    banned_keywords: Vec<String>,
+    max_retail_price: Decimal,
+    max_price_to_cost_ratio: Decimal,
+    duplicate_image_distance: i64,
+}
+
+/// Moderation service holds new items back until an admin approves them. Automated checks
+/// run as each item is submitted and their findings put suspect items at the front of the
+/// queue; the decision and its reason are sent to the seller.
+#[derive(Clone)]
+pub struct ModerationService {
+    db_pool: PgPool,
+    http_client: reqwest::Client,
+    media_service: Option<MediaService>,
+    cache: TaggedCache,
+}
+
+impl ModerationService {
+    /// Create a new moderation service
+    pub fn new(db_pool: PgPool) -> Self {
+        let http_client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            db_pool,
+            http_client,
+            media_service: None,
+            cache: TaggedCache::default(),
+        }
+    }
+
+    /// Fetch images through the CDN, at thumbnail size, for duplicate checks
+    pub fn with_media_service(mut self, media_service: MediaService) -> Self {
+        self.media_service = Some(media_service);
+        self
+    }
+
+    /// Invalidate item listings when an item is approved or rejected
+    pub fn with_cache(mut self, cache: TaggedCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Run checks for items whose checks never finished
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(SWEEP_INTERVAL_SECS));
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.check_unchecked().await {
+                    error!("Failed to run moderation checks: {}", e);
+                }
+            }
+        });
+
+        info!("Moderation service background tasks started");
+    }
+
+    /// Queue a new or edited item for review and start its automated checks. Items listed
+    /// by staff go live at once.
+    pub async fn submit(&self, item: &Item, staff: bool) -> Result<(), AppError> {
+        if staff {
+            sqlx::query(
+                r#"
+                INSERT INTO item_moderation (item_id, status, reviewed_by, reviewed_at, checked_at)
+                VALUES ($1, 'approved', $2, NOW(), NOW())
+                ON CONFLICT (item_id) DO UPDATE SET
+                    status = 'approved', findings = '[]', reason = NULL,
+                    reviewed_by = EXCLUDED.reviewed_by, reviewed_at = NOW(), checked_at = NOW(), submitted_at = NOW()
+                "#,
+            )
+            .bind(item.id)
+            .bind(item.seller_id)
+            .execute(&self.db_pool)
+            .await?;
+
+            Item::update_status(&self.db_pool, item.id, ItemStatus::Available).await?;
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO item_moderation (item_id) VALUES ($1)
+            ON CONFLICT (item_id) DO UPDATE SET
+                status = 'pending', findings = '[]', checked_at = NULL, reason = NULL,
+                reviewed_by = NULL, reviewed_at = NULL, submitted_at = NOW()
+            "#,
+        )
+        .bind(item.id)
+        .execute(&self.db_pool)
+        .await?;
+
+        Item::update_status(&self.db_pool, item.id, ItemStatus::PendingReview).await?;
+
+        // Downloading images can take a while; the seller doesn't wait for it
+        let service = self.clone();
+        let item = item.clone();
+        tokio::spawn(async move {
+            if let Err(e) = service.run_checks(&item).await {
+                warn!("Moderation checks failed for item {}, will retry: {}", item.id, e);
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Items awaiting review by default: flagged items first, then oldest first
+    pub async fn queue(&self, status: Option<ModerationStatus>, limit: i64) -> Result<Vec<ModerationEntry>, AppError> {
+        let rows = sqlx::query_as::<_, ModerationRow>(&format!(
+            r#"
+            SELECT {}
+            FROM item_moderation m
+            JOIN items i ON i.id = m.item_id
+            WHERE m.status = COALESCE($1, 'pending')
+            ORDER BY jsonb_array_length(m.findings) > 0 DESC, m.submitted_at ASC
+            LIMIT $2
+            "#,
+            COLUMNS
+        ))
+        .bind(status)
+        .bind(limit.clamp(1, 200))
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows.into_iter().map(ModerationEntry::from).collect())
+    }
+
+    pub async fn get(&self, item_id: Uuid) -> Result<ModerationEntry, AppError> {
+        sqlx::query_as::<_, ModerationRow>(&format!(
+            "SELECT {} FROM item_moderation m JOIN items i ON i.id = m.item_id WHERE m.item_id = $1",
+            COLUMNS
+        ))
+        .bind(item_id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .map(ModerationEntry::from)
+        .ok_or_else(|| AppError::NotFound("Item is not in the moderation queue".to_string()))
+    }
+
+    /// Put a pending item on sale (admin)
+    pub async fn approve(
+        &self,
+        item_id: Uuid,
+        admin_id: Uuid,
+        request: ModerationDecisionRequest,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<ModerationEntry, AppError> {
+        let reason = normalize_reason(request.reason)?;
+        self.decide(item_id, admin_id, ModerationStatus::Approved, reason, ip_address, user_agent)
+            .await
+    }
+
+    /// Keep a pending item off sale; the seller is told why and can edit it to resubmit (admin)
+    pub async fn reject(
+        &self,
+        item_id: Uuid,
+        admin_id: Uuid,
+        request: ModerationDecisionRequest,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<ModerationEntry, AppError> {
+        let reason = normalize_reason(request.reason)?
+            .ok_or_else(|| AppError::Validation("A reason is required to reject an item".to_string()))?;
+        self.decide(item_id, admin_id, ModerationStatus::Rejected, Some(reason), ip_address, user_agent)
+            .await
+    }
+
+    // Private helper methods
+
+    async fn decide(
+        &self,
+        item_id: Uuid,
+        admin_id: Uuid,
+        decision: ModerationStatus,
+        reason: Option<String>,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<ModerationEntry, AppError> {
+        let item_status = match decision {
+            ModerationStatus::Approved => ItemStatus::Available,
+            _ => ItemStatus::Rejected,
+        };
+
+        let mut tx = self.db_pool.begin().await?;
+
+        let decided = sqlx::query(
+            r#"
+            UPDATE item_moderation
+            SET status = $2, reason = $3, reviewed_by = $4, reviewed_at = NOW()
+            WHERE item_id = $1 AND status = 'pending'
+            "#,
+        )
+        .bind(item_id)
+        .bind(decision)
+        .bind(&reason)
+        .bind(admin_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if decided == 0 {
+            tx.rollback().await?;
+            self.get(item_id).await?;
+            return Err(AppError::Conflict("Item is not awaiting review".to_string()));
+        }
+
+        sqlx::query("UPDATE items SET status = $1, updated_at = NOW() WHERE id = $2")
+            .bind(item_status)
+            .bind(item_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        let entry = self.get(item_id).await?;
+
+        if let Err(e) = AuditLog::create(
+            &self.db_pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("item_moderation".to_string()),
+            Some(item_id),
+            Some(serde_json::json!({ "status": ModerationStatus::Pending })),
+            Some(serde_json::json!({ "status": decision, "reason": reason })),
+            ip_address,
+            user_agent,
+        )
+        .await
+        {
+            warn!("Failed to audit moderation of item {}: {}", item_id, e);
+        }
+
+        let mut tags = vec![CacheTag::Item(item_id), CacheTag::ItemListings];
+        tags.extend(entry.seller_id.map(CacheTag::Seller));
+        self.cache.invalidate(&tags).await;
+
+        info!("Admin {} {:?} item {}", admin_id, decision, item_id);
+
+        self.notify_seller(&entry).await;
+
+        Ok(entry)
+    }
+
+    async fn notify_seller(&self, entry: &ModerationEntry) {
+        let Some(seller_id) = entry.seller_id else {
+            return;
+        };
+
+        let (title, message) = match entry.status {
+            ModerationStatus::Approved => (
+                "Your item was approved".to_string(),
+                format!("'{}' passed review and can now be raffled.", entry.item_name),
+            ),
+            _ => (
+                "Your item was not approved".to_string(),
+                format!(
+                    "'{}' was not approved: {}. Edit the item to submit it for review again.",
+                    entry.item_name,
+                    entry.reason.as_deref().unwrap_or("no reason given")
+                ),
+            ),
+        };
+
+        if let Err(e) = Notification::create(
+            &self.db_pool,
+            seller_id,
+            title,
+            message,
+            NotificationType::SystemAlert,
+            Some(serde_json::json!({
+                "event": "item_moderation",
+                "item_id": entry.item_id,
+                "status": entry.status,
+                "reason": entry.reason
+            })),
+        )
+        .await
+        {
+            warn!("Failed to notify seller of moderation of item {}: {}", entry.item_id, e);
+        }
+    }
+
+    async fn check_unchecked(&self) -> Result<(), AppError> {
+        let item_ids: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT item_id FROM item_moderation
+            WHERE status = 'pending' AND checked_at IS NULL AND submitted_at < NOW() - INTERVAL '5 minutes'
+            ORDER BY submitted_at
+            LIMIT $1
+            "#,
+        )
+        .bind(SWEEP_BATCH)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        for item_id in item_ids {
+            let Some(item) = Item::find_by_id(&self.db_pool, item_id).await? else {
+                continue;
+            };
+            if let Err(e) = self.run_checks(&item).await {
+                warn!("Moderation checks failed for item {}: {}", item_id, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn run_checks(&self, item: &Item) -> Result<(), AppError> {
+        let settings = self.check_settings().await;
+
+        let text = format!("{} {}", item.name, item.description.as_deref().unwrap_or_default());
+        let mut findings: Vec<ModerationFinding> = banned_keywords_in(&text, &settings.banned_keywords)
+            .into_iter()
+            .map(|keyword| {
+                ModerationFinding::new(ModerationCheck::BannedKeyword, format!("Mentions \"{}\"", keyword))
+            })
+            .collect();
+
+        findings.extend(price_findings(
+            item.retail_price,
+            item.cost_of_goods,
+            settings.max_retail_price,
+            settings.max_price_to_cost_ratio,
+        ));
+
+        for image in &item.images {
+            findings.extend(self.check_image(item, image, settings.duplicate_image_distance).await?);
+        }
+
+        debug!("Moderation checks for item {} found {} issues", item.id, findings.len());
+
+        sqlx::query(
+            "UPDATE item_moderation SET findings = $2, checked_at = NOW() WHERE item_id = $1 AND status = 'pending'",
+        )
+        .bind(item.id)
+        .bind(serde_json::to_value(&findings).unwrap_or_default())
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Hash an image and look for other sellers' items with the same picture
+    async fn check_image(
+        &self,
+        item: &Item,
+        image: &str,
+        max_distance: i64,
+    ) -> Result<Option<ModerationFinding>, AppError> {
+        let hash = match self.fetch_image(image).await {
+            Ok(picture) => difference_hash(&picture),
+            Err(e) => {
+                debug!("Could not hash image {} of item {}: {}", image, item.id, e);
+                return Ok(Some(ModerationFinding::new(
+                    ModerationCheck::UnreadableImage,
+                    format!("Could not read {}", image),
+                )));
+            }
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO item_image_hashes (item_id, image, hash) VALUES ($1, $2, $3)
+            ON CONFLICT (item_id, image) DO UPDATE SET hash = EXCLUDED.hash
+            "#,
+        )
+        .bind(item.id)
+        .bind(image)
+        .bind(hash as i64)
+        .execute(&self.db_pool)
+        .await?;
+
+        // Sellers relisting their own photos is fine; another seller's is likely copied
+        let matching_item_id: Option<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT h.item_id
+            FROM item_image_hashes h
+            JOIN items i ON i.id = h.item_id
+            WHERE h.item_id <> $1
+              AND i.seller_id IS DISTINCT FROM $2
+              AND bit_count((h.hash # $3)::bit(64)) <= $4
+            ORDER BY bit_count((h.hash # $3)::bit(64))
+            LIMIT 1
+            "#,
+        )
+        .bind(item.id)
+        .bind(item.seller_id)
+        .bind(hash as i64)
+        .bind(max_distance)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(matching_item_id.map(|matching_item_id| ModerationFinding {
+            check: ModerationCheck::DuplicateImage,
+            detail: format!("{} matches an image on another seller's item", image),
+            matching_item_id: Some(matching_item_id),
+        }))
+    }
+
+    async fn fetch_image(&self, image: &str) -> Result<DynamicImage, String> {
+        let url = match &self.media_service {
+            Some(media_service) => media_service.variants(image).thumb,
+            None => image.to_string(),
+        };
+
+        let response = self
+            .http_client
+            .get(&url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| e.to_string())?;
+        if response.content_length().is_some_and(|len| len as usize > MAX_IMAGE_BYTES) {
+            return Err("image is too large".to_string());
+        }
+
+        let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+        if bytes.len() > MAX_IMAGE_BYTES {
+            return Err("image is too large".to_string());
+        }
+
+        image::load_from_memory(&bytes).map_err(|e| e.to_string())
+    }
+
+    async fn check_settings(&self) -> CheckSettings {
+        let setting = |key: &'static str| async move {
+            SystemSetting::get_by_key(&self.db_pool, key).await.ok().flatten()
+        };
+
+        CheckSettings {
+            banned_keywords: setting("moderation_banned_keywords")
+                .await
+                .and_then(|s| serde_json::from_value::<Vec<String>>(s.value).ok())
+                .unwrap_or_default(),
+            max_retail_price: setting("moderation_max_retail_price")
+                .await
+                .and_then(|s| s.get_decimal_value())
+                .filter(|v| *v > Decimal::ZERO)
+                .unwrap_or(Decimal::from(DEFAULT_MAX_RETAIL_PRICE)),
+            max_price_to_cost_ratio: setting("moderation_max_price_to_cost_ratio")
+                .await
+                .and_then(|s| s.get_decimal_value())
+                .filter(|v| *v > Decimal::ONE)
+                .unwrap_or(Decimal::from(DEFAULT_MAX_PRICE_TO_COST_RATIO)),
+            duplicate_image_distance: setting("moderation_duplicate_image_distance")
+                .await
+                .and_then(|s| s.get_int_value())
+                .filter(|v| (0..64).contains(v))
+                .unwrap_or(DEFAULT_DUPLICATE_IMAGE_DISTANCE),
+        }
+    }
+}
+
+/// Sellers can take their own items on and off sale, but can't move an item into or out
+/// of review
+pub fn check_seller_status_change(current: ItemStatus, requested: ItemStatus) -> Result<(), AppError> {
+    let in_review = |status: ItemStatus| matches!(status, ItemStatus::PendingReview | ItemStatus::Rejected);
+
+    if in_review(current) && current != requested {
+        return Err(AppError::Validation(match current {
+            ItemStatus::PendingReview => "Item is awaiting review".to_string(),
+            _ => "Item was rejected; edit it to submit it for review again".to_string(),
+        }));
+    }
+    if in_review(requested) && current != requested {
+        return Err(AppError::Validation("Only moderators can set this status".to_string()));
+    }
+
+    Ok(())
+}
+
+fn normalize_reason(reason: Option<String>) -> Result<Option<String>, AppError> {
+    let reason = reason.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+    if reason.as_ref().is_some_and(|r| r.chars().count() > MAX_REASON_LENGTH) {
+        return Err(AppError::Validation(format!(
+            "Reason cannot exceed {} characters",
+            MAX_REASON_LENGTH
+        )));
+    }
+    Ok(reason)
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Banned keywords or phrases that appear as whole words in the text
+fn banned_keywords_in(text: &str, keywords: &[String]) -> Vec<String> {
+    let text = words(text);
+
+    keywords
+        .iter()
+        .filter(|keyword| {
+            let phrase = words(keyword);
+            !phrase.is_empty() && text.windows(phrase.len()).any(|window| window == phrase.as_slice())
+        })
+        .cloned()
+        .collect()
+}
+
+fn price_findings(
+    retail_price: Decimal,
+    cost_of_goods: Decimal,
+    max_retail_price: Decimal,
+    max_price_to_cost_ratio: Decimal,
+) -> Vec<ModerationFinding> {
+    let mut findings = Vec::new();
+
+    if retail_price > max_retail_price {
+        findings.push(ModerationFinding::new(
+            ModerationCheck::PriceSanity,
+            format!("Retail price {} is above {}", retail_price, max_retail_price),
+        ));
+    }
+    if cost_of_goods > Decimal::ZERO && retail_price / cost_of_goods > max_price_to_cost_ratio {
+        findings.push(ModerationFinding::new(
+            ModerationCheck::PriceSanity,
+            format!(
+                "Retail price {} is more than {} times the cost of {}",
+                retail_price, max_price_to_cost_ratio, cost_of_goods
+            ),
+        ));
+    }
+
+    findings
+}
+
+/// 64-bit difference hash: each bit says whether a pixel of a 9x8 grayscale thumbnail is
+/// darker than its right neighbour. Resizing, recompression and small edits change few bits.
+fn difference_hash(picture: &DynamicImage) -> u64 {
+    let small = picture.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, _| Luma([(x * 255 / width) as u8])))
+    }
+
+    #[test]
+    fn test_banned_keywords_match_whole_words() {
+        let keywords = vec!["replica".to_string(), "fake id".to_string()];
+
+        assert_eq!(
+            banned_keywords_in("Designer bag (REPLICA), great condition", &keywords),
+            vec!["replica".to_string()]
+        );
+        assert_eq!(banned_keywords_in("Comes with a fake ID card", &keywords), vec!["fake id".to_string()]);
+        assert!(banned_keywords_in("Replicator toy", &keywords).is_empty());
+        assert!(banned_keywords_in("A fake and an id", &keywords).is_empty());
+    }
+
+    #[test]
+    fn test_price_findings() {
+        let max_price = Decimal::from(1000);
+        let max_ratio = Decimal::from(20);
+
+        assert!(price_findings(Decimal::from(100), Decimal::from(50), max_price, max_ratio).is_empty());
+        assert_eq!(price_findings(Decimal::from(5000), Decimal::from(4000), max_price, max_ratio).len(), 1);
+        assert_eq!(price_findings(Decimal::from(500), Decimal::from(10), max_price, max_ratio).len(), 1);
+        // No cost given isn't a markup
+        assert!(price_findings(Decimal::from(500), Decimal::ZERO, max_price, max_ratio).is_empty());
+    }
+
+    #[test]
+    fn test_difference_hash_survives_resizing() {
+        let original = gradient(400, 300);
+        let resized = original.resize_exact(120, 90, FilterType::Lanczos3);
+        let flipped = original.fliph();
+
+        let distance = |a: u64, b: u64| (a ^ b).count_ones();
+        let hash = difference_hash(&original);
+
+        assert!(distance(hash, difference_hash(&resized)) <= DEFAULT_DUPLICATE_IMAGE_DISTANCE as u32);
+        assert!(distance(hash, difference_hash(&flipped)) > DEFAULT_DUPLICATE_IMAGE_DISTANCE as u32);
+    }
+
+    #[test]
+    fn test_sellers_cannot_bypass_review() {
+        assert!(check_seller_status_change(ItemStatus::Available, ItemStatus::Inactive).is_ok());
+        assert!(check_seller_status_change(ItemStatus::Inactive, ItemStatus::Available).is_ok());
+        assert!(check_seller_status_change(ItemStatus::PendingReview, ItemStatus::Available).is_err());
+        assert!(check_seller_status_change(ItemStatus::Rejected, ItemStatus::Available).is_err());
+        assert!(check_seller_status_change(ItemStatus::Inactive, ItemStatus::PendingReview).is_err());
+    }
+
+    #[test]
+    fn test_reject_reasons_are_trimmed() {
+        assert_eq!(normalize_reason(Some("  ".to_string())).unwrap(), None);
+        assert_eq!(
+            normalize_reason(Some(" Blurry photos ".to_string())).unwrap(),
+            Some("Blurry photos".to_string())
+        );
+        assert!(normalize_reason(Some("x".repeat(MAX_REASON_LENGTH + 1))).is_err());
+    }
+}
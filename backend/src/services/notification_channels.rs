@@ -0,0 +1,623 @@
+use crate::config::{AppConfig, SmsProviderKind};
+use crate::error::AppError;
+use crate::models::notification_preferences::PushPlatform;
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+const TWILIO_API_BASE: &str = "https://api.twilio.com";
+const FCM_API_BASE: &str = "https://fcm.googleapis.com";
+const FCM_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
+const APNS_HOST: &str = "https://api.push.apple.com";
+const APNS_SANDBOX_HOST: &str = "https://api.sandbox.push.apple.com";
+/// APNs rejects provider tokens older than an hour and throttles ones refreshed more often than every 20 minutes
+const APNS_TOKEN_TTL_MINUTES: i64 = 50;
+const REQUEST_TIMEOUT_SECS: u64 = 15;
+
+pub type ChannelSendFuture<'a> = Pin<Box<dyn Future<Output = Result<String, DeliveryFailure>> + Send + 'a>>;
+
+/// Why a provider did not accept a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeliveryFailure {
+    /// Worth retrying: timeouts, throttling, provider outages
+    Transient(String),
+    /// Retrying won't help: the number or device token was rejected outright
+    Permanent(String),
+}
+
+impl std::fmt::Display for DeliveryFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeliveryFailure::Transient(reason) => write!(f, "transient: {}", reason),
+            DeliveryFailure::Permanent(reason) => write!(f, "permanent: {}", reason),
+        }
+    }
+}
+
+/// Transport that delivers text messages
+pub trait SmsProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Send `body` to an E.164 number; returns the id the provider assigned to the message
+    fn send<'a>(&'a self, to: &'a str, body: &'a str) -> ChannelSendFuture<'a>;
+}
+
+/// A notification as shown on a device's lock screen
+#[derive(Debug, Clone, Serialize)]
+pub struct PushMessage {
+    pub title: String,
+    pub body: String,
+    /// Passed to the app alongside the alert
+    pub data: Value,
+}
+
+impl PushMessage {
+    /// FCM only carries string values; nested values are sent as JSON text
+    pub fn data_strings(&self) -> HashMap<String, String> {
+        self.data
+            .as_object()
+            .map(|fields| {
+                fields
+                    .iter()
+                    .filter(|(_, value)| !value.is_null())
+                    .map(|(key, value)| {
+                        let value = match value {
+                            Value::String(text) => text.clone(),
+                            other => other.to_string(),
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Transport that delivers push notifications to one platform's devices
+pub trait PushProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Send to one device token; returns the id the provider assigned to the message
+    fn send<'a>(&'a self, token: &'a str, message: &'a PushMessage) -> ChannelSendFuture<'a>;
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+        .unwrap_or_default()
+}
+
+/// Throttling and server errors are retried; other rejections are final
+fn failure_for_status(status: reqwest::StatusCode, details: String) -> DeliveryFailure {
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        DeliveryFailure::Transient(format!("{}: {}", status, details))
+    } else {
+        DeliveryFailure::Permanent(format!("{}: {}", status, details))
+    }
+}
+
+/// Sends through the Twilio Messages API
+pub struct TwilioSmsProvider {
+    client: reqwest::Client,
+    account_sid: String,
+    auth_token: String,
+    /// A phone number, or a messaging service SID (`MG...`)
+    from: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TwilioMessage {
+    sid: String,
+}
+
+impl TwilioSmsProvider {
+    pub fn new(account_sid: String, auth_token: String, from: String) -> Self {
+        Self {
+            client: http_client(),
+            account_sid,
+            auth_token,
+            from,
+        }
+    }
+}
+
+impl SmsProvider for TwilioSmsProvider {
+    fn name(&self) -> &'static str {
+        "twilio"
+    }
+
+    fn send<'a>(&'a self, to: &'a str, body: &'a str) -> ChannelSendFuture<'a> {
+        Box::pin(async move {
+            let from_field = if self.from.starts_with("MG") { "MessagingServiceSid" } else { "From" };
+            let url = format!("{}/2010-04-01/Accounts/{}/Messages.json", TWILIO_API_BASE, self.account_sid);
+
+            let response = self
+                .client
+                .post(&url)
+                .basic_auth(&self.account_sid, Some(&self.auth_token))
+                .form(&[("To", to), (from_field, self.from.as_str()), ("Body", body)])
+                .send()
+                .await
+                .map_err(|e| DeliveryFailure::Transient(e.to_string()))?;
+
+            let status = response.status();
+            if !status.is_success() {
+                let details = response.text().await.unwrap_or_default();
+                return Err(failure_for_status(status, details));
+            }
+
+            let message: TwilioMessage = response
+                .json()
+                .await
+                .map_err(|e| DeliveryFailure::Transient(format!("Unexpected Twilio response: {}", e)))?;
+            Ok(message.sid)
+        })
+    }
+}
+
+/// Writes text messages to the log instead of sending them; used when no provider is configured
+pub struct LogSmsProvider;
+
+impl SmsProvider for LogSmsProvider {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn send<'a>(&'a self, to: &'a str, body: &'a str) -> ChannelSendFuture<'a> {
+        Box::pin(async move {
+            info!("SMS to {}: {}", to, body);
+            Ok(Uuid::new_v4().to_string())
+        })
+    }
+}
+
+/// The fields of a Firebase service account key file we use
+#[derive(Debug, Deserialize)]
+struct FcmServiceAccount {
+    project_id: String,
+    client_email: String,
+    private_key: String,
+    #[serde(default = "default_google_token_uri")]
+    token_uri: String,
+}
+
+fn default_google_token_uri() -> String {
+    "https://oauth2.googleapis.com/token".to_string()
+}
+
+#[derive(Debug, Serialize)]
+struct GoogleAssertionClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleAccessToken {
+    access_token: String,
+    expires_in: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct FcmSendResponse {
+    name: String,
+}
+
+/// Sends through the Firebase Cloud Messaging HTTP v1 API
+pub struct FcmPushProvider {
+    client: reqwest::Client,
+    account: FcmServiceAccount,
+    signing_key: EncodingKey,
+    access_token: Mutex<Option<(String, DateTime<Utc>)>>,
+}
+
+impl FcmPushProvider {
+    /// Load a service account key file downloaded from the Firebase console
+    pub fn from_file(path: &str) -> Result<Self, AppError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| AppError::Internal(format!("Could not read FCM credentials {}: {}", path, e)))?;
+        let account: FcmServiceAccount = serde_json::from_str(&contents)
+            .map_err(|e| AppError::Internal(format!("Invalid FCM credentials {}: {}", path, e)))?;
+        let signing_key = EncodingKey::from_rsa_pem(account.private_key.as_bytes())
+            .map_err(|e| AppError::Internal(format!("Invalid FCM private key: {}", e)))?;
+
+        Ok(Self {
+            client: http_client(),
+            account,
+            signing_key,
+            access_token: Mutex::new(None),
+        })
+    }
+
+    /// An OAuth token for the FCM scope, exchanged for a signed assertion and reused until shortly before it expires
+    async fn access_token(&self) -> Result<String, DeliveryFailure> {
+        let mut cached = self.access_token.lock().await;
+        if let Some((token, expires_at)) = cached.as_ref() {
+            if *expires_at > Utc::now() {
+                return Ok(token.clone());
+            }
+        }
+
+        let now = Utc::now().timestamp();
+        let claims = GoogleAssertionClaims {
+            iss: &self.account.client_email,
+            scope: FCM_SCOPE,
+            aud: &self.account.token_uri,
+            iat: now,
+            exp: now + 3600,
+        };
+        let assertion = jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &self.signing_key)
+            .map_err(|e| DeliveryFailure::Transient(format!("Could not sign FCM assertion: {}", e)))?;
+
+        let response = self
+            .client
+            .post(&self.account.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| DeliveryFailure::Transient(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let details = response.text().await.unwrap_or_default();
+            return Err(DeliveryFailure::Transient(format!("FCM token exchange failed with {}: {}", status, details)));
+        }
+
+        let token: GoogleAccessToken = response
+            .json()
+            .await
+            .map_err(|e| DeliveryFailure::Transient(format!("Unexpected token response: {}", e)))?;
+
+        let expires_at = Utc::now() + Duration::seconds((token.expires_in - 60).max(0));
+        *cached = Some((token.access_token.clone(), expires_at));
+        Ok(token.access_token)
+    }
+}
+
+impl PushProvider for FcmPushProvider {
+    fn name(&self) -> &'static str {
+        "fcm"
+    }
+
+    fn send<'a>(&'a self, token: &'a str, message: &'a PushMessage) -> ChannelSendFuture<'a> {
+        Box::pin(async move {
+            let access_token = self.access_token().await?;
+            let url = format!("{}/v1/projects/{}/messages:send", FCM_API_BASE, self.account.project_id);
+
+            let response = self
+                .client
+                .post(&url)
+                .bearer_auth(access_token)
+                .json(&serde_json::json!({
+                    "message": {
+                        "token": token,
+                        "notification": {
+                            "title": message.title,
+                            "body": message.body
+                        },
+                        "data": message.data_strings()
+                    }
+                }))
+                .send()
+                .await
+                .map_err(|e| DeliveryFailure::Transient(e.to_string()))?;
+
+            let status = response.status();
+            if status == reqwest::StatusCode::UNAUTHORIZED {
+                // Revoked or rotated credentials: fetch a fresh token on the next attempt
+                *self.access_token.lock().await = None;
+                return Err(DeliveryFailure::Transient("FCM rejected the access token".to_string()));
+            }
+            if !status.is_success() {
+                let details = response.text().await.unwrap_or_default();
+                if details.contains("UNREGISTERED") {
+                    return Err(DeliveryFailure::Permanent(format!("Device is no longer registered: {}", details)));
+                }
+                return Err(failure_for_status(status, details));
+            }
+
+            let sent: FcmSendResponse = response
+                .json()
+                .await
+                .map_err(|e| DeliveryFailure::Transient(format!("Unexpected FCM response: {}", e)))?;
+            Ok(sent.name)
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ApnsProviderClaims<'a> {
+    iss: &'a str,
+    iat: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApnsError {
+    reason: String,
+}
+
+/// Sends through the Apple Push Notification service HTTP/2 API with token-based authentication
+pub struct ApnsPushProvider {
+    client: reqwest::Client,
+    host: &'static str,
+    key_id: String,
+    team_id: String,
+    topic: String,
+    signing_key: EncodingKey,
+    provider_token: Mutex<Option<(String, DateTime<Utc>)>>,
+}
+
+impl ApnsPushProvider {
+    /// Load the `.p8` signing key created under Certificates, Identifiers & Profiles
+    pub fn from_key_file(
+        path: &str,
+        key_id: String,
+        team_id: String,
+        topic: String,
+        sandbox: bool,
+    ) -> Result<Self, AppError> {
+        let key = std::fs::read(path)
+            .map_err(|e| AppError::Internal(format!("Could not read APNs key {}: {}", path, e)))?;
+        let signing_key = EncodingKey::from_ec_pem(&key)
+            .map_err(|e| AppError::Internal(format!("Invalid APNs key {}: {}", path, e)))?;
+
+        let client = reqwest::Client::builder()
+            .http2_prior_knowledge()
+            .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| AppError::Internal(format!("Could not build APNs client: {}", e)))?;
+
+        Ok(Self {
+            client,
+            host: if sandbox { APNS_SANDBOX_HOST } else { APNS_HOST },
+            key_id,
+            team_id,
+            topic,
+            signing_key,
+            provider_token: Mutex::new(None),
+        })
+    }
+
+    async fn provider_token(&self) -> Result<String, DeliveryFailure> {
+        let mut cached = self.provider_token.lock().await;
+        if let Some((token, issued_at)) = cached.as_ref() {
+            if Utc::now() - *issued_at < Duration::minutes(APNS_TOKEN_TTL_MINUTES) {
+                return Ok(token.clone());
+            }
+        }
+
+        let issued_at = Utc::now();
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(self.key_id.clone());
+        let claims = ApnsProviderClaims {
+            iss: &self.team_id,
+            iat: issued_at.timestamp(),
+        };
+        let token = jsonwebtoken::encode(&header, &claims, &self.signing_key)
+            .map_err(|e| DeliveryFailure::Transient(format!("Could not sign APNs token: {}", e)))?;
+
+        *cached = Some((token.clone(), issued_at));
+        Ok(token)
+    }
+}
+
+/// Reasons APNs gives for a token that will never accept notifications again
+fn apns_reason_is_permanent(reason: &str) -> bool {
+    matches!(reason, "BadDeviceToken" | "DeviceTokenNotForTopic" | "Unregistered" | "TopicDisallowed")
+}
+
+impl PushProvider for ApnsPushProvider {
+    fn name(&self) -> &'static str {
+        "apns"
+    }
+
+    fn send<'a>(&'a self, token: &'a str, message: &'a PushMessage) -> ChannelSendFuture<'a> {
+        Box::pin(async move {
+            let provider_token = self.provider_token().await?;
+
+            let mut payload = serde_json::json!({
+                "aps": {
+                    "alert": { "title": message.title, "body": message.body },
+                    "sound": "default"
+                }
+            });
+            if let (Some(payload), Some(data)) = (payload.as_object_mut(), message.data.as_object()) {
+                for (key, value) in data {
+                    if key != "aps" {
+                        payload.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+
+            let response = self
+                .client
+                .post(format!("{}/3/device/{}", self.host, token))
+                .bearer_auth(provider_token)
+                .header("apns-topic", &self.topic)
+                .header("apns-push-type", "alert")
+                .header("apns-priority", "10")
+                .json(&payload)
+                .send()
+                .await
+                .map_err(|e| DeliveryFailure::Transient(e.to_string()))?;
+
+            let status = response.status();
+            if status.is_success() {
+                let apns_id = response
+                    .headers()
+                    .get("apns-id")
+                    .and_then(|id| id.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string();
+                return Ok(apns_id);
+            }
+
+            let reason = response
+                .json::<ApnsError>()
+                .await
+                .map(|error| error.reason)
+                .unwrap_or_default();
+
+            if status == reqwest::StatusCode::GONE || apns_reason_is_permanent(&reason) {
+                return Err(DeliveryFailure::Permanent(format!("{}: {}", status, reason)));
+            }
+            if reason == "ExpiredProviderToken" || reason == "InvalidProviderToken" {
+                *self.provider_token.lock().await = None;
+                return Err(DeliveryFailure::Transient(format!("{}: {}", status, reason)));
+            }
+            Err(failure_for_status(status, reason))
+        })
+    }
+}
+
+/// Writes push notifications to the log instead of sending them; used for platforms without credentials
+pub struct LogPushProvider;
+
+impl PushProvider for LogPushProvider {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn send<'a>(&'a self, token: &'a str, message: &'a PushMessage) -> ChannelSendFuture<'a> {
+        Box::pin(async move {
+            let preview: String = token.chars().take(8).collect();
+            info!("Push to device {}...: {}", preview, message.title);
+            debug!("{}", message.body);
+            Ok(Uuid::new_v4().to_string())
+        })
+    }
+}
+
+/// The SMS and push transports notifications are delivered through
+#[derive(Clone)]
+pub struct NotificationChannels {
+    sms: Arc<dyn SmsProvider>,
+    fcm: Arc<dyn PushProvider>,
+    apns: Arc<dyn PushProvider>,
+}
+
+impl NotificationChannels {
+    pub fn new(sms: Arc<dyn SmsProvider>, fcm: Arc<dyn PushProvider>, apns: Arc<dyn PushProvider>) -> Self {
+        Self { sms, fcm, apns }
+    }
+
+    /// Build providers from `SMS_PROVIDER`, `FCM_CREDENTIALS_PATH` and `APNS_KEY_PATH`; anything unset only logs
+    pub fn from_config(config: &AppConfig) -> Result<Self, AppError> {
+        let sms: Arc<dyn SmsProvider> = match config.sms_provider {
+            Some(SmsProviderKind::Twilio) => Arc::new(TwilioSmsProvider::new(
+                config.twilio_account_sid.clone().unwrap_or_default(),
+                config.twilio_auth_token.clone().unwrap_or_default(),
+                config.twilio_from_number.clone().unwrap_or_default(),
+            )),
+            None => {
+                warn!("SMS_PROVIDER is not set; text messages will only be logged");
+                Arc::new(LogSmsProvider)
+            }
+        };
+
+        let fcm: Arc<dyn PushProvider> = match &config.fcm_credentials_path {
+            Some(path) => Arc::new(FcmPushProvider::from_file(path)?),
+            None => {
+                warn!("FCM_CREDENTIALS_PATH is not set; Android push notifications will only be logged");
+                Arc::new(LogPushProvider)
+            }
+        };
+
+        let apns: Arc<dyn PushProvider> = match &config.apns_key_path {
+            Some(path) => Arc::new(ApnsPushProvider::from_key_file(
+                path,
+                config.apns_key_id.clone().unwrap_or_default(),
+                config.apns_team_id.clone().unwrap_or_default(),
+                config.apns_topic.clone().unwrap_or_default(),
+                config.apns_sandbox.unwrap_or(false),
+            )?),
+            None => {
+                warn!("APNS_KEY_PATH is not set; iOS push notifications will only be logged");
+                Arc::new(LogPushProvider)
+            }
+        };
+
+        info!(
+            "Notification channels: sms={}, fcm={}, apns={}",
+            sms.name(),
+            fcm.name(),
+            apns.name()
+        );
+
+        Ok(Self::new(sms, fcm, apns))
+    }
+
+    pub fn sms(&self) -> &dyn SmsProvider {
+        self.sms.as_ref()
+    }
+
+    pub fn push(&self, platform: PushPlatform) -> &dyn PushProvider {
+        match platform {
+            PushPlatform::Fcm => self.fcm.as_ref(),
+            PushPlatform::Apns => self.apns.as_ref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_data_strings() {
+        let message = PushMessage {
+            title: "Congratulations! You Won!".to_string(),
+            body: "You won".to_string(),
+            data: serde_json::json!({
+                "raffle_id": "5f0c",
+                "won": true,
+                "box_number": 7,
+                "credits_received": null
+            }),
+        };
+
+        let data = message.data_strings();
+        assert_eq!(data.get("raffle_id").map(String::as_str), Some("5f0c"));
+        assert_eq!(data.get("won").map(String::as_str), Some("true"));
+        assert_eq!(data.get("box_number").map(String::as_str), Some("7"));
+        assert!(!data.contains_key("credits_received"));
+    }
+
+    #[test]
+    fn test_failure_for_status() {
+        assert!(matches!(
+            failure_for_status(reqwest::StatusCode::TOO_MANY_REQUESTS, String::new()),
+            DeliveryFailure::Transient(_)
+        ));
+        assert!(matches!(
+            failure_for_status(reqwest::StatusCode::BAD_GATEWAY, String::new()),
+            DeliveryFailure::Transient(_)
+        ));
+        assert!(matches!(
+            failure_for_status(reqwest::StatusCode::BAD_REQUEST, String::new()),
+            DeliveryFailure::Permanent(_)
+        ));
+    }
+
+    #[test]
+    fn test_apns_permanent_reasons() {
+        assert!(apns_reason_is_permanent("BadDeviceToken"));
+        assert!(apns_reason_is_permanent("Unregistered"));
+        assert!(!apns_reason_is_permanent("TooManyRequests"));
+        assert!(!apns_reason_is_permanent("ExpiredProviderToken"));
+    }
+}
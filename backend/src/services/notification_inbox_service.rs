@@ -0,0 +1,252 @@
+use crate::error::AppError;
+use crate::models::notification::Notification;
+use crate::models::Pagination;
+use crate::services::realtime_service::{RealtimeService, WebSocketMessage};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use tracing::{debug, info, warn};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Postgres channel the notifications trigger announces inserts and reads on
+const NOTIFICATIONS_CHANNEL: &str = "notifications_changed";
+/// Wait before listening again after the listener connection drops
+const RELISTEN_DELAY_SECS: u64 = 5;
+
+/// The notifications a user has received, with read state, and the unread badge pushed to
+/// their open WebSocket connections.
+///
+/// Notifications are stored by whichever service raises them; a trigger announces every insert
+/// and read over Postgres, so each instance updates the badges of the connections it holds.
+#[derive(Clone)]
+pub struct NotificationInboxService {
+    db_pool: PgPool,
+    realtime_service: Option<RealtimeService>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NotificationListQuery {
+    /// Only notifications not yet read
+    #[serde(default)]
+    pub unread_only: bool,
+    /// Defaults to 20, at most 100
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// A page of the user's notifications, newest first
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NotificationPage {
+    pub data: Vec<Notification>,
+    pub total: i64,
+    pub unread_count: i64,
+    pub limit: i64,
+    pub offset: i64,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UnreadCount {
+    pub unread_count: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MarkAllReadResponse {
+    /// Notifications that were unread before the call
+    pub marked_read: u64,
+    pub unread_count: i64,
+}
+
+/// Sent to a user's connections when a notification arrives or is read
+#[derive(Debug, Serialize)]
+pub struct NotificationBadge {
+    pub unread_count: i64,
+    /// The notification that just arrived; None when the change was a read
+    pub notification: Option<Notification>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotificationsChanged {
+    user_id: Uuid,
+    notification_id: Option<Uuid>,
+}
+
+impl NotificationInboxService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self {
+            db_pool,
+            realtime_service: None,
+        }
+    }
+
+    /// Push unread badges to connected users
+    pub fn with_realtime(mut self, realtime_service: RealtimeService) -> Self {
+        self.realtime_service = Some(realtime_service);
+        self
+    }
+
+    /// Push badges as notifications arrive and are read, on this instance's connections
+    pub async fn start_background_tasks(&self) {
+        if self.realtime_service.is_none() {
+            return;
+        }
+
+        let service = self.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Some(mut listener) = service.listen().await {
+                    loop {
+                        match listener.recv().await {
+                            Ok(notification) => service.handle_change(notification.payload()).await,
+                            Err(e) => {
+                                warn!("Notification listener dropped, listening again: {}", e);
+                                break;
+                            }
+                        }
+                    }
+                }
+                tokio::time::sleep(tokio::time::Duration::from_secs(RELISTEN_DELAY_SECS)).await;
+            }
+        });
+
+        info!("Notification inbox background tasks started");
+    }
+
+    /// The user's notifications, newest first
+    pub async fn list(&self, user_id: Uuid, query: &NotificationListQuery) -> Result<NotificationPage, AppError> {
+        let pagination = Pagination::new(query.limit, query.offset);
+
+        let data = Notification::find_by_user(
+            &self.db_pool,
+            user_id,
+            pagination.limit,
+            pagination.offset,
+            query.unread_only,
+        )
+        .await?;
+        let total = Notification::count_for_user(&self.db_pool, user_id, query.unread_only).await?;
+        let unread_count = if query.unread_only {
+            total
+        } else {
+            Notification::get_unread_count(&self.db_pool, user_id).await?
+        };
+
+        Ok(NotificationPage {
+            data,
+            total,
+            unread_count,
+            limit: pagination.limit,
+            offset: pagination.offset,
+            has_more: pagination.offset + pagination.limit < total,
+        })
+    }
+
+    pub async fn unread_count(&self, user_id: Uuid) -> Result<UnreadCount, AppError> {
+        let unread_count = Notification::get_unread_count(&self.db_pool, user_id).await?;
+        Ok(UnreadCount { unread_count })
+    }
+
+    /// Mark one of the user's notifications read; reading it again changes nothing
+    pub async fn mark_read(&self, user_id: Uuid, notification_id: Uuid) -> Result<Notification, AppError> {
+        Notification::mark_read_for_user(&self.db_pool, user_id, notification_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Notification not found".to_string()))
+    }
+
+    pub async fn mark_all_read(&self, user_id: Uuid) -> Result<MarkAllReadResponse, AppError> {
+        let marked_read = Notification::mark_all_as_read(&self.db_pool, user_id).await?;
+        let unread_count = Notification::get_unread_count(&self.db_pool, user_id).await?;
+
+        debug!("User {} marked {} notifications read", user_id, marked_read);
+
+        Ok(MarkAllReadResponse { marked_read, unread_count })
+    }
+
+    // Private helper methods
+
+    async fn listen(&self) -> Option<PgListener> {
+        let listener = async {
+            let mut listener = PgListener::connect_with(&self.db_pool).await?;
+            listener.listen(NOTIFICATIONS_CHANNEL).await?;
+            Ok::<_, sqlx::Error>(listener)
+        };
+
+        match listener.await {
+            Ok(listener) => Some(listener),
+            Err(e) => {
+                warn!("Failed to listen for notification changes, retrying in {}s: {}", RELISTEN_DELAY_SECS, e);
+                None
+            }
+        }
+    }
+
+    async fn handle_change(&self, payload: &str) {
+        let change = match serde_json::from_str::<NotificationsChanged>(payload) {
+            Ok(change) => change,
+            Err(e) => {
+                warn!("Ignoring malformed notification change {}: {}", payload, e);
+                return;
+            }
+        };
+
+        if let Err(e) = self.push_badge(change.user_id, change.notification_id).await {
+            warn!("Failed to push notification badge to user {}: {}", change.user_id, e);
+        }
+    }
+
+    async fn push_badge(&self, user_id: Uuid, notification_id: Option<Uuid>) -> Result<(), AppError> {
+        let Some(realtime_service) = &self.realtime_service else { return Ok(()) };
+        if !realtime_service.is_user_connected(user_id).await {
+            return Ok(());
+        }
+
+        let notification = match notification_id {
+            Some(id) => Notification::find_for_user(&self.db_pool, user_id, id).await?,
+            None => None,
+        };
+        let badge = NotificationBadge {
+            unread_count: Notification::get_unread_count(&self.db_pool, user_id).await?,
+            notification,
+        };
+
+        let message = WebSocketMessage {
+            message_type: "notification_badge".to_string(),
+            data: serde_json::to_value(&badge)
+                .map_err(|e| AppError::Internal(format!("Failed to serialize notification badge: {}", e)))?,
+            timestamp: Utc::now(),
+        };
+
+        // The user may have disconnected since the check
+        if let Err(e) = realtime_service.send_to_user(user_id, message).await {
+            debug!("Notification badge for user {} not delivered: {}", user_id, e);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_payloads() {
+        let user_id = Uuid::new_v4();
+        let notification_id = Uuid::new_v4();
+
+        let inserted: NotificationsChanged = serde_json::from_str(&format!(
+            r#"{{"user_id": "{}", "notification_id": "{}"}}"#,
+            user_id, notification_id
+        ))
+        .unwrap();
+        assert_eq!(inserted.user_id, user_id);
+        assert_eq!(inserted.notification_id, Some(notification_id));
+
+        let read: NotificationsChanged =
+            serde_json::from_str(&format!(r#"{{"user_id": "{}", "notification_id": null}}"#, user_id)).unwrap();
+        assert_eq!(read.notification_id, None);
+    }
+}
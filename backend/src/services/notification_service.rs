@@ -1,18 +1,42 @@
+use crate::models::notification::{Notification, NotificationType as InAppNotificationType};
+use crate::models::notification_preferences::{NotificationPreferences, PushDevice};
+use crate::models::raffle_waitlist::WaitlistOpening;
 use crate::services::credit_service::{CreditService, ExpirationNotification};
+use crate::services::email_service::{EmailService, EmailTemplate};
+use crate::services::notification_channels::{DeliveryFailure, NotificationChannels, PushMessage};
+use crate::services::seller_webhook_service::{SellerWebhookEvent, SellerWebhookService};
 use crate::error::AppError;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+/// Recipients queued per fan-out batch
+pub const FAN_OUT_BATCH_SIZE: usize = 500;
+
+/// Delay between consecutive fan-out batches
+pub const FAN_OUT_BATCH_INTERVAL_SECS: i64 = 30;
+
+/// Upper bound on deliveries attempted per queue tick, so a large fan-out can't monopolize the queue
+const MAX_DELIVERIES_PER_TICK: usize = 1000;
+
+/// Two SMS segments; longer messages are cut short
+const MAX_SMS_LENGTH: usize = 320;
+
 /// Notification service handles sending notifications to users
 #[derive(Clone)]
 pub struct NotificationService {
     credit_service: CreditService,
     notification_queue: std::sync::Arc<RwLock<Vec<PendingNotification>>>,
     sent_notifications: std::sync::Arc<RwLock<HashMap<String, DateTime<Utc>>>>,
+    fan_outs: std::sync::Arc<RwLock<HashMap<Uuid, FanOutProgress>>>,
+    seller_webhooks: Option<SellerWebhookService>,
+    email_service: Option<EmailService>,
+    db_pool: Option<PgPool>,
+    channels: Option<NotificationChannels>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +51,82 @@ pub struct PendingNotification {
     pub scheduled_for: Option<DateTime<Utc>>,
     pub attempts: u32,
     pub max_attempts: u32,
+    #[serde(default)]
+    pub priority: NotificationPriority,
+    /// Set for notifications delivered as part of a batched fan-out
+    #[serde(default)]
+    pub fan_out_id: Option<Uuid>,
+}
+
+/// Delivery order within the queue; declared highest first
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationPriority {
+    High,
+    #[default]
+    Normal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanOutRecipient {
+    pub user_id: Uuid,
+    pub won: bool,
+    pub credits_received: Option<rust_decimal::Decimal>,
+}
+
+/// One paced batch of a raffle results fan-out, delivered through the outbox
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanOutBatch {
+    pub fan_out_id: Uuid,
+    pub raffle_id: Uuid,
+    pub item_title: String,
+    pub batch_index: usize,
+    pub batches: usize,
+    pub total_recipients: usize,
+    pub winners: usize,
+    pub started_at: DateTime<Utc>,
+    /// When the batch is due; each comes `FAN_OUT_BATCH_INTERVAL_SECS` after the one before
+    pub deliver_at: DateTime<Utc>,
+    pub recipients: Vec<FanOutRecipient>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanOutProgress {
+    pub fan_out_id: Uuid,
+    pub raffle_id: Uuid,
+    pub total_recipients: usize,
+    pub winners: usize,
+    pub batches: usize,
+    pub delivered: usize,
+    pub failed: usize,
+    pub winners_done: usize,
+    pub started_at: DateTime<Utc>,
+    pub winners_completed_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub duration_ms: Option<i64>,
+}
+
+impl FanOutProgress {
+    fn record(&mut self, is_winner: bool, delivered: bool) {
+        if delivered {
+            self.delivered += 1;
+        } else {
+            self.failed += 1;
+        }
+
+        let now = Utc::now();
+        if is_winner {
+            self.winners_done += 1;
+            if self.winners_done == self.winners {
+                self.winners_completed_at = Some(now);
+            }
+        }
+
+        if self.completed_at.is_none() && self.delivered + self.failed >= self.total_recipients {
+            self.completed_at = Some(now);
+            self.duration_ms = Some((now - self.started_at).num_milliseconds());
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -37,32 +137,38 @@ pub enum NotificationType {
     RaffleLost,
     BoxPurchased,
     PaymentReceived,
+    PurchaseReceipt,
+    /// Prize shipping and dispute updates, to winners and sellers
+    PrizeFulfillment,
+    /// Boxes a waitlisted user can buy again
+    WaitlistAvailable,
+    /// New raffles matching a saved search, and favorites nearly done
+    RaffleAlert,
     SystemAlert,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NotificationPreferences {
-    pub user_id: Uuid,
-    pub email_enabled: bool,
-    pub push_enabled: bool,
-    pub sms_enabled: bool,
-    pub credit_expiry_notifications: bool,
-    pub raffle_notifications: bool,
-    pub payment_notifications: bool,
-    pub marketing_notifications: bool,
-}
+impl NotificationType {
+    /// Text messages cost money per segment, so only these are worth one
+    fn sent_by_sms(&self) -> bool {
+        matches!(
+            self,
+            NotificationType::RaffleWon | NotificationType::CreditExpiring | NotificationType::SystemAlert
+        )
+    }
 
-impl Default for NotificationPreferences {
-    fn default() -> Self {
-        Self {
-            user_id: Uuid::new_v4(),
-            email_enabled: true,
-            push_enabled: true,
-            sms_enabled: false,
-            credit_expiry_notifications: true,
-            raffle_notifications: true,
-            payment_notifications: true,
-            marketing_notifications: false,
+    fn in_app_type(&self) -> InAppNotificationType {
+        match self {
+            NotificationType::CreditExpiring => InAppNotificationType::CreditExpiring,
+            NotificationType::CreditExpired => InAppNotificationType::CreditExpired,
+            NotificationType::RaffleWon => InAppNotificationType::RaffleWin,
+            NotificationType::RaffleLost => InAppNotificationType::RaffleLoss,
+            NotificationType::BoxPurchased => InAppNotificationType::BoxPurchased,
+            NotificationType::PaymentReceived => InAppNotificationType::PaymentReceived,
+            NotificationType::PurchaseReceipt => InAppNotificationType::PurchaseReceipt,
+            NotificationType::PrizeFulfillment => InAppNotificationType::PrizeFulfillment,
+            NotificationType::WaitlistAvailable => InAppNotificationType::RaffleWaitlist,
+            NotificationType::RaffleAlert => InAppNotificationType::RaffleAlert,
+            NotificationType::SystemAlert => InAppNotificationType::SystemAlert,
         }
     }
 }
@@ -74,9 +180,38 @@ impl NotificationService {
             credit_service,
             notification_queue: std::sync::Arc::new(RwLock::new(Vec::new())),
             sent_notifications: std::sync::Arc::new(RwLock::new(HashMap::new())),
+            fan_outs: std::sync::Arc::new(RwLock::new(HashMap::new())),
+            seller_webhooks: None,
+            email_service: None,
+            db_pool: None,
+            channels: None,
         }
     }
 
+    /// Read per-user preferences and devices, and store in-app notifications, in this database
+    pub fn with_database(mut self, db_pool: PgPool) -> Self {
+        self.db_pool = Some(db_pool);
+        self
+    }
+
+    /// Deliver the SMS and push channels through these providers
+    pub fn with_channels(mut self, channels: NotificationChannels) -> Self {
+        self.channels = Some(channels);
+        self
+    }
+
+    /// Also send seller events to the webhook endpoints sellers have registered
+    pub fn with_seller_webhooks(mut self, seller_webhooks: SellerWebhookService) -> Self {
+        self.seller_webhooks = Some(seller_webhooks);
+        self
+    }
+
+    /// Deliver the email channel through the email service's templates and send queue
+    pub fn with_email_service(mut self, email_service: EmailService) -> Self {
+        self.email_service = Some(email_service);
+        self
+    }
+
     /// Start background notification processing
     pub async fn start_background_tasks(&self) {
         let service = self.clone();
@@ -173,9 +308,12 @@ impl NotificationService {
             scheduled_for: None,
             attempts: 0,
             max_attempts: 3,
+            priority: NotificationPriority::Normal,
+            fan_out_id: None,
         };
 
         self.queue_notification(pending_notification).await;
+        self.credit_service.record_expiry_warning(&notification).await?;
 
         // Mark as sent
         {
@@ -193,18 +331,25 @@ impl NotificationService {
         debug!("Queued notification for user: {}", notification.user_id);
     }
 
-    /// Process the notification queue
+    /// Process due notifications, highest priority first, up to the per-tick limit
     async fn process_notification_queue(&self) -> Result<(), AppError> {
         let mut queue = self.notification_queue.write().await;
-        let mut processed_indices = Vec::new();
+        let now = Utc::now();
 
-        for (index, notification) in queue.iter_mut().enumerate() {
-            // Check if notification is scheduled for later
-            if let Some(scheduled_for) = notification.scheduled_for {
-                if scheduled_for > Utc::now() {
-                    continue;
-                }
-            }
+        let mut due: Vec<usize> = queue
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.scheduled_for.map_or(true, |at| at <= now))
+            .map(|(index, _)| index)
+            .collect();
+        due.sort_by_key(|&index| (queue[index].priority, queue[index].created_at));
+        due.truncate(MAX_DELIVERIES_PER_TICK);
+
+        let mut processed = HashSet::new();
+        let mut fan_out_outcomes = Vec::new();
+
+        for index in due {
+            let notification = &mut queue[index];
 
             // Check if we've exceeded max attempts
             if notification.attempts >= notification.max_attempts {
@@ -212,7 +357,10 @@ impl NotificationService {
                     "Notification {} exceeded max attempts ({}), removing from queue",
                     notification.id, notification.max_attempts
                 );
-                processed_indices.push(index);
+                processed.insert(notification.id);
+                if let Some(fan_out_id) = notification.fan_out_id {
+                    fan_out_outcomes.push((fan_out_id, notification.priority == NotificationPriority::High, false));
+                }
                 continue;
             }
 
@@ -221,8 +369,11 @@ impl NotificationService {
             
             match self.send_notification(notification).await {
                 Ok(()) => {
-                    info!("Successfully sent notification: {}", notification.id);
-                    processed_indices.push(index);
+                    debug!("Successfully sent notification: {}", notification.id);
+                    processed.insert(notification.id);
+                    if let Some(fan_out_id) = notification.fan_out_id {
+                        fan_out_outcomes.push((fan_out_id, notification.priority == NotificationPriority::High, true));
+                    }
                 }
                 Err(e) => {
                     error!(
@@ -237,15 +388,30 @@ impl NotificationService {
             }
         }
 
-        // Remove processed notifications (in reverse order to maintain indices)
-        for &index in processed_indices.iter().rev() {
-            queue.remove(index);
+        queue.retain(|n| !processed.contains(&n.id));
+        drop(queue);
+
+        if !fan_out_outcomes.is_empty() {
+            let mut fan_outs = self.fan_outs.write().await;
+            for (fan_out_id, is_winner, delivered) in fan_out_outcomes {
+                if let Some(progress) = fan_outs.get_mut(&fan_out_id) {
+                    let was_complete = progress.completed_at.is_some();
+                    progress.record(is_winner, delivered);
+                    if !was_complete && progress.completed_at.is_some() {
+                        info!(
+                            "Fan-out {} for raffle {} completed: {} delivered, {} failed in {}ms",
+                            fan_out_id, progress.raffle_id, progress.delivered, progress.failed,
+                            progress.duration_ms.unwrap_or_default()
+                        );
+                    }
+                }
+            }
         }
 
         Ok(())
     }
 
-    /// Send a notification (implement actual sending logic here)
+    /// Deliver a notification on every channel the user has turned on
     async fn send_notification(&self, notification: &PendingNotification) -> Result<(), AppError> {
         // Get user preferences
         let preferences = self.get_user_notification_preferences(notification.user_id).await?;
@@ -255,12 +421,16 @@ impl NotificationService {
             NotificationType::CreditExpiring | NotificationType::CreditExpired => {
                 preferences.credit_expiry_notifications
             }
-            NotificationType::RaffleWon | NotificationType::RaffleLost | NotificationType::BoxPurchased => {
-                preferences.raffle_notifications
-            }
+            NotificationType::RaffleWon
+            | NotificationType::RaffleLost
+            | NotificationType::BoxPurchased
+            | NotificationType::WaitlistAvailable
+            | NotificationType::RaffleAlert => preferences.raffle_notifications,
             NotificationType::PaymentReceived => {
                 preferences.payment_notifications
             }
+            NotificationType::PurchaseReceipt => true, // Receipts are transactional
+            NotificationType::PrizeFulfillment => true, // Shipping and disputes need action
             NotificationType::SystemAlert => true, // Always send system alerts
         };
 
@@ -272,6 +442,18 @@ impl NotificationService {
         // Send via different channels based on preferences
         let mut sent_via_any_channel = false;
 
+        if preferences.in_app_enabled {
+            match self.send_in_app_notification(notification).await {
+                Ok(()) => {
+                    debug!("In-app notification stored for user: {}", notification.user_id);
+                    sent_via_any_channel = true;
+                }
+                Err(e) => {
+                    warn!("Failed to store in-app notification: {}", e);
+                }
+            }
+        }
+
         if preferences.email_enabled {
             match self.send_email_notification(notification).await {
                 Ok(()) => {
@@ -286,8 +468,9 @@ impl NotificationService {
 
         if preferences.push_enabled {
             match self.send_push_notification(notification).await {
-                Ok(()) => {
-                    debug!("Push notification sent to user: {}", notification.user_id);
+                Ok(0) => {}
+                Ok(devices) => {
+                    debug!("Push notification sent to {} devices of user: {}", devices, notification.user_id);
                     sent_via_any_channel = true;
                 }
                 Err(e) => {
@@ -296,9 +479,10 @@ impl NotificationService {
             }
         }
 
-        if preferences.sms_enabled {
+        if preferences.sms_enabled && notification.notification_type.sent_by_sms() {
             match self.send_sms_notification(notification).await {
-                Ok(()) => {
+                Ok(false) => {}
+                Ok(true) => {
                     debug!("SMS notification sent to user: {}", notification.user_id);
                     sent_via_any_channel = true;
                 }
@@ -317,63 +501,154 @@ impl NotificationService {
         }
     }
 
-    /// Send email notification (placeholder implementation)
+    /// Queue the notification as an email; without an email service it is only logged
     async fn send_email_notification(&self, notification: &PendingNotification) -> Result<(), AppError> {
-        // TODO: Implement actual email sending logic
-        // This could use services like SendGrid, AWS SES, etc.
-        
-        debug!(
-            "Would send email to user {}: {} - {}",
-            notification.user_id, notification.title, notification.message
-        );
-        
-        // Simulate email sending delay
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        
+        let email_service = match &self.email_service {
+            Some(email_service) => email_service,
+            None => {
+                debug!(
+                    "Would send email to user {}: {} - {}",
+                    notification.user_id, notification.title, notification.message
+                );
+                return Ok(());
+            }
+        };
+
+        let template = match notification.notification_type {
+            NotificationType::RaffleWon => EmailTemplate::RaffleWon,
+            NotificationType::PurchaseReceipt => EmailTemplate::Receipt,
+            _ => EmailTemplate::Notification,
+        };
+
+        let mut data = notification.data.clone();
+        if let Some(fields) = data.as_object_mut() {
+            fields.insert("title".to_string(), serde_json::Value::String(notification.title.clone()));
+            fields.insert("message".to_string(), serde_json::Value::String(notification.message.clone()));
+        }
+
+        // The email queue retries on its own, so handing the message over counts as sent
+        email_service.queue_for_user(notification.user_id, template, data).await?;
+
         Ok(())
     }
 
-    /// Send push notification (placeholder implementation)
-    async fn send_push_notification(&self, notification: &PendingNotification) -> Result<(), AppError> {
-        // TODO: Implement actual push notification logic
-        // This could use services like Firebase Cloud Messaging, Apple Push Notification Service, etc.
-        
-        debug!(
-            "Would send push notification to user {}: {} - {}",
-            notification.user_id, notification.title, notification.message
-        );
-        
-        // Simulate push notification delay
-        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
-        
+    /// Store the notification in the user's in-app inbox
+    async fn send_in_app_notification(&self, notification: &PendingNotification) -> Result<(), AppError> {
+        let pool = match &self.db_pool {
+            Some(pool) => pool,
+            None => {
+                debug!(
+                    "Would store in-app notification for user {}: {}",
+                    notification.user_id, notification.title
+                );
+                return Ok(());
+            }
+        };
+
+        Notification::create(
+            pool,
+            notification.user_id,
+            notification.title.clone(),
+            notification.message.clone(),
+            notification.notification_type.in_app_type(),
+            Some(notification.data.clone()),
+        )
+        .await?;
+
         Ok(())
     }
 
-    /// Send SMS notification (placeholder implementation)
-    async fn send_sms_notification(&self, notification: &PendingNotification) -> Result<(), AppError> {
-        // TODO: Implement actual SMS sending logic
-        // This could use services like Twilio, AWS SNS, etc.
-        
-        debug!(
-            "Would send SMS to user {}: {} - {}",
-            notification.user_id, notification.title, notification.message
-        );
-        
-        // Simulate SMS sending delay
-        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
-        
-        Ok(())
+    /// Push to each of the user's devices; returns how many were reached
+    async fn send_push_notification(&self, notification: &PendingNotification) -> Result<usize, AppError> {
+        let (pool, channels) = match (&self.db_pool, &self.channels) {
+            (Some(pool), Some(channels)) => (pool, channels),
+            _ => {
+                debug!(
+                    "Would send push notification to user {}: {} - {}",
+                    notification.user_id, notification.title, notification.message
+                );
+                return Ok(1);
+            }
+        };
+
+        let devices = PushDevice::list_for_user(pool, notification.user_id).await?;
+        let message = PushMessage {
+            title: notification.title.clone(),
+            body: notification.message.clone(),
+            data: notification.data.clone(),
+        };
+
+        let mut delivered = 0;
+        let mut last_transient = None;
+        for device in devices {
+            let provider = channels.push(device.platform);
+            match provider.send(&device.token, &message).await {
+                Ok(_) => {
+                    delivered += 1;
+                    PushDevice::mark_used(pool, device.id).await?;
+                }
+                Err(DeliveryFailure::Permanent(reason)) => {
+                    info!("Removing push device {} of user {}: {}", device.id, device.user_id, reason);
+                    PushDevice::remove_token(pool, &device.token).await?;
+                }
+                Err(DeliveryFailure::Transient(reason)) => {
+                    warn!("{} push to device {} failed: {}", provider.name(), device.id, reason);
+                    last_transient = Some(reason);
+                }
+            }
+        }
+
+        match last_transient {
+            Some(reason) if delivered == 0 => Err(AppError::Internal(format!("Push delivery failed: {}", reason))),
+            _ => Ok(delivered),
+        }
+    }
+
+    /// Text the phone number on the user's account; returns false when there is none to text
+    async fn send_sms_notification(&self, notification: &PendingNotification) -> Result<bool, AppError> {
+        let (pool, channels) = match (&self.db_pool, &self.channels) {
+            (Some(pool), Some(channels)) => (pool, channels),
+            _ => {
+                debug!(
+                    "Would send SMS to user {}: {} - {}",
+                    notification.user_id, notification.title, notification.message
+                );
+                return Ok(true);
+            }
+        };
+
+        let phone_number: Option<String> = sqlx::query_scalar("SELECT phone_number FROM users WHERE id = $1")
+            .bind(notification.user_id)
+            .fetch_optional(pool)
+            .await?
+            .flatten();
+        let phone_number = match phone_number.filter(|phone| !phone.trim().is_empty()) {
+            Some(phone_number) => phone_number,
+            None => {
+                debug!("User {} has SMS enabled but no phone number", notification.user_id);
+                return Ok(false);
+            }
+        };
+
+        let body = sms_body(&notification.title, &notification.message);
+        match channels.sms().send(&phone_number, &body).await {
+            Ok(_) => Ok(true),
+            Err(DeliveryFailure::Permanent(reason)) => {
+                warn!("{} rejected SMS to user {}: {}", channels.sms().name(), notification.user_id, reason);
+                Ok(false)
+            }
+            Err(DeliveryFailure::Transient(reason)) => {
+                Err(AppError::Internal(format!("SMS delivery failed: {}", reason)))
+            }
+        }
     }
 
-    /// Get user notification preferences (placeholder implementation)
+    /// Get the user's saved preferences; defaults when none are saved or there is no database
     async fn get_user_notification_preferences(&self, user_id: Uuid) -> Result<NotificationPreferences, AppError> {
-        // TODO: Implement actual database lookup for user preferences
-        // For now, return default preferences
-        
-        Ok(NotificationPreferences {
-            user_id,
-            ..Default::default()
-        })
+        match &self.db_pool {
+            Some(pool) => NotificationPreferences::find(pool, user_id).await,
+            None => Ok(NotificationPreferences::defaults_for(user_id)),
+        }
     }
 
     /// Log notification sent (placeholder implementation)
@@ -397,86 +672,316 @@ impl NotificationService {
         item_title: String,
         credits_received: Option<rust_decimal::Decimal>,
     ) -> Result<(), AppError> {
-        let (notification_type, title, message) = if won {
-            (
-                NotificationType::RaffleWon,
-                "Congratulations! You Won!".to_string(),
-                format!("You won the raffle for '{}'! Check your account for details.", item_title),
-            )
-        } else {
-            let credits_msg = if let Some(credits) = credits_received {
-                format!(" You received {} credits that you can use for future purchases.", credits)
-            } else {
-                String::new()
-            };
-            
-            (
-                NotificationType::RaffleLost,
-                "Raffle Results".to_string(),
-                format!("The raffle for '{}' has ended.{}", item_title, credits_msg),
-            )
+        let notification = raffle_result_notification(user_id, raffle_id, won, &item_title, credits_received);
+
+        self.queue_notification(notification).await;
+        Ok(())
+    }
+
+    /// Queue one batch of a raffle results fan-out; the outbox paces the batches
+    pub async fn deliver_fan_out_batch(&self, batch: FanOutBatch) -> Result<(), AppError> {
+        let mut notifications = Vec::with_capacity(batch.recipients.len());
+        for recipient in &batch.recipients {
+            let mut notification = raffle_result_notification(
+                recipient.user_id,
+                batch.raffle_id,
+                recipient.won,
+                &batch.item_title,
+                recipient.credits_received,
+            );
+            notification.fan_out_id = Some(batch.fan_out_id);
+            if recipient.won {
+                notification.priority = NotificationPriority::High;
+            }
+            notifications.push(notification);
+        }
+
+        self.fan_outs
+            .write()
+            .await
+            .entry(batch.fan_out_id)
+            .or_insert_with(|| FanOutProgress {
+                fan_out_id: batch.fan_out_id,
+                raffle_id: batch.raffle_id,
+                total_recipients: batch.total_recipients,
+                winners: batch.winners,
+                batches: batch.batches,
+                delivered: 0,
+                failed: 0,
+                winners_done: 0,
+                started_at: batch.started_at,
+                winners_completed_at: None,
+                completed_at: None,
+                duration_ms: None,
+            });
+        self.notification_queue.write().await.extend(notifications);
+
+        info!(
+            "Queued batch {}/{} of fan-out {} for raffle {}: {} recipients",
+            batch.batch_index + 1,
+            batch.batches,
+            batch.fan_out_id,
+            batch.raffle_id,
+            batch.recipients.len()
+        );
+
+        Ok(())
+    }
+
+    /// Get progress and completion times of recent fan-outs, newest first
+    pub async fn get_fan_out_metrics(&self) -> Vec<FanOutProgress> {
+        let mut fan_outs: Vec<FanOutProgress> = self.fan_outs.read().await.values().cloned().collect();
+        fan_outs.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        fan_outs
+    }
+
+    /// Send box purchase confirmation notification
+    pub async fn send_box_purchase_notification(
+        &self,
+        user_id: Uuid,
+        raffle_id: Uuid,
+        item_title: String,
+        box_number: u32,
+        total_boxes: u32,
+    ) -> Result<(), AppError> {
+        let notification = PendingNotification {
+            id: Uuid::new_v4(),
+            user_id,
+            notification_type: NotificationType::BoxPurchased,
+            title: "Box Purchase Confirmed".to_string(),
+            message: format!(
+                "You purchased box #{} for '{}'. {} of {} boxes sold.",
+                box_number, item_title, box_number, total_boxes
+            ),
+            data: serde_json::json!({
+                "raffle_id": raffle_id,
+                "item_title": item_title,
+                "box_number": box_number,
+                "total_boxes": total_boxes
+            }),
+            created_at: Utc::now(),
+            scheduled_for: None,
+            attempts: 0,
+            max_attempts: 3,
+            priority: NotificationPriority::Normal,
+            fan_out_id: None,
+        };
+
+        self.queue_notification(notification).await;
+        Ok(())
+    }
+
+    /// Send box purchase receipt notification
+    pub async fn send_purchase_receipt_notification(
+        &self,
+        user_id: Uuid,
+        raffle_id: Uuid,
+        item_title: String,
+        receipt: serde_json::Value,
+    ) -> Result<(), AppError> {
+        let receipt_number = receipt["receipt_number"].as_str().unwrap_or_default().to_string();
+
+        let notification = PendingNotification {
+            id: Uuid::new_v4(),
+            user_id,
+            notification_type: NotificationType::PurchaseReceipt,
+            title: format!("Your receipt {}", receipt_number),
+            message: format!("Thanks for your purchase in the raffle for '{}'. Your receipt is attached.", item_title),
+            data: serde_json::json!({
+                "raffle_id": raffle_id,
+                "item_title": item_title,
+                "receipt": receipt
+            }),
+            created_at: Utc::now(),
+            scheduled_for: None,
+            attempts: 0,
+            max_attempts: 3,
+            priority: NotificationPriority::Normal,
+            fan_out_id: None,
+        };
+
+        self.queue_notification(notification).await;
+        Ok(())
+    }
+
+    /// Send a prize shipping or dispute update
+    pub async fn send_fulfillment_notification(
+        &self,
+        user_id: Uuid,
+        title: String,
+        message: String,
+        data: serde_json::Value,
+    ) {
+        let notification = PendingNotification {
+            id: Uuid::new_v4(),
+            user_id,
+            notification_type: NotificationType::PrizeFulfillment,
+            title,
+            message,
+            data,
+            created_at: Utc::now(),
+            scheduled_for: None,
+            attempts: 0,
+            max_attempts: 3,
+            priority: NotificationPriority::Normal,
+            fan_out_id: None,
+        };
+
+        self.queue_notification(notification).await;
+    }
+
+    /// Tell a waitlisted user they can buy boxes again
+    pub async fn send_waitlist_notification(
+        &self,
+        user_id: Uuid,
+        raffle_id: Uuid,
+        item_title: String,
+        opening: WaitlistOpening,
+    ) -> Result<(), AppError> {
+        let (title, message) = match opening {
+            WaitlistOpening::BoxesFreed => (
+                "Boxes are available again".to_string(),
+                format!("Boxes came free in the raffle for '{}'. Buy soon; others are waiting too.", item_title),
+            ),
+            WaitlistOpening::Relisted => (
+                "Back in stock".to_string(),
+                format!("'{}' is in a new raffle.", item_title),
+            ),
         };
 
         let notification = PendingNotification {
             id: Uuid::new_v4(),
             user_id,
-            notification_type,
+            notification_type: NotificationType::WaitlistAvailable,
             title,
             message,
             data: serde_json::json!({
                 "raffle_id": raffle_id,
                 "item_title": item_title,
-                "won": won,
-                "credits_received": credits_received
+                "opening": opening
             }),
             created_at: Utc::now(),
             scheduled_for: None,
             attempts: 0,
             max_attempts: 3,
+            priority: NotificationPriority::Normal,
+            fan_out_id: None,
         };
 
         self.queue_notification(notification).await;
         Ok(())
     }
 
-    /// Send box purchase confirmation notification
-    pub async fn send_box_purchase_notification(
+    /// Tell a user about new raffles matching one of their saved searches
+    pub async fn send_saved_search_notification(
+        &self,
+        user_id: Uuid,
+        saved_search_id: Uuid,
+        search_name: String,
+        raffle_ids: Vec<Uuid>,
+        item_titles: Vec<String>,
+    ) -> Result<(), AppError> {
+        let message = match item_titles.as_slice() {
+            [title] => format!("'{}' is in a new raffle.", title),
+            [first, rest @ ..] => format!("'{}' and {} more are in new raffles.", first, rest.len()),
+            [] => return Ok(()),
+        };
+
+        let notification = PendingNotification {
+            id: Uuid::new_v4(),
+            user_id,
+            notification_type: NotificationType::RaffleAlert,
+            title: format!("New matches for '{}'", search_name),
+            message,
+            data: serde_json::json!({
+                "saved_search_id": saved_search_id,
+                "raffle_ids": raffle_ids,
+                "item_titles": item_titles
+            }),
+            created_at: Utc::now(),
+            scheduled_for: None,
+            attempts: 0,
+            max_attempts: 3,
+            priority: NotificationPriority::Normal,
+            fan_out_id: None,
+        };
+
+        self.queue_notification(notification).await;
+        Ok(())
+    }
+
+    /// Tell a user a raffle they favorited is nearly sold out or about to end
+    pub async fn send_favorite_notification(
         &self,
         user_id: Uuid,
         raffle_id: Uuid,
         item_title: String,
-        box_number: u32,
-        total_boxes: u32,
+        boxes_remaining: i32,
+        ends_at: Option<DateTime<Utc>>,
     ) -> Result<(), AppError> {
+        let message = match ends_at {
+            Some(ends_at) if ends_at <= Utc::now() + chrono::Duration::hours(24) => format!(
+                "The raffle for '{}' ends at {} with {} boxes left.",
+                item_title,
+                ends_at.format("%H:%M UTC"),
+                boxes_remaining
+            ),
+            _ => format!("Only {} boxes are left in the raffle for '{}'.", boxes_remaining, item_title),
+        };
+
         let notification = PendingNotification {
             id: Uuid::new_v4(),
             user_id,
-            notification_type: NotificationType::BoxPurchased,
-            title: "Box Purchase Confirmed".to_string(),
-            message: format!(
-                "You purchased box #{} for '{}'. {} of {} boxes sold.",
-                box_number, item_title, box_number, total_boxes
-            ),
+            notification_type: NotificationType::RaffleAlert,
+            title: "A favorite raffle is almost done".to_string(),
+            message,
             data: serde_json::json!({
                 "raffle_id": raffle_id,
                 "item_title": item_title,
-                "box_number": box_number,
-                "total_boxes": total_boxes
+                "boxes_remaining": boxes_remaining,
+                "ends_at": ends_at
             }),
             created_at: Utc::now(),
             scheduled_for: None,
             attempts: 0,
             max_attempts: 3,
+            priority: NotificationPriority::Normal,
+            fan_out_id: None,
         };
 
         self.queue_notification(notification).await;
         Ok(())
     }
 
+    /// Send an event to the seller's webhook endpoints; failures are logged and never reach the caller
+    pub async fn notify_seller(&self, seller_id: Uuid, event: SellerWebhookEvent, data: serde_json::Value) {
+        let seller_webhooks = match &self.seller_webhooks {
+            Some(seller_webhooks) => seller_webhooks,
+            None => return,
+        };
+
+        if let Err(e) = seller_webhooks.publish(seller_id, event, data).await {
+            error!("Failed to queue {} webhook for seller {}: {}", event.as_str(), seller_id, e);
+        }
+    }
+
+    /// Send a raffle event to the platform webhook endpoints; failures are logged and never reach the caller
+    pub async fn notify_platform(&self, raffle_id: Uuid, event: SellerWebhookEvent, data: serde_json::Value) {
+        let seller_webhooks = match &self.seller_webhooks {
+            Some(seller_webhooks) => seller_webhooks,
+            None => return,
+        };
+
+        if let Err(e) = seller_webhooks.publish_platform(raffle_id, event, data).await {
+            error!("Failed to queue {} platform webhook for raffle {}: {}", event.as_str(), raffle_id, e);
+        }
+    }
+
     /// Get notification queue status
     pub async fn get_queue_status(&self) -> NotificationQueueStatus {
         let queue = self.notification_queue.read().await;
         let sent_notifications = self.sent_notifications.read().await;
+        let fan_outs = self.fan_outs.read().await;
 
         NotificationQueueStatus {
             pending_notifications: queue.len(),
@@ -486,6 +991,9 @@ impl NotificationService {
             queue_oldest_notification: queue.iter()
                 .map(|n| n.created_at)
                 .min(),
+            active_fan_outs: fan_outs.values()
+                .filter(|f| f.completed_at.is_none())
+                .count(),
         }
     }
 
@@ -495,6 +1003,9 @@ impl NotificationService {
         let cutoff = Utc::now() - chrono::Duration::days(7);
         
         sent_notifications.retain(|_, &mut sent_at| sent_at > cutoff);
+
+        let mut fan_outs = self.fan_outs.write().await;
+        fan_outs.retain(|_, f| f.completed_at.map_or(true, |completed_at| completed_at > cutoff));
         
         debug!("Cleaned up old notification records");
     }
@@ -505,4 +1016,229 @@ pub struct NotificationQueueStatus {
     pub pending_notifications: usize,
     pub total_sent_today: usize,
     pub queue_oldest_notification: Option<DateTime<Utc>>,
+    pub active_fan_outs: usize,
+}
+
+fn raffle_result_notification(
+    user_id: Uuid,
+    raffle_id: Uuid,
+    won: bool,
+    item_title: &str,
+    credits_received: Option<rust_decimal::Decimal>,
+) -> PendingNotification {
+    let (notification_type, title, message) = if won {
+        (
+            NotificationType::RaffleWon,
+            "Congratulations! You Won!".to_string(),
+            format!("You won the raffle for '{}'! Check your account for details.", item_title),
+        )
+    } else {
+        let credits_msg = if let Some(credits) = credits_received {
+            format!(" You received {} credits that you can use for future purchases.", credits)
+        } else {
+            String::new()
+        };
+        
+        (
+            NotificationType::RaffleLost,
+            "Raffle Results".to_string(),
+            format!("The raffle for '{}' has ended.{}", item_title, credits_msg),
+        )
+    };
+
+    PendingNotification {
+        id: Uuid::new_v4(),
+        user_id,
+        notification_type,
+        title,
+        message,
+        data: serde_json::json!({
+            "raffle_id": raffle_id,
+            "item_title": item_title,
+            "won": won,
+            "credits_received": credits_received
+        }),
+        created_at: Utc::now(),
+        scheduled_for: None,
+        attempts: 0,
+        max_attempts: 3,
+        priority: NotificationPriority::Normal,
+        fan_out_id: None,
+    }
+}
+
+/// Title and message as one text, cut to two SMS segments
+fn sms_body(title: &str, message: &str) -> String {
+    let body = format!("{}: {}", title, message);
+    if body.chars().count() <= MAX_SMS_LENGTH {
+        return body;
+    }
+
+    let mut truncated: String = body.chars().take(MAX_SMS_LENGTH - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Split recipients into delivery batches: one entry per user, winners before everyone else
+pub fn plan_fan_out(recipients: Vec<FanOutRecipient>, batch_size: usize) -> Vec<Vec<FanOutRecipient>> {
+    let mut by_user: HashMap<Uuid, FanOutRecipient> = HashMap::new();
+    let mut order = Vec::new();
+    for recipient in recipients {
+        match by_user.get_mut(&recipient.user_id) {
+            Some(existing) => existing.won |= recipient.won,
+            None => {
+                order.push(recipient.user_id);
+                by_user.insert(recipient.user_id, recipient);
+            }
+        }
+    }
+
+    let mut ordered: Vec<FanOutRecipient> = order
+        .into_iter()
+        .filter_map(|user_id| by_user.remove(&user_id))
+        .collect();
+    // Stable sort keeps the caller's order within winners and within everyone else
+    ordered.sort_by_key(|r| !r.won);
+
+    ordered
+        .chunks(batch_size.max(1))
+        .map(|chunk| chunk.to_vec())
+        .collect()
+}
+
+/// Plan a raffle results fan-out: winners in the first batch, each later batch paced
+/// `FAN_OUT_BATCH_INTERVAL_SECS` after the one before
+pub fn plan_fan_out_batches(
+    raffle_id: Uuid,
+    item_title: &str,
+    recipients: Vec<FanOutRecipient>,
+    started_at: DateTime<Utc>,
+) -> Vec<FanOutBatch> {
+    let fan_out_id = Uuid::new_v4();
+    let batches = plan_fan_out(recipients, FAN_OUT_BATCH_SIZE);
+    let total_recipients = batches.iter().map(Vec::len).sum();
+    let winners = batches.iter().flatten().filter(|r| r.won).count();
+    let batch_count = batches.len();
+
+    batches
+        .into_iter()
+        .enumerate()
+        .map(|(batch_index, recipients)| FanOutBatch {
+            fan_out_id,
+            raffle_id,
+            item_title: item_title.to_string(),
+            batch_index,
+            batches: batch_count,
+            total_recipients,
+            winners,
+            started_at,
+            deliver_at: started_at + chrono::Duration::seconds(FAN_OUT_BATCH_INTERVAL_SECS * batch_index as i64),
+            recipients,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipient(won: bool) -> FanOutRecipient {
+        FanOutRecipient {
+            user_id: Uuid::new_v4(),
+            won,
+            credits_received: None,
+        }
+    }
+
+    #[test]
+    fn test_plan_fan_out_puts_winners_first() {
+        let losers: Vec<_> = (0..5).map(|_| recipient(false)).collect();
+        let winner = recipient(true);
+
+        let mut recipients = losers.clone();
+        recipients.insert(3, winner.clone());
+
+        let batches = plan_fan_out(recipients, 2);
+
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0][0].user_id, winner.user_id);
+        assert_eq!(batches[0][1].user_id, losers[0].user_id);
+        assert_eq!(batches[2].len(), 2);
+    }
+
+    #[test]
+    fn test_plan_fan_out_deduplicates_users() {
+        let winner = recipient(true);
+        let as_loser = FanOutRecipient { won: false, ..winner.clone() };
+
+        let batches = plan_fan_out(vec![as_loser, winner.clone()], 10);
+
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].len(), 1);
+        assert!(batches[0][0].won);
+    }
+
+    #[test]
+    fn test_fan_out_batches_are_paced() {
+        let mut recipients: Vec<_> = (0..FAN_OUT_BATCH_SIZE * 2).map(|_| recipient(false)).collect();
+        recipients.push(recipient(true));
+        let started_at = Utc::now();
+
+        let batches = plan_fan_out_batches(Uuid::new_v4(), "Watch", recipients, started_at);
+
+        assert_eq!(batches.len(), 3);
+        assert!(batches[0].recipients[0].won);
+        assert!(batches.iter().all(|b| b.fan_out_id == batches[0].fan_out_id));
+        assert!(batches.iter().all(|b| b.total_recipients == FAN_OUT_BATCH_SIZE * 2 + 1 && b.winners == 1));
+        assert_eq!(batches[0].deliver_at, started_at);
+        assert_eq!(
+            batches[2].deliver_at - batches[1].deliver_at,
+            chrono::Duration::seconds(FAN_OUT_BATCH_INTERVAL_SECS)
+        );
+    }
+
+    #[test]
+    fn test_sms_body_is_truncated() {
+        assert_eq!(sms_body("Credits Expiring Soon", "Use them"), "Credits Expiring Soon: Use them");
+
+        let body = sms_body("Congratulations! You Won!", &"x".repeat(400));
+        assert_eq!(body.chars().count(), MAX_SMS_LENGTH);
+        assert!(body.ends_with('…'));
+    }
+
+    #[test]
+    fn test_only_urgent_notifications_go_by_sms() {
+        assert!(NotificationType::RaffleWon.sent_by_sms());
+        assert!(NotificationType::CreditExpiring.sent_by_sms());
+        assert!(!NotificationType::RaffleLost.sent_by_sms());
+        assert!(!NotificationType::PurchaseReceipt.sent_by_sms());
+    }
+
+    #[test]
+    fn test_fan_out_progress_completion() {
+        let mut progress = FanOutProgress {
+            fan_out_id: Uuid::new_v4(),
+            raffle_id: Uuid::new_v4(),
+            total_recipients: 3,
+            winners: 1,
+            batches: 1,
+            delivered: 0,
+            failed: 0,
+            winners_done: 0,
+            started_at: Utc::now(),
+            winners_completed_at: None,
+            completed_at: None,
+            duration_ms: None,
+        };
+
+        progress.record(true, true);
+        assert!(progress.winners_completed_at.is_some());
+        assert!(progress.completed_at.is_none());
+
+        progress.record(false, false);
+        progress.record(false, true);
+        assert_eq!((progress.delivered, progress.failed), (2, 1));
+        assert!(progress.completed_at.is_some());
+        assert!(progress.duration_ms.is_some());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,408 @@
+use crate::error::AppError;
+use crate::models::raffle_waitlist::WaitlistOpening;
+use crate::services::notification_service::{FanOutBatch, NotificationService};
+use crate::services::realtime_service::{RealtimeEvent, RealtimeService};
+use crate::services::seller_webhook_service::{SellerWebhookEvent, SellerWebhookService};
+use crate::services::webhook_service::retry_delay_seconds;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::postgres::PgListener;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+/// Channel the outbox insert trigger notifies on commit
+const OUTBOX_CHANNEL: &str = "event_outbox";
+/// Messages claimed per relay pass
+const RELAY_BATCH_SIZE: i64 = 100;
+/// Poll this often even without a notification, to pick up retries and missed wakeups
+const RELAY_POLL_SECS: u64 = 5;
+const RETRY_BASE_SECONDS: i64 = 5;
+const RETRY_MAX_SECONDS: i64 = 3600;
+/// Give up on a message after this many failed publishes
+const MAX_ATTEMPTS: i32 = 10;
+/// Published messages are kept this long for debugging
+const PUBLISHED_RETENTION_DAYS: i64 = 7;
+
+/// An event written to the outbox with the change it describes, published once that change
+/// has committed. Delivery is at least once: a relay crash after publishing but before marking
+/// the message published sends it again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "payload", rename_all = "snake_case")]
+pub enum OutboxMessage {
+    /// Broadcast to websocket subscribers
+    Realtime(RealtimeEvent),
+    /// Queue the buyer's purchase confirmation
+    BoxPurchaseNotification {
+        user_id: Uuid,
+        raffle_id: Uuid,
+        item_title: String,
+        box_number: u32,
+        total_boxes: u32,
+    },
+    /// Email the buyer the receipt for a checkout
+    PurchaseReceipt {
+        receipt_id: Uuid,
+        user_id: Uuid,
+        raffle_id: Uuid,
+        item_title: String,
+        receipt: Value,
+    },
+    /// Tell a waitlisted user they can buy again
+    WaitlistNotification {
+        user_id: Uuid,
+        raffle_id: Uuid,
+        item_title: String,
+        opening: WaitlistOpening,
+    },
+    /// Announce new raffles matching a saved search
+    SavedSearchAlert {
+        user_id: Uuid,
+        saved_search_id: Uuid,
+        search_name: String,
+        raffle_ids: Vec<Uuid>,
+        item_titles: Vec<String>,
+    },
+    /// Tell a user a favorite raffle is nearly done
+    FavoriteRaffleAlert {
+        user_id: Uuid,
+        raffle_id: Uuid,
+        item_title: String,
+        boxes_remaining: i32,
+        ends_at: Option<DateTime<Utc>>,
+    },
+    /// Notify one batch of a completed raffle's participants
+    RaffleResultBatch(FanOutBatch),
+    /// Queue a delivery to the seller's webhook endpoints
+    SellerWebhook {
+        seller_id: Uuid,
+        event: SellerWebhookEvent,
+        data: Value,
+    },
+    /// Queue a delivery to the platform webhook endpoints
+    PlatformWebhook {
+        raffle_id: Uuid,
+        event: SellerWebhookEvent,
+        data: Value,
+    },
+}
+
+impl OutboxMessage {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            OutboxMessage::Realtime(_) => "realtime",
+            OutboxMessage::BoxPurchaseNotification { .. } => "box_purchase_notification",
+            OutboxMessage::PurchaseReceipt { .. } => "purchase_receipt",
+            OutboxMessage::WaitlistNotification { .. } => "waitlist_notification",
+            OutboxMessage::SavedSearchAlert { .. } => "saved_search_alert",
+            OutboxMessage::FavoriteRaffleAlert { .. } => "favorite_raffle_alert",
+            OutboxMessage::RaffleResultBatch(_) => "raffle_result_batch",
+            OutboxMessage::SellerWebhook { .. } => "seller_webhook",
+            OutboxMessage::PlatformWebhook { .. } => "platform_webhook",
+        }
+    }
+
+    /// Write messages in the caller's transaction; they are published only if it commits
+    pub async fn enqueue(tx: &mut Transaction<'_, Postgres>, messages: &[OutboxMessage]) -> Result<(), AppError> {
+        Self::enqueue_at(tx, messages, Utc::now()).await
+    }
+
+    /// Like `enqueue`, but the messages aren't published before `publish_at`
+    pub async fn enqueue_at(
+        tx: &mut Transaction<'_, Postgres>,
+        messages: &[OutboxMessage],
+        publish_at: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        for message in messages {
+            let payload = serde_json::to_value(message)
+                .map_err(|e| AppError::Internal(format!("Failed to serialize outbox message: {}", e)))?;
+
+            sqlx::query("INSERT INTO event_outbox (kind, message, next_attempt_at) VALUES ($1, $2, $3)")
+                .bind(message.kind())
+                .bind(payload)
+                .bind(publish_at)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PendingMessage {
+    id: Uuid,
+    message: Value,
+    attempts: i32,
+}
+
+/// Outbox relay publishes committed outbox messages to websocket, notification and webhook
+/// consumers, retrying failures with backoff
+#[derive(Clone)]
+pub struct OutboxService {
+    db_pool: PgPool,
+    realtime_service: RealtimeService,
+    notification_service: NotificationService,
+    seller_webhook_service: SellerWebhookService,
+}
+
+impl OutboxService {
+    pub fn new(
+        db_pool: PgPool,
+        realtime_service: RealtimeService,
+        notification_service: NotificationService,
+        seller_webhook_service: SellerWebhookService,
+    ) -> Self {
+        Self {
+            db_pool,
+            realtime_service,
+            notification_service,
+            seller_webhook_service,
+        }
+    }
+
+    /// Start the relay and the cleanup of published messages
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut listener = service.listen().await;
+
+            loop {
+                loop {
+                    match service.relay_pending().await {
+                        Ok(n) if n as i64 == RELAY_BATCH_SIZE => continue,
+                        Ok(_) => break,
+                        Err(e) => {
+                            error!("Outbox relay pass failed: {}", e);
+                            break;
+                        }
+                    }
+                }
+
+                let poll = tokio::time::Duration::from_secs(RELAY_POLL_SECS);
+                match listener.as_mut() {
+                    Some(l) => match tokio::time::timeout(poll, l.recv()).await {
+                        Ok(Err(e)) => {
+                            warn!("Outbox listener dropped, falling back to polling: {}", e);
+                            listener = None;
+                        }
+                        Ok(Ok(_)) | Err(_) => {}
+                    },
+                    None => {
+                        tokio::time::sleep(poll).await;
+                        listener = service.listen().await;
+                    }
+                }
+            }
+        });
+
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(3600));
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.cleanup_published().await {
+                    error!("Outbox cleanup failed: {}", e);
+                }
+            }
+        });
+
+        info!("Outbox relay started");
+    }
+
+    /// Publish one batch of due messages; returns how many were claimed
+    pub async fn relay_pending(&self) -> Result<usize, AppError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        // Other instances skip rows this one holds, so each message is relayed by one of them
+        let pending = sqlx::query_as::<_, PendingMessage>(
+            r#"
+            SELECT id, message, attempts
+            FROM event_outbox
+            WHERE published_at IS NULL AND failed_at IS NULL AND next_attempt_at <= NOW()
+            ORDER BY created_at, id
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(RELAY_BATCH_SIZE)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for row in &pending {
+            let result = match serde_json::from_value::<OutboxMessage>(row.message.clone()) {
+                Ok(message) => self.publish(message).await,
+                Err(e) => Err(AppError::Internal(format!("Unreadable outbox message: {}", e))),
+            };
+
+            match result {
+                Ok(()) => {
+                    sqlx::query("UPDATE event_outbox SET published_at = NOW(), last_error = NULL WHERE id = $1")
+                        .bind(row.id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                Err(e) => {
+                    let attempts = row.attempts + 1;
+                    let retry_at = (attempts < MAX_ATTEMPTS).then(|| {
+                        Utc::now()
+                            + Duration::seconds(retry_delay_seconds(attempts as i64, RETRY_BASE_SECONDS, RETRY_MAX_SECONDS))
+                    });
+                    match retry_at {
+                        Some(_) => warn!("Outbox message {} failed (attempt {}): {}", row.id, attempts, e),
+                        None => error!("Outbox message {} failed {} times, giving up: {}", row.id, attempts, e),
+                    }
+
+                    sqlx::query(
+                        r#"
+                        UPDATE event_outbox
+                        SET attempts = $2, last_error = $3,
+                            next_attempt_at = COALESCE($4, next_attempt_at),
+                            failed_at = CASE WHEN $4 IS NULL THEN NOW() END
+                        WHERE id = $1
+                        "#,
+                    )
+                    .bind(row.id)
+                    .bind(attempts)
+                    .bind(e.to_string())
+                    .bind(retry_at)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+
+        tx.commit().await?;
+
+        if !pending.is_empty() {
+            debug!("Relayed {} outbox messages", pending.len());
+        }
+
+        Ok(pending.len())
+    }
+
+    async fn publish(&self, message: OutboxMessage) -> Result<(), AppError> {
+        match message {
+            OutboxMessage::Realtime(event) => self.realtime_service.broadcast_event(event).await,
+            OutboxMessage::BoxPurchaseNotification {
+                user_id,
+                raffle_id,
+                item_title,
+                box_number,
+                total_boxes,
+            } => {
+                self.notification_service
+                    .send_box_purchase_notification(user_id, raffle_id, item_title, box_number, total_boxes)
+                    .await
+            }
+            OutboxMessage::PurchaseReceipt {
+                receipt_id,
+                user_id,
+                raffle_id,
+                item_title,
+                receipt,
+            } => {
+                self.notification_service
+                    .send_purchase_receipt_notification(user_id, raffle_id, item_title, receipt)
+                    .await?;
+
+                sqlx::query!("UPDATE purchase_receipts SET emailed_at = NOW() WHERE id = $1", receipt_id)
+                    .execute(&self.db_pool)
+                    .await?;
+                Ok(())
+            }
+            OutboxMessage::WaitlistNotification {
+                user_id,
+                raffle_id,
+                item_title,
+                opening,
+            } => {
+                self.notification_service
+                    .send_waitlist_notification(user_id, raffle_id, item_title, opening)
+                    .await
+            }
+            OutboxMessage::SavedSearchAlert {
+                user_id,
+                saved_search_id,
+                search_name,
+                raffle_ids,
+                item_titles,
+            } => {
+                self.notification_service
+                    .send_saved_search_notification(user_id, saved_search_id, search_name, raffle_ids, item_titles)
+                    .await
+            }
+            OutboxMessage::FavoriteRaffleAlert {
+                user_id,
+                raffle_id,
+                item_title,
+                boxes_remaining,
+                ends_at,
+            } => {
+                self.notification_service
+                    .send_favorite_notification(user_id, raffle_id, item_title, boxes_remaining, ends_at)
+                    .await
+            }
+            OutboxMessage::RaffleResultBatch(batch) => self.notification_service.deliver_fan_out_batch(batch).await,
+            OutboxMessage::SellerWebhook { seller_id, event, data } => {
+                self.seller_webhook_service.publish(seller_id, event, data).await.map(|_| ())
+            }
+            OutboxMessage::PlatformWebhook { raffle_id, event, data } => {
+                self.seller_webhook_service.publish_platform(raffle_id, event, data).await.map(|_| ())
+            }
+        }
+    }
+
+    async fn listen(&self) -> Option<PgListener> {
+        let listener = async {
+            let mut listener = PgListener::connect_with(&self.db_pool).await?;
+            listener.listen(OUTBOX_CHANNEL).await?;
+            Ok::<_, sqlx::Error>(listener)
+        };
+
+        match listener.await {
+            Ok(listener) => Some(listener),
+            Err(e) => {
+                warn!("Failed to listen for outbox inserts, polling every {}s: {}", RELAY_POLL_SECS, e);
+                None
+            }
+        }
+    }
+
+    async fn cleanup_published(&self) -> Result<(), AppError> {
+        let deleted = sqlx::query("DELETE FROM event_outbox WHERE published_at < $1")
+            .bind(Utc::now() - Duration::days(PUBLISHED_RETENTION_DAYS))
+            .execute(&self.db_pool)
+            .await?
+            .rows_affected();
+
+        if deleted > 0 {
+            debug!("Deleted {} published outbox messages", deleted);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_round_trips_with_kind() {
+        let message = OutboxMessage::SellerWebhook {
+            seller_id: Uuid::new_v4(),
+            event: SellerWebhookEvent::BoxPurchased,
+            data: serde_json::json!({ "boxes_sold": 3 }),
+        };
+
+        let value = serde_json::to_value(&message).unwrap();
+        assert_eq!(value["kind"], message.kind());
+
+        let parsed: OutboxMessage = serde_json::from_value(value).unwrap();
+        assert!(matches!(parsed, OutboxMessage::SellerWebhook { event: SellerWebhookEvent::BoxPurchased, .. }));
+    }
+}
@@ -0,0 +1,351 @@
+use crate::error::AppError;
+use crate::repositories::{Cursor, CursorParams};
+use crate::services::reporting_service::csv_row;
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::CursorPage;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Exports stop here; narrow the filters to get the rest
+pub const MAX_EXPORT_ROWS: i64 = 10_000;
+
+const PAYMENT_STATUSES: &[&str] = &[
+    "pending",
+    "processing",
+    "succeeded",
+    "failed",
+    "cancelled",
+    "refunded",
+    "partially_refunded",
+];
+
+/// Payment search service lets support find Stripe payments and ledger transactions
+/// by customer, card, amount or processor reference without going to the database.
+#[derive(Clone)]
+pub struct PaymentSearchService {
+    db_pool: PgPool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PaymentSearchSource {
+    Payment,
+    Transaction,
+}
+
+impl PaymentSearchSource {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PaymentSearchSource::Payment => "payment",
+            PaymentSearchSource::Transaction => "transaction",
+        }
+    }
+}
+
+/// Filters combine with AND; every one is optional
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PaymentSearchFilters {
+    /// Free text: description words, an exact Stripe or gateway id, or part of an email
+    pub q: Option<String>,
+    /// Part of the customer's email
+    pub email: Option<String>,
+    pub last4: Option<String>,
+    /// Exact amount; shorthand for equal `amount_min` and `amount_max`
+    pub amount: Option<Decimal>,
+    pub amount_min: Option<Decimal>,
+    pub amount_max: Option<Decimal>,
+    /// Payment intent, refund or gateway reference, or a prefix of one
+    pub stripe_id: Option<String>,
+    pub status: Option<String>,
+    pub source: Option<PaymentSearchSource>,
+    pub user_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct PaymentSearchResult {
+    pub source: String,
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub email: Option<String>,
+    pub amount: Decimal,
+    pub currency: String,
+    pub status: String,
+    pub reference: Option<String>,
+    pub card_last4: Option<String>,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PaymentSearchExport {
+    pub filename: String,
+    pub body: String,
+    pub row_count: usize,
+    /// More rows matched than `MAX_EXPORT_ROWS`
+    pub truncated: bool,
+}
+
+impl PaymentSearchService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+
+    /// Search payments and transactions, newest first
+    pub async fn search(
+        &self,
+        filters: &PaymentSearchFilters,
+        limit: Option<i64>,
+        cursor: Option<&str>,
+    ) -> Result<CursorPage<PaymentSearchResult>, AppError> {
+        validate_filters(filters)?;
+        let pagination = CursorParams::new(limit, cursor)?;
+
+        let rows = self
+            .fetch(filters, pagination.after.as_ref(), pagination.fetch_limit())
+            .await?;
+
+        Ok(pagination.page(rows, |row| Cursor::new(row.created_at, row.id)))
+    }
+
+    /// Export everything matching the filters as CSV, up to `MAX_EXPORT_ROWS`
+    pub async fn export_csv(&self, filters: &PaymentSearchFilters) -> Result<PaymentSearchExport, AppError> {
+        validate_filters(filters)?;
+
+        let mut rows = self.fetch(filters, None, MAX_EXPORT_ROWS + 1).await?;
+        let truncated = rows.len() as i64 > MAX_EXPORT_ROWS;
+        rows.truncate(MAX_EXPORT_ROWS as usize);
+
+        let mut body = csv_row(&[
+            "source", "id", "created_at", "user_id", "email", "amount", "currency", "status", "reference",
+            "card_last4", "description",
+        ]);
+        for row in &rows {
+            body.push_str(&csv_row(&[
+                &row.source,
+                &row.id.to_string(),
+                &row.created_at.to_rfc3339(),
+                &row.user_id.map(|id| id.to_string()).unwrap_or_default(),
+                row.email.as_deref().unwrap_or(""),
+                &row.amount.to_string(),
+                &row.currency,
+                &row.status,
+                row.reference.as_deref().unwrap_or(""),
+                row.card_last4.as_deref().unwrap_or(""),
+                row.description.as_deref().unwrap_or(""),
+            ]));
+        }
+
+        Ok(PaymentSearchExport {
+            filename: format!("payment-search-{}.csv", Utc::now().format("%Y%m%d%H%M%S")),
+            body,
+            row_count: rows.len(),
+            truncated,
+        })
+    }
+
+    // Private helper methods
+
+    async fn fetch(
+        &self,
+        filters: &PaymentSearchFilters,
+        after: Option<&Cursor>,
+        limit: i64,
+    ) -> Result<Vec<PaymentSearchResult>, AppError> {
+        let (amount_min, amount_max) = amount_range(filters);
+        let (after_created_at, after_id) = Cursor::bounds(after);
+
+        let rows = sqlx::query_as::<_, PaymentSearchResult>(
+            r#"
+            WITH results AS (
+                SELECT
+                    'payment'::text AS source, p.id, p.user_id, u.email, p.amount,
+                    p.currency::text AS currency, p.status::text AS status,
+                    p.stripe_payment_intent_id AS reference, p.card_last4::text AS card_last4,
+                    p.description, p.created_at
+                FROM payments p
+                JOIN users u ON u.id = p.user_id
+                WHERE ($1::text IS NULL OR $1 = 'payment')
+                AND ($2::text IS NULL
+                    OR p.search_vector @@ plainto_tsquery('simple', $2)
+                    OR p.stripe_payment_intent_id = $2
+                    OR u.email ILIKE '%' || $3 || '%')
+                AND ($4::text IS NULL OR u.email ILIKE '%' || $4 || '%')
+                AND ($5::text IS NULL OR p.card_last4 = $5)
+                AND ($6::numeric IS NULL OR p.amount >= $6)
+                AND ($7::numeric IS NULL OR p.amount <= $7)
+                AND ($8::text IS NULL
+                    OR p.stripe_payment_intent_id LIKE $8 || '%'
+                    OR EXISTS (
+                        SELECT 1 FROM refunds r
+                        WHERE r.payment_id = p.id AND r.stripe_refund_id LIKE $8 || '%'
+                    ))
+                AND ($9::text IS NULL OR p.status::text = $9)
+                AND ($10::uuid IS NULL OR p.user_id = $10)
+                AND ($11::timestamptz IS NULL OR p.created_at >= $11)
+                AND ($12::timestamptz IS NULL OR p.created_at < $12)
+
+                UNION ALL
+
+                SELECT
+                    'transaction'::text, t.id, t.user_id, u.email, t.amount,
+                    'USD'::text, COALESCE(t.status, 'pending')::text,
+                    t.payment_gateway_ref, NULL::text,
+                    t.type::text, t.created_at
+                FROM transactions t
+                LEFT JOIN users u ON u.id = t.user_id
+                WHERE ($1::text IS NULL OR $1 = 'transaction')
+                AND ($2::text IS NULL
+                    OR t.search_vector @@ plainto_tsquery('simple', $2)
+                    OR t.payment_gateway_ref = $2
+                    OR u.email ILIKE '%' || $3 || '%')
+                AND ($4::text IS NULL OR u.email ILIKE '%' || $4 || '%')
+                -- Ledger transactions carry no card details
+                AND $5::text IS NULL
+                AND ($6::numeric IS NULL OR t.amount >= $6)
+                AND ($7::numeric IS NULL OR t.amount <= $7)
+                AND ($8::text IS NULL OR t.payment_gateway_ref LIKE $8 || '%')
+                AND ($9::text IS NULL OR t.status = $9)
+                AND ($10::uuid IS NULL OR t.user_id = $10)
+                AND ($11::timestamptz IS NULL OR t.created_at >= $11)
+                AND ($12::timestamptz IS NULL OR t.created_at < $12)
+            )
+            SELECT * FROM results
+            WHERE ($13::timestamptz IS NULL OR (created_at, id) < ($13, $14))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $15
+            "#,
+        )
+        .bind(filters.source.map(|s| s.as_str()))
+        .bind(non_empty(&filters.q))
+        .bind(non_empty(&filters.q).map(|q| like_escape(&q)))
+        .bind(non_empty(&filters.email).map(|email| like_escape(&email)))
+        .bind(non_empty(&filters.last4))
+        .bind(amount_min)
+        .bind(amount_max)
+        .bind(non_empty(&filters.stripe_id).map(|id| like_escape(&id)))
+        .bind(non_empty(&filters.status).map(|s| s.to_lowercase()))
+        .bind(filters.user_id)
+        .bind(filters.from)
+        .bind(filters.to)
+        .bind(after_created_at)
+        .bind(after_id)
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// Treat `%`, `_` and `\` in user input literally inside LIKE patterns
+fn like_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// An exact amount pins both ends of the range
+fn amount_range(filters: &PaymentSearchFilters) -> (Option<Decimal>, Option<Decimal>) {
+    match filters.amount {
+        Some(amount) => (Some(amount), Some(amount)),
+        None => (filters.amount_min, filters.amount_max),
+    }
+}
+
+fn validate_filters(filters: &PaymentSearchFilters) -> Result<(), AppError> {
+    if let Some(last4) = non_empty(&filters.last4) {
+        if last4.len() != 4 || !last4.chars().all(|c| c.is_ascii_digit()) {
+            return Err(AppError::Validation("last4 must be exactly four digits".to_string()));
+        }
+    }
+
+    if let Some(status) = non_empty(&filters.status) {
+        let status = status.to_lowercase();
+        // Transaction statuses are free-form, so only payment searches are checked
+        if filters.source == Some(PaymentSearchSource::Payment) && !PAYMENT_STATUSES.contains(&status.as_str()) {
+            return Err(AppError::Validation(format!("Unknown payment status: {}", status)));
+        }
+    }
+
+    if let (Some(min), Some(max)) = amount_range(filters) {
+        if min > max {
+            return Err(AppError::Validation("amount_min cannot exceed amount_max".to_string()));
+        }
+    }
+
+    if let (Some(from), Some(to)) = (filters.from, filters.to) {
+        if from >= to {
+            return Err(AppError::Validation("from must be before to".to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_like_escape() {
+        assert_eq!(like_escape("pi_3Nx"), "pi\\_3Nx");
+        assert_eq!(like_escape("100%"), "100\\%");
+        assert_eq!(like_escape("a\\b"), "a\\\\b");
+    }
+
+    #[test]
+    fn test_amount_range_prefers_exact_amount() {
+        let filters = PaymentSearchFilters {
+            amount: Some(Decimal::from_str("25.00").unwrap()),
+            amount_min: Some(Decimal::from(1)),
+            ..Default::default()
+        };
+        let exact = Decimal::from_str("25.00").unwrap();
+        assert_eq!(amount_range(&filters), (Some(exact), Some(exact)));
+    }
+
+    #[test]
+    fn test_validate_filters() {
+        let last4 = |v: &str| PaymentSearchFilters {
+            last4: Some(v.to_string()),
+            ..Default::default()
+        };
+        assert!(validate_filters(&last4("4242")).is_ok());
+        assert!(validate_filters(&last4("424")).is_err());
+        assert!(validate_filters(&last4("42a2")).is_err());
+
+        let inverted = PaymentSearchFilters {
+            amount_min: Some(Decimal::from(50)),
+            amount_max: Some(Decimal::from(10)),
+            ..Default::default()
+        };
+        assert!(validate_filters(&inverted).is_err());
+
+        let bad_status = PaymentSearchFilters {
+            status: Some("settled".to_string()),
+            source: Some(PaymentSearchSource::Payment),
+            ..Default::default()
+        };
+        assert!(validate_filters(&bad_status).is_err());
+    }
+}
@@ -1,18 +1,23 @@
 use crate::error::AppError;
+use crate::models::ledger::{accounts, JournalEntry, NewJournalEntry, PostingLine};
+use crate::models::promotion::{Promotion, PromotionKind};
 use crate::models::user::User;
-use crate::services::credit_service::{CreditService, CreditIssuanceRequest};
+use crate::services::credit_service::{CreditService, CreditIssuanceRequest, CreditRedemptionRequest};
+use crate::services::fx_service::{normalize_currency, settle_amount, FxService, DEFAULT_BASE_CURRENCY};
 use chrono::{DateTime, Utc};
 use raffle_platform_shared::{CreditSource, CreditType};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, Transaction};
 use std::collections::HashMap;
 use stripe::{
     Client, CreateCustomer, CreatePaymentIntent, CreatePrice, CreateProduct, CreateSubscription,
-    Currency, Customer, PaymentIntent, PaymentIntentConfirmParams, Price, Product, Subscription,
-    SubscriptionStatus, UpdateSubscription,
+    CreateRefund, Currency, Customer, PaymentIntent, PaymentIntentConfirmParams, PaymentMethod,
+    PaymentMethodId, Price, Product,
+    Refund, Subscription, SubscriptionStatus, UpdateSubscription,
 };
 use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 #[cfg(test)]
@@ -25,6 +30,7 @@ pub struct PaymentService {
     db_pool: PgPool,
     credit_service: CreditService,
     webhook_secret: String,
+    fx_service: Option<FxService>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,15 +40,36 @@ pub struct PaymentIntentRequest {
     pub currency: String,
     pub description: String,
     pub metadata: HashMap<String, String>,
+    /// Wallet the client will present its payment sheet for, if any
+    pub wallet: Option<WalletType>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PaymentIntentResponse {
     pub payment_intent_id: String,
     pub client_secret: String,
     pub amount: i64, // Amount in cents
     pub currency: String,
     pub status: String,
+    pub payment_method_types: Vec<String>,
+}
+
+/// Device wallets. Stripe charges them as cards and reports the wallet on the card's payment method.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WalletType {
+    ApplePay,
+    GooglePay,
+}
+
+impl WalletType {
+    /// Stripe's name for the wallet, as stored in `payments.wallet_type`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WalletType::ApplePay => "apple_pay",
+            WalletType::GooglePay => "google_pay",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,6 +96,8 @@ pub struct PaymentRecord {
     pub stripe_payment_intent_id: String,
     pub amount: Decimal,
     pub currency: String,
+    /// Units of `currency` per unit of the base currency credits are issued in
+    pub settlement_rate: Option<Decimal>,
     pub status: PaymentStatus,
     pub description: String,
     pub metadata: serde_json::Value,
@@ -78,6 +107,13 @@ pub struct PaymentRecord {
     pub failure_reason: Option<String>,
 }
 
+impl PaymentRecord {
+    /// Credits a share of this payment is worth, at the rate it settled at
+    pub fn credits_for(&self, amount: Decimal) -> Decimal {
+        settle_amount(amount, self.settlement_rate.unwrap_or(Decimal::ONE))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
 #[sqlx(type_name = "payment_status", rename_all = "lowercase")]
 pub enum PaymentStatus {
@@ -87,14 +123,85 @@ pub enum PaymentStatus {
     Failed,
     Cancelled,
     Refunded,
+    #[sqlx(rename = "partially_refunded")]
+    PartiallyRefunded,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RefundDestination {
+    Credits,
+    OriginalPaymentMethod,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RefundStatus {
+    Pending,
+    Succeeded,
+    Failed,
+    Canceled,
+}
+
+impl RefundStatus {
+    /// Map a Stripe refund status string onto our refund state
+    pub fn from_stripe(status: &str) -> Self {
+        match status {
+            "succeeded" => RefundStatus::Succeeded,
+            "failed" => RefundStatus::Failed,
+            "canceled" => RefundStatus::Canceled,
+            _ => RefundStatus::Pending, // pending, requires_action
+        }
+    }
+
+    /// The state a refund moves to when Stripe reports `reported`, or None to leave it.
+    /// Only pending refunds move, and terminal states are never revisited.
+    pub fn transition_to(self, reported: RefundStatus) -> Option<RefundStatus> {
+        match (self, reported) {
+            (RefundStatus::Pending, RefundStatus::Pending) => None,
+            (RefundStatus::Pending, reported) => Some(reported),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefundRequest {
+    pub payment_id: Uuid,
+    pub destination: RefundDestination,
+    /// Amount in dollars; defaults to the remaining refundable amount
+    pub amount: Option<Decimal>,
+    pub reason: Option<String>,
+    pub requested_by: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefundRecord {
+    pub id: Uuid,
+    pub payment_id: Uuid,
+    pub stripe_refund_id: Option<String>,
+    pub destination: RefundDestination,
+    pub amount: Decimal,
+    pub credits_clawed_back: Decimal,
+    pub clawback_shortfall: Decimal,
+    pub status: RefundStatus,
+    pub reason: Option<String>,
+    pub failure_reason: Option<String>,
+    pub requested_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SubscriptionRecord {
     pub id: Uuid,
     pub user_id: Uuid,
     pub stripe_subscription_id: String,
     pub stripe_customer_id: String,
+    #[schema(value_type = String)]
     pub status: SubscriptionStatus,
     pub current_period_start: DateTime<Utc>,
     pub current_period_end: DateTime<Utc>,
@@ -127,9 +234,16 @@ impl PaymentService {
             db_pool,
             credit_service,
             webhook_secret,
+            fx_service: None,
         }
     }
 
+    /// Accept payments in other currencies and record the user's display currency rate on each one
+    pub fn with_fx_service(mut self, fx_service: FxService) -> Self {
+        self.fx_service = Some(fx_service);
+        self
+    }
+
     /// Create a payment intent for credit purchase
     pub async fn create_payment_intent(
         &self,
@@ -147,19 +261,33 @@ impl PaymentService {
             return Err(AppError::Validation("Invalid amount".to_string()));
         }
 
+        // Credits are issued in the base currency at the rate quoted now
+        let currency = self.payment_currency(&request.currency).await?;
+        let settlement_rate = self.settlement_rate(&currency).await?;
+        let stripe_currency = currency
+            .to_ascii_lowercase()
+            .parse::<Currency>()
+            .map_err(|_| AppError::Validation(format!("{} is not supported by the payment processor", currency)))?;
+
         // Get or create Stripe customer
         let customer_id = self.get_or_create_customer(&user).await?;
 
         // Create payment intent
-        let mut create_params = CreatePaymentIntent::new(amount_cents, Currency::USD);
+        let mut create_params = CreatePaymentIntent::new(amount_cents, stripe_currency);
         create_params.customer = Some(customer_id);
         create_params.description = Some(&request.description);
-        create_params.automatic_payment_methods = Some(
-            stripe::CreatePaymentIntentAutomaticPaymentMethods {
-                enabled: true,
-                allow_redirects: Some(stripe::CreatePaymentIntentAutomaticPaymentMethodsAllowRedirects::Never),
+        match request.wallet {
+            // A wallet sheet hands over a tokenized card, so the intent only needs to accept cards
+            Some(_) => create_params.payment_method_types = Some(vec!["card".to_string()]),
+            None => {
+                create_params.automatic_payment_methods = Some(
+                    stripe::CreatePaymentIntentAutomaticPaymentMethods {
+                        enabled: true,
+                        allow_redirects: Some(stripe::CreatePaymentIntentAutomaticPaymentMethodsAllowRedirects::Never),
+                    }
+                );
             }
-        );
+        }
 
         // Add metadata
         let mut metadata = request.metadata.clone();
@@ -177,7 +305,7 @@ impl PaymentService {
                 request.user_id,
                 &payment_intent.id,
                 request.amount,
-                &request.currency,
+                &currency,
                 PaymentStatus::Pending,
                 &request.description,
                 serde_json::to_value(metadata)?,
@@ -185,17 +313,26 @@ impl PaymentService {
             )
             .await?;
 
+        self.record_settlement(payment_record.id, settle_amount(request.amount, settlement_rate), settlement_rate)
+            .await?;
+        self.record_fx_snapshot(&payment_record).await;
+        if let Some(wallet) = request.wallet {
+            // Corrected to the wallet actually used once the payment method is known
+            self.set_wallet_type(&payment_intent.id, Some(wallet.as_str())).await?;
+        }
+
         info!(
-            "Created payment intent {} for user {} (amount: {})",
-            payment_intent.id, request.user_id, request.amount
+            "Created payment intent {} for user {} (amount: {}, wallet: {:?})",
+            payment_intent.id, request.user_id, request.amount, request.wallet
         );
 
         Ok(PaymentIntentResponse {
             payment_intent_id: payment_intent.id,
             client_secret: payment_intent.client_secret.unwrap_or_default(),
             amount: amount_cents,
-            currency: request.currency,
+            currency,
             status: payment_intent.status.to_string(),
+            payment_method_types: payment_intent.payment_method_types,
         })
     }
 
@@ -223,6 +360,8 @@ impl PaymentService {
             None,
         )
         .await?;
+        self.record_card_last4(&payment_intent.id, payment_method_id).await?;
+        self.record_wallet_type(&payment_intent.id, payment_method_id).await;
 
         Ok(PaymentIntentResponse {
             payment_intent_id: payment_intent.id,
@@ -230,6 +369,7 @@ impl PaymentService {
             amount: payment_intent.amount,
             currency: payment_intent.currency.to_string(),
             status: payment_intent.status.to_string(),
+            payment_method_types: payment_intent.payment_method_types,
         })
     }
 
@@ -238,6 +378,7 @@ impl PaymentService {
         &self,
         payment_intent_id: &str,
         amount_cents: u64,
+        payment_method_id: Option<&str>,
     ) -> Result<(), AppError> {
         // Update payment record
         self.update_payment_status(
@@ -247,16 +388,28 @@ impl PaymentService {
         )
         .await?;
 
+        // Wallet sheets confirm client side, so this is the first time the method may be seen
+        if let Some(payment_method_id) = payment_method_id {
+            self.record_wallet_type(payment_intent_id, payment_method_id).await;
+        }
+
         // Get payment record to find user
         let payment_record = self.get_payment_record_by_stripe_id(payment_intent_id).await?;
 
-        // Convert cents back to dollars
-        let amount_dollars = Decimal::from(amount_cents) / Decimal::from(100);
+        // Credits are issued in the base currency at the rate quoted with the intent
+        let amount_paid = Decimal::from(amount_cents) / Decimal::from(100);
+        let credit_amount = payment_record.credits_for(amount_paid);
+        self.record_settlement(
+            payment_record.id,
+            credit_amount,
+            payment_record.settlement_rate.unwrap_or(Decimal::ONE),
+        )
+        .await?;
 
         // Issue credits to user
         let credit_request = CreditIssuanceRequest {
             user_id: payment_record.user_id,
-            amount: amount_dollars,
+            amount: credit_amount,
             source: CreditSource::Deposit,
             credit_type: CreditType::General,
             redeemable_on_item_id: None,
@@ -266,9 +419,20 @@ impl PaymentService {
 
         self.credit_service.issue_credits(credit_request).await?;
 
+        // A multiplier applies if the checkout started inside its window
+        if let Some(promotion) = Promotion::active_at(
+            &self.db_pool,
+            PromotionKind::CreditMultiplier,
+            payment_record.created_at,
+        ).await? {
+            self.credit_service
+                .issue_promotion_bonus(&promotion, payment_record.user_id, payment_record.id, credit_amount)
+                .await?;
+        }
+
         info!(
             "Processed successful payment {} - issued {} credits to user {}",
-            payment_intent_id, amount_dollars, payment_record.user_id
+            payment_intent_id, credit_amount, payment_record.user_id
         );
 
         Ok(())
@@ -426,7 +590,7 @@ impl PaymentService {
             PaymentRecord,
             r#"
             SELECT 
-                id, user_id, stripe_payment_intent_id, amount, currency,
+                id, user_id, stripe_payment_intent_id, amount, currency, settlement_rate,
                 status as "status: PaymentStatus",
                 description, metadata, created_at, updated_at, completed_at, failure_reason
             FROM payments 
@@ -486,6 +650,183 @@ impl PaymentService {
         Ok(subscriptions)
     }
 
+    /// Refund a payment to credits or to the original Stripe payment method (admin only)
+    ///
+    /// Card refunds claw back the credits the payment issued. If the user has already
+    /// spent part of them, only the unspent balance is clawed back and the remainder is
+    /// recorded as a shortfall on the refund.
+    pub async fn refund_payment(&self, request: RefundRequest) -> Result<RefundRecord, AppError> {
+        // The refund row is written under a lock on the payment, so concurrent refunds
+        // are capped by what the other has already reserved
+        let mut tx = self.db_pool.begin().await?;
+
+        let (status, payment_amount) = sqlx::query_as::<_, (PaymentStatus, Decimal)>(
+            "SELECT status, amount FROM payments WHERE id = $1 FOR UPDATE",
+        )
+        .bind(request.payment_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Payment not found".to_string()))?;
+
+        if !matches!(status, PaymentStatus::Succeeded | PaymentStatus::PartiallyRefunded) {
+            return Err(AppError::Validation("Only succeeded payments can be refunded".to_string()));
+        }
+
+        let already_refunded = committed_refund_total(&mut tx, request.payment_id).await?;
+        let amount = refund_amount(payment_amount, already_refunded, request.amount)?;
+
+        let refund = self.save_refund_record(
+            &mut tx,
+            request.payment_id,
+            request.destination,
+            amount,
+            request.reason.clone(),
+            request.requested_by,
+        ).await?;
+
+        tx.commit().await?;
+
+        let payment = self.get_payment_record_by_id(request.payment_id).await?;
+
+        // Credits move at the rate the payment settled at, in the base currency
+        let credits = payment.credits_for(amount);
+
+        match request.destination {
+            RefundDestination::Credits => {
+                if let Err(e) = self.credit_service.issue_refund_credits(
+                    payment.user_id,
+                    credits,
+                    None,
+                    format!("Refund for payment {}", payment.stripe_payment_intent_id),
+                ).await {
+                    self.fail_refund(&refund, RefundStatus::Failed, &format!("Credit issuance failed: {}", e)).await?;
+                    return Err(e);
+                }
+
+                sqlx::query!(
+                    "UPDATE refunds SET status = 'succeeded', completed_at = NOW() WHERE id = $1",
+                    refund.id
+                )
+                .execute(&self.db_pool)
+                .await?;
+
+                self.sync_payment_refund_status(payment.id).await?;
+
+                info!("Refunded {} of payment {} as credits", amount, payment.id);
+                self.get_refund_by_id(refund.id).await
+            }
+            RefundDestination::OriginalPaymentMethod => {
+                let mut refund = refund;
+                let stripe_refund = match self.refund_to_card(&mut refund, &payment, amount, credits).await {
+                    Ok(stripe_refund) => stripe_refund,
+                    Err(e) => {
+                        error!("Card refund {} for payment {} failed: {}", refund.id, payment.id, e);
+                        self.fail_refund(&refund, RefundStatus::Failed, &e.to_string()).await?;
+                        return Err(e);
+                    }
+                };
+
+                // The money has left through Stripe, so nothing from here on may fail the refund
+                sqlx::query!(
+                    "UPDATE refunds SET stripe_refund_id = $1 WHERE id = $2",
+                    stripe_refund.id.as_str(),
+                    refund.id
+                )
+                .execute(&self.db_pool)
+                .await?;
+
+                info!(
+                    "Created Stripe refund {} for payment {} (amount: {})",
+                    stripe_refund.id, payment.id, amount
+                );
+
+                let status = stripe_refund.status
+                    .map(|s| RefundStatus::from_stripe(&s.to_string()))
+                    .unwrap_or(RefundStatus::Pending);
+                self.process_refund_update(stripe_refund.id.as_str(), status, None).await
+            }
+        }
+    }
+
+    /// Apply a refund state change reported by Stripe (called from webhook)
+    pub async fn process_refund_update(
+        &self,
+        stripe_refund_id: &str,
+        status: RefundStatus,
+        failure_reason: Option<String>,
+    ) -> Result<RefundRecord, AppError> {
+        let refund = self.get_refund_by_stripe_id(stripe_refund_id).await?;
+
+        let Some(status) = refund.status.transition_to(status) else {
+            return Ok(refund);
+        };
+
+        match status {
+            RefundStatus::Succeeded => {
+                let mut tx = self.db_pool.begin().await?;
+
+                // Only the delivery that moves the refund out of pending posts it; a
+                // duplicate webhook finds it already settled and does nothing
+                let settled = sqlx::query_as!(
+                    RefundRecord,
+                    r#"
+                    UPDATE refunds SET status = $1, completed_at = NOW()
+                    WHERE id = $2 AND status = 'pending'
+                    RETURNING
+                        id, payment_id, stripe_refund_id,
+                        destination as "destination: RefundDestination",
+                        amount, credits_clawed_back, clawback_shortfall,
+                        status as "status: RefundStatus",
+                        reason, failure_reason, requested_by, created_at, updated_at, completed_at
+                    "#,
+                    RefundStatus::Succeeded as RefundStatus,
+                    refund.id
+                )
+                .fetch_optional(&mut *tx)
+                .await?;
+
+                if let Some(settled) = settled {
+                    JournalEntry::post(&mut *tx, &card_refund_entry(&settled)).await?;
+                    tx.commit().await?;
+
+                    self.sync_payment_refund_status(refund.payment_id).await?;
+                    info!("Refund {} succeeded", stripe_refund_id);
+                }
+            }
+            _ => {
+                let reason = failure_reason.unwrap_or_else(|| format!("Refund {:?}", status));
+                if self.fail_refund(&refund, status, &reason).await? {
+                    warn!("Refund {} did not complete: {}", stripe_refund_id, reason);
+                }
+            }
+        }
+
+        self.get_refund_by_stripe_id(stripe_refund_id).await
+    }
+
+    /// Get refunds recorded for a payment
+    pub async fn get_refunds(&self, payment_id: Uuid) -> Result<Vec<RefundRecord>, AppError> {
+        let refunds = sqlx::query_as!(
+            RefundRecord,
+            r#"
+            SELECT
+                id, payment_id, stripe_refund_id,
+                destination as "destination: RefundDestination",
+                amount, credits_clawed_back, clawback_shortfall,
+                status as "status: RefundStatus",
+                reason, failure_reason, requested_by, created_at, updated_at, completed_at
+            FROM refunds
+            WHERE payment_id = $1
+            ORDER BY created_at DESC
+            "#,
+            payment_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(refunds)
+    }
+
     /// Verify webhook signature
     pub fn verify_webhook_signature(&self, payload: &[u8], signature: &str) -> Result<(), AppError> {
         stripe::Webhook::construct_event(payload, signature, &self.webhook_secret)
@@ -561,7 +902,7 @@ impl PaymentService {
             INSERT INTO payments (user_id, stripe_payment_intent_id, amount, currency, status, description, metadata, completed_at)
             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             RETURNING 
-                id, user_id, stripe_payment_intent_id, amount, currency,
+                id, user_id, stripe_payment_intent_id, amount, currency, settlement_rate,
                 status as "status: PaymentStatus",
                 description, metadata, created_at, updated_at, completed_at, failure_reason
             "#,
@@ -580,6 +921,135 @@ impl PaymentService {
         Ok(record)
     }
 
+    /// A currency payments are accepted in; without FX only the base currency is
+    async fn payment_currency(&self, requested: &str) -> Result<String, AppError> {
+        match &self.fx_service {
+            Some(fx_service) => fx_service.accepted_currency(Some(requested)).await,
+            None => match normalize_currency(requested)? {
+                currency if currency == DEFAULT_BASE_CURRENCY => Ok(currency),
+                currency => Err(AppError::Validation(format!("{} is not an accepted currency", currency))),
+            },
+        }
+    }
+
+    async fn settlement_rate(&self, currency: &str) -> Result<Decimal, AppError> {
+        match &self.fx_service {
+            Some(fx_service) => fx_service.settlement_rate(currency).await,
+            None => Ok(Decimal::ONE),
+        }
+    }
+
+    /// Credits a payment is worth and the rate that decided it
+    async fn record_settlement(&self, payment_id: Uuid, amount: Decimal, rate: Decimal) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE payments SET settlement_amount = $2, settlement_rate = $3 WHERE id = $1",
+            payment_id,
+            amount,
+            rate
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Store the display rate with a payment; a missing rate never blocks the payment
+    async fn record_fx_snapshot(&self, record: &PaymentRecord) {
+        let fx_service = match &self.fx_service {
+            Some(fx_service) => fx_service,
+            None => return,
+        };
+
+        let snapshot = match fx_service
+            .snapshot_for_user(record.user_id, record.amount, &record.currency)
+            .await
+        {
+            Ok(Some(snapshot)) => snapshot,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Failed to get FX rate for payment {}: {}", record.id, e);
+                return;
+            }
+        };
+
+        if let Err(e) = sqlx::query!(
+            r#"
+            UPDATE payments
+            SET display_currency = $2, display_amount = $3, fx_rate = $4, fx_rate_id = $5
+            WHERE id = $1
+            "#,
+            record.id,
+            snapshot.display_currency,
+            snapshot.display_amount,
+            snapshot.rate,
+            snapshot.rate_id
+        )
+        .execute(&self.db_pool)
+        .await
+        {
+            warn!("Failed to store FX snapshot for payment {}: {}", record.id, e);
+        }
+    }
+
+    /// Copy the saved card's last4 onto the payment so support can search by it
+    async fn record_card_last4(&self, payment_intent_id: &str, payment_method_id: &str) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE payments
+            SET card_last4 = m.card_last4
+            FROM user_payment_methods m
+            WHERE payments.stripe_payment_intent_id = $1
+              AND m.stripe_payment_method_id = $2
+              AND m.card_last4 IS NOT NULL
+            "#,
+        )
+        .bind(payment_intent_id)
+        .bind(payment_method_id)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Store the wallet the payment method belongs to, or clear it for a plain card. Wallet type
+    /// is only kept for analytics, so a failed lookup never blocks the payment.
+    async fn record_wallet_type(&self, payment_intent_id: &str, payment_method_id: &str) {
+        let method = match payment_method_id.parse::<PaymentMethodId>() {
+            Ok(id) => PaymentMethod::retrieve(&self.stripe_client, &id, &[]).await,
+            Err(e) => {
+                warn!("Invalid payment method {} for payment {}: {}", payment_method_id, payment_intent_id, e);
+                return;
+            }
+        };
+
+        let wallet_type = match method {
+            Ok(method) => method
+                .card
+                .and_then(|card| card.wallet)
+                .map(|wallet| wallet.type_.as_str().to_string()),
+            Err(e) => {
+                warn!("Failed to look up payment method for payment {}: {}", payment_intent_id, e);
+                return;
+            }
+        };
+
+        if let Err(e) = self.set_wallet_type(payment_intent_id, wallet_type.as_deref()).await {
+            warn!("Failed to store wallet type for payment {}: {}", payment_intent_id, e);
+        }
+    }
+
+    async fn set_wallet_type(&self, stripe_payment_intent_id: &str, wallet_type: Option<&str>) -> Result<(), AppError> {
+        sqlx::query!(
+            "UPDATE payments SET wallet_type = $2 WHERE stripe_payment_intent_id = $1",
+            stripe_payment_intent_id,
+            wallet_type
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+
     async fn update_payment_status(
         &self,
         stripe_payment_intent_id: &str,
@@ -626,7 +1096,7 @@ impl PaymentService {
             PaymentRecord,
             r#"
             SELECT 
-                id, user_id, stripe_payment_intent_id, amount, currency,
+                id, user_id, stripe_payment_intent_id, amount, currency, settlement_rate,
                 status as "status: PaymentStatus",
                 description, metadata, created_at, updated_at, completed_at, failure_reason
             FROM payments 
@@ -640,6 +1110,251 @@ impl PaymentService {
         Ok(record)
     }
 
+    async fn get_payment_record_by_id(&self, payment_id: Uuid) -> Result<PaymentRecord, AppError> {
+        let record = sqlx::query_as!(
+            PaymentRecord,
+            r#"
+            SELECT 
+                id, user_id, stripe_payment_intent_id, amount, currency, settlement_rate,
+                status as "status: PaymentStatus",
+                description, metadata, created_at, updated_at, completed_at, failure_reason
+            FROM payments 
+            WHERE id = $1
+            "#,
+            payment_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Payment not found".to_string()))?;
+
+        Ok(record)
+    }
+
+    /// Claw back the payment's credits and send the refund to Stripe
+    ///
+    /// `refund` tracks what has been clawed back so far, so the caller can give it back
+    /// through `fail_refund` if a later step fails.
+    async fn refund_to_card(
+        &self,
+        refund: &mut RefundRecord,
+        payment: &PaymentRecord,
+        amount: Decimal,
+        credits: Decimal,
+    ) -> Result<Refund, AppError> {
+        let payment_intent = payment.stripe_payment_intent_id.parse()
+            .map_err(|_| AppError::Internal("Invalid payment intent ID".to_string()))?;
+
+        let (clawed_back, shortfall) = self.claw_back_credits(payment, credits).await?;
+        refund.credits_clawed_back = clawed_back;
+        refund.clawback_shortfall = shortfall;
+
+        if shortfall > Decimal::ZERO {
+            warn!(
+                "Refund of payment {} has clawback shortfall of {} (credits already spent)",
+                payment.id, shortfall
+            );
+        }
+
+        sqlx::query!(
+            "UPDATE refunds SET credits_clawed_back = $1, clawback_shortfall = $2 WHERE id = $3",
+            clawed_back,
+            shortfall,
+            refund.id
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        self.claw_back_promotion_bonus(payment, amount).await?;
+
+        let amount_cents = (amount * Decimal::from(100)).to_i64().unwrap_or(0);
+        let mut create_params = CreateRefund::new();
+        create_params.payment_intent = Some(payment_intent);
+        create_params.amount = Some(amount_cents);
+        create_params.metadata = Some({
+            let mut metadata = HashMap::new();
+            metadata.insert("refund_id".to_string(), refund.id.to_string());
+            metadata.insert("payment_id".to_string(), payment.id.to_string());
+            metadata
+        });
+
+        Refund::create(&self.stripe_client, create_params)
+            .await
+            .map_err(|e| AppError::External(format!("Stripe error: {}", e)))
+    }
+
+    /// Take back up to `amount` of the user's general credits; returns what was taken
+    /// and the shortfall left by credits already spent
+    async fn claw_back_credits(&self, payment: &PaymentRecord, amount: Decimal) -> Result<(Decimal, Decimal), AppError> {
+        let balance = self.credit_service.get_user_balance(payment.user_id).await?;
+        let (clawback, shortfall) = clawback_split(amount, balance.total_general);
+
+        if clawback > Decimal::ZERO {
+            self.credit_service.redeem_credits_against(CreditRedemptionRequest {
+                user_id: payment.user_id,
+                amount: clawback,
+                item_id: None,
+                credit_type: Some(CreditType::General),
+                description: format!("Credit clawback for refund of payment {}", payment.stripe_payment_intent_id),
+            }, accounts::REFUNDS_CLEARING).await?;
+        }
+
+        Ok((clawback, shortfall))
+    }
+
+    /// Take back the refunded share of any promotional bonus the payment earned
+    async fn claw_back_promotion_bonus(&self, payment: &PaymentRecord, amount: Decimal) -> Result<(), AppError> {
+        let bonus = Promotion::bonus_for_payment(&self.db_pool, payment.id).await?;
+        if bonus <= Decimal::ZERO || payment.amount <= Decimal::ZERO {
+            return Ok(());
+        }
+
+        let share = (bonus * amount / payment.amount).round_dp(2);
+        let balance = self.credit_service.get_user_balance(payment.user_id).await?;
+        let (clawback, _) = clawback_split(share, balance.total_general);
+
+        if clawback > Decimal::ZERO {
+            self.credit_service.redeem_credits_against(CreditRedemptionRequest {
+                user_id: payment.user_id,
+                amount: clawback,
+                item_id: None,
+                credit_type: Some(CreditType::General),
+                description: format!("Promotion bonus clawback for refund of payment {}", payment.stripe_payment_intent_id),
+            }, accounts::REFUNDS_CLEARING).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Move a pending refund to `status` (failed or canceled) and give back what it clawed back.
+    /// Returns false, changing nothing, if the refund has already left pending.
+    async fn fail_refund(&self, refund: &RefundRecord, status: RefundStatus, reason: &str) -> Result<bool, AppError> {
+        let failed = sqlx::query_scalar!(
+            "UPDATE refunds SET status = $1, failure_reason = $2 WHERE id = $3 AND status = 'pending' RETURNING id",
+            status as RefundStatus,
+            reason,
+            refund.id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        if failed.is_none() {
+            return Ok(false);
+        }
+
+        // Give back any credits we clawed back for a refund that never reached the card
+        if refund.credits_clawed_back > Decimal::ZERO {
+            let payment = self.get_payment_record_by_id(refund.payment_id).await?;
+            self.credit_service.issue_credits_against(CreditIssuanceRequest {
+                user_id: payment.user_id,
+                amount: refund.credits_clawed_back,
+                source: CreditSource::Refund,
+                credit_type: CreditType::General,
+                redeemable_on_item_id: None,
+                expires_at: None,
+                description: format!("Restored credits after failed refund {}", refund.id),
+            }, accounts::REFUNDS_CLEARING).await?;
+        }
+
+        Ok(true)
+    }
+
+    async fn sync_payment_refund_status(&self, payment_id: Uuid) -> Result<(), AppError> {
+        let payment = self.get_payment_record_by_id(payment_id).await?;
+
+        let refunded = sqlx::query_scalar!(
+            "SELECT COALESCE(SUM(amount), 0) FROM refunds WHERE payment_id = $1 AND status = 'succeeded'",
+            payment_id
+        )
+        .fetch_one(&self.db_pool)
+        .await?
+        .unwrap_or(Decimal::ZERO);
+
+        let Some(status) = refunded_payment_status(payment.amount, refunded) else {
+            return Ok(());
+        };
+
+        self.update_payment_status(&payment.stripe_payment_intent_id, status, payment.completed_at)
+            .await
+    }
+
+    /// Record a refund as pending, reserving its amount against the payment
+    async fn save_refund_record(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        payment_id: Uuid,
+        destination: RefundDestination,
+        amount: Decimal,
+        reason: Option<String>,
+        requested_by: Uuid,
+    ) -> Result<RefundRecord, AppError> {
+        let record = sqlx::query_as!(
+            RefundRecord,
+            r#"
+            INSERT INTO refunds (payment_id, destination, amount, status, reason, requested_by)
+            VALUES ($1, $2, $3, 'pending', $4, $5)
+            RETURNING
+                id, payment_id, stripe_refund_id,
+                destination as "destination: RefundDestination",
+                amount, credits_clawed_back, clawback_shortfall,
+                status as "status: RefundStatus",
+                reason, failure_reason, requested_by, created_at, updated_at, completed_at
+            "#,
+            payment_id,
+            destination as RefundDestination,
+            amount,
+            reason,
+            requested_by
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Ok(record)
+    }
+
+    async fn get_refund_by_id(&self, refund_id: Uuid) -> Result<RefundRecord, AppError> {
+        let record = sqlx::query_as!(
+            RefundRecord,
+            r#"
+            SELECT
+                id, payment_id, stripe_refund_id,
+                destination as "destination: RefundDestination",
+                amount, credits_clawed_back, clawback_shortfall,
+                status as "status: RefundStatus",
+                reason, failure_reason, requested_by, created_at, updated_at, completed_at
+            FROM refunds
+            WHERE id = $1
+            "#,
+            refund_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Refund not found".to_string()))?;
+
+        Ok(record)
+    }
+
+    async fn get_refund_by_stripe_id(&self, stripe_refund_id: &str) -> Result<RefundRecord, AppError> {
+        let record = sqlx::query_as!(
+            RefundRecord,
+            r#"
+            SELECT
+                id, payment_id, stripe_refund_id,
+                destination as "destination: RefundDestination",
+                amount, credits_clawed_back, clawback_shortfall,
+                status as "status: RefundStatus",
+                reason, failure_reason, requested_by, created_at, updated_at, completed_at
+            FROM refunds
+            WHERE stripe_refund_id = $1
+            "#,
+            stripe_refund_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Refund not found".to_string()))?;
+
+        Ok(record)
+    }
+
     async fn save_subscription_record(
         &self,
         user_id: Uuid,
@@ -694,4 +1409,70 @@ impl PaymentService {
     ) -> Result<PaymentRecord, AppError> {
         self.get_payment_record_by_stripe_id(stripe_payment_intent_id).await
     }
-}
\ No newline at end of file
+}
+
+/// Sum of refunds that are pending or completed (failed/canceled refunds free up the amount)
+async fn committed_refund_total(tx: &mut Transaction<'_, Postgres>, payment_id: Uuid) -> Result<Decimal, AppError> {
+    let total = sqlx::query_scalar!(
+        "SELECT COALESCE(SUM(amount), 0) FROM refunds WHERE payment_id = $1 AND status IN ('pending', 'succeeded')",
+        payment_id
+    )
+    .fetch_one(&mut **tx)
+    .await?
+    .unwrap_or(Decimal::ZERO);
+
+    Ok(total)
+}
+
+/// The amount to refund: what was asked for, or everything left when nothing was,
+/// capped at what hasn't been refunded yet
+pub fn refund_amount(
+    payment_amount: Decimal,
+    already_refunded: Decimal,
+    requested: Option<Decimal>,
+) -> Result<Decimal, AppError> {
+    let refundable = payment_amount - already_refunded;
+    let amount = requested.unwrap_or(refundable);
+
+    if amount <= Decimal::ZERO {
+        return Err(AppError::Validation("Refund amount must be positive".to_string()));
+    }
+
+    if amount > refundable {
+        return Err(AppError::Validation(format!(
+            "Refund amount {} exceeds refundable amount {}",
+            amount, refundable
+        )));
+    }
+
+    Ok(amount)
+}
+
+/// Split credits to claw back into what the user still holds and the shortfall they spent
+pub fn clawback_split(credits: Decimal, available: Decimal) -> (Decimal, Decimal) {
+    let clawed_back = credits.min(available.max(Decimal::ZERO));
+    (clawed_back, credits - clawed_back)
+}
+
+/// Payment status once `refunded` of `amount` has been refunded, or None if nothing has
+pub fn refunded_payment_status(amount: Decimal, refunded: Decimal) -> Option<PaymentStatus> {
+    if refunded >= amount {
+        Some(PaymentStatus::Refunded)
+    } else if refunded > Decimal::ZERO {
+        Some(PaymentStatus::PartiallyRefunded)
+    } else {
+        None
+    }
+}
+
+/// Cash leaving for a card refund: the clawed-back credits are in refunds clearing,
+/// and whatever the user had already spent is a loss
+pub fn card_refund_entry(refund: &RefundRecord) -> NewJournalEntry {
+    NewJournalEntry::new("refund", Some(refund.id), format!("Card refund for payment {}", refund.payment_id))
+        .line(PostingLine::debit(accounts::REFUNDS_CLEARING, refund.credits_clawed_back))
+        .line(PostingLine::debit(accounts::REFUND_CREDITS, refund.clawback_shortfall))
+        .line(PostingLine::credit(
+            accounts::STRIPE_CLEARING,
+            refund.credits_clawed_back + refund.clawback_shortfall,
+        ))
+}
@@ -39,6 +39,7 @@ mod tests {
             currency: "USD".to_string(),
             description: "Test credit purchase".to_string(),
             metadata: HashMap::new(),
+            wallet: None,
         };
 
         // This test would require mocking Stripe API calls
@@ -266,7 +267,7 @@ mod tests {
 
         // Process successful payment
         payment_service
-            .process_successful_payment("pi_test_success", 10000) // 100.00 in cents
+            .process_successful_payment("pi_test_success", 10000, None) // 100.00 in cents
             .await
             .unwrap();
 
@@ -284,6 +285,145 @@ mod tests {
         assert_eq!(balance.total_available, Decimal::from(100));
     }
 
+    #[test]
+    fn test_card_refund_entry_books_shortfall_as_loss() {
+        let now = Utc::now();
+        let refund = RefundRecord {
+            id: Uuid::new_v4(),
+            payment_id: Uuid::new_v4(),
+            stripe_refund_id: Some("re_123".to_string()),
+            destination: RefundDestination::OriginalPaymentMethod,
+            amount: Decimal::from(20),
+            credits_clawed_back: Decimal::from(15),
+            clawback_shortfall: Decimal::from(5),
+            status: RefundStatus::Succeeded,
+            reason: None,
+            failure_reason: None,
+            requested_by: None,
+            created_at: now,
+            updated_at: now,
+            completed_at: Some(now),
+        };
+
+        let entry = card_refund_entry(&refund);
+
+        assert!(entry.imbalance().is_zero());
+        assert_eq!(entry.source_id, Some(refund.id));
+        assert_eq!(entry.lines[1].amount, Decimal::from(5));
+        assert_eq!(entry.lines[2].amount, Decimal::from(-20));
+    }
+
+    #[test]
+    fn test_refund_amount_caps_partial_refunds() {
+        let paid = Decimal::from(100);
+
+        // Nothing asked for refunds whatever is left
+        assert_eq!(refund_amount(paid, Decimal::from(30), None).unwrap(), Decimal::from(70));
+        assert_eq!(refund_amount(paid, Decimal::from(30), Some(Decimal::from(70))).unwrap(), Decimal::from(70));
+
+        assert!(refund_amount(paid, Decimal::from(30), Some(Decimal::from(71))).is_err());
+        assert!(refund_amount(paid, Decimal::ZERO, Some(Decimal::ZERO)).is_err());
+        assert!(refund_amount(paid, Decimal::ZERO, Some(Decimal::from(-5))).is_err());
+        // Fully refunded payments have nothing left
+        assert!(refund_amount(paid, paid, None).is_err());
+    }
+
+    #[test]
+    fn test_clawback_split_records_spent_credits_as_shortfall() {
+        assert_eq!(clawback_split(Decimal::from(20), Decimal::from(50)), (Decimal::from(20), Decimal::ZERO));
+        assert_eq!(clawback_split(Decimal::from(20), Decimal::from(15)), (Decimal::from(15), Decimal::from(5)));
+        assert_eq!(clawback_split(Decimal::from(20), Decimal::ZERO), (Decimal::ZERO, Decimal::from(20)));
+        assert_eq!(clawback_split(Decimal::from(20), Decimal::from(-3)), (Decimal::ZERO, Decimal::from(20)));
+    }
+
+    #[test]
+    fn test_refunded_payment_status() {
+        let paid = Decimal::from(100);
+
+        assert_eq!(refunded_payment_status(paid, Decimal::ZERO), None);
+        assert_eq!(refunded_payment_status(paid, Decimal::from(40)), Some(PaymentStatus::PartiallyRefunded));
+        assert_eq!(refunded_payment_status(paid, paid), Some(PaymentStatus::Refunded));
+    }
+
+    #[test]
+    fn test_refund_webhook_status_transitions() {
+        assert_eq!(RefundStatus::from_stripe("succeeded"), RefundStatus::Succeeded);
+        assert_eq!(RefundStatus::from_stripe("failed"), RefundStatus::Failed);
+        assert_eq!(RefundStatus::from_stripe("canceled"), RefundStatus::Canceled);
+        assert_eq!(RefundStatus::from_stripe("requires_action"), RefundStatus::Pending);
+
+        // Pending refunds settle on whatever terminal state Stripe reports
+        for reported in [RefundStatus::Succeeded, RefundStatus::Failed, RefundStatus::Canceled] {
+            assert_eq!(RefundStatus::Pending.transition_to(reported), Some(reported));
+        }
+        assert_eq!(RefundStatus::Pending.transition_to(RefundStatus::Pending), None);
+
+        // Replayed or out-of-order webhooks leave terminal refunds alone
+        for current in [RefundStatus::Succeeded, RefundStatus::Failed, RefundStatus::Canceled] {
+            for reported in [RefundStatus::Pending, RefundStatus::Succeeded, RefundStatus::Failed] {
+                assert_eq!(current.transition_to(reported), None);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_refund_webhook_restores_credits_once() {
+        let pool = setup_test_pool().await;
+        let credit_service = CreditService::new(pool.clone());
+        let payment_service = PaymentService::new(
+            "sk_test_123".to_string(),
+            "whsec_test_123".to_string(),
+            pool.clone(),
+            credit_service.clone(),
+        );
+
+        let user_id = setup_test_data(&pool).await;
+
+        let payment = payment_service
+            .save_payment_record(
+                user_id,
+                "pi_test_refund_dup",
+                Decimal::from(20),
+                "USD",
+                PaymentStatus::Succeeded,
+                "Test refunded payment",
+                serde_json::json!({}),
+                None,
+            )
+            .await
+            .unwrap();
+
+        // A card refund that clawed back 20 credits and is waiting on Stripe
+        sqlx::query(
+            "INSERT INTO refunds (payment_id, stripe_refund_id, destination, amount, credits_clawed_back, status, requested_by)
+             VALUES ($1, 're_test_dup', 'original_payment_method', 20, 20, 'pending', $2)",
+        )
+        .bind(payment.id)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // Stripe delivers the same event twice at once
+        let (first, second) = tokio::join!(
+            payment_service.process_refund_update("re_test_dup", RefundStatus::Canceled, None),
+            payment_service.process_refund_update("re_test_dup", RefundStatus::Canceled, None),
+        );
+
+        assert_eq!(first.unwrap().status, RefundStatus::Canceled);
+        assert_eq!(second.unwrap().status, RefundStatus::Canceled);
+
+        let balance = credit_service.get_user_balance(user_id).await.unwrap();
+        assert_eq!(balance.total_available, Decimal::from(20));
+    }
+
+    #[test]
+    fn test_wallet_type_matches_stripe_names() {
+        for wallet in [WalletType::ApplePay, WalletType::GooglePay] {
+            assert_eq!(serde_json::to_value(wallet).unwrap(), wallet.as_str());
+        }
+    }
+
     // Helper function to set up test database pool
     async fn setup_test_pool() -> PgPool {
         // This would typically connect to a test database
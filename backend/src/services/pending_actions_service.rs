@@ -0,0 +1,381 @@
+use crate::error::AppError;
+use crate::services::realtime_service::{RealtimeService, WebSocketMessage};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, error};
+use uuid::Uuid;
+
+const PUSH_INTERVAL_SECONDS: u64 = 10;
+/// Purchases older than this are reconciled by support, not shown as in flight
+const PURCHASE_LOOKBACK_DAYS: i64 = 7;
+/// Payment intents the user walked away from stop being listed after a day
+const PAYMENT_INTENT_MAX_AGE_HOURS: i64 = 24;
+/// Withdrawals and payouts are settled by the gateway within three business days
+const WITHDRAWAL_ETA_HOURS: i64 = 72;
+
+/// Aggregates everything a user has in flight — chain transactions, purchases waiting
+/// for confirmation, withdrawals and unfinished payments — and pushes changes to
+/// their open WebSocket connections.
+#[derive(Clone)]
+pub struct PendingActionsService {
+    db_pool: PgPool,
+    realtime_service: RealtimeService,
+    block_time_secs: i64,
+    confirmations: i64,
+    last_pushed: Arc<Mutex<HashMap<Uuid, Vec<String>>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingActionKind {
+    BlockchainTransaction,
+    BoxPurchase,
+    Withdrawal,
+    PaymentIntent,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingAction {
+    /// Transaction hash, payment intent or transaction id, depending on the kind
+    pub id: String,
+    pub kind: PendingActionKind,
+    pub status: String,
+    pub description: String,
+    pub amount: Option<Decimal>,
+    pub raffle_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    /// Best estimate of when the action settles; `None` when it waits on the user or is unknown
+    pub eta: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingActions {
+    pub actions: Vec<PendingAction>,
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+struct PurchaseRow {
+    transaction_hash: Option<String>,
+    raffle_id: Uuid,
+    boxes: i64,
+    amount: Decimal,
+    created_at: DateTime<Utc>,
+    event_status: Option<String>,
+    event_timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, FromRow)]
+struct WithdrawalRow {
+    id: Uuid,
+    amount: Decimal,
+    status: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+struct PaymentRow {
+    stripe_payment_intent_id: String,
+    amount: Decimal,
+    currency: String,
+    status: String,
+    description: String,
+    created_at: DateTime<Utc>,
+}
+
+impl PendingActionsService {
+    pub fn new(db_pool: PgPool, realtime_service: RealtimeService) -> Self {
+        Self {
+            db_pool,
+            realtime_service,
+            block_time_secs: 2,
+            confirmations: 3,
+            last_pushed: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Estimate confirmation times from the network's block time and required confirmations
+    pub fn with_chain_timing(mut self, block_time_secs: u64, confirmations: usize) -> Self {
+        self.block_time_secs = block_time_secs as i64;
+        self.confirmations = confirmations as i64;
+        self
+    }
+
+    /// Push updated lists to connected users whenever one of their actions changes
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(PUSH_INTERVAL_SECONDS));
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.push_updates().await {
+                    error!("Pending actions push failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Everything the user is waiting on, newest first
+    pub async fn list_for_user(&self, user_id: Uuid) -> Result<PendingActions, AppError> {
+        let now = Utc::now();
+        let mut actions = self.purchase_actions(user_id, now).await?;
+        actions.extend(self.withdrawal_actions(user_id).await?);
+        actions.extend(self.payment_actions(user_id).await?);
+        actions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+        Ok(PendingActions {
+            actions,
+            generated_at: now,
+        })
+    }
+
+    // Private helper methods
+
+    async fn push_updates(&self) -> Result<(), AppError> {
+        let user_ids = self.realtime_service.connected_user_ids().await;
+        let mut last_pushed = self.last_pushed.lock().await;
+        last_pushed.retain(|user_id, _| user_ids.contains(user_id));
+
+        for user_id in user_ids {
+            let pending = self.list_for_user(user_id).await?;
+            let current = fingerprint(&pending.actions);
+
+            // A user who just connected has nothing to compare against, so they get
+            // the first list only if it isn't empty
+            let previous = last_pushed.get(&user_id).map(Vec::as_slice).unwrap_or(&[]);
+            if previous == current.as_slice() {
+                continue;
+            }
+
+            let message = WebSocketMessage {
+                message_type: "pending_actions".to_string(),
+                data: serde_json::to_value(&pending)
+                    .map_err(|e| AppError::Internal(format!("Failed to serialize pending actions: {}", e)))?,
+                timestamp: pending.generated_at,
+            };
+            if let Err(e) = self.realtime_service.send_to_user(user_id, message).await {
+                debug!("Pending actions for user {} not delivered: {}", user_id, e);
+                continue;
+            }
+            last_pushed.insert(user_id, current);
+        }
+
+        Ok(())
+    }
+
+    async fn purchase_actions(&self, user_id: Uuid, now: DateTime<Utc>) -> Result<Vec<PendingAction>, AppError> {
+        let rows = sqlx::query_as::<_, PurchaseRow>(
+            r#"
+            SELECT bp.blockchain_tx_hash AS transaction_hash, bp.raffle_id,
+                   COUNT(*) AS boxes, SUM(bp.purchase_price_in_credits) AS amount,
+                   MIN(bp.created_at) AS created_at,
+                   MAX(be.status) AS event_status, MAX(be.timestamp) AS event_timestamp
+            FROM box_purchases bp
+            JOIN raffles r ON r.id = bp.raffle_id
+            LEFT JOIN blockchain_events be
+                ON be.transaction_hash = bp.blockchain_tx_hash AND be.event_type = 'box_purchased'
+            WHERE bp.user_id = $1
+              AND r.blockchain_raffle_id IS NOT NULL
+              AND bp.created_at > NOW() - make_interval(days => $2)
+              AND (bp.blockchain_tx_hash IS NULL OR be.status IS DISTINCT FROM 'confirmed')
+            GROUP BY bp.blockchain_tx_hash, bp.raffle_id
+            "#,
+        )
+        .bind(user_id)
+        .bind(PURCHASE_LOOKBACK_DAYS as i32)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let status = purchase_status(row.transaction_hash.is_some(), row.event_status.as_deref());
+                let eta = chain_eta(
+                    status,
+                    row.created_at,
+                    row.event_timestamp,
+                    self.block_time_secs,
+                    self.confirmations,
+                    now,
+                );
+                let boxes = if row.boxes == 1 { "1 box".to_string() } else { format!("{} boxes", row.boxes) };
+
+                match row.transaction_hash {
+                    Some(hash) => PendingAction {
+                        id: hash,
+                        kind: PendingActionKind::BlockchainTransaction,
+                        status: status.to_string(),
+                        description: format!("On-chain purchase of {}", boxes),
+                        amount: Some(row.amount),
+                        raffle_id: Some(row.raffle_id),
+                        created_at: row.created_at,
+                        eta,
+                    },
+                    None => PendingAction {
+                        id: row.raffle_id.to_string(),
+                        kind: PendingActionKind::BoxPurchase,
+                        status: status.to_string(),
+                        description: format!("Purchase of {} waiting to be sent to the chain", boxes),
+                        amount: Some(row.amount),
+                        raffle_id: Some(row.raffle_id),
+                        created_at: row.created_at,
+                        eta,
+                    },
+                }
+            })
+            .collect())
+    }
+
+    async fn withdrawal_actions(&self, user_id: Uuid) -> Result<Vec<PendingAction>, AppError> {
+        let rows = sqlx::query_as::<_, WithdrawalRow>(
+            r#"
+            SELECT id, amount, status, created_at
+            FROM transactions
+            WHERE user_id = $1
+              AND type IN ('credit_withdrawal', 'payout')
+              AND status IN ('pending', 'processing')
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| PendingAction {
+                id: row.id.to_string(),
+                kind: PendingActionKind::Withdrawal,
+                description: format!("Withdrawal of {}", row.amount),
+                status: row.status,
+                amount: Some(row.amount),
+                raffle_id: None,
+                created_at: row.created_at,
+                eta: Some(row.created_at + Duration::hours(WITHDRAWAL_ETA_HOURS)),
+            })
+            .collect())
+    }
+
+    async fn payment_actions(&self, user_id: Uuid) -> Result<Vec<PendingAction>, AppError> {
+        let rows = sqlx::query_as::<_, PaymentRow>(
+            r#"
+            SELECT stripe_payment_intent_id, amount, currency, status::text AS status,
+                   description, created_at
+            FROM payments
+            WHERE user_id = $1
+              AND status IN ('pending', 'processing')
+              AND created_at > NOW() - make_interval(hours => $2)
+            "#,
+        )
+        .bind(user_id)
+        .bind(PAYMENT_INTENT_MAX_AGE_HOURS as i32)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| PendingAction {
+                id: row.stripe_payment_intent_id,
+                kind: PendingActionKind::PaymentIntent,
+                // A pending intent is waiting for the user to finish checkout
+                status: if row.status == "pending" { "requires_action".to_string() } else { row.status },
+                description: format!("{} ({} {})", row.description, row.amount, row.currency),
+                amount: Some(row.amount),
+                raffle_id: None,
+                created_at: row.created_at,
+                eta: None,
+            })
+            .collect())
+    }
+}
+
+/// Where a purchase is on its way to the chain
+fn purchase_status(has_tx_hash: bool, event_status: Option<&str>) -> &'static str {
+    match (has_tx_hash, event_status) {
+        (false, _) => "awaiting_submission",
+        (true, Some("pending")) => "confirming",
+        (true, _) => "submitted",
+    }
+}
+
+/// Expected confirmation time, never earlier than one block from now
+fn chain_eta(
+    status: &str,
+    created_at: DateTime<Utc>,
+    included_at: Option<DateTime<Utc>>,
+    block_time_secs: i64,
+    confirmations: i64,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let estimate = match (status, included_at) {
+        ("confirming", Some(included_at)) => included_at + Duration::seconds(block_time_secs * confirmations),
+        ("submitted", _) => created_at + Duration::seconds(block_time_secs * (confirmations + 1)),
+        _ => return None,
+    };
+
+    Some(estimate.max(now + Duration::seconds(block_time_secs)))
+}
+
+/// What a pushed list is compared on; amounts and ETAs alone don't warrant a push
+fn fingerprint(actions: &[PendingAction]) -> Vec<String> {
+    let mut keys: Vec<String> = actions.iter().map(|a| format!("{}:{}", a.id, a.status)).collect();
+    keys.sort();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_purchase_status() {
+        assert_eq!(purchase_status(false, None), "awaiting_submission");
+        assert_eq!(purchase_status(true, None), "submitted");
+        assert_eq!(purchase_status(true, Some("pending")), "confirming");
+    }
+
+    #[test]
+    fn test_chain_eta() {
+        let now = Utc::now();
+        let included = now - Duration::seconds(2);
+
+        // Included two seconds ago, three confirmations at two seconds each
+        assert_eq!(
+            chain_eta("confirming", now - Duration::seconds(10), Some(included), 2, 3, now),
+            Some(included + Duration::seconds(6))
+        );
+        // Overdue estimates are pushed out to the next block
+        assert_eq!(
+            chain_eta("submitted", now - Duration::minutes(5), None, 2, 3, now),
+            Some(now + Duration::seconds(2))
+        );
+        assert_eq!(chain_eta("awaiting_submission", now, None, 2, 3, now), None);
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_order() {
+        let action = |id: &str, status: &str| PendingAction {
+            id: id.to_string(),
+            kind: PendingActionKind::Withdrawal,
+            status: status.to_string(),
+            description: String::new(),
+            amount: None,
+            raffle_id: None,
+            created_at: Utc::now(),
+            eta: None,
+        };
+
+        assert_eq!(
+            fingerprint(&[action("a", "pending"), action("b", "processing")]),
+            fingerprint(&[action("b", "processing"), action("a", "pending")])
+        );
+        assert_ne!(
+            fingerprint(&[action("a", "pending")]),
+            fingerprint(&[action("a", "processing")])
+        );
+    }
+}
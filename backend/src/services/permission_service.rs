@@ -0,0 +1,279 @@
+use crate::error::AppError;
+use crate::models::AuditLog;
+use raffle_platform_shared::{AuditAction, UserRole};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub const CREDITS_ISSUE: &str = "credits.issue";
+pub const RAFFLES_FORCE_COMPLETE: &str = "raffles.force_complete";
+pub const RAFFLES_REPAIR: &str = "raffles.repair";
+pub const PAYMENTS_VIEW: &str = "payments.view";
+pub const PAYMENTS_REFUND: &str = "payments.refund";
+pub const USERS_BAN: &str = "users.ban";
+pub const USERS_CHANGE_ROLE: &str = "users.change_role";
+pub const PERMISSIONS_MANAGE: &str = "permissions.manage";
+pub const SETTINGS_MANAGE: &str = "settings.manage";
+pub const CATALOG_MANAGE: &str = "catalog.manage";
+pub const API_PLANS_MANAGE: &str = "api_plans.manage";
+
+/// Role grants are re-read from the database this often
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Permission service decides which admin operations each role may perform
+#[derive(Clone)]
+pub struct PermissionService {
+    db_pool: PgPool,
+    cache: Arc<RwLock<Option<CachedGrants>>>,
+}
+
+struct CachedGrants {
+    loaded_at: Instant,
+    grants: HashMap<String, HashSet<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Permission {
+    pub key: String,
+    pub description: String,
+}
+
+/// Every permission and the roles holding it
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PermissionMatrix {
+    pub permissions: Vec<Permission>,
+    /// Role name to its granted permission keys
+    pub roles: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SetRolePermissionsRequest {
+    pub permissions: Vec<String>,
+}
+
+#[derive(Debug, FromRow)]
+struct GrantRow {
+    role: String,
+    permission: String,
+}
+
+impl PermissionService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self {
+            db_pool,
+            cache: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Whether the role holds the permission; operators hold every permission
+    pub async fn has_permission(&self, role: UserRole, permission: &str) -> Result<bool, AppError> {
+        if role == UserRole::Operator {
+            return Ok(true);
+        }
+
+        {
+            let cache = self.cache.read().await;
+            if let Some(cached) = cache.as_ref().filter(|c| c.loaded_at.elapsed() < CACHE_TTL) {
+                return Ok(role_holds(&cached.grants, role, permission));
+            }
+        }
+
+        let grants = self.load_grants().await?;
+        let allowed = role_holds(&grants, role, permission);
+        *self.cache.write().await = Some(CachedGrants {
+            loaded_at: Instant::now(),
+            grants,
+        });
+
+        Ok(allowed)
+    }
+
+    /// Fail with Forbidden unless the role holds the permission
+    pub async fn require(&self, role: UserRole, permission: &str) -> Result<(), AppError> {
+        if self.has_permission(role, permission).await? {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!("Missing permission {}", permission)))
+        }
+    }
+
+    /// All permissions and the roles they are granted to
+    pub async fn get_matrix(&self) -> Result<PermissionMatrix, AppError> {
+        let permissions = sqlx::query_as::<_, Permission>("SELECT key, description FROM permissions ORDER BY key")
+            .fetch_all(&self.db_pool)
+            .await?;
+
+        let mut roles: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (role, granted) in self.load_grants().await? {
+            let mut granted: Vec<String> = granted.into_iter().collect();
+            granted.sort();
+            roles.insert(role, granted);
+        }
+
+        Ok(PermissionMatrix { permissions, roles })
+    }
+
+    /// Replace the permissions granted to a role; returns the new grants
+    pub async fn set_role_permissions(
+        &self,
+        admin_id: Uuid,
+        role: &str,
+        request: SetRolePermissionsRequest,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<Vec<String>, AppError> {
+        let role = parse_role(role)?;
+        if role == UserRole::Operator {
+            return Err(AppError::Validation("Operators always hold every permission".to_string()));
+        }
+
+        let known: HashSet<String> = sqlx::query_scalar::<_, String>("SELECT key FROM permissions")
+            .fetch_all(&self.db_pool)
+            .await?
+            .into_iter()
+            .collect();
+        let requested = normalize_permissions(&request.permissions, &known)?;
+
+        let mut tx = self.db_pool.begin().await?;
+
+        let previous: Vec<String> = sqlx::query_scalar::<_, String>(
+            "DELETE FROM role_permissions WHERE role = $1 RETURNING permission",
+        )
+        .bind(role.to_string())
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for permission in &requested {
+            sqlx::query("INSERT INTO role_permissions (role, permission, granted_by) VALUES ($1, $2, $3)")
+                .bind(role.to_string())
+                .bind(permission)
+                .bind(admin_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        *self.cache.write().await = None;
+
+        let mut previous = previous;
+        previous.sort();
+        let granted: Vec<String> = requested.into_iter().collect();
+
+        AuditLog::create(
+            &self.db_pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("role_permissions".to_string()),
+            None,
+            Some(serde_json::json!({ "role": role.to_string(), "permissions": previous })),
+            Some(serde_json::json!({ "role": role.to_string(), "permissions": granted })),
+            ip_address,
+            user_agent,
+        ).await?;
+
+        info!("Admin {} set {} permissions for role {}", admin_id, granted.len(), role);
+
+        Ok(granted)
+    }
+
+    // Private helper methods
+
+    async fn load_grants(&self) -> Result<HashMap<String, HashSet<String>>, AppError> {
+        let rows = sqlx::query_as::<_, GrantRow>("SELECT role, permission FROM role_permissions")
+            .fetch_all(&self.db_pool)
+            .await?;
+
+        let mut grants: HashMap<String, HashSet<String>> = HashMap::new();
+        for row in rows {
+            grants.entry(row.role).or_default().insert(row.permission);
+        }
+
+        Ok(grants)
+    }
+}
+
+fn role_holds(grants: &HashMap<String, HashSet<String>>, role: UserRole, permission: &str) -> bool {
+    role == UserRole::Operator
+        || grants
+            .get(&role.to_string())
+            .is_some_and(|granted| granted.contains(permission))
+}
+
+/// Parse a role name as stored in the database
+pub fn parse_role(name: &str) -> Result<UserRole, AppError> {
+    match name.trim().to_lowercase().as_str() {
+        "user" => Ok(UserRole::User),
+        "seller" => Ok(UserRole::Seller),
+        "support" => Ok(UserRole::Support),
+        "admin" => Ok(UserRole::Admin),
+        "operator" => Ok(UserRole::Operator),
+        _ => Err(AppError::Validation(format!("Unknown role: {}", name))),
+    }
+}
+
+/// Trim and deduplicate permission keys, rejecting any that don't exist
+pub fn normalize_permissions(requested: &[String], known: &HashSet<String>) -> Result<BTreeSet<String>, AppError> {
+    let mut permissions = BTreeSet::new();
+    for permission in requested {
+        let permission = permission.trim();
+        if !known.contains(permission) {
+            return Err(AppError::Validation(format!("Unknown permission: {}", permission)));
+        }
+        permissions.insert(permission.to_string());
+    }
+    Ok(permissions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grants(entries: &[(&str, &str)]) -> HashMap<String, HashSet<String>> {
+        let mut grants: HashMap<String, HashSet<String>> = HashMap::new();
+        for (role, permission) in entries {
+            grants.entry(role.to_string()).or_default().insert(permission.to_string());
+        }
+        grants
+    }
+
+    #[test]
+    fn test_role_holds() {
+        let grants = grants(&[("support", PAYMENTS_REFUND), ("admin", CREDITS_ISSUE)]);
+
+        assert!(role_holds(&grants, UserRole::Support, PAYMENTS_REFUND));
+        assert!(!role_holds(&grants, UserRole::Support, CREDITS_ISSUE));
+        assert!(role_holds(&grants, UserRole::Admin, CREDITS_ISSUE));
+        assert!(!role_holds(&grants, UserRole::Admin, PAYMENTS_REFUND));
+        assert!(role_holds(&grants, UserRole::Operator, PERMISSIONS_MANAGE));
+        assert!(!role_holds(&grants, UserRole::User, PAYMENTS_REFUND));
+    }
+
+    #[test]
+    fn test_parse_role() {
+        assert_eq!(parse_role("Support").unwrap(), UserRole::Support);
+        assert_eq!(parse_role(" admin ").unwrap(), UserRole::Admin);
+        assert!(parse_role("superuser").is_err());
+    }
+
+    #[test]
+    fn test_normalize_permissions() {
+        let known: HashSet<String> = [PAYMENTS_VIEW, PAYMENTS_REFUND].iter().map(|p| p.to_string()).collect();
+
+        let permissions = normalize_permissions(
+            &[" payments.refund".to_string(), "payments.view".to_string(), "payments.refund".to_string()],
+            &known,
+        )
+        .unwrap();
+        assert_eq!(permissions.into_iter().collect::<Vec<_>>(), vec![PAYMENTS_REFUND, PAYMENTS_VIEW]);
+
+        assert!(normalize_permissions(&["credits.issue".to_string()], &known).is_err());
+        assert!(normalize_permissions(&[], &known).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,344 @@
+use crate::error::AppError;
+use crate::models::promotion::{NewPromotion, Promotion, PromotionKind, PromotionStatus};
+use crate::services::realtime_service::{RealtimeEvent, RealtimeService};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use tracing::{error, info};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const SCHEDULER_INTERVAL_SECONDS: u64 = 15;
+
+/// Promotion service schedules credit multipliers and fee discounts, announces them
+/// on the platform channel as they start and end, and reports their uplift.
+///
+/// Pricing never waits for the scheduler: credit and fee calculations look up the
+/// promotion whose window contains the relevant time directly.
+#[derive(Clone)]
+pub struct PromotionService {
+    db_pool: PgPool,
+    realtime_service: RealtimeService,
+}
+
+/// Activity in a time window
+#[derive(Debug, Clone, Default, Serialize, FromRow, ToSchema)]
+pub struct PromotionWindowMetrics {
+    pub credit_purchases: i64,
+    pub credit_purchase_volume: Decimal,
+    pub boxes_sold: i64,
+    pub raffles_created: i64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PromotionUplift {
+    pub credit_purchases: Option<Decimal>,
+    pub credit_purchase_volume: Option<Decimal>,
+    pub boxes_sold: Option<Decimal>,
+    pub raffles_created: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PromotionReport {
+    pub promotion: Promotion,
+    /// The promotion's window, cut short if it was cancelled or is still running
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub during: PromotionWindowMetrics,
+    /// The window of the same length just before the promotion started
+    pub baseline: PromotionWindowMetrics,
+    /// Percent change from the baseline; None when the baseline had no activity
+    pub uplift_percentage: PromotionUplift,
+    pub redemptions: i64,
+    /// Bonus credits issued, for a credit multiplier
+    pub total_benefit: Decimal,
+    /// Raffles priced with the discount, for a fee discount
+    pub discounted_raffles: i64,
+}
+
+impl PromotionService {
+    pub fn new(db_pool: PgPool, realtime_service: RealtimeService) -> Self {
+        Self { db_pool, realtime_service }
+    }
+
+    /// Announce promotions as their windows open and close
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(SCHEDULER_INTERVAL_SECONDS));
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.run_transitions().await {
+                    error!("Promotion scheduler run failed: {}", e);
+                }
+            }
+        });
+    }
+
+    pub async fn create(&self, new: NewPromotion, created_by: Uuid) -> Result<Promotion, AppError> {
+        let promotion = Promotion::create(&self.db_pool, &new, created_by).await?;
+
+        info!(
+            "Scheduled promotion {} ({:?} {}) from {} to {}",
+            promotion.id, promotion.kind, promotion.value, promotion.starts_at, promotion.ends_at
+        );
+
+        Ok(promotion)
+    }
+
+    pub async fn list(&self, status: Option<PromotionStatus>, limit: i64) -> Result<Vec<Promotion>, AppError> {
+        Promotion::list(&self.db_pool, status, limit).await
+    }
+
+    /// Promotions that apply right now
+    pub async fn active(&self) -> Result<Vec<Promotion>, AppError> {
+        Promotion::all_active_at(&self.db_pool, Utc::now()).await
+    }
+
+    /// Stop a promotion that hasn't ended; it no longer applies from this moment
+    pub async fn cancel(&self, promotion_id: Uuid) -> Result<Promotion, AppError> {
+        let promotion = self.get(promotion_id).await?;
+
+        let from = match promotion.status {
+            PromotionStatus::Scheduled | PromotionStatus::Active => promotion.status,
+            _ => return Err(AppError::Conflict("Promotion has already ended".to_string())),
+        };
+
+        if !Promotion::transition(&self.db_pool, promotion_id, from, PromotionStatus::Cancelled).await? {
+            return Err(AppError::Conflict("Promotion changed status; try again".to_string()));
+        }
+
+        if from == PromotionStatus::Active {
+            self.announce_end(&promotion).await;
+        }
+
+        info!("Cancelled promotion {}", promotion_id);
+        self.get(promotion_id).await
+    }
+
+    /// Activity during the promotion compared with the same length of time before it
+    pub async fn report(&self, promotion_id: Uuid) -> Result<PromotionReport, AppError> {
+        let promotion = self.get(promotion_id).await?;
+        let (window_start, window_end) = report_window(&promotion, Utc::now())
+            .ok_or_else(|| AppError::Validation("Promotion hasn't started yet".to_string()))?;
+        let baseline_start = window_start - (window_end - window_start);
+
+        let during = self.window_metrics(window_start, window_end).await?;
+        let baseline = self.window_metrics(baseline_start, window_start).await?;
+
+        let (redemptions, total_benefit) = sqlx::query_as::<_, (i64, Decimal)>(
+            r#"
+            SELECT COUNT(*), COALESCE(SUM(benefit_amount), 0)
+            FROM promotion_redemptions
+            WHERE promotion_id = $1
+            "#,
+        )
+        .bind(promotion_id)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        let discounted_raffles = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM raffles WHERE fee_promotion_id = $1",
+        )
+        .bind(promotion_id)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(PromotionReport {
+            uplift_percentage: PromotionUplift {
+                credit_purchases: uplift_percentage(
+                    Decimal::from(during.credit_purchases),
+                    Decimal::from(baseline.credit_purchases),
+                ),
+                credit_purchase_volume: uplift_percentage(
+                    during.credit_purchase_volume,
+                    baseline.credit_purchase_volume,
+                ),
+                boxes_sold: uplift_percentage(Decimal::from(during.boxes_sold), Decimal::from(baseline.boxes_sold)),
+                raffles_created: uplift_percentage(
+                    Decimal::from(during.raffles_created),
+                    Decimal::from(baseline.raffles_created),
+                ),
+            },
+            promotion,
+            window_start,
+            window_end,
+            during,
+            baseline,
+            redemptions,
+            total_benefit,
+            discounted_raffles,
+        })
+    }
+
+    // Private helper methods
+
+    async fn get(&self, promotion_id: Uuid) -> Result<Promotion, AppError> {
+        Promotion::find_by_id(&self.db_pool, promotion_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Promotion not found".to_string()))
+    }
+
+    async fn run_transitions(&self) -> Result<(), AppError> {
+        let now = Utc::now();
+
+        for promotion in Promotion::due_transitions(&self.db_pool, now).await? {
+            let Some(next) = next_status(&promotion, now) else {
+                continue;
+            };
+            if !Promotion::transition(&self.db_pool, promotion.id, promotion.status, next).await? {
+                continue;
+            }
+
+            // A promotion the scheduler only noticed after its window has nothing left to announce
+            if promotion.status == PromotionStatus::Scheduled && next == PromotionStatus::Active {
+                info!("Promotion {} started", promotion.id);
+                let _ = self.realtime_service.broadcast_event(RealtimeEvent::PromotionStarted {
+                    promotion_id: promotion.id,
+                    name: promotion.name.clone(),
+                    kind: kind_str(promotion.kind).to_string(),
+                    value: promotion.value,
+                    ends_at: promotion.ends_at,
+                    started_at: promotion.starts_at,
+                }).await;
+            } else if promotion.status == PromotionStatus::Active {
+                info!("Promotion {} ended", promotion.id);
+                self.announce_end(&promotion).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn announce_end(&self, promotion: &Promotion) {
+        let _ = self.realtime_service.broadcast_event(RealtimeEvent::PromotionEnded {
+            promotion_id: promotion.id,
+            name: promotion.name.clone(),
+            ended_at: Utc::now(),
+        }).await;
+    }
+
+    async fn window_metrics(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<PromotionWindowMetrics, AppError> {
+        let metrics = sqlx::query_as::<_, PromotionWindowMetrics>(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM payments
+                 WHERE status IN ('succeeded', 'partially_refunded', 'refunded')
+                   AND created_at >= $1 AND created_at < $2) AS credit_purchases,
+                (SELECT COALESCE(SUM(amount), 0) FROM payments
+                 WHERE status IN ('succeeded', 'partially_refunded', 'refunded')
+                   AND created_at >= $1 AND created_at < $2) AS credit_purchase_volume,
+                (SELECT COUNT(*) FROM box_purchases
+                 WHERE created_at >= $1 AND created_at < $2) AS boxes_sold,
+                (SELECT COUNT(*) FROM raffles
+                 WHERE created_at >= $1 AND created_at < $2) AS raffles_created
+            "#,
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(metrics)
+    }
+}
+
+fn kind_str(kind: PromotionKind) -> &'static str {
+    match kind {
+        PromotionKind::CreditMultiplier => "credit_multiplier",
+        PromotionKind::FeeDiscount => "fee_discount",
+    }
+}
+
+/// The status a promotion should move to at `now`, if its current one is behind.
+///
+/// A scheduled promotion whose whole window has passed goes straight to ended.
+fn next_status(promotion: &Promotion, now: DateTime<Utc>) -> Option<PromotionStatus> {
+    match promotion.status {
+        PromotionStatus::Scheduled if now >= promotion.ends_at => Some(PromotionStatus::Ended),
+        PromotionStatus::Scheduled if now >= promotion.starts_at => Some(PromotionStatus::Active),
+        PromotionStatus::Active if now >= promotion.ends_at => Some(PromotionStatus::Ended),
+        _ => None,
+    }
+}
+
+/// The part of a promotion's window that has actually run
+fn report_window(promotion: &Promotion, now: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut end = promotion.ends_at.min(now);
+    if promotion.status == PromotionStatus::Cancelled {
+        end = end.min(promotion.ended_at.unwrap_or(end));
+    }
+
+    (end > promotion.starts_at).then_some((promotion.starts_at, end))
+}
+
+fn uplift_percentage(during: Decimal, baseline: Decimal) -> Option<Decimal> {
+    if baseline <= Decimal::ZERO {
+        return None;
+    }
+
+    Some(((during - baseline) * Decimal::from(100) / baseline).round_dp(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn promotion(status: PromotionStatus) -> Promotion {
+        let starts_at = Utc.with_ymd_and_hms(2024, 6, 1, 18, 0, 0).unwrap();
+        Promotion {
+            id: Uuid::new_v4(),
+            name: "Double credits".to_string(),
+            kind: PromotionKind::CreditMultiplier,
+            value: Decimal::from(2),
+            starts_at,
+            ends_at: starts_at + Duration::hours(2),
+            status,
+            activated_at: None,
+            ended_at: None,
+            created_by: None,
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn test_next_status() {
+        let scheduled = promotion(PromotionStatus::Scheduled);
+        let start = scheduled.starts_at;
+
+        assert_eq!(next_status(&scheduled, start - Duration::minutes(1)), None);
+        assert_eq!(next_status(&scheduled, start), Some(PromotionStatus::Active));
+        assert_eq!(next_status(&scheduled, start + Duration::hours(3)), Some(PromotionStatus::Ended));
+
+        let active = promotion(PromotionStatus::Active);
+        assert_eq!(next_status(&active, start + Duration::hours(1)), None);
+        assert_eq!(next_status(&active, start + Duration::hours(2)), Some(PromotionStatus::Ended));
+        assert_eq!(next_status(&promotion(PromotionStatus::Cancelled), start + Duration::hours(3)), None);
+    }
+
+    #[test]
+    fn test_report_window() {
+        let mut cancelled = promotion(PromotionStatus::Cancelled);
+        let start = cancelled.starts_at;
+        cancelled.ended_at = Some(start + Duration::minutes(30));
+
+        assert_eq!(
+            report_window(&cancelled, start + Duration::hours(5)),
+            Some((start, start + Duration::minutes(30)))
+        );
+        assert_eq!(
+            report_window(&promotion(PromotionStatus::Active), start + Duration::hours(1)),
+            Some((start, start + Duration::hours(1)))
+        );
+        assert_eq!(report_window(&promotion(PromotionStatus::Scheduled), start), None);
+    }
+
+    #[test]
+    fn test_uplift_percentage() {
+        assert_eq!(uplift_percentage(Decimal::from(150), Decimal::from(100)), Some(Decimal::from(50)));
+        assert_eq!(uplift_percentage(Decimal::from(80), Decimal::from(100)), Some(Decimal::from(-20)));
+        assert_eq!(uplift_percentage(Decimal::from(10), Decimal::ZERO), None);
+    }
+}
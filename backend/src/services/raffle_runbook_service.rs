@@ -0,0 +1,843 @@
+use crate::blockchain::types::TransactionState;
+use crate::error::AppError;
+use crate::models::audit::AuditLog;
+use crate::models::external_wallet::ExternalWallet;
+use crate::models::system_settings::SystemSetting;
+use crate::services::blockchain_service::BlockchainService;
+use crate::services::chain_reconciliation_service::{compare, ChainSnapshot, Divergence, RaffleSnapshot};
+use crate::services::data_fix_service::{check_apply_guard, DataFixRequest, DataFixService, FieldChange};
+use crate::services::raffle_service::RaffleService;
+use chrono::{DateTime, Duration, Utc};
+use raffle_platform_shared::{AuditAction, RaffleStatus};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use std::net::IpAddr;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const DEFAULT_RANDOMNESS_TIMEOUT_MINUTES: i64 = 30;
+/// The event processor counts as lagging once it is this many blocks behind the chain head
+const MAX_PROCESSOR_LAG_BLOCKS: i64 = 100;
+/// Remediations listed in a diagnosis
+const REMEDIATION_HISTORY_LIMIT: i64 = 20;
+
+/// Why a raffle looks stuck
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StuckSymptom {
+    /// Awaiting a draw, but the contract never emitted a randomness request
+    RandomnessNotRequested,
+    /// Randomness was requested longer than the timeout ago and no winner was drawn
+    RandomnessUnfulfilled,
+    /// The contract drew winners but the raffle is still awaiting its draw
+    WinnerNotRecorded,
+    /// The row disagrees with the contract; see `divergences`
+    ChainDivergence,
+    /// The contract could not be read
+    ChainUnreadable,
+    /// Events for the raffle are still waiting for confirmations
+    UnconfirmedEvents,
+    /// Events for the raffle failed to store and sit in the dead-letter queue
+    DeadLetteredEvents,
+    /// The event processor is far behind the chain head
+    EventProcessorLagging,
+}
+
+/// Remediations the runbook offers
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RemediationAction {
+    /// Ask the contract for randomness again
+    RerequestRandomness,
+    /// Set the raffle's sold count and status from the contract
+    Resync,
+    /// Record the winners the contract already drew and complete the raffle
+    ForceComplete,
+}
+
+impl RemediationAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RemediationAction::RerequestRandomness => "rerequest_randomness",
+            RemediationAction::Resync => "resync",
+            RemediationAction::ForceComplete => "force_complete",
+        }
+    }
+}
+
+/// What the contract reports for the raffle
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ChainState {
+    pub boxes_sold: i32,
+    pub completed: bool,
+    pub winner: Option<String>,
+}
+
+/// A stored contract event for the raffle
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct RaffleChainEvent {
+    pub event_type: String,
+    pub block_number: i64,
+    pub transaction_hash: String,
+    /// `pending` until the event is deep enough to be final
+    pub status: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct EventProcessorState {
+    pub last_processed_block: i64,
+    /// None when the chain head could not be read
+    pub chain_head: Option<i64>,
+    pub lag_blocks: Option<i64>,
+}
+
+/// A remediation applied to the raffle
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct RaffleRemediation {
+    pub id: Uuid,
+    pub raffle_id: Uuid,
+    pub action: RemediationAction,
+    pub justification: String,
+    pub plan_hash: String,
+    pub changes: Value,
+    pub blockchain_tx_id: Option<Uuid>,
+    pub performed_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A contract call sent by a remediation and what the transaction manager knows of it
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RemediationTransaction {
+    pub remediation_id: Uuid,
+    pub blockchain_tx_id: Uuid,
+    /// pending, confirmed or failed; None once the transaction manager has forgotten it
+    pub state: Option<String>,
+    pub tx_hash: Option<String>,
+    pub sent_at: DateTime<Utc>,
+}
+
+/// Everything an operator needs to decide how to unstick a raffle
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RaffleDiagnosis {
+    pub raffle_id: Uuid,
+    pub blockchain_raffle_id: Option<i64>,
+    pub status: RaffleStatus,
+    pub total_boxes: i32,
+    pub boxes_sold: i32,
+    pub total_winners: i32,
+    pub last_updated_at: DateTime<Utc>,
+    pub chain: Option<ChainState>,
+    pub chain_error: Option<String>,
+    pub divergences: Vec<Divergence>,
+    /// Contract events stored for the raffle, in block order
+    pub events: Vec<RaffleChainEvent>,
+    pub randomness_requested_at: Option<DateTime<Utc>>,
+    /// Winner wallets from the contract's `winner_selected` event, or its reported winner
+    pub chain_winners: Vec<String>,
+    pub dead_letters: i64,
+    pub event_processor: EventProcessorState,
+    pub pending_transactions: Vec<RemediationTransaction>,
+    pub symptoms: Vec<StuckSymptom>,
+    /// Remediations that are safe to run now; each is a dry run unless confirmed
+    pub available_actions: Vec<RemediationAction>,
+    pub remediations: Vec<RaffleRemediation>,
+}
+
+/// Result of a remediation: the diff and whether it was applied
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RemediationPlan {
+    pub action: RemediationAction,
+    pub raffle_id: Uuid,
+    pub changes: Vec<FieldChange>,
+    /// Send back with `dry_run: false` to apply exactly this plan
+    pub plan_hash: String,
+    pub applied: bool,
+    /// Transaction manager id of the contract call, once sent
+    pub blockchain_tx_id: Option<Uuid>,
+}
+
+/// The facts the symptoms and available actions are derived from
+#[derive(Debug, Clone)]
+struct Signals {
+    status: RaffleStatus,
+    on_chain: bool,
+    chain: Option<ChainSnapshot>,
+    chain_drawn: bool,
+    randomness_requested_at: Option<DateTime<Utc>>,
+    divergences: usize,
+    unconfirmed_events: usize,
+    dead_letters: i64,
+    processor_lag: Option<i64>,
+    /// A randomness re-request sent within the timeout that hasn't failed
+    rerequest_in_flight: bool,
+}
+
+#[derive(Debug, FromRow)]
+struct RaffleRow {
+    id: Uuid,
+    blockchain_raffle_id: Option<i64>,
+    total_boxes: i32,
+    boxes_sold: i32,
+    total_winners: i32,
+    status: RaffleStatus,
+    updated_at: DateTime<Utc>,
+    recorded_winner: Option<String>,
+}
+
+/// Raffle runbook service diagnoses raffles stuck between sale and draw and offers guided
+/// remediations, so operators don't fix them with raw SQL or contract calls.
+///
+/// A diagnosis compares the row with the contract, the stored contract events, the dead-letter
+/// queue and the event processor's progress. Remediations follow the data-fix flow: a dry run
+/// returns the plan and its hash, and applying needs that hash and a justification. Applied
+/// remediations are kept per raffle and written to the audit log.
+#[derive(Clone)]
+pub struct RaffleRunbookService {
+    db_pool: PgPool,
+    blockchain_service: BlockchainService,
+    data_fix_service: DataFixService,
+    raffle_service: RaffleService,
+}
+
+impl RaffleRunbookService {
+    pub fn new(
+        db_pool: PgPool,
+        blockchain_service: BlockchainService,
+        data_fix_service: DataFixService,
+        raffle_service: RaffleService,
+    ) -> Self {
+        Self {
+            db_pool,
+            blockchain_service,
+            data_fix_service,
+            raffle_service,
+        }
+    }
+
+    /// Compare the raffle with the chain and list what is safe to do about it
+    pub async fn diagnose(&self, raffle_id: Uuid) -> Result<RaffleDiagnosis, AppError> {
+        let raffle = self.load_raffle(raffle_id).await?;
+        let timeout = self.randomness_timeout().await?;
+
+        let (chain, chain_error) = match raffle.blockchain_raffle_id {
+            Some(id) => match self.blockchain_service.get_raffle_stats(id as u64).await {
+                Ok(stats) => (Some(ChainSnapshot::from_stats(&stats, raffle.total_boxes)), None),
+                Err(e) => (None, Some(e.to_string())),
+            },
+            None => (None, None),
+        };
+
+        let divergences = match (&chain, raffle.blockchain_raffle_id) {
+            (Some(chain), Some(blockchain_raffle_id)) => compare(
+                &RaffleSnapshot {
+                    id: raffle.id,
+                    blockchain_raffle_id,
+                    total_boxes: raffle.total_boxes,
+                    boxes_sold: raffle.boxes_sold,
+                    status: raffle.status,
+                    recorded_winner: raffle.recorded_winner.clone(),
+                },
+                chain,
+            ),
+            _ => Vec::new(),
+        };
+
+        let (events, chain_winners, dead_letters) = match raffle.blockchain_raffle_id {
+            Some(id) => (self.chain_events(id).await?, self.event_winners(id).await?, self.dead_letters(id).await?),
+            None => (Vec::new(), Vec::new(), 0),
+        };
+        let chain_winners = if chain_winners.is_empty() {
+            chain.as_ref().and_then(|c| c.winner.clone()).into_iter().collect()
+        } else {
+            chain_winners
+        };
+
+        let randomness_requested_at = events
+            .iter()
+            .filter(|e| e.event_type == "randomness_requested")
+            .map(|e| e.timestamp)
+            .max();
+
+        let event_processor = self.event_processor_state().await?;
+        let remediations = self.remediations(raffle_id).await?;
+        let pending_transactions = self.remediation_transactions(&remediations).await;
+
+        let now = Utc::now();
+        let rerequest_in_flight = remediations.iter().any(|r| {
+            r.action == RemediationAction::RerequestRandomness
+                && r.created_at > now - timeout
+                && !pending_transactions
+                    .iter()
+                    .any(|t| t.remediation_id == r.id && t.state.as_deref() == Some("failed"))
+        });
+
+        let signals = Signals {
+            status: raffle.status,
+            on_chain: raffle.blockchain_raffle_id.is_some(),
+            chain_drawn: chain.as_ref().is_some_and(|c| c.completed || c.winner.is_some()) || !chain_winners.is_empty(),
+            chain: chain.clone(),
+            randomness_requested_at,
+            divergences: divergences.len(),
+            unconfirmed_events: events.iter().filter(|e| e.status == "pending").count(),
+            dead_letters,
+            processor_lag: event_processor.lag_blocks,
+            rerequest_in_flight,
+        };
+        let symptoms = symptoms(&signals, now, timeout);
+        let available_actions = available_actions(&signals, &symptoms);
+
+        Ok(RaffleDiagnosis {
+            raffle_id,
+            blockchain_raffle_id: raffle.blockchain_raffle_id,
+            status: raffle.status,
+            total_boxes: raffle.total_boxes,
+            boxes_sold: raffle.boxes_sold,
+            total_winners: raffle.total_winners,
+            last_updated_at: raffle.updated_at,
+            chain: chain.map(|c| ChainState {
+                boxes_sold: c.boxes_sold,
+                completed: c.completed,
+                winner: c.winner,
+            }),
+            chain_error,
+            divergences,
+            events,
+            randomness_requested_at,
+            chain_winners,
+            dead_letters,
+            event_processor,
+            pending_transactions,
+            symptoms,
+            available_actions,
+            remediations,
+        })
+    }
+
+    /// Ask the contract for randomness again for a raffle whose request went unanswered
+    pub async fn rerequest_randomness(
+        &self,
+        raffle_id: Uuid,
+        request: DataFixRequest,
+        admin_id: Uuid,
+        ip_address: Option<IpAddr>,
+    ) -> Result<RemediationPlan, AppError> {
+        let action = RemediationAction::RerequestRandomness;
+        let diagnosis = self.diagnose(raffle_id).await?;
+        require_available(&diagnosis, action)?;
+        let blockchain_raffle_id = diagnosis
+            .blockchain_raffle_id
+            .ok_or_else(|| AppError::Validation("Raffle has no on-chain counterpart".to_string()))?;
+
+        let changes = vec![FieldChange {
+            field: "randomness_request".to_string(),
+            before: json!({
+                "requested_at": diagnosis.randomness_requested_at,
+                "symptoms": diagnosis.symptoms,
+            }),
+            after: json!("requested"),
+        }];
+        let mut plan = plan(action, raffle_id, changes);
+        if request.dry_run {
+            return Ok(plan);
+        }
+        let justification = check_apply_guard(&request, &plan.plan_hash)?;
+
+        let tx_id = self
+            .blockchain_service
+            .request_random_winner(blockchain_raffle_id as u64)
+            .await
+            .map_err(|e| AppError::External(format!("Blockchain error: {}", e)))?;
+        plan.blockchain_tx_id = Some(tx_id);
+        plan.applied = true;
+
+        self.record(&plan, &justification, admin_id, ip_address).await?;
+        Ok(plan)
+    }
+
+    /// Set the raffle's sold count and status from the contract, through the resync data fix
+    pub async fn resync(
+        &self,
+        raffle_id: Uuid,
+        request: DataFixRequest,
+        admin_id: Uuid,
+        ip_address: Option<IpAddr>,
+    ) -> Result<RemediationPlan, AppError> {
+        let action = RemediationAction::Resync;
+        let diagnosis = self.diagnose(raffle_id).await?;
+        require_available(&diagnosis, action)?;
+
+        // The data fix checks the plan hash and audits the write itself
+        let justification = request.reason.clone();
+        let fix = self
+            .data_fix_service
+            .resync_raffle_from_chain(raffle_id, request, admin_id, ip_address)
+            .await?;
+
+        let plan = RemediationPlan {
+            action,
+            raffle_id,
+            changes: fix.changes,
+            plan_hash: fix.plan_hash,
+            applied: fix.applied,
+            blockchain_tx_id: None,
+        };
+        if plan.applied {
+            self.store_remediation(&plan, justification.as_deref().unwrap_or_default().trim(), admin_id)
+                .await?;
+        }
+
+        Ok(plan)
+    }
+
+    /// Record the winners the contract already drew and complete the raffle
+    pub async fn force_complete(
+        &self,
+        raffle_id: Uuid,
+        request: DataFixRequest,
+        admin_id: Uuid,
+        ip_address: Option<IpAddr>,
+    ) -> Result<RemediationPlan, AppError> {
+        let action = RemediationAction::ForceComplete;
+        let diagnosis = self.diagnose(raffle_id).await?;
+        require_available(&diagnosis, action)?;
+
+        if diagnosis.chain_winners.len() as i32 != diagnosis.total_winners {
+            return Err(AppError::Validation(format!(
+                "The contract drew {} winners but the raffle expects {}; resync or reconcile instead",
+                diagnosis.chain_winners.len(),
+                diagnosis.total_winners
+            )));
+        }
+
+        let winner_user_ids = self.users_for_wallets(&diagnosis.chain_winners).await?;
+        let draw_tx_hash = diagnosis
+            .events
+            .iter()
+            .rev()
+            .find(|e| e.event_type == "winner_selected")
+            .map(|e| e.transaction_hash.clone());
+
+        let changes = vec![
+            FieldChange {
+                field: "status".to_string(),
+                before: json!(diagnosis.status.to_string()),
+                after: json!(RaffleStatus::Completed.to_string()),
+            },
+            FieldChange {
+                field: "winner_user_ids".to_string(),
+                before: json!([]),
+                after: json!(winner_user_ids),
+            },
+        ];
+        let mut plan = plan(action, raffle_id, changes);
+        if request.dry_run {
+            return Ok(plan);
+        }
+        let justification = check_apply_guard(&request, &plan.plan_hash)?;
+
+        self.raffle_service
+            .record_winners(raffle_id, winner_user_ids, draw_tx_hash)
+            .await?;
+        plan.applied = true;
+
+        self.record(&plan, &justification, admin_id, ip_address).await?;
+        Ok(plan)
+    }
+
+    // Private helper methods
+
+    async fn load_raffle(&self, raffle_id: Uuid) -> Result<RaffleRow, AppError> {
+        sqlx::query_as::<_, RaffleRow>(
+            r#"
+            SELECT r.id, r.blockchain_raffle_id, r.total_boxes, r.boxes_sold, r.total_winners, r.status,
+                   r.updated_at,
+                   (SELECT u.internal_wallet_address FROM users u
+                    WHERE u.id = r.winner_user_ids[1]) AS recorded_winner
+            FROM raffles r
+            WHERE r.id = $1
+            "#,
+        )
+        .bind(raffle_id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))
+    }
+
+    async fn chain_events(&self, blockchain_raffle_id: i64) -> Result<Vec<RaffleChainEvent>, AppError> {
+        let events = sqlx::query_as::<_, RaffleChainEvent>(
+            r#"
+            SELECT event_type, block_number, transaction_hash, status, timestamp
+            FROM blockchain_events
+            WHERE raffle_id = $1
+            ORDER BY block_number, id
+            "#,
+        )
+        .bind(blockchain_raffle_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// Winner wallets from the latest `winner_selected` event, lowercased
+    async fn event_winners(&self, blockchain_raffle_id: i64) -> Result<Vec<String>, AppError> {
+        let winners = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT lower(w)
+            FROM (
+                SELECT data FROM blockchain_events
+                WHERE raffle_id = $1 AND event_type = 'winner_selected'
+                ORDER BY block_number DESC
+                LIMIT 1
+            ) e, jsonb_array_elements_text(e.data->'winners') WITH ORDINALITY AS t(w, n)
+            ORDER BY n
+            "#,
+        )
+        .bind(blockchain_raffle_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(winners)
+    }
+
+    async fn dead_letters(&self, blockchain_raffle_id: i64) -> Result<i64, AppError> {
+        let count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM blockchain_event_dead_letters WHERE blockchain_raffle_id = $1 AND status <> 'resolved'",
+        )
+        .bind(blockchain_raffle_id)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn event_processor_state(&self) -> Result<EventProcessorState, AppError> {
+        let last_processed_block =
+            sqlx::query_scalar::<_, i64>("SELECT last_processed_block FROM event_processor_state WHERE id = 1")
+                .fetch_optional(&self.db_pool)
+                .await?
+                .unwrap_or(0);
+
+        let chain_head = match self.blockchain_service.client().get_block_number().await {
+            Ok(block) => Some(block as i64),
+            Err(e) => {
+                warn!("Failed to read chain head for raffle diagnosis: {}", e);
+                None
+            }
+        };
+
+        Ok(EventProcessorState {
+            last_processed_block,
+            chain_head,
+            lag_blocks: chain_head.map(|head| (head - last_processed_block).max(0)),
+        })
+    }
+
+    async fn remediations(&self, raffle_id: Uuid) -> Result<Vec<RaffleRemediation>, AppError> {
+        let remediations = sqlx::query_as::<_, RaffleRemediation>(
+            r#"
+            SELECT id, raffle_id, action, justification, plan_hash, changes, blockchain_tx_id,
+                   performed_by, created_at
+            FROM raffle_remediations
+            WHERE raffle_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(raffle_id)
+        .bind(REMEDIATION_HISTORY_LIMIT)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(remediations)
+    }
+
+    async fn remediation_transactions(&self, remediations: &[RaffleRemediation]) -> Vec<RemediationTransaction> {
+        let mut transactions = Vec::new();
+        for remediation in remediations {
+            let Some(tx_id) = remediation.blockchain_tx_id else {
+                continue;
+            };
+
+            let status = self.blockchain_service.get_transaction_status(tx_id).await;
+            transactions.push(RemediationTransaction {
+                remediation_id: remediation.id,
+                blockchain_tx_id: tx_id,
+                state: status.as_ref().map(|s| match s.status {
+                    TransactionState::Pending => "pending".to_string(),
+                    TransactionState::Confirmed => "confirmed".to_string(),
+                    _ => "failed".to_string(),
+                }),
+                tx_hash: status.map(|s| format!("{:?}", s.hash)),
+                sent_at: remediation.created_at,
+            });
+        }
+        transactions
+    }
+
+    /// User ids for winner wallets, in order; every wallet must belong to a user
+    async fn users_for_wallets(&self, wallets: &[String]) -> Result<Vec<Uuid>, AppError> {
+        let mut user_ids = Vec::with_capacity(wallets.len());
+        for wallet in wallets {
+            let user_id = ExternalWallet::owner_of(&self.db_pool, wallet)
+                .await?
+                .ok_or_else(|| AppError::Validation(format!("Winner wallet {} does not belong to a user", wallet)))?;
+            user_ids.push(user_id);
+        }
+        Ok(user_ids)
+    }
+
+    async fn randomness_timeout(&self) -> Result<Duration, AppError> {
+        let minutes = SystemSetting::get_by_key(&self.db_pool, "raffle_randomness_timeout_minutes")
+            .await?
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_RANDOMNESS_TIMEOUT_MINUTES);
+        Ok(Duration::minutes(minutes))
+    }
+
+    /// Keep an applied remediation with the raffle and in the audit log
+    async fn record(
+        &self,
+        plan: &RemediationPlan,
+        justification: &str,
+        admin_id: Uuid,
+        ip_address: Option<IpAddr>,
+    ) -> Result<(), AppError> {
+        self.store_remediation(plan, justification, admin_id).await?;
+
+        info!(
+            "Admin {} applied {} to raffle {}: {}",
+            admin_id,
+            plan.action.as_str(),
+            plan.raffle_id,
+            justification
+        );
+
+        if let Err(e) = AuditLog::create(
+            &self.db_pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("raffle".to_string()),
+            Some(plan.raffle_id),
+            Some(json!(plan.changes.iter().map(|c| (c.field.clone(), c.before.clone())).collect::<serde_json::Map<_, _>>())),
+            Some(json!({
+                "event": "raffle_remediation",
+                "action": plan.action.as_str(),
+                "reason": justification,
+                "plan_hash": plan.plan_hash,
+                "blockchain_tx_id": plan.blockchain_tx_id,
+                "values": plan.changes.iter().map(|c| (c.field.clone(), c.after.clone())).collect::<serde_json::Map<_, _>>(),
+            })),
+            ip_address,
+            None,
+        )
+        .await
+        {
+            warn!("Failed to audit {} on raffle {}: {}", plan.action.as_str(), plan.raffle_id, e);
+        }
+
+        Ok(())
+    }
+
+    async fn store_remediation(&self, plan: &RemediationPlan, justification: &str, admin_id: Uuid) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            INSERT INTO raffle_remediations
+                (raffle_id, action, justification, plan_hash, changes, blockchain_tx_id, performed_by)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(plan.raffle_id)
+        .bind(plan.action)
+        .bind(justification)
+        .bind(&plan.plan_hash)
+        .bind(json!(plan.changes))
+        .bind(plan.blockchain_tx_id)
+        .bind(admin_id)
+        .execute(&self.db_pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+fn plan(action: RemediationAction, raffle_id: Uuid, changes: Vec<FieldChange>) -> RemediationPlan {
+    let canonical = json!({
+        "action": action.as_str(),
+        "raffle_id": raffle_id,
+        "changes": changes,
+    });
+
+    RemediationPlan {
+        action,
+        raffle_id,
+        changes,
+        plan_hash: hex::encode(Sha256::digest(canonical.to_string().as_bytes())),
+        applied: false,
+        blockchain_tx_id: None,
+    }
+}
+
+fn require_available(diagnosis: &RaffleDiagnosis, action: RemediationAction) -> Result<(), AppError> {
+    if diagnosis.available_actions.contains(&action) {
+        return Ok(());
+    }
+
+    Err(AppError::Conflict(format!(
+        "{} is not a safe remediation for this raffle right now (symptoms: {:?})",
+        action.as_str(),
+        diagnosis.symptoms
+    )))
+}
+
+fn symptoms(signals: &Signals, now: DateTime<Utc>, randomness_timeout: Duration) -> Vec<StuckSymptom> {
+    let mut symptoms = Vec::new();
+    let awaiting_draw = matches!(signals.status, RaffleStatus::Full | RaffleStatus::Drawing);
+
+    if signals.on_chain && signals.chain.is_none() {
+        symptoms.push(StuckSymptom::ChainUnreadable);
+    }
+
+    if awaiting_draw && signals.on_chain && !signals.chain_drawn {
+        match signals.randomness_requested_at {
+            None => symptoms.push(StuckSymptom::RandomnessNotRequested),
+            Some(requested_at) if now - requested_at > randomness_timeout => {
+                symptoms.push(StuckSymptom::RandomnessUnfulfilled)
+            }
+            Some(_) => {}
+        }
+    }
+
+    if awaiting_draw && signals.chain_drawn {
+        symptoms.push(StuckSymptom::WinnerNotRecorded);
+    }
+    if signals.divergences > 0 {
+        symptoms.push(StuckSymptom::ChainDivergence);
+    }
+    if signals.unconfirmed_events > 0 {
+        symptoms.push(StuckSymptom::UnconfirmedEvents);
+    }
+    if signals.dead_letters > 0 {
+        symptoms.push(StuckSymptom::DeadLetteredEvents);
+    }
+    if signals.processor_lag.is_some_and(|lag| lag > MAX_PROCESSOR_LAG_BLOCKS) {
+        symptoms.push(StuckSymptom::EventProcessorLagging);
+    }
+
+    symptoms
+}
+
+/// Remediations that can't make things worse given what the diagnosis found
+fn available_actions(signals: &Signals, symptoms: &[StuckSymptom]) -> Vec<RemediationAction> {
+    let mut actions = Vec::new();
+
+    let randomness_missing = symptoms
+        .iter()
+        .any(|s| matches!(s, StuckSymptom::RandomnessNotRequested | StuckSymptom::RandomnessUnfulfilled));
+    // Don't pay for a second request while the first may still be answered
+    if randomness_missing && signals.chain.is_some() && !signals.rerequest_in_flight {
+        actions.push(RemediationAction::RerequestRandomness);
+    }
+
+    if symptoms.contains(&StuckSymptom::ChainDivergence) {
+        actions.push(RemediationAction::Resync);
+    }
+
+    if symptoms.contains(&StuckSymptom::WinnerNotRecorded) {
+        actions.push(RemediationAction::ForceComplete);
+    }
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signals(status: RaffleStatus) -> Signals {
+        Signals {
+            status,
+            on_chain: true,
+            chain: Some(ChainSnapshot {
+                boxes_sold: 100,
+                completed: false,
+                winner: None,
+            }),
+            chain_drawn: false,
+            randomness_requested_at: None,
+            divergences: 0,
+            unconfirmed_events: 0,
+            dead_letters: 0,
+            processor_lag: Some(3),
+            rerequest_in_flight: false,
+        }
+    }
+
+    #[test]
+    fn test_unanswered_randomness_offers_rerequest() {
+        let now = Utc::now();
+        let timeout = Duration::minutes(30);
+        let mut s = signals(RaffleStatus::Drawing);
+
+        s.randomness_requested_at = Some(now - Duration::minutes(5));
+        assert!(symptoms(&s, now, timeout).is_empty());
+
+        s.randomness_requested_at = Some(now - Duration::minutes(45));
+        let found = symptoms(&s, now, timeout);
+        assert_eq!(found, vec![StuckSymptom::RandomnessUnfulfilled]);
+        assert_eq!(available_actions(&s, &found), vec![RemediationAction::RerequestRandomness]);
+
+        s.rerequest_in_flight = true;
+        assert!(available_actions(&s, &found).is_empty());
+    }
+
+    #[test]
+    fn test_drawn_on_chain_offers_force_complete() {
+        let mut s = signals(RaffleStatus::Drawing);
+        s.chain_drawn = true;
+        s.divergences = 1;
+
+        let found = symptoms(&s, Utc::now(), Duration::minutes(30));
+        assert_eq!(found, vec![StuckSymptom::WinnerNotRecorded, StuckSymptom::ChainDivergence]);
+        assert_eq!(
+            available_actions(&s, &found),
+            vec![RemediationAction::Resync, RemediationAction::ForceComplete]
+        );
+    }
+
+    #[test]
+    fn test_unreadable_chain_offers_nothing_on_chain() {
+        let mut s = signals(RaffleStatus::Full);
+        s.chain = None;
+        s.dead_letters = 2;
+        s.processor_lag = Some(MAX_PROCESSOR_LAG_BLOCKS + 1);
+
+        let found = symptoms(&s, Utc::now(), Duration::minutes(30));
+        assert!(found.contains(&StuckSymptom::ChainUnreadable));
+        assert!(found.contains(&StuckSymptom::DeadLetteredEvents));
+        assert!(found.contains(&StuckSymptom::EventProcessorLagging));
+        assert!(!available_actions(&s, &found).contains(&RemediationAction::RerequestRandomness));
+    }
+
+    #[test]
+    fn test_plan_hash_tracks_the_diff() {
+        let raffle_id = Uuid::new_v4();
+        let change = |after: &str| FieldChange {
+            field: "status".to_string(),
+            before: json!("drawing"),
+            after: json!(after),
+        };
+
+        let a = plan(RemediationAction::ForceComplete, raffle_id, vec![change("completed")]);
+        let b = plan(RemediationAction::ForceComplete, raffle_id, vec![change("completed")]);
+        assert_eq!(a.plan_hash, b.plan_hash);
+        assert_ne!(a.plan_hash, plan(RemediationAction::Resync, raffle_id, vec![change("completed")]).plan_hash);
+        assert_ne!(a.plan_hash, plan(RemediationAction::ForceComplete, raffle_id, vec![change("open")]).plan_hash);
+    }
+}
@@ -1,28 +1,68 @@
-use crate::models::raffle::{Raffle, BoxPurchase};
+use crate::models::box_reservation::BoxReservation;
+use crate::models::category::{attribute_filter, Category, CategoryAttribute};
+use crate::models::raffle::{Raffle, BoxPurchase, GridSale};
+use crate::models::raffle_layout::{GridLayout, RaffleLayout};
+use crate::models::raffle_listing::{ListingCursor, ListingFilter, ListingSort, RaffleListing};
+use crate::models::raffle_pricing_curve::{PricingCurve, RafflePricingCurve};
+use crate::models::raffle_prize_tier::{validate_prize_tiers, PrizeChoice, RafflePrizeTier};
+use crate::models::raffle_policy::{RafflePolicy, RafflePolicyOverrides, TierRafflePolicy};
+use crate::models::seller_onboarding::{SellerFeature, SellerOnboarding};
+use crate::models::seller_subscription::SellerSubscription;
+use crate::models::system_settings::{SystemSetting, SystemSettings};
+use crate::models::tag::Tag;
 use crate::models::item::Item;
+use crate::models::ledger::accounts;
 use crate::models::user::User;
+use crate::models::winner_display::{self, PublicWinner};
+use crate::services::cache_invalidation::{self, CacheTag, TaggedCache};
 use crate::services::credit_service::{CreditService, CreditRedemptionRequest, CreditIssuanceRequest};
 use crate::services::blockchain_service::BlockchainService;
-use crate::services::notification_service::NotificationService;
-use crate::services::realtime_service::RealtimeService;
+use crate::services::compliance_service::ComplianceService;
+use crate::services::fee_service::{FeeBreakdown, FeeContext, FeeService, FeeType};
+use crate::services::fx_service::{settle_amount, FxService, DEFAULT_BASE_CURRENCY};
+use crate::services::media_service::MediaService;
+use crate::services::notification_service::{plan_fan_out_batches, FanOutRecipient, NotificationService};
+use crate::services::outbox_service::OutboxMessage;
+use crate::services::receipt_service::{self, ReceiptBox};
+use crate::services::realtime_service::{GridBoxChange, RealtimeEvent, RealtimeService};
+use crate::services::referral_service::ReferralService;
+use crate::services::seller_webhook_service::SellerWebhookEvent;
 use crate::error::AppError;
 use chrono::{DateTime, Utc};
-use raffle_platform_shared::{RaffleStatus, CreateRaffleRequest, RaffleResponse, BoxPurchaseResponse, PaginatedResponse, CreditSource, CreditType};
+use raffle_platform_shared::{ImageVariants, RaffleStatus, RaffleDeadlineAction, CreateRaffleRequest, RaffleResponse, BoxPurchaseResponse, CursorPage, PaginatedResponse, CreditSource, CreditType, GridCell, ItemStatus, PrizeType, UserRole};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
-use std::collections::HashMap;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
+const MAX_SEARCH_TAGS: usize = 10;
+/// How long a grid is served from the cache when its price has no time step coming sooner
+const GRID_CACHE_TTL: Duration = Duration::from_secs(30);
+/// How long a search page is served from the cache; raffle writes invalidate it sooner
+const RAFFLE_SEARCH_CACHE_TTL: Duration = Duration::from_secs(60);
+/// Most grid changes replayed to a client that fell behind; further back it refetches the grid
+const MAX_GRID_RESYNC_CHANGES: i64 = 500;
+const DEFAULT_BOX_RESERVATION_TTL_SECONDS: i64 = 120;
+
 /// Raffle management service handles all raffle-related operations
 #[derive(Clone)]
 pub struct RaffleService {
     db_pool: PgPool,
+    read_pool: PgPool,
     credit_service: CreditService,
     blockchain_service: BlockchainService,
     notification_service: NotificationService,
     realtime_service: RealtimeService,
+    fee_service: FeeService,
+    media_service: MediaService,
+    referral_service: ReferralService,
+    fx_service: Option<FxService>,
+    compliance_service: Option<ComplianceService>,
+    cache: TaggedCache,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,17 +75,33 @@ pub struct RaffleSearchParams {
     pub completion_max: Option<f64>,
     pub sort_by: Option<String>, // "created_asc", "created_desc", "price_asc", "price_desc", "completion"
     pub limit: Option<i64>,
-    pub offset: Option<i64>,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
+    /// Raffles carrying any of these tags, on the raffle or its item
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Require every tag instead
+    #[serde(default)]
+    pub match_all_tags: bool,
+    /// Category slug; raffles for items in it or any category below it match
+    #[serde(default)]
+    pub category: Option<String>,
+    /// `key:value` filters on item attributes; every one must hold
+    #[serde(default)]
+    pub attributes: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BoxPurchaseRequest {
     pub raffle_id: Uuid,
     pub box_numbers: Vec<i32>,
+    /// Boxes picked by grid position; resolved through the raffle's layout
+    #[serde(default)]
+    pub cells: Vec<GridCell>,
     pub use_credits: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct RaffleStatistics {
     pub total_raffles: i64,
     pub active_raffles: i64,
@@ -72,21 +128,144 @@ pub struct RaffleAnalytics {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GridState {
     pub raffle_id: Uuid,
+    /// Grid sequence the snapshot is at; `grid_diff` messages after it apply on top
+    pub sequence: i64,
     pub grid_rows: i32,
     pub grid_cols: i32,
     pub purchased_boxes: HashMap<i32, BoxOwner>,
     pub available_boxes: Vec<i32>,
     pub total_boxes: i32,
     pub boxes_sold: i32,
+    pub layout_name: Option<String>,
+    pub disabled_cells: Vec<GridCell>,
+    /// Box number for each cell in row-major order; None marks a disabled cell
+    pub layout_mask: Vec<Vec<Option<i32>>>,
+    pub prize_tiers: Vec<RafflePrizeTier>,
+    pub pricing: BoxPricing,
+    /// Most boxes one user may hold; None when the raffle is uncapped
+    pub max_boxes_per_user: Option<i32>,
+    /// Boxes held by the requesting user, when signed in
+    pub user_boxes: Option<i32>,
+    /// Boxes the requesting user may still buy, when signed in and the raffle is capped
+    pub remaining_allowance: Option<i32>,
+}
+
+/// One purchase's changes to a grid
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct GridDiff {
+    pub sequence: i64,
+    pub changes: Vec<GridBoxChange>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Changes a client missed since the grid sequence it last applied
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct GridChanges {
+    pub raffle_id: Uuid,
+    /// The raffle's grid sequence now
+    pub sequence: i64,
+    /// The missed changes can't be replayed (boxes were removed, or too many to send); refetch the grid
+    pub full_resync_required: bool,
+    /// Oldest first; empty when a full resync is required
+    pub diffs: Vec<GridDiff>,
+}
+
+/// Price of the next box sold and when it changes
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BoxPricing {
+    pub current_price: Decimal,
+    pub next_price: Option<Decimal>,
+    /// Boxes sold at which `next_price` starts, for fill percentage curves
+    pub next_price_at_boxes_sold: Option<i32>,
+    /// Time at which `next_price` starts, for time curves
+    pub next_price_at: Option<DateTime<Utc>>,
+}
+
+/// Win odds and value of a box at the current fill level
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RaffleOdds {
+    pub raffle_id: Uuid,
+    pub total_boxes: i32,
+    pub boxes_sold: i32,
+    pub boxes_remaining: i32,
+    pub total_winners: i32,
+    pub box_price: Decimal,
+    pub prize_value: Decimal,
+    /// Chance that any single box wins; winners are drawn once every box is sold
+    pub win_probability_per_box: f64,
+    pub expected_value_per_box: Decimal,
+    /// Credits paid per credit of expected prize value; None when the prize has no value
+    pub cost_per_unit_value: Option<Decimal>,
+    /// Boxes held by the requesting user, when signed in
+    pub user_boxes: Option<i32>,
+    /// Chance that at least one of the user's boxes wins
+    pub user_win_probability: Option<f64>,
+    /// The user's chance if the draw were made from the boxes sold so far, for raffles that
+    /// complete at their deadline whether or not they fill
+    pub user_win_probability_if_drawn_now: Option<f64>,
+    /// Public verification record of the draw, once winners are drawn
+    pub fairness_report_url: Option<String>,
+    pub calculated_at: DateTime<Utc>,
+}
+
+/// Raffles moved by one scheduler pass
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RaffleSchedulerReport {
+    pub opened: Vec<Uuid>,
+    pub cancelled: Vec<Uuid>,
+    pub completed: Vec<Uuid>,
+}
+
+/// Admin-configured bounds on raffle grid dimensions
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct GridLimits {
+    pub max_rows: i32,
+    pub max_cols: i32,
+}
+
+/// The platform raffle policy and how each subscription tier departs from it
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RafflePolicyOverview {
+    pub platform: RafflePolicy,
+    pub tiers: Vec<TierRafflePolicy>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BoxOwner {
     pub user_id: Uuid,
+    /// Public name following the owner's winner display rule
     pub username: String,
     pub purchased_at: DateTime<Utc>,
 }
 
+/// Recent prize awarded on the platform, for the public winner gallery
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WinnerGalleryEntry {
+    pub raffle_id: Uuid,
+    /// Raffle item, or the tier's own item for item prizes
+    pub item_id: Uuid,
+    pub item_name: String,
+    pub item_image: Option<ImageVariants>,
+    pub place: i32,
+    pub prize_type: PrizeType,
+    pub credit_amount: Option<Decimal>,
+    pub display_name: String,
+    pub awarded_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct GalleryRow {
+    raffle_id: Uuid,
+    place: i32,
+    prize_type: PrizeType,
+    credit_amount: Option<Decimal>,
+    winner_user_id: Uuid,
+    awarded_at: Option<DateTime<Utc>>,
+    item_id: Uuid,
+    item_name: String,
+    images: Vec<String>,
+}
+
 impl RaffleService {
     /// Create a new raffle service
     pub fn new(
@@ -95,16 +274,51 @@ impl RaffleService {
         blockchain_service: BlockchainService,
         notification_service: NotificationService,
         realtime_service: RealtimeService,
+        fee_service: FeeService,
+        media_service: MediaService,
     ) -> Self {
+        let referral_service = ReferralService::new(db_pool.clone(), credit_service.clone());
+
         Self {
+            read_pool: db_pool.clone(),
             db_pool,
             credit_service,
             blockchain_service,
             notification_service,
             realtime_service,
+            fee_service,
+            media_service,
+            referral_service,
+            fx_service: None,
+            compliance_service: None,
+            cache: TaggedCache::default(),
         }
     }
 
+    /// Run search and statistics queries on a read replica
+    pub fn with_read_pool(mut self, read_pool: PgPool) -> Self {
+        self.read_pool = read_pool;
+        self
+    }
+
+    /// Cache grids and search pages and invalidate them on raffle writes
+    pub fn with_cache(mut self, cache: TaggedCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Settle purchases of raffles priced in other currencies into base currency credits
+    pub fn with_fx_service(mut self, fx_service: FxService) -> Self {
+        self.fx_service = Some(fx_service);
+        self
+    }
+
+    /// Ask winners of valuable item prizes to verify their identity before the prize ships
+    pub fn with_compliance_service(mut self, compliance_service: ComplianceService) -> Self {
+        self.compliance_service = Some(compliance_service);
+        self
+    }
+
     /// Create a new raffle
     pub async fn create_raffle(
         &self,
@@ -119,6 +333,13 @@ impl RaffleService {
             return Err(AppError::Forbidden("User is not a seller".to_string()));
         }
 
+        // Raffles unlock once every onboarding step is complete; staff aren't onboarded
+        if !matches!(seller.role, UserRole::Admin | UserRole::Operator) {
+            SellerOnboarding::load(&self.db_pool, seller_id)
+                .await?
+                .require(SellerFeature::RunRaffles)?;
+        }
+
         // Validate item exists and belongs to seller
         let item = Item::find_by_id(&self.db_pool, request.item_id).await?
             .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
@@ -127,29 +348,68 @@ impl RaffleService {
             return Err(AppError::Forbidden("Item does not belong to seller".to_string()));
         }
 
+        if item.status == ItemStatus::PendingReview {
+            return Err(AppError::Validation("Item is awaiting review and can't be raffled yet".to_string()));
+        }
         if !item.is_available_for_raffle() {
             return Err(AppError::Validation("Item is not available for raffle".to_string()));
         }
 
         // Validate raffle parameters
         self.validate_raffle_parameters(&request)?;
+        self.check_raffle_policy(seller_id, &request).await?;
+        self.check_prize_items(seller_id, &request).await?;
 
         // Check if there's already an active raffle for this item
         let existing_raffles = Raffle::find_by_item(&self.db_pool, request.item_id).await?;
         let has_active_raffle = existing_raffles.iter().any(|r| {
-            matches!(r.status, RaffleStatus::Open | RaffleStatus::Full | RaffleStatus::Drawing)
+            matches!(r.status, RaffleStatus::Scheduled | RaffleStatus::Open | RaffleStatus::Full | RaffleStatus::Drawing)
         });
 
         if has_active_raffle {
             return Err(AppError::Validation("Item already has an active raffle".to_string()));
         }
 
-        // Calculate transaction fee
-        let transaction_fee = self.calculate_transaction_fee(&seller, &request).await?;
+        // Calculate transaction fee from the fee schedule in effect now
+        let fees = self.calculate_raffle_fees(&seller, &request).await?;
+        let transaction_fee = fees.fees.iter()
+            .find(|f| f.fee_type == FeeType::Platform)
+            .map(|f| f.percentage)
+            .unwrap_or(Decimal::ZERO);
 
         // Create the raffle
+        let layout = request.layout.clone().filter(|l| !l.disabled_cells.is_empty());
+        let pricing_curve = request.pricing_curve.clone();
         let raffle = Raffle::create(&self.db_pool, request, Some(transaction_fee)).await?;
 
+        if let Some(layout) = &layout {
+            RaffleLayout::create(&self.db_pool, raffle.id, layout).await?;
+        }
+
+        if let Some(pricing_curve) = &pricing_curve {
+            RafflePricingCurve::create(&self.db_pool, raffle.id, pricing_curve).await?;
+        }
+
+        // Snapshot the schedule used so completion accounting and statements use the same terms
+        if let Some(schedule_id) = fees.schedule_for(FeeType::Platform) {
+            sqlx::query!(
+                "UPDATE raffles SET fee_schedule_id = $1 WHERE id = $2",
+                schedule_id,
+                raffle.id
+            )
+            .execute(&self.db_pool)
+            .await?;
+        }
+        if let Some(promotion_id) = fees.promotion_for(FeeType::Platform) {
+            sqlx::query!(
+                "UPDATE raffles SET fee_promotion_id = $1 WHERE id = $2",
+                promotion_id,
+                raffle.id
+            )
+            .execute(&self.db_pool)
+            .await?;
+        }
+
         // Create blockchain raffle if needed
         let blockchain_raffle_id = self.create_blockchain_raffle(&raffle).await?;
 
@@ -158,6 +418,15 @@ impl RaffleService {
             self.update_raffle_blockchain_id(raffle.id, blockchain_id).await?;
         }
 
+        self.cache
+            .invalidate(&[
+                CacheTag::Raffle(raffle.id),
+                CacheTag::RaffleListings,
+                CacheTag::Item(request.item_id),
+                CacheTag::ItemListings,
+            ])
+            .await;
+
         // Log raffle creation
         self.log_raffle_activity(
             raffle.id,
@@ -166,24 +435,125 @@ impl RaffleService {
             &format!("Raffle created for item '{}'", item.name),
         ).await?;
 
-        // Broadcast raffle creation event
-        let _ = self.realtime_service.broadcast_event(
-            crate::services::realtime_service::RealtimeEvent::RaffleCreated {
-                raffle_id: raffle.id,
-                item_id: request.item_id,
-                seller_id,
-                total_boxes: raffle.total_boxes,
-                box_price: raffle.box_price,
-                created_at: raffle.created_at,
-            }
-        ).await;
+        // Raffle::create commits on its own, so creation events are queued right after it
+        let mut messages = vec![OutboxMessage::Realtime(RealtimeEvent::RaffleCreated {
+            raffle_id: raffle.id,
+            item_id: request.item_id,
+            seller_id,
+            total_boxes: raffle.total_boxes,
+            box_price: raffle.box_price,
+            created_at: raffle.created_at,
+        })];
+        if raffle.status == RaffleStatus::Open {
+            messages.extend(raffle_event_messages(raffle.id, Some(seller_id), SellerWebhookEvent::RaffleOpened, serde_json::json!({
+                "raffle_id": raffle.id,
+                "item_id": raffle.item_id,
+                "total_boxes": raffle.total_boxes,
+                "box_price": raffle.box_price,
+                "ends_at": raffle.ends_at
+            })));
+        }
+        let mut tx = self.db_pool.begin().await?;
+        OutboxMessage::enqueue(&mut tx, &messages).await?;
+        tx.commit().await?;
 
         info!(
             "Created raffle {} for item {} by seller {}",
             raffle.id, request.item_id, seller_id
         );
 
-        Ok(raffle.to_response(Some(item)))
+        Ok(self.raffle_response(&raffle, Some(item)))
+    }
+
+    /// Preview the fees a seller would pay for a raffle before creating it
+    pub async fn preview_raffle_fees(
+        &self,
+        seller_id: Uuid,
+        request: &CreateRaffleRequest,
+    ) -> Result<FeeBreakdown, AppError> {
+        let seller = User::find_by_id(&self.db_pool, seller_id).await?
+            .ok_or_else(|| AppError::NotFound("Seller not found".to_string()))?;
+
+        self.validate_raffle_parameters(request)?;
+        self.check_raffle_policy(seller_id, request).await?;
+        self.calculate_raffle_fees(&seller, request).await
+    }
+
+    /// Current platform bounds on raffle grid dimensions
+    pub async fn get_grid_limits(&self) -> Result<GridLimits, AppError> {
+        let policy = RafflePolicy::platform(&self.db_pool).await?;
+        Ok(GridLimits {
+            max_rows: policy.max_grid_rows,
+            max_cols: policy.max_grid_cols,
+        })
+    }
+
+    /// Change the platform bounds on raffle grid dimensions; existing raffles are unaffected
+    pub async fn update_grid_limits(&self, limits: GridLimits) -> Result<GridLimits, AppError> {
+        let policy = RafflePolicy {
+            max_grid_rows: limits.max_rows,
+            max_grid_cols: limits.max_cols,
+            ..RafflePolicy::platform(&self.db_pool).await?
+        };
+        RafflePolicy::set_platform(&self.db_pool, policy).await?;
+
+        info!("Raffle grid limits set to {} rows by {} columns", limits.max_rows, limits.max_cols);
+
+        Ok(limits)
+    }
+
+    /// The bounds a seller's raffles must keep to, from their subscription tier
+    pub async fn get_raffle_policy(&self, seller_id: Uuid) -> Result<RafflePolicy, AppError> {
+        let subscription_id = self.seller_subscription_id(seller_id).await?;
+        RafflePolicy::for_tier(&self.db_pool, subscription_id).await
+    }
+
+    /// The platform policy and each active subscription tier's overrides
+    pub async fn get_policy_overview(&self) -> Result<RafflePolicyOverview, AppError> {
+        let platform = RafflePolicy::platform(&self.db_pool).await?;
+
+        let mut tiers = Vec::new();
+        for subscription in SellerSubscription::find_active(&self.db_pool).await? {
+            let overrides = RafflePolicyOverrides::find(&self.db_pool, subscription.id).await?.unwrap_or_default();
+            tiers.push(TierRafflePolicy {
+                subscription_id: subscription.id,
+                subscription_name: subscription.name,
+                effective: platform.with_overrides(&overrides),
+                overrides,
+            });
+        }
+
+        Ok(RafflePolicyOverview { platform, tiers })
+    }
+
+    /// Replace the platform policy; raffles already created are unaffected
+    pub async fn update_platform_policy(&self, policy: RafflePolicy, admin_id: Uuid) -> Result<RafflePolicy, AppError> {
+        let policy = RafflePolicy::set_platform(&self.db_pool, policy).await?;
+        info!("Admin {} updated the platform raffle policy: {:?}", admin_id, policy);
+        Ok(policy)
+    }
+
+    /// Replace a subscription tier's overrides, returning the policy its sellers now get
+    pub async fn set_tier_policy(
+        &self,
+        subscription_id: Uuid,
+        overrides: RafflePolicyOverrides,
+        admin_id: Uuid,
+    ) -> Result<RafflePolicy, AppError> {
+        SellerSubscription::find_by_id(&self.db_pool, subscription_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Subscription tier not found".to_string()))?;
+
+        let policy = RafflePolicyOverrides::set(&self.db_pool, subscription_id, overrides).await?;
+        info!("Admin {} set raffle policy overrides for tier {}", admin_id, subscription_id);
+        Ok(policy)
+    }
+
+    /// Put a subscription tier back on the platform policy
+    pub async fn remove_tier_policy(&self, subscription_id: Uuid, admin_id: Uuid) -> Result<(), AppError> {
+        RafflePolicyOverrides::remove(&self.db_pool, subscription_id).await?;
+        info!("Admin {} removed raffle policy overrides for tier {}", admin_id, subscription_id);
+        Ok(())
     }
 
     /// Get raffle by ID
@@ -194,40 +564,96 @@ impl RaffleService {
         // Increment view count
         self.increment_raffle_views(raffle_id).await?;
 
-        Ok(raffle.to_response(Some(item)))
+        Ok(self.raffle_response(&raffle, Some(item)))
     }
 
-    /// Search raffles with filters
+    /// Search raffles with filters, one cursor page at a time
+    ///
+    /// Reads the `raffle_listings` read model, so a page is one index scan with the item
+    /// already attached rather than a raffle query plus an item lookup per row.
     pub async fn search_raffles(
         &self,
         params: RaffleSearchParams,
-    ) -> Result<PaginatedResponse<RaffleResponse>, AppError> {
-        let limit = params.limit.unwrap_or(20).min(100);
-        let offset = params.offset.unwrap_or(0);
-
-        // For simplicity, we'll use the existing find_active method
-        // In a real implementation, you'd build dynamic queries based on params
-        let raffles = if params.status.is_none() || params.status == Some(RaffleStatus::Open) {
-            Raffle::find_active(&self.db_pool, limit, offset).await?
+    ) -> Result<CursorPage<RaffleResponse>, AppError> {
+        let key = cache_invalidation::query_key("raffle_search", &params);
+
+        self.cache
+            .get_or_load(&key, &[CacheTag::RaffleListings], RAFFLE_SEARCH_CACHE_TTL, || {
+                self.load_search_page(params)
+            })
+            .await
+    }
+
+    async fn load_search_page(&self, params: RaffleSearchParams) -> Result<CursorPage<RaffleResponse>, AppError> {
+        let sort = ListingSort::parse(params.sort_by.as_deref())?;
+        let cursor = params
+            .cursor
+            .as_deref()
+            .filter(|c| !c.is_empty())
+            .map(|c| ListingCursor::decode(c, sort))
+            .transpose()?;
+        let limit = params.limit.unwrap_or(20).min(100).max(1);
+        if params.tags.len() > MAX_SEARCH_TAGS {
+            return Err(AppError::Validation(format!("Filter on at most {} tags", MAX_SEARCH_TAGS)));
+        }
+        let tags = Tag::canonicalize(&self.read_pool, &params.tags).await?;
+        let category_ids = match params.category.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+            Some(slug) => {
+                let category = Category::find_by_slug(&self.read_pool, slug)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound(format!("Category '{}' not found", slug)))?;
+                Category::subtree_ids(&self.read_pool, category.id).await?
+            }
+            None => Vec::new(),
+        };
+        let attributes = if params.attributes.is_empty() {
+            None
         } else {
-            // Would implement other status filters
-            Vec::new()
+            let keys: Vec<String> = params
+                .attributes
+                .iter()
+                .filter_map(|pair| pair.split_once(':').map(|(key, _)| key.trim().to_string()))
+                .collect();
+            let definitions = CategoryAttribute::find_by_keys(&self.read_pool, &keys).await?;
+            attribute_filter(&definitions, &params.attributes)?
         };
 
-        let total = Raffle::count_active(&self.db_pool).await?;
+        let filter = ListingFilter {
+            status: params.status,
+            item_id: params.item_id,
+            min_price: params.min_price,
+            max_price: params.max_price,
+            completion_min: completion_bound(params.completion_min)?,
+            completion_max: completion_bound(params.completion_max)?,
+            tags,
+            match_all_tags: params.match_all_tags,
+            category_ids,
+            attributes,
+        };
 
-        let mut raffle_responses = Vec::new();
-        for raffle in raffles {
-            let item = Item::find_by_id(&self.db_pool, raffle.item_id).await?;
-            raffle_responses.push(raffle.to_response(item));
-        }
+        let mut listings = RaffleListing::search(&self.read_pool, &filter, sort, cursor.as_ref(), limit + 1).await?;
 
-        Ok(PaginatedResponse {
-            data: raffle_responses,
-            total,
-            limit,
-            offset,
-            has_more: offset + limit < total,
+        let has_more = listings.len() as i64 > limit;
+        listings.truncate(limit as usize);
+        let next_cursor = if has_more {
+            listings.last().map(|listing| listing.cursor(sort).encode())
+        } else {
+            None
+        };
+
+        let data = listings
+            .iter()
+            .map(|listing| {
+                let mut response = listing.to_response();
+                self.media_service.apply_to_raffle(&mut response);
+                response
+            })
+            .collect();
+
+        Ok(CursorPage {
+            data,
+            next_cursor,
+            has_more,
         })
     }
 
@@ -249,99 +675,262 @@ impl RaffleService {
             return Err(AppError::Validation("Raffle is not open for purchases".to_string()));
         }
 
+        // The scheduler closes raffles once a minute; don't sell in the gap
+        if raffle.ends_at.map_or(false, |ends_at| ends_at <= Utc::now()) {
+            return Err(AppError::Validation("Raffle has ended".to_string()));
+        }
+
+        let stored_layout = RaffleLayout::find_by_raffle(&self.db_pool, raffle.id).await?;
+        let layout = GridLayout::for_raffle(raffle.grid_rows, raffle.grid_cols, stored_layout.as_ref())?;
+
+        // Resolve cell picks to box numbers; disabled cells hold no box
+        let mut box_numbers = request.box_numbers.clone();
+        for cell in &request.cells {
+            let box_number = layout.box_at(*cell).ok_or_else(|| {
+                AppError::Validation(format!("Cell ({}, {}) is not part of this raffle's grid", cell.row, cell.col))
+            })?;
+            box_numbers.push(box_number);
+        }
+
+        if box_numbers.is_empty() {
+            return Err(AppError::Validation("No boxes selected".to_string()));
+        }
+
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = box_numbers.iter().find(|n| !seen.insert(**n)) {
+            return Err(AppError::Validation(format!("Box {} is selected more than once", duplicate)));
+        }
+
         // Validate box numbers
-        for &box_number in &request.box_numbers {
-            if box_number < 1 || box_number > raffle.total_boxes {
+        for &box_number in &box_numbers {
+            if box_number > raffle.total_boxes || layout.cell_of(box_number).is_none() {
                 return Err(AppError::Validation(format!("Invalid box number: {}", box_number)));
             }
-
-            // Check if box is already purchased
-            if BoxPurchase::is_box_purchased(&self.db_pool, request.raffle_id, box_number).await? {
-                return Err(AppError::Validation(format!("Box {} is already purchased", box_number)));
-            }
         }
 
         // Check if raffle would be overfilled
-        if raffle.boxes_sold + request.box_numbers.len() as i32 > raffle.total_boxes {
+        if raffle.boxes_sold + box_numbers.len() as i32 > raffle.total_boxes {
             return Err(AppError::Validation("Not enough boxes available".to_string()));
         }
 
-        let total_cost = raffle.box_price * Decimal::from(request.box_numbers.len());
-
-        // Process payment
-        let mut purchases = Vec::new();
-        let mut transaction_id = None;
+        // Enforce the per-user cap here; the contract would only revert after gas is spent
+        if let Some(max_boxes) = raffle.max_boxes_per_user {
+            let held = BoxPurchase::count_user_boxes(&self.db_pool, user_id, raffle.id).await?;
+            check_box_allowance(max_boxes, held, box_numbers.len())?;
+        }
 
-        if request.use_credits {
-            // Use credits for payment
-            let redemption_request = CreditRedemptionRequest {
-                user_id,
-                amount: total_cost,
-                item_id: Some(raffle.item_id),
-                credit_type: None,
-                description: format!("Box purchase for raffle {}", request.raffle_id),
-            };
+        // Boxes are priced in the order they are listed, so a purchase can span price tiers
+        let pricing = self.pricing_curve(&raffle).await?;
+        let prices = pricing.quote(
+            raffle.boxes_sold,
+            raffle.total_boxes,
+            box_numbers.len(),
+            minutes_open(&raffle, Utc::now()),
+        );
+        // Credits are in the base currency, so each price settles at the current rate
+        let prices = self.settle_prices(&raffle.currency, prices).await?;
+        let total_cost: Decimal = prices.iter().sum();
 
-            let redemption_result = self.credit_service.redeem_credits(redemption_request).await?;
-            transaction_id = Some(Uuid::new_v4()); // Generate transaction ID
-        } else {
+        if !request.use_credits {
             // Would implement other payment methods (direct payment, etc.)
             return Err(AppError::Validation("Only credit payments are currently supported".to_string()));
         }
 
-        // Create box purchases
-        for box_number in request.box_numbers {
-            let purchase = BoxPurchase::create(
-                &self.db_pool,
-                request.raffle_id,
-                user_id,
-                box_number,
-                raffle.box_price,
-                transaction_id,
-            ).await?;
+        let seller_id = self.seller_of(&raffle).await;
+        let buyer_name = winner_display::display_names(&self.db_pool, &[user_id])
+            .await?
+            .remove(&user_id)
+            .unwrap_or_else(|| winner_display::ANONYMOUS_NAME.to_string());
+        let item_title = Item::find_by_id(&self.db_pool, raffle.item_id).await?
+            .map(|item| item.name)
+            .unwrap_or_default();
+        let tax_rate = SystemSettings::get_sales_tax_percentage(&self.db_pool).await?;
+
+        // Hold the boxes before taking payment, so a concurrent buyer of the same box gets a
+        // conflict up front instead of paying for a box that can't be theirs
+        let reservation_id = self.reserve_boxes(raffle.id, user_id, &box_numbers).await?;
+
+        // Use credits for payment
+        let redemption_request = CreditRedemptionRequest {
+            user_id,
+            amount: total_cost,
+            item_id: Some(raffle.item_id),
+            credit_type: None,
+            description: format!("Box purchase for raffle {}", request.raffle_id),
+        };
+
+        let committed: Result<_, AppError> = async {
+            let transaction_id = Uuid::new_v4(); // Generate transaction ID
+
+            // The payment, the purchase, the raffle's new state and the events announcing them
+            // commit together, so a failure anywhere leaves the user's credits untouched
+            let mut tx = self.db_pool.begin().await?;
+
+            // The check above can be raced by the user's own concurrent purchase; count again
+            // under a lock held until commit, so the second one sees the first one's boxes
+            if let Some(max_boxes) = raffle.max_boxes_per_user {
+                lock_user_raffle_boxes(&mut tx, raffle.id, user_id).await?;
+                let held = BoxPurchase::count_user_boxes(&mut *tx, user_id, raffle.id).await?;
+                check_box_allowance(max_boxes, held, box_numbers.len())?;
+            }
+
+            let redemption = self
+                .credit_service
+                .redeem_credits_in(&mut tx, &redemption_request, accounts::RAFFLE_REVENUE)
+                .await?;
+
+            // The hold is only lost if it expired and another buyer reserved a box since
+            let held = BoxReservation::confirm(&mut *tx, reservation_id).await?;
+            if held.len() != box_numbers.len() {
+                return Err(AppError::Conflict(
+                    "The hold on your boxes expired before the purchase completed; please try again".to_string(),
+                ));
+            }
+
+            // Create box purchases at the price each box actually sold for
+            let mut purchases = Vec::new();
+            for (&box_number, &price) in box_numbers.iter().zip(&prices) {
+                let purchase = BoxPurchase::create(
+                    &mut *tx,
+                    request.raffle_id,
+                    user_id,
+                    box_number,
+                    price,
+                    Some(transaction_id),
+                ).await?;
+
+                purchases.push(purchase);
+            }
 
-            purchases.push(purchase);
+            // Update raffle boxes sold count; the purchase is one change to the grid
+            let (new_boxes_sold, grid_sequence) =
+                Raffle::add_boxes_sold(&mut *tx, request.raffle_id, purchases.len() as i32)
+                    .await?
+                    .ok_or_else(|| AppError::Conflict("Not enough boxes available".to_string()))?;
+            let purchase_ids: Vec<Uuid> = purchases.iter().map(|p| p.id).collect();
+            BoxPurchase::set_grid_sequence(&mut *tx, &purchase_ids, grid_sequence).await?;
+
+            let mut messages: Vec<OutboxMessage> = purchases
+                .iter()
+                .map(|purchase| OutboxMessage::Realtime(RealtimeEvent::BoxPurchased {
+                    raffle_id: request.raffle_id,
+                    user_id,
+                    box_number: purchase.box_number,
+                    boxes_remaining: raffle.total_boxes - new_boxes_sold,
+                    completion_percentage: (new_boxes_sold as f64 / raffle.total_boxes as f64) * 100.0,
+                    purchased_at: Utc::now(),
+                }))
+                .collect();
+
+            messages.push(OutboxMessage::Realtime(RealtimeEvent::GridChanged {
+                raffle_id: request.raffle_id,
+                sequence: grid_sequence,
+                changes: purchases
+                    .iter()
+                    .map(|purchase| GridBoxChange {
+                        box_number: purchase.box_number,
+                        user_id,
+                        username: buyer_name.clone(),
+                    })
+                    .collect(),
+                boxes_sold: new_boxes_sold,
+                boxes_remaining: raffle.total_boxes - new_boxes_sold,
+                changed_at: Utc::now(),
+            }));
+
+            // Check if raffle is now full
+            let is_full = new_boxes_sold >= raffle.total_boxes;
+            if is_full {
+                Raffle::update_status(&mut *tx, request.raffle_id, RaffleStatus::Full).await?;
+
+                messages.push(OutboxMessage::Realtime(RealtimeEvent::RaffleFull {
+                    raffle_id: request.raffle_id,
+                    item_id: raffle.item_id,
+                    total_boxes: raffle.total_boxes,
+                    completed_at: Utc::now(),
+                }));
+                messages.extend(seller_message(seller_id, SellerWebhookEvent::ItemSoldOut, serde_json::json!({
+                    "raffle_id": raffle.id,
+                    "item_id": raffle.item_id,
+                    "total_boxes": raffle.total_boxes,
+                    "box_price": raffle.box_price,
+                    "currency": raffle.currency
+                })));
+            }
+
+            for purchase in &purchases {
+                messages.push(OutboxMessage::BoxPurchaseNotification {
+                    user_id,
+                    raffle_id: request.raffle_id,
+                    item_title: item_title.clone(),
+                    box_number: purchase.box_number as u32,
+                    total_boxes: raffle.total_boxes as u32,
+                });
+            }
+
+            // The receipt is issued with the checkout and emailed once it commits
+            let receipt_boxes: Vec<ReceiptBox> = purchases
+                .iter()
+                .map(|purchase| ReceiptBox {
+                    purchase_id: purchase.id,
+                    box_number: purchase.box_number,
+                    price: purchase.purchase_price_in_credits,
+                })
+                .collect();
+            if let Some(receipt) =
+                receipt_service::create_receipt(&mut tx, user_id, request.raffle_id, transaction_id, &receipt_boxes, tax_rate).await?
+            {
+                messages.push(receipt_service::receipt_email(&receipt, item_title.clone()));
+            }
+
+            messages.extend(seller_message(seller_id, SellerWebhookEvent::BoxPurchased, serde_json::json!({
+                "raffle_id": raffle.id,
+                "item_id": raffle.item_id,
+                "box_numbers": purchases.iter().map(|p| p.box_number).collect::<Vec<_>>(),
+                "amount": total_cost,
+                "boxes_sold": new_boxes_sold,
+                "total_boxes": raffle.total_boxes
+            })));
+
+            OutboxMessage::enqueue(&mut tx, &messages).await?;
+            tx.commit().await?;
+
+            Ok((purchases, is_full, redemption))
         }
+        .await;
 
-        // Update raffle boxes sold count
-        let new_boxes_sold = raffle.boxes_sold + purchases.len() as i32;
-        Raffle::update_boxes_sold(&self.db_pool, request.raffle_id, new_boxes_sold).await?;
+        let (purchases, is_full, redemption) = match committed {
+            Ok(committed) => committed,
+            Err(e) => {
+                self.abandon_purchase(reservation_id).await;
+                return Err(e);
+            }
+        };
 
-        // Broadcast box purchase events
-        for purchase in &purchases {
-            let _ = self.realtime_service.broadcast_box_purchase(
-                request.raffle_id,
-                user_id,
-                purchase.box_number,
-                raffle.total_boxes - new_boxes_sold,
-                (new_boxes_sold as f64 / raffle.total_boxes as f64) * 100.0,
-            ).await;
-        }
-
-        // Check if raffle is now full
-        if new_boxes_sold >= raffle.total_boxes {
-            Raffle::update_status(&self.db_pool, request.raffle_id, RaffleStatus::Full).await?;
-            
-            // Broadcast raffle full event
-            let _ = self.realtime_service.broadcast_raffle_full(
-                request.raffle_id,
-                raffle.item_id,
-                raffle.total_boxes,
-            ).await;
-            
-            // Trigger winner selection process
-            self.initiate_winner_selection(request.raffle_id).await?;
+        self.credit_service.credits_redeemed(&redemption_request, &redemption).await;
+
+        self.raffle_changed(request.raffle_id).await;
+
+        // Odds are derived from the committed state and superseded by the next purchase, so
+        // they are broadcast directly rather than through the outbox
+        match self.get_raffle_odds(request.raffle_id, None).await {
+            Ok(odds) => {
+                let _ = self.realtime_service.broadcast_event(RealtimeEvent::OddsUpdated {
+                    raffle_id: odds.raffle_id,
+                    boxes_sold: odds.boxes_sold,
+                    boxes_remaining: odds.boxes_remaining,
+                    win_probability_per_box: odds.win_probability_per_box,
+                    expected_value_per_box: odds.expected_value_per_box,
+                    cost_per_unit_value: odds.cost_per_unit_value,
+                    updated_at: odds.calculated_at,
+                }).await;
+            }
+            Err(e) => warn!("Failed to recalculate odds for raffle {}: {}", request.raffle_id, e),
         }
 
-        // Send notifications
-        for purchase in &purchases {
-            self.notification_service.send_box_purchase_notification(
-                user_id,
-                request.raffle_id,
-                "Item Name".to_string(), // Would get from item
-                purchase.box_number as u32,
-                raffle.total_boxes as u32,
-            ).await?;
+        // Trigger winner selection process
+        if is_full {
+            self.initiate_winner_selection(request.raffle_id).await?;
         }
 
         // Log purchases
@@ -352,6 +941,11 @@ impl RaffleService {
             &format!("Purchased {} boxes", purchases.len()),
         ).await?;
 
+        // A referred user's first purchase pays out their referral
+        if let Err(e) = self.referral_service.reward_first_purchase(user_id).await {
+            error!("Failed to reward referral for user {}: {}", user_id, e);
+        }
+
         info!(
             "User {} purchased {} boxes in raffle {}",
             user_id, purchases.len(), request.raffle_id
@@ -360,8 +954,73 @@ impl RaffleService {
         Ok(purchases.into_iter().map(|p| p.to_response()).collect())
     }
 
-    /// Get raffle grid state
-    pub async fn get_grid_state(&self, raffle_id: Uuid) -> Result<GridState, AppError> {
+    /// Hold boxes for a buyer, failing with a conflict naming every box that was taken
+    async fn reserve_boxes(&self, raffle_id: Uuid, user_id: Uuid, box_numbers: &[i32]) -> Result<Uuid, AppError> {
+        let ttl_seconds = SystemSetting::get_by_key(&self.db_pool, "box_reservation_ttl_seconds")
+            .await
+            .ok()
+            .flatten()
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_BOX_RESERVATION_TTL_SECONDS);
+
+        let reservation_id = Uuid::new_v4();
+        let conflicts = BoxReservation::reserve(
+            &self.db_pool,
+            reservation_id,
+            raffle_id,
+            user_id,
+            box_numbers,
+            Utc::now() + chrono::Duration::seconds(ttl_seconds),
+        )
+        .await?;
+
+        if !conflicts.is_empty() {
+            debug!("Box reservation in raffle {} refused: {:?}", raffle_id, conflicts);
+            return Err(conflicts.to_error());
+        }
+
+        Ok(reservation_id)
+    }
+
+    /// Free the boxes of a purchase that failed; its payment rolled back with it
+    async fn abandon_purchase(&self, reservation_id: Uuid) {
+        // Left in place, the hold still expires on its own
+        if let Err(e) = BoxReservation::release(&self.db_pool, reservation_id).await {
+            warn!("Failed to release box reservation {}: {}", reservation_id, e);
+        }
+    }
+
+    /// Get raffle grid state, including the user's remaining box allowance when given
+    ///
+    /// The shared part of the grid is cached until the raffle changes or its price steps; the
+    /// user's own counts are filled in from it on every call.
+    pub async fn get_grid_state(&self, raffle_id: Uuid, user_id: Option<Uuid>) -> Result<GridState, AppError> {
+        let mut grid = self
+            .cache
+            .get_or_load_with_ttl(
+                &format!("raffle_grid:{}", raffle_id),
+                &[CacheTag::Raffle(raffle_id)],
+                |grid: &GridState| grid_cache_ttl(&grid.pricing, Utc::now()),
+                || self.load_grid_state(raffle_id),
+            )
+            .await?;
+
+        grid.user_boxes = user_id.map(|user_id| {
+            grid.purchased_boxes.values().filter(|owner| owner.user_id == user_id).count() as i32
+        });
+        grid.remaining_allowance = grid
+            .user_boxes
+            .and_then(|held| remaining_allowance(grid.max_boxes_per_user, held));
+
+        Ok(grid)
+    }
+
+    async fn load_grid_state(&self, raffle_id: Uuid) -> Result<GridState, AppError> {
+        // Read before the boxes: a sale landing in between is then also sent as a later diff,
+        // and applying a diff twice is harmless
+        let sequences = Raffle::grid_sequences(&self.db_pool, raffle_id).await?
+            .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
         let raffle = Raffle::find_by_id(&self.db_pool, raffle_id).await?
             .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
 
@@ -370,14 +1029,17 @@ impl RaffleService {
         let mut purchased_boxes = HashMap::new();
         let mut available_boxes = Vec::new();
 
-        // Get user details for purchased boxes
-        for purchase in purchases {
-            let user = User::find_by_id(&self.db_pool, purchase.user_id).await?
-                .ok_or_else(|| AppError::Internal("Purchase user not found".to_string()))?;
+        // Owners are shown by their winner display rule, never by email
+        let owner_ids: Vec<Uuid> = purchases.iter().map(|p| p.user_id).collect::<HashSet<_>>().into_iter().collect();
+        let names = winner_display::display_names(&self.db_pool, &owner_ids).await?;
 
+        for purchase in purchases {
             purchased_boxes.insert(purchase.box_number, BoxOwner {
                 user_id: purchase.user_id,
-                username: user.email, // Using email as username for now
+                username: names
+                    .get(&purchase.user_id)
+                    .cloned()
+                    .unwrap_or_else(|| winner_display::ANONYMOUS_NAME.to_string()),
                 purchased_at: purchase.created_at,
             });
         }
@@ -389,17 +1051,316 @@ impl RaffleService {
             }
         }
 
+        let stored_layout = RaffleLayout::find_by_raffle(&self.db_pool, raffle_id).await?;
+        let layout = GridLayout::for_raffle(raffle.grid_rows, raffle.grid_cols, stored_layout.as_ref())?;
+        let (layout_name, disabled_cells) = stored_layout
+            .map(|l| (l.name, l.disabled_cells))
+            .unwrap_or_default();
+        let mut prize_tiers = RafflePrizeTier::find_by_raffle(&self.db_pool, raffle_id).await?;
+        winner_display::redact_winner_ids(&mut prize_tiers);
+        let pricing = self.box_pricing(&raffle, Utc::now()).await?;
+
         Ok(GridState {
             raffle_id,
+            sequence: sequences.current,
             grid_rows: raffle.grid_rows,
             grid_cols: raffle.grid_cols,
             purchased_boxes,
             available_boxes,
             total_boxes: raffle.total_boxes,
             boxes_sold: raffle.boxes_sold,
+            layout_name,
+            disabled_cells,
+            layout_mask: layout.mask(),
+            prize_tiers,
+            pricing,
+            max_boxes_per_user: raffle.max_boxes_per_user,
+            user_boxes: None,
+            remaining_allowance: None,
+        })
+    }
+
+    /// Grid changes after the sequence a client last applied, for catching up after a gap
+    pub async fn get_grid_changes(&self, raffle_id: Uuid, since: i64) -> Result<GridChanges, AppError> {
+        let sequences = Raffle::grid_sequences(&self.db_pool, raffle_id).await?
+            .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
+
+        let mut changes = GridChanges {
+            raffle_id,
+            sequence: sequences.current,
+            full_resync_required: false,
+            diffs: Vec::new(),
+        };
+
+        if needs_full_resync(since, sequences.current, sequences.reset) {
+            changes.full_resync_required = true;
+            return Ok(changes);
+        }
+        if since == sequences.current {
+            return Ok(changes);
+        }
+
+        let sales = BoxPurchase::grid_sales_since(&self.db_pool, raffle_id, since, sequences.current).await?;
+        let buyer_ids: Vec<Uuid> = sales.iter().map(|s| s.user_id).collect::<HashSet<_>>().into_iter().collect();
+        let names = winner_display::display_names(&self.db_pool, &buyer_ids).await?;
+        changes.diffs = group_grid_diffs(sales, &names);
+
+        Ok(changes)
+    }
+
+    /// Get win odds and expected value for a raffle, including the user's own odds when given
+    pub async fn get_raffle_odds(&self, raffle_id: Uuid, user_id: Option<Uuid>) -> Result<RaffleOdds, AppError> {
+        let raffle = Raffle::find_by_id(&self.db_pool, raffle_id).await?
+            .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
+
+        let item = Item::find_by_id(&self.db_pool, raffle.item_id).await?
+            .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
+
+        let user_boxes = match user_id {
+            Some(user_id) => Some(
+                BoxPurchase::find_user_purchases_for_raffle(&self.db_pool, user_id, raffle_id)
+                    .await?
+                    .len() as i32,
+            ),
+            None => None,
+        };
+
+        let expected_value_per_box = expected_value_per_box(item.retail_price, raffle.total_winners, raffle.total_boxes);
+        let box_price = self.box_pricing(&raffle, Utc::now()).await?.current_price;
+
+        Ok(RaffleOdds {
+            raffle_id,
+            total_boxes: raffle.total_boxes,
+            boxes_sold: raffle.boxes_sold,
+            boxes_remaining: raffle.total_boxes - raffle.boxes_sold,
+            total_winners: raffle.total_winners,
+            box_price,
+            prize_value: item.retail_price,
+            win_probability_per_box: win_probability(1, raffle.total_winners, raffle.total_boxes),
+            expected_value_per_box,
+            cost_per_unit_value: cost_per_unit_value(box_price, expected_value_per_box),
+            user_boxes,
+            user_win_probability: user_boxes.map(|held| win_probability(held, raffle.total_winners, raffle.total_boxes)),
+            user_win_probability_if_drawn_now: win_probability_if_drawn_now(&raffle, user_boxes),
+            fairness_report_url: fairness_report_url(&raffle),
+            calculated_at: Utc::now(),
         })
     }
 
+    /// Get the prize tiers of a raffle, first place first
+    pub async fn get_prize_tiers(&self, raffle_id: Uuid) -> Result<Vec<RafflePrizeTier>, AppError> {
+        RafflePrizeTier::find_by_raffle(&self.db_pool, raffle_id).await
+    }
+
+    /// Get the winners of a raffle in place order, each shown by their own display rule
+    pub async fn get_public_winners(&self, raffle_id: Uuid) -> Result<Vec<PublicWinner>, AppError> {
+        let tiers = RafflePrizeTier::find_by_raffle(&self.db_pool, raffle_id).await?;
+        self.public_winners(&tiers).await
+    }
+
+    /// Most recent prizes awarded across all raffles, newest first
+    pub async fn get_winner_gallery(&self, limit: i64) -> Result<Vec<WinnerGalleryEntry>, AppError> {
+        let rows = sqlx::query_as::<_, GalleryRow>(
+            r#"
+            SELECT t.raffle_id, t.place, t.prize_type, t.credit_amount,
+                   t.winner_user_id, t.awarded_at, i.id AS item_id, i.name AS item_name, i.images
+            FROM raffle_prize_tiers t
+            JOIN raffles r ON r.id = t.raffle_id
+            JOIN items i ON i.id = COALESCE(t.item_id, r.item_id)
+            WHERE t.winner_user_id IS NOT NULL AND r.status = 'completed'
+            ORDER BY t.awarded_at DESC NULLS LAST, t.raffle_id, t.place
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let winner_ids: Vec<Uuid> = rows.iter().map(|r| r.winner_user_id).collect::<HashSet<_>>().into_iter().collect();
+        let names = winner_display::display_names(&self.db_pool, &winner_ids).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| WinnerGalleryEntry {
+                raffle_id: row.raffle_id,
+                item_id: row.item_id,
+                item_name: row.item_name,
+                item_image: row.images.first().map(|image| self.media_service.variants(image)),
+                place: row.place,
+                prize_type: row.prize_type,
+                credit_amount: row.credit_amount,
+                display_name: names
+                    .get(&row.winner_user_id)
+                    .cloned()
+                    .unwrap_or_else(|| winner_display::ANONYMOUS_NAME.to_string()),
+                awarded_at: row.awarded_at,
+            })
+            .collect())
+    }
+
+    /// Record the drawn winners, in draw order, and pay out credit prizes
+    ///
+    /// The nth winner takes the nth place; item prizes are fulfilled outside the platform.
+    pub async fn record_winners(
+        &self,
+        raffle_id: Uuid,
+        winner_user_ids: Vec<Uuid>,
+        blockchain_tx_hash: Option<String>,
+    ) -> Result<Vec<RafflePrizeTier>, AppError> {
+        let raffle = Raffle::find_by_id(&self.db_pool, raffle_id).await?
+            .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
+
+        if !matches!(raffle.status, RaffleStatus::Full | RaffleStatus::Drawing) {
+            return Err(AppError::Conflict("Raffle is not awaiting a draw".to_string()));
+        }
+
+        if winner_user_ids.len() as i32 != raffle.total_winners {
+            return Err(AppError::Validation(format!(
+                "Expected {} winners but got {}",
+                raffle.total_winners,
+                winner_user_ids.len()
+            )));
+        }
+
+        let seller_id = self.seller_of(&raffle).await;
+        let item = Item::find_by_id(&self.db_pool, raffle.item_id).await?
+            .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
+
+        let mut tx = self.db_pool.begin().await?;
+        Raffle::set_winners(&mut tx, raffle_id, winner_user_ids.clone(), blockchain_tx_hash).await?;
+        let tiers = RafflePrizeTier::find_by_raffle(&mut *tx, raffle_id).await?;
+
+        let winners = self.public_winners(&tiers).await?;
+        let mut messages = vec![OutboxMessage::Realtime(RealtimeEvent::WinnerSelected {
+            raffle_id,
+            item_id: raffle.item_id,
+            winner_user_ids: winner_user_ids.clone(),
+            winners,
+            completed_at: Utc::now(),
+        })];
+        messages.extend(raffle_event_messages(raffle.id, seller_id, SellerWebhookEvent::RaffleCompleted, serde_json::json!({
+            "raffle_id": raffle.id,
+            "item_id": raffle.item_id,
+            "boxes_sold": raffle.boxes_sold,
+            "total_boxes": raffle.total_boxes,
+            "prizes": tiers.iter().map(|t| serde_json::json!({
+                "place": t.place,
+                "prize_type": t.prize_type,
+                "item_id": t.item_id,
+                "credit_amount": t.credit_amount,
+                "credit_alternative": t.credit_alternative,
+                "winner_user_id": t.winner_user_id
+            })).collect::<Vec<_>>()
+        })));
+        OutboxMessage::enqueue(&mut tx, &messages).await?;
+        self.enqueue_result_fan_out(&mut tx, raffle_id, &item.name, &winner_user_ids).await?;
+        tx.commit().await?;
+        self.raffle_changed(raffle_id).await;
+
+        for tier in &tiers {
+            if let (PrizeType::Credits, Some(winner_id), Some(amount)) =
+                (tier.prize_type, tier.winner_user_id, tier.credit_amount)
+            {
+                self.credit_service.issue_credits(CreditIssuanceRequest {
+                    user_id: winner_id,
+                    amount,
+                    source: CreditSource::RafflePrize,
+                    credit_type: CreditType::General,
+                    redeemable_on_item_id: None,
+                    expires_at: None,
+                    description: format!("Place {} prize in raffle {}", tier.place, raffle_id),
+                }).await?;
+            }
+        }
+
+        for tier in &tiers {
+            if let Some(winner_id) = tier.winner_user_id {
+                self.log_raffle_activity(
+                    raffle_id,
+                    winner_id,
+                    "prize_awarded",
+                    &format!("Won place {} ({})", tier.place, tier.prize_type),
+                ).await?;
+            }
+        }
+
+        if let Some(compliance_service) = &self.compliance_service {
+            if let Err(e) = compliance_service.prompt_winners(raffle_id).await {
+                warn!("Failed to ask winners of raffle {} to verify their identity: {}", raffle_id, e);
+            }
+        }
+
+        info!("Recorded {} winners for raffle {}", winner_user_ids.len(), raffle_id);
+
+        Ok(tiers)
+    }
+
+    /// Places a user won in a raffle, including any choice still to be made
+    pub async fn get_prize_choices(&self, raffle_id: Uuid, user_id: Uuid) -> Result<Vec<RafflePrizeTier>, AppError> {
+        RafflePrizeTier::find_for_winner(&self.db_pool, raffle_id, user_id).await
+    }
+
+    /// Winner picks between an item prize and the credit value the seller offered for it
+    ///
+    /// The choice is final. Taking credits pays them out immediately and the seller keeps
+    /// the item; the credit value is deducted from the seller's proceeds.
+    pub async fn choose_prize(
+        &self,
+        raffle_id: Uuid,
+        user_id: Uuid,
+        place: i32,
+        choice: PrizeChoice,
+    ) -> Result<RafflePrizeTier, AppError> {
+        let tier = RafflePrizeTier::find_for_winner(&self.db_pool, raffle_id, user_id).await?
+            .into_iter()
+            .find(|t| t.place == place)
+            .ok_or_else(|| AppError::NotFound("You did not win this place".to_string()))?;
+
+        let Some(alternative) = tier.credit_alternative else {
+            return Err(AppError::Validation("This prize has no credit alternative".to_string()));
+        };
+        if tier.winner_choice.is_some() {
+            return Err(AppError::Conflict("A choice has already been made for this prize".to_string()));
+        }
+
+        // Claim the choice first so two requests can't both pay out
+        let chosen = RafflePrizeTier::record_choice(&self.db_pool, tier.id, choice).await?
+            .ok_or_else(|| AppError::Conflict("A choice has already been made for this prize".to_string()))?;
+
+        if choice == PrizeChoice::Credits {
+            let issued = self.credit_service.issue_credits(CreditIssuanceRequest {
+                user_id,
+                amount: alternative,
+                source: CreditSource::RafflePrize,
+                credit_type: CreditType::General,
+                redeemable_on_item_id: None,
+                expires_at: None,
+                description: format!("Credit value of place {} prize in raffle {}", place, raffle_id),
+            }).await;
+
+            if let Err(e) = issued {
+                RafflePrizeTier::clear_choice(&self.db_pool, tier.id).await?;
+                return Err(e);
+            }
+        }
+
+        self.raffle_changed(raffle_id).await;
+
+        self.log_raffle_activity(
+            raffle_id,
+            user_id,
+            "prize_choice",
+            &match choice {
+                PrizeChoice::Item => format!("Took the item for place {}", place),
+                PrizeChoice::Credits => format!("Took {} credits instead of the item for place {}", alternative, place),
+            },
+        ).await?;
+
+        info!("Winner {} chose {:?} for place {} in raffle {}", user_id, choice, place, raffle_id);
+
+        Ok(chosen)
+    }
+
     /// Get user's box purchases for a raffle
     pub async fn get_user_purchases(
         &self,
@@ -450,6 +1411,49 @@ impl RaffleService {
         })
     }
 
+    /// Result notifications for every participant, in paced batches with winners first,
+    /// written to the outbox in the transaction that records the winners
+    async fn enqueue_result_fan_out(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        raffle_id: Uuid,
+        item_title: &str,
+        winner_user_ids: &[Uuid],
+    ) -> Result<(), AppError> {
+        let participants = sqlx::query!(
+            r#"
+            SELECT user_id, SUM(purchase_price_in_credits) as "total_spent!"
+            FROM box_purchases
+            WHERE raffle_id = $1
+            GROUP BY user_id
+            ORDER BY MIN(created_at)
+            "#,
+            raffle_id
+        )
+        .fetch_all(&mut **tx)
+        .await?;
+
+        // Non-winners receive their spend back as credits
+        let recipients = participants
+            .into_iter()
+            .map(|p| {
+                let won = winner_user_ids.contains(&p.user_id);
+                FanOutRecipient {
+                    user_id: p.user_id,
+                    won,
+                    credits_received: (!won).then_some(p.total_spent),
+                }
+            })
+            .collect();
+
+        for batch in plan_fan_out_batches(raffle_id, item_title, recipients, Utc::now()) {
+            let publish_at = batch.deliver_at;
+            OutboxMessage::enqueue_at(tx, &[OutboxMessage::RaffleResultBatch(batch)], publish_at).await?;
+        }
+
+        Ok(())
+    }
+
     /// Cancel a raffle (admin/seller only)
     pub async fn cancel_raffle(
         &self,
@@ -475,35 +1479,7 @@ impl RaffleService {
             return Err(AppError::Validation("Cannot cancel completed raffle".to_string()));
         }
 
-        // Refund all purchases
-        let purchases = BoxPurchase::find_by_raffle(&self.db_pool, raffle_id).await?;
-        for purchase in purchases {
-            // Issue refund credits
-            let refund_request = CreditIssuanceRequest {
-                user_id: purchase.user_id,
-                amount: purchase.purchase_price_in_credits,
-                source: CreditSource::Refund,
-                credit_type: CreditType::General,
-                redeemable_on_item_id: None,
-                expires_at: None,
-                description: format!("Refund for cancelled raffle {}", raffle_id),
-            };
-
-            self.credit_service.issue_credits(refund_request).await?;
-        }
-
-        // Update raffle status
-        Raffle::update_status(&self.db_pool, raffle_id, RaffleStatus::Cancelled).await?;
-
-        // Broadcast raffle cancellation event
-        let _ = self.realtime_service.broadcast_event(
-            crate::services::realtime_service::RealtimeEvent::RaffleCancelled {
-                raffle_id,
-                item_id: raffle.item_id,
-                reason: reason.clone(),
-                cancelled_at: chrono::Utc::now(),
-            }
-        ).await;
+        self.cancel_and_refund(&raffle, &reason).await?;
 
         // Log cancellation
         self.log_raffle_activity(
@@ -518,40 +1494,173 @@ impl RaffleService {
         Ok(())
     }
 
+    /// Stop selling and draw winners from the boxes sold so far
+    pub async fn force_complete_raffle(&self, raffle_id: Uuid) -> Result<(), AppError> {
+        let raffle = Raffle::find_by_id(&self.db_pool, raffle_id).await?
+            .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
+
+        if raffle.status != RaffleStatus::Open {
+            return Err(AppError::Validation("Only open raffles can be force completed".to_string()));
+        }
+
+        if raffle.boxes_sold < raffle.total_winners {
+            return Err(AppError::Validation(format!(
+                "Raffle needs at least {} boxes sold to draw winners but has {}",
+                raffle.total_winners, raffle.boxes_sold
+            )));
+        }
+
+        if !Raffle::transition_status(&self.db_pool, raffle_id, RaffleStatus::Open, RaffleStatus::Drawing).await? {
+            return Err(AppError::Conflict("Raffle changed status while completing".to_string()));
+        }
+        self.raffle_changed(raffle_id).await;
+
+        info!(
+            "Force completed raffle {} with {} of {} boxes sold",
+            raffle_id, raffle.boxes_sold, raffle.total_boxes
+        );
+
+        Ok(())
+    }
+
+    /// Replace a raffle's tags, which platform webhook endpoints filter on
+    pub async fn set_raffle_tags(&self, raffle_id: Uuid, tags: Vec<String>, admin_id: Uuid) -> Result<Vec<String>, AppError> {
+        let tags = Tag::resolve(&self.db_pool, &tags, true).await?;
+        let tags = Raffle::set_tags(&self.db_pool, raffle_id, &tags).await?;
+        self.raffle_changed(raffle_id).await;
+
+        info!("Admin {} set tags of raffle {} to {:?}", admin_id, raffle_id, tags);
+
+        Ok(tags)
+    }
+
+    /// Start the raffle scheduler
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+
+            loop {
+                interval.tick().await;
+
+                match service.run_scheduler().await {
+                    Ok(report) => {
+                        if !report.opened.is_empty() || !report.cancelled.is_empty() || !report.completed.is_empty() {
+                            info!(
+                                "Raffle scheduler opened {}, cancelled {}, completed {}",
+                                report.opened.len(), report.cancelled.len(), report.completed.len()
+                            );
+                        }
+                    }
+                    Err(e) => error!("Raffle scheduler run failed: {}", e),
+                }
+            }
+        });
+
+        info!("Raffle service background tasks started");
+    }
+
+    /// Open scheduled raffles whose start time has come and settle raffles past their end time
+    pub async fn run_scheduler(&self) -> Result<RaffleSchedulerReport, AppError> {
+        let now = Utc::now();
+        let mut report = RaffleSchedulerReport::default();
+
+        for raffle in Raffle::find_due_to_open(&self.db_pool, now).await? {
+            let seller_id = self.seller_of(&raffle).await;
+
+            let mut tx = self.db_pool.begin().await?;
+            if !Raffle::transition_status(&mut *tx, raffle.id, RaffleStatus::Scheduled, RaffleStatus::Open).await? {
+                continue;
+            }
+
+            let mut messages = vec![OutboxMessage::Realtime(RealtimeEvent::RaffleOpened {
+                raffle_id: raffle.id,
+                item_id: raffle.item_id,
+                ends_at: raffle.ends_at,
+                opened_at: now,
+            })];
+            messages.extend(raffle_event_messages(raffle.id, seller_id, SellerWebhookEvent::RaffleOpened, serde_json::json!({
+                "raffle_id": raffle.id,
+                "item_id": raffle.item_id,
+                "total_boxes": raffle.total_boxes,
+                "box_price": raffle.box_price,
+                "ends_at": raffle.ends_at
+            })));
+            OutboxMessage::enqueue(&mut tx, &messages).await?;
+            tx.commit().await?;
+            self.raffle_changed(raffle.id).await;
+
+            info!("Opened scheduled raffle {}", raffle.id);
+            report.opened.push(raffle.id);
+        }
+
+        for raffle in Raffle::find_past_deadline(&self.db_pool, now).await? {
+            let outcome = deadline_outcome(
+                raffle.status,
+                raffle.deadline_action,
+                raffle.boxes_sold,
+                raffle.total_winners,
+            );
+
+            let result = match outcome {
+                RaffleDeadlineAction::Complete => self.force_complete_raffle(raffle.id).await,
+                RaffleDeadlineAction::Cancel => self.cancel_and_refund(&raffle, "Raffle ended before it filled").await,
+            };
+
+            // One bad raffle shouldn't stop the rest; it is retried next pass
+            match result {
+                Ok(()) => match outcome {
+                    RaffleDeadlineAction::Complete => report.completed.push(raffle.id),
+                    RaffleDeadlineAction::Cancel => report.cancelled.push(raffle.id),
+                },
+                Err(e) => error!("Failed to close raffle {} past its deadline: {}", raffle.id, e),
+            }
+        }
+
+        Ok(report)
+    }
+
     /// Get raffle statistics
     pub async fn get_raffle_statistics(&self) -> Result<RaffleStatistics, AppError> {
         let total_raffles = sqlx::query_scalar!("SELECT COUNT(*) FROM raffles")
-            .fetch_one(&self.db_pool)
+            .fetch_one(&self.read_pool)
             .await?
             .unwrap_or(0);
 
-        let active_raffles = Raffle::count_active(&self.db_pool).await?;
+        let active_raffles = Raffle::count_active(&self.read_pool).await?;
 
         let completed_raffles = sqlx::query_scalar!(
             "SELECT COUNT(*) FROM raffles WHERE status = 'completed'"
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(0);
 
         let cancelled_raffles = sqlx::query_scalar!(
             "SELECT COUNT(*) FROM raffles WHERE status = 'cancelled'"
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(0);
 
+        // Summed per box, since pricing curves sell boxes of one raffle at different prices
         let total_revenue = sqlx::query_scalar!(
-            "SELECT COALESCE(SUM(boxes_sold * box_price), 0) FROM raffles WHERE status = 'completed'"
+            r#"
+            SELECT COALESCE(SUM(bp.purchase_price_in_credits), 0)
+            FROM box_purchases bp
+            JOIN raffles r ON r.id = bp.raffle_id
+            WHERE r.status = 'completed'
+            "#
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(Decimal::ZERO);
 
         let total_boxes_sold = sqlx::query_scalar!(
             "SELECT COALESCE(SUM(boxes_sold), 0) FROM raffles"
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&self.read_pool)
         .await?
         .unwrap_or(0);
 
@@ -575,6 +1684,68 @@ impl RaffleService {
 
     // Private helper methods
 
+    /// Refund every box, mark the raffle cancelled and tell subscribers
+    async fn cancel_and_refund(&self, raffle: &Raffle, reason: &str) -> Result<(), AppError> {
+        // Refund all purchases
+        let purchases = BoxPurchase::find_by_raffle(&self.db_pool, raffle.id).await?;
+        for purchase in purchases {
+            // Issue refund credits
+            let refund_request = CreditIssuanceRequest {
+                user_id: purchase.user_id,
+                amount: purchase.purchase_price_in_credits,
+                source: CreditSource::Refund,
+                credit_type: CreditType::General,
+                redeemable_on_item_id: None,
+                expires_at: None,
+                description: format!("Refund for cancelled raffle {}", raffle.id),
+            };
+
+            self.credit_service.issue_credits(refund_request).await?;
+        }
+
+        let seller_id = self.seller_of(raffle).await;
+
+        // Update raffle status and announce the cancellation together
+        let mut tx = self.db_pool.begin().await?;
+        Raffle::update_status(&mut *tx, raffle.id, RaffleStatus::Cancelled).await?;
+
+        let mut messages = vec![OutboxMessage::Realtime(RealtimeEvent::RaffleCancelled {
+            raffle_id: raffle.id,
+            item_id: raffle.item_id,
+            reason: reason.to_string(),
+            cancelled_at: Utc::now(),
+        })];
+        messages.extend(raffle_event_messages(raffle.id, seller_id, SellerWebhookEvent::RaffleCancelled, serde_json::json!({
+            "raffle_id": raffle.id,
+            "item_id": raffle.item_id,
+            "boxes_sold": raffle.boxes_sold,
+            "total_boxes": raffle.total_boxes,
+            "reason": reason
+        })));
+        OutboxMessage::enqueue(&mut tx, &messages).await?;
+        tx.commit().await?;
+        self.raffle_changed(raffle.id).await;
+
+        Ok(())
+    }
+
+    /// Box prices in the raffle's currency converted to base currency credits
+    async fn settle_prices(&self, currency: &str, prices: Vec<Decimal>) -> Result<Vec<Decimal>, AppError> {
+        let rate = match &self.fx_service {
+            Some(fx_service) => fx_service.settlement_rate(currency).await?,
+            None if currency == DEFAULT_BASE_CURRENCY => return Ok(prices),
+            None => return Err(AppError::Validation(format!("No exchange rate available for {}", currency))),
+        };
+
+        Ok(prices.into_iter().map(|price| settle_amount(price, rate)).collect())
+    }
+
+    fn raffle_response(&self, raffle: &Raffle, item: Option<Item>) -> RaffleResponse {
+        let mut response = raffle.to_response(item);
+        self.media_service.apply_to_raffle(&mut response);
+        response
+    }
+
     fn validate_raffle_parameters(&self, request: &CreateRaffleRequest) -> Result<(), AppError> {
         if request.total_boxes <= 0 {
             return Err(AppError::Validation("Total boxes must be positive".to_string()));
@@ -592,21 +1763,127 @@ impl RaffleService {
             return Err(AppError::Validation("Total winners cannot exceed total boxes".to_string()));
         }
 
-        if request.grid_rows <= 0 || request.grid_cols <= 0 {
-            return Err(AppError::Validation("Grid dimensions must be positive".to_string()));
+        if !request.prize_tiers.is_empty() {
+            validate_prize_tiers(&request.prize_tiers, request.total_winners)?;
+        }
+
+        if let Some(max_boxes) = request.max_boxes_per_user {
+            if max_boxes <= 0 || max_boxes > request.total_boxes {
+                return Err(AppError::Validation("Boxes per user must be between 1 and total boxes".to_string()));
+            }
+        }
+
+        PricingCurve::new(request.box_price, request.pricing_curve.as_ref())?;
+
+        validate_schedule(request.starts_at, request.ends_at, Utc::now())?;
+
+        let layout = GridLayout::new(request.grid_rows, request.grid_cols, request.layout.as_ref())?;
+        if layout.purchasable_boxes() != request.total_boxes {
+            return Err(AppError::Validation(format!(
+                "Grid has {} purchasable boxes but total boxes is {}",
+                layout.purchasable_boxes(),
+                request.total_boxes
+            )));
         }
 
-        if request.grid_rows * request.grid_cols < request.total_boxes {
-            return Err(AppError::Validation("Grid is too small for the number of boxes".to_string()));
+        Ok(())
+    }
+
+    async fn public_winners(&self, tiers: &[RafflePrizeTier]) -> Result<Vec<PublicWinner>, AppError> {
+        let winner_ids: Vec<Uuid> = tiers.iter().filter_map(|t| t.winner_user_id).collect();
+        let names = winner_display::display_names(&self.db_pool, &winner_ids).await?;
+        Ok(PublicWinner::from_tiers(tiers, &names))
+    }
+
+    async fn pricing_curve(&self, raffle: &Raffle) -> Result<PricingCurve, AppError> {
+        let stored = RafflePricingCurve::find_by_raffle(&self.db_pool, raffle.id).await?;
+        PricingCurve::for_raffle(raffle.box_price, stored.as_ref())
+    }
+
+    async fn box_pricing(&self, raffle: &Raffle, now: DateTime<Utc>) -> Result<BoxPricing, AppError> {
+        let curve = self.pricing_curve(raffle).await?;
+        let elapsed = minutes_open(raffle, now);
+        let next = curve.next_step(raffle.boxes_sold, raffle.total_boxes, elapsed);
+        let opened_at = raffle.started_at.unwrap_or(raffle.created_at);
+
+        Ok(BoxPricing {
+            current_price: curve.price_at(raffle.boxes_sold, raffle.total_boxes, elapsed),
+            next_price: next.as_ref().map(|step| step.box_price),
+            next_price_at_boxes_sold: next.as_ref().and_then(|step| step.at_boxes_sold),
+            next_price_at: next
+                .as_ref()
+                .and_then(|step| step.after_minutes)
+                .map(|minutes| opened_at + chrono::Duration::minutes(minutes)),
+        })
+    }
+
+    /// Every policy violation is reported at once, field by field
+    async fn check_raffle_policy(&self, seller_id: Uuid, request: &CreateRaffleRequest) -> Result<(), AppError> {
+        let policy = self.get_raffle_policy(seller_id).await?;
+        let violations = policy.check(request);
+
+        if !violations.is_empty() {
+            return Err(AppError::InvalidFields(violations));
         }
 
         Ok(())
     }
 
-    async fn calculate_transaction_fee(&self, _seller: &User, _request: &CreateRaffleRequest) -> Result<Decimal, AppError> {
-        // Calculate transaction fee based on seller's subscription tier
-        // For now, return a fixed percentage
-        Ok(Decimal::from(5)) // 5% transaction fee
+    async fn seller_subscription_id(&self, seller_id: Uuid) -> Result<Option<Uuid>, AppError> {
+        let subscription_id = sqlx::query_scalar!(
+            "SELECT current_subscription_id FROM sellers WHERE user_id = $1",
+            seller_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .flatten();
+
+        Ok(subscription_id)
+    }
+
+    /// Item prizes other than the raffle item must also be the seller's and available
+    async fn check_prize_items(&self, seller_id: Uuid, request: &CreateRaffleRequest) -> Result<(), AppError> {
+        let prize_item_ids: HashSet<Uuid> = request.prize_tiers.iter()
+            .filter_map(|t| t.item_id)
+            .filter(|id| *id != request.item_id)
+            .collect();
+
+        for item_id in prize_item_ids {
+            let item = Item::find_by_id(&self.db_pool, item_id).await?
+                .ok_or_else(|| AppError::NotFound(format!("Prize item {} not found", item_id)))?;
+
+            if item.seller_id != Some(seller_id) {
+                return Err(AppError::Forbidden(format!("Prize item {} does not belong to seller", item_id)));
+            }
+
+            if !item.is_available_for_raffle() {
+                return Err(AppError::Validation(format!("Prize item {} is not available", item_id)));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn calculate_raffle_fees(&self, seller: &User, request: &CreateRaffleRequest) -> Result<FeeBreakdown, AppError> {
+        let category = sqlx::query_scalar!(
+            "SELECT category FROM items WHERE id = $1",
+            request.item_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .flatten();
+
+        let subscription_tier_id = self.seller_subscription_id(seller.id).await?;
+
+        // A sold-out raffle's takings; time curves are estimated at their opening price
+        let curve = PricingCurve::new(request.box_price, request.pricing_curve.as_ref())?;
+        let gross_amount: Decimal = curve
+            .quote(0, request.total_boxes, request.total_boxes as usize, 0)
+            .iter()
+            .sum();
+        let context = FeeContext { category, subscription_tier_id };
+
+        self.fee_service.calculate_fees(gross_amount, &context, Utc::now()).await
     }
 
     async fn create_blockchain_raffle(&self, raffle: &Raffle) -> Result<Option<u64>, AppError> {
@@ -643,6 +1920,7 @@ impl RaffleService {
     async fn initiate_winner_selection(&self, raffle_id: Uuid) -> Result<(), AppError> {
         // Update status to drawing
         Raffle::update_status(&self.db_pool, raffle_id, RaffleStatus::Drawing).await?;
+        self.raffle_changed(raffle_id).await;
 
         // This would trigger the blockchain winner selection process
         // For now, we'll just log it
@@ -651,6 +1929,22 @@ impl RaffleService {
         Ok(())
     }
 
+    /// Drop the raffle's cached grid and every cached search page
+    async fn raffle_changed(&self, raffle_id: Uuid) {
+        self.cache.invalidate(&[CacheTag::Raffle(raffle_id), CacheTag::RaffleListings]).await;
+    }
+
+    /// The seller of the raffle's item, whose webhooks receive its events
+    async fn seller_of(&self, raffle: &Raffle) -> Option<Uuid> {
+        match Item::find_by_id(&self.db_pool, raffle.item_id).await {
+            Ok(item) => item.and_then(|item| item.seller_id),
+            Err(e) => {
+                warn!("Failed to look up seller of raffle {}: {}", raffle.id, e);
+                None
+            }
+        }
+    }
+
     async fn log_raffle_activity(
         &self,
         raffle_id: Uuid,
@@ -682,4 +1976,394 @@ impl RaffleService {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// A completion percentage filter as stored in the read model
+fn completion_bound(value: Option<f64>) -> Result<Option<Decimal>, AppError> {
+    value
+        .map(|v| {
+            if !(0.0..=100.0).contains(&v) {
+                return Err(AppError::Validation("Completion filters must be between 0 and 100".to_string()));
+            }
+            Decimal::try_from(v).map_err(|_| AppError::Validation("Invalid completion filter".to_string()))
+        })
+        .transpose()
+}
+
+/// Chance that at least one of `held` boxes is among `winners` drawn from `total` boxes
+///
+/// 1 - C(total - held, winners) / C(total, winners), computed as a running product.
+/// Boxes a user holding `held` may still buy; None when the raffle is uncapped
+fn remaining_allowance(max_boxes_per_user: Option<i32>, held: i32) -> Option<i32> {
+    max_boxes_per_user.map(|max_boxes| (max_boxes - held).max(0))
+}
+
+/// Reject a purchase of `requested` boxes that would take the user past the raffle's cap
+fn check_box_allowance(max_boxes: i32, held: i32, requested: usize) -> Result<(), AppError> {
+    let allowance = remaining_allowance(Some(max_boxes), held).unwrap_or(0);
+    if requested as i32 > allowance {
+        return Err(AppError::Validation(format!(
+            "Raffle allows {} boxes per user; you hold {} and can buy {} more",
+            max_boxes, held, allowance
+        )));
+    }
+
+    Ok(())
+}
+
+/// Serialize one user's purchases in one raffle until the transaction ends
+async fn lock_user_raffle_boxes(
+    tx: &mut Transaction<'_, Postgres>,
+    raffle_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1), hashtext($2))")
+        .bind(raffle_id.to_string())
+        .bind(user_id.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+    Ok(())
+}
+
+/// A cached grid must not outlive its current price; zero means don't cache it
+fn grid_cache_ttl(pricing: &BoxPricing, now: DateTime<Utc>) -> Duration {
+    let Some(next_price_at) = pricing.next_price_at else {
+        return GRID_CACHE_TTL;
+    };
+
+    let seconds = (next_price_at - now).num_seconds();
+    if seconds < 1 {
+        return Duration::ZERO;
+    }
+
+    GRID_CACHE_TTL.min(Duration::from_secs(seconds as u64))
+}
+
+/// Whether a client at `since` must refetch the grid rather than replay the changes after it:
+/// boxes were removed since, it is ahead of the raffle, or it missed too much
+fn needs_full_resync(since: i64, current: i64, reset: i64) -> bool {
+    since < 0 || since < reset || since > current || current - since > MAX_GRID_RESYNC_CHANGES
+}
+
+/// Group sold boxes into one diff per grid sequence, keeping their order
+fn group_grid_diffs(sales: Vec<GridSale>, names: &HashMap<Uuid, String>) -> Vec<GridDiff> {
+    let mut diffs: Vec<GridDiff> = Vec::new();
+
+    for sale in sales {
+        let change = GridBoxChange {
+            box_number: sale.box_number,
+            user_id: sale.user_id,
+            username: names
+                .get(&sale.user_id)
+                .cloned()
+                .unwrap_or_else(|| winner_display::ANONYMOUS_NAME.to_string()),
+        };
+
+        match diffs.last_mut() {
+            Some(diff) if diff.sequence == sale.grid_sequence => diff.changes.push(change),
+            _ => diffs.push(GridDiff {
+                sequence: sale.grid_sequence,
+                changes: vec![change],
+                changed_at: sale.created_at,
+            }),
+        }
+    }
+
+    diffs
+}
+
+fn win_probability(held: i32, winners: i32, total: i32) -> f64 {
+    if total <= 0 || winners <= 0 || held <= 0 {
+        return 0.0;
+    }
+    if held + winners > total {
+        return 1.0;
+    }
+
+    let miss = (0..winners).fold(1.0, |acc, i| acc * (total - held - i) as f64 / (total - i) as f64);
+    1.0 - miss
+}
+
+/// A user's chance if the raffle were drawn from the boxes sold so far. Only open raffles that
+/// complete at their deadline, filled or not, can be drawn short.
+fn win_probability_if_drawn_now(raffle: &Raffle, user_boxes: Option<i32>) -> Option<f64> {
+    let drawn_unfilled = raffle.status == RaffleStatus::Open
+        && raffle.deadline_action == RaffleDeadlineAction::Complete
+        && raffle.ends_at.is_some();
+
+    user_boxes
+        .filter(|_| drawn_unfilled)
+        .map(|held| win_probability(held, raffle.total_winners, raffle.boxes_sold))
+}
+
+/// The raffle's Verify Raffle Draw record, once winners are drawn
+fn fairness_report_url(raffle: &Raffle) -> Option<String> {
+    (raffle.status == RaffleStatus::Completed).then(|| format!("/api/v1/verify/{}", raffle.id))
+}
+
+/// Prize value a single box is worth on average
+fn expected_value_per_box(prize_value: Decimal, winners: i32, total: i32) -> Decimal {
+    if total <= 0 {
+        return Decimal::ZERO;
+    }
+    (prize_value * Decimal::from(winners) / Decimal::from(total)).round_dp(4)
+}
+
+fn cost_per_unit_value(box_price: Decimal, expected_value: Decimal) -> Option<Decimal> {
+    if expected_value <= Decimal::ZERO {
+        return None;
+    }
+    Some((box_price / expected_value).round_dp(4))
+}
+
+/// Minutes a raffle has been open, for time-based pricing; raffles opened on creation have no start time
+fn minutes_open(raffle: &Raffle, now: DateTime<Utc>) -> i64 {
+    let opened_at = raffle.started_at.unwrap_or(raffle.created_at);
+    (now - opened_at).num_minutes().max(0)
+}
+
+/// Start must come before end, and a raffle cannot be created already ended
+fn validate_schedule(
+    starts_at: Option<DateTime<Utc>>,
+    ends_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    if let Some(ends_at) = ends_at {
+        if ends_at <= now {
+            return Err(AppError::Validation("End time must be in the future".to_string()));
+        }
+        if starts_at.map_or(false, |starts_at| ends_at <= starts_at) {
+            return Err(AppError::Validation("End time must be after the start time".to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Raffles that never opened, or sold too few boxes to fill every place, are cancelled whatever their action
+fn deadline_outcome(
+    status: RaffleStatus,
+    action: RaffleDeadlineAction,
+    boxes_sold: i32,
+    total_winners: i32,
+) -> RaffleDeadlineAction {
+    match action {
+        RaffleDeadlineAction::Complete if status == RaffleStatus::Open && boxes_sold >= total_winners => {
+            RaffleDeadlineAction::Complete
+        }
+        _ => RaffleDeadlineAction::Cancel,
+    }
+}
+
+/// Webhook delivery to the raffle's seller, when the item has one
+fn seller_message(seller_id: Option<Uuid>, event: SellerWebhookEvent, data: serde_json::Value) -> Option<OutboxMessage> {
+    seller_id.map(|seller_id| OutboxMessage::SellerWebhook { seller_id, event, data })
+}
+
+/// Webhook deliveries for a raffle lifecycle event: to its seller and to the platform endpoints
+fn raffle_event_messages(
+    raffle_id: Uuid,
+    seller_id: Option<Uuid>,
+    event: SellerWebhookEvent,
+    data: serde_json::Value,
+) -> Vec<OutboxMessage> {
+    let mut messages: Vec<OutboxMessage> = seller_message(seller_id, event, data.clone()).into_iter().collect();
+    messages.push(OutboxMessage::PlatformWebhook { raffle_id, event, data });
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completion_bound() {
+        assert_eq!(completion_bound(None).unwrap(), None);
+        assert_eq!(completion_bound(Some(50.0)).unwrap(), Some(Decimal::from(50)));
+        assert!(completion_bound(Some(120.0)).is_err());
+    }
+
+    #[test]
+    fn test_win_probability() {
+        assert!((win_probability(1, 1, 100) - 0.01).abs() < 1e-12);
+        assert!((win_probability(1, 3, 100) - 0.03).abs() < 1e-12);
+        // 1 - (8/10 * 7/9)
+        assert!((win_probability(2, 2, 10) - (1.0 - 56.0 / 90.0)).abs() < 1e-12);
+        assert_eq!(win_probability(0, 3, 100), 0.0);
+        assert_eq!(win_probability(98, 3, 100), 1.0);
+    }
+
+    fn odds_raffle(status: RaffleStatus, deadline_action: RaffleDeadlineAction, boxes_sold: i32) -> Raffle {
+        let now = Utc::now();
+        Raffle {
+            id: Uuid::new_v4(),
+            item_id: Uuid::new_v4(),
+            total_boxes: 100,
+            box_price: Decimal::from(5),
+            currency: "USD".to_string(),
+            boxes_sold,
+            total_winners: 1,
+            status,
+            winner_user_ids: Vec::new(),
+            blockchain_tx_hash: None,
+            grid_rows: 10,
+            grid_cols: 10,
+            transaction_fee_applied: None,
+            starts_at: None,
+            ends_at: Some(now - chrono::Duration::minutes(1)),
+            deadline_action,
+            max_boxes_per_user: None,
+            started_at: Some(now - chrono::Duration::days(1)),
+            completed_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_win_probability_if_drawn_now() {
+        // Past its deadline but not yet closed by the scheduler: drawn from the 20 boxes sold
+        let open = odds_raffle(RaffleStatus::Open, RaffleDeadlineAction::Complete, 20);
+        assert!((win_probability_if_drawn_now(&open, Some(4)).unwrap() - 0.2).abs() < 1e-12);
+        assert_eq!(win_probability_if_drawn_now(&open, Some(0)), Some(0.0));
+        assert_eq!(win_probability_if_drawn_now(&open, None), None);
+
+        // Cancelled at the deadline rather than drawn short
+        let cancels = odds_raffle(RaffleStatus::Open, RaffleDeadlineAction::Cancel, 20);
+        assert_eq!(win_probability_if_drawn_now(&cancels, Some(4)), None);
+
+        let mut no_deadline = odds_raffle(RaffleStatus::Open, RaffleDeadlineAction::Complete, 20);
+        no_deadline.ends_at = None;
+        assert_eq!(win_probability_if_drawn_now(&no_deadline, Some(4)), None);
+
+        // Full and completed raffles are drawn from every box, or already have been
+        let full = odds_raffle(RaffleStatus::Full, RaffleDeadlineAction::Complete, 100);
+        assert_eq!(win_probability_if_drawn_now(&full, Some(4)), None);
+        let completed = odds_raffle(RaffleStatus::Completed, RaffleDeadlineAction::Complete, 20);
+        assert_eq!(win_probability_if_drawn_now(&completed, Some(4)), None);
+    }
+
+    #[test]
+    fn test_fairness_report_url() {
+        let completed = odds_raffle(RaffleStatus::Completed, RaffleDeadlineAction::Complete, 20);
+        assert_eq!(fairness_report_url(&completed), Some(format!("/api/v1/verify/{}", completed.id)));
+
+        for status in [RaffleStatus::Open, RaffleStatus::Full] {
+            assert_eq!(fairness_report_url(&odds_raffle(status, RaffleDeadlineAction::Complete, 20)), None);
+        }
+    }
+
+    #[test]
+    fn test_grid_cache_ttl_stops_at_price_step() {
+        let now = Utc::now();
+        let pricing = |next_price_at: Option<DateTime<Utc>>| BoxPricing {
+            current_price: Decimal::from(10),
+            next_price: next_price_at.map(|_| Decimal::from(12)),
+            next_price_at_boxes_sold: None,
+            next_price_at,
+        };
+
+        assert_eq!(grid_cache_ttl(&pricing(None), now), GRID_CACHE_TTL);
+        assert_eq!(
+            grid_cache_ttl(&pricing(Some(now + chrono::Duration::hours(1))), now),
+            GRID_CACHE_TTL
+        );
+        assert_eq!(
+            grid_cache_ttl(&pricing(Some(now + chrono::Duration::seconds(12))), now),
+            Duration::from_secs(12)
+        );
+        assert_eq!(grid_cache_ttl(&pricing(Some(now)), now), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_remaining_allowance() {
+        assert_eq!(remaining_allowance(None, 5), None);
+        assert_eq!(remaining_allowance(Some(5), 0), Some(5));
+        assert_eq!(remaining_allowance(Some(5), 3), Some(2));
+        assert_eq!(remaining_allowance(Some(5), 7), Some(0));
+    }
+
+    #[test]
+    fn test_expected_value_and_cost() {
+        let ev = expected_value_per_box(Decimal::from(200), 1, 100);
+        assert_eq!(ev, Decimal::from(2));
+        assert_eq!(cost_per_unit_value(Decimal::new(250, 2), ev), Some(Decimal::new(125, 2)));
+        assert_eq!(cost_per_unit_value(Decimal::ONE, Decimal::ZERO), None);
+    }
+
+    #[test]
+    fn test_validate_schedule() {
+        let now = Utc::now();
+        let hour = chrono::Duration::hours(1);
+
+        assert!(validate_schedule(None, None, now).is_ok());
+        assert!(validate_schedule(Some(now + hour), Some(now + hour * 2), now).is_ok());
+        // Past start opens immediately
+        assert!(validate_schedule(Some(now - hour), Some(now + hour), now).is_ok());
+        assert!(validate_schedule(None, Some(now - hour), now).is_err());
+        assert!(validate_schedule(Some(now + hour * 2), Some(now + hour), now).is_err());
+    }
+
+    #[test]
+    fn test_deadline_outcome() {
+        use RaffleDeadlineAction::{Cancel, Complete};
+
+        assert_eq!(deadline_outcome(RaffleStatus::Open, Complete, 5, 3), Complete);
+        assert_eq!(deadline_outcome(RaffleStatus::Open, Complete, 2, 3), Cancel);
+        assert_eq!(deadline_outcome(RaffleStatus::Scheduled, Complete, 0, 1), Cancel);
+        assert_eq!(deadline_outcome(RaffleStatus::Open, Cancel, 50, 1), Cancel);
+    }
+
+    #[test]
+    fn test_raffle_event_messages_skip_missing_seller() {
+        let raffle_id = Uuid::new_v4();
+        let data = serde_json::json!({ "raffle_id": raffle_id });
+
+        let with_seller = raffle_event_messages(raffle_id, Some(Uuid::new_v4()), SellerWebhookEvent::RaffleOpened, data.clone());
+        assert_eq!(with_seller.len(), 2);
+        assert!(matches!(with_seller[0], OutboxMessage::SellerWebhook { .. }));
+
+        let without_seller = raffle_event_messages(raffle_id, None, SellerWebhookEvent::RaffleOpened, data);
+        assert_eq!(without_seller.len(), 1);
+        assert!(matches!(without_seller[0], OutboxMessage::PlatformWebhook { raffle_id: id, .. } if id == raffle_id));
+    }
+
+    #[test]
+    fn test_needs_full_resync() {
+        assert!(!needs_full_resync(10, 12, 0));
+        assert!(!needs_full_resync(12, 12, 0));
+        // Boxes were removed at 11, which diffs can't replay
+        assert!(needs_full_resync(10, 12, 11));
+        assert!(!needs_full_resync(11, 12, 11));
+        // Ahead of the raffle, e.g. after a restore
+        assert!(needs_full_resync(13, 12, 0));
+        assert!(needs_full_resync(-1, 12, 0));
+        assert!(needs_full_resync(0, MAX_GRID_RESYNC_CHANGES + 1, 0));
+    }
+
+    #[test]
+    fn test_group_grid_diffs() {
+        let buyer = Uuid::new_v4();
+        let now = Utc::now();
+        let sale = |grid_sequence: i64, box_number: i32, user_id: Uuid| GridSale {
+            grid_sequence,
+            box_number,
+            user_id,
+            created_at: now,
+        };
+        let names = HashMap::from([(buyer, "Sam".to_string())]);
+
+        let diffs = group_grid_diffs(
+            vec![sale(4, 7, buyer), sale(4, 9, buyer), sale(5, 1, Uuid::new_v4())],
+            &names,
+        );
+
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].sequence, 4);
+        assert_eq!(diffs[0].changes.iter().map(|c| c.box_number).collect::<Vec<_>>(), vec![7, 9]);
+        assert_eq!(diffs[0].changes[0].username, "Sam");
+        assert_eq!(diffs[1].sequence, 5);
+        assert_eq!(diffs[1].changes[0].username, winner_display::ANONYMOUS_NAME);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::database::StartupRetry;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+const CHECK_INTERVAL_SECONDS: u64 = 15;
+const CHECK_TIMEOUT_SECONDS: u64 = 5;
+
+/// Readiness service tracks whether the database and Redis are reachable.
+///
+/// The database is required: without it the backend reports itself unavailable so the
+/// load balancer stops routing to it. Redis only backs caching, so losing it degrades
+/// the backend without taking it out of rotation.
+#[derive(Clone)]
+pub struct ReadinessService {
+    db_pool: PgPool,
+    redis_client: Option<redis::Client>,
+    state: Arc<RwLock<Vec<DependencyStatus>>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadinessState {
+    Ready,
+    Degraded,
+    Unavailable,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyStatus {
+    pub name: &'static str,
+    pub required: bool,
+    pub healthy: bool,
+    pub last_error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+    /// When the current outage started
+    pub down_since: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    pub status: ReadinessState,
+    pub dependencies: Vec<DependencyStatus>,
+}
+
+impl ReadinessService {
+    /// The database pool must already be connected; a bad Redis URL only disables the Redis check
+    pub fn new(db_pool: PgPool, redis_url: &str) -> Self {
+        let redis_client = match redis::Client::open(redis_url) {
+            Ok(client) => Some(client),
+            Err(e) => {
+                warn!("Invalid Redis URL, Redis will be reported down: {}", e);
+                None
+            }
+        };
+
+        let now = Utc::now();
+        let state = vec![
+            DependencyStatus {
+                name: "database",
+                required: true,
+                healthy: true,
+                last_error: None,
+                checked_at: now,
+                down_since: None,
+            },
+            DependencyStatus {
+                name: "redis",
+                required: false,
+                healthy: false,
+                last_error: Some("Not checked yet".to_string()),
+                checked_at: now,
+                down_since: Some(now),
+            },
+        ];
+
+        Self {
+            db_pool,
+            redis_client,
+            state: Arc::new(RwLock::new(state)),
+        }
+    }
+
+    /// Wait for Redis at startup with backoff; gives up (degraded) rather than failing boot
+    pub async fn wait_for_redis(&self, retry: StartupRetry) -> bool {
+        for attempt in 1..=retry.max_attempts {
+            match self.ping_redis().await {
+                Ok(()) => {
+                    self.record("redis", Ok(())).await;
+                    if attempt > 1 {
+                        info!("Connected to Redis after {} attempts", attempt);
+                    }
+                    return true;
+                }
+                Err(e) => {
+                    self.record("redis", Err(e.clone())).await;
+                    if attempt < retry.max_attempts {
+                        let delay = retry.delay(attempt);
+                        warn!(
+                            "Redis not reachable (attempt {}/{}): {}; retrying in {}s",
+                            attempt,
+                            retry.max_attempts,
+                            e,
+                            delay.as_secs()
+                        );
+                        tokio::time::sleep(delay).await;
+                    }
+                }
+            }
+        }
+
+        warn!("Redis still unreachable after {} attempts; starting degraded", retry.max_attempts);
+        false
+    }
+
+    /// Start background tasks for dependency checks
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(CHECK_INTERVAL_SECONDS));
+            loop {
+                interval.tick().await;
+                service.check_dependencies().await;
+            }
+        });
+    }
+
+    /// Check every dependency now
+    pub async fn check_dependencies(&self) {
+        let database = self.ping_database().await;
+        self.record("database", database).await;
+
+        let redis = self.ping_redis().await;
+        self.record("redis", redis).await;
+    }
+
+    /// Current readiness, from the last checks
+    pub async fn report(&self) -> ReadinessReport {
+        let dependencies = self.state.read().await.clone();
+
+        ReadinessReport {
+            status: overall_state(&dependencies),
+            dependencies,
+        }
+    }
+
+    // Private helper methods
+
+    async fn ping_database(&self) -> Result<(), String> {
+        let check = sqlx::query("SELECT 1").execute(&self.db_pool);
+
+        match tokio::time::timeout(Duration::from_secs(CHECK_TIMEOUT_SECONDS), check).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err("Timed out".to_string()),
+        }
+    }
+
+    async fn ping_redis(&self) -> Result<(), String> {
+        let client = self
+            .redis_client
+            .as_ref()
+            .ok_or_else(|| "Invalid Redis URL".to_string())?;
+
+        let check = async {
+            let mut conn = client.get_multiplexed_async_connection().await?;
+            redis::cmd("PING").query_async::<_, String>(&mut conn).await
+        };
+
+        match tokio::time::timeout(Duration::from_secs(CHECK_TIMEOUT_SECONDS), check).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err("Timed out".to_string()),
+        }
+    }
+
+    async fn record(&self, name: &str, result: Result<(), String>) {
+        let mut state = self.state.write().await;
+        let Some(status) = state.iter_mut().find(|d| d.name == name) else {
+            return;
+        };
+
+        let now = Utc::now();
+        match result {
+            Ok(()) => {
+                if !status.healthy {
+                    info!("{} is reachable again", name);
+                }
+                status.healthy = true;
+                status.last_error = None;
+                status.down_since = None;
+            }
+            Err(e) => {
+                if status.healthy {
+                    warn!("{} became unreachable: {}", name, e);
+                }
+                status.healthy = false;
+                status.last_error = Some(e);
+                status.down_since.get_or_insert(now);
+            }
+        }
+        status.checked_at = now;
+    }
+}
+
+/// Unavailable when a required dependency is down, degraded when only optional ones are
+fn overall_state(dependencies: &[DependencyStatus]) -> ReadinessState {
+    if dependencies.iter().any(|d| d.required && !d.healthy) {
+        ReadinessState::Unavailable
+    } else if dependencies.iter().any(|d| !d.healthy) {
+        ReadinessState::Degraded
+    } else {
+        ReadinessState::Ready
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(name: &'static str, required: bool, healthy: bool) -> DependencyStatus {
+        DependencyStatus {
+            name,
+            required,
+            healthy,
+            last_error: None,
+            checked_at: Utc::now(),
+            down_since: None,
+        }
+    }
+
+    #[test]
+    fn test_overall_state() {
+        assert_eq!(
+            overall_state(&[dependency("database", true, true), dependency("redis", false, true)]),
+            ReadinessState::Ready
+        );
+        assert_eq!(
+            overall_state(&[dependency("database", true, true), dependency("redis", false, false)]),
+            ReadinessState::Degraded
+        );
+        assert_eq!(
+            overall_state(&[dependency("database", true, false), dependency("redis", false, true)]),
+            ReadinessState::Unavailable
+        );
+    }
+}
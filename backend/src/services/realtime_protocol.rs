@@ -0,0 +1,245 @@
+use crate::error::AppError;
+use crate::services::realtime_service::{ClientMessage, WebSocketMessage};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+/// Envelope of clients that connect without asking for a version: `message_type`, `data`, `timestamp`
+pub const LEGACY_PROTOCOL_VERSION: u32 = 1;
+/// `type`, `version`, `payload`, `timestamp`, with event payloads no longer wrapped in the event's name
+pub const CURRENT_PROTOCOL_VERSION: u32 = 2;
+pub const SUPPORTED_PROTOCOL_VERSIONS: [u32; 2] = [LEGACY_PROTOCOL_VERSION, CURRENT_PROTOCOL_VERSION];
+
+/// How a connection's server messages are framed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// JSON in text frames
+    Json,
+    /// MessagePack in binary frames
+    Msgpack,
+}
+
+/// Protocol a client asks for when it connects
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProtocolQuery {
+    /// Envelope version; clients that leave it out get version 1
+    pub version: Option<u32>,
+    /// `json` (default) or `msgpack`; MessagePack needs version 2 or later
+    pub encoding: Option<String>,
+}
+
+/// Protocol agreed with a connection at connect time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Protocol {
+    pub version: u32,
+    pub encoding: Encoding,
+}
+
+impl Default for Protocol {
+    fn default() -> Self {
+        Self {
+            version: LEGACY_PROTOCOL_VERSION,
+            encoding: Encoding::Json,
+        }
+    }
+}
+
+/// A server message ready to write to the socket
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// Version 2 and later envelope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    #[serde(rename = "type")]
+    pub message_type: String,
+    pub version: u32,
+    pub payload: serde_json::Value,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Version 1 envelope, kept byte for byte for clients that predate versioning
+#[derive(Serialize)]
+struct LegacyEnvelope<'a> {
+    message_type: &'a str,
+    data: &'a serde_json::Value,
+    timestamp: DateTime<Utc>,
+}
+
+impl Protocol {
+    /// Agree on the protocol a client asked for, refusing versions and encodings we don't speak
+    pub fn negotiate(query: &ProtocolQuery) -> Result<Self, AppError> {
+        let version = query.version.unwrap_or(LEGACY_PROTOCOL_VERSION);
+        if !SUPPORTED_PROTOCOL_VERSIONS.contains(&version) {
+            return Err(AppError::Validation(format!(
+                "Unsupported protocol version {}; supported versions are {:?}",
+                version, SUPPORTED_PROTOCOL_VERSIONS
+            )));
+        }
+
+        let encoding = match query.encoding.as_deref().map(str::trim) {
+            None | Some("") | Some("json") => Encoding::Json,
+            Some("msgpack") => Encoding::Msgpack,
+            Some(other) => return Err(AppError::Validation(format!("Unsupported encoding: {}", other))),
+        };
+
+        if encoding == Encoding::Msgpack && version == LEGACY_PROTOCOL_VERSION {
+            return Err(AppError::Validation(
+                "MessagePack frames need protocol version 2 or later".to_string(),
+            ));
+        }
+
+        Ok(Self { version, encoding })
+    }
+
+    /// Frame a server message in this connection's envelope and encoding
+    pub fn encode(&self, message: &WebSocketMessage) -> Result<Frame, String> {
+        if self.version == LEGACY_PROTOCOL_VERSION {
+            let legacy = LegacyEnvelope {
+                message_type: &message.message_type,
+                data: &message.data,
+                timestamp: message.timestamp,
+            };
+            return serde_json::to_string(&legacy).map(Frame::Text).map_err(|e| e.to_string());
+        }
+
+        let envelope = Envelope {
+            message_type: message.message_type.clone(),
+            version: self.version,
+            payload: payload(&message.message_type, &message.data),
+            timestamp: message.timestamp,
+        };
+
+        match self.encoding {
+            Encoding::Json => serde_json::to_string(&envelope).map(Frame::Text).map_err(|e| e.to_string()),
+            Encoding::Msgpack => rmp_serde::to_vec_named(&envelope)
+                .map(Frame::Binary)
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Read a client message from a text frame, in either envelope
+pub fn decode_text(text: &str) -> Result<ClientMessage, String> {
+    serde_json::from_str(text).map_err(|e| e.to_string())
+}
+
+/// Read a client message from a MessagePack binary frame
+pub fn decode_binary(bytes: &[u8]) -> Result<ClientMessage, String> {
+    rmp_serde::from_slice(bytes).map_err(|e| e.to_string())
+}
+
+/// Event messages carry the event wrapped in its name (`{"BoxPurchased": {...}}`); from version 2
+/// the payload is the event's fields. Other messages are sent as they are.
+fn payload(message_type: &str, data: &serde_json::Value) -> serde_json::Value {
+    if let serde_json::Value::Object(map) = data {
+        if map.len() == 1 {
+            if let Some(fields) = map.get(&pascal_case(message_type)) {
+                return fields.clone();
+            }
+        }
+    }
+
+    data.clone()
+}
+
+fn pascal_case(snake: &str) -> String {
+    snake
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn query(version: Option<u32>, encoding: Option<&str>) -> ProtocolQuery {
+        ProtocolQuery {
+            version,
+            encoding: encoding.map(str::to_string),
+        }
+    }
+
+    fn box_purchased() -> WebSocketMessage {
+        WebSocketMessage {
+            message_type: "box_purchased".to_string(),
+            data: json!({"BoxPurchased": {"box_number": 25, "boxes_remaining": 75}}),
+            timestamp: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(Protocol::negotiate(&query(None, None)).unwrap(), Protocol::default());
+        assert_eq!(
+            Protocol::negotiate(&query(Some(2), Some("msgpack"))).unwrap(),
+            Protocol { version: 2, encoding: Encoding::Msgpack }
+        );
+        assert_eq!(Protocol::negotiate(&query(Some(2), None)).unwrap().encoding, Encoding::Json);
+
+        assert!(Protocol::negotiate(&query(Some(3), None)).is_err());
+        assert!(Protocol::negotiate(&query(None, Some("msgpack"))).is_err());
+        assert!(Protocol::negotiate(&query(Some(2), Some("cbor"))).is_err());
+    }
+
+    #[test]
+    fn test_legacy_envelope_is_unchanged() {
+        let frame = Protocol::default().encode(&box_purchased()).unwrap();
+        let Frame::Text(text) = frame else { panic!("legacy frames are text") };
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(value["message_type"], "box_purchased");
+        assert_eq!(value["data"]["BoxPurchased"]["box_number"], 25);
+        assert!(value.get("version").is_none());
+    }
+
+    #[test]
+    fn test_versioned_envelope_unwraps_events() {
+        let json = Protocol { version: 2, encoding: Encoding::Json };
+        let Frame::Text(text) = json.encode(&box_purchased()).unwrap() else { panic!("json frames are text") };
+        let envelope: Envelope = serde_json::from_str(&text).unwrap();
+        assert_eq!(envelope.message_type, "box_purchased");
+        assert_eq!(envelope.version, 2);
+        assert_eq!(envelope.payload, json!({"box_number": 25, "boxes_remaining": 75}));
+
+        let msgpack = Protocol { version: 2, encoding: Encoding::Msgpack };
+        let Frame::Binary(bytes) = msgpack.encode(&box_purchased()).unwrap() else { panic!("msgpack frames are binary") };
+        let envelope: Envelope = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(envelope.payload["boxes_remaining"], 75);
+
+        // Messages that aren't wrapped events keep their data
+        assert_eq!(payload("pong", &json!({"timestamp": 1})), json!({"timestamp": 1}));
+        assert_eq!(pascal_case("credit_balance_changed"), "CreditBalanceChanged");
+    }
+
+    #[test]
+    fn test_decode_either_envelope() {
+        let legacy = decode_text(r#"{"message_type": "join_room", "data": {"room": "raffle:1"}}"#).unwrap();
+        assert_eq!(legacy.message_type, "join_room");
+        assert_eq!(legacy.data["room"], "raffle:1");
+
+        let versioned = decode_text(r#"{"type": "join_room", "version": 2, "payload": {"room": "raffle:1"}}"#).unwrap();
+        assert_eq!(versioned.message_type, "join_room");
+        assert_eq!(versioned.data["room"], "raffle:1");
+
+        let ping = decode_text(r#"{"type": "ping", "version": 2}"#).unwrap();
+        assert!(ping.data.is_null());
+
+        let bytes = rmp_serde::to_vec_named(&json!({"type": "ping", "version": 2, "payload": {}})).unwrap();
+        assert_eq!(decode_binary(&bytes).unwrap().message_type, "ping");
+        assert!(decode_binary(b"not msgpack").is_err());
+    }
+}
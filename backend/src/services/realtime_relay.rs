@@ -0,0 +1,279 @@
+use crate::services::realtime_service::{ConnectionStats, RealtimeEvent, WebSocketMessage};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use redis::aio::MultiplexedConnection;
+use redis::{AsyncCommands, Client as RedisClient};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Replicas relay realtime events and announcements to each other on this Redis channel
+const RELAY_CHANNEL: &str = "raffle_platform:realtime";
+/// Hash of each replica's latest connection stats, by instance ID
+const INSTANCE_STATS_KEY: &str = "raffle_platform:ws_instances";
+/// How often a replica reports its connection stats
+pub const STATS_REPORT_INTERVAL: Duration = Duration::from_secs(15);
+/// Reports older than this come from replicas that have stopped and are left out of the totals
+const STATS_STALE_AFTER: Duration = Duration::from_secs(45);
+/// Wait before resubscribing after the pub/sub connection drops
+pub const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Redis pub/sub bridge between replicas' realtime services.
+///
+/// Each replica publishes the events raised on it and delivers the ones published by the
+/// others to its own connections, so a client sees every event whichever replica it is
+/// connected to. Relayed events go straight to connections: in-process listeners such as
+/// cache invalidation have already run on the replica that raised them.
+#[derive(Clone)]
+pub struct RealtimeRelay {
+    client: RedisClient,
+    /// Tells this replica's own messages apart from the others'
+    instance_id: Uuid,
+    connection: Arc<Mutex<Option<MultiplexedConnection>>>,
+}
+
+/// What one replica sends the others
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RelayPayload {
+    Event {
+        event: RealtimeEvent,
+        /// Winners aren't serialized with the event but route it to their rooms
+        #[serde(default)]
+        winner_user_ids: Vec<Uuid>,
+    },
+    /// An admin announcement to a room or to `all`
+    Announcement { channel: String, message: WebSocketMessage },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RelayMessage {
+    origin: Uuid,
+    payload: RelayPayload,
+}
+
+/// A replica's connection stats as last reported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceReport {
+    pub instance_id: Uuid,
+    pub reported_at: DateTime<Utc>,
+    pub stats: ConnectionStats,
+}
+
+impl RelayPayload {
+    pub fn event(event: &RealtimeEvent) -> Self {
+        let winner_user_ids = match event {
+            RealtimeEvent::WinnerSelected { winner_user_ids, .. } => winner_user_ids.clone(),
+            _ => Vec::new(),
+        };
+        RelayPayload::Event { event: event.clone(), winner_user_ids }
+    }
+}
+
+/// The event as raised on the other replica, winners included
+pub fn relayed_event(mut event: RealtimeEvent, winners: Vec<Uuid>) -> RealtimeEvent {
+    if let RealtimeEvent::WinnerSelected { winner_user_ids, .. } = &mut event {
+        *winner_user_ids = winners;
+    }
+    event
+}
+
+impl RealtimeRelay {
+    pub fn new(client: RedisClient) -> Self {
+        Self {
+            client,
+            instance_id: Uuid::new_v4(),
+            connection: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn instance_id(&self) -> Uuid {
+        self.instance_id
+    }
+
+    /// Send to every other replica; failures are logged, the local delivery has already happened
+    pub async fn publish(&self, payload: RelayPayload) {
+        let message = RelayMessage {
+            origin: self.instance_id,
+            payload,
+        };
+        let payload = match serde_json::to_string(&message) {
+            Ok(payload) => payload,
+            Err(e) => {
+                warn!("Failed to serialize realtime relay message: {}", e);
+                return;
+            }
+        };
+
+        let published = async {
+            let mut conn = self.connection().await?;
+            conn.publish::<_, _, ()>(RELAY_CHANNEL, payload).await
+        };
+        if let Err(e) = published.await {
+            self.reset_connection().await;
+            warn!("Failed to relay realtime message to other replicas: {}", e);
+        }
+    }
+
+    /// Pass every message the other replicas publish to `deliver` until the subscription drops
+    pub async fn follow<F, Fut>(&self, mut deliver: F) -> Result<(), redis::RedisError>
+    where
+        F: FnMut(RelayPayload) -> Fut,
+        Fut: std::future::Future<Output = ()>,
+    {
+        let mut pubsub = self.client.get_async_connection().await?.into_pubsub();
+        pubsub.subscribe(RELAY_CHANNEL).await?;
+        debug!("Subscribed to {}", RELAY_CHANNEL);
+
+        let mut messages = pubsub.on_message();
+        while let Some(message) = messages.next().await {
+            let payload: String = message.get_payload()?;
+            match serde_json::from_str::<RelayMessage>(&payload) {
+                Ok(message) if message.origin != self.instance_id => deliver(message.payload).await,
+                Ok(_) => {}
+                Err(e) => warn!("Ignoring malformed realtime relay message: {}", e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record this replica's connection stats for the others to aggregate
+    pub async fn report_stats(&self, stats: &ConnectionStats) -> Result<(), redis::RedisError> {
+        let report = InstanceReport {
+            instance_id: self.instance_id,
+            reported_at: Utc::now(),
+            stats: stats.clone(),
+        };
+        let Ok(value) = serde_json::to_string(&report) else { return Ok(()) };
+
+        let mut conn = self.connection().await?;
+        let reported = conn
+            .hset::<_, _, _, ()>(INSTANCE_STATS_KEY, self.instance_id.to_string(), value)
+            .await;
+        if reported.is_err() {
+            self.reset_connection().await;
+        }
+        reported
+    }
+
+    /// The latest report of every replica still running, this one's included if it has reported.
+    /// Reports from replicas that stopped are removed.
+    pub async fn instance_reports(&self) -> Result<Vec<InstanceReport>, redis::RedisError> {
+        let mut conn = self.connection().await?;
+        let reports: HashMap<String, String> = match conn.hgetall(INSTANCE_STATS_KEY).await {
+            Ok(reports) => reports,
+            Err(e) => {
+                self.reset_connection().await;
+                return Err(e);
+            }
+        };
+
+        let now = Utc::now();
+        let mut live = Vec::with_capacity(reports.len());
+        let mut stale = Vec::new();
+        for (field, value) in reports {
+            match serde_json::from_str::<InstanceReport>(&value) {
+                Ok(report) if !is_stale(report.reported_at, now) => live.push(report),
+                _ => stale.push(field),
+            }
+        }
+
+        if !stale.is_empty() {
+            if let Err(e) = conn.hdel::<_, _, ()>(INSTANCE_STATS_KEY, &stale).await {
+                debug!("Failed to remove stale WebSocket instance reports: {}", e);
+            }
+        }
+
+        live.sort_by_key(|report| report.instance_id);
+        Ok(live)
+    }
+
+    async fn connection(&self) -> Result<MultiplexedConnection, redis::RedisError> {
+        let mut connection = self.connection.lock().await;
+        if let Some(conn) = connection.as_ref() {
+            return Ok(conn.clone());
+        }
+
+        let conn = self.client.get_multiplexed_async_connection().await?;
+        *connection = Some(conn.clone());
+        Ok(conn)
+    }
+
+    /// Reconnect on the next use
+    async fn reset_connection(&self) {
+        *self.connection.lock().await = None;
+    }
+}
+
+fn is_stale(reported_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    (now - reported_at).to_std().map_or(false, |age| age > STATS_STALE_AFTER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::winner_display::PublicWinner;
+
+    #[test]
+    fn test_winners_survive_the_relay() {
+        let winner = Uuid::new_v4();
+        let event = RealtimeEvent::WinnerSelected {
+            raffle_id: Uuid::new_v4(),
+            item_id: Uuid::new_v4(),
+            winner_user_ids: vec![winner],
+            winners: Vec::<PublicWinner>::new(),
+            completed_at: Utc::now(),
+        };
+
+        let message = RelayMessage {
+            origin: Uuid::new_v4(),
+            payload: RelayPayload::event(&event),
+        };
+        let json = serde_json::to_string(&message).unwrap();
+        let received: RelayMessage = serde_json::from_str(&json).unwrap();
+
+        let RelayPayload::Event { event, winner_user_ids } = received.payload else {
+            panic!("expected an event");
+        };
+        match relayed_event(event, winner_user_ids) {
+            RealtimeEvent::WinnerSelected { winner_user_ids, .. } => assert_eq!(winner_user_ids, vec![winner]),
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_announcements_round_trip() {
+        let payload = RelayPayload::Announcement {
+            channel: "all".to_string(),
+            message: WebSocketMessage {
+                message_type: "announcement".to_string(),
+                data: serde_json::json!({"message": "Back in five"}),
+                timestamp: Utc::now(),
+            },
+        };
+
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["kind"], "announcement");
+
+        let RelayPayload::Announcement { channel, message } = serde_json::from_value(json).unwrap() else {
+            panic!("expected an announcement");
+        };
+        assert_eq!(channel, "all");
+        assert_eq!(message.data["message"], "Back in five");
+    }
+
+    #[test]
+    fn test_reports_go_stale() {
+        let now = Utc::now();
+
+        assert!(!is_stale(now - chrono::Duration::seconds(30), now));
+        assert!(is_stale(now - chrono::Duration::seconds(60), now));
+        // A replica whose clock runs ahead isn't dropped
+        assert!(!is_stale(now + chrono::Duration::seconds(5), now));
+    }
+}
@@ -1,19 +1,37 @@
 use crate::error::AppError;
 use crate::models::raffle::Raffle;
 use crate::models::item::Item;
+use crate::models::system_settings::SystemSetting;
 use crate::models::user::User;
+use crate::models::winner_display::PublicWinner;
+use crate::models::AuditLog;
+use crate::services::credit_service::CreditBalance;
+use crate::services::realtime_relay::{self, InstanceReport, RealtimeRelay, RelayPayload};
 use actix::prelude::*;
 use actix_web_actors::ws;
 use chrono::{DateTime, Utc};
+use raffle_platform_shared::AuditAction;
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
 use tracing::{debug, error, info, warn};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+const DEFAULT_MAX_ROOMS_PER_CONNECTION: i64 = 50;
+const DEFAULT_SLOW_CONSUMER_DROP_THRESHOLD: i64 = 20;
+/// Minutes of message counts kept for throughput stats
+const THROUGHPUT_WINDOW_MINUTES: i64 = 60;
+/// Channel name that reaches every connection in an admin broadcast
+pub const ALL_CONNECTIONS_CHANNEL: &str = "all";
+
 /// Real-time service for managing WebSocket connections and broadcasting events
 #[derive(Clone)]
 pub struct RealtimeService {
@@ -21,6 +39,9 @@ pub struct RealtimeService {
     connections: Arc<tokio::sync::RwLock<HashMap<Uuid, ConnectionInfo>>>,
     event_sender: broadcast::Sender<RealtimeEvent>,
     _event_receiver: broadcast::Receiver<RealtimeEvent>,
+    throughput: Arc<Mutex<MessageThroughput>>,
+    /// Shares events with the other replicas; None when running alone
+    relay: Option<RealtimeRelay>,
 }
 
 #[derive(Debug, Clone)]
@@ -28,13 +49,76 @@ pub struct ConnectionInfo {
     pub id: Uuid,
     pub user_id: Option<Uuid>,
     pub addr: Recipient<WebSocketMessage>,
+    pub closer: Recipient<Disconnect>,
+    pub counters: Arc<ConnectionCounters>,
     pub subscriptions: Vec<EventSubscription>,
+    pub rooms: HashSet<Room>,
     pub connected_at: Instant,
     pub last_ping: Instant,
     pub user_agent: Option<String>,
     pub ip_address: Option<String>,
 }
 
+/// Message counts for one connection, shared with its actor so received messages are counted
+#[derive(Debug, Default)]
+pub struct ConnectionCounters {
+    pub messages_sent: AtomicU64,
+    pub messages_received: AtomicU64,
+    /// Messages dropped because the connection's mailbox was full
+    pub messages_dropped: AtomicU64,
+    /// Drops since a message last got through; a slow consumer reaches the drop threshold
+    pub consecutive_drops: AtomicU64,
+}
+
+/// Messages sent to and received from all connections, counted per minute
+#[derive(Debug, Default)]
+struct MessageThroughput {
+    sent: ThroughputWindow,
+    received: ThroughputWindow,
+}
+
+/// Per-minute counts over the last hour
+#[derive(Debug, Default)]
+pub struct ThroughputWindow {
+    buckets: VecDeque<(i64, u64)>,
+}
+
+impl ThroughputWindow {
+    /// Count messages in the given minute (Unix seconds / 60) and drop buckets older than the window
+    pub fn record(&mut self, minute: i64, count: u64) {
+        match self.buckets.back_mut() {
+            Some((last, total)) if *last == minute => *total += count,
+            _ => self.buckets.push_back((minute, count)),
+        }
+
+        while let Some((oldest, _)) = self.buckets.front() {
+            if *oldest > minute - THROUGHPUT_WINDOW_MINUTES {
+                break;
+            }
+            self.buckets.pop_front();
+        }
+    }
+
+    /// Messages counted in the `minutes` minutes up to and including `minute`
+    pub fn total(&self, minute: i64, minutes: i64) -> u64 {
+        self.buckets
+            .iter()
+            .filter(|(m, _)| *m > minute - minutes && *m <= minute)
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+/// Outcome of handing a message to a connection's actor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Delivery {
+    Sent,
+    /// The mailbox was full; the connection is falling behind
+    Dropped,
+    /// The actor has stopped
+    Closed,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventSubscription {
     pub event_type: String,
@@ -44,6 +128,49 @@ pub struct EventSubscription {
     pub room: Option<String>,
 }
 
+/// Channel a connection can join to receive one raffle's, item's or user's events.
+/// `user:{id}` rooms are private to that signed-in user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Room {
+    Raffle(Uuid),
+    Item(Uuid),
+    User(Uuid),
+}
+
+impl Room {
+    /// Parse a room name such as `raffle:{id}`
+    pub fn parse(name: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::Validation(format!("Unknown room: {}", name));
+        let (kind, id) = name.trim().split_once(':').ok_or_else(invalid)?;
+        let id = Uuid::parse_str(id).map_err(|_| invalid())?;
+
+        match kind {
+            "raffle" => Ok(Room::Raffle(id)),
+            "item" => Ok(Room::Item(id)),
+            "user" => Ok(Room::User(id)),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Whether a connection signed in as `user_id` (or not at all) may be in the room
+    pub fn allows(&self, user_id: Option<Uuid>) -> bool {
+        match self {
+            Room::User(id) => user_id == Some(*id),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Room {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Room::Raffle(id) => write!(f, "raffle:{}", id),
+            Room::Item(id) => write!(f, "item:{}", id),
+            Room::User(id) => write!(f, "user:{}", id),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RealtimeEvent {
     // Raffle events
@@ -55,6 +182,12 @@ pub enum RealtimeEvent {
         box_price: rust_decimal::Decimal,
         created_at: DateTime<Utc>,
     },
+    RaffleOpened {
+        raffle_id: Uuid,
+        item_id: Uuid,
+        ends_at: Option<DateTime<Utc>>,
+        opened_at: DateTime<Utc>,
+    },
     BoxPurchased {
         raffle_id: Uuid,
         user_id: Uuid,
@@ -63,16 +196,38 @@ pub enum RealtimeEvent {
         completion_percentage: f64,
         purchased_at: DateTime<Utc>,
     },
+    /// Boxes sold by one purchase, so clients keep a grid current without refetching it
+    GridChanged {
+        raffle_id: Uuid,
+        /// The raffle's grid sequence after this change; one more than the previous change's
+        sequence: i64,
+        changes: Vec<GridBoxChange>,
+        boxes_sold: i32,
+        boxes_remaining: i32,
+        changed_at: DateTime<Utc>,
+    },
     RaffleFull {
         raffle_id: Uuid,
         item_id: Uuid,
         total_boxes: i32,
         completed_at: DateTime<Utc>,
     },
+    OddsUpdated {
+        raffle_id: Uuid,
+        boxes_sold: i32,
+        boxes_remaining: i32,
+        win_probability_per_box: f64,
+        expected_value_per_box: rust_decimal::Decimal,
+        cost_per_unit_value: Option<rust_decimal::Decimal>,
+        updated_at: DateTime<Utc>,
+    },
     WinnerSelected {
         raffle_id: Uuid,
         item_id: Uuid,
+        /// Routes the event to the winners' own rooms; never sent to clients
+        #[serde(skip_serializing, default)]
         winner_user_ids: Vec<Uuid>,
+        winners: Vec<PublicWinner>,
         completed_at: DateTime<Utc>,
     },
     RaffleCancelled {
@@ -81,6 +236,21 @@ pub enum RealtimeEvent {
         reason: String,
         cancelled_at: DateTime<Utc>,
     },
+    CommentPosted {
+        raffle_id: Uuid,
+        comment_id: Uuid,
+        parent_id: Option<Uuid>,
+        author_id: Uuid,
+        author_username: String,
+        body: String,
+        is_seller_answer: bool,
+        created_at: DateTime<Utc>,
+    },
+    CommentRemoved {
+        raffle_id: Uuid,
+        comment_id: Uuid,
+        removed_at: DateTime<Utc>,
+    },
     
     // Item events
     ItemCreated {
@@ -127,7 +297,29 @@ pub enum RealtimeEvent {
         item_id: Option<Uuid>,
         redeemed_at: DateTime<Utc>,
     },
+    CreditBalanceChanged {
+        user_id: Uuid,
+        /// What changed it, e.g. `credits_redeemed` or `hold_placed`
+        reason: String,
+        balance: CreditBalance,
+        changed_at: DateTime<Utc>,
+    },
     
+    // Platform events
+    PromotionStarted {
+        promotion_id: Uuid,
+        name: String,
+        kind: String,
+        value: rust_decimal::Decimal,
+        ends_at: DateTime<Utc>,
+        started_at: DateTime<Utc>,
+    },
+    PromotionEnded {
+        promotion_id: Uuid,
+        name: String,
+        ended_at: DateTime<Utc>,
+    },
+
     // System events
     SystemMaintenance {
         message: String,
@@ -140,7 +332,16 @@ pub enum RealtimeEvent {
     },
 }
 
-#[derive(Debug, Clone, Message)]
+/// A box sold to a user, as applied to a live grid
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GridBoxChange {
+    pub box_number: i32,
+    pub user_id: Uuid,
+    /// Shown by the owner's winner display rule, as on the grid
+    pub username: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Message)]
 #[rtype(result = "()")]
 pub struct WebSocketMessage {
     pub message_type: String,
@@ -148,9 +349,19 @@ pub struct WebSocketMessage {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Ask a connection's actor to close the socket
+#[derive(Debug, Clone, Message)]
+#[rtype(result = "()")]
+pub struct Disconnect {
+    pub reason: String,
+}
+
+/// A client message in either envelope: `message_type`/`data` or, from version 2, `type`/`payload`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClientMessage {
+    #[serde(alias = "type")]
     pub message_type: String,
+    #[serde(alias = "payload", default)]
     pub data: serde_json::Value,
 }
 
@@ -174,12 +385,83 @@ pub struct LeaveRoomMessage {
     pub room: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+/// Connection stats summed over every replica; `instances` breaks them down per replica
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
 pub struct ConnectionStats {
     pub total_connections: usize,
     pub authenticated_connections: usize,
+    /// Connections in each room
     pub active_rooms: HashMap<String, usize>,
+    /// Connections subscribed to each event type
+    pub subscriptions_by_event_type: HashMap<String, usize>,
     pub events_sent_last_hour: u64,
+    pub messages_sent_last_minute: u64,
+    pub messages_received_last_hour: u64,
+    pub messages_received_last_minute: u64,
+    /// Messages dropped for full mailboxes by the connections open now
+    pub messages_dropped: u64,
+    pub slow_consumers: usize,
+    pub oldest_connection_seconds: Option<u64>,
+    pub average_connection_seconds: Option<u64>,
+    /// One entry per replica that reported in the last minute; empty without Redis
+    #[serde(default)]
+    pub instances: Vec<InstanceConnectionStats>,
+}
+
+/// One replica's share of the connection stats
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InstanceConnectionStats {
+    pub instance_id: Uuid,
+    /// Whether this is the replica that answered the request
+    pub current: bool,
+    pub reported_at: DateTime<Utc>,
+    pub total_connections: usize,
+    pub authenticated_connections: usize,
+    pub messages_sent_last_minute: u64,
+    pub messages_dropped: u64,
+    pub slow_consumers: usize,
+}
+
+/// One connection as shown to admins
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ConnectionDetails {
+    pub connection_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub rooms: Vec<String>,
+    pub subscriptions: Vec<String>,
+    pub connected_seconds: u64,
+    /// Seconds since the client last pinged
+    pub idle_seconds: u64,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    pub messages_dropped: u64,
+    pub slow_consumer: bool,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ConnectionListQuery {
+    pub user_id: Option<Uuid>,
+    /// Only connections at or over the slow consumer drop threshold
+    #[serde(default)]
+    pub slow_only: bool,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ChannelBroadcastRequest {
+    /// A room such as `raffle:{id}`, a subscription room, or `all`
+    pub channel: String,
+    pub message: String,
+    /// Defaults to `info`
+    pub level: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct DisconnectRequest {
+    pub reason: Option<String>,
 }
 
 impl RealtimeService {
@@ -192,6 +474,8 @@ impl RealtimeService {
             connections: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
             event_sender,
             _event_receiver: event_receiver,
+            throughput: Arc::new(Mutex::new(MessageThroughput::default())),
+            relay: None,
         };
 
         // Start background tasks
@@ -208,11 +492,71 @@ impl RealtimeService {
         service
     }
 
+    /// Share events and announcements with the other replicas over Redis pub/sub
+    pub fn with_relay(mut self, relay: RealtimeRelay) -> Self {
+        self.relay = Some(relay);
+        self
+    }
+
+    /// Publish this replica's events, deliver the other replicas' and report connection stats
+    pub async fn start_relay(&self) {
+        let Some(relay) = self.relay.clone() else { return };
+        let instance_id = relay.instance_id();
+
+        // A single forwarder keeps events in the order they were raised, which grid sequences rely on
+        let forwarder = relay.clone();
+        let mut events = self.event_sender.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => forwarder.publish(RelayPayload::event(&event)).await,
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Realtime relay fell {} events behind; other replicas missed them", missed);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+
+        let service = self.clone();
+        let follower = relay.clone();
+        tokio::spawn(async move {
+            loop {
+                let result = follower
+                    .follow(|payload| {
+                        let service = service.clone();
+                        async move { service.deliver_relayed(payload).await }
+                    })
+                    .await;
+                if let Err(e) = result {
+                    warn!("Realtime relay subscription dropped, resubscribing: {}", e);
+                }
+                tokio::time::sleep(realtime_relay::RESUBSCRIBE_DELAY).await;
+            }
+        });
+
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(realtime_relay::STATS_REPORT_INTERVAL);
+            loop {
+                interval.tick().await;
+                let stats = service.local_connection_stats().await;
+                if let Err(e) = relay.report_stats(&stats).await {
+                    warn!("Failed to report WebSocket connection stats: {}", e);
+                }
+            }
+        });
+
+        info!("Realtime relay started for instance {}", instance_id);
+    }
+
     /// Add a new WebSocket connection
     pub async fn add_connection(
         &self,
         connection_id: Uuid,
         addr: Recipient<WebSocketMessage>,
+        closer: Recipient<Disconnect>,
+        counters: Arc<ConnectionCounters>,
         user_agent: Option<String>,
         ip_address: Option<String>,
     ) {
@@ -220,7 +564,10 @@ impl RealtimeService {
             id: connection_id,
             user_id: None,
             addr,
+            closer,
+            counters,
             subscriptions: Vec::new(),
+            rooms: HashSet::new(),
             connected_at: Instant::now(),
             last_ping: Instant::now(),
             user_agent,
@@ -260,6 +607,8 @@ impl RealtimeService {
         let mut connections = self.connections.write().await;
         if let Some(connection) = connections.get_mut(connection_id) {
             connection.user_id = Some(user_id);
+            // Private rooms joined under another account are dropped
+            connection.rooms.retain(|room| room.allows(Some(user_id)));
             
             // Get user details for broadcast
             if let Ok(Some(user)) = User::find_by_id(&self.db_pool, user_id).await {
@@ -294,6 +643,45 @@ impl RealtimeService {
         }
     }
 
+    /// Join a room; returns the connection's rooms afterwards
+    pub async fn join_room(&self, connection_id: &Uuid, name: &str) -> Result<Vec<String>, AppError> {
+        let room = Room::parse(name)?;
+        let max_rooms = self.max_rooms_per_connection().await;
+
+        let mut connections = self.connections.write().await;
+        let connection = connections.get_mut(connection_id)
+            .ok_or_else(|| AppError::NotFound("Connection not found".to_string()))?;
+
+        if !room.allows(connection.user_id) {
+            return Err(AppError::Authorization(format!("Authenticate as the room's user to join {}", room)));
+        }
+        if !connection.rooms.contains(&room) && connection.rooms.len() as i64 >= max_rooms {
+            return Err(AppError::Validation(format!(
+                "A connection can join at most {} rooms",
+                max_rooms
+            )));
+        }
+
+        connection.rooms.insert(room);
+        debug!("Connection {} joined room {}", connection_id, room);
+
+        Ok(room_names(&connection.rooms))
+    }
+
+    /// Leave a room; returns the connection's rooms afterwards
+    pub async fn leave_room(&self, connection_id: &Uuid, name: &str) -> Result<Vec<String>, AppError> {
+        let room = Room::parse(name)?;
+
+        let mut connections = self.connections.write().await;
+        let connection = connections.get_mut(connection_id)
+            .ok_or_else(|| AppError::NotFound("Connection not found".to_string()))?;
+
+        connection.rooms.remove(&room);
+        debug!("Connection {} left room {}", connection_id, room);
+
+        Ok(room_names(&connection.rooms))
+    }
+
     /// Update ping timestamp for a connection
     pub async fn update_ping(&self, connection_id: &Uuid) {
         let mut connections = self.connections.write().await;
@@ -302,12 +690,25 @@ impl RealtimeService {
         }
     }
 
+    /// Count a message received from a connection
+    pub fn record_received(&self, counters: &ConnectionCounters) {
+        counters.messages_received.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut throughput) = self.throughput.lock() {
+            throughput.received.record(current_minute(), 1);
+        }
+    }
+
     /// Broadcast an event to all relevant connections
     pub async fn broadcast_event(&self, event: RealtimeEvent) -> Result<(), AppError> {
         let _ = self.event_sender.send(event);
         Ok(())
     }
 
+    /// Receive every event broadcast from now on, for in-process listeners
+    pub fn subscribe(&self) -> broadcast::Receiver<RealtimeEvent> {
+        self.event_sender.subscribe()
+    }
+
     /// Broadcast raffle box purchase event
     pub async fn broadcast_box_purchase(
         &self,
@@ -348,11 +749,13 @@ impl RealtimeService {
         raffle_id: Uuid,
         item_id: Uuid,
         winner_user_ids: Vec<Uuid>,
+        winners: Vec<PublicWinner>,
     ) -> Result<(), AppError> {
         self.broadcast_event(RealtimeEvent::WinnerSelected {
             raffle_id,
             item_id,
             winner_user_ids,
+            winners,
             completed_at: Utc::now(),
         }).await
     }
@@ -372,42 +775,271 @@ impl RealtimeService {
         }).await
     }
 
-    /// Get connection statistics
+    /// Users with at least one authenticated connection
+    pub async fn connected_user_ids(&self) -> Vec<Uuid> {
+        let connections = self.connections.read().await;
+        let mut user_ids: Vec<Uuid> = connections.values().filter_map(|c| c.user_id).collect();
+        user_ids.sort();
+        user_ids.dedup();
+        user_ids
+    }
+
+    /// Whether the user has at least one authenticated connection
+    pub async fn is_user_connected(&self, user_id: Uuid) -> bool {
+        self.connections.read().await.values().any(|c| c.user_id == Some(user_id))
+    }
+
+    /// Connection statistics across every replica, or this one's alone when there is no relay
+    /// or Redis can't be reached
     pub async fn get_connection_stats(&self) -> ConnectionStats {
+        let local = self.local_connection_stats().await;
+        let Some(relay) = &self.relay else { return local };
+
+        let mut reports = match relay.instance_reports().await {
+            Ok(reports) => reports,
+            Err(e) => {
+                warn!("Failed to read other replicas' WebSocket stats: {}", e);
+                Vec::new()
+            }
+        };
+
+        // This replica's report may be up to a reporting interval old
+        reports.retain(|report| report.instance_id != relay.instance_id());
+        reports.push(InstanceReport {
+            instance_id: relay.instance_id(),
+            reported_at: Utc::now(),
+            stats: local,
+        });
+
+        merge_connection_stats(&reports, relay.instance_id())
+    }
+
+    /// This replica's connection statistics
+    pub async fn local_connection_stats(&self) -> ConnectionStats {
+        let drop_threshold = self.slow_consumer_drop_threshold().await;
         let connections = self.connections.read().await;
         let total_connections = connections.len();
         let authenticated_connections = connections.values()
             .filter(|c| c.user_id.is_some())
             .count();
 
-        // Count active rooms
+        // Count active rooms and subscriptions
         let mut active_rooms = HashMap::new();
+        let mut subscriptions_by_event_type = HashMap::new();
         for connection in connections.values() {
             for subscription in &connection.subscriptions {
                 if let Some(room) = &subscription.room {
                     *active_rooms.entry(room.clone()).or_insert(0) += 1;
                 }
+                *subscriptions_by_event_type.entry(subscription.event_type.clone()).or_insert(0) += 1;
+            }
+            for room in &connection.rooms {
+                *active_rooms.entry(room.to_string()).or_insert(0) += 1;
             }
         }
 
+        let ages: Vec<u64> = connections.values().map(|c| c.connected_at.elapsed().as_secs()).collect();
+        let messages_dropped = connections.values()
+            .map(|c| c.counters.messages_dropped.load(Ordering::Relaxed))
+            .sum();
+        let slow_consumers = connections.values()
+            .filter(|c| is_slow_consumer(c.counters.consecutive_drops.load(Ordering::Relaxed), drop_threshold))
+            .count();
+        drop(connections);
+
+        let minute = current_minute();
+        let (events_sent_last_hour, messages_sent_last_minute, messages_received_last_hour, messages_received_last_minute) =
+            match self.throughput.lock() {
+                Ok(throughput) => (
+                    throughput.sent.total(minute, THROUGHPUT_WINDOW_MINUTES),
+                    throughput.sent.total(minute, 1),
+                    throughput.received.total(minute, THROUGHPUT_WINDOW_MINUTES),
+                    throughput.received.total(minute, 1),
+                ),
+                Err(_) => (0, 0, 0, 0),
+            };
+
         ConnectionStats {
             total_connections,
             authenticated_connections,
             active_rooms,
-            events_sent_last_hour: 0, // Would track this in production
+            subscriptions_by_event_type,
+            events_sent_last_hour,
+            messages_sent_last_minute,
+            messages_received_last_hour,
+            messages_received_last_minute,
+            messages_dropped,
+            slow_consumers,
+            oldest_connection_seconds: ages.iter().max().copied(),
+            average_connection_seconds: if ages.is_empty() {
+                None
+            } else {
+                Some(ages.iter().sum::<u64>() / ages.len() as u64)
+            },
+            instances: Vec::new(),
         }
     }
 
+    /// Open connections, longest-connected first
+    pub async fn list_connections(&self, query: &ConnectionListQuery) -> Vec<ConnectionDetails> {
+        let drop_threshold = self.slow_consumer_drop_threshold().await;
+        let connections = self.connections.read().await;
+
+        let mut details: Vec<ConnectionDetails> = connections.values()
+            .filter(|c| query.user_id.map_or(true, |user_id| c.user_id == Some(user_id)))
+            .map(|c| {
+                let consecutive_drops = c.counters.consecutive_drops.load(Ordering::Relaxed);
+                let mut subscriptions: Vec<String> = c.subscriptions.iter().map(|s| s.event_type.clone()).collect();
+                subscriptions.sort();
+                subscriptions.dedup();
+
+                ConnectionDetails {
+                    connection_id: c.id,
+                    user_id: c.user_id,
+                    rooms: room_names(&c.rooms),
+                    subscriptions,
+                    connected_seconds: c.connected_at.elapsed().as_secs(),
+                    idle_seconds: c.last_ping.elapsed().as_secs(),
+                    messages_sent: c.counters.messages_sent.load(Ordering::Relaxed),
+                    messages_received: c.counters.messages_received.load(Ordering::Relaxed),
+                    messages_dropped: c.counters.messages_dropped.load(Ordering::Relaxed),
+                    slow_consumer: is_slow_consumer(consecutive_drops, drop_threshold),
+                    user_agent: c.user_agent.clone(),
+                    ip_address: c.ip_address.clone(),
+                }
+            })
+            .filter(|d| !query.slow_only || d.slow_consumer)
+            .collect();
+
+        details.sort_by(|a, b| b.connected_seconds.cmp(&a.connected_seconds));
+        details.truncate(query.limit.unwrap_or(100).clamp(1, 1000));
+        details
+    }
+
+    /// Send an admin announcement to a channel; returns the connections it reached
+    pub async fn broadcast_to_channel(
+        &self,
+        admin_id: Uuid,
+        request: ChannelBroadcastRequest,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<usize, AppError> {
+        let channel = request.channel.trim().to_string();
+        let message = request.message.trim().to_string();
+        if channel.is_empty() {
+            return Err(AppError::Validation("Channel is required".to_string()));
+        }
+        if message.is_empty() {
+            return Err(AppError::Validation("Message is required".to_string()));
+        }
+        let level = request.level.unwrap_or_else(|| "info".to_string());
+
+        let ws_message = WebSocketMessage {
+            message_type: "announcement".to_string(),
+            data: serde_json::json!({
+                "channel": channel,
+                "level": level,
+                "message": message
+            }),
+            timestamp: Utc::now(),
+        };
+
+        let reached = self.deliver_announcement(&channel, ws_message.clone()).await;
+        if let Some(relay) = &self.relay {
+            relay.publish(RelayPayload::Announcement { channel: channel.clone(), message: ws_message }).await;
+        }
+
+        info!("Admin {} broadcast to {} reached {} connections on this instance", admin_id, channel, reached);
+
+        AuditLog::create(
+            &self.db_pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("websocket_broadcast".to_string()),
+            None,
+            None,
+            Some(serde_json::json!({
+                "channel": channel,
+                "level": level,
+                "message": message,
+                "reached": reached
+            })),
+            ip_address,
+            user_agent,
+        )
+        .await?;
+
+        Ok(reached)
+    }
+
+    /// Close a connection from the server side
+    pub async fn disconnect_connection(
+        &self,
+        admin_id: Uuid,
+        connection_id: Uuid,
+        reason: Option<String>,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<(), AppError> {
+        let connection = self.connections.write().await
+            .remove(&connection_id)
+            .ok_or_else(|| AppError::NotFound("Connection not found".to_string()))?;
+
+        let reason = reason.unwrap_or_else(|| "Disconnected by an administrator".to_string());
+        self.close(&connection, &reason);
+
+        warn!("Admin {} disconnected WebSocket connection {}", admin_id, connection_id);
+
+        self.audit_disconnect(admin_id, connection.user_id, &[connection_id], &reason, ip_address, user_agent)
+            .await
+    }
+
+    /// Close all of a user's connections; returns how many were closed
+    pub async fn disconnect_user(
+        &self,
+        admin_id: Uuid,
+        user_id: Uuid,
+        reason: Option<String>,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<usize, AppError> {
+        let closed: Vec<ConnectionInfo> = {
+            let mut connections = self.connections.write().await;
+            let ids: Vec<Uuid> = connections.values()
+                .filter(|c| c.user_id == Some(user_id))
+                .map(|c| c.id)
+                .collect();
+            ids.iter().filter_map(|id| connections.remove(id)).collect()
+        };
+
+        if closed.is_empty() {
+            return Err(AppError::NotFound("User has no open connections".to_string()));
+        }
+
+        let reason = reason.unwrap_or_else(|| "Disconnected by an administrator".to_string());
+        for connection in &closed {
+            self.close(connection, &reason);
+        }
+
+        warn!("Admin {} disconnected {} WebSocket connections for user {}", admin_id, closed.len(), user_id);
+
+        let ids: Vec<Uuid> = closed.iter().map(|c| c.id).collect();
+        self.audit_disconnect(admin_id, Some(user_id), &ids, &reason, ip_address, user_agent)
+            .await?;
+
+        Ok(closed.len())
+    }
+
     /// Send message to specific user
     pub async fn send_to_user(&self, user_id: Uuid, message: WebSocketMessage) -> Result<(), AppError> {
         let connections = self.connections.read().await;
         let mut sent = false;
 
         for connection in connections.values() {
-            if connection.user_id == Some(user_id) {
-                if connection.addr.try_send(message.clone()).is_ok() {
-                    sent = true;
-                }
+            if connection.user_id == Some(user_id)
+                && self.deliver(connection, message.clone()) == Delivery::Sent
+            {
+                sent = true;
             }
         }
 
@@ -425,12 +1057,11 @@ impl RealtimeService {
 
         for connection in connections.values() {
             let in_room = connection.subscriptions.iter()
-                .any(|s| s.room.as_ref() == Some(&room.to_string()));
+                .any(|s| s.room.as_ref() == Some(&room.to_string()))
+                || connection.rooms.iter().any(|r| r.to_string() == room);
 
-            if in_room {
-                if connection.addr.try_send(message.clone()).is_ok() {
-                    sent_count += 1;
-                }
+            if in_room && self.deliver(connection, message.clone()) == Delivery::Sent {
+                sent_count += 1;
             }
         }
 
@@ -439,6 +1070,30 @@ impl RealtimeService {
 
     // Private helper methods
 
+    /// Deliver what another replica published to this replica's connections
+    async fn deliver_relayed(&self, payload: RelayPayload) {
+        match payload {
+            RelayPayload::Event { event, winner_user_ids } => {
+                self.process_and_broadcast_event(realtime_relay::relayed_event(event, winner_user_ids)).await;
+            }
+            RelayPayload::Announcement { channel, message } => {
+                let reached = self.deliver_announcement(&channel, message).await;
+                debug!("Relayed broadcast to {} reached {} connections", channel, reached);
+            }
+        }
+    }
+
+    async fn deliver_announcement(&self, channel: &str, message: WebSocketMessage) -> usize {
+        if channel == ALL_CONNECTIONS_CHANNEL {
+            let connections = self.connections.read().await;
+            connections.values()
+                .filter(|c| self.deliver(c, message.clone()) == Delivery::Sent)
+                .count()
+        } else {
+            self.send_to_room(channel, message).await.unwrap_or(0)
+        }
+    }
+
     async fn start_event_broadcasting(&self) {
         let mut event_receiver = self.event_sender.subscribe();
         
@@ -458,8 +1113,9 @@ impl RealtimeService {
         for (connection_id, connection) in connections.iter() {
             if self.should_send_event_to_connection(connection, &event) {
                 let message = self.create_websocket_message(&event);
-                
-                if connection.addr.try_send(message).is_err() {
+
+                // Slow consumers miss the event but stay connected; closed actors are removed
+                if self.deliver(connection, message) == Delivery::Closed {
                     failed_connections.push(*connection_id);
                 }
             }
@@ -477,6 +1133,12 @@ impl RealtimeService {
     }
 
     fn should_send_event_to_connection(&self, connection: &ConnectionInfo, event: &RealtimeEvent) -> bool {
+        if !connection.rooms.is_empty()
+            && event_rooms(event).iter().any(|room| connection.rooms.contains(room))
+        {
+            return true;
+        }
+
         if connection.subscriptions.is_empty() {
             return false;
         }
@@ -490,6 +1152,13 @@ impl RealtimeService {
                         }
                     }
                 }
+                RealtimeEvent::RaffleOpened { raffle_id, .. } => {
+                    if subscription.event_type == "raffle_opened" || subscription.event_type == "all" {
+                        if subscription.raffle_id.is_none() || subscription.raffle_id == Some(*raffle_id) {
+                            return true;
+                        }
+                    }
+                }
                 RealtimeEvent::BoxPurchased { raffle_id, user_id, .. } => {
                     if subscription.event_type == "box_purchased" || subscription.event_type == "all" {
                         if subscription.raffle_id.is_none() || subscription.raffle_id == Some(*raffle_id) {
@@ -500,6 +1169,20 @@ impl RealtimeService {
                         }
                     }
                 }
+                RealtimeEvent::GridChanged { raffle_id, .. } => {
+                    if subscription.event_type == "grid_diff" || subscription.event_type == "all" {
+                        if subscription.raffle_id.is_none() || subscription.raffle_id == Some(*raffle_id) {
+                            return true;
+                        }
+                    }
+                }
+                RealtimeEvent::OddsUpdated { raffle_id, .. } => {
+                    if subscription.event_type == "odds_updated" || subscription.event_type == "all" {
+                        if subscription.raffle_id.is_none() || subscription.raffle_id == Some(*raffle_id) {
+                            return true;
+                        }
+                    }
+                }
                 RealtimeEvent::WinnerSelected { raffle_id, winner_user_ids, .. } => {
                     if subscription.event_type == "winner_selected" || subscription.event_type == "all" {
                         if subscription.raffle_id.is_none() || subscription.raffle_id == Some(*raffle_id) {
@@ -512,6 +1195,14 @@ impl RealtimeService {
                         }
                     }
                 }
+                RealtimeEvent::CommentPosted { raffle_id, .. } |
+                RealtimeEvent::CommentRemoved { raffle_id, .. } => {
+                    if subscription.event_type == "comments" || subscription.event_type == "all" {
+                        if subscription.raffle_id.is_none() || subscription.raffle_id == Some(*raffle_id) {
+                            return true;
+                        }
+                    }
+                }
                 RealtimeEvent::ItemStockChanged { item_id, .. } => {
                     if subscription.event_type == "item_updated" || subscription.event_type == "all" {
                         if subscription.item_id.is_none() || subscription.item_id == Some(*item_id) {
@@ -520,13 +1211,20 @@ impl RealtimeService {
                     }
                 }
                 RealtimeEvent::CreditsIssued { user_id, .. } |
-                RealtimeEvent::CreditsRedeemed { user_id, .. } => {
+                RealtimeEvent::CreditsRedeemed { user_id, .. } |
+                RealtimeEvent::CreditBalanceChanged { user_id, .. } => {
                     if subscription.event_type == "credits" || subscription.event_type == "all" {
                         if connection.user_id == Some(*user_id) {
                             return true;
                         }
                     }
                 }
+                RealtimeEvent::PromotionStarted { .. } |
+                RealtimeEvent::PromotionEnded { .. } => {
+                    if subscription.event_type == "platform" || subscription.event_type == "all" {
+                        return true;
+                    }
+                }
                 _ => {
                     if subscription.event_type == "all" {
                         return true;
@@ -541,10 +1239,15 @@ impl RealtimeService {
     fn create_websocket_message(&self, event: &RealtimeEvent) -> WebSocketMessage {
         let (message_type, data) = match event {
             RealtimeEvent::RaffleCreated { .. } => ("raffle_created", serde_json::to_value(event).unwrap_or_default()),
+            RealtimeEvent::RaffleOpened { .. } => ("raffle_opened", serde_json::to_value(event).unwrap_or_default()),
             RealtimeEvent::BoxPurchased { .. } => ("box_purchased", serde_json::to_value(event).unwrap_or_default()),
+            RealtimeEvent::GridChanged { .. } => ("grid_diff", serde_json::to_value(event).unwrap_or_default()),
             RealtimeEvent::RaffleFull { .. } => ("raffle_full", serde_json::to_value(event).unwrap_or_default()),
+            RealtimeEvent::OddsUpdated { .. } => ("odds_updated", serde_json::to_value(event).unwrap_or_default()),
             RealtimeEvent::WinnerSelected { .. } => ("winner_selected", serde_json::to_value(event).unwrap_or_default()),
             RealtimeEvent::RaffleCancelled { .. } => ("raffle_cancelled", serde_json::to_value(event).unwrap_or_default()),
+            RealtimeEvent::CommentPosted { .. } => ("comment_posted", serde_json::to_value(event).unwrap_or_default()),
+            RealtimeEvent::CommentRemoved { .. } => ("comment_removed", serde_json::to_value(event).unwrap_or_default()),
             RealtimeEvent::ItemCreated { .. } => ("item_created", serde_json::to_value(event).unwrap_or_default()),
             RealtimeEvent::ItemUpdated { .. } => ("item_updated", serde_json::to_value(event).unwrap_or_default()),
             RealtimeEvent::ItemStockChanged { .. } => ("item_stock_changed", serde_json::to_value(event).unwrap_or_default()),
@@ -552,6 +1255,9 @@ impl RealtimeService {
             RealtimeEvent::UserLeft { .. } => ("user_left", serde_json::to_value(event).unwrap_or_default()),
             RealtimeEvent::CreditsIssued { .. } => ("credits_issued", serde_json::to_value(event).unwrap_or_default()),
             RealtimeEvent::CreditsRedeemed { .. } => ("credits_redeemed", serde_json::to_value(event).unwrap_or_default()),
+            RealtimeEvent::CreditBalanceChanged { .. } => ("credit_balance_changed", serde_json::to_value(event).unwrap_or_default()),
+            RealtimeEvent::PromotionStarted { .. } => ("promotion_started", serde_json::to_value(event).unwrap_or_default()),
+            RealtimeEvent::PromotionEnded { .. } => ("promotion_ended", serde_json::to_value(event).unwrap_or_default()),
             RealtimeEvent::SystemMaintenance { .. } => ("system_maintenance", serde_json::to_value(event).unwrap_or_default()),
             RealtimeEvent::SystemAlert { .. } => ("system_alert", serde_json::to_value(event).unwrap_or_default()),
         };
@@ -563,6 +1269,80 @@ impl RealtimeService {
         }
     }
 
+    fn deliver(&self, connection: &ConnectionInfo, message: WebSocketMessage) -> Delivery {
+        match connection.addr.try_send(message) {
+            Ok(()) => {
+                connection.counters.messages_sent.fetch_add(1, Ordering::Relaxed);
+                connection.counters.consecutive_drops.store(0, Ordering::Relaxed);
+                if let Ok(mut throughput) = self.throughput.lock() {
+                    throughput.sent.record(current_minute(), 1);
+                }
+                Delivery::Sent
+            }
+            Err(SendError::Full(_)) => {
+                connection.counters.messages_dropped.fetch_add(1, Ordering::Relaxed);
+                connection.counters.consecutive_drops.fetch_add(1, Ordering::Relaxed);
+                Delivery::Dropped
+            }
+            Err(SendError::Closed(_)) => Delivery::Closed,
+        }
+    }
+
+    fn close(&self, connection: &ConnectionInfo, reason: &str) {
+        if connection.closer.try_send(Disconnect { reason: reason.to_string() }).is_err() {
+            debug!("Connection {} was already closing", connection.id);
+        }
+    }
+
+    async fn audit_disconnect(
+        &self,
+        admin_id: Uuid,
+        user_id: Option<Uuid>,
+        connection_ids: &[Uuid],
+        reason: &str,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<(), AppError> {
+        AuditLog::create(
+            &self.db_pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("websocket_connection".to_string()),
+            user_id,
+            None,
+            Some(serde_json::json!({
+                "action": "disconnect",
+                "connection_ids": connection_ids,
+                "reason": reason
+            })),
+            ip_address,
+            user_agent,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn slow_consumer_drop_threshold(&self) -> u64 {
+        SystemSetting::get_by_key(&self.db_pool, "websocket_slow_consumer_drop_threshold")
+            .await
+            .ok()
+            .flatten()
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_SLOW_CONSUMER_DROP_THRESHOLD) as u64
+    }
+
+    async fn max_rooms_per_connection(&self) -> i64 {
+        SystemSetting::get_by_key(&self.db_pool, "websocket_max_rooms_per_connection")
+            .await
+            .ok()
+            .flatten()
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_MAX_ROOMS_PER_CONNECTION)
+    }
+
     async fn cleanup_stale_connections(&self) {
         let mut interval = tokio::time::interval(Duration::from_secs(60));
         
@@ -599,6 +1379,211 @@ impl RealtimeService {
         use crate::utils::jwt::JwtService;
         let jwt_service = JwtService::new()?;
         let claims = jwt_service.validate_token(token)?;
+        jwt_service.ensure_token_current(&claims, &self.db_pool).await?;
         Ok(claims.user_id)
     }
-}
\ No newline at end of file
+}
+
+/// Rooms an event is delivered to
+pub fn event_rooms(event: &RealtimeEvent) -> Vec<Room> {
+    match event {
+        RealtimeEvent::RaffleCreated { raffle_id, item_id, .. } |
+        RealtimeEvent::RaffleOpened { raffle_id, item_id, .. } |
+        RealtimeEvent::RaffleFull { raffle_id, item_id, .. } |
+        RealtimeEvent::RaffleCancelled { raffle_id, item_id, .. } => {
+            vec![Room::Raffle(*raffle_id), Room::Item(*item_id)]
+        }
+        RealtimeEvent::BoxPurchased { raffle_id, user_id, .. } => {
+            vec![Room::Raffle(*raffle_id), Room::User(*user_id)]
+        }
+        RealtimeEvent::WinnerSelected { raffle_id, item_id, winner_user_ids, .. } => {
+            let mut rooms = vec![Room::Raffle(*raffle_id), Room::Item(*item_id)];
+            rooms.extend(winner_user_ids.iter().map(|id| Room::User(*id)));
+            rooms
+        }
+        RealtimeEvent::GridChanged { raffle_id, .. } |
+        RealtimeEvent::OddsUpdated { raffle_id, .. } |
+        RealtimeEvent::CommentPosted { raffle_id, .. } |
+        RealtimeEvent::CommentRemoved { raffle_id, .. } => vec![Room::Raffle(*raffle_id)],
+        RealtimeEvent::ItemCreated { item_id, .. } |
+        RealtimeEvent::ItemUpdated { item_id, .. } |
+        RealtimeEvent::ItemStockChanged { item_id, .. } => vec![Room::Item(*item_id)],
+        RealtimeEvent::CreditsIssued { user_id, .. } |
+        RealtimeEvent::CreditsRedeemed { user_id, .. } |
+        RealtimeEvent::CreditBalanceChanged { user_id, .. } => vec![Room::User(*user_id)],
+        _ => Vec::new(),
+    }
+}
+
+/// Sum replicas' reports into cluster-wide stats, keeping each replica's share
+pub fn merge_connection_stats(reports: &[InstanceReport], current_instance: Uuid) -> ConnectionStats {
+    let mut merged = ConnectionStats::default();
+    let mut total_age: u64 = 0;
+
+    for report in reports {
+        let stats = &report.stats;
+        merged.total_connections += stats.total_connections;
+        merged.authenticated_connections += stats.authenticated_connections;
+        for (room, count) in &stats.active_rooms {
+            *merged.active_rooms.entry(room.clone()).or_insert(0) += count;
+        }
+        for (event_type, count) in &stats.subscriptions_by_event_type {
+            *merged.subscriptions_by_event_type.entry(event_type.clone()).or_insert(0) += count;
+        }
+        merged.events_sent_last_hour += stats.events_sent_last_hour;
+        merged.messages_sent_last_minute += stats.messages_sent_last_minute;
+        merged.messages_received_last_hour += stats.messages_received_last_hour;
+        merged.messages_received_last_minute += stats.messages_received_last_minute;
+        merged.messages_dropped += stats.messages_dropped;
+        merged.slow_consumers += stats.slow_consumers;
+        merged.oldest_connection_seconds = merged.oldest_connection_seconds.max(stats.oldest_connection_seconds);
+        total_age += stats.average_connection_seconds.unwrap_or(0) * stats.total_connections as u64;
+
+        merged.instances.push(InstanceConnectionStats {
+            instance_id: report.instance_id,
+            current: report.instance_id == current_instance,
+            reported_at: report.reported_at,
+            total_connections: stats.total_connections,
+            authenticated_connections: stats.authenticated_connections,
+            messages_sent_last_minute: stats.messages_sent_last_minute,
+            messages_dropped: stats.messages_dropped,
+            slow_consumers: stats.slow_consumers,
+        });
+    }
+
+    if merged.total_connections > 0 {
+        merged.average_connection_seconds = Some(total_age / merged.total_connections as u64);
+    }
+    merged.instances.sort_by_key(|instance| instance.instance_id);
+    merged
+}
+
+/// A connection that has missed this many messages in a row is falling behind
+pub fn is_slow_consumer(consecutive_drops: u64, drop_threshold: u64) -> bool {
+    consecutive_drops >= drop_threshold
+}
+
+fn current_minute() -> i64 {
+    Utc::now().timestamp().div_euclid(60)
+}
+
+fn room_names(rooms: &HashSet<Room>) -> Vec<String> {
+    let mut names: Vec<String> = rooms.iter().map(|room| room.to_string()).collect();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_room_parse() {
+        let id = Uuid::new_v4();
+        assert_eq!(Room::parse(&format!("raffle:{}", id)).unwrap(), Room::Raffle(id));
+        assert_eq!(Room::parse(&format!("user:{}", id)).unwrap().to_string(), format!("user:{}", id));
+        assert!(Room::parse("raffle:not-a-uuid").is_err());
+        assert!(Room::parse(&format!("seller:{}", id)).is_err());
+        assert!(Room::parse("raffle").is_err());
+    }
+
+    #[test]
+    fn test_private_rooms_need_their_user() {
+        let id = Uuid::new_v4();
+        assert!(!Room::User(id).allows(None));
+        assert!(!Room::User(id).allows(Some(Uuid::new_v4())));
+        assert!(Room::User(id).allows(Some(id)));
+        assert!(Room::Raffle(id).allows(None));
+    }
+
+    #[test]
+    fn test_event_rooms() {
+        let raffle_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        let rooms = event_rooms(&RealtimeEvent::BoxPurchased {
+            raffle_id,
+            user_id,
+            box_number: 1,
+            boxes_remaining: 9,
+            completion_percentage: 10.0,
+            purchased_at: Utc::now(),
+        });
+        assert_eq!(rooms, vec![Room::Raffle(raffle_id), Room::User(user_id)]);
+
+        let rooms = event_rooms(&RealtimeEvent::SystemAlert {
+            level: "info".to_string(),
+            message: "hello".to_string(),
+            created_at: Utc::now(),
+        });
+        assert!(rooms.is_empty());
+    }
+
+    #[test]
+    fn test_throughput_window() {
+        let mut window = ThroughputWindow::default();
+        window.record(1000, 3);
+        window.record(1000, 2);
+        window.record(1030, 4);
+
+        assert_eq!(window.total(1030, 1), 4);
+        assert_eq!(window.total(1030, THROUGHPUT_WINDOW_MINUTES), 9);
+        assert_eq!(window.total(1059, THROUGHPUT_WINDOW_MINUTES), 9);
+        assert_eq!(window.total(1060, THROUGHPUT_WINDOW_MINUTES), 4);
+
+        // Recording prunes buckets that have left the window
+        window.record(1061, 1);
+        assert_eq!(window.buckets.len(), 2);
+        assert_eq!(window.total(1061, THROUGHPUT_WINDOW_MINUTES), 5);
+    }
+
+    #[test]
+    fn test_merge_connection_stats() {
+        let report = |total: usize, average: u64, oldest: u64| {
+            let mut stats = ConnectionStats {
+                total_connections: total,
+                authenticated_connections: total / 2,
+                messages_dropped: 3,
+                slow_consumers: 1,
+                oldest_connection_seconds: Some(oldest),
+                average_connection_seconds: Some(average),
+                ..ConnectionStats::default()
+            };
+            stats.active_rooms.insert("raffle:1".to_string(), total);
+            InstanceReport {
+                instance_id: Uuid::new_v4(),
+                reported_at: Utc::now(),
+                stats,
+            }
+        };
+        let reports = vec![report(10, 60, 600), report(30, 20, 900)];
+
+        let merged = merge_connection_stats(&reports, reports[1].instance_id);
+
+        assert_eq!(merged.total_connections, 40);
+        assert_eq!(merged.authenticated_connections, 20);
+        assert_eq!(merged.active_rooms.get("raffle:1"), Some(&40));
+        assert_eq!(merged.messages_dropped, 6);
+        assert_eq!(merged.slow_consumers, 2);
+        assert_eq!(merged.oldest_connection_seconds, Some(900));
+        // Weighted by each replica's connections: (10 * 60 + 30 * 20) / 40
+        assert_eq!(merged.average_connection_seconds, Some(30));
+        assert_eq!(merged.instances.len(), 2);
+        assert_eq!(merged.instances.iter().filter(|i| i.current).count(), 1);
+    }
+
+    #[test]
+    fn test_merge_without_connections() {
+        let merged = merge_connection_stats(&[], Uuid::new_v4());
+
+        assert_eq!(merged.total_connections, 0);
+        assert_eq!(merged.average_connection_seconds, None);
+        assert!(merged.instances.is_empty());
+    }
+
+    #[test]
+    fn test_is_slow_consumer() {
+        assert!(!is_slow_consumer(0, 20));
+        assert!(!is_slow_consumer(19, 20));
+        assert!(is_slow_consumer(20, 20));
+    }
+}
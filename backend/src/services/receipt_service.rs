@@ -0,0 +1,363 @@
+use crate::error::AppError;
+use crate::models::box_purchase::BoxPurchase;
+use crate::models::item::Item;
+use crate::models::raffle::Raffle;
+use crate::models::system_settings::SystemSettings;
+use crate::services::outbox_service::OutboxMessage;
+use crate::utils::crypto::{generate_random_string, hash_with_salt};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Receipt service generates and persists receipts for box purchases.
+///
+/// A checkout gets its receipt when it commits (see `create_receipt`); purchases from before
+/// that get theirs on first request.
+#[derive(Clone)]
+pub struct ReceiptService {
+    db_pool: PgPool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxLine {
+    pub name: String,
+    pub rate_percentage: Decimal,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PurchaseReceipt {
+    pub id: Uuid,
+    pub receipt_number: String,
+    pub verification_code: String,
+    pub user_id: Uuid,
+    pub raffle_id: Uuid,
+    /// First box of the checkout
+    pub purchase_id: Uuid,
+    /// The checkout the receipt covers; one receipt per checkout
+    pub transaction_id: Uuid,
+    pub box_numbers: Vec<i32>,
+    pub subtotal: Decimal,
+    pub credits_used: Decimal,
+    pub tax_lines: serde_json::Value,
+    pub total_amount: Decimal,
+    pub currency: String,
+    pub emailed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ReceiptService {
+    /// Create a new receipt service
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+
+    /// Get the receipt for a purchase's checkout, generating and emailing it for purchases
+    /// made before receipts were issued at checkout
+    pub async fn get_or_create_receipt(
+        &self,
+        user_id: Uuid,
+        raffle_id: Uuid,
+        purchase_id: Uuid,
+    ) -> Result<PurchaseReceipt, AppError> {
+        let purchase = BoxPurchase::find_by_id(&self.db_pool, purchase_id).await?
+            .ok_or_else(|| AppError::NotFound("Purchase not found".to_string()))?;
+
+        if purchase.raffle_id != raffle_id {
+            return Err(AppError::NotFound("Purchase not found".to_string()));
+        }
+
+        if purchase.user_id != user_id {
+            return Err(AppError::Forbidden("Access denied".to_string()));
+        }
+
+        let transaction_id = receipt_transaction_id(&purchase);
+        if let Some(receipt) = self.find_by_transaction(transaction_id).await? {
+            return Ok(receipt);
+        }
+
+        let boxes: Vec<ReceiptBox> = self
+            .checkout_purchases(&purchase)
+            .await?
+            .iter()
+            .map(|p| ReceiptBox {
+                purchase_id: p.id,
+                box_number: p.box_number,
+                price: p.purchase_price_in_credits,
+            })
+            .collect();
+        let tax_rate = SystemSettings::get_sales_tax_percentage(&self.db_pool).await?;
+        let item_title = self.item_title(raffle_id).await?;
+
+        let mut tx = self.db_pool.begin().await?;
+        let Some(receipt) = create_receipt(&mut tx, user_id, raffle_id, transaction_id, &boxes, tax_rate).await? else {
+            // A concurrent request created it first
+            tx.rollback().await?;
+            return self
+                .find_by_transaction(transaction_id)
+                .await?
+                .ok_or_else(|| AppError::Internal("Receipt disappeared after creation".to_string()));
+        };
+        OutboxMessage::enqueue(&mut tx, &[receipt_email(&receipt, item_title)]).await?;
+        tx.commit().await?;
+
+        Ok(receipt)
+    }
+
+    /// Look up a receipt by its public verification code
+    pub async fn verify_receipt(&self, verification_code: &str) -> Result<ReceiptVerification, AppError> {
+        let receipt = sqlx::query_as!(
+            PurchaseReceipt,
+            r#"
+            SELECT id, receipt_number, verification_code, user_id, raffle_id, purchase_id, transaction_id,
+                   box_numbers, subtotal, credits_used, tax_lines, total_amount, currency, emailed_at,
+                   created_at as "created_at!"
+            FROM purchase_receipts
+            WHERE verification_code = $1
+            "#,
+            verification_code
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Receipt not found".to_string()))?;
+
+        Ok(ReceiptVerification::from(receipt))
+    }
+
+    // Private helper methods
+
+    async fn find_by_transaction(&self, transaction_id: Uuid) -> Result<Option<PurchaseReceipt>, AppError> {
+        let receipt = sqlx::query_as!(
+            PurchaseReceipt,
+            r#"
+            SELECT id, receipt_number, verification_code, user_id, raffle_id, purchase_id, transaction_id,
+                   box_numbers, subtotal, credits_used, tax_lines, total_amount, currency, emailed_at,
+                   created_at as "created_at!"
+            FROM purchase_receipts
+            WHERE transaction_id = $1
+            "#,
+            transaction_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(receipt)
+    }
+
+    /// Every box bought in the purchase's checkout
+    async fn checkout_purchases(&self, purchase: &BoxPurchase) -> Result<Vec<BoxPurchase>, AppError> {
+        let Some(transaction_id) = purchase.transaction_id else {
+            return Ok(vec![purchase.clone()]);
+        };
+
+        let purchases = sqlx::query_as!(
+            BoxPurchase,
+            r#"
+            SELECT id, raffle_id, user_id, box_number, purchase_price_in_credits, transaction_id, blockchain_tx_hash, created_at
+            FROM box_purchases
+            WHERE transaction_id = $1 AND user_id = $2
+            ORDER BY box_number ASC
+            "#,
+            transaction_id,
+            purchase.user_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(purchases)
+    }
+
+    async fn item_title(&self, raffle_id: Uuid) -> Result<String, AppError> {
+        let title = match Raffle::find_by_id(&self.db_pool, raffle_id).await? {
+            Some(raffle) => Item::find_by_id(&self.db_pool, raffle.item_id).await?
+                .map(|item| item.name)
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+
+        Ok(title)
+    }
+}
+
+/// What the public can see of a receipt when checking its verification code
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReceiptVerification {
+    pub receipt_number: String,
+    pub raffle_id: Uuid,
+    pub box_numbers: Vec<i32>,
+    pub total_amount: Decimal,
+    pub currency: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<PurchaseReceipt> for ReceiptVerification {
+    fn from(receipt: PurchaseReceipt) -> Self {
+        Self {
+            receipt_number: receipt.receipt_number,
+            raffle_id: receipt.raffle_id,
+            box_numbers: receipt.box_numbers,
+            total_amount: receipt.total_amount,
+            currency: receipt.currency,
+            created_at: receipt.created_at,
+        }
+    }
+}
+
+/// Receipts are keyed by checkout; purchases from before checkouts had an ID stand alone
+pub fn receipt_transaction_id(purchase: &BoxPurchase) -> Uuid {
+    purchase.transaction_id.unwrap_or(purchase.id)
+}
+
+/// Subtotal, tax lines and total of a checkout. Box prices include tax, so the total is
+/// what the boxes sold for and the tax is backed out of it.
+pub fn receipt_totals(prices: &[Decimal], tax_rate: Decimal) -> (Decimal, Vec<TaxLine>, Decimal) {
+    let total: Decimal = prices.iter().sum();
+
+    let tax_lines = if tax_rate > Decimal::ZERO {
+        vec![TaxLine {
+            name: "Sales tax (included)".to_string(),
+            rate_percentage: tax_rate,
+            amount: (total * tax_rate / (Decimal::from(100) + tax_rate)).round_dp(2),
+        }]
+    } else {
+        Vec::new()
+    };
+    let tax_total: Decimal = tax_lines.iter().map(|t| t.amount).sum();
+
+    (total - tax_total, tax_lines, total)
+}
+
+/// The outbox message that emails a receipt to its buyer
+pub fn receipt_email(receipt: &PurchaseReceipt, item_title: String) -> OutboxMessage {
+    OutboxMessage::PurchaseReceipt {
+        receipt_id: receipt.id,
+        user_id: receipt.user_id,
+        raffle_id: receipt.raffle_id,
+        item_title,
+        receipt: serde_json::to_value(receipt).unwrap_or_default(),
+    }
+}
+
+/// One box on a receipt
+#[derive(Debug, Clone, Copy)]
+pub struct ReceiptBox {
+    pub purchase_id: Uuid,
+    pub box_number: i32,
+    pub price: Decimal,
+}
+
+/// Write the receipt for one checkout's boxes in the caller's transaction. Returns None
+/// when the checkout already has one.
+pub async fn create_receipt(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    raffle_id: Uuid,
+    transaction_id: Uuid,
+    boxes: &[ReceiptBox],
+    tax_rate: Decimal,
+) -> Result<Option<PurchaseReceipt>, AppError> {
+    let Some(first) = boxes.iter().min_by_key(|b| b.box_number) else {
+        return Err(AppError::Internal("A receipt needs at least one box".to_string()));
+    };
+
+    let mut box_numbers: Vec<i32> = boxes.iter().map(|b| b.box_number).collect();
+    box_numbers.sort_unstable();
+    let prices: Vec<Decimal> = boxes.iter().map(|b| b.price).collect();
+    let (subtotal, tax_lines, total) = receipt_totals(&prices, tax_rate);
+
+    let receipt_number = format!(
+        "RCP-{}-{}",
+        Utc::now().format("%Y%m%d"),
+        generate_random_string(8).to_uppercase()
+    );
+    let verification_code = hash_with_salt(
+        &format!("{}:{}:{}", transaction_id, receipt_number, subtotal),
+        &generate_random_string(16),
+    );
+
+    let receipt = sqlx::query_as!(
+        PurchaseReceipt,
+        r#"
+        INSERT INTO purchase_receipts (
+            receipt_number, verification_code, user_id, raffle_id, purchase_id, transaction_id, box_numbers,
+            subtotal, credits_used, tax_lines, total_amount
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        ON CONFLICT (transaction_id) DO NOTHING
+        RETURNING id, receipt_number, verification_code, user_id, raffle_id, purchase_id, transaction_id,
+                  box_numbers, subtotal, credits_used, tax_lines, total_amount, currency, emailed_at,
+                  created_at as "created_at!"
+        "#,
+        receipt_number,
+        verification_code,
+        user_id,
+        raffle_id,
+        first.purchase_id,
+        transaction_id,
+        &box_numbers,
+        subtotal,
+        total, // All box purchases are currently paid with credits
+        serde_json::to_value(&tax_lines).unwrap_or_default(),
+        total
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    if let Some(receipt) = &receipt {
+        info!(
+            "Generated receipt {} for checkout {} ({} boxes)",
+            receipt.receipt_number, transaction_id, receipt.box_numbers.len()
+        );
+    }
+
+    Ok(receipt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn purchase(transaction_id: Option<Uuid>) -> BoxPurchase {
+        BoxPurchase {
+            id: Uuid::new_v4(),
+            raffle_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            box_number: 1,
+            purchase_price_in_credits: Decimal::from(5),
+            transaction_id,
+            blockchain_tx_hash: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_receipts_are_keyed_by_checkout() {
+        let transaction_id = Uuid::new_v4();
+        assert_eq!(receipt_transaction_id(&purchase(Some(transaction_id))), transaction_id);
+
+        let legacy = purchase(None);
+        assert_eq!(receipt_transaction_id(&legacy), legacy.id);
+    }
+
+    #[test]
+    fn test_receipt_totals_sum_each_box_price() {
+        // Boxes in one checkout can span price tiers
+        let prices = [Decimal::from(5), Decimal::from(5), Decimal::new(550, 2)];
+
+        let (subtotal, tax_lines, total) = receipt_totals(&prices, Decimal::ZERO);
+        assert_eq!(subtotal, Decimal::new(1550, 2));
+        assert!(tax_lines.is_empty());
+        assert_eq!(total, subtotal);
+
+        // Tax is included in the prices, so the total is still what the boxes cost
+        let (subtotal, tax_lines, total) = receipt_totals(&prices, Decimal::new(825, 2));
+        assert_eq!(total, Decimal::new(1550, 2));
+        assert_eq!(tax_lines.len(), 1);
+        assert_eq!(tax_lines[0].amount, Decimal::new(118, 2));
+        assert_eq!(subtotal, Decimal::new(1432, 2));
+    }
+}
@@ -0,0 +1,627 @@
+use crate::error::{AppError, FieldError};
+use crate::models::system_settings::SystemSetting;
+use crate::models::Notification;
+use crate::services::credit_service::{CreditIssuanceRequest, CreditService};
+use crate::utils::crypto::hash_token;
+use chrono::{DateTime, Duration, Utc};
+use raffle_platform_shared::{CreditSource, CreditType};
+use rand::Rng;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::net::IpAddr;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Header carrying a stable per-install id, used to spot one device referring itself
+pub const DEVICE_ID_HEADER: &str = "X-Device-Id";
+
+const POLICY_KEY: &str = "referral_policy";
+const CODE_LENGTH: usize = 8;
+/// No 0/O or 1/I, so codes survive being read aloud and retyped
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const MAX_CODE_ATTEMPTS: usize = 5;
+const MAX_DEVICE_ID_LENGTH: usize = 200;
+const TOP_REFERRERS: i64 = 10;
+
+const REFERRAL_COLUMNS: &str = "id, referrer_id, referee_id, code, status, rejection_reason, \
+    host(ip_address) AS ip_address, device_hash, referrer_reward, referee_reward, rewarded_at, created_at";
+
+/// Referral service hands out referral codes, links new signups to the user who referred
+/// them, and pays both sides in credits on the referred user's first purchase
+#[derive(Clone)]
+pub struct ReferralService {
+    db_pool: PgPool,
+    credit_service: CreditService,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "varchar", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ReferralStatus {
+    /// Waiting for the referred user's first purchase
+    Pending,
+    Rewarded,
+    /// Refused by the anti-abuse checks; never pays out
+    Rejected,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RejectionReason {
+    /// The referred user signed up from an IP the referrer, or another of their referrals, used
+    SameIp,
+    /// The referred user signed up on a device the referrer, or another of their referrals, used
+    SameDevice,
+}
+
+impl RejectionReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectionReason::SameIp => "same_ip",
+            RejectionReason::SameDevice => "same_device",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct Referral {
+    pub id: Uuid,
+    pub referrer_id: Uuid,
+    pub referee_id: Uuid,
+    pub code: String,
+    pub status: ReferralStatus,
+    pub rejection_reason: Option<String>,
+    pub ip_address: Option<String>,
+    pub device_hash: Option<String>,
+    pub referrer_reward: Option<Decimal>,
+    pub referee_reward: Option<Decimal>,
+    pub rewarded_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Credits paid for a referral and how long a new user has to attach a code
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReferralPolicy {
+    pub enabled: bool,
+    pub referrer_reward: Decimal,
+    pub referee_reward: Decimal,
+    pub credit_expiry_days: i64,
+    /// Days after signing up that a user may still attach a code, if they haven't bought anything
+    pub attach_window_days: i64,
+}
+
+impl Default for ReferralPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            referrer_reward: Decimal::new(500, 2),
+            referee_reward: Decimal::new(500, 2),
+            credit_expiry_days: 90,
+            attach_window_days: 7,
+        }
+    }
+}
+
+impl ReferralPolicy {
+    pub fn validate(&self) -> Result<(), AppError> {
+        let mut errors = Vec::new();
+        let max_reward = Decimal::new(1000, 0);
+
+        for (field, reward) in [("referrer_reward", self.referrer_reward), ("referee_reward", self.referee_reward)] {
+            if reward < Decimal::ZERO || reward > max_reward {
+                errors.push(
+                    FieldError::new(field, "out_of_range", "Rewards must be between 0 and 1000 credits")
+                        .with_limit(max_reward),
+                );
+            }
+        }
+        if !(1..=3650).contains(&self.credit_expiry_days) {
+            errors.push(
+                FieldError::new(
+                    "credit_expiry_days",
+                    "out_of_range",
+                    "Reward credits must expire between 1 day and 10 years after they are paid",
+                )
+                .with_limit(3650),
+            );
+        }
+        if !(0..=90).contains(&self.attach_window_days) {
+            errors.push(
+                FieldError::new("attach_window_days", "out_of_range", "The attach window must be at most 90 days")
+                    .with_limit(90),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::InvalidFields(errors))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AttachReferralRequest {
+    pub code: String,
+}
+
+/// A user's referral code and how their referrals are doing
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UserReferralStats {
+    pub code: String,
+    pub pending: i64,
+    pub rewarded: i64,
+    /// Referrals the anti-abuse checks refused
+    pub rejected: i64,
+    pub credits_earned: Decimal,
+    /// What the next referral pays each side
+    pub referrer_reward: Decimal,
+    pub referee_reward: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct TopReferrer {
+    pub user_id: Uuid,
+    pub username: String,
+    pub rewarded: i64,
+    pub credits_earned: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReferralProgramStats {
+    pub pending: i64,
+    pub rewarded: i64,
+    pub rejected_same_ip: i64,
+    pub rejected_same_device: i64,
+    /// Credits paid to referrers and referred users together
+    pub credits_issued: Decimal,
+    pub top_referrers: Vec<TopReferrer>,
+}
+
+#[derive(Debug, FromRow)]
+struct ReferralCounts {
+    pending: i64,
+    rewarded: i64,
+    rejected: i64,
+    rejected_same_ip: i64,
+    rejected_same_device: i64,
+    credits: Decimal,
+}
+
+#[derive(Debug, FromRow)]
+struct AbuseSignals {
+    same_ip: bool,
+    same_device: bool,
+}
+
+impl ReferralService {
+    pub fn new(db_pool: PgPool, credit_service: CreditService) -> Self {
+        Self { db_pool, credit_service }
+    }
+
+    /// The user's referral code, created the first time they ask for it
+    pub async fn get_or_create_code(&self, user_id: Uuid) -> Result<String, AppError> {
+        if let Some(code) = self.find_code(user_id).await? {
+            return Ok(code);
+        }
+
+        for _ in 0..MAX_CODE_ATTEMPTS {
+            let inserted = sqlx::query_scalar::<_, String>(
+                r#"
+                INSERT INTO referral_codes (user_id, code)
+                VALUES ($1, $2)
+                ON CONFLICT DO NOTHING
+                RETURNING code
+                "#,
+            )
+            .bind(user_id)
+            .bind(generate_code())
+            .fetch_optional(&self.db_pool)
+            .await?;
+
+            if let Some(code) = inserted {
+                return Ok(code);
+            }
+
+            // Either a concurrent request created the user's code or the code was taken
+            if let Some(code) = self.find_code(user_id).await? {
+                return Ok(code);
+            }
+        }
+
+        Err(AppError::Internal("Could not allocate a unique referral code".to_string()))
+    }
+
+    /// Link a new user to the user whose code they signed up with. Referrals the anti-abuse
+    /// checks refuse are kept as rejected so admins can see them.
+    pub async fn attach(
+        &self,
+        referee_id: Uuid,
+        code: &str,
+        ip_address: Option<IpAddr>,
+        device_id: Option<&str>,
+    ) -> Result<Referral, AppError> {
+        let policy = self.get_policy().await?;
+        if !policy.enabled {
+            return Err(AppError::Validation("The referral program is not running".to_string()));
+        }
+
+        let code = normalize_code(code);
+        let referrer_id = sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM referral_codes WHERE code = $1")
+            .bind(&code)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Referral code not found".to_string()))?;
+
+        if referrer_id == referee_id {
+            return Err(AppError::Validation("You cannot use your own referral code".to_string()));
+        }
+
+        let (signed_up_at, has_purchased) = sqlx::query_as::<_, (DateTime<Utc>, bool)>(
+            r#"
+            SELECT u.created_at, EXISTS (SELECT 1 FROM box_purchases bp WHERE bp.user_id = u.id)
+            FROM users u
+            WHERE u.id = $1
+            "#,
+        )
+        .bind(referee_id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        if has_purchased || signed_up_at + Duration::days(policy.attach_window_days) < Utc::now() {
+            return Err(AppError::Validation(
+                "Referral codes can only be added by new users before their first purchase".to_string(),
+            ));
+        }
+
+        let device_hash = device_id.and_then(hash_device_id);
+        let signals = sqlx::query_as::<_, AbuseSignals>(
+            r#"
+            SELECT
+                $2::inet IS NOT NULL AND (
+                    EXISTS (SELECT 1 FROM user_sessions s WHERE s.user_id = $1 AND s.ip_address = $2::inet)
+                    OR EXISTS (
+                        SELECT 1 FROM referrals r
+                        WHERE (r.referrer_id = $1 OR r.referee_id = $1) AND r.ip_address = $2::inet
+                    )
+                ) AS same_ip,
+                $3::varchar IS NOT NULL AND EXISTS (
+                    SELECT 1 FROM referrals r
+                    WHERE (r.referrer_id = $1 OR r.referee_id = $1) AND r.device_hash = $3::varchar
+                ) AS same_device
+            "#,
+        )
+        .bind(referrer_id)
+        .bind(ip_address)
+        .bind(&device_hash)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        let rejection = rejection_reason(signals.same_ip, signals.same_device);
+        let status = if rejection.is_some() { ReferralStatus::Rejected } else { ReferralStatus::Pending };
+
+        let referral = sqlx::query_as::<_, Referral>(&format!(
+            r#"
+            INSERT INTO referrals (referrer_id, referee_id, code, status, rejection_reason, ip_address, device_hash)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (referee_id) DO NOTHING
+            RETURNING {}
+            "#,
+            REFERRAL_COLUMNS
+        ))
+        .bind(referrer_id)
+        .bind(referee_id)
+        .bind(&code)
+        .bind(status)
+        .bind(rejection.map(|reason| reason.as_str()))
+        .bind(ip_address)
+        .bind(&device_hash)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::Conflict("You have already used a referral code".to_string()))?;
+
+        match rejection {
+            Some(reason) => warn!(
+                "Rejected referral of user {} by {}: {}",
+                referee_id, referrer_id, reason.as_str()
+            ),
+            None => info!("User {} was referred by {}", referee_id, referrer_id),
+        }
+
+        Ok(referral)
+    }
+
+    /// Pay a pending referral once the referred user buys something; later purchases find
+    /// nothing pending and do nothing
+    pub async fn reward_first_purchase(&self, referee_id: Uuid) -> Result<Option<Referral>, AppError> {
+        let policy = self.get_policy().await?;
+        if !policy.enabled {
+            return Ok(None);
+        }
+
+        let referral = sqlx::query_as::<_, Referral>(&format!(
+            r#"
+            UPDATE referrals
+            SET status = 'rewarded', rewarded_at = NOW(), referrer_reward = $2, referee_reward = $3
+            WHERE referee_id = $1 AND status = 'pending'
+            RETURNING {}
+            "#,
+            REFERRAL_COLUMNS
+        ))
+        .bind(referee_id)
+        .bind(policy.referrer_reward)
+        .bind(policy.referee_reward)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        let referral = match referral {
+            Some(referral) => referral,
+            None => return Ok(None),
+        };
+
+        let expires_at = Utc::now() + Duration::days(policy.credit_expiry_days);
+        let payouts = [
+            (referral.referee_id, policy.referee_reward, "Welcome credits for joining with a referral"),
+            (referral.referrer_id, policy.referrer_reward, "Credits for referring a new user"),
+        ];
+
+        for (user_id, amount, description) in payouts {
+            if amount <= Decimal::ZERO {
+                continue;
+            }
+
+            let issued = self
+                .credit_service
+                .issue_credits(CreditIssuanceRequest {
+                    user_id,
+                    amount,
+                    source: CreditSource::Referral,
+                    credit_type: CreditType::General,
+                    redeemable_on_item_id: None,
+                    expires_at: Some(expires_at),
+                    description: format!("{} (referral {})", description, referral.id),
+                })
+                .await;
+
+            match issued {
+                Ok(_) => {
+                    if let Err(e) =
+                        Notification::create_credit_issued_notification(&self.db_pool, user_id, amount, "referral").await
+                    {
+                        warn!("Failed to notify user {} of referral credits: {}", user_id, e);
+                    }
+                }
+                Err(e) => error!(
+                    "Failed to pay {} referral credits to user {} for referral {}: {}",
+                    amount, user_id, referral.id, e
+                ),
+            }
+        }
+
+        info!("Rewarded referral {} of user {}", referral.id, referral.referee_id);
+
+        Ok(Some(referral))
+    }
+
+    pub async fn get_user_stats(&self, user_id: Uuid) -> Result<UserReferralStats, AppError> {
+        let code = self.get_or_create_code(user_id).await?;
+        let policy = self.get_policy().await?;
+
+        let counts = sqlx::query_as::<_, ReferralCounts>(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE status = 'pending') AS pending,
+                COUNT(*) FILTER (WHERE status = 'rewarded') AS rewarded,
+                COUNT(*) FILTER (WHERE status = 'rejected') AS rejected,
+                COUNT(*) FILTER (WHERE rejection_reason = 'same_ip') AS rejected_same_ip,
+                COUNT(*) FILTER (WHERE rejection_reason = 'same_device') AS rejected_same_device,
+                COALESCE(SUM(referrer_reward) FILTER (WHERE status = 'rewarded'), 0) AS credits
+            FROM referrals
+            WHERE referrer_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(UserReferralStats {
+            code,
+            pending: counts.pending,
+            rewarded: counts.rewarded,
+            rejected: counts.rejected,
+            credits_earned: counts.credits,
+            referrer_reward: policy.referrer_reward,
+            referee_reward: policy.referee_reward,
+        })
+    }
+
+    pub async fn get_program_stats(&self) -> Result<ReferralProgramStats, AppError> {
+        let counts = sqlx::query_as::<_, ReferralCounts>(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE status = 'pending') AS pending,
+                COUNT(*) FILTER (WHERE status = 'rewarded') AS rewarded,
+                COUNT(*) FILTER (WHERE status = 'rejected') AS rejected,
+                COUNT(*) FILTER (WHERE rejection_reason = 'same_ip') AS rejected_same_ip,
+                COUNT(*) FILTER (WHERE rejection_reason = 'same_device') AS rejected_same_device,
+                COALESCE(SUM(referrer_reward + referee_reward) FILTER (WHERE status = 'rewarded'), 0) AS credits
+            FROM referrals
+            "#,
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        let top_referrers = sqlx::query_as::<_, TopReferrer>(
+            r#"
+            SELECT r.referrer_id AS user_id, u.username, COUNT(*) AS rewarded,
+                   COALESCE(SUM(r.referrer_reward), 0) AS credits_earned
+            FROM referrals r
+            JOIN users u ON u.id = r.referrer_id
+            WHERE r.status = 'rewarded'
+            GROUP BY r.referrer_id, u.username
+            ORDER BY rewarded DESC, credits_earned DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(TOP_REFERRERS)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(ReferralProgramStats {
+            pending: counts.pending,
+            rewarded: counts.rewarded,
+            rejected_same_ip: counts.rejected_same_ip,
+            rejected_same_device: counts.rejected_same_device,
+            credits_issued: counts.credits,
+            top_referrers,
+        })
+    }
+
+    /// Referrals newest first, for reviewing what the anti-abuse checks caught
+    pub async fn list_referrals(
+        &self,
+        status: Option<ReferralStatus>,
+        limit: i64,
+    ) -> Result<Vec<Referral>, AppError> {
+        let referrals = sqlx::query_as::<_, Referral>(&format!(
+            r#"
+            SELECT {}
+            FROM referrals
+            WHERE $1::varchar IS NULL OR status = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+            REFERRAL_COLUMNS
+        ))
+        .bind(status)
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(referrals)
+    }
+
+    pub async fn get_policy(&self) -> Result<ReferralPolicy, AppError> {
+        let policy = match SystemSetting::get_by_key(&self.db_pool, POLICY_KEY).await? {
+            Some(setting) => serde_json::from_value(setting.value).unwrap_or_else(|e| {
+                warn!("Invalid {} setting, using defaults: {}", POLICY_KEY, e);
+                ReferralPolicy::default()
+            }),
+            None => ReferralPolicy::default(),
+        };
+
+        Ok(policy)
+    }
+
+    /// Replace the policy; referrals already rewarded keep what they were paid
+    pub async fn update_policy(&self, policy: ReferralPolicy) -> Result<ReferralPolicy, AppError> {
+        policy.validate()?;
+
+        SystemSetting::upsert(
+            &self.db_pool,
+            POLICY_KEY.to_string(),
+            serde_json::to_value(&policy).map_err(|e| AppError::Internal(e.to_string()))?,
+            Some("Credits paid to both sides of a referral when the referred user makes their first purchase".to_string()),
+            false,
+        )
+        .await?;
+
+        Ok(policy)
+    }
+
+    // Private helper methods
+
+    async fn find_code(&self, user_id: Uuid) -> Result<Option<String>, AppError> {
+        let code = sqlx::query_scalar::<_, String>("SELECT code FROM referral_codes WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&self.db_pool)
+            .await?;
+
+        Ok(code)
+    }
+}
+
+fn generate_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..CODE_LENGTH)
+        .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+        .collect()
+}
+
+/// Codes are matched case-insensitively and ignoring surrounding whitespace
+fn normalize_code(code: &str) -> String {
+    code.trim().to_ascii_uppercase()
+}
+
+/// Device ids are only compared, so only their hash is stored
+fn hash_device_id(device_id: &str) -> Option<String> {
+    let device_id = device_id.trim();
+    if device_id.is_empty() || device_id.len() > MAX_DEVICE_ID_LENGTH {
+        return None;
+    }
+
+    Some(hash_token(device_id))
+}
+
+fn rejection_reason(same_ip: bool, same_device: bool) -> Option<RejectionReason> {
+    if same_device {
+        Some(RejectionReason::SameDevice)
+    } else if same_ip {
+        Some(RejectionReason::SameIp)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_codes_use_unambiguous_characters() {
+        for _ in 0..100 {
+            let code = generate_code();
+            assert_eq!(code.len(), CODE_LENGTH);
+            assert!(code.bytes().all(|c| CODE_ALPHABET.contains(&c)));
+            assert_eq!(normalize_code(&code), code);
+        }
+    }
+
+    #[test]
+    fn test_normalize_code() {
+        assert_eq!(normalize_code("  abcd2345 "), "ABCD2345");
+    }
+
+    #[test]
+    fn test_hash_device_id() {
+        assert_eq!(hash_device_id(" device-1 "), hash_device_id("device-1"));
+        assert_ne!(hash_device_id("device-1"), hash_device_id("device-2"));
+        assert_eq!(hash_device_id("   "), None);
+        assert_eq!(hash_device_id(&"x".repeat(MAX_DEVICE_ID_LENGTH + 1)), None);
+    }
+
+    #[test]
+    fn test_device_match_takes_precedence() {
+        assert_eq!(rejection_reason(false, false), None);
+        assert_eq!(rejection_reason(true, false), Some(RejectionReason::SameIp));
+        assert_eq!(rejection_reason(true, true), Some(RejectionReason::SameDevice));
+    }
+
+    #[test]
+    fn test_policy_validation() {
+        assert!(ReferralPolicy::default().validate().is_ok());
+
+        let policy = ReferralPolicy {
+            referrer_reward: Decimal::new(-1, 0),
+            attach_window_days: 365,
+            ..ReferralPolicy::default()
+        };
+        match policy.validate() {
+            Err(AppError::InvalidFields(errors)) => assert_eq!(errors.len(), 2),
+            other => panic!("expected field errors, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,895 @@
+use crate::error::AppError;
+use crate::models::ledger::LedgerAccount;
+use crate::models::promotion::discounted_fee;
+use crate::services::fee_service::FeeService;
+use actix_web::web::Bytes;
+use chrono::{DateTime, Months, NaiveDate, Utc};
+use futures_util::stream::{self, Stream};
+use raffle_platform_shared::TransactionType;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::collections::{BTreeMap, HashMap};
+use tracing::{error, info};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Reporting service produces financial reports and accounting exports
+#[derive(Clone)]
+pub struct ReportingService {
+    db_pool: PgPool,
+    read_pool: PgPool,
+    fee_service: FeeService,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountingExportFormat {
+    QuickbooksIif,
+    QuickbooksCsv,
+    XeroCsv,
+}
+
+impl AccountingExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            AccountingExportFormat::QuickbooksIif => "application/x-iif",
+            AccountingExportFormat::QuickbooksCsv | AccountingExportFormat::XeroCsv => "text/csv",
+        }
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            AccountingExportFormat::QuickbooksIif => "iif",
+            AccountingExportFormat::QuickbooksCsv | AccountingExportFormat::XeroCsv => "csv",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AccountMapping {
+    pub id: Uuid,
+    pub transaction_type: TransactionType,
+    pub debit_account_code: String,
+    pub debit_account_name: String,
+    pub credit_account_code: String,
+    pub credit_account_name: String,
+    pub tax_rate_name: String,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AccountMappingUpdate {
+    pub debit_account_code: String,
+    pub debit_account_name: String,
+    pub credit_account_code: String,
+    pub credit_account_name: String,
+    pub tax_rate_name: Option<String>,
+}
+
+/// A single completed transaction ready to be written as a journal entry
+#[derive(Debug, Clone)]
+pub struct JournalEntry {
+    pub transaction_id: Uuid,
+    pub transaction_type: TransactionType,
+    pub amount: Decimal,
+    pub memo: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SellerStatementLine {
+    pub raffle_id: Uuid,
+    pub item_name: String,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub gross_amount: Decimal,
+    pub fee_schedule_id: Option<Uuid>,
+    pub fee_percentage: Decimal,
+    pub fee_amount: Decimal,
+    /// Credit values winners took in place of the item; the seller keeps the item instead
+    pub prize_substitutions: Decimal,
+    pub net_amount: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SellerStatement {
+    pub seller_id: Uuid,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub lines: Vec<SellerStatementLine>,
+    pub total_gross: Decimal,
+    pub total_fees: Decimal,
+    pub total_prize_substitutions: Decimal,
+    pub total_net: Decimal,
+}
+
+/// Rows read per query while streaming a ledger
+const LEDGER_PAGE_SIZE: i64 = 1_000;
+
+/// Completed transactions plus succeeded card refunds, which are kept in their own table.
+/// Refunds carry no transaction type and are negative since the money goes back out.
+const LEDGER_SOURCE: &str = r#"
+    SELECT t.id, t.created_at AS occurred_at, t.type AS transaction_type,
+           t.user_id, t.seller_id, t.amount, t.payment_gateway_ref AS reference
+    FROM transactions t
+    WHERE t.status = 'completed'
+    UNION ALL
+    SELECT r.id, r.created_at, NULL::transaction_type,
+           p.user_id, NULL::uuid, -r.amount, r.stripe_refund_id
+    FROM refunds r
+    JOIN payments p ON p.id = r.payment_id
+    WHERE r.status = 'succeeded'
+"#;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerCategory {
+    Payment,
+    Refund,
+    CreditIssued,
+    CreditRedeemed,
+    Fee,
+    Payout,
+}
+
+impl LedgerCategory {
+    /// Ledger rows without a transaction type are card refunds
+    pub fn from_transaction_type(transaction_type: Option<TransactionType>) -> Self {
+        match transaction_type {
+            None => LedgerCategory::Refund,
+            Some(TransactionType::CreditDeposit) => LedgerCategory::Payment,
+            Some(TransactionType::RaffleWinCreditAddition) => LedgerCategory::CreditIssued,
+            Some(
+                TransactionType::BoxPurchaseCreditDeduction
+                | TransactionType::ItemPurchaseCreditDeduction
+                | TransactionType::FreeItemRedemption,
+            ) => LedgerCategory::CreditRedeemed,
+            Some(
+                TransactionType::SellerSubscriptionFee
+                | TransactionType::SellerListingFee
+                | TransactionType::SellerTransactionFee
+                | TransactionType::SellerApiUsageFee,
+            ) => LedgerCategory::Fee,
+            // Cash withdrawals leave the platform the same way seller payouts do
+            Some(TransactionType::Payout | TransactionType::CreditWithdrawal) => LedgerCategory::Payout,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerCategory::Payment => "payment",
+            LedgerCategory::Refund => "refund",
+            LedgerCategory::CreditIssued => "credit_issued",
+            LedgerCategory::CreditRedeemed => "credit_redeemed",
+            LedgerCategory::Fee => "fee",
+            LedgerCategory::Payout => "payout",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LedgerFormat {
+    Csv,
+    Json,
+}
+
+impl LedgerFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            LedgerFormat::Csv => "text/csv",
+            LedgerFormat::Json => "application/json",
+        }
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            LedgerFormat::Csv => "csv",
+            LedgerFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow)]
+struct LedgerRow {
+    id: Uuid,
+    occurred_at: DateTime<Utc>,
+    transaction_type: Option<TransactionType>,
+    user_id: Option<Uuid>,
+    seller_id: Option<Uuid>,
+    amount: Decimal,
+    reference: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LedgerLine {
+    pub id: Uuid,
+    /// `YYYY-MM` in UTC
+    pub month: String,
+    pub occurred_at: DateTime<Utc>,
+    pub category: LedgerCategory,
+    pub transaction_type: Option<TransactionType>,
+    pub user_id: Option<Uuid>,
+    pub seller_id: Option<Uuid>,
+    pub amount: Decimal,
+    /// Payment gateway or Stripe refund id
+    pub reference: Option<String>,
+}
+
+impl From<LedgerRow> for LedgerLine {
+    fn from(row: LedgerRow) -> Self {
+        Self {
+            id: row.id,
+            month: row.occurred_at.format("%Y-%m").to_string(),
+            occurred_at: row.occurred_at,
+            category: LedgerCategory::from_transaction_type(row.transaction_type),
+            transaction_type: row.transaction_type,
+            user_id: row.user_id,
+            seller_id: row.seller_id,
+            amount: row.amount,
+            reference: row.reference,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LedgerTotal {
+    pub month: String,
+    pub category: LedgerCategory,
+    pub entry_count: i64,
+    pub total: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LedgerSummary {
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    /// One row per month and category that had entries
+    pub totals: Vec<LedgerTotal>,
+}
+
+/// Where a ledger stream has got to
+struct LedgerCursor {
+    pool: PgPool,
+    format: LedgerFormat,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    after: Option<(DateTime<Utc>, Uuid)>,
+    written: usize,
+    done: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountingExport {
+    pub filename: String,
+    pub content_type: &'static str,
+    pub body: String,
+    pub entry_count: usize,
+    pub skipped_unmapped: usize,
+}
+
+impl ReportingService {
+    /// Create a new reporting service
+    pub fn new(db_pool: PgPool, fee_service: FeeService) -> Self {
+        Self { read_pool: db_pool.clone(), db_pool, fee_service }
+    }
+
+    /// Run statement and export queries on a read replica
+    pub fn with_read_pool(mut self, read_pool: PgPool) -> Self {
+        self.read_pool = read_pool;
+        self
+    }
+
+    /// Get the chart-of-accounts mapping used by accounting exports
+    pub async fn get_chart_of_accounts(&self) -> Result<Vec<AccountMapping>, AppError> {
+        let mappings = sqlx::query_as!(
+            AccountMapping,
+            r#"
+            SELECT id, transaction_type as "transaction_type: TransactionType",
+                   debit_account_code, debit_account_name, credit_account_code, credit_account_name,
+                   tax_rate_name, updated_at
+            FROM chart_of_accounts
+            ORDER BY transaction_type
+            "#
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(mappings)
+    }
+
+    /// Create or replace the account mapping for a transaction type
+    pub async fn upsert_account_mapping(
+        &self,
+        transaction_type: TransactionType,
+        update: AccountMappingUpdate,
+    ) -> Result<AccountMapping, AppError> {
+        if update.debit_account_code.trim().is_empty() || update.credit_account_code.trim().is_empty() {
+            return Err(AppError::Validation("Account codes are required".to_string()));
+        }
+
+        let mapping = sqlx::query_as!(
+            AccountMapping,
+            r#"
+            INSERT INTO chart_of_accounts (
+                transaction_type, debit_account_code, debit_account_name,
+                credit_account_code, credit_account_name, tax_rate_name
+            )
+            VALUES ($1, $2, $3, $4, $5, COALESCE($6, 'Tax Exempt'))
+            ON CONFLICT (transaction_type) DO UPDATE SET
+                debit_account_code = EXCLUDED.debit_account_code,
+                debit_account_name = EXCLUDED.debit_account_name,
+                credit_account_code = EXCLUDED.credit_account_code,
+                credit_account_name = EXCLUDED.credit_account_name,
+                tax_rate_name = EXCLUDED.tax_rate_name
+            RETURNING id, transaction_type as "transaction_type: TransactionType",
+                      debit_account_code, debit_account_name, credit_account_code, credit_account_name,
+                      tax_rate_name, updated_at
+            "#,
+            transaction_type as TransactionType,
+            update.debit_account_code,
+            update.debit_account_name,
+            update.credit_account_code,
+            update.credit_account_name,
+            update.tax_rate_name
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        // Postings for this type go to these codes from now on
+        LedgerAccount::ensure(&self.db_pool, &mapping.debit_account_code, &mapping.debit_account_name).await?;
+        LedgerAccount::ensure(&self.db_pool, &mapping.credit_account_code, &mapping.credit_account_name).await?;
+
+        Ok(mapping)
+    }
+
+    /// Export completed transactions in a format importable by QuickBooks or Xero
+    pub async fn export_accounting(
+        &self,
+        format: AccountingExportFormat,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<AccountingExport, AppError> {
+        if start_date >= end_date {
+            return Err(AppError::Validation("start_date must be before end_date".to_string()));
+        }
+
+        let mappings: HashMap<TransactionType, AccountMapping> = self
+            .get_chart_of_accounts()
+            .await?
+            .into_iter()
+            .map(|m| (m.transaction_type, m))
+            .collect();
+
+        let entries = self.get_journal_entries(start_date, end_date).await?;
+
+        let (mapped, unmapped): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|e| mappings.contains_key(&e.transaction_type));
+
+        let body = match format {
+            AccountingExportFormat::QuickbooksIif => render_quickbooks_iif(&mapped, &mappings),
+            AccountingExportFormat::QuickbooksCsv => render_quickbooks_csv(&mapped, &mappings),
+            AccountingExportFormat::XeroCsv => render_xero_csv(&mapped, &mappings),
+        };
+
+        info!(
+            "Generated {:?} accounting export with {} entries ({} unmapped skipped)",
+            format, mapped.len(), unmapped.len()
+        );
+
+        Ok(AccountingExport {
+            filename: format!(
+                "accounting_{}_{}.{}",
+                start_date.format("%Y%m%d"),
+                end_date.format("%Y%m%d"),
+                format.file_extension()
+            ),
+            content_type: format.content_type(),
+            body,
+            entry_count: mapped.len(),
+            skipped_unmapped: unmapped.len(),
+        })
+    }
+
+    /// Statement of completed raffles for a seller, priced with the fee schedule each raffle was created under
+    pub async fn get_seller_statement(
+        &self,
+        seller_id: Uuid,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<SellerStatement, AppError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT r.id, i.name as item_name, r.completed_at, r.fee_schedule_id, r.transaction_fee_applied,
+                   (SELECT COALESCE(SUM(bp.purchase_price_in_credits), 0)
+                    FROM box_purchases bp WHERE bp.raffle_id = r.id) as "gross_amount!",
+                   (SELECT COALESCE(SUM(t.credit_alternative), 0)
+                    FROM raffle_prize_tiers t
+                    WHERE t.raffle_id = r.id AND t.winner_choice = 'credits') as "prize_substitutions!",
+                   p.value as "fee_discount?"
+            FROM raffles r
+            JOIN items i ON i.id = r.item_id
+            JOIN sellers s ON s.id = i.seller_id
+            LEFT JOIN promotions p ON p.id = r.fee_promotion_id
+            WHERE s.user_id = $1 AND r.status = 'completed'
+            AND r.completed_at >= $2 AND r.completed_at < $3
+            ORDER BY r.completed_at ASC
+            "#,
+            seller_id,
+            start_date,
+            end_date
+        )
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        let mut lines = Vec::with_capacity(rows.len());
+        for row in rows {
+            // Boxes may have sold at different prices under a pricing curve
+            let gross_amount = row.gross_amount;
+
+            let (fee_percentage, fee_amount) = match row.fee_schedule_id {
+                Some(schedule_id) => {
+                    let schedule = self.fee_service.get_schedule(schedule_id).await?;
+                    let fee = schedule.apply(gross_amount);
+                    // Raffles created during a fee discount keep it
+                    let fee = row.fee_discount.map_or(fee, |discount| discounted_fee(fee, discount));
+                    (schedule.percentage, fee)
+                }
+                // Raffles created before fee schedules only recorded a percentage
+                None => {
+                    let percentage = row.transaction_fee_applied.unwrap_or(Decimal::ZERO);
+                    (percentage, (gross_amount * percentage / Decimal::from(100)).round_dp(2))
+                }
+            };
+
+            lines.push(SellerStatementLine {
+                raffle_id: row.id,
+                item_name: row.item_name,
+                completed_at: row.completed_at,
+                gross_amount,
+                fee_schedule_id: row.fee_schedule_id,
+                fee_percentage,
+                fee_amount,
+                prize_substitutions: row.prize_substitutions,
+                net_amount: gross_amount - fee_amount - row.prize_substitutions,
+            });
+        }
+
+        let total_gross: Decimal = lines.iter().map(|l| l.gross_amount).sum();
+        let total_fees: Decimal = lines.iter().map(|l| l.fee_amount).sum();
+        let total_prize_substitutions: Decimal = lines.iter().map(|l| l.prize_substitutions).sum();
+
+        Ok(SellerStatement {
+            seller_id,
+            period_start: start_date,
+            period_end: end_date,
+            lines,
+            total_gross,
+            total_fees,
+            total_prize_substitutions,
+            total_net: total_gross - total_fees - total_prize_substitutions,
+        })
+    }
+
+    /// Stream the ledger for a period a page at a time, so a year of entries never sits in memory.
+    /// The first chunk carries the CSV header or opening bracket; an error ends the stream.
+    pub fn stream_ledger(
+        &self,
+        format: LedgerFormat,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> impl Stream<Item = Result<Bytes, AppError>> + 'static {
+        let cursor = LedgerCursor {
+            pool: self.read_pool.clone(),
+            format,
+            start: start_date,
+            end: end_date,
+            after: None,
+            written: 0,
+            done: false,
+        };
+
+        stream::unfold(cursor, |mut cursor| async move {
+            if cursor.done {
+                return None;
+            }
+
+            let rows = match fetch_ledger_page(&cursor.pool, cursor.start, cursor.end, cursor.after).await {
+                Ok(rows) => rows,
+                Err(e) => {
+                    error!("Ledger export stopped after {} entries: {}", cursor.written, e);
+                    cursor.done = true;
+                    return Some((Err(e), cursor));
+                }
+            };
+
+            let mut chunk = String::new();
+            if cursor.after.is_none() {
+                chunk.push_str(match cursor.format {
+                    LedgerFormat::Csv => LEDGER_CSV_HEADER,
+                    LedgerFormat::Json => "[",
+                });
+            }
+
+            cursor.done = (rows.len() as i64) < LEDGER_PAGE_SIZE;
+            cursor.after = rows.last().map(|row| (row.occurred_at, row.id)).or(cursor.after);
+
+            for row in rows {
+                let line = LedgerLine::from(row);
+                match cursor.format {
+                    LedgerFormat::Csv => chunk.push_str(&render_ledger_csv_line(&line)),
+                    LedgerFormat::Json => {
+                        if cursor.written > 0 {
+                            chunk.push(',');
+                        }
+                        chunk.push_str(&serde_json::to_string(&line).unwrap_or_default());
+                    }
+                }
+                cursor.written += 1;
+            }
+
+            if cursor.done {
+                if cursor.format == LedgerFormat::Json {
+                    chunk.push(']');
+                }
+                info!("Streamed {:?} ledger with {} entries", cursor.format, cursor.written);
+            }
+
+            Some((Ok(Bytes::from(chunk)), cursor))
+        })
+    }
+
+    /// Entry counts and totals per month and ledger category
+    pub async fn get_ledger_summary(
+        &self,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<LedgerSummary, AppError> {
+        let rows: Vec<(String, Option<TransactionType>, i64, Decimal)> = sqlx::query_as(&format!(
+            r#"
+            SELECT to_char(occurred_at AT TIME ZONE 'UTC', 'YYYY-MM') AS month, transaction_type,
+                   COUNT(*) AS entry_count, COALESCE(SUM(amount), 0) AS total
+            FROM ({}) ledger
+            WHERE occurred_at >= $1 AND occurred_at < $2
+            GROUP BY 1, 2
+            "#,
+            LEDGER_SOURCE
+        ))
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        let mut totals: BTreeMap<(String, LedgerCategory), (i64, Decimal)> = BTreeMap::new();
+        for (month, transaction_type, entry_count, total) in rows {
+            let entry = totals
+                .entry((month, LedgerCategory::from_transaction_type(transaction_type)))
+                .or_insert((0, Decimal::ZERO));
+            entry.0 += entry_count;
+            entry.1 += total;
+        }
+
+        Ok(LedgerSummary {
+            period_start: start_date,
+            period_end: end_date,
+            totals: totals
+                .into_iter()
+                .map(|((month, category), (entry_count, total))| LedgerTotal { month, category, entry_count, total })
+                .collect(),
+        })
+    }
+
+    async fn get_journal_entries(
+        &self,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<JournalEntry>, AppError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, type as "transaction_type: TransactionType", amount,
+                   payment_gateway_ref, created_at as "created_at!"
+            FROM transactions
+            WHERE status = 'completed'
+            AND created_at >= $1 AND created_at < $2
+            ORDER BY created_at ASC
+            "#,
+            start_date,
+            end_date
+        )
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| JournalEntry {
+                transaction_id: row.id,
+                transaction_type: row.transaction_type,
+                amount: row.amount,
+                memo: match row.payment_gateway_ref {
+                    Some(reference) => format!("{:?} {}", row.transaction_type, reference),
+                    None => format!("{:?}", row.transaction_type),
+                },
+                created_at: row.created_at,
+            })
+            .collect())
+    }
+}
+
+/// Render entries as a QuickBooks Desktop IIF general journal
+pub fn render_quickbooks_iif(
+    entries: &[JournalEntry],
+    mappings: &HashMap<TransactionType, AccountMapping>,
+) -> String {
+    let mut out = String::new();
+    out.push_str("!TRNS\tTRNSID\tTRNSTYPE\tDATE\tACCNT\tAMOUNT\tDOCNUM\tMEMO\n");
+    out.push_str("!SPL\tSPLID\tTRNSTYPE\tDATE\tACCNT\tAMOUNT\tDOCNUM\tMEMO\n");
+    out.push_str("!ENDTRNS\n");
+
+    for entry in entries {
+        let mapping = &mappings[&entry.transaction_type];
+        let date = entry.created_at.format("%m/%d/%Y");
+        let memo = sanitize_iif(&entry.memo);
+        let amount = entry.amount.round_dp(2);
+
+        out.push_str(&format!(
+            "TRNS\t\tGENERAL JOURNAL\t{}\t{}\t{}\t{}\t{}\n",
+            date, sanitize_iif(&mapping.debit_account_name), amount, entry.transaction_id, memo
+        ));
+        out.push_str(&format!(
+            "SPL\t\tGENERAL JOURNAL\t{}\t{}\t{}\t{}\t{}\n",
+            date, sanitize_iif(&mapping.credit_account_name), -amount, entry.transaction_id, memo
+        ));
+        out.push_str("ENDTRNS\n");
+    }
+
+    out
+}
+
+/// Render entries as a QuickBooks Online journal entry CSV import
+pub fn render_quickbooks_csv(
+    entries: &[JournalEntry],
+    mappings: &HashMap<TransactionType, AccountMapping>,
+) -> String {
+    let mut out = String::from("JournalNo,JournalDate,AccountName,Debits,Credits,Description\n");
+
+    for entry in entries {
+        let mapping = &mappings[&entry.transaction_type];
+        let date = entry.created_at.format("%m/%d/%Y").to_string();
+        let journal_no = entry.transaction_id.to_string();
+        let amount = entry.amount.round_dp(2).to_string();
+
+        out.push_str(&csv_row(&[&journal_no, &date, &mapping.debit_account_name, &amount, "", &entry.memo]));
+        out.push_str(&csv_row(&[&journal_no, &date, &mapping.credit_account_name, "", &amount, &entry.memo]));
+    }
+
+    out
+}
+
+/// Render entries as a Xero manual journal CSV import
+pub fn render_xero_csv(
+    entries: &[JournalEntry],
+    mappings: &HashMap<TransactionType, AccountMapping>,
+) -> String {
+    let mut out = String::from("*Narration,*Date,Description,*AccountCode,*TaxRate,*Amount\n");
+
+    for entry in entries {
+        let mapping = &mappings[&entry.transaction_type];
+        let narration = format!("Transaction {}", entry.transaction_id);
+        let date = entry.created_at.format("%d/%m/%Y").to_string();
+        let amount = entry.amount.round_dp(2);
+
+        out.push_str(&csv_row(&[
+            &narration, &date, &entry.memo, &mapping.debit_account_code, &mapping.tax_rate_name, &amount.to_string(),
+        ]));
+        out.push_str(&csv_row(&[
+            &narration, &date, &entry.memo, &mapping.credit_account_code, &mapping.tax_rate_name, &(-amount).to_string(),
+        ]));
+    }
+
+    out
+}
+
+async fn fetch_ledger_page(
+    pool: &PgPool,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    after: Option<(DateTime<Utc>, Uuid)>,
+) -> Result<Vec<LedgerRow>, AppError> {
+    let (after_time, after_id) = after.unzip();
+    let rows = sqlx::query_as::<_, LedgerRow>(&format!(
+        r#"
+        SELECT id, occurred_at, transaction_type, user_id, seller_id, amount, reference
+        FROM ({}) ledger
+        WHERE occurred_at >= $1 AND occurred_at < $2
+        AND ($3::timestamptz IS NULL OR (occurred_at, id) > ($3, $4))
+        ORDER BY occurred_at ASC, id ASC
+        LIMIT $5
+        "#,
+        LEDGER_SOURCE
+    ))
+    .bind(start_date)
+    .bind(end_date)
+    .bind(after_time)
+    .bind(after_id)
+    .bind(LEDGER_PAGE_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Parse a `YYYY-MM` month into its first day
+pub fn parse_month(month: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(&format!("{}-01", month.trim()), "%Y-%m-%d")
+        .map_err(|_| AppError::Validation(format!("Invalid month '{}', expected YYYY-MM", month)))
+}
+
+/// The half-open range covering every day from the start of `from` to the end of `to`
+pub fn month_range(from: NaiveDate, to: NaiveDate) -> Result<(DateTime<Utc>, DateTime<Utc>), AppError> {
+    if from > to {
+        return Err(AppError::Validation("from month must not be after to month".to_string()));
+    }
+
+    let end = to
+        .checked_add_months(Months::new(1))
+        .ok_or_else(|| AppError::Validation("to month is out of range".to_string()))?;
+
+    Ok((
+        from.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc(),
+        end.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc(),
+    ))
+}
+
+const LEDGER_CSV_HEADER: &str =
+    "month,occurred_at,category,transaction_type,entry_id,user_id,seller_id,amount,reference\n";
+
+/// Render one ledger line as a CSV row matching `LEDGER_CSV_HEADER`
+pub fn render_ledger_csv_line(line: &LedgerLine) -> String {
+    let optional_id = |id: Option<Uuid>| id.map(|id| id.to_string()).unwrap_or_default();
+
+    csv_row(&[
+        &line.month,
+        &line.occurred_at.to_rfc3339(),
+        line.category.as_str(),
+        &line.transaction_type.map(|t| t.to_string()).unwrap_or_default(),
+        &line.id.to_string(),
+        &optional_id(line.user_id),
+        &optional_id(line.seller_id),
+        &line.amount.round_dp(2).to_string(),
+        line.reference.as_deref().unwrap_or(""),
+    ])
+}
+
+pub(crate) fn csv_row(fields: &[&str]) -> String {
+    let escaped: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            if field.contains(',') || field.contains('"') || field.contains('\n') {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+
+    format!("{}\n", escaped.join(","))
+}
+
+fn sanitize_iif(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sample() -> (Vec<JournalEntry>, HashMap<TransactionType, AccountMapping>) {
+        let mapping = AccountMapping {
+            id: Uuid::new_v4(),
+            transaction_type: TransactionType::CreditDeposit,
+            debit_account_code: "1010".to_string(),
+            debit_account_name: "Stripe Clearing".to_string(),
+            credit_account_code: "2100".to_string(),
+            credit_account_name: "Customer Credit Liability".to_string(),
+            tax_rate_name: "Tax Exempt".to_string(),
+            updated_at: None,
+        };
+        let entry = JournalEntry {
+            transaction_id: Uuid::nil(),
+            transaction_type: TransactionType::CreditDeposit,
+            amount: Decimal::new(2550, 2),
+            memo: "Deposit, via \"card\"".to_string(),
+            created_at: Utc.with_ymd_and_hms(2024, 3, 7, 12, 0, 0).unwrap(),
+        };
+
+        let mut mappings = HashMap::new();
+        mappings.insert(TransactionType::CreditDeposit, mapping);
+        (vec![entry], mappings)
+    }
+
+    #[test]
+    fn test_quickbooks_iif_balances() {
+        let (entries, mappings) = sample();
+        let iif = render_quickbooks_iif(&entries, &mappings);
+
+        assert!(iif.starts_with("!TRNS"));
+        assert!(iif.contains("TRNS\t\tGENERAL JOURNAL\t03/07/2024\tStripe Clearing\t25.50"));
+        assert!(iif.contains("SPL\t\tGENERAL JOURNAL\t03/07/2024\tCustomer Credit Liability\t-25.50"));
+        assert!(iif.trim_end().ends_with("ENDTRNS"));
+    }
+
+    #[test]
+    fn test_xero_csv_escapes_fields() {
+        let (entries, mappings) = sample();
+        let csv = render_xero_csv(&entries, &mappings);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].contains("\"Deposit, via \"\"card\"\"\""));
+        assert!(lines[1].ends_with(",1010,Tax Exempt,25.50"));
+        assert!(lines[2].ends_with(",2100,Tax Exempt,-25.50"));
+    }
+
+    #[test]
+    fn test_quickbooks_csv_debit_and_credit_columns() {
+        let (entries, mappings) = sample();
+        let csv = render_quickbooks_csv(&entries, &mappings);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert!(lines[1].contains(",Stripe Clearing,25.50,,"));
+        assert!(lines[2].contains(",Customer Credit Liability,,25.50,"));
+    }
+
+    #[test]
+    fn test_ledger_categories() {
+        assert_eq!(LedgerCategory::from_transaction_type(None), LedgerCategory::Refund);
+        assert_eq!(
+            LedgerCategory::from_transaction_type(Some(TransactionType::CreditDeposit)),
+            LedgerCategory::Payment
+        );
+        assert_eq!(
+            LedgerCategory::from_transaction_type(Some(TransactionType::FreeItemRedemption)),
+            LedgerCategory::CreditRedeemed
+        );
+        assert_eq!(
+            LedgerCategory::from_transaction_type(Some(TransactionType::SellerApiUsageFee)),
+            LedgerCategory::Fee
+        );
+        assert_eq!(
+            LedgerCategory::from_transaction_type(Some(TransactionType::CreditWithdrawal)),
+            LedgerCategory::Payout
+        );
+    }
+
+    #[test]
+    fn test_month_range_covers_whole_months() {
+        let (start, end) = month_range(parse_month("2024-11").unwrap(), parse_month("2025-01").unwrap()).unwrap();
+
+        assert_eq!(start, Utc.with_ymd_and_hms(2024, 11, 1, 0, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap());
+        assert!(parse_month("2024-13").is_err());
+        assert!(month_range(parse_month("2024-02").unwrap(), parse_month("2024-01").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_ledger_csv_line_matches_header() {
+        let line = LedgerLine {
+            id: Uuid::nil(),
+            month: "2024-03".to_string(),
+            occurred_at: Utc.with_ymd_and_hms(2024, 3, 7, 12, 0, 0).unwrap(),
+            category: LedgerCategory::Refund,
+            transaction_type: None,
+            user_id: Some(Uuid::nil()),
+            seller_id: None,
+            amount: Decimal::new(-1000, 2),
+            reference: Some("re_123".to_string()),
+        };
+        let csv = render_ledger_csv_line(&line);
+
+        assert_eq!(csv.split(',').count(), LEDGER_CSV_HEADER.split(',').count());
+        assert!(csv.starts_with("2024-03,2024-03-07T12:00:00+00:00,refund,,"));
+        assert!(csv.ends_with(",,-10.00,re_123\n"));
+    }
+}
@@ -0,0 +1,563 @@
+use crate::error::AppError;
+use crate::models::partner_api_key::PartnerApiKey;
+use crate::models::system_settings::SystemSetting;
+use crate::services::notification_service::NotificationService;
+use crate::services::seller_webhook_service::SellerWebhookEvent;
+use chrono::{DateTime, Utc};
+use rand::seq::SliceRandom;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const DEFAULT_MAX_RAFFLES: i64 = 100;
+const DEFAULT_MAX_TEST_KEYS: i64 = 5;
+const MAX_BOXES_PER_PURCHASE: usize = 100;
+
+/// Sandbox service lets sellers exercise an integration with test-mode API keys
+///
+/// Sandbox items, raffles and purchases are kept in their own tables and every response
+/// carries `"sandbox": true`. No credits move, nothing is written on chain, and nothing
+/// reaches analytics, fees or payouts. Webhooks are still sent, flagged the same way,
+/// so sellers can test their receivers.
+#[derive(Clone)]
+pub struct SandboxService {
+    db_pool: PgPool,
+    notification_service: NotificationService,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SandboxRaffleStatus {
+    Open,
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SandboxItem {
+    pub id: Uuid,
+    pub seller_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub images: Vec<String>,
+    pub retail_price: Decimal,
+    pub sandbox: bool,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SandboxRaffle {
+    pub id: Uuid,
+    pub seller_id: Uuid,
+    pub item_id: Uuid,
+    pub total_boxes: i32,
+    pub box_price: Decimal,
+    pub boxes_sold: i32,
+    pub total_winners: i32,
+    pub status: SandboxRaffleStatus,
+    pub winning_box_numbers: Vec<i32>,
+    pub sandbox: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SandboxPurchase {
+    pub id: Uuid,
+    pub raffle_id: Uuid,
+    pub box_number: i32,
+    pub buyer_reference: Option<String>,
+    pub amount: Decimal,
+    pub sandbox: bool,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateSandboxItemRequest {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub images: Vec<String>,
+    pub retail_price: Decimal,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateSandboxRaffleRequest {
+    pub item_id: Uuid,
+    pub total_boxes: i32,
+    pub box_price: Decimal,
+    pub total_winners: i32,
+}
+
+/// Fake purchase: pick boxes by number, or let the sandbox take the next `count` free ones
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct SandboxPurchaseRequest {
+    #[serde(default)]
+    pub box_numbers: Vec<i32>,
+    pub count: Option<usize>,
+    pub buyer_reference: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SandboxPurchaseResult {
+    pub purchases: Vec<SandboxPurchase>,
+    pub raffle: SandboxRaffle,
+}
+
+/// What a purge removed
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct SandboxPurgeReport {
+    pub items: u64,
+    pub raffles: u64,
+    pub purchases: u64,
+}
+
+impl SandboxService {
+    /// Create a new sandbox service
+    pub fn new(db_pool: PgPool, notification_service: NotificationService) -> Self {
+        Self {
+            db_pool,
+            notification_service,
+        }
+    }
+
+    /// Issue a test-mode API key to a seller
+    pub async fn create_test_key(&self, seller_id: Uuid, name: &str) -> Result<(PartnerApiKey, String), AppError> {
+        let name = name.trim();
+        if name.is_empty() || name.len() > 100 {
+            return Err(AppError::Validation("API key name must be 1 to 100 characters".to_string()));
+        }
+
+        let max_keys = self.get_setting("sandbox_max_test_keys", DEFAULT_MAX_TEST_KEYS).await?;
+        if PartnerApiKey::count_active_test_keys(&self.db_pool, seller_id).await? >= max_keys {
+            return Err(AppError::Validation(format!(
+                "A seller can hold at most {} active test keys",
+                max_keys
+            )));
+        }
+
+        let (api_key, key) = PartnerApiKey::create_test_key(&self.db_pool, name, seller_id).await?;
+
+        info!("Seller {} issued test API key {}", seller_id, api_key.id);
+
+        Ok((api_key, key))
+    }
+
+    /// Create a sandbox item
+    pub async fn create_item(&self, seller_id: Uuid, request: CreateSandboxItemRequest) -> Result<SandboxItem, AppError> {
+        if request.name.trim().is_empty() || request.name.len() > 255 {
+            return Err(AppError::Validation("Item name must be 1 to 255 characters".to_string()));
+        }
+        if request.retail_price <= Decimal::ZERO {
+            return Err(AppError::Validation("Retail price must be positive".to_string()));
+        }
+
+        let item = sqlx::query_as!(
+            SandboxItem,
+            r#"
+            INSERT INTO sandbox_items (seller_id, name, description, images, retail_price)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, seller_id, name, description, images, retail_price, TRUE as "sandbox!", created_at
+            "#,
+            seller_id,
+            request.name.trim(),
+            request.description,
+            &request.images,
+            request.retail_price
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(item)
+    }
+
+    /// A seller's sandbox items, newest first
+    pub async fn list_items(&self, seller_id: Uuid) -> Result<Vec<SandboxItem>, AppError> {
+        let items = sqlx::query_as!(
+            SandboxItem,
+            r#"
+            SELECT id, seller_id, name, description, images, retail_price, TRUE as "sandbox!", created_at
+            FROM sandbox_items
+            WHERE seller_id = $1
+            ORDER BY created_at DESC
+            "#,
+            seller_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(items)
+    }
+
+    /// Open a sandbox raffle for one of the seller's sandbox items
+    pub async fn create_raffle(
+        &self,
+        seller_id: Uuid,
+        request: CreateSandboxRaffleRequest,
+    ) -> Result<SandboxRaffle, AppError> {
+        if request.total_boxes < 1 || request.total_boxes > 10000 {
+            return Err(AppError::Validation("Total boxes must be 1 to 10000".to_string()));
+        }
+        if request.total_winners < 1 || request.total_winners > request.total_boxes {
+            return Err(AppError::Validation("Total winners must be between 1 and the number of boxes".to_string()));
+        }
+        if request.box_price <= Decimal::ZERO {
+            return Err(AppError::Validation("Box price must be positive".to_string()));
+        }
+
+        let item_exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM sandbox_items WHERE id = $1 AND seller_id = $2)",
+            request.item_id,
+            seller_id
+        )
+        .fetch_one(&self.db_pool)
+        .await?
+        .unwrap_or(false);
+
+        if !item_exists {
+            return Err(AppError::NotFound("Sandbox item not found".to_string()));
+        }
+
+        let max_raffles = self.get_setting("sandbox_max_raffles", DEFAULT_MAX_RAFFLES).await?;
+        let existing = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM sandbox_raffles WHERE seller_id = $1",
+            seller_id
+        )
+        .fetch_one(&self.db_pool)
+        .await?
+        .unwrap_or(0);
+
+        if existing >= max_raffles {
+            return Err(AppError::Validation(format!(
+                "Sandbox is limited to {} raffles; purge it to start over",
+                max_raffles
+            )));
+        }
+
+        let raffle = sqlx::query_as!(
+            SandboxRaffle,
+            r#"
+            INSERT INTO sandbox_raffles (seller_id, item_id, total_boxes, box_price, total_winners)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, seller_id, item_id, total_boxes, box_price, boxes_sold, total_winners,
+                      status as "status: SandboxRaffleStatus", winning_box_numbers,
+                      TRUE as "sandbox!", created_at, completed_at
+            "#,
+            seller_id,
+            request.item_id,
+            request.total_boxes,
+            request.box_price,
+            request.total_winners
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(raffle)
+    }
+
+    /// A seller's sandbox raffles, newest first
+    pub async fn list_raffles(&self, seller_id: Uuid) -> Result<Vec<SandboxRaffle>, AppError> {
+        let raffles = sqlx::query_as!(
+            SandboxRaffle,
+            r#"
+            SELECT id, seller_id, item_id, total_boxes, box_price, boxes_sold, total_winners,
+                   status as "status: SandboxRaffleStatus", winning_box_numbers,
+                   TRUE as "sandbox!", created_at, completed_at
+            FROM sandbox_raffles
+            WHERE seller_id = $1
+            ORDER BY created_at DESC
+            "#,
+            seller_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(raffles)
+    }
+
+    /// Get one of a seller's sandbox raffles
+    pub async fn get_raffle(&self, seller_id: Uuid, raffle_id: Uuid) -> Result<SandboxRaffle, AppError> {
+        sqlx::query_as!(
+            SandboxRaffle,
+            r#"
+            SELECT id, seller_id, item_id, total_boxes, box_price, boxes_sold, total_winners,
+                   status as "status: SandboxRaffleStatus", winning_box_numbers,
+                   TRUE as "sandbox!", created_at, completed_at
+            FROM sandbox_raffles
+            WHERE id = $1 AND seller_id = $2
+            "#,
+            raffle_id,
+            seller_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Sandbox raffle not found".to_string()))
+    }
+
+    /// Record fake purchases; a raffle that sells out is drawn at random straight away
+    pub async fn simulate_purchase(
+        &self,
+        seller_id: Uuid,
+        raffle_id: Uuid,
+        request: SandboxPurchaseRequest,
+    ) -> Result<SandboxPurchaseResult, AppError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        let raffle = sqlx::query_as!(
+            SandboxRaffle,
+            r#"
+            SELECT id, seller_id, item_id, total_boxes, box_price, boxes_sold, total_winners,
+                   status as "status: SandboxRaffleStatus", winning_box_numbers,
+                   TRUE as "sandbox!", created_at, completed_at
+            FROM sandbox_raffles
+            WHERE id = $1 AND seller_id = $2
+            FOR UPDATE
+            "#,
+            raffle_id,
+            seller_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Sandbox raffle not found".to_string()))?;
+
+        if raffle.status != SandboxRaffleStatus::Open {
+            return Err(AppError::Validation("Sandbox raffle is already completed".to_string()));
+        }
+
+        let taken: HashSet<i32> = sqlx::query_scalar!(
+            "SELECT box_number FROM sandbox_purchases WHERE raffle_id = $1",
+            raffle_id
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .collect();
+
+        let box_numbers = select_boxes(&request.box_numbers, request.count, &taken, raffle.total_boxes)?;
+
+        let mut purchases = Vec::with_capacity(box_numbers.len());
+        for box_number in &box_numbers {
+            let purchase = sqlx::query_as!(
+                SandboxPurchase,
+                r#"
+                INSERT INTO sandbox_purchases (raffle_id, box_number, buyer_reference, amount)
+                VALUES ($1, $2, $3, $4)
+                RETURNING id, raffle_id, box_number, buyer_reference, amount, TRUE as "sandbox!", created_at
+                "#,
+                raffle_id,
+                box_number,
+                request.buyer_reference,
+                raffle.box_price
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+
+            purchases.push(purchase);
+        }
+
+        let boxes_sold = raffle.boxes_sold + box_numbers.len() as i32;
+        let sold_out = boxes_sold >= raffle.total_boxes;
+        let winning_box_numbers = if sold_out {
+            let sold: Vec<i32> = taken.iter().copied().chain(box_numbers.iter().copied()).collect();
+            pick_winning_boxes(&sold, raffle.total_winners as usize)
+        } else {
+            Vec::new()
+        };
+
+        let raffle = sqlx::query_as!(
+            SandboxRaffle,
+            r#"
+            UPDATE sandbox_raffles
+            SET boxes_sold = $2,
+                status = CASE WHEN $3 THEN 'completed' ELSE status END,
+                winning_box_numbers = $4,
+                completed_at = CASE WHEN $3 THEN NOW() ELSE completed_at END
+            WHERE id = $1
+            RETURNING id, seller_id, item_id, total_boxes, box_price, boxes_sold, total_winners,
+                      status as "status: SandboxRaffleStatus", winning_box_numbers,
+                      TRUE as "sandbox!", created_at, completed_at
+            "#,
+            raffle_id,
+            boxes_sold,
+            sold_out,
+            &winning_box_numbers
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        self.notification_service.notify_seller(seller_id, SellerWebhookEvent::BoxPurchased, serde_json::json!({
+            "raffle_id": raffle.id,
+            "item_id": raffle.item_id,
+            "box_numbers": box_numbers,
+            "amount": raffle.box_price * Decimal::from(box_numbers.len()),
+            "boxes_sold": raffle.boxes_sold,
+            "total_boxes": raffle.total_boxes,
+            "sandbox": true
+        })).await;
+
+        if sold_out {
+            self.notification_service.notify_seller(seller_id, SellerWebhookEvent::ItemSoldOut, serde_json::json!({
+                "raffle_id": raffle.id,
+                "item_id": raffle.item_id,
+                "total_boxes": raffle.total_boxes,
+                "box_price": raffle.box_price,
+                "sandbox": true
+            })).await;
+
+            self.notification_service.notify_seller(seller_id, SellerWebhookEvent::RaffleCompleted, serde_json::json!({
+                "raffle_id": raffle.id,
+                "item_id": raffle.item_id,
+                "boxes_sold": raffle.boxes_sold,
+                "total_boxes": raffle.total_boxes,
+                "winning_box_numbers": raffle.winning_box_numbers,
+                "sandbox": true
+            })).await;
+        }
+
+        Ok(SandboxPurchaseResult { purchases, raffle })
+    }
+
+    /// Fake purchases of one of a seller's sandbox raffles
+    pub async fn list_purchases(&self, seller_id: Uuid, raffle_id: Uuid) -> Result<Vec<SandboxPurchase>, AppError> {
+        // Ownership check
+        self.get_raffle(seller_id, raffle_id).await?;
+
+        let purchases = sqlx::query_as!(
+            SandboxPurchase,
+            r#"
+            SELECT id, raffle_id, box_number, buyer_reference, amount, TRUE as "sandbox!", created_at
+            FROM sandbox_purchases
+            WHERE raffle_id = $1
+            ORDER BY box_number
+            "#,
+            raffle_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(purchases)
+    }
+
+    /// Delete everything in a seller's sandbox; test keys are kept
+    pub async fn purge(&self, seller_id: Uuid) -> Result<SandboxPurgeReport, AppError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        let purchases = sqlx::query!(
+            r#"
+            DELETE FROM sandbox_purchases
+            WHERE raffle_id IN (SELECT id FROM sandbox_raffles WHERE seller_id = $1)
+            "#,
+            seller_id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        let raffles = sqlx::query!("DELETE FROM sandbox_raffles WHERE seller_id = $1", seller_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        let items = sqlx::query!("DELETE FROM sandbox_items WHERE seller_id = $1", seller_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        tx.commit().await?;
+
+        info!(
+            "Purged sandbox of seller {}: {} items, {} raffles, {} purchases",
+            seller_id, items, raffles, purchases
+        );
+
+        Ok(SandboxPurgeReport { items, raffles, purchases })
+    }
+
+    // Private helper methods
+
+    async fn get_setting(&self, key: &str, default: i64) -> Result<i64, AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, key).await?;
+        Ok(setting
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default))
+    }
+}
+
+/// Boxes for a fake purchase: the requested numbers, or else the lowest `count` free boxes
+fn select_boxes(
+    requested: &[i32],
+    count: Option<usize>,
+    taken: &HashSet<i32>,
+    total_boxes: i32,
+) -> Result<Vec<i32>, AppError> {
+    if !requested.is_empty() {
+        if requested.len() > MAX_BOXES_PER_PURCHASE {
+            return Err(AppError::Validation(format!("At most {} boxes per purchase", MAX_BOXES_PER_PURCHASE)));
+        }
+
+        let mut seen = HashSet::new();
+        for &box_number in requested {
+            if box_number < 1 || box_number > total_boxes {
+                return Err(AppError::Validation(format!("Invalid box number: {}", box_number)));
+            }
+            if taken.contains(&box_number) || !seen.insert(box_number) {
+                return Err(AppError::Validation(format!("Box {} is already purchased", box_number)));
+            }
+        }
+        return Ok(requested.to_vec());
+    }
+
+    let count = count.unwrap_or(1);
+    if count == 0 || count > MAX_BOXES_PER_PURCHASE {
+        return Err(AppError::Validation(format!("Count must be 1 to {}", MAX_BOXES_PER_PURCHASE)));
+    }
+
+    let boxes: Vec<i32> = (1..=total_boxes).filter(|n| !taken.contains(n)).take(count).collect();
+    if boxes.len() < count {
+        return Err(AppError::Validation("Not enough boxes available".to_string()));
+    }
+    Ok(boxes)
+}
+
+/// Simulated draw; real raffles use Chainlink VRF
+fn pick_winning_boxes(sold: &[i32], winners: usize) -> Vec<i32> {
+    let mut rng = rand::thread_rng();
+    sold.choose_multiple(&mut rng, winners).copied().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_boxes() {
+        let taken: HashSet<i32> = [1, 2, 4].into_iter().collect();
+
+        assert_eq!(select_boxes(&[], Some(2), &taken, 10).unwrap(), vec![3, 5]);
+        assert_eq!(select_boxes(&[], None, &taken, 10).unwrap(), vec![3]);
+        assert_eq!(select_boxes(&[7, 9], None, &taken, 10).unwrap(), vec![7, 9]);
+
+        assert!(select_boxes(&[2], None, &taken, 10).is_err());
+        assert!(select_boxes(&[7, 7], None, &taken, 10).is_err());
+        assert!(select_boxes(&[11], None, &taken, 10).is_err());
+        assert!(select_boxes(&[], Some(3), &taken, 5).is_err());
+        assert!(select_boxes(&[], Some(0), &taken, 10).is_err());
+    }
+
+    #[test]
+    fn test_pick_winning_boxes_are_distinct_sold_boxes() {
+        let sold = vec![3, 5, 8, 13, 21];
+        let winners = pick_winning_boxes(&sold, 3);
+
+        assert_eq!(winners.len(), 3);
+        assert_eq!(winners.iter().collect::<HashSet<_>>().len(), 3);
+        assert!(winners.iter().all(|w| sold.contains(w)));
+    }
+}
@@ -0,0 +1,374 @@
+use crate::error::AppError;
+use crate::models::item::Item;
+use crate::models::raffle_listing::RaffleListing;
+use crate::services::cache_invalidation::{self, CacheTag, TaggedCache};
+use crate::services::media_service::MediaService;
+use raffle_platform_shared::{ItemResponse, RaffleResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::time::Duration;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+const MIN_QUERY_LENGTH: usize = 2;
+const MAX_QUERY_LENGTH: usize = 100;
+/// Words past this are ignored
+const MAX_QUERY_TERMS: usize = 8;
+const DEFAULT_GROUP_LIMIT: i64 = 5;
+const MAX_GROUP_LIMIT: i64 = 50;
+/// How long a group page is served from the cache; writes to what it lists invalidate it sooner
+const SEARCH_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Unified search service finds items, raffles and sellers matching one query, each group
+/// ranked by full-text relevance and paged on its own.
+#[derive(Clone)]
+pub struct SearchService {
+    db_pool: PgPool,
+    media_service: MediaService,
+    cache: TaggedCache,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchType {
+    Items,
+    Raffles,
+    Sellers,
+}
+
+impl SearchType {
+    pub const ALL: [SearchType; 3] = [SearchType::Items, SearchType::Raffles, SearchType::Sellers];
+
+    fn parse(value: &str) -> Result<Self, AppError> {
+        match value.trim() {
+            "items" => Ok(SearchType::Items),
+            "raffles" => Ok(SearchType::Raffles),
+            "sellers" => Ok(SearchType::Sellers),
+            other => Err(AppError::Validation(format!(
+                "Unknown search type: {} (expected items, raffles or sellers)",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UnifiedSearchQuery {
+    /// Words to search for; each matches as a word prefix
+    pub q: String,
+    /// Comma-separated groups to search: items, raffles, sellers. Defaults to all three
+    pub types: Option<String>,
+    /// Results per group, 5 by default and at most 50
+    pub limit: Option<i64>,
+    pub items_offset: Option<i64>,
+    pub raffles_offset: Option<i64>,
+    pub sellers_offset: Option<i64>,
+}
+
+/// One page of a result group, most relevant first
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[aliases(
+    ItemSearchGroup = SearchGroup<ItemSearchHit>,
+    RaffleSearchGroup = SearchGroup<RaffleSearchHit>,
+    SellerSearchGroup = SearchGroup<SellerSearchHit>
+)]
+pub struct SearchGroup<T> {
+    pub results: Vec<T>,
+    /// Matches across every page; 0 when `offset` is past the last one
+    pub total: i64,
+    pub offset: i64,
+    pub limit: i64,
+    /// Pass `offset + limit` as this group's offset for the next page
+    pub has_more: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ItemSearchHit {
+    /// Relevance between 0 and 1
+    pub score: f32,
+    pub item: ItemResponse,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RaffleSearchHit {
+    /// Relevance between 0 and 1
+    pub score: f32,
+    pub raffle: RaffleResponse,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SellerSearchHit {
+    /// Relevance between 0 and 1
+    pub score: f32,
+    pub user_id: Uuid,
+    pub company_name: String,
+    pub description: Option<String>,
+    pub is_verified: bool,
+}
+
+/// Groups that weren't asked for are null
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UnifiedSearchResults {
+    pub query: String,
+    pub items: Option<ItemSearchGroup>,
+    pub raffles: Option<RaffleSearchGroup>,
+    pub sellers: Option<SellerSearchGroup>,
+}
+
+#[derive(Debug, FromRow)]
+struct ItemRow {
+    #[sqlx(flatten)]
+    item: Item,
+    score: f32,
+    total_matches: i64,
+}
+
+#[derive(Debug, FromRow)]
+struct SellerRow {
+    user_id: Uuid,
+    company_name: String,
+    description: Option<String>,
+    is_verified: bool,
+    score: f32,
+    total_matches: i64,
+}
+
+impl SearchService {
+    pub fn new(db_pool: PgPool, media_service: MediaService) -> Self {
+        Self {
+            db_pool,
+            media_service,
+            cache: TaggedCache::default(),
+        }
+    }
+
+    /// Cache group pages and invalidate them when what they list changes
+    pub fn with_cache(mut self, cache: TaggedCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Search every requested group
+    pub async fn search(&self, query: &UnifiedSearchQuery) -> Result<UnifiedSearchResults, AppError> {
+        let q = query.q.trim();
+        let length = q.chars().count();
+        if !(MIN_QUERY_LENGTH..=MAX_QUERY_LENGTH).contains(&length) {
+            return Err(AppError::Validation(format!(
+                "Search query must be {} to {} characters",
+                MIN_QUERY_LENGTH, MAX_QUERY_LENGTH
+            )));
+        }
+        let tsquery = prefix_tsquery(q)
+            .ok_or_else(|| AppError::Validation("Search query must contain a letter or digit".to_string()))?;
+
+        let types = parse_types(query.types.as_deref())?;
+        let limit = query.limit.unwrap_or(DEFAULT_GROUP_LIMIT).clamp(1, MAX_GROUP_LIMIT);
+        let offset = |offset: Option<i64>| offset.unwrap_or(0).max(0);
+
+        let mut results = UnifiedSearchResults {
+            query: q.to_string(),
+            items: None,
+            raffles: None,
+            sellers: None,
+        };
+
+        if types.contains(&SearchType::Items) {
+            results.items = Some(self.search_items(&tsquery, limit, offset(query.items_offset)).await?);
+        }
+        if types.contains(&SearchType::Raffles) {
+            results.raffles = Some(self.search_raffles(&tsquery, limit, offset(query.raffles_offset)).await?);
+        }
+        if types.contains(&SearchType::Sellers) {
+            results.sellers = Some(self.search_sellers(&tsquery, limit, offset(query.sellers_offset)).await?);
+        }
+
+        Ok(results)
+    }
+
+    // Private helper methods
+
+    async fn search_items(&self, tsquery: &str, limit: i64, offset: i64) -> Result<ItemSearchGroup, AppError> {
+        let key = cache_invalidation::query_key("search_items", &(tsquery, limit, offset));
+
+        self.cache
+            .get_or_load(&key, &[CacheTag::ItemListings], SEARCH_CACHE_TTL, || async {
+                // Only available items are listed, as in item search
+                let rows = sqlx::query_as::<_, ItemRow>(
+                    r#"
+                    SELECT
+                        id, seller_id, name, description, images, retail_price, currency, cost_of_goods, status,
+                        stock_quantity, listing_fee_applied, listing_fee_type, created_at, updated_at,
+                        ts_rank(search_vector, query, 32) AS score, COUNT(*) OVER () AS total_matches
+                    FROM items
+                    CROSS JOIN to_tsquery('english', $1) query
+                    WHERE status = 'available' AND stock_quantity > 0
+                    AND search_vector @@ query
+                    ORDER BY score DESC, created_at DESC, id
+                    LIMIT $2 OFFSET $3
+                    "#,
+                )
+                .bind(tsquery)
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&self.db_pool)
+                .await?;
+
+                let total = rows.first().map(|row| row.total_matches).unwrap_or(0);
+                let results = rows
+                    .into_iter()
+                    .map(|row| {
+                        let mut item = row.item.to_response();
+                        self.media_service.apply_to_item(&mut item);
+                        ItemSearchHit { score: row.score, item }
+                    })
+                    .collect();
+
+                Ok(search_group(results, total, offset, limit))
+            })
+            .await
+    }
+
+    async fn search_raffles(&self, tsquery: &str, limit: i64, offset: i64) -> Result<RaffleSearchGroup, AppError> {
+        let key = cache_invalidation::query_key("search_raffles", &(tsquery, limit, offset));
+
+        // Listings carry their item and seller, so edits to either change them too
+        let tags = [CacheTag::RaffleListings, CacheTag::ItemListings, CacheTag::SellerListings];
+
+        self.cache
+            .get_or_load(&key, &tags, SEARCH_CACHE_TTL, || async {
+                let rows = RaffleListing::full_text_search(&self.db_pool, tsquery, limit, offset).await?;
+
+                let total = rows.first().map(|row| row.total_matches).unwrap_or(0);
+                let results = rows
+                    .into_iter()
+                    .map(|row| {
+                        let mut raffle = row.listing.to_response();
+                        self.media_service.apply_to_raffle(&mut raffle);
+                        RaffleSearchHit { score: row.score, raffle }
+                    })
+                    .collect();
+
+                Ok(search_group(results, total, offset, limit))
+            })
+            .await
+    }
+
+    async fn search_sellers(&self, tsquery: &str, limit: i64, offset: i64) -> Result<SellerSearchGroup, AppError> {
+        let key = cache_invalidation::query_key("search_sellers", &(tsquery, limit, offset));
+
+        self.cache
+            .get_or_load(&key, &[CacheTag::SellerListings], SEARCH_CACHE_TTL, || async {
+                let rows = sqlx::query_as::<_, SellerRow>(
+                    r#"
+                    SELECT
+                        s.user_id, s.company_name, s.description, COALESCE(s.is_verified, FALSE) AS is_verified,
+                        ts_rank(s.search_vector, query, 32) AS score, COUNT(*) OVER () AS total_matches
+                    FROM sellers s
+                    JOIN users u ON u.id = s.user_id
+                    CROSS JOIN to_tsquery('english', $1) query
+                    WHERE COALESCE(u.is_active, TRUE) AND s.company_name IS NOT NULL
+                    AND s.search_vector @@ query
+                    ORDER BY score DESC, is_verified DESC, s.user_id
+                    LIMIT $2 OFFSET $3
+                    "#,
+                )
+                .bind(tsquery)
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&self.db_pool)
+                .await?;
+
+                let total = rows.first().map(|row| row.total_matches).unwrap_or(0);
+                let results = rows
+                    .into_iter()
+                    .map(|row| SellerSearchHit {
+                        score: row.score,
+                        user_id: row.user_id,
+                        company_name: row.company_name,
+                        description: row.description,
+                        is_verified: row.is_verified,
+                    })
+                    .collect();
+
+                Ok(search_group(results, total, offset, limit))
+            })
+            .await
+    }
+}
+
+/// `to_tsquery` text requiring every word, each as a prefix. Only letters and digits are kept,
+/// so user input can't inject tsquery operators.
+pub(crate) fn prefix_tsquery(q: &str) -> Option<String> {
+    let terms: Vec<String> = q
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .take(MAX_QUERY_TERMS)
+        .map(|term| format!("{}:*", term.to_lowercase()))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" & "))
+    }
+}
+
+fn parse_types(types: Option<&str>) -> Result<Vec<SearchType>, AppError> {
+    let Some(types) = types.filter(|types| !types.trim().is_empty()) else {
+        return Ok(SearchType::ALL.to_vec());
+    };
+
+    let mut parsed = Vec::new();
+    for value in types.split(',') {
+        let search_type = SearchType::parse(value)?;
+        if !parsed.contains(&search_type) {
+            parsed.push(search_type);
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn search_group<T>(results: Vec<T>, total: i64, offset: i64, limit: i64) -> SearchGroup<T> {
+    SearchGroup {
+        has_more: offset + (results.len() as i64) < total,
+        results,
+        total,
+        offset,
+        limit,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_tsquery() {
+        assert_eq!(prefix_tsquery("Air Jordan").as_deref(), Some("air:* & jordan:*"));
+        assert_eq!(prefix_tsquery("  lego's & star-wars!").as_deref(), Some("lego:* & s:* & star:* & wars:*"));
+        assert_eq!(prefix_tsquery("!&|:*()"), None);
+
+        let long = "a b c d e f g h i j";
+        assert_eq!(prefix_tsquery(long).unwrap().matches(":*").count(), MAX_QUERY_TERMS);
+    }
+
+    #[test]
+    fn test_parse_types() {
+        assert_eq!(parse_types(None).unwrap(), SearchType::ALL.to_vec());
+        assert_eq!(parse_types(Some("")).unwrap(), SearchType::ALL.to_vec());
+        assert_eq!(
+            parse_types(Some("sellers, items,sellers")).unwrap(),
+            vec![SearchType::Sellers, SearchType::Items]
+        );
+        assert!(parse_types(Some("items,users")).is_err());
+    }
+
+    #[test]
+    fn test_search_group_has_more() {
+        assert!(search_group(vec![1, 2], 5, 0, 2).has_more);
+        assert!(!search_group(vec![5], 5, 4, 2).has_more);
+        assert!(!search_group(Vec::<i32>::new(), 0, 10, 2).has_more);
+    }
+}
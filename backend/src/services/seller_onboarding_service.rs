@@ -0,0 +1,225 @@
+use crate::error::AppError;
+use crate::models::seller_onboarding::{OnboardingStep, SellerOnboarding};
+use crate::models::{Notification, Seller, SellerSubscription};
+use crate::services::cache_invalidation::{CacheTag, TaggedCache};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use sqlx::PgPool;
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const MAX_COMPANY_NAME_LENGTH: usize = 255;
+const MAX_DESCRIPTION_LENGTH: usize = 2000;
+const SUBSCRIPTION_PERIOD_DAYS: i64 = 30;
+const CHECKLIST_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Seller onboarding service walks a new seller through the steps that unlock listing
+/// items and running raffles
+#[derive(Clone)]
+pub struct SellerOnboardingService {
+    db_pool: PgPool,
+    cache: TaggedCache,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateSellerProfileRequest {
+    pub company_name: String,
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ConnectPayoutRequest {
+    /// Stripe connected account, e.g. `acct_1Nv0FGQ9RKHgCVdK`
+    pub account_id: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SelectSubscriptionRequest {
+    pub subscription_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetKycStatusRequest {
+    pub passed: bool,
+}
+
+impl SellerOnboardingService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool, cache: TaggedCache::default() }
+    }
+
+    /// Cache checklists and invalidate cached seller search results when a seller's profile changes
+    pub fn with_cache(mut self, cache: TaggedCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub async fn get_checklist(&self, user_id: Uuid) -> Result<SellerOnboarding, AppError> {
+        self.cache
+            .get_or_load(
+                &format!("seller_checklist:{}", user_id),
+                &[CacheTag::Seller(user_id)],
+                CHECKLIST_CACHE_TTL,
+                || SellerOnboarding::load(&self.db_pool, user_id),
+            )
+            .await
+    }
+
+    /// Save the seller's company name and description, creating their seller profile
+    pub async fn update_profile(
+        &self,
+        user_id: Uuid,
+        request: UpdateSellerProfileRequest,
+    ) -> Result<SellerOnboarding, AppError> {
+        let company_name = request.company_name.trim();
+        let description = request.description.trim();
+
+        if company_name.is_empty() || description.is_empty() {
+            return Err(AppError::Validation("Company name and description are required".to_string()));
+        }
+        if company_name.chars().count() > MAX_COMPANY_NAME_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Company name must be at most {} characters",
+                MAX_COMPANY_NAME_LENGTH
+            )));
+        }
+        if description.chars().count() > MAX_DESCRIPTION_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Description must be at most {} characters",
+                MAX_DESCRIPTION_LENGTH
+            )));
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO sellers (user_id, company_name, description, is_verified)
+            SELECT $1, $2, $3, u.kyc_status = 'verified' FROM users u WHERE u.id = $1
+            ON CONFLICT (user_id) DO UPDATE
+            SET company_name = EXCLUDED.company_name, description = EXCLUDED.description, updated_at = NOW()
+            "#,
+        )
+        .bind(user_id)
+        .bind(company_name)
+        .bind(description)
+        .execute(&self.db_pool)
+        .await?;
+        self.cache.invalidate(&[CacheTag::Seller(user_id), CacheTag::SellerListings]).await;
+
+        self.get_checklist(user_id).await
+    }
+
+    /// Record the Stripe account the seller's payouts go to
+    pub async fn connect_payout(
+        &self,
+        user_id: Uuid,
+        request: ConnectPayoutRequest,
+    ) -> Result<SellerOnboarding, AppError> {
+        let account_id = request.account_id.trim();
+        if !is_connected_account_id(account_id) {
+            return Err(AppError::Validation("account_id must be a Stripe connected account (acct_...)".to_string()));
+        }
+
+        let seller = self.require_step_available(user_id, OnboardingStep::PayoutConnected).await?;
+
+        sqlx::query("UPDATE sellers SET payout_details = $1, updated_at = NOW() WHERE id = $2")
+            .bind(serde_json::json!({ "provider": "stripe", "account_id": account_id }))
+            .bind(seller.id)
+            .execute(&self.db_pool)
+            .await?;
+        self.cache.invalidate(&[CacheTag::Seller(user_id)]).await;
+
+        info!("Seller {} connected payout account", user_id);
+
+        self.get_checklist(user_id).await
+    }
+
+    /// Put the seller on an active subscription tier for a billing period
+    pub async fn select_subscription(
+        &self,
+        user_id: Uuid,
+        request: SelectSubscriptionRequest,
+    ) -> Result<SellerOnboarding, AppError> {
+        let subscription = SellerSubscription::find_by_id(&self.db_pool, request.subscription_id)
+            .await?
+            .filter(|subscription| subscription.is_active)
+            .ok_or_else(|| AppError::NotFound("Subscription plan not found".to_string()))?;
+
+        let seller = self.require_step_available(user_id, OnboardingStep::SubscriptionSelected).await?;
+
+        Seller::update_subscription(
+            &self.db_pool,
+            seller.id,
+            subscription.id,
+            Utc::now() + Duration::days(SUBSCRIPTION_PERIOD_DAYS),
+        )
+        .await?;
+        self.cache.invalidate(&[CacheTag::Seller(user_id)]).await;
+
+        info!("Seller {} selected subscription {}", user_id, subscription.name);
+
+        self.get_checklist(user_id).await
+    }
+
+    /// Record the outcome of a seller's identity verification (admin only)
+    pub async fn set_kyc_status(
+        &self,
+        user_id: Uuid,
+        admin_id: Uuid,
+        request: SetKycStatusRequest,
+    ) -> Result<SellerOnboarding, AppError> {
+        let seller = self.require_step_available(user_id, OnboardingStep::KycPassed).await?;
+
+        Seller::update_verification_status(&self.db_pool, seller.id, request.passed).await?;
+        // Keep the user's identity verification in step so prize fulfillment sees it too
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET kyc_status = CASE WHEN $2 THEN 'verified' ELSE 'rejected' END,
+                kyc_verified_at = CASE WHEN $2 THEN COALESCE(kyc_verified_at, NOW()) END,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(request.passed)
+        .execute(&self.db_pool)
+        .await?;
+        self.cache.invalidate(&[CacheTag::Seller(user_id), CacheTag::SellerListings]).await;
+        Notification::create_seller_verification_notification(&self.db_pool, user_id, request.passed).await?;
+
+        info!("Admin {} set KYC for seller {} to passed={}", admin_id, user_id, request.passed);
+
+        self.get_checklist(user_id).await
+    }
+
+    /// The seller's profile, once the step's prerequisites are complete
+    async fn require_step_available(&self, user_id: Uuid, step: OnboardingStep) -> Result<Seller, AppError> {
+        SellerOnboarding::load(&self.db_pool, user_id)
+            .await?
+            .require_available(step)?;
+
+        Seller::find_by_user_id(&self.db_pool, user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Seller profile not found".to_string()))
+    }
+}
+
+fn is_connected_account_id(account_id: &str) -> bool {
+    account_id
+        .strip_prefix("acct_")
+        .is_some_and(|rest| !rest.is_empty() && rest.len() <= 250 && rest.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connected_account_id() {
+        assert!(is_connected_account_id("acct_1Nv0FGQ9RKHgCVdK"));
+        assert!(!is_connected_account_id("acct_"));
+        assert!(!is_connected_account_id("cus_1Nv0FGQ9RKHgCVdK"));
+        assert!(!is_connected_account_id("acct_1Nv0 FGQ9"));
+    }
+}
@@ -0,0 +1,1035 @@
+use crate::error::AppError;
+use crate::models::api_usage::{period_start, SellerApiUsage};
+use crate::models::system_settings::SystemSetting;
+use crate::models::tag::normalize_tags;
+use crate::services::webhook_service::retry_delay_seconds;
+use crate::utils::crypto::generate_secure_token;
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use sqlx::PgPool;
+use tracing::{debug, error, info, warn};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const SIGNATURE_HEADER: &str = "X-Thriftee-Signature";
+pub const EVENT_HEADER: &str = "X-Thriftee-Event";
+pub const DELIVERY_HEADER: &str = "X-Thriftee-Delivery";
+
+const DEFAULT_MAX_ATTEMPTS: i64 = 10;
+const DEFAULT_RETRY_BASE_SECONDS: i64 = 60;
+const DEFAULT_RETRY_MAX_SECONDS: i64 = 21600;
+const DEFAULT_MAX_ENDPOINTS: i64 = 10;
+const DEFAULT_MAX_PLATFORM_ENDPOINTS: i64 = 20;
+const MAX_RAFFLE_TAG_FILTERS: usize = 20;
+const DELIVERY_BATCH_SIZE: i64 = 50;
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+/// A delivery left in `sending` this long is assumed to have died with its worker
+const STALE_SENDING_MINUTES: i32 = 10;
+/// Response bodies kept on a failed attempt, for sellers debugging their endpoint
+const MAX_ERROR_BODY_CHARS: usize = 500;
+
+/// Seller webhook service sends signed event callbacks to URLs registered by sellers,
+/// and to platform endpoints admins register for internal systems
+#[derive(Clone)]
+pub struct SellerWebhookService {
+    db_pool: PgPool,
+    http_client: reqwest::Client,
+}
+
+/// Events an endpoint can subscribe to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+pub enum SellerWebhookEvent {
+    #[serde(rename = "raffle.opened")]
+    RaffleOpened,
+    #[serde(rename = "raffle.completed")]
+    RaffleCompleted,
+    #[serde(rename = "raffle.cancelled")]
+    RaffleCancelled,
+    #[serde(rename = "box.purchased")]
+    BoxPurchased,
+    #[serde(rename = "item.sold_out")]
+    ItemSoldOut,
+}
+
+impl SellerWebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SellerWebhookEvent::RaffleOpened => "raffle.opened",
+            SellerWebhookEvent::RaffleCompleted => "raffle.completed",
+            SellerWebhookEvent::RaffleCancelled => "raffle.cancelled",
+            SellerWebhookEvent::BoxPurchased => "box.purchased",
+            SellerWebhookEvent::ItemSoldOut => "item.sold_out",
+        }
+    }
+
+    /// Events platform endpoints can subscribe to
+    pub fn is_raffle_lifecycle(&self) -> bool {
+        matches!(
+            self,
+            SellerWebhookEvent::RaffleOpened | SellerWebhookEvent::RaffleCompleted | SellerWebhookEvent::RaffleCancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum EndpointDeliveryStatus {
+    Pending,
+    Sending,
+    Delivered,
+    /// The endpoint did not accept the event and a retry is scheduled at `next_attempt_at`
+    Failed,
+    /// Retries are exhausted or the endpoint was disabled
+    Dead,
+}
+
+/// Registered endpoint; the signing secret is only returned when the endpoint is created
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    /// None for platform endpoints
+    pub seller_id: Option<Uuid>,
+    pub url: String,
+    pub event_types: Vec<String>,
+    /// Platform endpoints only receive raffles with one of these tags; empty means every raffle
+    pub raffle_tags: Vec<String>,
+    pub description: Option<String>,
+    pub is_active: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CreatedWebhookEndpoint {
+    #[serde(flatten)]
+    pub endpoint: WebhookEndpoint,
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreateWebhookEndpointRequest {
+    pub url: String,
+    pub event_types: Vec<SellerWebhookEvent>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdateWebhookEndpointRequest {
+    pub url: Option<String>,
+    pub event_types: Option<Vec<SellerWebhookEvent>>,
+    pub description: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreatePlatformWebhookEndpointRequest {
+    pub url: String,
+    pub event_types: Vec<SellerWebhookEvent>,
+    #[serde(default)]
+    pub raffle_tags: Vec<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct UpdatePlatformWebhookEndpointRequest {
+    #[serde(flatten)]
+    pub endpoint: UpdateWebhookEndpointRequest,
+    /// An empty list sends every raffle
+    pub raffle_tags: Option<Vec<String>>,
+}
+
+/// One event sent to one endpoint, with the outcome of its latest attempt
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EndpointDelivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub payload: Value,
+    pub status: EndpointDeliveryStatus,
+    pub attempts: i32,
+    pub last_response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub raffle_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EndpointDeliveryQuery {
+    pub endpoint_id: Option<Uuid>,
+    pub status: Option<EndpointDeliveryStatus>,
+    pub event_type: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+struct EndpointTarget {
+    url: String,
+    secret: String,
+    is_active: bool,
+    /// None for platform endpoints
+    seller_id: Option<Uuid>,
+}
+
+impl SellerWebhookService {
+    /// Create a new seller webhook service
+    pub fn new(db_pool: PgPool) -> Self {
+        let http_client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_default();
+
+        Self { db_pool, http_client }
+    }
+
+    /// Start the delivery loop for pending and failed deliveries
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.deliver_due().await {
+                    error!("Scheduled seller webhook delivery failed: {}", e);
+                }
+            }
+        });
+
+        info!("Seller webhook service background tasks started");
+    }
+
+    /// Register an endpoint for a seller; the returned secret signs every delivery
+    pub async fn create_endpoint(
+        &self,
+        seller_id: Uuid,
+        request: CreateWebhookEndpointRequest,
+    ) -> Result<CreatedWebhookEndpoint, AppError> {
+        validate_endpoint_url(&request.url)?;
+        let event_types = event_type_names(&request.event_types)?;
+
+        let max_endpoints = self.get_setting("seller_webhook_max_endpoints", DEFAULT_MAX_ENDPOINTS).await?;
+        let existing = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM webhook_endpoints WHERE seller_id = $1",
+            seller_id
+        )
+        .fetch_one(&self.db_pool)
+        .await?
+        .unwrap_or(0);
+
+        if existing >= max_endpoints {
+            return Err(AppError::Validation(format!(
+                "A seller can register at most {} webhook endpoints",
+                max_endpoints
+            )));
+        }
+
+        let secret = format!("whsec_{}", generate_secure_token());
+
+        let endpoint = sqlx::query_as!(
+            WebhookEndpoint,
+            r#"
+            INSERT INTO webhook_endpoints (seller_id, url, secret, event_types, description)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, seller_id, url, event_types, raffle_tags, description, is_active, created_at, updated_at
+            "#,
+            seller_id,
+            request.url,
+            secret,
+            &event_types,
+            request.description
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        info!("Seller {} registered webhook endpoint {}", seller_id, endpoint.id);
+
+        Ok(CreatedWebhookEndpoint { endpoint, secret })
+    }
+
+    /// A seller's endpoints, newest first
+    pub async fn list_endpoints(&self, seller_id: Uuid) -> Result<Vec<WebhookEndpoint>, AppError> {
+        let endpoints = sqlx::query_as!(
+            WebhookEndpoint,
+            r#"
+            SELECT id, seller_id, url, event_types, raffle_tags, description, is_active, created_at, updated_at
+            FROM webhook_endpoints
+            WHERE seller_id = $1
+            ORDER BY created_at DESC
+            "#,
+            seller_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(endpoints)
+    }
+
+    /// Change an endpoint; fields left out keep their value
+    pub async fn update_endpoint(
+        &self,
+        seller_id: Uuid,
+        endpoint_id: Uuid,
+        request: UpdateWebhookEndpointRequest,
+    ) -> Result<WebhookEndpoint, AppError> {
+        if let Some(url) = &request.url {
+            validate_endpoint_url(url)?;
+        }
+        let event_types = request.event_types.as_deref().map(event_type_names).transpose()?;
+
+        sqlx::query_as!(
+            WebhookEndpoint,
+            r#"
+            UPDATE webhook_endpoints
+            SET url = COALESCE($3, url),
+                event_types = COALESCE($4, event_types),
+                description = COALESCE($5, description),
+                is_active = COALESCE($6, is_active)
+            WHERE id = $1 AND seller_id = $2
+            RETURNING id, seller_id, url, event_types, raffle_tags, description, is_active, created_at, updated_at
+            "#,
+            endpoint_id,
+            seller_id,
+            request.url,
+            event_types.as_deref(),
+            request.description,
+            request.is_active
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Webhook endpoint not found".to_string()))
+    }
+
+    /// Remove an endpoint and its delivery history
+    pub async fn delete_endpoint(&self, seller_id: Uuid, endpoint_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!(
+            "DELETE FROM webhook_endpoints WHERE id = $1 AND seller_id = $2",
+            endpoint_id,
+            seller_id
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Webhook endpoint not found".to_string()));
+        }
+
+        info!("Seller {} deleted webhook endpoint {}", seller_id, endpoint_id);
+
+        Ok(())
+    }
+
+    /// Register a platform endpoint for an internal system; the returned secret signs every delivery
+    pub async fn create_platform_endpoint(
+        &self,
+        admin_id: Uuid,
+        request: CreatePlatformWebhookEndpointRequest,
+    ) -> Result<CreatedWebhookEndpoint, AppError> {
+        validate_endpoint_url(&request.url)?;
+        let event_types = platform_event_type_names(&request.event_types)?;
+        let raffle_tags = raffle_tag_filter(&request.raffle_tags)?;
+
+        let max_endpoints = self
+            .get_setting("platform_webhook_max_endpoints", DEFAULT_MAX_PLATFORM_ENDPOINTS)
+            .await?;
+        let existing = sqlx::query_scalar!("SELECT COUNT(*) FROM webhook_endpoints WHERE seller_id IS NULL")
+            .fetch_one(&self.db_pool)
+            .await?
+            .unwrap_or(0);
+
+        if existing >= max_endpoints {
+            return Err(AppError::Validation(format!(
+                "At most {} platform webhook endpoints can be registered",
+                max_endpoints
+            )));
+        }
+
+        let secret = format!("whsec_{}", generate_secure_token());
+
+        let endpoint = sqlx::query_as!(
+            WebhookEndpoint,
+            r#"
+            INSERT INTO webhook_endpoints (url, secret, event_types, raffle_tags, description, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, seller_id, url, event_types, raffle_tags, description, is_active, created_at, updated_at
+            "#,
+            request.url,
+            secret,
+            &event_types,
+            &raffle_tags,
+            request.description,
+            admin_id
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        info!("Admin {} registered platform webhook endpoint {}", admin_id, endpoint.id);
+
+        Ok(CreatedWebhookEndpoint { endpoint, secret })
+    }
+
+    /// Platform endpoints, newest first
+    pub async fn list_platform_endpoints(&self) -> Result<Vec<WebhookEndpoint>, AppError> {
+        let endpoints = sqlx::query_as!(
+            WebhookEndpoint,
+            r#"
+            SELECT id, seller_id, url, event_types, raffle_tags, description, is_active, created_at, updated_at
+            FROM webhook_endpoints
+            WHERE seller_id IS NULL
+            ORDER BY created_at DESC
+            "#
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(endpoints)
+    }
+
+    /// Change a platform endpoint; fields left out keep their value
+    pub async fn update_platform_endpoint(
+        &self,
+        endpoint_id: Uuid,
+        request: UpdatePlatformWebhookEndpointRequest,
+        admin_id: Uuid,
+    ) -> Result<WebhookEndpoint, AppError> {
+        let UpdatePlatformWebhookEndpointRequest { endpoint: request, raffle_tags } = request;
+        if let Some(url) = &request.url {
+            validate_endpoint_url(url)?;
+        }
+        let event_types = request.event_types.as_deref().map(platform_event_type_names).transpose()?;
+        let raffle_tags = raffle_tags.as_deref().map(raffle_tag_filter).transpose()?;
+
+        let endpoint = sqlx::query_as!(
+            WebhookEndpoint,
+            r#"
+            UPDATE webhook_endpoints
+            SET url = COALESCE($2, url),
+                event_types = COALESCE($3, event_types),
+                raffle_tags = COALESCE($4, raffle_tags),
+                description = COALESCE($5, description),
+                is_active = COALESCE($6, is_active)
+            WHERE id = $1 AND seller_id IS NULL
+            RETURNING id, seller_id, url, event_types, raffle_tags, description, is_active, created_at, updated_at
+            "#,
+            endpoint_id,
+            request.url,
+            event_types.as_deref(),
+            raffle_tags.as_deref(),
+            request.description,
+            request.is_active
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Platform webhook endpoint not found".to_string()))?;
+
+        info!("Admin {} updated platform webhook endpoint {}", admin_id, endpoint_id);
+
+        Ok(endpoint)
+    }
+
+    /// Remove a platform endpoint and its delivery history
+    pub async fn delete_platform_endpoint(&self, endpoint_id: Uuid, admin_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query!(
+            "DELETE FROM webhook_endpoints WHERE id = $1 AND seller_id IS NULL",
+            endpoint_id
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Platform webhook endpoint not found".to_string()));
+        }
+
+        info!("Admin {} deleted platform webhook endpoint {}", admin_id, endpoint_id);
+
+        Ok(())
+    }
+
+    /// Queue an event for every active endpoint of the seller subscribed to it, and send it right away
+    ///
+    /// Returns how many endpoints the event was queued for. Sending happens in the background;
+    /// endpoints that fail are retried by the delivery loop.
+    pub async fn publish(
+        &self,
+        seller_id: Uuid,
+        event: SellerWebhookEvent,
+        data: Value,
+    ) -> Result<usize, AppError> {
+        let event_id = Uuid::new_v4();
+        let raffle_id = raffle_id_of(&data);
+        let payload = event_payload(event_id, event, Utc::now(), data);
+
+        let delivery_ids = sqlx::query_scalar!(
+            r#"
+            INSERT INTO webhook_endpoint_deliveries (endpoint_id, event_id, event_type, payload, raffle_id, next_attempt_at)
+            SELECT id, $3, $2, $4, $5, NOW()
+            FROM webhook_endpoints
+            WHERE seller_id = $1 AND is_active AND $2 = ANY(event_types)
+            RETURNING id
+            "#,
+            seller_id,
+            event.as_str(),
+            event_id,
+            payload,
+            raffle_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let count = delivery_ids.len();
+        if count > 0 {
+            debug!("Queued {} for {} endpoints of seller {}", event.as_str(), count, seller_id);
+            self.send_now(delivery_ids);
+        }
+
+        Ok(count)
+    }
+
+    /// Queue a raffle event for every active platform endpoint subscribed to it whose tag
+    /// filter matches the raffle, and send it right away
+    ///
+    /// Events about the same raffle reach each endpoint in the order they were published.
+    pub async fn publish_platform(
+        &self,
+        raffle_id: Uuid,
+        event: SellerWebhookEvent,
+        data: Value,
+    ) -> Result<usize, AppError> {
+        let event_id = Uuid::new_v4();
+        let payload = event_payload(event_id, event, Utc::now(), data);
+
+        let delivery_ids = sqlx::query_scalar!(
+            r#"
+            INSERT INTO webhook_endpoint_deliveries (endpoint_id, event_id, event_type, payload, raffle_id, next_attempt_at)
+            SELECT e.id, $2, $1, $3, $4, NOW()
+            FROM webhook_endpoints e
+            WHERE e.seller_id IS NULL AND e.is_active AND $1 = ANY(e.event_types)
+            AND (
+                cardinality(e.raffle_tags) = 0
+                OR e.raffle_tags && (SELECT tags FROM raffles WHERE id = $4)
+            )
+            RETURNING id
+            "#,
+            event.as_str(),
+            event_id,
+            payload,
+            raffle_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let count = delivery_ids.len();
+        if count > 0 {
+            debug!("Queued {} for raffle {} to {} platform endpoints", event.as_str(), raffle_id, count);
+            self.send_now(delivery_ids);
+        }
+
+        Ok(count)
+    }
+
+    /// Send deliveries whose first attempt or retry is due
+    pub async fn deliver_due(&self) -> Result<usize, AppError> {
+        let deliveries = sqlx::query_as!(
+            EndpointDelivery,
+            r#"
+            UPDATE webhook_endpoint_deliveries
+            SET status = 'sending', last_attempt_at = NOW()
+            WHERE id IN (
+                SELECT d.id FROM webhook_endpoint_deliveries d
+                WHERE ((d.status IN ('pending', 'failed') AND d.next_attempt_at <= NOW())
+                    OR (d.status = 'sending' AND d.last_attempt_at < NOW() - make_interval(mins => $2)))
+                -- Events about a raffle wait for earlier ones to the same endpoint
+                AND NOT EXISTS (
+                    SELECT 1 FROM webhook_endpoint_deliveries earlier
+                    WHERE earlier.endpoint_id = d.endpoint_id
+                    AND earlier.raffle_id = d.raffle_id
+                    AND earlier.sequence < d.sequence
+                    AND earlier.status IN ('pending', 'sending', 'failed')
+                )
+                ORDER BY d.next_attempt_at NULLS FIRST
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, endpoint_id, event_id, event_type, payload,
+                      status as "status: EndpointDeliveryStatus", attempts, last_response_status,
+                      last_error, next_attempt_at, last_attempt_at, delivered_at, created_at, raffle_id
+            "#,
+            DELIVERY_BATCH_SIZE,
+            STALE_SENDING_MINUTES
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let count = deliveries.len();
+        for delivery in deliveries {
+            self.attempt(delivery).await?;
+        }
+
+        if count > 0 {
+            info!("Sent {} seller webhook deliveries", count);
+        }
+
+        Ok(count)
+    }
+
+    /// Deliveries to one of a seller's endpoints, newest first
+    pub async fn list_endpoint_deliveries(
+        &self,
+        seller_id: Uuid,
+        endpoint_id: Uuid,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> Result<Vec<EndpointDelivery>, AppError> {
+        let owned = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM webhook_endpoints WHERE id = $1 AND seller_id = $2)",
+            endpoint_id,
+            seller_id
+        )
+        .fetch_one(&self.db_pool)
+        .await?
+        .unwrap_or(false);
+
+        if !owned {
+            return Err(AppError::NotFound("Webhook endpoint not found".to_string()));
+        }
+
+        self.list_deliveries(&EndpointDeliveryQuery {
+            endpoint_id: Some(endpoint_id),
+            status: None,
+            event_type: None,
+            limit,
+            offset,
+        })
+        .await
+    }
+
+    /// List deliveries across all endpoints, newest first
+    pub async fn list_deliveries(&self, query: &EndpointDeliveryQuery) -> Result<Vec<EndpointDelivery>, AppError> {
+        let limit = query.limit.unwrap_or(50).clamp(1, 200);
+        let offset = query.offset.unwrap_or(0).max(0);
+
+        let deliveries = sqlx::query_as!(
+            EndpointDelivery,
+            r#"
+            SELECT id, endpoint_id, event_id, event_type, payload,
+                   status as "status: EndpointDeliveryStatus", attempts, last_response_status,
+                   last_error, next_attempt_at, last_attempt_at, delivered_at, created_at, raffle_id
+            FROM webhook_endpoint_deliveries
+            WHERE ($1::uuid IS NULL OR endpoint_id = $1)
+            AND ($2::varchar IS NULL OR status = $2)
+            AND ($3::varchar IS NULL OR event_type = $3)
+            ORDER BY created_at DESC
+            LIMIT $4 OFFSET $5
+            "#,
+            query.endpoint_id,
+            query.status as Option<EndpointDeliveryStatus>,
+            query.event_type,
+            limit,
+            offset
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Get a delivery with its payload
+    pub async fn get_delivery(&self, delivery_id: Uuid) -> Result<EndpointDelivery, AppError> {
+        sqlx::query_as!(
+            EndpointDelivery,
+            r#"
+            SELECT id, endpoint_id, event_id, event_type, payload,
+                   status as "status: EndpointDeliveryStatus", attempts, last_response_status,
+                   last_error, next_attempt_at, last_attempt_at, delivered_at, created_at, raffle_id
+            FROM webhook_endpoint_deliveries
+            WHERE id = $1
+            "#,
+            delivery_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Seller webhook delivery not found".to_string()))
+    }
+
+    /// Send a delivery again now, whatever its status, unless it is being sent
+    pub async fn redeliver(&self, delivery_id: Uuid, admin_id: Uuid) -> Result<EndpointDelivery, AppError> {
+        let delivery = sqlx::query_as!(
+            EndpointDelivery,
+            r#"
+            UPDATE webhook_endpoint_deliveries
+            SET status = 'sending', last_attempt_at = NOW()
+            WHERE id = $1 AND status <> 'sending'
+            RETURNING id, endpoint_id, event_id, event_type, payload,
+                      status as "status: EndpointDeliveryStatus", attempts, last_response_status,
+                      last_error, next_attempt_at, last_attempt_at, delivered_at, created_at, raffle_id
+            "#,
+            delivery_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        let delivery = match delivery {
+            Some(delivery) => delivery,
+            None => {
+                // Distinguish a missing delivery from one that is in flight
+                self.get_delivery(delivery_id).await?;
+                return Err(AppError::Conflict("Seller webhook delivery is being sent".to_string()));
+            }
+        };
+
+        info!("Admin {} redelivering seller webhook {}", admin_id, delivery_id);
+
+        self.attempt(delivery).await
+    }
+
+    // Private helper methods
+
+    /// Send freshly queued deliveries in the background; any held back for ordering or
+    /// that fail are left to the delivery loop
+    fn send_now(&self, delivery_ids: Vec<Uuid>) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            for delivery_id in delivery_ids {
+                match service.claim(delivery_id).await {
+                    Ok(Some(delivery)) => {
+                        if let Err(e) = service.attempt(delivery).await {
+                            error!("Failed to send seller webhook {}: {}", delivery_id, e);
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => error!("Failed to claim seller webhook {}: {}", delivery_id, e),
+                }
+            }
+        });
+    }
+
+    /// Mark a pending delivery as sending; None when another worker got to it first, or an
+    /// earlier event about the same raffle is still on its way to the endpoint
+    async fn claim(&self, delivery_id: Uuid) -> Result<Option<EndpointDelivery>, AppError> {
+        let delivery = sqlx::query_as!(
+            EndpointDelivery,
+            r#"
+            UPDATE webhook_endpoint_deliveries d
+            SET status = 'sending', last_attempt_at = NOW()
+            WHERE d.id = $1 AND d.status = 'pending'
+            AND NOT EXISTS (
+                SELECT 1 FROM webhook_endpoint_deliveries earlier
+                WHERE earlier.endpoint_id = d.endpoint_id
+                AND earlier.raffle_id = d.raffle_id
+                AND earlier.sequence < d.sequence
+                AND earlier.status IN ('pending', 'sending', 'failed')
+            )
+            RETURNING id, endpoint_id, event_id, event_type, payload,
+                      status as "status: EndpointDeliveryStatus", attempts, last_response_status,
+                      last_error, next_attempt_at, last_attempt_at, delivered_at, created_at, raffle_id
+            "#,
+            delivery_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(delivery)
+    }
+
+    /// POST a claimed delivery to its endpoint and record the outcome
+    async fn attempt(&self, delivery: EndpointDelivery) -> Result<EndpointDelivery, AppError> {
+        let target = sqlx::query_as!(
+            EndpointTarget,
+            "SELECT url, secret, is_active, seller_id FROM webhook_endpoints WHERE id = $1",
+            delivery.endpoint_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        let target = match target {
+            Some(target) if target.is_active => target,
+            _ => return self.record_dead(&delivery, "Endpoint is disabled").await,
+        };
+
+        let body = serde_json::to_vec(&delivery.payload)?;
+        let timestamp = Utc::now().timestamp();
+
+        let result = self
+            .http_client
+            .post(&target.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature_header(&target.secret, timestamp, &body))
+            .header(EVENT_HEADER, delivery.event_type.as_str())
+            .header(DELIVERY_HEADER, delivery.id.to_string())
+            .body(body)
+            .send()
+            .await;
+
+        let (response_status, failure) = match result {
+            Ok(response) if response.status().is_success() => (Some(response.status().as_u16() as i32), None),
+            Ok(response) => {
+                let status = response.status();
+                let body: String = response.text().await.unwrap_or_default().chars().take(MAX_ERROR_BODY_CHARS).collect();
+                (Some(status.as_u16() as i32), Some(format!("Endpoint returned {}: {}", status, body)))
+            }
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        let failure = match failure {
+            None => {
+                let delivered = sqlx::query_as!(
+                    EndpointDelivery,
+                    r#"
+                    UPDATE webhook_endpoint_deliveries
+                    SET status = 'delivered', attempts = attempts + 1, last_response_status = $2,
+                        last_error = NULL, next_attempt_at = NULL, delivered_at = NOW()
+                    WHERE id = $1
+                    RETURNING id, endpoint_id, event_id, event_type, payload,
+                              status as "status: EndpointDeliveryStatus", attempts, last_response_status,
+                              last_error, next_attempt_at, last_attempt_at, delivered_at, created_at, raffle_id
+                    "#,
+                    delivery.id,
+                    response_status
+                )
+                .fetch_one(&self.db_pool)
+                .await?;
+
+                // Deliveries count towards the seller's API rate plan
+                if let Some(seller_id) = target.seller_id {
+                    if let Err(e) = SellerApiUsage::record_webhook(&self.db_pool, seller_id, period_start(Utc::now())).await {
+                        warn!("Failed to meter seller webhook {}: {}", delivery.id, e);
+                    }
+                }
+
+                return Ok(delivered);
+            }
+            Some(failure) => failure,
+        };
+
+        let attempts = delivery.attempts as i64 + 1;
+        let max_attempts = self.get_setting("seller_webhook_max_attempts", DEFAULT_MAX_ATTEMPTS).await?;
+        let next_attempt_at = if attempts < max_attempts {
+            let base_seconds = self.get_setting("seller_webhook_retry_base_seconds", DEFAULT_RETRY_BASE_SECONDS).await?;
+            let max_seconds = self.get_setting("seller_webhook_retry_max_seconds", DEFAULT_RETRY_MAX_SECONDS).await?;
+            Some(Utc::now() + Duration::seconds(retry_delay_seconds(attempts, base_seconds, max_seconds)))
+        } else {
+            None
+        };
+        let status = if next_attempt_at.is_some() { EndpointDeliveryStatus::Failed } else { EndpointDeliveryStatus::Dead };
+
+        match status {
+            EndpointDeliveryStatus::Dead => error!(
+                "Seller webhook {} ({}) failed after {} attempts, giving up: {}",
+                delivery.id, delivery.event_type, attempts, failure
+            ),
+            _ => warn!(
+                "Seller webhook {} ({}) failed on attempt {}, retrying: {}",
+                delivery.id, delivery.event_type, attempts, failure
+            ),
+        }
+
+        let failed = sqlx::query_as!(
+            EndpointDelivery,
+            r#"
+            UPDATE webhook_endpoint_deliveries
+            SET status = $2, attempts = attempts + 1, last_response_status = $3,
+                last_error = $4, next_attempt_at = $5
+            WHERE id = $1
+            RETURNING id, endpoint_id, event_id, event_type, payload,
+                      status as "status: EndpointDeliveryStatus", attempts, last_response_status,
+                      last_error, next_attempt_at, last_attempt_at, delivered_at, created_at, raffle_id
+            "#,
+            delivery.id,
+            status as EndpointDeliveryStatus,
+            response_status,
+            failure,
+            next_attempt_at
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(failed)
+    }
+
+    async fn record_dead(&self, delivery: &EndpointDelivery, reason: &str) -> Result<EndpointDelivery, AppError> {
+        let dead = sqlx::query_as!(
+            EndpointDelivery,
+            r#"
+            UPDATE webhook_endpoint_deliveries
+            SET status = 'dead', last_error = $2, next_attempt_at = NULL
+            WHERE id = $1
+            RETURNING id, endpoint_id, event_id, event_type, payload,
+                      status as "status: EndpointDeliveryStatus", attempts, last_response_status,
+                      last_error, next_attempt_at, last_attempt_at, delivered_at, created_at, raffle_id
+            "#,
+            delivery.id,
+            reason
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(dead)
+    }
+
+    async fn get_setting(&self, key: &str, default: i64) -> Result<i64, AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, key).await?;
+        Ok(setting
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default))
+    }
+}
+
+/// Body sent to endpoints; `id` is shared by every endpoint receiving the event
+fn event_payload(event_id: Uuid, event: SellerWebhookEvent, created_at: DateTime<Utc>, data: Value) -> Value {
+    serde_json::json!({
+        "id": event_id,
+        "type": event.as_str(),
+        "created_at": created_at,
+        "data": data
+    })
+}
+
+/// The raffle an event is about, which orders its deliveries
+fn raffle_id_of(data: &Value) -> Option<Uuid> {
+    data.get("raffle_id")
+        .and_then(Value::as_str)
+        .and_then(|id| Uuid::parse_str(id).ok())
+}
+
+/// `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`, the scheme Stripe uses
+pub fn signature_header(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Endpoints must be HTTPS; plain HTTP is allowed for localhost during development
+fn validate_endpoint_url(url: &str) -> Result<(), AppError> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|_| AppError::Validation("Webhook URL is not a valid URL".to_string()))?;
+
+    let is_local = matches!(parsed.host_str(), Some("localhost") | Some("127.0.0.1"));
+    match parsed.scheme() {
+        "https" => Ok(()),
+        "http" if is_local => Ok(()),
+        _ => Err(AppError::Validation("Webhook URL must use https".to_string())),
+    }
+}
+
+fn event_type_names(events: &[SellerWebhookEvent]) -> Result<Vec<String>, AppError> {
+    if events.is_empty() {
+        return Err(AppError::Validation("Subscribe to at least one event type".to_string()));
+    }
+
+    let mut names: Vec<String> = events.iter().map(|e| e.as_str().to_string()).collect();
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+fn platform_event_type_names(events: &[SellerWebhookEvent]) -> Result<Vec<String>, AppError> {
+    if let Some(event) = events.iter().find(|e| !e.is_raffle_lifecycle()) {
+        return Err(AppError::Validation(format!(
+            "Platform endpoints cannot subscribe to {}",
+            event.as_str()
+        )));
+    }
+    event_type_names(events)
+}
+
+fn raffle_tag_filter(tags: &[String]) -> Result<Vec<String>, AppError> {
+    if tags.len() > MAX_RAFFLE_TAG_FILTERS {
+        return Err(AppError::Validation(format!(
+            "Filter on at most {} raffle tags",
+            MAX_RAFFLE_TAG_FILTERS
+        )));
+    }
+    normalize_tags(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_header_matches_stripe_scheme() {
+        let body = br#"{"type":"box.purchased"}"#;
+        let header = signature_header("whsec_test", 1700000000, body);
+
+        let mut mac = HmacSha256::new_from_slice(b"whsec_test").unwrap();
+        mac.update(b"1700000000.{\"type\":\"box.purchased\"}");
+        let expected = hex::encode(mac.finalize().into_bytes());
+
+        assert_eq!(header, format!("t=1700000000,v1={}", expected));
+        assert_ne!(header, signature_header("other_secret", 1700000000, body));
+    }
+
+    #[test]
+    fn test_validate_endpoint_url() {
+        assert!(validate_endpoint_url("https://seller.example.com/hooks").is_ok());
+        assert!(validate_endpoint_url("http://localhost:3000/hooks").is_ok());
+        assert!(validate_endpoint_url("http://seller.example.com/hooks").is_err());
+        assert!(validate_endpoint_url("ftp://seller.example.com").is_err());
+        assert!(validate_endpoint_url("not a url").is_err());
+    }
+
+    #[test]
+    fn test_event_type_names() {
+        let names = event_type_names(&[
+            SellerWebhookEvent::ItemSoldOut,
+            SellerWebhookEvent::BoxPurchased,
+            SellerWebhookEvent::ItemSoldOut,
+        ])
+        .unwrap();
+
+        assert_eq!(names, vec!["box.purchased", "item.sold_out"]);
+        assert!(event_type_names(&[]).is_err());
+    }
+
+    #[test]
+    fn test_raffle_lifecycle_event_names() {
+        assert_eq!(
+            serde_json::to_string(&SellerWebhookEvent::RaffleCancelled).unwrap(),
+            "\"raffle.cancelled\""
+        );
+        assert_eq!(
+            serde_json::from_str::<SellerWebhookEvent>("\"raffle.opened\"").unwrap(),
+            SellerWebhookEvent::RaffleOpened
+        );
+
+        assert!(platform_event_type_names(&[SellerWebhookEvent::RaffleCompleted]).is_ok());
+        assert!(platform_event_type_names(&[SellerWebhookEvent::RaffleCompleted, SellerWebhookEvent::BoxPurchased]).is_err());
+    }
+
+    #[test]
+    fn test_raffle_id_of() {
+        let raffle_id = Uuid::new_v4();
+        assert_eq!(raffle_id_of(&serde_json::json!({ "raffle_id": raffle_id })), Some(raffle_id));
+        assert_eq!(raffle_id_of(&serde_json::json!({ "raffle_id": "nope" })), None);
+        assert_eq!(raffle_id_of(&serde_json::json!({ "item_id": raffle_id })), None);
+    }
+
+    #[test]
+    fn test_raffle_tag_filter() {
+        let tags = raffle_tag_filter(&[" Featured ".to_string(), "holiday-2026".to_string(), "featured".to_string()])
+            .unwrap();
+        assert_eq!(tags, vec!["featured", "holiday-2026"]);
+
+        assert!(raffle_tag_filter(&[]).unwrap().is_empty());
+        assert!(raffle_tag_filter(&["50% off".to_string()]).is_err());
+        assert!(raffle_tag_filter(&["".to_string()]).is_err());
+
+        let too_many: Vec<String> = (0..=MAX_RAFFLE_TAG_FILTERS).map(|i| format!("tag-{}", i)).collect();
+        assert!(raffle_tag_filter(&too_many).is_err());
+    }
+}
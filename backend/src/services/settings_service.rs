@@ -0,0 +1,513 @@
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use crate::models::AuditLog;
+use raffle_platform_shared::AuditAction;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Channel the system_settings trigger notifies with the changed key
+const SETTINGS_CHANNEL: &str = "system_settings_changed";
+/// Every setting is reloaded this often even without a notification, to cover missed ones
+const FULL_RELOAD_SECS: u64 = 60;
+const CHANGE_BUFFER: usize = 256;
+
+/// What a setting's value must look like
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SettingKind {
+    Integer { min: Option<i64>, max: Option<i64> },
+    /// A decimal between 0 and 100, as a number or a string
+    Percentage,
+    Boolean,
+    Text,
+    /// Any JSON; only settings without a more specific kind
+    Json,
+}
+
+impl SettingKind {
+    /// Kind of an unregistered setting, taken from the value it holds so updates keep its type
+    fn of_value(value: &Value) -> Self {
+        match value {
+            Value::Number(n) if n.is_i64() => SettingKind::Integer { min: None, max: None },
+            Value::Bool(_) => SettingKind::Boolean,
+            Value::String(_) => SettingKind::Text,
+            _ => SettingKind::Json,
+        }
+    }
+
+    pub fn validate(&self, key: &str, value: &Value) -> Result<(), AppError> {
+        let invalid = |expected: &str| AppError::Validation(format!("{} must be {}", key, expected));
+
+        match *self {
+            SettingKind::Integer { min, max } => {
+                let n = value.as_i64().ok_or_else(|| invalid("an integer"))?;
+                if min.is_some_and(|min| n < min) || max.is_some_and(|max| n > max) {
+                    return Err(invalid(&match (min, max) {
+                        (Some(min), Some(max)) => format!("between {} and {}", min, max),
+                        (Some(min), None) => format!("at least {}", min),
+                        (None, _) => format!("at most {}", max.unwrap_or_default()),
+                    }));
+                }
+            }
+            SettingKind::Percentage => {
+                let percentage = match value {
+                    Value::String(s) => s.parse::<Decimal>().ok(),
+                    Value::Number(n) => n.to_string().parse::<Decimal>().ok(),
+                    _ => None,
+                };
+                if !percentage.is_some_and(|p| p >= Decimal::ZERO && p <= Decimal::from(100)) {
+                    return Err(invalid("a percentage between 0 and 100"));
+                }
+            }
+            SettingKind::Boolean if !value.is_boolean() => return Err(invalid("true or false")),
+            SettingKind::Text if !value.is_string() => return Err(invalid("a string")),
+            SettingKind::Boolean | SettingKind::Text | SettingKind::Json => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Kind of every setting whose key matches `pattern`; `*` matches any run of characters
+struct SettingSchema {
+    pattern: &'static str,
+    kind: SettingKind,
+}
+
+/// Checked in order, so specific patterns come before general ones
+const SETTING_SCHEMAS: &[SettingSchema] = &[
+    SettingSchema { pattern: "rate_limit_*_requests", kind: SettingKind::Integer { min: Some(1), max: None } },
+    SettingSchema { pattern: "rate_limit_*_window_seconds", kind: SettingKind::Integer { min: Some(1), max: None } },
+    SettingSchema { pattern: "*_enabled", kind: SettingKind::Boolean },
+    SettingSchema { pattern: "*_percentage", kind: SettingKind::Percentage },
+    SettingSchema { pattern: "*_seconds", kind: SettingKind::Integer { min: Some(0), max: None } },
+    SettingSchema { pattern: "*_minutes", kind: SettingKind::Integer { min: Some(0), max: None } },
+    SettingSchema { pattern: "*_hours", kind: SettingKind::Integer { min: Some(0), max: None } },
+    SettingSchema { pattern: "*_days", kind: SettingKind::Integer { min: Some(0), max: None } },
+    SettingSchema { pattern: "platform_name", kind: SettingKind::Text },
+];
+
+fn pattern_matches(pattern: &str, key: &str) -> bool {
+    match pattern.split_once('*') {
+        Some((prefix, suffix)) => {
+            key.len() >= prefix.len() + suffix.len() && key.starts_with(prefix) && key.ends_with(suffix)
+        }
+        None => pattern == key,
+    }
+}
+
+/// Registered kind of a key, if any
+fn registered_kind(key: &str) -> Option<SettingKind> {
+    SETTING_SCHEMAS
+        .iter()
+        .find(|schema| pattern_matches(schema.pattern, key))
+        .map(|schema| schema.kind)
+}
+
+/// A setting changed, on this instance or another one
+#[derive(Debug, Clone)]
+pub struct SettingChange {
+    pub key: String,
+    /// None when the setting was deleted
+    pub value: Option<Value>,
+}
+
+/// A setting with the kind its value is validated against
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SettingView {
+    pub key: String,
+    pub value: Value,
+    pub kind: SettingKind,
+    pub description: Option<String>,
+    pub is_public: bool,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl SettingView {
+    fn new(setting: SystemSetting) -> Self {
+        let kind = registered_kind(&setting.key).unwrap_or_else(|| SettingKind::of_value(&setting.value));
+        Self {
+            key: setting.key,
+            value: setting.value,
+            kind,
+            description: setting.description,
+            is_public: setting.is_public,
+            updated_at: setting.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateSettingRequest {
+    pub value: Value,
+    /// Keeps the current description when omitted
+    pub description: Option<String>,
+}
+
+/// Settings service keeps every system setting in memory and in step with the database.
+/// Changes made on any instance arrive through Postgres notifications and are passed on to
+/// subscribers, so values like rate limits and fee percentages apply without a restart.
+#[derive(Clone)]
+pub struct SettingsService {
+    db_pool: PgPool,
+    /// None until the first load
+    cache: Arc<RwLock<Option<HashMap<String, SystemSetting>>>>,
+    changes: broadcast::Sender<SettingChange>,
+}
+
+impl SettingsService {
+    pub fn new(db_pool: PgPool) -> Self {
+        let (changes, _) = broadcast::channel(CHANGE_BUFFER);
+
+        Self {
+            db_pool,
+            cache: Arc::new(RwLock::new(None)),
+            changes,
+        }
+    }
+
+    /// Load every setting and follow changes
+    pub async fn start_background_tasks(&self) {
+        if let Err(e) = self.reload_all().await {
+            error!("Failed to load system settings: {}", e);
+        }
+
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut listener = service.listen().await;
+
+            loop {
+                let poll = tokio::time::Duration::from_secs(FULL_RELOAD_SECS);
+                let changed_key = match listener.as_mut() {
+                    Some(l) => match tokio::time::timeout(poll, l.recv()).await {
+                        Ok(Ok(notification)) => Some(notification.payload().to_string()),
+                        Ok(Err(e)) => {
+                            warn!("Settings listener dropped, reloading every {}s: {}", FULL_RELOAD_SECS, e);
+                            listener = None;
+                            None
+                        }
+                        Err(_) => None,
+                    },
+                    None => {
+                        tokio::time::sleep(poll).await;
+                        listener = service.listen().await;
+                        None
+                    }
+                };
+
+                let result = match changed_key {
+                    Some(key) => service.reload_key(&key).await,
+                    None => service.reload_all().await,
+                };
+                if let Err(e) = result {
+                    error!("Failed to reload system settings: {}", e);
+                }
+            }
+        });
+
+        info!("Settings service background tasks started");
+    }
+
+    /// Changes to any setting from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<SettingChange> {
+        self.changes.subscribe()
+    }
+
+    /// Current value of a setting
+    pub async fn value(&self, key: &str) -> Result<Option<Value>, AppError> {
+        if let Some(settings) = self.cache.read().await.as_ref() {
+            return Ok(settings.get(key).map(|s| s.value.clone()));
+        }
+
+        Ok(SystemSetting::get_by_key(&self.db_pool, key).await?.map(|s| s.value))
+    }
+
+    /// Integer setting, or `default` when unset or not an integer
+    pub async fn int(&self, key: &str, default: i64) -> i64 {
+        match self.value(key).await {
+            Ok(value) => value.and_then(|v| v.as_i64()).unwrap_or(default),
+            Err(e) => {
+                warn!("Failed to read setting {}: {}", key, e);
+                default
+            }
+        }
+    }
+
+    /// Decimal setting stored as a number or a string, or `default`
+    pub async fn decimal(&self, key: &str, default: Decimal) -> Decimal {
+        match self.value(key).await {
+            Ok(value) => value
+                .and_then(|v| match v {
+                    Value::String(s) => s.parse().ok(),
+                    Value::Number(n) => n.to_string().parse().ok(),
+                    _ => None,
+                })
+                .unwrap_or(default),
+            Err(e) => {
+                warn!("Failed to read setting {}: {}", key, e);
+                default
+            }
+        }
+    }
+
+    /// Every setting, by key
+    pub async fn list(&self) -> Result<Vec<SettingView>, AppError> {
+        let settings = SystemSetting::get_all_settings(&self.db_pool).await?;
+        Ok(settings.into_iter().map(SettingView::new).collect())
+    }
+
+    pub async fn get(&self, key: &str) -> Result<SettingView, AppError> {
+        SystemSetting::get_by_key(&self.db_pool, key)
+            .await?
+            .map(SettingView::new)
+            .ok_or_else(|| AppError::NotFound(format!("Unknown setting: {}", key)))
+    }
+
+    /// Validate and store a setting, then tell every instance. Unregistered settings can only
+    /// be changed, keeping the type of their current value; registered ones can also be created.
+    pub async fn update(
+        &self,
+        key: &str,
+        request: UpdateSettingRequest,
+        admin_id: Uuid,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<String>,
+    ) -> Result<SettingView, AppError> {
+        let existing = SystemSetting::get_by_key(&self.db_pool, key).await?;
+        let kind = match (registered_kind(key), &existing) {
+            (Some(kind), _) => kind,
+            (None, Some(setting)) => SettingKind::of_value(&setting.value),
+            (None, None) => return Err(AppError::NotFound(format!("Unknown setting: {}", key))),
+        };
+        kind.validate(key, &request.value)?;
+
+        let setting = SystemSetting::upsert(
+            &self.db_pool,
+            key.to_string(),
+            request.value.clone(),
+            request
+                .description
+                .or_else(|| existing.as_ref().and_then(|s| s.description.clone())),
+            existing.as_ref().is_some_and(|s| s.is_public),
+        )
+        .await?;
+
+        if let Err(e) = AuditLog::create(
+            &self.db_pool,
+            Some(admin_id),
+            AuditAction::AdminAction,
+            Some("system_setting".to_string()),
+            Some(setting.id),
+            existing.as_ref().map(|s| serde_json::json!({ "key": key, "value": s.value })),
+            Some(serde_json::json!({ "key": key, "value": setting.value })),
+            ip_address,
+            user_agent,
+        )
+        .await
+        {
+            warn!("Failed to audit change to setting {}: {}", key, e);
+        }
+
+        info!("Admin {} set {} to {}", admin_id, key, setting.value);
+
+        self.apply(key, Some(setting.clone())).await;
+
+        Ok(SettingView::new(setting))
+    }
+
+    // Private helper methods
+
+    async fn listen(&self) -> Option<PgListener> {
+        let listener = async {
+            let mut listener = PgListener::connect_with(&self.db_pool).await?;
+            listener.listen(SETTINGS_CHANNEL).await?;
+            Ok::<_, sqlx::Error>(listener)
+        };
+
+        match listener.await {
+            Ok(listener) => Some(listener),
+            Err(e) => {
+                warn!("Failed to listen for setting changes, reloading every {}s: {}", FULL_RELOAD_SECS, e);
+                None
+            }
+        }
+    }
+
+    async fn reload_key(&self, key: &str) -> Result<(), AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, key).await?;
+        self.apply(key, setting).await;
+        Ok(())
+    }
+
+    async fn reload_all(&self) -> Result<(), AppError> {
+        let loaded: HashMap<String, SystemSetting> = SystemSetting::get_all_settings(&self.db_pool)
+            .await?
+            .into_iter()
+            .map(|s| (s.key.clone(), s))
+            .collect();
+
+        let mut cache = self.cache.write().await;
+        let changed: Vec<SettingChange> = match cache.as_ref() {
+            Some(previous) => changed_settings(previous, &loaded),
+            None => Vec::new(),
+        };
+        *cache = Some(loaded);
+        drop(cache);
+
+        for change in changed {
+            self.notify(change);
+        }
+
+        Ok(())
+    }
+
+    /// Store a setting's latest state and pass it on if it differs from what we had
+    async fn apply(&self, key: &str, setting: Option<SystemSetting>) {
+        let mut cache = self.cache.write().await;
+        let settings = cache.get_or_insert_with(HashMap::new);
+
+        let previous = settings.get(key).map(|s| s.value.clone());
+        let value = setting.as_ref().map(|s| s.value.clone());
+        match setting {
+            Some(setting) => settings.insert(key.to_string(), setting),
+            None => settings.remove(key),
+        };
+        drop(cache);
+
+        if previous != value {
+            self.notify(SettingChange {
+                key: key.to_string(),
+                value,
+            });
+        }
+    }
+
+    fn notify(&self, change: SettingChange) {
+        debug!("Setting {} changed", change.key);
+        // Nobody listening is fine
+        let _ = self.changes.send(change);
+    }
+}
+
+/// Settings added, changed or removed between two loads
+fn changed_settings(
+    previous: &HashMap<String, SystemSetting>,
+    current: &HashMap<String, SystemSetting>,
+) -> Vec<SettingChange> {
+    let mut changes: Vec<SettingChange> = current
+        .iter()
+        .filter(|(key, setting)| previous.get(*key).map(|p| &p.value) != Some(&setting.value))
+        .map(|(key, setting)| SettingChange {
+            key: key.clone(),
+            value: Some(setting.value.clone()),
+        })
+        .collect();
+
+    changes.extend(
+        previous
+            .keys()
+            .filter(|key| !current.contains_key(*key))
+            .map(|key| SettingChange {
+                key: key.clone(),
+                value: None,
+            }),
+    );
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn setting(key: &str, value: Value) -> SystemSetting {
+        SystemSetting {
+            id: Uuid::new_v4(),
+            key: key.to_string(),
+            value,
+            description: None,
+            is_public: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_registered_kinds_match_in_order() {
+        assert_eq!(
+            registered_kind("rate_limit_login_requests"),
+            Some(SettingKind::Integer { min: Some(1), max: None })
+        );
+        assert_eq!(
+            registered_kind("rate_limit_login_window_seconds"),
+            Some(SettingKind::Integer { min: Some(1), max: None })
+        );
+        assert_eq!(registered_kind("disposable_email_blocking_enabled"), Some(SettingKind::Boolean));
+        assert_eq!(registered_kind("platform_fee_percentage"), Some(SettingKind::Percentage));
+        assert_eq!(
+            registered_kind("box_reservation_ttl_seconds"),
+            Some(SettingKind::Integer { min: Some(0), max: None })
+        );
+        assert_eq!(registered_kind("supported_payment_tokens"), None);
+    }
+
+    #[test]
+    fn test_values_are_validated_against_their_kind() {
+        let requests = SettingKind::Integer { min: Some(1), max: None };
+        assert!(requests.validate("k", &json!(30)).is_ok());
+        assert!(requests.validate("k", &json!(0)).is_err());
+        assert!(requests.validate("k", &json!("30")).is_err());
+
+        assert!(SettingKind::Percentage.validate("k", &json!("5.00")).is_ok());
+        assert!(SettingKind::Percentage.validate("k", &json!(12.5)).is_ok());
+        assert!(SettingKind::Percentage.validate("k", &json!(101)).is_err());
+        assert!(SettingKind::Percentage.validate("k", &json!("-1")).is_err());
+
+        assert!(SettingKind::Boolean.validate("k", &json!(true)).is_ok());
+        assert!(SettingKind::Boolean.validate("k", &json!("true")).is_err());
+
+        // Unregistered settings keep the type they have
+        assert_eq!(SettingKind::of_value(&json!("USD")), SettingKind::Text);
+        assert_eq!(SettingKind::of_value(&json!([1, 2])), SettingKind::Json);
+    }
+
+    #[test]
+    fn test_changed_settings_include_removals() {
+        let previous: HashMap<String, SystemSetting> = [
+            ("kept".to_string(), setting("kept", json!(1))),
+            ("changed".to_string(), setting("changed", json!(1))),
+            ("removed".to_string(), setting("removed", json!(1))),
+        ]
+        .into_iter()
+        .collect();
+        let current: HashMap<String, SystemSetting> = [
+            ("kept".to_string(), setting("kept", json!(1))),
+            ("changed".to_string(), setting("changed", json!(2))),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut changes: Vec<(String, Option<Value>)> = changed_settings(&previous, &current)
+            .into_iter()
+            .map(|c| (c.key, c.value))
+            .collect();
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(
+            changes,
+            vec![("changed".to_string(), Some(json!(2))), ("removed".to_string(), None)]
+        );
+    }
+}
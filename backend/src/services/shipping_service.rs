@@ -0,0 +1,861 @@
+use crate::error::AppError;
+use crate::models::item::Item;
+use crate::models::raffle::Raffle;
+use crate::models::raffle_prize_tier::RafflePrizeTier;
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::{RaffleResponse, ShippingEstimate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+use std::collections::HashMap;
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Zone country that matches any destination without a more specific zone
+pub const ANY_COUNTRY: &str = "*";
+
+const MAX_ZONES: usize = 100;
+const MAX_HANDLING_DAYS: i32 = 60;
+const MAX_TRANSIT_DAYS: i32 = 90;
+
+/// Shipping service keeps seller shipping policies and item overrides, quotes shipping
+/// to a destination and checks winners' addresses before prizes are fulfilled
+#[derive(Clone)]
+pub struct ShippingService {
+    db_pool: PgPool,
+}
+
+/// How a zone charges for shipping
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShippingRate {
+    Flat { cost: Decimal },
+    /// `base_cost` plus `per_kg` for each kilogram of the item's weight
+    Weight { base_cost: Decimal, per_kg: Decimal },
+}
+
+/// Destinations a policy serves and what they cost
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ShippingZone {
+    /// ISO 3166-1 alpha-2 code, or `*` for everywhere else
+    pub country: String,
+    /// ISO 3166-2 subdivision within `country`; None covers the whole country
+    #[serde(default)]
+    pub region: Option<String>,
+    pub rate: ShippingRate,
+    pub transit_days_min: i32,
+    pub transit_days_max: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SellerShippingPolicy {
+    pub seller_id: Uuid,
+    pub handling_days_min: i32,
+    pub handling_days_max: i32,
+    pub zones: Vec<ShippingZone>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateShippingPolicyRequest {
+    pub handling_days_min: i32,
+    pub handling_days_max: i32,
+    pub zones: Vec<ShippingZone>,
+}
+
+/// Item-level shipping data; unset fields fall back to the seller's policy
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ItemShippingOverride {
+    pub item_id: Uuid,
+    pub weight_grams: Option<i32>,
+    pub handling_days_min: Option<i32>,
+    pub handling_days_max: Option<i32>,
+    /// Replaces the seller's zones entirely when set
+    pub zones: Option<Vec<ShippingZone>>,
+    pub free_shipping: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateItemShippingRequest {
+    pub weight_grams: Option<i32>,
+    pub handling_days_min: Option<i32>,
+    pub handling_days_max: Option<i32>,
+    pub zones: Option<Vec<ShippingZone>>,
+    #[serde(default)]
+    pub free_shipping: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ShippingAddress {
+    pub user_id: Uuid,
+    pub recipient_name: String,
+    pub line1: String,
+    pub line2: Option<String>,
+    pub city: String,
+    pub region_code: Option<String>,
+    pub postal_code: Option<String>,
+    pub country_code: String,
+    pub phone: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct UpdateShippingAddressRequest {
+    pub recipient_name: String,
+    pub line1: String,
+    pub line2: Option<String>,
+    pub city: String,
+    pub region_code: Option<String>,
+    pub postal_code: Option<String>,
+    pub country_code: String,
+    pub phone: Option<String>,
+}
+
+/// Country and optional subdivision a shipment goes to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Destination {
+    pub country: String,
+    pub region: Option<String>,
+}
+
+impl std::fmt::Display for Destination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.region {
+            Some(region) => write!(f, "{}-{}", self.country, region),
+            None => write!(f, "{}", self.country),
+        }
+    }
+}
+
+/// Whether a winner's prize can be shipped to the address they gave
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ShippingFulfillmentCheck {
+    pub raffle_id: Uuid,
+    pub user_id: Uuid,
+    /// False when every place the user won is paid in credits
+    pub needs_shipping: bool,
+    /// The winner has yet to choose between an item and its credit value
+    pub awaiting_choice: bool,
+    pub address_on_file: bool,
+    /// False when there is no address or the seller does not serve it
+    pub ships_to_address: bool,
+    pub estimate: Option<ShippingEstimate>,
+}
+
+/// Seller policy and item override for one item, as loaded for quoting
+#[derive(Debug, FromRow)]
+struct ItemShippingRow {
+    item_id: Uuid,
+    weight_grams: Option<i32>,
+    item_handling_days_min: Option<i32>,
+    item_handling_days_max: Option<i32>,
+    item_zones: Option<Value>,
+    free_shipping: Option<bool>,
+    handling_days_min: Option<i32>,
+    handling_days_max: Option<i32>,
+    zones: Option<Value>,
+}
+
+/// Policy an item ships under once its override is applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectivePolicy {
+    pub handling_days_min: i32,
+    pub handling_days_max: i32,
+    pub zones: Vec<ShippingZone>,
+    pub weight_grams: Option<i32>,
+    pub free_shipping: bool,
+}
+
+impl ShippingService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+
+    /// The seller's shipping policy, if they have set one
+    pub async fn get_seller_policy(&self, seller_id: Uuid) -> Result<Option<SellerShippingPolicy>, AppError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT seller_id, handling_days_min, handling_days_max, zones, created_at, updated_at
+            FROM seller_shipping_policies
+            WHERE seller_id = $1
+            "#,
+            seller_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        row.map(|r| {
+            Ok(SellerShippingPolicy {
+                seller_id: r.seller_id,
+                handling_days_min: r.handling_days_min,
+                handling_days_max: r.handling_days_max,
+                zones: parse_zones(r.zones)?,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+            })
+        })
+        .transpose()
+    }
+
+    /// Create or replace the seller's shipping policy
+    pub async fn set_seller_policy(
+        &self,
+        seller_id: Uuid,
+        request: UpdateShippingPolicyRequest,
+    ) -> Result<SellerShippingPolicy, AppError> {
+        validate_handling_days(request.handling_days_min, request.handling_days_max)?;
+        let zones = normalize_zones(request.zones)?;
+
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO seller_shipping_policies (seller_id, handling_days_min, handling_days_max, zones)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (seller_id) DO UPDATE SET
+                handling_days_min = EXCLUDED.handling_days_min,
+                handling_days_max = EXCLUDED.handling_days_max,
+                zones = EXCLUDED.zones
+            RETURNING created_at, updated_at
+            "#,
+            seller_id,
+            request.handling_days_min,
+            request.handling_days_max,
+            serde_json::to_value(&zones).map_err(|e| AppError::Internal(e.to_string()))?
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        info!("Seller {} updated shipping policy with {} zones", seller_id, zones.len());
+
+        Ok(SellerShippingPolicy {
+            seller_id,
+            handling_days_min: request.handling_days_min,
+            handling_days_max: request.handling_days_max,
+            zones,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+    }
+
+    /// The item's shipping override, if the seller has set one
+    pub async fn get_item_override(
+        &self,
+        seller_id: Uuid,
+        item_id: Uuid,
+    ) -> Result<Option<ItemShippingOverride>, AppError> {
+        self.ensure_item_owner(seller_id, item_id).await?;
+        self.fetch_item_override(item_id).await
+    }
+
+    /// Create or replace the item's shipping override
+    pub async fn set_item_override(
+        &self,
+        seller_id: Uuid,
+        item_id: Uuid,
+        request: UpdateItemShippingRequest,
+    ) -> Result<ItemShippingOverride, AppError> {
+        self.ensure_item_owner(seller_id, item_id).await?;
+
+        if matches!(request.weight_grams, Some(weight) if weight <= 0) {
+            return Err(AppError::Validation("Item weight must be positive".to_string()));
+        }
+        match (request.handling_days_min, request.handling_days_max) {
+            (Some(min), Some(max)) => validate_handling_days(min, max)?,
+            (None, None) => {}
+            _ => {
+                return Err(AppError::Validation(
+                    "Set both handling_days_min and handling_days_max or neither".to_string(),
+                ))
+            }
+        }
+        let zones = request.zones.map(normalize_zones).transpose()?;
+        let zones_json = zones
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO item_shipping_overrides
+                (item_id, weight_grams, handling_days_min, handling_days_max, zones, free_shipping)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (item_id) DO UPDATE SET
+                weight_grams = EXCLUDED.weight_grams,
+                handling_days_min = EXCLUDED.handling_days_min,
+                handling_days_max = EXCLUDED.handling_days_max,
+                zones = EXCLUDED.zones,
+                free_shipping = EXCLUDED.free_shipping
+            "#,
+            item_id,
+            request.weight_grams,
+            request.handling_days_min,
+            request.handling_days_max,
+            zones_json,
+            request.free_shipping
+        )
+        .execute(&self.db_pool)
+        .await?;
+
+        info!("Seller {} updated shipping override for item {}", seller_id, item_id);
+
+        self.fetch_item_override(item_id)
+            .await?
+            .ok_or_else(|| AppError::Internal("Shipping override missing after update".to_string()))
+    }
+
+    /// Remove the item's override so it ships under the seller's policy
+    pub async fn delete_item_override(&self, seller_id: Uuid, item_id: Uuid) -> Result<(), AppError> {
+        self.ensure_item_owner(seller_id, item_id).await?;
+
+        let result = sqlx::query!("DELETE FROM item_shipping_overrides WHERE item_id = $1", item_id)
+            .execute(&self.db_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Item has no shipping override".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// The user's saved shipping address
+    pub async fn get_address(&self, user_id: Uuid) -> Result<Option<ShippingAddress>, AppError> {
+        let address = sqlx::query_as::<_, ShippingAddress>(
+            r#"
+            SELECT user_id, recipient_name, line1, line2, city, region_code, postal_code,
+                   country_code, phone, created_at, updated_at
+            FROM user_shipping_addresses
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(address)
+    }
+
+    /// Save the address the user's prizes are shipped to
+    pub async fn set_address(
+        &self,
+        user_id: Uuid,
+        request: UpdateShippingAddressRequest,
+    ) -> Result<ShippingAddress, AppError> {
+        let destination = parse_destination(&request.country_code, request.region_code.as_deref())?;
+        for (field, value) in [
+            ("recipient_name", &request.recipient_name),
+            ("line1", &request.line1),
+            ("city", &request.city),
+        ] {
+            if value.trim().is_empty() {
+                return Err(AppError::Validation(format!("{} is required", field)));
+            }
+        }
+
+        let address = sqlx::query_as::<_, ShippingAddress>(
+            r#"
+            INSERT INTO user_shipping_addresses
+                (user_id, recipient_name, line1, line2, city, region_code, postal_code, country_code, phone)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (user_id) DO UPDATE SET
+                recipient_name = EXCLUDED.recipient_name,
+                line1 = EXCLUDED.line1,
+                line2 = EXCLUDED.line2,
+                city = EXCLUDED.city,
+                region_code = EXCLUDED.region_code,
+                postal_code = EXCLUDED.postal_code,
+                country_code = EXCLUDED.country_code,
+                phone = EXCLUDED.phone
+            RETURNING user_id, recipient_name, line1, line2, city, region_code, postal_code,
+                      country_code, phone, created_at, updated_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(request.recipient_name.trim())
+        .bind(request.line1.trim())
+        .bind(request.line2.as_deref().map(str::trim).filter(|l| !l.is_empty()))
+        .bind(request.city.trim())
+        .bind(&destination.region)
+        .bind(request.postal_code.as_deref().map(str::trim).filter(|p| !p.is_empty()))
+        .bind(&destination.country)
+        .bind(request.phone.as_deref().map(str::trim).filter(|p| !p.is_empty()))
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        Ok(address)
+    }
+
+    /// Destination for a request: an explicit header wins over the user's saved address
+    pub async fn resolve_destination(
+        &self,
+        requested: Option<&str>,
+        user_id: Option<Uuid>,
+    ) -> Result<Option<Destination>, AppError> {
+        if let Some(requested) = requested {
+            return parse_destination_code(requested).map(Some);
+        }
+
+        let user_id = match user_id {
+            Some(user_id) => user_id,
+            None => return Ok(None),
+        };
+
+        Ok(self
+            .get_address(user_id)
+            .await?
+            .map(|address| Destination {
+                country: address.country_code,
+                region: address.region_code,
+            }))
+    }
+
+    /// Quote shipping for one item; None when neither the seller nor the item has a policy
+    pub async fn estimate_for_item(
+        &self,
+        item_id: Uuid,
+        destination: &Destination,
+    ) -> Result<Option<ShippingEstimate>, AppError> {
+        let policies = self.load_effective_policies(&[item_id]).await?;
+
+        Ok(policies.get(&item_id).map(|policy| estimate(policy, destination)))
+    }
+
+    /// Fill in shipping estimates on raffles for the destination
+    pub async fn apply_to_raffles(
+        &self,
+        raffles: &mut [RaffleResponse],
+        destination: &Destination,
+    ) -> Result<(), AppError> {
+        if raffles.is_empty() {
+            return Ok(());
+        }
+
+        let mut item_ids: Vec<Uuid> = raffles.iter().map(|r| r.item_id).collect();
+        item_ids.sort();
+        item_ids.dedup();
+
+        let policies = self.load_effective_policies(&item_ids).await?;
+
+        for raffle in raffles {
+            raffle.shipping_estimate = policies
+                .get(&raffle.item_id)
+                .map(|policy| estimate(policy, destination));
+        }
+
+        Ok(())
+    }
+
+    /// Check that a raffle winner's saved address is one the seller ships to
+    pub async fn check_fulfillment(&self, raffle_id: Uuid, user_id: Uuid) -> Result<ShippingFulfillmentCheck, AppError> {
+        let raffle = Raffle::find_by_id(&self.db_pool, raffle_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
+
+        if !raffle.winner_user_ids.contains(&user_id) {
+            return Err(AppError::NotFound("User did not win this raffle".to_string()));
+        }
+
+        // Raffles drawn before prize tiers existed have none; their single prize is the item
+        let prizes = RafflePrizeTier::find_for_winner(&self.db_pool, raffle_id, user_id).await?;
+        let needs_shipping = prizes.is_empty() || prizes.iter().any(|p| p.ships_item());
+        let awaiting_choice = prizes.iter().any(|p| p.awaiting_choice());
+
+        let address = self.get_address(user_id).await?;
+        let estimate = match &address {
+            Some(address) => {
+                let destination = Destination {
+                    country: address.country_code.clone(),
+                    region: address.region_code.clone(),
+                };
+                self.estimate_for_item(raffle.item_id, &destination).await?
+            }
+            None => None,
+        };
+
+        Ok(ShippingFulfillmentCheck {
+            raffle_id,
+            user_id,
+            needs_shipping,
+            awaiting_choice,
+            address_on_file: address.is_some(),
+            // Items without any shipping policy are fulfilled outside the platform's rules
+            ships_to_address: address.is_some()
+                && estimate.as_ref().map_or(true, |e| e.ships_to_destination),
+            estimate,
+        })
+    }
+
+    /// Fail with a conflict unless the winner's address is within the served regions.
+    /// Prizes taken entirely as credits need no address.
+    pub async fn ensure_address_served(&self, raffle_id: Uuid, user_id: Uuid) -> Result<(), AppError> {
+        let check = self.check_fulfillment(raffle_id, user_id).await?;
+
+        if check.awaiting_choice {
+            return Err(AppError::Conflict(
+                "Prize fulfillment is on hold until the winner chooses the item or its credit value".to_string(),
+            ));
+        }
+        if !check.needs_shipping {
+            return Ok(());
+        }
+        if !check.address_on_file {
+            return Err(AppError::Conflict(
+                "Prize fulfillment is on hold until the winner adds a shipping address".to_string(),
+            ));
+        }
+        if !check.ships_to_address {
+            return Err(AppError::Conflict(
+                "The seller does not ship to the winner's address".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Private helper methods
+
+    async fn ensure_item_owner(&self, seller_id: Uuid, item_id: Uuid) -> Result<(), AppError> {
+        let item = Item::find_by_id(&self.db_pool, item_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
+
+        if item.seller_id != Some(seller_id) {
+            return Err(AppError::Forbidden("You can only manage shipping for your own items".to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn fetch_item_override(&self, item_id: Uuid) -> Result<Option<ItemShippingOverride>, AppError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT item_id, weight_grams, handling_days_min, handling_days_max, zones, free_shipping,
+                   created_at, updated_at
+            FROM item_shipping_overrides
+            WHERE item_id = $1
+            "#,
+            item_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        row.map(|r| {
+            Ok(ItemShippingOverride {
+                item_id: r.item_id,
+                weight_grams: r.weight_grams,
+                handling_days_min: r.handling_days_min,
+                handling_days_max: r.handling_days_max,
+                zones: r.zones.map(parse_zones).transpose()?,
+                free_shipping: r.free_shipping,
+                created_at: r.created_at,
+                updated_at: r.updated_at,
+            })
+        })
+        .transpose()
+    }
+
+    async fn load_effective_policies(&self, item_ids: &[Uuid]) -> Result<HashMap<Uuid, EffectivePolicy>, AppError> {
+        let rows = sqlx::query_as::<_, ItemShippingRow>(
+            r#"
+            SELECT i.id as item_id, o.weight_grams,
+                   o.handling_days_min as item_handling_days_min,
+                   o.handling_days_max as item_handling_days_max,
+                   o.zones as item_zones, o.free_shipping,
+                   p.handling_days_min, p.handling_days_max, p.zones
+            FROM items i
+            LEFT JOIN item_shipping_overrides o ON o.item_id = i.id
+            LEFT JOIN seller_shipping_policies p ON p.seller_id = i.seller_id
+            WHERE i.id = ANY($1)
+            "#,
+        )
+        .bind(item_ids)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let mut policies = HashMap::with_capacity(rows.len());
+        for row in rows {
+            let item_id = row.item_id;
+            if let Some(policy) = effective_policy(row)? {
+                policies.insert(item_id, policy);
+            }
+        }
+
+        Ok(policies)
+    }
+}
+
+/// Combine an item's override with its seller's policy; None when neither has zones
+fn effective_policy(row: ItemShippingRow) -> Result<Option<EffectivePolicy>, AppError> {
+    let zones = match (row.item_zones, row.zones) {
+        (Some(item_zones), _) => parse_zones(item_zones)?,
+        (None, Some(zones)) => parse_zones(zones)?,
+        (None, None) => return Ok(None),
+    };
+
+    let (handling_days_min, handling_days_max) = match (row.item_handling_days_min, row.item_handling_days_max) {
+        (Some(min), Some(max)) => (min, max),
+        _ => (row.handling_days_min.unwrap_or(0), row.handling_days_max.unwrap_or(0)),
+    };
+
+    Ok(Some(EffectivePolicy {
+        handling_days_min,
+        handling_days_max,
+        zones,
+        weight_grams: row.weight_grams,
+        free_shipping: row.free_shipping.unwrap_or(false),
+    }))
+}
+
+fn parse_zones(value: Value) -> Result<Vec<ShippingZone>, AppError> {
+    serde_json::from_value(value).map_err(|e| AppError::Internal(format!("Invalid stored shipping zones: {}", e)))
+}
+
+/// Quote shipping for an item's policy to a destination
+pub fn estimate(policy: &EffectivePolicy, destination: &Destination) -> ShippingEstimate {
+    let zone = match_zone(&policy.zones, destination);
+
+    let cost = zone.and_then(|zone| {
+        if policy.free_shipping {
+            Some(Decimal::ZERO)
+        } else {
+            rate_cost(&zone.rate, policy.weight_grams)
+        }
+    });
+
+    ShippingEstimate {
+        destination: destination.to_string(),
+        ships_to_destination: zone.is_some(),
+        cost,
+        handling_days_min: policy.handling_days_min,
+        handling_days_max: policy.handling_days_max,
+        delivery_days_min: zone.map(|z| policy.handling_days_min + z.transit_days_min),
+        delivery_days_max: zone.map(|z| policy.handling_days_max + z.transit_days_max),
+    }
+}
+
+/// Most specific zone for a destination: its region, then its country, then `*`
+pub fn match_zone<'a>(zones: &'a [ShippingZone], destination: &Destination) -> Option<&'a ShippingZone> {
+    let region_zone = destination.region.as_ref().and_then(|region| {
+        zones
+            .iter()
+            .find(|z| z.country == destination.country && z.region.as_ref() == Some(region))
+    });
+
+    region_zone
+        .or_else(|| zones.iter().find(|z| z.country == destination.country && z.region.is_none()))
+        .or_else(|| zones.iter().find(|z| z.country == ANY_COUNTRY))
+}
+
+/// Cost of a rate; None for weight-based rates when the item's weight is unknown
+pub fn rate_cost(rate: &ShippingRate, weight_grams: Option<i32>) -> Option<Decimal> {
+    match rate {
+        ShippingRate::Flat { cost } => Some(*cost),
+        ShippingRate::Weight { base_cost, per_kg } => weight_grams.map(|grams| {
+            (*base_cost + *per_kg * Decimal::from(grams) / Decimal::from(1000)).round_dp(2)
+        }),
+    }
+}
+
+/// Parse a destination code such as `US` or `US-CA`
+pub fn parse_destination_code(code: &str) -> Result<Destination, AppError> {
+    let code = code.trim();
+    match code.split_once('-') {
+        Some((country, region)) => parse_destination(country, Some(region)),
+        None => parse_destination(code, None),
+    }
+}
+
+/// Validate and upper-case a country code and optional subdivision
+pub fn parse_destination(country: &str, region: Option<&str>) -> Result<Destination, AppError> {
+    let country = country.trim().to_uppercase();
+    if country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(AppError::Validation(format!("Invalid country code: {}", country)));
+    }
+
+    let region = region
+        .map(|r| r.trim().to_uppercase())
+        .filter(|r| !r.is_empty());
+    if let Some(region) = &region {
+        if region.len() > 3 || !region.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(AppError::Validation(format!("Invalid region code: {}", region)));
+        }
+    }
+
+    Ok(Destination { country, region })
+}
+
+fn validate_handling_days(min: i32, max: i32) -> Result<(), AppError> {
+    if min < 0 || max < min || max > MAX_HANDLING_DAYS {
+        return Err(AppError::Validation(format!(
+            "Handling days must be between 0 and {} with min no greater than max",
+            MAX_HANDLING_DAYS
+        )));
+    }
+    Ok(())
+}
+
+/// Validate zones and normalize their codes; each destination may be listed once
+pub fn normalize_zones(zones: Vec<ShippingZone>) -> Result<Vec<ShippingZone>, AppError> {
+    if zones.len() > MAX_ZONES {
+        return Err(AppError::Validation(format!("At most {} shipping zones are allowed", MAX_ZONES)));
+    }
+
+    let mut normalized: Vec<ShippingZone> = Vec::with_capacity(zones.len());
+    for mut zone in zones {
+        if zone.country.trim() == ANY_COUNTRY {
+            if zone.region.is_some() {
+                return Err(AppError::Validation("The '*' zone cannot have a region".to_string()));
+            }
+            zone.country = ANY_COUNTRY.to_string();
+        } else {
+            let destination = parse_destination(&zone.country, zone.region.as_deref())?;
+            zone.country = destination.country;
+            zone.region = destination.region;
+        }
+
+        let negative = match &zone.rate {
+            ShippingRate::Flat { cost } => cost.is_sign_negative(),
+            ShippingRate::Weight { base_cost, per_kg } => base_cost.is_sign_negative() || per_kg.is_sign_negative(),
+        };
+        if negative {
+            return Err(AppError::Validation("Shipping costs cannot be negative".to_string()));
+        }
+
+        if zone.transit_days_min < 0
+            || zone.transit_days_max < zone.transit_days_min
+            || zone.transit_days_max > MAX_TRANSIT_DAYS
+        {
+            return Err(AppError::Validation(format!(
+                "Transit days must be between 0 and {} with min no greater than max",
+                MAX_TRANSIT_DAYS
+            )));
+        }
+
+        if normalized.iter().any(|z| z.country == zone.country && z.region == zone.region) {
+            return Err(AppError::Validation(format!(
+                "Shipping zone {} is listed more than once",
+                zone_label(&zone)
+            )));
+        }
+
+        normalized.push(zone);
+    }
+
+    Ok(normalized)
+}
+
+fn zone_label(zone: &ShippingZone) -> String {
+    match &zone.region {
+        Some(region) => format!("{}-{}", zone.country, region),
+        None => zone.country.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    fn zone(country: &str, region: Option<&str>, cost: Decimal) -> ShippingZone {
+        ShippingZone {
+            country: country.to_string(),
+            region: region.map(str::to_string),
+            rate: ShippingRate::Flat { cost },
+            transit_days_min: 2,
+            transit_days_max: 5,
+        }
+    }
+
+    fn policy(zones: Vec<ShippingZone>) -> EffectivePolicy {
+        EffectivePolicy {
+            handling_days_min: 1,
+            handling_days_max: 3,
+            zones,
+            weight_grams: Some(1500),
+            free_shipping: false,
+        }
+    }
+
+    #[test]
+    fn test_match_zone_prefers_most_specific() {
+        let zones = vec![
+            zone(ANY_COUNTRY, None, d("30")),
+            zone("US", None, d("10")),
+            zone("US", Some("AK"), d("20")),
+        ];
+
+        let ak = parse_destination_code("us-ak").unwrap();
+        let ca = parse_destination_code("US-CA").unwrap();
+        let fr = parse_destination_code("FR").unwrap();
+
+        assert_eq!(match_zone(&zones, &ak).unwrap().rate, ShippingRate::Flat { cost: d("20") });
+        assert_eq!(match_zone(&zones, &ca).unwrap().rate, ShippingRate::Flat { cost: d("10") });
+        assert_eq!(match_zone(&zones, &fr).unwrap().rate, ShippingRate::Flat { cost: d("30") });
+        assert!(match_zone(&zones[1..], &fr).is_none());
+    }
+
+    #[test]
+    fn test_rate_cost() {
+        let weight = ShippingRate::Weight { base_cost: d("3.00"), per_kg: d("1.50") };
+
+        assert_eq!(rate_cost(&weight, Some(1500)), Some(d("5.25")));
+        assert_eq!(rate_cost(&weight, None), None);
+        assert_eq!(rate_cost(&ShippingRate::Flat { cost: d("4.99") }, None), Some(d("4.99")));
+    }
+
+    #[test]
+    fn test_estimate() {
+        let mut policy = policy(vec![zone("US", None, d("10"))]);
+
+        let served = estimate(&policy, &parse_destination_code("US-NY").unwrap());
+        assert_eq!(served.destination, "US-NY");
+        assert!(served.ships_to_destination);
+        assert_eq!(served.cost, Some(d("10")));
+        assert_eq!(served.delivery_days_min, Some(3));
+        assert_eq!(served.delivery_days_max, Some(8));
+
+        let unserved = estimate(&policy, &parse_destination_code("CA").unwrap());
+        assert!(!unserved.ships_to_destination);
+        assert_eq!(unserved.cost, None);
+        assert_eq!(unserved.delivery_days_max, None);
+
+        policy.free_shipping = true;
+        assert_eq!(estimate(&policy, &parse_destination_code("US").unwrap()).cost, Some(Decimal::ZERO));
+    }
+
+    #[test]
+    fn test_parse_destination_code() {
+        assert_eq!(
+            parse_destination_code(" gb ").unwrap(),
+            Destination { country: "GB".to_string(), region: None }
+        );
+        assert_eq!(parse_destination_code("US-CA").unwrap().to_string(), "US-CA");
+        assert!(parse_destination_code("USA").is_err());
+        assert!(parse_destination_code("U1").is_err());
+        assert!(parse_destination_code("US-CALI").is_err());
+    }
+
+    #[test]
+    fn test_normalize_zones() {
+        let zones = normalize_zones(vec![zone("us", Some("ca"), d("5")), zone("*", None, d("25"))]).unwrap();
+        assert_eq!(zones[0].country, "US");
+        assert_eq!(zones[0].region.as_deref(), Some("CA"));
+
+        assert!(normalize_zones(vec![zone("US", None, d("5")), zone("us", None, d("6"))]).is_err());
+        assert!(normalize_zones(vec![zone("*", Some("CA"), d("5"))]).is_err());
+        assert!(normalize_zones(vec![zone("US", None, d("-1"))]).is_err());
+
+        let mut slow = zone("US", None, d("5"));
+        slow.transit_days_min = 10;
+        assert!(normalize_zones(vec![slow]).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+use crate::error::AppError;
+use crate::models::item::Item;
+use crate::models::system_settings::SystemSetting;
+use crate::models::tag::{
+    CreateTagCollectionRequest, CreateTagRequest, Tag, TagCollection, TagSynonym, UpdateTagCollectionRequest,
+    UpdateTagRequest,
+};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use tracing::info;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const DEFAULT_TRENDING_WINDOW_DAYS: i64 = 7;
+const MAX_TRENDING_WINDOW_DAYS: i64 = 90;
+
+/// Tag service keeps the tag registry: free-form tags sellers put on their items,
+/// curated tags and synonyms admins maintain, trending tags and the tag collections
+/// behind themed discovery pages.
+///
+/// Tags are stored canonical on items and raffles, so search never has to expand synonyms
+/// against rows; merging a tag into another rewrites the rows that carried it.
+#[derive(Clone)]
+pub struct TagService {
+    db_pool: PgPool,
+}
+
+/// Tags shown on a raffle; search matches either set
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RaffleTags {
+    pub raffle_id: Uuid,
+    /// Set by admins on the raffle
+    pub tags: Vec<String>,
+    /// Set by the seller on the raffle's item
+    pub item_tags: Vec<String>,
+}
+
+/// A tag ranked by the boxes bought in raffles carrying it
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct TrendingTag {
+    pub slug: String,
+    pub display_name: String,
+    pub is_curated: bool,
+    pub purchases: i64,
+    /// Purchases in the window of the same length just before
+    pub previous_purchases: i64,
+    pub raffles: i64,
+}
+
+impl TagService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+
+    /// Tags for autocomplete, curated first
+    pub async fn list_tags(&self, prefix: Option<&str>, curated_only: bool, limit: i64) -> Result<Vec<Tag>, AppError> {
+        let prefix = prefix
+            .map(|prefix| prefix.trim().to_lowercase().replace(|c: char| c.is_whitespace() || c == '_', "-"))
+            .filter(|prefix| !prefix.is_empty());
+
+        Tag::list(&self.db_pool, prefix.as_deref(), curated_only, limit).await
+    }
+
+    pub async fn create_tag(&self, request: CreateTagRequest, admin_id: Uuid) -> Result<Tag, AppError> {
+        let tag = Tag::create(&self.db_pool, request, admin_id).await?;
+
+        info!("Admin {} registered tag {} (curated: {})", admin_id, tag.slug, tag.is_curated);
+
+        Ok(tag)
+    }
+
+    pub async fn update_tag(&self, slug: &str, request: UpdateTagRequest, admin_id: Uuid) -> Result<Tag, AppError> {
+        let tag = Tag::update(&self.db_pool, slug, request).await?;
+
+        info!("Admin {} updated tag {}", admin_id, slug);
+
+        Ok(tag)
+    }
+
+    pub async fn delete_tag(&self, slug: &str, admin_id: Uuid) -> Result<(), AppError> {
+        Tag::delete(&self.db_pool, slug).await?;
+
+        info!("Admin {} deleted tag {}", admin_id, slug);
+
+        Ok(())
+    }
+
+    pub async fn list_synonyms(&self, slug: &str) -> Result<Vec<TagSynonym>, AppError> {
+        Tag::list_synonyms(&self.db_pool, slug).await
+    }
+
+    pub async fn add_synonym(&self, slug: &str, synonym: &str, admin_id: Uuid) -> Result<TagSynonym, AppError> {
+        let synonym = Tag::add_synonym(&self.db_pool, slug, synonym).await?;
+
+        info!("Admin {} made {} a synonym of tag {}", admin_id, synonym.synonym, slug);
+
+        Ok(synonym)
+    }
+
+    pub async fn remove_synonym(&self, synonym: &str, admin_id: Uuid) -> Result<(), AppError> {
+        Tag::remove_synonym(&self.db_pool, synonym).await?;
+
+        info!("Admin {} removed tag synonym {}", admin_id, synonym);
+
+        Ok(())
+    }
+
+    pub async fn item_tags(&self, item_id: Uuid) -> Result<Vec<String>, AppError> {
+        Item::tags(&self.db_pool, item_id).await
+    }
+
+    /// Replace an item's tags; only admins may apply curated tags
+    pub async fn set_item_tags(
+        &self,
+        item_id: Uuid,
+        seller_id: Uuid,
+        tags: Vec<String>,
+        is_admin: bool,
+    ) -> Result<Vec<String>, AppError> {
+        let item = Item::find_by_id(&self.db_pool, item_id).await?
+            .ok_or_else(|| AppError::NotFound("Item not found".to_string()))?;
+
+        if !is_admin && item.seller_id != Some(seller_id) {
+            return Err(AppError::Forbidden("Item does not belong to seller".to_string()));
+        }
+
+        let tags = Tag::resolve(&self.db_pool, &tags, is_admin).await?;
+        let tags = Item::set_tags(&self.db_pool, item_id, &tags).await?;
+
+        info!("User {} set tags of item {} to {:?}", seller_id, item_id, tags);
+
+        Ok(tags)
+    }
+
+    pub async fn raffle_tags(&self, raffle_id: Uuid) -> Result<RaffleTags, AppError> {
+        let (tags, item_tags): (Vec<String>, Vec<String>) = sqlx::query_as(
+            "SELECT r.tags, i.tags FROM raffles r JOIN items i ON i.id = r.item_id WHERE r.id = $1",
+        )
+        .bind(raffle_id)
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
+
+        Ok(RaffleTags { raffle_id, tags, item_tags })
+    }
+
+    /// Tags ranked by boxes bought over the last `days`, which defaults to the
+    /// `trending_tags_window_days` setting
+    pub async fn trending_tags(&self, days: Option<i64>, limit: i64) -> Result<Vec<TrendingTag>, AppError> {
+        let days = match days {
+            Some(days) => days,
+            None => self.get_setting("trending_tags_window_days", DEFAULT_TRENDING_WINDOW_DAYS).await?,
+        };
+        if !(1..=MAX_TRENDING_WINDOW_DAYS).contains(&days) {
+            return Err(AppError::Validation(format!(
+                "Trending window must be 1 to {} days",
+                MAX_TRENDING_WINDOW_DAYS
+            )));
+        }
+
+        let tags = sqlx::query_as::<_, TrendingTag>(
+            r#"
+            WITH counts AS (
+                SELECT
+                    tag,
+                    COUNT(*) FILTER (WHERE bp.created_at >= NOW() - make_interval(days => $1)) AS purchases,
+                    COUNT(*) FILTER (WHERE bp.created_at < NOW() - make_interval(days => $1)) AS previous_purchases,
+                    COUNT(DISTINCT bp.raffle_id) FILTER (
+                        WHERE bp.created_at >= NOW() - make_interval(days => $1)
+                    ) AS raffles
+                FROM box_purchases bp
+                JOIN raffle_listings rl ON rl.raffle_id = bp.raffle_id
+                CROSS JOIN LATERAL unnest(rl.tags) AS tag
+                WHERE bp.created_at >= NOW() - make_interval(days => $1 * 2)
+                GROUP BY tag
+            )
+            SELECT
+                c.tag AS slug,
+                COALESCE(t.display_name, c.tag) AS display_name,
+                COALESCE(t.is_curated, FALSE) AS is_curated,
+                c.purchases,
+                c.previous_purchases,
+                c.raffles
+            FROM counts c
+            LEFT JOIN tags t ON t.slug = c.tag
+            WHERE c.purchases > 0
+            ORDER BY c.purchases DESC, c.purchases - c.previous_purchases DESC, c.tag
+            LIMIT $2
+            "#,
+        )
+        .bind(days as i32)
+        .bind(limit)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(tags)
+    }
+
+    /// Collections in discovery order; drafts are only listed for admins
+    pub async fn list_collections(&self, published_only: bool) -> Result<Vec<TagCollection>, AppError> {
+        TagCollection::list(&self.db_pool, published_only).await
+    }
+
+    /// A published collection, for its discovery page
+    pub async fn published_collection(&self, slug: &str) -> Result<TagCollection, AppError> {
+        TagCollection::find_published(&self.db_pool, &slug.trim().to_lowercase())
+            .await?
+            .ok_or_else(|| AppError::NotFound("Collection not found".to_string()))
+    }
+
+    pub async fn create_collection(
+        &self,
+        request: CreateTagCollectionRequest,
+        admin_id: Uuid,
+    ) -> Result<TagCollection, AppError> {
+        let tags = self.collection_tags(&request.tags).await?;
+        let collection = TagCollection::create(&self.db_pool, request, tags, admin_id).await?;
+
+        info!("Admin {} created tag collection {}", admin_id, collection.slug);
+
+        Ok(collection)
+    }
+
+    pub async fn update_collection(
+        &self,
+        collection_id: Uuid,
+        request: UpdateTagCollectionRequest,
+        admin_id: Uuid,
+    ) -> Result<TagCollection, AppError> {
+        let tags = match request.tags.as_deref() {
+            Some(tags) => Some(self.collection_tags(tags).await?),
+            None => None,
+        };
+        let collection = TagCollection::update(&self.db_pool, collection_id, request, tags).await?;
+
+        info!("Admin {} updated tag collection {}", admin_id, collection.slug);
+
+        Ok(collection)
+    }
+
+    pub async fn delete_collection(&self, collection_id: Uuid, admin_id: Uuid) -> Result<(), AppError> {
+        TagCollection::delete(&self.db_pool, collection_id).await?;
+
+        info!("Admin {} deleted tag collection {}", admin_id, collection_id);
+
+        Ok(())
+    }
+
+    async fn collection_tags(&self, tags: &[String]) -> Result<Vec<String>, AppError> {
+        let tags = Tag::resolve(&self.db_pool, tags, true).await?;
+        if tags.is_empty() {
+            return Err(AppError::Validation("A collection needs at least one tag".to_string()));
+        }
+        Ok(tags)
+    }
+
+    async fn get_setting(&self, key: &str, default: i64) -> Result<i64, AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, key).await?;
+        Ok(setting
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default))
+    }
+}
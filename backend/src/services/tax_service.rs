@@ -0,0 +1,809 @@
+use crate::config::AppConfig;
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use crate::services::reporting_service::csv_row;
+use crate::utils::crypto::{decrypt_sensitive_data, encrypt_sensitive_data};
+use chrono::{DateTime, Datelike, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Completed raffles assessed per background run
+const ASSESSMENT_BATCH_SIZE: i64 = 100;
+
+const DEFAULT_COUNTRY_CODE: &str = "US";
+
+/// Tax service detects prizes that need tax reporting, collects winner tax forms and exports filings
+#[derive(Clone)]
+pub struct TaxService {
+    db_pool: PgPool,
+    encryption_key: Option<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdPeriod {
+    /// All prizes won in a tax year count towards the threshold
+    Annual,
+    /// Each prize is compared to the threshold on its own
+    PerPrize,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum TaxFormStatus {
+    Required,
+    Submitted,
+    Verified,
+    Rejected,
+}
+
+impl TaxFormStatus {
+    /// Prizes covered by the form may be fulfilled
+    pub fn allows_fulfillment(&self) -> bool {
+        matches!(self, TaxFormStatus::Submitted | TaxFormStatus::Verified)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TaxJurisdiction {
+    pub id: Uuid,
+    pub country_code: String,
+    pub name: String,
+    pub form_type: String,
+    pub filing_form_type: String,
+    pub reporting_threshold: Decimal,
+    pub currency: String,
+    pub threshold_period: ThresholdPeriod,
+    pub is_active: bool,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TaxJurisdictionUpdate {
+    pub name: String,
+    pub form_type: String,
+    pub filing_form_type: String,
+    pub reporting_threshold: Decimal,
+    pub currency: String,
+    pub threshold_period: ThresholdPeriod,
+    pub is_active: bool,
+}
+
+/// Tax form as shown to the winner and admins; the encrypted payload never leaves the service
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WinnerTaxForm {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub tax_year: i32,
+    pub country_code: String,
+    pub form_type: String,
+    pub status: TaxFormStatus,
+    pub tin_last4: Option<String>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub rejection_reason: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Details collected from the winner; stored encrypted as JSON
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct TaxInfoSubmission {
+    pub legal_name: String,
+    pub tin: String,
+    pub address_line1: String,
+    pub address_line2: Option<String>,
+    pub city: String,
+    pub region: Option<String>,
+    pub postal_code: String,
+    pub country_code: String,
+    /// Winner certifies the information is correct under penalty of perjury
+    pub certified: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TaxFormDecision {
+    Verify,
+    Reject,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReviewTaxFormRequest {
+    pub decision: TaxFormDecision,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrizeTaxAssessment {
+    pub raffle_id: Uuid,
+    pub user_id: Uuid,
+    pub prize_value: Decimal,
+    pub tax_year: i32,
+    pub country_code: String,
+    pub form_required: bool,
+    pub tax_form_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxFilingExport {
+    pub tax_year: i32,
+    pub country_code: String,
+    pub filename: String,
+    pub body: String,
+    pub filing_count: usize,
+    /// Winners above the threshold who have not provided a usable form yet
+    pub missing_forms: usize,
+}
+
+impl TaxService {
+    /// Create a new tax service; without an encryption key forms can be detected but not submitted
+    pub fn new(db_pool: PgPool, encryption_secret: Option<String>) -> Self {
+        let encryption_key = encryption_secret
+            .filter(|secret| !secret.is_empty())
+            .map(|secret| derive_encryption_key(&secret));
+
+        Self { db_pool, encryption_key }
+    }
+
+    pub fn from_config(db_pool: PgPool, config: &AppConfig) -> Self {
+        Self::new(db_pool, config.tax_data_encryption_key.clone())
+    }
+
+    /// Start the scheduled assessment of newly completed raffles
+    pub async fn start_background_tasks(&self) {
+        if self.encryption_key.is_none() {
+            warn!("TAX_DATA_ENCRYPTION_KEY is not set; winner tax forms cannot be submitted");
+        }
+
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(900)); // Every 15 minutes
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.assess_completed_raffles().await {
+                    error!("Scheduled prize tax assessment failed: {}", e);
+                }
+            }
+        });
+
+        info!("Tax service background tasks started");
+    }
+
+    /// Assess every completed raffle with winners that has not been assessed yet
+    pub async fn assess_completed_raffles(&self) -> Result<usize, AppError> {
+        let raffle_ids = sqlx::query_scalar!(
+            r#"
+            SELECT r.id FROM raffles r
+            WHERE r.status = 'completed'
+            AND cardinality(r.winner_user_ids) > 0
+            AND NOT EXISTS (SELECT 1 FROM prize_tax_assessments a WHERE a.raffle_id = r.id)
+            ORDER BY r.completed_at
+            LIMIT $1
+            "#,
+            ASSESSMENT_BATCH_SIZE
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let mut assessed = 0;
+        for raffle_id in raffle_ids {
+            match self.assess_raffle(raffle_id).await {
+                Ok(_) => assessed += 1,
+                Err(e) => warn!("Tax assessment of raffle {} failed: {}", raffle_id, e),
+            }
+        }
+
+        Ok(assessed)
+    }
+
+    /// Record the prize each winner of a raffle received and open tax forms where thresholds are crossed
+    pub async fn assess_raffle(&self, raffle_id: Uuid) -> Result<Vec<PrizeTaxAssessment>, AppError> {
+        let raffle = sqlx::query!(
+            r#"
+            SELECT r.winner_user_ids, r.completed_at, i.retail_price
+            FROM raffles r
+            JOIN items i ON i.id = r.item_id
+            WHERE r.id = $1
+            "#,
+            raffle_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
+
+        let tax_year = raffle.completed_at.unwrap_or_else(Utc::now).year();
+        let winners = raffle.winner_user_ids.unwrap_or_default();
+
+        let mut assessments = Vec::with_capacity(winners.len());
+        for user_id in winners {
+            assessments.push(
+                self.assess_prize(raffle_id, user_id, raffle.retail_price, tax_year).await?,
+            );
+        }
+
+        Ok(assessments)
+    }
+
+    /// Whether a won prize can be shipped or otherwise fulfilled
+    pub async fn is_fulfillment_allowed(&self, raffle_id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+        let assessment = sqlx::query!(
+            r#"
+            SELECT a.form_required, f.status as "status?: TaxFormStatus"
+            FROM prize_tax_assessments a
+            LEFT JOIN winner_tax_forms f ON f.id = a.tax_form_id
+            WHERE a.raffle_id = $1 AND a.user_id = $2
+            "#,
+            raffle_id,
+            user_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(match assessment {
+            // Not assessed yet; the prize may still cross a threshold
+            None => false,
+            Some(a) if !a.form_required => true,
+            Some(a) => a.status.map_or(false, |status| status.allows_fulfillment()),
+        })
+    }
+
+    /// Fail with a conflict unless the prize's tax requirements are met
+    pub async fn ensure_fulfillment_allowed(&self, raffle_id: Uuid, user_id: Uuid) -> Result<(), AppError> {
+        if self.is_fulfillment_allowed(raffle_id, user_id).await? {
+            Ok(())
+        } else {
+            Err(AppError::Conflict(
+                "Prize fulfillment is on hold until the winner's tax form is completed".to_string(),
+            ))
+        }
+    }
+
+    /// Tax forms the user has been asked for, newest year first
+    pub async fn get_user_forms(&self, user_id: Uuid) -> Result<Vec<WinnerTaxForm>, AppError> {
+        let forms = sqlx::query_as!(
+            WinnerTaxForm,
+            r#"
+            SELECT id, user_id, tax_year, country_code, form_type, status as "status: TaxFormStatus",
+                   tin_last4, submitted_at, reviewed_at, rejection_reason, created_at
+            FROM winner_tax_forms
+            WHERE user_id = $1
+            ORDER BY tax_year DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(forms)
+    }
+
+    /// Submit (or resubmit after rejection) the winner's tax information
+    pub async fn submit_form(
+        &self,
+        form_id: Uuid,
+        user_id: Uuid,
+        mut submission: TaxInfoSubmission,
+    ) -> Result<WinnerTaxForm, AppError> {
+        let key = self.encryption_key.as_ref().ok_or_else(|| {
+            AppError::Internal("Tax data encryption is not configured".to_string())
+        })?;
+
+        let form = self.get_form(form_id).await?;
+        if form.user_id != user_id {
+            return Err(AppError::NotFound("Tax form not found".to_string()));
+        }
+
+        if form.status == TaxFormStatus::Verified {
+            return Err(AppError::Conflict("Tax form has already been verified".to_string()));
+        }
+
+        if !submission.certified {
+            return Err(AppError::Validation("You must certify that the information is correct".to_string()));
+        }
+
+        submission.legal_name = submission.legal_name.trim().to_string();
+        submission.country_code = submission.country_code.trim().to_uppercase();
+
+        if submission.legal_name.is_empty()
+            || submission.address_line1.trim().is_empty()
+            || submission.city.trim().is_empty()
+            || submission.postal_code.trim().is_empty()
+        {
+            return Err(AppError::Validation("Legal name and full address are required".to_string()));
+        }
+
+        submission.tin = normalize_tin(&submission.country_code, &submission.tin)?;
+
+        // The winner's residency decides which form applies
+        let form_type = match self.get_jurisdiction(&submission.country_code).await? {
+            Some(jurisdiction) => jurisdiction.form_type,
+            None => form.form_type.clone(),
+        };
+
+        let tin_last4 = submission.tin[submission.tin.len().saturating_sub(4)..].to_string();
+        let payload = serde_json::to_string(&submission)
+            .map_err(|e| AppError::Internal(format!("Failed to serialize tax information: {}", e)))?;
+        let encrypted = encrypt_sensitive_data(&payload, key)?;
+
+        let form = sqlx::query_as!(
+            WinnerTaxForm,
+            r#"
+            UPDATE winner_tax_forms
+            SET status = 'submitted', encrypted_tax_info = $2, tin_last4 = $3, country_code = $4,
+                form_type = $5, submitted_at = NOW(), reviewed_by = NULL, reviewed_at = NULL,
+                rejection_reason = NULL
+            WHERE id = $1
+            RETURNING id, user_id, tax_year, country_code, form_type, status as "status: TaxFormStatus",
+                      tin_last4, submitted_at, reviewed_at, rejection_reason, created_at
+            "#,
+            form_id,
+            encrypted,
+            tin_last4,
+            submission.country_code,
+            form_type
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        info!("User {} submitted {} tax form for {}", user_id, form.form_type, form.tax_year);
+
+        Ok(form)
+    }
+
+    /// Forms awaiting review, oldest submission first (admin)
+    pub async fn get_review_queue(&self) -> Result<Vec<WinnerTaxForm>, AppError> {
+        let forms = sqlx::query_as!(
+            WinnerTaxForm,
+            r#"
+            SELECT id, user_id, tax_year, country_code, form_type, status as "status: TaxFormStatus",
+                   tin_last4, submitted_at, reviewed_at, rejection_reason, created_at
+            FROM winner_tax_forms
+            WHERE status = 'submitted'
+            ORDER BY submitted_at
+            "#
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(forms)
+    }
+
+    /// Verify or reject a submitted form (admin)
+    pub async fn review_form(
+        &self,
+        form_id: Uuid,
+        reviewer_id: Uuid,
+        request: ReviewTaxFormRequest,
+    ) -> Result<WinnerTaxForm, AppError> {
+        let form = self.get_form(form_id).await?;
+        if form.status != TaxFormStatus::Submitted {
+            return Err(AppError::Conflict("Only submitted tax forms can be reviewed".to_string()));
+        }
+
+        let (status, reason) = match request.decision {
+            TaxFormDecision::Verify => (TaxFormStatus::Verified, None),
+            TaxFormDecision::Reject => {
+                let reason = request.reason.filter(|r| !r.trim().is_empty()).ok_or_else(|| {
+                    AppError::Validation("A rejection reason is required".to_string())
+                })?;
+                (TaxFormStatus::Rejected, Some(reason))
+            }
+        };
+
+        let form = sqlx::query_as!(
+            WinnerTaxForm,
+            r#"
+            UPDATE winner_tax_forms
+            SET status = $2, rejection_reason = $3, reviewed_by = $4, reviewed_at = NOW()
+            WHERE id = $1
+            RETURNING id, user_id, tax_year, country_code, form_type, status as "status: TaxFormStatus",
+                      tin_last4, submitted_at, reviewed_at, rejection_reason, created_at
+            "#,
+            form_id,
+            status as TaxFormStatus,
+            reason,
+            reviewer_id
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        info!("Admin {} marked tax form {} as {:?}", reviewer_id, form_id, form.status);
+
+        Ok(form)
+    }
+
+    /// All configured jurisdictions
+    pub async fn get_jurisdictions(&self) -> Result<Vec<TaxJurisdiction>, AppError> {
+        let jurisdictions = sqlx::query_as!(
+            TaxJurisdiction,
+            r#"
+            SELECT id, country_code, name, form_type, filing_form_type, reporting_threshold, currency,
+                   threshold_period as "threshold_period: ThresholdPeriod", is_active as "is_active!", updated_at
+            FROM tax_jurisdictions
+            ORDER BY country_code
+            "#
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(jurisdictions)
+    }
+
+    /// Create or replace the reporting rules for a country (admin)
+    pub async fn upsert_jurisdiction(
+        &self,
+        country_code: &str,
+        update: TaxJurisdictionUpdate,
+    ) -> Result<TaxJurisdiction, AppError> {
+        let country_code = country_code.trim().to_uppercase();
+        if country_code.len() != 2 || !country_code.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(AppError::Validation("Country code must be an ISO 3166-1 alpha-2 code".to_string()));
+        }
+
+        if update.reporting_threshold < Decimal::ZERO {
+            return Err(AppError::Validation("Reporting threshold cannot be negative".to_string()));
+        }
+
+        let jurisdiction = sqlx::query_as!(
+            TaxJurisdiction,
+            r#"
+            INSERT INTO tax_jurisdictions (country_code, name, form_type, filing_form_type, reporting_threshold,
+                                           currency, threshold_period, is_active)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (country_code) DO UPDATE SET
+                name = EXCLUDED.name,
+                form_type = EXCLUDED.form_type,
+                filing_form_type = EXCLUDED.filing_form_type,
+                reporting_threshold = EXCLUDED.reporting_threshold,
+                currency = EXCLUDED.currency,
+                threshold_period = EXCLUDED.threshold_period,
+                is_active = EXCLUDED.is_active
+            RETURNING id, country_code, name, form_type, filing_form_type, reporting_threshold, currency,
+                      threshold_period as "threshold_period: ThresholdPeriod", is_active as "is_active!", updated_at
+            "#,
+            country_code,
+            update.name,
+            update.form_type,
+            update.filing_form_type,
+            update.reporting_threshold,
+            update.currency.to_uppercase(),
+            update.threshold_period as ThresholdPeriod,
+            update.is_active
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        info!(
+            "Tax jurisdiction {} set to {} above {} {}",
+            jurisdiction.country_code, jurisdiction.filing_form_type,
+            jurisdiction.reporting_threshold, jurisdiction.currency
+        );
+
+        Ok(jurisdiction)
+    }
+
+    /// Export required information returns for a tax year and jurisdiction as CSV (admin)
+    pub async fn export_filings(&self, tax_year: i32, country_code: &str) -> Result<TaxFilingExport, AppError> {
+        let key = self.encryption_key.as_ref().ok_or_else(|| {
+            AppError::Internal("Tax data encryption is not configured".to_string())
+        })?;
+
+        let country_code = country_code.trim().to_uppercase();
+        let jurisdiction = self
+            .get_jurisdiction(&country_code)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Tax jurisdiction not found".to_string()))?;
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT f.id, f.user_id, f.form_type, f.status as "status: TaxFormStatus", f.encrypted_tax_info,
+                   u.email, COALESCE(SUM(a.prize_value), 0) as "total_prize_value!"
+            FROM winner_tax_forms f
+            JOIN users u ON u.id = f.user_id
+            JOIN prize_tax_assessments a ON a.tax_form_id = f.id AND a.form_required = TRUE
+            WHERE f.tax_year = $1 AND f.country_code = $2
+            GROUP BY f.id, u.email
+            ORDER BY f.created_at
+            "#,
+            tax_year,
+            country_code
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let mut body = csv_row(&[
+            "form_id", "filing_form_type", "tax_year", "legal_name", "tin", "address_line1",
+            "address_line2", "city", "region", "postal_code", "country_code", "email",
+            "total_prize_value", "currency", "form_status",
+        ]);
+        let mut filing_count = 0;
+        let mut missing_forms = 0;
+
+        for row in &rows {
+            let info = match (&row.encrypted_tax_info, row.status.allows_fulfillment()) {
+                (Some(encrypted), true) => {
+                    let decrypted = decrypt_sensitive_data(encrypted, key)?;
+                    Some(serde_json::from_str::<TaxInfoSubmission>(&decrypted).map_err(|e| {
+                        AppError::Internal(format!("Failed to parse tax information: {}", e))
+                    })?)
+                }
+                _ => None,
+            };
+
+            if info.is_none() {
+                missing_forms += 1;
+            }
+            filing_count += 1;
+
+            // Rows without usable tax information are still exported so they can be chased up
+            let info = info.unwrap_or_default();
+            body.push_str(&csv_row(&[
+                &row.id.to_string(),
+                &jurisdiction.filing_form_type,
+                &tax_year.to_string(),
+                &info.legal_name,
+                &info.tin,
+                &info.address_line1,
+                info.address_line2.as_deref().unwrap_or_default(),
+                &info.city,
+                info.region.as_deref().unwrap_or_default(),
+                &info.postal_code,
+                &country_code,
+                &row.email,
+                &row.total_prize_value.round_dp(2).to_string(),
+                &jurisdiction.currency,
+                &format!("{:?}", row.status).to_lowercase(),
+            ]));
+        }
+
+        info!(
+            "Exported {} {} filings for {} ({} missing forms)",
+            filing_count, jurisdiction.filing_form_type, tax_year, missing_forms
+        );
+
+        Ok(TaxFilingExport {
+            tax_year,
+            filename: format!(
+                "{}-{}-{}.csv",
+                jurisdiction.filing_form_type.to_lowercase(),
+                country_code.to_lowercase(),
+                tax_year
+            ),
+            country_code,
+            body,
+            filing_count,
+            missing_forms,
+        })
+    }
+
+    // Private helper methods
+
+    async fn assess_prize(
+        &self,
+        raffle_id: Uuid,
+        user_id: Uuid,
+        prize_value: Decimal,
+        tax_year: i32,
+    ) -> Result<PrizeTaxAssessment, AppError> {
+        let country_code = self.resolve_country(user_id, tax_year).await?;
+        let jurisdiction = self.get_jurisdiction(&country_code).await?.filter(|j| j.is_active);
+
+        let mut tx = self.db_pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO prize_tax_assessments (raffle_id, user_id, prize_value, tax_year, country_code)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (raffle_id, user_id) DO NOTHING
+            "#,
+            raffle_id,
+            user_id,
+            prize_value,
+            tax_year,
+            country_code
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let year_to_date = sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(prize_value), 0) as "total!"
+            FROM prize_tax_assessments
+            WHERE user_id = $1 AND tax_year = $2 AND country_code = $3
+            "#,
+            user_id,
+            tax_year,
+            country_code
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if let Some(jurisdiction) = jurisdiction
+            .as_ref()
+            .filter(|j| requires_reporting(j.threshold_period, j.reporting_threshold, prize_value, year_to_date))
+        {
+            let form_id = sqlx::query_scalar!(
+                r#"
+                INSERT INTO winner_tax_forms (user_id, tax_year, country_code, form_type)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (user_id, tax_year) DO UPDATE SET updated_at = NOW()
+                RETURNING id
+                "#,
+                user_id,
+                tax_year,
+                country_code,
+                jurisdiction.form_type
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+
+            // Annual thresholds make every prize of the year reportable, not just the one that crossed it
+            sqlx::query!(
+                r#"
+                UPDATE prize_tax_assessments SET form_required = TRUE, tax_form_id = $1
+                WHERE user_id = $2 AND tax_year = $3 AND country_code = $4
+                AND ($5::boolean OR raffle_id = $6)
+                "#,
+                form_id,
+                user_id,
+                tax_year,
+                country_code,
+                jurisdiction.threshold_period == ThresholdPeriod::Annual,
+                raffle_id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            info!(
+                "Prize from raffle {} requires a {} from user {} for {}",
+                raffle_id, jurisdiction.form_type, user_id, tax_year
+            );
+        }
+
+        let assessment = sqlx::query_as!(
+            PrizeTaxAssessment,
+            r#"
+            SELECT raffle_id, user_id, prize_value, tax_year, country_code, form_required, tax_form_id
+            FROM prize_tax_assessments
+            WHERE raffle_id = $1 AND user_id = $2
+            "#,
+            raffle_id,
+            user_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(assessment)
+    }
+
+    /// Residency from the winner's latest form, falling back to the platform default
+    async fn resolve_country(&self, user_id: Uuid, tax_year: i32) -> Result<String, AppError> {
+        let latest = sqlx::query_scalar!(
+            r#"
+            SELECT country_code FROM winner_tax_forms
+            WHERE user_id = $1 AND tax_year <= $2
+            ORDER BY tax_year DESC
+            LIMIT 1
+            "#,
+            user_id,
+            tax_year
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        if let Some(country_code) = latest {
+            return Ok(country_code);
+        }
+
+        let setting = SystemSetting::get_by_key(&self.db_pool, "tax_default_country_code").await?;
+        Ok(setting
+            .and_then(|s| s.get_string_value())
+            .unwrap_or_else(|| DEFAULT_COUNTRY_CODE.to_string()))
+    }
+
+    async fn get_jurisdiction(&self, country_code: &str) -> Result<Option<TaxJurisdiction>, AppError> {
+        let jurisdiction = sqlx::query_as!(
+            TaxJurisdiction,
+            r#"
+            SELECT id, country_code, name, form_type, filing_form_type, reporting_threshold, currency,
+                   threshold_period as "threshold_period: ThresholdPeriod", is_active as "is_active!", updated_at
+            FROM tax_jurisdictions
+            WHERE country_code = $1
+            "#,
+            country_code
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(jurisdiction)
+    }
+
+    async fn get_form(&self, form_id: Uuid) -> Result<WinnerTaxForm, AppError> {
+        sqlx::query_as!(
+            WinnerTaxForm,
+            r#"
+            SELECT id, user_id, tax_year, country_code, form_type, status as "status: TaxFormStatus",
+                   tin_last4, submitted_at, reviewed_at, rejection_reason, created_at
+            FROM winner_tax_forms
+            WHERE id = $1
+            "#,
+            form_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tax form not found".to_string()))
+    }
+}
+
+/// Whether a prize pushes the winner over a jurisdiction's reporting threshold
+pub fn requires_reporting(
+    period: ThresholdPeriod,
+    threshold: Decimal,
+    prize_value: Decimal,
+    year_to_date: Decimal,
+) -> bool {
+    match period {
+        ThresholdPeriod::PerPrize => prize_value >= threshold,
+        ThresholdPeriod::Annual => year_to_date >= threshold,
+    }
+}
+
+/// Strip formatting from a taxpayer ID and check its shape where the format is known
+fn normalize_tin(country_code: &str, tin: &str) -> Result<String, AppError> {
+    let normalized: String = tin
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_uppercase();
+
+    let valid = match country_code {
+        // SSN, ITIN or EIN
+        "US" => normalized.len() == 9 && normalized.chars().all(|c| c.is_ascii_digit()),
+        _ => (4..=20).contains(&normalized.len()),
+    };
+
+    if valid {
+        Ok(normalized)
+    } else {
+        Err(AppError::Validation("Taxpayer identification number is not valid".to_string()))
+    }
+}
+
+fn derive_encryption_key(secret: &str) -> [u8; 32] {
+    Sha256::digest(secret.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annual_threshold_uses_year_to_date_total() {
+        let threshold = Decimal::from(600);
+
+        assert!(!requires_reporting(ThresholdPeriod::Annual, threshold, Decimal::from(400), Decimal::from(400)));
+        assert!(requires_reporting(ThresholdPeriod::Annual, threshold, Decimal::from(250), Decimal::from(650)));
+        assert!(!requires_reporting(ThresholdPeriod::PerPrize, threshold, Decimal::from(250), Decimal::from(650)));
+        assert!(requires_reporting(ThresholdPeriod::PerPrize, threshold, Decimal::from(600), Decimal::from(600)));
+    }
+
+    #[test]
+    fn test_normalize_tin() {
+        assert_eq!(normalize_tin("US", "123-45-6789").unwrap(), "123456789");
+        assert!(normalize_tin("US", "12-345").is_err());
+        assert!(normalize_tin("US", "12345678A").is_err());
+        assert_eq!(normalize_tin("GB", "qq 12 34 56 c").unwrap(), "QQ123456C");
+    }
+}
@@ -0,0 +1,548 @@
+use crate::error::AppError;
+use crate::models::raffle::{BoxPurchase, Raffle};
+use chrono::{DateTime, Utc};
+use ethers::abi::Token;
+use ethers::types::{Address, U256};
+use ethers::utils::keccak256;
+use raffle_platform_shared::RaffleStatus;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Bumped whenever a field is renamed, removed or changes meaning; additions keep the version
+pub const VERIFICATION_SCHEMA_VERSION: &str = "1";
+/// How `participants.commitment_hash` is computed, so verifiers don't have to guess
+pub const COMMITMENT_SCHEME: &str = "sha256(join(\"\\n\", sorted_by_box(\"{box_number}:{user_id}\")))";
+/// How the contract turns the random word into winners, mirroring `_selectWinners`. `box_owners`
+/// is every box's buyer address in on-chain purchase order and `block.timestamp` is the
+/// timestamp of the `winner_selected` block.
+pub const SELECTION_ALGORITHM: &str = "for i in 0..total_winners: \
+index = uint256(keccak256(abi.encode(random_word, i, block.timestamp))) % len(box_owners); \
+winner[i] = box_owners[index]; if that address already won, step index forward by one \
+(mod len(box_owners)) until it reaches an address that has not";
+
+/// Chain events that make up a draw, in the order they happen
+const DRAW_EVENT_TYPES: &[&str] = &[
+    "raffle_created",
+    "raffle_full",
+    "randomness_requested",
+    "winner_selected",
+];
+
+/// Verification service publishes everything needed to check a completed draw
+/// independently: what went in, what came out, and where to find it on chain.
+#[derive(Clone)]
+pub struct VerificationService {
+    db_pool: PgPool,
+    contract_address: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RaffleVerification {
+    #[schema(value_type = String)]
+    pub schema_version: &'static str,
+    pub raffle_id: Uuid,
+    pub status: RaffleStatus,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub draw: DrawRecord,
+    pub participants: ParticipantCommitment,
+    pub onchain: OnchainReferences,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DrawRecord {
+    pub total_boxes: i32,
+    pub boxes_sold: i32,
+    pub total_winners: i32,
+    /// Random word delivered by the VRF, as a decimal string
+    pub random_seed: Option<String>,
+    /// VRF request the random word answered, as a decimal string
+    pub vrf_request_id: Option<String>,
+    pub randomness_request_tx_hash: Option<String>,
+    /// Transaction that delivered the random word and selected the winners
+    pub selection_tx_hash: Option<String>,
+    #[schema(value_type = String)]
+    pub selection_algorithm: &'static str,
+    /// Winning wallet addresses as emitted by the contract, in selection order
+    pub winner_addresses: Vec<String>,
+    pub winner_user_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ParticipantCommitment {
+    pub box_count: usize,
+    pub unique_participants: usize,
+    #[schema(value_type = String)]
+    pub commitment_scheme: &'static str,
+    pub commitment_hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct OnchainReferences {
+    pub contract_address: Option<String>,
+    pub blockchain_raffle_id: Option<i64>,
+    pub creation_tx_hash: Option<String>,
+    pub events: Vec<OnchainEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct OnchainEvent {
+    pub event_type: String,
+    pub block_number: i64,
+    pub transaction_hash: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(skip)]
+    pub data: serde_json::Value,
+}
+
+/// Winners re-derived from the on-chain inputs, next to the winners the contract recorded
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DrawReplay {
+    #[schema(value_type = String)]
+    pub schema_version: &'static str,
+    pub raffle_id: Uuid,
+    pub outcome: ReplayOutcome,
+    pub inputs: ReplayInputs,
+    #[schema(value_type = String)]
+    pub selection_algorithm: &'static str,
+    /// Winning addresses in selection order, as computed here
+    pub derived_winners: Vec<String>,
+    /// Winning addresses in selection order, as emitted by `winner_selected`
+    pub recorded_winners: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayOutcome {
+    /// The derived winners equal the recorded ones
+    Verified,
+    Mismatch,
+    /// Some on-chain input has not been indexed, so nothing was derived
+    Incomplete,
+}
+
+/// Everything the selection consumed, so it can be rerun without this API
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReplayInputs {
+    pub blockchain_raffle_id: Option<i64>,
+    pub total_winners: i32,
+    /// VRF random word, as a decimal string
+    pub random_word: Option<String>,
+    /// `block.timestamp` of the selection, in Unix seconds
+    pub block_timestamp: Option<i64>,
+    pub selection_tx_hash: Option<String>,
+    /// Buyer address of every on-chain box, in purchase order (box 1 first)
+    pub box_owners: Vec<String>,
+    /// On-chain box numbers sold but with no indexed `box_purchased` event
+    pub missing_boxes: Vec<i64>,
+}
+
+impl VerificationService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self {
+            db_pool,
+            contract_address: None,
+        }
+    }
+
+    /// Reference this raffle contract in verification records
+    pub fn with_contract_address(mut self, contract_address: String) -> Self {
+        self.contract_address = Some(contract_address);
+        self
+    }
+
+    /// Build the verification record for a completed raffle
+    pub async fn verify_raffle(&self, raffle_id: Uuid) -> Result<RaffleVerification, AppError> {
+        let raffle = Raffle::find_by_id(&self.db_pool, raffle_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
+
+        if raffle.status != RaffleStatus::Completed {
+            return Err(AppError::Validation("Raffle is not completed yet".to_string()));
+        }
+
+        let purchases = BoxPurchase::find_by_raffle(&self.db_pool, raffle_id).await?;
+        let entries: Vec<(i32, Uuid)> = purchases.iter().map(|p| (p.box_number, p.user_id)).collect();
+
+        let blockchain_raffle_id = sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT blockchain_raffle_id FROM raffles WHERE id = $1",
+        )
+        .bind(raffle_id)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        let events = match blockchain_raffle_id {
+            Some(chain_id) => self.draw_events(chain_id).await?,
+            None => Vec::new(),
+        };
+        let request_event = events.iter().rev().find(|e| e.event_type == "randomness_requested");
+        let winner_event = events.iter().rev().find(|e| e.event_type == "winner_selected");
+
+        Ok(RaffleVerification {
+            schema_version: VERIFICATION_SCHEMA_VERSION,
+            raffle_id,
+            status: raffle.status,
+            completed_at: raffle.completed_at,
+            draw: DrawRecord {
+                total_boxes: raffle.total_boxes,
+                boxes_sold: raffle.boxes_sold,
+                total_winners: raffle.total_winners,
+                random_seed: winner_event.and_then(|e| decimal_field(&e.data, "random_word")),
+                vrf_request_id: request_event.and_then(|e| decimal_field(&e.data, "request_id")),
+                randomness_request_tx_hash: request_event.map(|e| e.transaction_hash.clone()),
+                selection_tx_hash: winner_event.map(|e| e.transaction_hash.clone()),
+                selection_algorithm: SELECTION_ALGORITHM,
+                winner_addresses: winner_event.map(|e| winner_addresses(&e.data)).unwrap_or_default(),
+                winner_user_ids: raffle.winner_user_ids,
+            },
+            participants: ParticipantCommitment {
+                box_count: entries.len(),
+                unique_participants: unique_participants(&entries),
+                commitment_scheme: COMMITMENT_SCHEME,
+                commitment_hash: participant_commitment(&entries),
+            },
+            onchain: OnchainReferences {
+                contract_address: self.contract_address.clone(),
+                blockchain_raffle_id,
+                creation_tx_hash: raffle.blockchain_tx_hash,
+                events,
+            },
+        })
+    }
+
+    /// Rerun the contract's winner selection for a completed raffle from its indexed chain events
+    pub async fn replay_draw(&self, raffle_id: Uuid) -> Result<DrawReplay, AppError> {
+        let raffle = Raffle::find_by_id(&self.db_pool, raffle_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
+
+        if raffle.status != RaffleStatus::Completed {
+            return Err(AppError::Validation("Raffle is not completed yet".to_string()));
+        }
+
+        let blockchain_raffle_id = sqlx::query_scalar::<_, Option<i64>>(
+            "SELECT blockchain_raffle_id FROM raffles WHERE id = $1",
+        )
+        .bind(raffle_id)
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        let (winner_event, purchases) = match blockchain_raffle_id {
+            Some(chain_id) => (
+                self.draw_events(chain_id)
+                    .await?
+                    .into_iter()
+                    .rev()
+                    .find(|e| e.event_type == "winner_selected"),
+                self.box_purchase_events(chain_id).await?,
+            ),
+            None => (None, Vec::new()),
+        };
+
+        let random_word = winner_event.as_ref().and_then(|e| u256_field(&e.data, "random_word"));
+        let block_timestamp = winner_event.as_ref().map(|e| e.timestamp.timestamp());
+        let (box_owners, missing_boxes) = box_owners(&purchases, raffle.boxes_sold.max(0) as u64);
+
+        let derived_winners = match (random_word, block_timestamp) {
+            (Some(random_word), Some(timestamp)) if missing_boxes.is_empty() && !box_owners.is_empty() => {
+                select_winners(random_word, U256::from(timestamp as u64), &box_owners, raffle.total_winners)
+                    .iter()
+                    .map(|winner| format!("{:?}", winner))
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        let recorded_winners = winner_event
+            .as_ref()
+            .map(|e| winner_addresses(&e.data))
+            .unwrap_or_default();
+
+        let outcome = if derived_winners.is_empty() {
+            ReplayOutcome::Incomplete
+        } else if derived_winners == recorded_winners {
+            ReplayOutcome::Verified
+        } else {
+            ReplayOutcome::Mismatch
+        };
+
+        Ok(DrawReplay {
+            schema_version: VERIFICATION_SCHEMA_VERSION,
+            raffle_id,
+            outcome,
+            inputs: ReplayInputs {
+                blockchain_raffle_id,
+                total_winners: raffle.total_winners,
+                random_word: random_word.map(|w| w.to_string()),
+                block_timestamp,
+                selection_tx_hash: winner_event.map(|e| e.transaction_hash),
+                box_owners: box_owners.iter().map(|owner| format!("{:?}", owner)).collect(),
+                missing_boxes,
+            },
+            selection_algorithm: SELECTION_ALGORITHM,
+            derived_winners,
+            recorded_winners,
+        })
+    }
+
+    // Private helper methods
+
+    async fn draw_events(&self, blockchain_raffle_id: i64) -> Result<Vec<OnchainEvent>, AppError> {
+        let event_types: Vec<String> = DRAW_EVENT_TYPES.iter().map(|t| t.to_string()).collect();
+
+        let events = sqlx::query_as::<_, OnchainEvent>(
+            r#"
+            SELECT event_type, block_number, transaction_hash, timestamp, data
+            FROM blockchain_events
+            WHERE raffle_id = $1 AND event_type = ANY($2)
+            ORDER BY block_number, id
+            "#,
+        )
+        .bind(blockchain_raffle_id)
+        .bind(&event_types)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    async fn box_purchase_events(&self, blockchain_raffle_id: i64) -> Result<Vec<OnchainEvent>, AppError> {
+        let events = sqlx::query_as::<_, OnchainEvent>(
+            r#"
+            SELECT event_type, block_number, transaction_hash, timestamp, data
+            FROM blockchain_events
+            WHERE raffle_id = $1 AND event_type = 'box_purchased'
+            ORDER BY block_number, id
+            "#,
+        )
+        .bind(blockchain_raffle_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(events)
+    }
+}
+
+/// The contract's `_selectWinners`: one derived index per winner, stepping past addresses that
+/// already won. A slot stays the zero address when every owner has won, as it does on chain.
+fn select_winners(random_word: U256, block_timestamp: U256, box_owners: &[Address], total_winners: i32) -> Vec<Address> {
+    if box_owners.is_empty() {
+        return Vec::new();
+    }
+
+    let count = U256::from(box_owners.len());
+    let mut winners: Vec<Address> = Vec::new();
+
+    for i in 0..total_winners.max(0) {
+        let encoded = ethers::abi::encode(&[
+            Token::Uint(random_word),
+            Token::Uint(U256::from(i as u64)),
+            Token::Uint(block_timestamp),
+        ]);
+        let start = (U256::from_big_endian(&keccak256(encoded)) % count).as_usize();
+
+        let winner = (0..box_owners.len())
+            .map(|k| box_owners[(start + k) % box_owners.len()])
+            .find(|owner| !winners.contains(owner))
+            .unwrap_or_else(Address::zero);
+        winners.push(winner);
+    }
+
+    winners
+}
+
+/// Owners in on-chain box order, and the box numbers with no indexed purchase among the first
+/// `boxes_sold` and any later ones that were indexed
+fn box_owners(purchases: &[OnchainEvent], boxes_sold: u64) -> (Vec<Address>, Vec<i64>) {
+    let mut boxes: Vec<(u64, Address)> = purchases
+        .iter()
+        .filter_map(|e| {
+            let box_number = u256_field(&e.data, "box_number")?.as_u64();
+            let buyer = e.data.get("buyer")?.as_str()?.parse::<Address>().ok()?;
+            Some((box_number, buyer))
+        })
+        .collect();
+    boxes.sort_by_key(|(box_number, _)| *box_number);
+    boxes.dedup_by_key(|(box_number, _)| *box_number);
+
+    let highest = boxes.last().map(|(box_number, _)| *box_number).unwrap_or(0).max(boxes_sold);
+    let missing = (1..=highest)
+        .filter(|n| boxes.binary_search_by_key(n, |(box_number, _)| *box_number).is_err())
+        .map(|n| n as i64)
+        .collect();
+
+    (boxes.into_iter().map(|(_, owner)| owner).collect(), missing)
+}
+
+/// Hash of every sold box and its owner, independent of the order they were read in
+fn participant_commitment(entries: &[(i32, Uuid)]) -> String {
+    let mut sorted = entries.to_vec();
+    sorted.sort();
+
+    let preimage = sorted
+        .iter()
+        .map(|(box_number, user_id)| format!("{}:{}", box_number, user_id))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    hex::encode(Sha256::digest(preimage.as_bytes()))
+}
+
+fn unique_participants(entries: &[(i32, Uuid)]) -> usize {
+    let mut users: Vec<Uuid> = entries.iter().map(|(_, user_id)| *user_id).collect();
+    users.sort();
+    users.dedup();
+    users.len()
+}
+
+fn u256_field(data: &serde_json::Value, key: &str) -> Option<U256> {
+    decimal_field(data, key).and_then(|value| U256::from_dec_str(&value).ok())
+}
+
+/// The event processor stores U256 values as either a decimal string or a `0x` hex string
+fn decimal_field(data: &serde_json::Value, key: &str) -> Option<String> {
+    let value = data.get(key)?;
+    let raw = match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Number(n) => return Some(n.to_string()),
+        _ => return None,
+    };
+
+    match raw.strip_prefix("0x") {
+        Some(hex_digits) => U256::from_str_radix(hex_digits, 16)
+            .ok()
+            .map(|v| v.to_string()),
+        None => Some(raw),
+    }
+}
+
+fn winner_addresses(data: &serde_json::Value) -> Vec<String> {
+    data.get("winners")
+        .and_then(|w| w.as_array())
+        .map(|winners| {
+            winners
+                .iter()
+                .filter_map(|w| w.as_str().map(|s| s.to_lowercase()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_participant_commitment_ignores_input_order() {
+        let alice = Uuid::parse_str("00000000-0000-0000-0000-00000000000a").unwrap();
+        let bob = Uuid::parse_str("00000000-0000-0000-0000-00000000000b").unwrap();
+
+        let forward = participant_commitment(&[(1, alice), (2, bob), (3, alice)]);
+        let shuffled = participant_commitment(&[(3, alice), (1, alice), (2, bob)]);
+        assert_eq!(forward, shuffled);
+        assert_eq!(forward.len(), 64);
+
+        let swapped = participant_commitment(&[(1, bob), (2, alice), (3, alice)]);
+        assert_ne!(forward, swapped);
+
+        assert_eq!(unique_participants(&[(1, alice), (2, bob), (3, alice)]), 2);
+    }
+
+    #[test]
+    fn test_decimal_field_formats() {
+        assert_eq!(
+            decimal_field(&serde_json::json!({ "random_word": "0xff" }), "random_word"),
+            Some("255".to_string())
+        );
+        assert_eq!(
+            decimal_field(&serde_json::json!({ "random_word": "12345" }), "random_word"),
+            Some("12345".to_string())
+        );
+        assert_eq!(
+            decimal_field(&serde_json::json!({ "request_id": "0x10" }), "request_id"),
+            Some("16".to_string())
+        );
+        assert_eq!(decimal_field(&serde_json::json!({}), "random_word"), None);
+    }
+
+    fn purchase(box_number: u64, buyer: Address) -> OnchainEvent {
+        OnchainEvent {
+            event_type: "box_purchased".to_string(),
+            block_number: 1,
+            transaction_hash: String::new(),
+            timestamp: Utc::now(),
+            data: serde_json::json!({ "box_number": format!("{:#x}", box_number), "buyer": format!("{:?}", buyer) }),
+        }
+    }
+
+    #[test]
+    fn test_box_owners_follow_chain_order_and_report_gaps() {
+        let (a, b, c) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2), Address::from_low_u64_be(3));
+
+        let (owners, missing) = box_owners(&[purchase(3, c), purchase(1, a), purchase(2, b)], 3);
+        assert_eq!(owners, vec![a, b, c]);
+        assert!(missing.is_empty());
+
+        let (_, missing) = box_owners(&[purchase(1, a), purchase(3, c)], 5);
+        assert_eq!(missing, vec![2, 4, 5]);
+    }
+
+    #[test]
+    fn test_select_winners_never_repeats_an_owner() {
+        let owners: Vec<Address> = (1..=4).map(Address::from_low_u64_be).collect();
+        let boxes = [owners[0], owners[1], owners[0], owners[2], owners[3], owners[0]];
+        let word = U256::from_dec_str("78541660797044910968829902406342334108369226379826116161446442989268089806461").unwrap();
+
+        let winners = select_winners(word, U256::from(1_705_332_600u64), &boxes, 3);
+        assert_eq!(winners.len(), 3);
+        assert!(winners.iter().all(|w| owners.contains(w)));
+        let mut distinct = winners.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 3);
+
+        // Same inputs, same winners
+        assert_eq!(select_winners(word, U256::from(1_705_332_600u64), &boxes, 3), winners);
+
+        // With fewer owners than winners the contract leaves the extra slots empty
+        let single = select_winners(word, U256::one(), &[owners[0], owners[0]], 2);
+        assert_eq!(single, vec![owners[0], Address::zero()]);
+    }
+
+    #[test]
+    fn test_selection_algorithm_describes_contract_and_replay() {
+        // The published description, the contract it mirrors and the replay must agree
+        let contract = include_str!("../../../contracts/contracts/RaffleContract.sol");
+        assert!(contract.contains("keccak256(abi.encode(randomWord, i, block.timestamp))"));
+        assert!(contract.contains("derivedRandom % boxOwners.length"));
+        assert!(contract.contains("(winnerIndex + k) % boxOwners.length"));
+        assert!(SELECTION_ALGORITHM.contains("keccak256(abi.encode(random_word, i, block.timestamp))"));
+        assert!(SELECTION_ALGORITHM.contains("% len(box_owners)"));
+
+        // Follow the description by hand for the first winner
+        let owners: Vec<Address> = (1..=5).map(Address::from_low_u64_be).collect();
+        let word = U256::from(42u64);
+        let timestamp = U256::from(1_705_332_600u64);
+        let hash = keccak256(ethers::abi::encode(&[Token::Uint(word), Token::Uint(U256::zero()), Token::Uint(timestamp)]));
+        let index = (U256::from_big_endian(&hash) % U256::from(owners.len())).as_usize();
+
+        assert_eq!(select_winners(word, timestamp, &owners, 1), vec![owners[index]]);
+
+        // An owner who already won is skipped by stepping forward to the next box
+        let mut boxes = vec![owners[index]; owners.len()];
+        boxes[(index + 2) % owners.len()] = owners[(index + 1) % owners.len()];
+        let winners = select_winners(word, timestamp, &boxes, 2);
+        assert_eq!(winners[0], owners[index]);
+        assert_eq!(winners[1], owners[(index + 1) % owners.len()]);
+    }
+
+    #[test]
+    fn test_winner_addresses_are_lowercased() {
+        let data = serde_json::json!({ "winners": ["0xABCdef0000000000000000000000000000000001"] });
+        assert_eq!(
+            winner_addresses(&data),
+            vec!["0xabcdef0000000000000000000000000000000001".to_string()]
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use crate::error::AppError;
+use crate::models::raffle_waitlist::{users_to_notify, waitlist_reason, WaitlistEntry, WaitlistOpening, WaitlistReason, WaitlistStatus};
+use crate::models::system_settings::SystemSetting;
+use crate::services::outbox_service::OutboxMessage;
+use chrono::{DateTime, Utc};
+use raffle_platform_shared::RaffleStatus;
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use tracing::{error, info};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+const SWEEP_INTERVAL_SECONDS: u64 = 60;
+const DEFAULT_CLAIM_WINDOW_MINUTES: i64 = 30;
+/// Relisted entries notified per sweep; the rest follow on the next one
+const RELIST_BATCH_SIZE: i64 = 500;
+
+/// A user's place on a raffle's waitlist
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WaitlistPosition {
+    pub raffle_id: Uuid,
+    pub reason: WaitlistReason,
+    pub status: WaitlistStatus,
+    /// Place in line counting from 1; None once notified
+    pub position: Option<i64>,
+    /// Users still waiting, including this one
+    pub waiting: i64,
+    pub joined_at: DateTime<Utc>,
+    /// Raffle the user was sent to when notified
+    pub notified_raffle_id: Option<Uuid>,
+    pub notified_at: Option<DateTime<Utc>>,
+}
+
+/// Users notified by one sweep
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct WaitlistSweepReport {
+    pub boxes_freed: u64,
+    pub relisted: u64,
+}
+
+#[derive(Debug, FromRow)]
+struct RaffleWithFreeBoxes {
+    id: Uuid,
+    item_title: String,
+    free_boxes: i64,
+    recently_notified: i64,
+}
+
+/// Waitlist service keeps users in line for sold-out raffles and out-of-stock items, and tells
+/// them in join order when boxes free up or the item is listed again.
+///
+/// Boxes can come free through data fixes, reorg rollbacks or a raffle reopening, so rather than
+/// hooking each of those the service sweeps for open raffles with people waiting.
+#[derive(Clone)]
+pub struct WaitlistService {
+    db_pool: PgPool,
+}
+
+impl WaitlistService {
+    pub fn new(db_pool: PgPool) -> Self {
+        Self { db_pool }
+    }
+
+    /// Notify waiting users as boxes free up and items are relisted
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(SWEEP_INTERVAL_SECONDS));
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.run_sweep().await {
+                    error!("Waitlist sweep failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Join a raffle's waitlist; raffles with boxes left can't be joined
+    pub async fn join(&self, user_id: Uuid, raffle_id: Uuid) -> Result<WaitlistPosition, AppError> {
+        let (status, boxes_sold, total_boxes, item_id, stock_quantity) =
+            sqlx::query_as::<_, (RaffleStatus, i32, i32, Uuid, i32)>(
+                "SELECT r.status, r.boxes_sold, r.total_boxes, r.item_id, i.stock_quantity \
+                 FROM raffles r JOIN items i ON i.id = r.item_id \
+                 WHERE r.id = $1",
+            )
+            .bind(raffle_id)
+            .fetch_optional(&self.db_pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Raffle not found".to_string()))?;
+
+        let reason = waitlist_reason(status, boxes_sold, total_boxes, stock_quantity)?;
+        let entry = WaitlistEntry::join(&self.db_pool, raffle_id, item_id, user_id, reason).await?;
+
+        info!("User {} joined the waitlist for raffle {} ({:?})", user_id, raffle_id, reason);
+        self.describe(entry).await
+    }
+
+    pub async fn leave(&self, user_id: Uuid, raffle_id: Uuid) -> Result<(), AppError> {
+        if !WaitlistEntry::leave(&self.db_pool, raffle_id, user_id).await? {
+            return Err(AppError::NotFound("You are not on this raffle's waitlist".to_string()));
+        }
+
+        info!("User {} left the waitlist for raffle {}", user_id, raffle_id);
+        Ok(())
+    }
+
+    /// The user's latest entry for the raffle and where it stands
+    pub async fn position(&self, user_id: Uuid, raffle_id: Uuid) -> Result<WaitlistPosition, AppError> {
+        let entry = WaitlistEntry::latest(&self.db_pool, raffle_id, user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("You are not on this raffle's waitlist".to_string()))?;
+
+        self.describe(entry).await
+    }
+
+    /// Notify users for freed boxes and relisted items
+    pub async fn run_sweep(&self) -> Result<WaitlistSweepReport, AppError> {
+        let report = WaitlistSweepReport {
+            boxes_freed: self.notify_freed_boxes().await?,
+            relisted: self.notify_relisted().await?,
+        };
+
+        if report.boxes_freed > 0 || report.relisted > 0 {
+            info!(
+                "Waitlist sweep notified {} users of freed boxes and {} of relisted items",
+                report.boxes_freed, report.relisted
+            );
+        }
+        Ok(report)
+    }
+
+    // Private helper methods
+
+    async fn describe(&self, entry: WaitlistEntry) -> Result<WaitlistPosition, AppError> {
+        let (position, waiting) = entry.position(&self.db_pool).await?;
+
+        Ok(WaitlistPosition {
+            raffle_id: entry.raffle_id,
+            reason: entry.reason,
+            status: entry.status,
+            position,
+            waiting,
+            joined_at: entry.created_at,
+            notified_raffle_id: entry.notified_raffle_id,
+            notified_at: entry.notified_at,
+        })
+    }
+
+    /// Tell the next users in line about boxes free in their raffle, one user per box. Users
+    /// told within the claim window keep their turn, so a freed box isn't announced twice.
+    async fn notify_freed_boxes(&self) -> Result<u64, AppError> {
+        let claim_window = self.claim_window_minutes().await;
+
+        let raffles = sqlx::query_as::<_, RaffleWithFreeBoxes>(
+            r#"
+            SELECT r.id, i.name AS item_title,
+                   (r.total_boxes - r.boxes_sold)::BIGINT AS free_boxes,
+                   (SELECT COUNT(*) FROM raffle_waitlist_entries n
+                    WHERE n.notified_raffle_id = r.id AND n.raffle_id = r.id AND n.status = 'notified'
+                      AND n.notified_at > NOW() - make_interval(mins => $1)) AS recently_notified
+            FROM raffles r
+            JOIN items i ON i.id = r.item_id
+            WHERE r.status = 'open' AND r.boxes_sold < r.total_boxes
+              AND (r.ends_at IS NULL OR r.ends_at > NOW())
+              AND i.stock_quantity > 0
+              AND EXISTS (SELECT 1 FROM raffle_waitlist_entries w WHERE w.raffle_id = r.id AND w.status = 'waiting')
+            "#,
+        )
+        .bind(claim_window as i32)
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let mut notified = 0;
+        for raffle in raffles {
+            let limit = users_to_notify(raffle.free_boxes, raffle.recently_notified);
+            if limit == 0 {
+                continue;
+            }
+
+            let mut tx = self.db_pool.begin().await?;
+            let user_ids = sqlx::query_scalar::<_, Uuid>(
+                r#"
+                UPDATE raffle_waitlist_entries
+                SET status = 'notified', notified_raffle_id = raffle_id, notified_at = NOW()
+                WHERE id IN (
+                    SELECT id FROM raffle_waitlist_entries
+                    WHERE raffle_id = $1 AND status = 'waiting'
+                    ORDER BY created_at, id
+                    LIMIT $2
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING user_id
+                "#,
+            )
+            .bind(raffle.id)
+            .bind(limit)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            let messages: Vec<OutboxMessage> = user_ids
+                .iter()
+                .map(|&user_id| OutboxMessage::WaitlistNotification {
+                    user_id,
+                    raffle_id: raffle.id,
+                    item_title: raffle.item_title.clone(),
+                    opening: WaitlistOpening::BoxesFreed,
+                })
+                .collect();
+            OutboxMessage::enqueue(&mut tx, &messages).await?;
+            tx.commit().await?;
+
+            notified += user_ids.len() as u64;
+        }
+
+        Ok(notified)
+    }
+
+    /// Send everyone waiting on an item to the first open raffle it was relisted in
+    async fn notify_relisted(&self) -> Result<u64, AppError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        let relisted = sqlx::query_as::<_, (Uuid, Uuid, String)>(
+            r#"
+            WITH relisted AS (
+                SELECT DISTINCT ON (w.id) w.id, r.id AS new_raffle_id
+                FROM raffle_waitlist_entries w
+                JOIN raffles r ON r.item_id = w.item_id AND r.id <> w.raffle_id
+                WHERE w.status = 'waiting' AND r.status = 'open' AND r.created_at > w.created_at
+                ORDER BY w.id, r.created_at
+                LIMIT $1
+            )
+            UPDATE raffle_waitlist_entries w
+            SET status = 'notified', notified_raffle_id = relisted.new_raffle_id, notified_at = NOW()
+            FROM relisted
+            WHERE w.id = relisted.id AND w.status = 'waiting'
+            RETURNING w.user_id, relisted.new_raffle_id, (SELECT name FROM items WHERE id = w.item_id)
+            "#,
+        )
+        .bind(RELIST_BATCH_SIZE)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let messages: Vec<OutboxMessage> = relisted
+            .iter()
+            .map(|(user_id, raffle_id, item_title)| OutboxMessage::WaitlistNotification {
+                user_id: *user_id,
+                raffle_id: *raffle_id,
+                item_title: item_title.clone(),
+                opening: WaitlistOpening::Relisted,
+            })
+            .collect();
+        OutboxMessage::enqueue(&mut tx, &messages).await?;
+        tx.commit().await?;
+
+        Ok(relisted.len() as u64)
+    }
+
+    async fn claim_window_minutes(&self) -> i64 {
+        SystemSetting::get_by_key(&self.db_pool, "waitlist_claim_window_minutes")
+            .await
+            .ok()
+            .flatten()
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_CLAIM_WINDOW_MINUTES)
+    }
+}
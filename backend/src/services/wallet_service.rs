@@ -4,107 +4,280 @@ use ethers::{
     signers::{LocalWallet, Signer},
     types::{Address, Signature, TransactionRequest, U256},
 };
+use serde::Serialize;
 use sqlx::PgPool;
 use std::str::FromStr;
 use uuid::Uuid;
-use bip39::{Mnemonic, Language, Seed};
-use hdwallet::{ExtendedPrivKey, ExtendedPubKey, KeyIndex, DefaultKeyChain};
-use secp256k1::Secp256k1;
-use sha2::{Digest, Sha256};
+use bip39::{Mnemonic, Language};
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
 
+use crate::config::AppConfig;
 use crate::error::AppError;
 use crate::models::User;
-use crate::utils::crypto::{encrypt_sensitive_data, decrypt_sensitive_data, derive_key_from_password};
+use crate::models::user_wallet::{RetiredWallet, WalletChainAddress, WalletDerivation, WalletKeySource};
+use crate::utils::crypto::derive_key_from_password;
+use crate::utils::envelope::{generate_data_key, KeyRing, SecretCipher, WrappedKey};
+use crate::utils::hd_wallet::{DerivationPath, EvmChain, MasterSeed, COIN_TYPE_ETHEREUM, EVM_CHAINS};
 
 #[cfg(test)]
 mod tests;
 
+/// Users given chain addresses per backfill run
+const BACKFILL_BATCH_SIZE: i64 = 200;
+
+/// Data keys moved onto the current master key per rewrap batch
+const REWRAP_BATCH_SIZE: i64 = 500;
+
+/// How often data keys still wrapped by a previous master key are looked for
+const REWRAP_INTERVAL_SECONDS: u64 = 600;
+
+/// Wallet service keeps users' internal wallets. With a platform master seed configured,
+/// every new wallet is a BIP-44 account under it (`m/44'/60'/account'/0/0`); otherwise a
+/// wallet gets its own mnemonic. Keys are stored encrypted with the user's wallet password.
+///
+/// With key encryption keys configured, secrets are also envelope encrypted: each user has
+/// a random data key, wrapped by a master key, that their secrets are sealed with together
+/// with the password key. Rotating the master key only rewraps the data keys, which needs
+/// neither the users' passwords nor downtime.
+///
+/// Platform wallets derive a key per chain from the chain's coin type; mnemonic, imported
+/// and legacy wallets use their one key on every chain.
 #[derive(Clone)]
 pub struct WalletService {
     pool: PgPool,
+    master_seed: Option<MasterSeed>,
+    key_ring: Option<KeyRing>,
+}
+
+/// A generated wallet, recorded on the user with `record_new_wallet` once they exist
+#[derive(Debug, Clone)]
+pub struct NewWallet {
+    pub address: String,
+    pub encrypted_private_key: String,
+    /// Only wallets with their own mnemonic have one
+    pub encrypted_mnemonic: Option<String>,
+    /// Data key the secrets are sealed with, saved for new users; `None` for legacy secrets
+    pub data_key: Option<WrappedKey>,
+    pub derivation: WalletDerivation,
+    /// Chain id, address and derivation path on each supported chain
+    pub chain_addresses: Vec<(u64, String, Option<String>)>,
+}
+
+/// A user's address on one chain
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainAddress {
+    pub chain_id: u64,
+    pub chain_name: Option<&'static str>,
+    pub address: String,
+    pub derivation_path: Option<String>,
+}
+
+/// A wallet backup: the key and where it was derived from
+#[derive(Debug, Clone, Serialize)]
+pub struct WalletExport {
+    pub address: String,
+    pub private_key: String,
+    #[serde(flatten)]
+    pub derivation: WalletDerivation,
+    pub chain_addresses: Vec<ChainAddress>,
+}
+
+/// Result of moving a wallet onto the platform master seed
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WalletUpgrade {
+    pub previous_address: String,
+    pub address: String,
+    #[serde(flatten)]
+    pub derivation: WalletDerivation,
+}
+
+/// Wallets per key source and what the chain address backfill has left to do
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WalletDerivationReport {
+    /// Seed new wallets are derived from; `None` means they get their own mnemonic
+    pub seed_id: Option<String>,
+    pub key_sources: Vec<KeySourceCount>,
+    pub platform_wallets_on_other_seeds: i64,
+    pub users_missing_chain_addresses: i64,
+    #[schema(value_type = Vec<EvmChain>)]
+    pub chains: &'static [EvmChain],
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct KeySourceCount {
+    pub key_source: WalletKeySource,
+    pub wallets: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct ChainAddressBackfill {
+    pub users: u64,
+    pub addresses: u64,
+    /// Platform wallets from another master seed, which this one cannot derive
+    pub skipped: u64,
+}
+
+/// Which master keys wrap the users' data keys, and who is still on legacy encryption
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WalletEncryptionStatus {
+    /// Master key new data keys are wrapped with; `None` when envelope encryption is off
+    pub current_key_id: Option<String>,
+    pub configured_key_ids: Vec<String>,
+    pub data_keys: Vec<MasterKeyCount>,
+    /// Wallets encrypted with the password only, sealed when their owner next unlocks them
+    pub legacy_wallets: i64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MasterKeyCount {
+    pub master_key_id: String,
+    pub wallets: i64,
+    /// Whether the key is still configured; keys that are not can no longer be unwrapped
+    pub configured: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct DataKeyRewrap {
+    pub rewrapped: u64,
+    /// Data keys still wrapped by a previous master key
+    pub remaining: i64,
+    /// Data keys wrapped by a master key that is no longer configured
+    pub unconfigured: i64,
 }
 
 impl WalletService {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self { pool, master_seed: None, key_ring: None }
+    }
+
+    /// Derive new wallets from the platform master seed
+    pub fn with_master_seed(mut self, master_seed: MasterSeed) -> Self {
+        self.master_seed = Some(master_seed);
+        self
+    }
+
+    /// Envelope encrypt wallet secrets with data keys wrapped by these master keys
+    pub fn with_key_ring(mut self, key_ring: KeyRing) -> Self {
+        self.key_ring = Some(key_ring);
+        self
     }
 
-    /// Generate a new HD wallet for a user using BIP44 standard
+    pub fn from_config(pool: PgPool, config: &AppConfig) -> Result<Self, AppError> {
+        let mut service = Self::new(pool);
+
+        match config.wallet_key_ring()? {
+            Some(key_ring) => {
+                info!("Sealing wallet secrets with data keys wrapped by master key {}", key_ring.current_id());
+                service = service.with_key_ring(key_ring);
+            }
+            None => warn!("WALLET_KEY_ENCRYPTION_KEYS is not set; wallet secrets are encrypted with the password only"),
+        }
+
+        match config.wallet_master_seed.as_deref().map(str::trim).filter(|seed| !seed.is_empty()) {
+            Some(phrase) => {
+                let passphrase = config.wallet_master_seed_passphrase.as_deref().unwrap_or("");
+                let master_seed = MasterSeed::from_mnemonic(phrase, passphrase)
+                    .map_err(|e| AppError::Internal(format!("WALLET_MASTER_SEED is not usable: {}", e)))?;
+                info!("Deriving user wallets from master seed {}", master_seed.id());
+                Ok(service.with_master_seed(master_seed))
+            }
+            None => {
+                warn!("WALLET_MASTER_SEED is not set; new wallets get their own mnemonic");
+                Ok(service)
+            }
+        }
+    }
+
+    /// Generate a wallet for a new user: the next account under the platform master seed,
+    /// or a wallet with its own mnemonic when no seed is configured
     pub async fn generate_wallet_for_user(
         &self,
         user_id: Uuid,
         password: &str,
-    ) -> Result<(String, String, String), AppError> {
-        // Generate a new mnemonic phrase (12 words)
-        let mnemonic = Mnemonic::generate_in(Language::English, 12)
-            .map_err(|e| AppError::Internal(format!("Failed to generate mnemonic: {}", e)))?;
+    ) -> Result<NewWallet, AppError> {
+        let (cipher, data_key) = self.new_cipher(user_id, password)?;
 
-        // Generate seed from mnemonic
-        let seed = Seed::new(&mnemonic, "");
-
-        // Create HD wallet using BIP44 derivation path for Ethereum
-        // m/44'/60'/0'/0/0 (first account, first address)
-        let secp = Secp256k1::new();
-        let master_key = ExtendedPrivKey::new_master(&secp, seed.as_bytes())
-            .map_err(|e| AppError::Internal(format!("Failed to create master key: {}", e)))?;
-
-        // Derive account key using BIP44 path: m/44'/60'/0'/0/0
-        let account_key = master_key
-            .derive_priv(&secp, &[
-                KeyIndex::Hardened(44),  // Purpose: BIP44
-                KeyIndex::Hardened(60),  // Coin type: Ethereum
-                KeyIndex::Hardened(0),   // Account: 0
-                KeyIndex::Normal(0),     // Change: 0 (external)
-                KeyIndex::Normal(0),     // Address index: 0
-            ])
-            .map_err(|e| AppError::Internal(format!("Failed to derive account key: {}", e)))?;
-
-        // Create LocalWallet from derived private key
-        let private_key_bytes = account_key.private_key.secret_bytes();
-        let signing_key = SigningKey::from_slice(&private_key_bytes)
-            .map_err(|e| AppError::Internal(format!("Failed to create signing key: {}", e)))?;
+        let Some(master_seed) = &self.master_seed else {
+            return generate_mnemonic_wallet(&cipher, data_key);
+        };
 
-        let wallet = LocalWallet::from(signing_key);
-        let address = format!("{:?}", wallet.address());
+        let (path, wallet, derivation) = self.next_platform_wallet(master_seed).await?;
+        let encrypted_private_key = cipher.encrypt(&hex::encode(wallet.signer().to_bytes()))?;
 
-        // Derive encryption key from user password
-        let salt = format!("wallet_salt_{}", user_id).as_bytes().to_vec();
-        let encryption_key = derive_key_from_password(password, &salt[..32]);
+        Ok(NewWallet {
+            address: format!("{:?}", wallet.address()),
+            encrypted_private_key,
+            encrypted_mnemonic: None,
+            data_key,
+            derivation,
+            chain_addresses: platform_chain_addresses(master_seed, path.account)?,
+        })
+    }
 
-        // Encrypt the private key
-        let encrypted_private_key = encrypt_sensitive_data(
-            &hex::encode(private_key_bytes),
-            &encryption_key,
-        )?;
+    /// Record how a new user's wallet was derived, its data key and its address on each chain
+    pub async fn record_new_wallet(&self, user_id: Uuid, wallet: &NewWallet) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        if let Some(data_key) = &wallet.data_key {
+            save_data_key(&mut tx, user_id, data_key).await?;
+        }
+        wallet.derivation.save(&mut tx, user_id).await?;
+        WalletChainAddress::replace_for_user(&mut tx, user_id, &wallet.chain_addresses).await?;
+        tx.commit().await?;
 
-        // Encrypt the mnemonic phrase for backup
-        let encrypted_mnemonic = encrypt_sensitive_data(
-            &mnemonic.phrase(),
-            &encryption_key,
-        )?;
+        Ok(())
+    }
 
-        Ok((address, encrypted_private_key, encrypted_mnemonic))
+    /// Allocate the next account under the master seed
+    async fn next_platform_wallet(
+        &self,
+        master_seed: &MasterSeed,
+    ) -> Result<(DerivationPath, LocalWallet, WalletDerivation), AppError> {
+        let account_index: i32 = sqlx::query_scalar("SELECT nextval('wallet_account_index_seq')::integer")
+            .fetch_one(&self.pool)
+            .await?;
+
+        let path = DerivationPath::ethereum(account_index as u32);
+        let wallet = master_seed.derive(&path)?;
+        let derivation = WalletDerivation {
+            key_source: WalletKeySource::Platform,
+            derivation_path: Some(path.to_string()),
+            account_index: Some(account_index),
+            seed_id: Some(master_seed.id()),
+        };
+
+        Ok((path, wallet, derivation))
     }
 
-    /// Get wallet for user (decrypt private key)
+    /// Get wallet for user (decrypt private key). Legacy secrets are sealed with a new data
+    /// key once the password has opened them.
     pub async fn get_user_wallet(
         &self,
         user_id: Uuid,
         password: &str,
     ) -> Result<LocalWallet, AppError> {
+        let cipher = self.cipher(user_id, password).await?;
+        let wallet = self.open_wallet(user_id, &cipher).await?;
+
+        if !cipher.is_sealed() && self.key_ring.is_some() {
+            let (sealed, data_key) = self.new_cipher(user_id, password)?;
+            match self.reseal(user_id, &cipher, &sealed, data_key.as_ref()).await {
+                Ok(()) => info!("Sealed wallet secrets of user {} with a data key", user_id),
+                Err(e) => warn!("Failed to seal wallet secrets of user {}: {}", user_id, e),
+            }
+        }
+
+        Ok(wallet)
+    }
+
+    /// Decrypt the user's current key and check it against their address
+    async fn open_wallet(&self, user_id: Uuid, cipher: &SecretCipher) -> Result<LocalWallet, AppError> {
         let user = User::find_by_id(&self.pool, user_id)
             .await?
             .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
-        // Derive decryption key
-        let salt = format!("wallet_salt_{}", user_id).as_bytes().to_vec();
-        let decryption_key = derive_key_from_password(password, &salt[..32]);
-
         // Decrypt private key
-        let private_key_hex = decrypt_sensitive_data(
-            &user.internal_wallet_private_key_encrypted,
-            &decryption_key,
-        )?;
+        let private_key_hex = cipher.decrypt(&user.internal_wallet_private_key_encrypted)?;
 
         let private_key_bytes = hex::decode(private_key_hex)
             .map_err(|e| AppError::Internal(format!("Failed to decode private key: {}", e)))?;
@@ -254,39 +427,243 @@ impl WalletService {
         format!("0x{}", hex::encode(address_bytes))
     }
 
-    /// Rotate wallet encryption (change password)
+    /// Rotate wallet encryption (change password); the mnemonic and retired keys are
+    /// re-encrypted along with the current key, under a new data key when envelope
+    /// encryption is configured
     pub async fn rotate_wallet_encryption(
         &self,
         user_id: Uuid,
         old_password: &str,
         new_password: &str,
     ) -> Result<(), AppError> {
-        // Get current wallet
-        let wallet = self.get_user_wallet(user_id, old_password).await?;
-        let private_key_bytes = wallet.signer().to_bytes();
+        let old_cipher = self.cipher(user_id, old_password).await?;
+        self.open_wallet(user_id, &old_cipher).await?;
+
+        let (new_cipher, data_key) = self.new_cipher(user_id, new_password)?;
+        self.reseal(user_id, &old_cipher, &new_cipher, data_key.as_ref()).await
+    }
 
-        // Derive new encryption key
-        let salt = format!("wallet_salt_{}", user_id).as_bytes().to_vec();
-        let new_encryption_key = derive_key_from_password(new_password, &salt[..32]);
+    /// Re-encrypt every wallet secret of the user from one cipher to another in one
+    /// transaction, saving the data key the new one seals with
+    async fn reseal(
+        &self,
+        user_id: Uuid,
+        from: &SecretCipher,
+        to: &SecretCipher,
+        data_key: Option<&WrappedKey>,
+    ) -> Result<(), AppError> {
+        let reencrypt = |encrypted: &str| from.decrypt(encrypted).and_then(|plaintext| to.encrypt(&plaintext));
+
+        let mut tx = self.pool.begin().await?;
+
+        // Locked so a concurrent reseal fails on the secrets this one wrote instead of
+        // overwriting them
+        let (private_key_encrypted, mnemonic_encrypted): (String, Option<String>) = sqlx::query_as(
+            "SELECT internal_wallet_private_key_encrypted, internal_wallet_mnemonic_encrypted \
+             FROM users WHERE id = $1 FOR UPDATE",
+        )
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
-        // Encrypt with new key
-        let new_encrypted_private_key = encrypt_sensitive_data(
-            &hex::encode(private_key_bytes),
-            &new_encryption_key,
-        )?;
+        let new_encrypted_private_key = reencrypt(&private_key_encrypted)?;
+        let new_encrypted_mnemonic = mnemonic_encrypted.as_deref().map(reencrypt).transpose()?;
 
-        // Update in database
         sqlx::query!(
-            "UPDATE users SET internal_wallet_private_key_encrypted = $1, updated_at = NOW() WHERE id = $2",
+            "UPDATE users SET internal_wallet_private_key_encrypted = $1, internal_wallet_mnemonic_encrypted = $2, updated_at = NOW() WHERE id = $3",
             new_encrypted_private_key,
+            new_encrypted_mnemonic,
             user_id
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
+        for retired_wallet in RetiredWallet::list_for_user(&self.pool, user_id).await? {
+            let private_key_encrypted = reencrypt(&retired_wallet.private_key_encrypted)?;
+            let mnemonic_encrypted = retired_wallet.mnemonic_encrypted.as_deref().map(reencrypt).transpose()?;
+            RetiredWallet::update_encryption(
+                &mut tx,
+                retired_wallet.id,
+                &private_key_encrypted,
+                mnemonic_encrypted.as_deref(),
+            )
+            .await?;
+        }
+
+        if let Some(data_key) = data_key {
+            save_data_key(&mut tx, user_id, data_key).await?;
+        }
+
+        tx.commit().await?;
+
         Ok(())
     }
 
+    /// The cipher for the user's stored secrets: sealed with their data key if they have
+    /// one, legacy otherwise
+    async fn cipher(&self, user_id: Uuid, password: &str) -> Result<SecretCipher, AppError> {
+        let password_key = wallet_encryption_key(user_id, password);
+
+        let stored: Option<(String, String)> =
+            sqlx::query_as("SELECT master_key_id, wrapped_key FROM wallet_data_keys WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        let Some((master_key_id, wrapped_key)) = stored else {
+            return Ok(SecretCipher::legacy(password_key));
+        };
+
+        let key_ring = self.key_ring.as_ref().ok_or_else(|| {
+            AppError::Internal("Wallet is envelope encrypted but WALLET_KEY_ENCRYPTION_KEYS is not set".to_string())
+        })?;
+        let data_key = key_ring.unwrap(&WrappedKey { master_key_id, wrapped_key })?;
+
+        Ok(SecretCipher::sealed(password_key, data_key))
+    }
+
+    /// A cipher with a fresh data key wrapped by the current master key, or a legacy one
+    /// when envelope encryption is not configured
+    fn new_cipher(&self, user_id: Uuid, password: &str) -> Result<(SecretCipher, Option<WrappedKey>), AppError> {
+        let password_key = wallet_encryption_key(user_id, password);
+
+        match &self.key_ring {
+            Some(key_ring) => {
+                let data_key = generate_data_key();
+                Ok((SecretCipher::sealed(password_key, data_key), Some(key_ring.wrap(&data_key)?)))
+            }
+            None => Ok((SecretCipher::legacy(password_key), None)),
+        }
+    }
+
+    /// Move data keys wrapped by a previous master key onto the current one, one batch per
+    /// call. The old master key must stay configured until nothing is left (admin).
+    pub async fn rewrap_data_keys(&self) -> Result<DataKeyRewrap, AppError> {
+        let key_ring = self
+            .key_ring
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("Envelope encryption is not enabled".to_string()))?;
+        let previous_key_ids: Vec<String> = key_ring
+            .key_ids()
+            .into_iter()
+            .filter(|id| *id != key_ring.current_id())
+            .map(String::from)
+            .collect();
+
+        let mut report = DataKeyRewrap::default();
+        let mut tx = self.pool.begin().await?;
+
+        let stale: Vec<(Uuid, String, String)> = sqlx::query_as(
+            "SELECT user_id, master_key_id, wrapped_key FROM wallet_data_keys \
+             WHERE master_key_id = ANY($1) ORDER BY created_at LIMIT $2 FOR UPDATE SKIP LOCKED",
+        )
+        .bind(&previous_key_ids)
+        .bind(REWRAP_BATCH_SIZE)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for (user_id, master_key_id, wrapped_key) in stale {
+            let rewrapped = key_ring.rewrap(&WrappedKey { master_key_id, wrapped_key })?;
+            sqlx::query(
+                "UPDATE wallet_data_keys SET wrapped_key = $1, master_key_id = $2, rotated_at = NOW() WHERE user_id = $3",
+            )
+            .bind(&rewrapped.wrapped_key)
+            .bind(&rewrapped.master_key_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+            report.rewrapped += 1;
+        }
+
+        tx.commit().await?;
+
+        let (remaining, unconfigured): (i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*) FILTER (WHERE master_key_id = ANY($1)), \
+                    COUNT(*) FILTER (WHERE master_key_id <> ALL($1) AND master_key_id <> $2) \
+             FROM wallet_data_keys",
+        )
+        .bind(&previous_key_ids)
+        .bind(key_ring.current_id())
+        .fetch_one(&self.pool)
+        .await?;
+        report.remaining = remaining;
+        report.unconfigured = unconfigured;
+
+        Ok(report)
+    }
+
+    /// Data keys per master key and the wallets still on legacy encryption (admin)
+    pub async fn encryption_status(&self) -> Result<WalletEncryptionStatus, AppError> {
+        let counts: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT master_key_id, COUNT(*) FROM wallet_data_keys GROUP BY master_key_id ORDER BY master_key_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let legacy_wallets: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM users u WHERE NOT EXISTS (SELECT 1 FROM wallet_data_keys k WHERE k.user_id = u.id)",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let key_ring = self.key_ring.as_ref();
+        Ok(WalletEncryptionStatus {
+            current_key_id: key_ring.map(|key_ring| key_ring.current_id().to_string()),
+            configured_key_ids: key_ring
+                .map(|key_ring| key_ring.key_ids().into_iter().map(String::from).collect())
+                .unwrap_or_default(),
+            data_keys: counts
+                .into_iter()
+                .map(|(master_key_id, wallets)| MasterKeyCount {
+                    configured: key_ring.is_some_and(|key_ring| key_ring.contains(&master_key_id)),
+                    master_key_id,
+                    wallets,
+                })
+                .collect(),
+            legacy_wallets,
+        })
+    }
+
+    /// Start rewrapping data keys in the background after the master key was rotated
+    pub async fn start_background_tasks(&self) {
+        if self.key_ring.is_none() {
+            return;
+        }
+
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(REWRAP_INTERVAL_SECONDS));
+            loop {
+                interval.tick().await;
+                loop {
+                    match service.rewrap_data_keys().await {
+                        Ok(report) => {
+                            if report.rewrapped > 0 {
+                                info!(
+                                    "Rewrapped {} wallet data keys, {} remaining",
+                                    report.rewrapped, report.remaining
+                                );
+                            }
+                            if report.unconfigured > 0 {
+                                warn!(
+                                    "{} wallet data keys are wrapped by master keys that are not configured",
+                                    report.unconfigured
+                                );
+                            }
+                            if report.rewrapped == 0 || report.remaining == 0 {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            error!("Wallet data key rewrap failed: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
     /// Export wallet private key (for backup purposes)
     pub async fn export_private_key(
         &self,
@@ -298,14 +675,36 @@ impl WalletService {
         Ok(hex::encode(private_key_bytes))
     }
 
-    /// Import wallet from private key
+    /// Export the wallet key with its derivation metadata and chain addresses. Platform
+    /// wallets export the key of their own account only, never the master seed.
+    pub async fn export_wallet(
+        &self,
+        user_id: Uuid,
+        password: &str,
+    ) -> Result<WalletExport, AppError> {
+        let wallet = self.get_user_wallet(user_id, password).await?;
+
+        Ok(WalletExport {
+            address: format!("{:?}", wallet.address()),
+            private_key: hex::encode(wallet.signer().to_bytes()),
+            derivation: self.wallet_derivation(user_id).await?,
+            chain_addresses: self.chain_addresses(user_id).await?,
+        })
+    }
+
+    /// Import wallet from private key, retiring the current one
     pub async fn import_wallet(
         &self,
         user_id: Uuid,
         password: &str,
         private_key_hex: &str,
     ) -> Result<String, AppError> {
+        // The replaced key stays encrypted with this password, so it must open the current one
+        self.get_user_wallet(user_id, password).await?;
+        let cipher = self.cipher(user_id, password).await?;
+
         // Validate private key
+        let private_key_hex = private_key_hex.trim().trim_start_matches("0x");
         let private_key_bytes = hex::decode(private_key_hex)
             .map_err(|e| AppError::Validation(format!("Invalid private key format: {}", e)))?;
 
@@ -315,22 +714,261 @@ impl WalletService {
         let wallet = LocalWallet::from(signing_key);
         let address = format!("{:?}", wallet.address());
 
-        // Encrypt private key
-        let salt = format!("wallet_salt_{}", user_id).as_bytes().to_vec();
-        let encryption_key = derive_key_from_password(password, &salt[..32]);
-        let encrypted_private_key = encrypt_sensitive_data(private_key_hex, &encryption_key)?;
+        let new_wallet = NewWallet {
+            address: address.clone(),
+            encrypted_private_key: cipher.encrypt(&hex::encode(private_key_bytes))?,
+            encrypted_mnemonic: None,
+            data_key: None,
+            derivation: WalletDerivation::imported(),
+            chain_addresses: single_key_chain_addresses(&address, None),
+        };
 
-        // Update in database
-        sqlx::query!(
-            "UPDATE users SET internal_wallet_address = $1, internal_wallet_private_key_encrypted = $2, updated_at = NOW() WHERE id = $3",
+        self.replace_wallet(user_id, &new_wallet, "imported").await?;
+
+        Ok(address)
+    }
+
+    /// Import a wallet from the user's own mnemonic at `path`, retiring the current one
+    pub async fn import_mnemonic_wallet(
+        &self,
+        user_id: Uuid,
+        password: &str,
+        mnemonic_phrase: &str,
+        path: DerivationPath,
+    ) -> Result<(String, WalletDerivation), AppError> {
+        self.get_user_wallet(user_id, password).await?;
+        let cipher = self.cipher(user_id, password).await?;
+
+        let wallet = MasterSeed::from_mnemonic(mnemonic_phrase, "")?.derive(&path)?;
+        let address = format!("{:?}", wallet.address());
+
+        let derivation = WalletDerivation {
+            key_source: WalletKeySource::Mnemonic,
+            derivation_path: Some(path.to_string()),
+            account_index: None,
+            seed_id: None,
+        };
+        let new_wallet = NewWallet {
+            address: address.clone(),
+            encrypted_private_key: cipher.encrypt(&hex::encode(wallet.signer().to_bytes()))?,
+            encrypted_mnemonic: Some(cipher.encrypt(mnemonic_phrase.trim())?),
+            data_key: None,
+            derivation: derivation.clone(),
+            chain_addresses: single_key_chain_addresses(&address, Some(path.to_string())),
+        };
+
+        self.replace_wallet(user_id, &new_wallet, "imported").await?;
+
+        Ok((address, derivation))
+    }
+
+    /// Move a mnemonic, imported or legacy wallet onto a fresh account under the platform
+    /// master seed. The old key is retired, not deleted, so funds on it can still be moved.
+    pub async fn upgrade_to_platform_wallet(
+        &self,
+        user_id: Uuid,
+        password: &str,
+    ) -> Result<WalletUpgrade, AppError> {
+        let master_seed = self
+            .master_seed
+            .as_ref()
+            .ok_or_else(|| AppError::Validation("Platform wallets are not enabled".to_string()))?;
+
+        let current = self.wallet_derivation(user_id).await?;
+        if current.key_source == WalletKeySource::Platform && self.seed_for(&current).is_ok() {
+            return Err(AppError::Conflict("Wallet is already derived from the platform seed".to_string()));
+        }
+
+        let previous = self.get_user_wallet(user_id, password).await?;
+        let cipher = self.cipher(user_id, password).await?;
+
+        let (path, wallet, derivation) = self.next_platform_wallet(master_seed).await?;
+        let address = format!("{:?}", wallet.address());
+        let new_wallet = NewWallet {
+            address: address.clone(),
+            encrypted_private_key: cipher.encrypt(&hex::encode(wallet.signer().to_bytes()))?,
+            encrypted_mnemonic: None,
+            data_key: None,
+            derivation: derivation.clone(),
+            chain_addresses: platform_chain_addresses(master_seed, path.account)?,
+        };
+
+        self.replace_wallet(user_id, &new_wallet, "upgraded").await?;
+
+        Ok(WalletUpgrade {
+            previous_address: format!("{:?}", previous.address()),
             address,
-            encrypted_private_key,
-            user_id
+            derivation,
+        })
+    }
+
+    /// Swap in a new wallet key, keeping the current one in `retired_wallets`
+    async fn replace_wallet(&self, user_id: Uuid, wallet: &NewWallet, reason: &str) -> Result<(), AppError> {
+        let in_use: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM users WHERE LOWER(internal_wallet_address) = LOWER($1) AND id <> $2)",
         )
-        .execute(&self.pool)
+        .bind(&wallet.address)
+        .bind(user_id)
+        .fetch_one(&self.pool)
         .await?;
+        if in_use {
+            return Err(AppError::Conflict("Wallet is already used by another account".to_string()));
+        }
 
-        Ok(address)
+        let mut tx = self.pool.begin().await?;
+
+        RetiredWallet::retire_current(&mut tx, user_id, reason).await?;
+
+        sqlx::query(
+            "UPDATE users SET internal_wallet_address = $1, internal_wallet_private_key_encrypted = $2, \
+             internal_wallet_mnemonic_encrypted = $3, updated_at = NOW() WHERE id = $4",
+        )
+        .bind(&wallet.address)
+        .bind(&wallet.encrypted_private_key)
+        .bind(&wallet.encrypted_mnemonic)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        wallet.derivation.save(&mut tx, user_id).await?;
+        WalletChainAddress::replace_for_user(&mut tx, user_id, &wallet.chain_addresses).await?;
+
+        tx.commit().await?;
+
+        info!(
+            "Replaced wallet of user {} with {} ({:?}, {})",
+            user_id, wallet.address, wallet.derivation.key_source, reason
+        );
+
+        Ok(())
+    }
+
+    /// How the user's wallet was derived
+    pub async fn wallet_derivation(&self, user_id: Uuid) -> Result<WalletDerivation, AppError> {
+        WalletDerivation::find(&self.pool, user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+    }
+
+    /// The user's address on each supported chain
+    pub async fn chain_addresses(&self, user_id: Uuid) -> Result<Vec<ChainAddress>, AppError> {
+        let addresses = WalletChainAddress::list_for_user(&self.pool, user_id).await?;
+
+        Ok(addresses
+            .into_iter()
+            .map(|address| ChainAddress {
+                chain_id: address.chain_id as u64,
+                chain_name: address.chain().map(|chain| chain.name),
+                address: address.address,
+                derivation_path: address.derivation_path,
+            })
+            .collect())
+    }
+
+    /// The user's key on a chain. Platform wallets derive a separate key on chains with
+    /// another coin type; every other wallet signs with its one key.
+    pub async fn get_user_wallet_for_chain(
+        &self,
+        user_id: Uuid,
+        password: &str,
+        chain_id: u64,
+    ) -> Result<LocalWallet, AppError> {
+        let wallet = self.get_user_wallet(user_id, password).await?;
+        let Some(chain) = EvmChain::find(chain_id) else {
+            return Ok(wallet);
+        };
+
+        let derivation = self.wallet_derivation(user_id).await?;
+        if derivation.key_source != WalletKeySource::Platform {
+            return Ok(wallet);
+        }
+
+        let path = stored_path(&derivation)?;
+        if chain.coin_type == path.coin_type {
+            return Ok(wallet);
+        }
+
+        self.seed_for(&derivation)?.derive(&chain.derivation_path(path.account))
+    }
+
+    /// The configured master seed, if it is the one a platform wallet was derived from
+    fn seed_for(&self, derivation: &WalletDerivation) -> Result<&MasterSeed, AppError> {
+        match &self.master_seed {
+            Some(master_seed) if derivation.seed_id.as_deref() == Some(master_seed.id().as_str()) => Ok(master_seed),
+            _ => Err(AppError::Internal(format!(
+                "Wallet was derived from master seed {}, which is not configured",
+                derivation.seed_id.as_deref().unwrap_or("unknown")
+            ))),
+        }
+    }
+
+    /// Wallets per key source and the chain address backfill still to do (admin)
+    pub async fn derivation_report(&self) -> Result<WalletDerivationReport, AppError> {
+        let seed_id = self.master_seed.as_ref().map(|master_seed| master_seed.id());
+
+        let key_sources = WalletDerivation::count_by_source(&self.pool)
+            .await?
+            .into_iter()
+            .map(|(key_source, wallets)| KeySourceCount { key_source, wallets })
+            .collect();
+
+        let platform_wallets_on_other_seeds: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM users WHERE wallet_key_source = 'platform' AND wallet_seed_id IS DISTINCT FROM $1",
+        )
+        .bind(&seed_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let chain_ids: Vec<i64> = EVM_CHAINS.iter().map(|chain| chain.chain_id as i64).collect();
+        let users_missing_chain_addresses: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM users u \
+             WHERE (SELECT COUNT(*) FROM wallet_chain_addresses a \
+                    WHERE a.user_id = u.id AND a.chain_id = ANY($1)) < cardinality($1)",
+        )
+        .bind(&chain_ids)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(WalletDerivationReport {
+            seed_id,
+            key_sources,
+            platform_wallets_on_other_seeds,
+            users_missing_chain_addresses,
+            chains: EVM_CHAINS,
+        })
+    }
+
+    /// Give every user an address on chains added to the registry since their wallet was
+    /// created. Runs in batches; call again until nothing is left (admin).
+    pub async fn backfill_chain_addresses(&self) -> Result<ChainAddressBackfill, AppError> {
+        let mut report = ChainAddressBackfill::default();
+
+        for user_id in WalletChainAddress::users_missing_chains(&self.pool, BACKFILL_BATCH_SIZE).await? {
+            let derivation = self.wallet_derivation(user_id).await?;
+
+            let addresses = if derivation.key_source == WalletKeySource::Platform {
+                let Ok(master_seed) = self.seed_for(&derivation) else {
+                    report.skipped += 1;
+                    continue;
+                };
+                platform_chain_addresses(master_seed, stored_path(&derivation)?.account)?
+            } else {
+                let address = self.get_user_wallet_address(user_id).await?;
+                single_key_chain_addresses(&address, derivation.derivation_path.clone())
+            };
+
+            let mut tx = self.pool.begin().await?;
+            report.addresses += WalletChainAddress::insert_missing(&mut tx, user_id, &addresses).await?;
+            tx.commit().await?;
+            report.users += 1;
+        }
+
+        info!(
+            "Backfilled {} chain addresses for {} users ({} skipped)",
+            report.addresses, report.users, report.skipped
+        );
+
+        Ok(report)
     }
 
     /// Generate HD wallet from mnemonic phrase
@@ -342,83 +980,56 @@ impl WalletService {
         account_index: u32,
         address_index: u32,
     ) -> Result<(String, String), AppError> {
-        // Parse mnemonic
-        let mnemonic = Mnemonic::parse_in(Language::English, mnemonic_phrase)
-            .map_err(|e| AppError::Validation(format!("Invalid mnemonic phrase: {}", e)))?;
-
-        // Generate seed from mnemonic
-        let seed = Seed::new(&mnemonic, "");
-
-        // Create HD wallet using BIP44 derivation path
-        let secp = Secp256k1::new();
-        let master_key = ExtendedPrivKey::new_master(&secp, seed.as_bytes())
-            .map_err(|e| AppError::Internal(format!("Failed to create master key: {}", e)))?;
-
-        // Derive account key using BIP44 path: m/44'/60'/account'/0/address_index
-        let account_key = master_key
-            .derive_priv(&secp, &[
-                KeyIndex::Hardened(44),                    // Purpose: BIP44
-                KeyIndex::Hardened(60),                    // Coin type: Ethereum
-                KeyIndex::Hardened(account_index),         // Account
-                KeyIndex::Normal(0),                       // Change: 0 (external)
-                KeyIndex::Normal(address_index),           // Address index
-            ])
-            .map_err(|e| AppError::Internal(format!("Failed to derive account key: {}", e)))?;
-
-        // Create LocalWallet from derived private key
-        let private_key_bytes = account_key.private_key.secret_bytes();
-        let signing_key = SigningKey::from_slice(&private_key_bytes)
-            .map_err(|e| AppError::Internal(format!("Failed to create signing key: {}", e)))?;
-
-        let wallet = LocalWallet::from(signing_key);
+        // BIP44 path: m/44'/60'/account'/0/address_index
+        let path = DerivationPath::new(COIN_TYPE_ETHEREUM, account_index, address_index);
+        let wallet = MasterSeed::from_mnemonic(mnemonic_phrase, "")?.derive(&path)?;
         let address = format!("{:?}", wallet.address());
 
-        // Derive encryption key from user password
-        let salt = format!("wallet_salt_{}", user_id).as_bytes().to_vec();
-        let encryption_key = derive_key_from_password(password, &salt[..32]);
-
         // Encrypt the private key
-        let encrypted_private_key = encrypt_sensitive_data(
-            &hex::encode(private_key_bytes),
-            &encryption_key,
-        )?;
+        let encrypted_private_key = self
+            .cipher(user_id, password)
+            .await?
+            .encrypt(&hex::encode(wallet.signer().to_bytes()))?;
 
         Ok((address, encrypted_private_key))
     }
 
-    /// Derive multiple addresses from HD wallet
+    /// Derive further addresses along the wallet's BIP-44 account, starting with its own
     pub async fn derive_addresses(
         &self,
         user_id: Uuid,
         password: &str,
         count: u32,
     ) -> Result<Vec<String>, AppError> {
-        let user = User::find_by_id(&self.pool, user_id)
-            .await?
-            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
-
-        // Get encrypted mnemonic (assuming it's stored in a separate field)
-        // For now, we'll derive from the existing private key
-        let wallet = self.get_user_wallet(user_id, password).await?;
-        let private_key_bytes = wallet.signer().to_bytes();
-
-        // For demonstration, we'll generate deterministic addresses
-        // In a real implementation, you'd store the mnemonic and derive properly
-        let mut addresses = Vec::new();
-        for i in 0..count {
-            let mut hasher = sha2::Sha256::new();
-            hasher.update(&private_key_bytes);
-            hasher.update(&i.to_be_bytes());
-            let derived_key = hasher.finalize();
-
-            let signing_key = SigningKey::from_slice(&derived_key)
-                .map_err(|e| AppError::Internal(format!("Failed to derive key {}: {}", i, e)))?;
-
-            let derived_wallet = LocalWallet::from(signing_key);
-            addresses.push(format!("{:?}", derived_wallet.address()));
-        }
-
-        Ok(addresses)
+        // Checks the password before any key material is touched
+        self.get_user_wallet(user_id, password).await?;
+
+        let derivation = self.wallet_derivation(user_id).await?;
+        let root = match derivation.key_source {
+            WalletKeySource::Platform => self.seed_for(&derivation)?.clone(),
+            WalletKeySource::Mnemonic => {
+                let phrase = self
+                    .get_user_mnemonic(user_id, password)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound("No mnemonic found for this wallet".to_string()))?;
+                MasterSeed::from_mnemonic(&phrase, "")?
+            }
+            WalletKeySource::Imported | WalletKeySource::Legacy => {
+                return Err(AppError::Validation(
+                    "Only HD wallets can derive further addresses".to_string(),
+                ));
+            }
+        };
+
+        let path = stored_path(&derivation)?;
+        (0..count)
+            .map(|i| {
+                root.derive_address(&DerivationPath {
+                    address_index: path.address_index + i,
+                    ..path
+                })
+            })
+            .collect()
     }
 
     /// Check wallet balance on blockchain
@@ -482,7 +1093,7 @@ impl WalletService {
         provider_url: &str,
         chain_id: u64,
     ) -> Result<TxHash, AppError> {
-        let wallet = self.get_user_wallet(user_id, password).await?;
+        let wallet = self.get_user_wallet_for_chain(user_id, password, chain_id).await?;
         
         // Create provider connection
         let provider = Provider::<Http>::try_from(provider_url)
@@ -572,12 +1183,8 @@ impl WalletService {
             .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
         if let Some(encrypted_mnemonic) = user.internal_wallet_mnemonic_encrypted {
-            // Derive decryption key
-            let salt = format!("wallet_salt_{}", user_id).as_bytes().to_vec();
-            let decryption_key = derive_key_from_password(password, &salt[..32]);
-
             // Decrypt mnemonic
-            let mnemonic = decrypt_sensitive_data(&encrypted_mnemonic, &decryption_key)?;
+            let mnemonic = self.cipher(user_id, password).await?.decrypt(&encrypted_mnemonic)?;
             Ok(Some(mnemonic))
         } else {
             Ok(None)
@@ -599,3 +1206,85 @@ impl WalletService {
     }
 }
 
+/// Key the wallet secrets of a user are encrypted with
+fn wallet_encryption_key(user_id: Uuid, password: &str) -> [u8; 32] {
+    let salt = format!("wallet_salt_{}", user_id).as_bytes().to_vec();
+    derive_key_from_password(password, &salt[..32])
+}
+
+/// Save the data key a user's secrets are sealed with, replacing any previous one
+async fn save_data_key(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: Uuid,
+    data_key: &WrappedKey,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT INTO wallet_data_keys (user_id, wrapped_key, master_key_id) VALUES ($1, $2, $3) \
+         ON CONFLICT (user_id) DO UPDATE SET wrapped_key = EXCLUDED.wrapped_key, \
+         master_key_id = EXCLUDED.master_key_id, created_at = NOW(), rotated_at = NULL",
+    )
+    .bind(user_id)
+    .bind(&data_key.wrapped_key)
+    .bind(&data_key.master_key_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// A wallet with its own 12 word mnemonic, for when no master seed is configured
+fn generate_mnemonic_wallet(cipher: &SecretCipher, data_key: Option<WrappedKey>) -> Result<NewWallet, AppError> {
+    let mnemonic = Mnemonic::generate_in(Language::English, 12)
+        .map_err(|e| AppError::Internal(format!("Failed to generate mnemonic: {}", e)))?;
+    let phrase = mnemonic.to_string();
+
+    // m/44'/60'/0'/0/0 (first account, first address)
+    let path = DerivationPath::ethereum(0);
+    let wallet = MasterSeed::from_mnemonic(&phrase, "")?.derive(&path)?;
+    let address = format!("{:?}", wallet.address());
+
+    Ok(NewWallet {
+        encrypted_private_key: cipher.encrypt(&hex::encode(wallet.signer().to_bytes()))?,
+        // Encrypt the mnemonic phrase for backup
+        encrypted_mnemonic: Some(cipher.encrypt(&phrase)?),
+        data_key,
+        derivation: WalletDerivation {
+            key_source: WalletKeySource::Mnemonic,
+            derivation_path: Some(path.to_string()),
+            account_index: None,
+            seed_id: None,
+        },
+        chain_addresses: single_key_chain_addresses(&address, Some(path.to_string())),
+        address,
+    })
+}
+
+/// A platform wallet account's address on each chain, from the chain's coin type
+fn platform_chain_addresses(
+    master_seed: &MasterSeed,
+    account: u32,
+) -> Result<Vec<(u64, String, Option<String>)>, AppError> {
+    EVM_CHAINS
+        .iter()
+        .map(|chain| {
+            let path = chain.derivation_path(account);
+            Ok((chain.chain_id, master_seed.derive_address(&path)?, Some(path.to_string())))
+        })
+        .collect()
+}
+
+/// The same address on every chain, for wallets with a single key
+fn single_key_chain_addresses(address: &str, path: Option<String>) -> Vec<(u64, String, Option<String>)> {
+    EVM_CHAINS
+        .iter()
+        .map(|chain| (chain.chain_id, address.to_string(), path.clone()))
+        .collect()
+}
+
+fn stored_path(derivation: &WalletDerivation) -> Result<DerivationPath, AppError> {
+    derivation
+        .derivation_path
+        .as_deref()
+        .ok_or_else(|| AppError::Internal("Wallet has no derivation path".to_string()))?
+        .parse()
+}
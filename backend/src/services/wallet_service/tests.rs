@@ -28,7 +28,7 @@ async fn test_hd_wallet_generation_with_bip44() {
     let user_id = Uuid::new_v4();
     let password = "test_password_123";
 
-    let (address, encrypted_key, encrypted_mnemonic) = wallet_service
+    let NewWallet { address, encrypted_private_key: encrypted_key, encrypted_mnemonic, derivation, .. } = wallet_service
         .generate_wallet_for_user(user_id, password)
         .await
         .expect("Failed to generate HD wallet");
@@ -36,6 +36,11 @@ async fn test_hd_wallet_generation_with_bip44() {
     // Verify address format
     assert!(address.starts_with("0x"));
     assert_eq!(address.len(), 42);
+
+    // Without a master seed the wallet gets its own mnemonic
+    assert_eq!(derivation.key_source, WalletKeySource::Mnemonic);
+    assert_eq!(derivation.derivation_path.as_deref(), Some("m/44'/60'/0'/0/0"));
+    let encrypted_mnemonic = encrypted_mnemonic.expect("Mnemonic wallets keep their mnemonic");
     
     // Verify encrypted data is not empty
     assert!(!encrypted_key.is_empty());
@@ -95,7 +100,7 @@ async fn test_wallet_balance_checking() {
     let password = "test_password_123";
 
     // Generate wallet
-    let (address, encrypted_key, _) = wallet_service
+    let NewWallet { address, encrypted_private_key: encrypted_key, .. } = wallet_service
         .generate_wallet_for_user(user_id, password)
         .await
         .expect("Failed to generate wallet");
@@ -140,7 +145,7 @@ async fn test_transaction_signing() {
     let password = "test_password_123";
 
     // Generate wallet
-    let (address, encrypted_key, _) = wallet_service
+    let NewWallet { address, encrypted_private_key: encrypted_key, .. } = wallet_service
         .generate_wallet_for_user(user_id, password)
         .await
         .expect("Failed to generate wallet");
@@ -188,7 +193,7 @@ async fn test_multiple_address_derivation() {
     let password = "test_password_123";
 
     // Generate wallet
-    let (address, encrypted_key, _) = wallet_service
+    let new_wallet = wallet_service
         .generate_wallet_for_user(user_id, password)
         .await
         .expect("Failed to generate wallet");
@@ -200,13 +205,25 @@ async fn test_multiple_address_derivation() {
         "testuser",
         "test@example.com",
         "hashed_password",
-        address,
-        encrypted_key
+        new_wallet.address,
+        new_wallet.encrypted_private_key
     )
     .execute(&pool)
     .await
     .expect("Failed to insert test user");
 
+    // Record the wallet's mnemonic and derivation so further addresses can be derived
+    sqlx::query("UPDATE users SET internal_wallet_mnemonic_encrypted = $1 WHERE id = $2")
+        .bind(&new_wallet.encrypted_mnemonic)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .expect("Failed to store mnemonic");
+    wallet_service
+        .record_new_wallet(user_id, &new_wallet)
+        .await
+        .expect("Failed to record wallet");
+
     // Derive multiple addresses
     let addresses = wallet_service
         .derive_addresses(user_id, password, 10)
@@ -214,6 +231,9 @@ async fn test_multiple_address_derivation() {
         .expect("Failed to derive addresses");
 
     assert_eq!(addresses.len(), 10);
+
+    // The first derived address is the wallet's own
+    assert_eq!(addresses[0], new_wallet.address);
     
     // All addresses should be valid and unique
     for (i, addr) in addresses.iter().enumerate() {
@@ -238,7 +258,7 @@ async fn test_wallet_encryption_security() {
     let wrong_password = "wrong_password";
 
     // Generate wallet
-    let (address, encrypted_key, _) = wallet_service
+    let NewWallet { address, encrypted_private_key: encrypted_key, .. } = wallet_service
         .generate_wallet_for_user(user_id, password)
         .await
         .expect("Failed to generate wallet");
@@ -276,79 +296,112 @@ async fn test_wallet_encryption_security() {
 #[tokio::test]
 async fn test_private_key_export_import_cycle() {
     let pool = setup_test_db().await;
-    let wallet_service = WalletService::new(pool);
-    let user_id1 = Uuid::new_v4();
-    let user_id2 = Uuid::new_v4();
+    let wallet_service = WalletService::new(pool.clone());
+    let user_id = Uuid::new_v4();
     let password = "test_password_123";
 
     // Generate original wallet
-    let (address1, encrypted_key1, _) = wallet_service
-        .generate_wallet_for_user(user_id1, password)
+    let NewWallet { address, encrypted_private_key: encrypted_key, .. } = wallet_service
+        .generate_wallet_for_user(user_id, password)
         .await
         .expect("Failed to generate wallet");
 
     // Create user record
     sqlx::query!(
         "INSERT INTO users (id, username, email, password_hash, internal_wallet_address, internal_wallet_private_key_encrypted) VALUES ($1, $2, $3, $4, $5, $6)",
-        user_id1,
+        user_id,
         "testuser1",
         "test1@example.com",
         "hashed_password",
-        address1,
-        encrypted_key1
+        address,
+        encrypted_key
     )
     .execute(&pool)
     .await
     .expect("Failed to insert test user");
 
-    // Export private key
-    let exported_key = wallet_service
-        .export_private_key(user_id1, password)
+    let message = b"test message";
+    let signature1 = wallet_service
+        .sign_message(user_id, password, message)
+        .await
+        .expect("Failed to sign with original wallet");
+
+    // Export carries the derivation metadata
+    let export = wallet_service
+        .export_wallet(user_id, password)
         .await
-        .expect("Failed to export private key");
+        .expect("Failed to export wallet");
+    assert_eq!(export.address, address);
 
-    // Import to new user
-    let address2 = wallet_service
-        .import_wallet(user_id2, password, &exported_key)
+    // Import a fresh key, then the exported one back
+    let other_key = hex::encode(LocalWallet::new(&mut rand::thread_rng()).signer().to_bytes());
+    let other_address = wallet_service
+        .import_wallet(user_id, password, &other_key)
         .await
         .expect("Failed to import wallet");
+    assert_ne!(other_address, address);
 
-    // Addresses should match
-    assert_eq!(address1, address2);
-
-    // Both wallets should be able to sign the same message identically
-    let message = b"test message";
-    
-    let signature1 = wallet_service
-        .sign_message(user_id1, password, message)
+    let restored_address = wallet_service
+        .import_wallet(user_id, password, &format!("0x{}", export.private_key))
         .await
-        .expect("Failed to sign with original wallet");
+        .expect("Failed to import exported key");
+    assert_eq!(restored_address, address);
 
-    // Create second user record for imported wallet
-    sqlx::query!(
-        "INSERT INTO users (id, username, email, password_hash, internal_wallet_address, internal_wallet_private_key_encrypted) VALUES ($1, $2, $3, $4, $5, $6)",
-        user_id2,
-        "testuser2",
-        "test2@example.com",
-        "hashed_password",
-        address2,
-        "dummy_encrypted_key" // Will be updated by import_wallet
-    )
-    .execute(&pool)
-    .await
-    .expect("Failed to insert second test user");
+    let derivation = wallet_service.wallet_derivation(user_id).await.expect("Failed to load derivation");
+    assert_eq!(derivation, WalletDerivation::imported());
 
+    // Replaced keys are retired, not lost
+    let retired = RetiredWallet::list_for_user(&pool, user_id).await.expect("Failed to list retired wallets");
+    assert_eq!(retired.len(), 2);
+
+    // Restored wallet signs exactly like the original
     let signature2 = wallet_service
-        .sign_message(user_id2, password, message)
+        .sign_message(user_id, password, message)
         .await
         .expect("Failed to sign with imported wallet");
 
-    // Signatures should be identical
     assert_eq!(signature1.r, signature2.r);
     assert_eq!(signature1.s, signature2.s);
     assert_eq!(signature1.v, signature2.v);
 }
 
+#[tokio::test]
+async fn test_platform_wallets_derive_from_master_seed() {
+    let pool = setup_test_db().await;
+    let master_seed = MasterSeed::from_mnemonic(
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        "",
+    )
+    .expect("Invalid master seed");
+    let wallet_service = WalletService::new(pool).with_master_seed(master_seed.clone());
+
+    let first = wallet_service
+        .generate_wallet_for_user(Uuid::new_v4(), "test_password_123")
+        .await
+        .expect("Failed to generate platform wallet");
+    let second = wallet_service
+        .generate_wallet_for_user(Uuid::new_v4(), "test_password_123")
+        .await
+        .expect("Failed to generate platform wallet");
+
+    assert_eq!(first.derivation.key_source, WalletKeySource::Platform);
+    assert_eq!(first.derivation.seed_id, Some(master_seed.id()));
+    assert!(first.encrypted_mnemonic.is_none());
+
+    // Each wallet is its own account under the seed
+    let account = first.derivation.account_index.expect("Platform wallets have an account") as u32;
+    assert_eq!(second.derivation.account_index, Some(account as i32 + 1));
+    assert_eq!(
+        first.address,
+        master_seed.derive_address(&DerivationPath::ethereum(account)).unwrap()
+    );
+
+    // One address per supported chain; mainnets share the wallet's address
+    assert_eq!(first.chain_addresses.len(), EVM_CHAINS.len());
+    let polygon = first.chain_addresses.iter().find(|(chain_id, _, _)| *chain_id == 137).unwrap();
+    assert_eq!(polygon.1, first.address);
+}
+
 #[test]
 fn test_address_validation() {
     // Valid addresses
@@ -0,0 +1,619 @@
+use crate::error::AppError;
+use crate::models::system_settings::SystemSetting;
+use crate::services::blockchain_service::BlockchainService;
+use crate::services::compliance_service::ComplianceService;
+use crate::services::payment_service::{PaymentService, RefundStatus};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use tracing::{error, info, warn};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+const DEFAULT_MAX_ATTEMPTS: i64 = 8;
+const DEFAULT_RETRY_BASE_SECONDS: i64 = 30;
+const DEFAULT_RETRY_MAX_SECONDS: i64 = 3600;
+const RETRY_BATCH_SIZE: i64 = 50;
+/// A delivery left in `processing` this long is assumed to have died with its worker
+const STALE_PROCESSING_MINUTES: i32 = 10;
+
+/// Webhook service stores incoming webhook payloads and processes them with internal retries,
+/// so a payload is not lost when the sender gives up
+#[derive(Clone)]
+pub struct WebhookService {
+    db_pool: PgPool,
+    payment_service: PaymentService,
+    blockchain_service: BlockchainService,
+    compliance_service: Option<ComplianceService>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookSource {
+    Stripe,
+    Blockchain,
+    /// Identity verification provider results
+    Identity,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[sqlx(rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookStatus {
+    Pending,
+    Processing,
+    Processed,
+    /// Processing failed and a retry is scheduled at `next_attempt_at`
+    Failed,
+    /// Retries are exhausted; only a manual redelivery processes it again
+    Dead,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub source: WebhookSource,
+    pub external_event_id: Option<String>,
+    pub event_type: String,
+    pub payload: Value,
+    pub status: WebhookStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub processed_at: Option<DateTime<Utc>>,
+    pub redelivered_by: Option<Uuid>,
+    pub redelivered_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Delivery listing entry; the payload is only returned by the detail endpoint
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDeliverySummary {
+    pub id: Uuid,
+    pub source: WebhookSource,
+    pub external_event_id: Option<String>,
+    pub event_type: String,
+    pub status: WebhookStatus,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub processed_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WebhookDeliveryQuery {
+    pub source: Option<WebhookSource>,
+    pub status: Option<WebhookStatus>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Admin-configured retry policy for stored webhooks
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct WebhookRetryPolicy {
+    pub max_attempts: i64,
+    pub base_seconds: i64,
+    pub max_seconds: i64,
+}
+
+impl WebhookService {
+    /// Create a new webhook service
+    pub fn new(
+        db_pool: PgPool,
+        payment_service: PaymentService,
+        blockchain_service: BlockchainService,
+    ) -> Self {
+        Self {
+            db_pool,
+            payment_service,
+            blockchain_service,
+            compliance_service: None,
+        }
+    }
+
+    /// Process identity verification results
+    pub fn with_compliance_service(mut self, compliance_service: ComplianceService) -> Self {
+        self.compliance_service = Some(compliance_service);
+        self
+    }
+
+    /// Start the retry loop for failed deliveries
+    pub async fn start_background_tasks(&self) {
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(30));
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = service.retry_due_deliveries().await {
+                    error!("Scheduled webhook retry failed: {}", e);
+                }
+            }
+        });
+
+        info!("Webhook service background tasks started");
+    }
+
+    /// Store an incoming webhook and process it straight away
+    ///
+    /// Once the payload is stored, processing errors are kept on the delivery and retried
+    /// internally instead of being returned to the sender. A payload the sender delivers again
+    /// is matched by its event id and only processed if it has not succeeded yet.
+    pub async fn receive(&self, source: WebhookSource, payload: Value) -> Result<WebhookDelivery, AppError> {
+        let event_type = payload["type"].as_str().unwrap_or("unknown").to_string();
+        let external_event_id = payload["id"].as_str().map(|id| id.to_string());
+
+        let delivery = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            INSERT INTO webhook_deliveries (source, external_event_id, event_type, payload)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (source, external_event_id) WHERE external_event_id IS NOT NULL
+            DO UPDATE SET updated_at = NOW()
+            RETURNING id, source as "source: WebhookSource", external_event_id, event_type, payload,
+                      status as "status: WebhookStatus", attempts, last_error, next_attempt_at,
+                      last_attempt_at, processed_at, redelivered_by, redelivered_at, created_at
+            "#,
+            source as WebhookSource,
+            external_event_id,
+            event_type,
+            payload
+        )
+        .fetch_one(&self.db_pool)
+        .await?;
+
+        info!("Stored {:?} webhook {} ({})", source, delivery.id, delivery.event_type);
+
+        match self.claim(delivery.id).await? {
+            Some(claimed) => self.process(claimed).await,
+            None => Ok(delivery),
+        }
+    }
+
+    /// Process failed deliveries whose retry is due
+    pub async fn retry_due_deliveries(&self) -> Result<usize, AppError> {
+        let deliveries = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'processing', last_attempt_at = NOW()
+            WHERE id IN (
+                SELECT id FROM webhook_deliveries
+                WHERE (status = 'failed' AND next_attempt_at <= NOW())
+                   OR (status = 'processing' AND last_attempt_at < NOW() - make_interval(mins => $2))
+                ORDER BY next_attempt_at NULLS FIRST
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, source as "source: WebhookSource", external_event_id, event_type, payload,
+                      status as "status: WebhookStatus", attempts, last_error, next_attempt_at,
+                      last_attempt_at, processed_at, redelivered_by, redelivered_at, created_at
+            "#,
+            RETRY_BATCH_SIZE,
+            STALE_PROCESSING_MINUTES
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        let count = deliveries.len();
+        for delivery in deliveries {
+            self.process(delivery).await?;
+        }
+
+        if count > 0 {
+            info!("Retried {} webhook deliveries", count);
+        }
+
+        Ok(count)
+    }
+
+    /// List stored deliveries, newest first
+    pub async fn list_deliveries(&self, query: &WebhookDeliveryQuery) -> Result<Vec<WebhookDeliverySummary>, AppError> {
+        let limit = query.limit.unwrap_or(50).clamp(1, 200);
+        let offset = query.offset.unwrap_or(0).max(0);
+
+        let deliveries = sqlx::query_as!(
+            WebhookDeliverySummary,
+            r#"
+            SELECT id, source as "source: WebhookSource", external_event_id, event_type,
+                   status as "status: WebhookStatus", attempts, last_error, next_attempt_at,
+                   processed_at, created_at
+            FROM webhook_deliveries
+            WHERE ($1::varchar IS NULL OR source = $1)
+            AND ($2::varchar IS NULL OR status = $2)
+            ORDER BY created_at DESC
+            LIMIT $3 OFFSET $4
+            "#,
+            query.source as Option<WebhookSource>,
+            query.status as Option<WebhookStatus>,
+            limit,
+            offset
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Get a stored delivery with its payload
+    pub async fn get_delivery(&self, delivery_id: Uuid) -> Result<WebhookDelivery, AppError> {
+        sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            SELECT id, source as "source: WebhookSource", external_event_id, event_type, payload,
+                   status as "status: WebhookStatus", attempts, last_error, next_attempt_at,
+                   last_attempt_at, processed_at, redelivered_by, redelivered_at, created_at
+            FROM webhook_deliveries
+            WHERE id = $1
+            "#,
+            delivery_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Webhook delivery not found".to_string()))
+    }
+
+    /// Process a stored delivery again, whatever its status, unless it is being processed now
+    pub async fn redeliver(&self, delivery_id: Uuid, admin_id: Uuid) -> Result<WebhookDelivery, AppError> {
+        let delivery = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'processing', last_attempt_at = NOW(), redelivered_by = $2, redelivered_at = NOW()
+            WHERE id = $1 AND status <> 'processing'
+            RETURNING id, source as "source: WebhookSource", external_event_id, event_type, payload,
+                      status as "status: WebhookStatus", attempts, last_error, next_attempt_at,
+                      last_attempt_at, processed_at, redelivered_by, redelivered_at, created_at
+            "#,
+            delivery_id,
+            admin_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        let delivery = match delivery {
+            Some(delivery) => delivery,
+            None => {
+                // Distinguish a missing delivery from one that is in flight
+                self.get_delivery(delivery_id).await?;
+                return Err(AppError::Conflict("Webhook delivery is being processed".to_string()));
+            }
+        };
+
+        info!("Admin {} redelivering webhook {}", admin_id, delivery_id);
+
+        self.process(delivery).await
+    }
+
+    /// Current retry policy
+    pub async fn get_retry_policy(&self) -> Result<WebhookRetryPolicy, AppError> {
+        Ok(WebhookRetryPolicy {
+            max_attempts: self.get_setting("webhook_retry_max_attempts", DEFAULT_MAX_ATTEMPTS).await?,
+            base_seconds: self.get_setting("webhook_retry_base_seconds", DEFAULT_RETRY_BASE_SECONDS).await?,
+            max_seconds: self.get_setting("webhook_retry_max_seconds", DEFAULT_RETRY_MAX_SECONDS).await?,
+        })
+    }
+
+    /// Update the retry policy
+    pub async fn update_retry_policy(&self, policy: WebhookRetryPolicy) -> Result<WebhookRetryPolicy, AppError> {
+        if policy.max_attempts < 1 || policy.base_seconds < 1 || policy.max_seconds < policy.base_seconds {
+            return Err(AppError::Validation(
+                "Retry policy needs at least one attempt and a maximum wait no shorter than the base wait".to_string(),
+            ));
+        }
+
+        for (key, value, description) in [
+            ("webhook_retry_max_attempts", policy.max_attempts, "Processing attempts before a stored webhook is marked dead"),
+            ("webhook_retry_base_seconds", policy.base_seconds, "Wait before the first webhook retry; doubles with each further attempt"),
+            ("webhook_retry_max_seconds", policy.max_seconds, "Longest wait between webhook retries"),
+        ] {
+            SystemSetting::upsert(
+                &self.db_pool,
+                key.to_string(),
+                serde_json::Value::Number(serde_json::Number::from(value)),
+                Some(description.to_string()),
+                false,
+            ).await?;
+        }
+
+        Ok(policy)
+    }
+
+    // Private helper methods
+
+    /// Mark a pending or failed delivery as processing; None when it needs no processing
+    async fn claim(&self, delivery_id: Uuid) -> Result<Option<WebhookDelivery>, AppError> {
+        let delivery = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'processing', last_attempt_at = NOW()
+            WHERE id = $1 AND status IN ('pending', 'failed')
+            RETURNING id, source as "source: WebhookSource", external_event_id, event_type, payload,
+                      status as "status: WebhookStatus", attempts, last_error, next_attempt_at,
+                      last_attempt_at, processed_at, redelivered_by, redelivered_at, created_at
+            "#,
+            delivery_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+
+        Ok(delivery)
+    }
+
+    /// Run a claimed delivery and record the outcome
+    async fn process(&self, delivery: WebhookDelivery) -> Result<WebhookDelivery, AppError> {
+        let result = match delivery.source {
+            WebhookSource::Stripe => self.dispatch_stripe(&delivery.payload).await,
+            WebhookSource::Blockchain => self.dispatch_blockchain(&delivery.payload).await,
+            WebhookSource::Identity => match &self.compliance_service {
+                Some(compliance_service) => compliance_service.handle_provider_event(&delivery.payload).await,
+                None => Err(AppError::Internal("Identity verification is not configured".to_string())),
+            },
+        };
+
+        let delivery = match result {
+            Ok(()) => {
+                sqlx::query_as!(
+                    WebhookDelivery,
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET status = 'processed', attempts = attempts + 1, last_error = NULL,
+                        next_attempt_at = NULL, processed_at = NOW()
+                    WHERE id = $1
+                    RETURNING id, source as "source: WebhookSource", external_event_id, event_type, payload,
+                              status as "status: WebhookStatus", attempts, last_error, next_attempt_at,
+                              last_attempt_at, processed_at, redelivered_by, redelivered_at, created_at
+                    "#,
+                    delivery.id
+                )
+                .fetch_one(&self.db_pool)
+                .await?
+            }
+            Err(e) => {
+                let policy = self.get_retry_policy().await?;
+                let attempts = delivery.attempts as i64 + 1;
+                let next_attempt_at = next_attempt_at(Utc::now(), attempts, &policy);
+                let status = if next_attempt_at.is_some() { WebhookStatus::Failed } else { WebhookStatus::Dead };
+
+                match status {
+                    WebhookStatus::Dead => error!(
+                        "Webhook {} ({}) failed after {} attempts, giving up: {}",
+                        delivery.id, delivery.event_type, attempts, e
+                    ),
+                    _ => warn!(
+                        "Webhook {} ({}) failed on attempt {}, retrying: {}",
+                        delivery.id, delivery.event_type, attempts, e
+                    ),
+                }
+
+                sqlx::query_as!(
+                    WebhookDelivery,
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET status = $2, attempts = attempts + 1, last_error = $3, next_attempt_at = $4
+                    WHERE id = $1
+                    RETURNING id, source as "source: WebhookSource", external_event_id, event_type, payload,
+                              status as "status: WebhookStatus", attempts, last_error, next_attempt_at,
+                              last_attempt_at, processed_at, redelivered_by, redelivered_at, created_at
+                    "#,
+                    delivery.id,
+                    status as WebhookStatus,
+                    e.to_string(),
+                    next_attempt_at
+                )
+                .fetch_one(&self.db_pool)
+                .await?
+            }
+        };
+
+        Ok(delivery)
+    }
+
+    async fn dispatch_stripe(&self, payload: &Value) -> Result<(), AppError> {
+        let event_type = payload["type"]
+            .as_str()
+            .ok_or_else(|| AppError::Validation("Missing event type".to_string()))?;
+
+        match event_type {
+            "payment_intent.succeeded" => {
+                let payment_intent_id = payload["data"]["object"]["id"]
+                    .as_str()
+                    .ok_or_else(|| AppError::Validation("Missing payment intent ID".to_string()))?;
+
+                let amount = payload["data"]["object"]["amount"]
+                    .as_u64()
+                    .ok_or_else(|| AppError::Validation("Missing amount".to_string()))?;
+
+                let payment_method_id = payload["data"]["object"]["payment_method"].as_str();
+
+                info!("Processing successful payment: {} for amount: {}", payment_intent_id, amount);
+
+                self.payment_service
+                    .process_successful_payment(payment_intent_id, amount, payment_method_id)
+                    .await?;
+            }
+            "payment_intent.payment_failed" => {
+                let payment_intent_id = payload["data"]["object"]["id"]
+                    .as_str()
+                    .ok_or_else(|| AppError::Validation("Missing payment intent ID".to_string()))?;
+
+                let failure_reason = payload["data"]["object"]["last_payment_error"]["message"]
+                    .as_str()
+                    .unwrap_or("Unknown error");
+
+                error!("Payment failed: {} - {}", payment_intent_id, failure_reason);
+
+                self.payment_service
+                    .process_failed_payment(payment_intent_id, failure_reason)
+                    .await?;
+            }
+            "invoice.payment_succeeded" => {
+                let subscription_id = payload["data"]["object"]["subscription"]
+                    .as_str()
+                    .ok_or_else(|| AppError::Validation("Missing subscription ID".to_string()))?;
+
+                self.payment_service
+                    .process_subscription_payment_success(subscription_id)
+                    .await?;
+            }
+            "invoice.payment_failed" => {
+                let subscription_id = payload["data"]["object"]["subscription"]
+                    .as_str()
+                    .ok_or_else(|| AppError::Validation("Missing subscription ID".to_string()))?;
+
+                error!("Subscription payment failed: {}", subscription_id);
+
+                self.payment_service
+                    .process_subscription_payment_failure(subscription_id)
+                    .await?;
+            }
+            "charge.refund.updated" | "refund.updated" | "refund.failed" => {
+                let refund_id = payload["data"]["object"]["id"]
+                    .as_str()
+                    .ok_or_else(|| AppError::Validation("Missing refund ID".to_string()))?;
+
+                let status = payload["data"]["object"]["status"]
+                    .as_str()
+                    .map(RefundStatus::from_stripe)
+                    .unwrap_or(RefundStatus::Pending);
+
+                let failure_reason = payload["data"]["object"]["failure_reason"]
+                    .as_str()
+                    .map(|r| r.to_string());
+
+                self.payment_service
+                    .process_refund_update(refund_id, status, failure_reason)
+                    .await?;
+            }
+            "customer.subscription.deleted" => {
+                let subscription_id = payload["data"]["object"]["id"]
+                    .as_str()
+                    .ok_or_else(|| AppError::Validation("Missing subscription ID".to_string()))?;
+
+                info!("Processing subscription cancellation: {}", subscription_id);
+
+                self.payment_service
+                    .process_subscription_cancellation(subscription_id)
+                    .await?;
+            }
+            _ => {
+                warn!("Unhandled Stripe webhook event: {}", event_type);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn dispatch_blockchain(&self, payload: &Value) -> Result<(), AppError> {
+        match payload["type"].as_str().unwrap_or("unknown") {
+            "ADDRESS_ACTIVITY" => {
+                if let Some(activities) = payload["activity"].as_array() {
+                    for activity in activities {
+                        self.blockchain_service.process_address_activity(activity).await?;
+                    }
+                }
+            }
+            "MINED_TRANSACTION" => {
+                let tx_hash = payload["data"]["transaction"]["hash"]
+                    .as_str()
+                    .ok_or_else(|| AppError::Validation("Missing transaction hash".to_string()))?;
+
+                info!("Processing mined transaction: {}", tx_hash);
+
+                self.blockchain_service.process_mined_transaction(tx_hash).await?;
+            }
+            _ => {
+                // Handle direct contract events
+                if let Some(logs) = payload["logs"].as_array() {
+                    for log in logs {
+                        self.dispatch_contract_event(log).await?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn dispatch_contract_event(&self, log: &Value) -> Result<(), AppError> {
+        let topics = log["topics"]
+            .as_array()
+            .ok_or_else(|| AppError::Validation("Missing topics".to_string()))?;
+
+        if let Some(event_signature) = topics.get(0).and_then(|t| t.as_str()) {
+            match event_signature {
+                // BoxPurchased event signature
+                "0x..." => {
+                    self.blockchain_service.handle_box_purchased_event(log).await?;
+                }
+                // WinnerSelected event signature
+                "0x..." => {
+                    self.blockchain_service.handle_winner_selected_event(log).await?;
+                }
+                // RaffleFull event signature
+                "0x..." => {
+                    self.blockchain_service.handle_raffle_full_event(log).await?;
+                }
+                _ => {
+                    warn!("Unhandled contract event: {}", event_signature);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get_setting(&self, key: &str, default: i64) -> Result<i64, AppError> {
+        let setting = SystemSetting::get_by_key(&self.db_pool, key).await?;
+        Ok(setting
+            .and_then(|s| s.get_int_value())
+            .filter(|v| *v > 0)
+            .unwrap_or(default))
+    }
+}
+
+/// Wait before retry number `attempts` (1 for the first retry), doubling up to the maximum
+pub fn retry_delay_seconds(attempts: i64, base_seconds: i64, max_seconds: i64) -> i64 {
+    let exponent = (attempts - 1).clamp(0, 32) as u32;
+    base_seconds.saturating_mul(2i64.saturating_pow(exponent)).min(max_seconds)
+}
+
+/// When to retry after `attempts` failed attempts, or None once the policy is exhausted
+fn next_attempt_at(now: DateTime<Utc>, attempts: i64, policy: &WebhookRetryPolicy) -> Option<DateTime<Utc>> {
+    if attempts >= policy.max_attempts {
+        return None;
+    }
+    Some(now + Duration::seconds(retry_delay_seconds(attempts, policy.base_seconds, policy.max_seconds)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_max() {
+        assert_eq!(retry_delay_seconds(1, 30, 3600), 30);
+        assert_eq!(retry_delay_seconds(2, 30, 3600), 60);
+        assert_eq!(retry_delay_seconds(4, 30, 3600), 240);
+        assert_eq!(retry_delay_seconds(10, 30, 3600), 3600);
+        assert_eq!(retry_delay_seconds(1000, 30, 3600), 3600);
+    }
+
+    #[test]
+    fn test_next_attempt_stops_at_max_attempts() {
+        let policy = WebhookRetryPolicy { max_attempts: 3, base_seconds: 30, max_seconds: 3600 };
+        let now = Utc::now();
+
+        assert_eq!(next_attempt_at(now, 1, &policy), Some(now + Duration::seconds(30)));
+        assert_eq!(next_attempt_at(now, 2, &policy), Some(now + Duration::seconds(60)));
+        assert_eq!(next_attempt_at(now, 3, &policy), None);
+    }
+}
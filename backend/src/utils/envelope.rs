@@ -0,0 +1,236 @@
+use crate::error::AppError;
+use crate::utils::crypto::{decrypt_sensitive_data, encrypt_sensitive_data};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::fmt;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Marks a wallet secret sealed with a data key; unmarked secrets are legacy ones encrypted
+/// with the password key alone
+const ENVELOPE_PREFIX: &str = "v2:";
+
+/// Master keys that wrap the per-user wallet data keys, by id. New data keys are wrapped
+/// with the current one; the others stay only to unwrap keys not yet moved onto it.
+#[derive(Clone)]
+pub struct KeyRing {
+    current: String,
+    keys: BTreeMap<String, [u8; 32]>,
+}
+
+/// A data key encrypted with a master key, and the id of that key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrappedKey {
+    pub master_key_id: String,
+    pub wrapped_key: String,
+}
+
+impl fmt::Debug for KeyRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyRing")
+            .field("current", &self.current)
+            .field("key_ids", &self.key_ids())
+            .finish()
+    }
+}
+
+impl KeyRing {
+    /// Parse `id:base64-key` pairs separated by commas. `current` picks the key new data
+    /// keys are wrapped with and defaults to the first one listed.
+    pub fn parse(keys: &str, current: Option<&str>) -> Result<Self, AppError> {
+        let mut parsed = BTreeMap::new();
+        let mut first = None;
+
+        for entry in keys.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (id, key) = entry
+                .split_once(':')
+                .ok_or_else(|| AppError::Internal(format!("Key encryption key '{}' is not id:key", entry)))?;
+            let id = id.trim();
+            if id.is_empty() || id.len() > 64 {
+                return Err(AppError::Internal("Key encryption key ids must be 1 to 64 characters".to_string()));
+            }
+
+            let bytes = base64::decode(key.trim())
+                .map_err(|e| AppError::Internal(format!("Key encryption key {} is not base64: {}", id, e)))?;
+            let key: [u8; 32] = bytes
+                .try_into()
+                .map_err(|_| AppError::Internal(format!("Key encryption key {} must be 32 bytes", id)))?;
+
+            if parsed.insert(id.to_string(), key).is_some() {
+                return Err(AppError::Internal(format!("Key encryption key {} is listed twice", id)));
+            }
+            first.get_or_insert_with(|| id.to_string());
+        }
+
+        let current = match current.map(str::trim).filter(|id| !id.is_empty()) {
+            Some(id) if parsed.contains_key(id) => id.to_string(),
+            Some(id) => return Err(AppError::Internal(format!("Current key encryption key {} is not listed", id))),
+            None => first.ok_or_else(|| AppError::Internal("No key encryption keys given".to_string()))?,
+        };
+
+        Ok(Self { current, keys: parsed })
+    }
+
+    /// Id of the master key new data keys are wrapped with
+    pub fn current_id(&self) -> &str {
+        &self.current
+    }
+
+    pub fn key_ids(&self) -> Vec<&str> {
+        self.keys.keys().map(String::as_str).collect()
+    }
+
+    pub fn contains(&self, master_key_id: &str) -> bool {
+        self.keys.contains_key(master_key_id)
+    }
+
+    /// Wrap a data key with the current master key
+    pub fn wrap(&self, data_key: &[u8; 32]) -> Result<WrappedKey, AppError> {
+        Ok(WrappedKey {
+            master_key_id: self.current.clone(),
+            wrapped_key: encrypt_sensitive_data(&hex::encode(data_key), &self.keys[&self.current])?,
+        })
+    }
+
+    pub fn unwrap(&self, wrapped: &WrappedKey) -> Result<[u8; 32], AppError> {
+        let master_key = self.keys.get(&wrapped.master_key_id).ok_or_else(|| {
+            AppError::Internal(format!("Key encryption key {} is not configured", wrapped.master_key_id))
+        })?;
+
+        hex::decode(decrypt_sensitive_data(&wrapped.wrapped_key, master_key)?)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| AppError::Internal("Unwrapped data key is malformed".to_string()))
+    }
+
+    /// Move a data key onto the current master key; the data key itself is unchanged, so
+    /// nothing it sealed has to be re-encrypted
+    pub fn rewrap(&self, wrapped: &WrappedKey) -> Result<WrappedKey, AppError> {
+        self.wrap(&self.unwrap(wrapped)?)
+    }
+}
+
+/// A fresh random data key for one user's wallet secrets
+pub fn generate_data_key() -> [u8; 32] {
+    rand::thread_rng().gen()
+}
+
+/// Encrypts and decrypts one user's wallet secrets. Sealed secrets need both the data key,
+/// which only the server can unwrap, and the key derived from the user's password.
+#[derive(Clone)]
+pub struct SecretCipher {
+    password_key: [u8; 32],
+    data_key: Option<[u8; 32]>,
+}
+
+impl SecretCipher {
+    /// Secrets encrypted with the password key alone, from before envelope encryption
+    pub fn legacy(password_key: [u8; 32]) -> Self {
+        Self { password_key, data_key: None }
+    }
+
+    pub fn sealed(password_key: [u8; 32], data_key: [u8; 32]) -> Self {
+        Self { password_key, data_key: Some(data_key) }
+    }
+
+    pub fn is_sealed(&self) -> bool {
+        self.data_key.is_some()
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, AppError> {
+        match &self.data_key {
+            Some(data_key) => Ok(format!(
+                "{}{}",
+                ENVELOPE_PREFIX,
+                encrypt_sensitive_data(plaintext, &secret_key(data_key, &self.password_key))?
+            )),
+            None => encrypt_sensitive_data(plaintext, &self.password_key),
+        }
+    }
+
+    pub fn decrypt(&self, ciphertext: &str) -> Result<String, AppError> {
+        match (ciphertext.strip_prefix(ENVELOPE_PREFIX), &self.data_key) {
+            (Some(sealed), Some(data_key)) => {
+                decrypt_sensitive_data(sealed, &secret_key(data_key, &self.password_key))
+            }
+            (None, _) => decrypt_sensitive_data(ciphertext, &self.password_key),
+            (Some(_), None) => Err(AppError::Internal("Wallet secret is sealed but has no data key".to_string())),
+        }
+    }
+}
+
+/// Key a sealed secret is encrypted with, bound to both the data key and the password
+fn secret_key(data_key: &[u8; 32], password_key: &[u8; 32]) -> [u8; 32] {
+    let mut mac = HmacSha256::new_from_slice(data_key).expect("HMAC accepts any key length");
+    mac.update(password_key);
+    mac.finalize().into_bytes().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(byte: u8) -> String {
+        base64::encode([byte; 32])
+    }
+
+    #[test]
+    fn test_key_ring_parse() {
+        let ring = KeyRing::parse(&format!("2024-01:{}, 2025-06:{}", encoded(1), encoded(2)), None).unwrap();
+        assert_eq!(ring.current_id(), "2024-01");
+        assert_eq!(ring.key_ids(), vec!["2024-01", "2025-06"]);
+
+        let ring = KeyRing::parse(&format!("a:{},b:{}", encoded(1), encoded(2)), Some("b")).unwrap();
+        assert_eq!(ring.current_id(), "b");
+
+        assert!(KeyRing::parse("", None).is_err());
+        assert!(KeyRing::parse(&encoded(1), None).is_err());
+        assert!(KeyRing::parse("a:c2hvcnQ=", None).is_err());
+        assert!(KeyRing::parse(&format!("a:{},a:{}", encoded(1), encoded(2)), None).is_err());
+        assert!(KeyRing::parse(&format!("a:{}", encoded(1)), Some("b")).is_err());
+    }
+
+    #[test]
+    fn test_rewrap_keeps_data_key() {
+        let old = KeyRing::parse(&format!("old:{}", encoded(1)), None).unwrap();
+        let both = KeyRing::parse(&format!("old:{},new:{}", encoded(1), encoded(2)), Some("new")).unwrap();
+        let data_key = generate_data_key();
+
+        let wrapped = old.wrap(&data_key).unwrap();
+        assert_eq!(wrapped.master_key_id, "old");
+
+        let rewrapped = both.rewrap(&wrapped).unwrap();
+        assert_eq!(rewrapped.master_key_id, "new");
+        assert_eq!(both.unwrap(&rewrapped).unwrap(), data_key);
+
+        // A ring that dropped the old key can no longer open what it wrapped
+        let new_only = KeyRing::parse(&format!("new:{}", encoded(2)), None).unwrap();
+        assert!(new_only.unwrap(&wrapped).is_err());
+        assert_eq!(new_only.unwrap(&rewrapped).unwrap(), data_key);
+    }
+
+    #[test]
+    fn test_sealed_secret_needs_data_key_and_password() {
+        let data_key = generate_data_key();
+        let cipher = SecretCipher::sealed([7; 32], data_key);
+
+        let sealed = cipher.encrypt("secret").unwrap();
+        assert!(sealed.starts_with(ENVELOPE_PREFIX));
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), "secret");
+
+        assert!(SecretCipher::sealed([8; 32], data_key).decrypt(&sealed).is_err());
+        assert!(SecretCipher::sealed([7; 32], generate_data_key()).decrypt(&sealed).is_err());
+        assert!(SecretCipher::legacy([7; 32]).decrypt(&sealed).is_err());
+    }
+
+    #[test]
+    fn test_sealed_cipher_reads_legacy_secrets() {
+        let legacy = SecretCipher::legacy([7; 32]).encrypt("secret").unwrap();
+        assert!(!legacy.starts_with(ENVELOPE_PREFIX));
+
+        let cipher = SecretCipher::sealed([7; 32], generate_data_key());
+        assert_eq!(cipher.decrypt(&legacy).unwrap(), "secret");
+    }
+}
@@ -0,0 +1,271 @@
+use crate::error::AppError;
+use bip39::{Language, Mnemonic};
+use ethers::core::k256::ecdsa::SigningKey;
+use ethers::signers::{LocalWallet, Signer};
+use hdwallet::{ExtendedPrivKey, ExtendedPubKey, KeyIndex};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use std::fmt;
+use std::str::FromStr;
+
+/// BIP-44 purpose level
+const BIP44_PURPOSE: u32 = 44;
+
+/// SLIP-44 coin type of Ethereum, shared by EVM mainnets
+pub const COIN_TYPE_ETHEREUM: u32 = 60;
+
+/// SLIP-44 coin type reserved for every testnet
+pub const COIN_TYPE_TESTNET: u32 = 1;
+
+/// Indices at or above this are hardened
+const HARDENED_OFFSET: u32 = 1 << 31;
+
+/// An EVM chain user wallets hold addresses on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct EvmChain {
+    pub chain_id: u64,
+    #[schema(value_type = String)]
+    pub name: &'static str,
+    pub coin_type: u32,
+    pub is_testnet: bool,
+}
+
+/// Chains user wallets are derived for. Mainnets share Ethereum's coin type, so a user has
+/// the same address on each of them, as in wallets like MetaMask; testnets use the testnet
+/// coin type so a testnet key is never also a mainnet key.
+pub const EVM_CHAINS: &[EvmChain] = &[
+    EvmChain { chain_id: 1, name: "Ethereum", coin_type: COIN_TYPE_ETHEREUM, is_testnet: false },
+    EvmChain { chain_id: 137, name: "Polygon", coin_type: COIN_TYPE_ETHEREUM, is_testnet: false },
+    EvmChain { chain_id: 42161, name: "Arbitrum One", coin_type: COIN_TYPE_ETHEREUM, is_testnet: false },
+    EvmChain { chain_id: 10, name: "Optimism", coin_type: COIN_TYPE_ETHEREUM, is_testnet: false },
+    EvmChain { chain_id: 8453, name: "Base", coin_type: COIN_TYPE_ETHEREUM, is_testnet: false },
+    EvmChain { chain_id: 11155111, name: "Sepolia", coin_type: COIN_TYPE_TESTNET, is_testnet: true },
+    EvmChain { chain_id: 80002, name: "Polygon Amoy", coin_type: COIN_TYPE_TESTNET, is_testnet: true },
+];
+
+impl EvmChain {
+    pub fn find(chain_id: u64) -> Option<&'static EvmChain> {
+        EVM_CHAINS.iter().find(|chain| chain.chain_id == chain_id)
+    }
+
+    /// The chain's path for a wallet account
+    pub fn derivation_path(&self, account: u32) -> DerivationPath {
+        DerivationPath::new(self.coin_type, account, 0)
+    }
+}
+
+/// A BIP-44 path, `m/44'/coin_type'/account'/change/address_index`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DerivationPath {
+    pub coin_type: u32,
+    pub account: u32,
+    pub change: u32,
+    pub address_index: u32,
+}
+
+impl DerivationPath {
+    /// External chain path for an account and address
+    pub fn new(coin_type: u32, account: u32, address_index: u32) -> Self {
+        Self { coin_type, account, change: 0, address_index }
+    }
+
+    /// Ethereum path for an account's first address, the default for user wallets
+    pub fn ethereum(account: u32) -> Self {
+        Self::new(COIN_TYPE_ETHEREUM, account, 0)
+    }
+
+    fn key_indices(&self) -> Result<[KeyIndex; 5], AppError> {
+        let hardened = |index: u32| {
+            KeyIndex::hardened_from_normalize_index(index)
+                .map_err(|e| AppError::Validation(format!("Invalid derivation index {}: {:?}", index, e)))
+        };
+        let normal = |index: u32| {
+            KeyIndex::from_index(index)
+                .map_err(|e| AppError::Validation(format!("Invalid derivation index {}: {:?}", index, e)))
+        };
+
+        Ok([
+            hardened(BIP44_PURPOSE)?,
+            hardened(self.coin_type)?,
+            hardened(self.account)?,
+            normal(self.change)?,
+            normal(self.address_index)?,
+        ])
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "m/{}'/{}'/{}'/{}/{}",
+            BIP44_PURPOSE, self.coin_type, self.account, self.change, self.address_index
+        )
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = AppError;
+
+    /// Parse a BIP-44 path; hardened levels may be marked with `'`, `h` or `H`
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        let invalid = || AppError::Validation(format!("Invalid BIP-44 derivation path: {}", path));
+
+        let segments: Vec<&str> = path.trim().split('/').collect();
+        if segments.len() != 6 || segments[0] != "m" {
+            return Err(invalid());
+        }
+
+        let mut indices = [0u32; 5];
+        for (i, segment) in segments[1..].iter().enumerate() {
+            let (digits, is_hardened) = match segment.strip_suffix(['\'', 'h', 'H']) {
+                Some(digits) => (digits, true),
+                None => (*segment, false),
+            };
+            // Purpose, coin type and account are hardened; change and address index are not
+            if is_hardened != (i < 3) {
+                return Err(invalid());
+            }
+            let index: u32 = digits.parse().map_err(|_| invalid())?;
+            if index >= HARDENED_OFFSET {
+                return Err(invalid());
+            }
+            indices[i] = index;
+        }
+
+        if indices[0] != BIP44_PURPOSE || indices[3] > 1 {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            coin_type: indices[1],
+            account: indices[2],
+            change: indices[3],
+            address_index: indices[4],
+        })
+    }
+}
+
+/// Root of an HD wallet, from a BIP-39 mnemonic
+#[derive(Clone)]
+pub struct MasterSeed {
+    root: ExtendedPrivKey,
+}
+
+impl fmt::Debug for MasterSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MasterSeed").field("id", &self.id()).finish()
+    }
+}
+
+impl MasterSeed {
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<Self, AppError> {
+        let mnemonic = Mnemonic::parse_in(Language::English, phrase.trim())
+            .map_err(|e| AppError::Validation(format!("Invalid mnemonic phrase: {}", e)))?;
+
+        Self::from_seed(&mnemonic.to_seed(passphrase))
+    }
+
+    pub fn from_seed(seed: &[u8]) -> Result<Self, AppError> {
+        let root = ExtendedPrivKey::with_seed(seed)
+            .map_err(|e| AppError::Internal(format!("Failed to create master key: {:?}", e)))?;
+
+        Ok(Self { root })
+    }
+
+    /// Short identifier of the seed, stored with derived wallets so a wallet derived from
+    /// another seed is never re-derived from this one. Safe to log.
+    pub fn id(&self) -> String {
+        let public_key = ExtendedPubKey::from_private_key(&self.root).public_key.serialize();
+        hex::encode(&Sha256::digest(public_key)[..4])
+    }
+
+    /// The wallet at `path`
+    pub fn derive(&self, path: &DerivationPath) -> Result<LocalWallet, AppError> {
+        let mut key = self.root.clone();
+        for index in path.key_indices()? {
+            key = key
+                .derive_private_key(index)
+                .map_err(|e| AppError::Internal(format!("Failed to derive {}: {:?}", path, e)))?;
+        }
+
+        let signing_key = SigningKey::from_slice(&key.private_key.secret_bytes())
+            .map_err(|e| AppError::Internal(format!("Failed to create signing key: {}", e)))?;
+
+        Ok(LocalWallet::from(signing_key))
+    }
+
+    /// Checksummed address of the wallet at `path`
+    pub fn derive_address(&self, path: &DerivationPath) -> Result<String, AppError> {
+        Ok(format!("{:?}", self.derive(path)?.address()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_derivation_path_round_trip() {
+        let path: DerivationPath = "m/44'/60'/7'/0/3".parse().unwrap();
+        assert_eq!(path, DerivationPath { coin_type: 60, account: 7, change: 0, address_index: 3 });
+        assert_eq!(path.to_string(), "m/44'/60'/7'/0/3");
+        assert_eq!("m/44h/1H/0'/1/0".parse::<DerivationPath>().unwrap().to_string(), "m/44'/1'/0'/1/0");
+    }
+
+    #[test]
+    fn test_derivation_path_rejects_non_bip44_paths() {
+        for path in [
+            "m/44'/60'/0'/0",
+            "m/49'/60'/0'/0/0",
+            "m/44/60'/0'/0/0",
+            "m/44'/60'/0'/0'/0",
+            "m/44'/60'/0'/2/0",
+            "m/44'/60'/2147483648'/0/0",
+            "44'/60'/0'/0/0",
+        ] {
+            assert!(path.parse::<DerivationPath>().is_err(), "{} should be rejected", path);
+        }
+    }
+
+    #[test]
+    fn test_derives_standard_ethereum_address() {
+        let seed = MasterSeed::from_mnemonic(TEST_MNEMONIC, "").unwrap();
+        // Well-known first address of the BIP-39 test mnemonic
+        assert_eq!(
+            seed.derive_address(&DerivationPath::ethereum(0)).unwrap(),
+            "0x9858EfFD232B4033E47d90003D41EC34EcaEda94"
+        );
+        assert_ne!(
+            seed.derive_address(&DerivationPath::ethereum(1)).unwrap(),
+            seed.derive_address(&DerivationPath::ethereum(0)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_mainnets_share_an_address_and_testnets_do_not() {
+        let seed = MasterSeed::from_mnemonic(TEST_MNEMONIC, "").unwrap();
+        let address = |chain_id| {
+            let chain = EvmChain::find(chain_id).unwrap();
+            seed.derive_address(&chain.derivation_path(3)).unwrap()
+        };
+
+        assert_eq!(address(1), address(137));
+        assert_eq!(address(1), address(8453));
+        assert_ne!(address(1), address(11155111));
+        assert_eq!(address(11155111), address(80002));
+    }
+
+    #[test]
+    fn test_seed_id_depends_on_passphrase() {
+        let plain = MasterSeed::from_mnemonic(TEST_MNEMONIC, "").unwrap();
+        let protected = MasterSeed::from_mnemonic(TEST_MNEMONIC, "platform").unwrap();
+        assert_eq!(plain.id().len(), 8);
+        assert_eq!(plain.id(), MasterSeed::from_mnemonic(TEST_MNEMONIC, "").unwrap().id());
+        assert_ne!(plain.id(), protected.id());
+    }
+}
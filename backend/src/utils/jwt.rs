@@ -22,6 +22,10 @@ pub struct Claims {
     pub iat: i64,          // Issued at
     pub jti: String,       // JWT ID (for token revocation)
     pub token_type: String, // "access" or "refresh"
+    /// Token version of the user at issue; tokens from before the last bump are rejected.
+    /// Tokens issued before versioning carry none and count as version 0.
+    #[serde(default)]
+    pub ver: i32,
 }
 
 #[derive(Debug, Clone)]
@@ -66,6 +70,7 @@ impl JwtService {
         username: String,
         email: String,
         role: UserRole,
+        token_version: i32,
     ) -> Result<String, AppError> {
         let now = Utc::now();
         let exp = now + Duration::from_std(JWT_ACCESS_TOKEN_EXPIRY)
@@ -80,6 +85,7 @@ impl JwtService {
             iat: now.timestamp(),
             jti: Uuid::new_v4().to_string(),
             token_type: "access".to_string(),
+            ver: token_version,
         };
 
         encode(&Header::default(), &claims, &self.encoding_key)
@@ -93,6 +99,7 @@ impl JwtService {
         username: String,
         email: String,
         role: UserRole,
+        token_version: i32,
     ) -> Result<String, AppError> {
         let now = Utc::now();
         let exp = now + Duration::from_std(JWT_REFRESH_TOKEN_EXPIRY)
@@ -107,6 +114,7 @@ impl JwtService {
             iat: now.timestamp(),
             jti: Uuid::new_v4().to_string(),
             token_type: "refresh".to_string(),
+            ver: token_version,
         };
 
         encode(&Header::default(), &claims, &self.encoding_key)
@@ -245,9 +253,10 @@ impl JwtService {
         username: String,
         email: String,
         role: UserRole,
+        token_version: i32,
     ) -> Result<TokenPair, AppError> {
-        let access_token = self.generate_access_token(user_id, username.clone(), email.clone(), role)?;
-        let refresh_token = self.generate_refresh_token(user_id, username, email, role)?;
+        let access_token = self.generate_access_token(user_id, username.clone(), email.clone(), role, token_version)?;
+        let refresh_token = self.generate_refresh_token(user_id, username, email, role, token_version)?;
 
         Ok(TokenPair {
             access_token,
@@ -329,18 +338,8 @@ impl JwtService {
         let user_id = uuid::Uuid::parse_str(&claims.sub)
             .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
 
-        // Check if user is still active
-        let user_active = sqlx::query_scalar!(
-            "SELECT is_active FROM users WHERE id = $1",
-            user_id
-        )
-        .fetch_optional(pool)
-        .await?
-        .unwrap_or(false);
-
-        if !user_active {
-            return Err(AppError::Authentication("User account is deactivated".to_string()));
-        }
+        // Check the user is still active and the token predates no revocation
+        self.ensure_token_current(claims, pool).await?;
 
         // For refresh tokens, check if the session is still valid
         if claims.token_type == "refresh" {
@@ -365,6 +364,26 @@ impl JwtService {
         Ok(())
     }
 
+    /// Reject tokens of deactivated users and tokens issued before the user's last revocation;
+    /// returns the current token version
+    pub async fn ensure_token_current(&self, claims: &Claims, pool: &sqlx::PgPool) -> Result<i32, AppError> {
+        let user_id = uuid::Uuid::parse_str(&claims.sub)
+            .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
+
+        let state = crate::models::User::token_state(pool, user_id)
+            .await?
+            .ok_or_else(|| AppError::Authentication("User not found".to_string()))?;
+
+        if !state.is_active {
+            return Err(AppError::Authentication("User account is deactivated".to_string()));
+        }
+        if !state.accepts(claims.ver) {
+            return Err(AppError::Authentication("Token has been revoked".to_string()));
+        }
+
+        Ok(state.token_version)
+    }
+
     /// Log token usage for security monitoring
     async fn log_token_usage(
         &self,
@@ -398,6 +417,8 @@ impl JwtService {
         let user_id = uuid::Uuid::parse_str(&claims.sub)
             .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))?;
 
+        let token_version = self.ensure_token_current(&claims, pool).await?;
+
         // Verify the refresh token exists in the database
         let session_exists = sqlx::query_scalar!(
             "SELECT COUNT(*) FROM user_sessions WHERE user_id = $1 AND refresh_token_hash = $2 AND is_active = true AND expires_at > NOW()",
@@ -418,6 +439,7 @@ impl JwtService {
             claims.username,
             claims.email,
             claims.role,
+            token_version,
         )?;
 
         // Update the session with new refresh token hash
@@ -509,7 +531,7 @@ mod tests {
 
         // Generate access token
         let access_token = jwt_service
-            .generate_access_token(user_id, username.clone(), email.clone(), role)
+            .generate_access_token(user_id, username.clone(), email.clone(), role, 0)
             .expect("Failed to generate access token");
 
         // Validate access token
@@ -525,7 +547,7 @@ mod tests {
 
         // Generate refresh token
         let refresh_token = jwt_service
-            .generate_refresh_token(user_id, username.clone(), email.clone(), role)
+            .generate_refresh_token(user_id, username.clone(), email.clone(), role, 0)
             .expect("Failed to generate refresh token");
 
         // Validate refresh token
@@ -545,7 +567,7 @@ mod tests {
         let role = UserRole::User;
 
         let token_pair = jwt_service
-            .create_token_pair(user_id, username, email, role)
+            .create_token_pair(user_id, username, email, role, 0)
             .expect("Failed to create token pair");
 
         assert!(!token_pair.access_token.is_empty());
@@ -575,7 +597,7 @@ mod tests {
         let role = UserRole::User;
 
         let token = jwt_service
-            .generate_access_token(user_id, username, email, role)
+            .generate_access_token(user_id, username, email, role, 0)
             .expect("Failed to generate token");
 
         let extracted_id = jwt_service
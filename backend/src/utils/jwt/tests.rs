@@ -35,7 +35,7 @@ async fn test_enhanced_token_generation_and_validation() {
 
     // Test token pair creation
     let token_pair = jwt_service
-        .create_token_pair(user_id, username.clone(), email.clone(), role)
+        .create_token_pair(user_id, username.clone(), email.clone(), role, 0)
         .expect("Failed to create token pair");
 
     assert!(!token_pair.access_token.is_empty());
@@ -72,7 +72,7 @@ async fn test_token_revocation() {
 
     // Generate token
     let token = jwt_service
-        .generate_access_token(user_id, username, email, role)
+        .generate_access_token(user_id, username, email, role, 0)
         .expect("Failed to generate token");
 
     // Validate token (should work)
@@ -100,7 +100,7 @@ async fn test_token_expiration_checks() {
     let role = UserRole::User;
 
     let token = jwt_service
-        .generate_access_token(user_id, username, email, role)
+        .generate_access_token(user_id, username, email, role, 0)
         .expect("Failed to generate token");
 
     // Test remaining time
@@ -137,11 +137,11 @@ async fn test_token_type_validation() {
     let role = UserRole::User;
 
     let access_token = jwt_service
-        .generate_access_token(user_id, username.clone(), email.clone(), role)
+        .generate_access_token(user_id, username.clone(), email.clone(), role, 0)
         .expect("Failed to generate access token");
 
     let refresh_token = jwt_service
-        .generate_refresh_token(user_id, username, email, role)
+        .generate_refresh_token(user_id, username, email, role, 0)
         .expect("Failed to generate refresh token");
 
     // Test token type checks
@@ -161,7 +161,7 @@ async fn test_unsafe_token_operations() {
     let role = UserRole::User;
 
     let token = jwt_service
-        .generate_access_token(user_id, username, email, role)
+        .generate_access_token(user_id, username, email, role, 0)
         .expect("Failed to generate token");
 
     // Test unsafe user ID extraction
@@ -197,7 +197,7 @@ async fn test_refresh_token_flow() {
 
     // Create initial token pair
     let initial_tokens = jwt_service
-        .create_token_pair(user_id, username.clone(), email.clone(), role)
+        .create_token_pair(user_id, username.clone(), email.clone(), role, 0)
         .expect("Failed to create initial token pair");
 
     // Create a user session in the database
@@ -264,7 +264,7 @@ async fn test_security_checks() {
     .expect("Failed to create user");
 
     let token = jwt_service
-        .generate_access_token(user_id, username, email, role)
+        .generate_access_token(user_id, username, email, role, 0)
         .expect("Failed to generate token");
 
     // Test security checks with active user
@@ -312,7 +312,7 @@ async fn test_anomaly_detection() {
     let role = UserRole::User;
 
     let token = jwt_service
-        .generate_access_token(user_id, username, email, role)
+        .generate_access_token(user_id, username, email, role, 0)
         .expect("Failed to generate token");
 
     // Test anomaly detection
@@ -335,7 +335,7 @@ async fn test_jwt_id_operations() {
     let role = UserRole::User;
 
     let token = jwt_service
-        .generate_access_token(user_id, username, email, role)
+        .generate_access_token(user_id, username, email, role, 0)
         .expect("Failed to generate token");
 
     // Get JWT ID
@@ -407,7 +407,7 @@ async fn test_concurrent_token_operations() {
         let email = format!("{}_{}", email, i);
         
         let handle = tokio::spawn(async move {
-            jwt_service.create_token_pair(user_id, username, email, role)
+            jwt_service.create_token_pair(user_id, username, email, role, 0)
         });
         
         handles.push(handle);
@@ -1,6 +1,8 @@
 pub mod jwt;
 pub mod validation;
 pub mod crypto;
+pub mod envelope;
+pub mod hd_wallet;
 pub mod webhook_verification;
 
 pub use jwt::{JwtService, Claims, TokenPair};
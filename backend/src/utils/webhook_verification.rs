@@ -82,6 +82,54 @@ pub fn verify_blockchain_signature(payload: &[u8], signature: &str) -> Result<()
     Ok(())
 }
 
+/// Verify identity verification provider webhook signature
+/// The header is `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`
+pub fn verify_kyc_signature(payload: &[u8], signature: &str) -> Result<(), AppError> {
+    let webhook_secret = env::var("KYC_WEBHOOK_SECRET")
+        .map_err(|_| AppError::Internal("Missing KYC_WEBHOOK_SECRET".to_string()))?;
+
+    let mut timestamp = None;
+    let mut v1_signature = None;
+    for part in signature.split(',') {
+        if let Some(ts) = part.trim().strip_prefix("t=") {
+            timestamp = Some(ts);
+        } else if let Some(v1) = part.trim().strip_prefix("v1=") {
+            v1_signature = Some(v1);
+        }
+    }
+
+    let timestamp = timestamp
+        .ok_or_else(|| AppError::Validation("Missing timestamp in signature".to_string()))?;
+    let v1_signature = v1_signature
+        .ok_or_else(|| AppError::Validation("Missing v1 signature".to_string()))?;
+
+    let mut mac = HmacSha256::new_from_slice(webhook_secret.as_bytes())
+        .map_err(|_| AppError::Internal("Invalid webhook secret".to_string()))?;
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(payload);
+    let expected_signature = hex::encode(mac.finalize().into_bytes());
+
+    if expected_signature != v1_signature {
+        return Err(AppError::Authentication("Invalid identity verification webhook signature".to_string()));
+    }
+
+    // Reject replays of old deliveries (within 5 minutes)
+    let current_time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|_| AppError::Internal("System time error".to_string()))?
+        .as_secs();
+
+    let webhook_time: u64 = timestamp.parse()
+        .map_err(|_| AppError::Validation("Invalid timestamp format".to_string()))?;
+
+    if current_time.saturating_sub(webhook_time) > 300 {
+        return Err(AppError::Authentication("Webhook timestamp too old".to_string()));
+    }
+
+    Ok(())
+}
+
 /// Verify notification service webhook signature
 pub fn verify_notification_signature(payload: &[u8], signature: &str) -> Result<(), AppError> {
     let webhook_secret = env::var("NOTIFICATION_WEBHOOK_SECRET")
@@ -230,6 +230,60 @@ async fn test_buy_box_already_sold() {
     cleanup_test_data(&pool, "buyer2@example.com").await;
 }
 
+#[actix_web::test]
+async fn test_concurrent_purchases_respect_per_user_cap() {
+    let pool = create_test_pool().await;
+    let app = create_test_app(pool.clone()).await;
+
+    // Create test data
+    let seller = create_test_user(&pool, "seller@example.com", "password123").await;
+    let buyer = create_test_user(&pool, "buyer@example.com", "password123").await;
+    let item = create_test_item(&pool, &seller.id, "Test Item").await;
+    let raffle = create_test_raffle(&pool, &seller.id, &item.id).await;
+
+    sqlx::query("UPDATE raffles SET max_boxes_per_user = 3 WHERE id = $1")
+        .bind(raffle.id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    add_credits_to_user(&pool, &buyer.id, 100.0).await;
+
+    let tokens = get_auth_tokens(&pool, &buyer).await;
+
+    // Each purchase fits the cap on its own, but not both together
+    let purchase = |box_numbers: [i32; 2]| {
+        test::TestRequest::post()
+            .uri(&format!("/api/raffles/{}/buy-box", raffle.id))
+            .insert_header(("Authorization", format!("Bearer {}", tokens.access_token)))
+            .set_json(&json!({
+                "box_numbers": box_numbers,
+                "payment_method": "credits"
+            }))
+            .to_request()
+    };
+
+    let (first, second) = tokio::join!(
+        test::call_service(&app, purchase([1, 2])),
+        test::call_service(&app, purchase([3, 4])),
+    );
+
+    let mut statuses = vec![first.status().as_u16(), second.status().as_u16()];
+    statuses.sort();
+    assert_eq!(statuses, vec![200, 400]);
+
+    let held: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM box_purchases WHERE raffle_id = $1 AND user_id = $2")
+        .bind(raffle.id)
+        .bind(buyer.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(held, 2);
+
+    cleanup_test_data(&pool, "seller@example.com").await;
+    cleanup_test_data(&pool, "buyer@example.com").await;
+}
+
 #[actix_web::test]
 async fn test_list_raffles_with_filters() {
     let pool = create_test_pool().await;
@@ -41,6 +41,7 @@ async fn test_hd_wallet_integration() {
         username: "testuser".to_string(),
         email: "test@example.com".to_string(),
         phone_number: None,
+        referral_code: None,
     };
 
     let user = User::create(
@@ -20,6 +20,10 @@ const networks = {
       callbackGasLimit: "500000",
       linkToken: "0x326C977E6efc84E512bB9C30f76E30c160eD06FB"
     },
+    automation: {
+      registrar: process.env.AUTOMATION_REGISTRAR_MUMBAI,
+      registry: process.env.AUTOMATION_REGISTRY_MUMBAI
+    },
     gasPrice: ethers.utils.parseUnits("30", "gwei"),
     confirmations: 2
   },
@@ -42,6 +46,10 @@ const networks = {
       callbackGasLimit: "500000",
       linkToken: "0xb0897686c545045aFc77CF20eC7A532E3120E0F1"
     },
+    automation: {
+      registrar: process.env.AUTOMATION_REGISTRAR_POLYGON,
+      registry: process.env.AUTOMATION_REGISTRY_POLYGON
+    },
     gasPrice: ethers.utils.parseUnits("50", "gwei"),
     confirmations: 5
   },
@@ -64,6 +72,10 @@ const networks = {
       callbackGasLimit: "500000",
       linkToken: "0x514910771AF9Ca656af840dff83E8264EcF986CA"
     },
+    automation: {
+      registrar: process.env.AUTOMATION_REGISTRAR_ETHEREUM,
+      registry: process.env.AUTOMATION_REGISTRY_ETHEREUM
+    },
     gasPrice: ethers.utils.parseUnits("20", "gwei"),
     confirmations: 6
   },
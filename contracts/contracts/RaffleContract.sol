@@ -3,17 +3,29 @@ pragma solidity ^0.8.20;
 
 import "@chainlink/contracts/src/v0.8/vrf/VRFConsumerBaseV2.sol";
 import "@chainlink/contracts/src/v0.8/interfaces/VRFCoordinatorV2Interface.sol";
+import "@chainlink/contracts/src/v0.8/automation/interfaces/AutomationCompatibleInterface.sol";
 import "@openzeppelin/contracts/access/Ownable.sol";
 import "@openzeppelin/contracts/security/ReentrancyGuard.sol";
 import "@openzeppelin/contracts/security/Pausable.sol";
 import "@openzeppelin/contracts/utils/math/SafeMath.sol";
+import "@openzeppelin/contracts/utils/cryptography/EIP712.sol";
+import "@openzeppelin/contracts/utils/cryptography/ECDSA.sol";
 
 /**
  * @title RaffleContract
- * @dev Smart contract for managing provably fair raffles using Chainlink VRF
+ * @dev Smart contract for managing provably fair raffles using Chainlink VRF.
+ * Chainlink Automation can trigger draws the backend scheduler misses.
+ * Users with their own wallets buy boxes with a purchase voucher signed by the backend.
  * @author Unit Shopping Platform
  */
-contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable {
+contract RaffleContract is
+    VRFConsumerBaseV2,
+    AutomationCompatibleInterface,
+    Ownable,
+    ReentrancyGuard,
+    Pausable,
+    EIP712
+{
     using SafeMath for uint256;
 
     // Chainlink VRF Configuration
@@ -23,6 +35,14 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
     uint32 private constant CALLBACK_GAS_LIMIT = 100000;
     uint16 private constant REQUEST_CONFIRMATIONS = 3;
 
+    // Draw Automation Configuration
+    uint256 private constant DEFAULT_RANDOMNESS_RETRY_INTERVAL = 30 minutes;
+    uint256 private constant UPKEEP_SCAN_LIMIT = 100;
+
+    // Purchase Voucher Configuration
+    bytes32 private constant BOX_PURCHASE_TYPEHASH =
+        keccak256("BoxPurchase(uint256 raffleId,address buyer,uint256 boxes,bytes32 purchaseId,uint256 deadline)");
+
     // Raffle States
     enum RaffleStatus {
         OPEN,
@@ -54,6 +74,10 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
     mapping(uint256 => address[]) public s_boxOwners;
     mapping(uint256 => uint256) private s_requestIdToRaffleId;
     mapping(address => bool) public s_authorizedCallers;
+    mapping(uint256 => uint256) private s_randomnessRequestedAt;
+    uint256 private s_randomnessRetryInterval;
+    address public s_automationForwarder;
+    mapping(bytes32 => bool) public s_redeemedPurchaseIds;
 
     // Events
     event RaffleCreated(
@@ -90,6 +114,16 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
     event AuthorizedCallerAdded(address indexed caller);
     event AuthorizedCallerRemoved(address indexed caller);
 
+    event RandomnessRetryIntervalUpdated(uint256 interval);
+    event AutomationForwarderUpdated(address indexed forwarder);
+
+    event VoucherRedeemed(
+        bytes32 indexed purchaseId,
+        uint256 indexed raffleId,
+        address indexed buyer,
+        uint256 boxes
+    );
+
     // Custom Errors
     error RaffleContract__InvalidRaffleId();
     error RaffleContract__RaffleNotOpen();
@@ -99,6 +133,11 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
     error RaffleContract__UnauthorizedCaller();
     error RaffleContract__RandomnessAlreadyRequested();
     error RaffleContract__InvalidRandomnessRequest();
+    error RaffleContract__InvalidRetryInterval();
+    error RaffleContract__InvalidBoxCount();
+    error RaffleContract__VoucherExpired();
+    error RaffleContract__VoucherRedeemed();
+    error RaffleContract__InvalidVoucher();
 
     // Modifiers
     modifier onlyAuthorizedCaller() {
@@ -108,6 +147,17 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
         _;
     }
 
+    modifier onlyDrawTrigger() {
+        if (
+            msg.sender != s_automationForwarder &&
+            !s_authorizedCallers[msg.sender] &&
+            msg.sender != owner()
+        ) {
+            revert RaffleContract__UnauthorizedCaller();
+        }
+        _;
+    }
+
     modifier validRaffleId(uint256 raffleId) {
         if (raffleId >= s_raffleIdCounter) {
             revert RaffleContract__InvalidRaffleId();
@@ -132,11 +182,12 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
         address vrfCoordinator,
         bytes32 keyHash,
         uint64 subscriptionId
-    ) VRFConsumerBaseV2(vrfCoordinator) {
+    ) VRFConsumerBaseV2(vrfCoordinator) EIP712("Thriftee Raffle", "1") {
         i_vrfCoordinator = VRFCoordinatorV2Interface(vrfCoordinator);
         i_keyHash = keyHash;
         i_subscriptionId = subscriptionId;
         s_raffleIdCounter = 0;
+        s_randomnessRetryInterval = DEFAULT_RANDOMNESS_RETRY_INTERVAL;
     }
 
     /**
@@ -199,19 +250,81 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
         raffleInStatus(raffleId, RaffleStatus.OPEN)
         nonReentrant
     {
-        Raffle storage raffle = s_raffles[raffleId];
+        if (s_raffles[raffleId].boxesSold >= s_raffles[raffleId].totalBoxes) {
+            revert RaffleContract__RaffleFull();
+        }
+
+        _buyBox(raffleId, msg.sender);
+    }
 
-        if (raffle.boxesSold >= raffle.totalBoxes) {
+    /**
+     * @dev Buy boxes from the caller's own wallet with a voucher signed by an authorized
+     * caller. The backend issues a voucher once the boxes are paid for, so each one can be
+     * redeemed only by the wallet it names, only once, and only before its deadline.
+     * @param raffleId ID of the raffle
+     * @param boxes Number of boxes the voucher covers
+     * @param purchaseId The backend's ID of the purchase
+     * @param deadline Unix time after which the voucher can no longer be redeemed
+     * @param signature EIP-712 signature of the BoxPurchase voucher
+     */
+    function buyBoxesWithVoucher(
+        uint256 raffleId,
+        uint256 boxes,
+        bytes32 purchaseId,
+        uint256 deadline,
+        bytes calldata signature
+    )
+        external
+        whenNotPaused
+        validRaffleId(raffleId)
+        raffleInStatus(raffleId, RaffleStatus.OPEN)
+        nonReentrant
+    {
+        if (block.timestamp > deadline) {
+            revert RaffleContract__VoucherExpired();
+        }
+        if (s_redeemedPurchaseIds[purchaseId]) {
+            revert RaffleContract__VoucherRedeemed();
+        }
+        if (boxes == 0) {
+            revert RaffleContract__InvalidBoxCount();
+        }
+        if (s_raffles[raffleId].boxesSold.add(boxes) > s_raffles[raffleId].totalBoxes) {
             revert RaffleContract__RaffleFull();
         }
 
+        bytes32 digest = _hashTypedDataV4(
+            keccak256(abi.encode(BOX_PURCHASE_TYPEHASH, raffleId, msg.sender, boxes, purchaseId, deadline))
+        );
+        address signer = ECDSA.recover(digest, signature);
+        if (!s_authorizedCallers[signer] && signer != owner()) {
+            revert RaffleContract__InvalidVoucher();
+        }
+
+        s_redeemedPurchaseIds[purchaseId] = true;
+        emit VoucherRedeemed(purchaseId, raffleId, msg.sender, boxes);
+
+        for (uint256 i = 0; i < boxes; i++) {
+            _buyBox(raffleId, msg.sender);
+        }
+    }
+
+    /**
+     * @dev Record one box for a buyer, requesting randomness once the last box is sold
+     * (internal function). Callers check there is a box left.
+     * @param raffleId ID of the raffle
+     * @param buyer Address that owns the box
+     */
+    function _buyBox(uint256 raffleId, address buyer) internal {
+        Raffle storage raffle = s_raffles[raffleId];
+
         // Record the box purchase
-        s_boxOwners[raffleId].push(msg.sender);
+        s_boxOwners[raffleId].push(buyer);
         raffle.boxesSold = raffle.boxesSold.add(1);
 
         emit BoxPurchased(
             raffleId,
-            msg.sender,
+            buyer,
             raffle.boxesSold,
             raffle.boxesSold
         );
@@ -226,6 +339,73 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
         }
     }
 
+    /**
+     * @dev Request randomness for a full raffle, or again for one whose request has gone
+     * unanswered for the retry interval. Used by the backend's draw scheduler.
+     * @param raffleId ID of the raffle
+     */
+    function requestRandomWinner(uint256 raffleId)
+        external
+        onlyAuthorizedCaller
+        whenNotPaused
+        validRaffleId(raffleId)
+        nonReentrant
+    {
+        _requestRandomness(raffleId);
+    }
+
+    /**
+     * @dev Chainlink Automation check: finds a raffle whose draw is due
+     * @param checkData Optional abi-encoded raffle ID to scan from; defaults to the latest raffles
+     * @return upkeepNeeded Whether a draw is due
+     * @return performData The abi-encoded raffle ID to draw
+     */
+    function checkUpkeep(bytes calldata checkData)
+        external
+        view
+        override
+        returns (bool upkeepNeeded, bytes memory performData)
+    {
+        uint256 counter = s_raffleIdCounter;
+        uint256 from = counter > UPKEEP_SCAN_LIMIT ? counter - UPKEEP_SCAN_LIMIT : 0;
+        if (checkData.length > 0) {
+            from = abi.decode(checkData, (uint256));
+        }
+
+        uint256 to = from.add(UPKEEP_SCAN_LIMIT);
+        if (to > counter) {
+            to = counter;
+        }
+
+        for (uint256 raffleId = from; raffleId < to; raffleId++) {
+            if (_isDrawDue(raffleId)) {
+                return (true, abi.encode(raffleId));
+            }
+        }
+
+        return (false, "");
+    }
+
+    /**
+     * @dev Chainlink Automation perform: requests randomness for the raffle found by checkUpkeep.
+     * The draw must still be due, so a stale or replayed call can't request twice.
+     * @param performData The abi-encoded raffle ID
+     */
+    function performUpkeep(bytes calldata performData)
+        external
+        override
+        onlyDrawTrigger
+        whenNotPaused
+        nonReentrant
+    {
+        uint256 raffleId = abi.decode(performData, (uint256));
+        if (raffleId >= s_raffleIdCounter) {
+            revert RaffleContract__InvalidRaffleId();
+        }
+
+        _requestRandomness(raffleId);
+    }
+
     /**
      * @dev Request randomness from Chainlink VRF (internal function)
      * @param raffleId ID of the raffle
@@ -233,10 +413,15 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
     function _requestRandomness(uint256 raffleId) internal {
         Raffle storage raffle = s_raffles[raffleId];
 
-        if (raffle.status != RaffleStatus.FULL) {
+        if (!_isDrawDue(raffleId)) {
             revert RaffleContract__RandomnessAlreadyRequested();
         }
 
+        // Only the newest request may settle the raffle
+        if (raffle.status == RaffleStatus.RANDOM_REQUESTED) {
+            delete s_requestIdToRaffleId[raffle.requestId];
+        }
+
         uint256 requestId = i_vrfCoordinator.requestRandomWords(
             i_keyHash,
             i_subscriptionId,
@@ -248,6 +433,7 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
         raffle.requestId = requestId;
         raffle.status = RaffleStatus.RANDOM_REQUESTED;
         s_requestIdToRaffleId[requestId] = raffleId;
+        s_randomnessRequestedAt[raffleId] = block.timestamp;
 
         emit RandomnessRequested(raffleId, requestId);
     }
@@ -264,7 +450,7 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
         uint256 raffleId = s_requestIdToRaffleId[requestId];
         Raffle storage raffle = s_raffles[raffleId];
 
-        if (raffle.status != RaffleStatus.RANDOM_REQUESTED) {
+        if (raffle.status != RaffleStatus.RANDOM_REQUESTED || raffle.requestId != requestId) {
             revert RaffleContract__InvalidRandomnessRequest();
         }
 
@@ -280,6 +466,23 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
         emit WinnerSelected(raffleId, winners, randomWord);
     }
 
+    /**
+     * @dev Whether randomness should be requested: the raffle is full, or its request has
+     * gone unanswered for the retry interval
+     * @param raffleId ID of the raffle
+     */
+    function _isDrawDue(uint256 raffleId) internal view returns (bool) {
+        Raffle storage raffle = s_raffles[raffleId];
+
+        if (raffle.status == RaffleStatus.FULL) {
+            return true;
+        }
+
+        return
+            raffle.status == RaffleStatus.RANDOM_REQUESTED &&
+            block.timestamp >= s_randomnessRequestedAt[raffleId].add(s_randomnessRetryInterval);
+    }
+
     /**
      * @dev Select winners using the random word
      * @param raffleId ID of the raffle
@@ -378,6 +581,27 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
         emit AuthorizedCallerRemoved(caller);
     }
 
+    /**
+     * @dev Set how long a randomness request may go unanswered before it can be sent again
+     * @param interval Retry interval in seconds
+     */
+    function setRandomnessRetryInterval(uint256 interval) external onlyOwner {
+        if (interval < 5 minutes) {
+            revert RaffleContract__InvalidRetryInterval();
+        }
+        s_randomnessRetryInterval = interval;
+        emit RandomnessRetryIntervalUpdated(interval);
+    }
+
+    /**
+     * @dev Set the Chainlink Automation forwarder allowed to call performUpkeep
+     * @param forwarder Forwarder address of the registered upkeep; zero to disable
+     */
+    function setAutomationForwarder(address forwarder) external onlyOwner {
+        s_automationForwarder = forwarder;
+        emit AutomationForwarderUpdated(forwarder);
+    }
+
     /**
      * @dev Pause the contract
      */
@@ -448,6 +672,46 @@ contract RaffleContract is VRFConsumerBaseV2, Ownable, ReentrancyGuard, Pausable
         return s_authorizedCallers[caller] || caller == owner();
     }
 
+    /**
+     * @dev Whether randomness is due to be requested for a raffle
+     * @param raffleId ID of the raffle
+     */
+    function isDrawDue(uint256 raffleId)
+        external
+        view
+        validRaffleId(raffleId)
+        returns (bool)
+    {
+        return _isDrawDue(raffleId);
+    }
+
+    /**
+     * @dev Get when randomness was last requested for a raffle; zero if never
+     * @param raffleId ID of the raffle
+     */
+    function getRandomnessRequestedAt(uint256 raffleId)
+        external
+        view
+        validRaffleId(raffleId)
+        returns (uint256)
+    {
+        return s_randomnessRequestedAt[raffleId];
+    }
+
+    /**
+     * @dev Get the randomness retry interval in seconds
+     */
+    function getRandomnessRetryInterval() external view returns (uint256) {
+        return s_randomnessRetryInterval;
+    }
+
+    /**
+     * @dev Get the EIP-712 domain separator purchase vouchers are signed under
+     */
+    function getDomainSeparator() external view returns (bytes32) {
+        return _domainSeparatorV4();
+    }
+
     /**
      * @dev Get VRF configuration
      */
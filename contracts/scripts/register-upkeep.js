@@ -0,0 +1,149 @@
+const { ethers } = require("hardhat");
+const { getNetworkConfig } = require("../config/networks");
+
+// Chainlink Automation v2.1 registrar and registry, only the calls used here
+const REGISTRAR_ABI = [
+    "function registerUpkeep((string name, bytes encryptedEmail, address upkeepContract, uint32 gasLimit, address adminAddress, uint8 triggerType, bytes checkData, bytes triggerConfig, bytes offchainConfig, uint96 amount) requestParams) returns (uint256)"
+];
+const REGISTRY_ABI = [
+    "function getForwarder(uint256 upkeepId) view returns (address)"
+];
+const LINK_ABI = [
+    "function approve(address spender, uint256 amount) returns (bool)",
+    "function balanceOf(address owner) view returns (uint256)"
+];
+
+// Requesting randomness costs about as much as a box purchase that fills the raffle
+const DEFAULT_GAS_LIMIT = 500000;
+// Conditional upkeeps are trigger type 0; log-triggered ones are 1
+const CONDITIONAL_TRIGGER = 0;
+
+/**
+ * Register the raffle contract as a conditional Chainlink Automation upkeep and allow its
+ * forwarder to call performUpkeep. Automation is the fallback draw trigger: the backend's
+ * draw scheduler requests randomness first, and the upkeep only finds work when a full
+ * raffle was missed or a request went unanswered for the retry interval.
+ */
+async function registerUpkeep(contractAddress, networkName, options) {
+    console.log(`\n=== Registering Draw Upkeep on ${networkName} ===`);
+    console.log(`Contract Address: ${contractAddress}`);
+
+    try {
+        const networkConfig = getNetworkConfig(networkName);
+        const { registrar, registry } = networkConfig.automation || {};
+        if (!registrar || !registry) {
+            throw new Error(
+                `Set AUTOMATION_REGISTRAR_${networkName.toUpperCase()} and AUTOMATION_REGISTRY_${networkName.toUpperCase()}`
+            );
+        }
+
+        const [admin] = await ethers.getSigners();
+        console.log(`Admin address: ${admin.address}`);
+
+        const RaffleContract = await ethers.getContractFactory("RaffleContract");
+        const contract = RaffleContract.attach(contractAddress);
+
+        const link = new ethers.Contract(networkConfig.vrf.linkToken, LINK_ABI, admin);
+        const balance = await link.balanceOf(admin.address);
+        if (balance < options.funding) {
+            throw new Error(
+                `Admin holds ${ethers.formatEther(balance)} LINK, ${ethers.formatEther(options.funding)} needed`
+            );
+        }
+
+        console.log(`\nApproving ${ethers.formatEther(options.funding)} LINK for the registrar...`);
+        await (await link.approve(registrar, options.funding)).wait();
+
+        console.log("Registering upkeep...");
+        const registrarContract = new ethers.Contract(registrar, REGISTRAR_ABI, admin);
+        const params = {
+            name: options.name,
+            encryptedEmail: "0x",
+            upkeepContract: contractAddress,
+            gasLimit: options.gasLimit,
+            adminAddress: admin.address,
+            triggerType: CONDITIONAL_TRIGGER,
+            checkData: "0x",
+            triggerConfig: "0x",
+            offchainConfig: "0x",
+            amount: options.funding
+        };
+        // The upkeep ID is only returned, not emitted by the registrar, so read it first
+        const upkeepId = await registrarContract.registerUpkeep.staticCall(params);
+        await (await registrarContract.registerUpkeep(params)).wait();
+        console.log(`✅ Registered upkeep ${upkeepId}`);
+
+        const registryContract = new ethers.Contract(registry, REGISTRY_ABI, admin);
+        const forwarder = await registryContract.getForwarder(upkeepId);
+        console.log(`Forwarder: ${forwarder}`);
+
+        console.log("Allowing the forwarder to perform upkeep...");
+        await (await contract.setAutomationForwarder(forwarder)).wait();
+        console.log("✅ Forwarder set");
+
+        const retryInterval = await contract.getRandomnessRetryInterval();
+        console.log(`\nRandomness retry interval: ${retryInterval} seconds`);
+        console.log("Keep raffle_randomness_timeout_minutes in the backend at or above this interval");
+
+        console.log("\n=== Upkeep Registration Complete ===\n");
+
+        return {
+            success: true,
+            contractAddress,
+            networkName,
+            upkeepId: upkeepId.toString(),
+            forwarder
+        };
+
+    } catch (error) {
+        console.error("\n❌ Upkeep registration failed:");
+        console.error("Error:", error.message);
+
+        return {
+            success: false,
+            error: error.message,
+            contractAddress,
+            networkName
+        };
+    }
+}
+
+// Main execution
+async function main() {
+    const args = process.argv.slice(2);
+
+    if (args.length < 1) {
+        console.log("Usage: npx hardhat run scripts/register-upkeep.js --network <network> <contract-address> [link-funding] [gas-limit]");
+        console.log("Defaults: 5 LINK funding, 500000 gas");
+        process.exit(1);
+    }
+
+    const contractAddress = args[0];
+    const networkName = process.env.HARDHAT_NETWORK || "hardhat";
+    const options = {
+        name: `Raffle draws ${contractAddress.slice(0, 10)}`,
+        funding: ethers.parseEther(args[1] || "5"),
+        gasLimit: Number(args[2] || DEFAULT_GAS_LIMIT)
+    };
+
+    const result = await registerUpkeep(contractAddress, networkName, options);
+
+    if (!result.success) {
+        process.exit(1);
+    }
+}
+
+// Export for use in other scripts
+module.exports = {
+    registerUpkeep
+};
+
+// Run if called directly
+if (require.main === module) {
+    main()
+        .then(() => process.exit(0))
+        .catch((error) => {
+            console.error("Upkeep registration script failed:", error);
+            process.exit(1);
+        });
+}
@@ -1,6 +1,6 @@
 const { expect } = require("chai");
 const { ethers } = require("hardhat");
-const { loadFixture } = require("@nomicfoundation/hardhat-network-helpers");
+const { loadFixture, time } = require("@nomicfoundation/hardhat-network-helpers");
 
 describe("RaffleContract", function () {
   // Fixture to deploy the contract
@@ -212,6 +212,165 @@ describe("RaffleContract", function () {
     });
   });
 
+  describe("Draw Automation", function () {
+    async function fullRaffleFixture() {
+      const fixture = await deployRaffleContractFixture();
+      const { raffleContract, operator, user1, user2 } = fixture;
+
+      const boxPrice = ethers.parseEther("0.01");
+      await raffleContract.connect(operator).createRaffle(1, 2, boxPrice, 1);
+      await raffleContract.connect(user1).buyBox(0, { value: boxPrice });
+      await raffleContract.connect(user2).buyBox(0, { value: boxPrice });
+
+      return fixture;
+    }
+
+    it("Should not find work while a request is within the retry interval", async function () {
+      const { raffleContract } = await loadFixture(fullRaffleFixture);
+
+      const [upkeepNeeded] = await raffleContract.checkUpkeep("0x");
+      expect(upkeepNeeded).to.be.false;
+      expect(await raffleContract.isDrawDue(0)).to.be.false;
+
+      await expect(raffleContract.requestRandomWinner(0))
+        .to.be.revertedWithCustomError(raffleContract, "RaffleContract__RandomnessAlreadyRequested");
+    });
+
+    it("Should re-request randomness once the retry interval has passed", async function () {
+      const { raffleContract, mockVRFCoordinator } = await loadFixture(fullRaffleFixture);
+      const firstRequestId = (await raffleContract.getRaffle(0)).requestId;
+
+      await time.increase(30 * 60);
+
+      const [upkeepNeeded, performData] = await raffleContract.checkUpkeep("0x");
+      expect(upkeepNeeded).to.be.true;
+      expect(performData).to.equal(ethers.AbiCoder.defaultAbiCoder().encode(["uint256"], [0]));
+
+      await expect(raffleContract.performUpkeep(performData))
+        .to.emit(raffleContract, "RandomnessRequested");
+
+      // The superseded request can no longer settle the raffle
+      await expect(mockVRFCoordinator.fulfillRandomWords(firstRequestId, [12345])).to.be.reverted;
+    });
+
+    it("Should only let the forwarder or authorized callers perform upkeep", async function () {
+      const { raffleContract, user3 } = await loadFixture(fullRaffleFixture);
+      await time.increase(30 * 60);
+
+      const performData = ethers.AbiCoder.defaultAbiCoder().encode(["uint256"], [0]);
+      await expect(raffleContract.connect(user3).performUpkeep(performData))
+        .to.be.revertedWithCustomError(raffleContract, "RaffleContract__UnauthorizedCaller");
+
+      await raffleContract.setAutomationForwarder(user3.address);
+      await expect(raffleContract.connect(user3).performUpkeep(performData))
+        .to.emit(raffleContract, "RandomnessRequested");
+    });
+  });
+
+  describe("Purchase Vouchers", function () {
+    const BoxPurchase = [
+      { name: "raffleId", type: "uint256" },
+      { name: "buyer", type: "address" },
+      { name: "boxes", type: "uint256" },
+      { name: "purchaseId", type: "bytes32" },
+      { name: "deadline", type: "uint256" },
+    ];
+
+    async function signVoucher(raffleContract, signer, voucher) {
+      const domain = {
+        name: "Thriftee Raffle",
+        version: "1",
+        chainId: (await ethers.provider.getNetwork()).chainId,
+        verifyingContract: await raffleContract.getAddress(),
+      };
+      return signer.signTypedData(domain, { BoxPurchase }, voucher);
+    }
+
+    async function voucherFixture() {
+      const fixture = await deployRaffleContractFixture();
+      const { raffleContract, operator, user1 } = fixture;
+
+      await raffleContract.connect(operator).createRaffle(1, 3, ethers.parseEther("0.01"), 1);
+      const voucher = {
+        raffleId: 0,
+        buyer: user1.address,
+        boxes: 2,
+        purchaseId: ethers.id("purchase-1"),
+        deadline: (await time.latest()) + 3600,
+      };
+      const signature = await signVoucher(raffleContract, operator, voucher);
+
+      return { ...fixture, voucher, signature };
+    }
+
+    function redeem(raffleContract, buyer, voucher, signature) {
+      return raffleContract
+        .connect(buyer)
+        .buyBoxesWithVoucher(voucher.raffleId, voucher.boxes, voucher.purchaseId, voucher.deadline, signature);
+    }
+
+    it("Should record boxes for the wallet named in the voucher", async function () {
+      const { raffleContract, user1, voucher, signature } = await loadFixture(voucherFixture);
+
+      await expect(redeem(raffleContract, user1, voucher, signature))
+        .to.emit(raffleContract, "VoucherRedeemed")
+        .withArgs(voucher.purchaseId, 0, user1.address, 2)
+        .and.to.emit(raffleContract, "BoxPurchased")
+        .withArgs(0, user1.address, 2, 2);
+
+      expect(await raffleContract.getBoxOwners(0)).to.deep.equal([user1.address, user1.address]);
+      expect(await raffleContract.s_redeemedPurchaseIds(voucher.purchaseId)).to.be.true;
+    });
+
+    it("Should reject a voucher redeemed twice, by another wallet, or after its deadline", async function () {
+      const { raffleContract, user1, user2, voucher, signature } = await loadFixture(voucherFixture);
+
+      await expect(redeem(raffleContract, user2, voucher, signature))
+        .to.be.revertedWithCustomError(raffleContract, "RaffleContract__InvalidVoucher");
+
+      await time.increaseTo(voucher.deadline + 1);
+      await expect(redeem(raffleContract, user1, voucher, signature))
+        .to.be.revertedWithCustomError(raffleContract, "RaffleContract__VoucherExpired");
+    });
+
+    it("Should reject a replayed voucher", async function () {
+      const { raffleContract, user1, voucher, signature } = await loadFixture(voucherFixture);
+
+      await redeem(raffleContract, user1, voucher, signature);
+      await expect(redeem(raffleContract, user1, voucher, signature))
+        .to.be.revertedWithCustomError(raffleContract, "RaffleContract__VoucherRedeemed");
+    });
+
+    it("Should reject vouchers not signed by an authorized caller", async function () {
+      const { raffleContract, user1, user2, voucher } = await loadFixture(voucherFixture);
+
+      const signature = await signVoucher(raffleContract, user2, voucher);
+      await expect(redeem(raffleContract, user1, voucher, signature))
+        .to.be.revertedWithCustomError(raffleContract, "RaffleContract__InvalidVoucher");
+    });
+
+    it("Should request randomness when a voucher buys the last boxes", async function () {
+      const { raffleContract, operator, user1 } = await loadFixture(voucherFixture);
+
+      const voucher = {
+        raffleId: 0,
+        buyer: user1.address,
+        boxes: 3,
+        purchaseId: ethers.id("purchase-2"),
+        deadline: (await time.latest()) + 3600,
+      };
+      const signature = await signVoucher(raffleContract, operator, voucher);
+
+      await expect(redeem(raffleContract, user1, voucher, signature))
+        .to.emit(raffleContract, "RaffleFull")
+        .and.to.emit(raffleContract, "RandomnessRequested");
+
+      const tooMany = { ...voucher, boxes: 1, purchaseId: ethers.id("purchase-3") };
+      await expect(redeem(raffleContract, user1, tooMany, await signVoucher(raffleContract, operator, tooMany)))
+        .to.be.revertedWithCustomError(raffleContract, "RaffleContract__RaffleNotOpen");
+    });
+  });
+
   describe("Access Control", function () {
     it("Should allow admin to add/remove authorized callers", async function () {
       const { raffleContract, operator, user1 } = await loadFixture(deployRaffleContractFixture);
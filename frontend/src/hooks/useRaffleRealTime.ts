@@ -80,7 +80,7 @@ export const useRaffleRealTime = ({
         console.log('Winner selected:', data);
         onWinnerSelected?.(data);
         
-        const winnerNames = data.winners.map((w: any) => w.user?.email || 'Anonymous').join(', ');
+        const winnerNames = data.winners.map((w: any) => w.display_name || 'Anonymous').join(', ');
         toast.success(`🏆 Winners selected: ${winnerNames}`, {
           autoClose: 10000,
         });
@@ -2,11 +2,12 @@ use crate::types::*;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
 // User DTOs
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateUserRequest {
     #[validate(length(min = 3, max = 50))]
     pub username: String,
@@ -19,9 +20,13 @@ pub struct CreateUserRequest {
     
     #[validate(phone)]
     pub phone_number: Option<String>,
+
+    /// Another user's referral code
+    #[serde(default)]
+    pub referral_code: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UserResponse {
     pub id: Uuid,
     pub username: String,
@@ -36,7 +41,7 @@ pub struct UserResponse {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct LoginRequest {
     #[validate(email)]
     pub email: String,
@@ -45,7 +50,7 @@ pub struct LoginRequest {
     pub password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AuthResponse {
     pub access_token: String,
     pub refresh_token: String,
@@ -54,7 +59,7 @@ pub struct AuthResponse {
 }
 
 // Item DTOs
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateItemRequest {
     #[validate(length(min = 1, max = 255))]
     pub name: String,
@@ -73,16 +78,28 @@ pub struct CreateItemRequest {
     
     #[validate(range(min = 1))]
     pub stock_quantity: i32,
+
+    /// ISO 4217 code the prices are in; defaults to the platform's base currency
+    #[serde(default)]
+    pub currency: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ItemResponse {
     pub id: Uuid,
     pub seller_id: Option<Uuid>,
     pub name: String,
     pub description: Option<String>,
     pub images: Vec<String>,
+    /// CDN variants for each entry in `images`, in the same order
+    #[serde(default)]
+    pub image_variants: Vec<ImageVariants>,
     pub retail_price: Decimal,
+    /// Currency `retail_price` and `cost_of_goods` are in
+    pub currency: String,
+    /// `retail_price` converted to the viewer's display currency
+    #[serde(default)]
+    pub display_retail_price: Option<DisplayAmount>,
     pub cost_of_goods: Decimal,
     pub status: ItemStatus,
     pub stock_quantity: i32,
@@ -91,8 +108,43 @@ pub struct ItemResponse {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Amount converted for display only; charges always settle in the platform's base currency
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct DisplayAmount {
+    pub currency: String,
+    pub amount: Decimal,
+    /// Units of `currency` per unit of the listing's currency
+    pub rate: Decimal,
+    pub rate_as_of: DateTime<Utc>,
+}
+
+/// Shipping quote for the viewer's destination, from the seller's policy or the item's override
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ShippingEstimate {
+    /// ISO 3166 country code, optionally with a subdivision (`US` or `US-CA`)
+    pub destination: String,
+    pub ships_to_destination: bool,
+    /// None when the item does not ship there or its weight is needed but not set
+    pub cost: Option<Decimal>,
+    pub handling_days_min: i32,
+    pub handling_days_max: i32,
+    /// Handling plus transit time
+    pub delivery_days_min: Option<i32>,
+    pub delivery_days_max: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ImageVariants {
+    pub original: String,
+    pub thumb: String,
+    pub medium: String,
+    pub large: String,
+    pub webp: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
 // Raffle DTOs
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateRaffleRequest {
     pub item_id: Uuid,
     
@@ -105,32 +157,126 @@ pub struct CreateRaffleRequest {
     #[validate(range(min = 1))]
     pub total_winners: i32,
     
-    #[validate(range(min = 1, max = 100))]
+    // Upper bounds are admin-configurable and enforced by the backend
+    #[validate(range(min = 1))]
     pub grid_rows: i32,
     
-    #[validate(range(min = 1, max = 100))]
+    #[validate(range(min = 1))]
     pub grid_cols: i32,
+    
+    /// Cells of the grid that hold no box; omitted for a plain rectangle
+    #[serde(default)]
+    pub layout: Option<RaffleLayoutDefinition>,
+    
+    /// One prize per place, 1st to `total_winners`th; omitted when every winner gets the raffle item
+    #[serde(default)]
+    pub prize_tiers: Vec<PrizeTierDefinition>,
+    
+    /// Price changes as the raffle fills or ages; omitted to sell every box at `box_price`
+    #[serde(default)]
+    pub pricing_curve: Option<PricingCurveDefinition>,
+    
+    /// Raffle stays scheduled until this time; omitted to open immediately
+    #[serde(default)]
+    pub starts_at: Option<DateTime<Utc>>,
+    
+    /// Deadline after which `deadline_action` applies if the raffle has not filled
+    #[serde(default)]
+    pub ends_at: Option<DateTime<Utc>>,
+    
+    #[serde(default)]
+    pub deadline_action: RaffleDeadlineAction,
+    
+    /// Most boxes one user may buy; omitted for no limit
+    #[serde(default)]
+    #[validate(range(min = 1))]
+    pub max_boxes_per_user: Option<i32>,
+    
+    /// Credits a winner may take instead of the raffle item; applies when `prize_tiers` is omitted
+    #[serde(default)]
+    pub credit_alternative: Option<Decimal>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Prize for one winning place: another item or a credit amount
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct PrizeTierDefinition {
+    pub place: i32,
+    pub prize_type: PrizeType,
+    pub item_id: Option<Uuid>,
+    pub credit_amount: Option<Decimal>,
+    /// Credits the winner may take instead of an item prize; funded from the seller's proceeds
+    #[serde(default)]
+    pub credit_alternative: Option<Decimal>,
+}
+
+/// What a pricing curve's tier thresholds measure
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PricingCurveBasis {
+    /// Percentage of boxes sold before the box being priced, 0-100
+    FillPercentage,
+    /// Minutes since the raffle opened
+    Time,
+}
+
+/// Box price schedule; boxes sell at the raffle's `box_price` until the first tier applies
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct PricingCurveDefinition {
+    pub basis: PricingCurveBasis,
+    pub tiers: Vec<PricingTierDefinition>,
+}
+
+/// Price from a threshold onwards, in the unit of the curve's basis
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct PricingTierDefinition {
+    pub from: i32,
+    pub box_price: Decimal,
+}
+
+/// Grid position, zero-based from the top-left cell
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, ToSchema)]
+pub struct GridCell {
+    pub row: i32,
+    pub col: i32,
+}
+
+/// Non-rectangular raffle layout (e.g. a themed shape) drawn on a rows x cols grid
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct RaffleLayoutDefinition {
+    pub name: Option<String>,
+    pub disabled_cells: Vec<GridCell>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RaffleResponse {
     pub id: Uuid,
     pub item_id: Uuid,
     pub item: Option<ItemResponse>,
     pub total_boxes: i32,
     pub box_price: Decimal,
+    /// Currency `box_price` is in; always the item's
+    pub currency: String,
+    /// `box_price` converted to the viewer's display currency
+    #[serde(default)]
+    pub display_box_price: Option<DisplayAmount>,
+    /// Shipping to the viewer's region, when the request or the user gives one
+    #[serde(default)]
+    pub shipping_estimate: Option<ShippingEstimate>,
     pub boxes_sold: i32,
     pub total_winners: i32,
     pub status: RaffleStatus,
     pub winner_user_ids: Vec<Uuid>,
     pub grid_rows: i32,
     pub grid_cols: i32,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub deadline_action: RaffleDeadlineAction,
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct PurchaseBoxRequest {
     pub raffle_id: Uuid,
     
@@ -138,7 +284,7 @@ pub struct PurchaseBoxRequest {
     pub box_number: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BoxPurchaseResponse {
     pub id: Uuid,
     pub raffle_id: Uuid,
@@ -146,11 +292,14 @@ pub struct BoxPurchaseResponse {
     pub box_number: i32,
     pub purchase_price_in_credits: Decimal,
     pub blockchain_tx_hash: Option<String>,
+    /// Listing versions the purchase was made against; see the snapshot endpoints
+    pub item_snapshot_id: Option<Uuid>,
+    pub raffle_snapshot_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
 }
 
 // Credit DTOs
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreditResponse {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -165,7 +314,7 @@ pub struct CreditResponse {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct RedeemCreditRequest {
     pub credit_ids: Vec<Uuid>,
     pub item_id: Option<Uuid>,
@@ -193,7 +342,7 @@ pub struct PaymentIntentResponse {
 }
 
 // Seller DTOs
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateSellerRequest {
     #[validate(length(min = 1, max = 255))]
     pub company_name: Option<String>,
@@ -204,7 +353,7 @@ pub struct CreateSellerRequest {
     pub subscription_id: Uuid,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SellerResponse {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -218,7 +367,7 @@ pub struct SellerResponse {
 }
 
 // Analytics DTOs
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RaffleMetricsResponse {
     pub raffle_id: Uuid,
     pub views_count: i32,
@@ -232,7 +381,7 @@ pub struct RaffleMetricsResponse {
     pub seller_payout: Option<Decimal>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PlatformStatsResponse {
     pub date: chrono::NaiveDate,
     pub total_users: i32,
@@ -250,7 +399,7 @@ pub struct PlatformStatsResponse {
 }
 
 // Common pagination and filtering
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct PaginationParams {
     #[validate(range(min = 1, max = 100))]
     pub limit: Option<i64>,
@@ -259,7 +408,8 @@ pub struct PaginationParams {
     pub offset: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[aliases(BoxPurchasePage = PaginatedResponse<BoxPurchaseResponse>)]
 pub struct PaginatedResponse<T> {
     pub data: Vec<T>,
     pub total: i64,
@@ -268,7 +418,16 @@ pub struct PaginatedResponse<T> {
     pub has_more: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A page of a cursor-paginated listing; pass `next_cursor` back as `cursor` for the next page
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[aliases(RaffleCursorPage = CursorPage<RaffleResponse>, CreditCursorPage = CursorPage<CreditResponse>)]
+pub struct CursorPage<T> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct FilterParams {
     pub status: Option<String>,
     pub created_after: Option<DateTime<Utc>>,
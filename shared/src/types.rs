@@ -1,12 +1,15 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::fmt;
 
 // User-related enums
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "user_role", rename_all = "lowercase")]
 pub enum UserRole {
     User,
     Seller,
+    /// Staff who can only use the admin permissions granted to the support role
+    Support,
     Admin,
     Operator,
 }
@@ -16,6 +19,7 @@ impl fmt::Display for UserRole {
         match self {
             UserRole::User => write!(f, "user"),
             UserRole::Seller => write!(f, "seller"),
+            UserRole::Support => write!(f, "support"),
             UserRole::Admin => write!(f, "admin"),
             UserRole::Operator => write!(f, "operator"),
         }
@@ -23,12 +27,17 @@ impl fmt::Display for UserRole {
 }
 
 // Item-related enums
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "item_status", rename_all = "lowercase")]
 pub enum ItemStatus {
     Available,
     Sold,
     Inactive,
+    /// Waiting for a moderator before it can be raffled
+    #[sqlx(rename = "pending_review")]
+    PendingReview,
+    /// Turned down by a moderator; editing it submits it again
+    Rejected,
 }
 
 impl fmt::Display for ItemStatus {
@@ -37,14 +46,18 @@ impl fmt::Display for ItemStatus {
             ItemStatus::Available => write!(f, "available"),
             ItemStatus::Sold => write!(f, "sold"),
             ItemStatus::Inactive => write!(f, "inactive"),
+            ItemStatus::PendingReview => write!(f, "pending_review"),
+            ItemStatus::Rejected => write!(f, "rejected"),
         }
     }
 }
 
 // Raffle-related enums
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "raffle_status", rename_all = "lowercase")]
 pub enum RaffleStatus {
+    /// Created with a future start time; opens automatically
+    Scheduled,
     Open,
     Full,
     Drawing,
@@ -55,6 +68,7 @@ pub enum RaffleStatus {
 impl fmt::Display for RaffleStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            RaffleStatus::Scheduled => write!(f, "scheduled"),
             RaffleStatus::Open => write!(f, "open"),
             RaffleStatus::Full => write!(f, "full"),
             RaffleStatus::Drawing => write!(f, "drawing"),
@@ -64,17 +78,57 @@ impl fmt::Display for RaffleStatus {
     }
 }
 
+/// What happens to a raffle that is still open when its end time passes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "raffle_deadline_action", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RaffleDeadlineAction {
+    /// Cancel and refund every box
+    #[default]
+    Cancel,
+    /// Draw winners from the boxes sold so far
+    Complete,
+}
+
+impl fmt::Display for RaffleDeadlineAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RaffleDeadlineAction::Cancel => write!(f, "cancel"),
+            RaffleDeadlineAction::Complete => write!(f, "complete"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "prize_type", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PrizeType {
+    Item,
+    Credits,
+}
+
+impl fmt::Display for PrizeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrizeType::Item => write!(f, "item"),
+            PrizeType::Credits => write!(f, "credits"),
+        }
+    }
+}
+
 // Credit-related enums
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "credit_source", rename_all = "snake_case")]
 pub enum CreditSource {
     RaffleLoss,
     Deposit,
     Refund,
     Bonus,
+    RafflePrize,
+    Referral,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "credit_type", rename_all = "snake_case")]
 pub enum CreditType {
     General,
@@ -82,7 +136,7 @@ pub enum CreditType {
 }
 
 // Transaction-related enums
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "transaction_type", rename_all = "snake_case")]
 pub enum TransactionType {
     CreditDeposit,
@@ -95,10 +149,11 @@ pub enum TransactionType {
     SellerSubscriptionFee,
     SellerListingFee,
     SellerTransactionFee,
+    SellerApiUsageFee,
 }
 
 // Audit-related enums
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum AuditAction {
     Create,
     Update,
@@ -120,7 +175,7 @@ pub enum AuditAction {
 }
 
 // Common status enum for various entities
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 pub enum Status {
     Pending,
     Processing,